@@ -7,6 +7,7 @@ import type {
   AddDeviceInstanceDetail,
   InstancePointList,
   InstanceMappingList,
+  NamingPolicy,
 } from '@/types/deviceConfiguration'
 
 export const getInstanceDetail = (
@@ -20,6 +21,12 @@ export const getInstanceDetail = (
 export const getProducts = (): Promise<ApiResponse<ProductListResponse>> => {
   return Request.get('/modApi/api/products')
 }
+/*
+获取命名规范
+*/
+export const getNamingPolicy = (): Promise<ApiResponse<NamingPolicy>> => {
+  return Request.get('/modApi/api/naming-policy')
+}
 export const createInstance = (data: AddDeviceInstanceDetail) => {
   return Request.post('/modApi/api/instances', data)
 }
@@ -142,6 +142,18 @@ export interface ProductListItem {
   product_name: string
   parent_name: string | null
 }
+// 命名规范（global.yaml 中的 naming 配置，由 modsrv 下发）
+export interface NamePolicy {
+  pattern: string
+  min_length: number
+  max_length: number
+  reserved: string[]
+  case_sensitive: boolean
+  unique: 'kind' | 'system'
+}
+export interface NamingPolicy {
+  instance: NamePolicy
+}
 export interface ProductListResponse {
   count: number
   products: ProductListItem[]
@@ -0,0 +1,418 @@
+//! Scripted mock endpoint
+//!
+//! The endpoint splits incoming bytes into request frames with a protocol
+//! [`Responder`] and answers according to the current [`Behavior`], which
+//! scenarios switch at runtime to simulate silent, fragmenting or
+//! disconnecting devices.
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::{JoinHandle, JoinSet};
+
+/// Protocol side of the mock endpoint
+pub trait Responder: Send + Sync + 'static {
+    /// Length of the complete request frame at the start of `buf`,
+    /// or None while the frame is still incomplete
+    fn frame_len(&self, buf: &[u8]) -> Option<usize>;
+
+    /// Response for one request frame (None = no reply, e.g. broadcast)
+    fn respond(&self, request: &[u8]) -> Option<Vec<u8>>;
+}
+
+/// How the endpoint answers the next requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Behavior {
+    /// Reply immediately with the full response
+    Respond,
+    /// Swallow requests without replying (response timeout)
+    Silent,
+    /// Reply in `chunk`-byte pieces with `gap` between them
+    Fragmented { chunk: usize, gap: Duration },
+    /// Close the connection on the next request, then go back to `Respond`
+    CloseConnection,
+}
+
+/// Counters observed by the endpoint
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EndpointStats {
+    pub connections: usize,
+    pub requests: usize,
+    pub responses: usize,
+}
+
+/// Where a runtime under test should connect
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EndpointAddress {
+    Tcp(SocketAddr),
+    /// Slave side of a pseudo-terminal
+    Serial(PathBuf),
+}
+
+impl EndpointAddress {
+    /// Host and port for TCP endpoints
+    pub fn host_port(&self) -> Option<(String, u16)> {
+        match self {
+            Self::Tcp(addr) => Some((addr.ip().to_string(), addr.port())),
+            Self::Serial(_) => None,
+        }
+    }
+
+    /// Device path for serial endpoints
+    pub fn device(&self) -> Option<&Path> {
+        match self {
+            Self::Tcp(_) => None,
+            Self::Serial(path) => Some(path),
+        }
+    }
+}
+
+struct Shared {
+    responder: Arc<dyn Responder>,
+    behavior: Mutex<Behavior>,
+    stats: Mutex<EndpointStats>,
+}
+
+impl Shared {
+    fn behavior(&self) -> Behavior {
+        *self.behavior.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set_behavior(&self, behavior: Behavior) {
+        *self.behavior.lock().unwrap_or_else(|e| e.into_inner()) = behavior;
+    }
+
+    fn update_stats(&self, f: impl FnOnce(&mut EndpointStats)) {
+        f(&mut self.stats.lock().unwrap_or_else(|e| e.into_inner()));
+    }
+}
+
+/// Mock device endpoint; stops serving when dropped
+pub struct MockEndpoint {
+    address: EndpointAddress,
+    shared: Arc<Shared>,
+    task: JoinHandle<()>,
+    #[cfg(unix)]
+    _pty_slave: Option<std::os::fd::OwnedFd>,
+}
+
+impl MockEndpoint {
+    /// Listen on an ephemeral localhost TCP port
+    pub async fn tcp(responder: Arc<dyn Responder>) -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let address = EndpointAddress::Tcp(listener.local_addr()?);
+        let shared = new_shared(responder);
+
+        let accept_shared = Arc::clone(&shared);
+        let task = tokio::spawn(async move {
+            // Dropping the set on abort closes all open connections
+            let mut connections = JoinSet::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                accept_shared.update_stats(|s| s.connections += 1);
+                connections.spawn(serve(stream, Arc::clone(&accept_shared)));
+            }
+        });
+
+        Ok(Self {
+            address,
+            shared,
+            task,
+            #[cfg(unix)]
+            _pty_slave: None,
+        })
+    }
+
+    /// Serve on the master side of a raw-mode pseudo-terminal; runtimes open
+    /// the slave path like a serial port
+    #[cfg(unix)]
+    pub fn serial(responder: Arc<dyn Responder>) -> std::io::Result<Self> {
+        let pty = pty::open()?;
+        let address = EndpointAddress::Serial(pty.slave_path);
+        let shared = new_shared(responder);
+        shared.update_stats(|s| s.connections += 1);
+
+        let task = tokio::spawn(serve(pty.master, Arc::clone(&shared)));
+        Ok(Self {
+            address,
+            shared,
+            task,
+            _pty_slave: Some(pty.slave),
+        })
+    }
+
+    pub fn address(&self) -> &EndpointAddress {
+        &self.address
+    }
+
+    pub fn set_behavior(&self, behavior: Behavior) {
+        self.shared.set_behavior(behavior);
+    }
+
+    pub fn stats(&self) -> EndpointStats {
+        *self.shared.stats.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for MockEndpoint {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn new_shared(responder: Arc<dyn Responder>) -> Arc<Shared> {
+    Arc::new(Shared {
+        responder,
+        behavior: Mutex::new(Behavior::Respond),
+        stats: Mutex::new(EndpointStats::default()),
+    })
+}
+
+/// Buffer limit before unframeable input is discarded
+const MAX_PENDING_BYTES: usize = 4096;
+
+async fn serve<S>(mut stream: S, shared: Arc<Shared>)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut pending = Vec::new();
+    let mut buf = [0u8; 512];
+
+    loop {
+        let n = match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => n,
+        };
+        pending.extend_from_slice(&buf[..n]);
+
+        while let Some(len) = shared.responder.frame_len(&pending) {
+            if len == 0 || len > pending.len() {
+                break;
+            }
+            let request: Vec<u8> = pending.drain(..len).collect();
+            shared.update_stats(|s| s.requests += 1);
+
+            let behavior = shared.behavior();
+            if behavior == Behavior::CloseConnection {
+                shared.set_behavior(Behavior::Respond);
+                tracing::debug!("Mock endpoint closing connection");
+                return;
+            }
+            if behavior == Behavior::Silent {
+                continue;
+            }
+            let Some(response) = shared.responder.respond(&request) else {
+                continue;
+            };
+
+            let written = match behavior {
+                Behavior::Fragmented { chunk, gap } => {
+                    write_fragmented(&mut stream, &response, chunk.max(1), gap).await
+                },
+                _ => stream.write_all(&response).await,
+            };
+            if written.and(stream.flush().await).is_err() {
+                return;
+            }
+            shared.update_stats(|s| s.responses += 1);
+        }
+
+        if pending.len() > MAX_PENDING_BYTES {
+            pending.clear();
+        }
+    }
+}
+
+async fn write_fragmented<S>(
+    stream: &mut S,
+    response: &[u8],
+    chunk: usize,
+    gap: Duration,
+) -> std::io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    for (i, piece) in response.chunks(chunk).enumerate() {
+        if i > 0 {
+            tokio::time::sleep(gap).await;
+        }
+        stream.write_all(piece).await?;
+        stream.flush().await?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+mod pty {
+    //! Non-blocking PTY master usable as a tokio stream
+
+    use std::io;
+    use std::os::fd::{AsRawFd, OwnedFd};
+    use std::path::PathBuf;
+    use std::pin::Pin;
+    use std::task::{ready, Context, Poll};
+
+    use nix::fcntl::{fcntl, FcntlArg, OFlag};
+    use nix::sys::termios::{cfmakeraw, tcgetattr, tcsetattr, SetArg};
+    use tokio::io::unix::AsyncFd;
+    use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+    pub(super) struct Pty {
+        pub master: PtyMaster,
+        pub slave: OwnedFd,
+        pub slave_path: PathBuf,
+    }
+
+    pub(super) fn open() -> io::Result<Pty> {
+        let pair = nix::pty::openpty(None, None)?;
+
+        // Binary-safe line discipline on both ends (no echo, no CR/LF mapping)
+        for fd in [&pair.master, &pair.slave] {
+            let mut termios = tcgetattr(fd)?;
+            cfmakeraw(&mut termios);
+            tcsetattr(fd, SetArg::TCSANOW, &termios)?;
+        }
+        let flags = OFlag::from_bits_truncate(fcntl(pair.master.as_raw_fd(), FcntlArg::F_GETFL)?);
+        fcntl(
+            pair.master.as_raw_fd(),
+            FcntlArg::F_SETFL(flags | OFlag::O_NONBLOCK),
+        )?;
+
+        let slave_path = nix::unistd::ttyname(&pair.slave)?;
+        Ok(Pty {
+            master: PtyMaster(AsyncFd::new(pair.master)?),
+            slave: pair.slave,
+            slave_path,
+        })
+    }
+
+    pub(super) struct PtyMaster(AsyncFd<OwnedFd>);
+
+    impl AsyncRead for PtyMaster {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            loop {
+                let mut guard = ready!(self.0.poll_read_ready(cx))?;
+                let unfilled = buf.initialize_unfilled();
+                match guard.try_io(|fd| {
+                    nix::unistd::read(fd.as_raw_fd(), unfilled).map_err(io::Error::from)
+                }) {
+                    Ok(Ok(n)) => {
+                        buf.advance(n);
+                        return Poll::Ready(Ok(()));
+                    },
+                    Ok(Err(e)) => return Poll::Ready(Err(e)),
+                    Err(_would_block) => continue,
+                }
+            }
+        }
+    }
+
+    impl AsyncWrite for PtyMaster {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            loop {
+                let mut guard = ready!(self.0.poll_write_ready(cx))?;
+                match guard.try_io(|fd| nix::unistd::write(fd, buf).map_err(io::Error::from)) {
+                    Ok(result) => return Poll::Ready(result),
+                    Err(_would_block) => continue,
+                }
+            }
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use tokio::net::TcpStream;
+
+    /// Newline-delimited echo
+    struct LineEcho;
+
+    impl Responder for LineEcho {
+        fn frame_len(&self, buf: &[u8]) -> Option<usize> {
+            buf.iter().position(|b| *b == b'\n').map(|i| i + 1)
+        }
+
+        fn respond(&self, request: &[u8]) -> Option<Vec<u8>> {
+            Some(request.to_ascii_uppercase())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tcp_endpoint_behaviors() {
+        let endpoint = MockEndpoint::tcp(Arc::new(LineEcho)).await.unwrap();
+        let addr = match endpoint.address() {
+            EndpointAddress::Tcp(addr) => *addr,
+            other => panic!("unexpected address {:?}", other),
+        };
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        // Request split across writes is reassembled
+        stream.write_all(b"pi").await.unwrap();
+        stream.write_all(b"ng\n").await.unwrap();
+        let mut reply = [0u8; 5];
+        stream.read_exact(&mut reply).await.unwrap();
+        assert_eq!(&reply, b"PING\n");
+
+        endpoint.set_behavior(Behavior::Fragmented {
+            chunk: 1,
+            gap: Duration::from_millis(1),
+        });
+        stream.write_all(b"abc\n").await.unwrap();
+        let mut reply = [0u8; 4];
+        stream.read_exact(&mut reply).await.unwrap();
+        assert_eq!(&reply, b"ABC\n");
+
+        endpoint.set_behavior(Behavior::CloseConnection);
+        stream.write_all(b"bye\n").await.unwrap();
+        assert_eq!(stream.read(&mut reply).await.unwrap(), 0);
+
+        let stats = endpoint.stats();
+        assert_eq!(stats.connections, 1);
+        assert_eq!(stats.requests, 3);
+        assert_eq!(stats.responses, 2);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_serial_loopback_endpoint() {
+        let endpoint = MockEndpoint::serial(Arc::new(LineEcho)).unwrap();
+        let device = endpoint.address().device().unwrap().to_path_buf();
+
+        let mut port = tokio::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&device)
+            .await
+            .unwrap();
+        port.write_all(b"serial\n").await.unwrap();
+        port.flush().await.unwrap();
+
+        let mut reply = [0u8; 7];
+        tokio::time::timeout(Duration::from_secs(2), port.read_exact(&mut reply))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(&reply, b"SERIAL\n");
+    }
+}
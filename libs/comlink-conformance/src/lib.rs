@@ -0,0 +1,30 @@
+//! Protocol conformance harness for channel runtimes
+//!
+//! Every protocol plugin that comsrv drives through `ChannelRuntime` must
+//! behave the same way at the trait level: fail (not hang) on dead links,
+//! survive fragmented responses, recover after the peer drops the connection
+//! and serialize concurrent polls and writes correctly. This crate provides a
+//! scripted mock endpoint (TCP, or a PTY acting as a serial loopback) and a
+//! battery of scenarios that run against any runtime built by a factory.
+//!
+//! ```ignore
+//! use comlink_conformance::{modbus::ModbusTcpResponder, ConformanceSuite};
+//!
+//! let report = ConformanceSuite::tcp("modbus_tcp", ModbusTcpResponder::default(), |address| {
+//!     let (host, port) = address.host_port().unwrap();
+//!     create_modbus_channel(1, &host, port, points.clone(), BlockConfig::default())
+//! })
+//! .with_write_commands(vec![(1, 1.0)])
+//! .run()
+//! .await;
+//! report.assert_passed();
+//! ```
+
+pub mod endpoint;
+pub mod modbus;
+pub mod suite;
+
+pub use endpoint::{Behavior, EndpointAddress, EndpointStats, MockEndpoint, Responder};
+pub use suite::{
+    ConformanceReport, ConformanceSuite, ConformanceTarget, Scenario, ScenarioOutcome,
+};
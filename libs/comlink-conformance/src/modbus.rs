@@ -0,0 +1,254 @@
+//! Modbus TCP/RTU responders backed by an in-memory register bank
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::endpoint::Responder;
+
+/// Holding/input registers and coils/discrete inputs keyed by (unit, address)
+///
+/// Unset addresses read as 0, so any point table can be polled without seeding.
+#[derive(Debug, Default)]
+pub struct RegisterBank {
+    registers: Mutex<HashMap<(u8, u16), u16>>,
+    bits: Mutex<HashMap<(u8, u16), bool>>,
+}
+
+impl RegisterBank {
+    pub fn set_register(&self, unit: u8, address: u16, value: u16) {
+        lock(&self.registers).insert((unit, address), value);
+    }
+
+    pub fn register(&self, unit: u8, address: u16) -> u16 {
+        lock(&self.registers)
+            .get(&(unit, address))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn set_bit(&self, unit: u8, address: u16, value: bool) {
+        lock(&self.bits).insert((unit, address), value);
+    }
+
+    pub fn bit(&self, unit: u8, address: u16) -> bool {
+        lock(&self.bits)
+            .get(&(unit, address))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Execute a request PDU and build the response PDU
+    fn execute(&self, unit: u8, pdu: &[u8]) -> Vec<u8> {
+        let fc = pdu.first().copied().unwrap_or_default();
+        self.try_execute(unit, pdu)
+            .unwrap_or_else(|code| vec![fc | 0x80, code])
+    }
+
+    fn try_execute(&self, unit: u8, pdu: &[u8]) -> Result<Vec<u8>, u8> {
+        const ILLEGAL_FUNCTION: u8 = 0x01;
+        const ILLEGAL_DATA_VALUE: u8 = 0x03;
+
+        let fc = pdu[0];
+        let field = |i: usize| {
+            pdu.get(i..i + 2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]))
+                .ok_or(ILLEGAL_DATA_VALUE)
+        };
+
+        match fc {
+            1 | 2 => {
+                let (start, quantity) = (field(1)?, field(3)?);
+                if quantity == 0 || quantity > 2000 {
+                    return Err(ILLEGAL_DATA_VALUE);
+                }
+                let mut bytes = vec![0u8; usize::from(quantity).div_ceil(8)];
+                for i in 0..quantity {
+                    if self.bit(unit, start.wrapping_add(i)) {
+                        bytes[usize::from(i / 8)] |= 1 << (i % 8);
+                    }
+                }
+                let mut response = vec![fc, bytes.len() as u8];
+                response.extend(bytes);
+                Ok(response)
+            },
+            3 | 4 => {
+                let (start, quantity) = (field(1)?, field(3)?);
+                if quantity == 0 || quantity > 125 {
+                    return Err(ILLEGAL_DATA_VALUE);
+                }
+                let mut response = vec![fc, (quantity * 2) as u8];
+                for i in 0..quantity {
+                    response.extend(self.register(unit, start.wrapping_add(i)).to_be_bytes());
+                }
+                Ok(response)
+            },
+            5 => {
+                let (address, value) = (field(1)?, field(3)?);
+                self.set_bit(unit, address, value == 0xFF00);
+                Ok(pdu[..5].to_vec())
+            },
+            6 => {
+                let (address, value) = (field(1)?, field(3)?);
+                self.set_register(unit, address, value);
+                Ok(pdu[..5].to_vec())
+            },
+            15 => {
+                let (start, quantity) = (field(1)?, field(3)?);
+                let bytes = pdu.get(6..).ok_or(ILLEGAL_DATA_VALUE)?;
+                for i in 0..quantity {
+                    let byte = bytes.get(usize::from(i / 8)).ok_or(ILLEGAL_DATA_VALUE)?;
+                    self.set_bit(unit, start.wrapping_add(i), byte >> (i % 8) & 1 == 1);
+                }
+                Ok(pdu[..5].to_vec())
+            },
+            16 => {
+                let (start, quantity) = (field(1)?, field(3)?);
+                for i in 0..quantity {
+                    let value = field(6 + usize::from(i) * 2)?;
+                    self.set_register(unit, start.wrapping_add(i), value);
+                }
+                Ok(pdu[..5].to_vec())
+            },
+            _ => Err(ILLEGAL_FUNCTION),
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Modbus TCP (MBAP framed) responder
+#[derive(Debug, Default, Clone)]
+pub struct ModbusTcpResponder {
+    pub bank: Arc<RegisterBank>,
+}
+
+impl Responder for ModbusTcpResponder {
+    fn frame_len(&self, buf: &[u8]) -> Option<usize> {
+        let length = buf.get(4..6)?;
+        Some(6 + usize::from(u16::from_be_bytes([length[0], length[1]])))
+    }
+
+    fn respond(&self, request: &[u8]) -> Option<Vec<u8>> {
+        let unit = *request.get(6)?;
+        let pdu = request.get(7..).filter(|pdu| !pdu.is_empty())?;
+        let body = self.bank.execute(unit, pdu);
+
+        let mut response = Vec::with_capacity(7 + body.len());
+        response.extend_from_slice(&request[0..4]);
+        response.extend(((body.len() + 1) as u16).to_be_bytes());
+        response.push(unit);
+        response.extend(body);
+        Some(response)
+    }
+}
+
+/// Modbus RTU responder; answers only frames for its own unit id
+#[derive(Debug, Clone)]
+pub struct ModbusRtuResponder {
+    pub unit: u8,
+    pub bank: Arc<RegisterBank>,
+}
+
+impl Default for ModbusRtuResponder {
+    fn default() -> Self {
+        Self {
+            unit: 1,
+            bank: Arc::default(),
+        }
+    }
+}
+
+impl Responder for ModbusRtuResponder {
+    fn frame_len(&self, buf: &[u8]) -> Option<usize> {
+        match *buf.get(1)? {
+            15 | 16 => buf.get(6).map(|count| 9 + usize::from(*count)),
+            _ => Some(8),
+        }
+    }
+
+    fn respond(&self, request: &[u8]) -> Option<Vec<u8>> {
+        let (body, crc) = request.split_at(request.len().checked_sub(2)?);
+        if body.len() < 2 || crc16(body).to_le_bytes() != [crc[0], crc[1]] {
+            // Corrupted frames are dropped silently, like a real slave
+            return None;
+        }
+        if body[0] != self.unit {
+            return None;
+        }
+
+        let mut response = vec![body[0]];
+        response.extend(self.bank.execute(body[0], &body[1..]));
+        response.extend(crc16(&response).to_le_bytes());
+        Some(response)
+    }
+}
+
+/// Modbus RTU CRC-16
+pub fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc = 0xFFFFu16;
+    for byte in bytes {
+        crc ^= u16::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xA001
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modbus_tcp_read_and_write() {
+        let responder = ModbusTcpResponder::default();
+        responder.bank.set_register(1, 100, 0x41C8);
+
+        let read = [
+            0x00, 0x07, 0x00, 0x00, 0x00, 0x06, 0x01, 0x03, 0x00, 0x64, 0x00, 0x02,
+        ];
+        assert_eq!(responder.frame_len(&read[..5]), None);
+        assert_eq!(responder.frame_len(&read), Some(12));
+        assert_eq!(
+            responder.respond(&read).unwrap(),
+            [0x00, 0x07, 0x00, 0x00, 0x00, 0x07, 0x01, 0x03, 0x04, 0x41, 0xC8, 0x00, 0x00]
+        );
+
+        let write = [
+            0x00, 0x08, 0x00, 0x00, 0x00, 0x06, 0x01, 0x05, 0x00, 0x0A, 0xFF, 0x00,
+        ];
+        assert_eq!(responder.respond(&write).unwrap()[7..], write[7..]);
+        assert!(responder.bank.bit(1, 10));
+
+        let unsupported = [0x00, 0x09, 0x00, 0x00, 0x00, 0x02, 0x01, 0x2B];
+        assert_eq!(responder.respond(&unsupported).unwrap()[7..], [0xAB, 0x01]);
+    }
+
+    #[test]
+    fn test_modbus_rtu_crc_and_unit_filter() {
+        let responder = ModbusRtuResponder::default();
+        let mut request = vec![0x01, 0x03, 0x00, 0x00, 0x00, 0x01];
+        request.extend(crc16(&request).to_le_bytes());
+        assert_eq!(request[6..], [0x84, 0x0A]);
+
+        let response = responder.respond(&request).unwrap();
+        assert_eq!(response[..5], [0x01, 0x03, 0x02, 0x00, 0x00]);
+        assert_eq!(
+            crc16(&response[..5]).to_le_bytes(),
+            [response[5], response[6]]
+        );
+
+        request[0] = 0x02;
+        assert!(responder.respond(&request).is_none());
+        request[0] = 0x01;
+        request[7] ^= 0xFF;
+        assert!(responder.respond(&request).is_none());
+    }
+}
@@ -0,0 +1,591 @@
+//! Conformance scenarios
+//!
+//! Scenarios run against a fresh endpoint and a fresh runtime each, so one
+//! misbehaving scenario cannot poison the next. Every scenario is bounded by a
+//! deadline; a runtime that hangs fails instead of blocking the test run.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use igw::gateway::ChannelRuntime;
+use tokio::sync::Mutex;
+
+use crate::endpoint::{Behavior, EndpointAddress, MockEndpoint, Responder};
+
+/// Trait-level operations exercised by the scenarios
+///
+/// Implemented for `Box<dyn ChannelRuntime>`; protocol-independent test
+/// clients can implement it directly.
+#[async_trait]
+pub trait ConformanceTarget: Send + 'static {
+    async fn connect(&mut self) -> Result<(), String>;
+
+    async fn disconnect(&mut self) -> Result<(), String>;
+
+    /// One poll cycle; number of values read, Err on any failed point
+    async fn poll(&mut self) -> Result<usize, String>;
+
+    /// Write control commands; number of commands accepted
+    async fn write(&mut self, commands: &[(u32, f64)]) -> Result<usize, String>;
+}
+
+#[async_trait]
+impl ConformanceTarget for Box<dyn ChannelRuntime> {
+    async fn connect(&mut self) -> Result<(), String> {
+        ChannelRuntime::connect(self.as_mut())
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn disconnect(&mut self) -> Result<(), String> {
+        ChannelRuntime::disconnect(self.as_mut())
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn poll(&mut self) -> Result<usize, String> {
+        let result = self.poll_once().await;
+        if result.has_failures() {
+            return Err(format!("{:?}", result.failures));
+        }
+        match result.data.len() {
+            0 => Err("poll returned no data".to_string()),
+            n => Ok(n),
+        }
+    }
+
+    async fn write(&mut self, commands: &[(u32, f64)]) -> Result<usize, String> {
+        self.write_control(commands)
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Scenario identifiers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scenario {
+    /// Connect, poll, write, disconnect against a healthy endpoint
+    ConnectAndPoll,
+    /// Nothing listening: connect or poll must fail within the deadline
+    UnreachableEndpoint,
+    /// Device stops answering: poll fails, then recovers once it answers again
+    ResponseTimeout,
+    /// Responses arrive one byte at a time
+    PartialFrames,
+    /// Peer closes the connection; the runtime must reconnect
+    Reconnect,
+    /// Interleaved polls and writes from several tasks
+    ConcurrentReadWrite,
+}
+
+impl Scenario {
+    const TCP: [Scenario; 6] = [
+        Self::ConnectAndPoll,
+        Self::UnreachableEndpoint,
+        Self::ResponseTimeout,
+        Self::PartialFrames,
+        Self::Reconnect,
+        Self::ConcurrentReadWrite,
+    ];
+    /// A serial line has no connection to refuse or drop
+    const SERIAL: [Scenario; 4] = [
+        Self::ConnectAndPoll,
+        Self::ResponseTimeout,
+        Self::PartialFrames,
+        Self::ConcurrentReadWrite,
+    ];
+}
+
+impl fmt::Display for Scenario {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::ConnectAndPoll => "connect_and_poll",
+            Self::UnreachableEndpoint => "unreachable_endpoint",
+            Self::ResponseTimeout => "response_timeout",
+            Self::PartialFrames => "partial_frames",
+            Self::Reconnect => "reconnect",
+            Self::ConcurrentReadWrite => "concurrent_read_write",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Result of one scenario
+#[derive(Debug, Clone)]
+pub struct ScenarioOutcome {
+    pub scenario: Scenario,
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// Results of a suite run
+#[derive(Debug, Clone)]
+pub struct ConformanceReport {
+    pub protocol: String,
+    pub outcomes: Vec<ScenarioOutcome>,
+}
+
+impl ConformanceReport {
+    pub fn passed(&self) -> bool {
+        self.outcomes.iter().all(|o| o.passed)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &ScenarioOutcome> {
+        self.outcomes.iter().filter(|o| !o.passed)
+    }
+
+    /// Panic with every failed scenario (for use in tests)
+    #[allow(clippy::panic)]
+    pub fn assert_passed(&self) {
+        let failures: Vec<String> = self
+            .failures()
+            .map(|o| format!("  {}: {}", o.scenario, o.detail))
+            .collect();
+        if !failures.is_empty() {
+            panic!(
+                "{} failed {} conformance scenario(s):\n{}",
+                self.protocol,
+                failures.len(),
+                failures.join("\n")
+            );
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transport {
+    Tcp,
+    #[cfg(unix)]
+    Serial,
+}
+
+type Factory<T> = Arc<dyn Fn(&EndpointAddress) -> T + Send + Sync>;
+
+/// Scenario battery for one protocol runtime
+pub struct ConformanceSuite<T> {
+    protocol: String,
+    transport: Transport,
+    responder: Arc<dyn Responder>,
+    factory: Factory<T>,
+    write_commands: Vec<(u32, f64)>,
+    deadline: Duration,
+}
+
+impl<T: ConformanceTarget> ConformanceSuite<T> {
+    /// Suite for a TCP protocol; `factory` builds a runtime for an endpoint address
+    pub fn tcp<F>(protocol: impl Into<String>, responder: impl Responder, factory: F) -> Self
+    where
+        F: Fn(&EndpointAddress) -> T + Send + Sync + 'static,
+    {
+        Self::new(
+            protocol.into(),
+            Transport::Tcp,
+            Arc::new(responder),
+            Arc::new(factory),
+        )
+    }
+
+    /// Suite for a serial protocol, using a PTY loopback as the device
+    #[cfg(unix)]
+    pub fn serial<F>(protocol: impl Into<String>, responder: impl Responder, factory: F) -> Self
+    where
+        F: Fn(&EndpointAddress) -> T + Send + Sync + 'static,
+    {
+        Self::new(
+            protocol.into(),
+            Transport::Serial,
+            Arc::new(responder),
+            Arc::new(factory),
+        )
+    }
+
+    fn new(
+        protocol: String,
+        transport: Transport,
+        responder: Arc<dyn Responder>,
+        factory: Factory<T>,
+    ) -> Self {
+        Self {
+            protocol,
+            transport,
+            responder,
+            factory,
+            write_commands: Vec::new(),
+            deadline: Duration::from_secs(15),
+        }
+    }
+
+    /// Control commands used by write scenarios (writes are skipped when empty)
+    pub fn with_write_commands(mut self, commands: Vec<(u32, f64)>) -> Self {
+        self.write_commands = commands;
+        self
+    }
+
+    /// Upper bound for a single operation, including the runtime's own
+    /// timeouts and retries (default 15 s)
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Run all scenarios applicable to the transport
+    pub async fn run(&self) -> ConformanceReport {
+        let scenarios: &[Scenario] = match self.transport {
+            Transport::Tcp => &Scenario::TCP,
+            #[cfg(unix)]
+            Transport::Serial => &Scenario::SERIAL,
+        };
+
+        let mut outcomes = Vec::with_capacity(scenarios.len());
+        for &scenario in scenarios {
+            let result = self.run_scenario(scenario).await;
+            let outcome = ScenarioOutcome {
+                scenario,
+                passed: result.is_ok(),
+                detail: result.err().unwrap_or_default(),
+            };
+            if outcome.passed {
+                tracing::debug!("{} {}: passed", self.protocol, scenario);
+            } else {
+                tracing::warn!("{} {}: {}", self.protocol, scenario, outcome.detail);
+            }
+            outcomes.push(outcome);
+        }
+
+        ConformanceReport {
+            protocol: self.protocol.clone(),
+            outcomes,
+        }
+    }
+
+    async fn run_scenario(&self, scenario: Scenario) -> Result<(), String> {
+        if scenario == Scenario::UnreachableEndpoint {
+            return self.unreachable_endpoint().await;
+        }
+
+        let endpoint = self.endpoint().await?;
+        let mut target = (self.factory)(endpoint.address());
+        match scenario {
+            Scenario::ConnectAndPoll => self.connect_and_poll(&endpoint, &mut target).await,
+            Scenario::ResponseTimeout => self.response_timeout(&endpoint, &mut target).await,
+            Scenario::PartialFrames => {
+                endpoint.set_behavior(Behavior::Fragmented {
+                    chunk: 1,
+                    gap: Duration::from_millis(5),
+                });
+                self.bounded("connect", target.connect()).await?;
+                self.bounded("poll", target.poll()).await.map(|_| ())
+            },
+            Scenario::Reconnect => self.reconnect(&endpoint, &mut target).await,
+            Scenario::ConcurrentReadWrite => self.concurrent_read_write(target).await,
+            Scenario::UnreachableEndpoint => unreachable!("handled above"),
+        }
+    }
+
+    async fn endpoint(&self) -> Result<MockEndpoint, String> {
+        let endpoint = match self.transport {
+            Transport::Tcp => MockEndpoint::tcp(Arc::clone(&self.responder)).await,
+            #[cfg(unix)]
+            Transport::Serial => MockEndpoint::serial(Arc::clone(&self.responder)),
+        };
+        endpoint.map_err(|e| format!("mock endpoint setup failed: {}", e))
+    }
+
+    /// Run one operation within the deadline; a timeout means the runtime hung
+    async fn bounded<R>(
+        &self,
+        operation: &str,
+        future: impl std::future::Future<Output = Result<R, String>>,
+    ) -> Result<R, String> {
+        tokio::time::timeout(self.deadline, future)
+            .await
+            .map_err(|_| format!("{} hung for more than {:?}", operation, self.deadline))?
+            .map_err(|e| format!("{} failed: {}", operation, e))
+    }
+
+    async fn connect_and_poll(
+        &self,
+        endpoint: &MockEndpoint,
+        target: &mut T,
+    ) -> Result<(), String> {
+        self.bounded("connect", target.connect()).await?;
+        self.bounded("poll", target.poll()).await?;
+        if endpoint.stats().requests == 0 {
+            return Err("poll succeeded without sending a request".to_string());
+        }
+
+        if !self.write_commands.is_empty() {
+            let written = self
+                .bounded("write", target.write(&self.write_commands))
+                .await?;
+            if written != self.write_commands.len() {
+                return Err(format!(
+                    "write accepted {} of {} commands",
+                    written,
+                    self.write_commands.len()
+                ));
+            }
+        }
+
+        self.bounded("disconnect", target.disconnect()).await
+    }
+
+    async fn unreachable_endpoint(&self) -> Result<(), String> {
+        // Reserve a port, then release it so nothing is listening there
+        let address = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .and_then(|listener| listener.local_addr())
+            .map_err(|e| format!("port reservation failed: {}", e))?;
+        let mut target = (self.factory)(&EndpointAddress::Tcp(address));
+
+        let connected = tokio::time::timeout(self.deadline, target.connect())
+            .await
+            .map_err(|_| format!("connect hung for more than {:?}", self.deadline))?;
+        if connected.is_err() {
+            return Ok(());
+        }
+        match tokio::time::timeout(self.deadline, target.poll()).await {
+            Err(_) => Err(format!("poll hung for more than {:?}", self.deadline)),
+            Ok(Ok(_)) => Err("poll succeeded with nothing listening".to_string()),
+            Ok(Err(_)) => Ok(()),
+        }
+    }
+
+    async fn response_timeout(
+        &self,
+        endpoint: &MockEndpoint,
+        target: &mut T,
+    ) -> Result<(), String> {
+        self.bounded("connect", target.connect()).await?;
+        endpoint.set_behavior(Behavior::Silent);
+        match tokio::time::timeout(self.deadline, target.poll()).await {
+            Err(_) => return Err(format!("poll hung for more than {:?}", self.deadline)),
+            Ok(Ok(_)) => return Err("poll succeeded without a response".to_string()),
+            Ok(Err(_)) => {},
+        }
+
+        // Late or missing responses must not desynchronize later polls
+        endpoint.set_behavior(Behavior::Respond);
+        self.eventually("poll after timeout", target).await
+    }
+
+    async fn reconnect(&self, endpoint: &MockEndpoint, target: &mut T) -> Result<(), String> {
+        self.bounded("connect", target.connect()).await?;
+        self.bounded("poll", target.poll()).await?;
+
+        endpoint.set_behavior(Behavior::CloseConnection);
+        // The poll that hits the closed connection may fail; it must not hang
+        self.bounded("poll on closed connection", async {
+            let _ = target.poll().await;
+            Ok(())
+        })
+        .await?;
+
+        self.eventually("poll after reconnect", target).await?;
+        match endpoint.stats().connections {
+            n if n >= 2 => Ok(()),
+            n => Err(format!("expected a new connection, endpoint saw {}", n)),
+        }
+    }
+
+    /// Poll until success within a few attempts, reconnecting between failures
+    async fn eventually(&self, operation: &str, target: &mut T) -> Result<(), String> {
+        const ATTEMPTS: usize = 5;
+
+        let mut last_error = String::new();
+        for _ in 0..ATTEMPTS {
+            match self.bounded(operation, target.poll()).await {
+                Ok(_) => return Ok(()),
+                Err(e) => last_error = e,
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let _ = tokio::time::timeout(self.deadline, target.connect()).await;
+        }
+        Err(format!(
+            "no success in {} attempts ({})",
+            ATTEMPTS, last_error
+        ))
+    }
+
+    async fn concurrent_read_write(&self, mut target: T) -> Result<(), String> {
+        const TASKS: usize = 4;
+        const ROUNDS: usize = 5;
+
+        self.bounded("connect", target.connect()).await?;
+        // Shared the same way comsrv shares a runtime between poller and command executor
+        let target = Arc::new(Mutex::new(target));
+        let commands = Arc::new(self.write_commands.clone());
+        let deadline = self.deadline;
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for task in 0..TASKS {
+            let target = Arc::clone(&target);
+            let commands = Arc::clone(&commands);
+            tasks.spawn(async move {
+                for round in 0..ROUNDS {
+                    let mut guard = target.lock().await;
+                    let writer = task % 2 == 1 && !commands.is_empty();
+                    let result = if writer {
+                        tokio::time::timeout(deadline, guard.write(&commands)).await
+                    } else {
+                        tokio::time::timeout(deadline, guard.poll()).await
+                    };
+                    let op = if writer { "write" } else { "poll" };
+                    match result {
+                        Err(_) => return Err(format!("task {} {} {} hung", task, op, round)),
+                        Ok(Err(e)) => {
+                            return Err(format!("task {} {} {} failed: {}", task, op, round, e))
+                        },
+                        Ok(Ok(_)) => {},
+                    }
+                    drop(guard);
+                    tokio::task::yield_now().await;
+                }
+                Ok(())
+            });
+        }
+
+        while let Some(joined) = tasks.join_next().await {
+            joined.map_err(|e| format!("task panicked: {}", e))??;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use crate::modbus::ModbusTcpResponder;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    /// Minimal Modbus TCP client: reads holding register 0, writes coil 0
+    struct ReferenceClient {
+        address: std::net::SocketAddr,
+        stream: Option<TcpStream>,
+        transaction: u16,
+        /// Disables the per-request timeout to emulate a broken plugin
+        timeout: Option<Duration>,
+    }
+
+    impl ReferenceClient {
+        fn new(address: &EndpointAddress, timeout: Option<Duration>) -> Self {
+            let EndpointAddress::Tcp(address) = address else {
+                panic!("TCP endpoint expected");
+            };
+            Self {
+                address: *address,
+                stream: None,
+                transaction: 0,
+                timeout,
+            }
+        }
+
+        async fn request(&mut self, pdu: &[u8]) -> Result<Vec<u8>, String> {
+            if self.stream.is_none() {
+                self.connect().await?;
+            }
+            self.transaction = self.transaction.wrapping_add(1);
+            let id = self.transaction;
+            let result = match self.timeout {
+                Some(timeout) => tokio::time::timeout(timeout, self.exchange(id, pdu))
+                    .await
+                    .unwrap_or_else(|_| Err("timeout".to_string())),
+                None => self.exchange(id, pdu).await,
+            };
+            if result.is_err() {
+                // Drop the connection so stale responses cannot be misread
+                self.stream = None;
+            }
+            result
+        }
+
+        async fn exchange(&mut self, id: u16, pdu: &[u8]) -> Result<Vec<u8>, String> {
+            let stream = self.stream.as_mut().ok_or("not connected")?;
+            let mut frame = id.to_be_bytes().to_vec();
+            frame.extend([0, 0]);
+            frame.extend(((pdu.len() + 1) as u16).to_be_bytes());
+            frame.push(1);
+            frame.extend(pdu);
+            stream.write_all(&frame).await.map_err(|e| e.to_string())?;
+
+            let mut header = [0u8; 7];
+            stream
+                .read_exact(&mut header)
+                .await
+                .map_err(|e| e.to_string())?;
+            let length = usize::from(u16::from_be_bytes([header[4], header[5]]));
+            let mut body = vec![0u8; length.saturating_sub(1)];
+            stream
+                .read_exact(&mut body)
+                .await
+                .map_err(|e| e.to_string())?;
+            if header[..2] != id.to_be_bytes() {
+                return Err("transaction id mismatch".to_string());
+            }
+            Ok(body)
+        }
+    }
+
+    #[async_trait]
+    impl ConformanceTarget for ReferenceClient {
+        async fn connect(&mut self) -> Result<(), String> {
+            let stream = TcpStream::connect(self.address)
+                .await
+                .map_err(|e| e.to_string())?;
+            self.stream = Some(stream);
+            Ok(())
+        }
+
+        async fn disconnect(&mut self) -> Result<(), String> {
+            self.stream = None;
+            Ok(())
+        }
+
+        async fn poll(&mut self) -> Result<usize, String> {
+            let body = self.request(&[0x03, 0x00, 0x00, 0x00, 0x01]).await?;
+            (body.first() == Some(&0x03))
+                .then_some(1)
+                .ok_or_else(|| format!("exception response {:02X?}", body))
+        }
+
+        async fn write(&mut self, commands: &[(u32, f64)]) -> Result<usize, String> {
+            for (_, value) in commands {
+                let on = if *value != 0.0 { 0xFF } else { 0x00 };
+                self.request(&[0x05, 0x00, 0x00, on, 0x00]).await?;
+            }
+            Ok(commands.len())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reference_client_passes_suite() {
+        let report = ConformanceSuite::tcp("reference", ModbusTcpResponder::default(), |a| {
+            ReferenceClient::new(a, Some(Duration::from_millis(200)))
+        })
+        .with_write_commands(vec![(1, 1.0)])
+        .with_deadline(Duration::from_secs(2))
+        .run()
+        .await;
+
+        assert_eq!(report.outcomes.len(), 6);
+        report.assert_passed();
+    }
+
+    #[tokio::test]
+    async fn test_suite_reports_hanging_client() {
+        let report = ConformanceSuite::tcp("no-timeout", ModbusTcpResponder::default(), |a| {
+            ReferenceClient::new(a, None)
+        })
+        .with_deadline(Duration::from_millis(300))
+        .run()
+        .await;
+
+        let failed: Vec<Scenario> = report.failures().map(|o| o.scenario).collect();
+        assert_eq!(failed, vec![Scenario::ResponseTimeout]);
+        assert!(report.outcomes[2].detail.contains("hung"));
+    }
+}
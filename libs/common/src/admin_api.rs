@@ -3,6 +3,8 @@
 //! Provides shared endpoints for all Rust services:
 //! - Dynamic log level adjustment
 //! - Service runtime configuration
+//! - Configuration consistency report (`/health/config`)
+//! - Feature flags with runtime override
 //!
 //! Usage in services:
 //! ```ignore
@@ -10,9 +12,12 @@
 //!
 //! // In routes:
 //! .route("/api/admin/logs/level", post(set_log_level).get(get_log_level))
+//! .route("/health/config", get(get_config_report))
+//! .route("/api/admin/features", get(list_feature_flags))
+//! .route("/api/admin/features/{name}", put(set_feature_flag))
 //! ```
 
-use axum::{http::StatusCode, response::IntoResponse, Json};
+use axum::{extract::Path, http::StatusCode, response::IntoResponse, Json};
 use serde::{Deserialize, Serialize};
 
 /// Request to set log level
@@ -91,3 +96,98 @@ pub async fn get_log_level() -> impl IntoResponse {
         error: None,
     })
 }
+
+/// Configuration consistency report from service startup
+///
+/// GET /health/config
+/// Returns 503 until the startup check has produced a report.
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/health/config",
+    responses(
+        (status = 200, description = "Dangling cross-service references found at startup",
+            body = crate::config_consistency::ConsistencyReport),
+        (status = 503, description = "Startup check has not completed")
+    ),
+    tag = "admin"
+))]
+#[allow(clippy::disallowed_methods)] // json! macro internally uses unwrap (safe for known valid JSON)
+pub async fn get_config_report() -> impl IntoResponse {
+    match crate::config_consistency::latest_report() {
+        Some(report) => (StatusCode::OK, Json(serde_json::json!(report))),
+        None => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({
+                "status": "pending",
+                "error": "Configuration consistency check has not completed"
+            })),
+        ),
+    }
+}
+
+/// Request to override a feature flag
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SetFeatureFlagRequest {
+    /// New value; `null` clears the override and falls back to the configuration
+    pub enabled: Option<bool>,
+}
+
+/// List feature flags with their current values
+///
+/// GET /api/admin/features
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/admin/features",
+    responses(
+        (status = 200, description = "Feature flags of this service",
+            body = Vec<crate::feature_flags::FlagStatus>)
+    ),
+    tag = "admin"
+))]
+pub async fn list_feature_flags() -> impl IntoResponse {
+    Json(crate::feature_flags::flags().all())
+}
+
+/// Override a feature flag at runtime
+///
+/// PUT /api/admin/features/{name}
+/// Body: {"enabled": true}
+#[cfg_attr(feature = "openapi", utoipa::path(
+    put,
+    path = "/api/admin/features/{name}",
+    params(("name" = String, Path, description = "Feature flag name")),
+    request_body = SetFeatureFlagRequest,
+    responses(
+        (status = 200, description = "Flag updated", body = crate::feature_flags::FlagStatus),
+        (status = 404, description = "Unknown feature flag"),
+        (status = 500, description = "Override could not be persisted")
+    ),
+    tag = "admin"
+))]
+#[allow(clippy::disallowed_methods)] // json! macro internally uses unwrap (safe for known valid JSON)
+pub async fn set_feature_flag(
+    Path(name): Path<String>,
+    Json(req): Json<SetFeatureFlagRequest>,
+) -> impl IntoResponse {
+    let Some(flag) = crate::feature_flags::find(&name) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": format!("Unknown feature flag '{}'", name) })),
+        );
+    };
+    match crate::feature_flags::set_override(flag, req.enabled).await {
+        Ok(status) => {
+            tracing::info!(
+                "Feature flag {} override set to {:?}",
+                flag.name,
+                req.enabled
+            );
+            (StatusCode::OK, Json(serde_json::json!(status)))
+        },
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        ),
+    }
+}
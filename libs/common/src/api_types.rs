@@ -150,6 +150,14 @@ impl AppError {
         }
     }
 
+    /// Create a 403 Forbidden error
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::FORBIDDEN,
+            error: ErrorInfo::new(message).with_code(403),
+        }
+    }
+
     /// Create a 404 Not Found error
     pub fn not_found(message: impl Into<String>) -> Self {
         Self {
@@ -203,6 +211,17 @@ impl IntoResponse for AppError {
     }
 }
 
+#[cfg(feature = "axum")]
+impl From<errors::VoltageError> for AppError {
+    fn from(err: errors::VoltageError) -> Self {
+        let code = err.status_code();
+        Self {
+            status: StatusCode::from_u16(code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+            error: ErrorInfo::new(err.to_string()).with_code(code),
+        }
+    }
+}
+
 #[cfg(feature = "axum")]
 impl From<anyhow::Error> for AppError {
     fn from(err: anyhow::Error) -> Self {
@@ -233,6 +252,9 @@ pub struct PaginatedResponse<T> {
     pub has_next: bool,
     /// Whether there are previous pages
     pub has_previous: bool,
+    /// Opaque cursor of the next page (keyset pagination)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
 // Note: Removed T: Clone constraint - these methods don't require cloning
@@ -248,9 +270,17 @@ impl<T> PaginatedResponse<T> {
             total_pages,
             has_next: page + 1 < total_pages,
             has_previous: page > 0,
+            next_cursor: None,
         }
     }
 
+    /// Attach the cursor of the next page; `None` marks the last page
+    pub fn with_next_cursor(mut self, cursor: Option<Cursor>) -> Self {
+        self.has_next = cursor.is_some();
+        self.next_cursor = cursor.map(|c| c.encode());
+        self
+    }
+
     /// Create paginated response from a slice with 1-indexed page number
     ///
     /// This is a convenience method that handles the common pagination pattern:
@@ -299,6 +329,45 @@ pub struct PaginationParams {
     /// Sort order
     #[serde(default)]
     pub sort_order: SortOrder,
+    /// Cursor from a previous page's `next_cursor` (takes precedence over `page`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+/// Opaque keyset pagination cursor
+///
+/// Carries the sort key of the last row of a page. Clients only pass back
+/// the encoded string from `next_cursor`; its content is not part of the API.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor(pub Vec<serde_json::Value>);
+
+impl Cursor {
+    pub fn new(key: Vec<serde_json::Value>) -> Self {
+        Self(key)
+    }
+
+    /// Sort key values, in key column order
+    pub fn key(&self) -> &[serde_json::Value] {
+        &self.0
+    }
+
+    /// URL-safe encoding used in `next_cursor` and the `cursor` parameter
+    pub fn encode(&self) -> String {
+        use base64::Engine;
+        let json = serde_json::to_vec(&self.0).unwrap_or_default();
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(json)
+    }
+
+    /// Decode a cursor received from a client
+    pub fn decode(cursor: &str) -> Result<Self, String> {
+        use base64::Engine;
+        let json = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(cursor.trim())
+            .map_err(|_| format!("Invalid cursor: {}", cursor))?;
+        serde_json::from_slice(&json)
+            .map(Self)
+            .map_err(|_| format!("Invalid cursor: {}", cursor))
+    }
 }
 
 /// Sort order
@@ -528,6 +597,22 @@ mod tests {
         assert!(!paginated.has_previous);
     }
 
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = Cursor::new(vec![serde_json::json!(9), serde_json::json!("a/b")]);
+        let encoded = cursor.encode();
+        assert!(encoded
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(Cursor::decode(&encoded).unwrap(), cursor);
+        assert!(Cursor::decode("not a cursor").is_err());
+
+        let page = PaginatedResponse::new(vec![1, 2], 10, 0, 2).with_next_cursor(None);
+        assert!(!page.has_next);
+        let json = serde_json::to_value(&page).unwrap();
+        assert!(json.get("next_cursor").is_none());
+    }
+
     #[test]
     fn test_time_range() {
         let range = TimeRange::last_hours(24);
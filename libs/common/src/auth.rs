@@ -0,0 +1,391 @@
+//! Bearer-token authentication for service APIs
+//!
+//! Services verify the HS256 access tokens issued by apigateway, signed with
+//! the shared `JWT_SECRET_KEY`, and hand the caller's [`Role`] to handlers.
+//! The [`authenticate`] middleware never rejects a request for lacking a
+//! token: requests without one (or any request when no secret is configured)
+//! run as an anonymous [`Caller`] with no role, and it is up to the handler
+//! to demand a role where it matters. A token that is present but invalid
+//! or expired is answered with 401.
+//!
+//! ```ignore
+//! let verifier = TokenVerifier::from_env().map(Arc::new);
+//! let app = Router::new()
+//!     // ... routes ...
+//!     .layer(axum::middleware::from_fn_with_state(verifier, authenticate));
+//!
+//! async fn handler(caller: Caller) -> Result<(), AppError> {
+//!     if !caller.has_role(Role::Operator) { ... }
+//! }
+//! ```
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sha2::Sha256;
+use std::fmt;
+
+/// Environment variable holding the token signing secret
+pub const JWT_SECRET_ENV: &str = "JWT_SECRET_KEY";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// User roles, ordered by privilege: a role includes every role below it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Viewer,
+    Operator,
+    Engineer,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Operator => "operator",
+            Role::Engineer => "engineer",
+            Role::Admin => "admin",
+        }
+    }
+
+    /// Parse a role name (case-insensitive, as issued by apigateway)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "viewer" => Some(Role::Viewer),
+            "operator" => Some(Role::Operator),
+            "engineer" => Some(Role::Engineer),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Who is making a request
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Caller {
+    pub username: Option<String>,
+    /// `None` for anonymous callers and unknown role names
+    pub role: Option<Role>,
+}
+
+impl Caller {
+    pub fn anonymous() -> Self {
+        Self::default()
+    }
+
+    /// Whether the caller holds `required` or a higher role
+    pub fn has_role(&self, required: Role) -> bool {
+        self.role.is_some_and(|role| role >= required)
+    }
+}
+
+impl fmt::Display for Caller {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.username, self.role) {
+            (Some(name), Some(role)) => write!(f, "{} ({})", name, role),
+            (Some(name), None) => write!(f, "{} (no role)", name),
+            (None, _) => f.write_str("anonymous"),
+        }
+    }
+}
+
+/// Why a token was refused
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AuthError {
+    #[error("malformed token: {0}")]
+    Malformed(&'static str),
+    #[error("unsupported token algorithm '{0}'")]
+    UnsupportedAlgorithm(String),
+    #[error("invalid token signature")]
+    BadSignature,
+    #[error("token expired")]
+    Expired,
+    #[error("not an access token")]
+    NotAccessToken,
+}
+
+/// Verifies HS256 access tokens against the shared secret
+pub struct TokenVerifier {
+    secret: Vec<u8>,
+}
+
+impl fmt::Debug for TokenVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenVerifier").finish_non_exhaustive()
+    }
+}
+
+impl TokenVerifier {
+    pub fn new(secret: impl AsRef<[u8]>) -> Self {
+        Self {
+            secret: secret.as_ref().to_vec(),
+        }
+    }
+
+    /// Verifier for `JWT_SECRET_KEY`, or `None` when it is unset or empty
+    pub fn from_env() -> Option<Self> {
+        std::env::var(JWT_SECRET_ENV)
+            .ok()
+            .filter(|secret| !secret.is_empty())
+            .map(Self::new)
+    }
+
+    fn mac(&self) -> HmacSha256 {
+        // HMAC accepts keys of any length
+        HmacSha256::new_from_slice(&self.secret).expect("HMAC key of any size")
+    }
+
+    /// Sign `claims` into a token (for tests and tooling; apigateway issues
+    /// the real ones)
+    pub fn sign(&self, claims: &JsonValue) -> String {
+        let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"HS256","typ":"JWT"}"#);
+        let payload = URL_SAFE_NO_PAD.encode(claims.to_string());
+        let signing_input = format!("{}.{}", header, payload);
+        let mut mac = self.mac();
+        mac.update(signing_input.as_bytes());
+        let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+        format!("{}.{}", signing_input, signature)
+    }
+
+    /// Check signature, expiry (`exp`, seconds) and token type, and return
+    /// the caller it identifies
+    pub fn verify(&self, token: &str, now_secs: i64) -> Result<Caller, AuthError> {
+        let Some((signing_input, signature)) = token.rsplit_once('.') else {
+            return Err(AuthError::Malformed("expected three segments"));
+        };
+        let Some((header, payload)) = signing_input
+            .split_once('.')
+            .filter(|(_, payload)| !payload.contains('.'))
+        else {
+            return Err(AuthError::Malformed("expected three segments"));
+        };
+
+        let header: JsonValue = decode_segment(header)?;
+        let alg = header.get("alg").and_then(|v| v.as_str()).unwrap_or("");
+        if alg != "HS256" {
+            return Err(AuthError::UnsupportedAlgorithm(alg.to_string()));
+        }
+
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| AuthError::Malformed("signature is not base64url"))?;
+        let mut mac = self.mac();
+        mac.update(signing_input.as_bytes());
+        mac.verify_slice(&signature)
+            .map_err(|_| AuthError::BadSignature)?;
+
+        let claims: JsonValue = decode_segment(payload)?;
+        match claims.get("exp").and_then(|v| v.as_i64()) {
+            Some(exp) if exp > now_secs => {},
+            Some(_) => return Err(AuthError::Expired),
+            None => return Err(AuthError::Malformed("missing exp claim")),
+        }
+        if let Some(kind) = claims.get("type").and_then(|v| v.as_str()) {
+            if kind != "access" {
+                return Err(AuthError::NotAccessToken);
+            }
+        }
+
+        let username = claims
+            .get("username")
+            .or_else(|| claims.get("sub"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+        let role = claims
+            .get("role")
+            .and_then(|v| v.as_str())
+            .and_then(Role::parse);
+        Ok(Caller { username, role })
+    }
+}
+
+fn decode_segment(segment: &str) -> Result<JsonValue, AuthError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(segment)
+        .map_err(|_| AuthError::Malformed("segment is not base64url"))?;
+    serde_json::from_slice(&bytes).map_err(|_| AuthError::Malformed("segment is not JSON"))
+}
+
+#[cfg(feature = "axum")]
+pub use middleware::authenticate;
+
+#[cfg(feature = "axum")]
+mod middleware {
+    use super::{Caller, TokenVerifier};
+    use crate::api_types::AppError;
+    use axum::{
+        extract::{FromRequestParts, Request, State},
+        http::{header, request::Parts},
+        middleware::Next,
+        response::{IntoResponse, Response},
+    };
+    use errors::VoltageError;
+    use std::convert::Infallible;
+    use std::sync::Arc;
+
+    /// Axum middleware attaching the [`Caller`] to the request (use with
+    /// `from_fn_with_state`; a `None` verifier makes every caller anonymous)
+    pub async fn authenticate(
+        State(verifier): State<Option<Arc<TokenVerifier>>>,
+        mut req: Request,
+        next: Next,
+    ) -> Response {
+        let token = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(str::trim);
+
+        let caller = match (token, verifier.as_deref()) {
+            (Some(token), Some(verifier)) => {
+                match verifier.verify(token, chrono::Utc::now().timestamp()) {
+                    Ok(caller) => caller,
+                    Err(e) => {
+                        tracing::debug!(
+                            "Rejected token on {} {}: {}",
+                            req.method(),
+                            req.uri().path(),
+                            e
+                        );
+                        return AppError::from(VoltageError::Unauthorized(e.to_string()))
+                            .into_response();
+                    },
+                }
+            },
+            _ => Caller::anonymous(),
+        };
+        req.extensions_mut().insert(caller);
+        next.run(req).await
+    }
+
+    /// Handlers take a `Caller` argument; it is anonymous on routes outside
+    /// the [`authenticate`] layer
+    impl<S: Send + Sync> FromRequestParts<S> for Caller {
+        type Rejection = Infallible;
+
+        async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Infallible> {
+            Ok(parts
+                .extensions
+                .get::<Caller>()
+                .cloned()
+                .unwrap_or_default())
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn token(verifier: &TokenVerifier, role: &str, exp: i64) -> String {
+        verifier.sign(&json!({
+            "user_id": 7,
+            "username": "alice",
+            "role": role,
+            "exp": exp,
+            "type": "access",
+        }))
+    }
+
+    #[test]
+    fn test_verify_token() {
+        let verifier = TokenVerifier::new("secret");
+        let caller = verifier
+            .verify(&token(&verifier, "Engineer", NOW + 60), NOW)
+            .unwrap();
+        assert_eq!(caller.username.as_deref(), Some("alice"));
+        assert_eq!(caller.role, Some(Role::Engineer));
+
+        assert_eq!(
+            verifier.verify(&token(&verifier, "Engineer", NOW), NOW),
+            Err(AuthError::Expired)
+        );
+        let other = TokenVerifier::new("other");
+        assert_eq!(
+            verifier.verify(&token(&other, "Admin", NOW + 60), NOW),
+            Err(AuthError::BadSignature)
+        );
+        let refresh =
+            verifier.sign(&json!({"username": "alice", "exp": NOW + 60, "type": "refresh"}));
+        assert_eq!(
+            verifier.verify(&refresh, NOW),
+            Err(AuthError::NotAccessToken)
+        );
+        assert!(matches!(
+            verifier.verify("not-a-token", NOW),
+            Err(AuthError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_role_ordering() {
+        let engineer = Caller {
+            username: None,
+            role: Some(Role::Engineer),
+        };
+        assert!(engineer.has_role(Role::Operator));
+        assert!(engineer.has_role(Role::Engineer));
+        assert!(!engineer.has_role(Role::Admin));
+        assert!(!Caller::anonymous().has_role(Role::Viewer));
+        assert_eq!(Role::parse("ADMIN"), Some(Role::Admin));
+        assert_eq!(Role::parse("guest"), None);
+    }
+
+    #[cfg(feature = "axum")]
+    #[tokio::test]
+    async fn test_middleware_attaches_caller() {
+        use axum::{
+            body::Body,
+            http::{header, Request, StatusCode},
+            middleware,
+            routing::get,
+            Router,
+        };
+        use std::sync::Arc;
+        use tower::ServiceExt;
+
+        let verifier = Arc::new(TokenVerifier::new("secret"));
+        let valid = token(&verifier, "Operator", chrono::Utc::now().timestamp() + 60);
+        let app = Router::new()
+            .route("/", get(|caller: Caller| async move { caller.to_string() }))
+            .layer(middleware::from_fn_with_state(Some(verifier), authenticate));
+        let call = |auth: Option<String>| {
+            let mut request = Request::get("/");
+            if let Some(auth) = auth {
+                request = request.header(header::AUTHORIZATION, auth);
+            }
+            app.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        let response = call(Some(format!("Bearer {}", valid))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), 1024)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"alice (operator)");
+
+        let response = call(None).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), 1024)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"anonymous");
+
+        let response = call(Some("Bearer garbage".into())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -1,57 +1,107 @@
 //! Common command-line arguments for all VoltageEMS services
 //!
-//! Provides a unified argument structure that can be extended by individual services
+//! Every service accepts the same standard flags ([`ServiceArgs`]) and adds
+//! its own through a flattened `clap::Args` struct:
+//!
+//! ```ignore
+//! #[derive(clap::Args, Debug, Clone)]
+//! pub struct ModsrvArgs {
+//!     /// Start without instances
+//!     #[arg(long, env = "MODSRV_ALLOW_EMPTY")]
+//!     pub allow_empty: bool,
+//! }
+//!
+//! let (args, modsrv_args) = ServiceArgs::parse_with::<ModsrvArgs>(&service_info);
+//! args.apply_env();
+//! ```
+//!
+//! Services without flags of their own use [`NoExtraArgs`].
+
+use std::path::PathBuf;
 
 #[cfg(feature = "cli")]
-use clap::Parser;
+use clap::{Args, CommandFactory, FromArgMatches, Parser};
 
 /// Common service startup arguments
 ///
 /// These arguments are shared by all VoltageEMS services and provide
 /// standard configuration options for logging, debugging, and validation.
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "cli", derive(Parser))]
-#[cfg_attr(feature = "cli", clap(author, version, about))]
+#[cfg_attr(feature = "cli", derive(Args))]
 pub struct ServiceArgs {
+    /// Environment file (KEY=VALUE lines) loaded before startup; variables
+    /// already set in the environment take precedence
+    #[cfg_attr(
+        feature = "cli",
+        arg(short = 'c', long, env = "VOLTAGE_ENV_FILE", value_name = "FILE")
+    )]
+    pub config: Option<PathBuf>,
+
     /// Log level (trace, debug, info, warn, error)
     #[cfg_attr(
         feature = "cli",
-        clap(short = 'l', long, default_value = "info", env = "RUST_LOG")
+        arg(short = 'l', long, default_value = "info", env = "RUST_LOG")
     )]
     pub log_level: String,
 
-    /// Optional bind address override (e.g., 127.0.0.1:6001)
-    #[cfg_attr(feature = "cli", clap(short = 'b', long, env = "BIND_ADDRESS"))]
+    /// Bind address override for the API server (e.g., 127.0.0.1:6001)
+    #[cfg_attr(
+        feature = "cli",
+        arg(
+            short = 'b',
+            long = "bind",
+            alias = "bind-address",
+            env = "BIND_ADDRESS",
+            value_name = "ADDR"
+        )
+    )]
     pub bind_address: Option<String>,
 
     /// Enable debug mode with verbose output
-    #[cfg_attr(feature = "cli", clap(long, env = "DEBUG"))]
+    #[cfg_attr(feature = "cli", arg(short = 'd', long, env = "DEBUG"))]
     pub debug: bool,
 
     /// Disable colored output (useful for log files)
-    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "cli", arg(long))]
     pub no_color: bool,
 
-    /// Only validate configuration without starting service
-    #[cfg_attr(feature = "cli", clap(long))]
+    /// Validate the configuration, run the consistency check and exit
+    #[cfg_attr(feature = "cli", arg(long))]
     pub validate: bool,
 
     /// Watch configuration for changes and reload automatically
-    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "cli", arg(long))]
     pub watch: bool,
 
     /// Custom database path override
-    #[cfg_attr(feature = "cli", clap(long, env = "DB_PATH"))]
+    #[cfg_attr(feature = "cli", arg(long, env = "DB_PATH", value_name = "PATH"))]
     pub db_path: Option<String>,
 
-    /// Redis URL override
-    #[cfg_attr(feature = "cli", clap(long, env = "REDIS_URL"))]
+    /// Redis URL override (takes precedence over the database and `REDIS_URL`)
+    #[cfg_attr(feature = "cli", arg(long, value_name = "URL"))]
     pub redis_url: Option<String>,
 }
 
+/// Extension of services without flags of their own
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "cli", derive(Args))]
+pub struct NoExtraArgs {}
+
+/// Command line of a service: standard flags plus `E`
+#[cfg(feature = "cli")]
+#[derive(Debug, Parser)]
+struct ServiceCommand<E: Args> {
+    #[command(flatten)]
+    common: ServiceArgs,
+
+    #[command(flatten)]
+    service: E,
+}
+
 impl Default for ServiceArgs {
     fn default() -> Self {
         Self {
+            config: None,
             log_level: "info".to_string(),
             bind_address: None,
             debug: false,
@@ -64,7 +114,65 @@ impl Default for ServiceArgs {
     }
 }
 
+#[cfg(feature = "cli")]
 impl ServiceArgs {
+    /// Parse the process arguments of `service`, exiting with usage on error
+    pub fn parse_with<E: Args + FromArgMatches>(
+        service: &crate::service_bootstrap::ServiceInfo,
+    ) -> (Self, E) {
+        let matches = Self::command_with::<E>(service).get_matches();
+        match ServiceCommand::<E>::from_arg_matches(&matches) {
+            Ok(parsed) => (parsed.common, parsed.service),
+            Err(e) => e.exit(),
+        }
+    }
+
+    /// Parse `args` (first item is the binary name)
+    pub fn try_parse_from<E, I, T>(
+        service: &crate::service_bootstrap::ServiceInfo,
+        args: I,
+    ) -> Result<(Self, E), clap::Error>
+    where
+        E: Args + FromArgMatches,
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        let matches = Self::command_with::<E>(service).try_get_matches_from(args)?;
+        let parsed = ServiceCommand::<E>::from_arg_matches(&matches)?;
+        Ok((parsed.common, parsed.service))
+    }
+
+    fn command_with<E: Args>(service: &crate::service_bootstrap::ServiceInfo) -> clap::Command {
+        ServiceCommand::<E>::command()
+            .name(service.name.clone())
+            .version(service.version.clone())
+            .about(service.description.clone())
+    }
+}
+
+impl ServiceArgs {
+    /// Apply the arguments that act through the environment
+    ///
+    /// Loads the `--config` file and exports `--db-path` as `VOLTAGE_DB_PATH`,
+    /// which every database lookup of the services reads. Call right after
+    /// parsing, before anything else reads the environment.
+    pub fn apply_env(&self) -> std::io::Result<()> {
+        if let Some(path) = &self.config {
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+            crate::service_bootstrap::load_env_content(&content);
+        }
+        if let Some(path) = &self.db_path {
+            std::env::set_var("VOLTAGE_DB_PATH", path);
+        }
+        Ok(())
+    }
+
+    /// Redis URL to connect to: `--redis-url`, else the database setting
+    pub fn redis_url_or(&self, db_url: Option<String>) -> Option<String> {
+        self.redis_url.clone().or(db_url)
+    }
+
     /// Parse log level string to tracing::Level
     pub fn parse_log_level(&self) -> tracing::Level {
         match self.log_level.to_lowercase().as_str() {
@@ -164,6 +272,48 @@ mod tests {
         assert!(args.is_development());
     }
 
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_parse_with_service_flags() {
+        #[derive(Debug, clap::Args)]
+        struct Extra {
+            #[arg(long)]
+            allow_empty: bool,
+        }
+
+        let service = crate::service_bootstrap::ServiceInfo::new("modsrv", "Model Service", 6002);
+        let (args, extra) = ServiceArgs::try_parse_from::<Extra, _, _>(
+            &service,
+            [
+                "modsrv",
+                "--bind-address",
+                "127.0.0.1:7002",
+                "--db-path",
+                "/tmp/v.db",
+                "--redis-url",
+                "redis://cache:6379",
+                "--validate",
+                "--allow-empty",
+            ],
+        )
+        .unwrap();
+        assert_eq!(args.bind_address.as_deref(), Some("127.0.0.1:7002"));
+        assert_eq!(args.db_path.as_deref(), Some("/tmp/v.db"));
+        assert_eq!(
+            args.redis_url_or(Some("redis://db:6379".to_string())),
+            Some("redis://cache:6379".to_string())
+        );
+        assert!(args.validate);
+        assert!(extra.allow_empty);
+
+        // Service flags are unknown to other services
+        assert!(ServiceArgs::try_parse_from::<NoExtraArgs, _, _>(
+            &service,
+            ["comsrv", "--allow-empty"]
+        )
+        .is_err());
+    }
+
     #[test]
     fn test_get_db_path() {
         // Clean up any environment variables that might affect the test
@@ -0,0 +1,3 @@
+//! Byte-level helpers shared by protocol implementations
+
+pub mod checksum;
@@ -0,0 +1,227 @@
+//! Frame checksums
+//!
+//! The checksums field protocols put on their frames, with lookup tables
+//! built at compile time and streaming digests for frames that arrive in
+//! pieces:
+//!
+//! - [`CRC16_MODBUS`]: Modbus RTU (poly 0x8005 reflected, init 0xFFFF),
+//!   sent low byte first
+//! - [`CRC16_CCITT`]: CRC-16/CCITT-FALSE (poly 0x1021, init 0xFFFF), sent
+//!   high byte first
+//! - [`CRC16_DNP`]: DNP3 link layer (poly 0x3D65 reflected, complemented),
+//!   sent low byte first
+//! - [`CRC16_X25`]: HDLC frame check sequence, e.g. DLMS/COSEM (poly 0x1021
+//!   reflected, init 0xFFFF, complemented), sent low byte first
+//! - [`lrc`]: Modbus ASCII longitudinal redundancy check (two's complement
+//!   of the byte sum)
+//! - [`sum8`] / [`sum8_complement`]: byte sum modulo 256 (DL/T 645,
+//!   IEC 60870-5 FT1.2) and its ones' complement
+//!
+//! ```
+//! use common::bytes::checksum::{Crc16, CRC16_MODBUS};
+//!
+//! let frame = [0x01, 0x03, 0x00, 0x00, 0x00, 0x01];
+//! let mut digest = Crc16::new(&CRC16_MODBUS);
+//! digest.update(&frame[..2]);
+//! digest.update(&frame[2..]);
+//! assert_eq!(digest.finish(), CRC16_MODBUS.checksum(&frame));
+//! ```
+
+/// A CRC-16 variant with its precomputed table
+#[derive(Debug)]
+pub struct Crc16Algorithm {
+    table: [u16; 256],
+    init: u16,
+    reflected: bool,
+    xorout: u16,
+}
+
+impl Crc16Algorithm {
+    /// CRC-16 of the (unreflected) polynomial `poly`
+    pub const fn new(poly: u16, init: u16, reflected: bool, xorout: u16) -> Self {
+        let mut table = [0u16; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc;
+            let mut bit = 0;
+            if reflected {
+                let poly = poly.reverse_bits();
+                crc = i as u16;
+                while bit < 8 {
+                    crc = if crc & 1 != 0 {
+                        (crc >> 1) ^ poly
+                    } else {
+                        crc >> 1
+                    };
+                    bit += 1;
+                }
+            } else {
+                crc = (i as u16) << 8;
+                while bit < 8 {
+                    crc = if crc & 0x8000 != 0 {
+                        (crc << 1) ^ poly
+                    } else {
+                        crc << 1
+                    };
+                    bit += 1;
+                }
+            }
+            table[i] = crc;
+            i += 1;
+        }
+        Self {
+            table,
+            init,
+            reflected,
+            xorout,
+        }
+    }
+
+    /// CRC of a complete buffer
+    pub fn checksum(&self, data: &[u8]) -> u16 {
+        let mut digest = Crc16::new(self);
+        digest.update(data);
+        digest.finish()
+    }
+
+    fn step(&self, crc: u16, byte: u8) -> u16 {
+        if self.reflected {
+            (crc >> 8) ^ self.table[usize::from((crc as u8) ^ byte)]
+        } else {
+            (crc << 8) ^ self.table[usize::from(((crc >> 8) as u8) ^ byte)]
+        }
+    }
+}
+
+/// CRC-16/MODBUS
+pub static CRC16_MODBUS: Crc16Algorithm = Crc16Algorithm::new(0x8005, 0xFFFF, true, 0x0000);
+
+/// CRC-16/CCITT-FALSE
+pub static CRC16_CCITT: Crc16Algorithm = Crc16Algorithm::new(0x1021, 0xFFFF, false, 0x0000);
+
+/// CRC-16/DNP
+pub static CRC16_DNP: Crc16Algorithm = Crc16Algorithm::new(0x3D65, 0x0000, true, 0xFFFF);
+
+/// CRC-16/X-25 (HDLC FCS)
+pub static CRC16_X25: Crc16Algorithm = Crc16Algorithm::new(0x1021, 0xFFFF, true, 0xFFFF);
+
+/// Streaming CRC-16 digest
+#[derive(Debug, Clone, Copy)]
+pub struct Crc16<'a> {
+    algorithm: &'a Crc16Algorithm,
+    crc: u16,
+}
+
+impl<'a> Crc16<'a> {
+    pub fn new(algorithm: &'a Crc16Algorithm) -> Self {
+        Self {
+            algorithm,
+            crc: algorithm.init,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.crc = self.algorithm.step(self.crc, byte);
+        }
+    }
+
+    /// CRC of the bytes fed so far; the digest can keep going
+    pub fn finish(&self) -> u16 {
+        self.crc ^ self.algorithm.xorout
+    }
+}
+
+/// Streaming byte sum modulo 256
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sum8 {
+    sum: u8,
+}
+
+impl Sum8 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.sum = data.iter().fold(self.sum, |sum, b| sum.wrapping_add(*b));
+    }
+
+    /// Byte sum
+    pub fn sum(&self) -> u8 {
+        self.sum
+    }
+
+    /// Ones' complement of the sum
+    pub fn complement(&self) -> u8 {
+        !self.sum
+    }
+
+    /// Two's complement of the sum (LRC): adding it to the sum gives zero
+    pub fn lrc(&self) -> u8 {
+        self.sum.wrapping_neg()
+    }
+}
+
+/// Byte sum modulo 256
+pub fn sum8(data: &[u8]) -> u8 {
+    let mut sum = Sum8::new();
+    sum.update(data);
+    sum.sum()
+}
+
+/// Ones' complement of the byte sum
+pub fn sum8_complement(data: &[u8]) -> u8 {
+    !sum8(data)
+}
+
+/// Modbus ASCII LRC of the binary message (address through data)
+pub fn lrc(data: &[u8]) -> u8 {
+    sum8(data).wrapping_neg()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHECK: &[u8] = b"123456789";
+
+    #[test]
+    fn test_crc16_check_values() {
+        assert_eq!(CRC16_MODBUS.checksum(CHECK), 0x4B37);
+        assert_eq!(CRC16_CCITT.checksum(CHECK), 0x29B1);
+        assert_eq!(CRC16_DNP.checksum(CHECK), 0xEA82);
+        assert_eq!(CRC16_X25.checksum(CHECK), 0x906E);
+        // Read holding register 0 of unit 1: CRC bytes 84 0A on the wire
+        assert_eq!(
+            CRC16_MODBUS.checksum(&[0x01, 0x03, 0x00, 0x00, 0x00, 0x01]),
+            0x0A84
+        );
+        assert_eq!(CRC16_MODBUS.checksum(&[]), 0xFFFF);
+    }
+
+    #[test]
+    fn test_streaming_matches_one_shot() {
+        for algorithm in [&CRC16_MODBUS, &CRC16_CCITT, &CRC16_DNP, &CRC16_X25] {
+            let mut digest = Crc16::new(algorithm);
+            for chunk in CHECK.chunks(4) {
+                digest.update(chunk);
+            }
+            assert_eq!(digest.finish(), algorithm.checksum(CHECK));
+        }
+        let mut sum = Sum8::new();
+        sum.update(&CHECK[..3]);
+        sum.update(&CHECK[3..]);
+        assert_eq!(sum.sum(), sum8(CHECK));
+    }
+
+    #[test]
+    fn test_sum_checksums() {
+        assert_eq!(sum8(CHECK), 0xDD);
+        assert_eq!(sum8_complement(CHECK), 0x22);
+        assert_eq!(lrc(CHECK), 0x23);
+        assert_eq!(sum8(CHECK).wrapping_add(lrc(CHECK)), 0);
+        // Modbus ASCII ":010300000001FB"
+        assert_eq!(lrc(&[0x01, 0x03, 0x00, 0x00, 0x00, 0x01]), 0xFB);
+    }
+}
@@ -0,0 +1,681 @@
+//! Cross-service configuration consistency
+//!
+//! comsrv and modsrv share one SQLite database but each only validates its own
+//! tables. This pass cross-checks the stored configuration at startup:
+//! routing rows against comsrv channels/points and modsrv instances, and rule
+//! documents against instances and their product points. Problems are logged
+//! as structured warnings and kept as the latest [`ConsistencyReport`], served
+//! by both services at `GET /health/config`.
+//!
+//! ```ignore
+//! let report = common::config_consistency::run_startup_check(&pool, "comsrv").await;
+//! ```
+//!
+//! The checks mirror `monarch config lint`, which runs the same kind of checks
+//! on the config directory before it is synced.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{OnceLock, RwLock};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use tracing::{error, info, warn};
+use voltage_model::product_lib::{get_builtin_product, get_builtin_products, BuiltinProduct};
+
+/// Issue severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum ConsistencySeverity {
+    /// The reference cannot work (data or commands are dropped)
+    Error,
+    /// Configured but inactive, likely unintended
+    Warning,
+}
+
+/// Single dangling or suspicious reference
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ConsistencyIssue {
+    /// Check identifier (e.g. "routing-unknown-channel")
+    pub check: &'static str,
+    pub severity: ConsistencySeverity,
+    /// Table row or rule the issue refers to (e.g. "measurement_routing #12")
+    pub source: String,
+    pub message: String,
+}
+
+/// Result of one consistency pass
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ConsistencyReport {
+    /// Service that ran the check
+    pub service: String,
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub checked_at: DateTime<Utc>,
+    /// "ok", "warning" or "error"
+    pub status: &'static str,
+    pub errors: usize,
+    pub warnings: usize,
+    /// Routing rows and rule references examined
+    pub checked: usize,
+    pub issues: Vec<ConsistencyIssue>,
+}
+
+impl ConsistencyReport {
+    fn new(service: &str, checked: usize, mut issues: Vec<ConsistencyIssue>) -> Self {
+        issues.sort_by(|a, b| a.severity.cmp(&b.severity).then(a.source.cmp(&b.source)));
+        let errors = issues
+            .iter()
+            .filter(|i| i.severity == ConsistencySeverity::Error)
+            .count();
+        let warnings = issues.len() - errors;
+        let status = if errors > 0 {
+            "error"
+        } else if warnings > 0 {
+            "warning"
+        } else {
+            "ok"
+        };
+        Self {
+            service: service.to_string(),
+            checked_at: Utc::now(),
+            status,
+            errors,
+            warnings,
+            checked,
+            issues,
+        }
+    }
+
+    /// Whether no issues were found
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+// ============================================================================
+// Latest report (served by /health/config)
+// ============================================================================
+
+fn latest() -> &'static RwLock<Option<ConsistencyReport>> {
+    static LATEST: OnceLock<RwLock<Option<ConsistencyReport>>> = OnceLock::new();
+    LATEST.get_or_init(|| RwLock::new(None))
+}
+
+/// Latest report of this process (None before the startup check ran)
+pub fn latest_report() -> Option<ConsistencyReport> {
+    latest().read().ok().and_then(|r| r.clone())
+}
+
+fn store_report(report: &ConsistencyReport) {
+    if let Ok(mut slot) = latest().write() {
+        *slot = Some(report.clone());
+    }
+}
+
+/// Run the check, log every issue and keep the report for `/health/config`
+///
+/// Never fails startup: a database error is logged and yields no report.
+#[cfg(feature = "sqlite")]
+pub async fn run_startup_check(
+    pool: &sqlx::SqlitePool,
+    service: &str,
+) -> Option<ConsistencyReport> {
+    let report = match check_consistency(pool, service).await {
+        Ok(report) => report,
+        Err(e) => {
+            error!("Config consistency check failed: {}", e);
+            return None;
+        },
+    };
+
+    for issue in &report.issues {
+        warn!(
+            check = issue.check,
+            severity = ?issue.severity,
+            source = %issue.source,
+            "Config consistency: {}",
+            issue.message
+        );
+    }
+    if report.is_clean() {
+        info!(
+            "Config consistency: {} references checked, no issues",
+            report.checked
+        );
+    } else {
+        warn!(
+            "Config consistency: {} errors, {} warnings in {} references (GET /health/config)",
+            report.errors, report.warnings, report.checked
+        );
+    }
+
+    store_report(&report);
+    Some(report)
+}
+
+// ============================================================================
+// Checks
+// ============================================================================
+
+/// Channel point tables by routing type
+const POINT_TABLES: [(&str, &str); 4] = [
+    ("T", "telemetry_points"),
+    ("S", "signal_points"),
+    ("C", "control_points"),
+    ("A", "adjustment_points"),
+];
+
+/// Configuration snapshot the checks run against
+#[derive(Debug, Default)]
+struct ConfigSnapshot {
+    /// channel_id -> (name, enabled)
+    channels: HashMap<u32, (String, bool)>,
+    /// (channel_id, type, point_id)
+    points: HashSet<(u32, String, u32)>,
+    /// instance_id -> (name, product)
+    instances: HashMap<u32, (String, String)>,
+}
+
+impl ConfigSnapshot {
+    fn instance_by_name(&self, name: &str) -> Option<(u32, &(String, String))> {
+        self.instances
+            .iter()
+            .find(|(_, (n, _))| n == name)
+            .map(|(id, info)| (*id, info))
+    }
+}
+
+/// Routing row as stored (channel side may be unbound)
+struct RouteRow {
+    table: &'static str,
+    routing_id: i64,
+    instance_id: u32,
+    /// "M" or "A"
+    instance_type: &'static str,
+    instance_point_id: u32,
+    channel_id: Option<u32>,
+    channel_type: Option<String>,
+    channel_point_id: Option<u32>,
+}
+
+/// Cross-check the configuration stored in SQLite
+///
+/// Tables that do not exist (e.g. `rules` in a comsrv-only database) are
+/// skipped.
+#[cfg(feature = "sqlite")]
+pub async fn check_consistency(
+    pool: &sqlx::SqlitePool,
+    service: &str,
+) -> Result<ConsistencyReport, sqlx::Error> {
+    let mut snapshot = ConfigSnapshot::default();
+    let mut issues = Vec::new();
+    let mut checked = 0;
+
+    if table_exists(pool, "channels").await? {
+        let rows: Vec<(u32, String, bool)> =
+            sqlx::query_as("SELECT channel_id, name, enabled FROM channels")
+                .fetch_all(pool)
+                .await?;
+        snapshot.channels = rows
+            .into_iter()
+            .map(|(id, name, enabled)| (id, (name, enabled)))
+            .collect();
+    }
+    for (point_type, table) in POINT_TABLES {
+        if !table_exists(pool, table).await? {
+            continue;
+        }
+        let rows: Vec<(u32, u32)> =
+            sqlx::query_as(&format!("SELECT channel_id, point_id FROM {}", table))
+                .fetch_all(pool)
+                .await?;
+        snapshot.points.extend(
+            rows.into_iter()
+                .map(|(channel_id, point_id)| (channel_id, point_type.to_string(), point_id)),
+        );
+    }
+    if table_exists(pool, "instances").await? {
+        let rows: Vec<(u32, String, String)> =
+            sqlx::query_as("SELECT instance_id, instance_name, product_name FROM instances")
+                .fetch_all(pool)
+                .await?;
+        snapshot.instances = rows
+            .into_iter()
+            .map(|(id, name, product)| (id, (name, product)))
+            .collect();
+    }
+
+    let mut routes = Vec::new();
+    for (table, point_column, instance_type) in [
+        ("measurement_routing", "measurement_id", "M"),
+        ("action_routing", "action_id", "A"),
+    ] {
+        if table_exists(pool, table).await? {
+            load_routes(pool, table, point_column, instance_type, &mut routes).await?;
+        }
+    }
+    checked += routes.len();
+    for route in &routes {
+        check_route(&snapshot, route, &mut issues);
+    }
+
+    if table_exists(pool, "channel_routing").await? {
+        let rows: Vec<(u32, String, u32, u32, String, u32)> = sqlx::query_as(
+            "SELECT source_channel_id, source_type, source_point_id, \
+             target_channel_id, target_type, target_point_id \
+             FROM channel_routing WHERE enabled = TRUE",
+        )
+        .fetch_all(pool)
+        .await?;
+        checked += rows.len();
+        for (
+            source_channel,
+            source_type,
+            source_point,
+            target_channel,
+            target_type,
+            target_point,
+        ) in rows
+        {
+            let source = format!(
+                "channel_routing {}:{}:{}",
+                source_channel, source_type, source_point
+            );
+            for (channel_id, point_type, point_id) in [
+                (source_channel, source_type, source_point),
+                (target_channel, target_type, target_point),
+            ] {
+                check_channel_point(
+                    &snapshot,
+                    &source,
+                    channel_id,
+                    &point_type,
+                    point_id,
+                    &mut issues,
+                );
+            }
+        }
+    }
+
+    if table_exists(pool, "rules").await? {
+        let rows: Vec<(i64, String, String, Option<String>)> = sqlx::query_as(
+            "SELECT id, name, nodes_json, flow_json FROM rules WHERE enabled = TRUE",
+        )
+        .fetch_all(pool)
+        .await?;
+        for (id, name, nodes_json, flow_json) in rows {
+            let source = format!("rule #{} '{}'", id, name);
+            let mut refs = Vec::new();
+            for document in std::iter::once(nodes_json).chain(flow_json) {
+                if let Ok(value) = serde_json::from_str::<JsonValue>(&document) {
+                    collect_rule_references(&value, &mut refs);
+                }
+            }
+            refs.sort_by(|a, b| format!("{:?}", a).cmp(&format!("{:?}", b)));
+            refs.dedup();
+            checked += refs.len();
+            for reference in refs {
+                check_rule_reference(&snapshot, &source, reference, &mut issues);
+            }
+        }
+    }
+
+    Ok(ConsistencyReport::new(service, checked, issues))
+}
+
+/// Routing row columns: routing_id, instance_id, point_id, channel_id, channel_type, channel_point_id
+type RouteColumns = (i64, u32, u32, Option<u32>, Option<String>, Option<u32>);
+
+#[cfg(feature = "sqlite")]
+async fn load_routes(
+    pool: &sqlx::SqlitePool,
+    table: &'static str,
+    point_column: &str,
+    instance_type: &'static str,
+    routes: &mut Vec<RouteRow>,
+) -> Result<(), sqlx::Error> {
+    let rows: Vec<RouteColumns> = sqlx::query_as(&format!(
+        "SELECT routing_id, instance_id, {}, channel_id, channel_type, channel_point_id \
+         FROM {} WHERE enabled = TRUE",
+        point_column, table
+    ))
+    .fetch_all(pool)
+    .await?;
+    routes.extend(rows.into_iter().map(
+        |(routing_id, instance_id, point_id, channel_id, channel_type, channel_point_id)| {
+            RouteRow {
+                table,
+                routing_id,
+                instance_id,
+                instance_type,
+                instance_point_id: point_id,
+                channel_id,
+                channel_type,
+                channel_point_id,
+            }
+        },
+    ));
+    Ok(())
+}
+
+#[cfg(feature = "sqlite")]
+async fn table_exists(pool: &sqlx::SqlitePool, table: &str) -> Result<bool, sqlx::Error> {
+    let found: Option<(String,)> =
+        sqlx::query_as("SELECT name FROM sqlite_master WHERE type = 'table' AND name = ?")
+            .bind(table)
+            .fetch_optional(pool)
+            .await?;
+    Ok(found.is_some())
+}
+
+fn check_route(snapshot: &ConfigSnapshot, route: &RouteRow, issues: &mut Vec<ConsistencyIssue>) {
+    let source = format!("{} #{}", route.table, route.routing_id);
+    let point = format!(
+        "{}:{}:{}",
+        route.instance_id, route.instance_type, route.instance_point_id
+    );
+
+    match snapshot.instances.get(&route.instance_id) {
+        None => issues.push(ConsistencyIssue {
+            check: "routing-unknown-instance",
+            severity: ConsistencySeverity::Error,
+            source: source.clone(),
+            message: format!(
+                "Route {} refers to missing instance {}",
+                point, route.instance_id
+            ),
+        }),
+        Some((name, product)) => {
+            if let Some(product) = builtin_product(product) {
+                if !product_has_point(product, route.instance_type, route.instance_point_id) {
+                    issues.push(ConsistencyIssue {
+                        check: "routing-unknown-instance-point",
+                        severity: ConsistencySeverity::Error,
+                        source: source.clone(),
+                        message: format!(
+                            "Instance '{}' (product '{}') has no {} point {}",
+                            name, product.name, route.instance_type, route.instance_point_id
+                        ),
+                    });
+                }
+            }
+        },
+    }
+
+    let (Some(channel_id), Some(channel_type), Some(channel_point_id)) = (
+        route.channel_id,
+        route.channel_type.as_deref(),
+        route.channel_point_id,
+    ) else {
+        issues.push(ConsistencyIssue {
+            check: "routing-unbound",
+            severity: ConsistencySeverity::Warning,
+            source,
+            message: format!("Enabled route for {} has no channel point", point),
+        });
+        return;
+    };
+    check_channel_point(
+        snapshot,
+        &source,
+        channel_id,
+        channel_type,
+        channel_point_id,
+        issues,
+    );
+}
+
+fn check_channel_point(
+    snapshot: &ConfigSnapshot,
+    source: &str,
+    channel_id: u32,
+    point_type: &str,
+    point_id: u32,
+    issues: &mut Vec<ConsistencyIssue>,
+) {
+    let point = format!("{}:{}:{}", channel_id, point_type, point_id);
+    match snapshot.channels.get(&channel_id) {
+        None => {
+            issues.push(ConsistencyIssue {
+                check: "routing-unknown-channel",
+                severity: ConsistencySeverity::Error,
+                source: source.to_string(),
+                message: format!(
+                    "Channel point {} refers to missing channel {}",
+                    point, channel_id
+                ),
+            });
+            return;
+        },
+        Some((name, false)) => issues.push(ConsistencyIssue {
+            check: "routing-disabled-channel",
+            severity: ConsistencySeverity::Warning,
+            source: source.to_string(),
+            message: format!(
+                "Channel {} ('{}') of {} is disabled",
+                channel_id, name, point
+            ),
+        }),
+        Some(_) => {},
+    }
+
+    if !snapshot
+        .points
+        .contains(&(channel_id, point_type.to_string(), point_id))
+    {
+        issues.push(ConsistencyIssue {
+            check: "routing-unknown-point",
+            severity: ConsistencySeverity::Error,
+            source: source.to_string(),
+            message: format!(
+                "Channel {} has no {} point {}",
+                channel_id, point_type, point_id
+            ),
+        });
+    }
+}
+
+fn check_rule_reference(
+    snapshot: &ConfigSnapshot,
+    source: &str,
+    (instance, point_type, point_id): (RuleInstance, String, u32),
+    issues: &mut Vec<ConsistencyIssue>,
+) {
+    let resolved = match &instance {
+        RuleInstance::Id(id) => snapshot.instances.get(id).map(|info| (*id, info)),
+        RuleInstance::Name(name) => snapshot.instance_by_name(name),
+    };
+    let label = match &instance {
+        RuleInstance::Id(id) => format!("#{}", id),
+        RuleInstance::Name(name) => name.clone(),
+    };
+
+    let Some((_, (name, product))) = resolved else {
+        issues.push(ConsistencyIssue {
+            check: "rule-unknown-instance",
+            severity: ConsistencySeverity::Error,
+            source: source.to_string(),
+            message: format!(
+                "Rule references {}.{}{} but instance {} does not exist",
+                label, point_type, point_id, label
+            ),
+        });
+        return;
+    };
+
+    if let Some(product) = builtin_product(product) {
+        if !product_has_point(product, &point_type, point_id) {
+            issues.push(ConsistencyIssue {
+                check: "rule-unknown-point",
+                severity: ConsistencySeverity::Error,
+                source: source.to_string(),
+                message: format!(
+                    "Rule references {}.{}{} but product '{}' has no such point",
+                    name, point_type, point_id, product.name
+                ),
+            });
+        }
+    }
+}
+
+/// Built-in product definition (None in builds without the product library)
+fn builtin_product(name: &str) -> Option<&'static BuiltinProduct> {
+    if get_builtin_products().is_empty() {
+        return None;
+    }
+    get_builtin_product(name)
+}
+
+fn product_has_point(product: &BuiltinProduct, instance_type: &str, point_id: u32) -> bool {
+    let points = if instance_type == "A" {
+        &product.actions
+    } else {
+        &product.measurements
+    };
+    points.iter().any(|d| d.id == point_id)
+}
+
+// ============================================================================
+// Rule references
+// ============================================================================
+
+/// Instance referenced by a rule, by ID or by name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleInstance {
+    Name(String),
+    Id(u32),
+}
+
+/// Collect instance point references from a rule document
+///
+/// Two forms are recognised: flow variables (`{"instance": 1, "pointType":
+/// "measurement", "point": 3}`) and expressions such as `motor_01.M33 == 1`.
+/// Each reference is `(instance, "M" | "A", point_id)`.
+pub fn collect_rule_references(value: &JsonValue, refs: &mut Vec<(RuleInstance, String, u32)>) {
+    match value {
+        JsonValue::Object(map) => {
+            let instance = map
+                .get("instance")
+                .or_else(|| map.get("instance_id"))
+                .and_then(|v| v.as_u64());
+            let point = map.get("point").and_then(|v| v.as_u64());
+            let point_type = match map.get("pointType").and_then(|v| v.as_str()) {
+                Some("measurement") => Some("M"),
+                Some("action") => Some("A"),
+                _ => None,
+            };
+            if let (Some(instance), Some(point), Some(point_type)) = (instance, point, point_type) {
+                refs.push((
+                    RuleInstance::Id(instance as u32),
+                    point_type.to_string(),
+                    point as u32,
+                ));
+            }
+            map.values().for_each(|v| collect_rule_references(v, refs));
+        },
+        JsonValue::Array(items) => items.iter().for_each(|v| collect_rule_references(v, refs)),
+        JsonValue::String(text) => {
+            let tokens = text.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'));
+            for token in tokens {
+                let Some((instance, point)) = token.split_once('.') else {
+                    continue;
+                };
+                let Some(point_type) = point.get(..1).filter(|t| *t == "M" || *t == "A") else {
+                    continue;
+                };
+                let valid_name = instance
+                    .chars()
+                    .next()
+                    .is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+                if let (true, Ok(point_id)) = (valid_name, point[1..].parse::<u32>()) {
+                    refs.push((
+                        RuleInstance::Name(instance.to_string()),
+                        point_type.to_string(),
+                        point_id,
+                    ));
+                }
+            }
+        },
+        _ => {},
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap and json! are acceptable
+mod tests {
+    use super::*;
+    use crate::test_utils::schema;
+    use serde_json::json;
+
+    #[test]
+    fn test_collect_rule_references() {
+        let rule = json!({
+            "trigger": {"expression": "motor_01.M33 == 1 && 2.5 > x.value"},
+            "actions": [{"target": "motor_01.A5"}],
+            "variables": [{"name": "X1", "instance": 3, "pointType": "measurement", "point": 7}]
+        });
+        let mut refs = Vec::new();
+        collect_rule_references(&rule, &mut refs);
+
+        assert_eq!(refs.len(), 3);
+        assert!(refs.contains(&(RuleInstance::Name("motor_01".into()), "M".into(), 33)));
+        assert!(refs.contains(&(RuleInstance::Name("motor_01".into()), "A".into(), 5)));
+        assert!(refs.contains(&(RuleInstance::Id(3), "M".into(), 7)));
+    }
+
+    #[tokio::test]
+    async fn test_dangling_references_reported() {
+        // Dangling rows are what the check looks for, so don't let SQLite reject them
+        let options = "sqlite::memory:"
+            .parse::<sqlx::sqlite::SqliteConnectOptions>()
+            .unwrap()
+            .foreign_keys(false);
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .unwrap();
+        schema::init_comsrv_schema(&pool).await.unwrap();
+        schema::init_modsrv_schema(&pool).await.unwrap();
+        schema::init_rules_schema(&pool).await.unwrap();
+
+        for sql in [
+            "INSERT INTO channels (channel_id, name, enabled) VALUES (1, 'pcs', 1), (2, 'old', 0)",
+            "INSERT INTO telemetry_points (point_id, channel_id, signal_name) VALUES (1, 1, 'P'), (1, 2, 'P')",
+            "INSERT INTO instances (instance_id, instance_name, product_name) VALUES (5, 'pcs_01', 'NoSuchProduct')",
+            "INSERT INTO measurement_routing (instance_id, instance_name, channel_id, channel_type, channel_point_id, measurement_id) \
+             VALUES (5, 'pcs_01', 1, 'T', 1, 1), (5, 'pcs_01', 1, 'T', 9, 2), (5, 'pcs_01', 2, 'T', 1, 3), (5, 'pcs_01', NULL, NULL, NULL, 4)",
+            "INSERT INTO action_routing (instance_id, instance_name, action_id, channel_id, channel_type, channel_point_id) \
+             VALUES (5, 'pcs_01', 1, 7, 'A', 1)",
+            r#"INSERT INTO rules (id, name, nodes_json) VALUES (1, 'r1', '{"expr": "pcs_01.M1 > 0 && bms_01.M2 < 5"}')"#,
+        ] {
+            sqlx::query(sql).execute(&pool).await.unwrap();
+        }
+
+        let report = check_consistency(&pool, "modsrv").await.unwrap();
+        let checks: Vec<&str> = report.issues.iter().map(|i| i.check).collect();
+
+        assert_eq!(report.status, "error");
+        assert_eq!(report.checked, 7);
+        assert!(checks.contains(&"routing-unknown-point"));
+        assert!(checks.contains(&"routing-disabled-channel"));
+        assert!(checks.contains(&"routing-unbound"));
+        assert!(checks.contains(&"routing-unknown-channel"));
+        assert!(checks.contains(&"rule-unknown-instance"));
+        assert_eq!(report.errors, 3);
+        assert_eq!(report.warnings, 2);
+    }
+
+    #[tokio::test]
+    async fn test_clean_config() {
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        schema::init_comsrv_schema(&pool).await.unwrap();
+
+        let report = check_consistency(&pool, "comsrv").await.unwrap();
+        assert!(report.is_clean());
+        assert_eq!(report.status, "ok");
+    }
+}
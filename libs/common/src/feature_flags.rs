@@ -0,0 +1,357 @@
+//! Per-service feature flags
+//!
+//! Risky new behaviors are gated behind flags declared in code as
+//! [`FeatureFlag`] constants, so they can be rolled out site by site. A flag's
+//! value is resolved from (highest priority first):
+//!
+//! 1. Runtime override set through `PUT /api/admin/features/{name}`, persisted
+//!    in the Redis hash `features:{service}` so it survives restarts
+//! 2. `service_config` row `(service, "feature.<name>")`
+//! 3. `service_config` row `("global", "feature.<name>")`
+//! 4. The default declared in code
+//!
+//! ```ignore
+//! if common::feature_flags::is_enabled(&common::feature_flags::BINARY_ENCODING) {
+//!     // new code path
+//! }
+//! ```
+//!
+//! Services load the configured values and overrides at startup through
+//! [`crate::service_bootstrap::init_feature_flags`].
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+/// Key prefix of feature flags in `service_config`
+pub const CONFIG_KEY_PREFIX: &str = "feature.";
+
+/// A feature flag declared in code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeatureFlag {
+    /// Stable name used in config, Redis and the API
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Value when nothing is configured
+    pub default: bool,
+}
+
+impl FeatureFlag {
+    pub const fn new(name: &'static str, description: &'static str, default: bool) -> Self {
+        Self {
+            name,
+            description,
+            default,
+        }
+    }
+}
+
+/// Commands delivered over Redis Streams instead of TODO lists
+pub const STREAMS_COMMAND_BUS: FeatureFlag = FeatureFlag::new(
+    "streams_command_bus",
+    "Deliver commands over Redis Streams instead of TODO lists",
+    false,
+);
+
+/// Compact binary encoding of RTDB values
+pub const BINARY_ENCODING: FeatureFlag = FeatureFlag::new(
+    "binary_encoding",
+    "Store RTDB values in compact binary encoding",
+    false,
+);
+
+/// All flags known to the services
+pub const FLAGS: &[FeatureFlag] = &[STREAMS_COMMAND_BUS, BINARY_ENCODING];
+
+/// Look up a declared flag by name
+pub fn find(name: &str) -> Option<&'static FeatureFlag> {
+    FLAGS.iter().find(|flag| flag.name == name)
+}
+
+/// Where a flag's current value comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum FlagSource {
+    Default,
+    Global,
+    Service,
+    Override,
+}
+
+/// Current state of a flag
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FlagStatus {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub enabled: bool,
+    pub default: bool,
+    pub source: FlagSource,
+}
+
+#[derive(Debug, Default)]
+struct FlagValues {
+    service: String,
+    global: HashMap<&'static str, bool>,
+    configured: HashMap<&'static str, bool>,
+    overrides: HashMap<&'static str, bool>,
+}
+
+/// Resolved flag values of one service
+#[derive(Debug, Default)]
+pub struct FeatureFlags {
+    values: RwLock<FlagValues>,
+}
+
+/// Parse a config/Redis flag value
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" | "on" | "yes" => Some(true),
+        "false" | "0" | "off" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+impl FeatureFlags {
+    pub fn new(service: impl Into<String>) -> Self {
+        Self {
+            values: RwLock::new(FlagValues {
+                service: service.into(),
+                ..FlagValues::default()
+            }),
+        }
+    }
+
+    /// Service the flags are resolved for
+    pub fn service(&self) -> String {
+        self.read().service.clone()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, FlagValues> {
+        self.values
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, FlagValues> {
+        self.values
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Replace the configured values from `(service_name, key, value)` rows
+    ///
+    /// Rows of other services, unknown flags and unparsable values are skipped.
+    /// Returns the number of values applied.
+    pub fn apply_config<I>(&self, rows: I) -> usize
+    where
+        I: IntoIterator<Item = (String, String, String)>,
+    {
+        let mut values = self.write();
+        let mut global = HashMap::new();
+        let mut configured = HashMap::new();
+        for (scope, key, value) in rows {
+            let Some(flag) = key.strip_prefix(CONFIG_KEY_PREFIX).and_then(find) else {
+                continue;
+            };
+            let Some(enabled) = parse_bool(&value) else {
+                tracing::warn!("Feature flag {} has invalid value '{}'", key, value);
+                continue;
+            };
+            if scope == "global" {
+                global.insert(flag.name, enabled);
+            } else if scope == values.service {
+                configured.insert(flag.name, enabled);
+            }
+        }
+        let applied = global.len() + configured.len();
+        values.global = global;
+        values.configured = configured;
+        applied
+    }
+
+    /// Replace the runtime overrides from `(name, value)` pairs
+    pub fn apply_overrides<I>(&self, overrides: I) -> usize
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let overrides: HashMap<_, _> = overrides
+            .into_iter()
+            .filter_map(|(name, value)| Some((find(&name)?.name, parse_bool(&value)?)))
+            .collect();
+        let count = overrides.len();
+        self.write().overrides = overrides;
+        count
+    }
+
+    /// Set (`Some`) or clear (`None`) the runtime override of a flag
+    pub fn set_override(&self, flag: &FeatureFlag, enabled: Option<bool>) -> FlagStatus {
+        {
+            let mut values = self.write();
+            match enabled {
+                Some(enabled) => values.overrides.insert(flag.name, enabled),
+                None => values.overrides.remove(flag.name),
+            };
+        }
+        self.status(flag)
+    }
+
+    pub fn status(&self, flag: &FeatureFlag) -> FlagStatus {
+        let values = self.read();
+        let (enabled, source) = if let Some(v) = values.overrides.get(flag.name) {
+            (*v, FlagSource::Override)
+        } else if let Some(v) = values.configured.get(flag.name) {
+            (*v, FlagSource::Service)
+        } else if let Some(v) = values.global.get(flag.name) {
+            (*v, FlagSource::Global)
+        } else {
+            (flag.default, FlagSource::Default)
+        };
+        FlagStatus {
+            name: flag.name,
+            description: flag.description,
+            enabled,
+            default: flag.default,
+            source,
+        }
+    }
+
+    pub fn is_enabled(&self, flag: &FeatureFlag) -> bool {
+        self.status(flag).enabled
+    }
+
+    /// State of all declared flags
+    pub fn all(&self) -> Vec<FlagStatus> {
+        FLAGS.iter().map(|flag| self.status(flag)).collect()
+    }
+}
+
+/// The process-wide flags
+pub fn flags() -> &'static FeatureFlags {
+    static FLAGS_REGISTRY: OnceLock<FeatureFlags> = OnceLock::new();
+    FLAGS_REGISTRY.get_or_init(FeatureFlags::default)
+}
+
+/// Whether a flag is enabled in this process
+pub fn is_enabled(flag: &FeatureFlag) -> bool {
+    flags().is_enabled(flag)
+}
+
+/// Redis hash holding the runtime overrides of a service
+pub fn overrides_key(service: &str) -> String {
+    format!("features:{}", service)
+}
+
+#[cfg(feature = "redis")]
+static REDIS: OnceLock<std::sync::Arc<crate::redis::RedisClient>> = OnceLock::new();
+
+/// Resolve the process-wide flags for `service`
+///
+/// Reads `feature.*` rows from `service_config` and, when Redis is given,
+/// the persisted runtime overrides; later overrides are written back to it.
+#[cfg(all(feature = "sqlite", feature = "redis"))]
+pub async fn init(
+    service: &str,
+    pool: &sqlx::SqlitePool,
+    redis: Option<std::sync::Arc<crate::redis::RedisClient>>,
+) -> anyhow::Result<()> {
+    flags().write().service = service.to_string();
+
+    let rows: Vec<(String, String, String)> = sqlx::query_as(
+        "SELECT service_name, key, value FROM service_config \
+         WHERE (service_name = 'global' OR service_name = ?) AND key LIKE 'feature.%'",
+    )
+    .bind(service)
+    .fetch_all(pool)
+    .await?;
+    flags().apply_config(rows);
+
+    if let Some(redis) = redis {
+        let overrides: HashMap<String, String> = redis.hgetall(&overrides_key(service)).await?;
+        flags().apply_overrides(overrides);
+        let _ = REDIS.set(redis);
+    }
+
+    for status in flags().all() {
+        if status.source != FlagSource::Default {
+            tracing::info!(
+                "Feature flag {} = {} ({:?})",
+                status.name,
+                status.enabled,
+                status.source
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Set or clear a runtime override of the process-wide flags
+///
+/// Persisted to Redis when the flags were initialized with it.
+pub async fn set_override(flag: &FeatureFlag, enabled: Option<bool>) -> anyhow::Result<FlagStatus> {
+    #[cfg(feature = "redis")]
+    if let Some(redis) = REDIS.get() {
+        let key = overrides_key(&flags().service());
+        match enabled {
+            Some(enabled) => redis.hset(&key, flag.name, enabled.to_string()).await?,
+            None => {
+                redis.hdel(&key, flag.name).await?;
+            },
+        }
+    }
+    Ok(flags().set_override(flag, enabled))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(scope: &str, key: &str, value: &str) -> (String, String, String) {
+        (scope.to_string(), key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_resolution_order() {
+        let flags = FeatureFlags::new("comsrv");
+        assert_eq!(flags.status(&BINARY_ENCODING).source, FlagSource::Default);
+        assert!(!flags.is_enabled(&BINARY_ENCODING));
+
+        let applied = flags.apply_config(vec![
+            row("global", "feature.binary_encoding", "true"),
+            row("global", "feature.streams_command_bus", "true"),
+            row("comsrv", "feature.streams_command_bus", "false"),
+            row("modsrv", "feature.binary_encoding", "false"),
+            row("comsrv", "feature.unknown", "true"),
+            row("comsrv", "service.port", "6001"),
+        ]);
+        assert_eq!(applied, 3);
+        assert_eq!(flags.status(&BINARY_ENCODING).source, FlagSource::Global);
+        assert!(flags.is_enabled(&BINARY_ENCODING));
+        assert_eq!(
+            flags.status(&STREAMS_COMMAND_BUS).source,
+            FlagSource::Service
+        );
+        assert!(!flags.is_enabled(&STREAMS_COMMAND_BUS));
+
+        let status = flags.set_override(&STREAMS_COMMAND_BUS, Some(true));
+        assert_eq!(status.source, FlagSource::Override);
+        assert!(status.enabled);
+
+        let status = flags.set_override(&STREAMS_COMMAND_BUS, None);
+        assert_eq!(status.source, FlagSource::Service);
+    }
+
+    #[test]
+    fn test_apply_overrides_skips_invalid() {
+        let flags = FeatureFlags::new("modsrv");
+        let count = flags.apply_overrides(vec![
+            ("binary_encoding".to_string(), "on".to_string()),
+            ("binary_encoding_v2".to_string(), "true".to_string()),
+            ("streams_command_bus".to_string(), "maybe".to_string()),
+        ]);
+        assert_eq!(count, 1);
+        assert!(flags.is_enabled(&BINARY_ENCODING));
+        assert_eq!(flags.all().len(), FLAGS.len());
+    }
+}
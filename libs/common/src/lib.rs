@@ -18,14 +18,25 @@ pub mod service_config;
 // Common modules
 pub mod admin_api;
 pub mod api_types;
+pub mod auth;
+pub mod bytes;
+pub mod config_consistency;
 pub mod config_loader;
+#[cfg(feature = "axum")]
+pub mod extract;
+pub mod feature_flags;
 pub mod logging;
+#[cfg(feature = "axum")]
+pub mod rate_limit;
+pub mod resource_profile;
 pub mod serde_helpers;
 pub mod service_bootstrap;
 pub mod shutdown;
 pub mod system_metrics;
 pub mod validation;
 pub mod warning_monitor;
+#[cfg(feature = "webhook")]
+pub mod webhook;
 
 // Re-export commonly used csv types (previously in csv.rs module)
 pub use csv::{Reader, ReaderBuilder, StringRecord, Writer, WriterBuilder};
@@ -88,6 +99,7 @@ pub use api_types::{
     BatchResult,
     ComponentHealth,
     ControlAction,
+    Cursor,
     ErrorInfo,
     ErrorResponse,
     HealthStatus,
@@ -103,6 +115,8 @@ pub use api_types::{
 // Re-export AppError when axum feature is enabled
 #[cfg(feature = "axum")]
 pub use api_types::AppError;
+#[cfg(feature = "axum")]
+pub use extract::ValidatedJson;
 
 // Re-export PointRole from voltage-model (canonical location)
 pub use voltage_model::PointRole;
@@ -0,0 +1,487 @@
+//! Token-bucket rate limiting for public APIs
+//!
+//! A [`RateLimiter`] holds one token bucket per (route class, client). The
+//! client is the authenticated [`Caller`] when the [`authenticate`] layer
+//! runs before this one, otherwise the socket peer IP (the server must be
+//! started with `into_make_service_with_connect_info::<SocketAddr>()`).
+//! `X-Forwarded-For` is only honoured when the peer is a configured trusted
+//! proxy ([`TRUSTED_PROXIES_ENV`]). Requests that match no class are not
+//! limited.
+//!
+//! [`authenticate`]: crate::auth::authenticate
+//!
+//! A request over the limit is answered with
+//! [`VoltageError::RateLimitExceeded`] (HTTP 429) and a `Retry-After` header.
+//!
+//! ```ignore
+//! let limiter = Arc::new(
+//!     RateLimiter::new(vec![
+//!         RouteClass::new("control", 10, 5.0).post("/api/channels/*/control"),
+//!     ])
+//!     .trust_proxies(trusted_proxies_from_env()),
+//! );
+//! let app = Router::new()
+//!     // ... routes ...
+//!     .layer(axum::middleware::from_fn_with_state(limiter, rate_limit))
+//!     .layer(axum::middleware::from_fn_with_state(verifier, authenticate));
+//! ```
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderValue, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use errors::VoltageError;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::api_types::AppError;
+use crate::auth::Caller;
+
+/// Environment variable listing proxy addresses (comma-separated IPs) whose
+/// `X-Forwarded-For` header is trusted
+pub const TRUSTED_PROXIES_ENV: &str = "TRUSTED_PROXIES";
+
+/// Upper bound on tracked (class, client) buckets
+const MAX_BUCKETS: usize = 10_000;
+
+/// Parse [`TRUSTED_PROXIES_ENV`]; unparsable entries are logged and skipped
+pub fn trusted_proxies_from_env() -> Vec<IpAddr> {
+    std::env::var(TRUSTED_PROXIES_ENV)
+        .map(|value| parse_proxies(&value))
+        .unwrap_or_default()
+}
+
+fn parse_proxies(value: &str) -> Vec<IpAddr> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| match entry.parse() {
+            Ok(ip) => Some(ip),
+            Err(_) => {
+                tracing::warn!("Ignoring invalid {} entry '{}'", TRUSTED_PROXIES_ENV, entry);
+                None
+            },
+        })
+        .collect()
+}
+
+/// A group of routes sharing one limit per client
+#[derive(Debug, Clone)]
+pub struct RouteClass {
+    pub name: String,
+    /// Bucket size (requests allowed in a burst)
+    pub burst: u32,
+    /// Refill rate (requests per second)
+    pub per_second: f64,
+    /// (method, path pattern); `*` matches one path segment
+    routes: Vec<(Method, String)>,
+}
+
+impl RouteClass {
+    pub fn new(name: impl Into<String>, burst: u32, per_second: f64) -> Self {
+        Self {
+            name: name.into(),
+            burst: burst.max(1),
+            per_second: per_second.max(0.0),
+            routes: Vec::new(),
+        }
+    }
+
+    /// Add a route matched by method and path pattern
+    pub fn route(mut self, method: Method, pattern: impl Into<String>) -> Self {
+        self.routes.push((method, pattern.into()));
+        self
+    }
+
+    pub fn get(self, pattern: impl Into<String>) -> Self {
+        self.route(Method::GET, pattern)
+    }
+
+    pub fn post(self, pattern: impl Into<String>) -> Self {
+        self.route(Method::POST, pattern)
+    }
+
+    fn matches(&self, method: &Method, path: &str) -> bool {
+        self.routes
+            .iter()
+            .any(|(m, pattern)| m == method && path_matches(pattern, path))
+    }
+}
+
+/// Match a path against a pattern where `*` stands for one segment
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let mut pattern_segments = pattern.trim_end_matches('/').split('/');
+    let mut path_segments = path.trim_end_matches('/').split('/');
+    loop {
+        match (pattern_segments.next(), path_segments.next()) {
+            (None, None) => return true,
+            (Some(p), Some(s)) if p == "*" || p == s => {},
+            _ => return false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// Time until the bucket is full again; a full bucket is the same as a
+    /// fresh one, so only then may it be forgotten
+    fn time_to_full(&self, limits: &RouteClass, now: Instant) -> Duration {
+        let burst = limits.burst as f64;
+        let tokens =
+            self.tokens + now.duration_since(self.updated).as_secs_f64() * limits.per_second;
+        if tokens >= burst {
+            Duration::ZERO
+        } else {
+            Duration::try_from_secs_f64((burst - tokens) / limits.per_second)
+                .unwrap_or(Duration::MAX)
+        }
+    }
+}
+
+/// Buckets of all clients, bounded by [`MAX_BUCKETS`]
+///
+/// When the table is full, a new client makes room by dropping buckets that
+/// have refilled; a bucket still below capacity is never dropped, so cycling
+/// through many client addresses cannot restore another client's burst. If
+/// nothing has refilled, new clients are refused until the first bucket does.
+#[derive(Debug, Default)]
+struct Buckets {
+    map: HashMap<(usize, String), TokenBucket>,
+    /// No bucket refills before this, so a sweep would free nothing
+    next_sweep: Option<Instant>,
+}
+
+impl Buckets {
+    /// Ensure there is room for one more bucket; on refusal returns the time
+    /// until a bucket can be dropped
+    fn make_room(&mut self, classes: &[RouteClass], now: Instant) -> Result<(), Duration> {
+        if self.map.len() < MAX_BUCKETS {
+            return Ok(());
+        }
+        if let Some(at) = self.next_sweep.filter(|at| now < *at) {
+            return Err(at - now);
+        }
+
+        let mut first_full = Duration::MAX;
+        self.map.retain(|(class, _), bucket| {
+            let wait = classes
+                .get(*class)
+                .map_or(Duration::ZERO, |limits| bucket.time_to_full(limits, now));
+            first_full = first_full.min(wait);
+            !wait.is_zero()
+        });
+        if self.map.len() < MAX_BUCKETS {
+            self.next_sweep = None;
+            Ok(())
+        } else {
+            self.next_sweep = now.checked_add(first_full);
+            Err(first_full)
+        }
+    }
+}
+
+/// Per-client token buckets for a set of route classes
+#[derive(Debug)]
+pub struct RateLimiter {
+    classes: Vec<RouteClass>,
+    trusted_proxies: Vec<IpAddr>,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub fn new(classes: Vec<RouteClass>) -> Self {
+        Self {
+            classes,
+            trusted_proxies: Vec::new(),
+            buckets: Mutex::new(Buckets::default()),
+        }
+    }
+
+    /// Honour `X-Forwarded-For` on requests from these peers
+    pub fn trust_proxies(mut self, proxies: Vec<IpAddr>) -> Self {
+        self.trusted_proxies = proxies;
+        self
+    }
+
+    pub fn classes(&self) -> &[RouteClass] {
+        &self.classes
+    }
+
+    /// Index of the first class matching the request
+    pub fn classify(&self, method: &Method, path: &str) -> Option<usize> {
+        self.classes.iter().position(|c| c.matches(method, path))
+    }
+
+    /// Take one token; on refusal returns the time until the next token
+    pub fn check(&self, class: usize, client: &str, now: Instant) -> Result<(), Duration> {
+        let Some(limits) = self.classes.get(class) else {
+            return Ok(());
+        };
+        let burst = limits.burst as f64;
+        let key = (class, client.to_string());
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        if !buckets.map.contains_key(&key) {
+            buckets.make_room(&self.classes, now)?;
+        }
+        let bucket = buckets.map.entry(key).or_insert(TokenBucket {
+            tokens: burst,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * limits.per_second).min(burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else if limits.per_second > 0.0 {
+            Err(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / limits.per_second,
+            ))
+        } else {
+            Err(Duration::MAX)
+        }
+    }
+
+    /// Client identity used for the buckets
+    fn client_key(&self, req: &Request) -> String {
+        if let Some(name) = req
+            .extensions()
+            .get::<Caller>()
+            .and_then(|caller| caller.username.as_deref())
+        {
+            return format!("user:{}", name);
+        }
+        let peer = req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|info| info.0.ip());
+        match peer {
+            Some(peer) => format!("ip:{}", self.client_ip(peer, req)),
+            None => "ip:unknown".to_string(),
+        }
+    }
+
+    /// The peer itself, or for a trusted proxy the nearest untrusted hop in
+    /// `X-Forwarded-For` (entries further left are client-controlled)
+    fn client_ip(&self, peer: IpAddr, req: &Request) -> IpAddr {
+        if !self.trusted_proxies.contains(&peer) {
+            return peer;
+        }
+        let mut client = peer;
+        let hops = req
+            .headers()
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .collect::<Vec<_>>();
+        for hop in hops.into_iter().rev() {
+            let Ok(ip) = hop.trim().parse::<IpAddr>() else {
+                break;
+            };
+            client = ip;
+            if !self.trusted_proxies.contains(&ip) {
+                break;
+            }
+        }
+        client
+    }
+}
+
+/// Axum middleware enforcing the limiter (use with `from_fn_with_state`)
+pub async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let Some(class) = limiter.classify(req.method(), req.uri().path()) else {
+        return next.run(req).await;
+    };
+    let client = limiter.client_key(&req);
+    match limiter.check(class, &client, Instant::now()) {
+        Ok(()) => next.run(req).await,
+        Err(retry_after) => {
+            let class_name = &limiter.classes()[class].name;
+            tracing::warn!(
+                "Rate limit '{}' exceeded by {} on {} {}",
+                class_name,
+                client,
+                req.method(),
+                req.uri().path()
+            );
+            let retry_secs = retry_after.as_secs_f64().ceil().min(3600.0) as u64;
+            let mut response = AppError::from(VoltageError::RateLimitExceeded)
+                .with_details(format!(
+                    "Limit '{}' exceeded, retry after {}s",
+                    class_name, retry_secs
+                ))
+                .into_response();
+            if let Ok(value) = HeaderValue::from_str(&retry_secs.max(1).to_string()) {
+                response.headers_mut().insert(header::RETRY_AFTER, value);
+            }
+            response
+        },
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, middleware, routing::post, Router};
+    use tower::ServiceExt;
+
+    fn limiter() -> RateLimiter {
+        RateLimiter::new(vec![
+            RouteClass::new("control", 2, 1.0).post("/api/channels/*/control")
+        ])
+    }
+
+    #[test]
+    fn test_classify() {
+        let limiter = limiter();
+        assert_eq!(
+            limiter.classify(&Method::POST, "/api/channels/1001/control"),
+            Some(0)
+        );
+        assert_eq!(
+            limiter.classify(&Method::GET, "/api/channels/1001/control"),
+            None
+        );
+        assert_eq!(
+            limiter.classify(&Method::POST, "/api/channels/1001/control/x"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_bucket_refill_per_client() {
+        let limiter = limiter();
+        let start = Instant::now();
+        assert!(limiter.check(0, "a", start).is_ok());
+        assert!(limiter.check(0, "a", start).is_ok());
+        let retry = limiter.check(0, "a", start).unwrap_err();
+        assert_eq!(retry, Duration::from_secs(1));
+        // Other clients have their own bucket
+        assert!(limiter.check(0, "b", start).is_ok());
+        // One token back after a second
+        assert!(limiter
+            .check(0, "a", start + Duration::from_secs(1))
+            .is_ok());
+        assert!(limiter
+            .check(0, "a", start + Duration::from_secs(1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_full_table_keeps_throttled_buckets() {
+        let limiter = limiter();
+        let start = Instant::now();
+        limiter.check(0, "a", start).unwrap();
+        limiter.check(0, "a", start).unwrap();
+        for i in 1..MAX_BUCKETS {
+            limiter.check(0, &i.to_string(), start).unwrap();
+        }
+        assert_eq!(limiter.buckets.lock().unwrap().map.len(), MAX_BUCKETS);
+
+        // Nothing has refilled: a new client waits instead of evicting "a"
+        let retry = limiter.check(0, "new", start).unwrap_err();
+        assert_eq!(retry, Duration::from_secs(1));
+        assert!(limiter.check(0, "a", start).is_err());
+
+        // After 1s the clients that took one token are full again and make room;
+        // "a" is still below capacity and keeps its bucket
+        let later = start + Duration::from_secs(1);
+        limiter.check(0, "new", later).unwrap();
+        let buckets = limiter.buckets.lock().unwrap();
+        assert!(buckets.map.len() <= MAX_BUCKETS);
+        assert!(buckets.map.contains_key(&(0, "a".to_string())));
+        assert!(!buckets.map.contains_key(&(0, "1".to_string())));
+        drop(buckets);
+        limiter.check(0, "a", later).unwrap();
+        assert!(limiter.check(0, "a", later).is_err());
+    }
+
+    fn request(peer: &str, forwarded: Option<&str>, caller: Option<&str>) -> Request {
+        let mut builder = Request::post("/api/channels/1/control");
+        if let Some(forwarded) = forwarded {
+            builder = builder.header("x-forwarded-for", forwarded);
+        }
+        let mut req = builder.body(Body::empty()).unwrap();
+        req.extensions_mut()
+            .insert(ConnectInfo(SocketAddr::new(peer.parse().unwrap(), 40000)));
+        if let Some(name) = caller {
+            req.extensions_mut().insert(Caller {
+                username: Some(name.to_string()),
+                role: None,
+            });
+        }
+        req
+    }
+
+    #[test]
+    fn test_client_key_ignores_untrusted_forwarded_for() {
+        let limiter = limiter();
+        let req = request("203.0.113.9", Some("10.0.0.1"), None);
+        assert_eq!(limiter.client_key(&req), "ip:203.0.113.9");
+    }
+
+    #[test]
+    fn test_client_key_uses_forwarded_for_from_trusted_proxy() {
+        let limiter = limiter().trust_proxies(parse_proxies("10.0.0.2, 10.0.0.3, bogus"));
+        assert_eq!(limiter.trusted_proxies.len(), 2);
+        // Leftmost entry is whatever the client sent; the nearest untrusted hop counts
+        let req = request("10.0.0.2", Some("1.2.3.4, 198.51.100.7, 10.0.0.3"), None);
+        assert_eq!(limiter.client_key(&req), "ip:198.51.100.7");
+        // Without the header the proxy itself is the client
+        let req = request("10.0.0.2", None, None);
+        assert_eq!(limiter.client_key(&req), "ip:10.0.0.2");
+    }
+
+    #[test]
+    fn test_client_key_prefers_authenticated_caller() {
+        let limiter = limiter();
+        let req = request("203.0.113.9", None, Some("alice"));
+        assert_eq!(limiter.client_key(&req), "user:alice");
+        let mut req = request("203.0.113.9", None, None);
+        req.extensions_mut().insert(Caller::anonymous());
+        assert_eq!(limiter.client_key(&req), "ip:203.0.113.9");
+    }
+
+    #[tokio::test]
+    async fn test_middleware_returns_429() {
+        let app = Router::new()
+            .route("/api/channels/{id}/control", post(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(
+                Arc::new(limiter()),
+                rate_limit,
+            ));
+        let from_client = || request("203.0.113.9", None, None);
+
+        for _ in 0..2 {
+            let response = app.clone().oneshot(from_client()).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let response = app.clone().oneshot(from_client()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+        // Another peer is unaffected
+        let response = app
+            .clone()
+            .oneshot(request("203.0.113.10", None, None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,294 @@
+//! Tiered resource profiles
+//!
+//! Buffer sizes, batch sizes and startup concurrency default to one of three
+//! built-in profiles, so a 512 MB ARM gateway and a rack server do not share
+//! the same tuning. The profile is resolved from (highest priority first):
+//!
+//! 1. `service_config` row `(service, "profile")`
+//! 2. `service_config` row `("global", "profile")`
+//! 3. The `VOLTAGE_PROFILE` environment variable
+//! 4. `medium`, the tuning the services used before profiles existed
+//!
+//! Single values can be overridden with `tuning.<name>` rows, again with the
+//! service's row taking precedence over the global one:
+//!
+//! ```yaml
+//! # global.yaml
+//! profile: small        # small | medium | large
+//! tuning:
+//!   command_mailbox_size: 64
+//! ```
+//!
+//! Services resolve the profile at startup through
+//! [`crate::service_bootstrap::init_resource_profile`] and read it with
+//! [`tuning()`].
+
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// Config key selecting the profile
+pub const PROFILE_KEY: &str = "profile";
+
+/// Key prefix of single-value overrides in `service_config`
+pub const TUNING_KEY_PREFIX: &str = "tuning.";
+
+/// Environment variable selecting the profile when none is configured
+pub const ENV_PROFILE: &str = "VOLTAGE_PROFILE";
+
+/// Built-in resource profile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourceProfile {
+    /// Constrained gateways (512 MB RAM, few cores)
+    Small,
+    /// Default
+    #[default]
+    Medium,
+    /// Rack servers with thousands of points
+    Large,
+}
+
+impl ResourceProfile {
+    pub const ALL: [ResourceProfile; 3] = [Self::Small, Self::Medium, Self::Large];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Small => "small",
+            Self::Medium => "medium",
+            Self::Large => "large",
+        }
+    }
+
+    /// Values of the profile without overrides
+    pub fn tuning(&self) -> Tuning {
+        match self {
+            Self::Small => Tuning {
+                profile: *self,
+                command_mailbox_size: 32,
+                event_buffer_size: 256,
+                write_flush_interval_ms: 50,
+                write_batch_size: 500,
+                startup_concurrency: 4,
+            },
+            Self::Medium => Tuning {
+                profile: *self,
+                command_mailbox_size: 100,
+                event_buffer_size: 1024,
+                write_flush_interval_ms: 20,
+                write_batch_size: 1000,
+                startup_concurrency: 16,
+            },
+            Self::Large => Tuning {
+                profile: *self,
+                command_mailbox_size: 500,
+                event_buffer_size: 4096,
+                write_flush_interval_ms: 20,
+                write_batch_size: 5000,
+                startup_concurrency: 64,
+            },
+        }
+    }
+}
+
+impl fmt::Display for ResourceProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ResourceProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "small" => Ok(Self::Small),
+            "medium" => Ok(Self::Medium),
+            "large" => Ok(Self::Large),
+            other => Err(format!(
+                "Unknown resource profile '{}' (small, medium or large)",
+                other
+            )),
+        }
+    }
+}
+
+/// Resolved tuning values of a service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Tuning {
+    /// Profile the values start from
+    pub profile: ResourceProfile,
+    /// Capacity of each channel's command mailbox (comsrv)
+    pub command_mailbox_size: usize,
+    /// Capacity of the point update broadcast (comsrv)
+    pub event_buffer_size: usize,
+    /// Interval of RTDB write buffer flushes (comsrv)
+    pub write_flush_interval_ms: u64,
+    /// Fields per key after which the RTDB write buffer flushes early (comsrv)
+    pub write_batch_size: usize,
+    /// Channels created, or instances registered, at the same time during
+    /// startup (comsrv, modsrv)
+    pub startup_concurrency: usize,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        ResourceProfile::default().tuning()
+    }
+}
+
+impl Tuning {
+    /// Resolve the tuning of `service` from `(service_name, key, value)` rows
+    ///
+    /// Rows of other services, unknown keys and invalid values are skipped
+    /// with a warning; zero is not accepted for any value.
+    pub fn resolve<I>(service: &str, rows: I) -> Self
+    where
+        I: IntoIterator<Item = (String, String, String)>,
+    {
+        let mut global = Vec::new();
+        let mut own = Vec::new();
+        for (scope, key, value) in rows {
+            if scope == service {
+                own.push((key, value));
+            } else if scope == "global" {
+                global.push((key, value));
+            }
+        }
+
+        let configured = |rows: &[(String, String)]| {
+            rows.iter()
+                .find(|(key, _)| key == PROFILE_KEY)
+                .and_then(|(_, value)| match value.parse::<ResourceProfile>() {
+                    Ok(profile) => Some(profile),
+                    Err(e) => {
+                        tracing::warn!("{}", e);
+                        None
+                    },
+                })
+        };
+        let profile = configured(&own)
+            .or_else(|| configured(&global))
+            .or_else(|| {
+                std::env::var(ENV_PROFILE)
+                    .ok()
+                    .filter(|v| !v.trim().is_empty())
+                    .and_then(|v| match v.parse() {
+                        Ok(profile) => Some(profile),
+                        Err(e) => {
+                            tracing::warn!("{}: {}", ENV_PROFILE, e);
+                            None
+                        },
+                    })
+            })
+            .unwrap_or_default();
+
+        let mut tuning = profile.tuning();
+        for (key, value) in global.iter().chain(&own) {
+            if let Some(name) = key.strip_prefix(TUNING_KEY_PREFIX) {
+                if let Err(e) = tuning.set(name, value) {
+                    tracing::warn!("{}{}: {}", TUNING_KEY_PREFIX, name, e);
+                }
+            }
+        }
+        tuning
+    }
+
+    /// Override one value by name
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let number = || {
+            value
+                .trim()
+                .parse::<u64>()
+                .ok()
+                .filter(|n| *n > 0)
+                .ok_or_else(|| format!("'{}' is not a positive integer", value))
+        };
+        match name {
+            "command_mailbox_size" => self.command_mailbox_size = number()? as usize,
+            "event_buffer_size" => self.event_buffer_size = number()? as usize,
+            "write_flush_interval_ms" => self.write_flush_interval_ms = number()?,
+            "write_batch_size" => self.write_batch_size = number()? as usize,
+            "startup_concurrency" => self.startup_concurrency = number()? as usize,
+            _ => return Err("unknown tuning value".to_string()),
+        }
+        Ok(())
+    }
+}
+
+static TUNING: OnceLock<Tuning> = OnceLock::new();
+
+/// Tuning of this process
+///
+/// Before [`init`] runs, this resolves the environment's profile without
+/// config overrides.
+pub fn tuning() -> Tuning {
+    *TUNING.get_or_init(|| Tuning::resolve("", Vec::new()))
+}
+
+/// Resolve the process-wide tuning for `service` from `service_config`
+#[cfg(feature = "sqlite")]
+pub async fn init(service: &str, pool: &sqlx::SqlitePool) -> anyhow::Result<Tuning> {
+    let rows: Vec<(String, String, String)> = sqlx::query_as(
+        "SELECT service_name, key, value FROM service_config \
+         WHERE (service_name = 'global' OR service_name = ?) \
+         AND (key = 'profile' OR key LIKE 'tuning.%')",
+    )
+    .bind(service)
+    .fetch_all(pool)
+    .await?;
+    let resolved = Tuning::resolve(service, rows);
+    if TUNING.set(resolved).is_err() && tuning() != resolved {
+        tracing::warn!("Resource profile already in use, restart to apply changes");
+    }
+    let tuning = tuning();
+    tracing::info!("Resource profile {}: {:?}", tuning.profile, tuning);
+    Ok(tuning)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(scope: &str, key: &str, value: &str) -> (String, String, String) {
+        (scope.to_string(), key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_profile_resolution() {
+        assert_eq!("Small".parse(), Ok(ResourceProfile::Small));
+        assert!("tiny".parse::<ResourceProfile>().is_err());
+
+        let tuning = Tuning::resolve(
+            "comsrv",
+            vec![
+                row("global", "profile", "large"),
+                row("comsrv", "profile", "small"),
+                row("modsrv", "profile", "medium"),
+                row("global", "tuning.command_mailbox_size", "64"),
+                row("global", "tuning.startup_concurrency", "8"),
+                row("comsrv", "tuning.startup_concurrency", "2"),
+                row("comsrv", "tuning.event_buffer_size", "0"),
+                row("comsrv", "tuning.unknown", "1"),
+            ],
+        );
+        assert_eq!(tuning.profile, ResourceProfile::Small);
+        assert_eq!(tuning.command_mailbox_size, 64);
+        assert_eq!(tuning.startup_concurrency, 2);
+        // Invalid override keeps the profile value
+        assert_eq!(
+            tuning.event_buffer_size,
+            ResourceProfile::Small.tuning().event_buffer_size
+        );
+
+        let modsrv = Tuning::resolve(
+            "modsrv",
+            vec![
+                row("global", "profile", "large"),
+                row("comsrv", "profile", "small"),
+            ],
+        );
+        assert_eq!(modsrv, ResourceProfile::Large.tuning());
+    }
+}
@@ -1,10 +1,13 @@
 //! Unified service bootstrap utilities
 //!
 //! Provides common initialization functionality for all VoltageEMS services,
-//! including startup banners, logging initialization, and environment setup.
+//! including startup banners, logging initialization, environment setup and
+//! waiting for startup dependencies (config, SQLite, Redis).
 
 use crate::logging::{self, LogConfig};
-use tracing::{debug, info, Level};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn, Level};
 
 /// Service metadata for startup
 pub struct ServiceInfo {
@@ -90,16 +93,25 @@ pub fn init_logging(
     service: &ServiceInfo,
     logging_config: Option<&crate::LoggingConfig>,
 ) -> anyhow::Result<()> {
-    // Initialize log root directory from config or environment
-    let config_dir = logging_config.map(|c| c.dir.as_str());
-    crate::logging::init_log_root(config_dir);
-
     // Check RUST_LOG environment variable for log level
     let console_level = std::env::var("RUST_LOG")
         .ok()
         .and_then(|s| s.parse::<Level>().ok())
         .unwrap_or(Level::INFO);
 
+    init_logging_at(service, logging_config, console_level)
+}
+
+/// Initialize logging with the console level from `--log-level`
+pub fn init_logging_at(
+    service: &ServiceInfo,
+    logging_config: Option<&crate::LoggingConfig>,
+    console_level: Level,
+) -> anyhow::Result<()> {
+    // Initialize log root directory from config or environment
+    let config_dir = logging_config.map(|c| c.dir.as_str());
+    crate::logging::init_log_root(config_dir);
+
     // Get log directory with service name subdirectory
     let log_dir = crate::logging::get_log_root().join(&service.name);
 
@@ -128,28 +140,33 @@ pub fn load_development_env() {
     #[cfg(debug_assertions)]
     {
         if let Ok(content) = std::fs::read_to_string(".env") {
-            for line in content.lines() {
-                // Skip comments and empty lines
-                let trimmed = line.trim();
-                if trimmed.starts_with('#') || trimmed.is_empty() {
-                    continue;
-                }
+            load_env_content(&content);
+        }
+    }
 
-                // Parse KEY=VALUE format
-                if let Some((key, value)) = trimmed.split_once('=') {
-                    let key = key.trim();
-                    let value = value.trim();
+    // No-op in release builds - production environments should set environment variables externally
+}
 
-                    // Only set if not already set
-                    if std::env::var(key).is_err() {
-                        std::env::set_var(key, value);
-                    }
-                }
+/// Set the variables of `KEY=VALUE` lines that are not already set
+pub(crate) fn load_env_content(content: &str) {
+    for line in content.lines() {
+        // Skip comments and empty lines
+        let trimmed = line.trim();
+        if trimmed.starts_with('#') || trimmed.is_empty() {
+            continue;
+        }
+
+        // Parse KEY=VALUE format
+        if let Some((key, value)) = trimmed.split_once('=') {
+            let key = key.trim();
+            let value = value.trim();
+
+            // Only set if not already set
+            if std::env::var(key).is_err() {
+                std::env::set_var(key, value);
             }
         }
     }
-
-    // No-op in release builds - production environments should set environment variables externally
 }
 
 /// Get service configuration path from environment or default
@@ -228,6 +245,238 @@ pub fn get_service_port(config_port: u16, service: &ServiceInfo) -> u16 {
     }
 }
 
+/// `--validate`: cross-check the stored configuration of `db_path`
+///
+/// Issues are logged like at startup. Fails when the check finds errors or
+/// cannot run; warnings alone pass.
+#[cfg(feature = "sqlite")]
+pub async fn validate_consistency(
+    service: &ServiceInfo,
+    db_path: &str,
+) -> anyhow::Result<crate::config_consistency::ConsistencyReport> {
+    let pool = sqlx::SqlitePool::connect(&format!("sqlite:{}?mode=ro", db_path)).await?;
+    let report = crate::config_consistency::run_startup_check(&pool, &service.name).await;
+    pool.close().await;
+
+    match report {
+        None => anyhow::bail!("Config consistency check could not run on {}", db_path),
+        Some(report) if report.errors > 0 => anyhow::bail!(
+            "Config consistency: {} errors, {} warnings",
+            report.errors,
+            report.warnings
+        ),
+        Some(report) => Ok(report),
+    }
+}
+
+/// Resolve the process-wide resource profile for a service
+///
+/// Failures are logged and leave the tuning at the environment's profile.
+pub async fn init_resource_profile(service: &ServiceInfo, pool: &sqlx::SqlitePool) {
+    if let Err(e) = crate::resource_profile::init(&service.name, pool).await {
+        warn!("Resource profile unavailable, using defaults: {}", e);
+    }
+}
+
+/// Resolve the process-wide feature flags for a service
+///
+/// Failures are logged and leave the flags at their code defaults.
+pub async fn init_feature_flags(
+    service: &ServiceInfo,
+    pool: &sqlx::SqlitePool,
+    redis: Option<std::sync::Arc<crate::redis::RedisClient>>,
+) {
+    if let Err(e) = crate::feature_flags::init(&service.name, pool, redis).await {
+        warn!("Feature flags unavailable, using defaults: {}", e);
+    }
+}
+
+// ============================================================================
+// Startup dependency wait
+// ============================================================================
+
+/// Environment variable overriding the dependency wait deadline (seconds)
+pub const ENV_STARTUP_DEADLINE_SECS: &str = "STARTUP_DEADLINE_SECS";
+
+/// A dependency that must be ready before the service starts its API
+#[derive(Debug, Clone)]
+pub enum Dependency {
+    /// Configuration database file exists on disk
+    ConfigPresent(PathBuf),
+    /// SQLite database opens and answers `SELECT 1`
+    Database(PathBuf),
+    /// Any of the Redis URLs answers PING
+    Redis(Vec<String>),
+}
+
+impl Dependency {
+    /// Redis dependency using the same candidate order as `bootstrap_database` (DB > ENV > default)
+    pub fn redis(db_url: Option<String>) -> Self {
+        let urls = crate::config_loader::build_redis_candidates(db_url, crate::DEFAULT_REDIS_URL)
+            .into_iter()
+            .map(|(_, url)| url)
+            .collect();
+        Dependency::Redis(urls)
+    }
+
+    /// Short name used in progress logs
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dependency::ConfigPresent(_) => "config",
+            Dependency::Database(_) => "database",
+            Dependency::Redis(_) => "redis",
+        }
+    }
+
+    /// Check the dependency once
+    async fn check(&self) -> anyhow::Result<()> {
+        match self {
+            Dependency::ConfigPresent(path) => {
+                if path.exists() {
+                    Ok(())
+                } else {
+                    anyhow::bail!("{} not found", path.display())
+                }
+            },
+            Dependency::Database(path) => {
+                let pool = sqlx::SqlitePool::connect(&format!("sqlite:{}?mode=ro", path.display()))
+                    .await?;
+                let result = sqlx::query("SELECT 1").fetch_one(&pool).await;
+                pool.close().await;
+                result.map(|_| ())?;
+                Ok(())
+            },
+            Dependency::Redis(urls) => {
+                let mut last_error = anyhow::anyhow!("no Redis URL configured");
+                for url in urls {
+                    match crate::redis::RedisClient::new(url).await {
+                        Ok(client) => match client.ping().await {
+                            Ok(_) => return Ok(()),
+                            Err(e) => last_error = anyhow::anyhow!("{}: {}", url, e),
+                        },
+                        Err(e) => last_error = anyhow::anyhow!("{}: {}", url, e),
+                    }
+                }
+                Err(last_error)
+            },
+        }
+    }
+}
+
+/// Backoff and deadline settings for the dependency wait phase
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DependencyWaitConfig {
+    /// Total time allowed for all dependencies, measured from waiter creation
+    pub deadline: Duration,
+    /// Delay before the first retry
+    pub initial_backoff: Duration,
+    /// Upper bound for the retry delay
+    pub max_backoff: Duration,
+}
+
+impl Default for DependencyWaitConfig {
+    fn default() -> Self {
+        Self {
+            deadline: Duration::from_secs(60),
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl DependencyWaitConfig {
+    /// Default settings with the deadline taken from `STARTUP_DEADLINE_SECS` if set
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Some(secs) = std::env::var(ENV_STARTUP_DEADLINE_SECS)
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+        {
+            config.deadline = Duration::from_secs(secs);
+        }
+        config
+    }
+
+    /// Delay before retry number `attempt` (1-based), doubling up to `max_backoff`
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32 << attempt.saturating_sub(1).min(16);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Waits for startup dependencies with backoff up to a shared deadline
+///
+/// The deadline starts when the waiter is created, so a service can wait for
+/// the database before loading config and for Redis afterwards without
+/// extending the total startup budget.
+pub struct DependencyWaiter {
+    config: DependencyWaitConfig,
+    started: Instant,
+}
+
+impl DependencyWaiter {
+    pub fn new(config: DependencyWaitConfig) -> Self {
+        Self {
+            config,
+            started: Instant::now(),
+        }
+    }
+
+    /// Wait for each dependency in order, failing once the deadline has passed
+    pub async fn wait_for(&self, dependencies: &[Dependency]) -> anyhow::Result<()> {
+        for dependency in dependencies {
+            self.wait_one(dependency).await?;
+        }
+        Ok(())
+    }
+
+    async fn wait_one(&self, dependency: &Dependency) -> anyhow::Result<()> {
+        let mut attempt = 0u32;
+        loop {
+            attempt = attempt.saturating_add(1);
+            let error = match dependency.check().await {
+                Ok(()) => {
+                    if attempt > 1 {
+                        info!(
+                            "Dependency {} ready after {} attempts",
+                            dependency.name(),
+                            attempt
+                        );
+                    } else {
+                        debug!("Dependency {} ready", dependency.name());
+                    }
+                    return Ok(());
+                },
+                Err(e) => e,
+            };
+
+            let elapsed = self.started.elapsed();
+            let delay = self.config.backoff(attempt);
+            if elapsed + delay > self.config.deadline {
+                anyhow::bail!(
+                    "Dependency {} not ready after {:?} ({} attempts): {}",
+                    dependency.name(),
+                    elapsed,
+                    attempt,
+                    error
+                );
+            }
+
+            warn!(
+                "Waiting for {} (attempt {}, {:?}/{:?}): {}",
+                dependency.name(),
+                attempt,
+                elapsed,
+                self.config.deadline,
+                error
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
 mod tests {
@@ -247,4 +496,54 @@ mod tests {
         let path = get_config_path(&service);
         assert_eq!(path, "data/testservice.db");
     }
+
+    #[test]
+    fn test_dependency_backoff_doubles_and_caps() {
+        let config = DependencyWaitConfig::default();
+        assert_eq!(config.backoff(1), Duration::from_millis(500));
+        assert_eq!(config.backoff(2), Duration::from_secs(1));
+        assert_eq!(config.backoff(3), Duration::from_secs(2));
+        assert_eq!(config.backoff(10), Duration::from_secs(5));
+        assert_eq!(config.backoff(u32::MAX), Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_config_present() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("svc.db");
+        let waiter = DependencyWaiter::new(DependencyWaitConfig {
+            deadline: Duration::from_secs(2),
+            initial_backoff: Duration::from_millis(20),
+            max_backoff: Duration::from_millis(50),
+        });
+
+        // File appears while we are waiting
+        let create_path = path.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(60)).await;
+            std::fs::write(create_path, b"").unwrap();
+        });
+
+        waiter
+            .wait_for(&[Dependency::ConfigPresent(path)])
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_gives_up_at_deadline() {
+        let waiter = DependencyWaiter::new(DependencyWaitConfig {
+            deadline: Duration::from_millis(100),
+            initial_backoff: Duration::from_millis(20),
+            max_backoff: Duration::from_millis(40),
+        });
+
+        let err = waiter
+            .wait_for(&[Dependency::ConfigPresent(PathBuf::from(
+                "/nonexistent/voltage/svc.db",
+            ))])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("config"));
+    }
 }
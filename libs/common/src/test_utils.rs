@@ -5,3 +5,6 @@
 
 #[cfg(feature = "sqlite")]
 pub mod schema;
+
+#[cfg(feature = "test-fixtures")]
+pub mod services;
@@ -89,6 +89,7 @@ pub const CONTROL_POINTS_TABLE: &str = r#"
         data_type TEXT,
         description TEXT,
         protocol_mappings TEXT,
+        access TEXT,
         PRIMARY KEY (channel_id, point_id)
     )
 "#;
@@ -106,6 +107,7 @@ pub const ADJUSTMENT_POINTS_TABLE: &str = r#"
         data_type TEXT,
         description TEXT,
         protocol_mappings TEXT,
+        access TEXT,
         PRIMARY KEY (channel_id, point_id)
     )
 "#;
@@ -127,12 +129,36 @@ pub const INSTANCES_TABLE: &str = r#"
         product_name TEXT NOT NULL,
         parent_id INTEGER,
         properties TEXT,
+        version INTEGER NOT NULL DEFAULT 1,
         created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
         updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
         FOREIGN KEY (parent_id) REFERENCES instances(instance_id) ON DELETE SET NULL
     )
 "#;
 
+/// Instance tags table DDL (matches modsrv::config::InstanceTagRecord)
+pub const INSTANCE_TAGS_TABLE: &str = r#"
+    CREATE TABLE IF NOT EXISTS instance_tags (
+        instance_id INTEGER NOT NULL REFERENCES instances(instance_id) ON DELETE CASCADE,
+        tag_key TEXT NOT NULL,
+        tag_value TEXT NOT NULL,
+        PRIMARY KEY (instance_id, tag_key)
+    )
+"#;
+
+/// Point aliases table DDL (matches modsrv::config::PointAliasRecord)
+pub const POINT_ALIASES_TABLE: &str = r#"
+    CREATE TABLE IF NOT EXISTS point_aliases (
+        alias TEXT NOT NULL PRIMARY KEY,
+        scheme TEXT NOT NULL DEFAULT 'custom',
+        address TEXT NOT NULL,
+        description TEXT,
+        created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+        updated_at TEXT DEFAULT CURRENT_TIMESTAMP,
+        UNIQUE(scheme, address)
+    )
+"#;
+
 /// Measurement routing table DDL (matches modsrv::config::MeasurementRoutingRecord)
 pub const MEASUREMENT_ROUTING_TABLE: &str = r#"
     CREATE TABLE IF NOT EXISTS measurement_routing (
@@ -144,6 +170,9 @@ pub const MEASUREMENT_ROUTING_TABLE: &str = r#"
         channel_point_id INTEGER,
         measurement_id INTEGER NOT NULL,
         description TEXT,
+        deadband REAL,
+        deadband_pct REAL,
+        refresh_secs INTEGER,
         enabled INTEGER NOT NULL DEFAULT 1,
         created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
         updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
@@ -184,6 +213,7 @@ pub const RULE_CHAINS_TABLE: &str = r#"
         enabled INTEGER DEFAULT 1,
         priority INTEGER DEFAULT 0,
         cooldown_ms INTEGER DEFAULT 0,
+        shadow INTEGER DEFAULT 0,
         nodes_json TEXT NOT NULL,
         flow_json TEXT,
         format TEXT DEFAULT 'vue-flow',
@@ -239,7 +269,7 @@ pub async fn init_comsrv_schema(pool: &SqlitePool) -> Result<()> {
 /// - service_config
 /// - sync_metadata
 /// - channels (required by routing table foreign keys)
-/// - instances
+/// - instances, instance_tags
 /// - measurement_routing, action_routing
 ///
 /// Note: Products are now compile-time built-in constants from voltage-model crate.
@@ -254,6 +284,8 @@ pub async fn init_modsrv_schema(pool: &SqlitePool) -> Result<()> {
 
     // Instance table (no longer references products table)
     sqlx::query(INSTANCES_TABLE).execute(pool).await?;
+    sqlx::query(INSTANCE_TAGS_TABLE).execute(pool).await?;
+    sqlx::query(POINT_ALIASES_TABLE).execute(pool).await?;
 
     // Routing tables
     sqlx::query(MEASUREMENT_ROUTING_TABLE).execute(pool).await?;
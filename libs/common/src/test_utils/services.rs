@@ -0,0 +1,425 @@
+//! In-process service fixtures for cross-service tests
+//!
+//! Runs comsrv, modsrv and the rule engine (rulesrv) inside the test process
+//! on loopback ports. All three share one temporary `voltage.db` and one
+//! `MemoryRtdb`, so a test can drive a virtual channel through comsrv,
+//! read the routed measurement from modsrv and let a rule write back, with
+//! no Docker, Redis or monarch run.
+//!
+//! The services implement [`ServiceFixture`] in their own crates
+//! (`comsrv::test_fixtures`, `modsrv::test_fixtures`), since common cannot
+//! depend on them.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use common::test_utils::services::{FixtureEnv, ServiceStack, VirtualChannel};
+//! use voltage_model::PointType;
+//!
+//! let env = FixtureEnv::new().await?;
+//! env.add_channel(
+//!     &VirtualChannel::new(1001, "pcs_link")
+//!         .point(PointType::Telemetry, 1, "P")
+//!         .point(PointType::Adjustment, 1, "P_set"),
+//! )
+//! .await?;
+//! env.add_instance(1, "pcs_01", "PCS").await?;
+//! env.route_measurement(1, 1, 1001, PointType::Telemetry, 1).await?;
+//!
+//! let stack: ServiceStack<ComsrvFixture, ModsrvFixture, RulesrvFixture> =
+//!     ServiceStack::start(env).await?;
+//! let health = reqwest::get(stack.comsrv.url("/health")).await?;
+//! ```
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use axum::Router;
+use sqlx::SqlitePool;
+use tempfile::TempDir;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use voltage_model::PointType;
+use voltage_rtdb::{MemoryRtdb, RoutingCache};
+
+use super::schema;
+
+// ============================================================================
+// Shared environment
+// ============================================================================
+
+/// Resources shared by the services of a stack
+///
+/// The database is a file in a temporary directory (removed on drop) so
+/// that every service can open its own pool on it, as in production.
+pub struct FixtureEnv {
+    dir: TempDir,
+    pool: SqlitePool,
+    rtdb: Arc<MemoryRtdb>,
+    routing_cache: Arc<RoutingCache>,
+}
+
+impl FixtureEnv {
+    /// Create an empty database with the comsrv, modsrv and rule tables
+    pub async fn new() -> Result<Self> {
+        let dir = tempfile::tempdir()?;
+        let url = format!(
+            "sqlite:{}?mode=rwc",
+            dir.path().join("voltage.db").display()
+        );
+        let pool = SqlitePool::connect(&url)
+            .await
+            .with_context(|| format!("open {}", url))?;
+        schema::init_comsrv_schema(&pool).await?;
+        schema::init_modsrv_schema(&pool).await?;
+        schema::init_rules_schema(&pool).await?;
+
+        Ok(Self {
+            dir,
+            pool,
+            rtdb: Arc::new(MemoryRtdb::new()),
+            routing_cache: Arc::new(RoutingCache::new()),
+        })
+    }
+
+    /// Path of the shared `voltage.db`
+    pub fn db_path(&self) -> PathBuf {
+        self.dir.path().join("voltage.db")
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    pub fn rtdb(&self) -> &Arc<MemoryRtdb> {
+        &self.rtdb
+    }
+
+    pub fn routing_cache(&self) -> &Arc<RoutingCache> {
+        &self.routing_cache
+    }
+
+    /// Insert a virtual channel and its points
+    pub async fn add_channel(&self, channel: &VirtualChannel) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO channels (channel_id, name, protocol, enabled, config) VALUES (?, ?, 'virtual', 1, '{}')",
+        )
+        .bind(channel.id)
+        .bind(&channel.name)
+        .execute(&self.pool)
+        .await?;
+
+        for (point_type, point_id, signal_name) in &channel.points {
+            let table = match point_type {
+                PointType::Telemetry => "telemetry_points",
+                PointType::Signal => "signal_points",
+                PointType::Control => "control_points",
+                PointType::Adjustment => "adjustment_points",
+            };
+            sqlx::query(&format!(
+                "INSERT INTO {} (channel_id, point_id, signal_name, data_type) VALUES (?, ?, ?, 'float64')",
+                table
+            ))
+            .bind(channel.id)
+            .bind(point_id)
+            .bind(signal_name)
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Insert an instance of a built-in product
+    pub async fn add_instance(&self, instance_id: u16, name: &str, product: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO instances (instance_id, instance_name, product_name, properties) VALUES (?, ?, ?, '{}')",
+        )
+        .bind(instance_id)
+        .bind(name)
+        .bind(product)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Route a telemetry or signal point to an instance measurement (C2M)
+    pub async fn route_measurement(
+        &self,
+        instance_id: u16,
+        measurement_id: u32,
+        channel_id: u32,
+        point_type: PointType,
+        point_id: u32,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO measurement_routing (instance_id, instance_name, channel_id, channel_type, channel_point_id, measurement_id)
+             SELECT instance_id, instance_name, ?, ?, ?, ? FROM instances WHERE instance_id = ?",
+        )
+        .bind(channel_id)
+        .bind(point_type.as_str())
+        .bind(point_id)
+        .bind(measurement_id)
+        .bind(instance_id)
+        .execute(&self.pool)
+        .await?;
+        self.reload_routing().await
+    }
+
+    /// Route an instance action to a control or adjustment point (M2C)
+    pub async fn route_action(
+        &self,
+        instance_id: u16,
+        action_id: u32,
+        channel_id: u32,
+        point_type: PointType,
+        point_id: u32,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO action_routing (instance_id, instance_name, action_id, channel_id, channel_type, channel_point_id)
+             SELECT instance_id, instance_name, ?, ?, ?, ? FROM instances WHERE instance_id = ?",
+        )
+        .bind(action_id)
+        .bind(channel_id)
+        .bind(point_type.as_str())
+        .bind(point_id)
+        .bind(instance_id)
+        .execute(&self.pool)
+        .await?;
+        self.reload_routing().await
+    }
+
+    /// Rebuild the shared routing cache from the routing tables
+    pub async fn reload_routing(&self) -> Result<()> {
+        let maps = voltage_routing::load_routing_maps(&self.pool).await?;
+        self.routing_cache.update(maps.c2m, maps.m2c, maps.c2c);
+        Ok(())
+    }
+}
+
+/// Virtual channel seeded by [`FixtureEnv::add_channel`]
+#[derive(Debug, Clone)]
+pub struct VirtualChannel {
+    pub id: u32,
+    pub name: String,
+    pub points: Vec<(PointType, u32, String)>,
+}
+
+impl VirtualChannel {
+    pub fn new(id: u32, name: impl Into<String>) -> Self {
+        Self {
+            id,
+            name: name.into(),
+            points: Vec::new(),
+        }
+    }
+
+    pub fn point(mut self, point_type: PointType, point_id: u32, signal_name: &str) -> Self {
+        self.points
+            .push((point_type, point_id, signal_name.to_string()));
+        self
+    }
+}
+
+// ============================================================================
+// Services
+// ============================================================================
+
+/// A service that can run inside a test process
+///
+/// Start futures run on the test's own task and need not be `Send`.
+#[async_trait(?Send)]
+pub trait ServiceFixture {
+    /// Service name, used in logs and error messages
+    const NAME: &'static str;
+
+    /// State handed back to the test (channel manager, app state, ...)
+    type State: Send + Sync + 'static;
+
+    /// Build the service's router and state on the shared environment
+    async fn start(env: &FixtureEnv) -> Result<(Router, Self::State)>;
+}
+
+/// A service listening on a loopback port
+///
+/// The server stops when the handle is shut down or dropped.
+pub struct ServiceHandle<S> {
+    pub name: &'static str,
+    pub addr: SocketAddr,
+    pub state: S,
+    cancel: CancellationToken,
+    task: JoinHandle<()>,
+}
+
+impl<S> ServiceHandle<S> {
+    /// Build and serve `F` on an ephemeral port
+    pub async fn spawn<F>(env: &FixtureEnv) -> Result<Self>
+    where
+        F: ServiceFixture<State = S>,
+    {
+        let (router, state) = F::start(env)
+            .await
+            .with_context(|| format!("start {}", F::NAME))?;
+        Self::serve(F::NAME, router, state).await
+    }
+
+    /// Serve an already built router on an ephemeral port
+    pub async fn serve(name: &'static str, router: Router, state: S) -> Result<Self> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let cancel = CancellationToken::new();
+        let shutdown = cancel.clone();
+        let task = tokio::spawn(async move {
+            let served = axum::serve(
+                listener,
+                router.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(async move { shutdown.cancelled().await })
+            .await;
+            if let Err(e) = served {
+                tracing::error!("{} fixture server: {}", name, e);
+            }
+        });
+        tracing::debug!("{} fixture on {}", name, addr);
+
+        Ok(Self {
+            name,
+            addr,
+            state,
+            cancel,
+            task,
+        })
+    }
+
+    /// `http://127.0.0.1:<port>`
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// URL of `path` on this service
+    pub fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url(), path.trim_start_matches('/'))
+    }
+
+    /// Stop accepting requests and wait for open ones to finish
+    pub async fn shutdown(mut self) {
+        self.cancel.cancel();
+        let _ = (&mut self.task).await;
+    }
+}
+
+impl<S> Drop for ServiceHandle<S> {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+/// comsrv, modsrv and rulesrv started on one [`FixtureEnv`]
+///
+/// Services start in dependency order: comsrv first (it owns the channels
+/// the others route to), then modsrv, then the rule engine.
+pub struct ServiceStack<C: ServiceFixture, M: ServiceFixture, R: ServiceFixture> {
+    pub comsrv: ServiceHandle<C::State>,
+    pub modsrv: ServiceHandle<M::State>,
+    pub rulesrv: ServiceHandle<R::State>,
+    pub env: FixtureEnv,
+}
+
+impl<C: ServiceFixture, M: ServiceFixture, R: ServiceFixture> ServiceStack<C, M, R> {
+    pub async fn start(env: FixtureEnv) -> Result<Self> {
+        env.reload_routing().await?;
+        let comsrv = ServiceHandle::spawn::<C>(&env).await?;
+        let modsrv = ServiceHandle::spawn::<M>(&env).await?;
+        let rulesrv = ServiceHandle::spawn::<R>(&env).await?;
+        Ok(Self {
+            comsrv,
+            modsrv,
+            rulesrv,
+            env,
+        })
+    }
+
+    /// Stop the services in reverse start order
+    pub async fn shutdown(self) {
+        self.rulesrv.shutdown().await;
+        self.modsrv.shutdown().await;
+        self.comsrv.shutdown().await;
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use axum::extract::State;
+    use axum::routing::get;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Service answering with the number of routed measurements
+    struct Routes;
+
+    #[async_trait(?Send)]
+    impl ServiceFixture for Routes {
+        const NAME: &'static str = "routes";
+        type State = Arc<RoutingCache>;
+
+        async fn start(env: &FixtureEnv) -> Result<(Router, Self::State)> {
+            let cache = Arc::clone(env.routing_cache());
+            let router = Router::new()
+                .route(
+                    "/count",
+                    get(|State(cache): State<Arc<RoutingCache>>| async move {
+                        cache.stats().c2m_count.to_string()
+                    }),
+                )
+                .with_state(Arc::clone(&cache));
+            Ok((router, cache))
+        }
+    }
+
+    async fn get_body(url: &str) -> String {
+        let authority = url.trim_start_matches("http://");
+        let (host, path) = authority.split_once('/').unwrap();
+        let mut stream = tokio::net::TcpStream::connect(host).await.unwrap();
+        let request = format!(
+            "GET /{} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+            path, host
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response.split("\r\n\r\n").nth(1).unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_stack_shares_environment() {
+        let env = FixtureEnv::new().await.unwrap();
+        env.add_channel(
+            &VirtualChannel::new(1001, "pcs_link")
+                .point(PointType::Telemetry, 1, "P")
+                .point(PointType::Adjustment, 1, "P_set"),
+        )
+        .await
+        .unwrap();
+        env.add_instance(1, "pcs_01", "PCS").await.unwrap();
+        env.route_measurement(1, 1, 1001, PointType::Telemetry, 1)
+            .await
+            .unwrap();
+        env.route_action(1, 1, 1001, PointType::Adjustment, 1)
+            .await
+            .unwrap();
+        assert!(env.routing_cache().lookup_c2m("1001:T:1").is_some());
+        assert!(env.routing_cache().lookup_m2c("1:A:1").is_some());
+
+        let stack: ServiceStack<Routes, Routes, Routes> = ServiceStack::start(env).await.unwrap();
+        assert_ne!(stack.comsrv.addr, stack.modsrv.addr);
+        assert!(Arc::ptr_eq(&stack.comsrv.state, &stack.rulesrv.state));
+        assert_eq!(get_body(&stack.modsrv.url("/count")).await, "1");
+
+        let addr = stack.rulesrv.addr;
+        stack.shutdown().await;
+        assert!(tokio::net::TcpStream::connect(addr).await.is_err());
+    }
+}
@@ -1,11 +1,20 @@
 //! CSV and configuration validation utilities
 //!
 //! Provides automatic CSV header validation by comparing actual CSV files
-//! against expected field names from Rust struct definitions.
+//! against expected field names from Rust struct definitions, a header mapping
+//! layer for vendor CSV dialects ([`CsvDialects`]), and a declarative rule set
+//! ([`RuleSet`]) for cross-field and referential checks.
 
-use std::collections::HashSet;
+pub mod rules;
+
+pub use rules::{Operand, RuleSet, RuleSeverity, RuleSpec, ValidationRule};
+
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
+use csv::StringRecord;
+use serde::{Deserialize, Serialize};
+
 use crate::service_config::{ValidationLevel, ValidationResult};
 
 /// Trait for types that can be deserialized from CSV files
@@ -28,6 +37,209 @@ pub trait CsvFields {
     }
 }
 
+// ============================================================================
+// Vendor Dialects (header alias tables)
+// ============================================================================
+
+/// Alternative header spellings used by one vendor's point lists
+///
+/// `aliases` maps a canonical field name to the spellings that vendor uses,
+/// e.g. `signal_name: ["Point Name", "Tag"]`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CsvDialect {
+    pub name: String,
+    #[serde(default)]
+    pub aliases: HashMap<String, Vec<String>>,
+}
+
+impl CsvDialect {
+    /// Create a dialect without aliases
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            aliases: HashMap::new(),
+        }
+    }
+
+    /// Add alternative spellings for a canonical field
+    pub fn alias(mut self, canonical: &str, spellings: &[&str]) -> Self {
+        self.aliases
+            .entry(canonical.to_string())
+            .or_default()
+            .extend(spellings.iter().map(|s| s.to_string()));
+        self
+    }
+
+    /// Map actual headers to expected fields; returns the canonical name per column
+    ///
+    /// Aliases of `fallback` apply where this dialect defines none.
+    fn map_headers(
+        &self,
+        actual: &[String],
+        expected: &[String],
+        fallback: Option<&CsvDialect>,
+    ) -> Vec<Option<String>> {
+        let mut lookup: HashMap<String, &String> = HashMap::new();
+        for field in expected {
+            lookup.insert(normalize_header(field), field);
+        }
+        for dialect in std::iter::once(self).chain(fallback) {
+            for (canonical, spellings) in &dialect.aliases {
+                if let Some(field) = expected.iter().find(|f| *f == canonical) {
+                    for spelling in spellings {
+                        lookup.entry(normalize_header(spelling)).or_insert(field);
+                    }
+                }
+            }
+        }
+
+        let mut assigned = HashSet::new();
+        actual
+            .iter()
+            .map(|header| {
+                lookup
+                    .get(&normalize_header(header))
+                    .filter(|field| assigned.insert((**field).clone()))
+                    .map(|field| (*field).clone())
+            })
+            .collect()
+    }
+}
+
+/// Alias table of known vendor dialects
+///
+/// Loaded from YAML (`dialects: [{name, aliases}]`). The dialect that maps the
+/// most expected fields wins; the built-in `standard` dialect is always tried.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CsvDialects {
+    #[serde(default)]
+    pub dialects: Vec<CsvDialect>,
+}
+
+impl CsvDialects {
+    /// Load an alias table from a YAML file
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_yaml::from_str(&content)?)
+    }
+
+    /// Common spellings seen in customer point lists (English and Chinese)
+    pub fn standard() -> CsvDialect {
+        CsvDialect::new("standard")
+            .alias(
+                "point_id",
+                &["id", "point_no", "point_number", "点号", "序号"],
+            )
+            .alias(
+                "signal_name",
+                &["name", "point_name", "signal", "点名", "名称"],
+            )
+            .alias(
+                "scale",
+                &["ratio", "factor", "coefficient", "multiplier", "系数"],
+            )
+            .alias("offset", &["bias", "偏移", "偏移量"])
+            .alias("unit", &["units", "uom", "单位"])
+            .alias("reverse", &["invert", "inverted", "取反"])
+            .alias("data_type", &["type", "datatype", "数据类型"])
+    }
+
+    /// Pick the dialect mapping the most expected fields (vendor aliases on top of standard)
+    fn best_mapping(&self, actual: &[String], expected: &[String]) -> HeaderMapping {
+        let standard = Self::standard();
+        std::iter::once((&standard, None))
+            .chain(self.dialects.iter().map(|d| (d, Some(&standard))))
+            .map(|(dialect, fallback)| HeaderMapping {
+                dialect: dialect.name.clone(),
+                original: actual.to_vec(),
+                columns: dialect.map_headers(actual, expected, fallback),
+            })
+            .fold(None::<HeaderMapping>, |best, candidate| match best {
+                Some(best) if best.mapped_count() >= candidate.mapped_count() => Some(best),
+                _ => Some(candidate),
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Normalize a header for comparison: trim, drop BOM, lowercase, unify separators
+pub fn normalize_header(header: &str) -> String {
+    header
+        .trim()
+        .trim_start_matches('\u{feff}')
+        .to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '.' || c == '_')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Result of mapping a CSV header row onto canonical field names
+#[derive(Debug, Clone, Default)]
+pub struct HeaderMapping {
+    /// Dialect that produced this mapping
+    pub dialect: String,
+    /// Header row as found in the file
+    pub original: Vec<String>,
+    /// Canonical field per column (`None` = unknown column, kept as-is)
+    pub columns: Vec<Option<String>>,
+}
+
+impl HeaderMapping {
+    fn mapped_count(&self) -> usize {
+        self.columns.iter().flatten().count()
+    }
+
+    /// Header row with canonical names substituted
+    pub fn headers(&self) -> Vec<String> {
+        self.original
+            .iter()
+            .zip(&self.columns)
+            .map(|(original, mapped)| mapped.clone().unwrap_or_else(|| original.clone()))
+            .collect()
+    }
+
+    /// Columns whose spelling differed from the canonical name
+    pub fn renamed(&self) -> Vec<(String, String)> {
+        self.original
+            .iter()
+            .zip(&self.columns)
+            .filter_map(|(original, mapped)| match mapped {
+                Some(field) if field != original => Some((original.clone(), field.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// CSV file with headers rewritten to canonical field names
+pub struct NormalizedCsv<R = std::fs::File> {
+    pub mapping: HeaderMapping,
+    /// Header validation after mapping (renames reported as warnings)
+    pub validation: ValidationResult,
+    /// Reader positioned after the header, using canonical headers
+    pub reader: csv::Reader<R>,
+}
+
+impl<R: std::io::Read> NormalizedCsv<R> {
+    /// Records as canonical field -> value maps
+    pub fn into_records(mut self) -> anyhow::Result<Vec<HashMap<String, String>>> {
+        let headers = self.reader.headers()?.clone();
+        let mut records = Vec::new();
+        for record in self.reader.records() {
+            let record = record?;
+            records.push(
+                headers
+                    .iter()
+                    .zip(record.iter())
+                    .map(|(h, v)| (h.to_string(), v.to_string()))
+                    .collect(),
+            );
+        }
+        Ok(records)
+    }
+}
+
 /// CSV Header Validator
 pub struct CsvHeaderValidator;
 
@@ -56,13 +268,86 @@ impl CsvHeaderValidator {
         let expected_headers = T::field_names();
 
         // Validate
-        Self::validate_headers(&actual_headers, &expected_headers, csv_path)
+        Self::validate_headers(
+            &actual_headers,
+            &expected_headers,
+            &T::optional_fields(),
+            csv_path,
+        )
+    }
+
+    /// Open a CSV file, mapping vendor header spellings onto `T`'s fields
+    ///
+    /// The returned reader deserializes with canonical names, so customer point
+    /// lists import without manual renaming.
+    pub fn open_normalized<T>(
+        csv_path: &Path,
+        dialects: &CsvDialects,
+    ) -> anyhow::Result<NormalizedCsv>
+    where
+        T: CsvFields,
+    {
+        let file = std::fs::File::open(csv_path)?;
+        Self::normalize_reader::<T, _>(file, csv_path, dialects)
+    }
+
+    /// Same as [`Self::open_normalized`] for CSV content from another source
+    ///
+    /// `source` is only used in messages (e.g. a converted spreadsheet sheet).
+    pub fn normalize_reader<T, R>(
+        csv_reader: R,
+        source: &Path,
+        dialects: &CsvDialects,
+    ) -> anyhow::Result<NormalizedCsv<R>>
+    where
+        T: CsvFields,
+        R: std::io::Read,
+    {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(true)
+            .from_reader(csv_reader);
+
+        let actual_headers: Vec<String> = reader.headers()?.iter().map(|s| s.to_string()).collect();
+        let expected_headers = T::field_names();
+
+        let mapping = dialects.best_mapping(&actual_headers, &expected_headers);
+        let headers = mapping.headers();
+        reader.set_headers(StringRecord::from(headers.clone()));
+
+        let mut validation =
+            Self::validate_headers(&headers, &expected_headers, &T::optional_fields(), source)?;
+        let renamed = mapping.renamed();
+        if !renamed.is_empty() {
+            let renamed_str = renamed
+                .iter()
+                .map(|(from, to)| format!("{} -> {}", from, to))
+                .collect::<Vec<_>>()
+                .join(", ");
+            validation.warnings.insert(
+                0,
+                format!(
+                    "Mapped headers in {} using dialect '{}': [{}]",
+                    source.display(),
+                    mapping.dialect,
+                    renamed_str
+                ),
+            );
+        }
+
+        Ok(NormalizedCsv {
+            mapping,
+            validation,
+            reader,
+        })
     }
 
     /// Validate headers with detailed error reporting
+    ///
+    /// `optional` columns may be absent and are not reported as extra.
     fn validate_headers(
         actual: &[String],
         expected: &[String],
+        optional: &[String],
         csv_path: &Path,
     ) -> anyhow::Result<ValidationResult> {
         let actual_set: HashSet<_> = actual.iter().collect();
@@ -88,7 +373,10 @@ impl CsvHeaderValidator {
         }
 
         // Check for extra fields (warnings only)
-        let extra: Vec<_> = actual_set.difference(&expected_set).collect();
+        let extra: Vec<_> = actual_set
+            .difference(&expected_set)
+            .filter(|field| !optional.contains(field))
+            .collect();
         if !extra.is_empty() {
             // Use clone() instead of to_string() to avoid extra deref chain
             let extra_str = extra
@@ -104,7 +392,8 @@ impl CsvHeaderValidator {
         }
 
         // Check field order (warning only - CSV allows any order)
-        if actual != expected && missing.is_empty() && extra.is_empty() {
+        let known: Vec<_> = actual.iter().filter(|f| expected.contains(f)).collect();
+        if known != expected.iter().collect::<Vec<_>>() && missing.is_empty() && extra.is_empty() {
             warnings.push(format!(
                 "Field order in {} differs from expected (this is OK, just informational)",
                 csv_path.display()
@@ -146,3 +435,56 @@ impl CsvHeaderValidator {
         Ok(aggregated)
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    struct PointRow;
+
+    impl CsvFields for PointRow {
+        fn field_names() -> Vec<String> {
+            ["point_id", "signal_name", "scale", "unit"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_normalize_header() {
+        assert_eq!(normalize_header("\u{feff} Point-Name "), "point_name");
+        assert_eq!(normalize_header("Signal  Name"), "signal_name");
+        assert_eq!(normalize_header("单位"), "单位");
+    }
+
+    #[test]
+    fn test_open_normalized_with_vendor_dialect() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("telemetry.csv");
+        std::fs::write(
+            &path,
+            "Tag No,Description,Ratio,单位,Remark\n1,Voltage,0.1,V,x\n",
+        )
+        .unwrap();
+
+        let dialects = CsvDialects {
+            dialects: vec![CsvDialect::new("vendor_x")
+                .alias("point_id", &["Tag No"])
+                .alias("signal_name", &["Description"])],
+        };
+
+        let normalized = CsvHeaderValidator::open_normalized::<PointRow>(&path, &dialects).unwrap();
+        assert_eq!(normalized.mapping.dialect, "vendor_x");
+        assert!(normalized.validation.is_valid);
+        assert!(normalized.validation.warnings[0].contains("Tag No -> point_id"));
+
+        let records = normalized.into_records().unwrap();
+        assert_eq!(records[0]["point_id"], "1");
+        assert_eq!(records[0]["signal_name"], "Voltage");
+        assert_eq!(records[0]["scale"], "0.1");
+        assert_eq!(records[0]["unit"], "V");
+        assert_eq!(records[0]["Remark"], "x");
+    }
+}
@@ -0,0 +1,524 @@
+//! Declarative validation rules
+//!
+//! A [`RuleSet`] describes configuration constraints as data instead of
+//! hand-written checks, so the same rules can be evaluated by monarch,
+//! config-ui (rules serialize to JSON/YAML) and service startup.
+//!
+//! Rules address fields with dotted paths evaluated against the serialized
+//! configuration, e.g. `channels[*].parameters.host`. `[*]` expands every
+//! array element and `[N]` selects one. A rule with `each` is evaluated once
+//! per element matched by `each`, with its paths relative to that element;
+//! reference targets are always resolved from the document root.
+//!
+//! ```yaml
+//! name: comsrv
+//! rules:
+//!   - rule: unique
+//!     path: channels[*].id
+//!   - rule: required_if
+//!     each: channels[*]
+//!     path: parameters.host
+//!     when: protocol
+//!     equals: modbus_tcp
+//!     message: "Channel {name}: Modbus TCP requires 'host' parameter"
+//!   - rule: reference
+//!     path: routing[*].channel_id
+//!     target: channels[*].id
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::service_config::{ComparisonOperator, ValidationLevel, ValidationResult};
+
+/// How a rule violation is reported
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleSeverity {
+    /// Violation makes the configuration invalid
+    #[default]
+    Error,
+    /// Violation is reported but the configuration stays valid
+    Warning,
+}
+
+/// Right-hand side of a comparison
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operand {
+    /// Another field (relative to the rule scope)
+    Field(String),
+    /// A literal value
+    Value(Value),
+}
+
+/// A single declarative constraint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum ValidationRule {
+    /// Field must be present, non-null and not an empty string
+    Required { path: String },
+    /// Field is required when another field equals the given value
+    RequiredIf {
+        path: String,
+        when: String,
+        equals: Value,
+    },
+    /// Cross-field comparison; skipped when the left field is absent
+    Compare {
+        left: String,
+        op: ComparisonOperator,
+        right: Operand,
+    },
+    /// Every value at `path` must exist among the values at `target`
+    Reference { path: String, target: String },
+    /// Values at `path` must not repeat
+    Unique { path: String },
+    /// Values at `path` must be one of the listed values
+    OneOf { path: String, values: Vec<Value> },
+}
+
+/// A rule with its scope, severity and optional message template
+///
+/// Message templates may use `{location}`, `{value}` and any field of the
+/// scope element such as `{name}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleSpec {
+    #[serde(flatten)]
+    pub rule: ValidationRule,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub each: Option<String>,
+    #[serde(default)]
+    pub severity: RuleSeverity,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl From<ValidationRule> for RuleSpec {
+    fn from(rule: ValidationRule) -> Self {
+        Self {
+            rule,
+            each: None,
+            severity: RuleSeverity::Error,
+            message: None,
+        }
+    }
+}
+
+impl RuleSpec {
+    /// Evaluate the rule once per element matched by `each`
+    pub fn each(mut self, each: impl Into<String>) -> Self {
+        self.each = Some(each.into());
+        self
+    }
+
+    /// Report violations as warnings
+    pub fn warning(mut self) -> Self {
+        self.severity = RuleSeverity::Warning;
+        self
+    }
+
+    /// Replace the default violation message
+    pub fn message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+}
+
+/// Named collection of declarative rules
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuleSet {
+    pub name: String,
+    #[serde(default)]
+    pub rules: Vec<RuleSpec>,
+}
+
+impl RuleSet {
+    /// Create an empty rule set
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            rules: Vec::new(),
+        }
+    }
+
+    /// Append a rule
+    pub fn rule(mut self, rule: impl Into<RuleSpec>) -> Self {
+        self.rules.push(rule.into());
+        self
+    }
+
+    /// Load a rule set from YAML (JSON is valid YAML as well)
+    pub fn from_yaml_str(content: &str) -> anyhow::Result<Self> {
+        Ok(serde_yaml::from_str(content)?)
+    }
+
+    /// Evaluate against any serializable configuration
+    pub fn evaluate<T: Serialize>(
+        &self,
+        config: &T,
+        level: ValidationLevel,
+    ) -> anyhow::Result<ValidationResult> {
+        let root = serde_json::to_value(config)?;
+        Ok(self.evaluate_value(&root, level))
+    }
+
+    /// Evaluate against an already serialized document
+    pub fn evaluate_value(&self, root: &Value, level: ValidationLevel) -> ValidationResult {
+        let mut result = ValidationResult::new(level);
+
+        for spec in &self.rules {
+            let scopes = match &spec.each {
+                Some(each) => resolve(root, each),
+                None => vec![(String::new(), root)],
+            };
+
+            for (scope_path, scope) in scopes {
+                for (location, value) in check(&spec.rule, root, scope, &scope_path) {
+                    let message = render_message(spec, &location, value.as_ref(), scope);
+                    match spec.severity {
+                        RuleSeverity::Error => result.add_error(message),
+                        RuleSeverity::Warning => result.add_warning(message),
+                    }
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// Resolve a dotted path into `(location, value)` pairs
+pub fn resolve<'a>(root: &'a Value, path: &str) -> Vec<(String, &'a Value)> {
+    let mut current = vec![(String::new(), root)];
+
+    for segment in path.split('.').filter(|s| !s.is_empty()) {
+        let (key, index) = match segment.find('[') {
+            Some(pos) if segment.ends_with(']') => {
+                (&segment[..pos], Some(&segment[pos + 1..segment.len() - 1]))
+            },
+            _ => (segment, None),
+        };
+
+        let mut next = Vec::new();
+        for (location, value) in current {
+            let (location, value) = if key.is_empty() {
+                (location, value)
+            } else {
+                match value.get(key) {
+                    Some(child) => (join_path(&location, key), child),
+                    None => continue,
+                }
+            };
+
+            match (index, value) {
+                (None, _) => next.push((location, value)),
+                (Some("*"), Value::Array(items)) => next.extend(
+                    items
+                        .iter()
+                        .enumerate()
+                        .map(|(i, item)| (format!("{}[{}]", location, i), item)),
+                ),
+                (Some(idx), Value::Array(items)) => {
+                    if let Some(item) = idx.parse::<usize>().ok().and_then(|i| items.get(i)) {
+                        next.push((format!("{}[{}]", location, idx), item));
+                    }
+                },
+                _ => {},
+            }
+        }
+        current = next;
+    }
+
+    current
+}
+
+fn join_path(base: &str, path: &str) -> String {
+    if base.is_empty() {
+        path.to_string()
+    } else if path.is_empty() {
+        base.to_string()
+    } else {
+        format!("{}.{}", base, path)
+    }
+}
+
+/// Comparable key: numbers and numeric strings compare equal (CSV vs YAML values)
+fn value_key(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.trim().to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn is_present(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::String(s) => !s.trim().is_empty(),
+        _ => true,
+    }
+}
+
+/// Return the violations of one rule within one scope
+fn check(
+    rule: &ValidationRule,
+    root: &Value,
+    scope: &Value,
+    scope_path: &str,
+) -> Vec<(String, Option<Value>)> {
+    let mut violations = Vec::new();
+
+    match rule {
+        ValidationRule::Required { path } => {
+            if !resolve(scope, path).iter().any(|(_, v)| is_present(v)) {
+                violations.push((join_path(scope_path, path), None));
+            }
+        },
+        ValidationRule::RequiredIf { path, when, equals } => {
+            let triggered = resolve(scope, when)
+                .iter()
+                .any(|(_, v)| value_key(v) == value_key(equals));
+            if triggered && !resolve(scope, path).iter().any(|(_, v)| is_present(v)) {
+                violations.push((join_path(scope_path, path), None));
+            }
+        },
+        ValidationRule::Compare { left, op, right } => {
+            let right_value = match right {
+                Operand::Field(field) => resolve(scope, field).first().map(|(_, v)| (*v).clone()),
+                Operand::Value(value) => Some(value.clone()),
+            };
+            let Some(right_value) = right_value else {
+                return violations;
+            };
+            for (location, value) in resolve(scope, left) {
+                if !compare(value, *op, &right_value) {
+                    violations.push((join_path(scope_path, &location), Some(value.clone())));
+                }
+            }
+        },
+        ValidationRule::Reference { path, target } => {
+            let known: HashSet<String> = resolve(root, target)
+                .iter()
+                .map(|(_, v)| value_key(v))
+                .collect();
+            for (location, value) in resolve(scope, path) {
+                if is_present(value) && !known.contains(&value_key(value)) {
+                    violations.push((join_path(scope_path, &location), Some(value.clone())));
+                }
+            }
+        },
+        ValidationRule::OneOf { path, values } => {
+            let allowed: HashSet<String> = values.iter().map(value_key).collect();
+            for (location, value) in resolve(scope, path) {
+                if !allowed.contains(&value_key(value)) {
+                    violations.push((join_path(scope_path, &location), Some(value.clone())));
+                }
+            }
+        },
+        ValidationRule::Unique { path } => {
+            let mut seen: HashMap<String, String> = HashMap::new();
+            for (location, value) in resolve(scope, path) {
+                if seen.insert(value_key(value), location.clone()).is_some() {
+                    violations.push((join_path(scope_path, &location), Some(value.clone())));
+                }
+            }
+        },
+    }
+
+    violations
+}
+
+fn compare(left: &Value, op: ComparisonOperator, right: &Value) -> bool {
+    let numbers = || Some((as_number(left)?, as_number(right)?));
+
+    match op {
+        ComparisonOperator::Equal => value_key(left) == value_key(right),
+        ComparisonOperator::NotEqual => value_key(left) != value_key(right),
+        ComparisonOperator::GreaterThan => numbers().is_some_and(|(l, r)| l > r),
+        ComparisonOperator::GreaterThanOrEqual => numbers().is_some_and(|(l, r)| l >= r),
+        ComparisonOperator::LessThan => numbers().is_some_and(|(l, r)| l < r),
+        ComparisonOperator::LessThanOrEqual => numbers().is_some_and(|(l, r)| l <= r),
+        ComparisonOperator::Contains => match left {
+            Value::Array(items) => items.iter().any(|i| value_key(i) == value_key(right)),
+            _ => value_key(left).contains(&value_key(right)),
+        },
+        // Range and regex operators belong to the rules engine, not config validation
+        _ => false,
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn render_message(spec: &RuleSpec, location: &str, value: Option<&Value>, scope: &Value) -> String {
+    let value_text = value.map(value_key).unwrap_or_default();
+
+    let Some(template) = &spec.message else {
+        return match &spec.rule {
+            ValidationRule::Required { .. } => format!("{}: required field is missing", location),
+            ValidationRule::RequiredIf { when, equals, .. } => format!(
+                "{}: required when {} is {}",
+                location,
+                when,
+                value_key(equals)
+            ),
+            ValidationRule::Compare { op, right, .. } => {
+                let right_text = match right {
+                    Operand::Field(field) => field.clone(),
+                    Operand::Value(v) => value_key(v),
+                };
+                format!(
+                    "{}: value {} must be {} {}",
+                    location,
+                    value_text,
+                    op.as_str(),
+                    right_text
+                )
+            },
+            ValidationRule::Reference { target, .. } => {
+                format!("{}: value {} not found in {}", location, value_text, target)
+            },
+            ValidationRule::Unique { .. } => {
+                format!("{}: duplicate value {}", location, value_text)
+            },
+            ValidationRule::OneOf { values, .. } => format!(
+                "{}: value {} must be one of [{}]",
+                location,
+                value_text,
+                values.iter().map(value_key).collect::<Vec<_>>().join(", ")
+            ),
+        };
+    };
+
+    let mut message = String::with_capacity(template.len());
+    let mut rest = template.as_str();
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        message.push_str(&rest[..start]);
+        let key = &rest[start + 1..start + len];
+        match key {
+            "location" => message.push_str(location),
+            "value" => message.push_str(&value_text),
+            _ => match resolve(scope, key).first() {
+                Some((_, v)) => message.push_str(&value_key(v)),
+                None => message.push_str(&rest[start..=start + len]),
+            },
+        }
+        rest = &rest[start + len + 1..];
+    }
+    message.push_str(rest);
+    message
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample() -> Value {
+        json!({
+            "api": {"port": 6001, "max_port": 6000},
+            "channels": [
+                {"id": 1, "name": "pcs", "protocol": "modbus_tcp", "parameters": {"host": "10.0.0.1"}},
+                {"id": 2, "name": "bms", "protocol": "modbus_tcp", "parameters": {}},
+                {"id": 2, "name": "meter", "protocol": "virtual", "parameters": {}}
+            ],
+            "routing": [{"channel_id": "1"}, {"channel_id": "9"}]
+        })
+    }
+
+    #[test]
+    fn test_resolve_paths() {
+        let doc = sample();
+        let ids: Vec<_> = resolve(&doc, "channels[*].id")
+            .into_iter()
+            .map(|(loc, v)| (loc, v.clone()))
+            .collect();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(ids[1], ("channels[1].id".to_string(), json!(2)));
+        assert_eq!(resolve(&doc, "channels[0].parameters.host").len(), 1);
+        assert!(resolve(&doc, "channels[5].id").is_empty());
+        assert!(resolve(&doc, "missing.field").is_empty());
+    }
+
+    #[test]
+    fn test_rule_set_reports_violations() {
+        let rules = RuleSet::new("test")
+            .rule(ValidationRule::Unique {
+                path: "channels[*].id".to_string(),
+            })
+            .rule(
+                RuleSpec::from(ValidationRule::RequiredIf {
+                    path: "parameters.host".to_string(),
+                    when: "protocol".to_string(),
+                    equals: json!("modbus_tcp"),
+                })
+                .each("channels[*]")
+                .message("Channel {name}: Modbus TCP requires 'host' parameter"),
+            )
+            .rule(ValidationRule::Reference {
+                path: "routing[*].channel_id".to_string(),
+                target: "channels[*].id".to_string(),
+            })
+            .rule(
+                RuleSpec::from(ValidationRule::Compare {
+                    left: "api.port".to_string(),
+                    op: ComparisonOperator::LessThanOrEqual,
+                    right: Operand::Field("api.max_port".to_string()),
+                })
+                .warning(),
+            );
+
+        let result = rules.evaluate_value(&sample(), ValidationLevel::Business);
+
+        assert!(!result.is_valid);
+        assert_eq!(
+            result.errors,
+            vec![
+                "channels[2].id: duplicate value 2".to_string(),
+                "Channel bms: Modbus TCP requires 'host' parameter".to_string(),
+                "routing[1].channel_id: value 9 not found in channels[*].id".to_string(),
+            ]
+        );
+        assert_eq!(
+            result.warnings,
+            vec!["api.port: value 6001 must be lte api.max_port".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_rule_set_from_yaml() {
+        let rules = RuleSet::from_yaml_str(
+            r#"
+name: api
+rules:
+  - rule: required
+    path: api.host
+    severity: warning
+  - rule: compare
+    left: api.port
+    op: gt
+    right: {value: 0}
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(rules.rules.len(), 2);
+        let result = rules.evaluate_value(&sample(), ValidationLevel::Schema);
+        assert!(result.is_valid);
+        assert_eq!(
+            result.warnings,
+            vec!["api.host: required field is missing".to_string()]
+        );
+    }
+}
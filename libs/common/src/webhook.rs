@@ -0,0 +1,182 @@
+//! Outbound webhook delivery
+//!
+//! Shared by the services that notify external systems over HTTP (modsrv
+//! data subscriptions, comsrv command result webhooks). A body is POSTed as
+//! JSON and retried with exponential backoff; the caller decides what to log
+//! or count once delivery succeeded or finally failed.
+//!
+//! Requests carry either a bearer token or, with a secret, a signature:
+//!
+//! - `X-Voltage-Timestamp`: Unix time in milliseconds
+//! - `X-Voltage-Signature`: `sha256=<hex>`, the HMAC-SHA256 of
+//!   `{timestamp}.{body}` keyed by the secret
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::Duration;
+use tracing::debug;
+
+pub const SIGNATURE_HEADER: &str = "x-voltage-signature";
+pub const TIMESTAMP_HEADER: &str = "x-voltage-timestamp";
+
+/// Delivery attempts per body
+pub const DELIVERY_ATTEMPTS: u32 = 3;
+
+/// Delay before the second delivery attempt (doubled for each further one)
+pub const DELIVERY_BACKOFF: Duration = Duration::from_millis(500);
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Authentication added to each delivery request
+#[derive(Debug, Clone, Copy, Default)]
+pub enum WebhookAuth<'a> {
+    #[default]
+    None,
+    /// `Authorization: Bearer <token>`
+    Bearer(&'a str),
+    /// Timestamp and HMAC signature headers keyed by the secret
+    Signed(&'a str),
+}
+
+/// Target of a webhook delivery
+#[derive(Debug, Clone, Copy)]
+pub struct WebhookTarget<'a> {
+    pub url: &'a str,
+    /// Timeout of each attempt
+    pub timeout: Duration,
+    pub auth: WebhookAuth<'a>,
+}
+
+/// `sha256=<hex>` signature of a request body sent at `timestamp`
+pub fn signature(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    #[allow(clippy::expect_used)] // HMAC accepts keys of any length
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC key of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    let digest = mac.finalize().into_bytes();
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", hex)
+}
+
+/// POST a JSON body, retrying failed attempts with backoff
+///
+/// Returns the error of the last attempt when all [`DELIVERY_ATTEMPTS`] fail.
+pub async fn deliver(
+    http: &reqwest::Client,
+    target: &WebhookTarget<'_>,
+    body: &[u8],
+) -> std::result::Result<(), String> {
+    deliver_with_backoff(http, target, body, DELIVERY_BACKOFF).await
+}
+
+async fn deliver_with_backoff(
+    http: &reqwest::Client,
+    target: &WebhookTarget<'_>,
+    body: &[u8],
+    mut backoff: Duration,
+) -> std::result::Result<(), String> {
+    let mut attempt = 1;
+    loop {
+        let mut request = http
+            .post(target.url)
+            .timeout(target.timeout)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_vec());
+        match target.auth {
+            WebhookAuth::None => {},
+            WebhookAuth::Bearer(token) => request = request.bearer_auth(token),
+            WebhookAuth::Signed(secret) => {
+                let timestamp = chrono::Utc::now().timestamp_millis();
+                request = request
+                    .header(TIMESTAMP_HEADER, timestamp.to_string())
+                    .header(SIGNATURE_HEADER, signature(secret, timestamp, body));
+            },
+        }
+
+        let error = match request.send().await {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => format!("HTTP {}", response.status()),
+            Err(e) => e.to_string(),
+        };
+
+        if attempt == DELIVERY_ATTEMPTS {
+            return Err(error);
+        }
+        debug!(
+            "Webhook {} attempt {}/{} failed: {}",
+            target.url, attempt, DELIVERY_ATTEMPTS, error
+        );
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Answer each connection with the next status, counting requests
+    async fn serve(statuses: Vec<u16>) -> (String, Arc<AtomicU32>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&requests);
+        tokio::spawn(async move {
+            for status in statuses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf).await;
+                counter.fetch_add(1, Ordering::SeqCst);
+                let response = format!(
+                    "HTTP/1.1 {} X\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                    status
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        (url, requests)
+    }
+
+    fn target(url: &str) -> WebhookTarget<'_> {
+        WebhookTarget {
+            url,
+            timeout: Duration::from_secs(2),
+            auth: WebhookAuth::Signed("secret"),
+        }
+    }
+
+    #[test]
+    fn test_signature() {
+        let signed = signature("secret", 1_700_000_000_000, b"{}");
+        assert!(signed.starts_with("sha256="));
+        assert_eq!(signed.len(), 7 + 64);
+        assert_ne!(signed, signature("secret", 1_700_000_000_001, b"{}"));
+    }
+
+    #[tokio::test]
+    async fn test_deliver_retries_until_success() {
+        let (url, requests) = serve(vec![500, 200]).await;
+        let http = reqwest::Client::new();
+        let result =
+            deliver_with_backoff(&http, &target(&url), b"{}", Duration::from_millis(1)).await;
+        assert_eq!(result, Ok(()));
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_deliver_gives_up_after_attempts() {
+        let (url, requests) = serve(vec![503; DELIVERY_ATTEMPTS as usize]).await;
+        let http = reqwest::Client::new();
+        let result =
+            deliver_with_backoff(&http, &target(&url), b"{}", Duration::from_millis(1)).await;
+        assert_eq!(result, Err("HTTP 503 Service Unavailable".to_string()));
+        assert_eq!(requests.load(Ordering::SeqCst), DELIVERY_ATTEMPTS);
+    }
+}
@@ -629,6 +629,8 @@ pub enum ErrorCategory {
     Validation,
     NotFound,
     Conflict,
+    /// Conditional request failed (e.g. stale If-Match version)
+    PreconditionFailed,
     Permission,
 
     // Protocol/communication layer (comsrv-specific)
@@ -708,6 +710,7 @@ pub trait VoltageErrorTrait: std::error::Error + Send + Sync + 'static {
             ErrorCategory::NotFound => StatusCode::NOT_FOUND,
             ErrorCategory::Permission => StatusCode::FORBIDDEN,
             ErrorCategory::Conflict => StatusCode::CONFLICT,
+            ErrorCategory::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
             ErrorCategory::Timeout => StatusCode::REQUEST_TIMEOUT,
             ErrorCategory::Network => StatusCode::SERVICE_UNAVAILABLE,
             ErrorCategory::ResourceBusy => StatusCode::SERVICE_UNAVAILABLE,
@@ -4,6 +4,7 @@
 //! And stateless functions: scale, clamp, abs, min, max
 
 use crate::error::{CalcError, Result};
+use crate::limits::CalcLimits;
 use crate::state::{state_key, IntegrateState, MovingAvgState, RateOfChangeState, StateStore};
 use chrono::Utc;
 use std::sync::Arc;
@@ -20,6 +21,8 @@ pub struct BuiltinFunctions<S: StateStore> {
     state_store: Arc<S>,
     /// Context identifier (e.g., rule_id, instance_id)
     context: String,
+    /// Store size above which no new state is created
+    max_state_entries: usize,
 }
 
 impl<S: StateStore> BuiltinFunctions<S> {
@@ -27,9 +30,29 @@ impl<S: StateStore> BuiltinFunctions<S> {
         Self {
             state_store,
             context: context.into(),
+            max_state_entries: CalcLimits::default().max_state_entries,
         }
     }
 
+    /// Set the store size above which no new state is created
+    pub fn with_max_state_entries(mut self, max_state_entries: usize) -> Self {
+        self.max_state_entries = max_state_entries;
+        self
+    }
+
+    /// Refuse to create new state once the store holds `max_state_entries`
+    async fn check_new_state(&self, key: &str) -> Result<()> {
+        if let Some(count) = self.state_store.entry_count().await? {
+            if count >= self.max_state_entries {
+                return Err(CalcError::limit(format!(
+                    "state store full ({} entries), cannot create {}",
+                    count, key
+                )));
+            }
+        }
+        Ok(())
+    }
+
     /// Execute integrate function
     ///
     /// Calculates time integral: accumulated += value * dt
@@ -49,6 +72,7 @@ impl<S: StateStore> BuiltinFunctions<S> {
                 .map_err(|e| CalcError::state(format!("Failed to deserialize state: {}", e)))?
         } else {
             // First call - initialize with current time, no accumulation yet
+            self.check_new_state(&key).await?;
             let initial = IntegrateState {
                 last_ts: now,
                 accumulated: 0.0,
@@ -112,6 +136,7 @@ impl<S: StateStore> BuiltinFunctions<S> {
                 s
             }
         } else {
+            self.check_new_state(&key).await?;
             MovingAvgState::new(window)
         };
 
@@ -151,6 +176,7 @@ impl<S: StateStore> BuiltinFunctions<S> {
                 .map_err(|e| CalcError::state(format!("Failed to deserialize state: {}", e)))?
         } else {
             // First call - store current and return 0
+            self.check_new_state(&key).await?;
             let initial = RateOfChangeState {
                 last_ts: now,
                 last_value: value,
@@ -300,6 +326,21 @@ mod tests {
         assert_eq!(avg, 20.0); // (10+20+30)/3
     }
 
+    #[tokio::test]
+    async fn test_state_entries_are_bounded() {
+        let store = Arc::new(MemoryStateStore::new());
+        let funcs = BuiltinFunctions::new(store, "test").with_max_state_entries(2);
+
+        funcs.integrate("a", 1.0, 1.0).await.unwrap();
+        funcs.moving_avg("b", 1.0, 3).await.unwrap();
+        assert!(matches!(
+            funcs.rate_of_change("c", 1.0).await,
+            Err(CalcError::Limit(_))
+        ));
+        // Existing state keeps updating
+        assert_eq!(funcs.moving_avg("b", 3.0, 3).await.unwrap(), 2.0);
+    }
+
     #[tokio::test]
     async fn test_rate_of_change_basic() {
         let store = Arc::new(MemoryStateStore::new());
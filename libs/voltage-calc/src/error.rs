@@ -16,6 +16,9 @@ pub enum CalcError {
 
     #[error("Variable not found: {0}")]
     VariableNotFound(String),
+
+    #[error("Limit exceeded: {0}")]
+    Limit(String),
 }
 
 impl CalcError {
@@ -34,6 +37,10 @@ impl CalcError {
     pub fn variable_not_found(name: impl Into<String>) -> Self {
         Self::VariableNotFound(name.into())
     }
+
+    pub fn limit(msg: impl Into<String>) -> Self {
+        Self::Limit(msg.into())
+    }
 }
 
 pub type Result<T> = std::result::Result<T, CalcError>;
@@ -5,10 +5,13 @@
 //! - Comparison: <, >, <=, >=, ==, !=
 //! - Logic: &&, ||, !
 //! - Built-in functions: integrate, moving_avg, rate_of_change, scale, clamp, etc.
+//! - Three-phase functions: phase_to_line, power_factor, seq_negative, etc.
 
 use crate::builtin_functions::{self, BuiltinFunctions};
 use crate::error::{CalcError, Result};
+use crate::limits::CalcLimits;
 use crate::state::StateStore;
+use crate::three_phase::{self, SequenceComponents};
 use evalexpr::{ContextWithMutableFunctions, ContextWithMutableVariables, Value};
 use regex::Regex;
 use std::borrow::Cow;
@@ -52,6 +55,8 @@ static RE_RATE_OF_CHANGE: LazyLock<Regex> = LazyLock::new(|| {
 pub struct CalcEngine<S: StateStore> {
     /// Built-in function executor
     builtin: BuiltinFunctions<S>,
+    /// Evaluation limits
+    limits: CalcLimits,
 }
 
 impl<S: StateStore> CalcEngine<S> {
@@ -63,16 +68,43 @@ impl<S: StateStore> CalcEngine<S> {
     pub fn new(state_store: Arc<S>, context: impl Into<String>) -> Self {
         Self {
             builtin: BuiltinFunctions::new(state_store, context),
+            limits: CalcLimits::default(),
         }
     }
 
+    /// Replace the default evaluation limits
+    pub fn with_limits(mut self, limits: CalcLimits) -> Self {
+        self.builtin = self
+            .builtin
+            .with_max_state_entries(limits.max_state_entries);
+        self.limits = limits;
+        self
+    }
+
+    /// Evaluation limits in effect
+    pub fn limits(&self) -> &CalcLimits {
+        &self.limits
+    }
+
     /// Evaluate a simple expression (no stateful functions)
     ///
     /// For expressions without integrate/moving_avg/rate_of_change,
     /// this is faster as it doesn't require async.
     ///
     /// Supported stateless functions: scale, clamp, abs, min, max, round, sign
+    /// and the three-phase functions (see [`crate::three_phase`])
     pub fn evaluate_simple(&self, formula: &str, variables: &HashMap<String, f64>) -> Result<f64> {
+        let tree = self.limits.parse(formula)?;
+        self.evaluate_tree(&tree, formula, variables)
+    }
+
+    /// Evaluate a formula already parsed (and checked) by [`CalcLimits::parse`]
+    fn evaluate_tree(
+        &self,
+        tree: &evalexpr::Node,
+        formula: &str,
+        variables: &HashMap<String, f64>,
+    ) -> Result<f64> {
         let mut context = evalexpr::HashMapContext::new();
 
         // Add variables
@@ -88,11 +120,12 @@ impl<S: StateStore> CalcEngine<S> {
         Self::register_stateless_functions(&mut context)?;
 
         // Evaluate
-        let result = evalexpr::eval_with_context(formula, &context).map_err(|e| {
+        let result = tree.eval_with_context(&context).map_err(|e| {
             CalcError::expression(format!("Failed to evaluate '{}': {}", formula, e))
         })?;
 
-        Self::value_to_f64(result, formula)
+        let value = Self::value_to_f64(result, formula)?;
+        self.limits.finite(value, "result")
     }
 
     /// Evaluate an expression with full function support (async)
@@ -104,12 +137,19 @@ impl<S: StateStore> CalcEngine<S> {
     ///
     /// Note: Function parsing is done via preprocessing, not evalexpr native functions.
     /// This allows async execution of stateful functions.
+    ///
+    /// The formula is checked against the limits before any state is touched.
     pub async fn evaluate(&self, formula: &str, variables: &HashMap<String, f64>) -> Result<f64> {
+        let tree = self.limits.parse(formula)?;
+
         // Check for stateful function calls
         let processed_formula = self.process_stateful_functions(formula, variables).await?;
 
-        // Evaluate the processed formula
-        self.evaluate_simple(&processed_formula, variables)
+        // Evaluate the processed formula (re-parse only if functions were substituted)
+        match processed_formula {
+            Cow::Borrowed(_) => self.evaluate_tree(&tree, formula, variables),
+            Cow::Owned(processed) => self.evaluate_simple(&processed, variables),
+        }
     }
 
     /// Process stateful functions in formula and replace with computed values
@@ -173,6 +213,7 @@ impl<S: StateStore> CalcEngine<S> {
                 .get(&var_name)
                 .copied()
                 .ok_or_else(|| CalcError::variable_not_found(format!("integrate: {}", var_name)))?;
+            let value = self.limits.finite(value, &var_name)?;
 
             let integrated = self.builtin.integrate(&var_name, value, factor).await?;
             result.replace_range(range, &integrated.to_string());
@@ -214,6 +255,8 @@ impl<S: StateStore> CalcEngine<S> {
             let value = variables.get(&var_name).copied().ok_or_else(|| {
                 CalcError::variable_not_found(format!("moving_avg: {}", var_name))
             })?;
+            let value = self.limits.finite(value, &var_name)?;
+            self.limits.check_window(window)?;
 
             let avg = self.builtin.moving_avg(&var_name, value, window).await?;
             result.replace_range(range, &avg.to_string());
@@ -254,6 +297,7 @@ impl<S: StateStore> CalcEngine<S> {
             let value = variables.get(&var_name).copied().ok_or_else(|| {
                 CalcError::variable_not_found(format!("rate_of_change: {}", var_name))
             })?;
+            let value = self.limits.finite(value, &var_name)?;
 
             let rate = self.builtin.rate_of_change(&var_name, value).await?;
             result.replace_range(range, &rate.to_string());
@@ -365,6 +409,8 @@ impl<S: StateStore> CalcEngine<S> {
             )
             .map_err(|e| CalcError::expression(format!("Failed to register sign: {}", e)))?;
 
+        Self::register_three_phase_functions(context, to_f64)?;
+
         // if(condition, then, else) - conditional expression
         // Note: evalexpr already has "if" built-in, but adding explicit support
         // The syntax is: if(condition, then_value, else_value)
@@ -372,6 +418,108 @@ impl<S: StateStore> CalcEngine<S> {
         Ok(())
     }
 
+    /// Register three-phase functions with evalexpr context
+    ///
+    /// Sequence functions take `(mag_a, ang_a, mag_b, ang_b, mag_c, ang_c)`
+    /// with angles in degrees and return magnitudes (unbalance in percent).
+    fn register_three_phase_functions(
+        context: &mut evalexpr::HashMapContext,
+        to_f64: fn(&Value) -> std::result::Result<f64, evalexpr::EvalexprError>,
+    ) -> Result<()> {
+        use evalexpr::{EvalexprError, Function};
+
+        fn args<const N: usize>(
+            args: &Value,
+            to_f64: fn(&Value) -> std::result::Result<f64, EvalexprError>,
+        ) -> std::result::Result<[f64; N], EvalexprError> {
+            let tuple = args.as_fixed_len_tuple(N)?;
+            let mut values = [0.0; N];
+            for (value, arg) in values.iter_mut().zip(&tuple) {
+                *value = to_f64(arg)?;
+            }
+            Ok(values)
+        }
+
+        fn sequence(
+            values: &Value,
+            to_f64: fn(&Value) -> std::result::Result<f64, EvalexprError>,
+        ) -> std::result::Result<SequenceComponents, EvalexprError> {
+            let [ma, aa, mb, ab, mc, ac] = args::<6>(values, to_f64)?;
+            Ok(three_phase::sequence_components_polar_deg(
+                (ma, aa),
+                (mb, ab),
+                (mc, ac),
+            ))
+        }
+
+        let functions: [(&str, Function); 10] = [
+            (
+                "phase_to_line",
+                Function::new(move |a| Ok(Value::Float(three_phase::phase_to_line(to_f64(a)?)))),
+            ),
+            (
+                "line_to_phase",
+                Function::new(move |a| Ok(Value::Float(three_phase::line_to_phase(to_f64(a)?)))),
+            ),
+            (
+                "apparent_power",
+                Function::new(move |a| {
+                    let [p, q] = args::<2>(a, to_f64)?;
+                    Ok(Value::Float(three_phase::apparent_power(p, q)))
+                }),
+            ),
+            (
+                "power_factor",
+                Function::new(move |a| {
+                    let [p, q] = args::<2>(a, to_f64)?;
+                    Ok(Value::Float(three_phase::power_factor(p, q)))
+                }),
+            ),
+            (
+                "three_phase_power",
+                Function::new(move |a| {
+                    let [u, i, pf] = args::<3>(a, to_f64)?;
+                    Ok(Value::Float(three_phase::three_phase_power(u, i, pf)))
+                }),
+            ),
+            (
+                "max_deviation_pct",
+                Function::new(move |a| {
+                    let [x, y, z] = args::<3>(a, to_f64)?;
+                    Ok(Value::Float(three_phase::max_deviation_pct(x, y, z)))
+                }),
+            ),
+            (
+                "seq_positive",
+                Function::new(move |a| Ok(Value::Float(sequence(a, to_f64)?.positive.abs()))),
+            ),
+            (
+                "seq_negative",
+                Function::new(move |a| Ok(Value::Float(sequence(a, to_f64)?.negative.abs()))),
+            ),
+            (
+                "seq_zero",
+                Function::new(move |a| Ok(Value::Float(sequence(a, to_f64)?.zero.abs()))),
+            ),
+            (
+                "unbalance_pct",
+                Function::new(move |a| {
+                    Ok(Value::Float(sequence(a, to_f64)?.negative_unbalance_pct()))
+                }),
+            ),
+        ];
+
+        for (name, function) in functions {
+            context
+                .set_function(name.to_string(), function)
+                .map_err(|e| {
+                    CalcError::expression(format!("Failed to register {}: {}", name, e))
+                })?;
+        }
+
+        Ok(())
+    }
+
     /// Convert evalexpr Value to f64
     fn value_to_f64(value: Value, formula: &str) -> Result<f64> {
         match value {
@@ -512,6 +660,73 @@ mod tests {
         assert_eq!(result, 1000.0);
     }
 
+    #[test]
+    fn test_three_phase_functions() {
+        let engine = create_engine();
+        let mut vars = HashMap::new();
+        vars.insert("P".to_string(), 80.0);
+        vars.insert("Q".to_string(), 60.0);
+
+        let pf = engine.evaluate_simple("power_factor(P, Q)", &vars).unwrap();
+        assert!((pf - 0.8).abs() < 1e-9);
+        let u = engine
+            .evaluate_simple("line_to_phase(phase_to_line(230))", &vars)
+            .unwrap();
+        assert!((u - 230.0).abs() < 1e-9);
+
+        // Balanced set: no negative sequence
+        let unbalance = engine
+            .evaluate_simple("unbalance_pct(230, 0, 230, -120, 230, 120)", &vars)
+            .unwrap();
+        assert!(unbalance < 1e-9);
+        let v1 = engine
+            .evaluate_simple("seq_positive(230, 0, 230, -120, 230, 120)", &vars)
+            .unwrap();
+        assert!((v1 - 230.0).abs() < 1e-9);
+
+        assert!(engine
+            .evaluate_simple("seq_zero(230, 0, 230)", &vars)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_limits_are_enforced() {
+        use crate::limits::NonFinitePolicy;
+
+        let store = Arc::new(MemoryStateStore::new());
+        let engine = CalcEngine::new(Arc::clone(&store), "test").with_limits(CalcLimits {
+            max_depth: 6,
+            ..Default::default()
+        });
+        let mut vars = HashMap::new();
+        vars.insert("P".to_string(), f64::NAN);
+        vars.insert("Q".to_string(), 2.0);
+
+        let nested = format!("{}Q{}", "(".repeat(8), ")".repeat(8));
+        assert!(matches!(
+            engine.evaluate_simple(&nested, &vars),
+            Err(CalcError::Limit(_))
+        ));
+
+        // NaN never reaches the state store
+        assert!(matches!(
+            engine.evaluate("integrate(P)", &vars).await,
+            Err(CalcError::Limit(_))
+        ));
+        assert_eq!(store.entry_count().await.unwrap(), Some(0));
+        assert!(matches!(
+            engine.evaluate("moving_avg(Q, 0)", &vars).await,
+            Err(CalcError::Limit(_))
+        ));
+        assert!(engine.evaluate_simple("Q / 0.0", &vars).is_err());
+
+        let lenient = CalcEngine::new(store, "test").with_limits(CalcLimits {
+            non_finite: NonFinitePolicy::Zero,
+            ..Default::default()
+        });
+        assert_eq!(lenient.evaluate_simple("Q / 0.0", &vars).unwrap(), 0.0);
+    }
+
     #[tokio::test]
     async fn test_integrate_in_formula() {
         let store = Arc::new(MemoryStateStore::new());
@@ -7,6 +7,8 @@
 //! - **Expression evaluation**: Arithmetic, comparison, and logic operations
 //! - **Stateful functions**: `integrate()`, `moving_avg()`, `rate_of_change()`
 //! - **Stateless functions**: `scale()`, `clamp()`, `abs()`, `min()`, `max()`, `round()`, `sign()`
+//! - **Three-phase functions**: line/phase conversion, power factor, sequence components
+//! - **Sandbox limits**: formula size/depth, state entries and NaN/Inf policy ([`CalcLimits`])
 //!
 //! # Example
 //!
@@ -64,16 +66,37 @@
 //! | `max` | `max(a, b)` | Maximum of two |
 //! | `round` | `round(value, decimals)` | Round to decimals |
 //! | `sign` | `sign(value)` | Sign: -1, 0, or 1 |
+//!
+//! ## Three-phase (sync)
+//!
+//! Sequence functions take magnitude/angle pairs of phases A, B, C (angles in degrees).
+//!
+//! | Function | Signature | Description |
+//! |----------|-----------|-------------|
+//! | `phase_to_line` | `phase_to_line(u_ph)` | Balanced U_LL = √3 · U_ph |
+//! | `line_to_phase` | `line_to_phase(u_ll)` | Balanced U_ph = U_LL / √3 |
+//! | `apparent_power` | `apparent_power(p, q)` | S = √(P² + Q²) |
+//! | `power_factor` | `power_factor(p, q)` | P / S, signed like P |
+//! | `three_phase_power` | `three_phase_power(u_ll, i, pf)` | √3 · U_LL · I · pf |
+//! | `max_deviation_pct` | `max_deviation_pct(a, b, c)` | Magnitude unbalance (NEMA) in % |
+//! | `seq_positive` | `seq_positive(ma, aa, mb, ab, mc, ac)` | Positive-sequence magnitude |
+//! | `seq_negative` | `seq_negative(ma, aa, mb, ab, mc, ac)` | Negative-sequence magnitude |
+//! | `seq_zero` | `seq_zero(ma, aa, mb, ab, mc, ac)` | Zero-sequence magnitude |
+//! | `unbalance_pct` | `unbalance_pct(ma, aa, mb, ab, mc, ac)` | Negative/positive sequence ratio in % |
 
 pub mod builtin_functions;
 pub mod error;
 pub mod evaluator;
+pub mod limits;
 pub mod state;
+pub mod three_phase;
 
 // Re-exports for convenience
 pub use error::{CalcError, Result};
 pub use evaluator::CalcEngine;
+pub use limits::{CalcLimits, NonFinitePolicy};
 pub use state::{MemoryStateStore, NullStateStore, StateStore};
+pub use three_phase::{Complex, SequenceComponents};
 
 // Re-export stateless functions for direct use
 pub use builtin_functions::{abs, clamp, max, min, round, scale, sign};
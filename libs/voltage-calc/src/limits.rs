@@ -0,0 +1,176 @@
+//! Evaluation limits for user formulas
+//!
+//! Formulas come from rule and product definitions edited by users. A
+//! malformed or hostile formula must not stall the compute loop or grow state
+//! storage without bound, so [`crate::CalcEngine`] checks every formula
+//! against [`CalcLimits`] before evaluating it.
+
+use crate::error::{CalcError, Result};
+use evalexpr::Node;
+use serde::{Deserialize, Serialize};
+
+/// Handling of NaN / ±Inf in inputs of stateful functions and in results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NonFinitePolicy {
+    /// Fail the evaluation
+    #[default]
+    Reject,
+    /// Replace the value with 0
+    Zero,
+    /// Pass the value through unchanged
+    Allow,
+}
+
+/// Limits enforced by [`crate::CalcEngine`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CalcLimits {
+    /// Maximum formula length in bytes (checked before parsing)
+    pub max_formula_len: usize,
+    /// Maximum depth of the parsed expression tree
+    pub max_depth: usize,
+    /// Maximum number of expression nodes evaluated per formula
+    ///
+    /// evalexpr has no loops, so this bounds the evaluation work.
+    pub max_iterations: usize,
+    /// Maximum number of entries in the state store before new stateful
+    /// function state is refused (existing entries keep updating)
+    pub max_state_entries: usize,
+    /// Maximum `moving_avg` window size
+    pub max_window: usize,
+    /// NaN / Inf handling
+    pub non_finite: NonFinitePolicy,
+}
+
+impl Default for CalcLimits {
+    fn default() -> Self {
+        Self {
+            max_formula_len: 4096,
+            max_depth: 64,
+            max_iterations: 1000,
+            max_state_entries: 10_000,
+            max_window: 3600,
+            non_finite: NonFinitePolicy::Reject,
+        }
+    }
+}
+
+impl CalcLimits {
+    /// No limits (trusted formulas only)
+    pub fn unlimited() -> Self {
+        Self {
+            max_formula_len: usize::MAX,
+            max_depth: usize::MAX,
+            max_iterations: usize::MAX,
+            max_state_entries: usize::MAX,
+            max_window: usize::MAX,
+            non_finite: NonFinitePolicy::Allow,
+        }
+    }
+
+    /// Parse a formula and check its size against the limits
+    pub fn parse(&self, formula: &str) -> Result<Node> {
+        if formula.len() > self.max_formula_len {
+            return Err(CalcError::limit(format!(
+                "formula length {} exceeds {}",
+                formula.len(),
+                self.max_formula_len
+            )));
+        }
+        let tree = evalexpr::build_operator_tree(formula)
+            .map_err(|e| CalcError::expression(format!("Failed to parse '{}': {}", formula, e)))?;
+        self.check_tree(&tree)?;
+        Ok(tree)
+    }
+
+    /// Check depth and node count of a parsed formula
+    pub fn check_tree(&self, tree: &Node) -> Result<()> {
+        let mut nodes = 0usize;
+        let mut stack = vec![(tree, 1usize)];
+        while let Some((node, depth)) = stack.pop() {
+            if depth > self.max_depth {
+                return Err(CalcError::limit(format!(
+                    "expression depth exceeds {}",
+                    self.max_depth
+                )));
+            }
+            nodes += 1;
+            if nodes > self.max_iterations {
+                return Err(CalcError::limit(format!(
+                    "expression has more than {} nodes",
+                    self.max_iterations
+                )));
+            }
+            stack.extend(node.children().iter().map(|child| (child, depth + 1)));
+        }
+        Ok(())
+    }
+
+    /// Apply the non-finite policy to a value
+    pub fn finite(&self, value: f64, what: &str) -> Result<f64> {
+        if value.is_finite() {
+            return Ok(value);
+        }
+        match self.non_finite {
+            NonFinitePolicy::Reject => Err(CalcError::limit(format!(
+                "{} is not finite ({})",
+                what, value
+            ))),
+            NonFinitePolicy::Zero => Ok(0.0),
+            NonFinitePolicy::Allow => Ok(value),
+        }
+    }
+
+    /// Check a `moving_avg` window size
+    pub fn check_window(&self, window: usize) -> Result<()> {
+        if window == 0 || window > self.max_window {
+            return Err(CalcError::limit(format!(
+                "moving_avg window {} outside 1..={}",
+                window, self.max_window
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_limits() {
+        let limits = CalcLimits {
+            max_depth: 8,
+            max_iterations: 20,
+            ..Default::default()
+        };
+        assert!(limits.parse("a + b * (c - 1)").is_ok());
+        assert!(matches!(
+            limits.parse(&format!("{}1{}", "(".repeat(10), ")".repeat(10))),
+            Err(CalcError::Limit(_))
+        ));
+        assert!(matches!(
+            limits.parse(&vec!["1"; 30].join(" + ")),
+            Err(CalcError::Limit(_))
+        ));
+
+        let short = CalcLimits {
+            max_formula_len: 4,
+            ..Default::default()
+        };
+        assert!(matches!(short.parse("a + b"), Err(CalcError::Limit(_))));
+    }
+
+    #[test]
+    fn test_non_finite_policy() {
+        let mut limits = CalcLimits::default();
+        assert_eq!(limits.finite(1.5, "x").unwrap(), 1.5);
+        assert!(limits.finite(f64::NAN, "x").is_err());
+        limits.non_finite = NonFinitePolicy::Zero;
+        assert_eq!(limits.finite(f64::INFINITY, "x").unwrap(), 0.0);
+        limits.non_finite = NonFinitePolicy::Allow;
+        assert!(limits.finite(f64::NAN, "x").unwrap().is_nan());
+    }
+}
@@ -22,6 +22,13 @@ pub trait StateStore: Send + Sync {
 
     /// Delete state for a key
     fn delete(&self, key: &str) -> impl Future<Output = Result<()>> + Send;
+
+    /// Number of stored entries (`None` if the backend cannot count them cheaply)
+    ///
+    /// Used to enforce [`crate::CalcLimits::max_state_entries`].
+    fn entry_count(&self) -> impl Future<Output = Result<Option<usize>>> + Send {
+        async { Ok(None) }
+    }
 }
 
 /// In-memory state store for testing and simple use cases
@@ -63,6 +70,10 @@ impl StateStore for MemoryStateStore {
             Ok(())
         }
     }
+
+    async fn entry_count(&self) -> Result<Option<usize>> {
+        Ok(Some(self.data.read().await.len()))
+    }
 }
 
 /// Null state store - no persistence (stateful functions will fail)
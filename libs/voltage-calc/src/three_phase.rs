@@ -0,0 +1,291 @@
+//! Complex numbers and three-phase helpers
+//!
+//! Phasor arithmetic for power quality formulas: line/phase conversions,
+//! power factor from P/Q and symmetrical (sequence) components. Angles are
+//! in degrees at the expression level and in radians on [`Complex`].
+
+use std::f64::consts::FRAC_PI_3;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// √3, the ratio between line-to-line and line-to-neutral magnitudes
+pub const SQRT_3: f64 = 1.732_050_807_568_877_2;
+
+/// Complex number (phasor)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub const ZERO: Complex = Complex { re: 0.0, im: 0.0 };
+    pub const ONE: Complex = Complex { re: 1.0, im: 0.0 };
+
+    pub const fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    /// Phasor from magnitude and angle in radians
+    pub fn from_polar(magnitude: f64, angle: f64) -> Self {
+        Self::new(magnitude * angle.cos(), magnitude * angle.sin())
+    }
+
+    /// Phasor from magnitude and angle in degrees
+    pub fn from_polar_deg(magnitude: f64, angle_deg: f64) -> Self {
+        Self::from_polar(magnitude, angle_deg.to_radians())
+    }
+
+    /// Magnitude |z|
+    pub fn abs(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    /// Angle in radians (-π, π]
+    pub fn arg(self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    /// Angle in degrees (-180, 180]
+    pub fn arg_deg(self) -> f64 {
+        self.arg().to_degrees()
+    }
+
+    pub fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    /// Multiply by a real factor
+    pub fn scale(self, factor: f64) -> Self {
+        Self::new(self.re * factor, self.im * factor)
+    }
+
+    /// Rotate by an angle in radians
+    pub fn rotate(self, angle: f64) -> Self {
+        self * Self::from_polar(1.0, angle)
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, rhs: Complex) -> Complex {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, rhs: Complex) -> Complex {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, rhs: Complex) -> Complex {
+        Complex::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+    /// Division by zero yields NaN components
+    fn div(self, rhs: Complex) -> Complex {
+        let denom = rhs.re * rhs.re + rhs.im * rhs.im;
+        Complex::new(
+            (self.re * rhs.re + self.im * rhs.im) / denom,
+            (self.im * rhs.re - self.re * rhs.im) / denom,
+        )
+    }
+}
+
+impl Neg for Complex {
+    type Output = Complex;
+    fn neg(self) -> Complex {
+        Complex::new(-self.re, -self.im)
+    }
+}
+
+/// Symmetrical components of a three-phase set (phase A reference)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SequenceComponents {
+    pub zero: Complex,
+    pub positive: Complex,
+    pub negative: Complex,
+}
+
+impl SequenceComponents {
+    /// Negative-sequence unbalance |X2| / |X1| in percent (0 without positive sequence)
+    pub fn negative_unbalance_pct(&self) -> f64 {
+        unbalance_ratio(self.negative.abs(), self.positive.abs())
+    }
+
+    /// Zero-sequence unbalance |X0| / |X1| in percent (0 without positive sequence)
+    pub fn zero_unbalance_pct(&self) -> f64 {
+        unbalance_ratio(self.zero.abs(), self.positive.abs())
+    }
+}
+
+fn unbalance_ratio(part: f64, positive: f64) -> f64 {
+    if positive > 0.0 {
+        part / positive * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// Fortescue decomposition of phasors A, B, C
+///
+/// X0 = (A + B + C) / 3, X1 = (A + aB + a²C) / 3, X2 = (A + a²B + aC) / 3
+/// with a = 1∠120°.
+pub fn sequence_components(a: Complex, b: Complex, c: Complex) -> SequenceComponents {
+    let op = Complex::from_polar(1.0, 2.0 * FRAC_PI_3);
+    let op2 = op * op;
+    SequenceComponents {
+        zero: (a + b + c).scale(1.0 / 3.0),
+        positive: (a + op * b + op2 * c).scale(1.0 / 3.0),
+        negative: (a + op2 * b + op * c).scale(1.0 / 3.0),
+    }
+}
+
+/// Sequence components from (magnitude, angle in degrees) pairs of phases A, B, C
+pub fn sequence_components_polar_deg(
+    a: (f64, f64),
+    b: (f64, f64),
+    c: (f64, f64),
+) -> SequenceComponents {
+    sequence_components(
+        Complex::from_polar_deg(a.0, a.1),
+        Complex::from_polar_deg(b.0, b.1),
+        Complex::from_polar_deg(c.0, c.1),
+    )
+}
+
+/// Line-to-line phasors (AB, BC, CA) from line-to-neutral phasors
+pub fn line_voltages(a: Complex, b: Complex, c: Complex) -> [Complex; 3] {
+    [a - b, b - c, c - a]
+}
+
+/// Line-to-line magnitude of a balanced system from its phase magnitude
+pub fn phase_to_line(v_phase: f64) -> f64 {
+    v_phase * SQRT_3
+}
+
+/// Phase (line-to-neutral) magnitude of a balanced system from its line magnitude
+pub fn line_to_phase(v_line: f64) -> f64 {
+    v_line / SQRT_3
+}
+
+/// Apparent power S = √(P² + Q²)
+pub fn apparent_power(p: f64, q: f64) -> f64 {
+    p.hypot(q)
+}
+
+/// Power factor P / S, signed like P (1 when S is zero)
+pub fn power_factor(p: f64, q: f64) -> f64 {
+    let s = apparent_power(p, q);
+    if s > 0.0 {
+        p / s
+    } else {
+        1.0
+    }
+}
+
+/// Total active power of a balanced system: √3 · U_LL · I · cos φ
+pub fn three_phase_power(v_line: f64, current: f64, pf: f64) -> f64 {
+    SQRT_3 * v_line * current * pf
+}
+
+/// Magnitude unbalance (NEMA): max deviation from the average in percent of the average
+pub fn max_deviation_pct(a: f64, b: f64, c: f64) -> f64 {
+    let avg = (a + b + c) / 3.0;
+    if avg == 0.0 {
+        return 0.0;
+    }
+    let dev = (a - avg).abs().max((b - avg).abs()).max((c - avg).abs());
+    dev / avg.abs() * 100.0
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    fn balanced(magnitude: f64) -> (Complex, Complex, Complex) {
+        (
+            Complex::from_polar_deg(magnitude, 0.0),
+            Complex::from_polar_deg(magnitude, -120.0),
+            Complex::from_polar_deg(magnitude, 120.0),
+        )
+    }
+
+    #[test]
+    fn test_complex_arithmetic() {
+        let z = Complex::new(3.0, 4.0);
+        assert_eq!(z.abs(), 5.0);
+        assert_eq!(z * z.conj(), Complex::new(25.0, 0.0));
+        let q = z / Complex::new(0.0, 1.0);
+        assert!(close(q.re, 4.0) && close(q.im, -3.0));
+        assert!(close(Complex::ONE.rotate(FRAC_PI_3 * 1.5).arg_deg(), 90.0));
+    }
+
+    #[test]
+    fn test_line_phase_conversion() {
+        assert!(close(phase_to_line(230.0), 398.371_685_740_841_7));
+        assert!(close(line_to_phase(phase_to_line(230.0)), 230.0));
+
+        let (a, b, c) = balanced(230.0);
+        let [ab, _, _] = line_voltages(a, b, c);
+        assert!(close(ab.abs(), phase_to_line(230.0)));
+        assert!(close(ab.arg_deg(), 30.0));
+    }
+
+    #[test]
+    fn test_power_factor() {
+        assert!(close(power_factor(80.0, 60.0), 0.8));
+        assert!(close(power_factor(-80.0, 60.0), -0.8));
+        assert_eq!(power_factor(0.0, 0.0), 1.0);
+        assert!(close(apparent_power(80.0, -60.0), 100.0));
+        assert!(close(three_phase_power(400.0, 10.0, 1.0), 4_000.0 * SQRT_3));
+    }
+
+    #[test]
+    fn test_sequence_components() {
+        let (a, b, c) = balanced(100.0);
+        let seq = sequence_components(a, b, c);
+        assert!(close(seq.positive.abs(), 100.0));
+        assert!(seq.negative.abs() < 1e-9);
+        assert!(seq.zero.abs() < 1e-9);
+
+        // Swapped B and C: pure negative sequence
+        let seq = sequence_components(a, c, b);
+        assert!(close(seq.negative.abs(), 100.0));
+        assert!(seq.positive.abs() < 1e-9);
+
+        // In-phase set: pure zero sequence
+        let seq = sequence_components(a, a, a);
+        assert!(close(seq.zero.abs(), 100.0));
+
+        // 10% negative sequence on top of a balanced set
+        let (n_a, n_c, n_b) = balanced(10.0);
+        let seq = sequence_components(a + n_a, b + n_b, c + n_c);
+        assert!(close(seq.negative_unbalance_pct(), 10.0));
+        assert!(seq.zero_unbalance_pct() < 1e-9);
+    }
+
+    #[test]
+    fn test_max_deviation() {
+        assert!(close(max_deviation_pct(230.0, 230.0, 230.0), 0.0));
+        assert!(close(
+            max_deviation_pct(220.0, 230.0, 240.0),
+            10.0 / 230.0 * 100.0
+        ));
+        assert_eq!(max_deviation_pct(0.0, 0.0, 0.0), 0.0);
+    }
+}
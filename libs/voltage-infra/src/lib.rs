@@ -3,6 +3,8 @@
 //! This library provides database infrastructure for VoltageEMS:
 //! - Redis client with connection pooling
 //! - SQLite client with optimized settings
+//! - Versioned SQLite schema migrations
+//! - Read-only SQLite access with change notification for UI tools
 //!
 //! # Features
 //!
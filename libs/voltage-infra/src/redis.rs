@@ -10,6 +10,12 @@ use redis::AsyncCommands;
 use std::sync::Arc;
 use std::time::Duration;
 
+/// Stream entry as read by XRANGE: (entry ID, fields)
+pub type StreamEntry = (String, Vec<(String, Bytes)>);
+
+/// XRANGE reply before values are converted to Bytes
+type RawStreamEntry = (String, Vec<(String, Vec<u8>)>);
+
 // Re-export commonly used types from redis crate
 pub use redis::Msg;
 
@@ -53,6 +59,24 @@ impl RedisPoolConfig {
     }
 }
 
+/// SET the lease when it is free or already ours (KEYS[1], owner, ttl ms)
+const LEASE_ACQUIRE_SCRIPT: &str = r#"
+local holder = redis.call('GET', KEYS[1])
+if holder == false or holder == ARGV[1] then
+    redis.call('SET', KEYS[1], ARGV[1], 'PX', ARGV[2])
+    return 1
+end
+return 0
+"#;
+
+/// DEL the lease only when it is ours (KEYS[1], owner)
+const LEASE_RELEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
 /// Redis asynchronous client with connection pooling
 pub struct RedisClient {
     pool: Arc<Pool<RedisConnectionManager>>,
@@ -428,6 +452,16 @@ impl RedisClient {
             .saturating_add(microseconds / 1000))
     }
 
+    /// Return the `INFO` text of one section (e.g. "memory")
+    pub async fn info(&self, section: &str) -> Result<String> {
+        let mut conn = self.get_connection().await?;
+        redis::cmd("INFO")
+            .arg(section)
+            .query_async(&mut *conn)
+            .await
+            .with_context(|| format!("Failed to fetch Redis INFO {}", section))
+    }
+
     /// Hash operation - batch set multiple fields (alias for hmset compatibility)
     pub async fn hmset(&self, key: &str, fields: &[(String, String)]) -> Result<()> {
         if fields.is_empty() {
@@ -445,6 +479,23 @@ impl RedisClient {
             .with_context(|| format!("Failed to HMSET in key: {}", key))
     }
 
+    /// HSET of multiple fields accepting Bytes values directly
+    pub async fn hmset_bytes(&self, key: &str, fields: &[(String, Bytes)]) -> Result<()> {
+        if fields.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = self.get_connection().await?;
+        let mut cmd = redis::cmd("HSET");
+        cmd.arg(key);
+        for (field, value) in fields {
+            cmd.arg(field).arg(value.as_ref());
+        }
+        cmd.query_async(&mut *conn)
+            .await
+            .with_context(|| format!("Failed to HMSET in key: {}", key))
+    }
+
     /// Hash operation - get multiple fields
     pub async fn hmget<T: redis::FromRedisValue>(
         &self,
@@ -533,6 +584,39 @@ impl RedisClient {
             .with_context(|| format!("Failed to FCALL function: {}", function))
     }
 
+    /// Take or renew a lease
+    ///
+    /// Sets `key` to `owner` with a `ttl_ms` expiry when the key is absent or
+    /// already holds `owner`, atomically. Returns whether `owner` holds the
+    /// lease afterwards.
+    pub async fn lease_acquire(&self, key: &str, owner: &str, ttl_ms: u64) -> Result<bool> {
+        let mut conn = self.get_connection().await?;
+        let held: i32 = redis::cmd("EVAL")
+            .arg(LEASE_ACQUIRE_SCRIPT)
+            .arg(1)
+            .arg(key)
+            .arg(owner)
+            .arg(ttl_ms.max(1))
+            .query_async(&mut *conn)
+            .await
+            .with_context(|| format!("Failed to acquire lease: {}", key))?;
+        Ok(held == 1)
+    }
+
+    /// Delete a lease if `owner` holds it; returns whether it was deleted
+    pub async fn lease_release(&self, key: &str, owner: &str) -> Result<bool> {
+        let mut conn = self.get_connection().await?;
+        let deleted: i32 = redis::cmd("EVAL")
+            .arg(LEASE_RELEASE_SCRIPT)
+            .arg(1)
+            .arg(key)
+            .arg(owner)
+            .query_async(&mut *conn)
+            .await
+            .with_context(|| format!("Failed to release lease: {}", key))?;
+        Ok(deleted == 1)
+    }
+
     /// Check if key exists
     pub async fn exists(&self, key: &str) -> Result<bool> {
         let mut conn = self.get_connection().await?;
@@ -544,6 +628,16 @@ impl RedisClient {
         Ok(result > 0)
     }
 
+    /// Type of a key (Redis TYPE): "string", "hash", "list", "set", ... or "none"
+    pub async fn key_type(&self, key: &str) -> Result<String> {
+        let mut conn = self.get_connection().await?;
+        redis::cmd("TYPE")
+            .arg(key)
+            .query_async(&mut *conn)
+            .await
+            .with_context(|| format!("Failed to TYPE key: {}", key))
+    }
+
     /// Pop value from left of list
     pub async fn lpop<T: redis::FromRedisValue>(&self, key: &str) -> Result<Option<T>> {
         let mut conn = self.get_connection().await?;
@@ -662,6 +756,107 @@ impl RedisClient {
         Ok(())
     }
 
+    /// Pipeline HSET accepting Bytes values directly (binary-safe)
+    pub async fn pipeline_hmset_bytes(
+        &self,
+        operations: &[(String, Vec<(String, Bytes)>)],
+    ) -> Result<()> {
+        if operations.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = self.get_connection().await?;
+        let mut pipe = redis::pipe();
+
+        for (key, fields) in operations {
+            if !fields.is_empty() {
+                let mut cmd = redis::cmd("HSET");
+                cmd.arg(key.as_str());
+                for (field, value) in fields {
+                    cmd.arg(field.as_str()).arg(value.as_ref());
+                }
+                pipe.add_command(cmd);
+            }
+        }
+
+        pipe.query_async::<()>(&mut *conn)
+            .await
+            .with_context(|| "Failed to execute pipeline HMSET")?;
+
+        Ok(())
+    }
+
+    /// Stream operation - append entries and trim in a single pipeline
+    ///
+    /// Each entry is added with `XADD key MAXLEN ~ max_len *`; the pipeline
+    /// ends with `XTRIM key MINID ~ min_id` to drop entries older than the
+    /// retention age. Returns the IDs assigned to the entries.
+    pub async fn xadd_trimmed(
+        &self,
+        key: &str,
+        entries: &[Vec<(String, Bytes)>],
+        max_len: usize,
+        min_id: &str,
+    ) -> Result<Vec<String>> {
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut conn = self.get_connection().await?;
+        let mut pipe = redis::pipe();
+
+        for fields in entries {
+            let mut cmd = redis::cmd("XADD");
+            cmd.arg(key).arg("MAXLEN").arg("~").arg(max_len).arg("*");
+            for (field, value) in fields {
+                cmd.arg(field.as_str()).arg(value.as_ref());
+            }
+            pipe.add_command(cmd);
+        }
+        pipe.cmd("XTRIM")
+            .arg(key)
+            .arg("MINID")
+            .arg("~")
+            .arg(min_id)
+            .ignore();
+
+        pipe.query_async(&mut *conn)
+            .await
+            .with_context(|| format!("Failed to XADD to stream: {}", key))
+    }
+
+    /// Stream operation - read entries after an ID (exclusive), oldest first
+    ///
+    /// `after = None` reads from the start of the stream.
+    pub async fn xrange_after(
+        &self,
+        key: &str,
+        after: Option<&str>,
+        count: usize,
+    ) -> Result<Vec<StreamEntry>> {
+        let mut conn = self.get_connection().await?;
+        let start = after.map_or_else(|| "-".to_string(), |id| format!("({}", id));
+        let entries: Vec<RawStreamEntry> = redis::cmd("XRANGE")
+            .arg(key)
+            .arg(start)
+            .arg("+")
+            .arg("COUNT")
+            .arg(count)
+            .query_async(&mut *conn)
+            .await
+            .with_context(|| format!("Failed to XRANGE stream: {}", key))?;
+        Ok(entries
+            .into_iter()
+            .map(|(id, fields)| {
+                let fields = fields
+                    .into_iter()
+                    .map(|(field, value)| (field, Bytes::from(value)))
+                    .collect();
+                (id, fields)
+            })
+            .collect())
+    }
+
     /// Get pool statistics
     pub fn pool_state(&self) -> bb8::State {
         self.pool.state()
@@ -3,7 +3,15 @@
 //! Provides SQLite client with optimized settings for edge deployment.
 
 pub mod client;
+pub mod keyset;
+pub mod migrations;
+pub mod readonly;
 pub mod service_config;
 
 pub use client::{SqliteClient, SqlitePool};
+pub use keyset::{KeyOrder, Keyset};
+pub use migrations::{Migration, MigrationReport, Migrator};
+pub use readonly::{
+    DatabaseChange, DatabaseWatcher, ReadOnlyDatabase, ReadOnlyOptions, ReadSnapshot,
+};
 pub use service_config::{migrate_yaml_to_db, ServiceConfig, ServiceConfigLoader};
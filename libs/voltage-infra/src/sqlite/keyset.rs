@@ -0,0 +1,202 @@
+//! Keyset pagination for SQLite queries
+//!
+//! `LIMIT ? OFFSET ?` makes SQLite walk and discard every skipped row, so
+//! deep pages of large lists get slower the further a client scrolls. Keyset
+//! pagination instead continues after the sort key of the last row returned:
+//!
+//! ```ignore
+//! let keyset = Keyset::new().desc("priority").asc("id");
+//! let sql = format!(
+//!     "SELECT * FROM rules WHERE {} ORDER BY {} LIMIT ?",
+//!     keyset.condition(after.is_some()),
+//!     keyset.order_by()
+//! );
+//! let rows = keyset.bind_after(sqlx::query(&sql), after)?.bind(limit).fetch_all(pool).await?;
+//! ```
+//!
+//! The key must be unique (end it with the primary key) so no row is skipped
+//! or repeated. Key values travel as JSON values so they can be carried in an
+//! opaque cursor between requests.
+
+use anyhow::{bail, Result};
+use serde_json::Value;
+use sqlx::sqlite::SqliteArguments;
+use sqlx::Sqlite;
+
+type SqliteQuery<'q> = sqlx::query::Query<'q, Sqlite, SqliteArguments<'q>>;
+
+/// Sort direction of a key column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyOrder {
+    Asc,
+    Desc,
+}
+
+/// Ordered key columns of a paginated query
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Keyset {
+    columns: Vec<(String, KeyOrder)>,
+}
+
+impl Keyset {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn asc(mut self, column: impl Into<String>) -> Self {
+        self.columns.push((column.into(), KeyOrder::Asc));
+        self
+    }
+
+    pub fn desc(mut self, column: impl Into<String>) -> Self {
+        self.columns.push((column.into(), KeyOrder::Desc));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// `ORDER BY` list, e.g. `priority DESC, id ASC`
+    pub fn order_by(&self) -> String {
+        self.columns
+            .iter()
+            .map(|(column, order)| match order {
+                KeyOrder::Asc => format!("{} ASC", column),
+                KeyOrder::Desc => format!("{} DESC", column),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// `WHERE` condition selecting the rows after a key (`1=1` for the first page)
+    ///
+    /// Expanded as `(a > ?) OR (a = ? AND b > ?) ...` so columns may mix
+    /// directions; bind the key with [`Keyset::bind_after`].
+    pub fn condition(&self, has_after: bool) -> String {
+        if !has_after || self.columns.is_empty() {
+            return "1=1".to_string();
+        }
+        let terms: Vec<String> = (0..self.columns.len())
+            .map(|i| {
+                let mut parts: Vec<String> = self.columns[..i]
+                    .iter()
+                    .map(|(column, _)| format!("{} = ?", column))
+                    .collect();
+                let (column, order) = &self.columns[i];
+                let op = match order {
+                    KeyOrder::Asc => ">",
+                    KeyOrder::Desc => "<",
+                };
+                parts.push(format!("{} {} ?", column, op));
+                format!("({})", parts.join(" AND "))
+            })
+            .collect();
+        format!("({})", terms.join(" OR "))
+    }
+
+    /// Bind the key placeholders of [`Keyset::condition`]
+    pub fn bind_after<'q>(
+        &self,
+        mut query: SqliteQuery<'q>,
+        after: Option<&[Value]>,
+    ) -> Result<SqliteQuery<'q>> {
+        let Some(after) = after else {
+            return Ok(query);
+        };
+        if after.len() != self.columns.len() {
+            bail!(
+                "Cursor has {} key values, expected {}",
+                after.len(),
+                self.columns.len()
+            );
+        }
+        for i in 0..after.len() {
+            for value in &after[..=i] {
+                query = bind_value(query, value)?;
+            }
+        }
+        Ok(query)
+    }
+}
+
+fn bind_value<'q>(query: SqliteQuery<'q>, value: &Value) -> Result<SqliteQuery<'q>> {
+    Ok(match value {
+        Value::Null => query.bind(None::<i64>),
+        Value::Bool(b) => query.bind(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => query.bind(i),
+            None => query.bind(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => query.bind(s.clone()),
+        other => bail!("Unsupported cursor key value: {}", other),
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use sqlx::Row;
+
+    #[test]
+    fn test_condition_and_order() {
+        let keyset = Keyset::new().desc("priority").asc("id");
+        assert_eq!(keyset.order_by(), "priority DESC, id ASC");
+        assert_eq!(keyset.condition(false), "1=1");
+        assert_eq!(
+            keyset.condition(true),
+            "((priority < ?) OR (priority = ? AND id > ?))"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pages_cover_all_rows_once() {
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::query("CREATE TABLE t (id INTEGER PRIMARY KEY, priority INTEGER)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        for (id, priority) in [(1, 5), (2, 9), (3, 5), (4, 1), (5, 9)] {
+            sqlx::query("INSERT INTO t VALUES (?, ?)")
+                .bind(id)
+                .bind(priority)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        let keyset = Keyset::new().desc("priority").asc("id");
+        let mut after: Option<Vec<Value>> = None;
+        let mut seen = Vec::new();
+        loop {
+            let sql = format!(
+                "SELECT id, priority FROM t WHERE {} ORDER BY {} LIMIT 2",
+                keyset.condition(after.is_some()),
+                keyset.order_by()
+            );
+            let rows = keyset
+                .bind_after(sqlx::query(&sql), after.as_deref())
+                .unwrap()
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+            let Some(last) = rows.last() else { break };
+            after = Some(vec![
+                json!(last.get::<i64, _>("priority")),
+                json!(last.get::<i64, _>("id")),
+            ]);
+            seen.extend(rows.iter().map(|row| row.get::<i64, _>("id")));
+        }
+        assert_eq!(seen, vec![2, 5, 1, 3, 4]);
+
+        assert!(keyset
+            .bind_after(sqlx::query("SELECT 1"), Some(&[json!(1)]))
+            .is_err());
+    }
+}
@@ -0,0 +1,506 @@
+//! Versioned schema migrations
+//!
+//! Each component (comsrv, modsrv, rules) owns an ordered list of migrations
+//! for its tables in the shared `voltage.db`. Applied versions are recorded in
+//! `schema_migrations` together with a checksum of the migration body, so an
+//! upgraded binary only applies what is missing and existing data (including
+//! history tables) is kept.
+//!
+//! A migration that was already applied must never be edited: the checksum
+//! check refuses to start on a database whose recorded history differs from
+//! the binary, or that was migrated by a newer binary. Migration DDL is
+//! therefore written out in full, not generated from record structs that
+//! keep changing.
+//!
+//! ```ignore
+//! const ADD_NOTE: &str = "ALTER TABLE foo ADD COLUMN note TEXT";
+//!
+//! static MIGRATIONS: &[Migration] = &[
+//!     Migration::sql(1, "create_foo", "CREATE TABLE IF NOT EXISTS foo (id INTEGER)"),
+//!     Migration::rust(2, "foo_note", &[ADD_NOTE], add_note_if_missing),
+//! ];
+//!
+//! Migrator::new("example", MIGRATIONS).run(&pool).await?;
+//! ```
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Instant;
+use tracing::{debug, info};
+
+/// Version tracking table shared by all components
+pub const SCHEMA_MIGRATIONS_TABLE: &str = r#"
+    CREATE TABLE IF NOT EXISTS schema_migrations (
+        component TEXT NOT NULL,
+        version INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        checksum TEXT NOT NULL,
+        execution_ms INTEGER NOT NULL DEFAULT 0,
+        applied_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (component, version)
+    )
+"#;
+
+/// Future returned by a Rust migration
+pub type MigrationFuture<'c> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'c>>;
+
+/// Rust migration; runs inside the migration transaction
+pub type MigrationFn = for<'c> fn(&'c mut SqliteConnection) -> MigrationFuture<'c>;
+
+/// Migration body
+#[derive(Clone, Copy)]
+pub enum MigrationKind {
+    /// One or more SQL statements
+    Sql(&'static str),
+    /// Code migration for changes SQL alone cannot express (conditional
+    /// column adds, data rewrites), with the statements it may execute
+    Rust {
+        ddl: &'static [&'static str],
+        run: MigrationFn,
+    },
+}
+
+/// A single schema migration
+#[derive(Clone, Copy)]
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub kind: MigrationKind,
+}
+
+impl Migration {
+    pub const fn sql(version: i64, name: &'static str, sql: &'static str) -> Self {
+        Self {
+            version,
+            name,
+            kind: MigrationKind::Sql(sql),
+        }
+    }
+
+    /// Rust migration; `ddl` lists every statement `run` may execute
+    ///
+    /// The checksum covers `ddl`, so `run` should execute these constants
+    /// rather than inline SQL.
+    pub const fn rust(
+        version: i64,
+        name: &'static str,
+        ddl: &'static [&'static str],
+        run: MigrationFn,
+    ) -> Self {
+        Self {
+            version,
+            name,
+            kind: MigrationKind::Rust { ddl, run },
+        }
+    }
+
+    /// Checksum recorded when the migration is applied
+    ///
+    /// SQL migrations hash their statements, Rust migrations the statements
+    /// they declare.
+    pub fn checksum(&self) -> String {
+        let body = match self.kind {
+            MigrationKind::Sql(sql) => fnv1a(sql.trim().as_bytes()),
+            MigrationKind::Rust { ddl, .. } => fnv1a(
+                ddl.iter()
+                    .map(|statement| statement.trim())
+                    .collect::<Vec<_>>()
+                    .join(";\n")
+                    .as_bytes(),
+            ),
+        };
+        format!("{:016x}", body)
+    }
+
+    /// Checksum older binaries recorded for Rust migrations (name only)
+    ///
+    /// Accepted for any kind, so a Rust migration can be rewritten as SQL.
+    fn legacy_checksum(&self) -> String {
+        format!("{:016x}", fnv1a(format!("rust:{}", self.name).as_bytes()))
+    }
+
+    async fn apply(&self, conn: &mut SqliteConnection) -> Result<()> {
+        match self.kind {
+            MigrationKind::Sql(sql) => {
+                sqlx::raw_sql(sql).execute(conn).await?;
+            },
+            MigrationKind::Rust { run, .. } => run(conn).await?,
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for Migration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Migration")
+            .field("version", &self.version)
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// 64-bit FNV-1a (stable across builds and platforms)
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// A migration recorded in `schema_migrations`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub name: String,
+    pub checksum: String,
+    pub applied_at: String,
+}
+
+/// Status of one known migration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub name: &'static str,
+    pub applied: bool,
+}
+
+/// Result of a migration run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationReport {
+    pub component: &'static str,
+    /// Versions applied by this run, in order
+    pub applied: Vec<i64>,
+    /// Schema version after the run (0 = nothing applied yet)
+    pub current_version: i64,
+}
+
+/// Applies a component's migrations to a database
+#[derive(Debug, Clone, Copy)]
+pub struct Migrator {
+    component: &'static str,
+    migrations: &'static [Migration],
+}
+
+impl Migrator {
+    pub const fn new(component: &'static str, migrations: &'static [Migration]) -> Self {
+        Self {
+            component,
+            migrations,
+        }
+    }
+
+    pub fn component(&self) -> &'static str {
+        self.component
+    }
+
+    /// Latest version known to this binary
+    pub fn latest_version(&self) -> i64 {
+        self.migrations.last().map_or(0, |m| m.version)
+    }
+
+    /// Verify the recorded history and apply pending migrations in order
+    ///
+    /// Each migration runs in its own transaction together with its
+    /// `schema_migrations` row, so a failure leaves the previous version intact.
+    pub async fn run(&self, pool: &SqlitePool) -> Result<MigrationReport> {
+        self.validate()?;
+        sqlx::raw_sql(SCHEMA_MIGRATIONS_TABLE).execute(pool).await?;
+
+        let applied = self.applied(pool).await?;
+        for migration in self.verify(&applied)? {
+            // Recorded before Rust migration checksums covered their DDL
+            sqlx::query(
+                "UPDATE schema_migrations SET checksum = ? WHERE component = ? AND version = ?",
+            )
+            .bind(migration.checksum())
+            .bind(self.component)
+            .bind(migration.version)
+            .execute(pool)
+            .await?;
+        }
+
+        let current = applied.last().map_or(0, |m| m.version);
+        let mut report = MigrationReport {
+            component: self.component,
+            applied: Vec::new(),
+            current_version: current,
+        };
+
+        for migration in self.migrations.iter().filter(|m| m.version > current) {
+            let started = Instant::now();
+            let mut tx = pool.begin().await?;
+            migration.apply(&mut tx).await.with_context(|| {
+                format!(
+                    "{} migration {} ({}) failed",
+                    self.component, migration.version, migration.name
+                )
+            })?;
+            sqlx::query(
+                "INSERT INTO schema_migrations (component, version, name, checksum, execution_ms)
+                 VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(self.component)
+            .bind(migration.version)
+            .bind(migration.name)
+            .bind(migration.checksum())
+            .bind(started.elapsed().as_millis() as i64)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+
+            info!(
+                "Migrated {} to v{} ({})",
+                self.component, migration.version, migration.name
+            );
+            report.applied.push(migration.version);
+            report.current_version = migration.version;
+        }
+
+        if report.applied.is_empty() {
+            debug!("{} schema up to date (v{})", self.component, current);
+        }
+        Ok(report)
+    }
+
+    /// Migrations recorded for this component, ordered by version
+    pub async fn applied(&self, pool: &SqlitePool) -> Result<Vec<AppliedMigration>> {
+        let table: Option<i64> = sqlx::query_scalar(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_migrations'",
+        )
+        .fetch_optional(pool)
+        .await?;
+        if table.is_none() {
+            return Ok(Vec::new());
+        }
+
+        let rows = sqlx::query(
+            "SELECT version, name, checksum, CAST(applied_at AS TEXT) AS applied_at
+             FROM schema_migrations WHERE component = ? ORDER BY version",
+        )
+        .bind(self.component)
+        .fetch_all(pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(AppliedMigration {
+                    version: row.try_get("version")?,
+                    name: row.try_get("name")?,
+                    checksum: row.try_get("checksum")?,
+                    applied_at: row
+                        .try_get::<Option<String>, _>("applied_at")?
+                        .unwrap_or_default(),
+                })
+            })
+            .collect()
+    }
+
+    /// Applied/pending state of every migration known to this binary
+    pub async fn status(&self, pool: &SqlitePool) -> Result<Vec<MigrationStatus>> {
+        let applied = self.applied(pool).await?;
+        Ok(self
+            .migrations
+            .iter()
+            .map(|m| MigrationStatus {
+                version: m.version,
+                name: m.name,
+                applied: applied.iter().any(|a| a.version == m.version),
+            })
+            .collect())
+    }
+
+    fn validate(&self) -> Result<()> {
+        for pair in self.migrations.windows(2) {
+            if pair[1].version <= pair[0].version {
+                bail!(
+                    "{} migrations out of order: v{} follows v{}",
+                    self.component,
+                    pair[1].version,
+                    pair[0].version
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the recorded history; returns the migrations whose rows still
+    /// carry a legacy checksum
+    fn verify(&self, applied: &[AppliedMigration]) -> Result<Vec<&'static Migration>> {
+        let mut legacy = Vec::new();
+        for record in applied {
+            let Some(migration) = self.migrations.iter().find(|m| m.version == record.version)
+            else {
+                bail!(
+                    "{} schema is at v{} ({}), which this binary does not know; \
+                     it was migrated by a newer version",
+                    self.component,
+                    record.version,
+                    record.name
+                );
+            };
+            if migration.legacy_checksum() == record.checksum {
+                legacy.push(migration);
+                continue;
+            }
+            if migration.checksum() != record.checksum {
+                bail!(
+                    "{} migration v{} ({}) was modified after it was applied \
+                     (checksum {} != {})",
+                    self.component,
+                    record.version,
+                    record.name,
+                    migration.checksum(),
+                    record.checksum
+                );
+            }
+        }
+        Ok(legacy)
+    }
+}
+
+/// Whether `table` has a column named `column`
+pub async fn has_column(conn: &mut SqliteConnection, table: &str, column: &str) -> Result<bool> {
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM pragma_table_info(?) WHERE name = ?")
+        .bind(table)
+        .bind(column)
+        .fetch_one(conn)
+        .await?;
+    Ok(count > 0)
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    const ADD_NOTE: &str = "ALTER TABLE items ADD COLUMN note TEXT";
+
+    fn add_note_column(conn: &mut SqliteConnection) -> MigrationFuture<'_> {
+        Box::pin(async move {
+            if !has_column(&mut *conn, "items", "note").await? {
+                sqlx::query(ADD_NOTE).execute(conn).await?;
+            }
+            Ok(())
+        })
+    }
+
+    static V1: &[Migration] = &[Migration::sql(
+        1,
+        "create_items",
+        "CREATE TABLE items (id INTEGER PRIMARY KEY); CREATE TABLE item_history (id INTEGER)",
+    )];
+
+    static V2: &[Migration] = &[
+        Migration::sql(
+            1,
+            "create_items",
+            "CREATE TABLE items (id INTEGER PRIMARY KEY); CREATE TABLE item_history (id INTEGER)",
+        ),
+        Migration::rust(2, "add_note", &[ADD_NOTE], add_note_column),
+    ];
+
+    async fn memory_pool() -> SqlitePool {
+        // Single connection so every query sees the same in-memory database
+        sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_keeps_data_and_is_idempotent() {
+        let pool = memory_pool().await;
+
+        let report = Migrator::new("test", V1).run(&pool).await.unwrap();
+        assert_eq!(report.applied, vec![1]);
+        sqlx::query("INSERT INTO item_history (id) VALUES (7)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let upgraded = Migrator::new("test", V2);
+        let report = upgraded.run(&pool).await.unwrap();
+        assert_eq!(report.applied, vec![2]);
+        assert_eq!(report.current_version, 2);
+
+        let report = upgraded.run(&pool).await.unwrap();
+        assert!(report.applied.is_empty());
+
+        let kept: i64 = sqlx::query_scalar("SELECT id FROM item_history")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(kept, 7);
+        let status = upgraded.status(&pool).await.unwrap();
+        assert!(status.iter().all(|s| s.applied));
+
+        // Another component has its own history in the same table
+        assert!(Migrator::new("other", &[])
+            .applied(&pool)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_modified_and_unknown_migrations() {
+        let pool = memory_pool().await;
+        Migrator::new("test", V2).run(&pool).await.unwrap();
+
+        static EDITED: &[Migration] = &[Migration::sql(
+            1,
+            "create_items",
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT)",
+        )];
+        let err = Migrator::new("test", EDITED).run(&pool).await.unwrap_err();
+        assert!(err.to_string().contains("modified"), "{}", err);
+
+        // Older binary on a newer database
+        let err = Migrator::new("test", V1).run(&pool).await.unwrap_err();
+        assert!(err.to_string().contains("newer"), "{}", err);
+
+        // Editing the DDL a Rust migration declares is detected too
+        static EDITED_RUST: &[Migration] = &[
+            V2[0],
+            Migration::rust(
+                2,
+                "add_note",
+                &["ALTER TABLE items ADD COLUMN note TEXT NOT NULL DEFAULT ''"],
+                add_note_column,
+            ),
+        ];
+        let err = Migrator::new("test", EDITED_RUST)
+            .run(&pool)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("modified"), "{}", err);
+
+        static UNORDERED: &[Migration] = &[
+            Migration::sql(2, "b", "SELECT 1"),
+            Migration::sql(1, "a", "SELECT 1"),
+        ];
+        assert!(Migrator::new("unordered", UNORDERED)
+            .run(&pool)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_adopts_legacy_rust_checksum() {
+        let pool = memory_pool().await;
+        Migrator::new("test", V2).run(&pool).await.unwrap();
+        sqlx::query("UPDATE schema_migrations SET checksum = ? WHERE version = 2")
+            .bind(V2[1].legacy_checksum())
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let report = Migrator::new("test", V2).run(&pool).await.unwrap();
+        assert!(report.applied.is_empty());
+        let applied = Migrator::new("test", V2).applied(&pool).await.unwrap();
+        assert_eq!(applied[1].checksum, V2[1].checksum());
+    }
+}
@@ -0,0 +1,254 @@
+//! Read-only SQLite access for UI tools
+//!
+//! Consoles and configuration UIs open the same `voltage.db` the services
+//! write to. In WAL mode a reader never blocks a writer: it sees the last
+//! committed state, and `busy_timeout` only covers the short windows where a
+//! checkpoint or recovery holds the file. Connections are also `query_only`,
+//! so a UI bug cannot write through this accessor.
+//!
+//! Instead of polling tables, UIs subscribe to a [`DatabaseWatcher`], which
+//! watches the database and WAL files and signals when another process
+//! committed a change.
+
+use anyhow::{Context, Result};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{Sqlite, SqliteConnection, SqlitePool, Transaction};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+use tracing::{debug, info};
+
+/// Options for [`ReadOnlyDatabase`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadOnlyOptions {
+    /// How long a read waits for a lock held by a checkpoint or recovery
+    pub busy_timeout: Duration,
+    pub max_connections: u32,
+}
+
+impl Default for ReadOnlyOptions {
+    fn default() -> Self {
+        Self {
+            busy_timeout: Duration::from_secs(5),
+            max_connections: 4,
+        }
+    }
+}
+
+/// Shared read-only accessor for a service database
+#[derive(Clone)]
+pub struct ReadOnlyDatabase {
+    pool: SqlitePool,
+    path: PathBuf,
+}
+
+impl ReadOnlyDatabase {
+    /// Open with default options; the database must already exist
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::open_with(path, ReadOnlyOptions::default()).await
+    }
+
+    pub async fn open_with(path: impl AsRef<Path>, options: ReadOnlyOptions) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if !path.exists() {
+            anyhow::bail!("Database file not found: {}", path.display());
+        }
+
+        // The journal mode is left alone: it belongs to the writers
+        let connect = SqliteConnectOptions::new()
+            .filename(&path)
+            .read_only(true)
+            .busy_timeout(options.busy_timeout)
+            .pragma("query_only", "ON");
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(options.max_connections.max(1))
+            .connect_with(connect)
+            .await
+            .with_context(|| format!("Failed to open {} read-only", path.display()))?;
+
+        info!("SQLite: {} (RO)", path.display());
+        Ok(Self { pool, path })
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Begin a read transaction pinned to the current committed state
+    ///
+    /// Every query on the snapshot sees the same data, even while services
+    /// keep writing; a UI page built from several queries stays consistent.
+    /// Release it promptly, since an open snapshot holds back WAL checkpoints.
+    pub async fn snapshot(&self) -> Result<ReadSnapshot> {
+        let mut tx = self.pool.begin().await?;
+        // A deferred transaction takes its snapshot on the first read
+        sqlx::query("SELECT COUNT(*) FROM sqlite_master")
+            .execute(&mut *tx)
+            .await?;
+        Ok(ReadSnapshot { tx })
+    }
+
+    /// Watch the database files for changes committed by other processes
+    pub fn watch(&self, interval: Duration) -> DatabaseWatcher {
+        DatabaseWatcher::new(&self.path, interval)
+    }
+}
+
+/// Consistent read view; dropping it ends the read transaction
+pub struct ReadSnapshot {
+    tx: Transaction<'static, Sqlite>,
+}
+
+impl ReadSnapshot {
+    /// Connection to run queries on (`query(..).fetch_all(snapshot.conn())`)
+    pub fn conn(&mut self) -> &mut SqliteConnection {
+        &mut self.tx
+    }
+}
+
+/// A change notification from [`DatabaseWatcher`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DatabaseChange {
+    /// Incremented on every detected change (0 = nothing seen yet)
+    pub sequence: u64,
+    pub detected_at: Option<SystemTime>,
+}
+
+/// Lightweight change notification for a database file
+///
+/// Polls the size and modification time of the database and its `-wal` /
+/// `-journal` files; no connection and no lock are involved, so watching
+/// never interferes with writers. Changes within one interval are coalesced.
+pub struct DatabaseWatcher {
+    rx: watch::Receiver<DatabaseChange>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl DatabaseWatcher {
+    /// Start watching `path` (must be called within a Tokio runtime)
+    pub fn new(path: impl AsRef<Path>, interval: Duration) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let (tx, rx) = watch::channel(DatabaseChange::default());
+
+        let task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval.max(Duration::from_millis(10)));
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut last = fingerprint(&path);
+            let mut sequence = 0;
+
+            loop {
+                ticker.tick().await;
+                let current = fingerprint(&path);
+                if current == last {
+                    continue;
+                }
+                last = current;
+                sequence += 1;
+                debug!("DB change #{}: {}", sequence, path.display());
+                let change = DatabaseChange {
+                    sequence,
+                    detected_at: Some(SystemTime::now()),
+                };
+                if tx.send(change).is_err() {
+                    break; // All receivers dropped
+                }
+            }
+        });
+
+        Self { rx, task }
+    }
+
+    /// Additional receiver (e.g. one per open UI view)
+    pub fn subscribe(&self) -> watch::Receiver<DatabaseChange> {
+        self.rx.clone()
+    }
+
+    /// Wait for the next change
+    pub async fn changed(&mut self) -> Result<DatabaseChange> {
+        self.rx
+            .changed()
+            .await
+            .context("Database watcher stopped")?;
+        Ok(*self.rx.borrow_and_update())
+    }
+}
+
+impl Drop for DatabaseWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+type FileStamp = Option<(u64, Option<SystemTime>)>;
+
+fn fingerprint(path: &Path) -> [FileStamp; 3] {
+    let stamp = |suffix: &str| {
+        let mut file = path.as_os_str().to_owned();
+        file.push(suffix);
+        std::fs::metadata(file)
+            .ok()
+            .map(|m| (m.len(), m.modified().ok()))
+    };
+    [stamp(""), stamp("-wal"), stamp("-journal")]
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use crate::sqlite::SqliteClient;
+
+    #[tokio::test]
+    async fn test_snapshot_and_change_notification() {
+        let dir = std::env::temp_dir().join(format!("voltage-ro-{}", std::process::id()));
+        let db_path = dir.join("voltage.db");
+        let writer = SqliteClient::new(&db_path).await.unwrap();
+        sqlx::query("CREATE TABLE items (id INTEGER PRIMARY KEY)")
+            .execute(writer.pool())
+            .await
+            .unwrap();
+
+        let reader = ReadOnlyDatabase::open(&db_path).await.unwrap();
+        assert!(sqlx::query("INSERT INTO items (id) VALUES (1)")
+            .execute(reader.pool())
+            .await
+            .is_err());
+
+        let mut watcher = reader.watch(Duration::from_millis(10));
+        let mut snapshot = reader.snapshot().await.unwrap();
+
+        // Writers are not blocked by an open snapshot
+        sqlx::query("INSERT INTO items (id) VALUES (1)")
+            .execute(writer.pool())
+            .await
+            .unwrap();
+
+        let change = tokio::time::timeout(Duration::from_secs(5), watcher.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(change.sequence >= 1);
+
+        let seen: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM items")
+            .fetch_one(snapshot.conn())
+            .await
+            .unwrap();
+        assert_eq!(seen, 0);
+        drop(snapshot);
+
+        let seen: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM items")
+            .fetch_one(reader.pool())
+            .await
+            .unwrap();
+        assert_eq!(seen, 1);
+
+        writer.pool().close().await;
+        reader.pool().close().await;
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -203,6 +203,41 @@ impl KeySpaceConfig {
         format!("{}:{}:{}:TODO", target, channel_id, point_type.as_str())
     }
 
+    /// Build dead letter key: comsrv:{channel_id}:DLQ
+    pub fn dead_letter_key(&self, channel_id: u32) -> String {
+        format!("{}:{}:DLQ", self.data_prefix, channel_id)
+    }
+
+    /// Build command tracking key: comsrv:{channel_id}:CMD
+    pub fn command_status_key(&self, channel_id: u32) -> String {
+        format!("{}:{}:CMD", self.data_prefix, channel_id)
+    }
+
+    /// Build channel poll statistics history key: comsrv:{channel_id}:STATS
+    pub fn channel_stats_history_key(&self, channel_id: u32) -> String {
+        format!("{}:{}:STATS", self.data_prefix, channel_id)
+    }
+
+    /// Build forced point audit log key: comsrv:{channel_id}:forced:log
+    pub fn channel_forced_log_key(&self, channel_id: u32) -> String {
+        format!("{}:{}:forced:log", self.data_prefix, channel_id)
+    }
+
+    /// Build channel status key: comsrv:{channel_id}:status
+    pub fn channel_status_key(&self, channel_id: u32) -> String {
+        format!("{}:{}:status", self.data_prefix, channel_id)
+    }
+
+    /// Build redundancy lease key: comsrv:ha:lease
+    pub fn redundancy_lease_key(&self) -> String {
+        format!("{}:ha:lease", self.data_prefix)
+    }
+
+    /// Build redundancy node status key: comsrv:ha:nodes
+    pub fn redundancy_nodes_key(&self) -> String {
+        format!("{}:ha:nodes", self.data_prefix)
+    }
+
     /// Build instance measurement key: inst:{instance_id}:M
     ///
     /// # Examples
@@ -221,6 +256,16 @@ impl KeySpaceConfig {
         format!("{}:{}:A", self.inst_prefix, instance_id)
     }
 
+    /// Build instance action override key: inst:{instance_id}:A:override
+    pub fn instance_override_key(&self, instance_id: u32) -> String {
+        format!("{}:{}:A:override", self.inst_prefix, instance_id)
+    }
+
+    /// Build instance override audit log key: inst:{instance_id}:A:override:log
+    pub fn instance_override_log_key(&self, instance_id: u32) -> String {
+        format!("{}:{}:A:override:log", self.inst_prefix, instance_id)
+    }
+
     /// Build instance name key: inst:{instance_id}:name
     pub fn instance_name_key(&self, instance_id: u32) -> String {
         format!("{}:{}:name", self.inst_prefix, instance_id)
@@ -231,6 +276,13 @@ impl KeySpaceConfig {
         format!("{}:{}:status", self.inst_prefix, instance_id)
     }
 
+    /// Build instance tag index key: inst:tag:{tag_key}:{tag_value}
+    ///
+    /// A set of the IDs of the instances carrying the tag.
+    pub fn instance_tag_index_key(&self, tag_key: &str, tag_value: &str) -> String {
+        format!("{}:tag:{}:{}", self.inst_prefix, tag_key, tag_value)
+    }
+
     /// Build instance config key: inst:{instance_id}:config
     pub fn instance_config_key(&self, instance_id: u32) -> String {
         format!("{}:{}:config", self.inst_prefix, instance_id)
@@ -404,6 +456,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_channel_status_key() {
+        let config = KeySpaceConfig::production();
+        assert_eq!(config.channel_status_key(1001), "comsrv:1001:status");
+        assert_eq!(config.channel_stats_history_key(1001), "comsrv:1001:STATS");
+        assert_eq!(config.redundancy_lease_key(), "comsrv:ha:lease");
+        assert_eq!(config.redundancy_nodes_key(), "comsrv:ha:nodes");
+    }
+
     #[test]
     fn test_instance_keys() {
         let config = KeySpaceConfig::production();
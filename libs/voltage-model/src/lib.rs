@@ -8,10 +8,12 @@
 //! - `types`: Core domain types (PointType, PointRole, etc.)
 //! - `keyspace`: Redis key generation configuration
 //! - `validation`: Input validation utilities for instance names, product names, etc.
+//! - `naming`: Configurable naming policy loaded from global config
 //! - `product_lib`: Built-in product definitions (embedded at compile time)
 
 pub mod error;
 pub mod keyspace;
+pub mod naming;
 pub mod product_lib;
 pub mod types;
 pub mod validation;
@@ -19,5 +21,6 @@ pub mod validation;
 // Re-exports for convenience
 pub use error::{ModelError, Result};
 pub use keyspace::KeySpaceConfig;
+pub use naming::{NamePolicy, NamingPolicy, UniqueScope};
 pub use types::{PointRole, PointType};
 pub use validation::{validate_calculation_id, validate_instance_name, validate_product_name};
@@ -0,0 +1,286 @@
+//! Naming Policy
+//!
+//! Configurable naming conventions for model entities. The policy is read from
+//! the `naming` section of `global.yaml` (synced by monarch into the
+//! `service_config` table) so modsrv, config-ui and monarch enforce the same
+//! rules per deployment. Without a `naming` section the built-in instance
+//! rules of [`crate::validate_instance_name`] apply.
+//!
+//! ```yaml
+//! naming:
+//!   instance:
+//!     pattern: "^[A-Z]{2,4}_[0-9]{2}$"
+//!     min_length: 5
+//!     max_length: 32
+//!     reserved: [system, all]
+//!     case_sensitive: false
+//!     unique: system
+//! ```
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::{ModelError, Result};
+
+/// Default instance name pattern (letters, digits, `_`, `-`; no leading digit)
+pub const DEFAULT_INSTANCE_PATTERN: &str = "^[A-Za-z_][A-Za-z0-9_-]*$";
+
+/// `service_config` key prefix of the naming section
+const CONFIG_PREFIX: &str = "naming.";
+
+/// Names a policy must be unique among
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum UniqueScope {
+    /// Unique among entities of the same kind (e.g. instances)
+    #[default]
+    Kind,
+    /// Unique across instances and channels
+    System,
+}
+
+/// Naming rules for one entity kind
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(default)]
+pub struct NamePolicy {
+    /// Regular expression the whole name must match
+    pub pattern: String,
+    /// Minimum length in characters
+    pub min_length: usize,
+    /// Maximum length in characters
+    pub max_length: usize,
+    /// Names that cannot be used
+    pub reserved: Vec<String>,
+    /// Whether reserved words and uniqueness compare case-sensitively
+    pub case_sensitive: bool,
+    /// Uniqueness scope
+    pub unique: UniqueScope,
+}
+
+impl Default for NamePolicy {
+    fn default() -> Self {
+        Self {
+            pattern: DEFAULT_INSTANCE_PATTERN.to_string(),
+            min_length: 1,
+            max_length: 64,
+            reserved: Vec::new(),
+            case_sensitive: true,
+            unique: UniqueScope::Kind,
+        }
+    }
+}
+
+impl NamePolicy {
+    /// Check the policy itself (pattern compiles, length bounds are consistent)
+    pub fn check(&self) -> Result<()> {
+        Regex::new(&self.pattern)
+            .map_err(|e| ModelError::Config(format!("Invalid naming pattern: {}", e)))?;
+        if self.min_length == 0 || self.min_length > self.max_length {
+            return Err(ModelError::Config(format!(
+                "Invalid naming length range {}..={}",
+                self.min_length, self.max_length
+            )));
+        }
+        Ok(())
+    }
+
+    /// Validate a name against the policy
+    ///
+    /// Uniqueness is not checked here since it needs the stored names; use
+    /// [`NamePolicy::same_name`] when comparing against them.
+    pub fn validate(&self, name: &str) -> Result<()> {
+        let len = name.chars().count();
+        if len < self.min_length || len > self.max_length {
+            return Err(ModelError::InvalidInstanceName(format!(
+                "'{}' has {} characters, expected {}-{}",
+                name, len, self.min_length, self.max_length
+            )));
+        }
+
+        let pattern = Regex::new(&self.pattern)
+            .map_err(|e| ModelError::Config(format!("Invalid naming pattern: {}", e)))?;
+        if !pattern.is_match(name) {
+            return Err(ModelError::InvalidInstanceName(format!(
+                "'{}' does not match the naming pattern {}",
+                name, self.pattern
+            )));
+        }
+
+        if self.reserved.iter().any(|word| self.same_name(word, name)) {
+            return Err(ModelError::InvalidInstanceName(format!(
+                "'{}' is a reserved name",
+                name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Whether two names collide under this policy
+    pub fn same_name(&self, a: &str, b: &str) -> bool {
+        if self.case_sensitive {
+            a == b
+        } else {
+            a.to_lowercase() == b.to_lowercase()
+        }
+    }
+}
+
+/// Naming policies per entity kind (`naming` section of global config)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(default)]
+pub struct NamingPolicy {
+    /// Instance names
+    pub instance: NamePolicy,
+}
+
+impl NamingPolicy {
+    /// Parse and check the `naming` section of `global.yaml`
+    pub fn from_value(value: Value) -> Result<Self> {
+        let policy: Self = serde_json::from_value(value)
+            .map_err(|e| ModelError::Config(format!("Invalid naming section: {}", e)))?;
+        policy.check()?;
+        Ok(policy)
+    }
+
+    /// Rebuild the policy from global `service_config` rows
+    ///
+    /// Rows are the flattened `(key, value)` pairs written by `monarch sync`;
+    /// keys outside `naming.` are ignored. Values that are not JSON (plain
+    /// strings) are taken verbatim.
+    pub fn from_config_rows<K, V>(rows: impl IntoIterator<Item = (K, V)>) -> Result<Self>
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut root = Map::new();
+        for (key, value) in rows {
+            let Some(path) = key.as_ref().strip_prefix(CONFIG_PREFIX) else {
+                continue;
+            };
+            let value = serde_json::from_str(value.as_ref())
+                .unwrap_or_else(|_| Value::String(value.as_ref().to_string()));
+
+            let segments: Vec<&str> = path.split('.').collect();
+            insert_path(&mut root, &segments, value);
+        }
+        Self::from_value(Value::Object(root))
+    }
+
+    /// Check all policies
+    pub fn check(&self) -> Result<()> {
+        self.instance.check()
+    }
+}
+
+/// Insert `value` at a dotted path, creating intermediate objects
+fn insert_path(node: &mut Map<String, Value>, path: &[&str], value: Value) {
+    match path {
+        [] => {},
+        [leaf] => {
+            node.insert(leaf.to_string(), value);
+        },
+        [head, rest @ ..] => {
+            let entry = node
+                .entry(head.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if !entry.is_object() {
+                *entry = Value::Object(Map::new());
+            }
+            if let Value::Object(child) = entry {
+                insert_path(child, rest, value);
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap and json! are acceptable
+mod tests {
+    use super::*;
+    use crate::validation::validate_instance_name;
+    use serde_json::json;
+
+    #[test]
+    fn test_default_policy_matches_builtin_rules() {
+        let policy = NamePolicy::default();
+        policy.check().unwrap();
+        for name in [
+            "pv_inverter_01",
+            "battery-system",
+            "_x",
+            "A",
+            "1test",
+            "",
+            "bad name!",
+        ] {
+            assert_eq!(
+                policy.validate(name).is_ok(),
+                validate_instance_name(name).is_ok(),
+                "{}",
+                name
+            );
+        }
+        assert!(policy.validate(&"a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn test_custom_policy() {
+        let policy = NamingPolicy::from_value(json!({
+            "instance": {
+                "pattern": "^[A-Z]{2,4}_[0-9]{2}$",
+                "max_length": 7,
+                "reserved": ["ALL_00"],
+                "case_sensitive": false,
+                "unique": "system"
+            }
+        }))
+        .unwrap()
+        .instance;
+
+        assert_eq!(policy.min_length, 1);
+        assert_eq!(policy.unique, UniqueScope::System);
+        assert!(policy.validate("PCS_01").is_ok());
+        assert!(policy.validate("pcs_01").is_err());
+        assert!(policy.validate("ABCD_010").is_err());
+        assert!(policy.validate("ALL_00").is_err());
+        assert!(policy.same_name("PCS_01", "pcs_01"));
+    }
+
+    #[test]
+    fn test_invalid_policy_rejected() {
+        assert!(NamingPolicy::from_value(json!({"instance": {"pattern": "("}})).is_err());
+        assert!(
+            NamingPolicy::from_value(json!({"instance": {"min_length": 10, "max_length": 5}}))
+                .is_err()
+        );
+        assert!(NamingPolicy::from_value(json!({"instance": {"unique": "global"}})).is_err());
+    }
+
+    #[test]
+    fn test_from_config_rows() {
+        let rows = [
+            ("rules.tick_ms", "100"),
+            ("naming.instance.pattern", "^[a-z_]+$"),
+            ("naming.instance.max_length", "16"),
+            ("naming.instance.reserved", r#"["system"]"#),
+            ("naming.instance.unique", "system"),
+        ];
+        let policy = NamingPolicy::from_config_rows(rows).unwrap().instance;
+
+        assert_eq!(policy.pattern, "^[a-z_]+$");
+        assert_eq!(policy.max_length, 16);
+        assert_eq!(policy.reserved, vec!["system".to_string()]);
+        assert_eq!(policy.unique, UniqueScope::System);
+        assert!(policy.case_sensitive);
+
+        let empty: [(&str, &str); 0] = [];
+        assert_eq!(
+            NamingPolicy::from_config_rows(empty).unwrap(),
+            NamingPolicy::default()
+        );
+    }
+}
@@ -7,11 +7,13 @@
 use anyhow::{Context, Result};
 use rustc_hash::FxHashMap;
 use std::sync::Arc;
+use std::time::Instant;
 use tracing::debug;
 use voltage_model::PointType;
 use voltage_rtdb::numfmt::{f64_to_bytes, precomputed};
 use voltage_rtdb::{KeySpaceConfig, RoutingCache, Rtdb, WriteBuffer};
 
+use crate::publish;
 use crate::MAX_C2C_CASCADE_DEPTH;
 
 /// Channel point update for batch operations
@@ -103,6 +105,8 @@ where
 
     let config = KeySpaceConfig::production_cached();
     let mut result = BatchRoutingResult::default();
+    let publish = publish::filter();
+    let now = Instant::now();
 
     // Cache point_id -> Arc<str> for O(1) clone - FxHashMap for faster hashing
     let mut point_id_str_cache: FxHashMap<u32, Arc<str>> = FxHashMap::default();
@@ -118,9 +122,17 @@ where
             points_3layer.push((update.point_id, update.value, raw_value));
 
             // C2M routing lookup - zero-allocation using structured key
-            if let Some(target) =
-                routing_cache.lookup_c2m_by_parts(channel_id, point_type, update.point_id)
-            {
+            // (report-by-exception routes skip updates inside their deadband)
+            let c2m_target = routing_cache
+                .lookup_c2m_by_parts(channel_id, point_type, update.point_id)
+                .filter(|_| {
+                    publish.should_publish(
+                        (channel_id, point_type, update.point_id),
+                        update.value,
+                        now,
+                    )
+                });
+            if let Some(target) = c2m_target {
                 // Use precomputed pool or itoa, cache Arc<str> for O(1) clone
                 let point_id_arc = point_id_str_cache
                     .entry(target.point_id)
@@ -222,6 +234,8 @@ pub fn write_channel_batch_buffered(
 
     let config = KeySpaceConfig::production_cached();
     let mut result = BatchRoutingResult::default();
+    let publish = publish::filter();
+    let now = Instant::now();
 
     // Cache point_id -> Arc<str> using precomputed pool - FxHashMap
     let mut point_id_str_cache: FxHashMap<u32, Arc<str>> = FxHashMap::default();
@@ -239,9 +253,17 @@ pub fn write_channel_batch_buffered(
             points_3layer.push((update.point_id, update.value, raw_value));
 
             // C2M routing lookup - zero-allocation using structured key
-            if let Some(target) =
-                routing_cache.lookup_c2m_by_parts(channel_id, point_type, update.point_id)
-            {
+            // (report-by-exception routes skip updates inside their deadband)
+            let c2m_target = routing_cache
+                .lookup_c2m_by_parts(channel_id, point_type, update.point_id)
+                .filter(|_| {
+                    publish.should_publish(
+                        (channel_id, point_type, update.point_id),
+                        update.value,
+                        now,
+                    )
+                });
+            if let Some(target) = c2m_target {
                 // Use precomputed pool (0-255) or itoa, O(1) Arc clone
                 let point_id_str = point_id_str_cache
                     .entry(target.point_id)
@@ -350,6 +372,8 @@ pub fn write_channel_batch_direct(
 
     let config = KeySpaceConfig::production_cached();
     let mut result = BatchRoutingResult::default();
+    let publish = publish::filter();
+    let now = Instant::now();
 
     // Group updates by (channel_id, point_type) for efficient buffer writes - FxHashMap
     let mut grouped: FxHashMap<(u32, PointType), Vec<ChannelPointUpdate>> = FxHashMap::default();
@@ -375,9 +399,18 @@ pub fn write_channel_batch_direct(
             let raw_value = update.raw_value.unwrap_or(update.value);
             points_3layer.push((update.point_id, update.value, raw_value));
 
+            // Report-by-exception routes skip instance updates inside their deadband
+            let published = publish.should_publish(
+                (channel_id, point_type, update.point_id),
+                update.value,
+                now,
+            );
+
             // ★ Direct shared memory write (fastest path)
             // Dual write - Instance area (via C2M) + Channel area
-            if let Some(slot_offset) = channel_index.lookup(channel_id, point_type, update.point_id)
+            if let Some(slot_offset) = channel_index
+                .lookup(channel_id, point_type, update.point_id)
+                .filter(|_| published)
             {
                 shared_writer.set_direct(slot_offset, update.value, timestamp_ms);
                 result.channel_writes += 1; // Count shared memory writes
@@ -392,8 +425,9 @@ pub fn write_channel_batch_direct(
             );
 
             // C2M routing for Redis backup
-            if let Some(target) =
-                routing_cache.lookup_c2m_by_parts(channel_id, point_type, update.point_id)
+            if let Some(target) = routing_cache
+                .lookup_c2m_by_parts(channel_id, point_type, update.point_id)
+                .filter(|_| published)
             {
                 // Use precomputed pool (0-255) or itoa, O(1) Arc clone
                 let point_id_str = point_id_str_cache
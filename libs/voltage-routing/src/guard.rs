@@ -0,0 +1,65 @@
+//! Pre-write guard for action points
+//!
+//! A service can install one [`ActionGuard`] per process; every
+//! [`crate::set_action_point`] (rules, dispatch, API) and every local
+//! override asks it before the value is written. A refused write fails with
+//! a [`ConstraintViolation`], which callers can downcast from the
+//! `anyhow::Error`. Without an installed guard all writes proceed.
+
+use serde::Serialize;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+
+/// Future returned by [`ActionGuard::check`]
+pub type GuardFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<(), ConstraintViolation>> + Send + 'a>>;
+
+/// Decides whether an action write may proceed
+pub trait ActionGuard: Send + Sync + 'static {
+    fn check<'a>(&'a self, instance_id: u32, point_id: &'a str, value: f64) -> GuardFuture<'a>;
+}
+
+/// An action write refused by a constraint (interlock)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConstraintViolation {
+    /// Name of the violated constraint
+    pub constraint: String,
+    pub instance_id: u32,
+    pub point_id: String,
+    /// Requested value
+    pub value: f64,
+    pub message: String,
+}
+
+impl fmt::Display for ConstraintViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Interlock '{}' blocks instance {} action {} = {}: {}",
+            self.constraint, self.instance_id, self.point_id, self.value, self.message
+        )
+    }
+}
+
+impl std::error::Error for ConstraintViolation {}
+
+static GUARD: OnceLock<Arc<dyn ActionGuard>> = OnceLock::new();
+
+/// Install the process-wide guard; returns false if one is already installed
+pub fn install(guard: Arc<dyn ActionGuard>) -> bool {
+    GUARD.set(guard).is_ok()
+}
+
+/// Check a write against the installed guard (if any)
+pub async fn check(
+    instance_id: u32,
+    point_id: &str,
+    value: f64,
+) -> Result<(), ConstraintViolation> {
+    match GUARD.get() {
+        Some(guard) => guard.check(instance_id, point_id, value).await,
+        None => Ok(()),
+    }
+}
@@ -7,17 +7,29 @@
 //! - Batch routing execution with 3-layer data architecture
 //! - SQLite routing loader for service initialization
 //! - Write-Triggers-Routing pattern implementation
+//! - Local HMI override of action points (blocks automatic writes)
+//! - Pre-write guard hook for action interlocks
+//! - Ramp-rate hook for action setpoints
+//! - Report-by-exception publishing of C2M routes
 
 #![allow(clippy::disallowed_methods)] // Used in specific contexts
 
 pub mod batch;
+pub mod guard;
 pub mod loader;
+pub mod manual_override;
+pub mod publish;
+pub mod ramp;
 
 pub use batch::{
     write_channel_batch, write_channel_batch_buffered, write_channel_batch_direct,
     BatchRoutingResult, ChannelPointUpdate,
 };
+pub use guard::{ActionGuard, ConstraintViolation};
 pub use loader::{load_routing_maps, RoutingMaps};
+pub use manual_override::{ActionOverride, OverrideActive};
+pub use publish::{PublishFilter, PublishPolicy};
+pub use ramp::{ActionRamp, RampStart};
 
 // Re-export RoutingCache for convenience
 pub use voltage_rtdb::RoutingCache;
@@ -28,6 +40,12 @@ use voltage_rtdb::Rtdb;
 /// Status string for successful operations
 const STATUS_SUCCESS: &str = "success";
 
+/// Status string for writes refused by a local override
+const STATUS_OVERRIDDEN: &str = "overridden";
+
+/// Status string for writes taken over by the ramp
+const STATUS_RAMPING: &str = "ramping";
+
 /// Structured representation of an action routing outcome.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ActionRouteOutcome {
@@ -52,6 +70,16 @@ impl ActionRouteOutcome {
     pub fn is_success(&self) -> bool {
         self.status.eq_ignore_ascii_case("success")
     }
+
+    /// Whether the write was refused because the point is under local override.
+    pub fn is_overridden(&self) -> bool {
+        self.status == STATUS_OVERRIDDEN
+    }
+
+    /// Whether the write was taken over by the ramp and will reach its target later.
+    pub fn is_ramping(&self) -> bool {
+        self.status == STATUS_RAMPING
+    }
 }
 
 /// Additional routing metadata when routing succeeds.
@@ -70,6 +98,12 @@ pub struct RouteContext {
 /// 2. Writes to instance Action Hash (state storage)
 /// 3. Writes to channel Hash + triggers TODO queue (Write-Triggers-Routing pattern)
 ///
+/// Points under local HMI override (see [`manual_override`]) are not written;
+/// the outcome then has status `"overridden"` and `routed == false`. Writes
+/// refused by the installed [`guard`] fail with a [`ConstraintViolation`].
+/// Writes taken over by the installed [`ramp`] have status `"ramping"` and
+/// `routed == false`; the ramp routes the intermediate setpoints itself.
+///
 /// # Arguments
 /// * `redis` - RTDB trait object
 /// * `routing_cache` - M2C routing cache
@@ -80,7 +114,6 @@ pub struct RouteContext {
 /// # Returns
 /// * `Ok(ActionRouteOutcome)` - Routing outcome with metadata
 /// * `Err(anyhow::Error)` - Routing error
-#[allow(deprecated)] // Uses time_millis internally until TimeProvider migration is complete
 pub async fn set_action_point<R>(
     redis: &R,
     routing_cache: &voltage_rtdb::RoutingCache,
@@ -88,6 +121,85 @@ pub async fn set_action_point<R>(
     point_id: &str,
     value: f64,
 ) -> Result<ActionRouteOutcome>
+where
+    R: Rtdb,
+{
+    write_action_point(redis, routing_cache, instance_id, point_id, value, true).await
+}
+
+/// [`set_action_point`] without the ramp (used by the ramp for its steps)
+///
+/// Overrides and the guard still apply.
+pub async fn set_action_point_immediate<R>(
+    redis: &R,
+    routing_cache: &voltage_rtdb::RoutingCache,
+    instance_id: u32,
+    point_id: &str,
+    value: f64,
+) -> Result<ActionRouteOutcome>
+where
+    R: Rtdb,
+{
+    write_action_point(redis, routing_cache, instance_id, point_id, value, false).await
+}
+
+async fn write_action_point<R>(
+    redis: &R,
+    routing_cache: &voltage_rtdb::RoutingCache,
+    instance_id: u32,
+    point_id: &str,
+    value: f64,
+    ramped: bool,
+) -> Result<ActionRouteOutcome>
+where
+    R: Rtdb,
+{
+    if let Some(active) = manual_override::get_override(redis, instance_id, point_id).await? {
+        tracing::debug!(
+            "Instance {} action {} write {} skipped: overridden by {}",
+            instance_id,
+            point_id,
+            value,
+            active.owner
+        );
+        return Ok(ActionRouteOutcome {
+            status: STATUS_OVERRIDDEN.to_string(),
+            instance_id,
+            point_id: point_id.to_string(),
+            value: value.to_string(),
+            routed: false,
+            route_result: Some(format!("override:{}", active.owner)),
+            route_context: None,
+        });
+    }
+
+    guard::check(instance_id, point_id, value).await?;
+
+    if ramped {
+        if let Some(start) = ramp::intercept(instance_id, point_id, value).await {
+            return Ok(ActionRouteOutcome {
+                status: STATUS_RAMPING.to_string(),
+                instance_id,
+                point_id: point_id.to_string(),
+                value: value.to_string(),
+                routed: false,
+                route_result: Some(format!("ramp:{}->{}", start.from, start.target)),
+                route_context: None,
+            });
+        }
+    }
+    route_action_point(redis, routing_cache, instance_id, point_id, value).await
+}
+
+/// M2C write without the override check (used by the override itself)
+#[allow(deprecated)] // Uses time_millis internally until TimeProvider migration is complete
+pub(crate) async fn route_action_point<R>(
+    redis: &R,
+    routing_cache: &voltage_rtdb::RoutingCache,
+    instance_id: u32,
+    point_id: &str,
+    value: f64,
+) -> Result<ActionRouteOutcome>
 where
     R: Rtdb,
 {
@@ -7,6 +7,8 @@ use std::collections::HashMap;
 use tracing::{debug, info};
 use voltage_rtdb::KeySpaceConfig;
 
+use crate::publish::{PublishKey, PublishPolicy};
+
 /// Routing maps loaded from SQLite
 #[derive(Debug, Default)]
 pub struct RoutingMaps {
@@ -16,6 +18,8 @@ pub struct RoutingMaps {
     pub m2c: HashMap<String, String>,
     /// Channel to Channel routing
     pub c2c: HashMap<String, String>,
+    /// Report-by-exception policies of C2M routes, keyed by source point
+    pub c2m_publish: HashMap<PublishKey, PublishPolicy>,
 }
 
 impl RoutingMaps {
@@ -52,6 +56,9 @@ pub async fn load_routing_maps(sqlite_pool: &sqlx::SqlitePool) -> Result<Routing
     // Load C2M routing (measurement_routing table)
     load_c2m_routes(sqlite_pool, &keyspace, &mut maps.c2m).await?;
 
+    // Load C2M publish policies (measurement_routing deadband columns) - optional
+    load_c2m_publish_policies(sqlite_pool, &mut maps.c2m_publish).await;
+
     // Load M2C routing (action_routing table)
     load_m2c_routes(sqlite_pool, &keyspace, &mut maps.m2c).await?;
 
@@ -110,6 +117,47 @@ async fn load_c2m_routes(
     Ok(())
 }
 
+/// Load report-by-exception policies from measurement_routing
+///
+/// Note: This is optional - databases not yet migrated lack the columns.
+async fn load_c2m_publish_policies(
+    pool: &sqlx::SqlitePool,
+    policies: &mut HashMap<PublishKey, PublishPolicy>,
+) {
+    let rows = sqlx::query_as::<_, (u32, String, u32, Option<f64>, Option<f64>, Option<u32>)>(
+        r#"
+        SELECT channel_id, channel_type, channel_point_id,
+               deadband, deadband_pct, refresh_secs
+        FROM measurement_routing
+        WHERE enabled = TRUE
+          AND channel_id IS NOT NULL AND channel_point_id IS NOT NULL
+          AND (deadband IS NOT NULL OR deadband_pct IS NOT NULL OR refresh_secs IS NOT NULL)
+        "#,
+    )
+    .fetch_all(pool)
+    .await;
+
+    let rows = match rows {
+        Ok(r) => r,
+        Err(e) => {
+            debug!(
+                "measurement_routing has no publish columns, publishing all updates: {}",
+                e
+            );
+            return;
+        },
+    };
+
+    for (channel_id, channel_type, channel_point_id, deadband, deadband_pct, refresh_secs) in rows {
+        let Some(point_type) = voltage_model::PointType::from_str(&channel_type) else {
+            continue;
+        };
+        if let Some(policy) = PublishPolicy::from_columns(deadband, deadband_pct, refresh_secs) {
+            policies.insert((channel_id, point_type, channel_point_id), policy);
+        }
+    }
+}
+
 /// Load M2C (Model to Channel) routing from action_routing table
 async fn load_m2c_routes(
     pool: &sqlx::SqlitePool,
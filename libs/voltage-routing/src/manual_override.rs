@@ -0,0 +1,411 @@
+//! Local HMI override of action points
+//!
+//! An operator can force an action point to a value from the local HMI. The
+//! override is kept in `inst:{id}:A:override` (one JSON field per point) with
+//! its owner and expiry. While it is active, [`crate::set_action_point`] does
+//! not write the point, so rules and dispatch cannot move it away from the
+//! forced value. The point reverts to automatic control when the override is
+//! released or expires; expired overrides are removed on the next read.
+//!
+//! Set, release and expiry are recorded in `inst:{id}:A:override:log`
+//! (newest first, at most [`MAX_AUDIT_ENTRIES`] per instance).
+
+use anyhow::{bail, Context, Result};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+use voltage_rtdb::{KeySpaceConfig, RoutingCache, Rtdb, SystemTimeProvider, TimeProvider};
+
+use crate::{route_action_point, ActionRouteOutcome};
+
+/// Longest override an operator can set
+pub const MAX_OVERRIDE_DURATION: Duration = Duration::from_secs(24 * 3600);
+
+/// Upper bound of audit entries per instance
+pub const MAX_AUDIT_ENTRIES: usize = 500;
+
+/// Actor recorded for overrides that expired
+pub const EXPIRY_ACTOR: &str = "system";
+
+/// A value forced on an action point from the local HMI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionOverride {
+    pub point_id: String,
+    pub value: f64,
+    /// Operator holding the override
+    pub owner: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// When the override was set (milliseconds)
+    pub set_at: i64,
+    /// When the point reverts to automatic control (milliseconds)
+    pub expires_at: i64,
+}
+
+impl ActionOverride {
+    /// Override starting now; the duration is capped at [`MAX_OVERRIDE_DURATION`]
+    pub fn new(
+        point_id: impl Into<String>,
+        value: f64,
+        owner: impl Into<String>,
+        duration: Duration,
+    ) -> Self {
+        let set_at = SystemTimeProvider.now_millis();
+        Self {
+            point_id: point_id.into(),
+            value,
+            owner: owner.into(),
+            reason: None,
+            set_at,
+            expires_at: set_at + duration.min(MAX_OVERRIDE_DURATION).as_millis() as i64,
+        }
+    }
+
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    pub fn is_active(&self, now_ms: i64) -> bool {
+        now_ms < self.expires_at
+    }
+}
+
+/// Error of a write rejected by an active override
+#[derive(Debug, Clone)]
+pub struct OverrideActive(pub ActionOverride);
+
+impl fmt::Display for OverrideActive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Action point {} is under local override by {} until {}",
+            self.0.point_id, self.0.owner, self.0.expires_at
+        )
+    }
+}
+
+impl std::error::Error for OverrideActive {}
+
+/// Kind of audited override change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverrideEvent {
+    Set,
+    Release,
+    Expire,
+}
+
+/// Audit record of an override change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OverrideAuditEntry {
+    pub event: OverrideEvent,
+    pub point_id: String,
+    pub value: f64,
+    /// Owner of the override
+    pub owner: String,
+    /// Who caused the change ([`EXPIRY_ACTOR`] for expiry)
+    pub actor: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// When the change happened (milliseconds)
+    pub at: i64,
+}
+
+/// Active override of an action point, if any
+pub async fn get_override<R: Rtdb>(
+    redis: &R,
+    instance_id: u32,
+    point_id: &str,
+) -> Result<Option<ActionOverride>> {
+    let key = KeySpaceConfig::production_cached().instance_override_key(instance_id);
+    let Some(payload) = redis.hash_get(&key, point_id).await? else {
+        return Ok(None);
+    };
+    let record = parse_record(&key, point_id, &payload);
+    match record {
+        Some(record) if record.is_active(SystemTimeProvider.now_millis()) => Ok(Some(record)),
+        record => {
+            expire(redis, instance_id, point_id, record).await?;
+            Ok(None)
+        },
+    }
+}
+
+/// Active overrides of an instance, ordered by point ID
+pub async fn list_overrides<R: Rtdb>(redis: &R, instance_id: u32) -> Result<Vec<ActionOverride>> {
+    let key = KeySpaceConfig::production_cached().instance_override_key(instance_id);
+    let now = SystemTimeProvider.now_millis();
+
+    let mut active = Vec::new();
+    for (point_id, payload) in redis.hash_get_all(&key).await? {
+        match parse_record(&key, &point_id, &payload) {
+            Some(record) if record.is_active(now) => active.push(record),
+            record => expire(redis, instance_id, &point_id, record).await?,
+        }
+    }
+    active.sort_by(|a, b| natural_cmp(&a.point_id, &b.point_id));
+    Ok(active)
+}
+
+/// Force an action point and write the value through the normal M2C route
+///
+/// Replaces an override of the same owner; an active override of another
+/// owner must be released first. The value must pass the installed
+/// [`crate::guard`].
+pub async fn set_override<R: Rtdb>(
+    redis: &R,
+    routing_cache: &RoutingCache,
+    instance_id: u32,
+    record: ActionOverride,
+) -> Result<ActionRouteOutcome> {
+    if !record.value.is_finite() {
+        bail!("Override value must be finite");
+    }
+    if let Some(current) = get_override(redis, instance_id, &record.point_id).await? {
+        if current.owner != record.owner {
+            return Err(OverrideActive(current).into());
+        }
+    }
+    // Interlocks apply to forced values as well
+    crate::guard::check(instance_id, &record.point_id, record.value).await?;
+
+    let key = KeySpaceConfig::production_cached().instance_override_key(instance_id);
+    redis
+        .hash_set(
+            &key,
+            &record.point_id,
+            Bytes::from(serde_json::to_vec(&record)?),
+        )
+        .await
+        .context("Failed to store override")?;
+    audit(
+        redis,
+        instance_id,
+        OverrideEvent::Set,
+        &record,
+        &record.owner,
+    )
+    .await;
+    tracing::info!(
+        "Instance {} action {} overridden to {} by {} until {}",
+        instance_id,
+        record.point_id,
+        record.value,
+        record.owner,
+        record.expires_at
+    );
+
+    route_action_point(
+        redis,
+        routing_cache,
+        instance_id,
+        &record.point_id,
+        record.value,
+    )
+    .await
+}
+
+/// Release an override; the point returns to automatic control
+///
+/// Returns the released override, or `None` if there was no active one.
+pub async fn release_override<R: Rtdb>(
+    redis: &R,
+    instance_id: u32,
+    point_id: &str,
+    actor: &str,
+) -> Result<Option<ActionOverride>> {
+    let Some(record) = get_override(redis, instance_id, point_id).await? else {
+        return Ok(None);
+    };
+    let key = KeySpaceConfig::production_cached().instance_override_key(instance_id);
+    if !redis.hash_del(&key, point_id).await? {
+        return Ok(None);
+    }
+
+    audit(redis, instance_id, OverrideEvent::Release, &record, actor).await;
+    tracing::info!(
+        "Instance {} action {} override by {} released by {}",
+        instance_id,
+        point_id,
+        record.owner,
+        actor
+    );
+    Ok(Some(record))
+}
+
+/// Override audit entries of an instance, newest first
+pub async fn audit_log<R: Rtdb>(
+    redis: &R,
+    instance_id: u32,
+    limit: usize,
+) -> Result<Vec<OverrideAuditEntry>> {
+    let key = KeySpaceConfig::production_cached().instance_override_log_key(instance_id);
+    let stop = limit.clamp(1, MAX_AUDIT_ENTRIES) as isize - 1;
+    Ok(redis
+        .list_range(&key, 0, stop)
+        .await?
+        .iter()
+        .filter_map(|payload| serde_json::from_slice(payload).ok())
+        .collect())
+}
+
+fn parse_record(key: &str, point_id: &str, payload: &[u8]) -> Option<ActionOverride> {
+    match serde_json::from_slice(payload) {
+        Ok(record) => Some(record),
+        Err(e) => {
+            tracing::warn!("{} field {} unreadable: {}", key, point_id, e);
+            None
+        },
+    }
+}
+
+/// Remove a lapsed (or unreadable) override; only the remover audits it
+async fn expire<R: Rtdb>(
+    redis: &R,
+    instance_id: u32,
+    point_id: &str,
+    record: Option<ActionOverride>,
+) -> Result<()> {
+    let key = KeySpaceConfig::production_cached().instance_override_key(instance_id);
+    if !redis.hash_del(&key, point_id).await? {
+        return Ok(());
+    }
+    if let Some(record) = record {
+        audit(
+            redis,
+            instance_id,
+            OverrideEvent::Expire,
+            &record,
+            EXPIRY_ACTOR,
+        )
+        .await;
+        tracing::info!(
+            "Instance {} action {} override by {} expired",
+            instance_id,
+            point_id,
+            record.owner
+        );
+    }
+    Ok(())
+}
+
+/// Append an audit entry; failures are logged, not propagated
+async fn audit<R: Rtdb>(
+    redis: &R,
+    instance_id: u32,
+    event: OverrideEvent,
+    record: &ActionOverride,
+    actor: &str,
+) {
+    let entry = OverrideAuditEntry {
+        event,
+        point_id: record.point_id.clone(),
+        value: record.value,
+        owner: record.owner.clone(),
+        actor: actor.to_string(),
+        reason: record.reason.clone(),
+        at: SystemTimeProvider.now_millis(),
+    };
+    let key = KeySpaceConfig::production_cached().instance_override_log_key(instance_id);
+    let result = async {
+        redis
+            .list_lpush(&key, Bytes::from(serde_json::to_vec(&entry)?))
+            .await?;
+        redis
+            .list_trim(&key, 0, MAX_AUDIT_ENTRIES as isize - 1)
+            .await
+    }
+    .await;
+    if let Err(e) = result {
+        tracing::warn!("Override audit {} failed: {}", key, e);
+    }
+}
+
+/// Order numeric point IDs numerically, others lexically after them
+fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        (Ok(_), Err(_)) => std::cmp::Ordering::Less,
+        (Err(_), Ok(_)) => std::cmp::Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use crate::set_action_point;
+    use voltage_rtdb::MemoryRtdb;
+
+    #[tokio::test]
+    async fn test_override_blocks_writes_until_released() {
+        let redis = MemoryRtdb::new();
+        let cache = RoutingCache::new();
+        let action_key = KeySpaceConfig::production_cached().instance_action_key(7);
+
+        let record = ActionOverride::new("1", 50.0, "alice", Duration::from_secs(600))
+            .with_reason("maintenance");
+        let outcome = set_override(&redis, &cache, 7, record).await.unwrap();
+        assert!(outcome.is_success());
+
+        // Rule/dispatch writes are refused while the override is active
+        let blocked = set_action_point(&redis, &cache, 7, "1", 80.0)
+            .await
+            .unwrap();
+        assert!(blocked.is_overridden());
+        assert!(!blocked.routed);
+        let stored = redis.hash_get(&action_key, "1").await.unwrap().unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&stored).parse::<f64>().unwrap(),
+            50.0
+        );
+
+        // Another operator cannot take over an active override
+        let other = ActionOverride::new("1", 10.0, "bob", Duration::from_secs(60));
+        let err = set_override(&redis, &cache, 7, other).await.unwrap_err();
+        assert!(err.downcast_ref::<OverrideActive>().is_some());
+
+        assert_eq!(list_overrides(&redis, 7).await.unwrap().len(), 1);
+        let released = release_override(&redis, 7, "1", "bob").await.unwrap();
+        assert_eq!(released.unwrap().owner, "alice");
+        assert!(set_action_point(&redis, &cache, 7, "1", 80.0)
+            .await
+            .unwrap()
+            .is_success());
+
+        let log = audit_log(&redis, 7, 10).await.unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].event, OverrideEvent::Release);
+        assert_eq!(log[0].actor, "bob");
+        assert_eq!(log[1].event, OverrideEvent::Set);
+        assert_eq!(log[1].reason.as_deref(), Some("maintenance"));
+    }
+
+    #[tokio::test]
+    async fn test_expired_override_reverts_to_automatic() {
+        let redis = MemoryRtdb::new();
+        let cache = RoutingCache::new();
+
+        let mut record = ActionOverride::new("2", 1.0, "alice", Duration::from_secs(60));
+        record.expires_at = record.set_at - 1;
+        let key = KeySpaceConfig::production_cached().instance_override_key(7);
+        redis
+            .hash_set(&key, "2", Bytes::from(serde_json::to_vec(&record).unwrap()))
+            .await
+            .unwrap();
+
+        assert!(set_action_point(&redis, &cache, 7, "2", 0.0)
+            .await
+            .unwrap()
+            .is_success());
+        assert!(list_overrides(&redis, 7).await.unwrap().is_empty());
+
+        let log = audit_log(&redis, 7, 10).await.unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].event, OverrideEvent::Expire);
+        assert_eq!(log[0].actor, EXPIRY_ACTOR);
+    }
+}
@@ -0,0 +1,244 @@
+//! Report-by-exception publishing of C2M routes
+//!
+//! By default every channel update is copied to the instance hash. A
+//! measurement route with a publish policy switches to report by exception:
+//! the value is only published when it moved out of the deadband since the
+//! last published value, or when the forced refresh is due. The refresh keeps
+//! the instance timestamp recent for genuinely constant values, so they are
+//! not mistaken for stale data.
+//!
+//! The deadband is dual-valued: an absolute band and a band in percent of the
+//! last published value; the wider of the two applies. The channel layer
+//! itself is always written, only the instance copy is filtered.
+//!
+//! Policies are installed process-wide by the service that executes C2M
+//! routing (comsrv) whenever it (re)loads the routing tables.
+
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use voltage_model::PointType;
+
+/// Forced refresh interval when a policy does not set one
+pub const DEFAULT_REFRESH: Duration = Duration::from_secs(60);
+
+/// Source point of a C2M route: (channel_id, point_type, point_id)
+pub type PublishKey = (u32, PointType, u32);
+
+/// Report-by-exception settings of one measurement route
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PublishPolicy {
+    /// Absolute deadband (engineering units)
+    pub deadband: f64,
+    /// Deadband in percent of the last published value
+    pub deadband_pct: f64,
+    /// Forced refresh interval (`None` = never)
+    pub refresh: Option<Duration>,
+}
+
+impl PublishPolicy {
+    /// Build from the nullable `measurement_routing` columns
+    ///
+    /// Returns `None` when none is set (the route publishes every update).
+    /// A missing `refresh_secs` falls back to [`DEFAULT_REFRESH`], 0 disables it.
+    pub fn from_columns(
+        deadband: Option<f64>,
+        deadband_pct: Option<f64>,
+        refresh_secs: Option<u32>,
+    ) -> Option<Self> {
+        if deadband.is_none() && deadband_pct.is_none() && refresh_secs.is_none() {
+            return None;
+        }
+        Some(Self {
+            deadband: deadband.unwrap_or(0.0).abs(),
+            deadband_pct: deadband_pct.unwrap_or(0.0).abs(),
+            refresh: match refresh_secs {
+                None => Some(DEFAULT_REFRESH),
+                Some(0) => None,
+                Some(secs) => Some(Duration::from_secs(secs as u64)),
+            },
+        })
+    }
+
+    /// Effective band around the last published value
+    fn band(&self, last: f64) -> f64 {
+        self.deadband.max(last.abs() * self.deadband_pct / 100.0)
+    }
+
+    /// Whether `value` must be published given the last published sample
+    pub fn admits(&self, last: Option<(f64, Instant)>, value: f64, now: Instant) -> bool {
+        let Some((last_value, published_at)) = last else {
+            return true;
+        };
+        if self
+            .refresh
+            .is_some_and(|refresh| now.duration_since(published_at) >= refresh)
+        {
+            return true;
+        }
+        if value.is_nan() || last_value.is_nan() {
+            return value.is_nan() != last_value.is_nan();
+        }
+        (value - last_value).abs() > self.band(last_value)
+    }
+}
+
+struct PointState {
+    policy: PublishPolicy,
+    /// Last published value and when it was published
+    last: Option<(f64, Instant)>,
+}
+
+/// Publish decisions for all routes with a policy
+#[derive(Default)]
+pub struct PublishFilter {
+    /// Fast path: false while no policy is installed
+    active: AtomicBool,
+    points: Mutex<FxHashMap<PublishKey, PointState>>,
+}
+
+impl PublishFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace all policies; routes whose policy is unchanged keep their last sample
+    pub fn set_policies(&self, policies: HashMap<PublishKey, PublishPolicy>) {
+        let mut points = self.points.lock();
+        let mut next = FxHashMap::default();
+        for (key, policy) in policies {
+            let last = points
+                .remove(&key)
+                .filter(|state| state.policy == policy)
+                .and_then(|state| state.last);
+            next.insert(key, PointState { policy, last });
+        }
+        self.active.store(!next.is_empty(), Ordering::Release);
+        *points = next;
+    }
+
+    /// Number of routes with a policy
+    pub fn len(&self) -> usize {
+        self.points.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.active.load(Ordering::Acquire)
+    }
+
+    /// Decide whether an update of a C2M source point is copied to the instance
+    pub fn should_publish(&self, key: PublishKey, value: f64, now: Instant) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        let mut points = self.points.lock();
+        let Some(state) = points.get_mut(&key) else {
+            return true;
+        };
+        if state.policy.admits(state.last, value, now) {
+            state.last = Some((value, now));
+            true
+        } else {
+            false
+        }
+    }
+}
+
+static FILTER: OnceLock<PublishFilter> = OnceLock::new();
+
+/// The process-wide filter used by the batch writers
+pub fn filter() -> &'static PublishFilter {
+    FILTER.get_or_init(PublishFilter::new)
+}
+
+/// Install the publish policies loaded with the routing tables
+pub fn set_policies(policies: HashMap<PublishKey, PublishPolicy>) {
+    filter().set_policies(policies);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: PublishKey = (1001, PointType::Telemetry, 1);
+
+    fn filter_with(policy: PublishPolicy) -> PublishFilter {
+        let filter = PublishFilter::new();
+        filter.set_policies(HashMap::from([(KEY, policy)]));
+        filter
+    }
+
+    #[test]
+    fn test_from_columns() {
+        assert!(PublishPolicy::from_columns(None, None, None).is_none());
+
+        let policy = PublishPolicy::from_columns(Some(0.5), None, None).unwrap();
+        assert_eq!(policy.deadband, 0.5);
+        assert_eq!(policy.refresh, Some(DEFAULT_REFRESH));
+
+        let policy = PublishPolicy::from_columns(None, Some(1.0), Some(0)).unwrap();
+        assert_eq!(policy.refresh, None);
+    }
+
+    #[test]
+    fn test_dual_deadband_uses_wider_band() {
+        let filter = filter_with(PublishPolicy {
+            deadband: 1.0,
+            deadband_pct: 2.0,
+            refresh: None,
+        });
+        let now = Instant::now();
+
+        assert!(filter.should_publish(KEY, 10.0, now));
+        // 2% of 10 = 0.2 < 1.0 → absolute band applies
+        assert!(!filter.should_publish(KEY, 10.9, now));
+        assert!(filter.should_publish(KEY, 11.5, now));
+
+        assert!(filter.should_publish(KEY, 1000.0, now));
+        // 2% of 1000 = 20 > 1.0 → relative band applies
+        assert!(!filter.should_publish(KEY, 1015.0, now));
+        assert!(filter.should_publish(KEY, 1025.0, now));
+
+        // Routes without a policy always publish
+        assert!(filter.should_publish((1001, PointType::Telemetry, 2), 1025.0, now));
+    }
+
+    #[test]
+    fn test_forced_refresh_for_constant_value() {
+        let filter = filter_with(PublishPolicy {
+            deadband: 0.0,
+            deadband_pct: 0.0,
+            refresh: Some(Duration::from_secs(10)),
+        });
+        let start = Instant::now();
+
+        assert!(filter.should_publish(KEY, 5.0, start));
+        assert!(!filter.should_publish(KEY, 5.0, start + Duration::from_secs(9)));
+        assert!(filter.should_publish(KEY, 5.0, start + Duration::from_secs(10)));
+        assert!(!filter.should_publish(KEY, 5.0, start + Duration::from_secs(15)));
+        // Any change publishes with a zero band
+        assert!(filter.should_publish(KEY, 5.1, start + Duration::from_secs(15)));
+    }
+
+    #[test]
+    fn test_set_policies_keeps_state_of_unchanged_routes() {
+        let policy = PublishPolicy {
+            deadband: 1.0,
+            deadband_pct: 0.0,
+            refresh: None,
+        };
+        let filter = filter_with(policy);
+        let now = Instant::now();
+        assert!(filter.should_publish(KEY, 10.0, now));
+
+        filter.set_policies(HashMap::from([(KEY, policy)]));
+        assert!(!filter.should_publish(KEY, 10.5, now));
+
+        filter.set_policies(HashMap::new());
+        assert!(filter.is_empty());
+        assert!(filter.should_publish(KEY, 10.5, now));
+    }
+}
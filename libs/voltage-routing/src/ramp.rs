@@ -0,0 +1,45 @@
+//! Ramp-rate hook for action points
+//!
+//! A service can install one [`ActionRamp`] per process. After the override
+//! and guard checks, [`crate::set_action_point`] offers every write to it; a
+//! ramp that takes the write over returns a [`RampStart`] and later writes the
+//! intermediate setpoints itself through [`crate::set_action_point_immediate`].
+//! Without an installed ramp all writes go straight to the channel.
+
+use serde::Serialize;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+
+/// Future returned by [`ActionRamp::intercept`]
+pub type RampFuture<'a> = Pin<Box<dyn Future<Output = Option<RampStart>> + Send + 'a>>;
+
+/// Takes over action writes that must be ramped
+pub trait ActionRamp: Send + Sync + 'static {
+    /// Return `Some` when the write will be ramped instead of written directly
+    fn intercept<'a>(&'a self, instance_id: u32, point_id: &'a str, value: f64) -> RampFuture<'a>;
+}
+
+/// A write taken over by the ramp
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct RampStart {
+    /// Setpoint the ramp starts from
+    pub from: f64,
+    /// Requested setpoint
+    pub target: f64,
+}
+
+static RAMP: OnceLock<Arc<dyn ActionRamp>> = OnceLock::new();
+
+/// Install the process-wide ramp; returns false if one is already installed
+pub fn install(ramp: Arc<dyn ActionRamp>) -> bool {
+    RAMP.set(ramp).is_ok()
+}
+
+/// Offer a write to the installed ramp (if any)
+pub async fn intercept(instance_id: u32, point_id: &str, value: f64) -> Option<RampStart> {
+    match RAMP.get() {
+        Some(ramp) => ramp.intercept(instance_id, point_id, value).await,
+        None => None,
+    }
+}
@@ -0,0 +1,239 @@
+//! Portable RTDB dumps
+//!
+//! Captures the keys matching glob patterns from any [`Rtdb`] backend into a
+//! serializable [`RtdbDump`], and writes a dump back into another backend.
+//! Support bundles use it to take a customer's Redis state home and replay
+//! it into a local `MemoryRtdb` or Redis.
+//!
+//! Unlike [`memory_snapshot`](crate::memory_snapshot), the dump is backend
+//! independent and serializes to readable JSON: values are text when valid
+//! UTF-8 and byte arrays otherwise.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! let dump = RtdbDump::capture(&redis, &["comsrv:*", "inst:*"]).await?;
+//! let local = MemoryRtdb::new();
+//! dump.restore(&local).await?;
+//! ```
+
+use crate::memory_snapshot::SnapshotStats;
+use crate::traits::{KeyType, Rtdb};
+use anyhow::Result;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Stored bytes: text when valid UTF-8, a byte array otherwise
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DumpBytes {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl From<Bytes> for DumpBytes {
+    fn from(bytes: Bytes) -> Self {
+        match String::from_utf8(bytes.to_vec()) {
+            Ok(text) => Self::Text(text),
+            Err(e) => Self::Binary(e.into_bytes()),
+        }
+    }
+}
+
+impl From<&DumpBytes> for Bytes {
+    fn from(value: &DumpBytes) -> Self {
+        match value {
+            DumpBytes::Text(text) => Bytes::from(text.clone()),
+            DumpBytes::Binary(bytes) => Bytes::from(bytes.clone()),
+        }
+    }
+}
+
+/// Value of one key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum DumpValue {
+    String(DumpBytes),
+    Hash(BTreeMap<String, DumpBytes>),
+    List(Vec<DumpBytes>),
+    /// Sorted members
+    Set(Vec<String>),
+}
+
+/// Keys captured from an RTDB
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RtdbDump {
+    /// Glob patterns the keys were captured with
+    pub patterns: Vec<String>,
+    pub keys: BTreeMap<String, DumpValue>,
+    /// Matching keys of types without trait operations (key -> type)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub skipped: BTreeMap<String, String>,
+}
+
+impl RtdbDump {
+    /// Capture all keys matching any of `patterns`
+    ///
+    /// Keys deleted between the scan and the read are left out.
+    pub async fn capture<R: Rtdb + ?Sized>(rtdb: &R, patterns: &[&str]) -> Result<Self> {
+        let mut dump = Self {
+            patterns: patterns.iter().map(|p| p.to_string()).collect(),
+            ..Self::default()
+        };
+        for pattern in patterns {
+            for key in rtdb.scan_match(pattern).await? {
+                if dump.keys.contains_key(&key) || dump.skipped.contains_key(&key) {
+                    continue;
+                }
+                let value = match rtdb.key_type(&key).await? {
+                    Some(KeyType::String) => {
+                        rtdb.get(&key).await?.map(|v| DumpValue::String(v.into()))
+                    },
+                    Some(KeyType::Hash) => {
+                        let fields = rtdb.hash_get_all(&key).await?;
+                        (!fields.is_empty()).then(|| {
+                            DumpValue::Hash(
+                                fields.into_iter().map(|(f, v)| (f, v.into())).collect(),
+                            )
+                        })
+                    },
+                    Some(KeyType::List) => {
+                        let items = rtdb.list_range(&key, 0, -1).await?;
+                        (!items.is_empty())
+                            .then(|| DumpValue::List(items.into_iter().map(Into::into).collect()))
+                    },
+                    Some(KeyType::Set) => {
+                        let mut members = rtdb.smembers(&key).await?;
+                        members.sort();
+                        (!members.is_empty()).then_some(DumpValue::Set(members))
+                    },
+                    Some(KeyType::Other(name)) => {
+                        dump.skipped.insert(key, name);
+                        continue;
+                    },
+                    None => None,
+                };
+                if let Some(value) = value {
+                    dump.keys.insert(key, value);
+                }
+            }
+        }
+        Ok(dump)
+    }
+
+    /// Keys per type
+    pub fn stats(&self) -> SnapshotStats {
+        let mut stats = SnapshotStats::default();
+        for value in self.keys.values() {
+            match value {
+                DumpValue::String(_) => stats.kv_count += 1,
+                DumpValue::Hash(_) => stats.hash_count += 1,
+                DumpValue::List(_) => stats.list_count += 1,
+                DumpValue::Set(_) => stats.set_count += 1,
+            }
+        }
+        stats
+    }
+
+    /// Write every key into `rtdb`, replacing existing keys of the same name
+    pub async fn restore<R: Rtdb + ?Sized>(&self, rtdb: &R) -> Result<SnapshotStats> {
+        for (key, value) in &self.keys {
+            rtdb.del(key).await?;
+            match value {
+                DumpValue::String(v) => rtdb.set(key, v.into()).await?,
+                DumpValue::Hash(fields) => {
+                    let fields = fields.iter().map(|(f, v)| (f.clone(), v.into())).collect();
+                    rtdb.hash_mset(key, fields).await?
+                },
+                DumpValue::List(items) => {
+                    for item in items {
+                        rtdb.list_rpush(key, item.into()).await?;
+                    }
+                },
+                DumpValue::Set(members) => {
+                    for member in members {
+                        rtdb.sadd(key, member).await?;
+                    }
+                },
+            }
+        }
+        Ok(self.stats())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use crate::MemoryRtdb;
+
+    #[tokio::test]
+    async fn test_capture_and_restore_roundtrip() {
+        let source = MemoryRtdb::new();
+        source
+            .hash_mset(
+                "comsrv:1:T",
+                vec![
+                    ("1".to_string(), Bytes::from("230.5")),
+                    ("2".to_string(), Bytes::from_static(&[0xFF, 0x00])),
+                ],
+            )
+            .await
+            .unwrap();
+        source
+            .set("comsrv:1:status", Bytes::from("online"))
+            .await
+            .unwrap();
+        source
+            .list_rpush("comsrv:1:C:TODO", Bytes::from("a"))
+            .await
+            .unwrap();
+        source
+            .list_rpush("comsrv:1:C:TODO", Bytes::from("b"))
+            .await
+            .unwrap();
+        source.sadd("comsrv:channels", "1").await.unwrap();
+        source.set("inst:1:name", Bytes::from("pcs")).await.unwrap();
+
+        let dump = RtdbDump::capture(&source, &["comsrv:*", "comsrv:1:*"])
+            .await
+            .unwrap();
+        assert_eq!(
+            dump.stats(),
+            SnapshotStats {
+                kv_count: 1,
+                hash_count: 1,
+                list_count: 1,
+                set_count: 1,
+            }
+        );
+
+        // JSON keeps text readable and binary values exact
+        let json = serde_json::to_string(&dump).unwrap();
+        assert!(json.contains(r#""1":"230.5""#));
+        assert!(json.contains(r#""2":[255,0]"#));
+        let dump: RtdbDump = serde_json::from_str(&json).unwrap();
+
+        let target = MemoryRtdb::new();
+        target
+            .set("comsrv:1:status", Bytes::from("stale"))
+            .await
+            .unwrap();
+        dump.restore(&target).await.unwrap();
+        assert_eq!(
+            target.get("comsrv:1:status").await.unwrap(),
+            Some(Bytes::from("online"))
+        );
+        assert_eq!(
+            target.hash_get("comsrv:1:T", "2").await.unwrap(),
+            Some(Bytes::from_static(&[0xFF, 0x00]))
+        );
+        assert_eq!(
+            target.list_range("comsrv:1:C:TODO", 0, -1).await.unwrap(),
+            vec![Bytes::from("a"), Bytes::from("b")]
+        );
+        assert_eq!(target.smembers("comsrv:channels").await.unwrap(), vec!["1"]);
+        assert!(target.get("inst:1:name").await.unwrap().is_none());
+    }
+}
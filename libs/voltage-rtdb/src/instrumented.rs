@@ -0,0 +1,879 @@
+//! RTDB operation metrics (feature `metrics`)
+//!
+//! `InstrumentedRtdb` wraps any `Rtdb` implementation and records, per
+//! operation type, the call count, error count, latency histogram and the
+//! payload bytes written and read. Counters are plain atomics in a fixed
+//! per-operation table, so the hot path takes no locks.
+//!
+//! Metrics are read with [`RtdbMetrics::snapshot`] (serializable, for JSON
+//! endpoints) or rendered in Prometheus text format with
+//! [`RtdbMetrics::render_prometheus`].
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! let rtdb = Arc::new(InstrumentedRtdb::new(RedisRtdb::from_client(client)));
+//! let metrics = rtdb.metrics();
+//! // ... later, in a /metrics handler
+//! let body = metrics.render_prometheus("comsrv");
+//! ```
+
+use crate::journal::{StreamEntry, StreamRetention};
+use crate::traits::{KeyType, Rtdb};
+use anyhow::Result;
+use bytes::Bytes;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Upper bounds (microseconds) of the latency histogram buckets; the last
+/// bucket is `+Inf`
+pub const LATENCY_BUCKETS_US: [u64; 11] = [
+    10, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 50_000, 100_000,
+];
+
+const BUCKET_COUNT: usize = LATENCY_BUCKETS_US.len() + 1;
+
+/// Counter metric: (name, help, value accessor)
+type CounterDef = (&'static str, &'static str, fn(&OpMetricsSnapshot) -> u64);
+
+/// Operation types tracked by `InstrumentedRtdb`
+///
+/// Trait methods with default implementations (`hash_del_many_str`,
+/// `write_point_init`, `enqueue_*`) are recorded as the primitive operations
+/// they are built on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RtdbOp {
+    Get,
+    Set,
+    Del,
+    Exists,
+    KeyType,
+    IncrByFloat,
+    HashSet,
+    HashSetF64,
+    HashGet,
+    HashMget,
+    HashMset,
+    HashGetAll,
+    HashDel,
+    HashDelMany,
+    HashIncrBy,
+    ListLpush,
+    ListRpush,
+    ListLpop,
+    ListRpop,
+    ListBlpop,
+    ListRange,
+    ListTrim,
+    Sadd,
+    Srem,
+    Smembers,
+    StreamAdd,
+    StreamRange,
+    ScanMatch,
+    TimeMillis,
+    PipelineHashMset,
+    LeaseAcquire,
+    LeaseRelease,
+}
+
+impl RtdbOp {
+    /// All operations, in table order
+    pub const ALL: [RtdbOp; 32] = [
+        Self::Get,
+        Self::Set,
+        Self::Del,
+        Self::Exists,
+        Self::KeyType,
+        Self::IncrByFloat,
+        Self::HashSet,
+        Self::HashSetF64,
+        Self::HashGet,
+        Self::HashMget,
+        Self::HashMset,
+        Self::HashGetAll,
+        Self::HashDel,
+        Self::HashDelMany,
+        Self::HashIncrBy,
+        Self::ListLpush,
+        Self::ListRpush,
+        Self::ListLpop,
+        Self::ListRpop,
+        Self::ListBlpop,
+        Self::ListRange,
+        Self::ListTrim,
+        Self::Sadd,
+        Self::Srem,
+        Self::Smembers,
+        Self::StreamAdd,
+        Self::StreamRange,
+        Self::ScanMatch,
+        Self::TimeMillis,
+        Self::PipelineHashMset,
+        Self::LeaseAcquire,
+        Self::LeaseRelease,
+    ];
+
+    /// Metric label value (snake_case)
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Get => "get",
+            Self::Set => "set",
+            Self::Del => "del",
+            Self::Exists => "exists",
+            Self::KeyType => "key_type",
+            Self::IncrByFloat => "incrbyfloat",
+            Self::HashSet => "hash_set",
+            Self::HashSetF64 => "hash_set_f64",
+            Self::HashGet => "hash_get",
+            Self::HashMget => "hash_mget",
+            Self::HashMset => "hash_mset",
+            Self::HashGetAll => "hash_get_all",
+            Self::HashDel => "hash_del",
+            Self::HashDelMany => "hash_del_many",
+            Self::HashIncrBy => "hincrby",
+            Self::ListLpush => "list_lpush",
+            Self::ListRpush => "list_rpush",
+            Self::ListLpop => "list_lpop",
+            Self::ListRpop => "list_rpop",
+            Self::ListBlpop => "list_blpop",
+            Self::ListRange => "list_range",
+            Self::ListTrim => "list_trim",
+            Self::Sadd => "sadd",
+            Self::Srem => "srem",
+            Self::Smembers => "smembers",
+            Self::StreamAdd => "stream_add",
+            Self::StreamRange => "stream_range",
+            Self::ScanMatch => "scan_match",
+            Self::TimeMillis => "time_millis",
+            Self::PipelineHashMset => "pipeline_hash_mset",
+            Self::LeaseAcquire => "lease_acquire",
+            Self::LeaseRelease => "lease_release",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Counters for a single operation type
+#[derive(Debug, Default)]
+struct OpCounters {
+    calls: AtomicU64,
+    errors: AtomicU64,
+    bytes_written: AtomicU64,
+    bytes_read: AtomicU64,
+    latency_sum_us: AtomicU64,
+    latency_max_us: AtomicU64,
+    buckets: [AtomicU64; BUCKET_COUNT],
+}
+
+impl OpCounters {
+    fn record(&self, elapsed_us: u64, ok: bool, written: usize, read: usize) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        if !ok {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        if written > 0 {
+            self.bytes_written
+                .fetch_add(written as u64, Ordering::Relaxed);
+        }
+        if read > 0 {
+            self.bytes_read.fetch_add(read as u64, Ordering::Relaxed);
+        }
+        self.latency_sum_us.fetch_add(elapsed_us, Ordering::Relaxed);
+        self.latency_max_us.fetch_max(elapsed_us, Ordering::Relaxed);
+        let bucket = LATENCY_BUCKETS_US
+            .iter()
+            .position(|&bound| elapsed_us <= bound)
+            .unwrap_or(LATENCY_BUCKETS_US.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn reset(&self) {
+        self.calls.store(0, Ordering::Relaxed);
+        self.errors.store(0, Ordering::Relaxed);
+        self.bytes_written.store(0, Ordering::Relaxed);
+        self.bytes_read.store(0, Ordering::Relaxed);
+        self.latency_sum_us.store(0, Ordering::Relaxed);
+        self.latency_max_us.store(0, Ordering::Relaxed);
+        for bucket in &self.buckets {
+            bucket.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Per-operation metrics shared between an `InstrumentedRtdb` and its readers
+#[derive(Debug)]
+pub struct RtdbMetrics {
+    ops: [OpCounters; RtdbOp::ALL.len()],
+}
+
+impl Default for RtdbMetrics {
+    fn default() -> Self {
+        Self {
+            ops: std::array::from_fn(|_| OpCounters::default()),
+        }
+    }
+}
+
+impl RtdbMetrics {
+    /// Create an empty metrics table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one completed operation
+    pub fn record(&self, op: RtdbOp, elapsed_us: u64, ok: bool, written: usize, read: usize) {
+        self.ops[op.index()].record(elapsed_us, ok, written, read);
+    }
+
+    /// Zero all counters
+    pub fn reset(&self) {
+        for op in &self.ops {
+            op.reset();
+        }
+    }
+
+    /// Snapshot of every operation that has been called at least once
+    pub fn snapshot(&self) -> RtdbMetricsSnapshot {
+        let ops = RtdbOp::ALL
+            .iter()
+            .filter_map(|&op| {
+                let c = &self.ops[op.index()];
+                let calls = c.calls.load(Ordering::Relaxed);
+                if calls == 0 {
+                    return None;
+                }
+                Some(OpMetricsSnapshot {
+                    op: op.as_str(),
+                    calls,
+                    errors: c.errors.load(Ordering::Relaxed),
+                    bytes_written: c.bytes_written.load(Ordering::Relaxed),
+                    bytes_read: c.bytes_read.load(Ordering::Relaxed),
+                    latency_sum_us: c.latency_sum_us.load(Ordering::Relaxed),
+                    latency_max_us: c.latency_max_us.load(Ordering::Relaxed),
+                    latency_buckets: c
+                        .buckets
+                        .iter()
+                        .map(|b| b.load(Ordering::Relaxed))
+                        .collect(),
+                })
+            })
+            .collect();
+        RtdbMetricsSnapshot { ops }
+    }
+
+    /// Render all metrics in Prometheus text exposition format
+    ///
+    /// Every sample carries a `service` label so several services can be
+    /// scraped into one dashboard.
+    pub fn render_prometheus(&self, service: &str) -> String {
+        let snapshot = self.snapshot();
+        let mut out = String::new();
+
+        let counters: [CounterDef; 4] = [
+            ("rtdb_ops_total", "RTDB operations", |s| s.calls),
+            ("rtdb_errors_total", "Failed RTDB operations", |s| s.errors),
+            (
+                "rtdb_bytes_written_total",
+                "Payload bytes sent to the RTDB",
+                |s| s.bytes_written,
+            ),
+            (
+                "rtdb_bytes_read_total",
+                "Payload bytes returned by the RTDB",
+                |s| s.bytes_read,
+            ),
+        ];
+        for (name, help, value) in counters {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} counter");
+            for op in &snapshot.ops {
+                let _ = writeln!(
+                    out,
+                    "{name}{{service=\"{service}\",op=\"{}\"}} {}",
+                    op.op,
+                    value(op)
+                );
+            }
+        }
+
+        let name = "rtdb_op_duration_seconds";
+        let _ = writeln!(out, "# HELP {name} RTDB operation latency");
+        let _ = writeln!(out, "# TYPE {name} histogram");
+        for op in &snapshot.ops {
+            let mut cumulative = 0;
+            for (i, count) in op.latency_buckets.iter().enumerate() {
+                cumulative += count;
+                let le = match LATENCY_BUCKETS_US.get(i) {
+                    Some(us) => format!("{}", *us as f64 / 1_000_000.0),
+                    None => "+Inf".to_string(),
+                };
+                let _ = writeln!(
+                    out,
+                    "{name}_bucket{{service=\"{service}\",op=\"{}\",le=\"{le}\"}} {cumulative}",
+                    op.op
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{name}_sum{{service=\"{service}\",op=\"{}\"}} {}",
+                op.op,
+                op.latency_sum_us as f64 / 1_000_000.0
+            );
+            let _ = writeln!(
+                out,
+                "{name}_count{{service=\"{service}\",op=\"{}\"}} {}",
+                op.op, op.calls
+            );
+        }
+
+        out
+    }
+}
+
+/// Point-in-time metrics for one operation type
+#[derive(Debug, Clone, Serialize)]
+pub struct OpMetricsSnapshot {
+    pub op: &'static str,
+    pub calls: u64,
+    pub errors: u64,
+    pub bytes_written: u64,
+    pub bytes_read: u64,
+    pub latency_sum_us: u64,
+    pub latency_max_us: u64,
+    /// Non-cumulative counts per `LATENCY_BUCKETS_US` bucket, plus `+Inf`
+    pub latency_buckets: Vec<u64>,
+}
+
+impl OpMetricsSnapshot {
+    /// Fraction of calls that failed (0.0 when never called)
+    pub fn error_rate(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.errors as f64 / self.calls as f64
+        }
+    }
+
+    /// Mean latency in microseconds
+    pub fn avg_latency_us(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.latency_sum_us as f64 / self.calls as f64
+        }
+    }
+}
+
+/// Point-in-time metrics for all operations that have been called
+#[derive(Debug, Clone, Serialize)]
+pub struct RtdbMetricsSnapshot {
+    pub ops: Vec<OpMetricsSnapshot>,
+}
+
+impl RtdbMetricsSnapshot {
+    /// Metrics for a single operation, if it has been called
+    pub fn get(&self, op: RtdbOp) -> Option<&OpMetricsSnapshot> {
+        self.ops.iter().find(|s| s.op == op.as_str())
+    }
+}
+
+/// Metrics-recording decorator around any `Rtdb` implementation
+///
+/// `as_any` forwards to the inner backend, so code that downcasts to
+/// `RedisRtdb` or `MemoryRtdb` keeps working when the wrapper is inserted.
+pub struct InstrumentedRtdb<R: Rtdb> {
+    inner: R,
+    metrics: Arc<RtdbMetrics>,
+}
+
+impl<R: Rtdb> InstrumentedRtdb<R> {
+    /// Wrap `inner` with a fresh metrics table
+    pub fn new(inner: R) -> Self {
+        Self::with_metrics(inner, Arc::new(RtdbMetrics::new()))
+    }
+
+    /// Wrap `inner`, recording into an existing (possibly shared) table
+    pub fn with_metrics(inner: R, metrics: Arc<RtdbMetrics>) -> Self {
+        Self { inner, metrics }
+    }
+
+    /// Handle to the metrics table
+    pub fn metrics(&self) -> Arc<RtdbMetrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// The wrapped backend
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    async fn observe<T>(
+        &self,
+        op: RtdbOp,
+        written: usize,
+        fut: impl Future<Output = Result<T>>,
+        read_size: impl FnOnce(&T) -> usize,
+    ) -> Result<T> {
+        let start = Instant::now();
+        let result = fut.await;
+        let elapsed_us = start.elapsed().as_micros() as u64;
+        let read = result.as_ref().map(read_size).unwrap_or(0);
+        self.metrics
+            .record(op, elapsed_us, result.is_ok(), written, read);
+        result
+    }
+}
+
+fn no_read<T>(_: &T) -> usize {
+    0
+}
+
+fn opt_len(value: &Option<Bytes>) -> usize {
+    value.as_ref().map_or(0, Bytes::len)
+}
+
+fn fields_len(fields: &[(String, Bytes)]) -> usize {
+    fields.iter().map(|(f, v)| f.len() + v.len()).sum()
+}
+
+impl<R: Rtdb> Rtdb for InstrumentedRtdb<R> {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self.inner.as_any()
+    }
+
+    fn raw_layer(&self) -> &crate::raw_layer::RawLayer {
+        self.inner.raw_layer()
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> impl Future<Output = Result<Option<Bytes>>> + Send + 'a {
+        self.observe(RtdbOp::Get, 0, self.inner.get(key), opt_len)
+    }
+
+    fn set<'a>(
+        &'a self,
+        key: &'a str,
+        value: Bytes,
+    ) -> impl Future<Output = Result<()>> + Send + 'a {
+        let written = value.len();
+        self.observe(RtdbOp::Set, written, self.inner.set(key, value), no_read)
+    }
+
+    fn del<'a>(&'a self, key: &'a str) -> impl Future<Output = Result<bool>> + Send + 'a {
+        self.observe(RtdbOp::Del, 0, self.inner.del(key), no_read)
+    }
+
+    fn exists<'a>(&'a self, key: &'a str) -> impl Future<Output = Result<bool>> + Send + 'a {
+        self.observe(RtdbOp::Exists, 0, self.inner.exists(key), no_read)
+    }
+
+    fn key_type<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> impl Future<Output = Result<Option<KeyType>>> + Send + 'a {
+        self.observe(RtdbOp::KeyType, 0, self.inner.key_type(key), no_read)
+    }
+
+    fn lease_acquire<'a>(
+        &'a self,
+        key: &'a str,
+        owner: &'a str,
+        ttl_ms: u64,
+    ) -> impl Future<Output = Result<bool>> + Send + 'a {
+        self.observe(
+            RtdbOp::LeaseAcquire,
+            owner.len(),
+            self.inner.lease_acquire(key, owner, ttl_ms),
+            no_read,
+        )
+    }
+
+    fn lease_release<'a>(
+        &'a self,
+        key: &'a str,
+        owner: &'a str,
+    ) -> impl Future<Output = Result<bool>> + Send + 'a {
+        self.observe(
+            RtdbOp::LeaseRelease,
+            0,
+            self.inner.lease_release(key, owner),
+            no_read,
+        )
+    }
+
+    fn incrbyfloat<'a>(
+        &'a self,
+        key: &'a str,
+        increment: f64,
+    ) -> impl Future<Output = Result<f64>> + Send + 'a {
+        self.observe(
+            RtdbOp::IncrByFloat,
+            0,
+            self.inner.incrbyfloat(key, increment),
+            no_read,
+        )
+    }
+
+    fn hash_set<'a>(
+        &'a self,
+        key: &'a str,
+        field: &'a str,
+        value: Bytes,
+    ) -> impl Future<Output = Result<()>> + Send + 'a {
+        let written = field.len() + value.len();
+        self.observe(
+            RtdbOp::HashSet,
+            written,
+            self.inner.hash_set(key, field, value),
+            no_read,
+        )
+    }
+
+    fn hash_get<'a>(
+        &'a self,
+        key: &'a str,
+        field: &'a str,
+    ) -> impl Future<Output = Result<Option<Bytes>>> + Send + 'a {
+        self.observe(RtdbOp::HashGet, 0, self.inner.hash_get(key, field), opt_len)
+    }
+
+    fn hash_mget<'a>(
+        &'a self,
+        key: &'a str,
+        fields: &'a [&'a str],
+    ) -> impl Future<Output = Result<Vec<Option<Bytes>>>> + Send + 'a {
+        self.observe(
+            RtdbOp::HashMget,
+            0,
+            self.inner.hash_mget(key, fields),
+            |values: &Vec<Option<Bytes>>| values.iter().map(opt_len).sum(),
+        )
+    }
+
+    fn hash_mset<'a>(
+        &'a self,
+        key: &'a str,
+        fields: Vec<(String, Bytes)>,
+    ) -> impl Future<Output = Result<()>> + Send + 'a {
+        let written = fields_len(&fields);
+        self.observe(
+            RtdbOp::HashMset,
+            written,
+            self.inner.hash_mset(key, fields),
+            no_read,
+        )
+    }
+
+    fn hash_get_all<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> impl Future<Output = Result<HashMap<String, Bytes>>> + Send + 'a {
+        self.observe(
+            RtdbOp::HashGetAll,
+            0,
+            self.inner.hash_get_all(key),
+            |map: &HashMap<String, Bytes>| map.iter().map(|(f, v)| f.len() + v.len()).sum(),
+        )
+    }
+
+    fn hash_set_f64<'a>(
+        &'a self,
+        key: &'a str,
+        field: &'a str,
+        value: f64,
+    ) -> impl Future<Output = Result<()>> + Send + 'a {
+        self.observe(
+            RtdbOp::HashSetF64,
+            field.len() + std::mem::size_of::<f64>(),
+            self.inner.hash_set_f64(key, field, value),
+            no_read,
+        )
+    }
+
+    fn hash_del<'a>(
+        &'a self,
+        key: &'a str,
+        field: &'a str,
+    ) -> impl Future<Output = Result<bool>> + Send + 'a {
+        self.observe(RtdbOp::HashDel, 0, self.inner.hash_del(key, field), no_read)
+    }
+
+    fn hash_del_many<'a>(
+        &'a self,
+        key: &'a str,
+        fields: &'a [String],
+    ) -> impl Future<Output = Result<usize>> + Send + 'a {
+        self.observe(
+            RtdbOp::HashDelMany,
+            0,
+            self.inner.hash_del_many(key, fields),
+            no_read,
+        )
+    }
+
+    fn hincrby<'a>(
+        &'a self,
+        key: &'a str,
+        field: &'a str,
+        increment: i64,
+    ) -> impl Future<Output = Result<i64>> + Send + 'a {
+        self.observe(
+            RtdbOp::HashIncrBy,
+            0,
+            self.inner.hincrby(key, field, increment),
+            no_read,
+        )
+    }
+
+    fn list_lpush<'a>(
+        &'a self,
+        key: &'a str,
+        value: Bytes,
+    ) -> impl Future<Output = Result<()>> + Send + 'a {
+        let written = value.len();
+        self.observe(
+            RtdbOp::ListLpush,
+            written,
+            self.inner.list_lpush(key, value),
+            no_read,
+        )
+    }
+
+    fn list_rpush<'a>(
+        &'a self,
+        key: &'a str,
+        value: Bytes,
+    ) -> impl Future<Output = Result<()>> + Send + 'a {
+        let written = value.len();
+        self.observe(
+            RtdbOp::ListRpush,
+            written,
+            self.inner.list_rpush(key, value),
+            no_read,
+        )
+    }
+
+    fn list_lpop<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> impl Future<Output = Result<Option<Bytes>>> + Send + 'a {
+        self.observe(RtdbOp::ListLpop, 0, self.inner.list_lpop(key), opt_len)
+    }
+
+    fn list_rpop<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> impl Future<Output = Result<Option<Bytes>>> + Send + 'a {
+        self.observe(RtdbOp::ListRpop, 0, self.inner.list_rpop(key), opt_len)
+    }
+
+    fn list_blpop<'a>(
+        &'a self,
+        keys: &'a [&'a str],
+        timeout_seconds: u64,
+    ) -> impl Future<Output = Result<Option<(String, Bytes)>>> + Send + 'a {
+        self.observe(
+            RtdbOp::ListBlpop,
+            0,
+            self.inner.list_blpop(keys, timeout_seconds),
+            |popped: &Option<(String, Bytes)>| popped.as_ref().map_or(0, |(_, v)| v.len()),
+        )
+    }
+
+    fn list_range<'a>(
+        &'a self,
+        key: &'a str,
+        start: isize,
+        stop: isize,
+    ) -> impl Future<Output = Result<Vec<Bytes>>> + Send + 'a {
+        self.observe(
+            RtdbOp::ListRange,
+            0,
+            self.inner.list_range(key, start, stop),
+            |values: &Vec<Bytes>| values.iter().map(Bytes::len).sum(),
+        )
+    }
+
+    fn list_trim<'a>(
+        &'a self,
+        key: &'a str,
+        start: isize,
+        stop: isize,
+    ) -> impl Future<Output = Result<()>> + Send + 'a {
+        self.observe(
+            RtdbOp::ListTrim,
+            0,
+            self.inner.list_trim(key, start, stop),
+            no_read,
+        )
+    }
+
+    fn sadd<'a>(
+        &'a self,
+        key: &'a str,
+        member: &'a str,
+    ) -> impl Future<Output = Result<bool>> + Send + 'a {
+        self.observe(
+            RtdbOp::Sadd,
+            member.len(),
+            self.inner.sadd(key, member),
+            no_read,
+        )
+    }
+
+    fn srem<'a>(
+        &'a self,
+        key: &'a str,
+        member: &'a str,
+    ) -> impl Future<Output = Result<bool>> + Send + 'a {
+        self.observe(RtdbOp::Srem, 0, self.inner.srem(key, member), no_read)
+    }
+
+    fn smembers<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> impl Future<Output = Result<Vec<String>>> + Send + 'a {
+        self.observe(
+            RtdbOp::Smembers,
+            0,
+            self.inner.smembers(key),
+            |members: &Vec<String>| members.iter().map(String::len).sum(),
+        )
+    }
+
+    fn stream_add<'a>(
+        &'a self,
+        key: &'a str,
+        entries: Vec<Vec<(String, Bytes)>>,
+        retention: StreamRetention,
+    ) -> impl Future<Output = Result<Vec<String>>> + Send + 'a {
+        let written = entries.iter().map(|fields| fields_len(fields)).sum();
+        self.observe(
+            RtdbOp::StreamAdd,
+            written,
+            self.inner.stream_add(key, entries, retention),
+            no_read,
+        )
+    }
+
+    fn stream_range<'a>(
+        &'a self,
+        key: &'a str,
+        after: Option<&'a str>,
+        count: usize,
+    ) -> impl Future<Output = Result<Vec<StreamEntry>>> + Send + 'a {
+        self.observe(
+            RtdbOp::StreamRange,
+            0,
+            self.inner.stream_range(key, after, count),
+            |entries: &Vec<StreamEntry>| {
+                entries
+                    .iter()
+                    .map(|(id, fields)| id.len() + fields_len(fields))
+                    .sum()
+            },
+        )
+    }
+
+    fn scan_match<'a>(
+        &'a self,
+        pattern: &'a str,
+    ) -> impl Future<Output = Result<Vec<String>>> + Send + 'a {
+        self.observe(
+            RtdbOp::ScanMatch,
+            0,
+            self.inner.scan_match(pattern),
+            |keys: &Vec<String>| keys.iter().map(String::len).sum(),
+        )
+    }
+
+    #[allow(deprecated)]
+    fn time_millis(&self) -> impl Future<Output = Result<i64>> + Send + '_ {
+        self.observe(RtdbOp::TimeMillis, 0, self.inner.time_millis(), no_read)
+    }
+
+    fn pipeline_hash_mset(
+        &self,
+        operations: Vec<(String, Vec<(String, Bytes)>)>,
+    ) -> impl Future<Output = Result<()>> + Send + '_ {
+        let written = operations.iter().map(|(_, f)| fields_len(f)).sum();
+        self.observe(
+            RtdbOp::PipelineHashMset,
+            written,
+            self.inner.pipeline_hash_mset(operations),
+            no_read,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryRtdb;
+
+    #[tokio::test]
+    async fn records_calls_bytes_and_latency() {
+        let rtdb = InstrumentedRtdb::new(MemoryRtdb::new());
+        rtdb.hash_set("k", "f1", Bytes::from("12345"))
+            .await
+            .unwrap();
+        rtdb.hash_set("k", "f2", Bytes::from("6")).await.unwrap();
+        rtdb.hash_get("k", "f1").await.unwrap();
+        rtdb.hash_get("k", "missing").await.unwrap();
+
+        let snapshot = rtdb.metrics().snapshot();
+        let set = snapshot.get(RtdbOp::HashSet).unwrap();
+        assert_eq!(set.calls, 2);
+        assert_eq!(set.errors, 0);
+        assert_eq!(set.bytes_written, 2 + 5 + 2 + 1);
+        assert_eq!(set.latency_buckets.iter().sum::<u64>(), 2);
+
+        let get = snapshot.get(RtdbOp::HashGet).unwrap();
+        assert_eq!(get.calls, 2);
+        assert_eq!(get.bytes_read, 5);
+        assert!(snapshot.get(RtdbOp::Get).is_none());
+    }
+
+    #[tokio::test]
+    async fn default_methods_record_primitives() {
+        let rtdb = InstrumentedRtdb::new(MemoryRtdb::new());
+        rtdb.enqueue_control(1, "{}").await.unwrap();
+        rtdb.write_point_init("inst:1:A", 1, 1.0).await.unwrap();
+
+        let snapshot = rtdb.metrics().snapshot();
+        assert_eq!(snapshot.get(RtdbOp::ListRpush).unwrap().calls, 1);
+        assert_eq!(snapshot.get(RtdbOp::HashSet).unwrap().calls, 2);
+    }
+
+    #[test]
+    fn error_rate_and_prometheus_output() {
+        let metrics = RtdbMetrics::new();
+        metrics.record(RtdbOp::Get, 20, true, 0, 4);
+        metrics.record(RtdbOp::Get, 200_000, false, 0, 0);
+
+        let snapshot = metrics.snapshot();
+        let get = snapshot.get(RtdbOp::Get).unwrap();
+        assert_eq!(get.error_rate(), 0.5);
+        assert_eq!(get.latency_max_us, 200_000);
+        assert_eq!(get.latency_buckets[1], 1);
+        assert_eq!(get.latency_buckets[BUCKET_COUNT - 1], 1);
+
+        let text = metrics.render_prometheus("comsrv");
+        assert!(text.contains("rtdb_ops_total{service=\"comsrv\",op=\"get\"} 2"));
+        assert!(text.contains("rtdb_errors_total{service=\"comsrv\",op=\"get\"} 1"));
+        assert!(text.contains(
+            "rtdb_op_duration_seconds_bucket{service=\"comsrv\",op=\"get\",le=\"+Inf\"} 2"
+        ));
+
+        metrics.reset();
+        assert!(metrics.snapshot().ops.is_empty());
+    }
+}
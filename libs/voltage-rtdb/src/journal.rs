@@ -0,0 +1,281 @@
+//! Replayable event journal
+//!
+//! Keyspace polling and pub/sub lose every change made while a consumer is
+//! down. With the journal enabled, each write to a journaled point hash also
+//! appends a `PointWritten` entry to the [`POINT_STREAM`] Redis stream, in the
+//! same backend call as the hash write. Consumers (hissrv, alarmsrv) read the
+//! stream through a consumer group, so Redis stores each consumer's offset
+//! and a restarted consumer continues after its last acknowledged entry
+//! (see `python-services/voltage_common/journal.py`). alarmsrv appends
+//! `AlarmRaised` / `AlarmRecovered` entries to [`ALARM_STREAM`] the same way.
+//!
+//! Retention is applied on every append: `MAXLEN ~` caps the entry count and
+//! `MINID ~` drops entries older than the maximum age. A consumer that is
+//! down for longer than the retention misses the trimmed entries.
+//!
+//! # Configuration
+//!
+//! | Variable | Meaning |
+//! |----------|---------|
+//! | `VOLTAGE_EVENT_JOURNAL` | `off` (default), `on` (default patterns) or comma-separated key patterns |
+//! | `VOLTAGE_EVENT_JOURNAL_MAXLEN` | entries kept per stream (default 100000) |
+//! | `VOLTAGE_EVENT_JOURNAL_MAX_AGE` | seconds an entry is kept (default 86400) |
+//!
+//! Patterns match Redis keys segment by segment, `*` standing for one segment.
+//!
+//! # Entry format
+//!
+//! | Field | Value |
+//! |-------|-------|
+//! | `type` | `PointWritten` |
+//! | `key` | hash key, e.g. `comsrv:1001:T` or `inst:5:M` |
+//! | `ts` | journal time in milliseconds |
+//! | `points` | JSON object of the written fields, e.g. `{"1":"230.5"}` |
+
+use bytes::Bytes;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Stream of point writes
+pub const POINT_STREAM: &str = "events:points";
+
+/// Stream of alarm transitions (written by alarmsrv)
+pub const ALARM_STREAM: &str = "events:alarms";
+
+/// Entry type of a point write
+pub const POINT_WRITTEN: &str = "PointWritten";
+
+/// Environment variable enabling the journal
+pub const JOURNAL_ENV: &str = "VOLTAGE_EVENT_JOURNAL";
+
+/// Environment variable overriding [`StreamRetention::max_len`]
+pub const JOURNAL_MAXLEN_ENV: &str = "VOLTAGE_EVENT_JOURNAL_MAXLEN";
+
+/// Environment variable overriding [`StreamRetention::max_age`] (seconds)
+pub const JOURNAL_MAX_AGE_ENV: &str = "VOLTAGE_EVENT_JOURNAL_MAX_AGE";
+
+/// Point hashes journaled by `VOLTAGE_EVENT_JOURNAL=on`
+pub const DEFAULT_PATTERNS: &[&str] = &[
+    "comsrv:*:T",
+    "comsrv:*:S",
+    "comsrv:*:C",
+    "comsrv:*:A",
+    "inst:*:M",
+    "inst:*:A",
+];
+
+/// One stream entry: (entry ID, fields)
+pub type StreamEntry = (String, Vec<(String, Bytes)>);
+
+/// Retention applied on each append to a stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamRetention {
+    /// Entries kept (approximate, trimmed in whole nodes by Redis)
+    pub max_len: usize,
+    /// Age after which entries are dropped
+    pub max_age: Duration,
+}
+
+impl Default for StreamRetention {
+    fn default() -> Self {
+        Self {
+            max_len: 100_000,
+            max_age: Duration::from_secs(24 * 3600),
+        }
+    }
+}
+
+impl StreamRetention {
+    /// Oldest entry ID kept at `now_ms` (`MINID` argument)
+    pub fn min_id(&self, now_ms: i64) -> String {
+        let age_ms = i64::try_from(self.max_age.as_millis()).unwrap_or(i64::MAX);
+        format!("{}-0", now_ms.saturating_sub(age_ms).max(0))
+    }
+}
+
+/// Which point hashes are journaled, and how long entries are kept
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventJournal {
+    patterns: Vec<String>,
+    retention: StreamRetention,
+}
+
+impl EventJournal {
+    pub fn new(patterns: Vec<String>, retention: StreamRetention) -> Self {
+        Self {
+            patterns,
+            retention,
+        }
+    }
+
+    /// Journal configured by `VOLTAGE_EVENT_JOURNAL*` (`None` when off)
+    pub fn from_env() -> Option<Self> {
+        let env = |name: &str| std::env::var(name).ok();
+        Self::parse(
+            env(JOURNAL_ENV).as_deref(),
+            env(JOURNAL_MAXLEN_ENV).as_deref(),
+            env(JOURNAL_MAX_AGE_ENV).as_deref(),
+        )
+    }
+
+    fn parse(patterns: Option<&str>, max_len: Option<&str>, max_age: Option<&str>) -> Option<Self> {
+        let patterns = match patterns.map(str::trim) {
+            None | Some("") | Some("off") | Some("false") | Some("0") => return None,
+            Some("on") | Some("true") | Some("1") => {
+                DEFAULT_PATTERNS.iter().map(|p| p.to_string()).collect()
+            },
+            Some(list) => list
+                .split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(str::to_string)
+                .collect(),
+        };
+
+        let mut retention = StreamRetention::default();
+        if let Some(value) = max_len {
+            match value.trim().parse::<usize>() {
+                Ok(n) if n > 0 => retention.max_len = n,
+                _ => tracing::warn!("Invalid {} '{}', using default", JOURNAL_MAXLEN_ENV, value),
+            }
+        }
+        if let Some(value) = max_age {
+            match value.trim().parse::<u64>() {
+                Ok(secs) if secs > 0 => retention.max_age = Duration::from_secs(secs),
+                _ => tracing::warn!("Invalid {} '{}', using default", JOURNAL_MAX_AGE_ENV, value),
+            }
+        }
+        Some(Self::new(patterns, retention))
+    }
+
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    pub fn retention(&self) -> StreamRetention {
+        self.retention
+    }
+
+    /// Whether writes to `key` are journaled
+    pub fn covers(&self, key: &str) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| key_matches(pattern, key))
+    }
+
+    /// `PointWritten` entries for the journaled hashes among `hashes`
+    pub fn point_entries<'a>(
+        &self,
+        hashes: impl IntoIterator<Item = (&'a str, &'a [(String, Bytes)])>,
+    ) -> Vec<Vec<(String, Bytes)>> {
+        let ts = Bytes::from(now_ms().to_string());
+        hashes
+            .into_iter()
+            .filter(|(key, fields)| !fields.is_empty() && self.covers(key))
+            .map(|(key, fields)| {
+                let points: serde_json::Map<String, serde_json::Value> = fields
+                    .iter()
+                    .map(|(field, value)| {
+                        (
+                            field.clone(),
+                            String::from_utf8_lossy(value).into_owned().into(),
+                        )
+                    })
+                    .collect();
+                vec![
+                    (
+                        "type".to_string(),
+                        Bytes::from_static(POINT_WRITTEN.as_bytes()),
+                    ),
+                    ("key".to_string(), Bytes::from(key.to_string())),
+                    ("ts".to_string(), ts.clone()),
+                    (
+                        "points".to_string(),
+                        Bytes::from(serde_json::Value::Object(points).to_string()),
+                    ),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Match a key against a pattern where `*` stands for one segment
+fn key_matches(pattern: &str, key: &str) -> bool {
+    let mut pattern_segments = pattern.split(':');
+    let mut key_segments = key.split(':');
+    loop {
+        match (pattern_segments.next(), key_segments.next()) {
+            (None, None) => return true,
+            (Some(p), Some(s)) if p == "*" || p == s => {},
+            _ => return false,
+        }
+    }
+}
+
+pub(crate) fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        assert_eq!(EventJournal::parse(None, None, None), None);
+        assert_eq!(EventJournal::parse(Some("off"), None, None), None);
+
+        let journal = EventJournal::parse(Some("on"), Some("500"), Some("60")).unwrap();
+        assert_eq!(journal.patterns().len(), DEFAULT_PATTERNS.len());
+        assert_eq!(
+            journal.retention(),
+            StreamRetention {
+                max_len: 500,
+                max_age: Duration::from_secs(60)
+            }
+        );
+
+        let journal =
+            EventJournal::parse(Some("inst:*:M, comsrv:1001:T"), Some("x"), None).unwrap();
+        assert_eq!(journal.patterns(), ["inst:*:M", "comsrv:1001:T"]);
+        assert_eq!(journal.retention(), StreamRetention::default());
+        assert!(journal.covers("inst:5:M"));
+        assert!(journal.covers("comsrv:1001:T"));
+        assert!(!journal.covers("comsrv:1002:T"));
+        assert!(!journal.covers("inst:5:M:ts"));
+    }
+
+    #[test]
+    fn test_point_entries() {
+        let journal = EventJournal::new(vec!["comsrv:*:T".to_string()], StreamRetention::default());
+        let values = vec![("1".to_string(), Bytes::from("230.5"))];
+        let timestamps = vec![("1".to_string(), Bytes::from("1700000000000"))];
+        let entries = journal.point_entries([
+            ("comsrv:1001:T", values.as_slice()),
+            ("comsrv:1001:T:ts", timestamps.as_slice()),
+        ]);
+
+        assert_eq!(entries.len(), 1);
+        let field = |name: &str| {
+            entries[0]
+                .iter()
+                .find(|(f, _)| f == name)
+                .map(|(_, v)| String::from_utf8_lossy(v).into_owned())
+                .unwrap()
+        };
+        assert_eq!(field("type"), POINT_WRITTEN);
+        assert_eq!(field("key"), "comsrv:1001:T");
+        assert_eq!(field("points"), r#"{"1":"230.5"}"#);
+        assert_eq!(
+            StreamRetention {
+                max_len: 10,
+                max_age: Duration::from_secs(5)
+            }
+            .min_id(60_000),
+            "55000-0"
+        );
+    }
+}
@@ -8,6 +8,7 @@
 //! - **Rtdb trait**: Core trait for realtime database operations
 //! - **KeySpaceConfig**: Redis key naming configuration
 //! - **RoutingCache**: In-memory routing table cache
+//! - **InstrumentedRtdb**: Per-operation metrics decorator (feature `metrics`)
 
 pub mod traits;
 
@@ -16,6 +17,10 @@ pub mod redis_impl;
 
 pub mod memory_impl;
 
+pub mod memory_snapshot;
+
+pub mod dump;
+
 pub mod vec_impl;
 
 pub mod shared_impl;
@@ -32,18 +37,30 @@ pub mod routing_cache;
 
 pub mod numfmt;
 
+pub mod raw_layer;
+
+pub mod journal;
+
+#[cfg(feature = "metrics")]
+pub mod instrumented;
+
 // Re-exports
 pub use bytes::Bytes;
-pub use traits::Rtdb;
+pub use traits::{KeyType, Rtdb};
 
 // KeySpace (canonical location: voltage_model) and Routing exports
-pub use routing_cache::{C2CTarget, C2MTarget, M2CTarget, RoutingCache, RoutingCacheStats};
+pub use routing_cache::{
+    C2CTarget, C2MTarget, LookupStats, M2CTarget, RouteEntry, RouteFilter, RouteMiss, RouteTable,
+    RoutingCache, RoutingCacheDiagnostics, RoutingCacheStats,
+};
 pub use voltage_model::KeySpaceConfig;
 
 #[cfg(feature = "redis-backend")]
 pub use redis_impl::RedisRtdb;
 
+pub use dump::{DumpBytes, DumpValue, RtdbDump};
 pub use memory_impl::{MemoryRtdb, MemoryStats};
+pub use memory_snapshot::{SnapshotConfig, SnapshotStats};
 
 // VecRtdb removed from public API - using SharedMemory + Redis two-tier architecture
 // PointSlot and ChannelVecStore are still used internally by SharedMemory
@@ -64,12 +81,23 @@ pub use write_buffer::{
     WriteBuffer, WriteBufferConfig, WriteBufferStats, WriteBufferStatsSnapshot,
 };
 
+pub use raw_layer::{RawLayer, RawLayerMode};
+
+pub use journal::{EventJournal, StreamEntry, StreamRetention};
+
+#[cfg(feature = "metrics")]
+pub use instrumented::{
+    InstrumentedRtdb, OpMetricsSnapshot, RtdbMetrics, RtdbMetricsSnapshot, RtdbOp,
+};
+
 /// Helper functions for common operations
 pub mod helpers {
     use super::numfmt::{f64_to_bytes, i64_to_bytes, precomputed};
+    use super::raw_layer::{self, RawLayerMode};
     use super::{KeySpaceConfig, MemoryRtdb, Rtdb, WriteBuffer};
     use anyhow::{Context, Result};
     use bytes::Bytes;
+    use std::collections::HashMap;
     use std::sync::Arc;
     use voltage_model::PointType;
 
@@ -162,7 +190,7 @@ pub mod helpers {
     /// Writes multiple points to three separate hashes:
     /// - `{channel_key}`     → engineering values
     /// - `{channel_key}:ts`  → timestamps
-    /// - `{channel_key}:raw` → raw values
+    /// - `{channel_key}:raw` → raw values (layout per [`Rtdb::raw_layer`])
     ///
     /// # Arguments
     /// * `rtdb` - RTDB trait object
@@ -205,23 +233,19 @@ pub mod helpers {
             let field: Arc<str> = precomputed::get_point_id_str_or_alloc(point_id);
 
             // Arc::clone is O(1), convert to String only when pushing to final Vec
-            // This reduces 3 String clones to 3 Arc::clone + 3 Arc->String conversions
             values.push((field.to_string(), f64_to_bytes(value)));
             timestamps.push((field.to_string(), timestamp_bytes.clone()));
-            raw_values.push((field.to_string(), f64_to_bytes(raw_value)));
+            raw_values.push((point_id, raw_value));
         }
 
         // Write all hashes in a single pipeline
         let ts_key = format!("{}:ts", channel_key);
-        let raw_key = format!("{}:raw", channel_key);
+        let mut hashes = vec![(channel_key.to_string(), values), (ts_key, timestamps)];
+        hashes.extend(raw_layer_hash(rtdb, channel_key, raw_values).await);
 
-        rtdb.pipeline_hash_mset(vec![
-            (channel_key.to_string(), values),
-            (ts_key, timestamps),
-            (raw_key, raw_values),
-        ])
-        .await
-        .context("Failed to write channel points")?;
+        rtdb.pipeline_hash_mset(hashes)
+            .await
+            .context("Failed to write channel points")?;
 
         Ok(count)
     }
@@ -263,27 +287,148 @@ pub mod helpers {
 
         for (point_id, value, raw_value) in points {
             // Use precomputed pool (0-255) or itoa for larger IDs
-            // Arc<str> allows O(1) clone across 3 layers
+            // Arc<str> allows O(1) clone across 2 layers
             let field: Arc<str> = precomputed::get_point_id_str_or_alloc(point_id);
 
             // Arc::clone is O(1) - just atomic counter increment
             // f64_to_bytes uses ryu for fast formatting
             values.push((Arc::clone(&field), f64_to_bytes(value)));
-            timestamps.push((Arc::clone(&field), timestamp_bytes.clone()));
-            raw_values.push((field, f64_to_bytes(raw_value)));
+            timestamps.push((field, timestamp_bytes.clone()));
+            raw_values.push((point_id, raw_value));
         }
 
-        // Buffer all hashes
+        // Buffer all hashes; the raw layout is chosen by the flushing RTDB
         let ts_key = format!("{}:ts", channel_key);
-        let raw_key = format!("{}:raw", channel_key);
 
         write_buffer.buffer_hash_mset(channel_key, values);
         write_buffer.buffer_hash_mset(&ts_key, timestamps);
-        write_buffer.buffer_hash_mset(&raw_key, raw_values);
+        write_buffer.buffer_raw_values(channel_key, raw_values);
 
         count
     }
 
+    /// Read the raw values of a channel
+    ///
+    /// Reads the compressed blob if present, otherwise the `:raw` hash, so
+    /// callers work regardless of the writer's raw layer mode. Empty when raw values
+    /// are not stored.
+    pub async fn read_raw_values<R>(
+        rtdb: &R,
+        config: &KeySpaceConfig,
+        channel_id: u32,
+        point_type: PointType,
+    ) -> Result<HashMap<u32, f64>>
+    where
+        R: Rtdb,
+    {
+        let channel_key = config.channel_key(channel_id, point_type);
+        let blob = load_raw_blob(rtdb, &raw_layer::blob_key(&channel_key)).await?;
+        if !blob.is_empty() {
+            return Ok(blob);
+        }
+
+        let raw_key = config.channel_raw_key(channel_id, point_type);
+        let values = rtdb
+            .hash_get_all(&raw_key)
+            .await
+            .context("Failed to read raw values")?
+            .into_iter()
+            .filter_map(|(field, bytes)| {
+                let point_id = field.parse::<u32>().ok()?;
+                let value = std::str::from_utf8(&bytes).ok()?.parse::<f64>().ok()?;
+                Some((point_id, value))
+            })
+            .collect();
+        Ok(values)
+    }
+
+    /// Read the raw value of a single point (see [`read_raw_values`])
+    pub async fn read_raw_value<R>(
+        rtdb: &R,
+        config: &KeySpaceConfig,
+        channel_id: u32,
+        point_type: PointType,
+        point_id: u32,
+    ) -> Result<Option<f64>>
+    where
+        R: Rtdb,
+    {
+        let channel_key = config.channel_key(channel_id, point_type);
+        let blob = load_raw_blob(rtdb, &raw_layer::blob_key(&channel_key)).await?;
+        if !blob.is_empty() {
+            return Ok(blob.get(&point_id).copied());
+        }
+
+        let raw_key = config.channel_raw_key(channel_id, point_type);
+        let value = rtdb
+            .hash_get(&raw_key, &point_id.to_string())
+            .await
+            .context("Failed to read raw value")?
+            .and_then(|bytes| std::str::from_utf8(&bytes).ok()?.parse::<f64>().ok());
+        Ok(value)
+    }
+
+    /// Raw layer hash write for a batch of raw values (`None` when disabled)
+    ///
+    /// In compressed mode the instance's channel values are seeded from the
+    /// stored blob first, so a batch never drops the points it does not carry.
+    pub(crate) async fn raw_layer_hash<R>(
+        rtdb: &R,
+        channel_key: &str,
+        raw_values: Vec<(u32, f64)>,
+    ) -> Option<(String, Vec<(String, Bytes)>)>
+    where
+        R: Rtdb,
+    {
+        let layer = rtdb.raw_layer();
+        match layer.mode() {
+            RawLayerMode::Hash => {
+                let fields = raw_values
+                    .into_iter()
+                    .map(|(point_id, raw_value)| {
+                        (
+                            precomputed::get_point_id_str_or_alloc(point_id).to_string(),
+                            f64_to_bytes(raw_value),
+                        )
+                    })
+                    .collect();
+                Some((format!("{}:raw", channel_key), fields))
+            },
+            RawLayerMode::Compressed => {
+                let blob_key = raw_layer::blob_key(channel_key);
+                if !layer.is_loaded(&blob_key) {
+                    // Keep points not in this batch across a restart
+                    let stored = load_raw_blob(rtdb, &blob_key).await.unwrap_or_else(|e| {
+                        tracing::warn!("{}: {:#}, rebuilding", blob_key, e);
+                        HashMap::new()
+                    });
+                    layer.load(&blob_key, stored.into_iter().collect());
+                }
+                let blob = layer.update(&blob_key, raw_values);
+                Some((blob_key, vec![(raw_layer::BLOB_FIELD.to_string(), blob)]))
+            },
+            RawLayerMode::Disabled => None,
+        }
+    }
+
+    /// Decode a stored raw blob (empty if absent)
+    async fn load_raw_blob<R>(rtdb: &R, blob_key: &str) -> Result<HashMap<u32, f64>>
+    where
+        R: Rtdb,
+    {
+        match rtdb
+            .hash_get(blob_key, raw_layer::BLOB_FIELD)
+            .await
+            .context("Failed to read raw blob")?
+        {
+            Some(blob) => Ok(raw_layer::decode(&blob)
+                .with_context(|| format!("Corrupt raw blob {}", blob_key))?
+                .into_iter()
+                .collect()),
+            None => Ok(HashMap::new()),
+        }
+    }
+
     /// Write a single point with automatic TODO queue trigger based on point type
     ///
     /// This function automatically determines whether to trigger the TODO queue:
@@ -1,9 +1,12 @@
 //! High-performance in-memory RTDB implementation
 //!
 //! Uses DashMap for lock-free concurrent access with excellent performance.
-//! Perfect for testing and embedded scenarios.
+//! Perfect for testing and embedded scenarios. Optional snapshot persistence
+//! lives in `memory_snapshot`.
 
+use crate::journal::{self, EventJournal, StreamEntry, StreamRetention};
 use crate::numfmt::{f64_to_bytes, i64_to_bytes};
+use crate::raw_layer::{RawLayer, RawLayerMode};
 use crate::traits::*;
 use anyhow::Result;
 use bytes::Bytes;
@@ -12,17 +15,67 @@ use parking_lot::RwLock;
 use std::collections::{HashMap, VecDeque};
 use std::future::Future;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 /// In-memory RTDB implementation with concurrent access support
 ///
 /// This is a pure storage abstraction. For routing logic, use the
 /// `voltage-routing` library which handles M2C routing externally.
 pub struct MemoryRtdb {
-    kv_store: Arc<DashMap<String, Bytes>>,
-    hash_store: Arc<DashMap<String, DashMap<String, Bytes>>>,
-    list_store: Arc<DashMap<String, RwLock<VecDeque<Bytes>>>>,
-    set_store: Arc<DashMap<String, DashSet<String>>>,
+    pub(crate) kv_store: Arc<DashMap<String, Bytes>>,
+    pub(crate) hash_store: Arc<DashMap<String, DashMap<String, Bytes>>>,
+    pub(crate) list_store: Arc<DashMap<String, RwLock<VecDeque<Bytes>>>>,
+    pub(crate) set_store: Arc<DashMap<String, DashSet<String>>>,
+    /// Expiry of the lease keys in `kv_store`
+    lease_expiry: Arc<DashMap<String, Instant>>,
+    /// Streams (event journal); not included in snapshots
+    stream_store: Arc<DashMap<String, RwLock<MemoryStream>>>,
+    raw_layer: RawLayer,
+    journal: Option<EventJournal>,
+}
+
+type Fields = Vec<(String, Bytes)>;
+
+/// In-memory stream with Redis-style `{ms}-{seq}` entry IDs
+#[derive(Default)]
+struct MemoryStream {
+    last_id: (i64, u64),
+    /// (`{ms}`, `{seq}`) ID and fields
+    entries: VecDeque<((i64, u64), Fields)>,
+}
+
+impl MemoryStream {
+    fn append(&mut self, fields: Vec<(String, Bytes)>, now_ms: i64) -> String {
+        let (last_ms, last_seq) = self.last_id;
+        self.last_id = if now_ms > last_ms {
+            (now_ms, 0)
+        } else {
+            (last_ms, last_seq + 1)
+        };
+        let (ms, seq) = self.last_id;
+        self.entries.push_back(((ms, seq), fields));
+        format!("{}-{}", ms, seq)
+    }
+
+    fn trim(&mut self, retention: StreamRetention, now_ms: i64) {
+        let min_ms = now_ms.saturating_sub(retention.max_age.as_millis() as i64);
+        while self
+            .entries
+            .front()
+            .is_some_and(|((ms, _), _)| *ms < min_ms)
+            || self.entries.len() > retention.max_len
+        {
+            self.entries.pop_front();
+        }
+    }
+}
+
+/// Parse a `{ms}-{seq}` stream ID (`{ms}` alone means `{ms}-0`)
+fn parse_stream_id(id: &str) -> Option<(i64, u64)> {
+    match id.split_once('-') {
+        Some((ms, seq)) => Some((ms.parse().ok()?, seq.parse().ok()?)),
+        None => Some((id.parse().ok()?, 0)),
+    }
 }
 
 impl MemoryRtdb {
@@ -33,6 +86,52 @@ impl MemoryRtdb {
             hash_store: Arc::new(DashMap::new()),
             list_store: Arc::new(DashMap::new()),
             set_store: Arc::new(DashMap::new()),
+            lease_expiry: Arc::new(DashMap::new()),
+            stream_store: Arc::new(DashMap::new()),
+            raw_layer: RawLayer::from_env(),
+            journal: EventJournal::from_env(),
+        }
+    }
+
+    /// Store raw values in `mode` instead of the `VOLTAGE_RAW_LAYER` default
+    pub fn with_raw_layer_mode(mut self, mode: RawLayerMode) -> Self {
+        self.raw_layer = RawLayer::new(mode);
+        self
+    }
+
+    /// Journal point writes with `journal` instead of the `VOLTAGE_EVENT_JOURNAL` default
+    pub fn with_event_journal(mut self, journal: Option<EventJournal>) -> Self {
+        self.journal = journal;
+        self
+    }
+
+    fn stream_append(
+        &self,
+        key: &str,
+        entries: Vec<Vec<(String, Bytes)>>,
+        retention: StreamRetention,
+    ) -> Vec<String> {
+        let now_ms = journal::now_ms();
+        let stream = self.stream_store.entry(key.to_string()).or_default();
+        let mut stream = stream.write();
+        let ids = entries
+            .into_iter()
+            .map(|fields| stream.append(fields, now_ms))
+            .collect();
+        stream.trim(retention, now_ms);
+        ids
+    }
+
+    /// Append `PointWritten` entries for the journaled hashes among `hashes`
+    fn journal_points<'a>(
+        &self,
+        hashes: impl IntoIterator<Item = (&'a str, &'a [(String, Bytes)])>,
+    ) {
+        if let Some(journal) = &self.journal {
+            let entries = journal.point_entries(hashes);
+            if !entries.is_empty() {
+                self.stream_append(journal::POINT_STREAM, entries, journal.retention());
+            }
         }
     }
 
@@ -42,6 +141,8 @@ impl MemoryRtdb {
         self.hash_store.clear();
         self.list_store.clear();
         self.set_store.clear();
+        self.lease_expiry.clear();
+        self.stream_store.clear();
     }
 
     /// Get statistics about stored data
@@ -82,7 +183,15 @@ impl Rtdb for MemoryRtdb {
     }
 
     fn del(&self, key: &str) -> impl Future<Output = Result<bool>> + Send + '_ {
-        let result = self.kv_store.remove(key).is_some();
+        // Like Redis DEL, removes the key whatever its type
+        let result = [
+            self.kv_store.remove(key).is_some(),
+            self.hash_store.remove(key).is_some(),
+            self.list_store.remove(key).is_some(),
+            self.set_store.remove(key).is_some(),
+            self.stream_store.remove(key).is_some(),
+        ]
+        .contains(&true);
         async move { Ok(result) }
     }
 
@@ -91,6 +200,70 @@ impl Rtdb for MemoryRtdb {
         async move { Ok(result) }
     }
 
+    fn key_type(&self, key: &str) -> impl Future<Output = Result<Option<KeyType>>> + Send + '_ {
+        let result = if self.kv_store.contains_key(key) {
+            Some(KeyType::String)
+        } else if self.hash_store.contains_key(key) {
+            Some(KeyType::Hash)
+        } else if self.list_store.contains_key(key) {
+            Some(KeyType::List)
+        } else if self.set_store.contains_key(key) {
+            Some(KeyType::Set)
+        } else if self.stream_store.contains_key(key) {
+            Some(KeyType::Other("stream".to_string()))
+        } else {
+            None
+        };
+        async move { Ok(result) }
+    }
+
+    fn lease_acquire<'a>(
+        &'a self,
+        key: &'a str,
+        owner: &'a str,
+        ttl_ms: u64,
+    ) -> impl Future<Output = Result<bool>> + Send + 'a {
+        // The expiry entry is held while the holder is checked and replaced
+        let mut expiry = self
+            .lease_expiry
+            .entry(key.to_string())
+            .or_insert_with(Instant::now);
+        let now = Instant::now();
+        let free = *expiry <= now
+            || self
+                .kv_store
+                .get(key)
+                .is_none_or(|holder| holder.as_ref() == owner.as_bytes());
+        if free {
+            self.kv_store
+                .insert(key.to_string(), Bytes::copy_from_slice(owner.as_bytes()));
+            *expiry = now + Duration::from_millis(ttl_ms.max(1));
+        }
+        async move { Ok(free) }
+    }
+
+    fn lease_release<'a>(
+        &'a self,
+        key: &'a str,
+        owner: &'a str,
+    ) -> impl Future<Output = Result<bool>> + Send + 'a {
+        let deleted = match self.lease_expiry.get_mut(key) {
+            Some(mut expiry) => {
+                let held = *expiry > Instant::now()
+                    && self
+                        .kv_store
+                        .remove_if(key, |_, holder| holder.as_ref() == owner.as_bytes())
+                        .is_some();
+                if held {
+                    *expiry = Instant::now();
+                }
+                held
+            },
+            None => false,
+        };
+        async move { Ok(deleted) }
+    }
+
     fn incrbyfloat(
         &self,
         key: &str,
@@ -138,6 +311,10 @@ impl Rtdb for MemoryRtdb {
         self
     }
 
+    fn raw_layer(&self) -> &RawLayer {
+        &self.raw_layer
+    }
+
     fn hash_set(
         &self,
         key: &str,
@@ -147,7 +324,8 @@ impl Rtdb for MemoryRtdb {
         self.hash_store
             .entry(key.to_string())
             .or_default()
-            .insert(field.to_string(), value);
+            .insert(field.to_string(), value.clone());
+        self.journal_points([(key, [(field.to_string(), value)].as_slice())]);
         async move { Ok(()) }
     }
 
@@ -184,10 +362,13 @@ impl Rtdb for MemoryRtdb {
         key: &str,
         fields: Vec<(String, Bytes)>,
     ) -> impl Future<Output = Result<()>> + Send + '_ {
-        let hash = self.hash_store.entry(key.to_string()).or_default();
-        for (field, value) in fields {
-            hash.insert(field, value);
+        {
+            let hash = self.hash_store.entry(key.to_string()).or_default();
+            for (field, value) in &fields {
+                hash.insert(field.clone(), value.clone());
+            }
         }
+        self.journal_points([(key, fields.as_slice())]);
         async move { Ok(()) }
     }
 
@@ -394,6 +575,7 @@ impl Rtdb for MemoryRtdb {
         let kv_store = self.kv_store.clone();
         let hash_store = self.hash_store.clone();
         let list_store = self.list_store.clone();
+        let set_store = self.set_store.clone();
 
         async move {
             let re = regex::Regex::new(&format!("^{}$", regex_pattern))?;
@@ -421,6 +603,13 @@ impl Rtdb for MemoryRtdb {
                 }
             }
 
+            // Scan set store
+            for entry in set_store.iter() {
+                if re.is_match(entry.key()) {
+                    matches.push(entry.key().clone());
+                }
+            }
+
             matches.sort();
             matches.dedup();
             Ok(matches)
@@ -505,6 +694,45 @@ impl Rtdb for MemoryRtdb {
         async move { Ok(new_value) }
     }
 
+    fn stream_add<'a>(
+        &'a self,
+        key: &'a str,
+        entries: Vec<Vec<(String, Bytes)>>,
+        retention: StreamRetention,
+    ) -> impl Future<Output = Result<Vec<String>>> + Send + 'a {
+        let ids = self.stream_append(key, entries, retention);
+        async move { Ok(ids) }
+    }
+
+    fn stream_range<'a>(
+        &'a self,
+        key: &'a str,
+        after: Option<&'a str>,
+        count: usize,
+    ) -> impl Future<Output = Result<Vec<StreamEntry>>> + Send + 'a {
+        let result = after
+            .map(|id| {
+                parse_stream_id(id).ok_or_else(|| anyhow::anyhow!("Invalid stream ID: {}", id))
+            })
+            .transpose()
+            .map(|after| {
+                self.stream_store
+                    .get(key)
+                    .map(|stream| {
+                        stream
+                            .read()
+                            .entries
+                            .iter()
+                            .filter(|(id, _)| after.is_none_or(|after| *id > after))
+                            .take(count)
+                            .map(|((ms, seq), fields)| (format!("{}-{}", ms, seq), fields.clone()))
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            });
+        async move { result }
+    }
+
     fn time_millis(&self) -> impl Future<Output = Result<i64>> + Send + '_ {
         let result = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -519,14 +747,19 @@ impl Rtdb for MemoryRtdb {
     ) -> impl Future<Output = Result<()>> + Send + '_ {
         // For in-memory implementation, just execute each HSET sequentially
         // This is efficient since it's all in-memory with no network overhead
-        for (key, fields) in operations {
+        for (key, fields) in &operations {
             if !fields.is_empty() {
-                let hash = self.hash_store.entry(key).or_default();
+                let hash = self.hash_store.entry(key.clone()).or_default();
                 for (field, value) in fields {
-                    hash.insert(field, value);
+                    hash.insert(field.clone(), value.clone());
                 }
             }
         }
+        self.journal_points(
+            operations
+                .iter()
+                .map(|(key, fields)| (key.as_str(), fields.as_slice())),
+        );
         async move { Ok(()) }
     }
 }
@@ -1198,4 +1431,109 @@ mod tests {
             );
         }
     }
+
+    #[tokio::test]
+    async fn test_lease_acquire_and_expiry() {
+        let rtdb = MemoryRtdb::new();
+
+        assert!(rtdb.lease_acquire("ha:lease", "node-a", 40).await.unwrap());
+        // Renewal by the holder, refusal for anyone else
+        assert!(rtdb.lease_acquire("ha:lease", "node-a", 40).await.unwrap());
+        assert!(!rtdb.lease_acquire("ha:lease", "node-b", 40).await.unwrap());
+        assert!(!rtdb.lease_release("ha:lease", "node-b").await.unwrap());
+        assert_eq!(
+            rtdb.get("ha:lease").await.unwrap(),
+            Some(Bytes::from("node-a"))
+        );
+
+        // Expired: the other node takes over
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(rtdb.lease_acquire("ha:lease", "node-b", 40).await.unwrap());
+        assert!(!rtdb.lease_acquire("ha:lease", "node-a", 40).await.unwrap());
+
+        assert!(rtdb.lease_release("ha:lease", "node-b").await.unwrap());
+        assert!(!rtdb.exists("ha:lease").await.unwrap());
+        assert!(rtdb.lease_acquire("ha:lease", "node-a", 40).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_event_journal_records_point_writes() {
+        let journal = EventJournal::new(
+            vec!["comsrv:*:T".to_string()],
+            StreamRetention {
+                max_len: 3,
+                max_age: Duration::from_secs(60),
+            },
+        );
+        let rtdb = MemoryRtdb::new().with_event_journal(Some(journal));
+
+        rtdb.hash_set("comsrv:1001:T", "1", Bytes::from("230.5"))
+            .await
+            .unwrap();
+        // Not journaled: timestamp hash and unmatched keys
+        rtdb.hash_set("comsrv:1001:T:ts", "1", Bytes::from("1700000000000"))
+            .await
+            .unwrap();
+        rtdb.hash_set("inst:1:M", "1", Bytes::from("1"))
+            .await
+            .unwrap();
+        rtdb.pipeline_hash_mset(vec![
+            (
+                "comsrv:1001:T".to_string(),
+                vec![("2".to_string(), Bytes::from("1.5"))],
+            ),
+            (
+                "comsrv:1002:T".to_string(),
+                vec![("1".to_string(), Bytes::from("7"))],
+            ),
+        ])
+        .await
+        .unwrap();
+
+        let entries = rtdb
+            .stream_range(journal::POINT_STREAM, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 3);
+        let key_and_points = |fields: &[(String, Bytes)]| {
+            let field = |name: &str| {
+                fields
+                    .iter()
+                    .find(|(f, _)| f == name)
+                    .map(|(_, v)| String::from_utf8_lossy(v).into_owned())
+                    .unwrap()
+            };
+            (field("key"), field("points"))
+        };
+        assert_eq!(
+            key_and_points(&entries[0].1),
+            ("comsrv:1001:T".to_string(), r#"{"1":"230.5"}"#.to_string())
+        );
+        assert_eq!(
+            key_and_points(&entries[2].1),
+            ("comsrv:1002:T".to_string(), r#"{"1":"7"}"#.to_string())
+        );
+
+        // Reading resumes after the last seen ID
+        let rest = rtdb
+            .stream_range(journal::POINT_STREAM, Some(&entries[0].0), 10)
+            .await
+            .unwrap();
+        assert_eq!(rest, entries[1..].to_vec());
+
+        // MAXLEN drops the oldest entries
+        rtdb.hash_mset("comsrv:1003:T", vec![("1".to_string(), Bytes::from("0"))])
+            .await
+            .unwrap();
+        let trimmed = rtdb
+            .stream_range(journal::POINT_STREAM, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(trimmed.len(), 3);
+        assert_eq!(trimmed[0].0, entries[1].0);
+        assert!(rtdb
+            .stream_range(journal::POINT_STREAM, Some("bad-id"), 10)
+            .await
+            .is_err());
+    }
 }
@@ -0,0 +1,478 @@
+//! MemoryRtdb snapshot persistence
+//!
+//! Periodically dumps the in-memory store to a file and restores it on start,
+//! so `MemoryRtdb` survives restarts in small standalone deployments and in
+//! integration tests that need warm restarts.
+//!
+//! Snapshots are written to `<path>.tmp`, fsynced and renamed over `<path>`,
+//! so a crash during a write leaves the previous snapshot intact.
+//!
+//! # File format
+//!
+//! Little-endian binary: the `VRTDBSN1` magic, then one record per key
+//! (`u8` type tag, length-prefixed key, payload) and an end tag. Strings and
+//! values are `u32` length-prefixed; hashes, lists and sets carry a `u32`
+//! element count. A file without the end tag is rejected as truncated.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! let config = SnapshotConfig::new("/var/lib/voltage/rtdb.snap").with_interval_ms(30_000);
+//! let rtdb = Arc::new(MemoryRtdb::with_snapshot(&config)?);
+//!
+//! let shutdown = Arc::new(Notify::new());
+//! let runner = Arc::clone(&rtdb);
+//! tokio::spawn(async move { runner.snapshot_loop_with_shutdown(&config, shutdown).await });
+//! ```
+
+use crate::memory_impl::MemoryRtdb;
+use anyhow::{bail, Context, Result};
+use bytes::Bytes;
+use dashmap::{DashMap, DashSet};
+use parking_lot::RwLock;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+const MAGIC: &[u8; 8] = b"VRTDBSN1";
+
+const TAG_KV: u8 = 0;
+const TAG_HASH: u8 = 1;
+const TAG_LIST: u8 = 2;
+const TAG_SET: u8 = 3;
+const TAG_END: u8 = 0xFF;
+
+/// Snapshot persistence configuration
+#[derive(Clone, Debug)]
+pub struct SnapshotConfig {
+    /// Snapshot file
+    pub path: PathBuf,
+    /// Interval between periodic snapshots in milliseconds (default: 60000)
+    pub interval_ms: u64,
+}
+
+impl SnapshotConfig {
+    /// Snapshot to `path` with the default interval
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            interval_ms: 60_000,
+        }
+    }
+
+    /// Set the periodic snapshot interval
+    pub fn with_interval_ms(mut self, interval_ms: u64) -> Self {
+        self.interval_ms = interval_ms;
+        self
+    }
+}
+
+/// Number of keys saved or restored, per type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SnapshotStats {
+    pub kv_count: usize,
+    pub hash_count: usize,
+    pub list_count: usize,
+    pub set_count: usize,
+}
+
+impl SnapshotStats {
+    /// Total number of keys
+    pub fn total(&self) -> usize {
+        self.kv_count + self.hash_count + self.list_count + self.set_count
+    }
+}
+
+impl MemoryRtdb {
+    /// Create an instance restored from the configured snapshot, if one exists
+    pub fn with_snapshot(config: &SnapshotConfig) -> Result<Self> {
+        let rtdb = Self::new();
+        if config.path.exists() {
+            let stats = rtdb.restore_snapshot(&config.path)?;
+            tracing::info!(
+                path = %config.path.display(),
+                keys = stats.total(),
+                "MemoryRtdb restored from snapshot"
+            );
+        }
+        Ok(rtdb)
+    }
+
+    /// Serialize the current contents into snapshot bytes
+    ///
+    /// Keys are read one at a time, so writes that happen concurrently may or
+    /// may not be included; each key itself is captured consistently.
+    pub fn encode_snapshot(&self) -> (Vec<u8>, SnapshotStats) {
+        let mut buf = Vec::with_capacity(4096);
+        let mut stats = SnapshotStats::default();
+        buf.extend_from_slice(MAGIC);
+
+        for entry in self.kv_store.iter() {
+            put_record_header(&mut buf, TAG_KV, entry.key());
+            put_bytes(&mut buf, entry.value());
+            stats.kv_count += 1;
+        }
+        for entry in self.hash_store.iter() {
+            put_record_header(&mut buf, TAG_HASH, entry.key());
+            let fields: Vec<(String, Bytes)> = entry
+                .value()
+                .iter()
+                .map(|f| (f.key().clone(), f.value().clone()))
+                .collect();
+            put_len(&mut buf, fields.len());
+            for (field, value) in &fields {
+                put_bytes(&mut buf, field.as_bytes());
+                put_bytes(&mut buf, value);
+            }
+            stats.hash_count += 1;
+        }
+        for entry in self.list_store.iter() {
+            put_record_header(&mut buf, TAG_LIST, entry.key());
+            let list = entry.value().read();
+            put_len(&mut buf, list.len());
+            for value in list.iter() {
+                put_bytes(&mut buf, value);
+            }
+            stats.list_count += 1;
+        }
+        for entry in self.set_store.iter() {
+            put_record_header(&mut buf, TAG_SET, entry.key());
+            let members: Vec<String> = entry.value().iter().map(|m| m.key().clone()).collect();
+            put_len(&mut buf, members.len());
+            for member in &members {
+                put_bytes(&mut buf, member.as_bytes());
+            }
+            stats.set_count += 1;
+        }
+
+        buf.push(TAG_END);
+        (buf, stats)
+    }
+
+    /// Replace the current contents with decoded snapshot bytes
+    ///
+    /// The snapshot is fully decoded before anything is replaced, so a
+    /// corrupt snapshot leaves the store untouched.
+    pub fn decode_snapshot(&self, data: &[u8]) -> Result<SnapshotStats> {
+        let kv = DashMap::new();
+        let hashes: DashMap<String, DashMap<String, Bytes>> = DashMap::new();
+        let lists = DashMap::new();
+        let sets: DashMap<String, DashSet<String>> = DashMap::new();
+        let mut stats = SnapshotStats::default();
+
+        let mut reader = Reader { data, pos: 0 };
+        if reader.take(MAGIC.len())? != MAGIC {
+            bail!("Not a MemoryRtdb snapshot (bad magic)");
+        }
+
+        loop {
+            let tag = reader.u8()?;
+            if tag == TAG_END {
+                break;
+            }
+            let key = reader.string()?;
+            match tag {
+                TAG_KV => {
+                    kv.insert(key, reader.bytes()?);
+                    stats.kv_count += 1;
+                },
+                TAG_HASH => {
+                    let hash = DashMap::new();
+                    for _ in 0..reader.len()? {
+                        let field = reader.string()?;
+                        hash.insert(field, reader.bytes()?);
+                    }
+                    hashes.insert(key, hash);
+                    stats.hash_count += 1;
+                },
+                TAG_LIST => {
+                    let count = reader.len()?;
+                    let mut list = VecDeque::with_capacity(count.min(reader.remaining()));
+                    for _ in 0..count {
+                        list.push_back(reader.bytes()?);
+                    }
+                    lists.insert(key, RwLock::new(list));
+                    stats.list_count += 1;
+                },
+                TAG_SET => {
+                    let set = DashSet::new();
+                    for _ in 0..reader.len()? {
+                        set.insert(reader.string()?);
+                    }
+                    sets.insert(key, set);
+                    stats.set_count += 1;
+                },
+                other => bail!("Corrupt snapshot: unknown record type {}", other),
+            }
+        }
+        if reader.remaining() != 0 {
+            bail!("Corrupt snapshot: {} trailing bytes", reader.remaining());
+        }
+
+        self.clear();
+        for (key, value) in kv {
+            self.kv_store.insert(key, value);
+        }
+        for (key, value) in hashes {
+            self.hash_store.insert(key, value);
+        }
+        for (key, value) in lists {
+            self.list_store.insert(key, value);
+        }
+        for (key, value) in sets {
+            self.set_store.insert(key, value);
+        }
+        Ok(stats)
+    }
+
+    /// Write a snapshot to `path`, atomically replacing any previous one
+    pub fn save_snapshot(&self, path: &Path) -> Result<SnapshotStats> {
+        let (data, stats) = self.encode_snapshot();
+        write_atomic(path, &data)?;
+        Ok(stats)
+    }
+
+    /// Replace the current contents with the snapshot at `path`
+    pub fn restore_snapshot(&self, path: &Path) -> Result<SnapshotStats> {
+        let data = std::fs::read(path)
+            .with_context(|| format!("Failed to read snapshot {}", path.display()))?;
+        self.decode_snapshot(&data)
+            .with_context(|| format!("Failed to restore snapshot {}", path.display()))
+    }
+
+    /// Periodic snapshot loop - runs until shutdown signal
+    ///
+    /// Writes a final snapshot before returning so a graceful shutdown loses
+    /// nothing. File IO runs on the blocking thread pool.
+    pub async fn snapshot_loop_with_shutdown(
+        self: &Arc<Self>,
+        config: &SnapshotConfig,
+        shutdown: Arc<Notify>,
+    ) {
+        let interval = Duration::from_millis(config.interval_ms.max(1));
+
+        loop {
+            let stop = tokio::select! {
+                biased;  // Check shutdown first
+
+                _ = shutdown.notified() => true,
+                _ = tokio::time::sleep(interval) => false,
+            };
+
+            let rtdb = Arc::clone(self);
+            let path = config.path.clone();
+            match tokio::task::spawn_blocking(move || rtdb.save_snapshot(&path)).await {
+                Ok(Ok(stats)) => {
+                    tracing::trace!(keys = stats.total(), "MemoryRtdb snapshot written")
+                },
+                Ok(Err(e)) => tracing::warn!(error = %e, "MemoryRtdb snapshot failed"),
+                Err(e) => tracing::warn!(error = %e, "MemoryRtdb snapshot task failed"),
+            }
+
+            if stop {
+                break;
+            }
+        }
+
+        tracing::debug!("MemoryRtdb snapshot loop stopped");
+    }
+}
+
+fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    let mut file = std::fs::File::create(&tmp_path)
+        .with_context(|| format!("Failed to create {}", tmp_path.display()))?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    drop(file);
+
+    std::fs::rename(&tmp_path, path).with_context(|| {
+        format!(
+            "Failed to move {} to {}",
+            tmp_path.display(),
+            path.display()
+        )
+    })?;
+    Ok(())
+}
+
+fn put_len(buf: &mut Vec<u8>, len: usize) {
+    buf.extend_from_slice(&(len as u32).to_le_bytes());
+}
+
+fn put_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    put_len(buf, bytes.len());
+    buf.extend_from_slice(bytes);
+}
+
+fn put_record_header(buf: &mut Vec<u8>, tag: u8, key: &str) {
+    buf.push(tag);
+    put_bytes(buf, key.as_bytes());
+}
+
+/// Bounds-checked cursor over snapshot bytes
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if n > self.remaining() {
+            bail!("Corrupt snapshot: truncated at byte {}", self.pos);
+        }
+        let slice = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn len(&mut self) -> Result<usize> {
+        let mut raw = [0u8; 4];
+        raw.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(raw) as usize)
+    }
+
+    fn bytes(&mut self) -> Result<Bytes> {
+        let len = self.len()?;
+        Ok(Bytes::copy_from_slice(self.take(len)?))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.len()?;
+        let raw = self.take(len)?;
+        String::from_utf8(raw.to_vec()).context("Corrupt snapshot: key is not UTF-8")
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use crate::Rtdb;
+    use tempfile::TempDir;
+
+    async fn populated() -> MemoryRtdb {
+        let rtdb = MemoryRtdb::new();
+        rtdb.set("version", Bytes::from("42")).await.unwrap();
+        rtdb.set("raw", Bytes::from_static(&[0, 159, 255]))
+            .await
+            .unwrap();
+        rtdb.hash_set("comsrv:1001:T", "1", Bytes::from("230.5"))
+            .await
+            .unwrap();
+        rtdb.hash_set("comsrv:1001:T", "2", Bytes::from("12.1"))
+            .await
+            .unwrap();
+        rtdb.list_rpush("comsrv:1001:C:TODO", Bytes::from("{\"point_id\":1}"))
+            .await
+            .unwrap();
+        rtdb.sadd("inst:tag:site:north", "5").await.unwrap();
+        rtdb
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_round_trip() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("nested").join("rtdb.snap");
+        let rtdb = populated().await;
+
+        let saved = rtdb.save_snapshot(&path).unwrap();
+        assert_eq!(
+            saved,
+            SnapshotStats {
+                kv_count: 2,
+                hash_count: 1,
+                list_count: 1,
+                set_count: 1
+            }
+        );
+        assert!(!path.with_extension("snap.tmp").exists());
+
+        let restored = MemoryRtdb::with_snapshot(&SnapshotConfig::new(&path)).unwrap();
+        assert_eq!(restored.get("version").await.unwrap().unwrap(), "42");
+        assert_eq!(
+            restored.get("raw").await.unwrap().unwrap().as_ref(),
+            &[0, 159, 255]
+        );
+        assert_eq!(
+            restored
+                .hash_get("comsrv:1001:T", "2")
+                .await
+                .unwrap()
+                .unwrap(),
+            "12.1"
+        );
+        assert_eq!(
+            restored
+                .list_lpop("comsrv:1001:C:TODO")
+                .await
+                .unwrap()
+                .unwrap(),
+            "{\"point_id\":1}"
+        );
+        assert_eq!(
+            restored.smembers("inst:tag:site:north").await.unwrap(),
+            vec!["5".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_snapshot_leaves_store_untouched() {
+        let rtdb = populated().await;
+        let (data, _) = rtdb.encode_snapshot();
+
+        let target = MemoryRtdb::new();
+        target.set("keep", Bytes::from("1")).await.unwrap();
+        assert!(target.decode_snapshot(&data[..data.len() - 1]).is_err());
+        assert!(target.decode_snapshot(b"NOTASNAP").is_err());
+        assert_eq!(target.get("keep").await.unwrap().unwrap(), "1");
+
+        // A missing snapshot file starts empty
+        let temp = TempDir::new().unwrap();
+        let fresh =
+            MemoryRtdb::with_snapshot(&SnapshotConfig::new(temp.path().join("none"))).unwrap();
+        assert_eq!(fresh.stats().kv_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_loop_final_write_on_shutdown() {
+        let temp = TempDir::new().unwrap();
+        let config = SnapshotConfig::new(temp.path().join("rtdb.snap")).with_interval_ms(3_600_000);
+        let rtdb = Arc::new(populated().await);
+        let shutdown = Arc::new(Notify::new());
+
+        let runner = Arc::clone(&rtdb);
+        let loop_config = config.clone();
+        let signal = Arc::clone(&shutdown);
+        let handle = tokio::spawn(async move {
+            runner
+                .snapshot_loop_with_shutdown(&loop_config, signal)
+                .await
+        });
+
+        shutdown.notify_one();
+        handle.await.unwrap();
+
+        let restored = MemoryRtdb::new();
+        assert_eq!(restored.restore_snapshot(&config.path).unwrap().total(), 5);
+    }
+}
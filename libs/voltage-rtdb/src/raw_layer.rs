@@ -0,0 +1,289 @@
+//! Storage mode of the raw value layer
+//!
+//! Every channel write stores engineering values, timestamps and raw
+//! (pre-transform) values in three hashes. The raw hash costs as much Redis
+//! memory as the value hash but is only read for diagnostics, so it can be
+//! stored compactly or not at all, selected by `VOLTAGE_RAW_LAYER`:
+//!
+//! | Mode | Storage |
+//! |------|---------|
+//! | `hash` (default) | `{channel_key}:raw` hash, one field per point |
+//! | `compressed` | `{channel_key}:rawz` hash, one delta-encoded blob per channel |
+//! | `off` | not stored |
+//!
+//! The mode and, in compressed mode, the channel's raw values held in process
+//! belong to the RTDB instance ([`crate::Rtdb::raw_layer`]); the writer
+//! rewrites the whole blob on each update. Readers should use
+//! [`crate::helpers::read_raw_values`], which understands both layouts.
+//!
+//! # Blob format
+//!
+//! `[version=1][varint count]` followed by the points in ascending ID order,
+//! each as `varint(id_gap << 1 | kind)`:
+//! - kind 0: integral value, zigzag varint delta to the previous integral value
+//! - kind 1: 8-byte little-endian `f64`
+//!
+//! Raw values are mostly register contents, so most points take 2-4 bytes.
+
+use bytes::Bytes;
+use dashmap::DashMap;
+use std::collections::BTreeMap;
+
+use crate::error::{Result, RtdbError};
+
+/// Environment variable selecting the raw layer mode
+pub const RAW_LAYER_ENV: &str = "VOLTAGE_RAW_LAYER";
+
+/// Hash field holding the blob in compressed mode
+pub const BLOB_FIELD: &str = "blob";
+
+const BLOB_VERSION: u8 = 1;
+
+/// Largest magnitude stored as an integer (exact in f64)
+const MAX_EXACT_INT: f64 = 9_007_199_254_740_992.0; // 2^53
+
+/// How raw values are stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RawLayerMode {
+    /// One hash field per point (`{channel_key}:raw`)
+    #[default]
+    Hash,
+    /// One delta-encoded blob per channel (`{channel_key}:rawz`)
+    Compressed,
+    /// Raw values are not stored
+    Disabled,
+}
+
+impl RawLayerMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Hash => "hash",
+            Self::Compressed => "compressed",
+            Self::Disabled => "off",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "hash" => Some(Self::Hash),
+            "compressed" | "delta" => Some(Self::Compressed),
+            "off" | "disabled" | "none" => Some(Self::Disabled),
+            _ => None,
+        }
+    }
+
+    /// Mode selected by `VOLTAGE_RAW_LAYER` (hash if unset or unknown)
+    pub fn from_env() -> Self {
+        match std::env::var(RAW_LAYER_ENV) {
+            Ok(value) => Self::parse(&value).unwrap_or_else(|| {
+                tracing::warn!("Unknown {} '{}', using hash", RAW_LAYER_ENV, value);
+                Self::Hash
+            }),
+            Err(_) => Self::Hash,
+        }
+    }
+}
+
+/// Raw layer state of one RTDB instance
+///
+/// Holds the storage mode and, in compressed mode, each channel's raw values
+/// keyed by blob key, so the blob can be rewritten without reading it back.
+#[derive(Debug, Default)]
+pub struct RawLayer {
+    mode: RawLayerMode,
+    channel_values: DashMap<String, BTreeMap<u32, f64>>,
+}
+
+impl RawLayer {
+    pub fn new(mode: RawLayerMode) -> Self {
+        Self {
+            mode,
+            channel_values: DashMap::new(),
+        }
+    }
+
+    /// Layer configured by `VOLTAGE_RAW_LAYER`
+    pub fn from_env() -> Self {
+        Self::new(RawLayerMode::from_env())
+    }
+
+    pub fn mode(&self) -> RawLayerMode {
+        self.mode
+    }
+
+    /// Whether the channel's raw values are already held
+    pub(crate) fn is_loaded(&self, blob_key: &str) -> bool {
+        self.channel_values.contains_key(blob_key)
+    }
+
+    /// Seed the channel's raw values from a stored blob (e.g. after a restart)
+    pub(crate) fn load(&self, blob_key: &str, values: BTreeMap<u32, f64>) {
+        self.channel_values
+            .entry(blob_key.to_string())
+            .or_insert(values);
+    }
+
+    /// Apply updates to the channel's raw values and return the new blob
+    pub(crate) fn update(
+        &self,
+        blob_key: &str,
+        updates: impl IntoIterator<Item = (u32, f64)>,
+    ) -> Bytes {
+        let mut values = self.channel_values.entry(blob_key.to_string()).or_default();
+        values.extend(updates);
+        encode(&values)
+    }
+}
+
+/// Blob key of a channel key (`comsrv:1001:T` → `comsrv:1001:T:rawz`)
+pub fn blob_key(channel_key: &str) -> String {
+    format!("{}:rawz", channel_key)
+}
+
+/// Encode raw values into a blob
+pub fn encode(values: &BTreeMap<u32, f64>) -> Bytes {
+    let mut out = Vec::with_capacity(2 + values.len() * 3);
+    out.push(BLOB_VERSION);
+    put_varint(&mut out, values.len() as u64);
+
+    let mut prev_id = 0u32;
+    let mut prev_int = 0i64;
+    for (&id, &value) in values {
+        let gap = u64::from(id - prev_id);
+        prev_id = id;
+        if value.fract() == 0.0 && value.abs() < MAX_EXACT_INT {
+            let int = value as i64;
+            put_varint(&mut out, gap << 1);
+            put_varint(&mut out, zigzag(int - prev_int));
+            prev_int = int;
+        } else {
+            put_varint(&mut out, (gap << 1) | 1);
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    Bytes::from(out)
+}
+
+/// Decode a blob into per-point raw values
+pub fn decode(blob: &[u8]) -> Result<BTreeMap<u32, f64>> {
+    let corrupt = |what: &str| RtdbError::SerializationError(format!("raw blob: {}", what));
+
+    let (&version, mut rest) = blob.split_first().ok_or_else(|| corrupt("empty"))?;
+    if version != BLOB_VERSION {
+        return Err(corrupt(&format!("unsupported version {}", version)));
+    }
+    let count = take_varint(&mut rest).ok_or_else(|| corrupt("truncated count"))?;
+
+    let mut values = BTreeMap::new();
+    let mut prev_id = 0u64;
+    let mut prev_int = 0i64;
+    for _ in 0..count {
+        let header = take_varint(&mut rest).ok_or_else(|| corrupt("truncated header"))?;
+        let id = prev_id
+            .checked_add(header >> 1)
+            .ok_or_else(|| corrupt("point id overflow"))?;
+        prev_id = id;
+        let id = u32::try_from(id).map_err(|_| corrupt("point id overflow"))?;
+
+        let value = if header & 1 == 0 {
+            let delta = take_varint(&mut rest).ok_or_else(|| corrupt("truncated value"))?;
+            prev_int = prev_int.wrapping_add(unzigzag(delta));
+            prev_int as f64
+        } else {
+            let (bits, tail) = rest
+                .split_first_chunk::<8>()
+                .ok_or_else(|| corrupt("truncated float"))?;
+            rest = tail;
+            f64::from_le_bytes(*bits)
+        };
+        values.insert(id, value);
+    }
+    Ok(values)
+}
+
+fn zigzag(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
+
+fn unzigzag(v: u64) -> i64 {
+    ((v >> 1) as i64) ^ -((v & 1) as i64)
+}
+
+fn put_varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+fn take_varint(input: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for (i, &byte) in input.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            *input = &input[i + 1..];
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blob_roundtrip() {
+        let values: BTreeMap<u32, f64> = [
+            (1, 4250.0),
+            (2, 4251.0),
+            (3, -12.0),
+            (10, 0.125),
+            (11, f64::NAN),
+            (1000, 65535.0),
+            (u32::MAX, 1e300),
+        ]
+        .into_iter()
+        .collect();
+
+        let blob = encode(&values);
+        let decoded = decode(&blob).unwrap();
+        assert_eq!(decoded.len(), values.len());
+        for (id, value) in &values {
+            let got = decoded[id];
+            assert!(
+                got == *value || (got.is_nan() && value.is_nan()),
+                "point {}",
+                id
+            );
+        }
+
+        // Integral register values stay far below the 8-byte hash encoding
+        let registers: BTreeMap<u32, f64> = (1..=100).map(|id| (id, 4000.0 + id as f64)).collect();
+        assert!(encode(&registers).len() < 100 * 3);
+
+        assert!(decode(&[]).is_err());
+        assert!(decode(&blob[..blob.len() - 3]).is_err());
+    }
+
+    #[test]
+    fn test_update_merges_channel_values() {
+        let layer = RawLayer::new(RawLayerMode::Compressed);
+        let key = blob_key("test:raw_layer:T");
+        layer.update(&key, [(1, 10.0), (2, 20.0)]);
+        let blob = layer.update(&key, [(2, 21.0), (3, 30.0)]);
+
+        let values = decode(&blob).unwrap();
+        assert_eq!(
+            values.into_iter().collect::<Vec<_>>(),
+            vec![(1, 10.0), (2, 21.0), (3, 30.0)]
+        );
+        assert_eq!(
+            RawLayerMode::parse("Compressed"),
+            Some(RawLayerMode::Compressed)
+        );
+        assert_eq!(RawLayerMode::parse("bogus"), None);
+    }
+}
@@ -1,5 +1,7 @@
 //! Redis implementation of RTDB traits
 
+use crate::journal::{self, EventJournal, StreamEntry, StreamRetention};
+use crate::raw_layer::RawLayer;
 use crate::traits::*;
 use anyhow::{Context, Result};
 use bytes::Bytes;
@@ -13,19 +15,29 @@ use voltage_infra::redis::RedisClient;
 /// `voltage-routing` library which handles M2C routing externally.
 pub struct RedisRtdb {
     client: Arc<RedisClient>,
+    raw_layer: RawLayer,
+    journal: Option<EventJournal>,
 }
 
 impl RedisRtdb {
     /// Create new Redis RTDB from URL
     pub async fn new(url: &str) -> Result<Self> {
-        Ok(Self {
-            client: Arc::new(RedisClient::new(url).await?),
-        })
+        Ok(Self::from_client(Arc::new(RedisClient::new(url).await?)))
     }
 
     /// Create from existing RedisClient
     pub fn from_client(client: Arc<RedisClient>) -> Self {
-        Self { client }
+        Self {
+            client,
+            raw_layer: RawLayer::from_env(),
+            journal: EventJournal::from_env(),
+        }
+    }
+
+    /// Journal point writes with `journal` instead of the `VOLTAGE_EVENT_JOURNAL` default
+    pub fn with_event_journal(mut self, journal: Option<EventJournal>) -> Self {
+        self.journal = journal;
+        self
     }
 
     /// Get reference to underlying Redis client
@@ -35,6 +47,27 @@ impl RedisRtdb {
     pub fn client(&self) -> &Arc<RedisClient> {
         &self.client
     }
+
+    /// Append `PointWritten` entries for the journaled hashes among `hashes`
+    ///
+    /// Runs after the hash write succeeded; a failed append is logged rather
+    /// than returned, since the write itself is already stored.
+    async fn journal_points(&self, hashes: &[(&str, &[(String, Bytes)])]) {
+        let Some(journal) = &self.journal else {
+            return;
+        };
+        let entries = journal.point_entries(hashes.iter().copied());
+        if entries.is_empty() {
+            return;
+        }
+        let count = entries.len();
+        if let Err(e) = self
+            .stream_add(journal::POINT_STREAM, entries, journal.retention())
+            .await
+        {
+            tracing::warn!("Event journal: {} entries not recorded: {:#}", count, e);
+        }
+    }
 }
 
 impl Rtdb for RedisRtdb {
@@ -42,6 +75,10 @@ impl Rtdb for RedisRtdb {
         self
     }
 
+    fn raw_layer(&self) -> &RawLayer {
+        &self.raw_layer
+    }
+
     async fn get<'a>(&'a self, key: &'a str) -> Result<Option<Bytes>> {
         let value: Option<String> = self.client.get(key).await.map_err(|e| anyhow::anyhow!(e))?;
         Ok(value.map(Bytes::from))
@@ -71,6 +108,34 @@ impl Rtdb for RedisRtdb {
             .map_err(|e| anyhow::anyhow!(e))
     }
 
+    async fn key_type<'a>(&'a self, key: &'a str) -> Result<Option<KeyType>> {
+        let name = self
+            .client
+            .key_type(key)
+            .await
+            .map_err(|e| anyhow::anyhow!(e))?;
+        Ok(KeyType::from_redis(&name))
+    }
+
+    async fn lease_acquire<'a>(
+        &'a self,
+        key: &'a str,
+        owner: &'a str,
+        ttl_ms: u64,
+    ) -> Result<bool> {
+        self.client
+            .lease_acquire(key, owner, ttl_ms)
+            .await
+            .map_err(|e| anyhow::anyhow!(e))
+    }
+
+    async fn lease_release<'a>(&'a self, key: &'a str, owner: &'a str) -> Result<bool> {
+        self.client
+            .lease_release(key, owner)
+            .await
+            .map_err(|e| anyhow::anyhow!(e))
+    }
+
     async fn incrbyfloat<'a>(&'a self, key: &'a str, increment: f64) -> Result<f64> {
         self.client
             .incrbyfloat(key, increment)
@@ -79,22 +144,21 @@ impl Rtdb for RedisRtdb {
     }
 
     async fn hash_set<'a>(&'a self, key: &'a str, field: &'a str, value: Bytes) -> Result<()> {
-        let s = std::str::from_utf8(value.as_ref())
-            .context("UTF-8 conversion failed")?
-            .to_owned();
+        // Binary-safe: compressed raw blobs are not UTF-8
         self.client
-            .hset(key, field, s)
+            .hset_bytes(key, field, value.clone())
             .await
-            .map_err(|e| anyhow::anyhow!(e))
+            .map_err(|e| anyhow::anyhow!(e))?;
+        self.journal_points(&[(key, &[(field.to_string(), value)])])
+            .await;
+        Ok(())
     }
 
     async fn hash_get<'a>(&'a self, key: &'a str, field: &'a str) -> Result<Option<Bytes>> {
-        let value: Option<String> = self
-            .client
-            .hget(key, field)
+        self.client
+            .hget_bytes(key, field)
             .await
-            .map_err(|e| anyhow::anyhow!(e))?;
-        Ok(value.map(Bytes::from))
+            .map_err(|e| anyhow::anyhow!(e))
     }
 
     async fn hash_mget<'a>(
@@ -111,19 +175,12 @@ impl Rtdb for RedisRtdb {
     }
 
     async fn hash_mset<'a>(&'a self, key: &'a str, fields: Vec<(String, Bytes)>) -> Result<()> {
-        let string_fields: Result<Vec<(String, String)>> = fields
-            .into_iter()
-            .map(|(k, v)| {
-                let s = std::str::from_utf8(v.as_ref())
-                    .context("UTF-8 conversion failed")?
-                    .to_owned();
-                Ok((k, s))
-            })
-            .collect();
         self.client
-            .hmset(key, &string_fields?)
+            .hmset_bytes(key, &fields)
             .await
-            .map_err(|e| anyhow::anyhow!(e))
+            .map_err(|e| anyhow::anyhow!(e))?;
+        self.journal_points(&[(key, &fields)]).await;
+        Ok(())
     }
 
     async fn hash_get_all<'a>(&'a self, key: &'a str) -> Result<HashMap<String, Bytes>> {
@@ -219,6 +276,31 @@ impl Rtdb for RedisRtdb {
             .map_err(|e| anyhow::anyhow!(e))
     }
 
+    async fn stream_add<'a>(
+        &'a self,
+        key: &'a str,
+        entries: Vec<Vec<(String, Bytes)>>,
+        retention: StreamRetention,
+    ) -> Result<Vec<String>> {
+        let min_id = retention.min_id(journal::now_ms());
+        self.client
+            .xadd_trimmed(key, &entries, retention.max_len, &min_id)
+            .await
+            .map_err(|e| anyhow::anyhow!(e))
+    }
+
+    async fn stream_range<'a>(
+        &'a self,
+        key: &'a str,
+        after: Option<&'a str>,
+        count: usize,
+    ) -> Result<Vec<StreamEntry>> {
+        self.client
+            .xrange_after(key, after, count)
+            .await
+            .map_err(|e| anyhow::anyhow!(e))
+    }
+
     async fn scan_match<'a>(&'a self, pattern: &'a str) -> Result<Vec<String>> {
         self.client
             .scan_match(pattern)
@@ -269,27 +351,17 @@ impl Rtdb for RedisRtdb {
             return Ok(());
         }
 
-        // Convert Bytes to String for the client (let compiler infer complex type)
-        let string_operations: Result<Vec<_>> = operations
-            .into_iter()
-            .map(|(key, fields)| {
-                let string_fields: Result<Vec<_>> = fields
-                    .into_iter()
-                    .map(|(f, v)| {
-                        let s = std::str::from_utf8(v.as_ref())
-                            .context("UTF-8 conversion failed")?
-                            .to_owned();
-                        Ok((f, s))
-                    })
-                    .collect();
-                Ok((key, string_fields?))
-            })
-            .collect();
-
+        // Values are sent as-is (binary-safe, no UTF-8 round trip)
         self.client
-            .pipeline_hmset(&string_operations?)
+            .pipeline_hmset_bytes(&operations)
             .await
-            .map_err(|e| anyhow::anyhow!(e))
+            .map_err(|e| anyhow::anyhow!(e))?;
+        let hashes: Vec<_> = operations
+            .iter()
+            .map(|(key, fields)| (key.as_str(), fields.as_slice()))
+            .collect();
+        self.journal_points(&hashes).await;
+        Ok(())
     }
 }
 
@@ -345,6 +417,28 @@ mod tests {
         rtdb.del("test:hash").await.unwrap();
     }
 
+    #[tokio::test]
+    #[ignore = "Requires Redis connection"]
+    async fn test_redis_rtdb_binary_hash_values() {
+        let rtdb = RedisRtdb::new("redis://localhost:6379")
+            .await
+            .expect("Failed to connect to Redis");
+
+        // Compressed raw blobs are arbitrary bytes
+        let blob = crate::raw_layer::encode(&[(1, 0.125), (2, 4250.0)].into_iter().collect());
+        rtdb.pipeline_hash_mset(vec![(
+            "test:binary:rawz".to_string(),
+            vec![("blob".to_string(), blob.clone())],
+        )])
+        .await
+        .unwrap();
+        let value = rtdb.hash_get("test:binary:rawz", "blob").await.unwrap();
+        assert_eq!(value, Some(blob));
+
+        // Cleanup
+        rtdb.del("test:binary:rawz").await.unwrap();
+    }
+
     #[tokio::test]
     #[ignore = "Requires Redis connection"]
     async fn test_redis_rtdb_list_operations() {
@@ -449,4 +543,44 @@ mod tests {
         // Cleanup
         rtdb.del("test:trim_list").await.unwrap();
     }
+
+    #[tokio::test]
+    #[ignore = "Requires Redis connection"]
+    async fn test_redis_rtdb_event_journal() {
+        let journal = EventJournal::new(
+            vec!["test:journal:*:T".to_string()],
+            StreamRetention::default(),
+        );
+        let rtdb = RedisRtdb::new("redis://localhost:6379")
+            .await
+            .expect("Failed to connect to Redis")
+            .with_event_journal(Some(journal));
+        rtdb.del(journal::POINT_STREAM).await.unwrap();
+
+        rtdb.hash_mset(
+            "test:journal:1:T",
+            vec![("1".to_string(), Bytes::from("230.5"))],
+        )
+        .await
+        .unwrap();
+        rtdb.hash_set("test:journal:2:T", "3", Bytes::from("1"))
+            .await
+            .unwrap();
+
+        let entries = rtdb
+            .stream_range(journal::POINT_STREAM, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        let rest = rtdb
+            .stream_range(journal::POINT_STREAM, Some(&entries[0].0), 10)
+            .await
+            .unwrap();
+        assert_eq!(rest, entries[1..].to_vec());
+
+        // Cleanup
+        rtdb.del(journal::POINT_STREAM).await.unwrap();
+        rtdb.del("test:journal:1:T").await.unwrap();
+        rtdb.del("test:journal:2:T").await.unwrap();
+    }
 }
@@ -18,14 +18,29 @@
 //!
 //! String-based lookups (`lookup_c2c("1001:T:1")`) parse the key first, then query the tuple index.
 //! Prefix queries (`get_c2c_by_prefix("1001:")`) iterate and filter the tuple index.
+//!
+//! ## Diagnostics
+//!
+//! Every lookup updates per-table hit/miss counters (one relaxed atomic add).
+//! With miss tracing enabled, missed keys are also kept in a small ring buffer
+//! so "route exists in SQLite but the cache says no" can be traced to the
+//! exact key; `diagnostics()` dumps counters, recent misses and the cached
+//! entries of one instance or channel.
 
 use arc_swap::ArcSwap;
+use parking_lot::Mutex;
 use rustc_hash::FxHashMap;
-use std::collections::HashMap;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
 use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use voltage_model::PointType;
 
+/// Capacity of the recent-miss ring buffer
+pub const MISS_RING_CAPACITY: usize = 128;
+
 // ============================================================================
 // Route Target Types
 // ============================================================================
@@ -241,13 +256,30 @@ struct RoutingTables {
 pub struct RoutingCache {
     /// Atomic-swappable routing tables snapshot
     tables: ArcSwap<RoutingTables>,
+    /// Lookup counters per table
+    c2m_lookups: LookupCounters,
+    m2c_lookups: LookupCounters,
+    c2c_lookups: LookupCounters,
+    /// Whether missed keys are recorded in `recent_misses`
+    trace_misses: AtomicBool,
+    /// Most recent misses (only while tracing), oldest first
+    recent_misses: Mutex<VecDeque<RouteMiss>>,
 }
 
 impl RoutingCache {
     /// Create an empty routing cache
     pub fn new() -> Self {
+        Self::with_tables(RoutingTables::default())
+    }
+
+    fn with_tables(tables: RoutingTables) -> Self {
         Self {
-            tables: ArcSwap::from_pointee(RoutingTables::default()),
+            tables: ArcSwap::from_pointee(tables),
+            c2m_lookups: LookupCounters::default(),
+            m2c_lookups: LookupCounters::default(),
+            c2c_lookups: LookupCounters::default(),
+            trace_misses: AtomicBool::new(false),
+            recent_misses: Mutex::new(VecDeque::new()),
         }
     }
 
@@ -291,9 +323,7 @@ impl RoutingCache {
             }
         }
 
-        Self {
-            tables: ArcSwap::from_pointee(tables),
-        }
+        Self::with_tables(tables)
     }
 
     /// Update routing cache with new data (atomic replacement)
@@ -349,8 +379,8 @@ impl RoutingCache {
     /// }
     /// ```
     pub fn lookup_c2m(&self, key: &str) -> Option<C2MTarget> {
-        let structured_key = parse_route_key(key)?;
-        self.tables.load().c2m.get(&structured_key).copied()
+        let (channel_id, point_type, point_id) = parse_route_key(key)?;
+        self.lookup_c2m_by_parts(channel_id, point_type, point_id)
     }
 
     /// Lookup C2M routing by structured key (zero-allocation)
@@ -380,11 +410,10 @@ impl RoutingCache {
         point_type: PointType,
         point_id: u32,
     ) -> Option<C2MTarget> {
-        self.tables
-            .load()
-            .c2m
-            .get(&(channel_id, point_type, point_id))
-            .copied()
+        let key = (channel_id, point_type, point_id);
+        let target = self.tables.load().c2m.get(&key).copied();
+        self.record_lookup(&self.c2m_lookups, RouteTable::C2m, key, target.is_some());
+        target
     }
 
     /// Lookup M2C routing by string key (parses key first)
@@ -406,8 +435,8 @@ impl RoutingCache {
     /// }
     /// ```
     pub fn lookup_m2c(&self, key: &str) -> Option<M2CTarget> {
-        let structured_key = parse_route_key(key)?;
-        self.tables.load().m2c.get(&structured_key).copied()
+        let (instance_id, point_type, point_id) = parse_route_key(key)?;
+        self.lookup_m2c_by_parts(instance_id, point_type, point_id)
     }
 
     /// Lookup M2C routing by structured key (zero-allocation)
@@ -418,11 +447,10 @@ impl RoutingCache {
         point_type: PointType,
         point_id: u32,
     ) -> Option<M2CTarget> {
-        self.tables
-            .load()
-            .m2c
-            .get(&(instance_id, point_type, point_id))
-            .copied()
+        let key = (instance_id, point_type, point_id);
+        let target = self.tables.load().m2c.get(&key).copied();
+        self.record_lookup(&self.m2c_lookups, RouteTable::M2c, key, target.is_some());
+        target
     }
 
     /// Lookup C2C routing by string key (parses key first)
@@ -446,8 +474,8 @@ impl RoutingCache {
     /// }
     /// ```
     pub fn lookup_c2c(&self, key: &str) -> Option<C2CTarget> {
-        let structured_key = parse_route_key(key)?;
-        self.tables.load().c2c.get(&structured_key).copied()
+        let (channel_id, point_type, point_id) = parse_route_key(key)?;
+        self.lookup_c2c_by_parts(channel_id, point_type, point_id)
     }
 
     /// Lookup C2C routing by structured key (zero-allocation)
@@ -477,11 +505,10 @@ impl RoutingCache {
         point_type: PointType,
         point_id: u32,
     ) -> Option<C2CTarget> {
-        self.tables
-            .load()
-            .c2c
-            .get(&(channel_id, point_type, point_id))
-            .copied()
+        let key = (channel_id, point_type, point_id);
+        let target = self.tables.load().c2c.get(&key).copied();
+        self.record_lookup(&self.c2c_lookups, RouteTable::C2c, key, target.is_some());
+        target
     }
 
     /// Insert C2C routing entry from string keys (copy-on-write)
@@ -611,6 +638,142 @@ impl RoutingCache {
             c2m_count: tables.c2m.len(),
             m2c_count: tables.m2c.len(),
             c2c_count: tables.c2c.len(),
+            c2m_lookups: self.c2m_lookups.snapshot(),
+            m2c_lookups: self.m2c_lookups.snapshot(),
+            c2c_lookups: self.c2c_lookups.snapshot(),
+            miss_tracing: self.miss_tracing(),
+        }
+    }
+
+    #[inline]
+    fn record_lookup(
+        &self,
+        counters: &LookupCounters,
+        table: RouteTable,
+        key: StructuredRouteKey,
+        hit: bool,
+    ) {
+        if hit {
+            counters.hits.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        counters.misses.fetch_add(1, Ordering::Relaxed);
+        if self.trace_misses.load(Ordering::Relaxed) {
+            self.record_miss(table, key);
+        }
+    }
+
+    #[cold]
+    fn record_miss(&self, table: RouteTable, key: StructuredRouteKey) {
+        let miss = RouteMiss {
+            table,
+            key: format_route_key(&key),
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+        };
+        let mut misses = self.recent_misses.lock();
+        if misses.len() == MISS_RING_CAPACITY {
+            misses.pop_front();
+        }
+        misses.push_back(miss);
+    }
+
+    /// Enable or disable recording of missed keys
+    ///
+    /// Disabling keeps the misses recorded so far.
+    pub fn set_miss_tracing(&self, enabled: bool) {
+        self.trace_misses.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Whether missed keys are being recorded
+    pub fn miss_tracing(&self) -> bool {
+        self.trace_misses.load(Ordering::Relaxed)
+    }
+
+    /// Most recent missed lookups, oldest first (empty unless tracing was enabled)
+    pub fn recent_misses(&self) -> Vec<RouteMiss> {
+        self.recent_misses.lock().iter().cloned().collect()
+    }
+
+    /// Reset hit/miss counters and forget recorded misses
+    pub fn reset_lookup_stats(&self) {
+        for counters in [&self.c2m_lookups, &self.m2c_lookups, &self.c2c_lookups] {
+            counters.hits.store(0, Ordering::Relaxed);
+            counters.misses.store(0, Ordering::Relaxed);
+        }
+        self.recent_misses.lock().clear();
+    }
+
+    /// Cached entries touching an instance and/or channel
+    ///
+    /// An entry matches an instance when it is the C2M target or the M2C
+    /// source, and a channel when it is the source or target channel. Without
+    /// filters all entries are returned. Sorted by table, then source key.
+    pub fn entries(&self, filter: &RouteFilter) -> Vec<RouteEntry> {
+        let tables = self.tables.load();
+        let table_wanted = |table: RouteTable| filter.table.is_none_or(|t| t == table);
+        let instance_matches = |id: Option<u32>| {
+            filter
+                .instance_id
+                .is_none_or(|wanted| id.is_some_and(|id| id == wanted))
+        };
+        let channel_matches =
+            |ids: &[u32]| filter.channel_id.is_none_or(|wanted| ids.contains(&wanted));
+
+        let mut entries = Vec::new();
+        if table_wanted(RouteTable::C2m) {
+            entries.extend(
+                tables
+                    .c2m
+                    .iter()
+                    .filter(|(k, v)| {
+                        instance_matches(Some(v.instance_id)) && channel_matches(&[k.0])
+                    })
+                    .map(|(k, v)| RouteEntry::new(RouteTable::C2m, k, v)),
+            );
+        }
+        if table_wanted(RouteTable::M2c) {
+            entries.extend(
+                tables
+                    .m2c
+                    .iter()
+                    .filter(|(k, v)| {
+                        instance_matches(Some(k.0)) && channel_matches(&[v.channel_id])
+                    })
+                    .map(|(k, v)| RouteEntry::new(RouteTable::M2c, k, v)),
+            );
+        }
+        if table_wanted(RouteTable::C2c) {
+            entries.extend(
+                tables
+                    .c2c
+                    .iter()
+                    .filter(|(k, v)| {
+                        instance_matches(None) && channel_matches(&[k.0, v.channel_id])
+                    })
+                    .map(|(k, v)| RouteEntry::new(RouteTable::C2c, k, v)),
+            );
+        }
+
+        entries.sort_by(|a, b| (a.table, &a.source).cmp(&(b.table, &b.source)));
+        entries
+    }
+
+    /// Counters, recent misses and filtered entries in one snapshot
+    pub fn diagnostics(&self, filter: &RouteFilter) -> RoutingCacheDiagnostics {
+        let recent_misses = self
+            .recent_misses()
+            .into_iter()
+            .filter(|miss| {
+                filter.table.is_none_or(|t| t == miss.table) && miss_matches(miss, filter)
+            })
+            .collect();
+        RoutingCacheDiagnostics {
+            stats: self.stats(),
+            recent_misses,
+            entries: self.entries(filter),
         }
     }
 
@@ -649,11 +812,115 @@ impl Default for RoutingCache {
 }
 
 /// Routing cache statistics
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct RoutingCacheStats {
     pub c2m_count: usize,
     pub m2c_count: usize,
     pub c2c_count: usize,
+    pub c2m_lookups: LookupStats,
+    pub m2c_lookups: LookupStats,
+    pub c2c_lookups: LookupStats,
+    /// Whether missed keys are being recorded
+    pub miss_tracing: bool,
+}
+
+/// Routing table identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteTable {
+    C2m,
+    M2c,
+    C2c,
+}
+
+impl RouteTable {
+    /// Parse `c2m` / `m2c` / `c2c` (case-insensitive)
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "c2m" => Some(Self::C2m),
+            "m2c" => Some(Self::M2c),
+            "c2c" => Some(Self::C2c),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct LookupCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl LookupCounters {
+    fn snapshot(&self) -> LookupStats {
+        LookupStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Hit/miss counters of one routing table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct LookupStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Lookup that found no route
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RouteMiss {
+    pub table: RouteTable,
+    /// Source key, e.g. `1001:T:5` (C2M/C2C) or `23:A:4` (M2C)
+    pub key: String,
+    pub timestamp_ms: u64,
+}
+
+/// Filter for routing cache dumps
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RouteFilter {
+    pub instance_id: Option<u32>,
+    pub channel_id: Option<u32>,
+    pub table: Option<RouteTable>,
+}
+
+/// Cached routing entry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RouteEntry {
+    pub table: RouteTable,
+    /// Source key (`channel:type:point` or `instance:type:point`)
+    pub source: String,
+    /// Target key
+    pub target: String,
+}
+
+impl RouteEntry {
+    fn new(table: RouteTable, key: &StructuredRouteKey, target: &impl fmt::Display) -> Self {
+        Self {
+            table,
+            source: format_route_key(key),
+            target: target.to_string(),
+        }
+    }
+}
+
+/// Routing cache diagnostics snapshot
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutingCacheDiagnostics {
+    pub stats: RoutingCacheStats,
+    pub recent_misses: Vec<RouteMiss>,
+    pub entries: Vec<RouteEntry>,
+}
+
+/// Whether a miss concerns the filtered instance/channel (misses only carry the source)
+fn miss_matches(miss: &RouteMiss, filter: &RouteFilter) -> bool {
+    let source_id: Option<u32> = miss.key.split(':').next().and_then(|id| id.parse().ok());
+    let (instance, channel) = match miss.table {
+        RouteTable::M2c => (source_id, None),
+        RouteTable::C2m | RouteTable::C2c => (None, source_id),
+    };
+    filter.instance_id.is_none_or(|id| instance == Some(id))
+        && filter.channel_id.is_none_or(|id| channel == Some(id))
 }
 
 #[cfg(test)]
@@ -913,4 +1180,95 @@ mod tests {
         });
         assert!(has_m2c_route);
     }
+
+    #[test]
+    fn test_lookup_counters_and_miss_tracing() {
+        let mut c2m_data = HashMap::new();
+        c2m_data.insert("1001:T:1".to_string(), "5:M:10".to_string());
+        let cache = RoutingCache::from_maps(c2m_data, HashMap::new(), HashMap::new());
+
+        assert!(cache.lookup_c2m("1001:T:1").is_some());
+        assert!(cache
+            .lookup_c2m_by_parts(1001, PointType::Telemetry, 2)
+            .is_none());
+        assert!(cache.lookup_m2c("5:A:1").is_none());
+        // Misses are counted but not recorded while tracing is off
+        assert!(cache.recent_misses().is_empty());
+
+        cache.set_miss_tracing(true);
+        for point_id in 0..(MISS_RING_CAPACITY as u32 + 3) {
+            cache.lookup_c2c_by_parts(1001, PointType::Signal, point_id);
+        }
+
+        let stats = cache.stats();
+        assert_eq!(stats.c2m_lookups, LookupStats { hits: 1, misses: 1 });
+        assert_eq!(stats.m2c_lookups, LookupStats { hits: 0, misses: 1 });
+        assert_eq!(stats.c2c_lookups.misses, MISS_RING_CAPACITY as u64 + 3);
+        assert!(stats.miss_tracing);
+
+        // Ring buffer keeps the most recent misses
+        let misses = cache.recent_misses();
+        assert_eq!(misses.len(), MISS_RING_CAPACITY);
+        assert_eq!(misses[0].key, "1001:S:3");
+        assert_eq!(misses[0].table, RouteTable::C2c);
+
+        cache.reset_lookup_stats();
+        assert_eq!(cache.stats().c2c_lookups, LookupStats::default());
+        assert!(cache.recent_misses().is_empty());
+    }
+
+    #[test]
+    fn test_entries_filtered_by_instance_and_channel() {
+        let mut c2m_data = HashMap::new();
+        c2m_data.insert("1001:T:1".to_string(), "5:M:10".to_string());
+        c2m_data.insert("1002:T:1".to_string(), "6:M:10".to_string());
+        let mut m2c_data = HashMap::new();
+        m2c_data.insert("5:A:1".to_string(), "1002:A:3".to_string());
+        let mut c2c_data = HashMap::new();
+        c2c_data.insert("1003:T:1".to_string(), "1001:T:9".to_string());
+        let cache = RoutingCache::from_maps(c2m_data, m2c_data, c2c_data);
+
+        let sources = |filter: RouteFilter| -> Vec<String> {
+            cache
+                .entries(&filter)
+                .into_iter()
+                .map(|e| format!("{:?} {}->{}", e.table, e.source, e.target))
+                .collect()
+        };
+
+        assert_eq!(
+            sources(RouteFilter {
+                instance_id: Some(5),
+                ..Default::default()
+            }),
+            vec!["C2m 1001:T:1->5:M:10", "M2c 5:A:1->1002:A:3"]
+        );
+        assert_eq!(
+            sources(RouteFilter {
+                channel_id: Some(1001),
+                ..Default::default()
+            }),
+            vec!["C2m 1001:T:1->5:M:10", "C2c 1003:T:1->1001:T:9"]
+        );
+        assert_eq!(
+            sources(RouteFilter {
+                channel_id: Some(1002),
+                table: Some(RouteTable::M2c),
+                ..Default::default()
+            }),
+            vec!["M2c 5:A:1->1002:A:3"]
+        );
+        assert_eq!(cache.entries(&RouteFilter::default()).len(), 4);
+
+        cache.set_miss_tracing(true);
+        cache.lookup_c2m("1001:T:7");
+        cache.lookup_c2m("1002:T:7");
+        let diagnostics = cache.diagnostics(&RouteFilter {
+            channel_id: Some(1001),
+            ..Default::default()
+        });
+        assert_eq!(diagnostics.recent_misses.len(), 1);
+        assert_eq!(diagnostics.recent_misses[0].key, "1001:T:7");
+        assert_eq!(diagnostics.stats.c2m_lookups.misses, 2);
+    }
 }
@@ -6,6 +6,34 @@ use std::any::Any;
 use std::collections::HashMap;
 use std::future::Future;
 
+use crate::journal::{StreamEntry, StreamRetention};
+use crate::raw_layer::RawLayer;
+
+/// Type of a stored key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyType {
+    String,
+    Hash,
+    List,
+    Set,
+    /// Types the trait has no operations for (zset, stream, ...)
+    Other(String),
+}
+
+impl KeyType {
+    /// Parse a Redis TYPE reply; `None` for "none"
+    pub fn from_redis(name: &str) -> Option<Self> {
+        Some(match name {
+            "none" => return None,
+            "string" => Self::String,
+            "hash" => Self::Hash,
+            "list" => Self::List,
+            "set" => Self::Set,
+            other => Self::Other(other.to_string()),
+        })
+    }
+}
+
 /// Unified RTDB Storage Trait
 ///
 /// Provides complete storage interface for VoltageEMS, combining:
@@ -29,6 +57,9 @@ pub trait Rtdb: Send + Sync + 'static {
     /// like RedisRtdb or MemoryRtdb when needed.
     fn as_any(&self) -> &dyn Any;
 
+    /// Raw value layer of this instance (mode and compressed-blob state)
+    fn raw_layer(&self) -> &RawLayer;
+
     // ========== Basic Key-Value Operations ==========
 
     /// Get value by key
@@ -47,6 +78,34 @@ pub trait Rtdb: Send + Sync + 'static {
     /// Check if key exists
     fn exists<'a>(&'a self, key: &'a str) -> impl Future<Output = Result<bool>> + Send + 'a;
 
+    /// Type of a key (Redis TYPE), `None` if the key does not exist
+    fn key_type<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> impl Future<Output = Result<Option<KeyType>>> + Send + 'a;
+
+    /// Take or renew a lease
+    ///
+    /// Sets `key` to `owner` with a `ttl_ms` expiry when the key is absent or
+    /// already holds `owner`, atomically; returns whether `owner` holds the
+    /// lease afterwards. Used for leader election between service instances.
+    ///
+    /// - **RedisRtdb**: compare-and-set Lua script (`SET ... PX`).
+    /// - **MemoryRtdb**: the expiry is only honoured by the lease operations.
+    fn lease_acquire<'a>(
+        &'a self,
+        key: &'a str,
+        owner: &'a str,
+        ttl_ms: u64,
+    ) -> impl Future<Output = Result<bool>> + Send + 'a;
+
+    /// Delete a lease if `owner` holds it; returns whether it was deleted
+    fn lease_release<'a>(
+        &'a self,
+        key: &'a str,
+        owner: &'a str,
+    ) -> impl Future<Output = Result<bool>> + Send + 'a;
+
     /// Increment key by float value (Redis INCRBYFLOAT)
     ///
     /// Returns the new value after incrementing.
@@ -278,6 +337,28 @@ pub trait Rtdb: Send + Sync + 'static {
         key: &'a str,
     ) -> impl Future<Output = Result<Vec<String>>> + Send + 'a;
 
+    // ========== Stream Operations ==========
+
+    /// Append entries to a stream, then apply `retention` (Redis XADD + XTRIM)
+    ///
+    /// Returns the IDs assigned to the entries, in order.
+    fn stream_add<'a>(
+        &'a self,
+        key: &'a str,
+        entries: Vec<Vec<(String, Bytes)>>,
+        retention: StreamRetention,
+    ) -> impl Future<Output = Result<Vec<String>>> + Send + 'a;
+
+    /// Read up to `count` stream entries after the `after` ID, oldest first (Redis XRANGE)
+    ///
+    /// `after = None` reads from the start of the stream.
+    fn stream_range<'a>(
+        &'a self,
+        key: &'a str,
+        after: Option<&'a str>,
+        count: usize,
+    ) -> impl Future<Output = Result<Vec<StreamEntry>>> + Send + 'a;
+
     // ========== Key Scanning Operations ==========
 
     /// Scan keys matching a pattern (Redis SCAN with MATCH)
@@ -117,6 +117,10 @@ pub struct WriteBuffer {
     /// Pending data: key -> {field -> value}
     /// Field names use Arc<str> for O(1) cloning in multi-layer writes
     pending: DashMap<String, DashMap<Arc<str>, Bytes>>,
+    /// Pending raw values: channel key -> {point_id -> raw value}
+    ///
+    /// Laid out at flush time per the flushing RTDB's raw layer.
+    pending_raw: DashMap<String, DashMap<u32, f64>>,
     /// Notification for forced flush
     flush_notify: Arc<Notify>,
     /// Configuration
@@ -130,6 +134,7 @@ impl WriteBuffer {
     pub fn new(config: WriteBufferConfig) -> Self {
         Self {
             pending: DashMap::new(),
+            pending_raw: DashMap::new(),
             flush_notify: Arc::new(Notify::new()),
             config,
             stats: WriteBufferStats::default(),
@@ -215,6 +220,50 @@ impl WriteBuffer {
         }
     }
 
+    /// Buffer raw (pre-transform) values of a channel (returns immediately)
+    ///
+    /// # Arguments
+    /// * `channel_key` - Base channel key (e.g. "comsrv:1001:T")
+    /// * `values` - (point_id, raw_value) pairs
+    pub fn buffer_raw_values(&self, channel_key: &str, values: Vec<(u32, f64)>) {
+        if values.is_empty() {
+            return;
+        }
+
+        let count = values.len() as u64;
+        let len = {
+            let entry = self.pending_raw.entry(channel_key.to_string()).or_default();
+            for (point_id, value) in values {
+                entry.insert(point_id, value);
+            }
+            entry.len()
+        };
+
+        self.stats
+            .buffered_writes
+            .fetch_add(count, Ordering::Relaxed);
+
+        if len >= self.config.max_fields_per_key {
+            self.stats.forced_flushes.fetch_add(1, Ordering::Relaxed);
+            self.flush_notify.notify_one();
+        }
+    }
+
+    /// Collect and clear all pending raw values
+    fn drain_pending_raw(&self) -> Vec<(String, Vec<(u32, f64)>)> {
+        let mut channels = Vec::with_capacity(self.pending_raw.len());
+        self.pending_raw.retain(|key, values| {
+            if !values.is_empty() {
+                channels.push((
+                    key.clone(),
+                    values.iter().map(|e| (*e.key(), *e.value())).collect(),
+                ));
+            }
+            false
+        });
+        channels
+    }
+
     /// Collect and clear all pending data
     ///
     /// Optimized to avoid double iteration and unnecessary clones.
@@ -250,12 +299,17 @@ impl WriteBuffer {
 
     /// Get the number of pending keys
     pub fn pending_keys(&self) -> usize {
-        self.pending.len()
+        self.pending.len() + self.pending_raw.len()
     }
 
     /// Get the total number of pending fields across all keys
     pub fn pending_fields(&self) -> usize {
-        self.pending.iter().map(|e| e.value().len()).sum()
+        self.pending.iter().map(|e| e.value().len()).sum::<usize>()
+            + self
+                .pending_raw
+                .iter()
+                .map(|e| e.value().len())
+                .sum::<usize>()
     }
 
     /// Background flush loop - runs until cancelled
@@ -335,7 +389,10 @@ impl WriteBuffer {
     where
         R: Rtdb,
     {
-        let operations = self.drain_pending();
+        let mut operations = self.drain_pending();
+        for (channel_key, raw_values) in self.drain_pending_raw() {
+            operations.extend(crate::helpers::raw_layer_hash(rtdb, &channel_key, raw_values).await);
+        }
 
         if operations.is_empty() {
             return Ok(0);
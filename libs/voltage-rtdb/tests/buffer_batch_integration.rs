@@ -9,11 +9,16 @@
 #![allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
 
 use bytes::Bytes;
+use std::collections::BTreeMap;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Notify;
-use voltage_rtdb::{MemoryRtdb, Rtdb, WriteBuffer, WriteBufferConfig};
+use voltage_model::PointType;
+use voltage_rtdb::{
+    helpers, raw_layer, KeySpaceConfig, MemoryRtdb, RawLayerMode, Rtdb, WriteBuffer,
+    WriteBufferConfig,
+};
 
 /// Creates a test RTDB
 fn create_test_rtdb() -> Arc<MemoryRtdb> {
@@ -400,6 +405,55 @@ async fn test_write_buffer_three_layer_data() {
     assert_eq!(raw_value, Some(Bytes::from("2205")));
 }
 
+#[tokio::test]
+async fn test_write_buffer_compressed_raw_layer_after_restart() {
+    let buffer = WriteBuffer::new(WriteBufferConfig::default());
+    let blob_key = raw_layer::blob_key("comsrv:1001:T");
+
+    // Blob left by the previous process; this instance has not loaded it
+    let rtdb = Arc::new(MemoryRtdb::new().with_raw_layer_mode(RawLayerMode::Compressed));
+    let stored: BTreeMap<u32, f64> = [(1, 100.0), (2, 200.0), (3, 300.0)].into_iter().collect();
+    rtdb.hash_set(&blob_key, raw_layer::BLOB_FIELD, raw_layer::encode(&stored))
+        .await
+        .unwrap();
+
+    // Two flushes, each carrying only some of the points
+    helpers::buffer_channel_points(&buffer, "comsrv:1001:T", vec![(2, 20.5, 201.0)], 1);
+    buffer.flush(&*rtdb).await.unwrap();
+    helpers::buffer_channel_points(&buffer, "comsrv:1001:T", vec![(4, 40.5, 400.0)], 2);
+    buffer.flush(&*rtdb).await.unwrap();
+
+    let raw = helpers::read_raw_values(
+        &*rtdb,
+        KeySpaceConfig::production_cached(),
+        1001,
+        PointType::Telemetry,
+    )
+    .await
+    .unwrap();
+    let mut raw: Vec<_> = raw.into_iter().collect();
+    raw.sort_by_key(|(id, _)| *id);
+    assert_eq!(raw, vec![(1, 100.0), (2, 201.0), (3, 300.0), (4, 400.0)]);
+    assert!(!rtdb.exists("comsrv:1001:T:raw").await.unwrap());
+
+    // Another instance keeps its own channel values
+    let other = Arc::new(MemoryRtdb::new().with_raw_layer_mode(RawLayerMode::Compressed));
+    helpers::buffer_channel_points(&buffer, "comsrv:1001:T", vec![(5, 50.5, 500.0)], 3);
+    buffer.flush(&*other).await.unwrap();
+    let blob = other
+        .hash_get(&blob_key, raw_layer::BLOB_FIELD)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        raw_layer::decode(&blob)
+            .unwrap()
+            .into_iter()
+            .collect::<Vec<_>>(),
+        vec![(5, 500.0)]
+    );
+}
+
 // ============================================================================
 // buffer_hash_mset Tests
 // ============================================================================
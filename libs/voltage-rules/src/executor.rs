@@ -7,14 +7,17 @@
 
 use crate::error::Result;
 use crate::logger::format_conditions;
+use crate::point_names::PointNames;
 use crate::types::{
-    CalculationRule, FlowCondition, Rule, RuleNode, RuleSwitchBranch, RuleValueAssignment,
-    RuleVariable,
+    CalculationRule, FlowCondition, HttpRequestRule, HttpSuccessCriteria, InstanceSelector, Rule,
+    RuleNode, RuleSwitchBranch, RuleValueAssignment, RuleVariable, SelectorPoint, SelectorReduce,
+    SelectorTarget,
 };
 use serde::Serialize;
 use std::collections::HashMap;
 use std::sync::Arc;
-use voltage_calc::{CalcEngine, MemoryStateStore, StateStore};
+use std::time::{Duration, Instant};
+use voltage_calc::{CalcEngine, CalcLimits, MemoryStateStore, StateStore};
 use voltage_routing::set_action_point;
 use voltage_rtdb::numfmt::precomputed;
 use voltage_rtdb::traits::Rtdb;
@@ -38,6 +41,8 @@ fn point_type_to_static(pt: Option<&str>, default: &'static str) -> &'static str
 pub struct RuleExecutionResult {
     pub rule_id: i64,
     pub success: bool,
+    /// Rule ran in shadow mode: actions were recorded but not written
+    pub shadow: bool,
     pub actions_executed: Vec<ActionResult>,
     pub error: Option<String>,
     pub execution_path: Vec<String>, // Node IDs visited
@@ -48,6 +53,8 @@ pub struct RuleExecutionResult {
     pub variable_values: Arc<HashMap<String, f64>>,
     /// Node execution details for debugging/visualization
     pub node_details: HashMap<String, NodeExecutionDetail>,
+    /// HTTP calls made by `action-http` nodes
+    pub http_calls: Vec<HttpCallResult>,
 }
 
 /// Record of an executed action
@@ -67,12 +74,87 @@ pub struct ActionResult {
     pub value: f64,
     /// Whether the action succeeded
     pub success: bool,
+    /// Shadow action: would have been written, but nothing was sent
+    pub shadow: bool,
+}
+
+/// Record of an HTTP call made by an `action-http` node
+#[derive(Debug, Clone, Serialize)]
+pub struct HttpCallResult {
+    /// HTTP method
+    pub method: String,
+    /// Rendered URL
+    pub url: String,
+    /// Response status (None when no response was received)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// Whether the success criteria were met
+    pub success: bool,
+    /// Round-trip time in milliseconds
+    pub duration_ms: u64,
+    /// Response body (truncated)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
+    /// Transport or criteria error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Shadow call: rendered but not sent
+    pub shadow: bool,
+}
+
+/// Maximum response body length kept in execution records
+const HTTP_RESPONSE_LIMIT: usize = 1024;
+
+/// Maximum response body length read for the success criteria
+const HTTP_BODY_READ_LIMIT: usize = 64 * 1024;
+
+/// HTTP call of an `action-http` node still in flight after its flow finished
+pub(crate) struct PendingHttpCall {
+    node_id: String,
+    /// Record of the call as dispatched (method, URL)
+    call: HttpCallResult,
+    handle: tokio::task::JoinHandle<HttpCallResult>,
+}
+
+/// Outcome of preparing an `action-http` call
+enum HttpDispatch {
+    /// Finished without sending (shadow rule or invalid request)
+    Done(HttpCallResult),
+    /// Ready to send
+    Send(HttpCallResult, Box<reqwest::RequestBuilder>),
+}
+
+impl RuleExecutionResult {
+    /// Record the outcome of HTTP calls that completed after the flow
+    pub(crate) async fn complete_http_calls(&mut self, pending: Vec<PendingHttpCall>) {
+        for PendingHttpCall {
+            node_id,
+            mut call,
+            handle,
+        } in pending
+        {
+            match handle.await {
+                Ok(done) => call = done,
+                Err(e) => call.error = Some(format!("HTTP task failed: {}", e)),
+            }
+            if let Some(detail) = self.node_details.get_mut(&node_id) {
+                detail.http = Some(call.clone());
+            }
+            self.http_calls.push(call);
+        }
+    }
+
+    /// Whether the rule acted: wrote a point or made a successful HTTP call
+    pub fn triggered(&self) -> bool {
+        self.success
+            && (!self.actions_executed.is_empty() || self.http_calls.iter().any(|c| c.success))
+    }
 }
 
 /// Execution details for a single node (for debugging/visualization)
 #[derive(Debug, Clone, Serialize)]
 pub struct NodeExecutionDetail {
-    /// Node type: "start", "switch", "change", "end", "calculation"
+    /// Node type: "start", "switch", "change", "end", "calculation", "http"
     pub node_type: &'static str,
     /// Variable values when entering this node (Arc-shared snapshot)
     pub input_values: Arc<HashMap<String, f64>>,
@@ -85,6 +167,9 @@ pub struct NodeExecutionDetail {
     /// Actions executed (for ChangeValue nodes)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub actions: Option<Vec<ActionResult>>,
+    /// HTTP call made (for Http nodes)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http: Option<HttpCallResult>,
 }
 
 /// Result of evaluating a single condition branch
@@ -111,6 +196,12 @@ pub struct RuleExecutor<R: Rtdb, S: StateStore = MemoryStateStore> {
     state_store: Arc<S>,
     /// Optional SharedVecRtdbReader for cross-process zero-copy reads
     shared_reader: Option<Arc<SharedVecRtdbReader>>,
+    /// Evaluation limits for calculation formulas
+    calc_limits: CalcLimits,
+    /// HTTP client for action-http nodes
+    http: reqwest::Client,
+    /// Product point names for selectors that name their point
+    point_names: Option<Arc<PointNames>>,
 }
 
 impl<R: Rtdb> RuleExecutor<R, MemoryStateStore> {
@@ -121,6 +212,9 @@ impl<R: Rtdb> RuleExecutor<R, MemoryStateStore> {
             routing_cache,
             state_store: Arc::new(MemoryStateStore::new()),
             shared_reader: None,
+            calc_limits: CalcLimits::default(),
+            http: reqwest::Client::new(),
+            point_names: None,
         }
     }
 }
@@ -137,6 +231,9 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
             routing_cache,
             state_store,
             shared_reader: None,
+            calc_limits: CalcLimits::default(),
+            http: reqwest::Client::new(),
+            point_names: None,
         }
     }
 
@@ -153,17 +250,63 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
         self
     }
 
+    /// Resolve point names in selectors against the product point definitions
+    pub fn with_point_names(mut self, point_names: Arc<PointNames>) -> Self {
+        self.point_names = Some(point_names);
+        self
+    }
+
+    /// Set the evaluation limits for calculation formulas
+    pub fn with_calc_limits(mut self, limits: CalcLimits) -> Self {
+        self.set_calc_limits(limits);
+        self
+    }
+
+    /// Replace the evaluation limits for calculation formulas
+    pub fn set_calc_limits(&mut self, limits: CalcLimits) {
+        self.calc_limits = limits;
+    }
+
     /// Execute a rule with RuleFlow
+    ///
+    /// Waits for the HTTP calls of `action-http` nodes, so the result is
+    /// complete (manual execution).
     pub async fn execute(&self, rule: &Rule) -> Result<RuleExecutionResult> {
+        let (mut result, pending) = self.execute_detached(rule).await?;
+        result.complete_http_calls(pending).await;
+        Ok(result)
+    }
+
+    /// Execute a rule without waiting for its HTTP calls
+    ///
+    /// `action-http` requests run in their own tasks so a slow endpoint does
+    /// not hold up the rule tick; their records are added by
+    /// [`RuleExecutionResult::complete_http_calls`].
+    pub(crate) async fn execute_detached(
+        &self,
+        rule: &Rule,
+    ) -> Result<(RuleExecutionResult, Vec<PendingHttpCall>)> {
+        let mut pending = Vec::new();
+        let result = self.run_flow(rule, &mut pending).await?;
+        Ok((result, pending))
+    }
+
+    async fn run_flow(
+        &self,
+        rule: &Rule,
+        pending: &mut Vec<PendingHttpCall>,
+    ) -> Result<RuleExecutionResult> {
         let mut result = RuleExecutionResult {
             rule_id: rule.id,
             success: false,
+            shadow: rule.shadow,
             actions_executed: vec![],
             error: None,
             execution_path: vec![],
             matched_condition: None,
             variable_values: Arc::new(HashMap::new()),
             node_details: HashMap::new(),
+            http_calls: vec![],
         };
 
         // Execute from start node, accumulating variable values along the path
@@ -262,6 +405,7 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
                             condition_results: Some(condition_results),
                             matched_port,
                             actions: None,
+                            http: None,
                         },
                     );
 
@@ -298,7 +442,9 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
                     for assignment in assignments {
                         let variable = variables.iter().find(|v| v.name == assignment.variables);
                         if let Some(var) = variable {
-                            let executed = self.execute_rule_change(var, assignment, &values).await;
+                            let executed = self
+                                .execute_rule_change(var, assignment, &values, rule.shadow)
+                                .await;
                             node_actions.push(executed);
                             result.actions_executed.push(executed);
                         }
@@ -313,6 +459,7 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
                             condition_results: None,
                             matched_port: None,
                             actions: Some(node_actions),
+                            http: None,
                         },
                     );
 
@@ -346,7 +493,8 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
 
                     // Create CalcEngine with rule_id as context (for stateful functions)
                     let calc_engine =
-                        CalcEngine::new(Arc::clone(&self.state_store), format!("rule_{}", rule.id));
+                        CalcEngine::new(Arc::clone(&self.state_store), format!("rule_{}", rule.id))
+                            .with_limits(self.calc_limits.clone());
                     let mut node_actions = Vec::new();
 
                     for calc in calculations {
@@ -362,8 +510,9 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
 
                         // Find output variable and write result
                         if let Some(var) = variables.iter().find(|v| v.name == calc.output) {
-                            let action =
-                                self.write_calculation_result(var, calc_result, calc).await;
+                            let action = self
+                                .write_calculation_result(var, calc_result, calc, rule.shadow)
+                                .await;
                             node_actions.push(action);
                             result.actions_executed.push(action);
                         }
@@ -382,6 +531,7 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
                             condition_results: None,
                             matched_port: None,
                             actions: Some(node_actions),
+                            http: None,
                         },
                     );
 
@@ -393,6 +543,67 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
                         },
                     };
                 },
+                RuleNode::Http {
+                    variables,
+                    rule: request,
+                    wires,
+                } => {
+                    // Read template variables
+                    let values_changed =
+                        match self.read_rule_variables(variables, &mut values).await {
+                            Ok(changed) => changed,
+                            Err(e) => {
+                                result.error = Some(format!("Failed to read variables: {}", e));
+                                return Ok(result);
+                            },
+                        };
+
+                    let input_snapshot =
+                        snapshot_or_reuse(&mut values_snapshot, &values, values_changed);
+                    result.variable_values = Arc::clone(&input_snapshot);
+
+                    // The call runs in its own task; a failed call is recorded
+                    // but does not stop the flow
+                    let call = match self.prepare_http_request(rule, request, &values) {
+                        HttpDispatch::Done(call) => {
+                            result.http_calls.push(call.clone());
+                            call
+                        },
+                        HttpDispatch::Send(call, builder) => {
+                            let handle = tokio::spawn(send_http_request(
+                                rule.id,
+                                *builder,
+                                call.clone(),
+                                request.success.clone(),
+                            ));
+                            pending.push(PendingHttpCall {
+                                node_id: current_id.to_string(),
+                                call: call.clone(),
+                                handle,
+                            });
+                            call
+                        },
+                    };
+                    result.node_details.insert(
+                        current_id.to_string(),
+                        NodeExecutionDetail {
+                            node_type: "http",
+                            input_values: input_snapshot,
+                            condition_results: None,
+                            matched_port: None,
+                            actions: None,
+                            http: Some(call),
+                        },
+                    );
+
+                    current_id = match wires.default.first() {
+                        Some(next) => next.as_str(),
+                        None => {
+                            result.error = Some("HTTP node has no output wire".to_string());
+                            return Ok(result);
+                        },
+                    };
+                },
             }
         }
 
@@ -407,7 +618,8 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
     ///
     /// Removed VecRtdb - using SharedMemory + Redis two-tier architecture
     ///
-    /// Reads variable values from Redis Hash `inst:{id}:M` or `inst:{id}:A`
+    /// Reads variable values from Redis Hash `inst:{id}:M` or `inst:{id}:A`;
+    /// selector variables are reduced over all matching instances
     async fn read_rule_variables(
         &self,
         variables: &[RuleVariable],
@@ -425,6 +637,13 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
             // Clone var.name once at loop start, reuse in all branches
             let var_name = var.name.clone();
 
+            // Wildcard selector: aggregate over all matching instances
+            if let Some(selector) = &var.selector {
+                let val = self.read_selector_variable(var, selector).await?;
+                values_changed |= values.insert(var_name, val) != Some(val);
+                continue;
+            }
+
             // Get instance ID (supports both "instance" and "instance_id" via serde alias)
             let instance_id = match var.instance {
                 Some(id) => id,
@@ -445,38 +664,56 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
             })?;
 
             let is_action = point_type == "action";
+            let val = self
+                .read_point_value(&var_name, instance_id, is_action, point)
+                .await
+                .unwrap_or(0.0);
+            values_changed |= values.insert(var_name, val) != Some(val);
+        }
 
-            // ★ Priority 1: SharedMemory (~5μs) - cross-process zero-copy
-            if let Some(reader) = &self.shared_reader {
-                let cached = if is_action {
-                    reader.get_action(instance_id, point)
-                } else {
-                    reader.get_measurement(instance_id, point)
-                };
-
-                if let Some(val) = cached {
-                    // SharedMemory hit - fastest path
-                    values_changed |= values.insert(var_name, val) != Some(val);
-                    continue;
-                }
-            }
+        Ok(values_changed)
+    }
 
-            // ★ Priority 2: Redis (~1ms) - remote fallback
-            let key = if is_action {
-                format!("inst:{}:A", instance_id)
+    /// Read one instance point value
+    ///
+    /// SharedMemory first, then Redis; `None` when missing or not a number.
+    async fn read_point_value(
+        &self,
+        var_name: &str,
+        instance_id: u32,
+        is_action: bool,
+        point: u32,
+    ) -> Option<f64> {
+        // ★ Priority 1: SharedMemory (~5μs) - cross-process zero-copy
+        if let Some(reader) = &self.shared_reader {
+            let cached = if is_action {
+                reader.get_action(instance_id, point)
             } else {
-                format!("inst:{}:M", instance_id)
+                reader.get_measurement(instance_id, point)
             };
 
-            // Use precomputed pool for common point IDs (0-255) to avoid allocation
-            let field = precomputed::get_point_id_str_or_alloc(point);
+            if cached.is_some() {
+                // SharedMemory hit - fastest path
+                return cached;
+            }
+        }
+
+        // ★ Priority 2: Redis (~1ms) - remote fallback
+        let key = if is_action {
+            format!("inst:{}:A", instance_id)
+        } else {
+            format!("inst:{}:M", instance_id)
+        };
 
-            match self.rtdb.hash_get(&key, &field).await {
-                Ok(Some(val_bytes)) => {
-                    let val_str = String::from_utf8_lossy(&val_bytes);
-                    if let Ok(val) = val_str.parse::<f64>() {
-                        values_changed |= values.insert(var_name, val) != Some(val);
-                    } else {
+        // Use precomputed pool for common point IDs (0-255) to avoid allocation
+        let field = precomputed::get_point_id_str_or_alloc(point);
+
+        match self.rtdb.hash_get(&key, &field).await {
+            Ok(Some(val_bytes)) => {
+                let val_str = String::from_utf8_lossy(&val_bytes);
+                match val_str.parse::<f64>() {
+                    Ok(val) => Some(val),
+                    Err(_) => {
                         tracing::warn!(
                             "Var {}: '{}' not number at {}:{}",
                             var_name,
@@ -484,21 +721,104 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
                             key,
                             field
                         );
-                        values_changed |= values.insert(var_name, 0.0) != Some(0.0);
+                        None
+                    },
+                }
+            },
+            Ok(None) => {
+                tracing::warn!("Var {}: {}:{} not found", var_name, key, field);
+                None
+            },
+            Err(e) => {
+                tracing::error!("Var {} read err: {}", var_name, e);
+                None
+            },
+        }
+    }
+
+    /// Resolve a wildcard selector variable against the instance name index
+    ///
+    /// Instances without a value are left out of sum/avg/min/max but still
+    /// counted by `count`. With a named point, instances whose product has
+    /// no such point do not match.
+    async fn read_selector_variable(&self, var: &RuleVariable, selector: &str) -> Result<f64> {
+        let invalid = |e: String| {
+            crate::error::RuleError::ExecutionError(format!("Variable '{}': {}", var.name, e))
+        };
+        let selector = InstanceSelector::parse(selector).map_err(invalid)?;
+        let reduce = SelectorReduce::parse(var.reduce.as_deref()).map_err(invalid)?;
+        let names = match (&selector.point, &self.point_names) {
+            (SelectorPoint::Id(_), _) => None,
+            (SelectorPoint::Name(_), Some(point_names)) => Some(point_names.table().await?),
+            (SelectorPoint::Name(_), None) => {
+                return Err(invalid(
+                    "point names need the product point definitions".to_string(),
+                ))
+            },
+        };
+
+        let instance_ids = self.selector_instances(&selector).await?;
+
+        let mut matched = 0usize;
+        let mut samples = Vec::new();
+        for instance_id in instance_ids {
+            let point = match (&selector.point, &names) {
+                (SelectorPoint::Id(id), _) => *id,
+                (SelectorPoint::Name(name), Some(names)) => {
+                    match names.resolve(instance_id, selector.is_action, name) {
+                        Some(id) => id,
+                        None => continue,
                     }
                 },
-                Ok(None) => {
-                    tracing::warn!("Var {}: {}:{} not found", var_name, key, field);
-                    values_changed |= values.insert(var_name, 0.0) != Some(0.0);
-                },
-                Err(e) => {
-                    tracing::error!("Var {} read err: {}", var_name, e);
-                    values_changed |= values.insert(var_name, 0.0) != Some(0.0);
-                },
+                (SelectorPoint::Name(_), None) => continue,
+            };
+            matched += 1;
+            if reduce == SelectorReduce::Count {
+                continue;
+            }
+            if let Some(val) = self
+                .read_point_value(&var.name, instance_id, selector.is_action, point)
+                .await
+            {
+                samples.push(val);
             }
         }
 
-        Ok(values_changed)
+        if matched == 0 {
+            tracing::warn!(
+                "Var {}: selector '{}' matched no instance",
+                var.name,
+                selector.instances
+            );
+        }
+
+        Ok(match reduce {
+            SelectorReduce::Count => matched as f64,
+            _ => reduce.apply(&samples),
+        })
+    }
+
+    /// IDs of the instances a selector covers: name patterns are matched
+    /// against the instance name index, tags read from their index set
+    async fn selector_instances(&self, selector: &InstanceSelector) -> Result<Vec<u32>> {
+        let rtdb_err = |e: anyhow::Error| crate::error::RuleError::ExecutionError(e.to_string());
+        let ids: Vec<String> = match &selector.instances {
+            SelectorTarget::Name(_) => self
+                .rtdb
+                .hash_get_all("inst:name:index")
+                .await
+                .map_err(rtdb_err)?
+                .into_iter()
+                .filter(|(name, _)| selector.matches(name))
+                .map(|(_, id)| String::from_utf8_lossy(&id).into_owned())
+                .collect(),
+            SelectorTarget::Tag { key, value } => self
+                .rtdb
+                .smembers(&KeySpaceConfig::production_cached().instance_tag_index_key(key, value))
+                .await
+                .map_err(rtdb_err)?,
+        };
+        Ok(ids.iter().filter_map(|id| id.parse().ok()).collect())
     }
 
     /// Evaluate compact switch rules and return the next node ID with matched condition and port
@@ -648,11 +968,14 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
     }
 
     /// Execute a compact value change action
+    ///
+    /// In shadow mode the action is resolved and logged but not routed.
     async fn execute_rule_change(
         &self,
         variable: &RuleVariable,
         assignment: &RuleValueAssignment,
         values: &HashMap<String, f64>,
+        shadow: bool,
     ) -> ActionResult {
         // Resolve the value to write
         let resolved_value: f64 = if let Some(n) = assignment.value.as_f64() {
@@ -678,6 +1001,7 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
                 point_id: 0,
                 value: resolved_value,
                 success: false,
+                shadow,
             };
         };
 
@@ -694,9 +1018,29 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
                 point_id: 0,
                 value: resolved_value,
                 success: false,
+                shadow,
             };
         };
 
+        let action = ActionResult {
+            target_type: "instance",
+            target_id: instance_id,
+            point_type: point_type_to_static(variable.point_type.as_deref(), "A"),
+            point_id: point,
+            value: resolved_value,
+            success: true,
+            shadow,
+        };
+        if shadow {
+            tracing::info!(
+                "Shadow action: inst:{}:A:{} = {} (not written)",
+                instance_id,
+                point,
+                resolved_value
+            );
+            return action;
+        }
+
         // Use voltage_routing to set the action point
         // Use precomputed pool for common point IDs (0-255)
         let point_str = precomputed::get_point_id_str_or_alloc(point);
@@ -722,12 +1066,8 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
         };
 
         ActionResult {
-            target_type: "instance",
-            target_id: instance_id,
-            point_type: point_type_to_static(variable.point_type.as_deref(), "A"),
-            point_id: point,
-            value: resolved_value,
             success: routed,
+            ..action
         }
     }
 
@@ -736,11 +1076,14 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
     /// Supports both measurement (M) and action (A) point types:
     /// - Measurement: Direct write to inst:{id}:M Hash
     /// - Action: Use M2C routing (triggers comsrv TODO queue)
+    ///
+    /// In shadow mode the result is logged but not written.
     async fn write_calculation_result(
         &self,
         variable: &RuleVariable,
         value: f64,
         calc: &CalculationRule,
+        shadow: bool,
     ) -> ActionResult {
         let Some(instance_id) = variable.instance else {
             tracing::error!(
@@ -755,6 +1098,7 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
                 point_id: 0,
                 value,
                 success: false,
+                shadow,
             };
         };
 
@@ -772,11 +1116,22 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
                 point_id: 0,
                 value,
                 success: false,
+                shadow,
             };
         };
         let point_type = variable.point_type.as_deref().unwrap_or("M");
 
         let success = match point_type {
+            "M" | "measurement" | "A" | "action" if shadow => {
+                tracing::info!(
+                    "Shadow calc: inst:{}:{}:{} = {} (not written)",
+                    instance_id,
+                    point_type_to_static(Some(point_type), "M"),
+                    point,
+                    value
+                );
+                true
+            },
             "M" | "measurement" => {
                 // Direct write to measurement hash (no routing)
                 self.write_measurement_point(instance_id, point, value)
@@ -825,9 +1180,68 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
             point_id: point,
             value,
             success,
+            shadow,
         }
     }
 
+    /// Render an `action-http` request; shadow rules record it without sending
+    fn prepare_http_request(
+        &self,
+        rule: &Rule,
+        request: &HttpRequestRule,
+        values: &HashMap<String, f64>,
+    ) -> HttpDispatch {
+        let method_name = request.method.trim().to_uppercase();
+        let url = render_template(&request.url, rule, values);
+        let mut call = HttpCallResult {
+            method: method_name.clone(),
+            url: url.clone(),
+            status: None,
+            success: false,
+            duration_ms: 0,
+            response: None,
+            error: None,
+            shadow: rule.shadow,
+        };
+
+        let method = match reqwest::Method::from_bytes(method_name.as_bytes()) {
+            Ok(m) => m,
+            Err(_) => {
+                call.error = Some(format!("Invalid HTTP method '{}'", request.method));
+                return HttpDispatch::Done(call);
+            },
+        };
+
+        if rule.shadow {
+            tracing::info!(
+                "Rule {} shadow HTTP {} {} (not sent)",
+                rule.id,
+                call.method,
+                call.url
+            );
+            call.success = true;
+            return HttpDispatch::Done(call);
+        }
+
+        let mut builder = self
+            .http
+            .request(method, &url)
+            .timeout(Duration::from_millis(request.timeout_ms.max(1)));
+        let mut has_content_type = false;
+        for (name, value) in &request.headers {
+            has_content_type |= name.eq_ignore_ascii_case("content-type");
+            builder = builder.header(name.as_str(), render_template(value, rule, values));
+        }
+        if let Some(body) = &request.body {
+            let body = render_template(body, rule, values);
+            if !has_content_type && serde_json::from_str::<serde_json::Value>(&body).is_ok() {
+                builder = builder.header("Content-Type", "application/json");
+            }
+            builder = builder.body(body);
+        }
+        HttpDispatch::Send(call, Box::new(builder))
+    }
+
     /// Write directly to measurement point (no routing)
     ///
     /// Used by calculation nodes to write computed values back to measurement points.
@@ -858,6 +1272,118 @@ impl<R: Rtdb, S: StateStore> RuleExecutor<R, S> {
     }
 }
 
+/// Render `{{name}}` placeholders with variable values and rule metadata
+///
+/// Unknown placeholders are left untouched.
+fn render_template(template: &str, rule: &Rule, values: &HashMap<String, f64>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let name = after[..end].trim();
+        match name {
+            "rule_id" => out.push_str(&rule.id.to_string()),
+            "rule_name" => out.push_str(&rule.name),
+            _ => match values.get(name) {
+                Some(v) => out.push_str(&v.to_string()),
+                None => out.push_str(&rest[start..start + 4 + end]),
+            },
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Truncate a response body to `HTTP_RESPONSE_LIMIT` bytes (on a char boundary)
+/// Send an `action-http` request and check the success criteria
+async fn send_http_request(
+    rule_id: i64,
+    builder: reqwest::RequestBuilder,
+    mut call: HttpCallResult,
+    success: HttpSuccessCriteria,
+) -> HttpCallResult {
+    let started = Instant::now();
+    let response = match builder.send().await {
+        Ok(resp) => {
+            let status = resp.status().as_u16();
+            read_body_capped(resp, HTTP_BODY_READ_LIMIT)
+                .await
+                .map(|text| (status, text))
+        },
+        Err(e) => Err(e),
+    };
+    call.duration_ms = started.elapsed().as_millis() as u64;
+
+    match response {
+        Ok((status, text)) => {
+            call.status = Some(status);
+            let status_ok = if success.status.is_empty() {
+                (200..300).contains(&status)
+            } else {
+                success.status.contains(&status)
+            };
+            let body_ok = success
+                .body_contains
+                .as_deref()
+                .is_none_or(|needle| text.contains(needle));
+            call.success = status_ok && body_ok;
+            if !status_ok {
+                call.error = Some(format!("Unexpected status {}", status));
+            } else if !body_ok {
+                call.error = Some("Response body does not match".to_string());
+            }
+            call.response = Some(truncate_response(text));
+        },
+        Err(e) => call.error = Some(e.to_string()),
+    }
+
+    if !call.success {
+        tracing::warn!(
+            "Rule {} HTTP {} {} failed: {}",
+            rule_id,
+            call.method,
+            call.url,
+            call.error.as_deref().unwrap_or("-")
+        );
+    }
+    call
+}
+
+/// Read at most `limit` bytes of a response body; the rest is not received
+async fn read_body_capped(
+    mut resp: reqwest::Response,
+    limit: usize,
+) -> std::result::Result<String, reqwest::Error> {
+    let mut body = Vec::new();
+    while body.len() < limit {
+        match resp.chunk().await? {
+            Some(chunk) => {
+                let take = chunk.len().min(limit - body.len());
+                body.extend_from_slice(&chunk[..take]);
+            },
+            None => break,
+        }
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+fn truncate_response(mut text: String) -> String {
+    if text.len() > HTTP_RESPONSE_LIMIT {
+        let mut cut = HTTP_RESPONSE_LIMIT;
+        while !text.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        text.truncate(cut);
+    }
+    text
+}
+
 #[cfg(test)]
 #[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
 mod tests {
@@ -1167,6 +1693,7 @@ mod tests {
             enabled: true,
             priority: 0,
             cooldown_ms: 0,
+            shadow: false,
             flow: rule_flow,
         }
     }
@@ -1196,6 +1723,37 @@ mod tests {
         assert_eq!(result.actions_executed[0].value, 999.0);
     }
 
+    #[tokio::test]
+    async fn test_shadow_rule_writes_nothing() {
+        let rtdb = Arc::new(MemoryRtdb::new());
+        let routing_cache = Arc::new(RoutingCache::default());
+
+        setup_name_index(&rtdb).await;
+        rtdb.hash_set("inst:5:M", "3", Bytes::from("3.5"))
+            .await
+            .unwrap();
+
+        let mut rule = create_soc_rule();
+        rule.shadow = true;
+        let executor = RuleExecutor::new(rtdb.clone(), routing_cache);
+        let result = executor.execute(&rule).await.unwrap();
+
+        assert!(result.success);
+        assert!(result.shadow);
+        assert_eq!(result.actions_executed.len(), 1);
+        let action = result.actions_executed[0];
+        assert!(action.shadow && action.success);
+        assert_eq!((action.target_id, action.point_id), (6, 5));
+        assert_eq!(action.value, 999.0);
+        assert!(rtdb.hash_get("inst:6:A", "5").await.unwrap().is_none());
+
+        // Armed again, the same rule writes the action point
+        rule.shadow = false;
+        let result = executor.execute(&rule).await.unwrap();
+        assert!(!result.actions_executed[0].shadow);
+        assert!(rtdb.hash_get("inst:6:A", "5").await.unwrap().is_some());
+    }
+
     #[tokio::test]
     async fn test_soc_strategy_boundary_5() {
         // SOC = 5.0 → should match out001 (X1 <= 5)
@@ -1311,6 +1869,8 @@ mod tests {
             point_type: Some("measurement".to_string()),
             point: Some(1),
             formula: vec![],
+            selector: None,
+            reduce: None,
         }];
 
         let mut values = HashMap::new();
@@ -1344,6 +1904,8 @@ mod tests {
             point_type: Some("measurement".to_string()),
             point: Some(1),
             formula: vec![],
+            selector: None,
+            reduce: None,
         }];
 
         let mut values = HashMap::new();
@@ -1355,4 +1917,304 @@ mod tests {
         // Should read directly from Redis
         assert_eq!(values.get("DIRECT"), Some(&55.5));
     }
+
+    /// Test: action-http renders templates, records the call and follows wires
+    #[tokio::test]
+    async fn test_http_node_execution() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let n = socket.read(&mut buf).await.unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]).to_string();
+            socket
+                .write_all(b"HTTP/1.1 202 Accepted\r\nContent-Length: 8\r\nConnection: close\r\n\r\naccepted")
+                .await
+                .unwrap();
+            request
+        });
+
+        let rtdb = Arc::new(MemoryRtdb::new());
+        rtdb.hash_set("inst:1:M", "3", Bytes::from("12.5"))
+            .await
+            .unwrap();
+        let executor = RuleExecutor::new(rtdb, Arc::new(RoutingCache::default()));
+
+        let flow = extract_rule_flow(&json!({
+            "nodes": [
+                { "id": "start", "type": "start", "data": { "config": { "wires": { "default": ["notify"] } } } },
+                {
+                    "id": "notify",
+                    "type": "custom",
+                    "data": {
+                        "type": "action-http",
+                        "config": {
+                            "variables": [
+                                { "name": "X1", "instance": 1, "pointType": "measurement", "point": 3 }
+                            ],
+                            "rule": {
+                                "url": format!("http://{}/alarm/{{{{rule_id}}}}?soc={{{{X1}}}}", addr),
+                                "body": "{\"soc\": {{X1}}}",
+                                "success": { "status": [202], "body_contains": "accepted" }
+                            },
+                            "wires": { "default": ["end"] }
+                        }
+                    }
+                },
+                { "id": "end", "type": "end" }
+            ]
+        }))
+        .unwrap();
+        let rule = Rule {
+            id: 7,
+            name: "notify".to_string(),
+            description: None,
+            enabled: true,
+            priority: 0,
+            cooldown_ms: 0,
+            shadow: false,
+            flow,
+        };
+
+        let result = executor.execute(&rule).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.execution_path, vec!["start", "notify", "end"]);
+        assert_eq!(result.http_calls.len(), 1);
+        let call = &result.http_calls[0];
+        assert!(call.success, "{:?}", call);
+        assert_eq!(call.status, Some(202));
+        assert_eq!(call.url, format!("http://{}/alarm/7?soc=12.5", addr));
+        assert!(result.node_details["notify"].http.is_some());
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /alarm/7?soc=12.5 "));
+        assert!(request.ends_with("{\"soc\": 12.5}"));
+    }
+
+    /// Test: action-http runs detached and reads only the capped body
+    #[tokio::test]
+    async fn test_http_node_detached_with_capped_body() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let _ = socket.read(&mut buf).await;
+            // The marker lies beyond the read limit
+            let mut body = vec![b'x'; HTTP_BODY_READ_LIMIT];
+            body.extend_from_slice(b"marker");
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            let _ = socket.write_all(head.as_bytes()).await;
+            let _ = socket.write_all(&body).await;
+        });
+
+        let rtdb = Arc::new(MemoryRtdb::new());
+        let executor = RuleExecutor::new(rtdb, Arc::new(RoutingCache::default()));
+        let flow = extract_rule_flow(&json!({
+            "nodes": [
+                { "id": "start", "type": "start", "data": { "config": { "wires": { "default": ["notify"] } } } },
+                {
+                    "id": "notify",
+                    "type": "custom",
+                    "data": {
+                        "type": "action-http",
+                        "config": {
+                            "variables": [],
+                            "rule": {
+                                "method": "GET",
+                                "url": format!("http://{}/status", addr),
+                                "success": { "body_contains": "marker" }
+                            },
+                            "wires": { "default": ["end"] }
+                        }
+                    }
+                },
+                { "id": "end", "type": "end" }
+            ]
+        }))
+        .unwrap();
+        let rule = Rule {
+            id: 8,
+            name: "poll".to_string(),
+            description: None,
+            enabled: true,
+            priority: 0,
+            cooldown_ms: 0,
+            shadow: false,
+            flow,
+        };
+
+        let (mut result, pending) = executor.execute_detached(&rule).await.unwrap();
+        assert!(result.success);
+        assert_eq!(pending.len(), 1);
+        assert!(result.http_calls.is_empty());
+
+        result.complete_http_calls(pending).await;
+        let call = &result.http_calls[0];
+        assert_eq!(call.status, Some(200));
+        assert!(!call.success);
+        assert_eq!(call.error.as_deref(), Some("Response body does not match"));
+        assert_eq!(call.response.as_ref().unwrap().len(), HTTP_RESPONSE_LIMIT);
+        assert!(!result.triggered());
+        assert!(result.node_details["notify"]
+            .http
+            .as_ref()
+            .unwrap()
+            .status
+            .is_some());
+    }
+
+    #[test]
+    fn test_render_template() {
+        let rule = Rule {
+            id: 3,
+            name: "r".to_string(),
+            description: None,
+            enabled: true,
+            priority: 0,
+            cooldown_ms: 0,
+            shadow: false,
+            flow: crate::types::RuleFlow {
+                start_node: "start".to_string(),
+                nodes: HashMap::new(),
+            },
+        };
+        let values = HashMap::from([("X1".to_string(), 1.0)]);
+        assert_eq!(
+            render_template("{{rule_name}}:{{ X1 }}:{{X9}}:{{", &rule, &values),
+            "r:1:{{X9}}:{{"
+        );
+    }
+
+    /// Test: selector variables aggregate over matching instances
+    #[tokio::test]
+    async fn test_read_selector_variables() {
+        let rtdb = Arc::new(MemoryRtdb::new());
+        for (name, id, power) in [
+            ("pv_01", "11", "120"),
+            ("pv_02", "12", "380.5"),
+            ("bess_01", "13", "999"),
+        ] {
+            rtdb.hash_set("inst:name:index", name, Bytes::from(id))
+                .await
+                .unwrap();
+            rtdb.hash_set(&format!("inst:{}:M", id), "2", Bytes::from(power))
+                .await
+                .unwrap();
+        }
+        // Tag index sets as written by modsrv instance tags
+        for id in ["11", "13"] {
+            rtdb.sadd("inst:tag:feeder:F1", id).await.unwrap();
+        }
+        let executor = RuleExecutor::new(rtdb, Arc::new(RoutingCache::default()));
+
+        let selector_var = |name: &str, selector: &str, reduce: Option<&str>| RuleVariable {
+            name: name.to_string(),
+            instance: None,
+            point_type: None,
+            point: None,
+            formula: vec![],
+            selector: Some(selector.to_string()),
+            reduce: reduce.map(String::from),
+        };
+        let variables = vec![
+            selector_var("TOTAL", "inst:pv_*:M:2", None),
+            selector_var("AVG", "inst:pv_*:M:2", Some("avg")),
+            selector_var("N", "inst:pv_*:M:2", Some("count")),
+            selector_var("F1", "tag:feeder:F1:M:2", None),
+            selector_var("F2", "tag:feeder:F2:M:2", Some("count")),
+        ];
+
+        let mut values = HashMap::new();
+        executor
+            .read_rule_variables(&variables, &mut values)
+            .await
+            .unwrap();
+
+        assert_eq!(values.get("TOTAL"), Some(&500.5));
+        assert_eq!(values.get("AVG"), Some(&250.25));
+        assert_eq!(values.get("N"), Some(&2.0));
+        assert_eq!(values.get("F1"), Some(&1119.0));
+        assert_eq!(values.get("F2"), Some(&0.0));
+    }
+
+    /// Test: selector point names resolve per instance product
+    #[tokio::test]
+    async fn test_read_selector_variables_by_point_name() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        for ddl in [
+            "CREATE TABLE instances (instance_id INTEGER, instance_name TEXT, product_name TEXT)",
+            "CREATE TABLE measurement_points (product_name TEXT, measurement_id INTEGER, name TEXT)",
+            "CREATE TABLE action_points (product_name TEXT, action_id INTEGER, name TEXT)",
+            "INSERT INTO instances VALUES (11, 'pv_01', 'pv_inverter'), (12, 'pv_02', 'pv_meter'), \
+             (13, 'pv_03', 'weather_station'), (14, 'bess_01', 'battery')",
+            "INSERT INTO measurement_points VALUES ('pv_inverter', 2, 'power'), \
+             ('pv_meter', 5, 'power'), ('weather_station', 1, 'irradiance'), ('battery', 2, 'power')",
+        ] {
+            sqlx::query(ddl).execute(&pool).await.unwrap();
+        }
+
+        let rtdb = Arc::new(MemoryRtdb::new());
+        for (name, id, point, power) in [
+            ("pv_01", "11", "2", "120"),
+            ("pv_02", "12", "5", "380"),
+            ("pv_03", "13", "1", "800"),
+            ("bess_01", "14", "2", "999"),
+        ] {
+            rtdb.hash_set("inst:name:index", name, Bytes::from(id))
+                .await
+                .unwrap();
+            rtdb.hash_set(&format!("inst:{}:M", id), point, Bytes::from(power))
+                .await
+                .unwrap();
+        }
+        let executor = RuleExecutor::new(rtdb, Arc::new(RoutingCache::default()))
+            .with_point_names(Arc::new(PointNames::new(pool)));
+
+        let selector_var = |name: &str, reduce: Option<&str>| RuleVariable {
+            name: name.to_string(),
+            instance: None,
+            point_type: None,
+            point: None,
+            formula: vec![],
+            selector: Some("inst:pv_*:M:power".to_string()),
+            reduce: reduce.map(String::from),
+        };
+        let variables = vec![
+            selector_var("TOTAL", None),
+            selector_var("N", Some("count")),
+        ];
+
+        let mut values = HashMap::new();
+        executor
+            .read_rule_variables(&variables, &mut values)
+            .await
+            .unwrap();
+
+        // pv_03 has no "power" point; point IDs differ between products
+        assert_eq!(values.get("TOTAL"), Some(&500.0));
+        assert_eq!(values.get("N"), Some(&2.0));
+
+        // Without the point definitions a named point cannot be resolved
+        let executor = RuleExecutor::new(
+            Arc::new(MemoryRtdb::new()),
+            Arc::new(RoutingCache::default()),
+        );
+        assert!(executor
+            .read_rule_variables(&variables, &mut HashMap::new())
+            .await
+            .is_err());
+    }
 }
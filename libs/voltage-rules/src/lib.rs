@@ -3,7 +3,7 @@
 //! A Vue Flow-based rule engine for VoltageEMS providing:
 //! - Rule parsing from Vue Flow JSON format
 //! - Rule execution with condition evaluation and action dispatch
-//! - Rule scheduling with interval-based triggers
+//! - Rule scheduling with interval-based triggers on a worker pool
 //! - SQLite persistence for rule storage
 //!
 //! # Architecture
@@ -24,24 +24,34 @@
 mod error;
 mod executor;
 pub mod logger;
+pub mod migrations;
 mod parser;
+pub mod point_names;
 mod repository;
 mod scheduler;
 pub mod types;
 
 // Re-export public API
 pub use error::{Result, RuleError};
-pub use executor::{ActionResult, RuleExecutionResult, RuleExecutor};
+pub use executor::{ActionResult, HttpCallResult, RuleExecutionResult, RuleExecutor};
 pub use logger::{format_conditions, RuleLogger, RuleLoggerManager};
 pub use parser::extract_rule_flow;
 pub use repository::{
-    delete_rule, get_rule, get_rule_for_execution, list_rules, list_rules_paginated,
-    load_all_rules, load_enabled_rules, set_rule_enabled, upsert_rule,
+    delete_rule, get_rule, get_rule_for_execution, list_rules, list_rules_after,
+    list_rules_paginated, load_all_rules, load_enabled_rules, set_rule_enabled, set_rule_shadow,
+    upsert_rule,
 };
-pub use scheduler::{RuleScheduler, SchedulerStatus, TriggerConfig, DEFAULT_TICK_MS};
+pub use scheduler::{
+    ExecutionConfig, FairnessPolicy, RuleScheduler, SchedulerStatus, TriggerConfig,
+    DEFAULT_RULE_WORKERS, DEFAULT_TICK_MS,
+};
+
+// Formula sandbox limits for calculation nodes
+pub use voltage_calc::{CalcLimits, NonFinitePolicy};
 
 // Re-export rule types for convenience
 pub use types::{
-    CalculationRule, FlowCondition, Rule, RuleFlow, RuleNode, RuleSwitchBranch,
-    RuleValueAssignment, RuleVariable, RuleWires,
+    CalculationRule, FlowCondition, HttpRequestRule, HttpSuccessCriteria, InstanceSelector, Rule,
+    RuleFlow, RuleNode, RuleSwitchBranch, RuleValueAssignment, RuleVariable, RuleWires,
+    SelectorPoint, SelectorReduce, SelectorTarget,
 };
@@ -16,7 +16,7 @@ use crate::types::FlowCondition;
 use chrono::{Local, Utc};
 use tracing::warn;
 
-use crate::executor::{ActionResult, RuleExecutionResult};
+use crate::executor::{ActionResult, HttpCallResult, RuleExecutionResult};
 
 /// Logger for individual rule execution
 pub struct RuleLogger {
@@ -69,7 +69,18 @@ impl RuleLogger {
         let cond_str = result.matched_condition.as_deref().unwrap_or("-");
 
         // Action results: "diesel_gen_01:A:2=1 OK"
-        let actions_str = format_actions(&result.actions_executed, result.error.as_deref());
+        let mut actions_str = format_actions(&result.actions_executed, result.error.as_deref());
+
+        // HTTP calls: "POST http://host/alarm 200 OK"
+        if result.error.is_none() && !result.http_calls.is_empty() {
+            let http_str = format_http_calls(&result.http_calls);
+            if result.actions_executed.is_empty() {
+                actions_str = http_str;
+            } else {
+                actions_str.push_str(", ");
+                actions_str.push_str(&http_str);
+            }
+        }
 
         // Compose: "X1=50.3 | X1>=49 | diesel_gen_01:A:2=1 OK"
         let message = format!("{} | {} | {}", vars_str, cond_str, actions_str);
@@ -135,7 +146,11 @@ fn format_actions(actions: &[ActionResult], error: Option<&str>) -> String {
         if i > 0 {
             result.push_str(", ");
         }
-        let status = if a.success { "OK" } else { "FAIL" };
+        let status = match (a.shadow, a.success) {
+            (true, _) => "SHADOW",
+            (false, true) => "OK",
+            (false, false) => "FAIL",
+        };
         // Format: "instance_id:point_type:point_id=value OK"
         let _ = write!(
             result,
@@ -146,6 +161,28 @@ fn format_actions(actions: &[ActionResult], error: Option<&str>) -> String {
     result
 }
 
+/// Format HTTP call results for logging
+fn format_http_calls(calls: &[HttpCallResult]) -> String {
+    let mut result = String::with_capacity(calls.len() * 60);
+    for (i, c) in calls.iter().enumerate() {
+        if i > 0 {
+            result.push_str(", ");
+        }
+        let status = match (c.shadow, c.success) {
+            (true, _) => "SHADOW",
+            (false, true) => "OK",
+            (false, false) => "FAIL",
+        };
+        // Format: "POST http://host/path 200 OK"
+        let code = c
+            .status
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let _ = write!(result, "{} {} {} {}", c.method, c.url, code, status);
+    }
+    result
+}
+
 /// Format conditions as expression string (e.g., "X1>=49" or "X1>10 && X2<50")
 pub fn format_conditions(conditions: &[FlowCondition]) -> String {
     if conditions.is_empty() {
@@ -322,6 +359,7 @@ mod tests {
             point_id: 2,
             value: 1.0,
             success: true,
+            shadow: false,
         }];
 
         assert_eq!(format_actions(&actions, None), "5:A:2=1 OK");
@@ -336,11 +374,43 @@ mod tests {
             point_id: 2,
             value: 1.0,
             success: false,
+            shadow: false,
         }];
 
         assert_eq!(format_actions(&actions, None), "5:A:2=1 FAIL");
     }
 
+    #[test]
+    fn test_format_actions_shadow() {
+        let actions = vec![ActionResult {
+            target_type: "instance",
+            target_id: 5,
+            point_type: "A",
+            point_id: 2,
+            value: 1.0,
+            success: true,
+            shadow: true,
+        }];
+
+        assert_eq!(format_actions(&actions, None), "5:A:2=1 SHADOW");
+    }
+
+    #[test]
+    fn test_format_http_calls() {
+        let calls = vec![HttpCallResult {
+            method: "POST".to_string(),
+            url: "http://host/alarm".to_string(),
+            status: None,
+            success: false,
+            duration_ms: 5000,
+            response: None,
+            error: Some("timeout".to_string()),
+            shadow: false,
+        }];
+
+        assert_eq!(format_http_calls(&calls), "POST http://host/alarm - FAIL");
+    }
+
     #[test]
     fn test_format_actions_with_error() {
         let actions = vec![];
@@ -0,0 +1,180 @@
+//! Rule table migrations
+//!
+//! Applied by modsrv (which hosts the rule engine) on startup and by
+//! `monarch init`.
+
+use sqlx::{Row, SqliteConnection};
+use tracing::warn;
+use voltage_infra::sqlite::migrations::{has_column, Migration, MigrationFuture, Migrator};
+
+/// Rules table SQL
+pub const RULES_TABLE: &str = r#"
+    CREATE TABLE IF NOT EXISTS rules (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        description TEXT,
+        enabled BOOLEAN DEFAULT TRUE,
+        priority INTEGER DEFAULT 0,
+        cooldown_ms INTEGER DEFAULT 0,
+        shadow BOOLEAN DEFAULT FALSE,
+        nodes_json TEXT NOT NULL,
+        flow_json TEXT,
+        format TEXT DEFAULT 'vue-flow',
+        created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    )
+"#;
+
+/// Rule history table SQL
+pub const RULE_HISTORY_TABLE: &str = r#"
+    CREATE TABLE IF NOT EXISTS rule_history (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        rule_id INTEGER NOT NULL,
+        triggered_at TIMESTAMP NOT NULL,
+        execution_result TEXT,
+        error TEXT,
+        FOREIGN KEY (rule_id) REFERENCES rules(id)
+    )
+"#;
+
+/// Ordered rule migrations
+pub static MIGRATIONS: &[Migration] = &[
+    Migration::rust(1, "baseline", &BASELINE, baseline),
+    Migration::rust(
+        2,
+        "rules_shadow_column",
+        &[RULES_SHADOW],
+        rules_shadow_column,
+    ),
+];
+
+/// Migrator for the rule tables
+pub const fn migrator() -> Migrator {
+    Migrator::new("rules", MIGRATIONS)
+}
+
+const KEEP_LEGACY_RULES: [&str; 5] = [
+    "ALTER TABLE rules RENAME TO rules_legacy",
+    "ALTER TABLE rule_history RENAME TO rule_history_legacy",
+    "DROP INDEX IF EXISTS idx_rules_enabled",
+    "DROP INDEX IF EXISTS idx_rule_history_rule",
+    "DROP INDEX IF EXISTS idx_rule_history_time",
+];
+
+const RULE_TABLES: [&str; 5] = [
+    RULES_TABLE,
+    RULE_HISTORY_TABLE,
+    "CREATE INDEX IF NOT EXISTS idx_rules_enabled ON rules(enabled)",
+    "CREATE INDEX IF NOT EXISTS idx_rule_history_rule ON rule_history(rule_id)",
+    "CREATE INDEX IF NOT EXISTS idx_rule_history_time ON rule_history(triggered_at)",
+];
+
+const BASELINE: [&str; 10] = [
+    KEEP_LEGACY_RULES[0],
+    KEEP_LEGACY_RULES[1],
+    KEEP_LEGACY_RULES[2],
+    KEEP_LEGACY_RULES[3],
+    KEEP_LEGACY_RULES[4],
+    RULE_TABLES[0],
+    RULE_TABLES[1],
+    RULE_TABLES[2],
+    RULE_TABLES[3],
+    RULE_TABLES[4],
+];
+
+const RULES_SHADOW: &str = "ALTER TABLE rules ADD COLUMN shadow BOOLEAN DEFAULT FALSE";
+
+/// Create the rule tables, setting aside a legacy `rules` table (id TEXT)
+///
+/// The legacy tables used to be dropped; they are now renamed to
+/// `rules_legacy` / `rule_history_legacy` so history survives the upgrade.
+fn baseline(conn: &mut SqliteConnection) -> MigrationFuture<'_> {
+    Box::pin(async move {
+        let id_type = sqlx::query("SELECT type FROM pragma_table_info('rules') WHERE name = 'id'")
+            .fetch_optional(&mut *conn)
+            .await?
+            .map(|row| row.try_get::<String, _>("type"))
+            .transpose()?;
+
+        if id_type.is_some_and(|t| t.eq_ignore_ascii_case("TEXT")) {
+            warn!("Legacy rules table (id TEXT) kept as rules_legacy/rule_history_legacy");
+            let [rename_rules, rename_history, drop_indexes @ ..] = KEEP_LEGACY_RULES;
+            sqlx::query(rename_rules).execute(&mut *conn).await?;
+            let history: Option<i64> = sqlx::query_scalar(
+                "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'rule_history'",
+            )
+            .fetch_optional(&mut *conn)
+            .await?;
+            if history.is_some() {
+                sqlx::query(rename_history).execute(&mut *conn).await?;
+            }
+            // Indexes follow the renamed tables; free their names for the new ones
+            for ddl in drop_indexes {
+                sqlx::query(ddl).execute(&mut *conn).await?;
+            }
+        }
+
+        for ddl in RULE_TABLES {
+            sqlx::query(ddl).execute(&mut *conn).await?;
+        }
+        Ok(())
+    })
+}
+
+/// Add the `shadow` flag (execute without writing) to rules
+///
+/// The baseline table has it already on fresh databases.
+fn rules_shadow_column(conn: &mut SqliteConnection) -> MigrationFuture<'_> {
+    Box::pin(async move {
+        if !has_column(&mut *conn, "rules", "shadow").await? {
+            sqlx::query(RULES_SHADOW).execute(&mut *conn).await?;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_legacy_rules_are_kept() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::raw_sql(
+            "CREATE TABLE rules (id TEXT PRIMARY KEY, name TEXT);
+             CREATE TABLE rule_history (id INTEGER PRIMARY KEY, rule_id TEXT);
+             CREATE INDEX idx_rule_history_rule ON rule_history(rule_id);
+             INSERT INTO rules VALUES ('r1', 'legacy');
+             INSERT INTO rule_history (rule_id) VALUES ('r1');",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        migrator().run(&pool).await.unwrap();
+
+        let history: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM rule_history_legacy")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(history, 1);
+        let id_type: String =
+            sqlx::query_scalar("SELECT type FROM pragma_table_info('rules') WHERE name = 'id'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(id_type, "INTEGER");
+        let index_table: String = sqlx::query_scalar(
+            "SELECT tbl_name FROM sqlite_master WHERE name = 'idx_rule_history_rule'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(index_table, "rule_history");
+    }
+}
@@ -7,8 +7,8 @@
 //! discarding UI-only data like positions, labels, and edge styling.
 
 use crate::types::{
-    CalculationRule, FlowCondition, RuleFlow, RuleNode, RuleSwitchBranch, RuleValueAssignment,
-    RuleVariable, RuleWires,
+    CalculationRule, FlowCondition, HttpRequestRule, InstanceSelector, RuleFlow, RuleNode,
+    RuleSwitchBranch, RuleValueAssignment, RuleVariable, RuleWires, SelectorReduce,
 };
 use serde_json::Value;
 use std::collections::HashMap;
@@ -77,6 +77,7 @@ pub fn extract_rule_flow(full_json: &Value) -> Result<RuleFlow> {
                     "function-switch" => extract_switch_rule_node(data)?,
                     "action-changeValue" => extract_change_value_rule_node(data)?,
                     "action-calculation" => extract_calculation_rule_node(data)?,
+                    "action-http" => extract_http_rule_node(data)?,
                     _ => {
                         tracing::warn!("Unknown node: {}", inner_type);
                         continue;
@@ -195,6 +196,38 @@ fn extract_calculation_rule_node(data: Option<&Value>) -> Result<RuleNode> {
     })
 }
 
+/// Extract action-http node as RuleNode::Http
+fn extract_http_rule_node(data: Option<&Value>) -> Result<RuleNode> {
+    let config = data
+        .and_then(|d| d.get("config"))
+        .ok_or_else(|| RuleError::ParseError("HTTP node missing 'config'".to_string()))?;
+
+    // Extract variables (template inputs)
+    let variables = extract_rule_variables(config)?;
+
+    // Extract request definition
+    let rule_value = config
+        .get("rule")
+        .cloned()
+        .ok_or_else(|| RuleError::ParseError("HTTP node missing 'rule'".to_string()))?;
+    let rule: HttpRequestRule = serde_json::from_value(rule_value)
+        .map_err(|e| RuleError::ParseError(format!("Invalid HTTP node rule: {}", e)))?;
+    if rule.url.trim().is_empty() {
+        return Err(RuleError::ParseError(
+            "HTTP node has empty 'url'".to_string(),
+        ));
+    }
+
+    // Extract wires (default output)
+    let wires = extract_rule_wires_default(Some(config))?;
+
+    Ok(RuleNode::Http {
+        variables,
+        rule,
+        wires,
+    })
+}
+
 /// Extract compact variables from config
 fn extract_rule_variables(config: &Value) -> Result<Vec<RuleVariable>> {
     let vars_arr = match config.get("variables").and_then(|v| v.as_array()) {
@@ -233,12 +266,26 @@ fn extract_rule_variables(config: &Value) -> Result<Vec<RuleVariable>> {
             .cloned()
             .unwrap_or_default();
 
+        // Wildcard selector (fleet-wide aggregate), validated up front
+        let selector = var
+            .get("selector")
+            .and_then(|v| v.as_str())
+            .map(String::from);
+        let reduce = var.get("reduce").and_then(|v| v.as_str()).map(String::from);
+        if let Some(selector) = &selector {
+            InstanceSelector::parse(selector)
+                .and_then(|_| SelectorReduce::parse(reduce.as_deref()))
+                .map_err(|e| RuleError::ParseError(format!("Variable '{}': {}", name, e)))?;
+        }
+
         variables.push(RuleVariable {
             name,
             instance,
             point_type,
             point,
             formula,
+            selector,
+            reduce,
         });
     }
 
@@ -575,4 +622,58 @@ mod tests {
         assert_eq!(deserialized.start_node, "start");
         assert_eq!(deserialized.nodes.len(), 2);
     }
+
+    #[test]
+    fn test_extract_http_node() {
+        let flow = json!({
+            "nodes": [
+                {
+                    "id": "start",
+                    "type": "start",
+                    "data": { "config": { "wires": { "default": ["notify"] } } }
+                },
+                {
+                    "id": "notify",
+                    "type": "custom",
+                    "data": {
+                        "type": "action-http",
+                        "config": {
+                            "variables": [
+                                { "name": "X1", "instance": 1, "pointType": "measurement", "point": 3 }
+                            ],
+                            "rule": {
+                                "url": "http://example.com/alarm?soc={{X1}}",
+                                "body": "{\"soc\": {{X1}}}",
+                                "timeout_ms": 1500,
+                                "success": { "status": [200, 202] }
+                            },
+                            "wires": { "default": ["end"] }
+                        }
+                    }
+                },
+                { "id": "end", "type": "end" }
+            ]
+        });
+
+        let compact = extract_rule_flow(&flow).unwrap();
+        match compact.nodes.get("notify").unwrap() {
+            RuleNode::Http {
+                variables,
+                rule,
+                wires,
+            } => {
+                assert_eq!(variables.len(), 1);
+                assert_eq!(rule.method, "POST");
+                assert_eq!(rule.timeout_ms, 1500);
+                assert_eq!(rule.success.status, vec![200, 202]);
+                assert_eq!(wires.default, vec!["end"]);
+            },
+            _ => panic!("Expected Http node"),
+        }
+
+        // Missing URL is rejected
+        let mut invalid = flow.clone();
+        invalid["nodes"][1]["data"]["config"]["rule"] = json!({ "method": "GET" });
+        assert!(extract_rule_flow(&invalid).is_err());
+    }
 }
@@ -0,0 +1,92 @@
+//! Point name resolution for instance selectors
+//!
+//! A selector may name its point (`inst:pv_*:M:power`) instead of giving the
+//! ID. Names come from the product point definitions: modsrv's
+//! `measurement_points` / `action_points` tables joined with `instances`.
+//! The table is reloaded at most once per [`REFRESH_INTERVAL`], so new
+//! instances resolve within that delay.
+
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use crate::error::Result;
+
+/// Maximum age of the loaded name table
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Point IDs by name, per (instance ID, is_action)
+#[derive(Debug, Default)]
+pub struct PointNameTable {
+    points: HashMap<(u32, bool), HashMap<String, u32>>,
+}
+
+impl PointNameTable {
+    /// ID of the named point of an instance's product
+    pub fn resolve(&self, instance_id: u32, is_action: bool, name: &str) -> Option<u32> {
+        self.points
+            .get(&(instance_id, is_action))
+            .and_then(|names| names.get(name))
+            .copied()
+    }
+}
+
+/// Cached product point names backed by the modsrv database
+#[derive(Debug)]
+pub struct PointNames {
+    pool: SqlitePool,
+    loaded: Mutex<Option<(Instant, Arc<PointNameTable>)>>,
+}
+
+impl PointNames {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self {
+            pool,
+            loaded: Mutex::new(None),
+        }
+    }
+
+    /// The name table, reloaded when older than [`REFRESH_INTERVAL`]
+    pub async fn table(&self) -> Result<Arc<PointNameTable>> {
+        let mut loaded = self.loaded.lock().await;
+        if let Some((at, table)) = loaded.as_ref() {
+            if at.elapsed() < REFRESH_INTERVAL {
+                return Ok(Arc::clone(table));
+            }
+        }
+        let table = Arc::new(self.load().await?);
+        *loaded = Some((Instant::now(), Arc::clone(&table)));
+        Ok(table)
+    }
+
+    async fn load(&self) -> Result<PointNameTable> {
+        let rows: Vec<(i64, i64, String, bool)> = sqlx::query_as(
+            r#"
+            SELECT i.instance_id, p.measurement_id, p.name, 0
+            FROM instances i JOIN measurement_points p ON p.product_name = i.product_name
+            UNION ALL
+            SELECT i.instance_id, p.action_id, p.name, 1
+            FROM instances i JOIN action_points p ON p.product_name = i.product_name
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut table = PointNameTable::default();
+        for (instance_id, point_id, name, is_action) in rows {
+            let (Ok(instance_id), Ok(point_id)) =
+                (u32::try_from(instance_id), u32::try_from(point_id))
+            else {
+                continue;
+            };
+            table
+                .points
+                .entry((instance_id, is_action))
+                .or_default()
+                .insert(name, point_id);
+        }
+        Ok(table)
+    }
+}
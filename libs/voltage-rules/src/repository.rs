@@ -7,12 +7,13 @@ use crate::parser::extract_rule_flow;
 use crate::types::{Rule, RuleFlow};
 use serde_json::Value;
 use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use voltage_infra::sqlite::Keyset;
 
 /// List all rules (returns metadata and flow_json for frontend editing)
 pub async fn list_rules(pool: &SqlitePool) -> Result<Vec<Value>> {
     let rows = sqlx::query(
         r#"
-        SELECT id, name, description, nodes_json, flow_json, format, enabled, priority, cooldown_ms
+        SELECT id, name, description, nodes_json, flow_json, format, enabled, priority, cooldown_ms, shadow
         FROM rules
         ORDER BY priority DESC, id ASC
         "#,
@@ -46,7 +47,7 @@ pub async fn list_rules_paginated(
     // Paged rows
     let rows = sqlx::query(
         r#"
-        SELECT id, name, description, nodes_json, flow_json, format, enabled, priority, cooldown_ms
+        SELECT id, name, description, nodes_json, flow_json, format, enabled, priority, cooldown_ms, shadow
         FROM rules
         ORDER BY priority DESC, id ASC
         LIMIT ? OFFSET ?
@@ -65,11 +66,64 @@ pub async fn list_rules_paginated(
     Ok((rules, total as usize))
 }
 
+/// Sort key of the rule list: `priority DESC, id ASC`
+fn rule_list_keyset() -> Keyset {
+    Keyset::new().desc("priority").asc("id")
+}
+
+/// List rules after a keyset cursor, returning rules, total count and the next key
+///
+/// `after` is the `[priority, id]` key returned with the previous page; the
+/// next key is `None` on the last page.
+pub async fn list_rules_after(
+    pool: &SqlitePool,
+    after: Option<&[Value]>,
+    limit: usize,
+) -> Result<(Vec<Value>, usize, Option<Vec<Value>>)> {
+    let limit = limit.clamp(1, 100);
+    let keyset = rule_list_keyset();
+
+    let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM rules")
+        .fetch_one(pool)
+        .await?;
+
+    // One extra row tells whether another page follows
+    let sql = format!(
+        "SELECT id, name, description, nodes_json, flow_json, format, enabled, priority, cooldown_ms, shadow \
+         FROM rules WHERE {} ORDER BY {} LIMIT ?",
+        keyset.condition(after.is_some()),
+        keyset.order_by()
+    );
+    let rows = keyset
+        .bind_after(sqlx::query(&sql), after)
+        .map_err(|e| RuleError::InvalidFormat(e.to_string()))?
+        .bind(limit as i64 + 1)
+        .fetch_all(pool)
+        .await?;
+
+    let has_more = rows.len() > limit;
+    let mut rules = Vec::with_capacity(limit);
+    for row in rows.into_iter().take(limit) {
+        rules.push(hydrate_rule_json(row)?);
+    }
+    let next = if has_more {
+        rules.last().map(|rule| {
+            vec![
+                rule.get("priority").cloned().unwrap_or(Value::Null),
+                rule.get("id").cloned().unwrap_or(Value::Null),
+            ]
+        })
+    } else {
+        None
+    };
+    Ok((rules, total as usize, next))
+}
+
 /// Get a single rule by ID (returns metadata and flow_json for frontend editing)
 pub async fn get_rule(pool: &SqlitePool, id: i64) -> Result<Value> {
     let row = sqlx::query(
         r#"
-        SELECT id, name, description, nodes_json, flow_json, format, enabled, priority, cooldown_ms
+        SELECT id, name, description, nodes_json, flow_json, format, enabled, priority, cooldown_ms, shadow
         FROM rules
         WHERE id = ?
         "#,
@@ -88,7 +142,7 @@ pub async fn get_rule(pool: &SqlitePool, id: i64) -> Result<Value> {
 pub async fn get_rule_for_execution(pool: &SqlitePool, id: i64) -> Result<Rule> {
     let row = sqlx::query(
         r#"
-        SELECT id, name, description, enabled, priority, cooldown_ms, nodes_json
+        SELECT id, name, description, enabled, priority, cooldown_ms, shadow, nodes_json
         FROM rules
         WHERE id = ? AND enabled = 1
         "#,
@@ -107,7 +161,7 @@ pub async fn get_rule_for_execution(pool: &SqlitePool, id: i64) -> Result<Rule>
 pub async fn load_enabled_rules(pool: &SqlitePool) -> Result<Vec<Rule>> {
     let rows = sqlx::query(
         r#"
-        SELECT id, name, description, enabled, priority, cooldown_ms, nodes_json
+        SELECT id, name, description, enabled, priority, cooldown_ms, shadow, nodes_json
         FROM rules
         WHERE enabled = 1
         ORDER BY priority DESC, id ASC
@@ -127,7 +181,7 @@ pub async fn load_enabled_rules(pool: &SqlitePool) -> Result<Vec<Rule>> {
 pub async fn load_all_rules(pool: &SqlitePool) -> Result<Vec<Rule>> {
     let rows = sqlx::query(
         r#"
-        SELECT id, name, description, enabled, priority, cooldown_ms, nodes_json
+        SELECT id, name, description, enabled, priority, cooldown_ms, shadow, nodes_json
         FROM rules
         ORDER BY priority DESC, id ASC
         "#,
@@ -170,6 +224,8 @@ pub async fn upsert_rule(pool: &SqlitePool, rule_id: i64, rule: &Value) -> Resul
         .and_then(|n| u64::try_from(n).ok())
         .unwrap_or(0);
 
+    let shadow = rule.get("shadow").and_then(Value::as_bool).unwrap_or(false);
+
     // Get format type (default: "vue-flow")
     let format = rule
         .get("format")
@@ -195,8 +251,8 @@ pub async fn upsert_rule(pool: &SqlitePool, rule_id: i64, rule: &Value) -> Resul
 
     sqlx::query(
         r#"
-        INSERT INTO rules (id, name, description, nodes_json, flow_json, format, enabled, priority, cooldown_ms)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO rules (id, name, description, nodes_json, flow_json, format, enabled, priority, cooldown_ms, shadow)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         ON CONFLICT(id) DO UPDATE SET
             name = excluded.name,
             description = excluded.description,
@@ -206,6 +262,7 @@ pub async fn upsert_rule(pool: &SqlitePool, rule_id: i64, rule: &Value) -> Resul
             enabled = excluded.enabled,
             priority = excluded.priority,
             cooldown_ms = excluded.cooldown_ms,
+            shadow = excluded.shadow,
             updated_at = CURRENT_TIMESTAMP
         "#,
     )
@@ -218,6 +275,7 @@ pub async fn upsert_rule(pool: &SqlitePool, rule_id: i64, rule: &Value) -> Resul
     .bind(enabled)
     .bind(priority as i64)
     .bind(cooldown_ms as i64)
+    .bind(shadow)
     .execute(pool)
     .await?;
 
@@ -259,6 +317,29 @@ pub async fn set_rule_enabled(pool: &SqlitePool, id: i64, enabled: bool) -> Resu
     Ok(())
 }
 
+/// Put a rule into or out of shadow mode
+///
+/// A shadow rule executes and logs its would-be actions but writes nothing.
+pub async fn set_rule_shadow(pool: &SqlitePool, id: i64, shadow: bool) -> Result<()> {
+    let result = sqlx::query(
+        r#"
+        UPDATE rules
+        SET shadow = ?, updated_at = CURRENT_TIMESTAMP
+        WHERE id = ?
+        "#,
+    )
+    .bind(shadow)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(RuleError::NotFound(id.to_string()));
+    }
+
+    Ok(())
+}
+
 /// Hydrate a row into JSON Value (for API response)
 #[allow(clippy::disallowed_methods)] // json! macro internal unwrap is safe for static structure
 fn hydrate_rule_json(row: SqliteRow) -> Result<Value> {
@@ -271,6 +352,7 @@ fn hydrate_rule_json(row: SqliteRow) -> Result<Value> {
     let enabled: i64 = row.try_get("enabled")?;
     let priority: i64 = row.try_get("priority")?;
     let cooldown_ms: i64 = row.try_get("cooldown_ms")?;
+    let shadow: i64 = row.try_get("shadow")?;
 
     // Parse compact flow (for execution info)
     let flow: Value = serde_json::from_str(&nodes_json_str)
@@ -291,6 +373,7 @@ fn hydrate_rule_json(row: SqliteRow) -> Result<Value> {
         "enabled": enabled != 0,
         "priority": priority,
         "cooldown_ms": cooldown_ms,
+        "shadow": shadow != 0,
         "flow": flow,
         "flow_json": flow_json
     }))
@@ -304,6 +387,7 @@ fn hydrate_rule(row: SqliteRow) -> Result<Rule> {
     let enabled: i64 = row.try_get("enabled")?;
     let priority: i64 = row.try_get("priority")?;
     let cooldown_ms: i64 = row.try_get("cooldown_ms")?;
+    let shadow: i64 = row.try_get("shadow")?;
     let nodes_json_str: String = row.try_get("nodes_json")?;
 
     // Deserialize compact flow
@@ -317,6 +401,7 @@ fn hydrate_rule(row: SqliteRow) -> Result<Rule> {
         enabled: enabled != 0,
         priority: priority as u32,
         cooldown_ms: cooldown_ms as u64,
+        shadow: shadow != 0,
         flow,
     })
 }
@@ -4,26 +4,104 @@
 //! - Interval: Execute rules at fixed intervals
 //!
 //! Current implementation uses a simple tick-based approach with 100ms granularity.
+//!
+//! Due rules are executed by a pool of worker tasks. Each rule is pinned to
+//! one worker (rule ID modulo worker count), so executions of a stateful rule
+//! never overlap and always see the state of its previous run. When a tick
+//! takes longer than the tick interval it is counted as an overrun; the
+//! [`FairnessPolicy`] decides which rules go first when a tick is busy.
+//!
+//! HTTP calls of `action-http` nodes do not hold up the tick: a rule that
+//! dispatched a call counts as triggered and its execution record is written
+//! once the call completes. A failed call is reported back and clears the
+//! rule's cooldown, so it is not counted as triggered.
 
-use crate::error::Result;
-use crate::executor::{RuleExecutionResult, RuleExecutor};
+use crate::error::{Result, RuleError};
+use crate::executor::{PendingHttpCall, RuleExecutionResult, RuleExecutor};
 use crate::logger::RuleLoggerManager;
+use crate::point_names::PointNames;
 use crate::repository;
 use crate::types::Rule;
 use bytes::Bytes;
 use sqlx::SqlitePool;
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
-use tokio::sync::RwLock;
-use tokio::time::interval;
+use tokio::sync::{mpsc, oneshot, RwLock};
+use tokio::time::{interval, MissedTickBehavior};
 use tracing::{debug, error, info, warn};
+use voltage_calc::CalcLimits;
 use voltage_rtdb::traits::Rtdb;
 use voltage_rtdb::{RoutingCache, SharedVecRtdbReader};
 
 /// Default scheduler tick interval (100ms)
 pub const DEFAULT_TICK_MS: u64 = 100;
 
+/// Default number of rule workers
+pub const DEFAULT_RULE_WORKERS: usize = 4;
+
+/// Order in which due rules are dispatched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FairnessPolicy {
+    /// Most overdue rules first; rules deferred by the tick budget go first
+    /// on the next tick, so no rule starves
+    #[default]
+    LongestWaiting,
+    /// Higher `priority` first, equal priorities by waiting time
+    Priority,
+}
+
+impl FairnessPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FairnessPolicy::LongestWaiting => "longest_waiting",
+            FairnessPolicy::Priority => "priority",
+        }
+    }
+}
+
+impl FromStr for FairnessPolicy {
+    type Err = RuleError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "longest_waiting" | "fair" => Ok(FairnessPolicy::LongestWaiting),
+            "priority" => Ok(FairnessPolicy::Priority),
+            other => Err(RuleError::SchedulerError(format!(
+                "Unknown fairness policy: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Worker pool configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionConfig {
+    /// Number of worker tasks (at least 1)
+    pub workers: usize,
+    pub fairness: FairnessPolicy,
+    /// Rules dispatched per tick (0 = unlimited); the rest wait for the next tick
+    pub max_rules_per_tick: usize,
+}
+
+impl Default for ExecutionConfig {
+    fn default() -> Self {
+        Self {
+            workers: DEFAULT_RULE_WORKERS,
+            fairness: FairnessPolicy::default(),
+            max_rules_per_tick: 0,
+        }
+    }
+}
+
+/// Worker a rule is pinned to
+fn worker_for(rule_id: i64, workers: usize) -> usize {
+    rule_id.rem_euclid(workers.max(1) as i64) as usize
+}
+
 /// Rule trigger configuration
 #[derive(Debug, Clone)]
 pub enum TriggerConfig {
@@ -50,12 +128,73 @@ struct ScheduledRule {
     last_cooldown_start: Option<Instant>,
 }
 
-/// Rule Scheduler - manages periodic rule execution
-pub struct RuleScheduler<R: Rtdb> {
+/// A rule due in the current tick
+struct DueRule {
+    idx: usize,
+    rule: Rule,
+    /// Time since the rule became due (`Duration::MAX` if it never ran)
+    overdue: Duration,
+}
+
+/// Sort due rules by dispatch order
+fn order_due(due: &mut [DueRule], policy: FairnessPolicy) {
+    match policy {
+        FairnessPolicy::LongestWaiting => due.sort_by(|a, b| b.overdue.cmp(&a.overdue)),
+        FairnessPolicy::Priority => due.sort_by(|a, b| {
+            b.rule
+                .priority
+                .cmp(&a.rule.priority)
+                .then(b.overdue.cmp(&a.overdue))
+        }),
+    }
+}
+
+/// Work item for a rule worker
+struct Job {
+    rule: Rule,
+    /// Receives whether the rule cooldown starts
+    done: oneshot::Sender<bool>,
+}
+
+/// Everything a worker needs to execute a rule
+struct ExecContext<R: Rtdb> {
     /// RTDB instance for reading/writing data
     rtdb: Arc<R>,
     /// Rule executor instance (uses default MemoryStateStore)
     executor: Arc<RuleExecutor<R, voltage_calc::MemoryStateStore>>,
+    /// Rule logger manager for independent rule log files
+    logger_manager: RuleLoggerManager,
+    /// Reports rules whose HTTP call failed after they counted as triggered
+    http_failures: mpsc::UnboundedSender<i64>,
+}
+
+/// Tick and worker counters reported in [`SchedulerStatus`]
+struct SchedulerStats {
+    ticks: AtomicU64,
+    tick_overruns: AtomicU64,
+    deferred_executions: AtomicU64,
+    last_tick_us: AtomicU64,
+    max_tick_us: AtomicU64,
+    worker_executions: Vec<AtomicU64>,
+}
+
+impl SchedulerStats {
+    fn new(workers: usize) -> Self {
+        Self {
+            ticks: AtomicU64::new(0),
+            tick_overruns: AtomicU64::new(0),
+            deferred_executions: AtomicU64::new(0),
+            last_tick_us: AtomicU64::new(0),
+            max_tick_us: AtomicU64::new(0),
+            worker_executions: (0..workers).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+}
+
+/// Rule Scheduler - manages periodic rule execution
+pub struct RuleScheduler<R: Rtdb> {
+    /// Executor, RTDB and loggers shared with the workers
+    ctx: Arc<ExecContext<R>>,
     /// SQLite pool for rule persistence
     pool: SqlitePool,
     /// Cached rules with their trigger configs
@@ -66,8 +205,11 @@ pub struct RuleScheduler<R: Rtdb> {
     running: Arc<std::sync::atomic::AtomicBool>,
     /// Scheduler tick interval in milliseconds
     tick_ms: u64,
-    /// Rule logger manager for independent rule log files
-    logger_manager: RuleLoggerManager,
+    /// Worker pool configuration
+    execution: ExecutionConfig,
+    stats: Arc<SchedulerStats>,
+    /// Rules whose HTTP call failed, drained at the start of each tick
+    http_failures: std::sync::Mutex<mpsc::UnboundedReceiver<i64>>,
 }
 
 impl<R: Rtdb + 'static> RuleScheduler<R> {
@@ -86,16 +228,7 @@ impl<R: Rtdb + 'static> RuleScheduler<R> {
         tick_ms: u64,
         log_root: PathBuf,
     ) -> Self {
-        Self {
-            rtdb: Arc::clone(&rtdb),
-            executor: Arc::new(RuleExecutor::new(rtdb, routing_cache)),
-            pool,
-            rules: Arc::new(RwLock::new(Vec::new())),
-            shutdown: Arc::new(tokio::sync::Notify::new()),
-            running: Arc::new(std::sync::atomic::AtomicBool::new(false)),
-            tick_ms,
-            logger_manager: RuleLoggerManager::new(log_root),
-        }
+        Self::with_shared_reader(rtdb, routing_cache, pool, tick_ms, log_root, None)
     }
 
     /// Create with SharedVecRtdbReader for two-tier priority reads
@@ -114,20 +247,47 @@ impl<R: Rtdb + 'static> RuleScheduler<R> {
         log_root: PathBuf,
         shared_reader: Option<Arc<SharedVecRtdbReader>>,
     ) -> Self {
-        let mut executor = RuleExecutor::new(Arc::clone(&rtdb), routing_cache);
+        let mut executor = RuleExecutor::new(Arc::clone(&rtdb), routing_cache)
+            .with_point_names(Arc::new(PointNames::new(pool.clone())));
         if let Some(reader) = shared_reader {
             executor = executor.with_shared_reader(reader);
         }
+        let execution = ExecutionConfig::default();
+        let (http_failures_tx, http_failures) = mpsc::unbounded_channel();
         Self {
-            rtdb,
-            executor: Arc::new(executor),
+            ctx: Arc::new(ExecContext {
+                rtdb,
+                executor: Arc::new(executor),
+                logger_manager: RuleLoggerManager::new(log_root),
+                http_failures: http_failures_tx,
+            }),
             pool,
             rules: Arc::new(RwLock::new(Vec::new())),
             shutdown: Arc::new(tokio::sync::Notify::new()),
             running: Arc::new(std::sync::atomic::AtomicBool::new(false)),
             tick_ms,
-            logger_manager: RuleLoggerManager::new(log_root),
+            stats: Arc::new(SchedulerStats::new(execution.workers)),
+            execution,
+            http_failures: std::sync::Mutex::new(http_failures),
+        }
+    }
+
+    /// Set the worker pool configuration (takes effect on `start`)
+    pub fn with_execution_config(mut self, mut execution: ExecutionConfig) -> Self {
+        execution.workers = execution.workers.max(1);
+        self.stats = Arc::new(SchedulerStats::new(execution.workers));
+        self.execution = execution;
+        self
+    }
+
+    /// Set the evaluation limits for calculation formulas (before `start`)
+    pub fn with_calc_limits(mut self, limits: CalcLimits) -> Self {
+        // The context is only shared once `start` spawns the workers
+        match Arc::get_mut(&mut self.ctx).and_then(|ctx| Arc::get_mut(&mut ctx.executor)) {
+            Some(executor) => executor.set_calc_limits(limits),
+            None => warn!("Rule executor already shared, calc limits unchanged"),
         }
+        self
     }
 
     /// Load rules from database and initialize scheduler state
@@ -170,22 +330,32 @@ impl<R: Rtdb + 'static> RuleScheduler<R> {
 
     /// Start the scheduler loop
     pub async fn start(&self) {
-        use std::sync::atomic::Ordering;
-
         if self.running.load(Ordering::Relaxed) {
             warn!("Scheduler running");
             return;
         }
 
         self.running.store(true, Ordering::Relaxed);
-        info!("Scheduler start ({}ms)", self.tick_ms);
+        info!(
+            "Scheduler start ({}ms, {} workers, {})",
+            self.tick_ms,
+            self.execution.workers,
+            self.execution.fairness.as_str()
+        );
+
+        // Workers exit when their senders are dropped at the end of the loop
+        let mut workers: Vec<_> = (0..self.execution.workers)
+            .map(|worker| self.spawn_worker(worker))
+            .collect();
 
         let mut tick_interval = interval(Duration::from_millis(self.tick_ms));
+        // An overrun tick is not made up with a burst of catch-up ticks
+        tick_interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
         loop {
             tokio::select! {
                 _ = tick_interval.tick() => {
-                    if let Err(e) = self.tick().await {
+                    if let Err(e) = self.tick(&mut workers).await {
                         error!("Tick err: {}", e);
                     }
                 }
@@ -208,22 +378,40 @@ impl<R: Rtdb + 'static> RuleScheduler<R> {
 
     /// Check if scheduler is running
     pub fn is_running(&self) -> bool {
-        self.running.load(std::sync::atomic::Ordering::Relaxed)
+        self.running.load(Ordering::Relaxed)
+    }
+
+    /// Spawn the worker task for one shard; jobs run one after another
+    fn spawn_worker(&self, worker: usize) -> mpsc::UnboundedSender<Job> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Job>();
+        let ctx = Arc::clone(&self.ctx);
+        let stats = Arc::clone(&self.stats);
+        tokio::spawn(async move {
+            while let Some(job) = rx.recv().await {
+                let start_cooldown = ctx.run(&job.rule).await;
+                if let Some(count) = stats.worker_executions.get(worker) {
+                    count.fetch_add(1, Ordering::Relaxed);
+                }
+                let _ = job.done.send(start_cooldown);
+            }
+        });
+        tx
     }
 
     /// Single scheduler tick - check all rules and execute if due
     ///
     /// Snapshot execution pattern for minimal lock hold time
     /// - Phase 1: Read lock to collect rules due for execution (~10μs)
-    /// - Phase 2: Execute rules without holding any lock (bulk of time)
+    /// - Phase 2: Dispatch to the workers without holding any lock (bulk of time)
     /// - Phase 3: Write lock to update timestamps (~100μs)
     ///
     /// This reduces write lock hold time from 100ms+ to ~100μs.
-    async fn tick(&self) -> Result<()> {
+    async fn tick(&self, workers: &mut [mpsc::UnboundedSender<Job>]) -> Result<()> {
         let now = Instant::now();
+        self.revoke_failed_http_triggers().await;
 
         // Phase 1: Read lock to collect rules that need execution (fast)
-        let rules_to_execute: Vec<(usize, Rule)> = {
+        let mut due: Vec<DueRule> = {
             let rules = self.rules.read().await;
             rules
                 .iter()
@@ -233,13 +421,14 @@ impl<R: Rtdb + 'static> RuleScheduler<R> {
                         return None;
                     }
 
-                    let should_execute = match &scheduled.trigger {
+                    let (should_execute, overdue) = match &scheduled.trigger {
                         TriggerConfig::Interval { interval_ms } => {
                             match scheduled.last_execution {
-                                None => true, // First execution
+                                None => (true, Duration::MAX), // First execution
                                 Some(last) => {
-                                    let elapsed = now.duration_since(last).as_millis() as u64;
-                                    elapsed >= *interval_ms
+                                    let elapsed = now.duration_since(last);
+                                    let interval = Duration::from_millis(*interval_ms);
+                                    (elapsed >= interval, elapsed.saturating_sub(interval))
                                 },
                             }
                         },
@@ -259,7 +448,11 @@ impl<R: Rtdb + 'static> RuleScheduler<R> {
                     };
 
                     if should_execute && cooldown_ok {
-                        Some((idx, scheduled.rule.clone()))
+                        Some(DueRule {
+                            idx,
+                            rule: scheduled.rule.clone(),
+                            overdue,
+                        })
                     } else {
                         None
                     }
@@ -267,60 +460,62 @@ impl<R: Rtdb + 'static> RuleScheduler<R> {
                 .collect()
         }; // Read lock released here (~10μs)
 
-        if rules_to_execute.is_empty() {
+        if due.is_empty() {
+            self.record_tick(now.elapsed());
             return Ok(());
         }
 
-        // Phase 2: Execute rules without holding any lock (bulk of time)
+        order_due(&mut due, self.execution.fairness);
+        let budget = self.execution.max_rules_per_tick;
+        if budget > 0 && due.len() > budget {
+            let deferred = due.split_off(budget);
+            self.stats
+                .deferred_executions
+                .fetch_add(deferred.len() as u64, Ordering::Relaxed);
+            debug!("Tick budget: {} rules deferred", deferred.len());
+        }
+
+        // Phase 2: Dispatch to the workers without holding any lock (bulk of time)
         // Track execution outcomes for Phase 3 timestamp updates
         struct ExecutionOutcome {
             idx: usize,
             rule_id: i64,
             start_cooldown: bool,
         }
-        let mut outcomes: Vec<ExecutionOutcome> = Vec::with_capacity(rules_to_execute.len());
 
-        for (idx, rule) in rules_to_execute {
+        // A worker only stops if a rule execution panicked
+        for (worker, tx) in workers.iter_mut().enumerate() {
+            if tx.is_closed() {
+                error!("Rule worker {} stopped, respawning", worker);
+                *tx = self.spawn_worker(worker);
+            }
+        }
+
+        let mut pending = Vec::with_capacity(due.len());
+        for DueRule { idx, rule, .. } in due {
             debug!("Executing rule: {}", rule.id);
             let rule_id = rule.id;
-
-            match self.executor.execute(&rule).await {
-                Ok(result) => {
-                    // Log rule execution to independent rule log file
-                    let logger = self.logger_manager.get_logger(rule.id, &rule.name);
-                    logger.log_execution(&result, &result.variable_values);
-
-                    // Write rule execution result to Redis for WebSocket monitoring
-                    self.write_rule_exec_to_redis(rule.id, &result).await;
-
-                    let start_cooldown = result.success && !result.actions_executed.is_empty();
-
-                    if result.success {
-                        debug!(
-                            "Rule {} executed successfully, {} actions",
-                            result.rule_id,
-                            result.actions_executed.len()
-                        );
-                    } else {
-                        warn!("Rule {} fail: {:?}", result.rule_id, result.error);
-                    }
-
-                    outcomes.push(ExecutionOutcome {
-                        idx,
-                        rule_id,
-                        start_cooldown,
-                    });
-                },
-                Err(e) => {
-                    error!("Rule {} err: {}", rule_id, e);
-                    // Still update last_execution to prevent retry spam
-                    outcomes.push(ExecutionOutcome {
-                        idx,
-                        rule_id,
-                        start_cooldown: false,
-                    });
-                },
+            let (done, result) = oneshot::channel();
+            let worker = worker_for(rule_id, workers.len());
+            if workers[worker].send(Job { rule, done }).is_err() {
+                error!("Rule {} not dispatched: worker {} stopped", rule_id, worker);
+                continue;
             }
+            pending.push((idx, rule_id, result));
+        }
+
+        let mut outcomes: Vec<ExecutionOutcome> = Vec::with_capacity(pending.len());
+        for (idx, rule_id, result) in pending {
+            // A lost result still updates last_execution to prevent retry spam
+            let start_cooldown = result.await.unwrap_or_else(|_| {
+                error!("Rule {} err: worker stopped", rule_id);
+                false
+            });
+            outcomes.push(ExecutionOutcome {
+                idx,
+                rule_id,
+                start_cooldown,
+            });
         }
 
         // Phase 3: Write lock to update timestamps (fast)
@@ -339,9 +534,52 @@ impl<R: Rtdb + 'static> RuleScheduler<R> {
             }
         } // Write lock released here (~100μs)
 
+        self.record_tick(now.elapsed());
         Ok(())
     }
 
+    /// Clear the cooldown of rules whose HTTP call failed after dispatch
+    ///
+    /// Runs at the start of a tick, after the tick that dispatched the call
+    /// started the cooldown.
+    async fn revoke_failed_http_triggers(&self) {
+        let failed: Vec<i64> = {
+            let mut rx = self.http_failures.lock().unwrap_or_else(|e| e.into_inner());
+            std::iter::from_fn(|| rx.try_recv().ok()).collect()
+        };
+        if failed.is_empty() {
+            return;
+        }
+        let mut rules = self.rules.write().await;
+        for scheduled in rules.iter_mut() {
+            if failed.contains(&scheduled.rule.id) {
+                scheduled.last_cooldown_start = None;
+            }
+        }
+    }
+
+    /// Record tick duration and detect overruns
+    fn record_tick(&self, elapsed: Duration) {
+        let stats = &self.stats;
+        let micros = elapsed.as_micros() as u64;
+        stats.ticks.fetch_add(1, Ordering::Relaxed);
+        stats.last_tick_us.store(micros, Ordering::Relaxed);
+        stats.max_tick_us.fetch_max(micros, Ordering::Relaxed);
+
+        if elapsed > Duration::from_millis(self.tick_ms) {
+            let overruns = stats.tick_overruns.fetch_add(1, Ordering::Relaxed) + 1;
+            // Log the first overrun and then every 100th, not every tick
+            if overruns == 1 || overruns.is_multiple_of(100) {
+                warn!(
+                    "Tick overrun: {}ms > {}ms ({} total)",
+                    elapsed.as_millis(),
+                    self.tick_ms,
+                    overruns
+                );
+            }
+        }
+    }
+
     /// Get current rules count
     pub async fn rules_count(&self) -> usize {
         self.rules.read().await.len()
@@ -352,11 +590,37 @@ impl<R: Rtdb + 'static> RuleScheduler<R> {
         let rules = self.rules.read().await;
         let enabled_count = rules.iter().filter(|r| r.rule.enabled).count();
 
+        let workers = self.execution.workers;
+        let mut worker_rules = vec![0; workers];
+        for scheduled in rules.iter().filter(|r| r.rule.enabled) {
+            worker_rules[worker_for(scheduled.rule.id, workers)] += 1;
+        }
+        let stats = &self.stats;
+        let ms = |us: &AtomicU64| us.load(Ordering::Relaxed) as f64 / 1000.0;
+
         SchedulerStatus {
             running: self.is_running(),
             total_rules: rules.len(),
             enabled_rules: enabled_count,
-            tick_interval_ms: DEFAULT_TICK_MS,
+            shadow_rules: rules
+                .iter()
+                .filter(|r| r.rule.enabled && r.rule.shadow)
+                .count(),
+            tick_interval_ms: self.tick_ms,
+            workers,
+            fairness: self.execution.fairness,
+            max_rules_per_tick: self.execution.max_rules_per_tick,
+            ticks: stats.ticks.load(Ordering::Relaxed),
+            tick_overruns: stats.tick_overruns.load(Ordering::Relaxed),
+            last_tick_ms: ms(&stats.last_tick_us),
+            max_tick_ms: ms(&stats.max_tick_us),
+            deferred_executions: stats.deferred_executions.load(Ordering::Relaxed),
+            worker_rules,
+            worker_executions: stats
+                .worker_executions
+                .iter()
+                .map(|count| count.load(Ordering::Relaxed))
+                .collect(),
         }
     }
 
@@ -366,7 +630,7 @@ impl<R: Rtdb + 'static> RuleScheduler<R> {
         let rule = repository::get_rule_for_execution(&self.pool, rule_id).await?;
 
         // Execute it
-        self.executor.execute(&rule).await
+        self.ctx.executor.execute(&rule).await
     }
 
     /// Get execution results for a rule (if cached)
@@ -375,6 +639,66 @@ impl<R: Rtdb + 'static> RuleScheduler<R> {
         // TODO: Implement result caching if needed
         None
     }
+}
+
+impl<R: Rtdb + 'static> ExecContext<R> {
+    /// Execute one rule; returns whether its cooldown starts
+    ///
+    /// A rule with HTTP calls in flight counts as triggered; its record is
+    /// written when they complete and a failed call is reported back.
+    async fn run(self: &Arc<Self>, rule: &Rule) -> bool {
+        match self.executor.execute_detached(rule).await {
+            Ok((result, pending)) if pending.is_empty() => {
+                self.record(rule, &result).await;
+                result.triggered()
+            },
+            Ok((result, pending)) => {
+                let triggered = result.success;
+                let ctx = Arc::clone(self);
+                let rule = rule.clone();
+                tokio::spawn(async move { ctx.complete(&rule, result, pending).await });
+                triggered
+            },
+            Err(e) => {
+                error!("Rule {} err: {}", rule.id, e);
+                false
+            },
+        }
+    }
+
+    /// Record a rule execution once its HTTP calls completed
+    async fn complete(
+        &self,
+        rule: &Rule,
+        mut result: RuleExecutionResult,
+        pending: Vec<PendingHttpCall>,
+    ) {
+        result.complete_http_calls(pending).await;
+        self.record(rule, &result).await;
+        if result.success && !result.triggered() {
+            let _ = self.http_failures.send(rule.id);
+        }
+    }
+
+    /// Log an execution and publish it for WebSocket monitoring
+    async fn record(&self, rule: &Rule, result: &RuleExecutionResult) {
+        // Log rule execution to independent rule log file
+        let logger = self.logger_manager.get_logger(rule.id, &rule.name);
+        logger.log_execution(result, &result.variable_values);
+
+        // Write rule execution result to Redis for WebSocket monitoring
+        self.write_rule_exec_to_redis(rule.id, result).await;
+
+        if result.success {
+            debug!(
+                "Rule {} executed successfully, {} actions",
+                result.rule_id,
+                result.actions_executed.len()
+            );
+        } else {
+            warn!("Rule {} fail: {:?}", result.rule_id, result.error);
+        }
+    }
 
     /// Write rule execution result to Redis
     ///
@@ -408,6 +732,20 @@ impl<R: Rtdb + 'static> RuleScheduler<R> {
             )
             .await;
 
+        // Write shadow flag (actions recorded but not written)
+        let _ = self
+            .rtdb
+            .hash_set(&exec_key, "shadow", Bytes::from(result.shadow.to_string()))
+            .await;
+
+        // Write executed actions as JSON
+        if let Ok(actions_json) = serde_json::to_string(&result.actions_executed) {
+            let _ = self
+                .rtdb
+                .hash_set(&exec_key, "actions", Bytes::from(actions_json))
+                .await;
+        }
+
         // Write execution path as JSON
         if let Ok(path_json) = serde_json::to_string(&result.execution_path) {
             let _ = self
@@ -449,12 +787,29 @@ pub struct SchedulerStatus {
     pub running: bool,
     pub total_rules: usize,
     pub enabled_rules: usize,
+    /// Enabled rules running in shadow mode
+    pub shadow_rules: usize,
     pub tick_interval_ms: u64,
+    pub workers: usize,
+    pub fairness: FairnessPolicy,
+    pub max_rules_per_tick: usize,
+    pub ticks: u64,
+    /// Ticks that took longer than the tick interval
+    pub tick_overruns: u64,
+    pub last_tick_ms: f64,
+    pub max_tick_ms: f64,
+    /// Rule executions postponed by `max_rules_per_tick`
+    pub deferred_executions: u64,
+    /// Enabled rules pinned to each worker
+    pub worker_rules: Vec<usize>,
+    pub worker_executions: Vec<u64>,
 }
 
 #[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
 mod tests {
     use super::*;
+    use crate::types::RuleFlow;
 
     #[test]
     fn test_trigger_config_default() {
@@ -462,4 +817,81 @@ mod tests {
         let TriggerConfig::Interval { interval_ms } = config;
         assert_eq!(interval_ms, 1000);
     }
+
+    fn due(id: i64, priority: u32, overdue_ms: u64) -> DueRule {
+        DueRule {
+            idx: id as usize,
+            rule: Rule {
+                id,
+                name: format!("rule_{}", id),
+                description: None,
+                enabled: true,
+                priority,
+                cooldown_ms: 0,
+                shadow: false,
+                flow: RuleFlow {
+                    start_node: "start".to_string(),
+                    nodes: Default::default(),
+                },
+            },
+            overdue: Duration::from_millis(overdue_ms),
+        }
+    }
+
+    #[test]
+    fn test_fairness_order() {
+        let mut rules = vec![due(1, 0, 10), due(2, 5, 0), due(3, 0, 300), due(4, 5, 20)];
+
+        order_due(&mut rules, FairnessPolicy::LongestWaiting);
+        let ids: Vec<i64> = rules.iter().map(|d| d.rule.id).collect();
+        assert_eq!(ids, vec![3, 4, 1, 2]);
+
+        order_due(&mut rules, FairnessPolicy::Priority);
+        let ids: Vec<i64> = rules.iter().map(|d| d.rule.id).collect();
+        assert_eq!(ids, vec![4, 2, 3, 1]);
+
+        assert_eq!(
+            "priority".parse::<FairnessPolicy>().unwrap(),
+            FairnessPolicy::Priority
+        );
+        assert!("random".parse::<FairnessPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_rule_affinity() {
+        // A rule always lands on the same worker, negative IDs included
+        assert_eq!(worker_for(7, 4), worker_for(7, 4));
+        assert_eq!(worker_for(7, 4), 3);
+        assert_eq!(worker_for(-1, 4), 3);
+        assert_eq!(worker_for(5, 0), 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_http_call_clears_cooldown() {
+        let scheduler = RuleScheduler::new(
+            Arc::new(voltage_rtdb::MemoryRtdb::new()),
+            Arc::new(RoutingCache::default()),
+            SqlitePool::connect_lazy("sqlite::memory:").unwrap(),
+            DEFAULT_TICK_MS,
+            std::env::temp_dir(),
+        );
+        let started = Some(Instant::now());
+        scheduler
+            .rules
+            .write()
+            .await
+            .extend([1, 2].map(|id| ScheduledRule {
+                rule: due(id, 0, 0).rule,
+                trigger: TriggerConfig::default(),
+                last_execution: started,
+                last_cooldown_start: started,
+            }));
+
+        scheduler.ctx.http_failures.send(2).unwrap();
+        scheduler.revoke_failed_http_triggers().await;
+
+        let rules = scheduler.rules.read().await;
+        assert_eq!(rules[0].last_cooldown_start, started);
+        assert_eq!(rules[1].last_cooldown_start, None);
+    }
 }
@@ -3,7 +3,7 @@
 //! Core types for rule parsing and execution:
 //! - Rule: execution structure with compact flow topology
 //! - RuleFlow: simplified flow topology for execution
-//! - RuleNode: node variants (Start, End, Switch, ChangeValue, Calculation)
+//! - RuleNode: node variants (Start, End, Switch, ChangeValue, Calculation, Http)
 //! - Supporting types for variables, conditions, and assignments
 
 use serde::{Deserialize, Serialize};
@@ -38,6 +38,10 @@ pub struct Rule {
     #[serde(default)]
     pub cooldown_ms: u64,
 
+    /// Shadow mode: execute and log would-be actions without writing to RTDB
+    #[serde(default)]
+    pub shadow: bool,
+
     /// Rule flow topology (nodes with local variables)
     pub flow: RuleFlow,
 }
@@ -108,6 +112,18 @@ pub enum RuleNode {
         /// Output wires
         wires: RuleWires,
     },
+
+    /// HTTP action node - call an external endpoint
+    #[serde(rename = "action-http")]
+    Http {
+        /// Variables available to the URL/body templates
+        #[serde(default)]
+        variables: Vec<RuleVariable>,
+        /// Request definition
+        rule: HttpRequestRule,
+        /// Output wires
+        wires: RuleWires,
+    },
 }
 
 /// Rule wires - output connections
@@ -139,6 +155,194 @@ pub struct RuleVariable {
     /// Formula tokens (for combined type, if non-empty this is a calculated variable)
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub formula: Vec<serde_json::Value>,
+
+    /// Selector over several instances (e.g. "inst:pv_*:M:3",
+    /// "inst:pv_*:M:power" or "tag:feeder:F1:M:power"), used instead of
+    /// instance/pointType/point (read-only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+
+    /// Reducer for selector matches: "sum" (default), "avg", "count", "min", "max"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reduce: Option<String>,
+}
+
+/// Parsed instance selector: `inst:{name_pattern}:{M|A}:{point}` or
+/// `tag:{key}:{value}:{M|A}:{point}`
+///
+/// `name_pattern` is matched against instance names; `*` matches any run of
+/// characters and `?` a single character. A tag selector takes the instances
+/// carrying the tag (see modsrv instance tags). `point` is a point ID, or a
+/// point name looked up in each matched instance's product definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstanceSelector {
+    /// Which instances the selector covers
+    pub instances: SelectorTarget,
+    /// Whether the selector reads action (A) points instead of measurements (M)
+    pub is_action: bool,
+    /// Point ID or name
+    pub point: SelectorPoint,
+}
+
+/// Instances covered by an [`InstanceSelector`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelectorTarget {
+    /// Instance name pattern (e.g. "pv_*")
+    Name(String),
+    /// Instances tagged `key` = `value` (e.g. feeder = F1)
+    Tag { key: String, value: String },
+}
+
+impl std::fmt::Display for SelectorTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Name(pattern) => write!(f, "inst:{}", pattern),
+            Self::Tag { key, value } => write!(f, "tag:{}:{}", key, value),
+        }
+    }
+}
+
+/// Point addressed by an [`InstanceSelector`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelectorPoint {
+    Id(u32),
+    /// Point name from the product definition (e.g. "power")
+    Name(String),
+}
+
+impl InstanceSelector {
+    /// Parse a selector string
+    pub fn parse(selector: &str) -> Result<Self, String> {
+        let malformed = || {
+            format!(
+                "Selector '{}' must be inst:<name>:<M|A>:<point> or tag:<key>:<value>:<M|A>:<point>",
+                selector
+            )
+        };
+        // Parsed from the right: tag values may contain ':'
+        let mut parts = selector.rsplitn(3, ':');
+        let (Some(point), Some(point_type), Some(target)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(malformed());
+        };
+        let instances = match target.split_once(':') {
+            Some(("inst", pattern)) if !pattern.is_empty() && !pattern.contains(':') => {
+                SelectorTarget::Name(pattern.to_string())
+            },
+            Some(("tag", tag)) => match tag.split_once(':') {
+                Some((key, value)) if !key.is_empty() && !value.is_empty() => SelectorTarget::Tag {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                },
+                _ => return Err(malformed()),
+            },
+            _ => return Err(malformed()),
+        };
+        let is_action = match point_type {
+            "M" | "measurement" => false,
+            "A" | "action" => true,
+            other => {
+                return Err(format!(
+                    "Selector '{}': unknown point type '{}'",
+                    selector, other
+                ))
+            },
+        };
+        let point =
+            if point.bytes().all(|b| b.is_ascii_digit()) {
+                SelectorPoint::Id(point.parse::<u32>().map_err(|_| {
+                    format!("Selector '{}': invalid point id '{}'", selector, point)
+                })?)
+            } else if point.trim() == point {
+                SelectorPoint::Name(point.to_string())
+            } else {
+                return Err(format!(
+                    "Selector '{}': invalid point name '{}'",
+                    selector, point
+                ));
+            };
+
+        Ok(Self {
+            instances,
+            is_action,
+            point,
+        })
+    }
+
+    /// Whether an instance name matches a name pattern selector (tag
+    /// selectors match no names)
+    pub fn matches(&self, name: &str) -> bool {
+        match &self.instances {
+            SelectorTarget::Name(pattern) => glob_match(pattern.as_bytes(), name.as_bytes()),
+            SelectorTarget::Tag { .. } => false,
+        }
+    }
+}
+
+/// Glob match supporting `*` and `?`
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            },
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            },
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                },
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// Reducer applied to the values matched by an [`InstanceSelector`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectorReduce {
+    Sum,
+    Avg,
+    Count,
+    Min,
+    Max,
+}
+
+impl SelectorReduce {
+    /// Parse a reducer name (defaults to sum)
+    pub fn parse(name: Option<&str>) -> Result<Self, String> {
+        match name.unwrap_or("sum") {
+            "sum" => Ok(Self::Sum),
+            "avg" | "mean" => Ok(Self::Avg),
+            "count" => Ok(Self::Count),
+            "min" => Ok(Self::Min),
+            "max" => Ok(Self::Max),
+            other => Err(format!("Unknown reducer '{}'", other)),
+        }
+    }
+
+    /// Reduce the matched values (empty input yields 0)
+    pub fn apply(self, values: &[f64]) -> f64 {
+        if values.is_empty() {
+            return 0.0;
+        }
+        match self {
+            Self::Sum => values.iter().sum(),
+            Self::Avg => values.iter().sum::<f64>() / values.len() as f64,
+            Self::Count => values.len() as f64,
+            Self::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+            Self::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
 }
 
 /// Rule switch branch (condition branch)
@@ -199,3 +403,106 @@ pub struct CalculationRule {
     /// Formula expression (evalexpr syntax, e.g., "a + b * 2")
     pub formula: String,
 }
+
+/// HTTP request issued by `action-http` nodes
+///
+/// `url`, `body` and header values are templates: `{{X1}}` is replaced by the
+/// value of variable `X1`, `{{rule_id}}` / `{{rule_name}}` by the rule's own.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpRequestRule {
+    /// HTTP method (GET, POST, PUT, PATCH, DELETE)
+    #[serde(default = "default_http_method")]
+    pub method: String,
+
+    /// URL template
+    pub url: String,
+
+    /// Extra request headers (values are templates)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+
+    /// Body template; sent as JSON when it parses as JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+
+    /// Request timeout in milliseconds
+    #[serde(default = "default_http_timeout_ms")]
+    pub timeout_ms: u64,
+
+    /// Success criteria
+    #[serde(default)]
+    pub success: HttpSuccessCriteria,
+}
+
+/// When an HTTP action counts as successful
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HttpSuccessCriteria {
+    /// Accepted status codes (any 2xx when empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub status: Vec<u16>,
+
+    /// Text the response body must contain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_contains: Option<String>,
+}
+
+fn default_http_method() -> String {
+    "POST".to_string()
+}
+
+fn default_http_timeout_ms() -> u64 {
+    5000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instance_selector() {
+        let selector = InstanceSelector::parse("inst:pv_*:M:3").unwrap();
+        assert!(!selector.is_action);
+        assert_eq!(selector.point, SelectorPoint::Id(3));
+        assert!(selector.matches("pv_01"));
+        assert!(selector.matches("pv_"));
+        assert!(!selector.matches("battery_01"));
+
+        let selector = InstanceSelector::parse("inst:bat_?_*:A:1").unwrap();
+        assert!(selector.is_action);
+        assert!(selector.matches("bat_1_north"));
+        assert!(!selector.matches("bat_12_north"));
+
+        assert!(InstanceSelector::parse("inst:pv_*:X:3").is_err());
+        let selector = InstanceSelector::parse("inst:pv_*:M:power").unwrap();
+        assert_eq!(selector.point, SelectorPoint::Name("power".to_string()));
+        assert!(InstanceSelector::parse("inst:pv_*:M:").is_err());
+        assert!(InstanceSelector::parse("inst:pv:x:M:3").is_err());
+        assert!(InstanceSelector::parse("inst:pv_*:M: power").is_err());
+        assert!(InstanceSelector::parse("pv_*:M:3").is_err());
+
+        let selector = InstanceSelector::parse("tag:site:north:hall:A:power").unwrap();
+        assert_eq!(
+            selector.instances,
+            SelectorTarget::Tag {
+                key: "site".to_string(),
+                value: "north:hall".to_string()
+            }
+        );
+        assert!(selector.is_action);
+        assert!(!selector.matches("north"));
+        assert!(InstanceSelector::parse("tag:site:M:3").is_err());
+        assert!(InstanceSelector::parse("tag::F1:M:3").is_err());
+    }
+
+    #[test]
+    fn test_selector_reduce() {
+        let values = [100.0, 250.0, 150.0];
+        assert_eq!(SelectorReduce::parse(None).unwrap().apply(&values), 500.0);
+        assert_eq!(SelectorReduce::Avg.apply(&values), 500.0 / 3.0);
+        assert_eq!(SelectorReduce::Count.apply(&values), 3.0);
+        assert_eq!(SelectorReduce::Min.apply(&values), 100.0);
+        assert_eq!(SelectorReduce::Max.apply(&values), 250.0);
+        assert_eq!(SelectorReduce::Avg.apply(&[]), 0.0);
+        assert!(SelectorReduce::parse(Some("median")).is_err());
+    }
+}
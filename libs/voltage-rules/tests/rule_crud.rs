@@ -6,7 +6,10 @@
 
 use serde_json::json;
 use sqlx::SqlitePool;
-use voltage_rules::{delete_rule, extract_rule_flow, get_rule, list_rules, upsert_rule, Result};
+use voltage_rules::{
+    delete_rule, extract_rule_flow, get_rule, get_rule_for_execution, list_rules, list_rules_after,
+    set_rule_shadow, upsert_rule, Result,
+};
 
 /// Create an in-memory SQLite pool and initialize tables
 async fn setup_test_db() -> SqlitePool {
@@ -24,6 +27,7 @@ async fn setup_test_db() -> SqlitePool {
             enabled INTEGER NOT NULL DEFAULT 1,
             priority INTEGER NOT NULL DEFAULT 100,
             cooldown_ms INTEGER NOT NULL DEFAULT 0,
+            shadow INTEGER NOT NULL DEFAULT 0,
             format TEXT NOT NULL DEFAULT 'vue-flow',
             flow_json TEXT NOT NULL,
             nodes_json TEXT NOT NULL,
@@ -174,6 +178,7 @@ async fn test_rule_crud_operations() -> Result<()> {
     assert!(rule["enabled"].as_bool().unwrap());
     assert_eq!(rule["priority"].as_u64().unwrap(), 100);
     assert_eq!(rule["cooldown_ms"].as_u64().unwrap(), 5000);
+    assert!(!rule["shadow"].as_bool().unwrap());
 
     // LIST
     let rules = list_rules(&pool).await?;
@@ -197,6 +202,12 @@ async fn test_rule_crud_operations() -> Result<()> {
     assert_eq!(updated["priority"].as_u64().unwrap(), 200);
     assert_eq!(updated["cooldown_ms"].as_u64().unwrap(), 10000);
 
+    // SHADOW
+    upsert_rule(&pool, rule_id, &rule_json).await?;
+    set_rule_shadow(&pool, rule_id, true).await?;
+    assert!(get_rule(&pool, rule_id).await?["shadow"].as_bool().unwrap());
+    assert!(get_rule_for_execution(&pool, rule_id).await?.shadow);
+
     // DELETE
     delete_rule(&pool, rule_id).await?;
 
@@ -206,6 +217,31 @@ async fn test_rule_crud_operations() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_list_rules_after_cursor() -> Result<()> {
+    let pool = setup_test_db().await;
+    for (id, priority) in [(1, 100), (2, 200), (3, 100), (4, 50), (5, 200)] {
+        let rule = create_rule_json(id, &format!("Rule {}", id), "", true, priority, 0);
+        upsert_rule(&pool, id, &rule).await?;
+    }
+
+    let mut ids = Vec::new();
+    let mut after = None;
+    loop {
+        let (rules, total, next) = list_rules_after(&pool, after.as_deref(), 2).await?;
+        assert_eq!(total, 5);
+        ids.extend(rules.iter().map(|r| r["id"].as_i64().unwrap()));
+        match next {
+            Some(key) => after = Some(key),
+            None => break,
+        }
+    }
+    // Same order as list_rules: priority DESC, id ASC
+    assert_eq!(ids, vec![2, 5, 1, 3, 4]);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_rule_flow_parsing_edge_cases() {
     // Test empty nodes array
@@ -42,6 +42,7 @@ class Settings(BaseSettings):
     # 数据调度设置
     DATA_FETCH_INTERVAL: int = 5  # 秒
     DATA_BATCH_SIZE: int = 100
+    PRODUCT_RULE_SYNC_INTERVAL: int = Field(30, description="产品告警定义同步到实例规则的间隔（秒）")
     
     # 数据库设置
     VOLTAGE_DB_PATH: str = Field("/app/data/voltage.db", description="数据库文件路径")
@@ -53,8 +54,46 @@ class Settings(BaseSettings):
     
     # 安全设置
     CORS_ORIGINS: List[str] = ["*"]
-    RATE_LIMIT_PER_MINUTE: int = 100
-    
+    RATE_LIMIT_PER_MINUTE: int = Field(100, description="告警查询每客户端每分钟请求数")
+    RATE_LIMIT_BURST: int = Field(20, description="告警查询每客户端突发请求数")
+
+    # 事件日志（Redis Streams，见 python-services/voltage_common/journal.py）
+    # 启用后重放 comsrv/modsrv 的 PointWritten 事件评估规则，重启后补上停机期间的越限与恢复，
+    # 并将告警触发/恢复写入 events:alarms（需 comsrv/modsrv 设置 VOLTAGE_EVENT_JOURNAL=on）
+    EVENT_JOURNAL_ENABLED: bool = Field(False, description="是否启用事件日志")
+    EVENT_JOURNAL_GROUP: str = Field("alarmsrv", description="点位事件消费组")
+    EVENT_JOURNAL_CONSUMER: str = Field("", description="消费者名称，默认使用主机名")
+    EVENT_JOURNAL_BATCH_SIZE: int = Field(500, description="每次读取的事件数")
+    EVENT_JOURNAL_MAX_LEN: int = Field(100_000, description="events:alarms 保留条数")
+    EVENT_JOURNAL_MAX_AGE: int = Field(86400, description="events:alarms 保留时长（秒）")
+
+    # 告警通知设置（模板语法见 python-services/voltage_common/templating.py）
+    NOTIFY_MIN_LEVEL: int = Field(1, description="发送通知的最低告警级别（1一般、2重要、3紧急）")
+    NOTIFY_ON_RECOVERY: bool = Field(True, description="告警恢复时是否发送通知")
+    NOTIFY_SUBJECT_TEMPLATE: str = Field(
+        "[{{ level_name }}] {{ rule.rule_name }}{{#if recovered}} 已恢复{{/if}}",
+        description="通知主题模板"
+    )
+    NOTIFY_BODY_TEMPLATE: str = Field(
+        "{{ message }}\n"
+        "来源: {{ rule.service_type }}:{{ rule.channel_id }}{{#if instance.name}} ({{ instance.name }}){{/if}}\n"
+        "点位: {{ rule.data_type }}:{{ rule.point_id }} 当前值: {{ value | default \"-\" }}\n"
+        "时间: {{ timestamp | datetime }}",
+        description="通知正文模板"
+    )
+    # Webhook通知，PAYLOAD模板为JSON文本，留空时发送默认告警结构
+    NOTIFY_WEBHOOK_URLS: List[str] = Field(default_factory=list, description="Webhook地址列表")
+    NOTIFY_WEBHOOK_PAYLOAD_TEMPLATE: Optional[str] = Field(None, description="Webhook消息体JSON模板")
+    NOTIFY_WEBHOOK_TIMEOUT: int = Field(5, description="Webhook请求超时时间（秒）")
+    # 邮件通知
+    NOTIFY_EMAIL_TO: List[str] = Field(default_factory=list, description="收件人列表，留空不发送邮件")
+    NOTIFY_EMAIL_FROM: str = Field("alarmsrv@localhost", description="发件人")
+    SMTP_HOST: str = "localhost"
+    SMTP_PORT: int = 25
+    SMTP_USER: Optional[str] = None
+    SMTP_PASSWORD: Optional[str] = None
+    SMTP_USE_TLS: bool = False
+
     class Config:
         env_file = ".env"
         env_file_encoding = "utf-8"
@@ -104,12 +104,17 @@ class DatabaseManager:
                 current_value REAL NOT NULL,
                 status TEXT NOT NULL DEFAULT 'active',
                 triggered_at INTEGER NOT NULL,
+                acknowledged_by TEXT,
+                acknowledged_at INTEGER,
                 UNIQUE(rule_id),
                 FOREIGN KEY (rule_id) REFERENCES alert_rule (id) ON DELETE CASCADE
             );
             """
             
             conn.execute(create_alert_sql)
+
+            # 旧库的alert表补充确认列
+            self.migrate_alert(conn)
             
             # 创建alert_event表（告警历史）
             create_alert_event_sql = """
@@ -151,6 +156,7 @@ class DatabaseManager:
                 value REAL NOT NULL,
                 enabled BOOLEAN NOT NULL DEFAULT 1,
                 description TEXT DEFAULT '',
+                definition_id INTEGER,
                 created_at INTEGER DEFAULT (strftime('%s', 'now')),
                 updated_at INTEGER DEFAULT (strftime('%s', 'now')),
                 UNIQUE(service_type, channel_id, data_type, point_id, rule_name)
@@ -158,7 +164,48 @@ class DatabaseManager:
             """
             
             conn.execute(create_alert_rule_sql)
-            
+
+            # 旧库的alert_rule表补充definition_id列
+            self.migrate_alert_rule(conn)
+
+            # 创建product_alert_rule表（产品告警定义，实例继承）
+            create_product_alert_rule_sql = """
+            CREATE TABLE IF NOT EXISTS product_alert_rule (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                product_name TEXT NOT NULL,
+                data_type TEXT NOT NULL DEFAULT 'M' CHECK(data_type IN ('M', 'A')),
+                point_id INTEGER NOT NULL,
+                rule_name TEXT NOT NULL,
+                warning_level INTEGER NOT NULL CHECK(warning_level IN (1, 2, 3)),
+                operator TEXT NOT NULL CHECK(operator IN ('>', '<', '>=', '<=', '==', '!=')),
+                value REAL NOT NULL,
+                enabled BOOLEAN NOT NULL DEFAULT 1,
+                description TEXT DEFAULT '',
+                created_at INTEGER DEFAULT (strftime('%s', 'now')),
+                updated_at INTEGER DEFAULT (strftime('%s', 'now')),
+                UNIQUE(product_name, data_type, point_id, rule_name)
+            );
+            """
+
+            conn.execute(create_product_alert_rule_sql)
+
+            # 创建product_alert_override表（实例覆盖项，NULL表示沿用定义）
+            create_product_alert_override_sql = """
+            CREATE TABLE IF NOT EXISTS product_alert_override (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                definition_id INTEGER NOT NULL,
+                instance_id INTEGER NOT NULL,
+                warning_level INTEGER CHECK(warning_level IS NULL OR warning_level IN (1, 2, 3)),
+                operator TEXT CHECK(operator IS NULL OR operator IN ('>', '<', '>=', '<=', '==', '!=')),
+                value REAL,
+                enabled BOOLEAN,
+                UNIQUE(definition_id, instance_id),
+                FOREIGN KEY (definition_id) REFERENCES product_alert_rule (id) ON DELETE CASCADE
+            );
+            """
+
+            conn.execute(create_product_alert_override_sql)
+
             # 创建索引以提高查询性能
             indexes = [
                 # alert_rule表索引
@@ -169,7 +216,12 @@ class DatabaseManager:
                 "CREATE INDEX IF NOT EXISTS idx_alert_rule_created_at ON alert_rule(created_at);",
                 "CREATE INDEX IF NOT EXISTS idx_alert_rule_rule_name ON alert_rule(rule_name);",
                 "CREATE INDEX IF NOT EXISTS idx_alert_rule_description ON alert_rule(description);",
-                
+                "CREATE INDEX IF NOT EXISTS idx_alert_rule_definition_id ON alert_rule(definition_id);",
+
+                # product_alert_rule / product_alert_override表索引
+                "CREATE INDEX IF NOT EXISTS idx_product_alert_rule_product ON product_alert_rule(product_name);",
+                "CREATE INDEX IF NOT EXISTS idx_product_alert_override_instance ON product_alert_override(instance_id);",
+
                 # alert表索引
                 "CREATE INDEX IF NOT EXISTS idx_alert_rule_id ON alert(rule_id);",
                 "CREATE INDEX IF NOT EXISTS idx_alert_service_channel ON alert(service_type, channel_id);",
@@ -199,13 +251,38 @@ class DatabaseManager:
             END;
             """
             conn.execute(trigger_sql)
-            
+
+            product_trigger_sql = """
+            CREATE TRIGGER IF NOT EXISTS update_product_alert_rule_timestamp
+            AFTER UPDATE ON product_alert_rule
+            BEGIN
+                UPDATE product_alert_rule SET updated_at = strftime('%s', 'now') WHERE id = NEW.id;
+            END;
+            """
+            conn.execute(product_trigger_sql)
+
             logger.info("数据库表结构创建完成")
-            
+
         except Exception as e:
             logger.error(f"创建表结构失败: {e}")
             raise
-    
+
+    def migrate_alert(self, conn: sqlite3.Connection):
+        """为旧版alert表添加acknowledged_by/acknowledged_at列（操作员确认）"""
+        columns = {row[1] for row in conn.execute("PRAGMA table_info(alert);")}
+        if "acknowledged_by" not in columns:
+            conn.execute("ALTER TABLE alert ADD COLUMN acknowledged_by TEXT;")
+        if "acknowledged_at" not in columns:
+            conn.execute("ALTER TABLE alert ADD COLUMN acknowledged_at INTEGER;")
+            logger.info("alert表已添加确认列")
+
+    def migrate_alert_rule(self, conn: sqlite3.Connection):
+        """为旧版alert_rule表添加definition_id列（产品告警定义生成的实例规则）"""
+        columns = {row[1] for row in conn.execute("PRAGMA table_info(alert_rule);")}
+        if "definition_id" not in columns:
+            conn.execute("ALTER TABLE alert_rule ADD COLUMN definition_id INTEGER;")
+            logger.info("alert_rule表已添加definition_id列")
+
     @contextmanager
     def get_connection(self):
         """获取数据库连接的上下文管理器"""
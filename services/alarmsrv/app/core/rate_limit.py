@@ -0,0 +1,164 @@
+"""
+告警查询限流
+令牌桶按 (路由类别, 客户端IP) 计数，与 Rust 服务的 common::rate_limit 行为一致：
+- 仅当对端为可信代理（TRUSTED_PROXIES，逗号分隔IP）时才采用 X-Forwarded-For 中最近的非可信地址
+- 桶数量有上限，满时只淘汰已回满的桶，未回满的桶不会被挤掉，
+  轮换大量源地址也无法让已被限流的客户端恢复突发额度；无桶可淘汰时新客户端需等待
+- 超限返回 HTTP 429 并带 Retry-After 头
+"""
+
+import ipaddress
+import logging
+import math
+import os
+import threading
+import time
+from typing import Callable, Dict, List, Optional, Tuple
+
+from fastapi import Request
+from fastapi.responses import JSONResponse
+
+logger = logging.getLogger(__name__)
+
+TRUSTED_PROXIES_ENV = "TRUSTED_PROXIES"
+
+# 同时跟踪的 (类别, 客户端) 桶上限
+MAX_BUCKETS = 10_000
+
+
+def trusted_proxies_from_env() -> List[str]:
+    """解析可信代理列表，无效条目记录警告后跳过"""
+    proxies = []
+    for entry in os.environ.get(TRUSTED_PROXIES_ENV, "").split(","):
+        entry = entry.strip()
+        if not entry:
+            continue
+        try:
+            proxies.append(str(ipaddress.ip_address(entry)))
+        except ValueError:
+            logger.warning(f"忽略无效的 {TRUSTED_PROXIES_ENV} 条目: {entry}")
+    return proxies
+
+
+class RouteClass:
+    """共享同一限额的一组路由，prefixes 为 GET 路径前缀"""
+
+    def __init__(self, name: str, burst: int, per_second: float, prefixes: List[str]):
+        self.name = name
+        self.burst = max(burst, 1)
+        self.per_second = max(per_second, 0.0)
+        self.prefixes = prefixes
+
+    def matches(self, method: str, path: str) -> bool:
+        return method == "GET" and any(
+            path == prefix or path.startswith(prefix + "/") for prefix in self.prefixes
+        )
+
+
+class RateLimiter:
+    """按客户端的令牌桶限流器"""
+
+    def __init__(self, classes: List[RouteClass], trusted_proxies: Optional[List[str]] = None,
+                 clock: Callable[[], float] = time.monotonic):
+        self.classes = classes
+        self.trusted_proxies = set(trusted_proxies or [])
+        self.clock = clock
+        # (类别序号, 客户端) -> [令牌数, 更新时间]
+        self._buckets: Dict[Tuple[int, str], List[float]] = {}
+        # 在此之前没有桶会回满，清理也腾不出空间
+        self._next_sweep: Optional[float] = None
+        self._lock = threading.Lock()
+
+    def classify(self, method: str, path: str) -> Optional[int]:
+        """返回第一个匹配的类别序号"""
+        for index, route_class in enumerate(self.classes):
+            if route_class.matches(method, path):
+                return index
+        return None
+
+    def _time_to_full(self, class_index: int, bucket: List[float], now: float) -> float:
+        limits = self.classes[class_index]
+        tokens = bucket[0] + (now - bucket[1]) * limits.per_second
+        if tokens >= limits.burst:
+            return 0.0
+        if limits.per_second <= 0:
+            return math.inf
+        return (limits.burst - tokens) / limits.per_second
+
+    def _make_room(self, now: float) -> Optional[float]:
+        """确保还能新建一个桶，否则返回需要等待的秒数"""
+        if len(self._buckets) < MAX_BUCKETS:
+            return None
+        if self._next_sweep is not None and now < self._next_sweep:
+            return self._next_sweep - now
+
+        first_full = math.inf
+        for key, bucket in list(self._buckets.items()):
+            wait = self._time_to_full(key[0], bucket, now)
+            if wait == 0:
+                del self._buckets[key]
+            else:
+                first_full = min(first_full, wait)
+        if len(self._buckets) < MAX_BUCKETS:
+            self._next_sweep = None
+            return None
+        self._next_sweep = now + first_full
+        return first_full
+
+    def check(self, class_index: int, client: str) -> Optional[float]:
+        """取一个令牌；被拒绝时返回距下一个令牌的秒数"""
+        limits = self.classes[class_index]
+        now = self.clock()
+        key = (class_index, client)
+        with self._lock:
+            bucket = self._buckets.get(key)
+            if bucket is None:
+                wait = self._make_room(now)
+                if wait is not None:
+                    return wait
+                bucket = self._buckets[key] = [float(limits.burst), now]
+
+            bucket[0] = min(bucket[0] + (now - bucket[1]) * limits.per_second, float(limits.burst))
+            bucket[1] = now
+            if bucket[0] >= 1.0:
+                bucket[0] -= 1.0
+                return None
+            if limits.per_second > 0:
+                return (1.0 - bucket[0]) / limits.per_second
+            return math.inf
+
+    def client_ip(self, request: Request) -> str:
+        """对端地址；可信代理转发时取 X-Forwarded-For 中最近的非可信地址（更左侧的条目由客户端控制）"""
+        peer = request.client.host if request.client else "unknown"
+        if peer not in self.trusted_proxies:
+            return peer
+        client = peer
+        hops = [hop for value in request.headers.getlist("x-forwarded-for") for hop in value.split(",")]
+        for hop in reversed(hops):
+            try:
+                client = str(ipaddress.ip_address(hop.strip()))
+            except ValueError:
+                break
+            if client not in self.trusted_proxies:
+                break
+        return client
+
+    async def middleware(self, request: Request, call_next):
+        """FastAPI HTTP 中间件（app.middleware("http")(limiter.middleware)）"""
+        class_index = self.classify(request.method, request.url.path)
+        if class_index is None:
+            return await call_next(request)
+
+        client = self.client_ip(request)
+        retry_after = self.check(class_index, client)
+        if retry_after is None:
+            return await call_next(request)
+
+        class_name = self.classes[class_index].name
+        retry_secs = max(1, math.ceil(min(retry_after, 3600.0)))
+        logger.warning(f"限流 '{class_name}' 超限: {client} {request.method} {request.url.path}")
+        return JSONResponse(
+            status_code=429,
+            content={"detail": f"请求过于频繁（限额 '{class_name}'），请 {retry_secs} 秒后重试"},
+            headers={"Retry-After": str(retry_secs)},
+        )
@@ -4,7 +4,9 @@
 """
 
 from .alert_rule import AlertRule, WarningLevel, ComparisonOperator, DataType, ServiceType
+from .product_alert_rule import ProductAlertRule, ProductAlertOverride
 from .alert import Alert, AlertEvent, AlertStatus, EventType
 
 __all__ = ["AlertRule", "WarningLevel", "ComparisonOperator", "DataType", "ServiceType", 
+           "ProductAlertRule", "ProductAlertOverride",
            "Alert", "AlertEvent", "AlertStatus", "EventType"]
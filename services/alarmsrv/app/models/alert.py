@@ -16,6 +16,11 @@ class AlertStatus(Enum):
     RESOLVED = "resolved"  # 已恢复
 
 
+# 告警级别名称及告警横幅颜色
+LEVEL_NAMES = {1: "一般", 2: "重要", 3: "紧急"}
+LEVEL_COLORS = {1: "#FFC107", 2: "#FF9800", 3: "#F44336"}
+
+
 class EventType(Enum):
     """事件类型枚举"""
     TRIGGER = "trigger"
@@ -39,6 +44,8 @@ class Alert:
     current_value: float = 0.0
     status: str = "active"
     triggered_at: Optional[int] = None  # 时间戳（秒）
+    acknowledged_by: Optional[str] = None  # 确认人
+    acknowledged_at: Optional[int] = None  # 确认时间戳（秒），未确认为None
     
     def to_dict(self) -> Dict[str, Any]:
         """转换为字典格式"""
@@ -57,6 +64,9 @@ class Alert:
             "current_value": self.current_value,
             "status": self.status,
             "triggered_at": self.triggered_at,
+            "acknowledged": self.acknowledged_at is not None,
+            "acknowledged_by": self.acknowledged_by,
+            "acknowledged_at": self.acknowledged_at,
         }
     
     @classmethod
@@ -77,6 +87,8 @@ class Alert:
             current_value=data.get("current_value", 0.0),
             status=data.get("status", "active"),
             triggered_at=cls.isoformat_to_timestamp(data.get("triggered_at")),
+            acknowledged_by=data.get("acknowledged_by"),
+            acknowledged_at=cls.isoformat_to_timestamp(data.get("acknowledged_at")),
         )
     
     @staticmethod
@@ -59,6 +59,7 @@ class AlertRule:
     value: float = 0.0
     enabled: bool = True
     description: str = ""
+    definition_id: Optional[int] = None  # 由产品告警定义生成时指向 product_alert_rule.id
     created_at: Optional[int] = None  # 时间戳（秒）
     updated_at: Optional[int] = None  # 时间戳（秒）
 
@@ -76,6 +77,7 @@ class AlertRule:
             "value": self.value,
             "enabled": self.enabled,
             "description": self.description,
+            "definition_id": self.definition_id,
             "created_at": self.created_at,
             "updated_at": self.updated_at,
         }
@@ -95,6 +97,7 @@ class AlertRule:
             value=data.get("value", 0.0),
             enabled=data.get("enabled", True),
             description=data.get("description", ""),
+            definition_id=data.get("definition_id"),
             created_at=cls.isoformat_to_timestamp(data.get("created_at")),
             updated_at=cls.isoformat_to_timestamp(data.get("updated_at")),
         )
@@ -123,6 +126,9 @@ class AlertRule:
 
     def redis_key(self) -> str:
         """生成对应的Redis键"""
+        # modsrv 的 channel_id 为实例ID，实例数据存放在 inst:{id}:M / inst:{id}:A
+        if self.service_type == "modsrv":
+            return f"inst:{self.channel_id}:{self.data_type}"
         return f"{self.service_type}:{self.channel_id}:{self.data_type}"
 
     def validate(self) -> bool:
@@ -0,0 +1,185 @@
+"""
+产品告警定义数据模型
+告警规则声明在产品上（如 PCS 过温），该产品的每个实例自动继承，
+实例可单独覆盖级别、操作符、阈值和启用状态。
+同步时按 定义 × 实例 生成 alert_rule 表中的实例规则（definition_id 指向定义），
+由告警监控引擎照常评估。
+"""
+
+from dataclasses import dataclass
+from typing import Optional, Dict, Any
+
+from app.models.alert_rule import AlertRule
+
+VALID_OPERATORS = [">", "<", ">=", "<=", "==", "!="]
+
+
+@dataclass
+class ProductAlertRule:
+    """产品告警定义"""
+    id: Optional[int] = None
+    product_name: str = ""
+    data_type: str = "M"  # 实例点位类型：M 测量，A 动作
+    point_id: int = None
+    rule_name: str = ""
+    warning_level: int = 1
+    operator: str = ">"
+    value: float = 0.0
+    enabled: bool = True
+    description: str = ""
+    created_at: Optional[int] = None  # 时间戳（秒）
+    updated_at: Optional[int] = None  # 时间戳（秒）
+
+    def to_dict(self) -> Dict[str, Any]:
+        """转换为字典格式"""
+        return {
+            "id": self.id,
+            "product_name": self.product_name,
+            "data_type": self.data_type,
+            "point_id": self.point_id,
+            "rule_name": self.rule_name,
+            "warning_level": self.warning_level,
+            "operator": self.operator,
+            "value": self.value,
+            "enabled": self.enabled,
+            "description": self.description,
+            "created_at": self.created_at,
+            "updated_at": self.updated_at,
+        }
+
+    @classmethod
+    def from_dict(cls, data: Dict[str, Any]) -> "ProductAlertRule":
+        """从字典创建实例"""
+        return cls(
+            id=data.get("id"),
+            product_name=(data.get("product_name") or "").strip(),
+            data_type=(data.get("data_type") or "M").strip(),
+            point_id=data.get("point_id"),
+            rule_name=data.get("rule_name", ""),
+            warning_level=data.get("warning_level", 1),
+            operator=data.get("operator", ">"),
+            value=data.get("value", 0.0),
+            enabled=data.get("enabled", True),
+            description=data.get("description", "") or "",
+        )
+
+    def validate_detailed(self) -> tuple[bool, str]:
+        """详细验证定义有效性，返回验证结果和错误信息"""
+        if not self.product_name:
+            return False, "产品名称不能为空"
+
+        if self.data_type not in ["M", "A"]:
+            return False, f"数据类型必须为M(测量)或A(动作)，当前值: '{self.data_type}'"
+
+        if not self.point_id or self.point_id <= 0:
+            return False, f"点位ID必须大于0，当前值: {self.point_id}"
+
+        if not self.rule_name or self.rule_name.strip() == "":
+            return False, "规则名称不能为空"
+
+        if len(self.rule_name.strip()) > 100:
+            return False, f"规则名称过长，最大100字符，当前: {len(self.rule_name)}字符"
+
+        if self.warning_level not in [1, 2, 3]:
+            return False, f"告警级别必须为1(一般)、2(重要)或3(紧急)，当前值: {self.warning_level}"
+
+        if self.operator not in VALID_OPERATORS:
+            return False, f"不支持的比较操作符'{self.operator}'，支持的操作符: {', '.join(VALID_OPERATORS)}"
+
+        if self.description and len(self.description) > 500:
+            return False, f"描述过长，最大500字符，当前: {len(self.description)}字符"
+
+        try:
+            float(self.value)
+        except (ValueError, TypeError):
+            return False, f"阈值必须为有效数值，当前值: {self.value}"
+
+        return True, "验证通过"
+
+    def materialize(self, instance_id: int,
+                    override: Optional["ProductAlertOverride"] = None) -> AlertRule:
+        """生成实例规则，覆盖项中非空的字段替换定义中的值"""
+        rule = AlertRule(
+            service_type="modsrv",
+            channel_id=instance_id,
+            data_type=self.data_type,
+            point_id=self.point_id,
+            rule_name=self.rule_name,
+            warning_level=self.warning_level,
+            operator=self.operator,
+            value=float(self.value),
+            enabled=bool(self.enabled),
+            description=self.description,
+            definition_id=self.id,
+        )
+        if override:
+            if override.warning_level is not None:
+                rule.warning_level = override.warning_level
+            if override.operator is not None:
+                rule.operator = override.operator
+            if override.value is not None:
+                rule.value = float(override.value)
+            # 定义禁用时实例规则一律禁用，覆盖只能额外禁用
+            if override.enabled is not None:
+                rule.enabled = rule.enabled and bool(override.enabled)
+        return rule
+
+
+@dataclass
+class ProductAlertOverride:
+    """实例覆盖项，为空的字段沿用产品定义"""
+    id: Optional[int] = None
+    definition_id: int = None
+    instance_id: int = None
+    warning_level: Optional[int] = None
+    operator: Optional[str] = None
+    value: Optional[float] = None
+    enabled: Optional[bool] = None
+
+    def to_dict(self) -> Dict[str, Any]:
+        """转换为字典格式"""
+        return {
+            "id": self.id,
+            "definition_id": self.definition_id,
+            "instance_id": self.instance_id,
+            "warning_level": self.warning_level,
+            "operator": self.operator,
+            "value": self.value,
+            "enabled": self.enabled,
+        }
+
+    @classmethod
+    def from_dict(cls, data: Dict[str, Any]) -> "ProductAlertOverride":
+        """从字典创建实例"""
+        enabled = data.get("enabled")
+        return cls(
+            id=data.get("id"),
+            definition_id=data.get("definition_id"),
+            instance_id=data.get("instance_id"),
+            warning_level=data.get("warning_level"),
+            operator=data.get("operator"),
+            value=data.get("value"),
+            enabled=None if enabled is None else bool(enabled),
+        )
+
+    def validate_detailed(self) -> tuple[bool, str]:
+        """详细验证覆盖项有效性，返回验证结果和错误信息"""
+        if not self.instance_id or self.instance_id <= 0:
+            return False, f"实例ID必须大于0，当前值: {self.instance_id}"
+
+        if self.warning_level is not None and self.warning_level not in [1, 2, 3]:
+            return False, f"告警级别必须为1(一般)、2(重要)或3(紧急)，当前值: {self.warning_level}"
+
+        if self.operator is not None and self.operator not in VALID_OPERATORS:
+            return False, f"不支持的比较操作符'{self.operator}'，支持的操作符: {', '.join(VALID_OPERATORS)}"
+
+        if self.value is not None:
+            try:
+                self.value = float(self.value)
+            except (ValueError, TypeError):
+                return False, f"阈值必须为有效数值，当前值: {self.value}"
+
+        if all(v is None for v in (self.warning_level, self.operator, self.value, self.enabled)):
+            return False, "覆盖项至少包含 warning_level、operator、value、enabled 之一"
+
+        return True, "验证通过"
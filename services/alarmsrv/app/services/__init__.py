@@ -4,9 +4,15 @@
 """
 
 from .alert_rule_service import AlertRuleService, alert_rule_service
+from .product_alert_rule_service import ProductAlertRuleService, product_alert_rule_service
 from .alert_service import AlertService, alert_service
 from .alarm_monitor import AlarmMonitor, alarm_monitor
+from .alarm_statistics_service import AlarmStatisticsService, alarm_statistics_service
+from .notification_service import NotificationService, notification_service
 
 __all__ = ["AlertRuleService", "alert_rule_service", 
+           "ProductAlertRuleService", "product_alert_rule_service",
            "AlertService", "alert_service", 
-           "AlarmMonitor", "alarm_monitor"]
+           "AlarmMonitor", "alarm_monitor",
+           "AlarmStatisticsService", "alarm_statistics_service",
+           "NotificationService", "notification_service"]
@@ -5,6 +5,7 @@
 
 import asyncio
 import logging
+import socket
 import redis
 import json
 import requests
@@ -15,10 +16,20 @@ from concurrent.futures import ThreadPoolExecutor
 from app.core.config import settings
 from app.services.alert_rule_service import alert_rule_service
 from app.services.alert_service import alert_service
+from app.services.notification_service import notification_service
+from app.services.product_alert_rule_service import product_alert_rule_service
 from app.models.alert_rule import AlertRule
 
+try:
+    from voltage_common import journal
+except ImportError:  # 单独构建的服务镜像不包含公共模块
+    journal = None
+
 logger = logging.getLogger(__name__)
 
+# 每个监控周期最多重放的事件批数，其余留到下个周期
+JOURNAL_MAX_BATCHES = 20
+
 
 class AlarmMonitor:
     """告警监控引擎"""
@@ -31,6 +42,9 @@ class AlarmMonitor:
         self.executor = ThreadPoolExecutor(max_workers=4)
         self.last_check_time = None
         self.last_alarm_count = 0  # 上次广播的告警数量
+        self.last_product_sync_time = None  # 上次产品告警定义同步时间
+        self.product_sync_lock = asyncio.Lock()
+        self.journal_consumer = None  # 点位事件消费者（EVENT_JOURNAL_ENABLED）
         
     def start(self):
         """启动监控"""
@@ -55,6 +69,16 @@ class AlarmMonitor:
             self.redis_client.ping()
             logger.info(f"Redis连接成功: {settings.REDIS_HOST}:{settings.REDIS_PORT}")
             
+            if settings.EVENT_JOURNAL_ENABLED:
+                if journal is None:
+                    logger.warning("未找到公共模块 voltage_common，事件日志已禁用")
+                else:
+                    consumer = settings.EVENT_JOURNAL_CONSUMER or socket.gethostname()
+                    self.journal_consumer = journal.JournalConsumer(
+                        lambda: self.redis_client, journal.POINT_STREAM,
+                        settings.EVENT_JOURNAL_GROUP, consumer)
+                    logger.info(f"事件日志已启用: 消费组 {settings.EVENT_JOURNAL_GROUP}, 消费者 {consumer}")
+            
             self.is_running = True
             # 启动异步监控任务
             self.monitor_task = asyncio.create_task(self._monitor_loop())
@@ -100,6 +124,12 @@ class AlarmMonitor:
         while self.is_running:
             try:
                 start_time = datetime.now()
+
+                # 定期将产品告警定义同步为实例规则（新实例自动继承）
+                if (self.last_product_sync_time is None or
+                        (start_time - self.last_product_sync_time).total_seconds() >= settings.PRODUCT_RULE_SYNC_INTERVAL):
+                    await self.sync_product_rules()
+                    self.last_product_sync_time = start_time
                 
                 # 获取所有启用的规则
                 enabled_rules = alert_rule_service.get_all_enabled_rules()
@@ -108,6 +138,10 @@ class AlarmMonitor:
                 else:
                     logger.debug(f"监控 {len(enabled_rules)} 条告警规则")
                     
+                    # 先按顺序重放点位事件（含停机期间的），再检查当前值
+                    if self.journal_consumer is not None:
+                        await self._replay_journal(enabled_rules)
+                    
                     # 并发处理规则检查
                     await self._process_rules_concurrent(enabled_rules)
                 
@@ -150,41 +184,114 @@ class AlarmMonitor:
                 logger.debug(f"规则 {rule.rule_name} 无法获取数据: {rule.redis_key()}")
                 return
             
-            # 评估规则
-            is_triggered = rule.evaluate(current_value)
-            
-            # 检查当前是否已有告警
-            existing_alert = alert_service.get_alert_by_rule_id(rule.id)
-            
-            if is_triggered:
-                if existing_alert:
-                    # 已有告警，更新当前值
-                    alert_service.update_alert_value(existing_alert.id, current_value)
-                    logger.debug(f"更新告警值: {rule.rule_name}, 当前值: {current_value}")
-                else:
-                    # 新触发告警
-                    alert_id = alert_service.create_alert(rule, current_value)
-                    if alert_id:
-                        logger.warning(f"触发告警: {rule.rule_name}, 当前值: {current_value}, 阈值: {rule.operator} {rule.value}")
-                        # 发送告警广播
-                        await self._send_alarm_broadcast(alert_id, rule, current_value)
-                        # 立即发送告警数量广播
-                        await self._send_alarm_count_broadcast()
-                        self.last_alarm_count = alert_service.get_active_alert_count()
-            else:
-                if existing_alert:
-                    # 告警恢复
-                    if alert_service.resolve_alert(existing_alert.id, current_value):
-                        logger.info(f"告警恢复: {rule.rule_name}, 当前值: {current_value}")
-                        # 发送恢复广播
-                        await self._send_alarm_recovery_broadcast(existing_alert.id, rule, current_value)
-                        # 立即发送告警数量广播
-                        await self._send_alarm_count_broadcast()
-                        self.last_alarm_count = alert_service.get_active_alert_count()
+            await self._apply_rule_value(rule, current_value)
                 
         except Exception as e:
             logger.error(f"检查规则失败 {rule.rule_name}: {e}")
     
+    async def _apply_rule_value(self, rule: AlertRule, current_value: float):
+        """按给定值评估规则，触发、更新或恢复告警"""
+        is_triggered = rule.evaluate(current_value)
+        
+        # 检查当前是否已有告警
+        existing_alert = alert_service.get_alert_by_rule_id(rule.id)
+        
+        if is_triggered:
+            if existing_alert:
+                # 已有告警，更新当前值
+                alert_service.update_alert_value(existing_alert.id, current_value)
+                logger.debug(f"更新告警值: {rule.rule_name}, 当前值: {current_value}")
+            else:
+                # 新触发告警
+                alert_id = alert_service.create_alert(rule, current_value)
+                if alert_id:
+                    logger.warning(f"触发告警: {rule.rule_name}, 当前值: {current_value}, 阈值: {rule.operator} {rule.value}")
+                    # 发送告警广播
+                    await self._send_alarm_broadcast(alert_id, rule, current_value)
+                    # 立即发送告警数量广播
+                    await self._send_alarm_count_broadcast()
+                    self.last_alarm_count = alert_service.get_active_alert_count()
+        else:
+            if existing_alert:
+                # 告警恢复
+                if alert_service.resolve_alert(existing_alert.id, current_value):
+                    logger.info(f"告警恢复: {rule.rule_name}, 当前值: {current_value}")
+                    # 发送恢复广播
+                    await self._send_alarm_recovery_broadcast(existing_alert.id, rule, current_value)
+                    # 立即发送告警数量广播
+                    await self._send_alarm_count_broadcast()
+                    self.last_alarm_count = alert_service.get_active_alert_count()
+    
+    async def _replay_journal(self, rules: List[AlertRule]):
+        """按写入顺序重放点位事件并评估相关规则，处理完成后确认
+
+        停机期间的事件在重启后从消费组偏移量处继续读取，短暂越限又恢复的点位也能产生告警记录。
+        """
+        rules_by_point: Dict[tuple, List[AlertRule]] = {}
+        for rule in rules:
+            rules_by_point.setdefault((rule.redis_key(), str(rule.point_id)), []).append(rule)
+        
+        loop = asyncio.get_event_loop()
+        batch_size = settings.EVENT_JOURNAL_BATCH_SIZE
+        try:
+            for _ in range(JOURNAL_MAX_BATCHES):
+                events = await loop.run_in_executor(
+                    self.executor, self.journal_consumer.read, batch_size)
+                if not events:
+                    break
+                
+                for event in events:
+                    if event.type != journal.POINT_WRITTEN:
+                        continue
+                    for field, value_str in event.points.items():
+                        matched = rules_by_point.get((event.key, field))
+                        if not matched:
+                            continue
+                        try:
+                            value = float(value_str)
+                        except (ValueError, TypeError):
+                            logger.warning(f"无效的数值格式: {event.key}:{field} = {value_str}")
+                            continue
+                        for rule in matched:
+                            try:
+                                await self._apply_rule_value(rule, value)
+                            except Exception as e:
+                                logger.error(f"重放事件失败 {rule.rule_name} ({event.id}): {e}")
+                
+                await loop.run_in_executor(
+                    self.executor, self.journal_consumer.ack, [event.id for event in events])
+                if len(events) < batch_size:
+                    break
+        except Exception as e:
+            logger.error(f"读取事件日志失败: {e}")
+    
+    async def _append_alarm_event(self, event_type: str, alert_id: int, rule: AlertRule,
+                                  value: Optional[float], reason: Optional[str] = None):
+        """将告警触发/恢复写入 events:alarms，供 hissrv 等消费者补采"""
+        if self.journal_consumer is None or not self.redis_client:
+            return
+        fields = {
+            "alert_id": alert_id,
+            "rule_id": rule.id,
+            "rule_name": rule.rule_name,
+            "service_type": rule.service_type,
+            "channel_id": rule.channel_id,
+            "data_type": rule.data_type,
+            "point_id": rule.point_id,
+            "level": rule.warning_level,
+            "value": value,
+            "reason": reason,
+        }
+        try:
+            loop = asyncio.get_event_loop()
+            await loop.run_in_executor(
+                self.executor,
+                lambda: journal.append(self.redis_client, journal.ALARM_STREAM, event_type, fields,
+                                       max_len=settings.EVENT_JOURNAL_MAX_LEN,
+                                       max_age=settings.EVENT_JOURNAL_MAX_AGE))
+        except Exception as e:
+            logger.error(f"写入告警事件失败: 规则={rule.rule_name}, 告警ID={alert_id}, 异常={e}")
+    
     async def _get_redis_value(self, rule: AlertRule) -> Optional[float]:
         """从Redis获取数据值"""
         try:
@@ -333,6 +440,22 @@ class AlarmMonitor:
         except Exception as e:
             logger.error(f"处理规则删除失败: {e}")
     
+    async def sync_product_rules(self) -> Dict[str, Any]:
+        """同步产品告警定义到实例规则，移除或禁用的规则先解除告警"""
+        async with self.product_sync_lock:
+            plan = product_alert_rule_service.plan_sync()
+
+            # 删除前解除告警并发送恢复广播
+            for rule in plan["delete"]:
+                await self.on_rule_deleted(rule.id)
+
+            result = product_alert_rule_service.apply_sync(plan)
+
+            for rule_id in result["updated"]:
+                await self.on_rule_updated(rule_id)
+
+            return result
+    
     async def _send_alarm_broadcast(self, alert_id: int, rule: AlertRule, current_value: float):
         """发送告警广播消息到6005端口"""
         try:
@@ -396,6 +519,14 @@ class AlarmMonitor:
                         logger.warning(f"告警广播发送失败: 规则={rule.rule_name}, 告警ID={alert_id}, 端点={url}, 错误={response_text}")
             
             logger.info(f"告警广播完成: 规则={rule.rule_name}, 告警ID={alert_id}, 成功={success_count}/{len(broadcast_urls)}")
+
+            # 事件日志
+            if journal is not None:
+                await self._append_alarm_event(journal.ALARM_RAISED, alert_id, rule, current_value)
+
+            # 邮件/Webhook通知
+            await notification_service.notify(rule, broadcast_data, current_value,
+                                              redis_client=self.redis_client)
                 
         except Exception as e:
             logger.error(f"发送告警广播异常: 规则={rule.rule_name}, 告警ID={alert_id}, 异常={e}")
@@ -471,6 +602,15 @@ class AlarmMonitor:
                         logger.warning(f"告警恢复广播发送失败: 规则={rule.rule_name}, 告警ID={alert_id}, 端点={url}, 错误={response_text}")
             
             logger.info(f"告警恢复广播完成: 规则={rule.rule_name}, 告警ID={alert_id}, 成功={success_count}/{len(broadcast_urls)}")
+
+            # 事件日志
+            if journal is not None:
+                await self._append_alarm_event(journal.ALARM_RECOVERED, alert_id, rule,
+                                               recovery_value, reason)
+
+            # 邮件/Webhook通知
+            await notification_service.notify(rule, broadcast_data, recovery_value, recovered=True,
+                                              reason=reason, redis_client=self.redis_client)
                 
         except Exception as e:
             logger.error(f"发送告警恢复广播异常: 规则={rule.rule_name}, 告警ID={alert_id}, 异常={e}")
@@ -0,0 +1,228 @@
+"""
+告警统计服务
+基于告警历史计算运维报表所需的统计指标和KPI
+
+一次告警发生 = alert_event 中的恢复记录（已结束）+ alert 中的活跃告警（未结束），
+均按 triggered_at 落入时间范围统计。
+"""
+
+import logging
+from datetime import datetime, timedelta
+from typing import Any, Dict, List, Optional, Tuple
+
+from app.core.database import get_db_manager
+
+logger = logging.getLogger(__name__)
+
+# 未指定时间范围时默认统计最近30天
+DEFAULT_RANGE_DAYS = 30
+
+# 告警发生记录（历史 + 活跃），供各统计查询复用
+OCCURRENCES_SQL = """
+SELECT service_type, channel_id, rule_id, rule_name, warning_level,
+       triggered_at, duration, 'recovered' AS state
+FROM alert_event
+WHERE event_type = 'recovery' AND triggered_at >= ? AND triggered_at <= ?{event_filter}
+UNION ALL
+SELECT service_type, channel_id, rule_id, rule_name, warning_level,
+       triggered_at, NULL AS duration, 'active' AS state
+FROM alert
+WHERE triggered_at >= ? AND triggered_at <= ?{alert_filter}
+"""
+
+
+class AlarmStatisticsService:
+    """告警统计服务类"""
+
+    def __init__(self):
+        self.db_manager = get_db_manager()
+
+    @staticmethod
+    def resolve_range(start_time: Optional[datetime],
+                      end_time: Optional[datetime]) -> Tuple[datetime, datetime]:
+        """补全统计时间范围（默认最近30天）"""
+        end = end_time or datetime.now()
+        start = start_time or end - timedelta(days=DEFAULT_RANGE_DAYS)
+        return start, end
+
+    def _occurrences(self, start_time: datetime, end_time: datetime,
+                     service_type: str = "") -> Tuple[str, tuple]:
+        """构造告警发生记录子查询及参数"""
+        start_ts, end_ts = int(start_time.timestamp()), int(end_time.timestamp())
+        if service_type:
+            sql = OCCURRENCES_SQL.format(event_filter=" AND service_type = ?",
+                                         alert_filter=" AND service_type = ?")
+            params = (start_ts, end_ts, service_type, start_ts, end_ts, service_type)
+        else:
+            sql = OCCURRENCES_SQL.format(event_filter="", alert_filter="")
+            params = (start_ts, end_ts, start_ts, end_ts)
+        return sql, params
+
+    @staticmethod
+    def _range_dict(start_time: datetime, end_time: datetime) -> Dict[str, str]:
+        return {
+            "start_time": start_time.strftime("%Y-%m-%d %H:%M:%S"),
+            "end_time": end_time.strftime("%Y-%m-%d %H:%M:%S"),
+        }
+
+    def get_daily_counts(self, start_time: datetime, end_time: datetime,
+                         service_type: str = "") -> Dict[str, Any]:
+        """按天和级别统计告警次数"""
+        try:
+            occurrences, params = self._occurrences(start_time, end_time, service_type)
+            sql = f"""
+            SELECT DATE(triggered_at, 'unixepoch', 'localtime') AS day,
+                   warning_level, COUNT(*)
+            FROM ({occurrences})
+            GROUP BY day, warning_level
+            ORDER BY day
+            """
+            rows = self.db_manager.execute_query(sql, params)
+
+            days: Dict[str, Dict[str, Any]] = {}
+            by_level = {1: 0, 2: 0, 3: 0}
+            for day, level, count in rows:
+                entry = days.setdefault(day, {"date": day, "total": 0,
+                                              "by_level": {1: 0, 2: 0, 3: 0}})
+                entry["by_level"][level] = count
+                entry["total"] += count
+                by_level[level] = by_level.get(level, 0) + count
+
+            return {
+                "success": True,
+                "message": "统计数据获取成功",
+                "data": {
+                    **self._range_dict(start_time, end_time),
+                    "total": sum(by_level.values()),
+                    "by_level": by_level,
+                    "days": list(days.values()),
+                }
+            }
+
+        except Exception as e:
+            logger.error(f"按天统计告警失败: {e}")
+            return {"success": False, "message": f"获取失败: {str(e)}", "data": {}}
+
+    def get_kpi(self, start_time: datetime, end_time: datetime,
+                service_type: str = "") -> Dict[str, Any]:
+        """告警KPI：总数、活跃数、平均恢复时间（MTTR）
+
+        告警没有单独的确认操作，平均确认时间以触发到恢复（自动或手动解除）的
+        持续时间计算。
+        """
+        try:
+            occurrences, params = self._occurrences(start_time, end_time, service_type)
+            sql = f"""
+            SELECT warning_level,
+                   COUNT(*),
+                   SUM(CASE WHEN state = 'active' THEN 1 ELSE 0 END),
+                   AVG(duration),
+                   MAX(duration)
+            FROM ({occurrences})
+            GROUP BY warning_level
+            """
+            rows = self.db_manager.execute_query(sql, params)
+
+            by_level: Dict[int, Dict[str, Any]] = {}
+            total = active = 0
+            duration_sum = 0.0
+            recovered = 0
+            max_duration: Optional[int] = None
+            for level, count, level_active, avg_duration, level_max in rows:
+                by_level[level] = {
+                    "count": count,
+                    "active": level_active,
+                    "mean_time_to_recover": round(avg_duration, 1) if avg_duration is not None else None,
+                    "max_time_to_recover": level_max,
+                }
+                total += count
+                active += level_active
+                if avg_duration is not None:
+                    # AVG 忽略 NULL，按已恢复条数加权
+                    level_recovered = count - level_active
+                    duration_sum += avg_duration * level_recovered
+                    recovered += level_recovered
+                if level_max is not None:
+                    max_duration = level_max if max_duration is None else max(max_duration, level_max)
+
+            return {
+                "success": True,
+                "message": "统计数据获取成功",
+                "data": {
+                    **self._range_dict(start_time, end_time),
+                    "total": total,
+                    "active": active,
+                    "recovered": recovered,
+                    "mean_time_to_recover": round(duration_sum / recovered, 1) if recovered else None,
+                    "max_time_to_recover": max_duration,
+                    "by_level": by_level,
+                }
+            }
+
+        except Exception as e:
+            logger.error(f"获取告警KPI失败: {e}")
+            return {"success": False, "message": f"获取失败: {str(e)}", "data": {}}
+
+    def get_top_sources(self, start_time: datetime, end_time: datetime,
+                        service_type: str = "", limit: int = 10) -> Dict[str, Any]:
+        """告警最频繁的来源（服务+通道/实例）及规则"""
+        try:
+            occurrences, params = self._occurrences(start_time, end_time, service_type)
+            sources_sql = f"""
+            SELECT service_type, channel_id, COUNT(*) AS cnt,
+                   MAX(warning_level), COUNT(DISTINCT rule_id)
+            FROM ({occurrences})
+            GROUP BY service_type, channel_id
+            ORDER BY cnt DESC, service_type, channel_id
+            LIMIT ?
+            """
+            rules_sql = f"""
+            SELECT rule_id, rule_name, service_type, channel_id, COUNT(*) AS cnt,
+                   MAX(warning_level)
+            FROM ({occurrences})
+            GROUP BY rule_id
+            ORDER BY cnt DESC, rule_id
+            LIMIT ?
+            """
+            source_rows = self.db_manager.execute_query(sources_sql, params + (limit,))
+            rule_rows = self.db_manager.execute_query(rules_sql, params + (limit,))
+
+            sources: List[Dict[str, Any]] = [
+                {
+                    "service_type": row[0],
+                    "channel_id": row[1],
+                    "count": row[2],
+                    "max_level": row[3],
+                    "rule_count": row[4],
+                }
+                for row in source_rows
+            ]
+            rules: List[Dict[str, Any]] = [
+                {
+                    "rule_id": row[0],
+                    "rule_name": row[1],
+                    "service_type": row[2],
+                    "channel_id": row[3],
+                    "count": row[4],
+                    "max_level": row[5],
+                }
+                for row in rule_rows
+            ]
+
+            return {
+                "success": True,
+                "message": "统计数据获取成功",
+                "data": {
+                    **self._range_dict(start_time, end_time),
+                    "sources": sources,
+                    "rules": rules,
+                }
+            }
+
+        except Exception as e:
+            logger.error(f"获取告警来源排行失败: {e}")
+            return {"success": False, "message": f"获取失败: {str(e)}", "data": {}}
+
+
+# 创建全局服务实例
+alarm_statistics_service = AlarmStatisticsService()
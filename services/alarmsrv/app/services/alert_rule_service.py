@@ -35,8 +35,8 @@ class AlertRuleService:
             INSERT INTO alert_rule (
                 service_type, channel_id, data_type, point_id, rule_name, 
                 warning_level, operator, value, enabled, 
-                description, created_at, updated_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                description, definition_id, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             """
             
             params = (
@@ -50,6 +50,7 @@ class AlertRuleService:
                 rule.value,
                 rule.enabled,
                 rule.description,
+                rule.definition_id,
                 rule.created_at,
                 rule.updated_at
             )
@@ -107,6 +108,18 @@ class AlertRuleService:
             logger.error(f"获取所有告警规则失败: {e}")
             return []
     
+    def get_rules_by_definition(self, definition_id: int) -> List[AlertRule]:
+        """获取由指定产品告警定义生成的实例规则"""
+        try:
+            sql = "SELECT * FROM alert_rule WHERE definition_id = ? ORDER BY channel_id"
+            results = self.db_manager.execute_query(sql, (definition_id,))
+            
+            return [self._row_to_alert_rule(row) for row in results]
+            
+        except Exception as e:
+            logger.error(f"获取定义实例规则失败: {e}")
+            return []
+    
     def get_rules_by_channel(self, channel_id: int) -> List[AlertRule]:
         """根据通道ID获取所有告警规则"""
         try:
@@ -372,6 +385,7 @@ class AlertRuleService:
             value=row["value"],
             enabled=bool(row["enabled"]),
             description=row["description"] or "",
+            definition_id=row["definition_id"] if "definition_id" in row.keys() else None,
             created_at=row["created_at"],  # 直接使用时间戳
             updated_at=row["updated_at"],  # 直接使用时间戳
         )
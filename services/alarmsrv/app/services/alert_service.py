@@ -13,6 +13,12 @@ from typing import List, Optional, Dict, Any
 from app.models.alert import Alert, AlertEvent
 from app.models.alert_rule import AlertRule
 from app.core.database import get_db_manager
+from app.utils.pagination import Keyset, decode_cursor, next_cursor
+
+# 当前告警列表排序：级别升序、触发时间倒序，id 保证排序唯一
+ALERT_KEYSET = Keyset([("warning_level", False), ("triggered_at", True), ("id", True)])
+# 告警事件历史排序：触发时间倒序
+ALERT_EVENT_KEYSET = Keyset([("triggered_at", True), ("id", True)])
 
 logger = logging.getLogger(__name__)
 
@@ -134,8 +140,9 @@ class AlertService:
     
     def search_alerts(self, keyword: str = "", warning_level: Optional[int] = None,
                      service_type: str = "", start_time: Optional[datetime] = None,
-                     end_time: Optional[datetime] = None, page: int = 1, page_size: int = 10) -> Dict[str, Any]:
-        """搜索告警"""
+                     end_time: Optional[datetime] = None, page: int = 1, page_size: int = 10,
+                     cursor: Optional[str] = None) -> Dict[str, Any]:
+        """搜索告警，传入 cursor 时按游标分页（忽略 page）"""
         try:
             conditions = ["status = 'active'"]
             params = []
@@ -162,7 +169,6 @@ class AlertService:
                 params.append(int(end_time.timestamp()))
             
             where_clause = " AND ".join(conditions)
-            offset = (page - 1) * page_size
             
             # 查询总数
             count_sql = f"SELECT COUNT(*) FROM alert WHERE {where_clause}"
@@ -170,13 +176,9 @@ class AlertService:
             total = count_result[0][0] if count_result else 0
             
             # 查询数据
-            data_sql = f"""
-            SELECT * FROM alert WHERE {where_clause} 
-            ORDER BY warning_level ASC, triggered_at DESC 
-            LIMIT ? OFFSET ?
-            """
-            data_params = list(params) + [page_size, offset]
-            results = self.db_manager.execute_query(data_sql, tuple(data_params))
+            results, cursor_next = self._query_page(
+                "alert", where_clause, params, ALERT_KEYSET, page, page_size, cursor
+            )
             
             alerts = [self._row_to_alert(row) for row in results]
             
@@ -185,7 +187,8 @@ class AlertService:
                 "message": f"查询成功，共找到 {total} 条记录",
                 "data": {
                     "total": total,
-                    "list": [alert.to_dict() for alert in alerts]
+                    "list": [alert.to_dict() for alert in alerts],
+                    "next_cursor": cursor_next
                 }
             }
             
@@ -210,6 +213,36 @@ class AlertService:
             logger.error(f"获取所有活跃告警失败: {e}")
             return []
     
+    def get_unacknowledged_alerts(self) -> List[Alert]:
+        """获取未确认的活跃告警（告警横幅），紧急级别在前"""
+        try:
+            sql = """
+            SELECT * FROM alert WHERE status = 'active' AND acknowledged_at IS NULL
+            ORDER BY warning_level DESC, triggered_at DESC, id DESC
+            """
+            results = self.db_manager.execute_query(sql)
+            return [self._row_to_alert(row) for row in results]
+        except Exception as e:
+            logger.error(f"获取未确认告警失败: {e}")
+            return []
+    
+    def acknowledge_alert(self, alert_id: int, acknowledged_by: str) -> Optional[Alert]:
+        """确认告警，已确认的告警保留首次确认人和时间；告警不存在时返回None"""
+        try:
+            now = int(datetime.now().timestamp())
+            sql = """
+            UPDATE alert SET acknowledged_by = ?, acknowledged_at = ?
+            WHERE id = ? AND acknowledged_at IS NULL
+            """
+            affected_rows = self.db_manager.execute_update(sql, (acknowledged_by, now, alert_id))
+            if affected_rows > 0:
+                logger.info(f"告警已确认，ID: {alert_id}, 确认人: {acknowledged_by}")
+            return self.get_alert_by_id(alert_id)
+            
+        except Exception as e:
+            logger.error(f"确认告警失败: {e}")
+            return None
+    
     def get_active_alert_count(self) -> int:
         """获取活跃告警数量"""
         try:
@@ -419,8 +452,9 @@ class AlertService:
     def get_alert_events(self, keyword: str = "", warning_level: Optional[int] = None,
                         service_type: str = "", event_type: str = "",
                         start_time: Optional[datetime] = None, end_time: Optional[datetime] = None,
-                        page: int = 1, page_size: int = 10) -> Dict[str, Any]:
-        """查询告警事件历史"""
+                        page: int = 1, page_size: int = 10,
+                        cursor: Optional[str] = None) -> Dict[str, Any]:
+        """查询告警事件历史，传入 cursor 时按游标分页（忽略 page）"""
         try:
             conditions = []
             params = []
@@ -451,7 +485,6 @@ class AlertService:
                 params.append(int(end_time.timestamp()))
             
             where_clause = " AND ".join(conditions) if conditions else "1=1"
-            offset = (page - 1) * page_size
             
             # 查询总数
             count_sql = f"SELECT COUNT(*) FROM alert_event WHERE {where_clause}"
@@ -459,13 +492,9 @@ class AlertService:
             total = count_result[0][0] if count_result else 0
             
             # 查询数据
-            data_sql = f"""
-            SELECT * FROM alert_event WHERE {where_clause} 
-            ORDER BY triggered_at DESC 
-            LIMIT ? OFFSET ?
-            """
-            data_params = list(params) + [page_size, offset]
-            results = self.db_manager.execute_query(data_sql, tuple(data_params))
+            results, cursor_next = self._query_page(
+                "alert_event", where_clause, params, ALERT_EVENT_KEYSET, page, page_size, cursor
+            )
             
             events = [self._row_to_alert_event(row) for row in results]
             
@@ -474,7 +503,8 @@ class AlertService:
                 "message": f"查询成功，共找到 {total} 条记录",
                 "data": {
                     "total": total,
-                    "list": [event.to_dict() for event in events]
+                    "list": [event.to_dict() for event in events],
+                    "next_cursor": cursor_next
                 }
             }
             
@@ -633,6 +663,29 @@ class AlertService:
     
     # ==================== Helper Methods ====================
     
+    def _query_page(self, table: str, where_clause: str, params: List[Any], keyset: Keyset,
+                    page: int, page_size: int, cursor: Optional[str]):
+        """
+        分页查询一页数据，返回 (行列表, 下一页游标)
+
+        有 cursor 时从游标之后继续（键集分页），否则按 page 偏移；
+        多取一行用于判断是否还有下一页
+        """
+        data_params = list(params)
+        if cursor:
+            key_clause, key_params = keyset.condition(decode_cursor(cursor))
+            where_clause = f"({where_clause}) AND {key_clause}"
+            data_params.extend(key_params)
+            limit_clause = "LIMIT ?"
+            data_params.append(page_size + 1)
+        else:
+            limit_clause = "LIMIT ? OFFSET ?"
+            data_params.extend([page_size + 1, (page - 1) * page_size])
+        
+        data_sql = f"SELECT * FROM {table} WHERE {where_clause} ORDER BY {keyset.order_by()} {limit_clause}"
+        results = self.db_manager.execute_query(data_sql, tuple(data_params))
+        return results[:page_size], next_cursor(keyset, results, page_size)
+    
     def _row_to_alert(self, row) -> Alert:
         """将数据库行转换为Alert对象"""
         return Alert(
@@ -650,6 +703,8 @@ class AlertService:
             current_value=row["current_value"],
             status=row["status"],
             triggered_at=row["triggered_at"],  # 直接使用时间戳
+            acknowledged_by=row["acknowledged_by"],
+            acknowledged_at=row["acknowledged_at"],
         )
     
     def _row_to_alert_event(self, row) -> AlertEvent:
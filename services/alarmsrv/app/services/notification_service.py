@@ -0,0 +1,161 @@
+"""
+告警通知服务
+告警触发/恢复时按模板渲染邮件和Webhook通知
+
+模板上下文:
+    alarm       告警广播消息（type/id/timestamp/data）
+    alarm_id    告警ID
+    rule        告警规则字段（rule_name/service_type/channel_id/data_type/point_id/operator/value/warning_level/...）
+    value       当前值（规则删除/禁用导致的恢复为空）
+    level       告警级别，level_name 为级别名称
+    message     告警描述
+    recovered   是否为恢复通知，reason 为恢复原因
+    timestamp   通知时间戳（秒）
+    instance    实例信息 {id, name}（modsrv 告警，按需从Redis读取）
+    points      告警来源的全部点位值 {点位ID: 值}（按需从Redis读取）
+"""
+
+import asyncio
+import json
+import logging
+import smtplib
+from concurrent.futures import ThreadPoolExecutor
+from email.message import EmailMessage
+from typing import Any, Dict, List, Optional
+
+import requests
+
+from app.core.config import settings
+from app.models.alert import LEVEL_NAMES
+from app.models.alert_rule import AlertRule
+
+try:
+    from voltage_common import templating
+except ImportError:  # 单独构建的服务镜像不包含公共模块
+    templating = None
+
+logger = logging.getLogger(__name__)
+
+class NotificationService:
+    """告警通知服务类"""
+
+    def __init__(self):
+        self.executor = ThreadPoolExecutor(max_workers=2)
+        self.webhook_payload_template: Optional[Any] = None
+        self.enabled = False
+        self._load_templates()
+
+    def _load_templates(self):
+        """加载并校验通知模板，模板无效时禁用通知"""
+        if not settings.NOTIFY_WEBHOOK_URLS and not settings.NOTIFY_EMAIL_TO:
+            return
+        if templating is None:
+            logger.warning("未找到公共模块 voltage_common，告警通知已禁用")
+            return
+
+        try:
+            templating.validate(settings.NOTIFY_SUBJECT_TEMPLATE)
+            templating.validate(settings.NOTIFY_BODY_TEMPLATE)
+            if settings.NOTIFY_WEBHOOK_PAYLOAD_TEMPLATE:
+                self.webhook_payload_template = json.loads(settings.NOTIFY_WEBHOOK_PAYLOAD_TEMPLATE)
+                templating.validate(self.webhook_payload_template)
+        except (ValueError, templating.TemplateError) as e:
+            logger.error(f"告警通知模板无效，通知已禁用: {e}")
+            return
+
+        self.enabled = True
+        logger.info(f"告警通知已启用: Webhook {len(settings.NOTIFY_WEBHOOK_URLS)} 个, "
+                    f"邮件收件人 {len(settings.NOTIFY_EMAIL_TO)} 个")
+
+    def build_context(self, rule: AlertRule, alarm: Dict[str, Any], value: Optional[float],
+                      recovered: bool, reason: str = "", redis_client=None) -> Dict[str, Any]:
+        """构建模板上下文，实例信息和点位值在模板使用时才读取Redis"""
+        data = alarm.get("data", {})
+
+        def instance():
+            name = None
+            if redis_client and rule.service_type == "modsrv":
+                name = redis_client.get(f"inst:{rule.channel_id}:name")
+            return {"id": rule.channel_id, "name": name}
+
+        def points():
+            return redis_client.hgetall(rule.redis_key()) if redis_client else {}
+
+        return {
+            "alarm": alarm,
+            "alarm_id": data.get("alarm_id"),
+            "rule": rule.to_dict(),
+            "value": value,
+            "level": rule.warning_level,
+            "level_name": LEVEL_NAMES.get(rule.warning_level, str(rule.warning_level)),
+            "message": data.get("message", ""),
+            "recovered": recovered,
+            "reason": reason,
+            "timestamp": alarm.get("timestamp"),
+            "instance": instance,
+            "points": points,
+        }
+
+    async def notify(self, rule: AlertRule, alarm: Dict[str, Any], value: Optional[float],
+                     recovered: bool = False, reason: str = "", redis_client=None):
+        """发送告警通知（不抛出异常）"""
+        if not self.enabled:
+            return
+        if rule.warning_level < settings.NOTIFY_MIN_LEVEL:
+            return
+        if recovered and not settings.NOTIFY_ON_RECOVERY:
+            return
+
+        try:
+            context = self.build_context(rule, alarm, value, recovered, reason, redis_client)
+            loop = asyncio.get_event_loop()
+            tasks = []
+            if settings.NOTIFY_WEBHOOK_URLS:
+                payload = alarm
+                if self.webhook_payload_template is not None:
+                    payload = templating.render_payload(self.webhook_payload_template, context)
+                for url in settings.NOTIFY_WEBHOOK_URLS:
+                    tasks.append(loop.run_in_executor(self.executor, self._send_webhook, url, payload))
+            if settings.NOTIFY_EMAIL_TO:
+                subject = templating.render(settings.NOTIFY_SUBJECT_TEMPLATE, context)
+                body = templating.render(settings.NOTIFY_BODY_TEMPLATE, context)
+                tasks.append(loop.run_in_executor(
+                    self.executor, self._send_email, subject, body, settings.NOTIFY_EMAIL_TO
+                ))
+
+            results = await asyncio.gather(*tasks, return_exceptions=True)
+            for result in results:
+                if isinstance(result, Exception):
+                    logger.warning(f"告警通知发送失败: 规则={rule.rule_name}, 错误={result}")
+
+        except Exception as e:
+            logger.error(f"告警通知异常: 规则={rule.rule_name}, 异常={e}")
+
+    @staticmethod
+    def _send_webhook(url: str, payload: Any):
+        """发送Webhook通知（同步）"""
+        response = requests.post(url, json=payload, timeout=settings.NOTIFY_WEBHOOK_TIMEOUT)
+        if response.status_code >= 300:
+            raise RuntimeError(f"{url} 返回 {response.status_code}: {response.text[:200]}")
+        logger.info(f"Webhook通知发送成功: {url}")
+
+    @staticmethod
+    def _send_email(subject: str, body: str, recipients: List[str]):
+        """发送邮件通知（同步）"""
+        message = EmailMessage()
+        message["Subject"] = subject
+        message["From"] = settings.NOTIFY_EMAIL_FROM
+        message["To"] = ", ".join(recipients)
+        message.set_content(body)
+
+        with smtplib.SMTP(settings.SMTP_HOST, settings.SMTP_PORT, timeout=10) as smtp:
+            if settings.SMTP_USE_TLS:
+                smtp.starttls()
+            if settings.SMTP_USER:
+                smtp.login(settings.SMTP_USER, settings.SMTP_PASSWORD or "")
+            smtp.send_message(message)
+        logger.info(f"邮件通知发送成功: {subject}")
+
+
+# 创建全局服务实例
+notification_service = NotificationService()
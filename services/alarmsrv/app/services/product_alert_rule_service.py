@@ -0,0 +1,326 @@
+"""
+产品告警定义服务
+提供product_alert_rule / product_alert_override表的CRUD操作，
+并将定义按 定义 × 实例 同步为alert_rule表中的实例规则
+"""
+
+import logging
+import sqlite3
+from typing import List, Optional, Dict, Any, Tuple
+
+from app.models.alert_rule import AlertRule
+from app.models.product_alert_rule import ProductAlertRule, ProductAlertOverride
+from app.core.database import get_db_manager
+from app.services.alert_rule_service import alert_rule_service
+
+logger = logging.getLogger(__name__)
+
+# 同步时比较的实例规则字段，任一不同即更新
+SYNC_FIELDS = ("data_type", "point_id", "rule_name", "warning_level",
+               "operator", "value", "enabled", "description")
+
+
+class ProductAlertRuleService:
+    """产品告警定义服务类"""
+
+    def __init__(self):
+        self.db_manager = get_db_manager()
+
+    # ==================== 产品告警定义 ====================
+
+    def create_definition(self, definition: ProductAlertRule) -> Optional[int]:
+        """创建产品告警定义"""
+        try:
+            sql = """
+            INSERT INTO product_alert_rule (
+                product_name, data_type, point_id, rule_name,
+                warning_level, operator, value, enabled, description
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            """
+
+            params = (
+                definition.product_name,
+                definition.data_type,
+                definition.point_id,
+                definition.rule_name,
+                definition.warning_level,
+                definition.operator,
+                definition.value,
+                definition.enabled,
+                definition.description,
+            )
+
+            definition_id = self.db_manager.execute_insert(sql, params)
+            logger.info(f"创建产品告警定义成功，ID: {definition_id}")
+            return definition_id
+
+        except Exception as e:
+            logger.error(f"创建产品告警定义失败: {e}")
+            return None
+
+    def get_definition(self, definition_id: int) -> Optional[ProductAlertRule]:
+        """根据ID获取产品告警定义"""
+        try:
+            sql = "SELECT * FROM product_alert_rule WHERE id = ?"
+            results = self.db_manager.execute_query(sql, (definition_id,))
+
+            if results:
+                return self._row_to_definition(results[0])
+
+            return None
+
+        except Exception as e:
+            logger.error(f"获取产品告警定义失败: {e}")
+            return None
+
+    def list_definitions(self, product_name: str = "") -> List[ProductAlertRule]:
+        """获取产品告警定义列表，可按产品过滤"""
+        try:
+            if product_name:
+                sql = "SELECT * FROM product_alert_rule WHERE product_name = ? ORDER BY id ASC"
+                results = self.db_manager.execute_query(sql, (product_name,))
+            else:
+                sql = "SELECT * FROM product_alert_rule ORDER BY id ASC"
+                results = self.db_manager.execute_query(sql)
+
+            return [self._row_to_definition(row) for row in results]
+
+        except Exception as e:
+            logger.error(f"获取产品告警定义列表失败: {e}")
+            return []
+
+    def update_definition(self, definition: ProductAlertRule) -> bool:
+        """更新产品告警定义"""
+        try:
+            sql = """
+            UPDATE product_alert_rule SET
+                product_name = ?, data_type = ?, point_id = ?, rule_name = ?,
+                warning_level = ?, operator = ?, value = ?, enabled = ?, description = ?
+            WHERE id = ?
+            """
+
+            params = (
+                definition.product_name,
+                definition.data_type,
+                definition.point_id,
+                definition.rule_name,
+                definition.warning_level,
+                definition.operator,
+                definition.value,
+                definition.enabled,
+                definition.description,
+                definition.id,
+            )
+
+            affected_rows = self.db_manager.execute_update(sql, params)
+
+            if affected_rows > 0:
+                logger.info(f"更新产品告警定义成功，ID: {definition.id}")
+                return True
+            else:
+                logger.warning(f"未找到要更新的产品告警定义，ID: {definition.id}")
+                return False
+
+        except Exception as e:
+            logger.error(f"更新产品告警定义失败: {e}")
+            return False
+
+    def delete_definition(self, definition_id: int) -> bool:
+        """删除产品告警定义（覆盖项级联删除，实例规则由同步移除）"""
+        try:
+            sql = "DELETE FROM product_alert_rule WHERE id = ?"
+            affected_rows = self.db_manager.execute_delete(sql, (definition_id,))
+
+            if affected_rows > 0:
+                logger.info(f"删除产品告警定义成功，ID: {definition_id}")
+                return True
+            else:
+                logger.warning(f"未找到要删除的产品告警定义，ID: {definition_id}")
+                return False
+
+        except Exception as e:
+            logger.error(f"删除产品告警定义失败: {e}")
+            return False
+
+    # ==================== 实例覆盖项 ====================
+
+    def get_overrides(self, definition_id: Optional[int] = None) -> List[ProductAlertOverride]:
+        """获取覆盖项列表，不指定定义时返回全部"""
+        try:
+            if definition_id is not None:
+                sql = "SELECT * FROM product_alert_override WHERE definition_id = ? ORDER BY instance_id"
+                results = self.db_manager.execute_query(sql, (definition_id,))
+            else:
+                sql = "SELECT * FROM product_alert_override"
+                results = self.db_manager.execute_query(sql)
+
+            return [self._row_to_override(row) for row in results]
+
+        except Exception as e:
+            logger.error(f"获取实例覆盖项失败: {e}")
+            return []
+
+    def set_override(self, override: ProductAlertOverride) -> bool:
+        """创建或替换实例覆盖项"""
+        try:
+            sql = """
+            INSERT INTO product_alert_override (
+                definition_id, instance_id, warning_level, operator, value, enabled
+            ) VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(definition_id, instance_id) DO UPDATE SET
+                warning_level = excluded.warning_level,
+                operator = excluded.operator,
+                value = excluded.value,
+                enabled = excluded.enabled
+            """
+
+            params = (
+                override.definition_id,
+                override.instance_id,
+                override.warning_level,
+                override.operator,
+                override.value,
+                override.enabled,
+            )
+
+            self.db_manager.execute_insert(sql, params)
+            logger.info(f"设置实例覆盖项成功，定义ID: {override.definition_id}, 实例ID: {override.instance_id}")
+            return True
+
+        except Exception as e:
+            logger.error(f"设置实例覆盖项失败: {e}")
+            return False
+
+    def delete_override(self, definition_id: int, instance_id: int) -> bool:
+        """删除实例覆盖项，实例恢复为产品定义"""
+        try:
+            sql = "DELETE FROM product_alert_override WHERE definition_id = ? AND instance_id = ?"
+            affected_rows = self.db_manager.execute_delete(sql, (definition_id, instance_id))
+
+            if affected_rows > 0:
+                logger.info(f"删除实例覆盖项成功，定义ID: {definition_id}, 实例ID: {instance_id}")
+                return True
+            else:
+                logger.warning(f"未找到要删除的实例覆盖项，定义ID: {definition_id}, 实例ID: {instance_id}")
+                return False
+
+        except Exception as e:
+            logger.error(f"删除实例覆盖项失败: {e}")
+            return False
+
+    # ==================== 实例 ====================
+
+    def get_instances(self, product_name: str = "") -> List[Dict[str, Any]]:
+        """从modsrv的instances表读取实例（与告警共用voltage.db）"""
+        try:
+            if product_name:
+                sql = "SELECT instance_id, instance_name, product_name FROM instances WHERE product_name = ? ORDER BY instance_id"
+                results = self.db_manager.execute_query(sql, (product_name,))
+            else:
+                sql = "SELECT instance_id, instance_name, product_name FROM instances ORDER BY instance_id"
+                results = self.db_manager.execute_query(sql)
+
+            return [dict(row) for row in results]
+
+        except sqlite3.OperationalError as e:
+            # modsrv尚未初始化数据库时instances表不存在
+            logger.debug(f"读取实例表失败: {e}")
+            return []
+        except Exception as e:
+            logger.error(f"读取实例表失败: {e}")
+            return []
+
+    # ==================== 同步 ====================
+
+    def plan_sync(self) -> Dict[str, List[AlertRule]]:
+        """计算实例规则与产品定义的差异：create新增、update变更、delete移除"""
+        instances_by_product: Dict[str, List[int]] = {}
+        for instance in self.get_instances():
+            instances_by_product.setdefault(instance["product_name"], []).append(instance["instance_id"])
+
+        overrides = {(o.definition_id, o.instance_id): o for o in self.get_overrides()}
+
+        desired: Dict[Tuple[int, int], AlertRule] = {}
+        for definition in self.list_definitions():
+            for instance_id in instances_by_product.get(definition.product_name, []):
+                key = (definition.id, instance_id)
+                desired[key] = definition.materialize(instance_id, overrides.get(key))
+
+        plan: Dict[str, List[AlertRule]] = {"create": [], "update": [], "delete": []}
+        for rule in self._get_derived_rules():
+            target = desired.pop((rule.definition_id, rule.channel_id), None)
+            if target is None:
+                plan["delete"].append(rule)
+            elif any(getattr(rule, f) != getattr(target, f) for f in SYNC_FIELDS):
+                target.id = rule.id
+                plan["update"].append(target)
+
+        plan["create"] = list(desired.values())
+        return plan
+
+    def apply_sync(self, plan: Dict[str, List[AlertRule]]) -> Dict[str, Any]:
+        """执行同步计划，返回各类变更数量及被更新的规则ID"""
+        deleted = sum(1 for rule in plan["delete"] if alert_rule_service.delete_rule(rule.id))
+
+        updated = [rule.id for rule in plan["update"] if alert_rule_service.update_rule(rule)]
+
+        created = 0
+        skipped = 0
+        for rule in plan["create"]:
+            if alert_rule_service.create_rule(rule):
+                created += 1
+            else:
+                # 通常是同一点位已存在同名手工规则
+                skipped += 1
+                logger.warning(
+                    f"实例规则生成失败: 定义ID={rule.definition_id}, 实例ID={rule.channel_id}, 规则={rule.rule_name}"
+                )
+
+        if created or updated or deleted:
+            logger.info(f"产品告警定义同步完成: 新增={created}, 更新={len(updated)}, 删除={deleted}, 跳过={skipped}")
+
+        return {
+            "created": created,
+            "updated": updated,
+            "deleted": deleted,
+            "skipped": skipped,
+        }
+
+    def _get_derived_rules(self) -> List[AlertRule]:
+        """获取所有由产品告警定义生成的实例规则"""
+        sql = "SELECT * FROM alert_rule WHERE definition_id IS NOT NULL ORDER BY id ASC"
+        results = self.db_manager.execute_query(sql)
+        return [alert_rule_service._row_to_alert_rule(row) for row in results]
+
+    def _row_to_definition(self, row) -> ProductAlertRule:
+        """将数据库行转换为ProductAlertRule对象"""
+        return ProductAlertRule(
+            id=row["id"],
+            product_name=row["product_name"],
+            data_type=row["data_type"],
+            point_id=row["point_id"],
+            rule_name=row["rule_name"],
+            warning_level=row["warning_level"],
+            operator=row["operator"],
+            value=row["value"],
+            enabled=bool(row["enabled"]),
+            description=row["description"] or "",
+            created_at=row["created_at"],
+            updated_at=row["updated_at"],
+        )
+
+    def _row_to_override(self, row) -> ProductAlertOverride:
+        """将数据库行转换为ProductAlertOverride对象"""
+        return ProductAlertOverride(
+            id=row["id"],
+            definition_id=row["definition_id"],
+            instance_id=row["instance_id"],
+            warning_level=row["warning_level"],
+            operator=row["operator"],
+            value=row["value"],
+            enabled=None if row["enabled"] is None else bool(row["enabled"]),
+        )
+
+
+# 创建全局服务实例
+product_alert_rule_service = ProductAlertRuleService()
@@ -0,0 +1,76 @@
+"""
+游标分页工具
+深翻页时 LIMIT/OFFSET 需要扫描并丢弃前面所有行，告警事件越多越慢。
+键集分页记住上一页最后一行的排序键，下一页直接从该键之后继续查询。
+游标编码与 Rust 服务的 common::Cursor 一致（JSON 数组的 URL 安全 base64，无填充）
+"""
+
+import base64
+import json
+from typing import Any, List, Optional, Sequence, Tuple
+
+
+def encode_cursor(key: Sequence[Any]) -> str:
+    """将排序键编码为不透明游标"""
+    data = json.dumps(list(key), separators=(",", ":")).encode("utf-8")
+    return base64.urlsafe_b64encode(data).decode("ascii").rstrip("=")
+
+
+def decode_cursor(cursor: str) -> List[Any]:
+    """解码客户端传回的游标，格式错误时抛出 ValueError"""
+    try:
+        text = cursor.strip()
+        data = base64.urlsafe_b64decode(text + "=" * (-len(text) % 4))
+        key = json.loads(data)
+    except Exception:
+        raise ValueError(f"无效的游标: {cursor}")
+    if not isinstance(key, list):
+        raise ValueError(f"无效的游标: {cursor}")
+    return key
+
+
+class Keyset:
+    """
+    键集分页的排序列
+
+    columns 为 (列名, 是否降序) 列表，最后一列应为主键以保证排序唯一
+    """
+
+    def __init__(self, columns: Sequence[Tuple[str, bool]]):
+        self.columns = list(columns)
+
+    def order_by(self) -> str:
+        """ORDER BY 子句，例如 triggered_at DESC, id DESC"""
+        return ", ".join(f"{column} {'DESC' if desc else 'ASC'}" for column, desc in self.columns)
+
+    def condition(self, key: Sequence[Any]) -> Tuple[str, List[Any]]:
+        """
+        生成“位于 key 之后”的 WHERE 条件及参数
+
+        展开为 (a < ?) OR (a = ? AND b < ?) 的形式，支持各列方向不同
+        """
+        if len(key) != len(self.columns):
+            raise ValueError(f"游标包含 {len(key)} 个键值，应为 {len(self.columns)} 个")
+        terms = []
+        params: List[Any] = []
+        for i, (column, desc) in enumerate(self.columns):
+            parts = [f"{prev} = ?" for prev, _ in self.columns[:i]]
+            parts.append(f"{column} {'<' if desc else '>'} ?")
+            terms.append("(" + " AND ".join(parts) + ")")
+            params.extend(key[:i + 1])
+        return "(" + " OR ".join(terms) + ")", params
+
+    def key_of(self, row) -> List[Any]:
+        """取出一行数据的排序键"""
+        return [row[column] for column, _ in self.columns]
+
+
+def next_cursor(keyset: Keyset, rows: Sequence[Any], page_size: int) -> Optional[str]:
+    """
+    计算下一页游标
+
+    rows 为多查询一行（LIMIT page_size + 1）的结果，超出 page_size 时说明还有下一页
+    """
+    if len(rows) <= page_size:
+        return None
+    return encode_cursor(keyset.key_of(rows[page_size - 1]))
@@ -17,12 +17,22 @@ from datetime import datetime
 import uvicorn
 import io
 
+# 本地运行时加载公共模块（镜像中位于 /app/voltage_common）
+_SHARED_DIR = Path(__file__).resolve().parent.parent / "python-services"
+if _SHARED_DIR.is_dir() and str(_SHARED_DIR) not in sys.path:
+    sys.path.append(str(_SHARED_DIR))
+
 from app.core.config import settings
 from app.core.database import init_database
+from app.core.rate_limit import RateLimiter, RouteClass, trusted_proxies_from_env
 from app.services.alert_rule_service import alert_rule_service
 from app.services.alert_service import alert_service
+from app.models.alert import LEVEL_COLORS, LEVEL_NAMES
 from app.services.alarm_monitor import alarm_monitor
+from app.services.alarm_statistics_service import alarm_statistics_service
+from app.services.product_alert_rule_service import product_alert_rule_service
 from app.models.alert_rule import AlertRule
+from app.models.product_alert_rule import ProductAlertRule, ProductAlertOverride
 
 # 配置日志
 logging.basicConfig(
@@ -50,6 +60,21 @@ app.add_middleware(
     allow_headers=["*"],
 )
 
+# 告警查询按客户端限流（导出为整表扫描，单独限额）
+rate_limiter = RateLimiter(
+    [
+        RouteClass("export", 2, 0.1, ["/alarmApi/alert-events/export"]),
+        RouteClass(
+            "alarm_query",
+            settings.RATE_LIMIT_BURST,
+            settings.RATE_LIMIT_PER_MINUTE / 60.0,
+            ["/alarmApi/alerts", "/alarmApi/alert-events", "/alarmApi/alert-statistics"],
+        ),
+    ],
+    trusted_proxies=trusted_proxies_from_env(),
+)
+app.middleware("http")(rate_limiter.middleware)
+
 
 @app.on_event("startup")
 async def startup_event():
@@ -398,10 +423,26 @@ async def get_channel_rules(
         }
 
 
+def _derived_rule_response(rule_id: int) -> Optional[dict]:
+    """由产品告警定义生成的实例规则不可直接修改，返回拒绝响应"""
+    rule = alert_rule_service.get_rule_by_id(rule_id)
+    if rule and rule.definition_id is not None:
+        return {
+            "success": False,
+            "message": f"该规则由产品告警定义(ID: {rule.definition_id})生成，请修改产品定义或实例覆盖项",
+            "data": {"rule_id": rule_id, "definition_id": rule.definition_id}
+        }
+    return None
+
+
 @app.put("/alarmApi/rules/{rule_id}")
 async def update_alert_rule(rule_id: int, rule_data: dict):
     """更新告警规则"""
     try:
+        derived = _derived_rule_response(rule_id)
+        if derived:
+            return derived
+
         # 确保规则ID正确
         rule_data["id"] = rule_id
         
@@ -438,6 +479,10 @@ async def update_alert_rule(rule_id: int, rule_data: dict):
 async def delete_alert_rule(rule_id: int):
     """删除告警规则"""
     try:
+        derived = _derived_rule_response(rule_id)
+        if derived:
+            return derived
+
         # 先通知监控引擎处理相关告警
         await alarm_monitor.on_rule_deleted(rule_id)
         
@@ -468,6 +513,10 @@ async def delete_alert_rule(rule_id: int):
 async def enable_alert_rule(rule_id: int):
     """启用告警规则"""
     try:
+        derived = _derived_rule_response(rule_id)
+        if derived:
+            return derived
+
         success = alert_rule_service.enable_rule(rule_id)
         if success:
             # 通知监控引擎规则已启用
@@ -497,6 +546,10 @@ async def enable_alert_rule(rule_id: int):
 async def disable_alert_rule(rule_id: int):
     """禁用告警规则"""
     try:
+        derived = _derived_rule_response(rule_id)
+        if derived:
+            return derived
+
         success = alert_rule_service.disable_rule(rule_id)
         if success:
             # 通知监控引擎规则已禁用（将解除相关告警）
@@ -522,6 +575,275 @@ async def disable_alert_rule(rule_id: int):
         }
 
 
+# ==================== 产品告警定义API ====================
+
+def _definition_detail(definition: ProductAlertRule) -> dict:
+    """产品告警定义详情：定义、实例覆盖项和生成的实例规则"""
+    return {
+        **definition.to_dict(),
+        "overrides": [o.to_dict() for o in product_alert_rule_service.get_overrides(definition.id)],
+        "rules": [r.to_dict() for r in alert_rule_service.get_rules_by_definition(definition.id)]
+    }
+
+
+@app.post("/alarmApi/product-rules")
+async def create_product_alert_rule(definition_data: dict):
+    """创建产品告警定义，该产品的所有实例自动继承"""
+    try:
+        definition_data.pop("id", None)
+        definition = ProductAlertRule.from_dict(definition_data)
+
+        is_valid, error_message = definition.validate_detailed()
+        if not is_valid:
+            return {
+                "success": False,
+                "message": f"参数验证失败: {error_message}",
+                "data": {}
+            }
+
+        definition_id = product_alert_rule_service.create_definition(definition)
+        if not definition_id:
+            return {
+                "success": False,
+                "message": f"创建失败，产品 {definition.product_name} 的点位 {definition.data_type}:{definition.point_id} 可能已存在同名定义",
+                "data": {}
+            }
+
+        sync_result = await alarm_monitor.sync_product_rules()
+        return {
+            "success": True,
+            "message": "产品告警定义创建成功",
+            "data": {"definition_id": definition_id, "sync": sync_result}
+        }
+
+    except Exception as e:
+        logger.error(f"创建产品告警定义失败: {e}")
+        return {
+            "success": False,
+            "message": f"创建失败: {str(e)}",
+            "data": {}
+        }
+
+
+@app.get("/alarmApi/product-rules")
+async def list_product_alert_rules(
+    product_name: str = Query("", description="产品名称过滤，如：PCS")
+):
+    """获取产品告警定义列表"""
+    try:
+        definitions = product_alert_rule_service.list_definitions(product_name)
+        return {
+            "success": True,
+            "message": f"查询成功，共找到 {len(definitions)} 条记录",
+            "data": {
+                "total": len(definitions),
+                "list": [d.to_dict() for d in definitions]
+            }
+        }
+
+    except Exception as e:
+        logger.error(f"获取产品告警定义列表失败: {e}")
+        return {
+            "success": False,
+            "message": f"查询失败: {str(e)}",
+            "data": {"total": 0, "list": []}
+        }
+
+
+@app.get("/alarmApi/product-rules/{definition_id}")
+async def get_product_alert_rule(definition_id: int):
+    """获取产品告警定义详情，包括实例覆盖项和生成的实例规则"""
+    try:
+        definition = product_alert_rule_service.get_definition(definition_id)
+        if not definition:
+            return {
+                "success": False,
+                "message": "产品告警定义不存在",
+                "data": {}
+            }
+
+        return {
+            "success": True,
+            "message": "获取产品告警定义成功",
+            "data": _definition_detail(definition)
+        }
+
+    except Exception as e:
+        logger.error(f"获取产品告警定义失败: {e}")
+        return {
+            "success": False,
+            "message": f"获取失败: {str(e)}",
+            "data": {}
+        }
+
+
+@app.put("/alarmApi/product-rules/{definition_id}")
+async def update_product_alert_rule(definition_id: int, definition_data: dict):
+    """更新产品告警定义，实例规则随之同步"""
+    try:
+        definition_data["id"] = definition_id
+        definition = ProductAlertRule.from_dict(definition_data)
+
+        is_valid, error_message = definition.validate_detailed()
+        if not is_valid:
+            return {
+                "success": False,
+                "message": f"参数验证失败: {error_message}",
+                "data": {}
+            }
+
+        if not product_alert_rule_service.update_definition(definition):
+            return {
+                "success": False,
+                "message": "产品告警定义不存在或更新失败",
+                "data": {}
+            }
+
+        sync_result = await alarm_monitor.sync_product_rules()
+        return {
+            "success": True,
+            "message": "产品告警定义更新成功",
+            "data": {"definition_id": definition_id, "sync": sync_result}
+        }
+
+    except Exception as e:
+        logger.error(f"更新产品告警定义失败: {e}")
+        return {
+            "success": False,
+            "message": f"更新失败: {str(e)}",
+            "data": {}
+        }
+
+
+@app.delete("/alarmApi/product-rules/{definition_id}")
+async def delete_product_alert_rule(definition_id: int):
+    """删除产品告警定义，其生成的实例规则和相关告警一并移除"""
+    try:
+        if not product_alert_rule_service.delete_definition(definition_id):
+            return {
+                "success": False,
+                "message": "产品告警定义不存在",
+                "data": {}
+            }
+
+        sync_result = await alarm_monitor.sync_product_rules()
+        return {
+            "success": True,
+            "message": "产品告警定义删除成功",
+            "data": {"definition_id": definition_id, "sync": sync_result}
+        }
+
+    except Exception as e:
+        logger.error(f"删除产品告警定义失败: {e}")
+        return {
+            "success": False,
+            "message": f"删除失败: {str(e)}",
+            "data": {}
+        }
+
+
+@app.put("/alarmApi/product-rules/{definition_id}/overrides/{instance_id}")
+async def set_product_alert_override(definition_id: int, instance_id: int, override_data: dict):
+    """设置实例覆盖项，未提供的字段沿用产品定义"""
+    try:
+        definition = product_alert_rule_service.get_definition(definition_id)
+        if not definition:
+            return {
+                "success": False,
+                "message": "产品告警定义不存在",
+                "data": {}
+            }
+
+        instances = product_alert_rule_service.get_instances(definition.product_name)
+        if not any(i["instance_id"] == instance_id for i in instances):
+            return {
+                "success": False,
+                "message": f"实例 {instance_id} 不存在或不属于产品 {definition.product_name}",
+                "data": {}
+            }
+
+        override_data["definition_id"] = definition_id
+        override_data["instance_id"] = instance_id
+        override = ProductAlertOverride.from_dict(override_data)
+
+        is_valid, error_message = override.validate_detailed()
+        if not is_valid:
+            return {
+                "success": False,
+                "message": f"参数验证失败: {error_message}",
+                "data": {}
+            }
+
+        if not product_alert_rule_service.set_override(override):
+            return {
+                "success": False,
+                "message": "设置实例覆盖项失败",
+                "data": {}
+            }
+
+        sync_result = await alarm_monitor.sync_product_rules()
+        return {
+            "success": True,
+            "message": "实例覆盖项设置成功",
+            "data": {"definition_id": definition_id, "instance_id": instance_id, "sync": sync_result}
+        }
+
+    except Exception as e:
+        logger.error(f"设置实例覆盖项失败: {e}")
+        return {
+            "success": False,
+            "message": f"设置失败: {str(e)}",
+            "data": {}
+        }
+
+
+@app.delete("/alarmApi/product-rules/{definition_id}/overrides/{instance_id}")
+async def delete_product_alert_override(definition_id: int, instance_id: int):
+    """删除实例覆盖项，实例规则恢复为产品定义"""
+    try:
+        if not product_alert_rule_service.delete_override(definition_id, instance_id):
+            return {
+                "success": False,
+                "message": "实例覆盖项不存在",
+                "data": {}
+            }
+
+        sync_result = await alarm_monitor.sync_product_rules()
+        return {
+            "success": True,
+            "message": "实例覆盖项删除成功",
+            "data": {"definition_id": definition_id, "instance_id": instance_id, "sync": sync_result}
+        }
+
+    except Exception as e:
+        logger.error(f"删除实例覆盖项失败: {e}")
+        return {
+            "success": False,
+            "message": f"删除失败: {str(e)}",
+            "data": {}
+        }
+
+
+@app.post("/alarmApi/product-rules/sync")
+async def sync_product_alert_rules():
+    """立即将产品告警定义同步为实例规则（新增实例后无需等待定时同步）"""
+    try:
+        sync_result = await alarm_monitor.sync_product_rules()
+        return {
+            "success": True,
+            "message": "产品告警定义同步完成",
+            "data": sync_result
+        }
+
+    except Exception as e:
+        logger.error(f"同步产品告警定义失败: {e}")
+        return {
+            "success": False,
+            "message": f"同步失败: {str(e)}",
+            "data": {}
+        }
+
+
 # ==================== 告警管理API ====================
 
 @app.get("/alarmApi/alerts")
@@ -532,7 +854,8 @@ async def list_alerts(
     start_time: Optional[str] = Query(None, description="开始时间，支持多种格式：2025-08-21、2025-08-21 00:00:00、2025-08-21T00:00:00等"),
     end_time: Optional[str] = Query(None, description="结束时间，支持多种格式：2025-08-21、2025-08-21 23:59:59、2025-08-21T23:59:59等"),
     page: int = Query(1, ge=1, description="页码"),
-    page_size: int = Query(10, ge=1, le=100, description="每页大小")
+    page_size: int = Query(10, ge=1, le=100, description="每页大小"),
+    cursor: Optional[str] = Query(None, description="上一页返回的 next_cursor，传入时忽略 page")
 ):
     """获取当前告警列表"""
     try:
@@ -564,7 +887,8 @@ async def list_alerts(
             start_time=start_datetime,
             end_time=end_datetime,
             page=page,
-            page_size=page_size
+            page_size=page_size,
+            cursor=cursor
         )
         
         return result
@@ -578,6 +902,32 @@ async def list_alerts(
         }
 
 
+@app.get("/alarmApi/alerts/banner")
+async def get_alert_banner():
+    """获取告警横幅：未确认的活跃告警，按级别着色，紧急级别在前"""
+    try:
+        alerts = alert_service.get_unacknowledged_alerts()
+        items = []
+        for alert in alerts:
+            item = alert.to_dict()
+            item["level_name"] = LEVEL_NAMES.get(alert.warning_level, str(alert.warning_level))
+            item["color"] = LEVEL_COLORS.get(alert.warning_level, LEVEL_COLORS[1])
+            items.append(item)
+        return {
+            "success": True,
+            "message": "获取告警横幅成功",
+            "data": {"total": len(items), "list": items}
+        }
+        
+    except Exception as e:
+        logger.error(f"获取告警横幅失败: {e}")
+        return {
+            "success": False,
+            "message": f"查询失败: {str(e)}",
+            "data": {"total": 0, "list": []}
+        }
+
+
 @app.get("/alarmApi/alerts/{alert_id}")
 async def get_alert(alert_id: int):
     """获取指定告警详情"""
@@ -637,6 +987,35 @@ async def resolve_alert(alert_id: int, resolve_data: dict = None):
         }
 
 
+@app.patch("/alarmApi/alerts/{alert_id}/acknowledge")
+async def acknowledge_alert(alert_id: int, ack_data: dict = None):
+    """确认告警，确认后不再显示在告警横幅中，告警仍保持活跃直至恢复"""
+    try:
+        acknowledged_by = (ack_data or {}).get("acknowledged_by") or "operator"
+        
+        alert = alert_service.acknowledge_alert(alert_id, str(acknowledged_by))
+        if alert:
+            return {
+                "success": True,
+                "message": "告警已确认",
+                "data": alert.to_dict()
+            }
+        else:
+            return {
+                "success": False,
+                "message": "告警不存在或确认失败",
+                "data": {}
+            }
+            
+    except Exception as e:
+        logger.error(f"确认告警失败: {e}")
+        return {
+            "success": False,
+            "message": f"确认失败: {str(e)}",
+            "data": {}
+        }
+
+
 @app.get("/alarmApi/alert-events")
 async def list_alert_events(
     keyword: str = Query("", description="关键词搜索，支持模糊匹配：规则名称、通道ID、点位ID"),
@@ -646,7 +1025,8 @@ async def list_alert_events(
     start_time: Optional[str] = Query(None, description="开始时间，支持多种格式：2025-08-21、2025-08-21 00:00:00、2025-08-21T00:00:00等"),
     end_time: Optional[str] = Query(None, description="结束时间，支持多种格式：2025-08-21、2025-08-21 23:59:59、2025-08-21T23:59:59等"),
     page: int = Query(1, ge=1, description="页码"),
-    page_size: int = Query(10, ge=1, le=100, description="每页大小")
+    page_size: int = Query(10, ge=1, le=100, description="每页大小"),
+    cursor: Optional[str] = Query(None, description="上一页返回的 next_cursor，传入时忽略 page")
 ):
     """获取告警事件历史"""
     try:
@@ -679,7 +1059,8 @@ async def list_alert_events(
             start_time=start_datetime,
             end_time=end_datetime,
             page=page,
-            page_size=page_size
+            page_size=page_size,
+            cursor=cursor
         )
         
         return result
@@ -763,6 +1144,59 @@ async def get_alert_statistics():
         }
 
 
+def _parse_report_range(start_time: Optional[str], end_time: Optional[str]):
+    """解析统计时间范围，返回 (start, end, 错误信息)；未指定时默认最近30天"""
+    from app.utils.time_parser import parse_time_range
+
+    start_datetime, end_datetime = parse_time_range(start_time, end_time)
+    if start_time and not start_datetime:
+        return None, None, f"开始时间格式错误: {start_time}，支持格式：2025-08-21、2025-08-21 00:00:00、2025-08-21T00:00:00等"
+    if end_time and not end_datetime:
+        return None, None, f"结束时间格式错误: {end_time}，支持格式：2025-08-21、2025-08-21 23:59:59、2025-08-21T23:59:59等"
+    start_datetime, end_datetime = alarm_statistics_service.resolve_range(start_datetime, end_datetime)
+    return start_datetime, end_datetime, None
+
+
+@app.get("/alarmApi/alert-statistics/daily")
+async def get_alert_daily_statistics(
+    service_type: str = Query("", description="服务类型过滤"),
+    start_time: Optional[str] = Query(None, description="开始时间，默认30天前"),
+    end_time: Optional[str] = Query(None, description="结束时间，默认当前时间")
+):
+    """按天和级别统计告警次数"""
+    start_datetime, end_datetime, error = _parse_report_range(start_time, end_time)
+    if error:
+        return {"success": False, "message": error, "data": {}}
+    return alarm_statistics_service.get_daily_counts(start_datetime, end_datetime, service_type)
+
+
+@app.get("/alarmApi/alert-statistics/kpi")
+async def get_alert_kpi(
+    service_type: str = Query("", description="服务类型过滤"),
+    start_time: Optional[str] = Query(None, description="开始时间，默认30天前"),
+    end_time: Optional[str] = Query(None, description="结束时间，默认当前时间")
+):
+    """告警KPI：总数、活跃数、平均恢复时间（秒）"""
+    start_datetime, end_datetime, error = _parse_report_range(start_time, end_time)
+    if error:
+        return {"success": False, "message": error, "data": {}}
+    return alarm_statistics_service.get_kpi(start_datetime, end_datetime, service_type)
+
+
+@app.get("/alarmApi/alert-statistics/top-sources")
+async def get_alert_top_sources(
+    service_type: str = Query("", description="服务类型过滤"),
+    start_time: Optional[str] = Query(None, description="开始时间，默认30天前"),
+    end_time: Optional[str] = Query(None, description="结束时间，默认当前时间"),
+    limit: int = Query(10, ge=1, le=100, description="返回条数")
+):
+    """告警最频繁的来源（通道/实例）和规则"""
+    start_datetime, end_datetime, error = _parse_report_range(start_time, end_time)
+    if error:
+        return {"success": False, "message": error, "data": {}}
+    return alarm_statistics_service.get_top_sources(start_datetime, end_datetime, service_type, limit)
+
+
 # ==================== 监控管理API ====================
 
 @app.get("/alarmApi/monitor/status")
@@ -0,0 +1,61 @@
+/*
+ * comsrv protocol plugin interface (ABI version 1)
+ *
+ * Build a shared library exporting voltage_protocol_plugin() and drop it in
+ * the directory named by COMSRV_PLUGIN_DIR. Channels whose `protocol` equals
+ * the plugin name are then run by the plugin.
+ *
+ * See services/comsrv/src/core/plugins/dylib.rs for the full contract.
+ */
+#ifndef VOLTAGE_PROTOCOL_PLUGIN_H
+#define VOLTAGE_PROTOCOL_PLUGIN_H
+
+#include <stddef.h>
+#include <stdint.h>
+#include <sys/types.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define VOLTAGE_PLUGIN_ABI_VERSION 1
+
+/* Value exchanged with comsrv; point_type is 'T', 'S', 'C' or 'A' */
+typedef struct {
+    uint8_t point_type;
+    uint32_t point_id;
+    double value;
+    uint8_t quality; /* 0 = good, anything else = bad */
+} voltage_plugin_value;
+
+typedef struct {
+    uint32_t abi_version;          /* VOLTAGE_PLUGIN_ABI_VERSION */
+    const char *name;              /* stored in channels.protocol */
+    const char *display_name;
+    const char *description;
+    const char *mapping_columns;   /* JSON array, may be NULL */
+    const char *point_types;       /* JSON array e.g. ["T","S"], NULL = all */
+
+    /* All functions are required; comsrv rejects a table with a NULL entry */
+
+    /* config: channel parameters (JSON object)
+     * points: [{"point_type","point_id","signal_name","mapping"}] (JSON array)
+     * returns NULL when the configuration is rejected */
+    void *(*create)(uint32_t channel_id, const char *config, const char *points);
+    int32_t (*connect)(void *instance);
+    int32_t (*disconnect)(void *instance);
+    /* returns the number of values written to out, negative on failure */
+    ssize_t (*poll)(void *instance, voltage_plugin_value *out, size_t capacity);
+    int32_t (*write)(void *instance, uint8_t point_type, uint32_t point_id, double value);
+    const char *(*last_error)(void *instance);
+    void (*destroy)(void *instance);
+} voltage_plugin_vtable;
+
+/* Entry point looked up by comsrv */
+const voltage_plugin_vtable *voltage_protocol_plugin(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* VOLTAGE_PROTOCOL_PLUGIN_H */
@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::collections::HashMap;
 use utoipa::ToSchema;
+use validator::Validate;
 
 pub use crate::core::config::{ChannelConfig, ChannelCore};
 pub use common::{
@@ -206,6 +207,301 @@ pub struct WritePointResponse {
     pub timestamp_ms: i64,
 }
 
+// ============================================================================
+// Versioned Command Schema
+// ============================================================================
+
+/// Header selecting the schema of the write endpoint; echoed on the response
+pub const API_VERSION_HEADER: &str = "x-api-version";
+
+/// Schema version of `POST /api/channels/{channel_id}/write`
+///
+/// Requests without [`API_VERSION_HEADER`] use v1, so existing SCADA
+/// integrations keep working while the payload evolves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum ApiVersion {
+    /// [`v1::WritePointRequest`] / [`v1::WriteResponse`]
+    #[default]
+    V1,
+    /// [`v2::CommandRequest`] / [`v2::CommandResponse`]
+    V2,
+}
+
+impl ApiVersion {
+    pub const SUPPORTED: [ApiVersion; 2] = [ApiVersion::V1, ApiVersion::V2];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "1",
+            ApiVersion::V2 => "2",
+        }
+    }
+
+    /// Version requested by the header value (`2`, `v2`, `V2`); none means v1
+    pub fn negotiate(header: Option<&str>) -> Result<Self, String> {
+        let Some(requested) = header.map(str::trim).filter(|v| !v.is_empty()) else {
+            return Ok(ApiVersion::default());
+        };
+        let number = requested.trim_start_matches(['v', 'V']);
+        Self::SUPPORTED
+            .into_iter()
+            .find(|version| version.as_str() == number)
+            .ok_or_else(|| {
+                let supported: Vec<_> = Self::SUPPORTED.iter().map(|v| v.as_str()).collect();
+                format!(
+                    "Unsupported API version '{}'. Supported versions: {}",
+                    requested,
+                    supported.join(", ")
+                )
+            })
+    }
+}
+
+/// Write endpoint schema v1 (the unversioned API)
+pub mod v1 {
+    pub use super::{
+        BatchCommandError, BatchCommandResult, PointValue, WritePointData, WritePointRequest,
+        WritePointResponse, WriteResponse,
+    };
+    use voltage_model::PointType;
+
+    /// Point type of a v1 request: T/S/C/A or the full name
+    pub fn parse_point_type(type_str: &str) -> Result<PointType, String> {
+        match type_str {
+            "T" | "t" | "Telemetry" | "telemetry" | "TELEMETRY" => Ok(PointType::Telemetry),
+            "S" | "s" | "Signal" | "signal" | "SIGNAL" => Ok(PointType::Signal),
+            "C" | "c" | "Control" | "control" | "CONTROL" => Ok(PointType::Control),
+            "A" | "a" | "Adjustment" | "adjustment" | "ADJUSTMENT" => Ok(PointType::Adjustment),
+            _ => Err(format!(
+                "Invalid point type '{}'. Must be one of: T/Telemetry, S/Signal, C/Control, A/Adjustment",
+                type_str
+            )),
+        }
+    }
+}
+
+/// Write endpoint schema v2: typed point IDs, caller command IDs, priorities
+///
+/// v1 requests are upgraded to [`CommandRequest`] and executed the same way;
+/// the [`CommandResponse`] is then downgraded to the v1 shape.
+pub mod v2 {
+    use super::v1;
+    use serde::{Deserialize, Serialize};
+    #[allow(unused_imports)] // Used in #[schema] macro expansion
+    use serde_json::json;
+    use utoipa::ToSchema;
+    use voltage_model::PointType;
+
+    /// Longest caller-assigned command ID
+    pub const MAX_COMMAND_ID_LEN: usize = 64;
+
+    /// Control/adjustment request
+    ///
+    /// ```json
+    /// {
+    ///   "point_type": "A",
+    ///   "commands": [
+    ///     {"point_id": 201, "value": 4500.0, "command_id": "ems-7781", "priority": 10},
+    ///     {"point_id": 202, "value": 380.0}
+    ///   ]
+    /// }
+    /// ```
+    #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+    pub struct CommandRequest {
+        /// T/S/C/A (also telemetry/signal/control/adjustment, YC/YX/YK/YT)
+        #[schema(value_type = String, example = "A")]
+        pub point_type: PointType,
+        pub commands: Vec<Command>,
+    }
+
+    /// One command of a request
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+    pub struct Command {
+        #[schema(example = 201)]
+        pub point_id: u32,
+        #[schema(example = 4500.0)]
+        pub value: f64,
+        /// Caller-assigned ID, tracked under `/api/channels/{id}/commands/{command_id}`;
+        /// assigned by comsrv when omitted
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[schema(example = "ems-7781")]
+        pub command_id: Option<String>,
+        /// Commands of a request are dispatched highest priority first
+        #[serde(default)]
+        #[schema(example = 10)]
+        pub priority: u8,
+    }
+
+    /// v1 request upgraded to v2
+    #[derive(Debug, Clone)]
+    pub struct UpgradedRequest {
+        pub request: CommandRequest,
+        /// v1 single-point form; the response is downgraded to it
+        pub single: bool,
+        /// Batch entries v1 reports as failed without executing them
+        pub rejected: Vec<v1::BatchCommandError>,
+    }
+
+    impl CommandRequest {
+        /// Upgrade a v1 request; an unknown point type or the invalid ID of
+        /// a single-point write fails the whole request as in v1
+        pub fn from_v1(request: v1::WritePointRequest) -> Result<UpgradedRequest, String> {
+            let point_type = v1::parse_point_type(&request.r#type)?;
+            let command = |point_id, value| Command {
+                point_id,
+                value,
+                command_id: None,
+                priority: 0,
+            };
+            let (commands, single, rejected) = match request.data {
+                v1::WritePointData::Single { id, value } => {
+                    let point_id = id
+                        .parse()
+                        .map_err(|_| format!("Invalid point ID: {}", id))?;
+                    (vec![command(point_id, value)], true, Vec::new())
+                },
+                v1::WritePointData::Batch { points } => {
+                    let mut commands = Vec::with_capacity(points.len());
+                    let mut rejected = Vec::new();
+                    for point in points {
+                        match point.id.parse() {
+                            Ok(point_id) => commands.push(command(point_id, point.value)),
+                            Err(_) => rejected.push(v1::BatchCommandError {
+                                point_id: 0,
+                                error: format!("Invalid point ID: {}", point.id),
+                            }),
+                        }
+                    }
+                    (commands, false, rejected)
+                },
+            };
+            Ok(UpgradedRequest {
+                request: CommandRequest {
+                    point_type,
+                    commands,
+                },
+                single,
+                rejected,
+            })
+        }
+
+        /// Check the request before executing any command
+        pub fn check(&self) -> Result<(), String> {
+            if self.commands.is_empty() {
+                return Err("'commands' must not be empty".to_string());
+            }
+            for id in self.commands.iter().filter_map(|c| c.command_id.as_deref()) {
+                let valid_chars = id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+                if id.is_empty() || id.len() > MAX_COMMAND_ID_LEN || !valid_chars {
+                    return Err(format!(
+                        "Invalid command_id '{}': 1-{} characters of A-Z, a-z, 0-9, '-', '_', '.', ':'",
+                        id, MAX_COMMAND_ID_LEN
+                    ));
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Outcome of one command
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+    #[serde(rename_all = "snake_case")]
+    pub enum CommandStatus {
+        /// Handed to the channel (or its command queue) and persisted
+        Accepted,
+        Failed,
+    }
+
+    /// Result of one command, in request order
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+    pub struct CommandResult {
+        #[schema(example = 201)]
+        pub point_id: u32,
+        /// ID the command is tracked under; absent for v1 commands queued
+        /// without one
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[schema(example = "ems-7781")]
+        pub command_id: Option<String>,
+        #[schema(example = 10)]
+        pub priority: u8,
+        #[schema(example = 4500.0)]
+        pub value: f64,
+        pub status: CommandStatus,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub error: Option<String>,
+        /// When the command was accepted (milliseconds since Unix epoch)
+        #[schema(example = 1699876543210_i64)]
+        pub timestamp_ms: i64,
+    }
+
+    /// Control/adjustment response
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+    pub struct CommandResponse {
+        #[schema(example = 1001)]
+        pub channel_id: u32,
+        #[schema(example = "A")]
+        pub point_type: String,
+        pub succeeded: usize,
+        pub failed: usize,
+        pub results: Vec<CommandResult>,
+    }
+
+    impl CommandResponse {
+        pub fn new(channel_id: u32, point_type: PointType, results: Vec<CommandResult>) -> Self {
+            let succeeded = results
+                .iter()
+                .filter(|r| r.status == CommandStatus::Accepted)
+                .count();
+            Self {
+                channel_id,
+                point_type: point_type.as_str().to_string(),
+                succeeded,
+                failed: results.len() - succeeded,
+                results,
+            }
+        }
+
+        /// Downgrade to the v1 response of an upgraded request
+        ///
+        /// A failed single-point write is an error, as v1 reports it.
+        pub fn into_v1(self, upgraded: UpgradedRequest) -> Result<v1::WriteResponse, String> {
+            if upgraded.single {
+                let result = self
+                    .results
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| "No command result".to_string())?;
+                if let Some(error) = result.error {
+                    return Err(error);
+                }
+                return Ok(v1::WriteResponse::Single(v1::WritePointResponse {
+                    channel_id: self.channel_id,
+                    point_type: self.point_type,
+                    point_id: result.point_id,
+                    value: result.value,
+                    timestamp_ms: result.timestamp_ms,
+                }));
+            }
+
+            let mut errors = upgraded.rejected;
+            errors.extend(self.results.into_iter().filter_map(|r| {
+                r.error.map(|error| v1::BatchCommandError {
+                    point_id: r.point_id,
+                    error,
+                })
+            }));
+            Ok(v1::WriteResponse::Batch(v1::BatchCommandResult {
+                total: self.succeeded + errors.len(),
+                succeeded: self.succeeded,
+                failed: errors.len(),
+                errors,
+            }))
+        }
+    }
+}
+
 /// service status response
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ServiceStatus {
@@ -371,7 +667,7 @@ pub struct ChannelOperation {
 ///   "update_interval_ms": 1000
 /// }
 /// ```
-#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
 pub struct ChannelCreateRequest {
     /// Channel ID (optional, auto-assigned if not provided)
     ///
@@ -391,6 +687,7 @@ pub struct ChannelCreateRequest {
     /// - Used for channel identification in UI and logs
     /// - Recommended format: descriptive names like "PV Inverter 01"
     #[schema(example = "PV Inverter Channel")]
+    #[validate(custom(function = "common::extract::not_blank"))]
     pub name: String,
 
     /// Channel description (optional)
@@ -530,6 +827,55 @@ pub struct RoutingReloadResult {
     pub duration_ms: u64,
 }
 
+/// Channel archive import options (query parameters)
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct ChannelImportQuery {
+    /// Import under this channel ID instead of the archived one
+    #[schema(example = 12)]
+    pub channel_id: Option<u32>,
+
+    /// Import under this channel name instead of the archived one
+    #[schema(example = "PCS Site B")]
+    pub name: Option<String>,
+
+    /// Replace `slave_id` in all protocol mappings (Modbus)
+    #[schema(example = 2)]
+    pub slave_id: Option<u8>,
+
+    /// Start the imported channel (default false, so parameters can be checked first)
+    #[serde(default)]
+    #[schema(example = false)]
+    pub enabled: bool,
+
+    /// Validate only without writing to database
+    #[serde(default)]
+    #[schema(example = false)]
+    pub dry_run: bool,
+}
+
+/// Channel archive import result
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ChannelImportResult {
+    /// Core fields of the imported channel (after remapping)
+    #[serde(flatten)]
+    #[schema(value_type = Object)]
+    pub core: ChannelCore,
+
+    /// Imported points by type
+    pub points: PointCounts,
+
+    /// Number of points with a protocol mapping
+    #[schema(example = 42)]
+    pub mapped_points: usize,
+
+    /// Whether this was a validation-only run
+    pub dry_run: bool,
+
+    /// Runtime status ("stopped", "connecting", "not_started"; "validated" for dry runs)
+    #[schema(example = "stopped")]
+    pub runtime_status: String,
+}
+
 // ============================================================================
 // Phase 1: Channel Detail and Pagination
 // ============================================================================
@@ -1015,6 +1361,7 @@ mod tests {
     fn test_combase_channel_status_conversion() {
         let combase_status = crate::core::channels::ChannelStatus {
             is_connected: true,
+            state: crate::core::channels::ConnectionState::Connected,
             last_update: 1_234_567_890,
         };
         let api_status = ChannelStatusDto::from(combase_status);
@@ -1078,4 +1425,64 @@ mod tests {
         let params = request.parameters.unwrap();
         assert_eq!(params.get("timeout"), Some(&json!(5000)));
     }
+
+    #[test]
+    fn test_api_version_shims() {
+        assert_eq!(ApiVersion::negotiate(None).unwrap(), ApiVersion::V1);
+        assert_eq!(ApiVersion::negotiate(Some(" v2 ")).unwrap(), ApiVersion::V2);
+        assert!(ApiVersion::negotiate(Some("3"))
+            .unwrap_err()
+            .contains("Supported versions: 1, 2"));
+
+        let request: WritePointRequest = serde_json::from_value(json!({
+            "type": "Adjustment",
+            "points": [{"id": "201", "value": 50.0}, {"id": "x", "value": 1.0}]
+        }))
+        .unwrap();
+        let upgraded = v2::CommandRequest::from_v1(request).unwrap();
+        assert_eq!(
+            upgraded.request.point_type,
+            voltage_model::PointType::Adjustment
+        );
+        assert_eq!(upgraded.request.commands.len(), 1);
+        assert_eq!(upgraded.rejected.len(), 1);
+
+        let result = v2::CommandResult {
+            point_id: 201,
+            command_id: None,
+            priority: 0,
+            value: 50.0,
+            status: v2::CommandStatus::Accepted,
+            error: None,
+            timestamp_ms: 1,
+        };
+        let response =
+            v2::CommandResponse::new(1001, upgraded.request.point_type, vec![result.clone()]);
+        let WriteResponse::Batch(batch) = response.into_v1(upgraded).unwrap() else {
+            panic!("batch response expected");
+        };
+        assert_eq!((batch.total, batch.succeeded, batch.failed), (2, 1, 1));
+        assert_eq!(batch.errors[0].error, "Invalid point ID: x");
+
+        // A failed single-point write is an error in v1
+        let request: WritePointRequest =
+            serde_json::from_value(json!({"type": "C", "id": "7", "value": 1.0})).unwrap();
+        let upgraded = v2::CommandRequest::from_v1(request).unwrap();
+        assert!(upgraded.single);
+        let failed = v2::CommandResult {
+            status: v2::CommandStatus::Failed,
+            error: Some("Failed to write point value: down".to_string()),
+            ..result
+        };
+        let response = v2::CommandResponse::new(1001, upgraded.request.point_type, vec![failed]);
+        assert_eq!(response.failed, 1);
+        assert!(response.into_v1(upgraded).is_err());
+
+        let request: v2::CommandRequest = serde_json::from_value(json!({
+            "point_type": "control",
+            "commands": [{"point_id": 7, "value": 1.0, "command_id": "bad id"}]
+        }))
+        .unwrap();
+        assert!(request.check().is_err());
+    }
 }
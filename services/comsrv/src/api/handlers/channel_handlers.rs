@@ -9,8 +9,10 @@ use axum::{
     response::Json,
 };
 use chrono::{DateTime, Utc};
+use std::sync::Arc;
 
 use crate::api::routes::AppState;
+use crate::core::channels::{PollSummary, ReadJob, StatsHistory, StatsHistoryConfig};
 use crate::dto::{
     AppError, ChannelConfig, ChannelDetail, ChannelListQuery, ChannelRuntimeStatus,
     ChannelStatusDto, ChannelStatusResponse, PaginatedResponse, PointCounts, SuccessResponse,
@@ -260,6 +262,173 @@ pub async fn get_channel_status<R: Rtdb>(
     }
 }
 
+/// Query parameters for channel statistics history
+#[derive(Debug, serde::Deserialize)]
+pub struct StatsHistoryQuery {
+    /// Latest samples to return (default 100)
+    pub limit: Option<usize>,
+}
+
+/// Get per-poll statistics history of a channel
+///
+/// Returns the latest poll cycles (oldest first) with latency, points updated
+/// and failed points, plus a summary over the returned window.
+///
+/// @route GET /api/channels/{id}/stats/history
+#[utoipa::path(
+    get,
+    path = "/api/channels/{id}/stats/history",
+    params(
+        ("id" = u32, Path, description = "Channel identifier"),
+        ("limit" = Option<usize>, Query, description = "Latest samples to return (default 100)")
+    ),
+    responses(
+        (status = 200, description = "Poll statistics history", body = serde_json::Value,
+            example = json!({
+                "success": true,
+                "data": {
+                    "channel_id": 1001,
+                    "samples": [
+                        {"ts": 1735689600000_i64, "latency_ms": 12.4, "points_updated": 48, "failed_points": 0},
+                        {"ts": 1735689601000_i64, "latency_ms": 3000.2, "points_updated": 0, "failed_points": 0,
+                         "error": "no response within 3000ms (1/3 missed)"}
+                    ],
+                    "summary": {
+                        "samples": 2,
+                        "failed_cycles": 1,
+                        "avg_latency_ms": 1506.3,
+                        "max_latency_ms": 3000.2,
+                        "points_updated": 48,
+                        "failed_points": 0
+                    }
+                }
+            })
+        )
+    ),
+    tag = "comsrv"
+)]
+pub async fn get_channel_stats_history<R: Rtdb>(
+    State(state): State<AppState<R>>,
+    Path(channel_id): Path<u32>,
+    Query(query): Query<StatsHistoryQuery>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, AppError> {
+    let limit = query.limit.unwrap_or(100);
+    let history = StatsHistory::new(
+        Arc::clone(&state.rtdb),
+        channel_id,
+        StatsHistoryConfig::default(),
+    );
+    let samples = history.recent(limit).await.map_err(|e| {
+        tracing::error!("Ch{} stats history: {}", channel_id, e);
+        AppError::internal_error(format!("Failed to read stats history: {}", e))
+    })?;
+    let summary = PollSummary::from_samples(&samples);
+
+    Ok(Json(SuccessResponse::new(serde_json::json!({
+        "channel_id": channel_id,
+        "samples": samples,
+        "summary": summary,
+    }))))
+}
+
+/// Read all points of a channel now
+///
+/// Schedules one poll outside the regular cycle and returns the job tracking
+/// it; the values are stored like regular poll data. While a job of the
+/// channel is pending, the pending job is returned instead of a new one.
+///
+/// @route POST /api/channels/{id}/read-all
+#[utoipa::path(
+    post,
+    path = "/api/channels/{id}/read-all",
+    params(
+        ("id" = u32, Path, description = "Channel identifier")
+    ),
+    responses(
+        (status = 200, description = "Read job scheduled", body = crate::core::channels::ReadJob,
+            example = json!({
+                "success": true,
+                "data": {
+                    "job_id": "read_1001_1735689600000_0",
+                    "channel_id": 1001,
+                    "status": "queued",
+                    "created_at": 1735689600000_i64,
+                    "points_read": 0,
+                    "failed_points": 0,
+                    "values": [],
+                    "failures": []
+                }
+            })
+        ),
+        (status = 404, description = "Channel not found", body = String)
+    ),
+    tag = "comsrv"
+)]
+pub async fn read_all_channel<R: Rtdb>(
+    State(state): State<AppState<R>>,
+    Path(channel_id): Path<u32>,
+) -> Result<Json<SuccessResponse<ReadJob>>, AppError> {
+    let (job, _created) = state
+        .channel_manager
+        .schedule_read_all(channel_id)
+        .map_err(|_| AppError::not_found(format!("Channel {} not found", channel_id)))?;
+    Ok(Json(SuccessResponse::new(job)))
+}
+
+/// Get the progress and result of a read-all job
+///
+/// @route GET /api/channels/{id}/read-all/{job_id}
+#[utoipa::path(
+    get,
+    path = "/api/channels/{id}/read-all/{job_id}",
+    params(
+        ("id" = u32, Path, description = "Channel identifier"),
+        ("job_id" = String, Path, description = "Read job identifier")
+    ),
+    responses(
+        (status = 200, description = "Read job", body = crate::core::channels::ReadJob,
+            example = json!({
+                "success": true,
+                "data": {
+                    "job_id": "read_1001_1735689600000_0",
+                    "channel_id": 1001,
+                    "status": "completed",
+                    "created_at": 1735689600000_i64,
+                    "started_at": 1735689600001_i64,
+                    "finished_at": 1735689600042_i64,
+                    "points_read": 2,
+                    "failed_points": 1,
+                    "values": [
+                        {"point_type": "T", "point_id": 1, "value": 230.4, "quality": "good"},
+                        {"point_type": "S", "point_id": 1, "value": 1.0, "quality": "good"}
+                    ],
+                    "failures": [
+                        {"point_type": "T", "point_id": 2, "error": "Illegal data address"}
+                    ]
+                }
+            })
+        ),
+        (status = 404, description = "Job not found (unknown or expired)", body = String)
+    ),
+    tag = "comsrv"
+)]
+pub async fn get_read_all_job<R: Rtdb>(
+    State(state): State<AppState<R>>,
+    Path((channel_id, job_id)): Path<(u32, String)>,
+) -> Result<Json<SuccessResponse<ReadJob>>, AppError> {
+    state
+        .channel_manager
+        .read_jobs()
+        .get(channel_id, &job_id)
+        .map(|job| Json(SuccessResponse::new(job)))
+        .ok_or_else(|| {
+            AppError::not_found(format!(
+                "Read job {} not found on channel {}",
+                job_id, channel_id
+            ))
+        })
+}
+
 /// Get complete channel details (configuration + runtime + statistics)
 #[utoipa::path(
     get,
@@ -17,6 +17,7 @@ use axum::{
     extract::{Path, State},
     response::Json,
 };
+use common::ValidatedJson;
 use std::sync::Arc;
 use voltage_rtdb::Rtdb;
 
@@ -379,7 +380,7 @@ async fn perform_hot_reload<R: Rtdb + 'static>(
 )]
 pub async fn create_channel_handler<R: Rtdb>(
     State(state): State<AppState<R>>,
-    Json(req): Json<crate::dto::ChannelCreateRequest>,
+    ValidatedJson(req): ValidatedJson<crate::dto::ChannelCreateRequest>,
 ) -> Result<Json<SuccessResponse<crate::dto::ChannelCrudResult>>, AppError> {
     use crate::core::config::ChannelConfig;
 
@@ -1355,3 +1356,504 @@ pub async fn reload_routing_handler<R: Rtdb>(
 
     Ok(Json(SuccessResponse::new(result)))
 }
+
+/// Generate a virtual twin of a channel from a captured session
+///
+/// The request body is a JSON Lines capture of raw Modbus frames
+/// (`{"ts_ms": 1718000000000, "dir": "tx", "hex": "00 01 ..."}` per line). Frames
+/// are decoded with the channel's point mappings and turned into a `virtual`
+/// channel with typical values, update timing and a replay timeline.
+///
+/// @route POST /api/channels/{id}/virtual-twin
+/// @input Path(id): u32 - Source channel ID (modbus_tcp or modbus_rtu)
+/// @input State(state): AppState - Application state with SQLite
+/// @input body: String - JSON Lines frame capture
+/// @output `Json<ApiResponse<VirtualTwin>>` - Twin channel, point mappings and config files
+/// @status 200 - Twin generated
+/// @status 400 - Invalid capture or unsupported protocol
+/// @status 404 - Channel not found
+/// @side-effects None (configuration is returned, not stored)
+#[utoipa::path(
+    post,
+    path = "/api/channels/{id}/virtual-twin",
+    params(
+        ("id" = u32, Path, description = "Source channel identifier")
+    ),
+    request_body(
+        content = String,
+        content_type = "application/x-ndjson",
+        description = "Captured frames, one JSON object per line"
+    ),
+    responses(
+        (status = 200, description = "Twin generated", body = serde_json::Value),
+        (status = 400, description = "Invalid capture or unsupported protocol"),
+        (status = 404, description = "Channel not found")
+    ),
+    tag = "comsrv"
+)]
+pub async fn generate_virtual_twin_handler<R: Rtdb>(
+    Path(id): Path<u32>,
+    State(state): State<AppState<R>>,
+    body: String,
+) -> Result<Json<SuccessResponse<crate::core::twin::VirtualTwin>>, AppError> {
+    use crate::core::config::{ChannelConfig, ComsrvSqliteLoader, RuntimeChannelConfig};
+    use crate::core::twin::{generate_virtual_twin, parse_capture};
+
+    let row: Option<(String, String, bool, Option<String>)> =
+        sqlx::query_as("SELECT name, protocol, enabled, config FROM channels WHERE channel_id = ?")
+            .bind(id as i64)
+            .fetch_optional(&state.sqlite_pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("DB err: {}", e);
+                AppError::internal_error("Database operation failed")
+            })?;
+    let Some((name, protocol, enabled, config_str)) = row else {
+        return Err(AppError::not_found(format!("Channel {} not found", id)));
+    };
+
+    let (description, parameters, logging) = parse_channel_config(id, config_str)?;
+    let mut runtime_config = RuntimeChannelConfig::from_base(ChannelConfig {
+        core: ChannelCore {
+            id,
+            name,
+            description,
+            protocol,
+            enabled,
+        },
+        parameters,
+        logging,
+    });
+    ComsrvSqliteLoader::with_pool(state.sqlite_pool.clone())
+        .load_runtime_channel_points(&mut runtime_config)
+        .await
+        .map_err(|e| {
+            tracing::error!("Ch{} load points: {}", id, e);
+            AppError::internal_error(format!("Failed to load points: {}", e))
+        })?;
+
+    let frames =
+        parse_capture(body.as_bytes()).map_err(|e| AppError::bad_request(e.to_string()))?;
+    let twin = generate_virtual_twin(&runtime_config, &frames)
+        .map_err(|e| AppError::bad_request(e.to_string()))?;
+
+    tracing::info!(
+        "Ch{} twin: {} frames, {} points, {} events",
+        id,
+        twin.stats.frames,
+        twin.points.len(),
+        twin.replay.len()
+    );
+
+    Ok(Json(SuccessResponse::new(twin)))
+}
+
+/// Load a channel with all points and mappings from SQLite as an archive
+async fn load_channel_archive(
+    pool: &sqlx::SqlitePool,
+    id: u32,
+) -> Result<crate::core::archive::ChannelArchive, AppError> {
+    use crate::core::archive::{ArchivedPoint, ChannelArchive, POINT_TABLES};
+    use crate::core::config::ChannelConfig;
+
+    let row: Option<(String, String, bool, Option<String>)> =
+        sqlx::query_as("SELECT name, protocol, enabled, config FROM channels WHERE channel_id = ?")
+            .bind(id as i64)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("DB err: {}", e);
+                AppError::internal_error("Database operation failed")
+            })?;
+    let Some((name, protocol, enabled, config_str)) = row else {
+        return Err(AppError::not_found(format!("Channel {} not found", id)));
+    };
+    let (description, parameters, logging) = parse_channel_config(id, config_str)?;
+
+    let mut points = std::collections::HashMap::new();
+    for (point_type, _, table) in POINT_TABLES {
+        let rows: Vec<(
+            i64,
+            String,
+            Option<f64>,
+            Option<f64>,
+            Option<String>,
+            Option<bool>,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+        )> = sqlx::query_as(&format!(
+            "SELECT point_id, signal_name, scale, offset, unit, reverse, data_type, description, protocol_mappings
+             FROM {} WHERE channel_id = ? ORDER BY point_id",
+            table
+        ))
+        .bind(id as i64)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Ch{} load {}: {}", id, table, e);
+            AppError::internal_error(format!("Failed to load {}: {}", table, e))
+        })?;
+
+        let archived = rows
+            .into_iter()
+            .map(
+                |(
+                    point_id,
+                    signal_name,
+                    scale,
+                    offset,
+                    unit,
+                    reverse,
+                    data_type,
+                    description,
+                    mappings,
+                )| {
+                    let mapping = mappings
+                        .and_then(|m| serde_json::from_str::<serde_json::Value>(&m).ok())
+                        .and_then(|v| v.as_object().cloned())
+                        .filter(|m| !m.is_empty());
+                    ArchivedPoint {
+                        point_id: point_id as u32,
+                        signal_name,
+                        scale: scale.unwrap_or(1.0),
+                        offset: offset.unwrap_or(0.0),
+                        unit: unit.filter(|u| !u.is_empty()),
+                        reverse: reverse.unwrap_or(false),
+                        data_type,
+                        description,
+                        mapping,
+                    }
+                },
+            )
+            .collect();
+        points.insert(point_type, archived);
+    }
+
+    Ok(ChannelArchive {
+        channel: ChannelConfig {
+            core: ChannelCore {
+                id,
+                name,
+                description,
+                protocol,
+                enabled,
+            },
+            parameters,
+            logging,
+        },
+        points,
+    })
+}
+
+/// Export a channel configuration as a portable archive
+///
+/// The zip contains `channel.yaml`, `parameters.yaml` (protocol parameters),
+/// the four point CSVs and the four mapping CSVs under `mapping/`, in the same
+/// layout as the `comsrv/{channel_id}/` config directory.
+///
+/// @route GET /api/channels/{id}/export
+/// @input Path(id): u32 - Channel ID
+/// @input State(state): AppState - Application state with SQLite
+/// @output Response - `application/zip` attachment
+/// @status 200 - Archive generated
+/// @status 404 - Channel not found
+/// @side-effects None
+#[utoipa::path(
+    get,
+    path = "/api/channels/{id}/export",
+    params(
+        ("id" = u32, Path, description = "Channel identifier")
+    ),
+    responses(
+        (status = 200, description = "Channel archive", content_type = "application/zip", body = Vec<u8>),
+        (status = 404, description = "Channel not found")
+    ),
+    tag = "comsrv"
+)]
+pub async fn export_channel_handler<R: Rtdb>(
+    Path(id): Path<u32>,
+    State(state): State<AppState<R>>,
+) -> Result<axum::response::Response, AppError> {
+    use axum::http::header;
+    use axum::response::IntoResponse;
+
+    let archive = load_channel_archive(&state.sqlite_pool, id).await?;
+    let bytes = archive.to_zip().map_err(|e| {
+        tracing::error!("Ch{} export: {}", id, e);
+        AppError::internal_error(format!("Failed to build archive: {}", e))
+    })?;
+
+    let safe_name: String = archive
+        .channel
+        .core
+        .name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    tracing::info!("Ch{} exported ({} bytes)", id, bytes.len());
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"channel-{}-{}.zip\"", id, safe_name),
+            ),
+        ],
+        bytes,
+    )
+        .into_response())
+}
+
+/// Import a channel configuration archive
+///
+/// Accepts a zip produced by `GET /api/channels/{id}/export` (or edited copies
+/// of it). The archive is validated as a whole: point tables, protocol mappings
+/// against the channel protocol, and channel ID / name conflicts. Nothing is
+/// written unless everything passes. Imported channels start disabled unless
+/// `enabled=true`, so site-specific parameters can be checked first.
+///
+/// @route POST /api/channels/import
+/// @input State(state): AppState - Application state with manager and SQLite
+/// @input Query(query): ChannelImportQuery - ID remapping, enabled and dry_run options
+/// @input body: Bytes - Zip archive
+/// @output `Json<ApiResponse<ChannelImportResult>>` - Imported channel and point counts
+/// @status 200 - Channel imported (or validated with dry_run)
+/// @status 400 - Invalid archive or validation errors
+/// @status 409 - Channel ID or name already exists
+/// @side-effects Creates channel and points in SQLite; starts the channel when enabled
+#[utoipa::path(
+    post,
+    path = "/api/channels/import",
+    params(
+        ("channel_id" = Option<u32>, Query, description = "Import under this channel ID"),
+        ("name" = Option<String>, Query, description = "Import under this channel name"),
+        ("slave_id" = Option<u8>, Query, description = "Replace slave_id in all protocol mappings"),
+        ("enabled" = Option<bool>, Query, description = "Start the channel after import (default false)"),
+        ("dry_run" = Option<bool>, Query, description = "Validate only (default false)")
+    ),
+    request_body(
+        content = Vec<u8>,
+        content_type = "application/zip",
+        description = "Channel archive from /api/channels/{id}/export"
+    ),
+    responses(
+        (status = 200, description = "Channel imported", body = crate::dto::ChannelImportResult),
+        (status = 400, description = "Invalid archive or validation failed"),
+        (status = 409, description = "Channel ID or name already exists")
+    ),
+    tag = "comsrv"
+)]
+pub async fn import_channel_handler<R: Rtdb>(
+    State(state): State<AppState<R>>,
+    axum::extract::Query(query): axum::extract::Query<crate::dto::ChannelImportQuery>,
+    body: bytes::Bytes,
+) -> Result<Json<SuccessResponse<crate::dto::ChannelImportResult>>, AppError> {
+    use crate::api::handlers::mapping_handlers::{normalize_protocol_data, validate_mappings};
+    use crate::core::archive::{ArchiveRemap, ChannelArchive, POINT_TABLES};
+    use crate::core::plugins::ProtocolPlugins;
+
+    // 1. Parse and remap
+    let mut archive =
+        ChannelArchive::from_zip(&body).map_err(|e| AppError::bad_request(e.to_string()))?;
+    archive.remap(&ArchiveRemap {
+        channel_id: query.channel_id,
+        name: query.name.clone(),
+        slave_id: query.slave_id,
+        enabled: Some(query.enabled),
+    });
+    let core = archive.channel.core.clone();
+
+    // 2. Validate points and protocol mappings
+    let mut errors = archive.validate();
+    let mut mapping_items = Vec::new();
+    for (point_type, _, _) in POINT_TABLES {
+        let points = archive.points.entry(point_type).or_default();
+        for point in points.iter_mut() {
+            if let Some(mapping) = point.mapping.take() {
+                let normalized =
+                    normalize_protocol_data(&core.protocol, &serde_json::Value::Object(mapping));
+                mapping_items.push(crate::dto::PointMappingItem {
+                    point_id: point.point_id,
+                    four_remote: point_type.as_str().to_string(),
+                    protocol_data: normalized.clone(),
+                });
+                point.mapping = normalized.as_object().cloned();
+            }
+        }
+    }
+    if ProtocolPlugins::global().get(&core.protocol).is_some() {
+        errors.extend(validate_mappings(&core.protocol, &mapping_items));
+    }
+    if !errors.is_empty() {
+        return Err(AppError::bad_request(format!(
+            "Validation errors: {}",
+            errors.join("; ")
+        )));
+    }
+
+    // 3. Conflicts with existing channels
+    let existing_name: Option<i64> =
+        sqlx::query_scalar("SELECT channel_id FROM channels WHERE name = ?")
+            .bind(&core.name)
+            .fetch_optional(&state.sqlite_pool)
+            .await
+            .map_err(|e| AppError::internal_error(format!("Database error: {}", e)))?;
+    if let Some(existing_id) = existing_name {
+        return Err(AppError::conflict(format!(
+            "Channel name '{}' already exists (ID: {}), import with ?name= to rename",
+            core.name, existing_id
+        )));
+    }
+    let id_exists: bool =
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM channels WHERE channel_id = ?)")
+            .bind(core.id as i64)
+            .fetch_one(&state.sqlite_pool)
+            .await
+            .map_err(|e| AppError::internal_error(format!("Database error: {}", e)))?;
+    if id_exists || state.channel_manager.get_channel(core.id).is_some() {
+        return Err(AppError::conflict(format!(
+            "Channel ID {} already exists, import with ?channel_id= to remap",
+            core.id
+        )));
+    }
+
+    let count = |t| archive.points(t).len();
+    let points = crate::dto::PointCounts {
+        telemetry: count(voltage_model::PointType::Telemetry),
+        signal: count(voltage_model::PointType::Signal),
+        control: count(voltage_model::PointType::Control),
+        adjustment: count(voltage_model::PointType::Adjustment),
+    };
+    let mapped_points = mapping_items.len();
+
+    if query.dry_run {
+        return Ok(Json(SuccessResponse::new(
+            crate::dto::ChannelImportResult {
+                core,
+                points,
+                mapped_points,
+                dry_run: true,
+                runtime_status: "validated".to_string(),
+            },
+        )));
+    }
+
+    // 4. Write channel and points atomically
+    let config_json = build_channel_config_json(
+        core.description.as_ref(),
+        &archive.channel.parameters,
+        &archive.channel.logging,
+    )
+    .map_err(|e| AppError::internal_error(format!("Failed to build config JSON: {}", e)))?;
+
+    let mut tx = state
+        .sqlite_pool
+        .begin()
+        .await
+        .map_err(|e| AppError::internal_error(format!("Failed to start transaction: {}", e)))?;
+    sqlx::query(
+        "INSERT INTO channels (channel_id, name, protocol, enabled, config) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(core.id as i64)
+    .bind(&core.name)
+    .bind(&core.protocol)
+    .bind(core.enabled)
+    .bind(&config_json)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Import Ch{}: {}", core.id, e);
+        AppError::internal_error(format!("Database error: {}", e))
+    })?;
+
+    for (point_type, _, table) in POINT_TABLES {
+        let insert_sql = format!(
+            "INSERT INTO {} (point_id, channel_id, signal_name, scale, offset, unit, reverse, data_type, description, protocol_mappings)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            table
+        );
+        for point in archive.points(point_type) {
+            let mappings = point
+                .mapping
+                .as_ref()
+                .map(|m| serde_json::to_string(m).unwrap_or_else(|_| "{}".to_string()));
+            sqlx::query(&insert_sql)
+                .bind(point.point_id as i64)
+                .bind(core.id as i64)
+                .bind(&point.signal_name)
+                .bind(point.scale)
+                .bind(point.offset)
+                .bind(&point.unit)
+                .bind(point.reverse)
+                .bind(&point.data_type)
+                .bind(&point.description)
+                .bind(mappings)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Import Ch{} {} {}: {}", core.id, table, point.point_id, e);
+                    AppError::internal_error(format!(
+                        "Failed to insert {} point {}: {}",
+                        table, point.point_id, e
+                    ))
+                })?;
+        }
+    }
+    tx.commit()
+        .await
+        .map_err(|e| AppError::internal_error(format!("Failed to commit transaction: {}", e)))?;
+
+    // 5. Start runtime (points are loaded from SQLite)
+    let runtime_status = if core.enabled {
+        match state
+            .channel_manager
+            .create_channel(Arc::new(archive.channel.clone()))
+            .await
+        {
+            Ok(channel_impl) => {
+                let id = core.id;
+                tokio::spawn(async move {
+                    if let Err(e) = channel_impl.write().await.connect().await {
+                        tracing::warn!("Ch{} connect: {}", id, e);
+                    }
+                });
+                "connecting".to_string()
+            },
+            Err(e) => {
+                tracing::warn!("Create Ch{} runtime: {}", core.id, e);
+                "not_started".to_string()
+            },
+        }
+    } else {
+        "stopped".to_string()
+    };
+
+    tracing::info!(
+        "Ch{} imported: {} ({}) T={} S={} C={} A={}",
+        core.id,
+        core.name,
+        core.protocol,
+        points.telemetry,
+        points.signal,
+        points.control,
+        points.adjustment
+    );
+
+    Ok(Json(SuccessResponse::new(
+        crate::dto::ChannelImportResult {
+            core,
+            points,
+            mapped_points,
+            dry_run: false,
+            runtime_status,
+        },
+    )))
+}
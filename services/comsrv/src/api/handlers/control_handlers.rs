@@ -9,11 +9,20 @@
 #![allow(clippy::disallowed_methods)] // json! macro used in multiple functions
 
 use crate::api::routes::AppState;
-use crate::dto::{AppError, ChannelOperation, SuccessResponse, WritePointRequest, WriteResponse};
+use crate::core::channels::command_webhooks::CLIENT_ID_HEADER;
+use crate::core::channels::{CommandTracker, CommandWebhooks};
+use crate::core::config::PointAccess;
+use crate::dto::{
+    v1, v2, ApiVersion, AppError, ChannelOperation, SuccessResponse, WritePointRequest,
+    WriteResponse, API_VERSION_HEADER,
+};
 use axum::{
     extract::{Path, State},
-    response::Json,
+    http::{HeaderMap, HeaderValue},
+    response::{IntoResponse, Json, Response},
 };
+use common::auth::Caller;
+use std::sync::Arc;
 use voltage_model::PointType;
 use voltage_rtdb::KeySpaceConfig;
 use voltage_rtdb::Rtdb;
@@ -167,18 +176,47 @@ pub async fn control_channel<R: Rtdb>(
 /// }
 /// ```
 ///
+/// ## Schema Versions
+/// The `X-Api-Version` header selects the schema and is echoed on the
+/// response. Without it the v1 schema above is used. With `X-Api-Version: 2`
+/// the body is a `v2::CommandRequest` (numeric point IDs, optional
+/// `command_id` and `priority` per command) and the response a
+/// `v2::CommandResponse` with one result per command:
+/// ```json
+/// POST /api/channels/1001/write
+/// X-Api-Version: 2
+/// {
+///   "point_type": "C",
+///   "commands": [{"point_id": 101, "value": 1.0, "command_id": "ems-7781", "priority": 10}]
+/// }
+/// ```
+///
+/// ## Completion Webhooks
+/// Control/adjustment writes sent with an `X-Client-Id` header are reported
+/// to that client's webhook (see `/api/command-webhooks`) once executed.
+///
+/// ## Point Scopes
+/// Control/adjustment points whose `access` is `read_only`, `operator` or
+/// `engineer` are refused with 403 unless the bearer token carries the
+/// required role; a batch containing such a point is refused as a whole.
+///
 /// @route POST /api/channels/{channel_id}/write
 #[utoipa::path(
     post,
     path = "/api/channels/{channel_id}/write",
     params(
-        ("channel_id" = u16, Path, description = "Channel identifier", example = 1001)
+        ("channel_id" = u16, Path, description = "Channel identifier", example = 1001),
+        ("X-Api-Version" = Option<String>, Header, description = "Schema version: 1 (default) or 2"),
+        ("X-Client-Id" = Option<String>, Header, description = "API client whose webhook receives the command result")
     ),
-    request_body = WritePointRequest,
+    request_body(content = WritePointRequest,
+        description = "v1 request; `v2::CommandRequest` with `X-Api-Version: 2`"),
     responses(
-        (status = 200, description = "Write operation completed (single or batch)",
+        (status = 200, description = "Write operation completed (v1: single or batch; v2: `v2::CommandResponse`)",
             body = WriteResponse),
-        (status = 400, description = "Invalid point type or parameters", body = String),
+        (status = 400, description = "Invalid point type, parameters or API version", body = String),
+        (status = 401, description = "Invalid or expired bearer token", body = String),
+        (status = 403, description = "Point is read-only or requires a higher role", body = String),
         (status = 500, description = "Write operation failed", body = String)
     ),
     tag = "comsrv"
@@ -186,219 +224,415 @@ pub async fn control_channel<R: Rtdb>(
 pub async fn write_channel_point<R: Rtdb + 'static>(
     State(state): State<AppState<R>>,
     Path(channel_id): Path<u32>,
-    Json(request): Json<WritePointRequest>,
-) -> Result<Json<SuccessResponse<crate::dto::WriteResponse>>, AppError> {
-    use crate::core::channels::types::ChannelCommand;
-    use crate::dto::{BatchCommandError, BatchCommandResult, WritePointData, WriteResponse};
+    headers: HeaderMap,
+    caller: Caller,
+    Json(body): Json<serde_json::Value>,
+) -> Result<Response, AppError> {
+    let version = ApiVersion::negotiate(
+        headers
+            .get(API_VERSION_HEADER)
+            .and_then(|v| v.to_str().ok()),
+    )
+    .map_err(AppError::bad_request)?;
+    let invalid_body = |e: serde_json::Error| {
+        AppError::bad_request(format!("Invalid v{} request: {}", version.as_str(), e))
+    };
 
-    let rtdb = &state.rtdb;
+    // Both versions execute as v2; v1 requests are upgraded first
+    let (request, upgraded) = match version {
+        ApiVersion::V1 => {
+            let request: v1::WritePointRequest =
+                serde_json::from_value(body).map_err(invalid_body)?;
+            let upgraded = v2::CommandRequest::from_v1(request).map_err(AppError::bad_request)?;
+            (upgraded.request.clone(), Some(upgraded))
+        },
+        ApiVersion::V2 => {
+            let request: v2::CommandRequest = serde_json::from_value(body).map_err(invalid_body)?;
+            request.check().map_err(AppError::bad_request)?;
+            (request, None)
+        },
+    };
+    let point_type = request.point_type;
+    authorize_write(
+        &state,
+        &caller,
+        channel_id,
+        point_type,
+        request
+            .commands
+            .iter()
+            .map(|c| c.point_id)
+            .collect::<Vec<_>>(),
+    )
+    .await?;
 
-    // Normalize point type: support both short (T/S/C/A) and full names (Telemetry/Signal/Control/Adjustment)
-    let point_type = normalize_point_type(&request.r#type)?;
+    // Commands from a named API client report their outcome to its webhook
+    let webhook_client = headers
+        .get(CLIENT_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|client| !client.is_empty())
+        .filter(|_| point_type.is_action());
 
-    // Handle single vs batch based on request data (use cached config to avoid allocation)
-    let config = KeySpaceConfig::production_cached();
+    let mut commands = request.commands;
+    if version >= ApiVersion::V2 {
+        // Every v2 command is tracked under an ID the caller gets back
+        let now = timestamp_ms();
+        for (index, command) in commands.iter_mut().enumerate() {
+            command
+                .command_id
+                .get_or_insert_with(|| format!("api_{}_{}_{}", channel_id, now, index));
+        }
+    }
+    let results = execute_commands(&state, channel_id, point_type, &commands, webhook_client).await;
+    tracing::debug!(
+        "Write Ch{}:{:?}: {}/{} ok (v{})",
+        channel_id,
+        point_type,
+        results
+            .iter()
+            .filter(|r| r.status == v2::CommandStatus::Accepted)
+            .count(),
+        results.len(),
+        version.as_str()
+    );
+    let response = v2::CommandResponse::new(channel_id, point_type, results);
+
+    let mut response = match upgraded {
+        Some(upgraded) => {
+            let response = response
+                .into_v1(upgraded)
+                .map_err(AppError::internal_error)?;
+            Json(SuccessResponse::new(response)).into_response()
+        },
+        None => Json(SuccessResponse::new(response)).into_response(),
+    };
+    response.headers_mut().insert(
+        API_VERSION_HEADER,
+        HeaderValue::from_static(version.as_str()),
+    );
+    Ok(response)
+}
 
-    match &request.data {
-        WritePointData::Single { id, value } => {
-            // Single point write using voltage-rtdb helper
-            let point_id = id
-                .parse::<u32>()
-                .map_err(|_| AppError::bad_request(format!("Invalid point ID: {}", id)))?;
+fn timestamp_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
 
-            let timestamp_ms = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as i64;
+/// Hand commands to the channel and persist their values
+///
+/// Commands are dispatched highest priority first; results are returned in
+/// request order.
+async fn execute_commands<R: Rtdb + 'static>(
+    state: &AppState<R>,
+    channel_id: u32,
+    point_type: PointType,
+    commands: &[v2::Command],
+    webhook_client: Option<&str>,
+) -> Vec<v2::CommandResult> {
+    let mut order: Vec<usize> = (0..commands.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(commands[i].priority));
 
-            // Optimization: O(1) CommandTxCache lookup for Control/Adjustment
-            // Bypasses ChannelManager RwLock entirely for ~97% latency reduction
-            // P50: 50μs → 1-2μs
-            let direct_triggered =
-                if matches!(point_type, PointType::Control | PointType::Adjustment) {
-                    // O(1) lookup from CommandTxCache - no RwLock, no DashMap Ref lifetime issues
-                    let tx_clone = state.command_tx_cache.get_tx(channel_id);
+    let mut results = vec![None; commands.len()];
+    for index in order {
+        let command = &commands[index];
+        let result = execute_command(
+            state,
+            channel_id,
+            point_type,
+            command,
+            commands.len() > 1,
+            webhook_client,
+        )
+        .await;
+        results[index] = Some(result);
+    }
+    results.into_iter().flatten().collect()
+}
 
-                    if let Some(tx) = tx_clone {
-                        let cmd = match point_type {
-                            PointType::Control => ChannelCommand::Control {
-                                command_id: format!("direct_{}_{}", channel_id, timestamp_ms),
-                                point_id,
-                                value: *value,
-                                timestamp: timestamp_ms / 1000,
-                            },
-                            PointType::Adjustment => ChannelCommand::Adjustment {
-                                command_id: format!("direct_{}_{}", channel_id, timestamp_ms),
-                                point_id,
-                                value: *value,
-                                timestamp: timestamp_ms / 1000,
-                            },
-                            _ => unreachable!(),
-                        };
+async fn execute_command<R: Rtdb + 'static>(
+    state: &AppState<R>,
+    channel_id: u32,
+    point_type: PointType,
+    command: &v2::Command,
+    in_batch: bool,
+    webhook_client: Option<&str>,
+) -> v2::CommandResult {
+    use crate::core::channels::types::ChannelCommand;
 
-                        match tx.send(cmd).await {
-                            Ok(_) => {
-                                tracing::debug!(
-                                    "Direct trigger Ch{}:{:?}:{} = {} @{}",
-                                    channel_id,
-                                    point_type,
-                                    id,
-                                    value,
-                                    timestamp_ms
-                                );
-                                true
-                            },
-                            Err(_) => {
-                                tracing::warn!(
-                                    "Direct trigger failed Ch{}, fallback to TODO",
-                                    channel_id
-                                );
-                                false
-                            },
-                        }
-                    } else {
-                        false
-                    }
-                } else {
-                    false // T/S don't use command trigger
-                };
+    let rtdb = &state.rtdb;
+    let config = KeySpaceConfig::production_cached();
+    let point_id = command.point_id;
+    let value = command.value;
+    let timestamp_ms = timestamp_ms();
+
+    if let Some(client) = webhook_client {
+        CommandWebhooks::global()
+            .track(client, channel_id, point_type, point_id)
+            .await;
+    }
 
-            // Always write to Redis Hash (for modsrv sync and state persistence)
-            // If direct_triggered, use write_channel_hash_only (skip TODO queue)
-            // Otherwise, use full write_point_auto_trigger (includes TODO queue as fallback)
-            if direct_triggered {
-                // Direct trigger succeeded - write Hash only (no TODO queue)
-                voltage_rtdb::helpers::write_channel_hash_only(
-                    rtdb.as_ref(),
-                    config,
-                    channel_id,
-                    point_type,
+    // Optimization: O(1) CommandTxCache lookup for Control/Adjustment
+    // Bypasses ChannelManager RwLock entirely for ~97% latency reduction
+    // P50: 50μs → 1-2μs
+    let mut direct_id = None;
+    if point_type.is_action() {
+        if let Some(tx) = state.command_tx_cache.get_tx(channel_id) {
+            let command_id = command.command_id.clone().unwrap_or_else(|| {
+                if in_batch {
+                    format!("direct_{}_{}_{}", channel_id, timestamp_ms, point_id)
+                } else {
+                    format!("direct_{}_{}", channel_id, timestamp_ms)
+                }
+            });
+            let cmd = match point_type {
+                PointType::Control => ChannelCommand::Control {
+                    command_id: command_id.clone(),
                     point_id,
-                    *value,
-                    timestamp_ms,
-                )
-                .await
-                .map_err(|e| {
-                    tracing::error!("Hash write Ch{}:{:?}:{}: {}", channel_id, point_type, id, e);
-                    AppError::internal_error(format!("Failed to write point value: {}", e))
-                })?;
-            } else {
-                // Fallback: use full write path (includes TODO queue)
-                voltage_rtdb::helpers::write_point_auto_trigger(
-                    rtdb.as_ref(),
-                    config,
-                    channel_id,
-                    point_type,
+                    value,
+                    timestamp: timestamp_ms / 1000,
+                },
+                _ => ChannelCommand::Adjustment {
+                    command_id: command_id.clone(),
                     point_id,
-                    *value,
-                )
-                .await
-                .map_err(|e| {
-                    tracing::error!("Write Ch{}:{:?}:{}: {}", channel_id, point_type, id, e);
-                    AppError::internal_error(format!("Failed to write point value: {}", e))
-                })?;
+                    value,
+                    timestamp: timestamp_ms / 1000,
+                },
+            };
+            match tx.send(cmd).await {
+                Ok(_) => direct_id = Some(command_id),
+                Err(_) => {
+                    tracing::warn!("Direct trigger failed Ch{}, fallback to TODO", channel_id)
+                },
             }
+        }
+    }
 
-            tracing::debug!(
-                "Write Ch{}:{:?}:{} = {} @{} (direct={})",
+    // Always write to Redis Hash (for modsrv sync and state persistence).
+    // A direct trigger skips the TODO queue; otherwise the queue is the
+    // fallback, carrying the caller's command ID when there is one.
+    let written = match (&direct_id, &command.command_id) {
+        (Some(_), _) => {
+            voltage_rtdb::helpers::write_channel_hash_only(
+                rtdb.as_ref(),
+                config,
                 channel_id,
                 point_type,
-                id,
+                point_id,
                 value,
                 timestamp_ms,
-                direct_triggered
-            );
-
-            let response = crate::dto::WritePointResponse {
+            )
+            .await
+        },
+        (None, Some(command_id)) if point_type.is_action() => {
+            queue_tracked_command(
+                rtdb.as_ref(),
+                config,
                 channel_id,
-                point_type: point_type.as_str().to_string(),
-                point_id,
-                value: *value,
+                point_type,
+                command,
+                command_id,
                 timestamp_ms,
-            };
-
-            Ok(Json(SuccessResponse::new(WriteResponse::Single(response))))
+            )
+            .await
         },
-        WritePointData::Batch { points } => {
-            // Batch write using application layer function
-            let mut errors = Vec::new();
-            let total = points.len();
-            let mut succeeded = 0;
+        _ => voltage_rtdb::helpers::write_point_auto_trigger(
+            rtdb.as_ref(),
+            config,
+            channel_id,
+            point_type,
+            point_id,
+            value,
+        )
+        .await
+        .map(|_| ()),
+    };
 
-            for point in points {
-                // Parse point ID
-                let point_id = match point.id.parse::<u32>() {
-                    Ok(id) => id,
-                    Err(_) => {
-                        tracing::warn!("Invalid ID: Ch{}:{}:{}", channel_id, point_type, point.id);
-                        errors.push(BatchCommandError {
-                            point_id: 0,
-                            error: format!("Invalid point ID: {}", point.id),
-                        });
-                        continue;
-                    },
-                };
+    let error = written.err().map(|e| {
+        tracing::warn!(
+            "Write Ch{}:{:?}:{}: {}",
+            channel_id,
+            point_type,
+            point_id,
+            e
+        );
+        format!("Failed to write point value: {}", e)
+    });
+    v2::CommandResult {
+        point_id,
+        command_id: direct_id.or_else(|| command.command_id.clone()),
+        priority: command.priority,
+        value,
+        status: if error.is_none() {
+            v2::CommandStatus::Accepted
+        } else {
+            v2::CommandStatus::Failed
+        },
+        error,
+        timestamp_ms,
+    }
+}
 
-                // Write point using voltage-rtdb helper
-                match voltage_rtdb::helpers::write_point_auto_trigger(
-                    rtdb.as_ref(),
-                    config,
-                    channel_id,
-                    point_type,
-                    point_id,
-                    point.value,
-                )
-                .await
-                {
-                    Ok(_) => {
-                        succeeded += 1;
-                    },
-                    Err(e) => {
-                        tracing::warn!(
-                            "Write Ch{}:{:?}:{}: {}",
-                            channel_id,
-                            point_type,
-                            point.id,
-                            e
-                        );
-                        errors.push(BatchCommandError {
-                            point_id,
-                            error: format!("Failed to write: {}", e),
-                        });
-                    },
-                }
-            }
+/// Persist a command and queue it under the caller's command ID
+async fn queue_tracked_command<R: Rtdb>(
+    rtdb: &R,
+    config: &KeySpaceConfig,
+    channel_id: u32,
+    point_type: PointType,
+    command: &v2::Command,
+    command_id: &str,
+    timestamp_ms: i64,
+) -> anyhow::Result<()> {
+    voltage_rtdb::helpers::write_channel_hash_only(
+        rtdb,
+        config,
+        channel_id,
+        point_type,
+        command.point_id,
+        command.value,
+        timestamp_ms,
+    )
+    .await?;
+    let trigger = serde_json::json!({
+        "point_id": command.point_id,
+        "value": command.value,
+        "timestamp": timestamp_ms,
+        "command_id": command_id,
+    });
+    rtdb.list_rpush(
+        &config.todo_queue_key(channel_id, point_type),
+        bytes::Bytes::from(trigger.to_string()),
+    )
+    .await
+}
 
-            tracing::debug!(
-                "Batch Ch{}:{:?}: {}/{} ok",
-                channel_id,
-                point_type,
-                succeeded,
-                total
-            );
+/// Refuse the write unless `caller` may write every listed C/A point
+///
+/// Scopes come from the running channel; for a channel that is not loaded
+/// they are read from the point table so queued commands cannot bypass them.
+pub(crate) async fn authorize_write<R: Rtdb>(
+    state: &AppState<R>,
+    caller: &Caller,
+    channel_id: u32,
+    point_type: PointType,
+    point_ids: impl IntoIterator<Item = u32>,
+) -> Result<(), AppError> {
+    let table = match point_type {
+        PointType::Control => "control_points",
+        PointType::Adjustment => "adjustment_points",
+        PointType::Telemetry | PointType::Signal => return Ok(()),
+    };
+    let entry = state.channel_manager.get_channel_entry(channel_id);
 
-            let result = BatchCommandResult {
-                total,
-                succeeded,
-                failed: total - succeeded,
-                errors,
-            };
+    for point_id in point_ids {
+        let access = match &entry {
+            Some(entry) => entry.point_access(point_type, point_id),
+            None => {
+                let access: Option<String> = sqlx::query_scalar(&format!(
+                    "SELECT access FROM {} WHERE channel_id = ? AND point_id = ?",
+                    table
+                ))
+                .bind(channel_id as i64)
+                .bind(point_id as i64)
+                .fetch_optional(&state.sqlite_pool)
+                .await
+                .map_err(|e| {
+                    tracing::error!(
+                        "Point access Ch{}:{}:{}: {}",
+                        channel_id,
+                        point_type,
+                        point_id,
+                        e
+                    );
+                    AppError::internal_error("Failed to read point access")
+                })?
+                .flatten();
+                access
+                    .as_deref()
+                    .unwrap_or_default()
+                    .parse()
+                    .unwrap_or(PointAccess::ReadOnly)
+            },
+        };
 
-            Ok(Json(SuccessResponse::new(WriteResponse::Batch(result))))
-        },
+        let point = format!(
+            "Point {}:{} on channel {}",
+            point_type, point_id, channel_id
+        );
+        if access == PointAccess::ReadOnly {
+            tracing::warn!("Write refused for {}: {} is read-only", caller, point);
+            return Err(AppError::forbidden(format!("{} is read-only", point)));
+        }
+        if let Some(role) = access.required_role() {
+            if !caller.has_role(role) {
+                tracing::warn!(
+                    "Write refused for {}: {} requires role '{}'",
+                    caller,
+                    point,
+                    role
+                );
+                return Err(AppError::forbidden(format!(
+                    "{} requires role '{}' or higher",
+                    point, role
+                ))
+                .with_details(format!("Caller: {}", caller)));
+            }
+        }
     }
+    Ok(())
 }
 
-/// Normalize point type from full name or short name to single letter
-fn normalize_point_type(type_str: &str) -> Result<PointType, AppError> {
-    match type_str {
-        "T" | "t" | "Telemetry" | "telemetry" | "TELEMETRY" => Ok(PointType::Telemetry),
-        "S" | "s" | "Signal" | "signal" | "SIGNAL" => Ok(PointType::Signal),
-        "C" | "c" | "Control" | "control" | "CONTROL" => Ok(PointType::Control),
-        "A" | "a" | "Adjustment" | "adjustment" | "ADJUSTMENT" => Ok(PointType::Adjustment),
-        _ => Err(AppError::bad_request(format!(
-            "Invalid point type '{}'. Must be one of: T/Telemetry, S/Signal, C/Control, A/Adjustment",
-            type_str
-        ))),
-    }
+/// Get the tracking record of a control or adjustment command
+///
+/// Includes every write attempt made by the channel's retry policy.
+///
+/// @route GET /api/channels/{id}/commands/{command_id}
+#[utoipa::path(
+    get,
+    path = "/api/channels/{id}/commands/{command_id}",
+    params(
+        ("id" = u32, Path, description = "Channel identifier"),
+        ("command_id" = String, Path, description = "Command identifier")
+    ),
+    responses(
+        (status = 200, description = "Command tracking record", body = serde_json::Value,
+            example = json!({
+                "success": true,
+                "data": {
+                    "command_id": "trigger_1001_1735689599000",
+                    "status": "success",
+                    "result": {"point_type": "C", "point_id": 1, "value": 1.0},
+                    "error": null,
+                    "timestamp": 1735689600500_i64,
+                    "attempts": [
+                        {"attempt": 1, "at": 1735689600000_i64, "error": "Connection timed out"},
+                        {"attempt": 2, "at": 1735689600500_i64, "error": null}
+                    ]
+                }
+            })
+        ),
+        (status = 404, description = "Command not tracked", body = String)
+    ),
+    tag = "comsrv"
+)]
+pub async fn get_command_status<R: Rtdb>(
+    State(state): State<AppState<R>>,
+    Path((channel_id, command_id)): Path<(u32, String)>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, AppError> {
+    let status = CommandTracker::new(Arc::clone(&state.rtdb), channel_id)
+        .get(&command_id)
+        .await
+        .map_err(|e| AppError::internal_error(format!("Failed to read command status: {}", e)))?
+        .ok_or_else(|| {
+            AppError::not_found(format!(
+                "Command {} not tracked on channel {}",
+                command_id, channel_id
+            ))
+        })?;
+
+    let status = serde_json::to_value(status)
+        .map_err(|e| AppError::internal_error(format!("Serialization failed: {}", e)))?;
+    Ok(Json(SuccessResponse::new(status)))
 }
@@ -0,0 +1,208 @@
+//! Dead Letter Handlers
+//!
+//! Lists, retries and discards commands that could not be delivered to the
+//! device (see `core::channels::dead_letter`).
+
+#![allow(clippy::disallowed_methods)] // json! macro used in multiple functions
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use common::auth::Caller;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::api::handlers::control_handlers::authorize_write;
+use crate::api::routes::AppState;
+use crate::core::channels::DeadLetterQueue;
+use crate::dto::{AppError, SuccessResponse};
+use voltage_rtdb::{KeySpaceConfig, Rtdb};
+
+/// List undeliverable commands of a channel (oldest first)
+///
+/// @route GET /api/channels/{id}/dead-letters
+#[utoipa::path(
+    get,
+    path = "/api/channels/{id}/dead-letters",
+    params(
+        ("id" = u32, Path, description = "Channel identifier")
+    ),
+    responses(
+        (status = 200, description = "Dead letter entries", body = serde_json::Value,
+            example = json!({
+                "success": true,
+                "data": [{
+                    "id": "1735689600000-0",
+                    "channel_id": 1001,
+                    "command_id": "trigger_1001_1735689599000",
+                    "point_type": "C",
+                    "point_id": 1,
+                    "value": 1.0,
+                    "command_timestamp": 1735689599,
+                    "failed_at": 1735689600000_i64,
+                    "reason": "write rejected"
+                }]
+            })
+        )
+    ),
+    tag = "comsrv"
+)]
+pub async fn list_dead_letters<R: Rtdb>(
+    State(state): State<AppState<R>>,
+    Path(channel_id): Path<u32>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, AppError> {
+    let entries = DeadLetterQueue::new(Arc::clone(&state.rtdb), channel_id)
+        .list()
+        .await
+        .map_err(|e| {
+            tracing::error!("Ch{} dead letters: {}", channel_id, e);
+            AppError::internal_error(format!("Failed to read dead letters: {}", e))
+        })?;
+
+    let entries = serde_json::to_value(entries)
+        .map_err(|e| AppError::internal_error(format!("Serialization failed: {}", e)))?;
+    Ok(Json(SuccessResponse::new(entries)))
+}
+
+/// Retry an undeliverable command
+///
+/// Removes the entry and writes the value again through the normal routed
+/// path (point hash + TODO queue), so the retry is executed like any new
+/// command. The point's write scope is checked against the caller first, as
+/// for `/write`. The entry is restored if the write fails.
+///
+/// @route POST /api/channels/{id}/dead-letters/{entry_id}/retry
+#[utoipa::path(
+    post,
+    path = "/api/channels/{id}/dead-letters/{entry_id}/retry",
+    params(
+        ("id" = u32, Path, description = "Channel identifier"),
+        ("entry_id" = String, Path, description = "Dead letter entry ID")
+    ),
+    responses(
+        (status = 200, description = "Command queued again", body = serde_json::Value,
+            example = json!({
+                "success": true,
+                "data": {
+                    "id": "1735689600000-0",
+                    "point_type": "C",
+                    "point_id": 1,
+                    "value": 1.0
+                }
+            })
+        ),
+        (status = 401, description = "Invalid or expired bearer token", body = String),
+        (status = 403, description = "Point is read-only or requires a higher role", body = String),
+        (status = 404, description = "Entry not found", body = String)
+    ),
+    tag = "comsrv"
+)]
+pub async fn retry_dead_letter<R: Rtdb>(
+    State(state): State<AppState<R>>,
+    Path((channel_id, entry_id)): Path<(u32, String)>,
+    caller: Caller,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, AppError> {
+    let not_found = || {
+        AppError::not_found(format!(
+            "Dead letter {} not found on channel {}",
+            entry_id, channel_id
+        ))
+    };
+    let read_failed = |e: crate::error::ComSrvError| {
+        AppError::internal_error(format!("Failed to read dead letter: {}", e))
+    };
+
+    let mut queue = DeadLetterQueue::new(Arc::clone(&state.rtdb), channel_id);
+    let entry = queue
+        .get(&entry_id)
+        .await
+        .map_err(read_failed)?
+        .ok_or_else(not_found)?;
+    let Some(point_type) = entry.point_type() else {
+        return Err(AppError::bad_request(format!(
+            "Dead letter {} has invalid point type {}",
+            entry_id, entry.point_type
+        )));
+    };
+    authorize_write(&state, &caller, channel_id, point_type, [entry.point_id]).await?;
+
+    let entry = queue
+        .take(&entry_id)
+        .await
+        .map_err(read_failed)?
+        .ok_or_else(not_found)?;
+
+    if let Err(e) = voltage_rtdb::helpers::write_point_auto_trigger(
+        state.rtdb.as_ref(),
+        KeySpaceConfig::production_cached(),
+        channel_id,
+        point_type,
+        entry.point_id,
+        entry.value,
+    )
+    .await
+    {
+        tracing::error!("Ch{} dead letter {} retry: {}", channel_id, entry_id, e);
+        if let Err(e) = queue.push(&entry).await {
+            tracing::error!("Ch{} dead letter {} lost: {}", channel_id, entry_id, e);
+        }
+        return Err(AppError::internal_error(format!(
+            "Failed to queue command: {}",
+            e
+        )));
+    }
+
+    tracing::info!(
+        "Ch{} dead letter {} retried ({}:{} = {})",
+        channel_id,
+        entry_id,
+        entry.point_type,
+        entry.point_id,
+        entry.value
+    );
+    Ok(Json(SuccessResponse::new(json!({
+        "id": entry.id,
+        "point_type": entry.point_type,
+        "point_id": entry.point_id,
+        "value": entry.value
+    }))))
+}
+
+/// Discard an undeliverable command
+///
+/// @route DELETE /api/channels/{id}/dead-letters/{entry_id}
+#[utoipa::path(
+    delete,
+    path = "/api/channels/{id}/dead-letters/{entry_id}",
+    params(
+        ("id" = u32, Path, description = "Channel identifier"),
+        ("entry_id" = String, Path, description = "Dead letter entry ID")
+    ),
+    responses(
+        (status = 200, description = "Entry discarded", body = String),
+        (status = 404, description = "Entry not found", body = String)
+    ),
+    tag = "comsrv"
+)]
+pub async fn discard_dead_letter<R: Rtdb>(
+    State(state): State<AppState<R>>,
+    Path((channel_id, entry_id)): Path<(u32, String)>,
+) -> Result<Json<SuccessResponse<String>>, AppError> {
+    let removed = DeadLetterQueue::new(Arc::clone(&state.rtdb), channel_id)
+        .discard(&entry_id)
+        .await
+        .map_err(|e| AppError::internal_error(format!("Failed to discard dead letter: {}", e)))?;
+    if !removed {
+        return Err(AppError::not_found(format!(
+            "Dead letter {} not found on channel {}",
+            entry_id, channel_id
+        )));
+    }
+
+    tracing::info!("Ch{} dead letter {} discarded", channel_id, entry_id);
+    Ok(Json(SuccessResponse::new(format!(
+        "Dead letter {} discarded",
+        entry_id
+    ))))
+}
@@ -0,0 +1,270 @@
+//! Force Handlers
+//!
+//! Commissioning endpoints that force telemetry/signal points to simulated
+//! values for a bounded duration (see `core::channels::forced_points`).
+
+#![allow(clippy::disallowed_methods)] // json! macro used in multiple functions
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+use utoipa::{IntoParams, ToSchema};
+use voltage_model::PointType;
+
+use crate::api::routes::AppState;
+use crate::core::channels::forced_points::{self, MAX_FORCE_DURATION};
+use crate::core::channels::{ForceAuditEntry, ForcedPoint, ForcedPoints};
+use crate::dto::{AppError, SuccessResponse};
+use voltage_rtdb::Rtdb;
+
+/// Default force duration when none is given
+const DEFAULT_FORCE_SECS: u64 = 300;
+
+/// Force request body
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ForcePointRequest {
+    /// "T" (telemetry) or "S" (signal)
+    pub point_type: String,
+    pub point_id: u32,
+    /// Simulated value (0/1 for signals)
+    pub value: f64,
+    /// Seconds until the point reverts (default 300, at most 14400)
+    #[serde(default)]
+    pub duration_secs: Option<u64>,
+    /// Who sets the force
+    pub owner: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Release query
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ReleaseForceQuery {
+    /// Who releases the force (defaults to its owner)
+    pub actor: Option<String>,
+}
+
+/// Force log query
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ForceLogQuery {
+    /// Maximum entries to return (default 100)
+    pub limit: Option<usize>,
+}
+
+fn ensure_channel<R: Rtdb>(state: &AppState<R>, channel_id: u32) -> Result<(), AppError> {
+    if state
+        .channel_manager
+        .get_channel_entry(channel_id)
+        .is_none()
+    {
+        return Err(AppError::not_found(format!(
+            "Channel {} not found",
+            channel_id
+        )));
+    }
+    Ok(())
+}
+
+fn forceable_type(point_type: &str) -> Result<PointType, AppError> {
+    match PointType::from_str(point_type) {
+        Some(t @ (PointType::Telemetry | PointType::Signal)) => Ok(t),
+        _ => Err(AppError::bad_request(format!(
+            "Invalid point type '{}'. Only T and S points can be forced",
+            point_type
+        ))),
+    }
+}
+
+/// Force a telemetry/signal point to a simulated value
+///
+/// The value is written and routed like a polled value and kept until the
+/// force expires or is released; the point then reverts to the value it had
+/// before. Point info reports the point with quality "forced" meanwhile.
+///
+/// @route POST /api/channels/{id}/force
+#[utoipa::path(
+    post,
+    path = "/api/channels/{id}/force",
+    params(
+        ("id" = u32, Path, description = "Channel identifier")
+    ),
+    request_body = ForcePointRequest,
+    responses(
+        (status = 200, description = "Point forced", body = serde_json::Value,
+            example = json!({
+                "success": true,
+                "data": {
+                    "channel_id": 1001,
+                    "point_type": "T",
+                    "point_id": 3,
+                    "value": 280.0,
+                    "original": 230.5,
+                    "quality": "forced",
+                    "owner": "commissioning",
+                    "reason": "overvoltage trip test",
+                    "set_at": 1735689600000_i64,
+                    "expires_at": 1735689900000_i64
+                }
+            })
+        ),
+        (status = 400, description = "Invalid point type, value or duration", body = String),
+        (status = 404, description = "Channel or point not found", body = String),
+        (status = 409, description = "Point forced by another owner", body = String)
+    ),
+    tag = "comsrv"
+)]
+pub async fn force_point<R: Rtdb + 'static>(
+    State(state): State<AppState<R>>,
+    Path(channel_id): Path<u32>,
+    Json(req): Json<ForcePointRequest>,
+) -> Result<Json<SuccessResponse<ForcedPoint>>, AppError> {
+    ensure_channel(&state, channel_id)?;
+    let point_type = forceable_type(&req.point_type)?;
+    let duration = Duration::from_secs(req.duration_secs.unwrap_or(DEFAULT_FORCE_SECS));
+    if duration.is_zero() || duration > MAX_FORCE_DURATION {
+        return Err(AppError::bad_request(format!(
+            "duration_secs must be between 1 and {}",
+            MAX_FORCE_DURATION.as_secs()
+        )));
+    }
+
+    let registry = ForcedPoints::global();
+    if let Some(current) = registry.get(channel_id, point_type, req.point_id) {
+        if current.owner != req.owner {
+            return Err(AppError::conflict(format!(
+                "Point {}:{} is forced by {} until {}",
+                current.point_type, req.point_id, current.owner, current.expires_at
+            )));
+        }
+    }
+
+    let mut record = ForcedPoint::new(
+        channel_id,
+        point_type,
+        req.point_id,
+        req.value,
+        req.owner,
+        duration,
+    );
+    if let Some(reason) = req.reason {
+        record = record.with_reason(reason);
+    }
+    let forced = registry
+        .force(
+            Arc::clone(&state.rtdb),
+            Arc::clone(&state.channel_manager.routing_cache),
+            record,
+        )
+        .await?;
+    Ok(Json(SuccessResponse::new(forced)))
+}
+
+/// List active forces of a channel
+///
+/// @route GET /api/channels/{id}/force
+#[utoipa::path(
+    get,
+    path = "/api/channels/{id}/force",
+    params(
+        ("id" = u32, Path, description = "Channel identifier")
+    ),
+    responses(
+        (status = 200, description = "Active forces", body = Vec<ForcedPoint>)
+    ),
+    tag = "comsrv"
+)]
+pub async fn list_forced_points<R: Rtdb>(
+    State(state): State<AppState<R>>,
+    Path(channel_id): Path<u32>,
+) -> Result<Json<SuccessResponse<Vec<ForcedPoint>>>, AppError> {
+    ensure_channel(&state, channel_id)?;
+    Ok(Json(SuccessResponse::new(
+        ForcedPoints::global().list(channel_id),
+    )))
+}
+
+/// Release a force; the point reverts to its original value
+///
+/// @route DELETE /api/channels/{id}/force/{point_type}/{point_id}
+#[utoipa::path(
+    delete,
+    path = "/api/channels/{id}/force/{point_type}/{point_id}",
+    params(
+        ("id" = u32, Path, description = "Channel identifier"),
+        ("point_type" = String, Path, description = "Point type: T or S"),
+        ("point_id" = u32, Path, description = "Point identifier"),
+        ReleaseForceQuery
+    ),
+    responses(
+        (status = 200, description = "Force released", body = ForcedPoint),
+        (status = 404, description = "Point not forced", body = String)
+    ),
+    tag = "comsrv"
+)]
+pub async fn release_forced_point<R: Rtdb>(
+    State(state): State<AppState<R>>,
+    Path((channel_id, point_type, point_id)): Path<(u32, String, u32)>,
+    Query(query): Query<ReleaseForceQuery>,
+) -> Result<Json<SuccessResponse<ForcedPoint>>, AppError> {
+    ensure_channel(&state, channel_id)?;
+    let point_type = forceable_type(&point_type)?;
+    let registry = ForcedPoints::global();
+    let Some(current) = registry.get(channel_id, point_type, point_id) else {
+        return Err(AppError::not_found(format!(
+            "Point {}:{} is not forced",
+            point_type.as_str(),
+            point_id
+        )));
+    };
+
+    let actor = query.actor.unwrap_or(current.owner);
+    let released = registry
+        .release(
+            state.rtdb.as_ref(),
+            &state.channel_manager.routing_cache,
+            channel_id,
+            point_type,
+            point_id,
+            &actor,
+        )
+        .await?
+        .ok_or_else(|| {
+            AppError::not_found(format!(
+                "Point {}:{} is not forced",
+                point_type.as_str(),
+                point_id
+            ))
+        })?;
+    Ok(Json(SuccessResponse::new(released)))
+}
+
+/// Force audit log of a channel (newest first)
+///
+/// @route GET /api/channels/{id}/force/log
+#[utoipa::path(
+    get,
+    path = "/api/channels/{id}/force/log",
+    params(
+        ("id" = u32, Path, description = "Channel identifier"),
+        ForceLogQuery
+    ),
+    responses(
+        (status = 200, description = "Force, release and expiry records", body = Vec<ForceAuditEntry>)
+    ),
+    tag = "comsrv"
+)]
+pub async fn get_force_log<R: Rtdb>(
+    State(state): State<AppState<R>>,
+    Path(channel_id): Path<u32>,
+    Query(query): Query<ForceLogQuery>,
+) -> Result<Json<SuccessResponse<Vec<ForceAuditEntry>>>, AppError> {
+    ensure_channel(&state, channel_id)?;
+    let entries =
+        forced_points::audit_log(state.rtdb.as_ref(), channel_id, query.limit.unwrap_or(100))
+            .await?;
+    Ok(Json(SuccessResponse::new(entries)))
+}
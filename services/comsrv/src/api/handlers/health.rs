@@ -6,10 +6,13 @@ use axum::{extract::State, response::Json};
 use chrono::Utc;
 use common::system_metrics::SystemMetrics;
 use common::{ComponentHealth, ServiceStatus as HealthServiceStatus};
+use serde::Serialize;
 use std::collections::HashMap;
 use std::time::Instant;
+use utoipa::ToSchema;
 
 use crate::api::routes::{get_service_start_time, AppState};
+use crate::core::channels::redundancy::{self, NodeStatus, Redundancy};
 use crate::dto::{AppError, HealthStatus, ServiceStatus, SuccessResponse};
 use voltage_rtdb::Rtdb;
 
@@ -53,6 +56,47 @@ pub async fn get_service_status<R: Rtdb>(
     Ok(Json(SuccessResponse::new(status)))
 }
 
+/// Redundancy state of this node and its peers
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RedundancyStatus {
+    /// Whether active/standby redundancy is configured
+    pub enabled: bool,
+    /// This node; absent without redundancy
+    pub node: Option<NodeStatus>,
+    /// All nodes published in `comsrv:ha:nodes`
+    pub nodes: Vec<NodeStatus>,
+}
+
+/// Get redundancy status endpoint
+///
+/// @route GET /api/redundancy
+/// @output `Json<SuccessResponse<RedundancyStatus>>` - Role of this node and its peers
+/// @status 200 - Success
+/// @status 500 - RTDB read failed
+#[utoipa::path(
+    get,
+    path = "/api/redundancy",
+    responses(
+        (status = 200, description = "Redundancy status retrieved", body = RedundancyStatus)
+    ),
+    tag = "comsrv"
+)]
+pub async fn get_redundancy_status<R: Rtdb>(
+    State(state): State<AppState<R>>,
+) -> Result<Json<SuccessResponse<RedundancyStatus>>, AppError> {
+    let redundancy = Redundancy::global();
+    let nodes = if redundancy.is_enabled() {
+        redundancy::node_statuses(state.rtdb.as_ref()).await?
+    } else {
+        Vec::new()
+    };
+    Ok(Json(SuccessResponse::new(RedundancyStatus {
+        enabled: redundancy.is_enabled(),
+        node: redundancy.status(),
+        nodes,
+    })))
+}
+
 /// Health check endpoint
 ///
 /// Performs actual connectivity checks on Redis and SQLite dependencies.
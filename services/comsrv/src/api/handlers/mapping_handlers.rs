@@ -8,6 +8,8 @@
 #![allow(clippy::disallowed_methods)] // json! macro used in multiple functions
 
 use crate::api::routes::AppState;
+use crate::core::channels::waveform::{Generator, SIGNAL_WAVEFORMS};
+use crate::core::plugins::{PointType, ProtocolPlugins};
 use crate::dto::{AppError, MappingBatchUpdateResult, MappingUpdateMode, SuccessResponse};
 use axum::{
     extract::{Path, Query, State},
@@ -48,19 +50,25 @@ struct ModbusMappingValidator {
     bit_position: Option<u8>,
 }
 
-/// Virtual mapping validator - Expression-based simulation validation
+/// Virtual mapping validator - Expression or waveform based simulation
+///
+/// A point either computes an expression or follows a signal generator
+/// (see `core::channels::waveform` for the generator options).
 #[derive(Debug, Deserialize)]
 struct VirtualMappingValidator {
     /// Mathematical expression for value calculation
     /// Supports: +, -, *, /, %, pow(), sqrt(), abs()
     /// Point references: P{id} (e.g., "P1 + P2 * 0.5")
-    expression: String,
+    expression: Option<String>,
+    /// Signal generator: sine, ramp, square, random_walk or steps
+    waveform: Option<String>,
 }
 
 /// GPIO mapping validator for DI/DO protocol
 ///
 /// Validates GPIO pin configuration for digital I/O operations.
-/// Only supports Signal (S) and Control (C) point types:
+/// Supports Telemetry (T), Signal (S) and Control (C) point types:
+/// - Telemetry (T): pulse counter on a GPIO input
 /// - Signal (S): GPIO input (DI)
 /// - Control (C): GPIO output (DO)
 #[derive(Debug, Deserialize)]
@@ -68,6 +76,12 @@ struct VirtualMappingValidator {
 struct GpioMappingValidator {
     /// GPIO pin number (e.g., 496, 504 for ECU-1170)
     gpio_number: u32,
+    /// Input debounce time (S, T)
+    debounce_ms: Option<u64>,
+    /// Counted edges: rising, falling or both (T)
+    count_edge: Option<String>,
+    /// Output pulse width (C)
+    pulse_ms: Option<u64>,
 }
 
 /// Get all mapping configurations for a channel
@@ -376,7 +390,7 @@ pub async fn get_channel_mappings_handler<R: Rtdb>(
             )),
             ("Virtual - Expression Mapping" = (
                 summary = "Virtual protocol with expression-based calculations",
-                description = "Map virtual points using mathematical expressions",
+                description = "Map virtual points using mathematical expressions or signal generators",
                 value = json!({
                     "mappings": [
                         {
@@ -399,6 +413,16 @@ pub async fn get_channel_mappings_handler<R: Rtdb>(
                             "protocol_data": {
                                 "expression": "pow(P1, 2) + sqrt(P2)"
                             }
+                        },
+                        {
+                            "point_id": 104,
+                            "four_remote": "T",
+                            "protocol_data": {
+                                "waveform": "sine",
+                                "amplitude": 20,
+                                "baseline": 50,
+                                "period_ms": 60000
+                            }
                         }
                     ],
                     "validate_only": false,
@@ -408,14 +432,15 @@ pub async fn get_channel_mappings_handler<R: Rtdb>(
             )),
             ("DI/DO GPIO - Digital I/O Mapping" = (
                 summary = "GPIO digital input/output mapping",
-                description = "Map GPIO pins for digital I/O on industrial controllers (e.g., ECU-1170). S=Digital Input, C=Digital Output",
+                description = "Map GPIO pins for digital I/O on industrial controllers (e.g., ECU-1170). S=Digital Input, T=Pulse counter, C=Digital Output",
                 value = json!({
                     "mappings": [
                         {
                             "point_id": 401,
                             "four_remote": "S",
                             "protocol_data": {
-                                "gpio_number": 496
+                                "gpio_number": 496,
+                                "debounce_ms": 50
                             }
                         },
                         {
@@ -425,11 +450,20 @@ pub async fn get_channel_mappings_handler<R: Rtdb>(
                                 "gpio_number": 497
                             }
                         },
+                        {
+                            "point_id": 301,
+                            "four_remote": "T",
+                            "protocol_data": {
+                                "gpio_number": 497,
+                                "count_edge": "rising"
+                            }
+                        },
                         {
                             "point_id": 501,
                             "four_remote": "C",
                             "protocol_data": {
-                                "gpio_number": 504
+                                "gpio_number": 504,
+                                "pulse_ms": 500
                             }
                         }
                     ],
@@ -786,7 +820,10 @@ pub async fn update_channel_mappings_handler<R: Rtdb>(
 /// Uses strong-typed Validator structures to automatically validate types and ranges.
 /// Serde deserialization provides automatic type checking (u8, u16, u32, etc.).
 /// Additional business rules are enforced after type validation.
-fn validate_mappings(protocol: &str, mappings: &[crate::dto::PointMappingItem]) -> Vec<String> {
+pub(crate) fn validate_mappings(
+    protocol: &str,
+    mappings: &[crate::dto::PointMappingItem],
+) -> Vec<String> {
     let mut errors = Vec::new();
 
     for mapping in mappings {
@@ -881,12 +918,46 @@ fn validate_mappings(protocol: &str, mappings: &[crate::dto::PointMappingItem])
                     mapping.protocol_data.clone(),
                 ) {
                     Ok(validated) => {
-                        // Check expression is not empty
-                        if validated.expression.trim().is_empty() {
-                            errors.push(format!(
-                                "Point {}: expression cannot be empty",
-                                mapping.point_id
-                            ));
+                        let is_type = |t: &str| mapping.four_remote.eq_ignore_ascii_case(t);
+                        match validated.waveform {
+                            Some(_) => {
+                                // Generators produce measurements (T) and states (S)
+                                match Generator::from_mapping(&mapping.protocol_data) {
+                                    Ok(generator) => {
+                                        let name = generator.waveform().name();
+                                        if !is_type("T") && !is_type("S") {
+                                            errors.push(format!(
+                                                "Point {}: waveform applies to Telemetry (T) and Signal (S) points",
+                                                mapping.point_id
+                                            ));
+                                        } else if is_type("S") && !SIGNAL_WAVEFORMS.contains(&name)
+                                        {
+                                            errors.push(format!(
+                                                "Point {}: signal points only support {} waveforms",
+                                                mapping.point_id,
+                                                SIGNAL_WAVEFORMS.join(" and ")
+                                            ));
+                                        }
+                                    },
+                                    Err(e) => errors.push(format!(
+                                        "Point {}: invalid waveform - {}",
+                                        mapping.point_id, e
+                                    )),
+                                }
+                            },
+                            // Check expression is not empty
+                            None => {
+                                if validated
+                                    .expression
+                                    .as_deref()
+                                    .is_none_or(|e| e.trim().is_empty())
+                                {
+                                    errors.push(format!(
+                                        "Point {}: expression or waveform is required",
+                                        mapping.point_id
+                                    ));
+                                }
+                            },
                         }
                     },
                     Err(e) => {
@@ -910,16 +981,42 @@ fn validate_mappings(protocol: &str, mappings: &[crate::dto::PointMappingItem])
                             ));
                         }
 
-                        // 2. GPIO only supports Signal (input) and Control (output)
+                        // 2. GPIO supports counters (T), inputs (S) and outputs (C)
                         // Use eq_ignore_ascii_case to avoid String allocation from to_uppercase()
-                        if !mapping.four_remote.eq_ignore_ascii_case("S")
-                            && !mapping.four_remote.eq_ignore_ascii_case("C")
-                        {
+                        let is_type = |t: &str| mapping.four_remote.eq_ignore_ascii_case(t);
+                        if !is_type("T") && !is_type("S") && !is_type("C") {
                             errors.push(format!(
-                                "Point {}: GPIO only supports Signal (S) and Control (C) types, got: {}",
+                                "Point {}: GPIO only supports Telemetry (T), Signal (S) and Control (C) types, got: {}",
                                 mapping.point_id, mapping.four_remote
                             ));
                         }
+
+                        // 3. Per-type options
+                        if validated.debounce_ms.is_some() && is_type("C") {
+                            errors.push(format!(
+                                "Point {}: debounce_ms applies to inputs (S) and counters (T)",
+                                mapping.point_id
+                            ));
+                        }
+                        if let Some(edge) = &validated.count_edge {
+                            if !is_type("T") {
+                                errors.push(format!(
+                                    "Point {}: count_edge applies to counters (T)",
+                                    mapping.point_id
+                                ));
+                            } else if !matches!(edge.as_str(), "rising" | "falling" | "both") {
+                                errors.push(format!(
+                                    "Point {}: count_edge must be rising, falling or both, got: {}",
+                                    mapping.point_id, edge
+                                ));
+                            }
+                        }
+                        if validated.pulse_ms.is_some() && !is_type("C") {
+                            errors.push(format!(
+                                "Point {}: pulse_ms applies to outputs (C)",
+                                mapping.point_id
+                            ));
+                        }
                     },
                     Err(e) => {
                         errors.push(format!(
@@ -930,8 +1027,25 @@ fn validate_mappings(protocol: &str, mappings: &[crate::dto::PointMappingItem])
                 }
             },
             other => {
-                errors.push(format!("Unsupported protocol: {}", other));
-                break; // Protocol error affects all mappings
+                // Protocols without a hand-written validator: check against the
+                // mapping columns their plugin declares
+                let plugin = ProtocolPlugins::global().get(other);
+                let point_type = PointType::from_str(&mapping.four_remote);
+                match (plugin, point_type) {
+                    (Some(plugin), Some(point_type)) => errors.extend(plugin.validate_mapping(
+                        point_type,
+                        mapping.point_id,
+                        &mapping.protocol_data,
+                    )),
+                    (Some(_), None) => errors.push(format!(
+                        "Point {}: invalid point type '{}'",
+                        mapping.point_id, mapping.four_remote
+                    )),
+                    (None, _) => {
+                        errors.push(format!("Unsupported protocol: {}", other));
+                        break; // Protocol error affects all mappings
+                    },
+                }
             },
         }
     }
@@ -964,12 +1078,19 @@ fn validate_mappings(protocol: &str, mappings: &[crate::dto::PointMappingItem])
 /// - `signed`: boolean (unchanged)
 ///
 /// ### Virtual Protocol
-/// - No numeric normalization needed (expression-based)
+/// - `amplitude`, `baseline`, `period_ms`, `phase_ms`, `duty`, `step`: number
+/// - `expression`, `waveform`, `csv`: string (unchanged)
+///
+/// ### Other Protocols
+/// - Converted by the column kinds of their protocol plugin (`core::plugins`)
 ///
 /// @param protocol: Protocol name (modbus_tcp/modbus_rtu/can/virt)
 /// @param value: protocol_data JSON value to normalize
 /// @return Normalized JSON value with corrected types
-fn normalize_protocol_data(protocol: &str, value: &serde_json::Value) -> serde_json::Value {
+pub(crate) fn normalize_protocol_data(
+    protocol: &str,
+    value: &serde_json::Value,
+) -> serde_json::Value {
     use serde_json::{Number, Value};
 
     let Some(obj) = value.as_object() else {
@@ -1007,10 +1128,21 @@ fn normalize_protocol_data(protocol: &str, value: &serde_json::Value) -> serde_j
             "register_address",
             "bit_position",
         ],
-        "di_do" | "gpio" | "dido" => &["gpio_number"],
-        _ => {
-            // Virtual or unknown protocol: no normalization needed
-            return value.clone();
+        "di_do" | "gpio" | "dido" => &["gpio_number", "debounce_ms", "pulse_ms"],
+        "virtual" => &[
+            "amplitude",
+            "baseline",
+            "period_ms",
+            "phase_ms",
+            "duty",
+            "step",
+        ],
+        other => {
+            // Plugin protocols: column kinds drive the conversion
+            return match ProtocolPlugins::global().get(other) {
+                Some(plugin) => plugin.normalize_mapping(value),
+                None => value.clone(),
+            };
         },
     };
 
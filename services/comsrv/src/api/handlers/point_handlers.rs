@@ -8,11 +8,14 @@
 #![allow(clippy::disallowed_methods)] // json! macro used in multiple functions
 
 use crate::api::routes::AppState;
+use crate::core::channels::forced_points::FORCED_QUALITY;
+use crate::core::channels::ForcedPoints;
 use crate::dto::{AppError, SuccessResponse};
 use axum::{
     extract::{Path, Query, State},
     response::Json,
 };
+use serde_json::json;
 use voltage_model::{KeySpaceConfig, PointType};
 use voltage_rtdb::Rtdb;
 
@@ -43,7 +46,8 @@ use voltage_rtdb::Rtdb;
                     "point_id": 101,
                     "value": "650.5",
                     "timestamp": "1729000815",
-                    "raw": "6505"
+                    "raw": "6505",
+                    "quality": null
                 }
             })
         )
@@ -69,7 +73,6 @@ pub async fn get_point_info_handler<R: Rtdb>(
     let field = point_id.to_string();
     let data_key = keyspace.channel_key(channel_id, point_type);
     let ts_key = keyspace.channel_ts_key(channel_id, point_type);
-    let raw_key = keyspace.channel_raw_key(channel_id, point_type);
 
     let value = match state.rtdb.hash_get(&data_key, &field).await {
         Ok(opt) => opt.map(|bytes| String::from_utf8_lossy(&bytes).to_string()),
@@ -91,8 +94,17 @@ pub async fn get_point_info_handler<R: Rtdb>(
         },
     };
 
-    let raw_value = match state.rtdb.hash_get(&raw_key, &field).await {
-        Ok(opt) => opt.map(|bytes| String::from_utf8_lossy(&bytes).to_string()),
+    // Raw values may be stored compressed or not at all (see raw_layer)
+    let raw_value = match voltage_rtdb::helpers::read_raw_value(
+        state.rtdb.as_ref(),
+        keyspace,
+        channel_id,
+        point_type,
+        point_id,
+    )
+    .await
+    {
+        Ok(opt) => opt.map(|raw| raw.to_string()),
         Err(e) => {
             return Err(AppError::internal_error(format!(
                 "Failed to read raw value: {}",
@@ -108,6 +120,9 @@ pub async fn get_point_info_handler<R: Rtdb>(
         "value": value,
         "timestamp": timestamp,
         "raw": raw_value,
+        "quality": ForcedPoints::global()
+            .get(channel_id, point_type, point_id)
+            .map(|_| FORCED_QUALITY),
         "source": "redis"  // Indicate data source for debugging
     }))))
 }
@@ -518,6 +533,224 @@ pub async fn get_point_mapping_with_type_handler<R: Rtdb>(
     }
 }
 
+/// Get point metadata with its protocol source
+///
+/// Merges the four-remote rows sharing `point_id` (T/S/C/A each have their own
+/// ID space) with their protocol mapping, answering "which register feeds this
+/// value" without reading the point CSVs. `?type=T|S|C|A` restricts the lookup
+/// to one table.
+///
+/// @route GET /api/channels/{id}/points/{point_id}/meta
+#[utoipa::path(
+    get,
+    path = "/api/channels/{id}/points/{point_id}/meta",
+    params(
+        ("id" = u32, Path, description = "Channel identifier"),
+        ("point_id" = u32, Path, description = "Point identifier"),
+        ("type" = Option<String>, Query, description = "Restrict to one four-remote type (T/S/C/A)")
+    ),
+    responses(
+        (status = 200, description = "Point metadata", body = serde_json::Value,
+            example = json!({
+                "success": true,
+                "data": {
+                    "channel_id": 1,
+                    "channel_name": "PCS#1",
+                    "protocol": "modbus_tcp",
+                    "point_id": 101,
+                    "points": [{
+                        "point_type": "T",
+                        "signal_name": "DC_Voltage",
+                        "description": "DC bus voltage",
+                        "unit": "V",
+                        "data_type": "float32",
+                        "scaling": {"scale": 0.1, "offset": 0.0, "reverse": false},
+                        "source": "slave 1, FC3, register 100, float32 ABCD",
+                        "protocol_mapping": {
+                            "slave_id": 1,
+                            "function_code": 3,
+                            "register_address": 100,
+                            "data_type": "float32",
+                            "byte_order": "ABCD"
+                        }
+                    }]
+                }
+            })
+        ),
+        (status = 400, description = "Invalid four-remote type"),
+        (status = 404, description = "Channel or point not found")
+    ),
+    tag = "comsrv"
+)]
+pub async fn get_point_meta_handler<R: Rtdb>(
+    Path((channel_id, point_id)): Path<(u32, u32)>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+    State(state): State<AppState<R>>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, AppError> {
+    let types: Vec<PointType> = match params.get("type") {
+        Some(t) => vec![PointType::from_str(t).ok_or_else(|| {
+            AppError::bad_request(format!(
+                "Invalid four-remote type '{}'. Must be T, S, C, or A",
+                t
+            ))
+        })?],
+        None => vec![
+            PointType::Telemetry,
+            PointType::Signal,
+            PointType::Control,
+            PointType::Adjustment,
+        ],
+    };
+
+    let channel: Option<(String, String)> =
+        sqlx::query_as("SELECT name, protocol FROM channels WHERE channel_id = ?")
+            .bind(channel_id as i64)
+            .fetch_optional(&state.sqlite_pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Ch check: {}", e);
+                AppError::internal_error("Database operation failed")
+            })?;
+    let Some((channel_name, protocol)) = channel else {
+        return Err(AppError::not_found(format!(
+            "Channel {} not found",
+            channel_id
+        )));
+    };
+
+    let mut points = Vec::new();
+    for point_type in types {
+        let table = match point_type {
+            PointType::Telemetry => "telemetry_points",
+            PointType::Signal => "signal_points",
+            PointType::Control => "control_points",
+            PointType::Adjustment => "adjustment_points",
+        };
+        let query = format!(
+            "SELECT signal_name, scale, offset, unit, reverse, data_type, description, protocol_mappings \
+             FROM {} WHERE channel_id = ? AND point_id = ?",
+            table
+        );
+        #[allow(clippy::type_complexity)]
+        let row: Option<(
+            String,
+            Option<f64>,
+            Option<f64>,
+            Option<String>,
+            Option<bool>,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+        )> = sqlx::query_as(&query)
+            .bind(channel_id as i64)
+            .bind(point_id as i64)
+            .fetch_optional(&state.sqlite_pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Query {}: {}", table, e);
+                AppError::internal_error("Database operation failed")
+            })?;
+        let Some((signal_name, scale, offset, unit, reverse, data_type, description, mappings)) =
+            row
+        else {
+            continue;
+        };
+
+        let mapping = mappings
+            .and_then(
+                |json| match serde_json::from_str::<serde_json::Value>(&json) {
+                    Ok(value) => Some(value),
+                    Err(e) => {
+                        tracing::warn!(
+                            "Ch{}:{}:{} bad mapping: {}",
+                            channel_id,
+                            point_type.as_str(),
+                            point_id,
+                            e
+                        );
+                        None
+                    },
+                },
+            )
+            .unwrap_or_else(|| json!({}));
+
+        points.push(json!({
+            "point_type": point_type.as_str(),
+            "signal_name": signal_name,
+            "description": description,
+            "unit": unit,
+            "data_type": data_type,
+            "scaling": {
+                "scale": scale.unwrap_or(1.0),
+                "offset": offset.unwrap_or(0.0),
+                "reverse": reverse.unwrap_or(false)
+            },
+            "source": describe_source(&protocol, &mapping),
+            "protocol_mapping": mapping
+        }));
+    }
+
+    if points.is_empty() {
+        return Err(AppError::not_found(format!(
+            "Point {} not found in channel {}",
+            point_id, channel_id
+        )));
+    }
+
+    Ok(Json(SuccessResponse::new(json!({
+        "channel_id": channel_id,
+        "channel_name": channel_name,
+        "protocol": protocol,
+        "point_id": point_id,
+        "points": points
+    }))))
+}
+
+/// One-line description of where a point's value comes from
+///
+/// Modbus mappings name the register; other protocols list their mapping
+/// fields, and points without a mapping have no source.
+fn describe_source(protocol: &str, mapping: &serde_json::Value) -> Option<String> {
+    let fields = mapping.as_object().filter(|m| !m.is_empty())?;
+    let field = |name: &str| {
+        fields
+            .get(name)
+            .map(|v| v.to_string().trim_matches('"').to_string())
+    };
+
+    if protocol.starts_with("modbus") {
+        let mut parts = Vec::new();
+        if let Some(slave) = field("slave_id") {
+            parts.push(format!("slave {}", slave));
+        }
+        if let Some(fc) = field("function_code") {
+            parts.push(format!("FC{}", fc));
+        }
+        if let Some(register) = field("register_address") {
+            parts.push(format!("register {}", register));
+        }
+        if let Some(bit) = field("bit_position").filter(|b| b != "0") {
+            parts.push(format!("bit {}", bit));
+        }
+        let format = [field("data_type"), field("byte_order")]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !format.is_empty() {
+            parts.push(format);
+        }
+        return Some(parts.join(", "));
+    }
+
+    let mut pairs: Vec<String> = fields
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v.to_string().trim_matches('"')))
+        .collect();
+    pairs.sort();
+    Some(format!("{}: {}", protocol, pairs.join(", ")))
+}
+
 // ============================================================================
 // Point CRUD Handlers (Create, Update, Delete)
 // ============================================================================
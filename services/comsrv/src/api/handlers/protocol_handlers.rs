@@ -2,11 +2,19 @@
 //!
 //! Provides endpoints for discovering available protocols and their configuration options.
 
-use axum::response::Json;
+#![allow(clippy::disallowed_methods)] // json! macro used in utoipa examples
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
 use igw::{get_protocol_registry, DriverMetadata, ProtocolMetadata};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use crate::dto::{AppError, SuccessResponse};
+use crate::api::routes::AppState;
+use crate::core::plugins::{MappingColumn, ProtocolPlugin, ProtocolPlugins};
+use crate::dto::{AppError, GroupedPoints, SuccessResponse};
+use voltage_rtdb::Rtdb;
 
 /// Protocol information for API response.
 #[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
@@ -60,6 +68,50 @@ pub struct ParameterInfo {
     pub param_type: String,
 }
 
+/// Protocol plugin descriptor for API response.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct ProtocolPluginInfo {
+    /// Protocol name stored in `channels.protocol`.
+    pub name: String,
+    /// Other accepted spellings.
+    pub aliases: Vec<String>,
+    /// Human-readable display name.
+    pub display_name: String,
+    /// Description of the protocol.
+    pub description: String,
+    /// Supported point types ("T", "S", "C", "A").
+    pub point_types: Vec<String>,
+    /// Channel configuration parameters.
+    pub parameters: Vec<ParameterInfo>,
+    /// Columns of the point mapping CSVs (without `point_id`).
+    pub mapping_columns: Vec<MappingColumn>,
+    /// Header line of the mapping CSVs.
+    pub mapping_template: String,
+}
+
+impl From<&dyn ProtocolPlugin> for ProtocolPluginInfo {
+    fn from(plugin: &dyn ProtocolPlugin) -> Self {
+        Self {
+            name: plugin.name().to_string(),
+            aliases: plugin.aliases().iter().map(|a| a.to_string()).collect(),
+            display_name: plugin.display_name().to_string(),
+            description: plugin.description().to_string(),
+            point_types: plugin
+                .point_types()
+                .iter()
+                .map(|t| t.as_str().to_string())
+                .collect(),
+            parameters: plugin
+                .parameters()
+                .iter()
+                .map(ParameterInfo::from)
+                .collect(),
+            mapping_columns: plugin.mapping_columns().to_vec(),
+            mapping_template: plugin.mapping_template(),
+        }
+    }
+}
+
 impl From<&ProtocolMetadata> for ProtocolInfo {
     fn from(meta: &ProtocolMetadata) -> Self {
         Self {
@@ -124,3 +176,415 @@ pub async fn list_protocols() -> Result<Json<SuccessResponse<Vec<ProtocolInfo>>>
         .collect();
     Ok(Json(SuccessResponse::new(protocols)))
 }
+
+/// List protocol plugins
+///
+/// Returns the self-described configuration schema of every registered
+/// protocol plugin: channel parameters, point types and mapping CSV columns.
+///
+/// @route GET /api/protocols/plugins
+/// @output `Json<SuccessResponse<Vec<ProtocolPluginInfo>>>` - Plugin descriptors
+/// @status 200 - Success with plugin descriptors
+#[utoipa::path(
+    get,
+    path = "/api/protocols/plugins",
+    responses(
+        (status = 200, description = "Registered protocol plugins", body = Vec<ProtocolPluginInfo>)
+    ),
+    tag = "comsrv"
+)]
+pub async fn list_protocol_plugins(
+) -> Result<Json<SuccessResponse<Vec<ProtocolPluginInfo>>>, AppError> {
+    let plugins = ProtocolPlugins::global()
+        .list()
+        .iter()
+        .map(|plugin| ProtocolPluginInfo::from(plugin.as_ref()))
+        .collect();
+    Ok(Json(SuccessResponse::new(plugins)))
+}
+
+/// Get a protocol plugin
+///
+/// Accepts the protocol name or any alias (e.g. `di_do` for `gpio`).
+///
+/// @route GET /api/protocols/plugins/{name}
+/// @input Path(name): String - Protocol name or alias
+/// @output `Json<SuccessResponse<ProtocolPluginInfo>>` - Plugin descriptor
+/// @status 200 - Success with plugin descriptor
+/// @status 404 - No plugin for the protocol
+#[utoipa::path(
+    get,
+    path = "/api/protocols/plugins/{name}",
+    params(
+        ("name" = String, Path, description = "Protocol name or alias")
+    ),
+    responses(
+        (status = 200, description = "Protocol plugin", body = ProtocolPluginInfo),
+        (status = 404, description = "No plugin for the protocol", body = String)
+    ),
+    tag = "comsrv"
+)]
+pub async fn get_protocol_plugin(
+    Path(name): Path<String>,
+) -> Result<Json<SuccessResponse<ProtocolPluginInfo>>, AppError> {
+    let plugin = ProtocolPlugins::global()
+        .get(&name)
+        .ok_or_else(|| AppError::not_found(format!("No protocol plugin for '{}'", name)))?;
+    Ok(Json(SuccessResponse::new(ProtocolPluginInfo::from(
+        plugin.as_ref(),
+    ))))
+}
+
+/// Query of the SCL point-table generation
+#[derive(Debug, Deserialize)]
+pub struct SclQuery {
+    /// IED to list from a multi-IED (SCD) file
+    pub ied: Option<String>,
+}
+
+/// Generate IEC 61850 point tables from an SCL file
+///
+/// Parses an ICD/CID/SCD file and returns one point per mappable data
+/// attribute, grouped by suggested point type and numbered from 1 per type.
+/// Each point carries the `reference`/`fc` mapping of the `iec61850`
+/// protocol, ready to be edited and imported into a channel.
+///
+/// @route POST /api/protocols/iec61850/scl
+/// @input Query(ied): Option<String> - IED name (SCD files)
+/// @input body: String - SCL XML
+/// @output `Json<SuccessResponse<GroupedPoints>>` - Generated point definitions
+/// @status 200 - Success with point definitions
+/// @status 400 - Not a valid SCL file
+#[utoipa::path(
+    post,
+    path = "/api/protocols/iec61850/scl",
+    params(
+        ("ied" = Option<String>, Query, description = "IED to list from a multi-IED (SCD) file")
+    ),
+    request_body(content = String, content_type = "application/xml", description = "ICD/CID/SCD file"),
+    responses(
+        (status = 200, description = "Generated point definitions", body = crate::dto::GroupedPoints),
+        (status = 400, description = "Invalid SCL file", body = String)
+    ),
+    tag = "comsrv"
+)]
+pub async fn generate_scl_points(
+    Query(query): Query<SclQuery>,
+    body: String,
+) -> Result<Json<SuccessResponse<GroupedPoints>>, AppError> {
+    #[cfg(feature = "iec61850")]
+    {
+        use crate::core::protocols::iec61850::scl;
+        use crate::dto::PointDefinition;
+        use voltage_model::PointType;
+
+        let attributes = scl::parse_scl(&body, query.ied.as_deref())
+            .map_err(|e| AppError::bad_request(e.to_string()))?;
+        let mut grouped = GroupedPoints {
+            telemetry: Vec::new(),
+            signal: Vec::new(),
+            control: Vec::new(),
+            adjustment: Vec::new(),
+        };
+        for attribute in attributes {
+            let mut mapping = serde_json::json!({
+                "reference": attribute.reference,
+                "fc": attribute.fc,
+            });
+            if matches!(
+                attribute.point_type,
+                PointType::Control | PointType::Adjustment
+            ) {
+                mapping["value_type"] = attribute.value_type().into();
+            }
+            let points = match attribute.point_type {
+                PointType::Telemetry => &mut grouped.telemetry,
+                PointType::Signal => &mut grouped.signal,
+                PointType::Control => &mut grouped.control,
+                PointType::Adjustment => &mut grouped.adjustment,
+            };
+            let signal_name = attribute
+                .reference
+                .split_once('/')
+                .map_or(attribute.reference.as_str(), |(_, path)| path)
+                .to_string();
+            points.push(PointDefinition {
+                point_id: points.len() as u32 + 1,
+                signal_name,
+                scale: 1.0,
+                offset: 0.0,
+                unit: String::new(),
+                data_type: attribute.data_type().to_string(),
+                reverse: false,
+                description: attribute.description,
+                protocol_mapping: Some(mapping),
+            });
+        }
+        Ok(Json(SuccessResponse::new(grouped)))
+    }
+    #[cfg(not(feature = "iec61850"))]
+    {
+        let _ = (query, body);
+        Err(AppError::bad_request(
+            "comsrv was built without IEC 61850 support",
+        ))
+    }
+}
+
+/// Request of an OPC UA browse
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct OpcUaBrowseRequest {
+    /// Channel parameters (`endpoint_url`, `username`, ...)
+    #[schema(value_type = Object)]
+    pub parameters: std::collections::HashMap<String, serde_json::Value>,
+    /// Node to browse; the Objects folder (`i=85`) when absent
+    #[serde(default)]
+    pub node_id: Option<String>,
+}
+
+/// Browse the node tree of an OPC UA server
+///
+/// Connects with the given channel parameters and lists the children of a
+/// node. Variables carry their data type and a suggested point type, so
+/// their `node_id` can be copied into point mappings of the `opcua`
+/// protocol.
+///
+/// @route POST /api/protocols/opcua/browse
+/// @input Json(request): OpcUaBrowseRequest - Channel parameters and node
+/// @output `Json<SuccessResponse<Vec<BrowseNode>>>` - Child nodes
+/// @status 200 - Success with child nodes
+/// @status 400 - Invalid parameters or server unreachable
+#[utoipa::path(
+    post,
+    path = "/api/protocols/opcua/browse",
+    request_body = OpcUaBrowseRequest,
+    responses(
+        (status = 200, description = "Child nodes", body = serde_json::Value,
+            example = json!({
+                "success": true,
+                "data": [
+                    {"node_id": "ns=2;s=PCS1", "browse_name": "PCS1", "display_name": "PCS1", "node_class": "object"},
+                    {"node_id": "ns=2;s=PCS1.P", "browse_name": "P", "display_name": "Active Power",
+                     "node_class": "variable", "value_type": "double", "point_type": "T"}
+                ]
+            })
+        ),
+        (status = 400, description = "Invalid parameters or browse failed", body = String)
+    ),
+    tag = "comsrv"
+)]
+pub async fn browse_opcua(
+    Json(request): Json<OpcUaBrowseRequest>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, AppError> {
+    #[cfg(feature = "opcua")]
+    {
+        let nodes =
+            crate::core::protocols::opcua::browse(&request.parameters, request.node_id.as_deref())
+                .await
+                .map_err(|e| AppError::bad_request(e.to_string()))?;
+        let nodes =
+            serde_json::to_value(nodes).map_err(|e| AppError::internal_error(e.to_string()))?;
+        Ok(Json(SuccessResponse::new(nodes)))
+    }
+    #[cfg(not(feature = "opcua"))]
+    {
+        let _ = request;
+        Err(AppError::bad_request(
+            "comsrv was built without OPC UA support",
+        ))
+    }
+}
+
+/// Request of a SunSpec discovery
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct SunSpecDiscoveryRequest {
+    /// `modbus_tcp` channel parameters (`host`, `port`) plus optional
+    /// `slave_id` (default 1), `base_address` and `timeout_ms`
+    #[schema(value_type = Object)]
+    pub parameters: std::collections::HashMap<String, serde_json::Value>,
+}
+
+/// Discover the SunSpec models of a Modbus device
+///
+/// Connects with the given channel parameters, walks the SunSpec model
+/// chain and returns the models found together with telemetry and signal
+/// point tables for the inverter and meter models. The points carry
+/// `modbus_tcp` mappings with the device's scale factors applied, ready to
+/// be imported into a channel.
+///
+/// @route POST /api/protocols/modbus/sunspec
+/// @input Json(request): SunSpecDiscoveryRequest - Channel parameters
+/// @output `Json<SuccessResponse<Value>>` - Models and generated points
+/// @status 200 - Success with models and points
+/// @status 400 - Invalid parameters, device unreachable or not SunSpec
+#[utoipa::path(
+    post,
+    path = "/api/protocols/modbus/sunspec",
+    request_body = SunSpecDiscoveryRequest,
+    responses(
+        (status = 200, description = "Discovered models and generated points", body = serde_json::Value,
+            example = json!({
+                "success": true,
+                "data": {
+                    "slave_id": 1,
+                    "base_address": 40000,
+                    "models": [
+                        {"id": 1, "name": "Common", "address": 40002, "length": 66, "supported": false,
+                         "device": {"manufacturer": "Acme", "model": "PV-10K", "options": "",
+                                    "version": "1.2.3", "serial_number": "SN0001", "device_address": 1}},
+                        {"id": 103, "name": "Inverter (three phase)", "address": 40070, "length": 50, "supported": true}
+                    ],
+                    "points": {
+                        "telemetry": [
+                            {"point_id": 1, "signal_name": "103_W", "scale": 10.0, "offset": 0.0, "unit": "W",
+                             "data_type": "int16", "reverse": false, "description": "Inverter (three phase) AC power",
+                             "protocol_mapping": {"slave_id": 1, "function_code": 3, "register_address": 40084,
+                                                  "data_type": "int16", "byte_order": "ABCD"}}
+                        ],
+                        "signal": [],
+                        "control": [],
+                        "adjustment": []
+                    }
+                }
+            })
+        ),
+        (status = 400, description = "Invalid parameters or discovery failed", body = String)
+    ),
+    tag = "comsrv"
+)]
+pub async fn discover_sunspec(
+    Json(request): Json<SunSpecDiscoveryRequest>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, AppError> {
+    #[cfg(feature = "modbus")]
+    {
+        use crate::dto::PointDefinition;
+        use voltage_model::PointType;
+
+        let discovery = crate::core::protocols::sunspec::discover(&request.parameters)
+            .await
+            .map_err(|e| AppError::bad_request(e.to_string()))?;
+        let mut grouped = GroupedPoints {
+            telemetry: Vec::new(),
+            signal: Vec::new(),
+            control: Vec::new(),
+            adjustment: Vec::new(),
+        };
+        for point in discovery.points() {
+            let points = match point.point_type {
+                PointType::Signal => &mut grouped.signal,
+                _ => &mut grouped.telemetry,
+            };
+            points.push(PointDefinition {
+                point_id: points.len() as u32 + 1,
+                protocol_mapping: Some(point.mapping(discovery.slave_id)),
+                signal_name: point.signal_name,
+                scale: point.scale,
+                offset: 0.0,
+                unit: point.unit,
+                data_type: point.data_type.to_string(),
+                reverse: false,
+                description: point.description,
+            });
+        }
+
+        let mut data = serde_json::to_value(&discovery)
+            .map_err(|e| AppError::internal_error(e.to_string()))?;
+        data["points"] =
+            serde_json::to_value(grouped).map_err(|e| AppError::internal_error(e.to_string()))?;
+        Ok(Json(SuccessResponse::new(data)))
+    }
+    #[cfg(not(feature = "modbus"))]
+    {
+        let _ = request;
+        Err(AppError::bad_request(
+            "comsrv was built without Modbus support",
+        ))
+    }
+}
+
+/// Request of a DBC reload
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct DbcReloadRequest {
+    /// DBC file readable by comsrv
+    pub path: String,
+}
+
+/// Replace the DBC file of a running CAN channel
+///
+/// Rebuilds the signal tables of the channel's `signal` points from the
+/// new file and swaps them in at the next poll, without reconnecting. The
+/// path is stored as the channel's `dbc_file` parameter. A file that cannot
+/// be read or parsed leaves the channel unchanged.
+///
+/// @route POST /api/channels/{id}/can/dbc
+/// @input Json(request): DbcReloadRequest - New DBC file
+/// @output `Json<SuccessResponse<Value>>` - Points added, removed and changed
+/// @status 200 - Tables replaced
+/// @status 400 - Unreadable DBC file or not a running raw CAN channel
+#[utoipa::path(
+    post,
+    path = "/api/channels/{id}/can/dbc",
+    params(
+        ("id" = u32, Path, description = "Channel identifier")
+    ),
+    request_body = DbcReloadRequest,
+    responses(
+        (status = 200, description = "DBC reloaded", body = serde_json::Value,
+            example = json!({
+                "success": true,
+                "data": {
+                    "channel_id": 12,
+                    "dbc_file": "/etc/voltage/can/bms-v2.dbc",
+                    "messages": 14,
+                    "skipped_signals": 3,
+                    "points": 42,
+                    "added": ["T41", "T42"],
+                    "removed": ["S7"],
+                    "changed": ["T3"]
+                }
+            })
+        ),
+        (status = 400, description = "Reload failed", body = String)
+    ),
+    tag = "comsrv"
+)]
+pub async fn reload_can_dbc<R: Rtdb>(
+    State(state): State<AppState<R>>,
+    Path(channel_id): Path<u32>,
+    Json(request): Json<DbcReloadRequest>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, AppError> {
+    #[cfg(all(feature = "can", target_os = "linux"))]
+    {
+        use crate::core::protocols::can::DbcTables;
+
+        let tables = DbcTables::of_channel(channel_id).ok_or_else(|| {
+            AppError::bad_request(format!(
+                "Channel {} is not a running raw CAN channel",
+                channel_id
+            ))
+        })?;
+        let path = std::path::PathBuf::from(request.path.trim());
+        let report = tables
+            .reload(&path)
+            .map_err(|e| AppError::bad_request(e.to_string()))?;
+        sqlx::query(
+            "UPDATE channels SET config = json_set(COALESCE(config, '{}'), '$.parameters.dbc_file', ?) \
+             WHERE channel_id = ?",
+        )
+        .bind(report.dbc_file.as_str())
+        .bind(channel_id)
+        .execute(&state.sqlite_pool)
+        .await
+        .map_err(|e| AppError::internal_error(format!("DBC reloaded but not saved: {}", e)))?;
+        let report =
+            serde_json::to_value(report).map_err(|e| AppError::internal_error(e.to_string()))?;
+        Ok(Json(SuccessResponse::new(report)))
+    }
+    #[cfg(not(all(feature = "can", target_os = "linux")))]
+    {
+        let _ = (state, channel_id, request);
+        Err(AppError::bad_request(
+            "comsrv was built without CAN support",
+        ))
+    }
+}
@@ -0,0 +1,184 @@
+//! Command Webhook Handlers
+//!
+//! Registers per-client webhooks that receive control/adjustment results
+//! (see `core::channels::command_webhooks`).
+
+#![allow(clippy::disallowed_methods)] // json! macro used in multiple functions
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::api::routes::AppState;
+use crate::core::channels::{CommandWebhook, CommandWebhooks};
+use crate::dto::{AppError, SuccessResponse};
+use voltage_rtdb::Rtdb;
+
+/// Webhook settings of a client (the client name is taken from the path)
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct CommandWebhookRequest {
+    /// Callback URL (POST, JSON body)
+    pub url: String,
+    /// Sent as `Authorization: Bearer {token}`
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Only these channels (all when absent)
+    #[serde(default)]
+    pub channels: Option<Vec<u32>>,
+    #[serde(default = "common::serde_helpers::bool_true")]
+    pub on_success: bool,
+    #[serde(default = "common::serde_helpers::bool_true")]
+    pub on_failure: bool,
+    /// Also notify commands not submitted by this client
+    #[serde(default)]
+    pub all_commands: bool,
+    #[serde(default)]
+    pub timeout_ms: Option<u32>,
+    #[serde(default = "common::serde_helpers::bool_true")]
+    pub enabled: bool,
+}
+
+/// List command result webhooks
+///
+/// Tokens are not returned.
+///
+/// @route GET /api/command-webhooks
+#[utoipa::path(
+    get,
+    path = "/api/command-webhooks",
+    responses(
+        (status = 200, description = "Registered webhooks", body = serde_json::Value,
+            example = json!({
+                "success": true,
+                "data": [{
+                    "client": "dispatch",
+                    "url": "https://dispatch.example.com/ems/commands",
+                    "channels": [1001, 1002],
+                    "on_success": true,
+                    "on_failure": true,
+                    "all_commands": false,
+                    "timeout_ms": 3000,
+                    "enabled": true
+                }]
+            })
+        )
+    ),
+    tag = "comsrv"
+)]
+pub async fn list_command_webhooks<R: Rtdb>(
+    State(_state): State<AppState<R>>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, AppError> {
+    let webhooks: Vec<CommandWebhook> = CommandWebhooks::global()
+        .list()
+        .await
+        .into_iter()
+        .map(|webhook| CommandWebhook {
+            token: None,
+            ..webhook
+        })
+        .collect();
+
+    let webhooks = serde_json::to_value(webhooks)
+        .map_err(|e| AppError::internal_error(format!("Serialization failed: {}", e)))?;
+    Ok(Json(SuccessResponse::new(webhooks)))
+}
+
+/// Create or replace the webhook of an API client
+///
+/// Control/adjustment writes sent with `X-Client-Id: {client}` are reported
+/// to the URL once the channel has executed them (success or failure,
+/// read-back value and latency).
+///
+/// @route PUT /api/command-webhooks/{client}
+#[utoipa::path(
+    put,
+    path = "/api/command-webhooks/{client}",
+    params(
+        ("client" = String, Path, description = "API client name (X-Client-Id)")
+    ),
+    request_body = CommandWebhookRequest,
+    responses(
+        (status = 200, description = "Webhook saved", body = serde_json::Value,
+            example = json!({
+                "success": true,
+                "data": {"client": "dispatch", "url": "https://dispatch.example.com/ems/commands"}
+            })
+        ),
+        (status = 400, description = "Invalid URL or settings", body = String)
+    ),
+    tag = "comsrv"
+)]
+pub async fn put_command_webhook<R: Rtdb>(
+    State(state): State<AppState<R>>,
+    Path(client): Path<String>,
+    Json(request): Json<CommandWebhookRequest>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, AppError> {
+    let webhook = CommandWebhook {
+        client: client.clone(),
+        url: request.url,
+        token: request.token,
+        channels: request.channels,
+        on_success: request.on_success,
+        on_failure: request.on_failure,
+        all_commands: request.all_commands,
+        timeout_ms: request.timeout_ms.unwrap_or(3000),
+        enabled: request.enabled,
+    };
+    webhook
+        .validate()
+        .map_err(|e| AppError::bad_request(e.to_string()))?;
+
+    let url = webhook.url.clone();
+    CommandWebhooks::global()
+        .upsert(&state.sqlite_pool, webhook)
+        .await
+        .map_err(|e| {
+            tracing::error!("Webhook '{}' save: {}", client, e);
+            AppError::internal_error(format!("Failed to save webhook: {}", e))
+        })?;
+
+    tracing::info!("Command webhook '{}' -> {}", client, url);
+    Ok(Json(SuccessResponse::new(json!({
+        "client": client,
+        "url": url,
+    }))))
+}
+
+/// Remove the webhook of an API client
+///
+/// @route DELETE /api/command-webhooks/{client}
+#[utoipa::path(
+    delete,
+    path = "/api/command-webhooks/{client}",
+    params(
+        ("client" = String, Path, description = "API client name (X-Client-Id)")
+    ),
+    responses(
+        (status = 200, description = "Webhook removed", body = serde_json::Value),
+        (status = 404, description = "Client has no webhook", body = String)
+    ),
+    tag = "comsrv"
+)]
+pub async fn delete_command_webhook<R: Rtdb>(
+    State(state): State<AppState<R>>,
+    Path(client): Path<String>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, AppError> {
+    let removed = CommandWebhooks::global()
+        .remove(&state.sqlite_pool, &client)
+        .await
+        .map_err(|e| AppError::internal_error(format!("Failed to remove webhook: {}", e)))?;
+    if !removed {
+        return Err(AppError::not_found(format!(
+            "Client '{}' has no command webhook",
+            client
+        )));
+    }
+
+    Ok(Json(SuccessResponse::new(json!({
+        "client": client,
+        "removed": true,
+    }))))
+}
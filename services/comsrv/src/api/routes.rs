@@ -20,10 +20,15 @@ use crate::api::{
     handlers::health::*,
     handlers::{
         channel_handlers::*, channel_management_handlers::*, control_handlers::*,
-        mapping_handlers::*, point_handlers::*, protocol_handlers::*,
+        dead_letter_handlers::*, force_handlers::*, mapping_handlers::*, point_handlers::*,
+        protocol_handlers::*, webhook_handlers::*,
     },
 };
-use common::admin_api::{get_log_level, set_log_level};
+use common::admin_api::{
+    get_config_report, get_log_level, list_feature_flags, set_feature_flag, set_log_level,
+};
+use common::auth::{authenticate, TokenVerifier};
+use common::rate_limit::{rate_limit, trusted_proxies_from_env, RateLimiter, RouteClass};
 
 /// Global service start time storage
 static SERVICE_START_TIME: OnceLock<DateTime<Utc>> = OnceLock::new();
@@ -122,6 +127,7 @@ pub type ProductionAppState = AppState<voltage_rtdb::RedisRtdb>;
         // Health and service status
         crate::api::handlers::health::get_service_status,
         crate::api::handlers::health::health_check,
+        crate::api::handlers::health::get_redundancy_status,
 
         // Channel queries and status
         crate::api::handlers::channel_handlers::get_all_channels,
@@ -129,15 +135,34 @@ pub type ProductionAppState = AppState<voltage_rtdb::RedisRtdb>;
         crate::api::handlers::channel_handlers::search_channels,
         crate::api::handlers::channel_handlers::get_channel_detail_handler,
         crate::api::handlers::channel_handlers::get_channel_status,
+        crate::api::handlers::channel_handlers::get_channel_stats_history,
+        crate::api::handlers::channel_handlers::read_all_channel,
+        crate::api::handlers::channel_handlers::get_read_all_job,
         crate::api::handlers::channel_handlers::list_all_points,
 
         // Control operations
         crate::api::handlers::control_handlers::control_channel,
         crate::api::handlers::control_handlers::write_channel_point,  // Unified write endpoint (supports single & batch)
+        crate::api::handlers::control_handlers::get_command_status,
+
+        // Dead letter queue (undeliverable commands)
+        crate::api::handlers::dead_letter_handlers::list_dead_letters,
+        crate::api::handlers::dead_letter_handlers::retry_dead_letter,
+        crate::api::handlers::dead_letter_handlers::discard_dead_letter,
+
+        // Forced points (commissioning)
+        crate::api::handlers::force_handlers::force_point,
+        crate::api::handlers::force_handlers::list_forced_points,
+        crate::api::handlers::force_handlers::release_forced_point,
+        crate::api::handlers::force_handlers::get_force_log,
+        crate::api::handlers::webhook_handlers::list_command_webhooks,
+        crate::api::handlers::webhook_handlers::put_command_webhook,
+        crate::api::handlers::webhook_handlers::delete_command_webhook,
 
         // Point information
         crate::api::handlers::point_handlers::get_point_info_handler,
         crate::api::handlers::point_handlers::get_channel_points_handler,
+        crate::api::handlers::point_handlers::get_point_meta_handler,
         crate::api::handlers::point_handlers::get_unmapped_points_handler,
         crate::api::handlers::point_handlers::get_point_mapping_with_type_handler,
 
@@ -157,14 +182,28 @@ pub type ProductionAppState = AppState<voltage_rtdb::RedisRtdb>;
         crate::api::handlers::channel_management_handlers::delete_channel_handler,
         crate::api::handlers::channel_management_handlers::reload_configuration_handler,
         crate::api::handlers::channel_management_handlers::reload_routing_handler,
+        crate::api::handlers::channel_management_handlers::generate_virtual_twin_handler,
+        crate::api::handlers::channel_management_handlers::export_channel_handler,
+        crate::api::handlers::channel_management_handlers::import_channel_handler,
 
         // Mapping management
         crate::api::handlers::mapping_handlers::get_channel_mappings_handler,
         crate::api::handlers::mapping_handlers::update_channel_mappings_handler,
 
+        // Protocol plugins
+        crate::api::handlers::protocol_handlers::list_protocol_plugins,
+        crate::api::handlers::protocol_handlers::get_protocol_plugin,
+        crate::api::handlers::protocol_handlers::generate_scl_points,
+        crate::api::handlers::protocol_handlers::browse_opcua,
+        crate::api::handlers::protocol_handlers::discover_sunspec,
+        crate::api::handlers::protocol_handlers::reload_can_dbc,
+
         // Admin endpoints
         common::admin_api::set_log_level,
-        common::admin_api::get_log_level
+        common::admin_api::get_log_level,
+        common::admin_api::get_config_report,
+        common::admin_api::list_feature_flags,
+        common::admin_api::set_feature_flag
     ),
     components(
         schemas(
@@ -185,12 +224,19 @@ pub type ProductionAppState = AppState<voltage_rtdb::RedisRtdb>;
             crate::dto::BatchAdjustmentRequest,
             crate::dto::BatchCommandResult,
             crate::dto::BatchCommandError,
+            crate::dto::v2::CommandRequest,
+            crate::dto::v2::Command,
+            crate::dto::v2::CommandResponse,
+            crate::dto::v2::CommandResult,
+            crate::dto::v2::CommandStatus,
             crate::dto::ChannelCreateRequest,
             crate::dto::ChannelConfigUpdateRequest,
             crate::dto::ChannelEnabledRequest,
             crate::dto::ChannelCrudResult,
             crate::dto::ReloadConfigResult,
             crate::dto::RoutingReloadResult,
+            crate::dto::ChannelImportQuery,
+            crate::dto::ChannelImportResult,
             crate::dto::PointDefinition,
             crate::dto::GroupedPoints,
             crate::dto::GroupedMappings,
@@ -211,9 +257,39 @@ pub type ProductionAppState = AppState<voltage_rtdb::RedisRtdb>;
             crate::api::handlers::point_handlers::OperationStats,
             crate::api::handlers::point_handlers::OperationStat,
             crate::api::handlers::point_handlers::PointBatchError,
+            // Command webhook DTOs
+            crate::api::handlers::webhook_handlers::CommandWebhookRequest,
+            crate::core::channels::CommandWebhookEvent,
+            // Redundancy DTOs
+            crate::api::handlers::health::RedundancyStatus,
+            crate::core::channels::NodeStatus,
+            crate::core::channels::Role,
+            // Forced point DTOs
+            crate::api::handlers::force_handlers::ForcePointRequest,
+            crate::core::channels::ForcedPoint,
+            crate::core::channels::ForceEvent,
+            crate::core::channels::ForceAuditEntry,
+            crate::core::channels::ReadJob,
+            crate::core::channels::ReadJobStatus,
+            crate::core::channels::read_jobs::ReadJobValue,
+            crate::core::channels::read_jobs::ReadJobFailure,
+            // Protocol plugin DTOs
+            crate::api::handlers::protocol_handlers::ProtocolPluginInfo,
+            crate::api::handlers::protocol_handlers::ParameterInfo,
+            crate::api::handlers::protocol_handlers::OpcUaBrowseRequest,
+            crate::api::handlers::protocol_handlers::SunSpecDiscoveryRequest,
+            crate::api::handlers::protocol_handlers::DbcReloadRequest,
+            crate::core::plugins::MappingColumn,
+            crate::core::plugins::ColumnKind,
             // Admin schemas
             common::admin_api::SetLogLevelRequest,
-            common::admin_api::LogLevelResponse
+            common::admin_api::LogLevelResponse,
+            common::admin_api::SetFeatureFlagRequest,
+            common::feature_flags::FlagStatus,
+            common::feature_flags::FlagSource,
+            common::config_consistency::ConsistencyReport,
+            common::config_consistency::ConsistencyIssue,
+            common::config_consistency::ConsistencySeverity
         )
     ),
     tags(
@@ -253,10 +329,17 @@ pub fn create_api_routes_generic<R: Rtdb>(
     Router::new()
         // Health check (top-level for monitoring systems)
         .route("/health", get(health_check))
+        .route("/health/config", get(get_config_report))
         // Service management
         .route("/api/status", get(get_service_status))
+        .route("/api/redundancy", get(get_redundancy_status))
         // Protocol discovery
         .route("/api/protocols", get(list_protocols))
+        .route("/api/protocols/plugins", get(list_protocol_plugins))
+        .route("/api/protocols/plugins/{name}", get(get_protocol_plugin))
+        .route("/api/protocols/iec61850/scl", post(generate_scl_points))
+        .route("/api/protocols/opcua/browse", post(browse_opcua))
+        .route("/api/protocols/modbus/sunspec", post(discover_sunspec))
         // Channel management (CRUD)
         .route("/api/channels", get(get_all_channels).post(create_channel_handler))
         .route("/api/channels/list", get(list_channels))
@@ -264,11 +347,28 @@ pub fn create_api_routes_generic<R: Rtdb>(
         .route("/api/points", get(list_all_points))
         .route("/api/channels/{id}", get(get_channel_detail_handler).put(update_channel_handler).delete(delete_channel_handler))
         .route("/api/channels/{id}/status", get(get_channel_status))
+        .route("/api/channels/{id}/stats/history", get(get_channel_stats_history))
+        .route("/api/channels/{id}/read-all", post(read_all_channel))
+        .route("/api/channels/{id}/read-all/{job_id}", get(get_read_all_job))
         .route("/api/channels/{id}/control", post(control_channel))
+        .route("/api/channels/{id}/commands/{command_id}", get(get_command_status))
+        .route("/api/channels/{id}/dead-letters", get(list_dead_letters))
+        .route("/api/channels/{id}/dead-letters/{entry_id}", axum::routing::delete(discard_dead_letter))
+        .route("/api/channels/{id}/dead-letters/{entry_id}/retry", post(retry_dead_letter))
+        .route("/api/channels/{id}/force", get(list_forced_points).post(force_point))
+        .route("/api/channels/{id}/force/log", get(get_force_log))
+        .route("/api/channels/{id}/can/dbc", post(reload_can_dbc))
+        .route("/api/channels/{id}/force/{point_type}/{point_id}", axum::routing::delete(release_forced_point))
+        .route("/api/command-webhooks", get(list_command_webhooks))
+        .route("/api/command-webhooks/{client}", axum::routing::put(put_command_webhook).delete(delete_command_webhook))
         .route("/api/channels/{id}/enabled", axum::routing::put(set_channel_enabled_handler))
         .route("/api/channels/{id}/points", get(get_channel_points_handler))
+        .route("/api/channels/{id}/points/{point_id}/meta", get(get_point_meta_handler))
         .route("/api/channels/{id}/unmapped-points", get(get_unmapped_points_handler))
         .route("/api/channels/{id}/mappings", get(get_channel_mappings_handler).put(update_channel_mappings_handler))
+        .route("/api/channels/{id}/virtual-twin", post(generate_virtual_twin_handler))
+        .route("/api/channels/{id}/export", get(export_channel_handler))
+        .route("/api/channels/import", post(import_channel_handler))
         .route("/api/channels/{channel_id}/{type}/points/{point_id}/mapping", get(get_point_mapping_with_type_handler))
         .route("/api/channels/reload", post(reload_configuration_handler))
         .route("/api/routing/reload", post(reload_routing_handler))
@@ -306,11 +406,41 @@ pub fn create_api_routes_generic<R: Rtdb>(
             "/api/admin/logs/level",
             get(get_log_level).post(set_log_level),
         )
+        .route("/api/admin/features", get(list_feature_flags))
+        .route("/api/admin/features/{name}", axum::routing::put(set_feature_flag))
         // CRITICAL: Apply middleware BEFORE .with_state() for it to work
+        // Bearer tokens from apigateway (JWT_SECRET_KEY) carry the role
+        // checked against point write scopes
+        // Limits are keyed by the authenticated caller, so the limiter runs
+        // inside the authenticate layer
+        .layer(axum::middleware::from_fn_with_state(
+            api_rate_limiter(),
+            rate_limit,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            TokenVerifier::from_env().map(Arc::new),
+            authenticate,
+        ))
         .layer(axum::middleware::from_fn(common::logging::http_request_logger))
         .with_state(state)
 }
 
+/// Per-client limits of the public API (by authenticated user, else client IP)
+fn api_rate_limiter() -> Arc<RateLimiter> {
+    Arc::new(
+        RateLimiter::new(vec![
+            RouteClass::new("control", 20, 10.0)
+                .post("/api/channels/*/control")
+                .post("/api/channels/*/write")
+                .post("/api/channels/*/force")
+                .post("/api/channels/*/dead-letters/*/retry"),
+            // Each read-all is a full device poll on top of the regular cycle
+            RouteClass::new("read_all", 5, 0.5).post("/api/channels/*/read-all"),
+        ])
+        .trust_proxies(trusted_proxies_from_env()),
+    )
+}
+
 // NOTE: These tests are temporarily disabled during AFIT migration.
 // The handlers use ProductionAppState (hardcoded to RedisRtdb), but tests use MemoryRtdb.
 // TODO: Either genericize handlers or convert these to integration tests with Redis.
@@ -566,6 +566,201 @@ async fn test_grouped_mappings_unfiltered() {
     assert_eq!(v["data"]["adjustment"].as_array().unwrap().len(), 1);
 }
 
+#[tokio::test]
+async fn test_point_meta_merges_four_remote_rows() {
+    let channel_manager = Arc::new(ChannelManager::new(
+        crate::test_utils::create_test_rtdb(),
+        crate::test_utils::create_test_routing_cache(),
+    ));
+    let pool = create_test_sqlite_pool_with_points().await;
+
+    sqlx::query("INSERT INTO channels (channel_id, name, protocol, enabled, config) VALUES (9003, 'Ch9003', 'modbus_tcp', 1, '{}')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO telemetry_points (channel_id, point_id, signal_name, scale, offset, unit, reverse, data_type, description, protocol_mappings) VALUES (9003, 1, 'DC_Voltage', 0.1, 0.0, 'V', 0, 'float32', '', ?)")
+        .bind(r#"{"slave_id":1,"function_code":3,"register_address":100,"data_type":"float32","byte_order":"ABCD"}"#)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO control_points (channel_id, point_id, signal_name, unit, data_type, description, protocol_mappings) VALUES (9003, 1, 'Start', '', 'bool', '', null)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let app = create_test_api_with_pool(channel_manager, pool).await;
+    use http_body_util::BodyExt as _;
+
+    let req = Request::builder()
+        .uri("/api/channels/9003/points/1/meta")
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let v: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    let points = v["data"]["points"].as_array().unwrap();
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[0]["point_type"], "T");
+    assert_eq!(points[0]["scaling"]["scale"], 0.1);
+    assert_eq!(points[0]["protocol_mapping"]["register_address"], 100);
+    assert_eq!(
+        points[0]["source"],
+        "slave 1, FC3, register 100, float32 ABCD"
+    );
+    assert!(points[1]["source"].is_null());
+
+    let req = Request::builder()
+        .uri("/api/channels/9003/points/1/meta?type=C")
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.clone().oneshot(req).await.unwrap();
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let v: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(v["data"]["points"].as_array().unwrap().len(), 1);
+
+    let req = Request::builder()
+        .uri("/api/channels/9003/points/2/meta")
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_write_enforces_point_access_scopes() {
+    let channel_manager = Arc::new(ChannelManager::new(
+        crate::test_utils::create_test_rtdb(),
+        crate::test_utils::create_test_routing_cache(),
+    ));
+    let pool = create_test_sqlite_pool_with_points().await;
+
+    sqlx::query("INSERT INTO channels (channel_id, name, protocol, enabled, config) VALUES (9004, 'Ch9004', 'virtual', 0, '{}')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    for (point_id, access) in [(1, None), (2, Some("operator")), (3, Some("read_only"))] {
+        sqlx::query("INSERT INTO control_points (channel_id, point_id, signal_name, access) VALUES (9004, ?, 'Breaker', ?)")
+            .bind(point_id)
+            .bind(access)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    let app = create_test_api_with_pool(channel_manager, pool).await;
+    use http_body_util::BodyExt as _;
+    let write = |body: serde_json::Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/channels/9004/write")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let resp = app
+        .clone()
+        .oneshot(write(json!({"type": "C", "id": "1", "value": 1.0})))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // No bearer token: anonymous callers hold no role
+    let resp = app
+        .clone()
+        .oneshot(write(json!({"type": "C", "id": "2", "value": 1.0})))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let v: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(
+        v["error"]["message"],
+        "Point C:2 on channel 9004 requires role 'operator' or higher"
+    );
+
+    let resp = app
+        .clone()
+        .oneshot(write(json!({"type": "C", "id": "3", "value": 1.0})))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    // One scoped point refuses the whole batch
+    let resp = app
+        .oneshot(write(json!({
+            "type": "C",
+            "points": [{"id": "1", "value": 1.0}, {"id": "2", "value": 0.0}]
+        })))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_dead_letter_retry_enforces_point_access_scopes() {
+    use crate::core::channels::{DeadLetterEntry, DeadLetterQueue};
+
+    let channel_manager = Arc::new(ChannelManager::new(
+        crate::test_utils::create_test_rtdb(),
+        crate::test_utils::create_test_routing_cache(),
+    ));
+    let pool = create_test_sqlite_pool_with_points().await;
+    sqlx::query("INSERT INTO channels (channel_id, name, protocol, enabled, config) VALUES (9005, 'Ch9005', 'virtual', 0, '{}')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    for (point_id, access) in [(1, None), (2, Some("read_only"))] {
+        sqlx::query("INSERT INTO control_points (channel_id, point_id, signal_name, access) VALUES (9005, ?, 'Breaker', ?)")
+            .bind(point_id)
+            .bind(access)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    let rtdb = Arc::new(MemoryRtdb::new());
+    let mut queue = DeadLetterQueue::new(Arc::clone(&rtdb), 9005);
+    for point_id in [1, 2] {
+        queue
+            .push(&DeadLetterEntry {
+                id: format!("dl-{}", point_id),
+                channel_id: 9005,
+                command_id: format!("cmd-{}", point_id),
+                point_type: "C".to_string(),
+                point_id,
+                value: 1.0,
+                command_timestamp: 0,
+                failed_at: chrono::Utc::now().timestamp_millis(),
+                reason: "write rejected".to_string(),
+            })
+            .await
+            .unwrap();
+    }
+
+    let (app, _) = create_test_api_with_pool_rtdb_and_instance(channel_manager, pool, rtdb).await;
+    let retry = |entry_id: &str| {
+        Request::builder()
+            .method("POST")
+            .uri(format!(
+                "/api/channels/9005/dead-letters/{}/retry",
+                entry_id
+            ))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // A read-only point cannot be written through the retry either
+    let resp = app.clone().oneshot(retry("dl-2")).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    assert!(queue.get("dl-2").await.unwrap().is_some());
+
+    let resp = app.oneshot(retry("dl-1")).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(queue.get("dl-1").await.unwrap().is_none());
+}
+
 #[tokio::test]
 async fn test_channel_detail_returns_description() {
     let channel_manager = Arc::new(ChannelManager::new(
@@ -868,6 +1063,100 @@ async fn test_update_mappings_invalid_function_code_for_t_400() {
     assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
 }
 
+#[tokio::test]
+async fn test_channel_export_import_round_trip() {
+    let channel_manager = Arc::new(ChannelManager::new(
+        crate::test_utils::create_test_rtdb(),
+        crate::test_utils::create_test_routing_cache(),
+    ));
+    let pool = create_test_sqlite_pool_with_points().await;
+    sqlx::query("INSERT INTO channels (channel_id, name, protocol, enabled, config) VALUES (8010, 'PCS', 'modbus_tcp', 1, '{\"parameters\":{\"host\":\"10.0.0.5\",\"port\":502}}')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO telemetry_points (channel_id, point_id, signal_name, scale, offset, unit, reverse, data_type, description, protocol_mappings) VALUES (8010, 1, 'Power', 0.1, 0.0, 'kW', 0, 'float32', 'Active power', '{\"slave_id\":1,\"function_code\":3,\"register_address\":100,\"data_type\":\"float32\",\"byte_order\":\"ABCD\"}')")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let app = create_test_api_with_pool(channel_manager, pool.clone()).await;
+    let resp = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/channels/8010/export")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["content-type"], "application/zip");
+    let archive = axum::body::to_bytes(resp.into_body(), usize::MAX)
+        .await
+        .unwrap();
+
+    // Same ID and name conflict with the source channel
+    let import = |query: &str| {
+        Request::builder()
+            .uri(format!("/api/channels/import{}", query))
+            .method("POST")
+            .header("content-type", "application/zip")
+            .body(Body::from(archive.clone()))
+            .unwrap()
+    };
+    let resp = app.clone().oneshot(import("")).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+    let resp = app
+        .clone()
+        .oneshot(import("?channel_id=8011&name=PCS-B&slave_id=2"))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"]["id"], 8011);
+    assert_eq!(json["data"]["points"]["telemetry"], 1);
+    assert_eq!(json["data"]["runtime_status"], "stopped");
+
+    let (config, enabled): (String, bool) =
+        sqlx::query_as("SELECT config, enabled FROM channels WHERE channel_id = 8011")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(config.contains("10.0.0.5"));
+    assert!(!enabled);
+    let mapping: String = sqlx::query_scalar(
+        "SELECT protocol_mappings FROM telemetry_points WHERE channel_id = 8011 AND point_id = 1",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    let mapping: serde_json::Value = serde_json::from_str(&mapping).unwrap();
+    assert_eq!(mapping["slave_id"], 2);
+    assert_eq!(mapping["register_address"], 100);
+}
+
+#[tokio::test]
+async fn test_channel_import_rejects_invalid_archive() {
+    let channel_manager = Arc::new(ChannelManager::new(
+        crate::test_utils::create_test_rtdb(),
+        crate::test_utils::create_test_routing_cache(),
+    ));
+    let app = create_test_api_routes(channel_manager).await;
+    let req = Request::builder()
+        .uri("/api/channels/import")
+        .method("POST")
+        .header("content-type", "application/zip")
+        .body(Body::from("not a zip"))
+        .unwrap();
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
 #[tokio::test]
 async fn test_reload_configuration_disabled_channel_adds_without_runtime() {
     // Build sqlite with channels table only and a disabled channel
@@ -2903,3 +3192,142 @@ async fn test_write_response_format_batch() {
     assert!(json["data"]["failed"].is_number());
     assert!(json["data"]["errors"].is_array());
 }
+
+#[tokio::test]
+async fn test_write_versioned_schema() {
+    let (app, rtdb) = setup_write_test_env().await;
+    let write = |version: Option<&str>, body: serde_json::Value| {
+        let mut builder = Request::builder()
+            .uri("/api/channels/1005/write")
+            .method("POST")
+            .header("content-type", "application/json");
+        if let Some(version) = version {
+            builder = builder.header("X-Api-Version", version);
+        }
+        builder.body(Body::from(body.to_string())).unwrap()
+    };
+
+    // No header: v1, echoed on the response
+    let resp = app
+        .clone()
+        .oneshot(write(None, json!({"type": "C", "id": "10", "value": 1.0})))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["x-api-version"], "1");
+
+    let resp = app
+        .clone()
+        .oneshot(write(
+            Some("v2"),
+            json!({
+                "point_type": "A",
+                "commands": [
+                    {"point_id": 200, "value": 10.0},
+                    {"point_id": 201, "value": 20.0, "command_id": "ems-42", "priority": 9}
+                ]
+            }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["x-api-version"], "2");
+    let json = extract_write_response_json(resp).await;
+    assert_eq!(json["data"]["succeeded"], 2);
+    let results = json["data"]["results"].as_array().unwrap();
+    assert_eq!(results[0]["point_id"], 200);
+    assert!(results[0]["command_id"]
+        .as_str()
+        .unwrap()
+        .starts_with("api_1005_"));
+    assert_eq!(results[1]["command_id"], "ems-42");
+    assert_eq!(results[1]["status"], "accepted");
+
+    // Queued highest priority first, with the caller's command ID
+    let todo = rtdb.list_range("comsrv:1005:A:TODO", 0, -1).await.unwrap();
+    let first: serde_json::Value = serde_json::from_slice(&todo[0]).unwrap();
+    assert_eq!(first["point_id"], 201);
+    assert_eq!(first["command_id"], "ems-42");
+
+    // v1 body under v2 and an unknown version are refused
+    for (version, body) in [
+        ("2", json!({"type": "C", "id": "10", "value": 1.0})),
+        ("3", json!({"point_type": "C", "commands": []})),
+    ] {
+        let resp = app
+            .clone()
+            .oneshot(write(Some(version), body))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+// ========================================================================
+// Read-all jobs
+// ========================================================================
+
+#[tokio::test]
+async fn test_read_all_job_completes() {
+    let sqlite_pool = create_test_sqlite_pool().await;
+    let channel_manager = Arc::new(ChannelManager::with_sqlite_pool(
+        crate::test_utils::create_test_rtdb(),
+        crate::test_utils::create_test_routing_cache(),
+        sqlite_pool.clone(),
+    ));
+    let config: crate::core::config::ChannelConfig =
+        serde_json::from_value(json!({"id": 1001, "name": "sim", "protocol": "virtual"})).unwrap();
+    channel_manager
+        .create_channel(Arc::new(config))
+        .await
+        .unwrap();
+    let app = create_test_api_with_pool(channel_manager, sqlite_pool).await;
+
+    let post = |uri: &str| {
+        Request::builder()
+            .uri(uri)
+            .method("POST")
+            .body(Body::empty())
+            .unwrap()
+    };
+    let response = app
+        .clone()
+        .oneshot(post("/api/channels/9999/read-all"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = app
+        .clone()
+        .oneshot(post("/api/channels/1001/read-all"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = extract_json(response).await;
+    let job_id = body["data"]["job_id"].as_str().unwrap().to_string();
+    assert_json_field(&body, "/data/channel_id", json!(1001));
+
+    let mut status = String::new();
+    for _ in 0..50 {
+        let request = Request::builder()
+            .uri(format!("/api/channels/1001/read-all/{}", job_id))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = extract_json(response).await;
+        status = body["data"]["status"].as_str().unwrap().to_string();
+        if status == "completed" || status == "failed" {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(status, "completed");
+
+    let request = Request::builder()
+        .uri("/api/channels/1002/read-all/".to_string() + &job_id)
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,622 @@
+//! Portable channel configuration archive
+//!
+//! Packs one channel (core fields, protocol parameters, the four point tables
+//! and their protocol mappings) into a zip so a proven setup can be copied to
+//! another site. The layout mirrors the `comsrv/{channel_id}/` config
+//! directory:
+//!
+//! ```text
+//! channel.yaml                      id, name, description, protocol, enabled, logging
+//! parameters.yaml                   protocol parameters (host, port, device, ...)
+//! telemetry.csv  signal.csv  control.csv  adjustment.csv
+//! mapping/telemetry_mapping.csv  mapping/signal_mapping.csv
+//! mapping/control_mapping.csv    mapping/adjustment_mapping.csv
+//! ```
+//!
+//! Protocol parameters live in their own file because they are the part that
+//! usually differs between sites (IP address, serial device).
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{Cursor, Read, Write};
+use voltage_model::PointType;
+
+use crate::core::config::{ChannelConfig, ChannelCore, ChannelLoggingConfig};
+use crate::error::{ComSrvError, Result};
+
+/// Archive format written by this version
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+/// Upper bound for a single uncompressed archive entry
+const MAX_ENTRY_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Point types with their file label and SQLite table
+pub const POINT_TABLES: [(PointType, &str, &str); 4] = [
+    (PointType::Telemetry, "telemetry", "telemetry_points"),
+    (PointType::Signal, "signal", "signal_points"),
+    (PointType::Control, "control", "control_points"),
+    (PointType::Adjustment, "adjustment", "adjustment_points"),
+];
+
+const POINT_HEADER: [&str; 8] = [
+    "point_id",
+    "signal_name",
+    "scale",
+    "offset",
+    "unit",
+    "reverse",
+    "data_type",
+    "description",
+];
+
+/// Preferred column order of mapping CSVs; other keys follow alphabetically
+const MAPPING_COLUMN_ORDER: [&str; 13] = [
+    "slave_id",
+    "function_code",
+    "register_address",
+    "data_type",
+    "byte_order",
+    "bit_position",
+    "gpio_number",
+    "can_id",
+    "expression",
+    "waveform",
+    "update_interval",
+    "initial_value",
+    "noise_range",
+];
+
+/// Mapping fields that stay text even when they look like numbers
+const TEXT_MAPPING_FIELDS: [&str; 10] = [
+    "data_type",
+    "byte_order",
+    "expression",
+    "msg_name",
+    "signal_name",
+    "unit",
+    "service_name",
+    "method_name",
+    "field_path",
+    "gpio_chip",
+];
+
+/// `channel.yaml` contents
+#[derive(Serialize, Deserialize)]
+struct ChannelDocument {
+    #[serde(default = "default_format_version")]
+    format_version: u32,
+    #[serde(flatten)]
+    core: ChannelCore,
+    #[serde(default)]
+    logging: ChannelLoggingConfig,
+}
+
+fn default_format_version() -> u32 {
+    ARCHIVE_FORMAT_VERSION
+}
+
+/// Point row of an archive (same columns for all four types)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchivedPoint {
+    pub point_id: u32,
+    pub signal_name: String,
+    pub scale: f64,
+    pub offset: f64,
+    pub unit: Option<String>,
+    pub reverse: bool,
+    pub data_type: Option<String>,
+    pub description: Option<String>,
+    /// Protocol mapping (`protocol_mappings` column), `None` when unmapped
+    pub mapping: Option<Map<String, Value>>,
+}
+
+/// CSV row as read from a point file
+#[derive(Deserialize)]
+struct PointRow {
+    point_id: u32,
+    signal_name: String,
+    scale: Option<f64>,
+    offset: Option<f64>,
+    unit: Option<String>,
+    reverse: Option<String>,
+    data_type: Option<String>,
+    description: Option<String>,
+}
+
+/// ID remapping applied on import
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveRemap {
+    /// New channel ID
+    pub channel_id: Option<u32>,
+    /// New channel name
+    pub name: Option<String>,
+    /// Replace `slave_id` in all mappings that have one
+    pub slave_id: Option<u8>,
+    /// Override the enabled flag
+    pub enabled: Option<bool>,
+}
+
+/// In-memory form of a channel archive
+#[derive(Debug, Clone)]
+pub struct ChannelArchive {
+    pub channel: ChannelConfig,
+    pub points: HashMap<PointType, Vec<ArchivedPoint>>,
+}
+
+impl ChannelArchive {
+    /// Points of one type (empty when the type has none)
+    pub fn points(&self, point_type: PointType) -> &[ArchivedPoint] {
+        self.points.get(&point_type).map_or(&[], Vec::as_slice)
+    }
+
+    /// Write the archive as zip bytes
+    pub fn to_zip(&self) -> Result<Vec<u8>> {
+        use zip::write::SimpleFileOptions;
+
+        let zip_error = |e: zip::result::ZipError| ComSrvError::IoError(e.to_string());
+        let options =
+            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let mut add = |name: &str, content: String| -> Result<()> {
+            writer.start_file(name, options).map_err(zip_error)?;
+            writer
+                .write_all(content.as_bytes())
+                .map_err(|e| ComSrvError::IoError(e.to_string()))
+        };
+
+        let document = ChannelDocument {
+            format_version: ARCHIVE_FORMAT_VERSION,
+            core: self.channel.core.clone(),
+            logging: self.channel.logging.clone(),
+        };
+        add("channel.yaml", to_yaml(&document)?)?;
+        let parameters: BTreeMap<_, _> = self.channel.parameters.iter().collect();
+        add("parameters.yaml", to_yaml(&parameters)?)?;
+
+        for (point_type, label, _) in POINT_TABLES {
+            let points = self.points(point_type);
+            add(&format!("{}.csv", label), points_csv(points)?)?;
+            add(
+                &format!("mapping/{}_mapping.csv", label),
+                mappings_csv(points)?,
+            )?;
+        }
+
+        let cursor = writer.finish().map_err(zip_error)?;
+        Ok(cursor.into_inner())
+    }
+
+    /// Read an archive from zip bytes
+    ///
+    /// Missing point or mapping files count as empty; `channel.yaml` is required.
+    pub fn from_zip(bytes: &[u8]) -> Result<Self> {
+        let mut zip = zip::ZipArchive::new(Cursor::new(bytes))
+            .map_err(|e| ComSrvError::DataError(format!("Invalid zip archive: {}", e)))?;
+        let files = read_entries(&mut zip)?;
+
+        let channel_yaml = files
+            .get("channel.yaml")
+            .ok_or_else(|| ComSrvError::DataError("Archive has no channel.yaml".to_string()))?;
+        let document: ChannelDocument = serde_yaml::from_str(channel_yaml)
+            .map_err(|e| ComSrvError::DataError(format!("channel.yaml: {}", e)))?;
+        if document.format_version > ARCHIVE_FORMAT_VERSION {
+            return Err(ComSrvError::DataError(format!(
+                "Archive format {} is newer than supported format {}",
+                document.format_version, ARCHIVE_FORMAT_VERSION
+            )));
+        }
+        let parameters = match files.get("parameters.yaml") {
+            Some(text) if !text.trim().is_empty() => serde_yaml::from_str(text)
+                .map_err(|e| ComSrvError::DataError(format!("parameters.yaml: {}", e)))?,
+            _ => HashMap::new(),
+        };
+
+        let mut points = HashMap::new();
+        for (point_type, label, _) in POINT_TABLES {
+            let file = format!("{}.csv", label);
+            let mut rows = match files.get(&file) {
+                Some(text) => parse_points(&file, text)?,
+                None => Vec::new(),
+            };
+            let mapping_file = format!("mapping/{}_mapping.csv", label);
+            if let Some(text) = files.get(&mapping_file) {
+                let mut mappings = parse_mappings(&mapping_file, text)?;
+                for point in &mut rows {
+                    point.mapping = mappings.remove(&point.point_id);
+                }
+                if let Some(orphan) = mappings.keys().next() {
+                    return Err(ComSrvError::DataError(format!(
+                        "{}: point {} has a mapping but no entry in {}",
+                        mapping_file, orphan, file
+                    )));
+                }
+            }
+            points.insert(point_type, rows);
+        }
+
+        Ok(Self {
+            channel: ChannelConfig {
+                core: document.core,
+                parameters,
+                logging: document.logging,
+            },
+            points,
+        })
+    }
+
+    /// Apply import-time ID remapping
+    pub fn remap(&mut self, remap: &ArchiveRemap) {
+        if let Some(id) = remap.channel_id {
+            self.channel.core.id = id;
+        }
+        if let Some(name) = &remap.name {
+            self.channel.core.name = name.clone();
+        }
+        if let Some(enabled) = remap.enabled {
+            self.channel.core.enabled = enabled;
+        }
+        if let Some(slave_id) = remap.slave_id {
+            for point in self.points.values_mut().flatten() {
+                if let Some(value) = point.mapping.as_mut().and_then(|m| m.get_mut("slave_id")) {
+                    *value = Value::from(slave_id);
+                }
+            }
+        }
+    }
+
+    /// Structural checks independent of the target database
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let core = &self.channel.core;
+        if core.id == 0 {
+            errors.push("Channel ID must be greater than 0".to_string());
+        }
+        if core.name.trim().is_empty() {
+            errors.push("Channel name must not be empty".to_string());
+        }
+        if core.protocol.trim().is_empty() {
+            errors.push("Channel protocol must not be empty".to_string());
+        }
+
+        for (point_type, label, _) in POINT_TABLES {
+            let mut seen = BTreeSet::new();
+            for point in self.points(point_type) {
+                if !seen.insert(point.point_id) {
+                    errors.push(format!("{}: duplicate point_id {}", label, point.point_id));
+                }
+                if point.signal_name.trim().is_empty() {
+                    errors.push(format!(
+                        "{}: point {} has an empty signal_name",
+                        label, point.point_id
+                    ));
+                }
+                if !point.scale.is_finite() || !point.offset.is_finite() {
+                    errors.push(format!(
+                        "{}: point {} has a non-finite scale or offset",
+                        label, point.point_id
+                    ));
+                }
+            }
+        }
+        errors
+    }
+}
+
+fn to_yaml<T: Serialize>(value: &T) -> Result<String> {
+    serde_yaml::to_string(value).map_err(|e| ComSrvError::DataError(e.to_string()))
+}
+
+/// Read all file entries, rejecting oversized ones
+fn read_entries(zip: &mut zip::ZipArchive<Cursor<&[u8]>>) -> Result<HashMap<String, String>> {
+    let mut files = HashMap::new();
+    for index in 0..zip.len() {
+        let entry = zip
+            .by_index(index)
+            .map_err(|e| ComSrvError::DataError(format!("Invalid zip entry: {}", e)))?;
+        if entry.is_dir() {
+            continue;
+        }
+        // Tolerate archives re-packed with a top-level folder
+        let name = entry.name().replace('\\', "/");
+        let name = match name.split_once('/') {
+            Some((_, rest)) if !name.starts_with("mapping/") && rest.contains('.') => {
+                rest.to_string()
+            },
+            _ => name,
+        };
+
+        let mut content = String::new();
+        entry
+            .take(MAX_ENTRY_SIZE + 1)
+            .read_to_string(&mut content)
+            .map_err(|e| ComSrvError::DataError(format!("{}: {}", name, e)))?;
+        if content.len() as u64 > MAX_ENTRY_SIZE {
+            return Err(ComSrvError::DataError(format!(
+                "{}: exceeds {} bytes",
+                name, MAX_ENTRY_SIZE
+            )));
+        }
+        // Spreadsheet tools like to add a BOM
+        let content = content.trim_start_matches('\u{feff}').to_string();
+        files.insert(name, content);
+    }
+    Ok(files)
+}
+
+fn csv_error(file: &str) -> impl Fn(csv::Error) -> ComSrvError + '_ {
+    move |e| ComSrvError::DataError(format!("{}: {}", file, e))
+}
+
+fn csv_string(writer: csv::Writer<Vec<u8>>) -> Result<String> {
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| ComSrvError::DataError(e.to_string()))?;
+    String::from_utf8(bytes).map_err(|e| ComSrvError::DataError(e.to_string()))
+}
+
+fn points_csv(points: &[ArchivedPoint]) -> Result<String> {
+    let to_error = |e: csv::Error| ComSrvError::DataError(e.to_string());
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(POINT_HEADER).map_err(to_error)?;
+    for point in points {
+        writer
+            .write_record([
+                point.point_id.to_string(),
+                point.signal_name.clone(),
+                point.scale.to_string(),
+                point.offset.to_string(),
+                point.unit.clone().unwrap_or_default(),
+                point.reverse.to_string(),
+                point.data_type.clone().unwrap_or_default(),
+                point.description.clone().unwrap_or_default(),
+            ])
+            .map_err(to_error)?;
+    }
+    csv_string(writer)
+}
+
+fn mappings_csv(points: &[ArchivedPoint]) -> Result<String> {
+    let to_error = |e: csv::Error| ComSrvError::DataError(e.to_string());
+    let keys: BTreeSet<&str> = points
+        .iter()
+        .filter_map(|p| p.mapping.as_ref())
+        .flat_map(|m| m.keys().map(String::as_str))
+        .filter(|k| *k != "point_id")
+        .collect();
+    let mut columns: Vec<&str> = MAPPING_COLUMN_ORDER
+        .into_iter()
+        .filter(|k| keys.contains(k))
+        .collect();
+    columns.extend(keys.iter().filter(|k| !MAPPING_COLUMN_ORDER.contains(k)));
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(std::iter::once("point_id").chain(columns.iter().copied()))
+        .map_err(to_error)?;
+    for point in points {
+        let Some(mapping) = &point.mapping else {
+            continue;
+        };
+        let cells = columns.iter().map(|k| match mapping.get(*k) {
+            None | Some(Value::Null) => String::new(),
+            Some(Value::String(s)) => s.clone(),
+            Some(other) => other.to_string(),
+        });
+        writer
+            .write_record(std::iter::once(point.point_id.to_string()).chain(cells))
+            .map_err(to_error)?;
+    }
+    csv_string(writer)
+}
+
+fn parse_points(file: &str, text: &str) -> Result<Vec<ArchivedPoint>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+    let mut points = Vec::new();
+    for row in reader.deserialize::<PointRow>() {
+        let row = row.map_err(csv_error(file))?;
+        let reverse = match row.reverse.as_deref().map(str::to_ascii_lowercase) {
+            None => false,
+            Some(v) if v == "true" || v == "1" => true,
+            Some(v) if v == "false" || v == "0" => false,
+            Some(v) => {
+                return Err(ComSrvError::DataError(format!(
+                    "{}: point {} has invalid reverse '{}'",
+                    file, row.point_id, v
+                )))
+            },
+        };
+        points.push(ArchivedPoint {
+            point_id: row.point_id,
+            signal_name: row.signal_name,
+            scale: row.scale.unwrap_or(1.0),
+            offset: row.offset.unwrap_or(0.0),
+            unit: row.unit,
+            reverse,
+            data_type: row.data_type,
+            description: row.description,
+            mapping: None,
+        });
+    }
+    Ok(points)
+}
+
+fn parse_mappings(file: &str, text: &str) -> Result<BTreeMap<u32, Map<String, Value>>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+    let headers = reader.headers().map_err(csv_error(file))?.clone();
+    let id_column = headers
+        .iter()
+        .position(|h| h == "point_id")
+        .ok_or_else(|| ComSrvError::DataError(format!("{}: missing point_id column", file)))?;
+
+    let mut mappings = BTreeMap::new();
+    for record in reader.records() {
+        let record = record.map_err(csv_error(file))?;
+        let raw_id = record.get(id_column).unwrap_or_default();
+        let point_id: u32 = raw_id.parse().map_err(|_| {
+            ComSrvError::DataError(format!("{}: invalid point_id '{}'", file, raw_id))
+        })?;
+        let mapping: Map<String, Value> = headers
+            .iter()
+            .zip(record.iter())
+            .filter(|(key, cell)| *key != "point_id" && !cell.is_empty())
+            .map(|(key, cell)| (key.to_string(), mapping_value(key, cell)))
+            .collect();
+        if mappings.insert(point_id, mapping).is_some() {
+            return Err(ComSrvError::DataError(format!(
+                "{}: duplicate mapping for point {}",
+                file, point_id
+            )));
+        }
+    }
+    Ok(mappings)
+}
+
+/// Restore the JSON type of a mapping cell
+fn mapping_value(key: &str, cell: &str) -> Value {
+    if TEXT_MAPPING_FIELDS.contains(&key) {
+        return Value::String(cell.to_string());
+    }
+    if let Ok(n) = cell.parse::<i64>() {
+        return Value::Number(n.into());
+    }
+    if let Some(n) = cell.parse::<f64>().ok().and_then(Number::from_f64) {
+        return Value::Number(n);
+    }
+    match cell {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => Value::String(cell.to_string()),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn point(point_id: u32, name: &str, mapping: Value) -> ArchivedPoint {
+        ArchivedPoint {
+            point_id,
+            signal_name: name.to_string(),
+            scale: 0.1,
+            offset: 0.0,
+            unit: Some("kW".to_string()),
+            reverse: false,
+            data_type: Some("float32".to_string()),
+            description: None,
+            mapping: mapping.as_object().cloned(),
+        }
+    }
+
+    fn archive() -> ChannelArchive {
+        let mut parameters = HashMap::new();
+        parameters.insert("host".to_string(), json!("192.168.1.10"));
+        parameters.insert("port".to_string(), json!(502));
+        let mut points = HashMap::new();
+        points.insert(
+            PointType::Telemetry,
+            vec![
+                point(
+                    1,
+                    "Power",
+                    json!({"slave_id": 1, "function_code": 3, "register_address": 100,
+                           "data_type": "float32", "byte_order": "ABCD", "bit_position": 0}),
+                ),
+                point(2, "Unmapped", Value::Null),
+            ],
+        );
+        points.insert(
+            PointType::Control,
+            vec![point(
+                5,
+                "Start",
+                json!({"slave_id": 1, "function_code": 5, "register_address": 10,
+                       "data_type": "bool", "byte_order": "AB", "bit_position": 0}),
+            )],
+        );
+        ChannelArchive {
+            channel: ChannelConfig {
+                core: ChannelCore {
+                    id: 3,
+                    name: "PCS".to_string(),
+                    description: Some("PCS main".to_string()),
+                    protocol: "modbus_tcp".to_string(),
+                    enabled: true,
+                },
+                parameters,
+                logging: ChannelLoggingConfig::default(),
+            },
+            points,
+        }
+    }
+
+    #[test]
+    fn test_zip_round_trip() {
+        let original = archive();
+        let restored = ChannelArchive::from_zip(&original.to_zip().unwrap()).unwrap();
+
+        assert_eq!(restored.channel.core.name, "PCS");
+        assert_eq!(restored.channel.parameters["port"], json!(502));
+        for (point_type, _, _) in POINT_TABLES {
+            assert_eq!(restored.points(point_type), original.points(point_type));
+        }
+        assert!(restored.validate().is_empty());
+    }
+
+    #[test]
+    fn test_remap_and_validate() {
+        let mut archive = ChannelArchive::from_zip(&archive().to_zip().unwrap()).unwrap();
+        archive.remap(&ArchiveRemap {
+            channel_id: Some(9),
+            name: Some("PCS site B".to_string()),
+            slave_id: Some(7),
+            enabled: Some(false),
+        });
+        assert_eq!(archive.channel.core.id, 9);
+        assert!(!archive.channel.core.enabled);
+        let mapping = archive.points(PointType::Control)[0]
+            .mapping
+            .as_ref()
+            .unwrap();
+        assert_eq!(mapping["slave_id"], json!(7));
+        assert_eq!(mapping["byte_order"], json!("AB"));
+
+        archive
+            .points
+            .get_mut(&PointType::Telemetry)
+            .unwrap()
+            .push(point(1, "", Value::Null));
+        let errors = archive.validate();
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+    }
+
+    #[test]
+    fn test_orphan_mapping_rejected() {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        writer.start_file("channel.yaml", options).unwrap();
+        writer
+            .write_all(b"id: 1\nname: Meter\nprotocol: modbus_rtu\n")
+            .unwrap();
+        writer.start_file("telemetry.csv", options).unwrap();
+        writer
+            .write_all(b"point_id,signal_name\n1,Voltage\n")
+            .unwrap();
+        writer
+            .start_file("mapping/telemetry_mapping.csv", options)
+            .unwrap();
+        writer
+            .write_all(b"point_id,slave_id,function_code,register_address\n2,1,3,0\n")
+            .unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let err = ChannelArchive::from_zip(&bytes).unwrap_err();
+        assert!(err.to_string().contains("point 2"), "{}", err);
+    }
+}
@@ -7,64 +7,23 @@
 //!
 //! Uses common bootstrap utilities for shared functionality
 
-use clap::Parser;
-use tracing::{debug, info};
+use tracing::{debug, error, info, warn};
 
 use crate::core::config::DEFAULT_PORT;
 use common::service_bootstrap::ServiceInfo;
-use common::DEFAULT_API_HOST;
+use common::{ValidationLevel, DEFAULT_API_HOST};
 use errors::{VoltageError, VoltageResult};
 
-use crate::core::config::ConfigManager;
+use crate::core::config::{AppConfig, ConfigManager};
 
 // Re-export common bootstrap functionality
-pub use common::bootstrap_args::ServiceArgs;
+pub use common::bootstrap_args::{NoExtraArgs, ServiceArgs};
 pub use common::bootstrap_database::setup_redis_connection;
 pub use common::bootstrap_system::check_system_requirements;
 
-/// Command-line arguments for comsrv
-#[derive(Parser, Clone)]
-#[command(
-    name = "comsrv",
-    version = env!("CARGO_PKG_VERSION"),
-    about = "Industrial Communication Service",
-    long_about = None
-)]
-pub struct Args {
-    /// Log level (trace, debug, info, warn, error)
-    #[arg(short = 'l', long, default_value = "info")]
-    pub log_level: String,
-
-    /// Bind address for API server
-    #[arg(short = 'b', long)]
-    pub bind_address: Option<String>,
-
-    /// Enable debug mode
-    #[arg(short = 'd', long)]
-    pub debug: bool,
-
-    /// Disable colored output
-    #[arg(long)]
-    pub no_color: bool,
-
-    /// Validation mode - only validate configuration without starting service
-    #[arg(long)]
-    pub validate: bool,
-}
-
-impl From<Args> for ServiceArgs {
-    fn from(args: Args) -> Self {
-        ServiceArgs {
-            log_level: args.log_level,
-            bind_address: args.bind_address,
-            debug: args.debug,
-            no_color: args.no_color,
-            validate: args.validate,
-            watch: false,
-            db_path: None,
-            redis_url: None,
-        }
-    }
+/// Parse the command line: standard service flags, no comsrv-specific ones
+pub fn parse_args(service_info: &ServiceInfo) -> ServiceArgs {
+    ServiceArgs::parse_with::<NoExtraArgs>(service_info).0
 }
 
 /// Initialize logging system with command-line arguments
@@ -108,8 +67,13 @@ pub fn initialize_logging(
     Ok(())
 }
 
-/// Validate configuration from SQLite database
-pub async fn validate_configuration() -> VoltageResult<()> {
+/// Validate configuration from SQLite database (`--validate`)
+///
+/// Runs the comsrv rule set, then the cross-service consistency check.
+pub async fn validate_configuration(
+    service_info: &ServiceInfo,
+    db_path: &str,
+) -> VoltageResult<()> {
     debug!("Validating configuration from SQLite database");
 
     // Load and validate configuration
@@ -139,6 +103,25 @@ pub async fn validate_configuration() -> VoltageResult<()> {
         info!("    Points will be loaded from SQLite at runtime");
     }
 
+    // Same declarative rules as `monarch validate`
+    let result = AppConfig::rule_set()
+        .evaluate(config_manager.config(), ValidationLevel::Business)
+        .map_err(|e| VoltageError::Configuration(e.to_string()))?;
+    for warning in &result.warnings {
+        warn!("{}", warning);
+    }
+    if !result.is_valid {
+        for error in &result.errors {
+            error!("{}", error);
+        }
+        return Err(VoltageError::Configuration(result.errors.join("; ")));
+    }
+
+    // Routing and rule references against channels and instances
+    common::service_bootstrap::validate_consistency(service_info, db_path)
+        .await
+        .map_err(|e| VoltageError::Configuration(e.to_string()))?;
+
     info!("Configuration validation completed successfully");
     Ok(())
 }
@@ -5,9 +5,22 @@
 
 // Core modules
 pub mod channel_manager; // Channel lifecycle manager (includes ChannelEntry, ChannelStats)
+pub mod command_retry; // Retry policy and tracking records for control writes
+pub mod command_webhooks; // Webhook callbacks on control/adjustment completion
+pub mod connection; // Connection state machine and transition events
+pub mod dead_letter; // Dead letter queue for undeliverable commands
+#[cfg(all(target_os = "linux", feature = "gpio"))]
+pub mod dido; // Debounce, pulse counters and pulse outputs on GPIO channels
+pub mod forced_points; // Commissioning value injection with automatic revert
+pub mod heartbeat; // Heartbeat output and device watchdog input
+pub mod poll_scheduler; // Per-point poll groups (fast/normal/slow) with priority scheduling
+pub mod read_jobs; // On-demand full reads with job tracking
+pub mod redundancy; // Active/standby election between instances via an RTDB lease
+pub mod stats_history; // Per-poll statistics history in the RTDB
 pub mod traits; // Core traits and type definitions (re-exports from types)
 pub mod trigger; // Command trigger for storage and synchronization
 pub mod types; // Channel communication types (owned by comsrv)
+pub mod waveform; // Signal generators (sine, ramp, square, random walk, CSV steps) for virtual channels
 
 // IGW integration
 pub mod igw_bridge; // Bridge for IGW protocol clients
@@ -18,6 +31,18 @@ pub use types::{ChannelCommand, ChannelStatus, ConnectionState, ProtocolValue};
 // Re-export other types from local modules
 pub use crate::core::config::FourRemote;
 pub use channel_manager::{ChannelEntry, ChannelManager, ChannelMetadata, ChannelStats};
+pub use command_retry::{CommandAttempt, CommandRetryPolicy, CommandTracker};
+pub use command_webhooks::{CommandWebhook, CommandWebhookEvent, CommandWebhooks};
+pub use connection::{
+    ConnectionEvent, ConnectionSnapshot, ConnectionStateMachine, ConnectionTransition,
+};
+pub use dead_letter::{DeadLetterEntry, DeadLetterQueue};
+pub use forced_points::{ForceAuditEntry, ForceEvent, ForcedPoint, ForcedPoints};
+pub use heartbeat::{HeartbeatConfig, HeartbeatMonitor};
+pub use poll_scheduler::{GroupedRuntime, PollGroup, PollGroupIntervals, PollScheduler};
+pub use read_jobs::{ReadJob, ReadJobStatus, ReadJobs};
+pub use redundancy::{NodeStatus, Redundancy, RedundancyConfig, Role};
+pub use stats_history::{PollSample, PollSummary, StatsHistory, StatsHistoryConfig};
 pub use trigger::{CommandStatus, CommandTrigger, CommandTriggerConfig, ControlCommand};
 
 // IGW bridge types (ProtocolClientImpl removed - now using Box<dyn ChannelRuntime>)
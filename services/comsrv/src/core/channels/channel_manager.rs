@@ -5,6 +5,7 @@
 #![allow(clippy::disallowed_methods)] // json! macro used in multiple functions
 
 use arc_swap::ArcSwapOption;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::RwLock;
@@ -15,20 +16,28 @@ use tracing::{debug, error, info, warn};
 const MAX_CHANNELS: usize = 10000;
 
 use crate::core::channels::igw_bridge::{
-    convert_to_igw_point_configs, convert_to_modbus_point_configs, create_modbus_channel,
-    create_modbus_rtu_channel, create_virtual_channel, ChannelImpl, IgwChannelWrapper,
+    convert_to_igw_point_configs, create_virtual_channel, ChannelImpl, ChannelOptions,
+    IgwChannelWrapper,
+};
+#[cfg(feature = "modbus")]
+use crate::core::channels::igw_bridge::{
+    convert_to_modbus_point_configs, create_modbus_channel, create_modbus_rtu_channel,
+    KeepaliveConfig,
 };
 
 #[cfg(all(target_os = "linux", feature = "gpio"))]
 use crate::core::channels::igw_bridge::create_gpio_channel;
-#[cfg(all(feature = "can", target_os = "linux"))]
-use crate::core::channels::igw_bridge::{
-    convert_can_to_igw_point_configs, convert_to_can_point_configs, create_can_channel,
-};
+#[cfg(feature = "modbus")]
+use crate::core::channels::poll_scheduler::{self, GroupedRuntime, PollGroup, PollGroupIntervals};
+use crate::core::channels::read_jobs::{ReadJob, ReadJobs};
 use crate::core::channels::trigger::CommandTrigger;
-use crate::core::config::{ChannelConfig, RuntimeChannelConfig};
+use crate::core::channels::waveform::{WaveformPoints, WaveformRuntime};
+use crate::core::config::{ChannelConfig, PointAccess, RuntimeChannelConfig};
+#[cfg(feature = "modbus")]
+use crate::core::protocols::modbus_blocks::BlockConfig;
 use crate::error::{ComSrvError, Result};
 use crate::store::RedisDataStore;
+use voltage_model::PointType;
 use voltage_rtdb::{ChannelToSlotIndex, Rtdb, SharedVecRtdbWriter};
 
 // ============================================================================
@@ -55,6 +64,8 @@ pub struct ChannelEntry<R: Rtdb> {
     /// Direct command sender for bypassing TODO queue
     pub command_tx:
         Option<tokio::sync::mpsc::Sender<crate::core::channels::traits::ChannelCommand>>,
+    /// Write scopes of the C/A points that have one (others are `Any`)
+    point_access: HashMap<(PointType, u32), PointAccess>,
 }
 
 impl<R: Rtdb> std::fmt::Debug for ChannelEntry<R> {
@@ -76,6 +87,19 @@ pub struct ChannelStats {
     pub last_accessed: Instant,
 }
 
+/// Outcome of draining a channel before shutdown
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DrainSummary {
+    /// Whether all commands already in the mailbox were written in time
+    pub drained: bool,
+    /// Commands left in the mailbox when the drain timed out
+    pub pending_mailbox: usize,
+    /// Control items still waiting in the Redis TODO queue
+    pub pending_control: usize,
+    /// Adjustment items still waiting in the Redis TODO queue
+    pub pending_adjustment: usize,
+}
+
 impl<R: Rtdb> ChannelEntry<R> {
     /// Create new channel entry
     pub fn new(
@@ -100,9 +124,35 @@ impl<R: Rtdb> ChannelEntry<R> {
             command_trigger,
             channel_config,
             command_tx,
+            point_access: HashMap::new(),
         }
     }
 
+    /// Take the point write scopes from the loaded point tables
+    pub fn with_point_access(mut self, config: &RuntimeChannelConfig) -> Self {
+        let control = config
+            .control_points
+            .iter()
+            .map(|p| ((PointType::Control, p.base.point_id), p.access));
+        let adjustment = config
+            .adjustment_points
+            .iter()
+            .map(|p| ((PointType::Adjustment, p.base.point_id), p.access));
+        self.point_access = control
+            .chain(adjustment)
+            .filter(|(_, access)| *access != PointAccess::Any)
+            .collect();
+        self
+    }
+
+    /// Write scope of a point
+    pub fn point_access(&self, point_type: PointType, point_id: u32) -> PointAccess {
+        self.point_access
+            .get(&(point_type, point_id))
+            .copied()
+            .unwrap_or_default()
+    }
+
     /// Get channel statistics
     pub async fn get_stats(&self, channel_id: u32) -> ChannelStats {
         let last_accessed = *self.metadata.last_accessed.read().await;
@@ -152,6 +202,8 @@ pub struct ChannelManager<R: Rtdb> {
     /// Command TX cache for O(1) hot path access
     /// Shared with AppState for direct API access bypassing RwLock
     command_tx_cache: Option<Arc<crate::api::command_cache::CommandTxCache>>,
+    /// On-demand read-all jobs
+    read_jobs: Arc<ReadJobs>,
 }
 
 impl<R: Rtdb> std::fmt::Debug for ChannelManager<R> {
@@ -179,6 +231,7 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
             shared_writer: None,
             channel_index: None,
             command_tx_cache: None,
+            read_jobs: Arc::new(ReadJobs::new()),
         }
     }
 
@@ -196,6 +249,7 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
             shared_writer: None,
             channel_index: None,
             command_tx_cache: None,
+            read_jobs: Arc::new(ReadJobs::new()),
         }
     }
 
@@ -226,6 +280,7 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
             shared_writer,
             channel_index,
             command_tx_cache,
+            read_jobs: Arc::new(ReadJobs::new()),
         }
     }
 
@@ -276,11 +331,44 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
                 self.create_igw_virtual_channel(channel_id, &runtime_config)
                     .await?
             },
+            #[cfg(feature = "modbus")]
+            "modbus_tcp"
+                if crate::core::protocols::modbus_server::is_server_mode(
+                    &runtime_config.base.parameters,
+                ) =>
+            {
+                // In-tree runtime: Modbus TCP server exposing RTDB points to masters
+                let protocol =
+                    crate::core::protocols::modbus_server::ModbusServerRuntime::from_runtime_config(
+                        &runtime_config,
+                        Arc::clone(&self.rtdb),
+                        Arc::clone(&self.routing_cache),
+                    )?;
+                self.create_runtime_channel(channel_id, &runtime_config, Box::new(protocol))
+                    .await?
+            },
+            #[cfg(feature = "modbus")]
+            "modbus_tcp" | "modbus_rtu"
+                if crate::core::protocols::modbus_rtu_tcp::CommunicationMode::of_channel(
+                    &protocol_name,
+                    &runtime_config.base.parameters,
+                )? == crate::core::protocols::modbus_rtu_tcp::CommunicationMode::RtuOverTcp =>
+            {
+                // In-tree runtime: RTU frames through a TCP device server
+                let protocol =
+                    crate::core::protocols::modbus_rtu_tcp::ModbusRtuOverTcpRuntime::from_runtime_config(
+                        &runtime_config,
+                    )?;
+                self.create_runtime_channel(channel_id, &runtime_config, Box::new(protocol))
+                    .await?
+            },
+            #[cfg(feature = "modbus")]
             "modbus_tcp" => {
                 // IGW path: Use igw::ModbusChannel (TCP) with RedisDataStore
                 self.create_igw_modbus_channel(channel_id, &runtime_config)
                     .await?
             },
+            #[cfg(feature = "modbus")]
             "modbus_rtu" => {
                 // IGW path: Use igw::ModbusChannel (RTU/serial) with RedisDataStore
                 self.create_igw_modbus_rtu_channel(channel_id, &runtime_config)
@@ -292,10 +380,104 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
                 self.create_igw_gpio_channel(channel_id, &runtime_config)
                     .await?
             },
+            #[cfg(feature = "bacnet")]
+            "bacnet" => {
+                // In-tree runtime: BACnet/IP client
+                let protocol = crate::core::protocols::bacnet::BacnetRuntime::from_runtime_config(
+                    &runtime_config,
+                )?;
+                self.create_runtime_channel(channel_id, &runtime_config, Box::new(protocol))
+                    .await?
+            },
             #[cfg(all(feature = "can", target_os = "linux"))]
             "can" => {
-                // IGW path: Use igw::CanClient with RedisDataStore
-                self.create_igw_can_channel(channel_id, &runtime_config)
+                // In-tree runtime: CAN bus over SocketCAN
+                self.create_can_channel(channel_id, &runtime_config).await?
+            },
+            #[cfg(feature = "dlt645")]
+            "dlt645" => {
+                // In-tree runtime: DL/T 645-2007 meters over serial
+                let protocol = crate::core::protocols::dlt645::Dlt645Runtime::from_runtime_config(
+                    &runtime_config,
+                )?;
+                self.create_runtime_channel(channel_id, &runtime_config, Box::new(protocol))
+                    .await?
+            },
+            #[cfg(feature = "dlms")]
+            "dlms" => {
+                // In-tree runtime: DLMS/COSEM meters over HDLC or the TCP wrapper
+                let protocol = crate::core::protocols::dlms::DlmsRuntime::from_runtime_config(
+                    &runtime_config,
+                )?;
+                self.create_runtime_channel(channel_id, &runtime_config, Box::new(protocol))
+                    .await?
+            },
+            #[cfg(feature = "dnp3")]
+            "dnp3_tcp" => {
+                // In-tree runtime: DNP3 master over TCP
+                let protocol = crate::core::protocols::dnp3::Dnp3Runtime::from_runtime_config(
+                    &runtime_config,
+                )?;
+                self.create_runtime_channel(channel_id, &runtime_config, Box::new(protocol))
+                    .await?
+            },
+            #[cfg(feature = "enip")]
+            "enip" => {
+                // In-tree runtime: EtherNet/IP explicit messaging to Logix controllers
+                let protocol = crate::core::protocols::enip::EnipRuntime::from_runtime_config(
+                    &runtime_config,
+                )?;
+                self.create_runtime_channel(channel_id, &runtime_config, Box::new(protocol))
+                    .await?
+            },
+            #[cfg(feature = "iec61850")]
+            "iec61850" => {
+                // In-tree runtime: IEC 61850 MMS client
+                let protocol = crate::core::protocols::iec61850::MmsRuntime::from_runtime_config(
+                    &runtime_config,
+                )?;
+                self.create_runtime_channel(channel_id, &runtime_config, Box::new(protocol))
+                    .await?
+            },
+            #[cfg(feature = "mqtt")]
+            "mqtt" => {
+                // In-tree runtime: MQTT subscriber
+                let protocol = crate::core::protocols::mqtt::MqttRuntime::from_runtime_config(
+                    &runtime_config,
+                )?;
+                self.create_runtime_channel(channel_id, &runtime_config, Box::new(protocol))
+                    .await?
+            },
+            #[cfg(feature = "opcua")]
+            "opcua" => {
+                // In-tree runtime: OPC UA client
+                let protocol = crate::core::protocols::opcua::OpcUaRuntime::from_runtime_config(
+                    &runtime_config,
+                )?;
+                self.create_runtime_channel(channel_id, &runtime_config, Box::new(protocol))
+                    .await?
+            },
+            #[cfg(feature = "s7")]
+            "s7" => {
+                // In-tree runtime: S7comm to Siemens S7-300/400/1200/1500 CPUs
+                let protocol =
+                    crate::core::protocols::s7::S7Runtime::from_runtime_config(&runtime_config)?;
+                self.create_runtime_channel(channel_id, &runtime_config, Box::new(protocol))
+                    .await?
+            },
+            #[cfg(feature = "snmp")]
+            "snmp" => {
+                // In-tree runtime: SNMP v2c/v3 manager
+                let protocol = crate::core::protocols::snmp::SnmpRuntime::from_runtime_config(
+                    &runtime_config,
+                )?;
+                self.create_runtime_channel(channel_id, &runtime_config, Box::new(protocol))
+                    .await?
+            },
+            #[cfg(feature = "dylib-plugins")]
+            name if crate::core::plugins::dylib::get(name).is_some() => {
+                // Plugin path: protocol implemented by a shared library
+                self.create_dylib_channel(channel_id, &runtime_config)
                     .await?
             },
             _ => {
@@ -307,9 +489,45 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
                 #[cfg(all(target_os = "linux", feature = "gpio"))]
                 supported.push_str(", gpio/di_do");
 
+                #[cfg(feature = "bacnet")]
+                supported.push_str(", bacnet");
+
                 #[cfg(all(feature = "can", target_os = "linux"))]
                 supported.push_str(", can");
 
+                #[cfg(feature = "dlt645")]
+                supported.push_str(", dlt645");
+
+                #[cfg(feature = "dlms")]
+                supported.push_str(", dlms");
+
+                #[cfg(feature = "dnp3")]
+                supported.push_str(", dnp3_tcp");
+
+                #[cfg(feature = "enip")]
+                supported.push_str(", enip");
+
+                #[cfg(feature = "iec61850")]
+                supported.push_str(", iec61850");
+
+                #[cfg(feature = "mqtt")]
+                supported.push_str(", mqtt");
+
+                #[cfg(feature = "opcua")]
+                supported.push_str(", opcua");
+
+                #[cfg(feature = "s7")]
+                supported.push_str(", s7");
+
+                #[cfg(feature = "snmp")]
+                supported.push_str(", snmp");
+
+                #[cfg(feature = "dylib-plugins")]
+                for name in crate::core::plugins::dylib::loaded_protocols() {
+                    supported.push_str(", ");
+                    supported.push_str(name);
+                }
+
                 return Err(anyhow::anyhow!(
                     "Unsupported protocol '{}' for channel {}. Supported: {}",
                     protocol_name,
@@ -332,11 +550,15 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
             protocol_name.clone(),
             command_trigger,
             command_tx, // Direct command sender for bypassing TODO queue
-        );
+        )
+        .with_point_access(&runtime_config);
 
         // O(1) atomic store (slot already validated above)
         slot.store(Some(Arc::new(entry)));
 
+        self.write_channel_status(channel_id, vec![("state", "running".to_string())])
+            .await;
+
         info!("Ch{} created ({})", channel_id, protocol_name);
         Ok(channel_impl)
     }
@@ -365,11 +587,24 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
         // 3. Start background flush task for write buffer
         store.start_flush_task().await;
 
-        // 4. Create VirtualChannel (no store - storage handled by IgwChannelWrapper)
-        let protocol = create_virtual_channel(channel_id, runtime_config.name(), point_configs);
+        // 4. Create VirtualChannel (no store - storage handled by IgwChannelWrapper),
+        //    with waveform generators on the points that configure one
+        let mut protocol = create_virtual_channel(channel_id, runtime_config.name(), point_configs);
+        let generators = WaveformPoints::from_runtime_config(runtime_config)?;
+        if !generators.is_empty() {
+            debug!(
+                "Ch{} {} generated points",
+                channel_id,
+                generators.generators.len()
+            );
+            protocol = Box::new(WaveformRuntime::new(protocol, generators));
+        }
 
         // 5. Setup command trigger for M2C control
-        let (command_trigger, rx, command_tx) = self.create_command_trigger(channel_id).await?;
+        let options = ChannelOptions::from_parameters(&runtime_config.base.parameters);
+        let (command_trigger, rx, command_tx) = self
+            .create_command_trigger(channel_id, options.isolation.mailbox_size)
+            .await?;
 
         // 6. Create IgwChannelWrapper with command processing and storage
         // Virtual channel uses default 1000ms polling
@@ -381,17 +616,107 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
             .unwrap_or(1000);
 
         // Point types are encoded in internal_id by igw_bridge - no registration needed
-        let wrapper = IgwChannelWrapper::new(protocol, channel_id, store, rx, poll_interval_ms);
+        let wrapper =
+            IgwChannelWrapper::new(protocol, channel_id, store, rx, poll_interval_ms, options);
         let channel_impl: ChannelImpl<R> = Arc::new(RwLock::new(wrapper));
 
         info!("Ch{} created via IGW (virtual)", channel_id);
         Ok((channel_impl, command_trigger, command_tx))
     }
 
+    /// Create a channel whose protocol is implemented by a shared library plugin.
+    ///
+    /// The plugin instance runs behind the same IgwChannelWrapper as igw protocols.
+    #[cfg(feature = "dylib-plugins")]
+    async fn create_dylib_channel(
+        &self,
+        channel_id: u32,
+        runtime_config: &Arc<RuntimeChannelConfig>,
+    ) -> Result<(
+        ChannelImpl<R>,
+        Option<Arc<RwLock<CommandTrigger<R>>>>,
+        Option<tokio::sync::mpsc::Sender<crate::core::channels::traits::ChannelCommand>>,
+    )> {
+        let plugin =
+            crate::core::plugins::dylib::get(runtime_config.protocol()).ok_or_else(|| {
+                ComSrvError::ConfigError(format!(
+                    "Ch{}: protocol plugin '{}' not loaded",
+                    channel_id,
+                    runtime_config.protocol()
+                ))
+            })?;
+        debug!(
+            "Ch{} creating via plugin {}",
+            channel_id,
+            plugin.path().display()
+        );
+
+        let protocol = plugin.create_runtime(runtime_config)?;
+        self.create_runtime_channel(channel_id, runtime_config, protocol)
+            .await
+    }
+
+    /// Wrap a protocol runtime built outside igw (plugins, `core::protocols`)
+    #[cfg(any(
+        feature = "bacnet",
+        feature = "dlt645",
+        feature = "dlms",
+        feature = "dnp3",
+        feature = "enip",
+        feature = "iec61850",
+        feature = "modbus",
+        feature = "mqtt",
+        feature = "opcua",
+        feature = "s7",
+        feature = "snmp",
+        feature = "dylib-plugins"
+    ))]
+    async fn create_runtime_channel(
+        &self,
+        channel_id: u32,
+        runtime_config: &Arc<RuntimeChannelConfig>,
+        protocol: Box<dyn igw::gateway::ChannelRuntime>,
+    ) -> Result<(
+        ChannelImpl<R>,
+        Option<Arc<RwLock<CommandTrigger<R>>>>,
+        Option<tokio::sync::mpsc::Sender<crate::core::channels::traits::ChannelCommand>>,
+    )> {
+        // 1. Create RedisDataStore and register point transforms
+        let store = self.create_data_store();
+        let point_configs = convert_to_igw_point_configs(runtime_config);
+        store.set_point_configs(channel_id, point_configs);
+        store.start_flush_task().await;
+
+        // 2. Setup command trigger for M2C control
+        let options = ChannelOptions::from_parameters(&runtime_config.base.parameters);
+        let (command_trigger, rx, command_tx) = self
+            .create_command_trigger(channel_id, options.isolation.mailbox_size)
+            .await?;
+
+        let poll_interval_ms = runtime_config
+            .base
+            .parameters
+            .get("poll_interval_ms")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(1000);
+
+        let wrapper =
+            IgwChannelWrapper::new(protocol, channel_id, store, rx, poll_interval_ms, options);
+        let channel_impl: ChannelImpl<R> = Arc::new(RwLock::new(wrapper));
+
+        info!(
+            "Ch{} created via {} runtime",
+            channel_id,
+            runtime_config.protocol()
+        );
+        Ok((channel_impl, command_trigger, command_tx))
+    }
+
     /// Create IGW-based Modbus TCP channel.
     ///
     /// Uses igw::ModbusChannel with RedisDataStore for data persistence.
     /// Includes batch read optimization, auto-reconnect, and zero-data detection.
+    #[cfg(feature = "modbus")]
     async fn create_igw_modbus_channel(
         &self,
         channel_id: u32,
@@ -407,7 +732,11 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
         let store = self.create_data_store();
 
         // 2. Convert Modbus point configs to IGW format
-        let point_configs = convert_to_modbus_point_configs(runtime_config);
+        let mut point_configs = convert_to_modbus_point_configs(runtime_config);
+        point_configs.extend(
+            KeepaliveConfig::from_parameters(&runtime_config.base.parameters)
+                .and_then(|k| k.probe_point()),
+        );
         store.set_point_configs(channel_id, point_configs.clone());
 
         // 3. Start background flush task for write buffer
@@ -425,21 +754,52 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
             .map(|n| n as u16)
             .unwrap_or(502);
 
-        // 5. Create ModbusChannel via igw_bridge (no store - storage handled by IgwChannelWrapper)
-        let protocol = create_modbus_channel(channel_id, host, port, point_configs);
-
-        // 6. Setup command trigger for M2C control
-        let (command_trigger, rx, command_tx) = self.create_command_trigger(channel_id).await?;
-
-        // 7. Create IgwChannelWrapper with command processing and storage
         // Modbus uses internal polling, external polling as backup (default 1000ms)
-        let poll_interval_ms = params
+        let mut poll_interval_ms = params
             .get("poll_interval_ms")
             .and_then(|v| v.as_u64())
             .unwrap_or(1000);
 
+        // 5. Create ModbusChannel via igw_bridge (no store - storage handled by IgwChannelWrapper);
+        // with poll groups each group gets its own client connection
+        let blocks = BlockConfig::from_parameters(params);
+        let groups = poll_scheduler::point_groups(runtime_config);
+        let protocol: Box<dyn igw::gateway::ChannelRuntime> =
+            if groups.values().any(|g| *g != PollGroup::Normal) {
+                let grouped = GroupedRuntime::new(
+                    channel_id,
+                    format!("modbus_tcp_{}", channel_id),
+                    "modbus_tcp",
+                    point_configs,
+                    |p| {
+                        (
+                            p.id,
+                            groups.get(&p.id).copied().unwrap_or(PollGroup::Normal),
+                        )
+                    },
+                    PollGroupIntervals::from_parameters(params),
+                    |_, points| create_modbus_channel(channel_id, host, port, points, blocks),
+                );
+                poll_interval_ms = grouped.tick().as_millis() as u64;
+                info!(
+                    "Ch{} poll groups enabled (tick: {}ms)",
+                    channel_id, poll_interval_ms
+                );
+                Box::new(grouped)
+            } else {
+                create_modbus_channel(channel_id, host, port, point_configs, blocks)
+            };
+
+        // 6. Setup command trigger for M2C control
+        let options = ChannelOptions::from_parameters(&runtime_config.base.parameters);
+        let (command_trigger, rx, command_tx) = self
+            .create_command_trigger(channel_id, options.isolation.mailbox_size)
+            .await?;
+
+        // 7. Create IgwChannelWrapper with command processing and storage
         // Point types are encoded in internal_id by igw_bridge - no registration needed
-        let wrapper = IgwChannelWrapper::new(protocol, channel_id, store, rx, poll_interval_ms);
+        let wrapper =
+            IgwChannelWrapper::new(protocol, channel_id, store, rx, poll_interval_ms, options);
         let channel_impl: ChannelImpl<R> = Arc::new(RwLock::new(wrapper));
 
         info!("Ch{} created via IGW (modbus_tcp)", channel_id);
@@ -450,6 +810,7 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
     ///
     /// Uses igw::ModbusChannel in RTU mode with RedisDataStore for data persistence.
     /// Includes batch read optimization, auto-reconnect, and zero-data detection.
+    #[cfg(feature = "modbus")]
     async fn create_igw_modbus_rtu_channel(
         &self,
         channel_id: u32,
@@ -465,7 +826,11 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
         let store = self.create_data_store();
 
         // 2. Convert Modbus point configs to IGW format
-        let point_configs = convert_to_modbus_point_configs(runtime_config);
+        let mut point_configs = convert_to_modbus_point_configs(runtime_config);
+        point_configs.extend(
+            KeepaliveConfig::from_parameters(&runtime_config.base.parameters)
+                .and_then(|k| k.probe_point()),
+        );
         store.set_point_configs(channel_id, point_configs.clone());
 
         // 3. Start background flush task for write buffer
@@ -483,11 +848,30 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
             .map(|n| n as u32)
             .unwrap_or(9600);
 
-        // 5. Create ModbusChannel (RTU) via igw_bridge
-        let protocol = create_modbus_rtu_channel(channel_id, device, baud_rate, point_configs);
+        // 5. Create ModbusChannel (RTU) via igw_bridge; the serial port
+        // cannot be opened once per poll group
+        if poll_scheduler::point_groups(runtime_config)
+            .values()
+            .any(|g| *g != PollGroup::Normal)
+        {
+            warn!(
+                "Ch{} poll groups are not supported on serial lines, all points use poll_interval_ms",
+                channel_id
+            );
+        }
+        let protocol = create_modbus_rtu_channel(
+            channel_id,
+            device,
+            baud_rate,
+            point_configs,
+            BlockConfig::from_parameters(params),
+        );
 
         // 6. Setup command trigger for M2C control
-        let (command_trigger, rx, command_tx) = self.create_command_trigger(channel_id).await?;
+        let options = ChannelOptions::from_parameters(&runtime_config.base.parameters);
+        let (command_trigger, rx, command_tx) = self
+            .create_command_trigger(channel_id, options.isolation.mailbox_size)
+            .await?;
 
         // 7. Create IgwChannelWrapper with command processing and storage
         // Modbus uses internal polling, external polling as backup (default 1000ms)
@@ -497,7 +881,8 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
             .unwrap_or(1000);
 
         // Point types are encoded in internal_id by igw_bridge - no registration needed
-        let wrapper = IgwChannelWrapper::new(protocol, channel_id, store, rx, poll_interval_ms);
+        let wrapper =
+            IgwChannelWrapper::new(protocol, channel_id, store, rx, poll_interval_ms, options);
         let channel_impl: ChannelImpl<R> = Arc::new(RwLock::new(wrapper));
 
         info!("Ch{} created via IGW (modbus_rtu)", channel_id);
@@ -535,7 +920,10 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
         let protocol = create_gpio_channel(channel_id, runtime_config);
 
         // 5. Setup command trigger for M2C control (DO commands)
-        let (command_trigger, rx, command_tx) = self.create_command_trigger(channel_id).await?;
+        let options = ChannelOptions::from_parameters(&runtime_config.base.parameters);
+        let (command_trigger, rx, command_tx) = self
+            .create_command_trigger(channel_id, options.isolation.mailbox_size)
+            .await?;
 
         // 6. Create IgwChannelWrapper
         // GPIO needs faster polling (default 200ms for responsive DI detection)
@@ -547,19 +935,20 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
             .unwrap_or(200);
 
         // Point types are encoded in internal_id by igw_bridge - no registration needed
-        let wrapper = IgwChannelWrapper::new(protocol, channel_id, store, rx, poll_interval_ms);
+        let wrapper =
+            IgwChannelWrapper::new(protocol, channel_id, store, rx, poll_interval_ms, options);
         let channel_impl: ChannelImpl<R> = Arc::new(RwLock::new(wrapper));
 
         info!("Ch{} created via IGW (gpio)", channel_id);
         Ok((channel_impl, command_trigger, command_tx))
     }
 
-    /// Create IGW-based CAN channel.
+    /// Create CAN channel on a SocketCAN interface.
     ///
-    /// Uses igw::CanClient with RedisDataStore for data persistence.
-    /// CAN protocol is event-driven and read-only (no M2C control).
+    /// Like [`Self::create_runtime_channel`], with the faster default poll
+    /// interval a bus of periodically broadcast frames needs.
     #[cfg(all(feature = "can", target_os = "linux"))]
-    async fn create_igw_can_channel(
+    async fn create_can_channel(
         &self,
         channel_id: u32,
         runtime_config: &Arc<RuntimeChannelConfig>,
@@ -568,49 +957,40 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
         Option<Arc<RwLock<CommandTrigger<R>>>>,
         Option<tokio::sync::mpsc::Sender<crate::core::channels::traits::ChannelCommand>>,
     )> {
-        debug!("Ch{} creating via IGW CAN path", channel_id);
+        let protocol =
+            crate::core::protocols::can::SocketCanRuntime::from_runtime_config(runtime_config)?;
 
-        // 1. Create RedisDataStore for this channel (with optional shared memory)
+        // 1. Create RedisDataStore and register point transforms
         let store = self.create_data_store();
-
-        // 2. Convert CAN mappings to IGW formats
-        let can_point_configs = convert_to_can_point_configs(runtime_config);
-        let igw_point_configs = convert_can_to_igw_point_configs(runtime_config);
-
-        if can_point_configs.is_empty() {
-            warn!("Ch{} has no CAN point mappings configured", channel_id);
-        }
-
-        store.set_point_configs(channel_id, igw_point_configs);
-
-        // 3. Start background flush task for write buffer
+        store.set_point_configs(channel_id, convert_to_igw_point_configs(runtime_config));
         store.start_flush_task().await;
 
-        // 4. Extract CAN interface from runtime config parameters
-        let params = &runtime_config.base.parameters;
-        let can_interface = params
-            .get("device")
-            .and_then(|v| v.as_str())
-            .unwrap_or("can0");
-
-        // 5. Create CanClient via igw_bridge
-        let protocol = create_can_channel(channel_id, can_interface, can_point_configs);
-
-        // 6. Setup command trigger (CAN is read-only, but we still create the trigger for consistency)
-        let (command_trigger, rx, command_tx) = self.create_command_trigger(channel_id).await?;
+        // 2. Setup command trigger for M2C control
+        let options = ChannelOptions::from_parameters(&runtime_config.base.parameters);
+        let (command_trigger, rx, command_tx) = self
+            .create_command_trigger(channel_id, options.isolation.mailbox_size)
+            .await?;
 
-        // 7. Create IgwChannelWrapper
-        // CAN is event-driven, needs faster polling (default 200ms)
-        let poll_interval_ms = params
+        // 3. Create IgwChannelWrapper
+        // Received frames are drained on each poll (default 200ms)
+        let poll_interval_ms = runtime_config
+            .base
+            .parameters
             .get("poll_interval_ms")
             .and_then(|v| v.as_u64())
             .unwrap_or(200);
 
-        // Point types are encoded in internal_id by igw_bridge - no registration needed
-        let wrapper = IgwChannelWrapper::new(protocol, channel_id, store, rx, poll_interval_ms);
+        let wrapper = IgwChannelWrapper::new(
+            Box::new(protocol),
+            channel_id,
+            store,
+            rx,
+            poll_interval_ms,
+            options,
+        );
         let channel_impl: ChannelImpl<R> = Arc::new(RwLock::new(wrapper));
 
-        info!("Ch{} created via IGW (can)", channel_id);
+        info!("Ch{} created via can runtime", channel_id);
         Ok((channel_impl, command_trigger, command_tx))
     }
 
@@ -686,6 +1066,108 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
         }
     }
 
+    /// Drain a channel before it is removed during shutdown.
+    ///
+    /// 1. Marks the channel `stopping` in its Redis status hash
+    /// 2. Stops the command trigger so no new TODO items are dequeued
+    /// 3. Waits up to `timeout` for commands already dequeued to be written
+    /// 4. Records the remaining mailbox and TODO queue depth in the status hash
+    ///
+    /// Items left in the TODO queues stay in Redis and are picked up on restart.
+    /// The channel itself is still registered; call `remove_channel` afterwards.
+    pub async fn drain_channel(
+        &self,
+        channel_id: u32,
+        timeout: std::time::Duration,
+    ) -> Result<DrainSummary> {
+        let entry = self
+            .get_channel_entry(channel_id)
+            .ok_or_else(|| ComSrvError::channel_not_found(channel_id))?;
+
+        self.write_channel_status(channel_id, vec![("state", "stopping".to_string())])
+            .await;
+
+        // Stop accepting new commands: direct API sends and the TODO queues
+        if let Some(ref cache) = self.command_tx_cache {
+            cache.unregister(channel_id);
+        }
+        if let Some(trigger_arc) = &entry.command_trigger {
+            let _ = trigger_arc.write().await.stop().await;
+        }
+
+        let drained = entry.channel.write().await.drain(timeout).await;
+        let pending_mailbox = match (&entry.command_tx, drained) {
+            (Some(tx), false) => tx.max_capacity() - tx.capacity(),
+            _ => 0,
+        };
+
+        let keyspace = voltage_rtdb::KeySpaceConfig::production_cached();
+        let mut todo_depth = [0usize; 2];
+        for (depth, point_type) in todo_depth.iter_mut().zip([
+            voltage_model::PointType::Control,
+            voltage_model::PointType::Adjustment,
+        ]) {
+            let key = keyspace.todo_queue_key(channel_id, point_type);
+            *depth = match self.rtdb.list_range(&key, 0, -1).await {
+                Ok(items) => items.len(),
+                Err(e) => {
+                    warn!("Ch{} TODO depth read failed: {}", channel_id, e);
+                    0
+                },
+            };
+        }
+
+        let summary = DrainSummary {
+            drained,
+            pending_mailbox,
+            pending_control: todo_depth[0],
+            pending_adjustment: todo_depth[1],
+        };
+        self.write_channel_status(
+            channel_id,
+            vec![
+                ("pending_mailbox", summary.pending_mailbox.to_string()),
+                ("pending_control", summary.pending_control.to_string()),
+                ("pending_adjustment", summary.pending_adjustment.to_string()),
+            ],
+        )
+        .await;
+
+        if summary.drained {
+            info!(
+                "Ch{} drained ({} C / {} A left in TODO)",
+                channel_id, summary.pending_control, summary.pending_adjustment
+            );
+        } else {
+            warn!(
+                "Ch{} drain incomplete: {} commands dropped from mailbox",
+                channel_id, summary.pending_mailbox
+            );
+        }
+
+        Ok(summary)
+    }
+
+    /// Write fields to the channel status hash (comsrv:{id}:status).
+    ///
+    /// Status is informational, so failures are logged and not propagated.
+    async fn write_channel_status(&self, channel_id: u32, fields: Vec<(&str, String)>) {
+        let keyspace = voltage_rtdb::KeySpaceConfig::production_cached();
+        let key = keyspace.channel_status_key(channel_id);
+        let mut fields: Vec<(String, bytes::Bytes)> = fields
+            .into_iter()
+            .map(|(field, value)| (field.to_string(), bytes::Bytes::from(value)))
+            .collect();
+        fields.push((
+            "updated_at".to_string(),
+            bytes::Bytes::from(chrono::Utc::now().timestamp_millis().to_string()),
+        ));
+
+        if let Err(e) = self.rtdb.hash_mset(&key, fields).await {
+            warn!("Ch{} status write failed: {}", channel_id, e);
+        }
+    }
+
     /// Get channel implementation (dual-mode)
     ///
     /// # O(1) lock-free access (~5ns)
@@ -697,6 +1179,49 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
             .map(|entry| entry.channel.clone())
     }
 
+    /// Read-all jobs of all channels
+    pub fn read_jobs(&self) -> &Arc<ReadJobs> {
+        &self.read_jobs
+    }
+
+    /// Schedule a full read of a channel outside its poll cycle
+    ///
+    /// Returns the job and whether it was created (`false`: the channel
+    /// already has a pending job, which is returned instead).
+    pub fn schedule_read_all(&self, channel_id: u32) -> Result<(ReadJob, bool)> {
+        let channel = self
+            .get_channel(channel_id)
+            .ok_or_else(|| ComSrvError::channel_not_found(channel_id))?;
+        let (job, created) = self.read_jobs.create(channel_id);
+        if !created {
+            return Ok((job, false));
+        }
+
+        let jobs = Arc::clone(&self.read_jobs);
+        let job_id = job.job_id.clone();
+        tokio::spawn(async move {
+            jobs.start(&job_id);
+            let result = channel.read().await.read_all().await;
+            match result {
+                Ok(result) => {
+                    info!(
+                        "Ch{} read-all {}: {} points, {} failed",
+                        channel_id,
+                        job_id,
+                        result.data.len(),
+                        result.failures.len()
+                    );
+                    jobs.update(&job_id, |job| job.complete(&result.data, &result.failures));
+                },
+                Err(e) => {
+                    warn!("Ch{} read-all {} failed: {}", channel_id, job_id, e);
+                    jobs.update(&job_id, |job| job.fail(e.to_string()));
+                },
+            }
+        });
+        Ok((job, true))
+    }
+
     /// Get channel entry (for direct command_tx access)
     ///
     /// Unlike `get_channel()` which returns only the ChannelImpl,
@@ -831,6 +1356,29 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
         }
     }
 
+    /// Disconnect all channels but keep them (and their tasks) in place
+    ///
+    /// Used when this instance becomes the redundancy standby; the channels
+    /// are connected again by `connect_all_channels` on takeover.
+    pub async fn suspend_all_channels(&self) {
+        for slot in self.channels.iter() {
+            if let Some(entry) = slot.load_full() {
+                let channel = entry.channel.read().await;
+                if let Err(e) = channel.suspend().await {
+                    debug!("Ch{} suspend: {}", channel.channel_id(), e);
+                }
+            }
+        }
+    }
+
+    /// Record the active redundancy node in every channel status hash
+    pub async fn publish_active_node(&self, node_id: &str) {
+        for channel_id in self.get_channel_ids() {
+            self.write_channel_status(channel_id, vec![("active_node", node_id.to_string())])
+                .await;
+        }
+    }
+
     /// Cleanup all resources
     pub async fn cleanup(&self) -> Result<()> {
         info!("Cleanup started");
@@ -1031,6 +1579,7 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
     async fn create_command_trigger(
         &self,
         channel_id: u32,
+        mailbox_size: usize,
     ) -> Result<(
         Option<Arc<RwLock<crate::core::channels::trigger::CommandTrigger<R>>>>,
         tokio::sync::mpsc::Receiver<crate::core::channels::traits::ChannelCommand>,
@@ -1045,7 +1594,7 @@ impl<R: Rtdb + 'static> ChannelManager<R> {
             timeout_seconds: 1, // Default BLPOP timeout
         };
 
-        let (tx, rx) = tokio::sync::mpsc::channel(100);
+        let (tx, rx) = tokio::sync::mpsc::channel(mailbox_size);
 
         // Pass RTDB directly to trigger (works with both RedisRtdb and MemoryRtdb)
         let mut trigger = CommandTrigger::new(config, tx.clone(), self.rtdb.clone()).await?;
@@ -1091,4 +1640,92 @@ mod tests {
         let count = manager.running_channel_count().await;
         assert_eq!(count, 0);
     }
+
+    #[tokio::test]
+    async fn test_drain_channel_marks_stopping_and_records_depth() {
+        use crate::core::channels::igw_bridge::create_virtual_channel;
+        use crate::core::config::{ChannelCore, ChannelLoggingConfig};
+
+        let rtdb = create_test_rtdb();
+        let manager: ChannelManager<voltage_rtdb::MemoryRtdb> =
+            ChannelManager::new(rtdb.clone(), create_test_routing_cache());
+
+        let channel_id = 42;
+        let (trigger, rx, tx) = manager.create_command_trigger(channel_id, 8).await.unwrap();
+        let store = Arc::new(RedisDataStore::new(
+            rtdb.clone(),
+            Arc::clone(&manager.routing_cache),
+        ));
+        let wrapper = IgwChannelWrapper::new(
+            create_virtual_channel(channel_id, "drain", Vec::new()),
+            channel_id,
+            store,
+            rx,
+            60_000,
+            ChannelOptions::default(),
+        );
+        let config = Arc::new(ChannelConfig {
+            core: ChannelCore {
+                id: channel_id,
+                name: "drain".to_string(),
+                description: None,
+                protocol: "virtual".to_string(),
+                enabled: true,
+            },
+            parameters: Default::default(),
+            logging: ChannelLoggingConfig::default(),
+        });
+        let entry = ChannelEntry::new(
+            Arc::new(RwLock::new(wrapper)),
+            config,
+            "virtual".to_string(),
+            trigger,
+            tx.clone(),
+        );
+        manager.channels[channel_id as usize].store(Some(Arc::new(entry)));
+
+        let summary = manager
+            .drain_channel(channel_id, std::time::Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert!(summary.drained);
+        assert_eq!(summary.pending_mailbox, 0);
+
+        // Trigger is stopped: new TODO items stay queued, direct sends are refused
+        let keyspace = voltage_rtdb::KeySpaceConfig::production_cached();
+        let todo_key = keyspace.todo_queue_key(channel_id, PointType::Control);
+        rtdb.list_rpush(&todo_key, bytes::Bytes::from("{\"point_id\":1}"))
+            .await
+            .unwrap();
+        assert!(tx
+            .unwrap()
+            .send(crate::core::channels::traits::ChannelCommand::Control {
+                command_id: "late".to_string(),
+                point_id: 1,
+                value: 1.0,
+                timestamp: 0,
+            })
+            .await
+            .is_err());
+
+        let summary = manager
+            .drain_channel(channel_id, std::time::Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(summary.pending_control, 1);
+
+        let status = rtdb
+            .hash_get_all(&keyspace.channel_status_key(channel_id))
+            .await
+            .unwrap();
+        assert_eq!(status["state"], "stopping");
+        assert_eq!(status["pending_control"], "1");
+        assert_eq!(status["pending_adjustment"], "0");
+
+        assert!(manager.remove_channel(channel_id).await.is_ok());
+        assert!(manager
+            .drain_channel(channel_id, std::time::Duration::ZERO)
+            .await
+            .is_err());
+    }
 }
@@ -0,0 +1,201 @@
+//! Automatic retry of failed control writes
+//!
+//! Transient protocol failures (timeouts, dropped connections) are retried by
+//! the command executor according to the channel's [`CommandRetryPolicy`],
+//! instead of the operator resending the command. Every attempt is recorded
+//! in the command's tracking record ([`CommandStatus`]), kept in
+//! `comsrv:{channel_id}:CMD` keyed by command ID.
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::debug;
+use voltage_model::KeySpaceConfig;
+use voltage_rtdb::Rtdb;
+
+use super::dead_letter::prune_hash;
+use super::trigger::CommandStatus;
+use crate::error::Result;
+
+/// How long tracking records are kept
+const TRACKING_RETENTION: Duration = Duration::from_secs(3600);
+
+/// Upper bound of tracking records per channel
+const MAX_TRACKED_COMMANDS: usize = 500;
+
+/// Minimum time between two retention sweeps triggered by `record`
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Retry policy for control and adjustment writes
+///
+/// Read from the channel parameter `command_retry` (absent = no retry):
+/// ```yaml
+/// command_retry:
+///   attempts: 3                 # total attempts, including the first
+///   interval_ms: 500
+///   abort_on_value_change: true # stop once a newer value was written to the point
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandRetryPolicy {
+    pub max_attempts: u32,
+    pub interval: Duration,
+    pub abort_on_value_change: bool,
+}
+
+impl Default for CommandRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            interval: Duration::from_millis(500),
+            abort_on_value_change: true,
+        }
+    }
+}
+
+impl CommandRetryPolicy {
+    /// Parse the retry policy from channel parameters, falling back to defaults
+    pub fn from_parameters(params: &HashMap<String, serde_json::Value>) -> Self {
+        let defaults = Self::default();
+        let Some(obj) = params.get("command_retry").and_then(|v| v.as_object()) else {
+            return defaults;
+        };
+
+        Self {
+            max_attempts: obj
+                .get("attempts")
+                .and_then(|v| v.as_u64())
+                .filter(|n| *n > 0)
+                .map(|n| n.min(u32::MAX as u64) as u32)
+                .unwrap_or(defaults.max_attempts),
+            interval: obj
+                .get("interval_ms")
+                .and_then(|v| v.as_u64())
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.interval),
+            abort_on_value_change: obj
+                .get("abort_on_value_change")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(defaults.abort_on_value_change),
+        }
+    }
+}
+
+/// Whether a protocol error is worth retrying
+///
+/// Only link-level failures are retried; a rejected write or an invalid
+/// point will fail again.
+pub fn is_transient_error(error: &str) -> bool {
+    let error = error.to_lowercase();
+    [
+        "timeout",
+        "timed out",
+        "connection",
+        "not connected",
+        "disconnected",
+        "broken pipe",
+        "reset",
+        "busy",
+        "i/o",
+        "io error",
+    ]
+    .iter()
+    .any(|pattern| error.contains(pattern))
+}
+
+/// One write attempt of a command
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandAttempt {
+    /// 1-based attempt number
+    pub attempt: u32,
+    /// When the attempt finished (milliseconds)
+    pub at: i64,
+    /// `None` if the write succeeded
+    pub error: Option<String>,
+}
+
+/// Tracking records of a channel's commands in the RTDB
+pub struct CommandTracker<R: Rtdb> {
+    rtdb: Arc<R>,
+    key: String,
+    last_prune: Option<Instant>,
+}
+
+impl<R: Rtdb> CommandTracker<R> {
+    pub fn new(rtdb: Arc<R>, channel_id: u32) -> Self {
+        Self {
+            rtdb,
+            key: KeySpaceConfig::production_cached().command_status_key(channel_id),
+            last_prune: None,
+        }
+    }
+
+    /// Store the current state of a command; sweeps old records at most once a minute
+    pub async fn record(&mut self, status: &CommandStatus) -> Result<()> {
+        let payload = serde_json::to_vec(status)?;
+        self.rtdb
+            .hash_set(&self.key, &status.command_id, Bytes::from(payload))
+            .await?;
+
+        if self
+            .last_prune
+            .is_none_or(|at| at.elapsed() >= PRUNE_INTERVAL)
+        {
+            self.last_prune = Some(Instant::now());
+            let cutoff =
+                chrono::Utc::now().timestamp_millis() - TRACKING_RETENTION.as_millis() as i64;
+            let removed = prune_hash(
+                self.rtdb.as_ref(),
+                &self.key,
+                cutoff,
+                MAX_TRACKED_COMMANDS,
+                |payload| {
+                    serde_json::from_slice::<CommandStatus>(payload)
+                        .ok()
+                        .map(|status| status.timestamp)
+                },
+            )
+            .await?;
+            if removed > 0 {
+                debug!("{} pruned {}", self.key, removed);
+            }
+        }
+        Ok(())
+    }
+
+    /// Tracking record of a command
+    pub async fn get(&self, command_id: &str) -> Result<Option<CommandStatus>> {
+        match self.rtdb.hash_get(&self.key, command_id).await? {
+            Some(payload) => Ok(Some(serde_json::from_slice(&payload)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_policy_from_parameters() {
+        let mut params = HashMap::new();
+        assert_eq!(
+            CommandRetryPolicy::from_parameters(&params),
+            CommandRetryPolicy::default()
+        );
+
+        params.insert(
+            "command_retry".to_string(),
+            serde_json::json!({"attempts": 3, "interval_ms": 200, "abort_on_value_change": false}),
+        );
+        let policy = CommandRetryPolicy::from_parameters(&params);
+        assert_eq!(policy.max_attempts, 3);
+        assert_eq!(policy.interval, Duration::from_millis(200));
+        assert!(!policy.abort_on_value_change);
+
+        assert!(is_transient_error("Modbus: Connection timed out"));
+        assert!(!is_transient_error("Illegal data address"));
+    }
+}
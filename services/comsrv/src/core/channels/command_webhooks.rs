@@ -0,0 +1,503 @@
+//! Command result webhooks
+//!
+//! External dispatch systems register a webhook per API client instead of
+//! polling the channel hash for the outcome of their commands. A write sent
+//! with an `X-Client-Id` header is remembered per point; when the command
+//! executor finishes the command, the client's webhook receives a
+//! [`CommandWebhookEvent`] with the outcome, the value read back from the
+//! point hash and the latency since the API accepted the write.
+//!
+//! Registrations are stored in the `command_webhooks` table and held in a
+//! process-wide registry ([`CommandWebhooks::global`]) that the executors of
+//! all channels report to. Delivery runs in background tasks and never
+//! delays the command executor.
+
+use common::webhook::{WebhookAuth, WebhookTarget, DELIVERY_ATTEMPTS};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tracing::{debug, warn};
+use voltage_model::PointType;
+
+use super::trigger::CommandStatus;
+use crate::error::{ComSrvError, Result};
+
+/// Header naming the API client that submitted a write
+pub const CLIENT_ID_HEADER: &str = "x-client-id";
+
+/// Pending writes older than this are dropped (the command never completed)
+const PENDING_TTL: Duration = Duration::from_secs(600);
+
+/// Webhook registration of an API client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct CommandWebhook {
+    /// API client name, matched against the `X-Client-Id` header
+    pub client: String,
+    /// Callback URL (POST, JSON body)
+    pub url: String,
+    /// Sent as `Authorization: Bearer {token}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Only these channels (all when absent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channels: Option<Vec<u32>>,
+    #[serde(default = "common::serde_helpers::bool_true")]
+    pub on_success: bool,
+    /// Failed and aborted commands
+    #[serde(default = "common::serde_helpers::bool_true")]
+    pub on_failure: bool,
+    /// Also notify commands submitted by other clients or routed from modsrv
+    #[serde(default)]
+    pub all_commands: bool,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u32,
+    #[serde(default = "common::serde_helpers::bool_true")]
+    pub enabled: bool,
+}
+
+fn default_timeout_ms() -> u32 {
+    3000
+}
+
+impl CommandWebhook {
+    /// Check the registration before storing it
+    pub fn validate(&self) -> Result<()> {
+        if self.client.trim().is_empty() {
+            return Err(ComSrvError::ValidationError(
+                "Webhook client must not be empty".to_string(),
+            ));
+        }
+        if !(self.url.starts_with("http://") || self.url.starts_with("https://")) {
+            return Err(ComSrvError::ValidationError(format!(
+                "Webhook URL must be http(s): {}",
+                self.url
+            )));
+        }
+        if self.timeout_ms == 0 {
+            return Err(ComSrvError::ValidationError(
+                "Webhook timeout_ms must be positive".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether this webhook wants the outcome of a command
+    ///
+    /// `submitted_by` is the client that sent the write, if any.
+    pub fn wants(&self, channel_id: u32, submitted_by: Option<&str>, succeeded: bool) -> bool {
+        self.enabled
+            && (self.all_commands || submitted_by == Some(self.client.as_str()))
+            && self
+                .channels
+                .as_ref()
+                .is_none_or(|channels| channels.contains(&channel_id))
+            && if succeeded {
+                self.on_success
+            } else {
+                self.on_failure
+            }
+    }
+}
+
+/// Payload posted to a webhook when a command completes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct CommandWebhookEvent {
+    /// Client that submitted the write (absent for commands from other sources)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client: Option<String>,
+    pub channel_id: u32,
+    pub command_id: String,
+    /// "C" or "A"
+    pub point_type: String,
+    pub point_id: u32,
+    /// Commanded value
+    pub value: f64,
+    /// success, failed or aborted
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Point value in the channel hash once the command finished
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_back: Option<f64>,
+    /// From API acceptance to completion (absent for commands from other sources)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// Write attempts made by the retry policy
+    pub attempts: usize,
+    /// Completion time (milliseconds)
+    pub timestamp: i64,
+}
+
+/// Write accepted from an API client, waiting for its command to complete
+#[derive(Debug, Clone)]
+struct PendingWrite {
+    client: String,
+    accepted: Instant,
+}
+
+/// Process-wide webhook registry
+pub struct CommandWebhooks {
+    webhooks: RwLock<HashMap<String, CommandWebhook>>,
+    /// (channel_id, point_type, point_id) -> latest write from an API client
+    pending: RwLock<HashMap<(u32, PointType, u32), PendingWrite>>,
+    /// Fast path for executors: false while no webhook is enabled
+    active: AtomicBool,
+    http: reqwest::Client,
+}
+
+impl Default for CommandWebhooks {
+    fn default() -> Self {
+        Self {
+            webhooks: RwLock::new(HashMap::new()),
+            pending: RwLock::new(HashMap::new()),
+            active: AtomicBool::new(false),
+            http: reqwest::Client::new(),
+        }
+    }
+}
+
+impl CommandWebhooks {
+    /// Registry shared by the API and all channel executors
+    pub fn global() -> &'static Arc<CommandWebhooks> {
+        static GLOBAL: OnceLock<Arc<CommandWebhooks>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(CommandWebhooks::default()))
+    }
+
+    /// Whether any webhook is enabled
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    /// Load registrations from SQLite, replacing the registry
+    pub async fn load(&self, pool: &sqlx::SqlitePool) -> Result<usize> {
+        type Row = (
+            String,
+            String,
+            Option<String>,
+            Option<String>,
+            bool,
+            bool,
+            bool,
+            u32,
+            bool,
+        );
+        let rows: Vec<Row> = sqlx::query_as(
+            "SELECT client, url, token, channels, on_success, on_failure, all_commands, \
+             timeout_ms, enabled FROM command_webhooks",
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(storage_error)?;
+
+        let mut webhooks = HashMap::new();
+        for (
+            client,
+            url,
+            token,
+            channels,
+            on_success,
+            on_failure,
+            all_commands,
+            timeout_ms,
+            enabled,
+        ) in rows
+        {
+            let channels = match channels {
+                Some(json) => match serde_json::from_str(&json) {
+                    Ok(channels) => Some(channels),
+                    Err(e) => {
+                        warn!("Webhook '{}' has invalid channels, skipped: {}", client, e);
+                        continue;
+                    },
+                },
+                None => None,
+            };
+            webhooks.insert(
+                client.clone(),
+                CommandWebhook {
+                    client,
+                    url,
+                    token,
+                    channels,
+                    on_success,
+                    on_failure,
+                    all_commands,
+                    timeout_ms,
+                    enabled,
+                },
+            );
+        }
+
+        let count = webhooks.len();
+        *self.webhooks.write().await = webhooks;
+        self.refresh_active().await;
+        Ok(count)
+    }
+
+    /// All registrations, sorted by client
+    pub async fn list(&self) -> Vec<CommandWebhook> {
+        let mut webhooks: Vec<_> = self.webhooks.read().await.values().cloned().collect();
+        webhooks.sort_by(|a, b| a.client.cmp(&b.client));
+        webhooks
+    }
+
+    /// Create or replace a registration (SQLite and registry)
+    pub async fn upsert(&self, pool: &sqlx::SqlitePool, webhook: CommandWebhook) -> Result<()> {
+        webhook.validate()?;
+        let channels = webhook
+            .channels
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
+        sqlx::query(
+            "INSERT INTO command_webhooks \
+             (client, url, token, channels, on_success, on_failure, all_commands, timeout_ms, enabled) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?) \
+             ON CONFLICT(client) DO UPDATE SET url = excluded.url, token = excluded.token, \
+             channels = excluded.channels, on_success = excluded.on_success, \
+             on_failure = excluded.on_failure, all_commands = excluded.all_commands, \
+             timeout_ms = excluded.timeout_ms, enabled = excluded.enabled, \
+             updated_at = CURRENT_TIMESTAMP",
+        )
+        .bind(&webhook.client)
+        .bind(&webhook.url)
+        .bind(&webhook.token)
+        .bind(channels)
+        .bind(webhook.on_success)
+        .bind(webhook.on_failure)
+        .bind(webhook.all_commands)
+        .bind(webhook.timeout_ms)
+        .bind(webhook.enabled)
+        .execute(pool)
+        .await
+        .map_err(storage_error)?;
+
+        self.webhooks
+            .write()
+            .await
+            .insert(webhook.client.clone(), webhook);
+        self.refresh_active().await;
+        Ok(())
+    }
+
+    /// Remove a registration; returns false if the client had none
+    pub async fn remove(&self, pool: &sqlx::SqlitePool, client: &str) -> Result<bool> {
+        sqlx::query("DELETE FROM command_webhooks WHERE client = ?")
+            .bind(client)
+            .execute(pool)
+            .await
+            .map_err(storage_error)?;
+        let removed = self.webhooks.write().await.remove(client).is_some();
+        self.refresh_active().await;
+        Ok(removed)
+    }
+
+    async fn refresh_active(&self) {
+        let active = self.webhooks.read().await.values().any(|w| w.enabled);
+        self.active.store(active, Ordering::Relaxed);
+    }
+
+    /// Remember a control/adjustment write accepted from an API client
+    ///
+    /// A later write to the same point replaces it: the executor reports
+    /// the outcome of the newest command.
+    pub async fn track(&self, client: &str, channel_id: u32, point_type: PointType, point_id: u32) {
+        if !self.is_active() {
+            return;
+        }
+        let mut pending = self.pending.write().await;
+        pending.retain(|_, write| write.accepted.elapsed() < PENDING_TTL);
+        pending.insert(
+            (channel_id, point_type, point_id),
+            PendingWrite {
+                client: client.to_string(),
+                accepted: Instant::now(),
+            },
+        );
+    }
+
+    /// Report a finished command and notify the webhooks that want it
+    ///
+    /// Called by the command executor for every final status.
+    pub async fn completed(
+        &self,
+        channel_id: u32,
+        point_type: PointType,
+        point_id: u32,
+        value: f64,
+        status: &CommandStatus,
+        read_back: Option<f64>,
+    ) {
+        let pending = self
+            .pending
+            .write()
+            .await
+            .remove(&(channel_id, point_type, point_id));
+
+        let event = CommandWebhookEvent {
+            client: pending.as_ref().map(|p| p.client.clone()),
+            channel_id,
+            command_id: status.command_id.clone(),
+            point_type: point_type.as_str().to_string(),
+            point_id,
+            value,
+            status: status.status.clone(),
+            error: status.error.clone(),
+            read_back,
+            latency_ms: pending
+                .as_ref()
+                .map(|p| p.accepted.elapsed().as_millis() as u64),
+            attempts: status.attempts.len(),
+            timestamp: status.timestamp,
+        };
+
+        let succeeded = status.status == "success";
+        let targets: Vec<CommandWebhook> = self
+            .webhooks
+            .read()
+            .await
+            .values()
+            .filter(|w| w.wants(channel_id, event.client.as_deref(), succeeded))
+            .cloned()
+            .collect();
+
+        for webhook in targets {
+            let http = self.http.clone();
+            let event = event.clone();
+            tokio::spawn(async move {
+                deliver(&http, &webhook, &event).await;
+            });
+        }
+    }
+}
+
+fn storage_error(e: sqlx::Error) -> ComSrvError {
+    ComSrvError::StorageError(format!("Command webhooks: {}", e))
+}
+
+/// POST an event, retrying failed deliveries with backoff
+async fn deliver(http: &reqwest::Client, webhook: &CommandWebhook, event: &CommandWebhookEvent) {
+    let body = match serde_json::to_vec(event) {
+        Ok(body) => body,
+        Err(e) => {
+            warn!(
+                "Webhook '{}' event {} not serializable: {}",
+                webhook.client, event.command_id, e
+            );
+            return;
+        },
+    };
+    let target = WebhookTarget {
+        url: &webhook.url,
+        timeout: Duration::from_millis(webhook.timeout_ms as u64),
+        auth: webhook
+            .token
+            .as_deref()
+            .map_or(WebhookAuth::None, WebhookAuth::Bearer),
+    };
+
+    match common::webhook::deliver(http, &target, &body).await {
+        Ok(()) => debug!(
+            "Webhook '{}' notified: Ch{} {} {}",
+            webhook.client, event.channel_id, event.command_id, event.status
+        ),
+        Err(error) => warn!(
+            "Webhook '{}' delivery of {} failed after {} attempts: {}",
+            webhook.client, event.command_id, DELIVERY_ATTEMPTS, error
+        ),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    fn webhook(client: &str) -> CommandWebhook {
+        serde_json::from_value(serde_json::json!({
+            "client": client,
+            "url": "http://dispatch.local/callback"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_webhook_filters() {
+        let mut hook = webhook("dispatch");
+        assert!(hook.validate().is_ok());
+        assert!(hook.wants(1001, Some("dispatch"), true));
+        assert!(hook.wants(1001, Some("dispatch"), false));
+        assert!(!hook.wants(1001, Some("scada"), true));
+        assert!(!hook.wants(1001, None, true));
+
+        hook.channels = Some(vec![1002]);
+        assert!(!hook.wants(1001, Some("dispatch"), true));
+
+        hook.channels = None;
+        hook.on_success = false;
+        hook.all_commands = true;
+        assert!(!hook.wants(1001, None, true));
+        assert!(hook.wants(1001, None, false));
+
+        hook.enabled = false;
+        assert!(!hook.wants(1001, None, false));
+
+        hook.url = "ftp://x".to_string();
+        assert!(hook.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_registry_roundtrip_and_tracking() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::core::config::migrations::migrator()
+            .run(&pool)
+            .await
+            .unwrap();
+
+        let registry = CommandWebhooks::default();
+        assert!(!registry.is_active());
+
+        let mut hook = webhook("dispatch");
+        hook.channels = Some(vec![1001]);
+        hook.token = Some("secret".to_string());
+        registry.upsert(&pool, hook.clone()).await.unwrap();
+        assert!(registry.is_active());
+
+        let reloaded = CommandWebhooks::default();
+        assert_eq!(reloaded.load(&pool).await.unwrap(), 1);
+        assert_eq!(reloaded.list().await, vec![hook]);
+
+        registry
+            .track("dispatch", 1001, PointType::Control, 3)
+            .await;
+        assert!(registry
+            .pending
+            .read()
+            .await
+            .contains_key(&(1001, PointType::Control, 3)));
+
+        let status = CommandStatus {
+            command_id: "direct_1001_1".to_string(),
+            status: "success".to_string(),
+            result: None,
+            error: None,
+            timestamp: 1,
+            attempts: Vec::new(),
+        };
+        registry
+            .completed(1001, PointType::Control, 3, 1.0, &status, Some(1.0))
+            .await;
+        assert!(registry.pending.read().await.is_empty());
+
+        assert!(registry.remove(&pool, "dispatch").await.unwrap());
+        assert!(!registry.is_active());
+        assert!(!registry.remove(&pool, "dispatch").await.unwrap());
+    }
+}
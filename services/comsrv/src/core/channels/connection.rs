@@ -0,0 +1,485 @@
+//! Channel connection state machine
+//!
+//! Connection state is driven by typed [`ConnectionEvent`]s instead of being
+//! flipped ad hoc. The transition table lives in [`ConnectionState::next`];
+//! events that are not valid in the current state are rejected and leave the
+//! state unchanged.
+//!
+//! ```text
+//!                 ConnectRequested            ConnectSucceeded
+//!  Disconnected ───────────────────▶ Connecting ────────────────▶ Connected
+//!       ▲  ▲                             │                         │    ▲
+//!       │  └────── ConnectFailed ────────┘              PollFailed │    │ PollSucceeded
+//!       │                                                          ▼    │
+//!       │◀────────────────────── LinkLost ─────────────────────  Degraded
+//!       │
+//!       └──────────── Closed ◀──── Closing ◀──── CloseRequested (any open state)
+//! ```
+//!
+//! A stalled heartbeat (see `heartbeat`) holds an open link in `Degraded`
+//! until it is restored, even while polls succeed; it does not count towards
+//! link loss.
+//!
+//! Every accepted transition is published as a [`ConnectionTransition`] on a
+//! broadcast channel for status reporting and reconnect handling.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+
+pub use super::types::ConnectionState;
+
+/// Consecutive failed polls after which a degraded link is considered lost
+pub const DEFAULT_LINK_LOSS_THRESHOLD: u32 = 3;
+
+/// Capacity of the transition broadcast channel
+const TRANSITION_CHANNEL_SIZE: usize = 32;
+
+/// Input to the connection state machine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ConnectionEvent {
+    /// A connect attempt is starting
+    ConnectRequested,
+    /// The connect attempt succeeded
+    ConnectSucceeded,
+    /// The connect attempt failed
+    ConnectFailed { error: String },
+    /// A poll returned data without failures
+    PollSucceeded,
+    /// A poll failed or returned partial failures
+    PollFailed { error: String },
+    /// Too many consecutive failures; the link is treated as down
+    LinkLost,
+    /// Heartbeat output or device heartbeat input stopped
+    HeartbeatStalled { error: String },
+    /// Heartbeat output and input are alive again
+    HeartbeatRestored,
+    /// Shutdown of the channel has started
+    CloseRequested,
+    /// The protocol client has been disconnected
+    Closed,
+}
+
+impl ConnectionEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ConnectRequested => "connect_requested",
+            Self::ConnectSucceeded => "connect_succeeded",
+            Self::ConnectFailed { .. } => "connect_failed",
+            Self::PollSucceeded => "poll_succeeded",
+            Self::PollFailed { .. } => "poll_failed",
+            Self::LinkLost => "link_lost",
+            Self::HeartbeatStalled { .. } => "heartbeat_stalled",
+            Self::HeartbeatRestored => "heartbeat_restored",
+            Self::CloseRequested => "close_requested",
+            Self::Closed => "closed",
+        }
+    }
+}
+
+impl ConnectionState {
+    /// Next state for `event`, or None if the event is invalid in this state
+    pub fn next(self, event: &ConnectionEvent) -> Option<ConnectionState> {
+        use ConnectionEvent as E;
+        use ConnectionState as S;
+
+        match (self, event) {
+            (S::Disconnected, E::ConnectRequested) => Some(S::Connecting),
+            // Protocols with internal reconnect can come back on their own
+            (S::Disconnected, E::PollSucceeded) => Some(S::Connected),
+            (S::Disconnected, E::PollFailed { .. }) => Some(S::Disconnected),
+
+            (S::Connecting, E::ConnectSucceeded) => Some(S::Connected),
+            (S::Connecting, E::ConnectFailed { .. }) => Some(S::Disconnected),
+
+            (S::Connected, E::PollSucceeded) => Some(S::Connected),
+            (S::Connected, E::PollFailed { .. }) => Some(S::Degraded),
+            (S::Connected, E::LinkLost) => Some(S::Disconnected),
+            // Explicit reconnect (e.g. after a configuration change)
+            (S::Connected | S::Degraded, E::ConnectRequested) => Some(S::Connecting),
+
+            (S::Degraded, E::PollSucceeded) => Some(S::Connected),
+            (S::Degraded, E::PollFailed { .. }) => Some(S::Degraded),
+            (S::Degraded, E::LinkLost) => Some(S::Disconnected),
+
+            (S::Connected | S::Degraded, E::HeartbeatStalled { .. }) => Some(S::Degraded),
+            (S::Connected | S::Degraded, E::HeartbeatRestored) => Some(S::Connected),
+
+            (S::Disconnected | S::Connecting | S::Connected | S::Degraded, E::CloseRequested) => {
+                Some(S::Closing)
+            },
+            (S::Closing, E::Closed) => Some(S::Disconnected),
+
+            _ => None,
+        }
+    }
+}
+
+/// Accepted state change, published to subscribers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionTransition {
+    pub channel_id: u32,
+    pub from: ConnectionState,
+    pub to: ConnectionState,
+    pub event: ConnectionEvent,
+    /// Unix timestamp in milliseconds
+    pub timestamp: i64,
+}
+
+/// Event that is not valid in the current state
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Ch{channel_id} invalid transition: {event} in state {state}")]
+pub struct InvalidTransition {
+    pub channel_id: u32,
+    pub state: ConnectionState,
+    pub event: &'static str,
+}
+
+#[derive(Debug)]
+struct MachineState {
+    state: ConnectionState,
+    consecutive_failures: u32,
+    last_error: Option<String>,
+    last_transition: i64,
+    heartbeat_stalled: bool,
+}
+
+/// Snapshot of a channel's connection for status reporting
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionSnapshot {
+    pub state: ConnectionState,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    /// Unix timestamp (ms) of the last state change
+    pub last_transition: i64,
+    /// Heartbeat output or input is stalled
+    #[serde(default)]
+    pub heartbeat_stalled: bool,
+}
+
+/// Per-channel connection state machine with transition events
+///
+/// Interior mutability lets the polling task, command executor and API
+/// handlers share one machine through `&self`.
+#[derive(Debug)]
+pub struct ConnectionStateMachine {
+    channel_id: u32,
+    link_loss_threshold: u32,
+    inner: Mutex<MachineState>,
+    events: broadcast::Sender<ConnectionTransition>,
+}
+
+impl ConnectionStateMachine {
+    pub fn new(channel_id: u32) -> Self {
+        Self::with_link_loss_threshold(channel_id, DEFAULT_LINK_LOSS_THRESHOLD)
+    }
+
+    pub fn with_link_loss_threshold(channel_id: u32, link_loss_threshold: u32) -> Self {
+        let (events, _) = broadcast::channel(TRANSITION_CHANNEL_SIZE);
+        Self {
+            channel_id,
+            link_loss_threshold: link_loss_threshold.max(1),
+            inner: Mutex::new(MachineState {
+                state: ConnectionState::Disconnected,
+                consecutive_failures: 0,
+                last_error: None,
+                last_transition: chrono::Utc::now().timestamp_millis(),
+                heartbeat_stalled: false,
+            }),
+            events,
+        }
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.lock().state
+    }
+
+    pub fn snapshot(&self) -> ConnectionSnapshot {
+        let inner = self.lock();
+        ConnectionSnapshot {
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+            last_error: inner.last_error.clone(),
+            last_transition: inner.last_transition,
+            heartbeat_stalled: inner.heartbeat_stalled,
+        }
+    }
+
+    /// Receive every accepted transition
+    pub fn subscribe(&self) -> broadcast::Receiver<ConnectionTransition> {
+        self.events.subscribe()
+    }
+
+    /// Apply an event; returns the transition if the state changed
+    ///
+    /// A failed poll in `Degraded` that reaches the link loss threshold is
+    /// followed by an automatic `LinkLost` transition. While the heartbeat is
+    /// stalled (or polls are still failing) an open link stays `Degraded`.
+    pub fn apply(
+        &self,
+        event: ConnectionEvent,
+    ) -> Result<Option<ConnectionTransition>, InvalidTransition> {
+        let mut inner = self.lock();
+        let from = inner.state;
+        let mut to = from.next(&event).ok_or(InvalidTransition {
+            channel_id: self.channel_id,
+            state: from,
+            event: event.name(),
+        })?;
+
+        match &event {
+            ConnectionEvent::PollFailed { error } | ConnectionEvent::ConnectFailed { error } => {
+                inner.consecutive_failures += 1;
+                inner.last_error = Some(error.clone());
+            },
+            ConnectionEvent::PollSucceeded | ConnectionEvent::ConnectSucceeded => {
+                inner.consecutive_failures = 0;
+            },
+            ConnectionEvent::HeartbeatStalled { error } => {
+                inner.heartbeat_stalled = true;
+                inner.last_error = Some(error.clone());
+            },
+            ConnectionEvent::HeartbeatRestored => {
+                inner.heartbeat_stalled = false;
+                if inner.consecutive_failures > 0 {
+                    to = ConnectionState::Degraded;
+                }
+            },
+            _ => {},
+        }
+        if inner.heartbeat_stalled && to == ConnectionState::Connected {
+            to = ConnectionState::Degraded;
+        }
+
+        let link_lost = to == ConnectionState::Degraded
+            && inner.consecutive_failures >= self.link_loss_threshold;
+        let transition = self.transition(&mut inner, from, to, event);
+
+        if link_lost {
+            let lost = self.transition(
+                &mut inner,
+                to,
+                ConnectionState::Disconnected,
+                ConnectionEvent::LinkLost,
+            );
+            return Ok(lost.or(transition));
+        }
+        Ok(transition)
+    }
+
+    fn transition(
+        &self,
+        inner: &mut MachineState,
+        from: ConnectionState,
+        to: ConnectionState,
+        event: ConnectionEvent,
+    ) -> Option<ConnectionTransition> {
+        if from == to {
+            return None;
+        }
+        inner.state = to;
+        inner.last_transition = chrono::Utc::now().timestamp_millis();
+
+        let transition = ConnectionTransition {
+            channel_id: self.channel_id,
+            from,
+            to,
+            event,
+            timestamp: inner.last_transition,
+        };
+        tracing::debug!(
+            "Ch{} connection {} -> {} ({})",
+            self.channel_id,
+            from,
+            to,
+            transition.event.name()
+        );
+        // No receivers is fine; status is also available via snapshot()
+        let _ = self.events.send(transition.clone());
+        Some(transition)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MachineState> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    const STATES: [ConnectionState; 5] = [
+        ConnectionState::Disconnected,
+        ConnectionState::Connecting,
+        ConnectionState::Connected,
+        ConnectionState::Degraded,
+        ConnectionState::Closing,
+    ];
+
+    fn events() -> Vec<ConnectionEvent> {
+        vec![
+            ConnectionEvent::ConnectRequested,
+            ConnectionEvent::ConnectSucceeded,
+            ConnectionEvent::ConnectFailed {
+                error: "refused".into(),
+            },
+            ConnectionEvent::PollSucceeded,
+            ConnectionEvent::PollFailed {
+                error: "timeout".into(),
+            },
+            ConnectionEvent::LinkLost,
+            ConnectionEvent::CloseRequested,
+            ConnectionEvent::Closed,
+            ConnectionEvent::HeartbeatStalled {
+                error: "stalled".into(),
+            },
+            ConnectionEvent::HeartbeatRestored,
+        ]
+    }
+
+    #[test]
+    fn test_transition_table() {
+        use ConnectionState::*;
+
+        // Rows follow STATES, columns follow events(); None = rejected
+        let expected: [[Option<ConnectionState>; 10]; 5] = [
+            [
+                Some(Connecting),
+                None,
+                None,
+                Some(Connected),
+                Some(Disconnected),
+                None,
+                Some(Closing),
+                None,
+                None,
+                None,
+            ],
+            [
+                None,
+                Some(Connected),
+                Some(Disconnected),
+                None,
+                None,
+                None,
+                Some(Closing),
+                None,
+                None,
+                None,
+            ],
+            [
+                Some(Connecting),
+                None,
+                None,
+                Some(Connected),
+                Some(Degraded),
+                Some(Disconnected),
+                Some(Closing),
+                None,
+                Some(Degraded),
+                Some(Connected),
+            ],
+            [
+                Some(Connecting),
+                None,
+                None,
+                Some(Connected),
+                Some(Degraded),
+                Some(Disconnected),
+                Some(Closing),
+                None,
+                Some(Degraded),
+                Some(Connected),
+            ],
+            [
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(Disconnected),
+                None,
+                None,
+            ],
+        ];
+
+        for (state, row) in STATES.iter().zip(expected) {
+            for (event, want) in events().iter().zip(row) {
+                assert_eq!(state.next(event), want, "{} + {}", state, event.name());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_machine_degrades_and_loses_link() {
+        let machine = ConnectionStateMachine::with_link_loss_threshold(5, 2);
+        let mut rx = machine.subscribe();
+
+        machine.apply(ConnectionEvent::ConnectRequested).unwrap();
+        machine.apply(ConnectionEvent::ConnectSucceeded).unwrap();
+        assert!(machine.state().is_connected());
+
+        // Closed is not valid while connected and leaves the state untouched
+        let err = machine.apply(ConnectionEvent::Closed).unwrap_err();
+        assert_eq!(err.state, ConnectionState::Connected);
+
+        let fail = || ConnectionEvent::PollFailed {
+            error: "timeout".into(),
+        };
+        machine.apply(fail()).unwrap();
+        assert_eq!(machine.state(), ConnectionState::Degraded);
+        assert!(machine.state().is_connected());
+
+        let lost = machine.apply(fail()).unwrap().unwrap();
+        assert_eq!(lost.event, ConnectionEvent::LinkLost);
+        assert_eq!(machine.state(), ConnectionState::Disconnected);
+        assert_eq!(machine.snapshot().consecutive_failures, 2);
+
+        let seen: Vec<ConnectionState> = std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|t| t.to)
+            .collect();
+        assert_eq!(
+            seen,
+            vec![
+                ConnectionState::Connecting,
+                ConnectionState::Connected,
+                ConnectionState::Degraded,
+                ConnectionState::Disconnected,
+            ]
+        );
+
+        machine.apply(ConnectionEvent::PollSucceeded).unwrap();
+        assert_eq!(machine.snapshot().consecutive_failures, 0);
+        assert_eq!(machine.state(), ConnectionState::Connected);
+    }
+
+    #[test]
+    fn test_heartbeat_stall_holds_degraded() {
+        let machine = ConnectionStateMachine::with_link_loss_threshold(6, 2);
+        machine.apply(ConnectionEvent::ConnectRequested).unwrap();
+        machine.apply(ConnectionEvent::ConnectSucceeded).unwrap();
+
+        machine
+            .apply(ConnectionEvent::HeartbeatStalled {
+                error: "device heartbeat frozen".into(),
+            })
+            .unwrap();
+        assert_eq!(machine.state(), ConnectionState::Degraded);
+
+        // Successful polls do not clear a stalled heartbeat, nor count as link loss
+        machine.apply(ConnectionEvent::PollSucceeded).unwrap();
+        machine.apply(ConnectionEvent::PollSucceeded).unwrap();
+        let snapshot = machine.snapshot();
+        assert_eq!(snapshot.state, ConnectionState::Degraded);
+        assert!(snapshot.heartbeat_stalled);
+        assert_eq!(snapshot.consecutive_failures, 0);
+
+        machine.apply(ConnectionEvent::HeartbeatRestored).unwrap();
+        assert_eq!(machine.state(), ConnectionState::Connected);
+        assert!(!machine.snapshot().heartbeat_stalled);
+    }
+}
@@ -0,0 +1,302 @@
+//! Dead letter queue for undeliverable commands
+//!
+//! When the command executor cannot write a control or adjustment to the
+//! device (link down, protocol error, rejected write), the command is kept in
+//! `comsrv:{channel_id}:DLQ` with the failure reason instead of only being
+//! logged. Operators list the entries over the API and retry or discard them.
+//!
+//! The queue is a hash keyed by entry ID, so a single entry can be removed
+//! atomically (`HDEL`) and two operators cannot retry the same entry twice.
+//! Entries older than the retention are dropped, and each channel keeps at
+//! most [`MAX_ENTRIES_PER_CHANNEL`] entries (oldest dropped first).
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+use voltage_model::{KeySpaceConfig, PointType};
+use voltage_rtdb::Rtdb;
+
+use super::types::ChannelCommand;
+use crate::error::Result;
+
+/// How long failed commands are kept
+pub const DEFAULT_RETENTION: Duration = Duration::from_secs(24 * 3600);
+
+/// Upper bound of entries per channel
+pub const MAX_ENTRIES_PER_CHANNEL: usize = 1000;
+
+/// Minimum time between two retention sweeps triggered by `push`
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Disambiguates entries failing within the same millisecond
+static ENTRY_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// A command that could not be delivered to the device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct DeadLetterEntry {
+    /// Entry ID (unique per channel)
+    pub id: String,
+    pub channel_id: u32,
+    pub command_id: String,
+    /// "C" (control) or "A" (adjustment)
+    pub point_type: String,
+    pub point_id: u32,
+    pub value: f64,
+    /// Command timestamp (seconds)
+    pub command_timestamp: i64,
+    /// When delivery failed (milliseconds)
+    pub failed_at: i64,
+    pub reason: String,
+}
+
+impl DeadLetterEntry {
+    /// Build an entry for a failed command
+    pub fn from_command(
+        channel_id: u32,
+        command: &ChannelCommand,
+        reason: impl Into<String>,
+    ) -> Self {
+        let (point_type, command_id, point_id, value, timestamp) = match command {
+            ChannelCommand::Control {
+                command_id,
+                point_id,
+                value,
+                timestamp,
+            } => (PointType::Control, command_id, point_id, value, timestamp),
+            ChannelCommand::Adjustment {
+                command_id,
+                point_id,
+                value,
+                timestamp,
+            } => (
+                PointType::Adjustment,
+                command_id,
+                point_id,
+                value,
+                timestamp,
+            ),
+        };
+        let failed_at = chrono::Utc::now().timestamp_millis();
+        let seq = ENTRY_SEQ.fetch_add(1, Ordering::Relaxed);
+
+        Self {
+            id: format!("{}-{}", failed_at, seq),
+            channel_id,
+            command_id: command_id.clone(),
+            point_type: point_type.as_str().to_string(),
+            point_id: *point_id,
+            value: *value,
+            command_timestamp: *timestamp,
+            failed_at,
+            reason: reason.into(),
+        }
+    }
+
+    /// Point type of the command (`None` for a corrupted entry)
+    pub fn point_type(&self) -> Option<PointType> {
+        match self.point_type.as_str() {
+            "C" => Some(PointType::Control),
+            "A" => Some(PointType::Adjustment),
+            _ => None,
+        }
+    }
+}
+
+/// Per-channel dead letter queue in the RTDB
+pub struct DeadLetterQueue<R: Rtdb> {
+    rtdb: Arc<R>,
+    key: String,
+    retention: Duration,
+    last_prune: Option<Instant>,
+}
+
+impl<R: Rtdb> DeadLetterQueue<R> {
+    pub fn new(rtdb: Arc<R>, channel_id: u32) -> Self {
+        Self {
+            rtdb,
+            key: KeySpaceConfig::production_cached().dead_letter_key(channel_id),
+            retention: DEFAULT_RETENTION,
+            last_prune: None,
+        }
+    }
+
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Store a failed command; sweeps expired entries at most once a minute
+    pub async fn push(&mut self, entry: &DeadLetterEntry) -> Result<()> {
+        let payload = serde_json::to_vec(entry)?;
+        self.rtdb
+            .hash_set(&self.key, &entry.id, Bytes::from(payload))
+            .await?;
+        debug!("DLQ {} += {} ({})", self.key, entry.id, entry.reason);
+
+        if self
+            .last_prune
+            .is_none_or(|at| at.elapsed() >= PRUNE_INTERVAL)
+        {
+            self.last_prune = Some(Instant::now());
+            self.prune().await?;
+        }
+        Ok(())
+    }
+
+    /// All entries within retention, oldest first
+    pub async fn list(&self) -> Result<Vec<DeadLetterEntry>> {
+        let cutoff = self.cutoff();
+        let mut entries: Vec<DeadLetterEntry> = self
+            .rtdb
+            .hash_get_all(&self.key)
+            .await?
+            .into_iter()
+            .filter_map(|(id, payload)| match serde_json::from_slice(&payload) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    warn!("DLQ {} entry {} unreadable: {}", self.key, id, e);
+                    None
+                },
+            })
+            .filter(|entry: &DeadLetterEntry| entry.failed_at >= cutoff)
+            .collect();
+        entries.sort_by(|a, b| a.failed_at.cmp(&b.failed_at).then(a.id.cmp(&b.id)));
+        Ok(entries)
+    }
+
+    /// Read an entry without removing it
+    pub async fn get(&self, id: &str) -> Result<Option<DeadLetterEntry>> {
+        match self.rtdb.hash_get(&self.key, id).await? {
+            Some(payload) => Ok(Some(serde_json::from_slice(&payload)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Remove an entry and return it for a retry
+    ///
+    /// Returns `None` if the entry does not exist or was taken concurrently.
+    pub async fn take(&self, id: &str) -> Result<Option<DeadLetterEntry>> {
+        let Some(payload) = self.rtdb.hash_get(&self.key, id).await? else {
+            return Ok(None);
+        };
+        if !self.rtdb.hash_del(&self.key, id).await? {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_slice(&payload)?))
+    }
+
+    /// Drop an entry; returns whether it existed
+    pub async fn discard(&self, id: &str) -> Result<bool> {
+        Ok(self.rtdb.hash_del(&self.key, id).await?)
+    }
+
+    /// Apply retention and the per-channel bound; returns removed entries
+    pub async fn prune(&self) -> Result<usize> {
+        let removed = prune_hash(
+            self.rtdb.as_ref(),
+            &self.key,
+            self.cutoff(),
+            MAX_ENTRIES_PER_CHANNEL,
+            |payload| {
+                serde_json::from_slice::<DeadLetterEntry>(payload)
+                    .ok()
+                    .map(|entry| entry.failed_at)
+            },
+        )
+        .await?;
+        if removed > 0 {
+            debug!("DLQ {} pruned {}", self.key, removed);
+        }
+        Ok(removed)
+    }
+
+    fn cutoff(&self) -> i64 {
+        chrono::Utc::now().timestamp_millis() - self.retention.as_millis() as i64
+    }
+}
+
+/// Remove hash fields older than `cutoff` (ms) and all but the newest `max_entries`
+///
+/// `stamp` extracts the timestamp of a field value; unreadable values count
+/// as expired.
+pub(crate) async fn prune_hash<R: Rtdb>(
+    rtdb: &R,
+    key: &str,
+    cutoff: i64,
+    max_entries: usize,
+    stamp: impl Fn(&[u8]) -> Option<i64>,
+) -> Result<usize> {
+    let mut fields: Vec<(String, i64)> = rtdb
+        .hash_get_all(key)
+        .await?
+        .into_iter()
+        .map(|(field, payload)| {
+            let at = stamp(&payload).unwrap_or(i64::MIN);
+            (field, at)
+        })
+        .collect();
+    fields.sort_by_key(|(_, at)| std::cmp::Reverse(*at));
+
+    let stale: Vec<String> = fields
+        .into_iter()
+        .enumerate()
+        .filter(|(rank, (_, at))| *at < cutoff || *rank >= max_entries)
+        .map(|(_, (field, _))| field)
+        .collect();
+    if stale.is_empty() {
+        return Ok(0);
+    }
+    Ok(rtdb.hash_del_many(key, &stale).await?)
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    fn control(point_id: u32) -> ChannelCommand {
+        ChannelCommand::Control {
+            command_id: format!("cmd_{}", point_id),
+            point_id,
+            value: 1.0,
+            timestamp: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_push_take_and_retention() {
+        let rtdb = Arc::new(voltage_rtdb::MemoryRtdb::new());
+        let mut dlq = DeadLetterQueue::new(Arc::clone(&rtdb), 1001);
+
+        let first = DeadLetterEntry::from_command(1001, &control(1), "link down");
+        let second = DeadLetterEntry::from_command(1001, &control(2), "write rejected");
+        dlq.push(&first).await.unwrap();
+        dlq.push(&second).await.unwrap();
+
+        let entries = dlq.list().await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].point_type(), Some(PointType::Control));
+        assert_eq!(entries[0].reason, "link down");
+
+        // An entry can only be taken once
+        let taken = dlq.take(&first.id).await.unwrap().unwrap();
+        assert_eq!(taken.point_id, 1);
+        assert!(dlq.take(&first.id).await.unwrap().is_none());
+
+        // Expired entries are hidden and pruned
+        let mut old = DeadLetterEntry::from_command(1001, &control(3), "timeout");
+        old.failed_at -= DEFAULT_RETENTION.as_millis() as i64 + 1;
+        dlq.push(&old).await.unwrap();
+        assert_eq!(dlq.list().await.unwrap().len(), 1);
+        assert_eq!(dlq.prune().await.unwrap(), 1);
+
+        assert!(dlq.discard(&second.id).await.unwrap());
+        assert!(dlq.list().await.unwrap().is_empty());
+    }
+}
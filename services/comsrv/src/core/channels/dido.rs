@@ -0,0 +1,610 @@
+//! Debounce, pulse counting and timed pulse outputs for DI/DO channels
+//!
+//! [`DidoRuntime`] wraps the GPIO runtime and adds, per point mapping:
+//!
+//! - `debounce_ms` (signal points): a new input level is published only
+//!   after it has been read unchanged for that long
+//! - counters (telemetry points with a `gpio_number`): count the edges of
+//!   that input, `count_edge` is `rising` (default), `falling` or `both`;
+//!   `debounce_ms` applies before counting
+//! - `pulse_ms` (control points): an ON command drives the output for that
+//!   long and then switches it off; OFF ends a running pulse early
+//!
+//! Inputs are sampled at the channel's `poll_interval_ms`, so debounce times
+//! and the shortest countable pulse are bounded by it. Output pulses are
+//! timed independently of polling.
+//!
+//! ```json
+//! {"gpio_number": 496, "debounce_ms": 50}
+//! {"gpio_number": 497, "count_edge": "falling"}
+//! {"gpio_number": 504, "pulse_ms": 500}
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use igw::core::traits::{DataEventReceiver, Diagnostics, PollResult};
+use igw::gateway::ChannelRuntime;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+use voltage_model::PointType;
+
+use crate::core::config::RuntimeChannelConfig;
+
+/// Edges a pulse counter counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CountEdge {
+    #[default]
+    Rising,
+    Falling,
+    Both,
+}
+
+impl CountEdge {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "rising" | "" => Some(Self::Rising),
+            "falling" => Some(Self::Falling),
+            "both" => Some(Self::Both),
+            _ => None,
+        }
+    }
+
+    fn counts(self, from: bool, to: bool) -> bool {
+        match self {
+            Self::Rising => !from && to,
+            Self::Falling => from && !to,
+            Self::Both => from != to,
+        }
+    }
+}
+
+/// Debounced level of one input
+#[derive(Debug, Clone)]
+pub struct InputFilter {
+    debounce: Duration,
+    stable: Option<bool>,
+    /// Differing level and when it was first read
+    candidate: Option<(bool, Instant)>,
+}
+
+impl InputFilter {
+    pub fn new(debounce: Duration) -> Self {
+        Self {
+            debounce,
+            stable: None,
+            candidate: None,
+        }
+    }
+
+    /// Feed a sample and return the debounced level
+    ///
+    /// The first sample is taken as is.
+    pub fn sample(&mut self, level: bool, now: Instant) -> bool {
+        let Some(stable) = self.stable else {
+            self.stable = Some(level);
+            return level;
+        };
+        if level == stable {
+            self.candidate = None;
+            return stable;
+        }
+        let since = match self.candidate {
+            Some((candidate, since)) if candidate == level => since,
+            _ => {
+                self.candidate = Some((level, now));
+                now
+            },
+        };
+        if now.duration_since(since) >= self.debounce {
+            self.stable = Some(level);
+            self.candidate = None;
+            return level;
+        }
+        stable
+    }
+}
+
+/// Edge counter on a debounced input
+#[derive(Debug, Clone)]
+pub struct PulseCounter {
+    filter: InputFilter,
+    edge: CountEdge,
+    count: u64,
+}
+
+impl PulseCounter {
+    pub fn new(debounce: Duration, edge: CountEdge) -> Self {
+        Self {
+            filter: InputFilter::new(debounce),
+            edge,
+            count: 0,
+        }
+    }
+
+    /// Feed a sample and return the count
+    pub fn sample(&mut self, level: bool, now: Instant) -> u64 {
+        let previous = self.filter.stable;
+        let level = self.filter.sample(level, now);
+        if previous.is_some_and(|previous| self.edge.counts(previous, level)) {
+            self.count += 1;
+        }
+        self.count
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+/// DI/DO behaviour of a channel's points, keyed by internal point ID
+#[derive(Debug, Clone, Default)]
+pub struct DidoPoints {
+    /// Signal inputs with a debounce time
+    pub debounce: HashMap<u32, Duration>,
+    /// Telemetry counters: debounce time and counted edges
+    pub counters: HashMap<u32, (Duration, CountEdge)>,
+    /// Control outputs driven as pulses
+    pub pulses: HashMap<u32, Duration>,
+}
+
+impl DidoPoints {
+    /// Collect the DI/DO settings of the point mappings
+    pub fn from_runtime_config(config: &RuntimeChannelConfig) -> Self {
+        let mut points = Self::default();
+        for pt in &config.signal_points {
+            let mapping = parse_mapping(pt.base.protocol_mappings.as_deref());
+            let debounce = duration_ms(&mapping, "debounce_ms");
+            if !debounce.is_zero() {
+                let internal_id = PointType::Signal.to_internal_id(pt.base.point_id);
+                points.debounce.insert(internal_id, debounce);
+            }
+        }
+        for pt in &config.telemetry_points {
+            let mapping = parse_mapping(pt.base.protocol_mappings.as_deref());
+            if mapping.get("gpio_number").is_none() {
+                continue;
+            }
+            let edge = match mapping.get("count_edge").and_then(|v| v.as_str()) {
+                None => CountEdge::Rising,
+                Some(name) => CountEdge::parse(name).unwrap_or_else(|| {
+                    warn!(
+                        "T{}: unknown count_edge '{}', counting rising edges",
+                        pt.base.point_id, name
+                    );
+                    CountEdge::Rising
+                }),
+            };
+            let internal_id = PointType::Telemetry.to_internal_id(pt.base.point_id);
+            points
+                .counters
+                .insert(internal_id, (duration_ms(&mapping, "debounce_ms"), edge));
+        }
+        for pt in &config.control_points {
+            let mapping = parse_mapping(pt.base.protocol_mappings.as_deref());
+            let pulse = duration_ms(&mapping, "pulse_ms");
+            if !pulse.is_zero() {
+                let internal_id = PointType::Control.to_internal_id(pt.base.point_id);
+                points.pulses.insert(internal_id, pulse);
+            }
+        }
+        points
+    }
+
+    /// No point needs more than plain level reads and writes
+    pub fn is_plain(&self) -> bool {
+        self.debounce.is_empty() && self.counters.is_empty() && self.pulses.is_empty()
+    }
+}
+
+fn parse_mapping(mapping: Option<&str>) -> serde_json::Value {
+    mapping
+        .and_then(|m| serde_json::from_str(m).ok())
+        .unwrap_or(serde_json::Value::Null)
+}
+
+fn duration_ms(mapping: &serde_json::Value, key: &str) -> Duration {
+    Duration::from_millis(mapping.get(key).and_then(|v| v.as_u64()).unwrap_or(0))
+}
+
+type SharedRuntime = Arc<Mutex<Box<dyn ChannelRuntime>>>;
+
+/// GPIO runtime with debounced inputs, pulse counters and pulse outputs
+///
+/// The inner runtime is shared with the timers ending output pulses.
+pub struct DidoRuntime {
+    id: u32,
+    name: String,
+    protocol: String,
+    inner: SharedRuntime,
+    inputs: HashMap<u32, InputFilter>,
+    counters: HashMap<u32, PulseCounter>,
+    pulses: HashMap<u32, Duration>,
+    /// Running output pulses by internal point ID
+    pending: HashMap<u32, JoinHandle<()>>,
+}
+
+impl DidoRuntime {
+    pub fn new(inner: Box<dyn ChannelRuntime>, points: DidoPoints) -> Self {
+        Self {
+            id: inner.id(),
+            name: inner.name().to_string(),
+            protocol: inner.protocol().to_string(),
+            inner: Arc::new(Mutex::new(inner)),
+            inputs: points
+                .debounce
+                .into_iter()
+                .map(|(id, debounce)| (id, InputFilter::new(debounce)))
+                .collect(),
+            counters: points
+                .counters
+                .into_iter()
+                .map(|(id, (debounce, edge))| (id, PulseCounter::new(debounce, edge)))
+                .collect(),
+            pulses: points.pulses,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Start a pulse on `internal_id`, replacing a running one
+    fn schedule_off(&mut self, internal_id: u32, width: Duration) {
+        self.cancel_pulse(internal_id);
+        let inner = Arc::clone(&self.inner);
+        let handle = tokio::spawn(async move {
+            tokio::time::sleep(width).await;
+            let mut runtime = inner.lock().await;
+            match runtime.write_control(&[(internal_id, 0.0)]).await {
+                Ok(_) => debug!("Ch{} pulse on {} ended", runtime.id(), internal_id),
+                Err(e) => warn!(
+                    "Ch{} pulse on {} failed to switch off: {}",
+                    runtime.id(),
+                    internal_id,
+                    e
+                ),
+            }
+        });
+        self.pending.insert(internal_id, handle);
+    }
+
+    fn cancel_pulse(&mut self, internal_id: u32) -> bool {
+        match self.pending.remove(&internal_id) {
+            Some(handle) => {
+                let running = !handle.is_finished();
+                handle.abort();
+                running
+            },
+            None => false,
+        }
+    }
+}
+
+#[async_trait]
+impl ChannelRuntime for DidoRuntime {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn protocol(&self) -> &str {
+        &self.protocol
+    }
+
+    fn is_event_driven(&self) -> bool {
+        false
+    }
+
+    async fn connect(&mut self) -> igw::Result<()> {
+        self.inner.lock().await.connect().await
+    }
+
+    /// Switch off outputs in the middle of a pulse before disconnecting
+    async fn disconnect(&mut self) -> igw::Result<()> {
+        let interrupted: Vec<u32> = self.pending.keys().copied().collect();
+        let interrupted: Vec<(u32, f64)> = interrupted
+            .into_iter()
+            .filter(|id| self.cancel_pulse(*id))
+            .map(|id| (id, 0.0))
+            .collect();
+        let mut inner = self.inner.lock().await;
+        if !interrupted.is_empty() {
+            if let Err(e) = inner.write_control(&interrupted).await {
+                warn!("Ch{} failed to end pulses on disconnect: {}", self.id, e);
+            }
+        }
+        inner.disconnect().await
+    }
+
+    async fn poll_once(&mut self) -> PollResult {
+        let mut result = self.inner.lock().await.poll_once().await;
+        let now = Instant::now();
+        for point in result.data.iter_mut() {
+            let Some(level) = point.value.as_bool() else {
+                continue;
+            };
+            if let Some(filter) = self.inputs.get_mut(&point.id) {
+                point.value = filter.sample(level, now).into();
+            } else if let Some(counter) = self.counters.get_mut(&point.id) {
+                point.value = (counter.sample(level, now) as f64).into();
+            }
+        }
+        result
+    }
+
+    async fn write_control(&mut self, commands: &[(u32, f64)]) -> igw::Result<usize> {
+        let mut pulses = Vec::new();
+        for &(internal_id, value) in commands {
+            if let Some(&width) = self.pulses.get(&internal_id) {
+                if value != 0.0 {
+                    pulses.push((internal_id, width));
+                } else {
+                    self.cancel_pulse(internal_id);
+                }
+            }
+        }
+        let written = self.inner.lock().await.write_control(commands).await?;
+        for (internal_id, width) in pulses {
+            self.schedule_off(internal_id, width);
+        }
+        Ok(written)
+    }
+
+    async fn write_adjustment(&mut self, adjustments: &[(u32, f64)]) -> igw::Result<usize> {
+        self.inner.lock().await.write_adjustment(adjustments).await
+    }
+
+    fn subscribe(&self) -> Option<DataEventReceiver> {
+        None
+    }
+
+    async fn start_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn stop_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn diagnostics(&self) -> igw::Result<Diagnostics> {
+        let mut diagnostics = self.inner.lock().await.diagnostics().await?;
+        let counters: serde_json::Map<String, serde_json::Value> = self
+            .counters
+            .iter()
+            .map(|(id, counter)| {
+                let (_, point_id) = PointType::from_internal_id(*id);
+                (point_id.to_string(), counter.count().into())
+            })
+            .collect();
+        let pulsing: Vec<u32> = self
+            .pending
+            .iter()
+            .filter(|(_, handle)| !handle.is_finished())
+            .map(|(id, _)| PointType::from_internal_id(*id).1)
+            .collect();
+        diagnostics.extra = serde_json::json!({
+            "counters": counters,
+            "pulsing_outputs": pulsing,
+        });
+        Ok(diagnostics)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use crate::core::config::{
+        ChannelConfig, ChannelCore, ControlPoint, Point, SignalPoint, TelemetryPoint,
+    };
+    use igw::{DataBatch, DataPoint};
+    use std::sync::Mutex as StdMutex;
+
+    /// Inputs served from a shared level table, writes recorded
+    struct FakePins {
+        levels: Arc<StdMutex<HashMap<u32, bool>>>,
+        writes: Arc<StdMutex<Vec<(u32, f64)>>>,
+    }
+
+    #[async_trait]
+    impl ChannelRuntime for FakePins {
+        fn id(&self) -> u32 {
+            1
+        }
+        fn name(&self) -> &str {
+            "fake"
+        }
+        fn protocol(&self) -> &str {
+            "gpio"
+        }
+        fn is_event_driven(&self) -> bool {
+            false
+        }
+        async fn connect(&mut self) -> igw::Result<()> {
+            Ok(())
+        }
+        async fn disconnect(&mut self) -> igw::Result<()> {
+            Ok(())
+        }
+        async fn poll_once(&mut self) -> PollResult {
+            let levels = self.levels.lock().unwrap();
+            PollResult::success(DataBatch::from_points(
+                levels
+                    .iter()
+                    .map(|(id, level)| DataPoint::new(*id, *level))
+                    .collect(),
+            ))
+        }
+        async fn write_control(&mut self, commands: &[(u32, f64)]) -> igw::Result<usize> {
+            self.writes.lock().unwrap().extend_from_slice(commands);
+            Ok(commands.len())
+        }
+        async fn write_adjustment(&mut self, _: &[(u32, f64)]) -> igw::Result<usize> {
+            Ok(0)
+        }
+        fn subscribe(&self) -> Option<DataEventReceiver> {
+            None
+        }
+        async fn start_events(&mut self) -> igw::Result<()> {
+            Ok(())
+        }
+        async fn stop_events(&mut self) -> igw::Result<()> {
+            Ok(())
+        }
+        async fn diagnostics(&self) -> igw::Result<Diagnostics> {
+            Ok(Diagnostics::new("gpio"))
+        }
+    }
+
+    fn point(point_id: u32, mapping: serde_json::Value) -> Point {
+        Point {
+            point_id,
+            signal_name: format!("P{}", point_id),
+            description: None,
+            unit: None,
+            protocol_mappings: Some(mapping.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_debounce_and_counting() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        let mut filter = InputFilter::new(Duration::from_millis(50));
+        assert!(!filter.sample(false, at(0)));
+        // Bounce shorter than the debounce time is ignored
+        assert!(!filter.sample(true, at(10)));
+        assert!(!filter.sample(false, at(30)));
+        assert!(!filter.sample(true, at(40)));
+        assert!(!filter.sample(true, at(80)));
+        assert!(filter.sample(true, at(90)));
+
+        let mut rising = PulseCounter::new(Duration::ZERO, CountEdge::Rising);
+        let mut both = PulseCounter::new(Duration::ZERO, CountEdge::Both);
+        for (i, level) in [true, false, true, false, true].into_iter().enumerate() {
+            rising.sample(level, at(i as u64));
+            both.sample(level, at(i as u64));
+        }
+        // The first sample sets the level without counting
+        assert_eq!(rising.count(), 2);
+        assert_eq!(both.count(), 4);
+        assert_eq!(CountEdge::parse("Falling"), Some(CountEdge::Falling));
+        assert_eq!(CountEdge::parse("level"), None);
+    }
+
+    #[test]
+    fn test_points_from_mappings() {
+        let mut config = RuntimeChannelConfig::from_base(ChannelConfig {
+            core: ChannelCore {
+                id: 1,
+                name: "dido".to_string(),
+                description: None,
+                protocol: "gpio".to_string(),
+                enabled: true,
+            },
+            parameters: HashMap::new(),
+            logging: Default::default(),
+        });
+        config.signal_points.push(SignalPoint {
+            base: point(
+                1,
+                serde_json::json!({"gpio_number": 496, "debounce_ms": 20}),
+            ),
+            reverse: false,
+        });
+        config.signal_points.push(SignalPoint {
+            base: point(2, serde_json::json!({"gpio_number": 497})),
+            reverse: false,
+        });
+        config.telemetry_points.push(TelemetryPoint {
+            base: point(
+                1,
+                serde_json::json!({"gpio_number": 497, "count_edge": "both"}),
+            ),
+            scale: 1.0,
+            offset: 0.0,
+            data_type: "uint32".to_string(),
+            reverse: false,
+        });
+        config.control_points.push(ControlPoint {
+            base: point(1, serde_json::json!({"gpio_number": 504, "pulse_ms": 500})),
+            reverse: false,
+            control_type: "latching".to_string(),
+            on_value: 1,
+            off_value: 0,
+            pulse_duration_ms: None,
+            access: Default::default(),
+        });
+
+        let points = DidoPoints::from_runtime_config(&config);
+        assert_eq!(
+            points.debounce,
+            HashMap::from([(
+                PointType::Signal.to_internal_id(1),
+                Duration::from_millis(20)
+            )])
+        );
+        assert_eq!(
+            points.counters[&PointType::Telemetry.to_internal_id(1)],
+            (Duration::ZERO, CountEdge::Both)
+        );
+        assert_eq!(
+            points.pulses[&PointType::Control.to_internal_id(1)],
+            Duration::from_millis(500)
+        );
+        assert!(!points.is_plain());
+    }
+
+    #[tokio::test]
+    async fn test_runtime_counts_and_pulses() {
+        let counter_id = PointType::Telemetry.to_internal_id(1);
+        let relay_id = PointType::Control.to_internal_id(1);
+        let levels = Arc::new(StdMutex::new(HashMap::from([(counter_id, false)])));
+        let writes = Arc::new(StdMutex::new(Vec::new()));
+        let inner = FakePins {
+            levels: Arc::clone(&levels),
+            writes: Arc::clone(&writes),
+        };
+        let mut runtime = DidoRuntime::new(
+            Box::new(inner),
+            DidoPoints {
+                counters: HashMap::from([(counter_id, (Duration::ZERO, CountEdge::Rising))]),
+                pulses: HashMap::from([(relay_id, Duration::from_millis(100))]),
+                ..Default::default()
+            },
+        );
+
+        for level in [false, true, false, true] {
+            levels.lock().unwrap().insert(counter_id, level);
+            runtime.poll_once().await;
+        }
+        let result = runtime.poll_once().await;
+        let value = result.data.iter().next().unwrap().value.as_f64();
+        assert_eq!(value, Some(2.0));
+
+        // ON starts the pulse, the timer switches the relay off
+        assert_eq!(runtime.write_control(&[(relay_id, 1.0)]).await.unwrap(), 1);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(*writes.lock().unwrap(), vec![(relay_id, 1.0)]);
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(
+            *writes.lock().unwrap(),
+            vec![(relay_id, 1.0), (relay_id, 0.0)]
+        );
+
+        // Disconnecting in the middle of a pulse ends it
+        runtime.write_control(&[(relay_id, 1.0)]).await.unwrap();
+        runtime.disconnect().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(
+            writes.lock().unwrap()[2..],
+            [(relay_id, 1.0), (relay_id, 0.0)]
+        );
+    }
+}
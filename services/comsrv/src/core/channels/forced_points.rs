@@ -0,0 +1,541 @@
+//! Forced point values for commissioning
+//!
+//! Protection logic is tested during commissioning by forcing a telemetry or
+//! signal point to a simulated value instead of touching the wiring. A forced
+//! value is written to the channel hash and routed to the instances right
+//! away; while it is active, [`crate::store::RedisDataStore`] substitutes it
+//! for the polled value so the device cannot overwrite it.
+//!
+//! Every force has a bounded duration (capped at [`MAX_FORCE_DURATION`]).
+//! When it expires, or is released, the value the point had before the force
+//! is written back and the next poll takes over again. Force, release and
+//! expiry are recorded in `comsrv:{channel_id}:forced:log` (newest first, at
+//! most [`MAX_AUDIT_ENTRIES`] per channel).
+//!
+//! Forces live in a process-wide registry ([`ForcedPoints::global`]) and are
+//! not persisted: a restart releases them all.
+
+use bytes::Bytes;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use utoipa::ToSchema;
+use voltage_model::{KeySpaceConfig, PointType};
+use voltage_routing::ChannelPointUpdate;
+use voltage_rtdb::{RoutingCache, Rtdb, SystemTimeProvider, TimeProvider};
+
+use crate::error::{ComSrvError, Result};
+
+/// Longest a point can be forced
+pub const MAX_FORCE_DURATION: Duration = Duration::from_secs(4 * 3600);
+
+/// Upper bound of audit entries per channel
+pub const MAX_AUDIT_ENTRIES: usize = 500;
+
+/// Quality reported for forced points
+pub const FORCED_QUALITY: &str = "forced";
+
+/// Actor recorded for forces that expired
+pub const EXPIRY_ACTOR: &str = "system";
+
+/// A simulated value forced on a telemetry or signal point
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ForcedPoint {
+    pub channel_id: u32,
+    /// "T" or "S"
+    pub point_type: String,
+    pub point_id: u32,
+    /// Forced value
+    pub value: f64,
+    /// Value before the force, written back on revert
+    pub original: f64,
+    /// Always [`FORCED_QUALITY`]
+    pub quality: String,
+    /// Who set the force
+    pub owner: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// When the force was set (milliseconds)
+    pub set_at: i64,
+    /// When the point reverts (milliseconds)
+    pub expires_at: i64,
+}
+
+impl ForcedPoint {
+    /// Force starting now; the duration is capped at [`MAX_FORCE_DURATION`]
+    pub fn new(
+        channel_id: u32,
+        point_type: PointType,
+        point_id: u32,
+        value: f64,
+        owner: impl Into<String>,
+        duration: Duration,
+    ) -> Self {
+        let set_at = SystemTimeProvider.now_millis();
+        Self {
+            channel_id,
+            point_type: point_type.as_str().to_string(),
+            point_id,
+            value,
+            original: 0.0,
+            quality: FORCED_QUALITY.to_string(),
+            owner: owner.into(),
+            reason: None,
+            set_at,
+            expires_at: set_at + duration.min(MAX_FORCE_DURATION).as_millis() as i64,
+        }
+    }
+
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    fn key(&self) -> Result<ForceKey> {
+        let point_type = PointType::from_str(&self.point_type)
+            .filter(|t| matches!(t, PointType::Telemetry | PointType::Signal))
+            .ok_or_else(|| {
+                ComSrvError::ValidationError(format!(
+                    "Only telemetry (T) and signal (S) points can be forced, not '{}'",
+                    self.point_type
+                ))
+            })?;
+        Ok((self.channel_id, point_type, self.point_id))
+    }
+}
+
+/// Kind of audited force change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ForceEvent {
+    Force,
+    Release,
+    Expire,
+}
+
+/// Audit record of a force change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ForceAuditEntry {
+    pub event: ForceEvent,
+    pub point_type: String,
+    pub point_id: u32,
+    /// Forced value
+    pub value: f64,
+    /// Value before the force
+    pub original: f64,
+    /// Owner of the force
+    pub owner: String,
+    /// Who caused the change ([`EXPIRY_ACTOR`] for expiry)
+    pub actor: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// When the change happened (milliseconds)
+    pub at: i64,
+}
+
+/// (channel_id, point_type, point_id)
+type ForceKey = (u32, PointType, u32);
+
+/// Process-wide registry of forced points
+#[derive(Default)]
+pub struct ForcedPoints {
+    points: DashMap<ForceKey, ForcedPoint>,
+    /// Fast path for the data store: false while no point is forced
+    active: AtomicBool,
+}
+
+impl ForcedPoints {
+    /// Registry shared by the API and all channel data stores
+    pub fn global() -> &'static Arc<ForcedPoints> {
+        static GLOBAL: OnceLock<Arc<ForcedPoints>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(ForcedPoints::default()))
+    }
+
+    /// Forced value replacing a polled one, if the point is forced
+    #[inline]
+    pub fn substitute(&self, channel_id: u32, point_type: PointType, point_id: u32) -> Option<f64> {
+        if !self.active.load(Ordering::Relaxed) {
+            return None;
+        }
+        self.points
+            .get(&(channel_id, point_type, point_id))
+            .map(|forced| forced.value)
+    }
+
+    /// Active force of a point, if any
+    pub fn get(
+        &self,
+        channel_id: u32,
+        point_type: PointType,
+        point_id: u32,
+    ) -> Option<ForcedPoint> {
+        self.points
+            .get(&(channel_id, point_type, point_id))
+            .map(|forced| forced.clone())
+    }
+
+    /// Active forces of a channel, ordered by point type and ID
+    pub fn list(&self, channel_id: u32) -> Vec<ForcedPoint> {
+        let mut forced: Vec<ForcedPoint> = self
+            .points
+            .iter()
+            .filter(|entry| entry.key().0 == channel_id)
+            .map(|entry| entry.value().clone())
+            .collect();
+        forced.sort_by(|a, b| (&a.point_type, a.point_id).cmp(&(&b.point_type, b.point_id)));
+        forced
+    }
+
+    /// Force a point and schedule its revert
+    ///
+    /// The point must exist in the channel hash; its current value is kept
+    /// and written back on revert. Replaces a force of the same owner (the
+    /// original value is kept); a force of another owner must be released
+    /// first.
+    pub async fn force<R: Rtdb + 'static>(
+        self: &Arc<Self>,
+        rtdb: Arc<R>,
+        routing_cache: Arc<RoutingCache>,
+        mut record: ForcedPoint,
+    ) -> Result<ForcedPoint> {
+        let key = record.key()?;
+        let (channel_id, point_type, point_id) = key;
+        if !record.value.is_finite() {
+            return Err(ComSrvError::ValidationError(
+                "Forced value must be finite".to_string(),
+            ));
+        }
+        if point_type == PointType::Signal && record.value != 0.0 && record.value != 1.0 {
+            return Err(ComSrvError::ValidationError(format!(
+                "Signal points can only be forced to 0 or 1, not {}",
+                record.value
+            )));
+        }
+        if record.owner.trim().is_empty() {
+            return Err(ComSrvError::ValidationError(
+                "Force owner must not be empty".to_string(),
+            ));
+        }
+        if record.expires_at <= record.set_at {
+            return Err(ComSrvError::ValidationError(
+                "Force duration must be positive".to_string(),
+            ));
+        }
+
+        record.original = match self.get(channel_id, point_type, point_id) {
+            Some(current) if current.owner != record.owner => {
+                return Err(ComSrvError::ResourceError(format!(
+                    "Ch{} {}:{} is forced by {} until {}",
+                    channel_id, current.point_type, point_id, current.owner, current.expires_at
+                )));
+            },
+            Some(current) => current.original,
+            None => read_value(rtdb.as_ref(), channel_id, point_type, point_id)
+                .await?
+                .ok_or_else(|| {
+                    ComSrvError::PointError(format!(
+                        "Ch{} {}:{} has no value",
+                        channel_id,
+                        point_type.as_str(),
+                        point_id
+                    ))
+                })?,
+        };
+
+        self.points.insert(key, record.clone());
+        self.active.store(true, Ordering::Relaxed);
+        if let Err(e) = write_value(rtdb.as_ref(), &routing_cache, key, record.value).await {
+            self.points.remove(&key);
+            self.refresh_active();
+            return Err(e);
+        }
+        audit(rtdb.as_ref(), ForceEvent::Force, &record, &record.owner).await;
+        tracing::info!(
+            "Ch{} {}:{} forced to {} (was {}) by {} until {}",
+            channel_id,
+            record.point_type,
+            point_id,
+            record.value,
+            record.original,
+            record.owner,
+            record.expires_at
+        );
+
+        let registry = Arc::clone(self);
+        let set_at = record.set_at;
+        let delay = Duration::from_millis((record.expires_at - set_at).max(0) as u64);
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            registry
+                .expire(rtdb.as_ref(), &routing_cache, key, set_at)
+                .await;
+        });
+
+        Ok(record)
+    }
+
+    /// Release a force; the original value is written back
+    ///
+    /// Returns the released force, or `None` if the point was not forced.
+    pub async fn release<R: Rtdb>(
+        &self,
+        rtdb: &R,
+        routing_cache: &RoutingCache,
+        channel_id: u32,
+        point_type: PointType,
+        point_id: u32,
+        actor: &str,
+    ) -> Result<Option<ForcedPoint>> {
+        let key = (channel_id, point_type, point_id);
+        let Some((_, record)) = self.points.remove(&key) else {
+            return Ok(None);
+        };
+        self.refresh_active();
+
+        audit(rtdb, ForceEvent::Release, &record, actor).await;
+        tracing::info!(
+            "Ch{} {}:{} force by {} released by {}",
+            channel_id,
+            record.point_type,
+            point_id,
+            record.owner,
+            actor
+        );
+        write_value(rtdb, routing_cache, key, record.original).await?;
+        Ok(Some(record))
+    }
+
+    /// Revert a force once its duration has passed
+    ///
+    /// Only the force set at `set_at` is reverted; a replacement schedules its own.
+    async fn expire<R: Rtdb>(
+        &self,
+        rtdb: &R,
+        routing_cache: &RoutingCache,
+        key: ForceKey,
+        set_at: i64,
+    ) {
+        let Some((_, record)) = self.points.remove_if(&key, |_, r| r.set_at == set_at) else {
+            return;
+        };
+        self.refresh_active();
+
+        audit(rtdb, ForceEvent::Expire, &record, EXPIRY_ACTOR).await;
+        tracing::info!(
+            "Ch{} {}:{} force by {} expired",
+            key.0,
+            record.point_type,
+            key.2,
+            record.owner
+        );
+        if let Err(e) = write_value(rtdb, routing_cache, key, record.original).await {
+            tracing::warn!(
+                "Ch{} {}:{} revert failed (next poll restores it): {}",
+                key.0,
+                record.point_type,
+                key.2,
+                e
+            );
+        }
+    }
+
+    fn refresh_active(&self) {
+        self.active
+            .store(!self.points.is_empty(), Ordering::Relaxed);
+    }
+}
+
+/// Force audit entries of a channel, newest first
+pub async fn audit_log<R: Rtdb>(
+    rtdb: &R,
+    channel_id: u32,
+    limit: usize,
+) -> Result<Vec<ForceAuditEntry>> {
+    let key = KeySpaceConfig::production_cached().channel_forced_log_key(channel_id);
+    let stop = limit.clamp(1, MAX_AUDIT_ENTRIES) as isize - 1;
+    Ok(rtdb
+        .list_range(&key, 0, stop)
+        .await
+        .map_err(|e| ComSrvError::storage(format!("Failed to read force log: {}", e)))?
+        .iter()
+        .filter_map(|payload| serde_json::from_slice(payload).ok())
+        .collect())
+}
+
+async fn read_value<R: Rtdb>(
+    rtdb: &R,
+    channel_id: u32,
+    point_type: PointType,
+    point_id: u32,
+) -> Result<Option<f64>> {
+    let key = KeySpaceConfig::production_cached().channel_key(channel_id, point_type);
+    let value = rtdb
+        .hash_get(&key, &point_id.to_string())
+        .await
+        .map_err(|e| ComSrvError::storage(format!("Failed to read {}: {}", key, e)))?;
+    Ok(value.and_then(|bytes| String::from_utf8_lossy(&bytes).parse().ok()))
+}
+
+/// Write a value to the channel hash and route it to the instances
+async fn write_value<R: Rtdb>(
+    rtdb: &R,
+    routing_cache: &RoutingCache,
+    (channel_id, point_type, point_id): ForceKey,
+    value: f64,
+) -> Result<()> {
+    voltage_routing::write_channel_batch(
+        rtdb,
+        routing_cache,
+        vec![ChannelPointUpdate::new(
+            channel_id, point_type, point_id, value,
+        )],
+    )
+    .await
+    .map_err(|e| ComSrvError::storage(format!("Failed to write forced value: {}", e)))?;
+    Ok(())
+}
+
+/// Append an audit entry; failures are logged, not propagated
+async fn audit<R: Rtdb>(rtdb: &R, event: ForceEvent, record: &ForcedPoint, actor: &str) {
+    let entry = ForceAuditEntry {
+        event,
+        point_type: record.point_type.clone(),
+        point_id: record.point_id,
+        value: record.value,
+        original: record.original,
+        owner: record.owner.clone(),
+        actor: actor.to_string(),
+        reason: record.reason.clone(),
+        at: SystemTimeProvider.now_millis(),
+    };
+    let key = KeySpaceConfig::production_cached().channel_forced_log_key(record.channel_id);
+    let result = async {
+        let payload = serde_json::to_vec(&entry)?;
+        rtdb.list_lpush(&key, Bytes::from(payload)).await?;
+        rtdb.list_trim(&key, 0, MAX_AUDIT_ENTRIES as isize - 1)
+            .await
+    }
+    .await;
+    if let Err(e) = result {
+        tracing::warn!("Force audit {} failed: {}", key, e);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use voltage_rtdb::MemoryRtdb;
+
+    async fn setup(value: &str) -> (Arc<MemoryRtdb>, Arc<RoutingCache>) {
+        let rtdb = Arc::new(MemoryRtdb::new());
+        rtdb.hash_set("comsrv:7:T", "3", Bytes::from(value.to_string()))
+            .await
+            .unwrap();
+        (rtdb, Arc::new(RoutingCache::new()))
+    }
+
+    async fn stored(rtdb: &MemoryRtdb) -> f64 {
+        let bytes = rtdb.hash_get("comsrv:7:T", "3").await.unwrap().unwrap();
+        String::from_utf8_lossy(&bytes).parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_force_substitutes_until_released() {
+        let registry = Arc::new(ForcedPoints::default());
+        let (rtdb, cache) = setup("230.5").await;
+
+        let record = ForcedPoint::new(
+            7,
+            PointType::Telemetry,
+            3,
+            280.0,
+            "alice",
+            MAX_FORCE_DURATION,
+        )
+        .with_reason("overvoltage trip test");
+        let forced = registry
+            .force(Arc::clone(&rtdb), Arc::clone(&cache), record)
+            .await
+            .unwrap();
+        assert_eq!(forced.original, 230.5);
+        assert_eq!(forced.quality, FORCED_QUALITY);
+        assert_eq!(stored(&rtdb).await, 280.0);
+        assert_eq!(registry.substitute(7, PointType::Telemetry, 3), Some(280.0));
+        assert_eq!(registry.substitute(7, PointType::Signal, 3), None);
+
+        // Another owner cannot take over an active force
+        let other = ForcedPoint::new(7, PointType::Telemetry, 3, 0.0, "bob", MAX_FORCE_DURATION);
+        assert!(registry
+            .force(Arc::clone(&rtdb), Arc::clone(&cache), other)
+            .await
+            .is_err());
+        assert_eq!(registry.list(7).len(), 1);
+
+        let released = registry
+            .release(rtdb.as_ref(), &cache, 7, PointType::Telemetry, 3, "bob")
+            .await
+            .unwrap();
+        assert_eq!(released.unwrap().owner, "alice");
+        assert_eq!(stored(&rtdb).await, 230.5);
+        assert_eq!(registry.substitute(7, PointType::Telemetry, 3), None);
+
+        let log = audit_log(rtdb.as_ref(), 7, 10).await.unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].event, ForceEvent::Release);
+        assert_eq!(log[0].actor, "bob");
+        assert_eq!(log[1].event, ForceEvent::Force);
+        assert_eq!(log[1].reason.as_deref(), Some("overvoltage trip test"));
+    }
+
+    #[tokio::test]
+    async fn test_force_reverts_on_expiry() {
+        let registry = Arc::new(ForcedPoints::default());
+        let (rtdb, cache) = setup("12").await;
+
+        let record = ForcedPoint::new(
+            7,
+            PointType::Telemetry,
+            3,
+            99.0,
+            "alice",
+            Duration::from_millis(50),
+        );
+        registry
+            .force(Arc::clone(&rtdb), Arc::clone(&cache), record)
+            .await
+            .unwrap();
+        assert_eq!(stored(&rtdb).await, 99.0);
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(registry.list(7).is_empty());
+        assert_eq!(stored(&rtdb).await, 12.0);
+        let log = audit_log(rtdb.as_ref(), 7, 10).await.unwrap();
+        assert_eq!(log[0].event, ForceEvent::Expire);
+        assert_eq!(log[0].actor, EXPIRY_ACTOR);
+    }
+
+    #[tokio::test]
+    async fn test_force_rejects_invalid_points() {
+        let registry = Arc::new(ForcedPoints::default());
+        let (rtdb, cache) = setup("1").await;
+        let force = |point_type, point_id, value| {
+            ForcedPoint::new(7, point_type, point_id, value, "alice", MAX_FORCE_DURATION)
+        };
+
+        for record in [
+            force(PointType::Control, 3, 1.0),
+            force(PointType::Telemetry, 3, f64::NAN),
+            force(PointType::Telemetry, 4, 1.0),
+            force(PointType::Signal, 3, 0.5),
+        ] {
+            assert!(registry
+                .force(Arc::clone(&rtdb), Arc::clone(&cache), record)
+                .await
+                .is_err());
+        }
+        assert!(registry.list(7).is_empty());
+    }
+}
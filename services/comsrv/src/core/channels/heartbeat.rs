@@ -0,0 +1,315 @@
+//! Channel heartbeat output and device watchdog input
+//!
+//! Many PLC safety programs expect the SCADA side to keep a heartbeat register
+//! moving and stop the plant when it freezes. With `heartbeat.output` the
+//! channel writes an incrementing counter (or a toggling bit) to a control or
+//! adjustment point at a fixed rate. With `heartbeat.input` it watches a
+//! device-side heartbeat (telemetry or signal point) and expects its value to
+//! change within the timeout.
+//!
+//! When writes keep failing or the input stops changing, the channel is
+//! flagged `Degraded` through the connection state machine until both are
+//! alive again.
+//!
+//! ```json
+//! {
+//!   "heartbeat": {
+//!     "output": { "point_type": "A", "point_id": 100, "mode": "counter", "interval_ms": 1000, "max": 65535 },
+//!     "input": { "point_type": "T", "point_id": 200, "timeout_ms": 5000 }
+//!   }
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::time::Instant;
+use voltage_model::PointType;
+
+/// Default heartbeat write interval
+pub const DEFAULT_OUTPUT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default device heartbeat timeout
+pub const DEFAULT_INPUT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Failed output writes in a row after which the output counts as stalled
+pub const OUTPUT_STALL_WRITES: u32 = 3;
+
+/// How the heartbeat output value moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeartbeatMode {
+    /// 1, 2, ... `max`, then wraps to 0
+    Counter { max: u64 },
+    /// Alternates between 0 and 1
+    Toggle,
+}
+
+/// Heartbeat written to the device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeartbeatOutput {
+    /// Control or Adjustment
+    pub point_type: PointType,
+    pub point_id: u32,
+    pub mode: HeartbeatMode,
+    pub interval: Duration,
+}
+
+/// Device heartbeat that must keep changing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeartbeatInput {
+    /// Telemetry or Signal
+    pub point_type: PointType,
+    pub point_id: u32,
+    pub timeout: Duration,
+}
+
+/// Heartbeat settings of a channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeartbeatConfig {
+    pub output: Option<HeartbeatOutput>,
+    pub input: Option<HeartbeatInput>,
+}
+
+impl HeartbeatConfig {
+    /// Read `heartbeat` from the channel parameters
+    ///
+    /// Returns `None` when neither a valid output nor input is configured.
+    pub fn from_parameters(params: &HashMap<String, serde_json::Value>) -> Option<Self> {
+        let obj = params.get("heartbeat")?.as_object()?;
+        let point = |v: &serde_json::Value, allowed: [PointType; 2]| {
+            let point_type = v
+                .get("point_type")
+                .and_then(|t| t.as_str())
+                .and_then(PointType::from_str)
+                .filter(|t| allowed.contains(t))?;
+            let point_id = v
+                .get("point_id")
+                .and_then(|id| id.as_u64())
+                .and_then(|id| u32::try_from(id).ok())?;
+            Some((point_type, point_id))
+        };
+        let millis = |v: &serde_json::Value, key: &str| {
+            v.get(key)
+                .and_then(|ms| ms.as_u64())
+                .filter(|ms| *ms > 0)
+                .map(Duration::from_millis)
+        };
+
+        let output = obj.get("output").and_then(|v| {
+            let (point_type, point_id) = point(v, [PointType::Control, PointType::Adjustment])?;
+            let mode = match v.get("mode").and_then(|m| m.as_str()).unwrap_or("counter") {
+                "toggle" => HeartbeatMode::Toggle,
+                _ => HeartbeatMode::Counter {
+                    max: v
+                        .get("max")
+                        .and_then(|m| m.as_u64())
+                        .filter(|m| *m > 0)
+                        .unwrap_or(u16::MAX as u64),
+                },
+            };
+            Some(HeartbeatOutput {
+                point_type,
+                point_id,
+                mode,
+                interval: millis(v, "interval_ms").unwrap_or(DEFAULT_OUTPUT_INTERVAL),
+            })
+        });
+        let input = obj.get("input").and_then(|v| {
+            let (point_type, point_id) = point(v, [PointType::Telemetry, PointType::Signal])?;
+            Some(HeartbeatInput {
+                point_type,
+                point_id,
+                timeout: millis(v, "timeout_ms").unwrap_or(DEFAULT_INPUT_TIMEOUT),
+            })
+        });
+
+        if output.is_none() && input.is_none() {
+            return None;
+        }
+        Some(Self { output, input })
+    }
+
+    /// Period of the heartbeat task
+    pub fn tick(&self) -> Duration {
+        let output = self.output.map(|o| o.interval);
+        let input = self.input.map(|i| i.timeout / 4);
+        output
+            .into_iter()
+            .chain(input)
+            .min()
+            .unwrap_or(DEFAULT_OUTPUT_INTERVAL)
+            .max(Duration::from_millis(50))
+    }
+}
+
+/// Heartbeat bookkeeping, independent of I/O
+#[derive(Debug)]
+pub struct HeartbeatMonitor {
+    config: HeartbeatConfig,
+    counter: u64,
+    last_write: Option<Instant>,
+    failed_writes: u32,
+    last_input: Option<f64>,
+    input_changed_at: Instant,
+}
+
+impl HeartbeatMonitor {
+    pub fn new(config: HeartbeatConfig, now: Instant) -> Self {
+        Self {
+            config,
+            counter: 0,
+            last_write: None,
+            failed_writes: 0,
+            last_input: None,
+            input_changed_at: now,
+        }
+    }
+
+    pub fn config(&self) -> &HeartbeatConfig {
+        &self.config
+    }
+
+    /// Next output value if a write is due
+    pub fn due_output(&mut self, now: Instant) -> Option<f64> {
+        let output = self.config.output?;
+        if self
+            .last_write
+            .is_some_and(|at| now.duration_since(at) < output.interval)
+        {
+            return None;
+        }
+        self.last_write = Some(now);
+        self.counter = match output.mode {
+            HeartbeatMode::Counter { max } => {
+                if self.counter >= max {
+                    0
+                } else {
+                    self.counter + 1
+                }
+            },
+            HeartbeatMode::Toggle => 1 - self.counter.min(1),
+        };
+        Some(self.counter as f64)
+    }
+
+    /// Record the outcome of an output write
+    pub fn output_written(&mut self, ok: bool) {
+        self.failed_writes = if ok { 0 } else { self.failed_writes + 1 };
+    }
+
+    /// Record the current input value (`None` if it could not be read)
+    pub fn observe_input(&mut self, value: Option<f64>, now: Instant) {
+        if value.is_some() && value != self.last_input {
+            self.last_input = value;
+            self.input_changed_at = now;
+        }
+    }
+
+    /// Restart the input timeout (after the link came back)
+    pub fn reset(&mut self, now: Instant) {
+        self.input_changed_at = now;
+        self.failed_writes = 0;
+        self.last_write = None;
+    }
+
+    /// Why the heartbeat is stalled, if it is
+    pub fn stall_reason(&self, now: Instant) -> Option<String> {
+        if let Some(output) = self.config.output {
+            if self.failed_writes >= OUTPUT_STALL_WRITES {
+                return Some(format!(
+                    "heartbeat output {}{} failed {} writes",
+                    output.point_type.as_str(),
+                    output.point_id,
+                    self.failed_writes
+                ));
+            }
+        }
+        if let Some(input) = self.config.input {
+            let idle = now.duration_since(self.input_changed_at);
+            if idle >= input.timeout {
+                return Some(format!(
+                    "device heartbeat {}{} unchanged for {}ms",
+                    input.point_type.as_str(),
+                    input.point_id,
+                    idle.as_millis()
+                ));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config(value: serde_json::Value) -> Option<HeartbeatConfig> {
+        HeartbeatConfig::from_parameters(&HashMap::from([("heartbeat".to_string(), value)]))
+    }
+
+    #[test]
+    fn test_from_parameters() {
+        let cfg = config(json!({
+            "output": {"point_type": "C", "point_id": 5, "mode": "toggle", "interval_ms": 500},
+            "input": {"point_type": "T", "point_id": 7, "timeout_ms": 2000}
+        }))
+        .unwrap();
+        let output = cfg.output.unwrap();
+        assert_eq!(output.mode, HeartbeatMode::Toggle);
+        assert_eq!(output.interval, Duration::from_millis(500));
+        assert_eq!(cfg.input.unwrap().timeout, Duration::from_secs(2));
+        assert_eq!(cfg.tick(), Duration::from_millis(500));
+
+        // Output must be writable, input must be readable
+        assert!(config(json!({"output": {"point_type": "T", "point_id": 5}})).is_none());
+        assert!(config(json!({"input": {"point_type": "A", "point_id": 5}})).is_none());
+    }
+
+    #[test]
+    fn test_counter_wraps_and_output_stall() {
+        let cfg = config(json!({"output": {"point_type": "A", "point_id": 1, "max": 2}})).unwrap();
+        let start = Instant::now();
+        let mut monitor = HeartbeatMonitor::new(cfg, start);
+
+        let values: Vec<f64> = (0..4)
+            .filter_map(|i| monitor.due_output(start + Duration::from_secs(i)))
+            .collect();
+        assert_eq!(values, vec![1.0, 2.0, 0.0, 1.0]);
+        // Not due again within the interval
+        assert!(monitor
+            .due_output(start + Duration::from_millis(3500))
+            .is_none());
+
+        for _ in 0..OUTPUT_STALL_WRITES {
+            assert!(monitor.stall_reason(start).is_none());
+            monitor.output_written(false);
+        }
+        assert!(monitor.stall_reason(start).is_some());
+        monitor.output_written(true);
+        assert!(monitor.stall_reason(start).is_none());
+    }
+
+    #[test]
+    fn test_input_stall() {
+        let cfg = config(json!({"input": {"point_type": "S", "point_id": 3, "timeout_ms": 1000}}))
+            .unwrap();
+        let start = Instant::now();
+        let mut monitor = HeartbeatMonitor::new(cfg, start);
+
+        monitor.observe_input(Some(1.0), start);
+        monitor.observe_input(Some(1.0), start + Duration::from_millis(900));
+        assert!(monitor
+            .stall_reason(start + Duration::from_millis(900))
+            .is_none());
+        assert!(monitor
+            .stall_reason(start + Duration::from_millis(1000))
+            .unwrap()
+            .contains("S3"));
+
+        monitor.observe_input(Some(0.0), start + Duration::from_millis(1100));
+        assert!(monitor
+            .stall_reason(start + Duration::from_millis(1500))
+            .is_none());
+    }
+}
@@ -16,10 +16,32 @@
 //!         ├─ store: RedisDataStore (service layer storage)
 //!         └─ poll_once() → protocol.poll_once() → store.write_batch()
 //! ```
+//!
+//! # Isolation
+//!
+//! By default a channel's polling and command tasks run on the shared service
+//! runtime. Channels configured with `isolation: dedicated` get their own
+//! single-worker Tokio runtime (one OS thread), so a protocol that blocks its
+//! executor (e.g. a stalled serial port) cannot starve other channels.
+//!
+//! # Keepalive
+//!
+//! Channels with a `keepalive` parameter probe an idle link at a short interval
+//! (Modbus: a read of a configured register) and bound every poll by a timeout,
+//! so a dead TCP session is detected within seconds rather than after the OS
+//! gives up on the socket.
+//!
+//! # Draining
+//!
+//! On shutdown `drain()` stops polling and closes the command mailbox: no new
+//! commands are accepted, but those already queued are written to the device
+//! before the executor exits.
 
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{mpsc, Notify, RwLock};
 use tracing::{debug, error, info, warn};
 
 use igw::core::point::{
@@ -27,30 +49,250 @@ use igw::core::point::{
     VirtualAddress,
 };
 use igw::core::traits::PollResult;
+#[cfg(feature = "modbus")]
 use igw::protocols::modbus::{ModbusChannel, ModbusChannelConfig, ReconnectConfig};
 use igw::protocols::virtual_channel::{VirtualChannel, VirtualChannelConfig};
 
 // ChannelRuntime trait and wrappers from igw
-use igw::gateway::wrappers::{ModbusRuntime, VirtualRuntime};
+#[cfg(feature = "modbus")]
+use igw::gateway::wrappers::ModbusRuntime;
+use igw::gateway::wrappers::VirtualRuntime;
 use igw::gateway::ChannelRuntime;
 
-#[cfg(all(feature = "can", target_os = "linux"))]
-use igw::gateway::wrappers::CanRuntime;
-#[cfg(all(feature = "can", target_os = "linux"))]
-use igw::protocols::can::{CanClient, CanConfig, CanDataType, CanPoint};
-
 #[cfg(all(target_os = "linux", feature = "gpio"))]
 use igw::gateway::wrappers::GpioRuntime;
 #[cfg(all(target_os = "linux", feature = "gpio"))]
 use igw::protocols::gpio::{GpioChannel, GpioChannelConfig, GpioPinConfig};
 
+#[cfg(all(target_os = "linux", feature = "gpio"))]
+use crate::core::channels::dido::{DidoPoints, DidoRuntime};
+
+use crate::core::channels::command_retry::{
+    self, CommandAttempt, CommandRetryPolicy, CommandTracker,
+};
+use crate::core::channels::command_webhooks::CommandWebhooks;
+use crate::core::channels::connection::{ConnectionEvent, ConnectionStateMachine};
+use crate::core::channels::dead_letter::{DeadLetterEntry, DeadLetterQueue};
+use crate::core::channels::heartbeat::{HeartbeatConfig, HeartbeatMonitor};
+use crate::core::channels::redundancy::Redundancy;
+use crate::core::channels::stats_history::{PollSample, StatsHistory, StatsHistoryConfig};
 use crate::core::channels::traits::ChannelCommand;
-use crate::core::channels::types::ChannelStatus;
+use crate::core::channels::trigger::CommandStatus;
+use crate::core::channels::types::{ChannelStatus, ConnectionState};
 use crate::core::config::RuntimeChannelConfig;
-use crate::store::RedisDataStore;
-use voltage_model::PointType;
+#[cfg(feature = "modbus")]
+use crate::core::protocols::modbus_blocks::BlockConfig;
+use crate::store::{RedisDataStore, KEEPALIVE_POINT_ID};
+use voltage_model::{KeySpaceConfig, PointType};
 use voltage_rtdb::Rtdb;
 
+// ============================================================================
+// Channel Isolation
+// ============================================================================
+
+/// Where a channel's polling and command tasks are executed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IsolationMode {
+    /// Shared service runtime (default)
+    #[default]
+    Shared,
+    /// Dedicated single-worker runtime on its own OS thread
+    Dedicated,
+}
+
+impl IsolationMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IsolationMode::Shared => "shared",
+            IsolationMode::Dedicated => "dedicated",
+        }
+    }
+}
+
+/// Per-channel execution isolation options
+///
+/// Read from channel parameters:
+/// - `isolation`: `"shared"` (default) or `"dedicated"`
+/// - `mailbox_size`: bounded command mailbox capacity (default from the
+///   resource profile, 100 on `medium`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelIsolation {
+    pub mode: IsolationMode,
+    pub mailbox_size: usize,
+}
+
+impl Default for ChannelIsolation {
+    fn default() -> Self {
+        Self {
+            mode: IsolationMode::Shared,
+            mailbox_size: common::resource_profile::tuning().command_mailbox_size,
+        }
+    }
+}
+
+impl ChannelIsolation {
+    /// Parse isolation options from channel parameters, falling back to defaults
+    pub fn from_parameters(params: &HashMap<String, serde_json::Value>) -> Self {
+        let mode = match params.get("isolation").and_then(|v| v.as_str()) {
+            Some(s) if s.eq_ignore_ascii_case("dedicated") => IsolationMode::Dedicated,
+            Some(s) if s.eq_ignore_ascii_case("shared") => IsolationMode::Shared,
+            Some(other) => {
+                warn!("Unknown isolation '{}', using shared runtime", other);
+                IsolationMode::Shared
+            },
+            None => IsolationMode::Shared,
+        };
+        let mailbox_size = params
+            .get("mailbox_size")
+            .and_then(|v| v.as_u64())
+            .filter(|n| *n > 0)
+            .map(|n| n as usize)
+            .unwrap_or_else(|| common::resource_profile::tuning().command_mailbox_size);
+
+        Self { mode, mailbox_size }
+    }
+}
+
+/// Modbus register read by every keepalive probe
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepaliveRegister {
+    pub slave_id: u8,
+    pub function_code: u8,
+    pub address: u16,
+}
+
+/// Protocol-level keepalive options
+///
+/// A half-open TCP session only surfaces at the next poll that runs into the
+/// OS timeout. With keepalive enabled, the polling task probes the link once
+/// it has been idle for `interval`, bounds every poll by `timeout` and treats
+/// the link as lost after `max_missed` consecutive missed probes.
+///
+/// Read from the channel parameter `keepalive` (`true` uses the defaults):
+/// ```yaml
+/// keepalive:
+///   interval_ms: 5000
+///   timeout_ms: 2000
+///   max_missed: 2
+///   register: { slave_id: 1, function_code: 3, address: 0 }  # Modbus only
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepaliveConfig {
+    pub interval: Duration,
+    pub timeout: Duration,
+    pub max_missed: u32,
+    /// Register probed on Modbus channels, so control-only point tables still
+    /// generate read traffic
+    pub register: Option<KeepaliveRegister>,
+}
+
+impl Default for KeepaliveConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(5000),
+            timeout: Duration::from_millis(2000),
+            max_missed: 2,
+            register: None,
+        }
+    }
+}
+
+impl KeepaliveConfig {
+    /// Parse keepalive options from channel parameters (`None` when disabled)
+    pub fn from_parameters(params: &HashMap<String, serde_json::Value>) -> Option<Self> {
+        let value = params.get("keepalive")?;
+        let obj = match value {
+            serde_json::Value::Bool(true) => return Some(Self::default()),
+            serde_json::Value::Object(obj) => obj,
+            _ => return None,
+        };
+        if obj.get("enabled").and_then(|v| v.as_bool()) == Some(false) {
+            return None;
+        }
+
+        let defaults = Self::default();
+        let millis = |key: &str, default: Duration| {
+            obj.get(key)
+                .and_then(|v| v.as_u64())
+                .filter(|n| *n > 0)
+                .map(Duration::from_millis)
+                .unwrap_or(default)
+        };
+        let register = obj.get("register").and_then(|r| {
+            let address = r.get("address").and_then(|v| v.as_u64())?;
+            let Ok(address) = u16::try_from(address) else {
+                warn!("Keepalive register address {} out of range", address);
+                return None;
+            };
+            Some(KeepaliveRegister {
+                slave_id: r.get("slave_id").and_then(|v| v.as_u64()).unwrap_or(1) as u8,
+                function_code: r.get("function_code").and_then(|v| v.as_u64()).unwrap_or(3) as u8,
+                address,
+            })
+        });
+
+        Some(Self {
+            interval: millis("interval_ms", defaults.interval),
+            timeout: millis("timeout_ms", defaults.timeout),
+            max_missed: obj
+                .get("max_missed")
+                .and_then(|v| v.as_u64())
+                .filter(|n| *n > 0)
+                .map(|n| n as u32)
+                .unwrap_or(defaults.max_missed),
+            register,
+        })
+    }
+
+    /// Hidden Modbus point that the probe reads, if a register is configured
+    pub fn probe_point(&self) -> Option<PointConfig> {
+        let register = self.register?;
+        let address = ModbusAddress {
+            slave_id: register.slave_id,
+            function_code: register.function_code,
+            register: register.address,
+            format: DataFormat::UInt16,
+            byte_order: ByteOrder::Abcd,
+            bit_position: None,
+        };
+        Some(PointConfig::new(
+            KEEPALIVE_POINT_ID,
+            ProtocolAddress::Modbus(address),
+        ))
+    }
+}
+
+/// Per-channel task options read from the channel parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChannelOptions {
+    pub isolation: ChannelIsolation,
+    pub keepalive: Option<KeepaliveConfig>,
+    pub command_retry: CommandRetryPolicy,
+    pub stats_history: StatsHistoryConfig,
+    pub heartbeat: Option<HeartbeatConfig>,
+}
+
+impl ChannelOptions {
+    pub fn from_parameters(params: &HashMap<String, serde_json::Value>) -> Self {
+        Self {
+            isolation: ChannelIsolation::from_parameters(params),
+            keepalive: KeepaliveConfig::from_parameters(params),
+            command_retry: CommandRetryPolicy::from_parameters(params),
+            stats_history: StatsHistoryConfig::from_parameters(params),
+            heartbeat: HeartbeatConfig::from_parameters(params),
+        }
+    }
+}
+
+/// Build the dedicated runtime for an isolated channel.
+fn build_dedicated_runtime(channel_id: u32) -> std::io::Result<tokio::runtime::Runtime> {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name(format!("comsrv-ch{}", channel_id))
+        .enable_all()
+        .build()
+}
+
 // ============================================================================
 // IgwChannelWrapper - Protocol wrapper with storage integration
 // ============================================================================
@@ -71,8 +313,18 @@ pub struct IgwChannelWrapper<R: Rtdb> {
     store: Arc<RedisDataStore<R>>,
     /// Command executor task handle (used for cleanup on disconnect)
     executor_handle: Option<tokio::task::JoinHandle<()>>,
+    /// Signals the executor to close its mailbox and drain queued commands
+    drain_signal: Arc<Notify>,
     /// Polling task handle (used for cleanup on disconnect)
     polling_handle: Option<tokio::task::JoinHandle<()>>,
+    /// Heartbeat task handle (only with `heartbeat` configured)
+    heartbeat_handle: Option<tokio::task::JoinHandle<()>>,
+    /// Isolation options this channel was started with
+    isolation: ChannelIsolation,
+    /// Connection state machine shared with the polling task
+    connection: Arc<ConnectionStateMachine>,
+    /// Dedicated runtime (only for `IsolationMode::Dedicated`)
+    runtime: Option<tokio::runtime::Runtime>,
 }
 
 impl<R: Rtdb> IgwChannelWrapper<R> {
@@ -86,31 +338,88 @@ impl<R: Rtdb> IgwChannelWrapper<R> {
     /// * `store` - Data store for persisting polled data
     /// * `command_rx` - Receiver for control commands
     /// * `poll_interval_ms` - Polling interval in milliseconds
+    /// * `options` - Isolation, keepalive probing and command retry policy
     pub fn new(
         protocol: Box<dyn ChannelRuntime>,
         channel_id: u32,
         store: Arc<RedisDataStore<R>>,
         command_rx: mpsc::Receiver<ChannelCommand>,
         poll_interval_ms: u64,
+        options: ChannelOptions,
     ) -> Self {
+        let ChannelOptions {
+            mut isolation,
+            keepalive,
+            command_retry,
+            stats_history,
+            heartbeat,
+        } = options;
         let protocol = Arc::new(RwLock::new(protocol));
-        let protocol_clone = Arc::clone(&protocol);
+        let connection = Arc::new(ConnectionStateMachine::new(channel_id));
+
+        // Dedicated runtime falls back to the shared one if it cannot be built
+        let runtime = match isolation.mode {
+            IsolationMode::Shared => None,
+            IsolationMode::Dedicated => match build_dedicated_runtime(channel_id) {
+                Ok(rt) => Some(rt),
+                Err(e) => {
+                    error!(
+                        "Ch{} dedicated runtime failed, using shared: {}",
+                        channel_id, e
+                    );
+                    isolation.mode = IsolationMode::Shared;
+                    None
+                },
+            },
+        };
+        let spawner = match &runtime {
+            Some(rt) => rt.handle().clone(),
+            None => tokio::runtime::Handle::current(),
+        };
 
         // Spawn command executor task
-        let executor_handle = tokio::spawn(async move {
-            Self::run_command_executor(protocol_clone, command_rx, channel_id).await;
+        let protocol_clone = Arc::clone(&protocol);
+        let drain_signal = Arc::new(Notify::new());
+        let drain_clone = Arc::clone(&drain_signal);
+        let context = CommandContext::new(Arc::clone(store.rtdb()), channel_id, command_retry);
+        let executor_handle = spawner.spawn(async move {
+            Self::run_command_executor(protocol_clone, command_rx, drain_clone, context).await;
         });
 
         // Start polling task with configured interval
         let protocol_clone = Arc::clone(&protocol);
         let store_clone = Arc::clone(&store);
-        let polling_handle = Some(tokio::spawn(async move {
-            run_polling_task(protocol_clone, store_clone, channel_id, poll_interval_ms).await;
+        let connection_clone = Arc::clone(&connection);
+        let history = StatsHistory::new(Arc::clone(store.rtdb()), channel_id, stats_history);
+        let polling_handle = Some(spawner.spawn(async move {
+            run_polling_task(
+                protocol_clone,
+                store_clone,
+                connection_clone,
+                history,
+                channel_id,
+                poll_interval_ms,
+                keepalive,
+            )
+            .await;
         }));
 
+        // Heartbeat output / device watchdog
+        let heartbeat_handle = heartbeat.map(|config| {
+            let protocol_clone = Arc::clone(&protocol);
+            let rtdb = Arc::clone(store.rtdb());
+            let connection_clone = Arc::clone(&connection);
+            spawner.spawn(async move {
+                run_heartbeat_task(protocol_clone, rtdb, connection_clone, channel_id, config)
+                    .await;
+            })
+        });
+
         info!(
-            "Ch{} started polling task (interval: {}ms)",
-            channel_id, poll_interval_ms
+            "Ch{} started polling task (interval: {}ms, isolation: {})",
+            channel_id,
+            poll_interval_ms,
+            isolation.mode.as_str()
         );
 
         Self {
@@ -118,7 +427,12 @@ impl<R: Rtdb> IgwChannelWrapper<R> {
             channel_id,
             store,
             executor_handle: Some(executor_handle),
+            drain_signal,
             polling_handle,
+            heartbeat_handle,
+            isolation,
+            connection,
+            runtime,
         }
     }
 
@@ -128,8 +442,17 @@ impl<R: Rtdb> IgwChannelWrapper<R> {
     /// 1. Call protocol.poll_once() to get PollResult from device
     /// 2. Write the batch to RedisDataStore (with transformations and routing)
     pub async fn poll_once(&self) -> crate::error::Result<usize> {
+        Ok(self.read_all().await?.data.len())
+    }
+
+    /// Poll once outside the regular cycle, store the data and return the
+    /// full poll result (values and failed points)
+    ///
+    /// Waits for a running poll or command to release the protocol.
+    pub async fn read_all(&self) -> crate::error::Result<PollResult> {
         let mut protocol = self.protocol.write().await;
         let result: PollResult = protocol.poll_once().await;
+        drop(protocol);
 
         // Check failures first before moving data
         if result.has_failures() {
@@ -139,15 +462,14 @@ impl<R: Rtdb> IgwChannelWrapper<R> {
             );
         }
 
-        let count = result.data.len();
-        if count > 0 {
+        if !result.data.is_empty() {
             self.store
-                .write_batch(self.channel_id, result.data)
+                .write_batch(self.channel_id, result.data.clone())
                 .await
                 .map_err(|e| crate::error::ComSrvError::storage(e.to_string()))?;
         }
 
-        Ok(count)
+        Ok(result)
     }
 
     /// Get the protocol client for status queries.
@@ -160,13 +482,29 @@ impl<R: Rtdb> IgwChannelWrapper<R> {
         self.channel_id
     }
 
+    /// Get the isolation options this channel runs with.
+    pub fn isolation(&self) -> ChannelIsolation {
+        self.isolation
+    }
+
+    /// Get the connection state machine (for state queries and transition events).
+    pub fn connection(&self) -> &Arc<ConnectionStateMachine> {
+        &self.connection
+    }
+
     /// Connect the protocol client.
+    ///
+    /// A redundancy standby leaves the device alone; the channel is
+    /// connected when the node takes over.
     pub async fn connect(&self) -> crate::error::Result<()> {
+        if Redundancy::global().is_standby() {
+            debug!("Ch{} connect deferred: standby node", self.channel_id);
+            return Ok(());
+        }
         let mut protocol = self.protocol.write().await;
-        protocol
-            .connect()
+        connect_protocol(&mut protocol, &self.connection, self.channel_id)
             .await
-            .map_err(|e| crate::error::ComSrvError::ConnectionError(e.to_string()))
+            .map_err(crate::error::ComSrvError::ConnectionError)
     }
 
     /// Shutdown all background tasks (polling and command executor).
@@ -182,6 +520,11 @@ impl<R: Rtdb> IgwChannelWrapper<R> {
             }
         }
 
+        // Abort heartbeat task
+        if let Some(handle) = self.heartbeat_handle.take() {
+            handle.abort();
+        }
+
         // Abort executor task
         if let Some(handle) = self.executor_handle.take() {
             if !handle.is_finished() {
@@ -189,6 +532,48 @@ impl<R: Rtdb> IgwChannelWrapper<R> {
                 handle.abort();
             }
         }
+
+        // Stop dedicated runtime without blocking (safe inside async context)
+        if let Some(runtime) = self.runtime.take() {
+            info!("Ch{} stopping dedicated runtime", self.channel_id);
+            runtime.shutdown_background();
+        }
+    }
+
+    /// Drain queued commands before shutdown.
+    ///
+    /// Stops the polling task, closes the command mailbox so no new commands
+    /// are accepted, and waits up to `timeout` for the executor to write the
+    /// commands that were already queued. Returns `true` if the executor
+    /// finished within the timeout; otherwise it is left for `shutdown()`.
+    pub async fn drain(&mut self, timeout: Duration) -> bool {
+        if let Some(handle) = self.polling_handle.take() {
+            handle.abort();
+        }
+        if let Some(handle) = self.heartbeat_handle.take() {
+            handle.abort();
+        }
+
+        let Some(handle) = self.executor_handle.as_mut() else {
+            return true;
+        };
+
+        self.drain_signal.notify_one();
+        match tokio::time::timeout(timeout, handle).await {
+            Ok(_) => {
+                self.executor_handle = None;
+                debug!("Ch{} commands drained", self.channel_id);
+                true
+            },
+            Err(_) => {
+                warn!(
+                    "Ch{} drain timed out after {}ms",
+                    self.channel_id,
+                    timeout.as_millis()
+                );
+                false
+            },
+        }
     }
 
     /// Disconnect the protocol client and shutdown background tasks.
@@ -197,101 +582,306 @@ impl<R: Rtdb> IgwChannelWrapper<R> {
     /// 1. First aborts all background tasks (polling, executor)
     /// 2. Then disconnects the underlying protocol
     pub async fn disconnect(&mut self) -> crate::error::Result<()> {
+        record_event(&self.connection, ConnectionEvent::CloseRequested);
+
         // First shutdown background tasks to prevent orphaned tasks
         self.shutdown();
 
         // Then disconnect protocol
         let mut protocol = self.protocol.write().await;
-        protocol
+        let result = protocol
+            .disconnect()
+            .await
+            .map_err(|e| crate::error::ComSrvError::ConnectionError(e.to_string()));
+        record_event(&self.connection, ConnectionEvent::Closed);
+        result
+    }
+
+    /// Disconnect the protocol client but keep the background tasks.
+    ///
+    /// The tasks idle while the node is a redundancy standby and resume
+    /// once `connect()` succeeds again.
+    pub async fn suspend(&self) -> crate::error::Result<()> {
+        if !self.connection.state().is_connected() {
+            return Ok(());
+        }
+        record_event(&self.connection, ConnectionEvent::CloseRequested);
+        let mut protocol = self.protocol.write().await;
+        let result = protocol
             .disconnect()
             .await
-            .map_err(|e| crate::error::ComSrvError::ConnectionError(e.to_string()))
+            .map_err(|e| crate::error::ComSrvError::ConnectionError(e.to_string()));
+        record_event(&self.connection, ConnectionEvent::Closed);
+        result
     }
 
-    /// Check if connected.
+    /// Check if connected (including degraded links).
     ///
-    /// Note: ChannelRuntime doesn't expose connection state directly.
-    /// We use diagnostics as a proxy - if diagnostics succeed, we assume connected.
+    /// Answered by the connection state machine, which is fed by connect
+    /// attempts and poll results.
     pub async fn is_connected(&self) -> bool {
-        let protocol = self.protocol.read().await;
-        // If diagnostics returns Ok, we consider it connected
-        protocol.diagnostics().await.is_ok()
+        self.connection.state().is_connected()
     }
 
     /// Run the command executor loop.
     ///
     /// Uses ChannelRuntime's `write_control` and `write_adjustment` which
     /// take `&[(u32, f64)]` tuples instead of command structs.
+    ///
+    /// When `drain` is notified the mailbox is closed; the loop keeps running
+    /// until the already queued commands have been written.
+    ///
+    /// Transient write failures are retried per the channel's retry policy;
+    /// commands that still cannot be written are moved to the channel's dead
+    /// letter queue.
     async fn run_command_executor(
         protocol: Arc<RwLock<Box<dyn ChannelRuntime>>>,
         mut command_rx: mpsc::Receiver<ChannelCommand>,
-        channel_id: u32,
+        drain: Arc<Notify>,
+        mut context: CommandContext<R>,
     ) {
+        let channel_id = context.channel_id;
         debug!("Ch{} igw command executor started", channel_id);
 
-        while let Some(cmd) = command_rx.recv().await {
-            let mut protocol_guard = protocol.write().await;
-
-            match cmd {
-                ChannelCommand::Control {
-                    point_id, value, ..
-                } => {
-                    // Convert to internal_id: IGW pins use PointType offset encoding
-                    // to distinguish Control from Signal points with same point_id
-                    let internal_id = PointType::Control.to_internal_id(point_id);
-                    match protocol_guard.write_control(&[(internal_id, value)]).await {
-                        Ok(success_count) => {
-                            if success_count > 0 {
-                                debug!("Ch{} control pt{} = {} ok", channel_id, point_id, value);
-                            } else {
-                                warn!("Ch{} control pt{} = {} failed", channel_id, point_id, value);
-                            }
-                        },
-                        Err(e) => {
-                            error!("Ch{} control pt{} err: {}", channel_id, point_id, e);
-                        },
-                    }
+        let mut draining = false;
+        loop {
+            let cmd = tokio::select! {
+                cmd = command_rx.recv() => match cmd {
+                    Some(cmd) => cmd,
+                    None => break,
                 },
-                ChannelCommand::Adjustment {
-                    point_id, value, ..
-                } => {
-                    // Convert to internal_id: IGW pins use PointType offset encoding
-                    let internal_id = PointType::Adjustment.to_internal_id(point_id);
-                    match protocol_guard
-                        .write_adjustment(&[(internal_id, value)])
-                        .await
-                    {
-                        Ok(success_count) => {
-                            if success_count > 0 {
-                                debug!("Ch{} adjustment pt{} = {} ok", channel_id, point_id, value);
-                            } else {
-                                warn!(
-                                    "Ch{} adjustment pt{} = {} failed",
-                                    channel_id, point_id, value
-                                );
-                            }
-                        },
-                        Err(e) => {
-                            error!("Ch{} adjustment pt{} err: {}", channel_id, point_id, e);
-                        },
-                    }
+                _ = drain.notified(), if !draining => {
+                    draining = true;
+                    command_rx.close();
+                    debug!("Ch{} mailbox closed, draining", channel_id);
+                    continue;
                 },
+            };
+            let status = Self::execute_command(&protocol, &cmd, &mut context).await;
+            if status.status == "failed" {
+                let reason = status.error.clone().unwrap_or_default();
+                let entry = DeadLetterEntry::from_command(channel_id, &cmd, reason);
+                if let Err(e) = context.dead_letters.push(&entry).await {
+                    error!("Ch{} dead letter err: {}", channel_id, e);
+                }
+            }
+            if let Err(e) = context.tracker.record(&status).await {
+                debug!("Ch{} command tracking err: {}", channel_id, e);
+            }
+            if CommandWebhooks::global().is_active() {
+                context.notify_webhooks(&cmd, &status).await;
             }
         }
 
         debug!("Ch{} igw command executor stopped", channel_id);
     }
+
+    /// Write one command, retrying transient failures
+    ///
+    /// The protocol lock is released between attempts, so polling (and with
+    /// it reconnection) continues while a command waits for its next attempt.
+    #[allow(clippy::disallowed_methods)] // json! macro internally uses unwrap
+    async fn execute_command(
+        protocol: &Arc<RwLock<Box<dyn ChannelRuntime>>>,
+        cmd: &ChannelCommand,
+        context: &mut CommandContext<R>,
+    ) -> CommandStatus {
+        let channel_id = context.channel_id;
+        let policy = context.retry;
+        let (point_type, command_id, point_id, value) = match cmd {
+            ChannelCommand::Control {
+                command_id,
+                point_id,
+                value,
+                ..
+            } => (PointType::Control, command_id, *point_id, *value),
+            ChannelCommand::Adjustment {
+                command_id,
+                point_id,
+                value,
+                ..
+            } => (PointType::Adjustment, command_id, *point_id, *value),
+        };
+        // Convert to internal_id: IGW pins use PointType offset encoding
+        // to distinguish Control/Adjustment from Signal/Telemetry points with same point_id
+        let internal_id = point_type.to_internal_id(point_id);
+        let kind = match point_type {
+            PointType::Control => "control",
+            _ => "adjustment",
+        };
+
+        let mut status = CommandStatus {
+            command_id: command_id.clone(),
+            status: "executing".to_string(),
+            result: Some(serde_json::json!({
+                "point_type": point_type.as_str(),
+                "point_id": point_id,
+                "value": value,
+            })),
+            error: None,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            attempts: Vec::new(),
+        };
+
+        for attempt in 1..=policy.max_attempts {
+            let written = {
+                let mut protocol_guard = protocol.write().await;
+                match point_type {
+                    PointType::Control => {
+                        protocol_guard.write_control(&[(internal_id, value)]).await
+                    },
+                    _ => {
+                        protocol_guard
+                            .write_adjustment(&[(internal_id, value)])
+                            .await
+                    },
+                }
+            };
+            // A rejected write is answered by the device and fails again
+            let (error, transient) = match written {
+                Ok(success_count) if success_count > 0 => (None, false),
+                Ok(_) => (Some("write rejected".to_string()), false),
+                Err(e) => {
+                    let error = e.to_string();
+                    let transient = command_retry::is_transient_error(&error);
+                    (Some(error), transient)
+                },
+            };
+            status.timestamp = chrono::Utc::now().timestamp_millis();
+            status.attempts.push(CommandAttempt {
+                attempt,
+                at: status.timestamp,
+                error: error.clone(),
+            });
+
+            let Some(error) = error else {
+                debug!("Ch{} {} pt{} = {} ok", channel_id, kind, point_id, value);
+                status.status = "success".to_string();
+                return status;
+            };
+            if !transient || attempt >= policy.max_attempts {
+                error!(
+                    "Ch{} {} pt{} = {} failed: {}",
+                    channel_id, kind, point_id, value, error
+                );
+                status.status = "failed".to_string();
+                status.error = Some(error);
+                return status;
+            }
+
+            warn!(
+                "Ch{} {} pt{} attempt {}/{} failed: {}, retry in {:?}",
+                channel_id, kind, point_id, attempt, policy.max_attempts, error, policy.interval
+            );
+            status.error = Some(error);
+            if let Err(e) = context.tracker.record(&status).await {
+                debug!("Ch{} command tracking err: {}", channel_id, e);
+            }
+            tokio::time::sleep(policy.interval).await;
+
+            if policy.abort_on_value_change
+                && context.value_changed(point_type, point_id, value).await
+            {
+                info!(
+                    "Ch{} {} pt{} retry aborted: value changed",
+                    channel_id, kind, point_id
+                );
+                status.status = "aborted".to_string();
+                status.timestamp = chrono::Utc::now().timestamp_millis();
+                return status;
+            }
+        }
+
+        // Only reached with max_attempts == 0, which the policy parser rejects
+        status.status = "failed".to_string();
+        status
+    }
+}
+
+/// Per-channel state of the command executor
+struct CommandContext<R: Rtdb> {
+    channel_id: u32,
+    rtdb: Arc<R>,
+    retry: CommandRetryPolicy,
+    dead_letters: DeadLetterQueue<R>,
+    tracker: CommandTracker<R>,
+}
+
+impl<R: Rtdb> CommandContext<R> {
+    fn new(rtdb: Arc<R>, channel_id: u32, retry: CommandRetryPolicy) -> Self {
+        Self {
+            channel_id,
+            dead_letters: DeadLetterQueue::new(Arc::clone(&rtdb), channel_id),
+            tracker: CommandTracker::new(Arc::clone(&rtdb), channel_id),
+            rtdb,
+            retry,
+        }
+    }
+
+    /// Whether the point's current value differs from the command value
+    ///
+    /// Every control write updates the point hash, so a different value
+    /// means a newer command superseded this one.
+    async fn value_changed(&self, point_type: PointType, point_id: u32, value: f64) -> bool {
+        self.point_value(point_type, point_id)
+            .await
+            .is_some_and(|current| (current - value).abs() > f64::EPSILON)
+    }
+
+    /// Current value of a point in the channel hash
+    async fn point_value(&self, point_type: PointType, point_id: u32) -> Option<f64> {
+        let key = KeySpaceConfig::production_cached().channel_key(self.channel_id, point_type);
+        match self.rtdb.hash_get(&key, &point_id.to_string()).await {
+            Ok(Some(current)) => std::str::from_utf8(&current)
+                .ok()
+                .and_then(|v| v.parse::<f64>().ok()),
+            _ => None,
+        }
+    }
+
+    /// Report a finished command to the webhook registry
+    async fn notify_webhooks(&self, cmd: &ChannelCommand, status: &CommandStatus) {
+        let (point_type, point_id, value) = match cmd {
+            ChannelCommand::Control {
+                point_id, value, ..
+            } => (PointType::Control, *point_id, *value),
+            ChannelCommand::Adjustment {
+                point_id, value, ..
+            } => (PointType::Adjustment, *point_id, *value),
+        };
+        let read_back = self.point_value(point_type, point_id).await;
+        CommandWebhooks::global()
+            .completed(
+                self.channel_id,
+                point_type,
+                point_id,
+                value,
+                status,
+                read_back,
+            )
+            .await;
+    }
 }
 
 /// Run the polling task for all channels.
 ///
 /// Periodically calls poll_once() to retrieve data and write to store.
 /// The polling interval is configurable via `poll_interval_ms`.
+/// Poll results drive the connection state machine; once the link is lost
+/// the task reconnects with exponential backoff (capped at `MAX_RECONNECT_DELAY`).
+///
+/// With `keepalive`, an idle link is probed between polls, every poll is bounded
+/// by the keepalive timeout and repeated misses mark the link as lost right away.
+///
+/// Every poll cycle is recorded in the channel's statistics history.
 async fn run_polling_task<R: Rtdb>(
     protocol: Arc<RwLock<Box<dyn ChannelRuntime>>>,
     store: Arc<RedisDataStore<R>>,
+    connection: Arc<ConnectionStateMachine>,
+    history: StatsHistory<R>,
     channel_id: u32,
     poll_interval_ms: u64,
+    keepalive: Option<KeepaliveConfig>,
 ) {
     info!(
         "Ch{} polling task started (interval: {}ms)",
@@ -307,12 +897,91 @@ async fn run_polling_task<R: Rtdb>(
     // Track previous error count to detect new errors
     let mut prev_error_count: u64 = 0;
 
+    let poll_interval = tokio::time::Duration::from_millis(poll_interval_ms.max(1));
+    let mut reconnect_delay = poll_interval;
+    let mut next_reconnect = tokio::time::Instant::now();
+
+    let mut keepalive_timer = keepalive.map(|k| tokio::time::interval(k.interval));
+    let mut last_activity = tokio::time::Instant::now();
+    let mut missed_probes: u32 = 0;
+
     loop {
-        interval.tick().await;
+        let probe = tokio::select! {
+            _ = interval.tick() => false,
+            _ = tick_keepalive(&mut keepalive_timer) => true,
+        };
+        // Regular polls already prove the link is alive
+        if probe && keepalive.is_some_and(|k| last_activity.elapsed() < k.interval) {
+            continue;
+        }
+        // The active redundancy node owns the device
+        if Redundancy::global().is_standby() {
+            continue;
+        }
 
-        // Poll data using ChannelRuntime interface
         let mut protocol_guard = protocol.write().await;
-        let result: PollResult = protocol_guard.poll_once().await;
+
+        // Reconnect after a lost link (initial connect is done by the channel manager)
+        if connection.state().can_retry() && connection.snapshot().consecutive_failures > 0 {
+            if tokio::time::Instant::now() < next_reconnect {
+                continue;
+            }
+            if connect_protocol(&mut protocol_guard, &connection, channel_id)
+                .await
+                .is_err()
+            {
+                reconnect_delay = (reconnect_delay * 2).min(MAX_RECONNECT_DELAY);
+                next_reconnect = tokio::time::Instant::now() + reconnect_delay;
+                continue;
+            }
+            reconnect_delay = poll_interval;
+        }
+
+        // Poll data using ChannelRuntime interface
+        last_activity = tokio::time::Instant::now();
+        let poll_started_at = chrono::Utc::now().timestamp_millis();
+        let result: PollResult = match keepalive {
+            Some(k) => match tokio::time::timeout(k.timeout, protocol_guard.poll_once()).await {
+                Ok(result) => {
+                    missed_probes = 0;
+                    result
+                },
+                Err(_) => {
+                    missed_probes += 1;
+                    let error = format!(
+                        "no response within {}ms ({}/{} missed)",
+                        k.timeout.as_millis(),
+                        missed_probes,
+                        k.max_missed
+                    );
+                    warn!("Ch{} keepalive: {}", channel_id, error);
+                    record_poll_sample(
+                        &history,
+                        channel_id,
+                        PollSample {
+                            ts: poll_started_at,
+                            latency_ms: last_activity.elapsed().as_secs_f64() * 1000.0,
+                            points_updated: 0,
+                            failed_points: 0,
+                            error: Some(error.clone()),
+                        },
+                    )
+                    .await;
+                    record_event(&connection, ConnectionEvent::PollFailed { error });
+                    if missed_probes >= k.max_missed {
+                        // Drop the half-open session so the reconnect starts clean
+                        if let Err(e) = protocol_guard.disconnect().await {
+                            debug!("Ch{} disconnect after link loss: {}", channel_id, e);
+                        }
+                        record_event(&connection, ConnectionEvent::LinkLost);
+                        missed_probes = 0;
+                    }
+                    continue;
+                },
+            },
+            None => protocol_guard.poll_once().await,
+        };
+        let latency_ms = last_activity.elapsed().as_secs_f64() * 1000.0;
 
         // Log partial failures from poll result (before moving data)
         let failure_count = result.failures.len();
@@ -321,6 +990,19 @@ async fn run_polling_task<R: Rtdb>(
                 "Ch{} partial read failure: {} points failed",
                 channel_id, failure_count
             );
+            let error = result
+                .failures
+                .first()
+                .map(|f| {
+                    format!(
+                        "{} points failed, first {}: {}",
+                        failure_count, f.point_id, f.error
+                    )
+                })
+                .unwrap_or_default();
+            record_event(&connection, ConnectionEvent::PollFailed { error });
+        } else {
+            record_event(&connection, ConnectionEvent::PollSucceeded);
         }
 
         let count = result.data.len();
@@ -330,6 +1012,18 @@ async fn run_polling_task<R: Rtdb>(
                 error!("Ch{} failed to write to Redis: {}", channel_id, e);
             }
         }
+        record_poll_sample(
+            &history,
+            channel_id,
+            PollSample {
+                ts: poll_started_at,
+                latency_ms,
+                points_updated: count as u32,
+                failed_points: failure_count as u32,
+                error: None,
+            },
+        )
+        .await;
 
         // Check diagnostics for accumulated errors
         if let Ok(diag) = protocol_guard.diagnostics().await {
@@ -345,6 +1039,149 @@ async fn run_polling_task<R: Rtdb>(
     }
 }
 
+/// Run the heartbeat task of a channel.
+///
+/// Writes the heartbeat output and checks the device heartbeat input while the
+/// link is up; a stall is reported to the connection state machine, which
+/// keeps the channel `Degraded` until the heartbeat recovers.
+async fn run_heartbeat_task<R: Rtdb>(
+    protocol: Arc<RwLock<Box<dyn ChannelRuntime>>>,
+    rtdb: Arc<R>,
+    connection: Arc<ConnectionStateMachine>,
+    channel_id: u32,
+    config: HeartbeatConfig,
+) {
+    info!("Ch{} heartbeat task started", channel_id);
+    let mut monitor = HeartbeatMonitor::new(config, tokio::time::Instant::now());
+    let mut interval = tokio::time::interval(config.tick());
+    let mut stalled = false;
+    let mut was_connected = false;
+
+    loop {
+        interval.tick().await;
+        let now = tokio::time::Instant::now();
+
+        // Nothing to watch while the link is down; restart timing once it is back
+        let connected = connection.state().is_connected();
+        if !connected {
+            was_connected = false;
+            continue;
+        }
+        if !was_connected {
+            monitor.reset(now);
+            was_connected = true;
+        }
+
+        if let (Some(output), Some(value)) = (config.output, monitor.due_output(now)) {
+            let internal_id = output.point_type.to_internal_id(output.point_id);
+            let written = {
+                let mut protocol_guard = protocol.write().await;
+                match output.point_type {
+                    PointType::Control => {
+                        protocol_guard.write_control(&[(internal_id, value)]).await
+                    },
+                    _ => {
+                        protocol_guard
+                            .write_adjustment(&[(internal_id, value)])
+                            .await
+                    },
+                }
+            };
+            let ok = matches!(written, Ok(n) if n > 0);
+            if !ok {
+                debug!(
+                    "Ch{} heartbeat write failed: {:?}",
+                    channel_id,
+                    written.err()
+                );
+            }
+            monitor.output_written(ok);
+        }
+
+        if let Some(input) = config.input {
+            let key = KeySpaceConfig::production_cached().channel_key(channel_id, input.point_type);
+            let value = match rtdb.hash_get(&key, &input.point_id.to_string()).await {
+                Ok(Some(raw)) => std::str::from_utf8(&raw)
+                    .ok()
+                    .and_then(|v| v.parse::<f64>().ok()),
+                _ => None,
+            };
+            monitor.observe_input(value, now);
+        }
+
+        match monitor.stall_reason(now) {
+            Some(error) if !stalled => {
+                warn!("Ch{} heartbeat stalled: {}", channel_id, error);
+                stalled = true;
+                record_event(&connection, ConnectionEvent::HeartbeatStalled { error });
+            },
+            None if stalled => {
+                info!("Ch{} heartbeat restored", channel_id);
+                stalled = false;
+                record_event(&connection, ConnectionEvent::HeartbeatRestored);
+            },
+            _ => {},
+        }
+    }
+}
+
+/// Store a poll sample; RTDB failures only cost the sample.
+async fn record_poll_sample<R: Rtdb>(
+    history: &StatsHistory<R>,
+    channel_id: u32,
+    sample: PollSample,
+) {
+    if let Err(e) = history.record(&sample).await {
+        debug!("Ch{} stats history: {}", channel_id, e);
+    }
+}
+
+/// Wait for the next keepalive tick (never resolves when keepalive is disabled).
+async fn tick_keepalive(timer: &mut Option<tokio::time::Interval>) {
+    match timer {
+        Some(timer) => {
+            timer.tick().await;
+        },
+        None => std::future::pending().await,
+    }
+}
+
+/// Upper bound for the reconnect backoff in the polling task
+const MAX_RECONNECT_DELAY: tokio::time::Duration = tokio::time::Duration::from_secs(30);
+
+/// Connect a protocol client and record the attempt in the state machine.
+async fn connect_protocol(
+    protocol: &mut Box<dyn ChannelRuntime>,
+    connection: &ConnectionStateMachine,
+    channel_id: u32,
+) -> Result<(), String> {
+    record_event(connection, ConnectionEvent::ConnectRequested);
+    match protocol.connect().await {
+        Ok(()) => {
+            record_event(connection, ConnectionEvent::ConnectSucceeded);
+            Ok(())
+        },
+        Err(e) => {
+            let error = e.to_string();
+            warn!("Ch{} connect failed: {}", channel_id, error);
+            record_event(
+                connection,
+                ConnectionEvent::ConnectFailed {
+                    error: error.clone(),
+                },
+            );
+            Err(error)
+        },
+    }
+}
+
+/// Apply an event; invalid transitions are logged and otherwise ignored.
+fn record_event(connection: &ConnectionStateMachine, event: ConnectionEvent) {
+    if let Err(e) = connection.apply(event) {
+        debug!("{}", e);
+    }
+}
+
 // ============================================================================
 // Point Configuration Conversion
 // ============================================================================
@@ -721,11 +1558,14 @@ pub fn create_virtual_channel(
 /// * `host` - Modbus TCP server host address
 /// * `port` - Modbus TCP server port
 /// * `point_configs` - Point configurations with Modbus addresses
+/// * `blocks` - Limits for coalescing registers into block reads
+#[cfg(feature = "modbus")]
 pub fn create_modbus_channel(
     channel_id: u32,
     host: &str,
     port: u16,
     point_configs: Vec<PointConfig>,
+    blocks: BlockConfig,
 ) -> Box<dyn ChannelRuntime> {
     use igw::core::logging::{ChannelLogConfig, LoggableProtocol, TracingLogHandler};
 
@@ -733,6 +1573,8 @@ pub fn create_modbus_channel(
 
     let config = ModbusChannelConfig::tcp(&address)
         .with_points(point_configs)
+        .with_max_batch_size(blocks.max_batch_size)
+        .with_max_gap(blocks.max_gap)
         .with_reconnect(ReconnectConfig::default());
 
     let mut channel = ModbusChannel::new(config, channel_id);
@@ -758,16 +1600,21 @@ pub fn create_modbus_channel(
 /// * `device` - Serial device path (e.g., "/dev/ttyUSB0" on Linux)
 /// * `baud_rate` - Serial baud rate (e.g., 9600, 19200, 115200)
 /// * `point_configs` - Point configurations with Modbus addresses
+/// * `blocks` - Limits for coalescing registers into block reads
+#[cfg(feature = "modbus")]
 pub fn create_modbus_rtu_channel(
     channel_id: u32,
     device: &str,
     baud_rate: u32,
     point_configs: Vec<PointConfig>,
+    blocks: BlockConfig,
 ) -> Box<dyn ChannelRuntime> {
     use igw::core::logging::{ChannelLogConfig, LoggableProtocol, TracingLogHandler};
 
     let config = ModbusChannelConfig::rtu(device, baud_rate)
         .with_points(point_configs)
+        .with_max_batch_size(blocks.max_batch_size)
+        .with_max_gap(blocks.max_gap)
         .with_reconnect(ReconnectConfig::default());
 
     let mut channel = ModbusChannel::new(config, channel_id);
@@ -795,6 +1642,10 @@ pub fn create_modbus_rtu_channel(
 ///
 /// * `channel_id` - Unique channel identifier
 /// * `runtime_config` - Channel configuration containing GPIO pin mappings
+///
+/// Telemetry points with a `gpio_number` are pulse counters; points using
+/// `debounce_ms`, counters or `pulse_ms` get a [`DidoRuntime`] around the
+/// GPIO runtime.
 #[cfg(all(target_os = "linux", feature = "gpio"))]
 pub fn create_gpio_channel(
     channel_id: u32,
@@ -835,6 +1686,17 @@ pub fn create_gpio_channel(
         }
     }
 
+    // Pulse counters read their pin as an input of their own
+    for pt in &runtime_config.telemetry_points {
+        if let Some(gpio_num) = parse_gpio_number(&pt.base.protocol_mappings) {
+            let internal_id = PointType::Telemetry.to_internal_id(pt.base.point_id);
+            let pin_config = GpioPinConfig::digital_input_sysfs(gpio_num, internal_id)
+                .with_active_low(pt.reverse);
+
+            gpio_config = gpio_config.add_pin(pin_config);
+        }
+    }
+
     // Configure DO pins from control points (using sysfs with global GPIO numbers)
     // Use internal_id to avoid collision with signal points
     for pt in &runtime_config.control_points {
@@ -848,11 +1710,19 @@ pub fn create_gpio_channel(
     }
 
     let channel = GpioChannel::new(gpio_config);
-    Box::new(GpioRuntime::new(
+    let runtime: Box<dyn ChannelRuntime> = Box::new(GpioRuntime::new(
         channel_id,
         format!("gpio_{}", channel_id),
         channel,
-    ))
+    ));
+
+    // Debounce, counters and pulse outputs only when a point asks for them
+    let dido = DidoPoints::from_runtime_config(runtime_config);
+    if dido.is_plain() {
+        runtime
+    } else {
+        Box::new(DidoRuntime::new(runtime, dido))
+    }
 }
 
 // ============================================================================
@@ -869,8 +1739,10 @@ pub type ChannelImpl<R> = Arc<RwLock<IgwChannelWrapper<R>>>;
 impl<R: Rtdb> IgwChannelWrapper<R> {
     /// Get channel status.
     pub async fn get_status(&self) -> ChannelStatus {
+        let state: ConnectionState = self.connection.state();
         ChannelStatus {
-            is_connected: self.is_connected().await,
+            is_connected: state.is_connected(),
+            state,
             last_update: chrono::Utc::now().timestamp(),
         }
     }
@@ -878,11 +1750,18 @@ impl<R: Rtdb> IgwChannelWrapper<R> {
     /// Get diagnostics information.
     #[allow(clippy::disallowed_methods)] // json! macro internally uses unwrap
     pub async fn get_diagnostics(&self) -> crate::error::Result<serde_json::Value> {
-        let is_connected = self.is_connected().await;
+        let connection = self.connection.snapshot();
+        let isolation = self.isolation();
         Ok(serde_json::json!({
             "protocol_type": "igw",
-            "connected": is_connected,
-            "channel_id": self.channel_id()
+            "connected": connection.state.is_connected(),
+            "connection_state": connection.state,
+            "consecutive_failures": connection.consecutive_failures,
+            "last_error": connection.last_error,
+            "heartbeat_stalled": connection.heartbeat_stalled,
+            "channel_id": self.channel_id(),
+            "isolation": isolation.mode.as_str(),
+            "mailbox_size": isolation.mailbox_size
         }))
     }
 }
@@ -894,7 +1773,11 @@ impl<R: Rtdb> IgwChannelWrapper<R> {
 /// in edge cases where the channel is dropped unexpectedly.
 impl<R: Rtdb> Drop for IgwChannelWrapper<R> {
     fn drop(&mut self) {
-        if self.executor_handle.is_some() || self.polling_handle.is_some() {
+        if self.executor_handle.is_some()
+            || self.polling_handle.is_some()
+            || self.heartbeat_handle.is_some()
+            || self.runtime.is_some()
+        {
             warn!(
                 "Ch{} IgwChannelWrapper dropped without explicit cleanup, aborting tasks",
                 self.channel_id
@@ -904,204 +1787,6 @@ impl<R: Rtdb> Drop for IgwChannelWrapper<R> {
     }
 }
 
-// ============================================================================
-// CAN Channel Creation
-// ============================================================================
-
-/// CAN protocol mapping from protocol_mappings JSON field
-#[cfg(all(feature = "can", target_os = "linux"))]
-#[derive(Debug, Clone, serde::Deserialize)]
-struct CanProtocolMapping {
-    can_id: u32,
-    start_bit: u32,
-    bit_length: u32,
-    #[serde(default)]
-    data_type: CanDataType,
-    #[serde(default = "default_scale")]
-    scale: f64,
-    #[serde(default)]
-    offset: f64,
-}
-
-#[cfg(all(feature = "can", target_os = "linux"))]
-fn default_scale() -> f64 {
-    1.0
-}
-
-#[cfg(all(feature = "can", target_os = "linux"))]
-/// Convert RuntimeChannelConfig to IGW CanPoint list for CAN protocol.
-///
-/// Parses CAN configuration from each point's protocol_mappings JSON field.
-/// Scale and offset are applied during decoding in the protocol layer.
-///
-/// **Important**: Uses `PointType::to_internal_id()` to encode type into point_id.
-pub fn convert_to_can_point_configs(runtime_config: &RuntimeChannelConfig) -> Vec<CanPoint> {
-    let mut configs = Vec::new();
-
-    // Helper to parse protocol_mappings JSON and create CanPoint
-    let parse_can_point =
-        |internal_id: u32, protocol_mappings: &Option<String>| -> Option<CanPoint> {
-            let json_str = protocol_mappings.as_ref()?;
-            let mapping: CanProtocolMapping = serde_json::from_str(json_str)
-                .map_err(|e| {
-                    tracing::warn!(
-                        internal_id,
-                        error = %e,
-                        "Failed to parse CAN protocol_mappings JSON"
-                    );
-                    e
-                })
-                .ok()?;
-
-            Some(CanPoint {
-                point_id: internal_id,
-                can_id: mapping.can_id,
-                byte_offset: (mapping.start_bit / 8) as u8,
-                bit_position: (mapping.start_bit % 8) as u8,
-                bit_length: mapping.bit_length as u8,
-                data_type: mapping.data_type,
-                scale: mapping.scale,
-                offset: mapping.offset,
-            })
-        };
-
-    // Collect from all point types with internal_id encoding
-    for pt in &runtime_config.telemetry_points {
-        let internal_id = PointType::Telemetry.to_internal_id(pt.base.point_id);
-        if let Some(can_point) = parse_can_point(internal_id, &pt.base.protocol_mappings) {
-            configs.push(can_point);
-        }
-    }
-    for pt in &runtime_config.signal_points {
-        let internal_id = PointType::Signal.to_internal_id(pt.base.point_id);
-        if let Some(can_point) = parse_can_point(internal_id, &pt.base.protocol_mappings) {
-            configs.push(can_point);
-        }
-    }
-    for pt in &runtime_config.control_points {
-        let internal_id = PointType::Control.to_internal_id(pt.base.point_id);
-        if let Some(can_point) = parse_can_point(internal_id, &pt.base.protocol_mappings) {
-            configs.push(can_point);
-        }
-    }
-    for pt in &runtime_config.adjustment_points {
-        let internal_id = PointType::Adjustment.to_internal_id(pt.base.point_id);
-        if let Some(can_point) = parse_can_point(internal_id, &pt.base.protocol_mappings) {
-            configs.push(can_point);
-        }
-    }
-
-    configs
-}
-
-#[cfg(all(feature = "can", target_os = "linux"))]
-/// Convert runtime CAN mappings to IGW PointConfig format (for RedisDataStore).
-///
-/// This conversion is used to register points with the data store for proper
-/// data transformation and storage.
-/// Parses CAN configuration from each point's protocol_mappings JSON field.
-///
-/// **Important**: Uses `PointType::to_internal_id()` to encode type into point_id.
-pub fn convert_can_to_igw_point_configs(runtime_config: &RuntimeChannelConfig) -> Vec<PointConfig> {
-    use igw::core::point::ProtocolAddress;
-
-    let mut configs = Vec::new();
-
-    // Helper to build protocol address from CAN mapping
-    let build_protocol_addr = |protocol_mappings: &Option<String>| -> Option<ProtocolAddress> {
-        let json_str = protocol_mappings.as_ref()?;
-        let mapping: CanProtocolMapping = serde_json::from_str(json_str).ok()?;
-        Some(ProtocolAddress::Generic(format!(
-            "can_id:0x{:X},start_bit:{},len:{}",
-            mapping.can_id, mapping.start_bit, mapping.bit_length
-        )))
-    };
-
-    // Telemetry points
-    for pt in &runtime_config.telemetry_points {
-        if let Some(protocol_addr) = build_protocol_addr(&pt.base.protocol_mappings) {
-            let internal_id = PointType::Telemetry.to_internal_id(pt.base.point_id);
-            let transform = TransformConfig {
-                scale: pt.scale,
-                offset: pt.offset,
-                reverse: pt.reverse,
-                ..Default::default()
-            };
-            let config = PointConfig::new(internal_id, protocol_addr).with_transform(transform);
-            configs.push(config);
-        }
-    }
-
-    // Signal points
-    for pt in &runtime_config.signal_points {
-        if let Some(protocol_addr) = build_protocol_addr(&pt.base.protocol_mappings) {
-            let internal_id = PointType::Signal.to_internal_id(pt.base.point_id);
-            let transform = TransformConfig {
-                reverse: pt.reverse,
-                ..Default::default()
-            };
-            let config = PointConfig::new(internal_id, protocol_addr).with_transform(transform);
-            configs.push(config);
-        }
-    }
-
-    // Control points
-    for pt in &runtime_config.control_points {
-        if let Some(protocol_addr) = build_protocol_addr(&pt.base.protocol_mappings) {
-            let internal_id = PointType::Control.to_internal_id(pt.base.point_id);
-            let transform = TransformConfig {
-                reverse: pt.reverse,
-                ..Default::default()
-            };
-            let config = PointConfig::new(internal_id, protocol_addr).with_transform(transform);
-            configs.push(config);
-        }
-    }
-
-    // Adjustment points
-    for pt in &runtime_config.adjustment_points {
-        if let Some(protocol_addr) = build_protocol_addr(&pt.base.protocol_mappings) {
-            let internal_id = PointType::Adjustment.to_internal_id(pt.base.point_id);
-            let transform = TransformConfig {
-                scale: pt.scale,
-                offset: pt.offset,
-                ..Default::default()
-            };
-            let config = PointConfig::new(internal_id, protocol_addr).with_transform(transform);
-            configs.push(config);
-        }
-    }
-
-    configs
-}
-
-#[cfg(all(feature = "can", target_os = "linux"))]
-/// Create an IGW CAN channel with the given configuration wrapped as ChannelRuntime.
-///
-/// This function creates a CanClient from igw library with the specified
-/// CAN interface and point configurations.
-pub fn create_can_channel(
-    channel_id: u32,
-    can_interface: &str,
-    points: Vec<CanPoint>,
-) -> Box<dyn ChannelRuntime> {
-    let config = CanConfig {
-        can_interface: can_interface.to_string(),
-        bitrate: 250000,
-        rx_poll_interval_ms: 50,
-        data_read_interval_ms: 1000,
-    };
-
-    let mut client = CanClient::new(config);
-    client.add_points(points);
-
-    Box::new(CanRuntime::new(
-        channel_id,
-        format!("can_{}", channel_id),
-        client,
-    ))
-}
-
 // ============================================================================
 // Unit Tests
 // ============================================================================
@@ -1168,6 +1853,7 @@ mod tests {
             on_value: 1,
             off_value: 0,
             pulse_duration_ms: None,
+            access: Default::default(),
         });
 
         config.adjustment_points.push(AdjustmentPoint {
@@ -1184,6 +1870,7 @@ mod tests {
             data_type: "float32".to_string(),
             scale: 1.0,
             offset: 0.0,
+            access: Default::default(),
         });
 
         config
@@ -1412,9 +2099,14 @@ mod tests {
 
         // Spawn command executor
         let mock_clone = Arc::clone(&mock);
+        let rtdb = Arc::new(voltage_rtdb::MemoryRtdb::new());
+        let context = CommandContext::new(Arc::clone(&rtdb), 1, CommandRetryPolicy::default());
         let handle = tokio::spawn(async move {
             IgwChannelWrapper::<voltage_rtdb::MemoryRtdb>::run_command_executor(
-                mock_clone, rx, 1, // channel_id
+                mock_clone,
+                rx,
+                Arc::new(Notify::new()),
+                context,
             )
             .await;
         });
@@ -1468,11 +2160,193 @@ mod tests {
             "Adjustment internal_id should be >= OFFSET*3 (0xC0000000)"
         );
 
+        // Successful writes never reach the dead letter queue
+        let dead_letters = DeadLetterQueue::new(Arc::clone(&rtdb), 1)
+            .list()
+            .await
+            .unwrap();
+        assert!(dead_letters.is_empty());
+
+        // Each command is tracked with its single attempt
+        let tracked = CommandTracker::new(rtdb, 1)
+            .get("test-adj-1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(tracked.status, "success");
+        assert_eq!(tracked.attempts.len(), 1);
+
         // Cleanup
         drop(tx);
         let _ = tokio::time::timeout(tokio::time::Duration::from_millis(100), handle).await;
     }
 
+    #[test]
+    fn test_channel_isolation_from_parameters() {
+        let defaults = ChannelIsolation::from_parameters(&HashMap::new());
+        assert_eq!(defaults, ChannelIsolation::default());
+        assert_eq!(
+            defaults.mailbox_size,
+            common::resource_profile::tuning().command_mailbox_size
+        );
+
+        let mut params = HashMap::new();
+        params.insert("isolation".to_string(), serde_json::json!("Dedicated"));
+        params.insert("mailbox_size".to_string(), serde_json::json!(16));
+        let isolation = ChannelIsolation::from_parameters(&params);
+        assert_eq!(isolation.mode, IsolationMode::Dedicated);
+        assert_eq!(isolation.mailbox_size, 16);
+
+        // Unknown mode and zero mailbox fall back to defaults
+        params.insert("isolation".to_string(), serde_json::json!("bogus"));
+        params.insert("mailbox_size".to_string(), serde_json::json!(0));
+        assert_eq!(
+            ChannelIsolation::from_parameters(&params),
+            ChannelIsolation::default()
+        );
+    }
+
+    #[test]
+    fn test_keepalive_from_parameters() {
+        let mut params = HashMap::new();
+        assert_eq!(KeepaliveConfig::from_parameters(&params), None);
+
+        params.insert("keepalive".to_string(), serde_json::json!(true));
+        let defaults = KeepaliveConfig::from_parameters(&params).unwrap();
+        assert_eq!(defaults, KeepaliveConfig::default());
+        assert!(defaults.probe_point().is_none());
+
+        params.insert(
+            "keepalive".to_string(),
+            serde_json::json!({
+                "interval_ms": 1000,
+                "max_missed": 0,
+                "register": {"slave_id": 2, "address": 40}
+            }),
+        );
+        let keepalive = KeepaliveConfig::from_parameters(&params).unwrap();
+        assert_eq!(keepalive.interval, Duration::from_millis(1000));
+        assert_eq!(keepalive.timeout, KeepaliveConfig::default().timeout);
+        assert_eq!(keepalive.max_missed, 2);
+        let probe = keepalive.probe_point().unwrap();
+        assert_eq!(probe.id, KEEPALIVE_POINT_ID);
+        match probe.address {
+            ProtocolAddress::Modbus(addr) => {
+                assert_eq!(
+                    (addr.slave_id, addr.function_code, addr.register),
+                    (2, 3, 40)
+                );
+            },
+            other => panic!("unexpected probe address {:?}", other),
+        }
+
+        params.insert(
+            "keepalive".to_string(),
+            serde_json::json!({"enabled": false, "interval_ms": 1000}),
+        );
+        assert_eq!(KeepaliveConfig::from_parameters(&params), None);
+    }
+
+    /// Dedicated channels execute commands on their own runtime thread and
+    /// release it on shutdown.
+    #[tokio::test]
+    async fn test_dedicated_isolation_runs_on_own_runtime() {
+        let rtdb = Arc::new(voltage_rtdb::MemoryRtdb::new());
+        let routing_cache = Arc::new(voltage_rtdb::RoutingCache::new());
+        let store = Arc::new(RedisDataStore::new(rtdb, routing_cache));
+        let (tx, rx) = mpsc::channel::<ChannelCommand>(4);
+
+        let mut wrapper = IgwChannelWrapper::new(
+            Box::new(MockChannelRuntime::new()),
+            7,
+            store,
+            rx,
+            60_000,
+            ChannelOptions {
+                isolation: ChannelIsolation {
+                    mode: IsolationMode::Dedicated,
+                    mailbox_size: 4,
+                },
+                ..Default::default()
+            },
+        );
+        assert_eq!(wrapper.isolation().mode, IsolationMode::Dedicated);
+
+        let diag = wrapper.get_diagnostics().await.unwrap();
+        assert_eq!(diag["isolation"], "dedicated");
+        assert_eq!(diag["mailbox_size"], 4);
+
+        tx.send(ChannelCommand::Control {
+            command_id: "iso-1".to_string(),
+            point_id: 1,
+            value: 1.0,
+            timestamp: 0,
+        })
+        .await
+        .unwrap();
+
+        // Executor on the dedicated runtime drains the mailbox
+        let drained = tokio::time::timeout(tokio::time::Duration::from_secs(1), async {
+            while tx.capacity() < 4 {
+                tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        assert!(drained.is_ok());
+
+        wrapper.shutdown();
+        assert!(wrapper.runtime.is_none());
+    }
+
+    /// Draining writes commands already in the mailbox and refuses new ones.
+    #[tokio::test]
+    async fn test_drain_finishes_queued_commands() {
+        let rtdb = Arc::new(voltage_rtdb::MemoryRtdb::new());
+        let routing_cache = Arc::new(voltage_rtdb::RoutingCache::new());
+        let store = Arc::new(RedisDataStore::new(rtdb, routing_cache));
+        let (tx, rx) = mpsc::channel::<ChannelCommand>(8);
+
+        let mut wrapper = IgwChannelWrapper::new(
+            Box::new(MockChannelRuntime::new()),
+            8,
+            store,
+            rx,
+            60_000,
+            ChannelOptions::default(),
+        );
+
+        // Hold the protocol lock so the commands stay queued
+        let guard = Arc::clone(wrapper.protocol()).write_owned().await;
+        for point_id in 1..=3 {
+            tx.send(ChannelCommand::Adjustment {
+                command_id: format!("drain-{}", point_id),
+                point_id,
+                value: 1.0,
+                timestamp: 0,
+            })
+            .await
+            .unwrap();
+        }
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(guard);
+        });
+
+        assert!(wrapper.drain(Duration::from_secs(1)).await);
+        assert_eq!(tx.capacity(), 8);
+        assert!(tx
+            .send(ChannelCommand::Control {
+                command_id: "late".to_string(),
+                point_id: 1,
+                value: 0.0,
+                timestamp: 0,
+            })
+            .await
+            .is_err());
+
+        wrapper.disconnect().await.unwrap();
+    }
+
     /// Test the specific internal_id encoding for all four point types.
     #[test]
     fn test_internal_id_encoding_for_all_point_types() {
@@ -0,0 +1,550 @@
+//! Per-point poll groups
+//!
+//! Points of a channel can be polled at different rates: the `poll_group`
+//! mapping column puts a point in the `fast`, `normal` (default) or `slow`
+//! group. Control readbacks and critical telemetry go to `fast`, slow
+//! diagnostics to `slow`, everything else keeps the channel's
+//! `poll_interval_ms`.
+//!
+//! A grouped channel runs one runtime per group (see [`GroupedRuntime`]) and
+//! the polling task ticks at the fastest group's interval. Due groups are
+//! polled highest priority first, and the scheduler is asked again after
+//! every group, so a fast group that falls due while a slow group is being
+//! read is polled before the remaining lower-priority groups.
+//!
+//! ```json
+//! {
+//!   "poll_interval_ms": 1000,
+//!   "fast_poll_interval_ms": 200,
+//!   "slow_poll_interval_ms": 30000
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use igw::core::traits::{DataEventReceiver, Diagnostics, PollResult};
+use igw::gateway::ChannelRuntime;
+use igw::{DataBatch, GatewayError};
+use tokio::time::Instant;
+
+use crate::core::config::RuntimeChannelConfig;
+
+/// Default `poll_interval_ms` (normal group)
+pub const DEFAULT_NORMAL_INTERVAL: Duration = Duration::from_millis(1000);
+
+/// Fast group interval when only the normal one is set: a quarter of it
+const DEFAULT_FAST_DIVISOR: u32 = 4;
+
+/// Slow group interval when only the normal one is set: ten times it
+const DEFAULT_SLOW_FACTOR: u32 = 10;
+
+/// Shortest interval a group can be polled at
+const MIN_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Poll group of a point, in priority order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PollGroup {
+    Fast,
+    Normal,
+    Slow,
+}
+
+impl PollGroup {
+    pub const ALL: [PollGroup; 3] = [PollGroup::Fast, PollGroup::Normal, PollGroup::Slow];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "fast" | "high" => Some(Self::Fast),
+            "normal" | "" => Some(Self::Normal),
+            "slow" | "low" => Some(Self::Slow),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Fast => "fast",
+            Self::Normal => "normal",
+            Self::Slow => "slow",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    /// `poll_group` of a point mapping; unknown or missing groups are normal
+    pub fn of_mapping(mapping: Option<&str>) -> Self {
+        mapping
+            .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+            .and_then(|m| m.get("poll_group")?.as_str().and_then(Self::parse))
+            .unwrap_or(Self::Normal)
+    }
+}
+
+/// Poll interval of each group
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollGroupIntervals {
+    pub fast: Duration,
+    pub normal: Duration,
+    pub slow: Duration,
+}
+
+impl Default for PollGroupIntervals {
+    fn default() -> Self {
+        Self::from_normal(DEFAULT_NORMAL_INTERVAL)
+    }
+}
+
+impl PollGroupIntervals {
+    fn from_normal(normal: Duration) -> Self {
+        Self {
+            fast: (normal / DEFAULT_FAST_DIVISOR).max(MIN_INTERVAL),
+            normal,
+            slow: normal * DEFAULT_SLOW_FACTOR,
+        }
+    }
+
+    /// Read `poll_interval_ms`, `fast_poll_interval_ms` and
+    /// `slow_poll_interval_ms` from the channel parameters
+    pub fn from_parameters(params: &HashMap<String, serde_json::Value>) -> Self {
+        let millis = |key: &str| {
+            params
+                .get(key)
+                .and_then(|v| v.as_u64())
+                .filter(|ms| *ms > 0)
+                .map(|ms| Duration::from_millis(ms).max(MIN_INTERVAL))
+        };
+        let defaults =
+            Self::from_normal(millis("poll_interval_ms").unwrap_or(DEFAULT_NORMAL_INTERVAL));
+        Self {
+            fast: millis("fast_poll_interval_ms").unwrap_or(defaults.fast),
+            slow: millis("slow_poll_interval_ms").unwrap_or(defaults.slow),
+            ..defaults
+        }
+    }
+
+    pub fn get(&self, group: PollGroup) -> Duration {
+        match group {
+            PollGroup::Fast => self.fast,
+            PollGroup::Normal => self.normal,
+            PollGroup::Slow => self.slow,
+        }
+    }
+}
+
+/// Poll group of every point of a channel, keyed by internal point ID
+pub fn point_groups(runtime_config: &RuntimeChannelConfig) -> HashMap<u32, PollGroup> {
+    runtime_config
+        .points()
+        .map(|(point_type, point)| {
+            (
+                point_type.to_internal_id(point.point_id),
+                PollGroup::of_mapping(point.protocol_mappings.as_deref()),
+            )
+        })
+        .collect()
+}
+
+/// Decides which poll group is due next
+#[derive(Debug, Clone)]
+pub struct PollScheduler {
+    intervals: PollGroupIntervals,
+    /// Next poll of each group; `None` for groups without points
+    next_due: [Option<Instant>; 3],
+}
+
+impl PollScheduler {
+    /// Schedule the given groups; all of them are due right away
+    pub fn new(
+        intervals: PollGroupIntervals,
+        groups: impl IntoIterator<Item = PollGroup>,
+        now: Instant,
+    ) -> Self {
+        let mut next_due = [None; 3];
+        for group in groups {
+            next_due[group.index()] = Some(now);
+        }
+        Self {
+            intervals,
+            next_due,
+        }
+    }
+
+    /// Groups with points, in priority order
+    pub fn groups(&self) -> impl Iterator<Item = PollGroup> + '_ {
+        PollGroup::ALL
+            .into_iter()
+            .filter(|g| self.next_due[g.index()].is_some())
+    }
+
+    /// Interval the polling task has to tick at: the fastest group's
+    pub fn tick(&self) -> Duration {
+        self.groups()
+            .map(|g| self.intervals.get(g))
+            .min()
+            .unwrap_or(self.intervals.normal)
+    }
+
+    /// Highest-priority group due at `now`
+    pub fn next_due(&self, now: Instant) -> Option<PollGroup> {
+        self.groups()
+            .find(|g| self.next_due[g.index()].is_some_and(|due| due <= now))
+    }
+
+    /// Record a poll of `group` that finished at `now`
+    ///
+    /// The next poll keeps the group's cadence; a group that fell behind
+    /// (e.g. a slow device) restarts from `now` instead of catching up.
+    pub fn polled(&mut self, group: PollGroup, now: Instant) {
+        let interval = self.intervals.get(group);
+        if let Some(due) = self.next_due[group.index()].as_mut() {
+            *due += interval;
+            if *due <= now {
+                *due = now + interval;
+            }
+        }
+    }
+}
+
+/// Channel runtime polling its points in groups, one inner runtime per group
+///
+/// Writes are routed to the runtime holding the point; points the routing
+/// table does not know go to the normal (or first) group.
+pub struct GroupedRuntime {
+    id: u32,
+    name: String,
+    protocol: String,
+    runtimes: Vec<(PollGroup, Box<dyn ChannelRuntime>)>,
+    /// Internal point ID -> index into `runtimes`
+    routes: HashMap<u32, usize>,
+    scheduler: PollScheduler,
+}
+
+impl GroupedRuntime {
+    /// Build one runtime per group from the points assigned to it
+    ///
+    /// `create` receives the group and its points; groups without points get
+    /// no runtime.
+    pub fn new<P>(
+        id: u32,
+        name: impl Into<String>,
+        protocol: impl Into<String>,
+        points: Vec<P>,
+        group_of: impl Fn(&P) -> (u32, PollGroup),
+        intervals: PollGroupIntervals,
+        mut create: impl FnMut(PollGroup, Vec<P>) -> Box<dyn ChannelRuntime>,
+    ) -> Self {
+        let mut grouped: [Vec<P>; 3] = Default::default();
+        let mut point_groups = HashMap::new();
+        for point in points {
+            let (internal_id, group) = group_of(&point);
+            point_groups.insert(internal_id, group);
+            grouped[group.index()].push(point);
+        }
+
+        let mut runtimes = Vec::new();
+        for (group, points) in PollGroup::ALL.into_iter().zip(grouped) {
+            if !points.is_empty() {
+                runtimes.push((group, create(group, points)));
+            }
+        }
+        let routes = point_groups
+            .into_iter()
+            .filter_map(|(internal_id, group)| {
+                let index = runtimes.iter().position(|(g, _)| *g == group)?;
+                Some((internal_id, index))
+            })
+            .collect();
+        let scheduler =
+            PollScheduler::new(intervals, runtimes.iter().map(|(g, _)| *g), Instant::now());
+
+        Self {
+            id,
+            name: name.into(),
+            protocol: protocol.into(),
+            runtimes,
+            routes,
+            scheduler,
+        }
+    }
+
+    /// Interval the polling task has to tick at
+    pub fn tick(&self) -> Duration {
+        self.scheduler.tick()
+    }
+
+    fn default_route(&self) -> usize {
+        self.runtimes
+            .iter()
+            .position(|(g, _)| *g == PollGroup::Normal)
+            .unwrap_or(0)
+    }
+
+    /// Split writes by the runtime holding each point
+    fn route(&self, values: &[(u32, f64)]) -> Vec<(usize, Vec<(u32, f64)>)> {
+        let mut routed: Vec<(usize, Vec<(u32, f64)>)> = Vec::new();
+        for &(internal_id, value) in values {
+            let index = self
+                .routes
+                .get(&internal_id)
+                .copied()
+                .unwrap_or_else(|| self.default_route());
+            match routed.iter_mut().find(|(i, _)| *i == index) {
+                Some((_, values)) => values.push((internal_id, value)),
+                None => routed.push((index, vec![(internal_id, value)])),
+            }
+        }
+        routed
+    }
+
+    async fn write(&mut self, values: &[(u32, f64)], control: bool) -> igw::Result<usize> {
+        let mut written = 0;
+        let mut last_error = None;
+        for (index, values) in self.route(values) {
+            let runtime = &mut self.runtimes[index].1;
+            let result = if control {
+                runtime.write_control(&values).await
+            } else {
+                runtime.write_adjustment(&values).await
+            };
+            match result {
+                Ok(n) => written += n,
+                Err(e) => last_error = Some(e),
+            }
+        }
+        match last_error {
+            Some(e) if written == 0 => Err(e),
+            _ => Ok(written),
+        }
+    }
+}
+
+#[async_trait]
+impl ChannelRuntime for GroupedRuntime {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn protocol(&self) -> &str {
+        &self.protocol
+    }
+
+    fn is_event_driven(&self) -> bool {
+        false
+    }
+
+    async fn connect(&mut self) -> igw::Result<()> {
+        let mut result = Ok(());
+        for (_, runtime) in &mut self.runtimes {
+            if let Err(e) = runtime.connect().await {
+                result = Err(e);
+            }
+        }
+        result
+    }
+
+    async fn disconnect(&mut self) -> igw::Result<()> {
+        let mut result = Ok(());
+        for (_, runtime) in &mut self.runtimes {
+            if let Err(e) = runtime.disconnect().await {
+                result = Err(e);
+            }
+        }
+        result
+    }
+
+    /// Poll the due groups, re-checking priorities after every group
+    async fn poll_once(&mut self) -> PollResult {
+        let mut data = DataBatch::new();
+        let mut failures = Vec::new();
+        // A fast group can be polled again within one call, but never forever
+        for _ in 0..self.runtimes.len() * 2 {
+            let Some(group) = self.scheduler.next_due(Instant::now()) else {
+                break;
+            };
+            let Some((_, runtime)) = self.runtimes.iter_mut().find(|(g, _)| *g == group) else {
+                break;
+            };
+            let result = runtime.poll_once().await;
+            self.scheduler.polled(group, Instant::now());
+            data.merge(result.data);
+            failures.extend(result.failures);
+        }
+        PollResult::partial(data, failures)
+    }
+
+    async fn write_control(&mut self, commands: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(commands, true).await
+    }
+
+    async fn write_adjustment(&mut self, adjustments: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(adjustments, false).await
+    }
+
+    fn subscribe(&self) -> Option<DataEventReceiver> {
+        None
+    }
+
+    async fn start_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn stop_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    /// Normal group's connection state, counters summed over all groups
+    async fn diagnostics(&self) -> igw::Result<Diagnostics> {
+        let mut combined: Option<Diagnostics> = None;
+        let mut groups = serde_json::Map::new();
+        for (index, (group, runtime)) in self.runtimes.iter().enumerate() {
+            let diagnostics = runtime.diagnostics().await?;
+            groups.insert(
+                group.as_str().to_string(),
+                serde_json::json!({
+                    "interval_ms": self.scheduler.intervals.get(*group).as_millis() as u64,
+                    "connection_state": format!("{:?}", diagnostics.connection_state),
+                    "read_count": diagnostics.read_count,
+                    "error_count": diagnostics.error_count,
+                }),
+            );
+            match combined.as_mut() {
+                None => combined = Some(diagnostics),
+                Some(total) => {
+                    if index == self.default_route() {
+                        total.connection_state = diagnostics.connection_state;
+                    }
+                    total.read_count += diagnostics.read_count;
+                    total.write_count += diagnostics.write_count;
+                    total.error_count += diagnostics.error_count;
+                    if diagnostics.last_error.is_some() {
+                        total.last_error = diagnostics.last_error;
+                    }
+                },
+            }
+        }
+        let mut diagnostics = combined.ok_or(GatewayError::NotConnected)?;
+        diagnostics.extra = serde_json::json!({ "poll_groups": groups });
+        Ok(diagnostics)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use voltage_model::PointType;
+
+    #[test]
+    fn test_intervals_from_parameters() {
+        let intervals = PollGroupIntervals::from_parameters(&HashMap::from([
+            ("poll_interval_ms".to_string(), json!(2000)),
+            ("slow_poll_interval_ms".to_string(), json!(60000)),
+        ]));
+        assert_eq!(intervals.fast, Duration::from_millis(500));
+        assert_eq!(intervals.normal, Duration::from_millis(2000));
+        assert_eq!(intervals.slow, Duration::from_secs(60));
+        assert_eq!(
+            PollGroup::of_mapping(Some(r#"{"slave_id": 1, "poll_group": "FAST"}"#)),
+            PollGroup::Fast
+        );
+        assert_eq!(PollGroup::of_mapping(Some("{}")), PollGroup::Normal);
+    }
+
+    #[test]
+    fn test_scheduler_priorities() {
+        let intervals = PollGroupIntervals {
+            fast: Duration::from_millis(100),
+            normal: Duration::from_millis(1000),
+            slow: Duration::from_millis(5000),
+        };
+        let start = Instant::now();
+        let mut scheduler = PollScheduler::new(
+            intervals,
+            [PollGroup::Slow, PollGroup::Fast, PollGroup::Normal],
+            start,
+        );
+        assert_eq!(scheduler.tick(), Duration::from_millis(100));
+
+        // Everything is due at start, fast first
+        assert_eq!(scheduler.next_due(start), Some(PollGroup::Fast));
+        scheduler.polled(PollGroup::Fast, start);
+        assert_eq!(scheduler.next_due(start), Some(PollGroup::Normal));
+        scheduler.polled(PollGroup::Normal, start);
+        assert_eq!(scheduler.next_due(start), Some(PollGroup::Slow));
+
+        // A slow poll that overruns lets fast preempt the next normal poll
+        let later = start + Duration::from_millis(1200);
+        scheduler.polled(PollGroup::Slow, later);
+        assert_eq!(scheduler.next_due(later), Some(PollGroup::Fast));
+        scheduler.polled(PollGroup::Fast, later);
+        assert_eq!(scheduler.next_due(later), Some(PollGroup::Normal));
+        scheduler.polled(PollGroup::Normal, later);
+        assert_eq!(scheduler.next_due(later), None);
+
+        // Fast fell behind and restarts from the poll time instead of catching up
+        assert_eq!(scheduler.next_due(later + Duration::from_millis(99)), None);
+        assert_eq!(
+            scheduler.next_due(later + Duration::from_millis(100)),
+            Some(PollGroup::Fast)
+        );
+        let idle = PollScheduler::new(intervals, [PollGroup::Slow], start);
+        assert_eq!(idle.tick(), Duration::from_millis(5000));
+        assert_eq!(idle.groups().collect::<Vec<_>>(), vec![PollGroup::Slow]);
+    }
+
+    #[tokio::test]
+    async fn test_grouped_runtime_routes_writes() {
+        use crate::core::channels::igw_bridge::create_virtual_channel;
+
+        let points = vec![
+            (PointType::Telemetry.to_internal_id(1), PollGroup::Fast),
+            (PointType::Telemetry.to_internal_id(2), PollGroup::Slow),
+            (PointType::Adjustment.to_internal_id(1), PollGroup::Fast),
+        ];
+        let mut created = Vec::new();
+        let mut runtime = GroupedRuntime::new(
+            7,
+            "grouped",
+            "virtual",
+            points,
+            |p| *p,
+            PollGroupIntervals::default(),
+            |group, points| {
+                created.push((group, points.len()));
+                create_virtual_channel(7, group.as_str(), Vec::new())
+            },
+        );
+        assert_eq!(created, vec![(PollGroup::Fast, 2), (PollGroup::Slow, 1)]);
+        assert_eq!(runtime.tick(), Duration::from_millis(250));
+        assert_eq!(
+            runtime.route(&[
+                (PointType::Adjustment.to_internal_id(1), 1.0),
+                (PointType::Telemetry.to_internal_id(2), 2.0),
+                (PointType::Adjustment.to_internal_id(9), 3.0),
+            ]),
+            // No normal group: unknown points go to the first one
+            vec![
+                (
+                    0,
+                    vec![
+                        (PointType::Adjustment.to_internal_id(1), 1.0),
+                        (PointType::Adjustment.to_internal_id(9), 3.0)
+                    ]
+                ),
+                (1, vec![(PointType::Telemetry.to_internal_id(2), 2.0)]),
+            ]
+        );
+        runtime.connect().await.unwrap();
+        let diagnostics = runtime.diagnostics().await.unwrap();
+        assert!(diagnostics.extra["poll_groups"].get("slow").is_some());
+    }
+}
@@ -0,0 +1,294 @@
+//! On-demand full reads of a channel
+//!
+//! `POST /api/channels/{id}/read-all` schedules one poll outside the regular
+//! cycle and returns a job whose progress and result are served by
+//! `GET /api/channels/{id}/read-all/{job_id}`. Commissioning tools use it to
+//! force-refresh a device right after changing its configuration instead of
+//! waiting for the next cycle.
+//!
+//! Jobs live in memory only. At most one job per channel is pending at a time
+//! (a second request returns the pending job), and finished jobs are dropped
+//! oldest first once [`MAX_JOBS`] are kept.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+use utoipa::ToSchema;
+use voltage_model::PointType;
+
+/// Jobs kept before finished ones are dropped
+pub const MAX_JOBS: usize = 256;
+
+/// Values returned with a job (the full read still reaches the RTDB)
+pub const MAX_JOB_VALUES: usize = 10_000;
+
+/// Job lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadJobStatus {
+    /// Accepted, not started yet
+    Queued,
+    /// Waiting for or running the poll
+    Running,
+    /// Poll finished; some points may still have failed
+    Completed,
+    /// The poll could not run or its data could not be stored
+    Failed,
+}
+
+impl ReadJobStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, Self::Completed | Self::Failed)
+    }
+}
+
+/// One point value read by a job
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct ReadJobValue {
+    /// T, S, C or A
+    pub point_type: String,
+    pub point_id: u32,
+    pub value: Option<f64>,
+    pub quality: String,
+}
+
+/// One point a job failed to read
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct ReadJobFailure {
+    /// T, S, C or A (absent for whole-poll failures)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub point_type: Option<String>,
+    pub point_id: u32,
+    pub error: String,
+}
+
+/// State and result of a read-all job
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct ReadJob {
+    pub job_id: String,
+    pub channel_id: u32,
+    pub status: ReadJobStatus,
+    /// Creation time (milliseconds)
+    pub created_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<i64>,
+    /// Points read and stored
+    pub points_read: usize,
+    /// Points that failed to read
+    pub failed_points: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub values: Vec<ReadJobValue>,
+    pub failures: Vec<ReadJobFailure>,
+}
+
+impl ReadJob {
+    /// Record the poll result
+    pub fn complete(
+        &mut self,
+        data: &igw::DataBatch,
+        failures: &[igw::core::traits::PointFailure],
+    ) {
+        self.status = ReadJobStatus::Completed;
+        self.finished_at = Some(now_ms());
+        self.points_read = data.len();
+        self.failed_points = failures.len();
+        self.values = data
+            .iter()
+            .take(MAX_JOB_VALUES)
+            .map(|point| {
+                let (point_type, point_id) = PointType::from_internal_id(point.id);
+                ReadJobValue {
+                    point_type: point_type.as_str().to_string(),
+                    point_id,
+                    value: point.value.as_f64(),
+                    quality: serde_json::to_value(point.quality)
+                        .ok()
+                        .and_then(|q| q.as_str().map(str::to_string))
+                        .unwrap_or_default(),
+                }
+            })
+            .collect();
+        self.failures = failures
+            .iter()
+            .map(|failure| {
+                // Protocols report whole-poll failures on point 0
+                let (point_type, point_id) = match failure.point_id {
+                    0 => (None, 0),
+                    id => {
+                        let (point_type, point_id) = PointType::from_internal_id(id);
+                        (Some(point_type.as_str().to_string()), point_id)
+                    },
+                };
+                ReadJobFailure {
+                    point_type,
+                    point_id,
+                    error: failure.error.to_string(),
+                }
+            })
+            .collect();
+    }
+
+    /// Record a failure of the whole job
+    pub fn fail(&mut self, error: impl Into<String>) {
+        self.status = ReadJobStatus::Failed;
+        self.finished_at = Some(now_ms());
+        self.error = Some(error.into());
+    }
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// In-memory registry of read-all jobs
+#[derive(Debug, Default)]
+pub struct ReadJobs {
+    jobs: Mutex<VecDeque<ReadJob>>,
+    next_seq: AtomicU64,
+}
+
+impl ReadJobs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a job for a channel
+    ///
+    /// Returns `(job, true)` for a new job, or the pending job of the channel
+    /// with `false`.
+    pub fn create(&self, channel_id: u32) -> (ReadJob, bool) {
+        let mut jobs = self.jobs.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(pending) = jobs
+            .iter()
+            .find(|j| j.channel_id == channel_id && !j.status.is_finished())
+        {
+            return (pending.clone(), false);
+        }
+
+        while jobs.len() >= MAX_JOBS {
+            match jobs.iter().position(|j| j.status.is_finished()) {
+                Some(oldest) => {
+                    jobs.remove(oldest);
+                },
+                None => break,
+            }
+        }
+
+        let created_at = now_ms();
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        let job = ReadJob {
+            job_id: format!("read_{}_{}_{}", channel_id, created_at, seq),
+            channel_id,
+            status: ReadJobStatus::Queued,
+            created_at,
+            started_at: None,
+            finished_at: None,
+            points_read: 0,
+            failed_points: 0,
+            error: None,
+            values: Vec::new(),
+            failures: Vec::new(),
+        };
+        jobs.push_back(job.clone());
+        (job, true)
+    }
+
+    /// Job of a channel
+    pub fn get(&self, channel_id: u32, job_id: &str) -> Option<ReadJob> {
+        let jobs = self.jobs.lock().unwrap_or_else(PoisonError::into_inner);
+        jobs.iter()
+            .find(|j| j.channel_id == channel_id && j.job_id == job_id)
+            .cloned()
+    }
+
+    /// Update a job in place (no-op for unknown jobs)
+    pub fn update(&self, job_id: &str, f: impl FnOnce(&mut ReadJob)) {
+        let mut jobs = self.jobs.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(job) = jobs.iter_mut().find(|j| j.job_id == job_id) {
+            f(job);
+        }
+    }
+
+    /// Mark a queued job as running
+    pub fn start(&self, job_id: &str) {
+        self.update(job_id, |job| {
+            job.status = ReadJobStatus::Running;
+            job.started_at = Some(now_ms());
+        });
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use igw::core::traits::PointFailure;
+    use igw::{DataBatch, DataPoint, Quality};
+
+    #[test]
+    fn test_one_pending_job_per_channel() {
+        let jobs = ReadJobs::new();
+        let (first, created) = jobs.create(1);
+        assert!(created);
+        let (again, created) = jobs.create(1);
+        assert!(!created);
+        assert_eq!(again.job_id, first.job_id);
+        assert!(jobs.create(2).1);
+
+        jobs.start(&first.job_id);
+        assert_eq!(
+            jobs.get(1, &first.job_id).unwrap().status,
+            ReadJobStatus::Running
+        );
+        // Jobs are scoped to their channel
+        assert!(jobs.get(2, &first.job_id).is_none());
+
+        jobs.update(&first.job_id, |job| job.fail("not connected"));
+        let (next, created) = jobs.create(1);
+        assert!(created);
+        assert_ne!(next.job_id, first.job_id);
+    }
+
+    #[test]
+    fn test_complete_records_values_and_failures() {
+        let jobs = ReadJobs::new();
+        let (job, _) = jobs.create(5);
+
+        let mut batch = DataBatch::default();
+        batch.add(DataPoint::new(PointType::Telemetry.to_internal_id(3), 12.5));
+        batch.add(
+            DataPoint::new(PointType::Signal.to_internal_id(1), 1.0).with_quality(Quality::Bad),
+        );
+        let failures = vec![PointFailure::new(
+            PointType::Telemetry.to_internal_id(4),
+            "timeout",
+        )];
+        jobs.update(&job.job_id, |job| job.complete(&batch, &failures));
+
+        let job = jobs.get(5, &job.job_id).unwrap();
+        assert_eq!(job.status, ReadJobStatus::Completed);
+        assert_eq!((job.points_read, job.failed_points), (2, 1));
+        assert_eq!(job.values[0].point_type, "T");
+        assert_eq!(job.values[0].value, Some(12.5));
+        assert_eq!(job.values[1].quality, "bad");
+        assert_eq!(job.failures[0].point_id, 4);
+        assert!(job.finished_at.is_some());
+    }
+
+    #[test]
+    fn test_finished_jobs_evicted_first() {
+        let jobs = ReadJobs::new();
+        let (oldest, _) = jobs.create(0);
+        jobs.update(&oldest.job_id, |job| job.fail("x"));
+        for channel_id in 1..MAX_JOBS as u32 {
+            jobs.create(channel_id);
+        }
+        jobs.create(MAX_JOBS as u32);
+        assert!(jobs.get(0, &oldest.job_id).is_none());
+        assert!(jobs.get(1, &jobs.create(1).0.job_id).is_some());
+    }
+}
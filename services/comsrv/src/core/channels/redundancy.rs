@@ -0,0 +1,526 @@
+//! Active/standby redundancy between comsrv instances
+//!
+//! Two comsrv instances sharing one Redis coordinate through a lease
+//! (`comsrv:ha:lease`, holding the node ID of the active instance). Only the
+//! active instance connects to devices, polls them and consumes the C/A TODO
+//! queues. The standby loads the same configuration and keeps its channels
+//! created but disconnected, retrying the lease every renew interval; when
+//! the active instance dies its lease expires and the standby takes over
+//! within one lease TTL plus one renew interval.
+//!
+//! An active instance that cannot renew its lease (Redis unreachable or
+//! stalled) steps down before the lease can expire, so two nodes never drive
+//! the same devices at once.
+//!
+//! Each node publishes its role in `comsrv:ha:nodes` (one JSON field per
+//! node), and the active node writes `active_node` into every channel status
+//! hash on takeover.
+//!
+//! Configured in `service_config` (service `comsrv`):
+//!
+//! | Key                            | Default                  |                        |
+//! |--------------------------------|--------------------------|------------------------|
+//! | `redundancy.enabled`           | `false`                  | always active when off |
+//! | `redundancy.node_id`           | `COMSRV_NODE_ID`/host    | unique per instance    |
+//! | `redundancy.lease_ttl_ms`      | `5000`                   | takeover delay         |
+//! | `redundancy.renew_interval_ms` | a third of the lease TTL |                        |
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use utoipa::ToSchema;
+use voltage_model::KeySpaceConfig;
+use voltage_rtdb::Rtdb;
+
+use super::ChannelManager;
+use crate::error::{ComSrvError, Result};
+
+const DEFAULT_LEASE_TTL_MS: u64 = 5000;
+const MIN_LEASE_TTL_MS: u64 = 1000;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Redundancy settings from `service_config`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RedundancyConfig {
+    pub enabled: bool,
+    pub node_id: String,
+    /// Lease lifetime; a standby takes over after the active node misses it
+    pub lease_ttl: Duration,
+    pub renew_interval: Duration,
+}
+
+impl Default for RedundancyConfig {
+    fn default() -> Self {
+        let lease_ttl = Duration::from_millis(DEFAULT_LEASE_TTL_MS);
+        Self {
+            enabled: false,
+            node_id: default_node_id(),
+            lease_ttl,
+            renew_interval: lease_ttl / 3,
+        }
+    }
+}
+
+/// `COMSRV_NODE_ID`, else the host name
+fn default_node_id() -> String {
+    ["COMSRV_NODE_ID", "HOSTNAME"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .map(|id| id.trim().to_string())
+        .find(|id| !id.is_empty())
+        .unwrap_or_else(|| format!("comsrv-{}", std::process::id()))
+}
+
+impl RedundancyConfig {
+    /// Parse `(key, value)` rows; keys without the `redundancy.` prefix are ignored
+    pub fn from_rows<I>(rows: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut config = Self::default();
+        let mut renew_interval = None;
+        for (key, value) in rows {
+            let Some(key) = key.strip_prefix("redundancy.") else {
+                continue;
+            };
+            let value = value.trim();
+            let invalid = || {
+                ComSrvError::ConfigError(format!("redundancy.{}: invalid value '{}'", key, value))
+            };
+            let millis = || value.parse::<u64>().map_err(|_| invalid());
+            match key {
+                "enabled" => {
+                    config.enabled = match value.to_ascii_lowercase().as_str() {
+                        "true" | "1" | "yes" | "on" => true,
+                        "false" | "0" | "no" | "off" => false,
+                        _ => return Err(invalid()),
+                    }
+                },
+                "node_id" if !value.is_empty() => config.node_id = value.to_string(),
+                "lease_ttl_ms" => {
+                    config.lease_ttl = Duration::from_millis(millis()?.max(MIN_LEASE_TTL_MS))
+                },
+                "renew_interval_ms" => renew_interval = Some(Duration::from_millis(millis()?)),
+                _ => debug!("Unknown redundancy setting redundancy.{}", key),
+            }
+        }
+        config.renew_interval = renew_interval.unwrap_or(config.lease_ttl / 3);
+        if config.renew_interval.is_zero() || config.renew_interval >= config.lease_ttl {
+            return Err(ComSrvError::ConfigError(format!(
+                "redundancy.renew_interval_ms ({}) must be below redundancy.lease_ttl_ms ({})",
+                config.renew_interval.as_millis(),
+                config.lease_ttl.as_millis()
+            )));
+        }
+        Ok(config)
+    }
+
+    pub async fn load(pool: &SqlitePool) -> Result<Self> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT key, value FROM service_config \
+             WHERE service_name = 'comsrv' AND key LIKE 'redundancy.%'",
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            ComSrvError::ConfigError(format!("Failed to load redundancy config: {}", e))
+        })?;
+        Self::from_rows(rows)
+    }
+}
+
+// ============================================================================
+// Node state
+// ============================================================================
+
+/// Role of this instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Active,
+    Standby,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Standby => "standby",
+        }
+    }
+}
+
+/// Role of a node as published in `comsrv:ha:nodes`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct NodeStatus {
+    pub node_id: String,
+    pub role: Role,
+    /// When the node took this role (milliseconds)
+    pub since: i64,
+    /// Last lease round of the node (milliseconds)
+    pub heartbeat: i64,
+}
+
+/// Redundancy state of this instance
+#[derive(Debug, Default)]
+pub struct Redundancy {
+    enabled: AtomicBool,
+    standby: AtomicBool,
+    since: AtomicI64,
+    node_id: RwLock<String>,
+}
+
+impl Redundancy {
+    /// State consulted by the channel tasks and the API
+    pub fn global() -> &'static Arc<Redundancy> {
+        static GLOBAL: OnceLock<Arc<Redundancy>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(Redundancy::default()))
+    }
+
+    /// Whether this instance must leave devices and TODO queues alone
+    ///
+    /// Always `false` without redundancy.
+    #[inline]
+    pub fn is_standby(&self) -> bool {
+        self.standby.load(Ordering::Relaxed)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn role(&self) -> Role {
+        if self.is_standby() {
+            Role::Standby
+        } else {
+            Role::Active
+        }
+    }
+
+    /// Status of this node; `None` without redundancy
+    pub fn status(&self) -> Option<NodeStatus> {
+        if !self.is_enabled() {
+            return None;
+        }
+        Some(NodeStatus {
+            node_id: self.node_id.read().map(|id| id.clone()).unwrap_or_default(),
+            role: self.role(),
+            since: self.since.load(Ordering::Relaxed),
+            heartbeat: chrono::Utc::now().timestamp_millis(),
+        })
+    }
+
+    fn set_role(&self, role: Role) {
+        self.standby.store(role == Role::Standby, Ordering::Relaxed);
+        self.since
+            .store(chrono::Utc::now().timestamp_millis(), Ordering::Relaxed);
+    }
+
+    /// Join the election as a standby node
+    fn enable(&self, config: &RedundancyConfig) {
+        if let Ok(mut node_id) = self.node_id.write() {
+            *node_id = config.node_id.clone();
+        }
+        self.set_role(Role::Standby);
+        self.enabled.store(true, Ordering::Relaxed);
+    }
+
+    /// One lease round; returns the new role when it changed
+    async fn round<R: Rtdb>(
+        &self,
+        rtdb: &R,
+        config: &RedundancyConfig,
+        renewed: &mut Option<Instant>,
+    ) -> Option<Role> {
+        let key = KeySpaceConfig::production_cached().redundancy_lease_key();
+        let ttl_ms = config.lease_ttl.as_millis() as u64;
+        self.settle(
+            rtdb.lease_acquire(&key, &config.node_id, ttl_ms),
+            config,
+            renewed,
+        )
+        .await
+    }
+
+    /// Apply the outcome of one lease attempt
+    ///
+    /// `renewed` is when this node last sent a successful renew; the lease
+    /// runs out no earlier than one TTL after it. The attempt is cut short
+    /// at that deadline, and without an answer from the RTDB an active node
+    /// steps down once the lease would expire before the next round.
+    async fn settle(
+        &self,
+        acquire: impl Future<Output = anyhow::Result<bool>>,
+        config: &RedundancyConfig,
+        renewed: &mut Option<Instant>,
+    ) -> Option<Role> {
+        let sent = Instant::now();
+        let budget = renewed
+            .map(|at| (at + config.lease_ttl).saturating_duration_since(sent))
+            .unwrap_or(config.renew_interval)
+            .min(config.renew_interval);
+        let outcome = match tokio::time::timeout(budget, acquire).await {
+            Ok(outcome) => outcome,
+            Err(_) => Err(anyhow::anyhow!("no answer within {}ms", budget.as_millis())),
+        };
+        let role = match outcome {
+            Ok(true) => {
+                *renewed = Some(sent);
+                Role::Active
+            },
+            Ok(false) => Role::Standby,
+            Err(e) => {
+                warn!("Redundancy lease round failed: {}", e);
+                match renewed {
+                    Some(at) if at.elapsed() + config.renew_interval < config.lease_ttl => {
+                        Role::Active
+                    },
+                    _ => Role::Standby,
+                }
+            },
+        };
+        if role == Role::Standby {
+            *renewed = None;
+        }
+        if role == self.role() {
+            return None;
+        }
+        self.set_role(role);
+        Some(role)
+    }
+
+    async fn publish<R: Rtdb>(&self, rtdb: &R) {
+        let Some(status) = self.status() else {
+            return;
+        };
+        let key = KeySpaceConfig::production_cached().redundancy_nodes_key();
+        let payload = match serde_json::to_vec(&status) {
+            Ok(payload) => payload,
+            Err(e) => {
+                warn!("Redundancy status encode failed: {}", e);
+                return;
+            },
+        };
+        if let Err(e) = rtdb
+            .hash_set(&key, &status.node_id, Bytes::from(payload))
+            .await
+        {
+            debug!("Redundancy status write failed: {}", e);
+        }
+    }
+
+    /// Join the election when enabled; `None` when redundancy is off
+    ///
+    /// The first lease round runs before returning, so the role is settled
+    /// before the channels are connected. The returned task renews the
+    /// lease, connects all channels on takeover and disconnects them when
+    /// the lease is lost; on shutdown it releases the lease.
+    pub async fn start<R: Rtdb + 'static>(
+        self: &Arc<Self>,
+        config: RedundancyConfig,
+        rtdb: Arc<R>,
+        channel_manager: Arc<ChannelManager<R>>,
+        shutdown: CancellationToken,
+    ) -> Option<JoinHandle<()>> {
+        if !config.enabled {
+            return None;
+        }
+        self.enable(&config);
+        let mut renewed = None;
+        self.round(rtdb.as_ref(), &config, &mut renewed).await;
+        self.publish(rtdb.as_ref()).await;
+        info!(
+            "Redundancy: node {} starts as {} (lease {}ms)",
+            config.node_id,
+            self.role().as_str(),
+            config.lease_ttl.as_millis()
+        );
+
+        let state = Arc::clone(self);
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(config.renew_interval);
+            interval.tick().await;
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = interval.tick() => {},
+                }
+                match state.round(rtdb.as_ref(), &config, &mut renewed).await {
+                    Some(Role::Active) => {
+                        info!("Redundancy: node {} took over", config.node_id);
+                        channel_manager.publish_active_node(&config.node_id).await;
+                        if let Err(e) = channel_manager.connect_all_channels().await {
+                            warn!("Redundancy takeover: {}", e);
+                        }
+                    },
+                    Some(Role::Standby) => {
+                        warn!(
+                            "Redundancy: node {} lost the lease, standing by",
+                            config.node_id
+                        );
+                        channel_manager.suspend_all_channels().await;
+                    },
+                    None => {},
+                }
+                state.publish(rtdb.as_ref()).await;
+            }
+
+            let key = KeySpaceConfig::production_cached().redundancy_lease_key();
+            match rtdb.lease_release(&key, &config.node_id).await {
+                Ok(true) => info!("Redundancy: node {} released the lease", config.node_id),
+                Ok(false) => {},
+                Err(e) => warn!("Redundancy lease release failed: {}", e),
+            }
+            let nodes = KeySpaceConfig::production_cached().redundancy_nodes_key();
+            let _ = rtdb.hash_del(&nodes, &config.node_id).await;
+        }))
+    }
+}
+
+/// Status of all nodes from `comsrv:ha:nodes`, sorted by node ID
+pub async fn node_statuses<R: Rtdb>(rtdb: &R) -> Result<Vec<NodeStatus>> {
+    let key = KeySpaceConfig::production_cached().redundancy_nodes_key();
+    let fields = rtdb
+        .hash_get_all(&key)
+        .await
+        .map_err(|e| ComSrvError::storage(format!("Failed to read {}: {}", key, e)))?;
+    let mut nodes: Vec<NodeStatus> = fields
+        .values()
+        .filter_map(|raw| serde_json::from_slice(raw).ok())
+        .collect();
+    nodes.sort_by(|a, b| a.node_id.cmp(&b.node_id));
+    Ok(nodes)
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use voltage_rtdb::MemoryRtdb;
+
+    fn rows(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_config_rows() {
+        let config = RedundancyConfig::from_rows(rows(&[
+            ("redundancy.enabled", "true"),
+            ("redundancy.node_id", "comsrv-a"),
+            ("redundancy.lease_ttl_ms", "3000"),
+            ("api.port", "6001"),
+        ]))
+        .unwrap();
+        assert!(config.enabled);
+        assert_eq!(config.node_id, "comsrv-a");
+        assert_eq!(config.lease_ttl, Duration::from_secs(3));
+        assert_eq!(config.renew_interval, Duration::from_secs(1));
+
+        assert!(!RedundancyConfig::from_rows(Vec::new()).unwrap().enabled);
+        assert!(RedundancyConfig::from_rows(rows(&[("redundancy.enabled", "maybe")])).is_err());
+        assert!(RedundancyConfig::from_rows(rows(&[
+            ("redundancy.lease_ttl_ms", "2000"),
+            ("redundancy.renew_interval_ms", "2000"),
+        ]))
+        .is_err());
+    }
+
+    fn node(node_id: &str) -> (Redundancy, RedundancyConfig) {
+        let config = RedundancyConfig {
+            enabled: true,
+            node_id: node_id.to_string(),
+            lease_ttl: Duration::from_millis(200),
+            renew_interval: Duration::from_millis(50),
+        };
+        let state = Redundancy::default();
+        state.enable(&config);
+        (state, config)
+    }
+
+    #[tokio::test]
+    async fn test_standby_takes_over_after_lease_expiry() {
+        let rtdb = MemoryRtdb::new();
+        let (a, config_a) = node("a");
+        let (b, config_b) = node("b");
+        let (mut renewed_a, mut renewed_b) = (None, None);
+        assert!(Redundancy::default().status().is_none());
+        assert!(a.is_standby() && b.is_standby());
+
+        assert_eq!(
+            a.round(&rtdb, &config_a, &mut renewed_a).await,
+            Some(Role::Active)
+        );
+        assert_eq!(b.round(&rtdb, &config_b, &mut renewed_b).await, None);
+        assert!(!a.is_standby());
+        assert!(b.is_standby());
+
+        // Renewing keeps the roles
+        assert_eq!(a.round(&rtdb, &config_a, &mut renewed_a).await, None);
+        a.publish(&rtdb).await;
+        b.publish(&rtdb).await;
+        let nodes = node_statuses(&rtdb).await.unwrap();
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(
+            (nodes[0].node_id.as_str(), nodes[0].role),
+            ("a", Role::Active)
+        );
+        assert_eq!(
+            (nodes[1].node_id.as_str(), nodes[1].role),
+            ("b", Role::Standby)
+        );
+
+        // Node a stops renewing; b takes over once the lease expired
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert_eq!(
+            b.round(&rtdb, &config_b, &mut renewed_b).await,
+            Some(Role::Active)
+        );
+        assert_eq!(
+            a.round(&rtdb, &config_a, &mut renewed_a).await,
+            Some(Role::Standby)
+        );
+        assert!(a.is_standby());
+        assert!(!b.is_standby());
+    }
+
+    #[tokio::test]
+    async fn test_active_steps_down_before_lease_expires_during_stall() {
+        let rtdb = MemoryRtdb::new();
+        let (a, config) = node("a");
+        let mut renewed = None;
+        assert_eq!(
+            a.round(&rtdb, &config, &mut renewed).await,
+            Some(Role::Active)
+        );
+        let lease_start = renewed.unwrap();
+
+        // Redis stops answering for longer than the lease TTL
+        let stalled = || async {
+            tokio::time::sleep(config.lease_ttl * 2).await;
+            Ok(true)
+        };
+        let mut rounds = 0;
+        while a.settle(stalled(), &config, &mut renewed).await.is_none() {
+            rounds += 1;
+            assert!(rounds < 10, "never stepped down");
+        }
+        assert!(a.is_standby());
+        assert!(renewed.is_none());
+        // Stepped down while the lease granted by the last renew still held
+        assert!(lease_start.elapsed() < config.lease_ttl);
+    }
+}
@@ -0,0 +1,235 @@
+//! Channel poll statistics history
+//!
+//! The polling task records one sample per poll cycle (latency, points
+//! updated, failed points) in `comsrv:{channel_id}:STATS`, a Redis list with
+//! the newest sample first that is trimmed to a fixed capacity. The list is
+//! served by `/api/channels/{id}/stats/history` and can be read directly by
+//! hissrv, so degrading links show up as a trend instead of a single status.
+//!
+//! Configured per channel in the parameters:
+//!
+//! ```json
+//! { "stats_history": { "capacity": 3600 } }
+//! ```
+//!
+//! `"stats_history": false` (or a capacity of 0) disables recording.
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::debug;
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+use voltage_model::KeySpaceConfig;
+use voltage_rtdb::Rtdb;
+
+use crate::error::Result;
+
+/// Samples kept per channel unless configured otherwise
+pub const DEFAULT_CAPACITY: usize = 1000;
+
+/// Upper bound for the configured capacity
+pub const MAX_CAPACITY: usize = 100_000;
+
+/// Statistics of one poll cycle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct PollSample {
+    /// Poll start (milliseconds)
+    pub ts: i64,
+    /// Poll duration in milliseconds
+    pub latency_ms: f64,
+    /// Points read and stored
+    pub points_updated: u32,
+    /// Points that failed to read
+    pub failed_points: u32,
+    /// Whole-poll failure (e.g. keepalive timeout)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl PollSample {
+    /// Whether the cycle completed without any failure
+    pub fn is_ok(&self) -> bool {
+        self.failed_points == 0 && self.error.is_none()
+    }
+}
+
+/// Aggregates over a list of samples
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct PollSummary {
+    pub samples: usize,
+    /// Cycles with failed points or a whole-poll failure
+    pub failed_cycles: usize,
+    pub avg_latency_ms: f64,
+    pub max_latency_ms: f64,
+    pub points_updated: u64,
+    pub failed_points: u64,
+}
+
+impl PollSummary {
+    pub fn from_samples(samples: &[PollSample]) -> Self {
+        let count = samples.len();
+        let total_latency: f64 = samples.iter().map(|s| s.latency_ms).sum();
+        Self {
+            samples: count,
+            failed_cycles: samples.iter().filter(|s| !s.is_ok()).count(),
+            avg_latency_ms: if count == 0 {
+                0.0
+            } else {
+                total_latency / count as f64
+            },
+            max_latency_ms: samples.iter().map(|s| s.latency_ms).fold(0.0, f64::max),
+            points_updated: samples.iter().map(|s| s.points_updated as u64).sum(),
+            failed_points: samples.iter().map(|s| s.failed_points as u64).sum(),
+        }
+    }
+}
+
+/// Per-channel recording options
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatsHistoryConfig {
+    /// Samples kept (0 = disabled)
+    pub capacity: usize,
+}
+
+impl Default for StatsHistoryConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+}
+
+impl StatsHistoryConfig {
+    pub fn from_parameters(params: &HashMap<String, serde_json::Value>) -> Self {
+        match params.get("stats_history") {
+            Some(serde_json::Value::Bool(false)) => Self { capacity: 0 },
+            Some(serde_json::Value::Object(obj)) => Self {
+                capacity: obj
+                    .get("capacity")
+                    .and_then(|v| v.as_u64())
+                    .map(|n| (n as usize).min(MAX_CAPACITY))
+                    .unwrap_or(DEFAULT_CAPACITY),
+            },
+            _ => Self::default(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+}
+
+/// Rotating poll statistics of a channel in the RTDB
+pub struct StatsHistory<R: Rtdb> {
+    rtdb: Arc<R>,
+    key: String,
+    capacity: usize,
+}
+
+impl<R: Rtdb> StatsHistory<R> {
+    pub fn new(rtdb: Arc<R>, channel_id: u32, config: StatsHistoryConfig) -> Self {
+        Self {
+            rtdb,
+            key: KeySpaceConfig::production_cached().channel_stats_history_key(channel_id),
+            capacity: config.capacity,
+        }
+    }
+
+    /// Append a sample and drop the oldest beyond the capacity
+    pub async fn record(&self, sample: &PollSample) -> Result<()> {
+        if self.capacity == 0 {
+            return Ok(());
+        }
+        let payload = serde_json::to_vec(sample)?;
+        self.rtdb
+            .list_lpush(&self.key, Bytes::from(payload))
+            .await?;
+        self.rtdb
+            .list_trim(&self.key, 0, self.capacity as isize - 1)
+            .await?;
+        Ok(())
+    }
+
+    /// The latest `limit` samples, oldest first
+    pub async fn recent(&self, limit: usize) -> Result<Vec<PollSample>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let mut samples: Vec<PollSample> = self
+            .rtdb
+            .list_range(&self.key, 0, limit as isize - 1)
+            .await?
+            .iter()
+            .filter_map(|payload| match serde_json::from_slice(payload) {
+                Ok(sample) => Some(sample),
+                Err(e) => {
+                    debug!("{} unreadable sample: {}", self.key, e);
+                    None
+                },
+            })
+            .collect();
+        samples.reverse();
+        Ok(samples)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample(ts: i64, latency_ms: f64, failed_points: u32) -> PollSample {
+        PollSample {
+            ts,
+            latency_ms,
+            points_updated: 10 - failed_points,
+            failed_points,
+            error: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_rotates_and_summarizes() {
+        let rtdb = Arc::new(voltage_rtdb::MemoryRtdb::new());
+        let history = StatsHistory::new(rtdb, 1001, StatsHistoryConfig { capacity: 3 });
+
+        for (i, failed) in [0, 2, 0, 0].into_iter().enumerate() {
+            let s = sample(i as i64, 10.0 * (i + 1) as f64, failed);
+            history.record(&s).await.unwrap();
+        }
+
+        // Oldest sample rotated out, returned oldest first
+        let samples = history.recent(10).await.unwrap();
+        assert_eq!(
+            samples.iter().map(|s| s.ts).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(history.recent(1).await.unwrap()[0].ts, 3);
+
+        let summary = PollSummary::from_samples(&samples);
+        assert_eq!(summary.samples, 3);
+        assert_eq!(summary.failed_cycles, 1);
+        assert_eq!(summary.avg_latency_ms, 30.0);
+        assert_eq!(summary.max_latency_ms, 40.0);
+        assert_eq!(summary.failed_points, 2);
+    }
+
+    #[test]
+    fn test_config_from_parameters() {
+        let params = |v: serde_json::Value| HashMap::from([("stats_history".to_string(), v)]);
+        assert_eq!(
+            StatsHistoryConfig::from_parameters(&HashMap::new()).capacity,
+            DEFAULT_CAPACITY
+        );
+        assert!(!StatsHistoryConfig::from_parameters(&params(json!(false))).is_enabled());
+        assert_eq!(
+            StatsHistoryConfig::from_parameters(&params(json!({"capacity": 60}))).capacity,
+            60
+        );
+    }
+}
@@ -13,9 +13,14 @@ use tracing::{debug, error, info, warn};
 use voltage_model::{KeySpaceConfig, PointType};
 use voltage_rtdb::Rtdb;
 
+use super::command_retry::CommandAttempt;
+use super::redundancy::Redundancy;
 use super::traits::ChannelCommand;
 use crate::error::Result;
 
+/// How often a redundancy standby checks whether it took over the queues
+const STANDBY_RECHECK: std::time::Duration = std::time::Duration::from_secs(1);
+
 /// Control command type
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CommandType {
@@ -35,6 +40,9 @@ struct CompactTrigger {
     value: f64,
     /// Timestamp in milliseconds
     timestamp: i64,
+    /// Command ID assigned by the API caller (v2 write requests)
+    #[serde(default)]
+    command_id: Option<String>,
 }
 
 /// Trigger message parsed from TODO queue
@@ -81,14 +89,17 @@ fn current_timestamp() -> i64 {
     chrono::Utc::now().timestamp()
 }
 
-/// Command status
+/// Command status (tracking record of a command)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommandStatus {
     pub command_id: String,
-    pub status: String, // pending, executing, success, failed
+    pub status: String, // pending, executing, success, failed, aborted
     pub result: Option<serde_json::Value>,
     pub error: Option<String>,
     pub timestamp: i64,
+    /// Write attempts, oldest first
+    #[serde(default)]
+    pub attempts: Vec<CommandAttempt>,
 }
 
 /// Command trigger configuration
@@ -223,6 +234,8 @@ impl<R: Rtdb + 'static> CommandTrigger<R> {
 
             let inner_loop_result: Result<()> = async {
                 loop {
+                    // A redundancy standby leaves the queues to the active node
+                    let standby = Redundancy::global().is_standby();
                     tokio::select! {
                         // Listen for the stop signal.
                         _ = shutdown_rx.changed() => {
@@ -230,8 +243,9 @@ impl<R: Rtdb + 'static> CommandTrigger<R> {
                                 return Ok(());
                             }
                         }
+                        _ = tokio::time::sleep(STANDBY_RECHECK), if standby => {}
                         // Use BLPOP to wait for commands.
-                        result = rtdb.list_blpop(&queues, timeout) => {
+                        result = rtdb.list_blpop(&queues, timeout), if !standby => {
                             match result {
                                 Ok(Some((queue, data_bytes))) => {
                                     // Determine the command type.
@@ -247,10 +261,10 @@ impl<R: Rtdb + 'static> CommandTrigger<R> {
                                         }
                                     };
 
-                                    let (point_id, value, current_ts) = match trigger_msg {
+                                    let (point_id, value, current_ts, command_id) = match trigger_msg {
                                         TriggerMessage::Compact(trigger) => {
                                             // Full format - all data in JSON
-                                            (trigger.point_id, trigger.value, trigger.timestamp)
+                                            (trigger.point_id, trigger.value, trigger.timestamp, trigger.command_id)
                                         }
                                         TriggerMessage::Legacy { point_id } => {
                                             // Legacy format - read value/timestamp from Redis hashes
@@ -311,7 +325,7 @@ impl<R: Rtdb + 'static> CommandTrigger<R> {
                                                 }
                                             };
 
-                                            (point_id, value, current_ts)
+                                            (point_id, value, current_ts, None)
                                         }
                                     };
 
@@ -348,7 +362,7 @@ impl<R: Rtdb + 'static> CommandTrigger<R> {
 
                                     // Build ControlCommand
                                     let command = ControlCommand {
-                                        command_id: format!("trigger_{}_{}", channel_id, current_ts),
+                                        command_id: command_id.unwrap_or_else(|| format!("trigger_{}_{}", channel_id, current_ts)),
                                         channel_id: Some(channel_id),
                                         command_type: if is_control { CommandType::Control } else { CommandType::Adjustment },
                                         point_id,
@@ -878,6 +892,7 @@ mod tests {
             result: None,
             error: None,
             timestamp: 1234567890,
+            attempts: Vec::new(),
         };
 
         assert_eq!(status.command_id, "cmd_123");
@@ -894,6 +909,7 @@ mod tests {
             result: Some(serde_json::json!({"data": "test"})),
             error: None,
             timestamp: 1234567890,
+            attempts: Vec::new(),
         };
 
         let json = serde_json::to_string(&status).unwrap();
@@ -14,53 +14,44 @@ use crate::core::config::FourRemote;
 // ============================================================================
 
 /// Connection state for communication channels
+///
+/// Driven by `ConnectionStateMachine` (see `connection.rs`) rather than set directly.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
 pub enum ConnectionState {
-    /// Not initialized yet
+    /// No link; initial state and target of failed connects / lost links
     #[default]
-    Uninitialized,
-    /// Initializing connection
-    Initializing,
-    /// Attempting to connect
+    Disconnected,
+    /// Connect attempt in progress
     Connecting,
-    /// Successfully connected
+    /// Link up, last poll succeeded
     Connected,
-    /// Connection failed, will retry
-    Disconnected,
-    /// In retry process
-    Retrying,
-    /// Connection closed normally
-    Closed,
-    /// Fatal error, won't retry
-    Failed,
+    /// Link up but polls are failing
+    Degraded,
+    /// Channel is shutting down
+    Closing,
 }
 
 impl ConnectionState {
-    /// Check if state represents an active connection
+    /// Check if state represents an active connection (possibly degraded)
     pub fn is_connected(&self) -> bool {
-        matches!(self, ConnectionState::Connected)
+        matches!(self, ConnectionState::Connected | ConnectionState::Degraded)
     }
 
-    /// Check if state allows retry
+    /// Check if the reconnect logic should attempt a new connect
     pub fn can_retry(&self) -> bool {
-        matches!(
-            self,
-            ConnectionState::Disconnected | ConnectionState::Retrying
-        )
+        matches!(self, ConnectionState::Disconnected)
     }
 }
 
 impl std::fmt::Display for ConnectionState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ConnectionState::Uninitialized => write!(f, "UNINITIALIZED"),
-            ConnectionState::Initializing => write!(f, "INITIALIZING"),
+            ConnectionState::Disconnected => write!(f, "DISCONNECTED"),
             ConnectionState::Connecting => write!(f, "CONNECTING"),
             ConnectionState::Connected => write!(f, "CONNECTED"),
-            ConnectionState::Disconnected => write!(f, "DISCONNECTED"),
-            ConnectionState::Retrying => write!(f, "RETRYING"),
-            ConnectionState::Closed => write!(f, "CLOSED"),
-            ConnectionState::Failed => write!(f, "FAILED"),
+            ConnectionState::Degraded => write!(f, "DEGRADED"),
+            ConnectionState::Closing => write!(f, "CLOSING"),
         }
     }
 }
@@ -218,6 +209,7 @@ impl ProtocolValue {
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub struct ChannelStatus {
     pub is_connected: bool,
+    pub state: ConnectionState,
     pub last_update: i64,
 }
 
@@ -316,8 +308,9 @@ mod tests {
     #[test]
     fn test_connection_state() {
         assert!(ConnectionState::Connected.is_connected());
+        assert!(ConnectionState::Degraded.is_connected());
         assert!(!ConnectionState::Disconnected.is_connected());
         assert!(ConnectionState::Disconnected.can_retry());
-        assert!(!ConnectionState::Failed.can_retry());
+        assert!(!ConnectionState::Closing.can_retry());
     }
 }
@@ -0,0 +1,592 @@
+//! Signal generators for virtual channels
+//!
+//! [`WaveformRuntime`] wraps the virtual runtime and, on every poll, replaces
+//! the values of points whose mapping names a `waveform`:
+//!
+//! - `sine`: `baseline ± amplitude` over `period_ms`
+//! - `ramp`: rises from `baseline` to `baseline + amplitude` over `period_ms`,
+//!   then starts again
+//! - `square`: `baseline + amplitude` for the first `duty` (default 0.5) of
+//!   each period, `baseline` for the rest
+//! - `random_walk`: starts at `baseline` and moves by at most `step`
+//!   (default `amplitude / 10`) per poll, kept within `baseline ± amplitude`
+//! - `steps`: replays a CSV of `duration_ms,value` rows and loops; a header
+//!   row is allowed
+//!
+//! `amplitude` defaults to 1, `baseline` to 0 and `period_ms` to 60000.
+//! `phase_ms` shifts periodic waveforms so points on one channel need not
+//! move in lockstep. Signal points only take `square` and `steps` and publish
+//! `value != 0`. Time is measured from channel connect; values are sampled at
+//! the channel's `poll_interval_ms`.
+//!
+//! ```json
+//! {"waveform": "sine", "amplitude": 20, "baseline": 50, "period_ms": 60000}
+//! {"waveform": "square", "period_ms": 10000, "duty": 0.2}
+//! {"waveform": "random_walk", "amplitude": 5, "baseline": 230, "step": 0.5}
+//! {"waveform": "steps", "csv": "profiles/load.csv"}
+//! ```
+
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+use std::path::Path;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use igw::core::traits::{DataEventReceiver, Diagnostics, PollResult};
+use igw::gateway::ChannelRuntime;
+use igw::DataPoint;
+use rand::Rng;
+use tokio::time::Instant;
+use voltage_model::PointType;
+
+use crate::core::config::RuntimeChannelConfig;
+use crate::error::{ComSrvError, Result};
+
+/// Waveform names accepted in point mappings
+pub const WAVEFORMS: &[&str] = &["sine", "ramp", "square", "random_walk", "steps"];
+
+/// Waveforms a signal point may use
+pub const SIGNAL_WAVEFORMS: &[&str] = &["square", "steps"];
+
+/// Shape of a generated signal
+#[derive(Debug, Clone, PartialEq)]
+pub enum Waveform {
+    Sine,
+    Ramp,
+    Square {
+        duty: f64,
+    },
+    RandomWalk {
+        step: f64,
+    },
+    /// Values and how long each is held
+    Steps(Vec<(Duration, f64)>),
+}
+
+impl Waveform {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sine => "sine",
+            Self::Ramp => "ramp",
+            Self::Square { .. } => "square",
+            Self::RandomWalk { .. } => "random_walk",
+            Self::Steps(_) => "steps",
+        }
+    }
+}
+
+/// Generator of one point's values
+#[derive(Debug, Clone)]
+pub struct Generator {
+    waveform: Waveform,
+    amplitude: f64,
+    baseline: f64,
+    period: Duration,
+    phase: Duration,
+    /// Current random walk position
+    position: f64,
+}
+
+impl Generator {
+    pub fn new(
+        waveform: Waveform,
+        amplitude: f64,
+        baseline: f64,
+        period: Duration,
+        phase: Duration,
+    ) -> Self {
+        Self {
+            waveform,
+            amplitude,
+            baseline,
+            period,
+            phase,
+            position: baseline,
+        }
+    }
+
+    /// Build a generator from a point mapping
+    ///
+    /// Relative CSV paths are resolved against the working directory.
+    pub fn from_mapping(mapping: &serde_json::Value) -> std::result::Result<Self, String> {
+        let number = |key: &str, default: f64| -> std::result::Result<f64, String> {
+            match mapping.get(key) {
+                None | Some(serde_json::Value::Null) => Ok(default),
+                Some(v) => v
+                    .as_f64()
+                    .filter(|n| n.is_finite())
+                    .ok_or_else(|| format!("{} must be a number", key)),
+            }
+        };
+        let millis = |key: &str, default: u64| -> std::result::Result<Duration, String> {
+            match mapping.get(key) {
+                None | Some(serde_json::Value::Null) => Ok(Duration::from_millis(default)),
+                Some(v) => v
+                    .as_u64()
+                    .map(Duration::from_millis)
+                    .ok_or_else(|| format!("{} must be a non-negative integer", key)),
+            }
+        };
+
+        let amplitude = number("amplitude", 1.0)?;
+        let baseline = number("baseline", 0.0)?;
+        let period = millis("period_ms", 60_000)?;
+        let phase = millis("phase_ms", 0)?;
+        if period.is_zero() {
+            return Err("period_ms must be greater than 0".to_string());
+        }
+
+        let name = mapping
+            .get("waveform")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        let waveform = match name.trim().to_ascii_lowercase().as_str() {
+            "sine" => Waveform::Sine,
+            "ramp" => Waveform::Ramp,
+            "square" => {
+                let duty = number("duty", 0.5)?;
+                if !(0.0..=1.0).contains(&duty) {
+                    return Err(format!("duty must be between 0 and 1, got {}", duty));
+                }
+                Waveform::Square { duty }
+            },
+            "random_walk" => {
+                let step = number("step", amplitude.abs() / 10.0)?.abs();
+                // Steps are drawn from [-step, step], whose width must be finite
+                if !(2.0 * step).is_finite() {
+                    return Err(format!("step is too large, got {}", step));
+                }
+                Waveform::RandomWalk { step }
+            },
+            "steps" => {
+                let path = mapping
+                    .get("csv")
+                    .and_then(|v| v.as_str())
+                    .filter(|p| !p.trim().is_empty())
+                    .ok_or("steps waveform needs a csv file")?;
+                Waveform::Steps(load_steps(Path::new(path.trim()))?)
+            },
+            other => {
+                return Err(format!(
+                    "unknown waveform '{}', expected one of {}",
+                    other,
+                    WAVEFORMS.join(", ")
+                ))
+            },
+        };
+        Ok(Self::new(waveform, amplitude, baseline, period, phase))
+    }
+
+    pub fn waveform(&self) -> &Waveform {
+        &self.waveform
+    }
+
+    /// Value at `elapsed` since the generator started
+    ///
+    /// Random walks advance one step per call.
+    pub fn sample(&mut self, elapsed: Duration) -> f64 {
+        let t = elapsed + self.phase;
+        let fraction =
+            (t.as_nanos() % self.period.as_nanos()) as f64 / self.period.as_nanos() as f64;
+        match &self.waveform {
+            Waveform::Sine => self.baseline + self.amplitude * (TAU * fraction).sin(),
+            Waveform::Ramp => self.baseline + self.amplitude * fraction,
+            Waveform::Square { duty } => {
+                if fraction < *duty {
+                    self.baseline + self.amplitude
+                } else {
+                    self.baseline
+                }
+            },
+            Waveform::RandomWalk { step } => {
+                if *step > 0.0 {
+                    self.position += rand::thread_rng().gen_range(-*step..=*step);
+                }
+                let bound = self.amplitude.abs();
+                self.position = self
+                    .position
+                    .clamp(self.baseline - bound, self.baseline + bound);
+                self.position
+            },
+            Waveform::Steps(steps) => step_value(steps, t),
+        }
+    }
+}
+
+/// Value held at `t` by a looping step sequence
+fn step_value(steps: &[(Duration, f64)], t: Duration) -> f64 {
+    let total: u128 = steps.iter().map(|(d, _)| d.as_nanos()).sum();
+    let mut offset = t.as_nanos() % total.max(1);
+    for (duration, value) in steps {
+        if offset < duration.as_nanos() {
+            return *value;
+        }
+        offset -= duration.as_nanos();
+    }
+    steps.last().map_or(0.0, |(_, value)| *value)
+}
+
+/// Read a `duration_ms,value` step sequence
+pub fn load_steps(path: &Path) -> std::result::Result<Vec<(Duration, f64)>, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    parse_steps(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn parse_steps(text: &str) -> std::result::Result<Vec<(Duration, f64)>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .trim(csv::Trim::All)
+        .comment(Some(b'#'))
+        .from_reader(text.as_bytes());
+
+    let mut steps = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| e.to_string())?;
+        let duration = record.get(0).unwrap_or_default();
+        let value = record.get(1).unwrap_or_default();
+        match (duration.parse::<u64>(), value.parse::<f64>()) {
+            (Ok(ms), Ok(value)) if ms > 0 && value.is_finite() => {
+                steps.push((Duration::from_millis(ms), value))
+            },
+            // Header row
+            _ if index == 0 && duration.parse::<f64>().is_err() => continue,
+            _ => {
+                return Err(format!(
+                    "row {}: expected duration_ms > 0 and a value, got '{},{}'",
+                    index + 1,
+                    duration,
+                    value
+                ))
+            },
+        }
+    }
+    if steps.is_empty() {
+        return Err("no steps".to_string());
+    }
+    Ok(steps)
+}
+
+/// Generators of a channel's points, keyed by internal point ID
+#[derive(Debug, Clone, Default)]
+pub struct WaveformPoints {
+    pub generators: HashMap<u32, Generator>,
+}
+
+impl WaveformPoints {
+    /// Collect the generators named in the point mappings
+    pub fn from_runtime_config(config: &RuntimeChannelConfig) -> Result<Self> {
+        let mut points = Self::default();
+        let telemetry = config
+            .telemetry_points
+            .iter()
+            .map(|pt| (PointType::Telemetry, &pt.base));
+        let signal = config
+            .signal_points
+            .iter()
+            .map(|pt| (PointType::Signal, &pt.base));
+        for (point_type, base) in telemetry.chain(signal) {
+            let mapping: serde_json::Value = base
+                .protocol_mappings
+                .as_deref()
+                .and_then(|m| serde_json::from_str(m).ok())
+                .unwrap_or(serde_json::Value::Null);
+            if mapping.get("waveform").is_none() {
+                continue;
+            }
+            let generator = Generator::from_mapping(&mapping).map_err(|e| {
+                ComSrvError::ConfigError(format!("{}{}: {}", point_type, base.point_id, e))
+            })?;
+            if point_type == PointType::Signal
+                && !SIGNAL_WAVEFORMS.contains(&generator.waveform().name())
+            {
+                return Err(ComSrvError::ConfigError(format!(
+                    "S{}: signal points only support {} waveforms",
+                    base.point_id,
+                    SIGNAL_WAVEFORMS.join(" and ")
+                )));
+            }
+            points
+                .generators
+                .insert(point_type.to_internal_id(base.point_id), generator);
+        }
+        Ok(points)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.generators.is_empty()
+    }
+}
+
+/// Virtual runtime whose generated points follow their waveforms
+pub struct WaveformRuntime {
+    inner: Box<dyn ChannelRuntime>,
+    generators: HashMap<u32, Generator>,
+    started: Instant,
+}
+
+impl WaveformRuntime {
+    pub fn new(inner: Box<dyn ChannelRuntime>, points: WaveformPoints) -> Self {
+        Self {
+            inner,
+            generators: points.generators,
+            started: Instant::now(),
+        }
+    }
+
+    fn generated_point(internal_id: u32, value: f64) -> DataPoint {
+        match PointType::from_internal_id(internal_id).0 {
+            PointType::Signal => DataPoint::new(internal_id, value != 0.0),
+            _ => DataPoint::new(internal_id, value),
+        }
+    }
+}
+
+#[async_trait]
+impl ChannelRuntime for WaveformRuntime {
+    fn id(&self) -> u32 {
+        self.inner.id()
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn protocol(&self) -> &str {
+        self.inner.protocol()
+    }
+
+    fn is_event_driven(&self) -> bool {
+        self.inner.is_event_driven()
+    }
+
+    /// Restart all waveforms from zero
+    async fn connect(&mut self) -> igw::Result<()> {
+        self.started = Instant::now();
+        self.inner.connect().await
+    }
+
+    async fn disconnect(&mut self) -> igw::Result<()> {
+        self.inner.disconnect().await
+    }
+
+    async fn poll_once(&mut self) -> PollResult {
+        let mut result = self.inner.poll_once().await;
+        let elapsed = self.started.elapsed();
+        let mut values: HashMap<u32, f64> = self
+            .generators
+            .iter_mut()
+            .map(|(id, generator)| (*id, generator.sample(elapsed)))
+            .collect();
+        // Points already written to the virtual channel are overwritten in place
+        for point in result.data.iter_mut() {
+            if let Some(value) = values.remove(&point.id) {
+                *point = Self::generated_point(point.id, value);
+            }
+        }
+        for (id, value) in values {
+            result.data.add(Self::generated_point(id, value));
+        }
+        result
+    }
+
+    async fn write_control(&mut self, commands: &[(u32, f64)]) -> igw::Result<usize> {
+        self.inner.write_control(commands).await
+    }
+
+    async fn write_adjustment(&mut self, adjustments: &[(u32, f64)]) -> igw::Result<usize> {
+        self.inner.write_adjustment(adjustments).await
+    }
+
+    fn subscribe(&self) -> Option<DataEventReceiver> {
+        self.inner.subscribe()
+    }
+
+    async fn start_events(&mut self) -> igw::Result<()> {
+        self.inner.start_events().await
+    }
+
+    async fn stop_events(&mut self) -> igw::Result<()> {
+        self.inner.stop_events().await
+    }
+
+    async fn diagnostics(&self) -> igw::Result<Diagnostics> {
+        let mut diagnostics = self.inner.diagnostics().await?;
+        let generators: serde_json::Map<String, serde_json::Value> = self
+            .generators
+            .iter()
+            .map(|(id, generator)| {
+                let (point_type, point_id) = PointType::from_internal_id(*id);
+                (
+                    format!("{}{}", point_type, point_id),
+                    generator.waveform().name().into(),
+                )
+            })
+            .collect();
+        if let serde_json::Value::Object(extra) = &mut diagnostics.extra {
+            extra.insert("generators".to_string(), generators.into());
+        } else {
+            diagnostics.extra = serde_json::json!({ "generators": generators });
+        }
+        Ok(diagnostics)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use crate::core::channels::igw_bridge::{convert_to_igw_point_configs, create_virtual_channel};
+    use crate::core::config::{ChannelConfig, ChannelCore, Point, SignalPoint, TelemetryPoint};
+
+    fn generator(mapping: serde_json::Value) -> Generator {
+        Generator::from_mapping(&mapping).unwrap()
+    }
+
+    fn at(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn periodic_waveforms_follow_amplitude_period_and_phase() {
+        let mut sine = generator(serde_json::json!({
+            "waveform": "sine", "amplitude": 10, "baseline": 50, "period_ms": 4000
+        }));
+        assert!((sine.sample(at(0)) - 50.0).abs() < 1e-9);
+        assert!((sine.sample(at(1000)) - 60.0).abs() < 1e-9);
+        assert!((sine.sample(at(3000)) - 40.0).abs() < 1e-9);
+        assert!((sine.sample(at(5000)) - 60.0).abs() < 1e-9);
+
+        let mut ramp = generator(serde_json::json!({
+            "waveform": "ramp", "amplitude": 100, "period_ms": 1000, "phase_ms": 250
+        }));
+        assert!((ramp.sample(at(0)) - 25.0).abs() < 1e-9);
+        assert!((ramp.sample(at(500)) - 75.0).abs() < 1e-9);
+        assert!((ramp.sample(at(750)) - 0.0).abs() < 1e-9);
+
+        let mut square = generator(serde_json::json!({
+            "waveform": "square", "period_ms": 1000, "duty": 0.2
+        }));
+        assert_eq!(square.sample(at(100)), 1.0);
+        assert_eq!(square.sample(at(200)), 0.0);
+        assert_eq!(square.sample(at(1100)), 1.0);
+    }
+
+    #[test]
+    fn random_walk_stays_within_bounds() {
+        let mut walk = generator(serde_json::json!({
+            "waveform": "random_walk", "amplitude": 5, "baseline": 230, "step": 2
+        }));
+        let mut previous = 230.0;
+        for i in 0..1000 {
+            let value = walk.sample(at(i));
+            assert!((225.0..=235.0).contains(&value));
+            assert!((value - previous).abs() <= 2.0 + 1e-9);
+            previous = value;
+        }
+    }
+
+    #[test]
+    fn steps_replay_csv_and_loop() {
+        let steps = parse_steps("duration_ms,value\n1000,5\n# hold high\n500, 7.5\n").unwrap();
+        assert_eq!(steps, vec![(at(1000), 5.0), (at(500), 7.5)]);
+        let mut gen = Generator::new(Waveform::Steps(steps), 1.0, 0.0, at(60_000), at(0));
+        assert_eq!(gen.sample(at(999)), 5.0);
+        assert_eq!(gen.sample(at(1000)), 7.5);
+        assert_eq!(gen.sample(at(1500)), 5.0);
+
+        assert!(parse_steps("1000,5\n0,1\n").is_err());
+        assert!(parse_steps("duration_ms,value\n").is_err());
+    }
+
+    #[test]
+    fn invalid_mappings_are_rejected() {
+        for mapping in [
+            serde_json::json!({"waveform": "triangle"}),
+            serde_json::json!({"waveform": "sine", "period_ms": 0}),
+            serde_json::json!({"waveform": "square", "duty": 1.5}),
+            serde_json::json!({"waveform": "steps"}),
+            serde_json::json!({"waveform": "sine", "amplitude": "big"}),
+            serde_json::json!({"waveform": "random_walk", "step": 1e308}),
+        ] {
+            assert!(Generator::from_mapping(&mapping).is_err(), "{}", mapping);
+        }
+    }
+
+    fn point(point_id: u32, mapping: serde_json::Value) -> Point {
+        Point {
+            point_id,
+            signal_name: format!("p{}", point_id),
+            description: None,
+            unit: None,
+            protocol_mappings: Some(mapping.to_string()),
+        }
+    }
+
+    fn runtime_config(signal_waveform: &str) -> RuntimeChannelConfig {
+        let mut config = RuntimeChannelConfig::from_base(ChannelConfig {
+            core: ChannelCore {
+                id: 7,
+                name: "sim".to_string(),
+                description: None,
+                protocol: "virtual".to_string(),
+                enabled: true,
+            },
+            parameters: HashMap::new(),
+            logging: Default::default(),
+        });
+        config.telemetry_points.push(TelemetryPoint {
+            base: point(
+                1,
+                serde_json::json!({"waveform": "ramp", "amplitude": 10, "period_ms": 3_600_000}),
+            ),
+            scale: 1.0,
+            offset: 0.0,
+            data_type: "float32".to_string(),
+            reverse: false,
+        });
+        config.telemetry_points.push(TelemetryPoint {
+            base: point(2, serde_json::json!({"expression": "P1 * 2"})),
+            scale: 1.0,
+            offset: 0.0,
+            data_type: "float32".to_string(),
+            reverse: false,
+        });
+        config.signal_points.push(SignalPoint {
+            base: point(
+                1,
+                serde_json::json!({"waveform": signal_waveform, "period_ms": 3_600_000}),
+            ),
+            reverse: false,
+        });
+        config
+    }
+
+    #[tokio::test]
+    async fn runtime_overlays_generated_points() {
+        let config = runtime_config("square");
+        let points = WaveformPoints::from_runtime_config(&config).unwrap();
+        assert_eq!(points.generators.len(), 2);
+
+        let inner = create_virtual_channel(7, "sim", convert_to_igw_point_configs(&config));
+        let mut runtime = WaveformRuntime::new(inner, points);
+        runtime.connect().await.unwrap();
+
+        let t1 = PointType::Telemetry.to_internal_id(1);
+        let s1 = PointType::Signal.to_internal_id(1);
+        let result = runtime.poll_once().await;
+        let values: HashMap<u32, DataPoint> =
+            result.data.iter().map(|p| (p.id, p.clone())).collect();
+        assert_eq!(values.len(), 2);
+        assert!(values[&t1].value.as_f64().unwrap() < 0.1);
+        assert_eq!(values[&s1].value.as_bool(), Some(true));
+
+        let diagnostics = runtime.diagnostics().await.unwrap();
+        assert_eq!(diagnostics.extra["generators"]["T1"], "ramp");
+        assert_eq!(diagnostics.extra["generators"]["S1"], "square");
+        assert_eq!(diagnostics.extra["name"], "sim");
+
+        let err = WaveformPoints::from_runtime_config(&runtime_config("sine")).unwrap_err();
+        assert!(err.to_string().contains("S1"));
+    }
+}
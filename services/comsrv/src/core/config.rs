@@ -22,6 +22,7 @@
 #![allow(ambiguous_glob_reexports)]
 
 pub mod manager;
+pub mod migrations;
 pub mod sqlite_loader;
 pub mod types;
 
@@ -45,6 +46,7 @@ pub use types::{
     IecMapping,
     ModbusMapping,
     Point,
+    PointAccess,
     ProtocolQueries,
     RuntimeChannelConfig,
     SignalPoint,
@@ -56,6 +58,7 @@ pub use types::{
     ADJUSTMENT_POINTS_TABLE,
     CHANNELS_TABLE,
     CHANNEL_ROUTING_TABLE,
+    COMMAND_WEBHOOKS_TABLE,
     CONTROL_POINTS_TABLE,
     DEFAULT_PORT,
     SERVICE_CONFIG_TABLE,
@@ -0,0 +1,306 @@
+//! comsrv schema migrations
+//!
+//! Owns the shared config tables, channels and the four point tables in
+//! `voltage.db`. Applied by comsrv on startup and by `monarch init`.
+//!
+//! Migration DDL is frozen: the record structs in `types` keep changing, so
+//! the tables are written out as they were when each migration was added. A
+//! column added to a record struct needs a new migration here. The baseline
+//! only creates what is missing, so adopting it on a database initialized
+//! before migrations existed is safe.
+
+use common::sqlite::migrations::{has_column, Migration, MigrationFuture, Migrator};
+use sqlx::SqliteConnection;
+
+/// Ordered comsrv migrations
+pub static MIGRATIONS: &[Migration] = &[
+    Migration::sql(1, "baseline", BASELINE),
+    Migration::sql(2, "command_webhooks", COMMAND_WEBHOOKS),
+    Migration::rust(3, "point_access_scopes", &POINT_ACCESS, point_access_scopes),
+];
+
+/// Migrator for the comsrv tables
+pub const fn migrator() -> Migrator {
+    Migrator::new("comsrv", MIGRATIONS)
+}
+
+/// Config tables, channels and the point tables, with indexes and the
+/// triggers removing modsrv routing rows when the point they reference is
+/// deleted (trigger bodies are resolved when they fire, so the routing
+/// tables do not have to exist yet)
+const BASELINE: &str = r#"
+    CREATE TABLE IF NOT EXISTS service_config (
+        service_name TEXT NOT NULL,
+        key TEXT NOT NULL,
+        value TEXT NOT NULL,
+        type TEXT NOT NULL DEFAULT 'string',
+        description TEXT,
+        updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (service_name, key)
+    );
+
+    CREATE TABLE IF NOT EXISTS sync_metadata (
+        service TEXT PRIMARY KEY,
+        last_sync TEXT NOT NULL,
+        version TEXT
+    );
+
+    CREATE TABLE IF NOT EXISTS channels (
+        channel_id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE,
+        protocol TEXT,
+        enabled BOOLEAN NOT NULL DEFAULT TRUE,
+        config TEXT,
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+
+    CREATE TABLE IF NOT EXISTS telemetry_points (
+        point_id INTEGER NOT NULL,
+        channel_id INTEGER NOT NULL REFERENCES channels(channel_id),
+        signal_name TEXT NOT NULL,
+        scale REAL NOT NULL DEFAULT 1.0,
+        offset REAL NOT NULL DEFAULT 0.0,
+        unit TEXT,
+        reverse BOOLEAN NOT NULL DEFAULT FALSE,
+        data_type TEXT,
+        description TEXT,
+        protocol_mappings TEXT,
+        PRIMARY KEY (channel_id, point_id)
+    );
+
+    CREATE TABLE IF NOT EXISTS signal_points (
+        point_id INTEGER NOT NULL,
+        channel_id INTEGER NOT NULL REFERENCES channels(channel_id),
+        signal_name TEXT NOT NULL,
+        scale REAL NOT NULL DEFAULT 1.0,
+        offset REAL NOT NULL DEFAULT 0.0,
+        unit TEXT,
+        reverse BOOLEAN NOT NULL DEFAULT FALSE,
+        normal_state INTEGER NOT NULL DEFAULT 0,
+        data_type TEXT,
+        description TEXT,
+        protocol_mappings TEXT,
+        PRIMARY KEY (channel_id, point_id)
+    );
+
+    CREATE TABLE IF NOT EXISTS control_points (
+        point_id INTEGER NOT NULL,
+        channel_id INTEGER NOT NULL REFERENCES channels(channel_id),
+        signal_name TEXT NOT NULL,
+        scale REAL NOT NULL DEFAULT 1.0,
+        offset REAL NOT NULL DEFAULT 0.0,
+        unit TEXT,
+        reverse BOOLEAN NOT NULL DEFAULT FALSE,
+        data_type TEXT,
+        description TEXT,
+        protocol_mappings TEXT,
+        PRIMARY KEY (channel_id, point_id)
+    );
+
+    CREATE TABLE IF NOT EXISTS adjustment_points (
+        point_id INTEGER NOT NULL,
+        channel_id INTEGER NOT NULL REFERENCES channels(channel_id),
+        signal_name TEXT NOT NULL,
+        scale REAL NOT NULL DEFAULT 1.0,
+        offset REAL NOT NULL DEFAULT 0.0,
+        unit TEXT,
+        reverse BOOLEAN NOT NULL DEFAULT FALSE,
+        data_type TEXT,
+        description TEXT,
+        protocol_mappings TEXT,
+        PRIMARY KEY (channel_id, point_id)
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_telemetry_points_channel ON telemetry_points(channel_id);
+    CREATE INDEX IF NOT EXISTS idx_signal_points_channel ON signal_points(channel_id);
+    CREATE INDEX IF NOT EXISTS idx_control_points_channel ON control_points(channel_id);
+    CREATE INDEX IF NOT EXISTS idx_adjustment_points_channel ON adjustment_points(channel_id);
+
+    CREATE TRIGGER IF NOT EXISTS cleanup_routing_on_telemetry_delete
+    AFTER DELETE ON telemetry_points
+    FOR EACH ROW
+    BEGIN
+        DELETE FROM measurement_routing
+        WHERE channel_id = OLD.channel_id
+          AND channel_type = 'T'
+          AND channel_point_id = OLD.point_id;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS cleanup_routing_on_signal_delete
+    AFTER DELETE ON signal_points
+    FOR EACH ROW
+    BEGIN
+        DELETE FROM measurement_routing
+        WHERE channel_id = OLD.channel_id
+          AND channel_type = 'S'
+          AND channel_point_id = OLD.point_id;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS cleanup_routing_on_control_delete
+    AFTER DELETE ON control_points
+    FOR EACH ROW
+    BEGIN
+        DELETE FROM action_routing
+        WHERE channel_id = OLD.channel_id
+          AND channel_type = 'C'
+          AND channel_point_id = OLD.point_id;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS cleanup_routing_on_adjustment_delete
+    AFTER DELETE ON adjustment_points
+    FOR EACH ROW
+    BEGIN
+        DELETE FROM action_routing
+        WHERE channel_id = OLD.channel_id
+          AND channel_type = 'A'
+          AND channel_point_id = OLD.point_id;
+    END;
+"#;
+
+/// Outbound webhooks notified of command results
+const COMMAND_WEBHOOKS: &str = r#"
+    CREATE TABLE IF NOT EXISTS command_webhooks (
+        client TEXT PRIMARY KEY,
+        url TEXT NOT NULL,
+        token TEXT,
+        channels TEXT,
+        on_success BOOLEAN NOT NULL DEFAULT TRUE,
+        on_failure BOOLEAN NOT NULL DEFAULT TRUE,
+        all_commands BOOLEAN NOT NULL DEFAULT FALSE,
+        timeout_ms INTEGER NOT NULL DEFAULT 3000,
+        enabled BOOLEAN NOT NULL DEFAULT TRUE,
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    )
+"#;
+
+const POINT_ACCESS: [&str; 2] = [
+    "ALTER TABLE control_points ADD COLUMN access TEXT",
+    "ALTER TABLE adjustment_points ADD COLUMN access TEXT",
+];
+
+/// Add the per-point write scope (`access`) to the control/adjustment tables
+///
+/// Databases whose baseline was generated from record structs that already
+/// had the column skip the ALTER.
+fn point_access_scopes(conn: &mut SqliteConnection) -> MigrationFuture<'_> {
+    Box::pin(async move {
+        for (table, ddl) in ["control_points", "adjustment_points"]
+            .into_iter()
+            .zip(POINT_ACCESS)
+        {
+            if !has_column(&mut *conn, table, "access").await? {
+                sqlx::query(ddl).execute(&mut *conn).await?;
+            }
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_baseline_creates_comsrv_tables() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        let report = migrator().run(&pool).await.unwrap();
+        assert_eq!(report.current_version, 3);
+
+        let tables: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master WHERE type IN ('table', 'trigger') ORDER BY name",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        for expected in [
+            "channels",
+            "telemetry_points",
+            "adjustment_points",
+            "service_config",
+            "command_webhooks",
+            "cleanup_routing_on_signal_delete",
+        ] {
+            assert!(tables.iter().any(|t| t == expected), "missing {}", expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_point_access_added_to_existing_tables() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        // Point table as created before the access scope existed
+        sqlx::query(
+            "CREATE TABLE control_points (point_id INTEGER NOT NULL, channel_id INTEGER NOT NULL,
+             signal_name TEXT NOT NULL, protocol_mappings TEXT, PRIMARY KEY (channel_id, point_id))",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        migrator().run(&pool).await.unwrap();
+
+        for table in ["control_points", "adjustment_points"] {
+            let mut conn = pool.acquire().await.unwrap();
+            assert!(has_column(&mut conn, table, "access").await.unwrap());
+        }
+    }
+
+    /// Every column of the record structs exists after migrating a new database
+    #[tokio::test]
+    async fn test_migrations_cover_record_structs() {
+        use crate::core::config::types::{
+            ADJUSTMENT_POINTS_TABLE, CHANNELS_TABLE, COMMAND_WEBHOOKS_TABLE, CONTROL_POINTS_TABLE,
+            SIGNAL_POINTS_TABLE, TELEMETRY_POINTS_TABLE,
+        };
+
+        let connect = || {
+            sqlx::sqlite::SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect("sqlite::memory:")
+        };
+        let migrated = connect().await.unwrap();
+        migrator().run(&migrated).await.unwrap();
+        let generated = connect().await.unwrap();
+
+        for (table, ddl) in [
+            ("channels", CHANNELS_TABLE),
+            ("telemetry_points", TELEMETRY_POINTS_TABLE),
+            ("signal_points", SIGNAL_POINTS_TABLE),
+            ("control_points", CONTROL_POINTS_TABLE),
+            ("adjustment_points", ADJUSTMENT_POINTS_TABLE),
+            ("command_webhooks", COMMAND_WEBHOOKS_TABLE),
+        ] {
+            sqlx::query(ddl).execute(&generated).await.unwrap();
+            let columns = "SELECT name FROM pragma_table_info(?) ORDER BY name";
+            let expected: Vec<String> = sqlx::query_scalar(columns)
+                .bind(table)
+                .fetch_all(&generated)
+                .await
+                .unwrap();
+            let actual: Vec<String> = sqlx::query_scalar(columns)
+                .bind(table)
+                .fetch_all(&migrated)
+                .await
+                .unwrap();
+            for column in expected {
+                assert!(
+                    actual.contains(&column),
+                    "{}.{} has no migration",
+                    table,
+                    column
+                );
+            }
+        }
+    }
+}
@@ -4,8 +4,8 @@
 
 use crate::core::config::Point;
 use crate::core::config::{
-    AdjustmentPoint, AppConfig, ChannelConfig, ControlPoint, RuntimeChannelConfig, ServiceConfig,
-    SignalPoint, TelemetryPoint,
+    AdjustmentPoint, AppConfig, ChannelConfig, ControlPoint, PointAccess, RuntimeChannelConfig,
+    ServiceConfig, SignalPoint, TelemetryPoint,
 };
 #[cfg(test)]
 use crate::core::config::{
@@ -19,7 +19,7 @@ use sqlx::{Row, SqlitePool};
 use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
-use tracing::info;
+use tracing::{info, warn};
 
 /// Comsrv-specific SQLite configuration loader
 pub struct ComsrvSqliteLoader {
@@ -262,7 +262,7 @@ impl ComsrvSqliteLoader {
 
         // Load control points from control_points table (with embedded protocol mappings)
         let control_rows = sqlx::query(
-            "SELECT point_id, signal_name, unit, reverse, data_type, description, protocol_mappings
+            "SELECT point_id, signal_name, unit, reverse, data_type, description, protocol_mappings, access
              FROM control_points
              WHERE channel_id = ?
              ORDER BY point_id",
@@ -274,7 +274,7 @@ impl ComsrvSqliteLoader {
 
         // Load adjustment points from adjustment_points table (with embedded protocol mappings)
         let adjustment_rows = sqlx::query(
-            "SELECT point_id, signal_name, scale, offset, unit, reverse, data_type, description, protocol_mappings
+            "SELECT point_id, signal_name, scale, offset, unit, reverse, data_type, description, protocol_mappings, access
              FROM adjustment_points
              WHERE channel_id = ?
              ORDER BY point_id",
@@ -399,6 +399,7 @@ impl ComsrvSqliteLoader {
                 on_value: 1,
                 off_value: 0,
                 pulse_duration_ms: Some(100),
+                access: point_access(&row, channel_id, "C", point_id_u32),
             };
             runtime_config.control_points.push(point);
         }
@@ -443,6 +444,7 @@ impl ComsrvSqliteLoader {
                 data_type,
                 scale,
                 offset,
+                access: point_access(&row, channel_id, "A", point_id_u32),
             };
             runtime_config.adjustment_points.push(point);
         }
@@ -464,6 +466,28 @@ impl ComsrvSqliteLoader {
     }
 }
 
+/// Write scope of a C/A point row; an unrecognized scope locks the point
+/// rather than opening it up
+fn point_access(
+    row: &sqlx::sqlite::SqliteRow,
+    channel_id: u32,
+    point_type: &str,
+    point_id: u32,
+) -> PointAccess {
+    let value: Option<String> = row.try_get("access").ok().flatten();
+    value
+        .as_deref()
+        .unwrap_or_default()
+        .parse()
+        .unwrap_or_else(|e| {
+            warn!(
+                "Ch{}:{}:{} {}; treating as read_only",
+                channel_id, point_type, point_id, e
+            );
+            PointAccess::ReadOnly
+        })
+}
+
 #[cfg(test)]
 #[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
 mod tests {
@@ -1,7 +1,7 @@
 //! Comsrv service configuration structures
 
 use common::serde_helpers::{deserialize_bool_flexible, deserialize_u8_default_zero};
-use common::validation::CsvFields;
+use common::validation::{CsvFields, RuleSet, RuleSpec, ValidationRule};
 use common::{
     ApiConfig, BaseServiceConfig, ConfigValidator, LoggingConfig, RedisConfig, ValidationLevel,
     ValidationResult,
@@ -249,6 +249,77 @@ pub struct SignalPoint {
     pub reverse: bool,
 }
 
+/// Who may write a control/adjustment point through the API
+///
+/// Stored in the point table's `access` column; empty means anyone the API
+/// accepts. Role scopes are checked against the caller's token role (see
+/// `common::auth`), so they need `JWT_SECRET_KEY` to be set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum PointAccess {
+    /// No restriction
+    #[default]
+    Any,
+    /// Never written through the API
+    ReadOnly,
+    /// Operator role or higher
+    Operator,
+    /// Engineer role or higher
+    Engineer,
+}
+
+impl PointAccess {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PointAccess::Any => "any",
+            PointAccess::ReadOnly => "read_only",
+            PointAccess::Operator => "operator",
+            PointAccess::Engineer => "engineer",
+        }
+    }
+
+    /// Role a caller needs to write the point (`None` for `Any` and `ReadOnly`)
+    pub fn required_role(&self) -> Option<common::auth::Role> {
+        match self {
+            PointAccess::Operator => Some(common::auth::Role::Operator),
+            PointAccess::Engineer => Some(common::auth::Role::Engineer),
+            PointAccess::Any | PointAccess::ReadOnly => None,
+        }
+    }
+}
+
+impl std::str::FromStr for PointAccess {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "" | "any" => Ok(PointAccess::Any),
+            "read_only" | "readonly" | "ro" => Ok(PointAccess::ReadOnly),
+            "operator" => Ok(PointAccess::Operator),
+            "engineer" => Ok(PointAccess::Engineer),
+            other => Err(format!(
+                "Invalid point access '{}'. Must be one of: any, read_only, operator, engineer",
+                other
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PointAccess {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = Option::<String>::deserialize(deserializer)?;
+        value
+            .as_deref()
+            .unwrap_or_default()
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 /// Control point (C)
 /// For remote control commands
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -277,6 +348,10 @@ pub struct ControlPoint {
 
     /// Pulse duration in milliseconds (for momentary controls)
     pub pulse_duration_ms: Option<u32>,
+
+    /// Who may write the point through the API
+    #[serde(default)]
+    pub access: PointAccess,
 }
 
 /// Adjustment point (A)
@@ -309,6 +384,10 @@ pub struct AdjustmentPoint {
     /// Offset for value conversion
     #[serde(default, deserialize_with = "deserialize_offset")]
     pub offset: f64,
+
+    /// Who may write the point through the API
+    #[serde(default)]
+    pub access: PointAccess,
 }
 
 /// Telemetry points table record
@@ -414,6 +493,8 @@ struct ControlPointRecord {
     description: Option<String>,
 
     protocol_mappings: Option<String>, // JSON TEXT
+
+    access: Option<String>, // PointAccess, NULL = any
 }
 
 /// Adjustment points table record
@@ -450,6 +531,8 @@ struct AdjustmentPointRecord {
     description: Option<String>,
 
     protocol_mappings: Option<String>, // JSON TEXT
+
+    access: Option<String>, // PointAccess, NULL = any
 }
 
 /// Telemetry points table SQL (generated by Schema macro)
@@ -514,6 +597,52 @@ struct ChannelRoutingRecord {
 // Schema SQL constant
 pub const CHANNEL_ROUTING_TABLE: &str = ChannelRoutingRecord::CREATE_TABLE_SQL;
 
+// ────────────────────── Command Webhooks Table ──────────────────────
+
+/// Command result webhook of an API client
+/// Receives control/adjustment completion callbacks (see `core::channels::command_webhooks`)
+#[allow(dead_code)]
+#[derive(Schema)]
+#[table(name = "command_webhooks")]
+struct CommandWebhookRecord {
+    #[column(primary_key)]
+    client: String,
+
+    #[column(not_null)]
+    url: String,
+
+    // Sent as `Authorization: Bearer {token}`
+    token: Option<String>,
+
+    // JSON array of channel IDs; NULL = all channels
+    channels: Option<String>,
+
+    #[column(default = "true")]
+    on_success: bool,
+
+    #[column(default = "true")]
+    on_failure: bool,
+
+    // Also notify commands not submitted by this client (e.g. modsrv actions)
+    #[column(default = "false")]
+    all_commands: bool,
+
+    #[column(default = "3000")]
+    timeout_ms: u32,
+
+    #[column(default = "true")]
+    enabled: bool,
+
+    #[column(default = "CURRENT_TIMESTAMP")]
+    created_at: String, // TIMESTAMP type
+
+    #[column(default = "CURRENT_TIMESTAMP")]
+    updated_at: String, // TIMESTAMP type
+}
+
+/// Command webhooks table SQL (generated by Schema macro)
+pub const COMMAND_WEBHOOKS_TABLE: &str = CommandWebhookRecord::CREATE_TABLE_SQL;
+
 // ============================================================================
 // Protocol Mapping Structures
 // ============================================================================
@@ -787,6 +916,27 @@ impl RuntimeChannelConfig {
             PointType::Adjustment => self.get_adjustment_point(point_id).map(|p| &p.base),
         }
     }
+
+    /// All points with their type, in T/S/C/A order
+    pub fn points(&self) -> impl Iterator<Item = (PointType, &Point)> {
+        let telemetry = self
+            .telemetry_points
+            .iter()
+            .map(|p| (PointType::Telemetry, &p.base));
+        let signal = self
+            .signal_points
+            .iter()
+            .map(|p| (PointType::Signal, &p.base));
+        let control = self
+            .control_points
+            .iter()
+            .map(|p| (PointType::Control, &p.base));
+        let adjustment = self
+            .adjustment_points
+            .iter()
+            .map(|p| (PointType::Adjustment, &p.base));
+        telemetry.chain(signal).chain(control).chain(adjustment)
+    }
 }
 
 // Default value functions
@@ -861,40 +1011,13 @@ impl ConfigValidator for ComsrvConfig {
     }
 
     fn validate_business(&self) -> Result<ValidationResult> {
-        let mut result = ValidationResult::new(ValidationLevel::Business);
-
-        // Check for duplicate channel IDs
-        let mut channel_ids = std::collections::HashSet::new();
-        for channel in &self.channels {
-            if !channel_ids.insert(channel.core.id) {
-                result.add_error(format!("Duplicate channel ID: {}", channel.core.id));
-            }
-        }
-
-        // Check for duplicate channel names
-        let mut channel_names = std::collections::HashSet::new();
-        for channel in &self.channels {
-            if !channel_names.insert(&channel.core.name) {
-                result.add_error(format!("Duplicate channel name: {}", channel.core.name));
-            }
-        }
+        let mut result = Self::rule_set().evaluate(self, ValidationLevel::Business)?;
 
         // Warn if no channels configured
         if self.channels.is_empty() {
             result.add_warning("No channels configured".to_string());
         }
 
-        // Check if protocol is supported
-        let supported_protocols = ["modbus_tcp", "modbus_rtu", "virtual", "grpc"];
-        for channel in &self.channels {
-            if !supported_protocols.contains(&channel.core.protocol.as_str()) {
-                result.add_warning(format!(
-                    "Channel {} uses unknown protocol: {}",
-                    channel.core.name, channel.core.protocol
-                ));
-            }
-        }
-
         Ok(result)
     }
 
@@ -925,40 +1048,58 @@ impl ChannelConfig {
             ));
         }
 
-        // Protocol-specific parameter validation
-        match self.core.protocol.as_str() {
-            "modbus_tcp" => {
-                if !self.parameters.contains_key("host") {
-                    result.add_error(format!(
-                        "Channel {}: Modbus TCP requires 'host' parameter",
-                        self.core.name
-                    ));
-                }
-                if !self.parameters.contains_key("port") {
-                    result.add_error(format!(
-                        "Channel {}: Modbus TCP requires 'port' parameter",
-                        self.core.name
-                    ));
-                }
-            },
-            "modbus_rtu" => {
-                if !self.parameters.contains_key("device") {
-                    result.add_error(format!(
-                        "Channel {}: Modbus RTU requires 'device' parameter",
-                        self.core.name
-                    ));
-                }
-                if !self.parameters.contains_key("baud_rate") {
-                    result.add_error(format!(
-                        "Channel {}: Modbus RTU requires 'baud_rate' parameter",
-                        self.core.name
-                    ));
-                }
-            },
-            _ => {
-                // Other protocols may have different requirements
-            },
+        // Protocol-specific parameters are checked by ComsrvConfig::rule_set()
+    }
+}
+
+impl ComsrvConfig {
+    /// Declarative business rules shared by monarch validate and service startup
+    pub fn rule_set() -> RuleSet {
+        let supported_protocols = ["modbus_tcp", "modbus_rtu", "virtual", "grpc"];
+
+        let mut rules = RuleSet::new("comsrv")
+            .rule(
+                RuleSpec::from(ValidationRule::Unique {
+                    path: "channels[*].id".to_string(),
+                })
+                .message("Duplicate channel ID: {value}"),
+            )
+            .rule(
+                RuleSpec::from(ValidationRule::Unique {
+                    path: "channels[*].name".to_string(),
+                })
+                .message("Duplicate channel name: {value}"),
+            );
+
+        for (protocol, label, parameter) in [
+            ("modbus_tcp", "Modbus TCP", "host"),
+            ("modbus_tcp", "Modbus TCP", "port"),
+            ("modbus_rtu", "Modbus RTU", "device"),
+            ("modbus_rtu", "Modbus RTU", "baud_rate"),
+        ] {
+            rules = rules.rule(
+                RuleSpec::from(ValidationRule::RequiredIf {
+                    path: format!("parameters.{}", parameter),
+                    when: "protocol".to_string(),
+                    equals: protocol.into(),
+                })
+                .each("channels[*]")
+                .message(format!(
+                    "Channel {{name}}: {} requires '{}' parameter",
+                    label, parameter
+                )),
+            );
         }
+
+        rules.rule(
+            RuleSpec::from(ValidationRule::OneOf {
+                path: "protocol".to_string(),
+                values: supported_protocols.iter().map(|p| (*p).into()).collect(),
+            })
+            .each("channels[*]")
+            .warning()
+            .message("Channel {name} uses unknown protocol: {protocol}"),
+        )
     }
 }
 
@@ -1228,6 +1369,10 @@ impl CsvFields for ControlPoint {
             "data_type".to_string(),
         ]
     }
+
+    fn optional_fields() -> Vec<String> {
+        vec!["access".to_string()]
+    }
 }
 
 impl CsvFields for AdjustmentPoint {
@@ -1244,6 +1389,10 @@ impl CsvFields for AdjustmentPoint {
             "data_type".to_string(),
         ]
     }
+
+    fn optional_fields() -> Vec<String> {
+        vec!["access".to_string()]
+    }
 }
 
 // ============================================================================
@@ -0,0 +1,1060 @@
+//! Protocol Plugin SDK
+//!
+//! A protocol plugin describes itself instead of being spelled out in every
+//! consumer: its channel parameters, the columns of its point mapping CSVs and
+//! the point types it serves. comsrv serves the descriptors at
+//! `GET /api/protocols/plugins` (config-ui builds its forms and CSV templates
+//! from them), validates and normalizes mappings of protocols that have no
+//! hand-written validator, and `monarch sync` reads the same descriptors when
+//! it imports mapping CSVs.
+//!
+//! ```ignore
+//! comsrv::protocol_plugin! {
+//!     /// IEC 60870-5-104 client
+//!     pub struct Iec104Plugin {
+//!         name: "iec104",
+//!         display_name: "IEC 60870-5-104",
+//!         description: "IEC 104 telecontrol protocol over TCP/IP",
+//!         mapping_columns: &[
+//!             MappingColumn::integer("common_address", "ASDU common address")
+//!                 .required()
+//!                 .range(1, 65534),
+//!             MappingColumn::integer("ioa", "Information object address")
+//!                 .required()
+//!                 .range(1, 16_777_215),
+//!         ],
+//!     }
+//! }
+//!
+//! comsrv::register_protocol_plugins!(Iec104Plugin);
+//! ```
+//!
+//! Channel parameters default to the driver metadata igw publishes for the
+//! protocol. Channel construction stays in
+//! [`ChannelManager`](crate::core::channels::ChannelManager); protocols
+//! shipped as shared libraries bring their own runtime (see [`dylib`]).
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value as JsonValue};
+use std::sync::{Arc, OnceLock, RwLock};
+
+pub use igw::{ParameterMetadata, ParameterType};
+pub use voltage_model::PointType;
+
+use crate::utils::normalize_protocol_name;
+
+#[cfg(feature = "dylib-plugins")]
+pub mod dylib; // Plugins loaded from shared libraries (C ABI)
+
+/// All four point types
+pub const ALL_POINT_TYPES: &[PointType] = &[
+    PointType::Telemetry,
+    PointType::Signal,
+    PointType::Control,
+    PointType::Adjustment,
+];
+
+// ============================================================================
+// Mapping columns
+// ============================================================================
+
+/// Value type of a mapping column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnKind {
+    String,
+    Integer,
+    Float,
+    Boolean,
+}
+
+/// Column of a point mapping CSV (`mapping/{type}_mapping.csv`)
+///
+/// Built in const context, e.g.
+/// `MappingColumn::integer("slave_id", "Slave ID").required().range(1, 247)`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, utoipa::ToSchema)]
+pub struct MappingColumn {
+    /// Column header and key in `protocol_mappings`
+    pub name: &'static str,
+    pub kind: ColumnKind,
+    pub description: &'static str,
+    pub required: bool,
+    /// Value used when the column is empty or absent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<&'static str>,
+    /// Inclusive bounds (integer and float columns)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<i64>,
+    /// Allowed values (empty: any)
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    #[schema(value_type = Vec<String>)]
+    pub choices: &'static [&'static str],
+}
+
+impl MappingColumn {
+    /// Optional column without bounds
+    pub const fn new(name: &'static str, kind: ColumnKind, description: &'static str) -> Self {
+        Self {
+            name,
+            kind,
+            description,
+            required: false,
+            default: None,
+            min: None,
+            max: None,
+            choices: &[],
+        }
+    }
+
+    pub const fn string(name: &'static str, description: &'static str) -> Self {
+        Self::new(name, ColumnKind::String, description)
+    }
+
+    pub const fn integer(name: &'static str, description: &'static str) -> Self {
+        Self::new(name, ColumnKind::Integer, description)
+    }
+
+    pub const fn float(name: &'static str, description: &'static str) -> Self {
+        Self::new(name, ColumnKind::Float, description)
+    }
+
+    pub const fn boolean(name: &'static str, description: &'static str) -> Self {
+        Self::new(name, ColumnKind::Boolean, description)
+    }
+
+    /// Column must be present on every mapped point
+    pub const fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Value filled in when the column is left empty
+    pub const fn default_value(mut self, value: &'static str) -> Self {
+        self.default = Some(value);
+        self
+    }
+
+    /// Inclusive numeric bounds
+    pub const fn range(mut self, min: i64, max: i64) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    /// Restrict the column to a fixed set of values
+    pub const fn choices(mut self, choices: &'static [&'static str]) -> Self {
+        self.choices = choices;
+        self
+    }
+
+    /// Convert a cell to the JSON type of the column (None if it does not parse)
+    fn coerce(&self, value: &JsonValue) -> Option<JsonValue> {
+        match (self.kind, value) {
+            (ColumnKind::String, JsonValue::String(_)) => Some(value.clone()),
+            (ColumnKind::String, JsonValue::Number(n)) => Some(JsonValue::String(n.to_string())),
+            (ColumnKind::Integer, JsonValue::Number(n)) => {
+                if n.is_i64() || n.is_u64() {
+                    Some(value.clone())
+                } else {
+                    // Spreadsheets export integers as 1.0
+                    n.as_f64()
+                        .filter(|f| f.fract() == 0.0)
+                        .map(|f| JsonValue::Number(Number::from(f as i64)))
+                }
+            },
+            (ColumnKind::Integer, JsonValue::String(s)) => {
+                let s = s.trim();
+                s.parse::<i64>()
+                    .ok()
+                    .or_else(|| {
+                        s.parse::<f64>()
+                            .ok()
+                            .filter(|f| f.fract() == 0.0)
+                            .map(|f| f as i64)
+                    })
+                    .map(|n| JsonValue::Number(Number::from(n)))
+            },
+            (ColumnKind::Float, JsonValue::Number(_)) => Some(value.clone()),
+            (ColumnKind::Float, JsonValue::String(s)) => s
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .and_then(Number::from_f64)
+                .map(JsonValue::Number),
+            (ColumnKind::Boolean, JsonValue::Bool(_)) => Some(value.clone()),
+            (ColumnKind::Boolean, JsonValue::Number(n)) => match n.as_i64() {
+                Some(0) => Some(JsonValue::Bool(false)),
+                Some(1) => Some(JsonValue::Bool(true)),
+                _ => None,
+            },
+            (ColumnKind::Boolean, JsonValue::String(s)) => {
+                match s.trim().to_ascii_lowercase().as_str() {
+                    "true" | "1" | "yes" => Some(JsonValue::Bool(true)),
+                    "false" | "0" | "no" => Some(JsonValue::Bool(false)),
+                    _ => None,
+                }
+            },
+            _ => None,
+        }
+    }
+
+    /// Check a coerced value against bounds and choices
+    fn check(&self, value: &JsonValue) -> Option<String> {
+        if let Some(n) = value.as_f64() {
+            let below = self.min.is_some_and(|min| n < min as f64);
+            let above = self.max.is_some_and(|max| n > max as f64);
+            if below || above {
+                return Some(format!(
+                    "'{}' = {} out of range ({}-{})",
+                    self.name,
+                    value,
+                    self.min.map_or_else(String::new, |v| v.to_string()),
+                    self.max.map_or_else(String::new, |v| v.to_string()),
+                ));
+            }
+        }
+        if !self.choices.is_empty() {
+            let text = match value {
+                JsonValue::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            if !self.choices.contains(&text.as_str()) {
+                return Some(format!(
+                    "'{}' = '{}' invalid (valid: {})",
+                    self.name,
+                    text,
+                    self.choices.join(", ")
+                ));
+            }
+        }
+        None
+    }
+}
+
+/// Empty CSV cells arrive as "" and mean "not set"
+fn is_blank(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Null => true,
+        JsonValue::String(s) => s.trim().is_empty(),
+        _ => false,
+    }
+}
+
+// ============================================================================
+// Plugin trait
+// ============================================================================
+
+/// Self-describing protocol
+///
+/// Implement it with [`protocol_plugin!`](crate::protocol_plugin) and make it
+/// known with [`register_protocol_plugins!`](crate::register_protocol_plugins).
+pub trait ProtocolPlugin: Send + Sync + 'static {
+    /// Protocol name stored in `channels.protocol` (e.g. "modbus_tcp")
+    fn name(&self) -> &'static str;
+
+    /// Other spellings accepted for the protocol
+    fn aliases(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    fn display_name(&self) -> &'static str;
+
+    fn description(&self) -> &'static str;
+
+    /// Point types the protocol can serve
+    fn point_types(&self) -> &'static [PointType] {
+        ALL_POINT_TYPES
+    }
+
+    /// Channel parameters (`channels.config`)
+    ///
+    /// Defaults to the parameters of the recommended igw driver.
+    fn parameters(&self) -> Vec<ParameterMetadata> {
+        igw_parameters(self.name(), self.aliases())
+    }
+
+    /// Columns of the point mapping CSVs, `point_id` excluded
+    fn mapping_columns(&self) -> &'static [MappingColumn];
+
+    /// Validate the mapping of one point
+    ///
+    /// An empty mapping clears the binding and is always accepted.
+    fn validate_mapping(
+        &self,
+        point_type: PointType,
+        point_id: u32,
+        mapping: &JsonValue,
+    ) -> Vec<String> {
+        validate_columns(self, point_type, point_id, mapping)
+    }
+
+    /// Convert mapping cells to the JSON types of their columns and fill defaults
+    fn normalize_mapping(&self, mapping: &JsonValue) -> JsonValue {
+        normalize_columns(self.mapping_columns(), mapping)
+    }
+
+    /// CSV header of the mapping files (`point_id` followed by the columns)
+    fn mapping_template(&self) -> String {
+        std::iter::once("point_id")
+            .chain(self.mapping_columns().iter().map(|c| c.name))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Parameters of the recommended igw driver registered for a protocol
+pub fn igw_parameters(name: &str, aliases: &[&str]) -> Vec<ParameterMetadata> {
+    let registry = igw::get_protocol_registry();
+    let Some(protocol) = registry
+        .protocols()
+        .iter()
+        .find(|p| p.protocol_type == name || aliases.contains(&p.protocol_type))
+    else {
+        return Vec::new();
+    };
+    protocol
+        .drivers
+        .iter()
+        .find(|d| d.is_recommended)
+        .or_else(|| protocol.drivers.first())
+        .map(|d| d.parameters.clone())
+        .unwrap_or_default()
+}
+
+/// Default mapping validation driven by [`ProtocolPlugin::mapping_columns`]
+pub fn validate_columns<P: ProtocolPlugin + ?Sized>(
+    plugin: &P,
+    point_type: PointType,
+    point_id: u32,
+    mapping: &JsonValue,
+) -> Vec<String> {
+    let Some(cells) = mapping.as_object() else {
+        if mapping.is_null() {
+            return Vec::new();
+        }
+        return vec![format!("Point {}: mapping must be an object", point_id)];
+    };
+    if cells.is_empty() {
+        return Vec::new();
+    }
+
+    let mut errors = Vec::new();
+    if !plugin.point_types().contains(&point_type) {
+        errors.push(format!(
+            "Point {}: {} does not support {} points",
+            point_id,
+            plugin.display_name(),
+            point_type.as_str()
+        ));
+    }
+
+    let columns = plugin.mapping_columns();
+    for column in columns {
+        match cells.get(column.name).filter(|v| !is_blank(v)) {
+            None => {
+                if column.required && column.default.is_none() {
+                    errors.push(format!("Point {}: '{}' is required", point_id, column.name));
+                }
+            },
+            Some(value) => match column.coerce(value) {
+                Some(coerced) => {
+                    if let Some(e) = column.check(&coerced) {
+                        errors.push(format!("Point {}: {}", point_id, e));
+                    }
+                },
+                None => errors.push(format!(
+                    "Point {}: '{}' = {} is not a valid {:?}",
+                    point_id, column.name, value, column.kind
+                )),
+            },
+        }
+    }
+
+    for key in cells.keys() {
+        if key != "point_id" && !columns.iter().any(|c| c.name == key) {
+            errors.push(format!(
+                "Point {}: unknown mapping column '{}' for {}",
+                point_id,
+                key,
+                plugin.name()
+            ));
+        }
+    }
+
+    errors
+}
+
+/// Default mapping normalization driven by the column kinds
+///
+/// Cells that do not parse are kept as they are (validation reports them).
+pub fn normalize_columns(columns: &[MappingColumn], mapping: &JsonValue) -> JsonValue {
+    let Some(cells) = mapping.as_object() else {
+        return mapping.clone();
+    };
+    if cells.is_empty() {
+        return mapping.clone();
+    }
+
+    let mut normalized = Map::with_capacity(cells.len());
+    for (key, value) in cells {
+        if key == "point_id" {
+            continue;
+        }
+        let value = match columns.iter().find(|c| c.name == key) {
+            Some(column) if !is_blank(value) => {
+                column.coerce(value).unwrap_or_else(|| value.clone())
+            },
+            _ => value.clone(),
+        };
+        normalized.insert(key.clone(), value);
+    }
+
+    for column in columns {
+        let Some(default) = column.default else {
+            continue;
+        };
+        if normalized.get(column.name).is_none_or(is_blank) {
+            let value = JsonValue::String(default.to_string());
+            normalized.insert(
+                column.name.to_string(),
+                column.coerce(&value).unwrap_or(value),
+            );
+        }
+    }
+
+    JsonValue::Object(normalized)
+}
+
+// ============================================================================
+// Macros
+// ============================================================================
+
+/// Declare a protocol plugin as a unit struct implementing [`ProtocolPlugin`]
+///
+/// `aliases`, `point_types` and `parameters` are optional; see the module
+/// documentation for an example.
+#[macro_export]
+macro_rules! protocol_plugin {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $plugin:ident {
+            name: $name:expr,
+            $(aliases: $aliases:expr,)?
+            display_name: $display_name:expr,
+            description: $description:expr,
+            $(point_types: $point_types:expr,)?
+            $(parameters: $parameters:expr,)?
+            mapping_columns: $columns:expr $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, Default)]
+        $vis struct $plugin;
+
+        impl $crate::core::plugins::ProtocolPlugin for $plugin {
+            fn name(&self) -> &'static str {
+                $name
+            }
+
+            $(
+                fn aliases(&self) -> &'static [&'static str] {
+                    $aliases
+                }
+            )?
+
+            fn display_name(&self) -> &'static str {
+                $display_name
+            }
+
+            fn description(&self) -> &'static str {
+                $description
+            }
+
+            $(
+                fn point_types(&self) -> &'static [$crate::core::plugins::PointType] {
+                    $point_types
+                }
+            )?
+
+            $(
+                fn parameters(&self) -> ::std::vec::Vec<$crate::core::plugins::ParameterMetadata> {
+                    $parameters
+                }
+            )?
+
+            fn mapping_columns(&self) -> &'static [$crate::core::plugins::MappingColumn] {
+                const COLUMNS: &[$crate::core::plugins::MappingColumn] = $columns;
+                COLUMNS
+            }
+        }
+    };
+}
+
+/// Register plugins with the process-wide [`ProtocolPlugins`] registry
+///
+/// A plugin with the name of a registered one replaces it.
+#[macro_export]
+macro_rules! register_protocol_plugins {
+    ($($plugin:expr),+ $(,)?) => {
+        $(
+            $crate::core::plugins::ProtocolPlugins::global()
+                .register(::std::sync::Arc::new($plugin));
+        )+
+    };
+}
+
+// ============================================================================
+// Registry
+// ============================================================================
+
+/// Process-wide plugin registry, pre-filled with the built-in protocols
+pub struct ProtocolPlugins {
+    plugins: RwLock<Vec<Arc<dyn ProtocolPlugin>>>,
+}
+
+impl ProtocolPlugins {
+    /// Registry shared by the API, mapping validation and monarch
+    pub fn global() -> &'static ProtocolPlugins {
+        static GLOBAL: OnceLock<ProtocolPlugins> = OnceLock::new();
+        GLOBAL.get_or_init(|| ProtocolPlugins {
+            plugins: RwLock::new(builtin_plugins()),
+        })
+    }
+
+    /// Add a plugin, replacing one with the same name (returned)
+    pub fn register(&self, plugin: Arc<dyn ProtocolPlugin>) -> Option<Arc<dyn ProtocolPlugin>> {
+        let Ok(mut plugins) = self.plugins.write() else {
+            return None;
+        };
+        match plugins.iter_mut().find(|p| p.name() == plugin.name()) {
+            Some(existing) => Some(std::mem::replace(existing, plugin)),
+            None => {
+                tracing::debug!("Protocol plugin registered: {}", plugin.name());
+                plugins.push(plugin);
+                None
+            },
+        }
+    }
+
+    /// Plugin for a protocol name or alias (any spelling `normalize_protocol_name` accepts)
+    pub fn get(&self, protocol: &str) -> Option<Arc<dyn ProtocolPlugin>> {
+        let wanted = normalize_protocol_name(protocol);
+        let plugins = self.plugins.read().ok()?;
+        plugins
+            .iter()
+            .find(|p| {
+                normalize_protocol_name(p.name()) == wanted
+                    || p.aliases()
+                        .iter()
+                        .any(|a| normalize_protocol_name(a) == wanted)
+            })
+            .cloned()
+    }
+
+    /// All plugins, sorted by name
+    pub fn list(&self) -> Vec<Arc<dyn ProtocolPlugin>> {
+        let mut plugins = self.plugins.read().map(|p| p.clone()).unwrap_or_default();
+        plugins.sort_by_key(|p| p.name());
+        plugins
+    }
+}
+
+// ============================================================================
+// Built-in plugins
+// ============================================================================
+
+/// Mapping columns of the Modbus plugins, followed by protocol-specific ones
+macro_rules! modbus_columns {
+    ($($extra:expr),* $(,)?) => {
+        &[
+            MappingColumn::integer("slave_id", "Modbus slave ID")
+                .required()
+                .range(1, 247),
+            MappingColumn::integer("function_code", "Read/write function code")
+                .required()
+                .choices(&["1", "2", "3", "4", "5", "6", "15", "16"]),
+            MappingColumn::integer("register_address", "Register or coil address")
+                .required()
+                .range(0, 65535),
+            MappingColumn::string("data_type", "Register data type").choices(&[
+                "bool", "boolean", "uint16", "int16", "uint32", "int32", "float32", "float64",
+            ]),
+            MappingColumn::string("byte_order", "Word/byte order")
+                .choices(&["ABCD", "DCBA", "BADC", "CDAB", "AB", "BA"]),
+            MappingColumn::integer("bit_position", "Bit within the register")
+                .default_value("0")
+                .range(0, 15),
+            MappingColumn::string("poll_group", "Poll group: fast, normal or slow")
+                .choices(&["fast", "normal", "slow"]),
+            $($extra,)*
+        ]
+    };
+}
+
+const MODBUS_COLUMNS: &[MappingColumn] = modbus_columns![];
+
+const MODBUS_TCP_COLUMNS: &[MappingColumn] = modbus_columns![MappingColumn::string(
+    "source",
+    "Server mode: RTDB point served, e.g. inst:5:M:1 or comsrv:1001:A:2"
+)];
+
+/// Modbus TCP parameters: the igw client's, plus client transport and server mode
+fn modbus_tcp_parameters() -> Vec<ParameterMetadata> {
+    let mut parameters = igw_parameters("modbus_tcp", &[]);
+    block_read_parameters(&mut parameters);
+    parameters.extend([
+        ParameterMetadata::optional(
+            "fast_poll_interval_ms",
+            "Fast Poll Interval (ms)",
+            "Polling interval of points in the fast poll group (default: a quarter of poll_interval_ms)",
+            ParameterType::Integer,
+            serde_json::json!(250),
+        ),
+        ParameterMetadata::optional(
+            "slow_poll_interval_ms",
+            "Slow Poll Interval (ms)",
+            "Polling interval of points in the slow poll group (default: 10x poll_interval_ms)",
+            ParameterType::Integer,
+            serde_json::json!(10000),
+        ),
+        ParameterMetadata::optional(
+            "mode",
+            "Mode",
+            "client polls a device; server exposes RTDB points to masters",
+            ParameterType::String,
+            serde_json::json!("client"),
+        ),
+        communication_mode_parameter("tcp"),
+        ParameterMetadata::optional(
+            "max_connections",
+            "Max Connections",
+            "Server mode: masters served at once",
+            ParameterType::Integer,
+            serde_json::json!(8),
+        ),
+        ParameterMetadata::optional(
+            "read_only",
+            "Read Only",
+            "Server mode: reject all writes from masters",
+            ParameterType::Boolean,
+            serde_json::json!(false),
+        ),
+    ]);
+    parameters
+}
+
+/// Client transport: the protocol's own, or RTU frames through a TCP device server
+fn communication_mode_parameter(default: &str) -> ParameterMetadata {
+    ParameterMetadata::optional(
+        "communication_mode",
+        "Communication Mode",
+        "tcp/rtu, or rtu_over_tcp for RTU frames forwarded raw by a device server",
+        ParameterType::String,
+        serde_json::json!(default),
+    )
+}
+
+/// Block read limits, adding `max_batch_size` where igw does not list it
+fn block_read_parameters(parameters: &mut Vec<ParameterMetadata>) {
+    if !parameters.iter().any(|p| p.name == "max_batch_size") {
+        parameters.push(ParameterMetadata::optional(
+            "max_batch_size",
+            "Max Batch Size",
+            "Maximum registers per batch read (max 125)",
+            ParameterType::Integer,
+            serde_json::json!(125),
+        ));
+    }
+    parameters.push(ParameterMetadata::optional(
+        "max_gap",
+        "Max Gap",
+        "Unmapped registers a batch read may span between points (0 = contiguous only)",
+        ParameterType::Integer,
+        serde_json::json!(10),
+    ));
+}
+
+/// Modbus RTU parameters: the serial line, or the device server of RTU over TCP
+fn modbus_rtu_parameters() -> Vec<ParameterMetadata> {
+    let mut parameters = igw_parameters("modbus_rtu", &[]);
+    block_read_parameters(&mut parameters);
+    parameters.extend([
+        communication_mode_parameter("rtu"),
+        ParameterMetadata::optional(
+            "host",
+            "Device Server Host",
+            "RTU over TCP: address of the serial device server",
+            ParameterType::String,
+            serde_json::json!(""),
+        ),
+        ParameterMetadata::optional(
+            "port",
+            "Device Server Port",
+            "RTU over TCP: TCP port of the serial device server",
+            ParameterType::Integer,
+            serde_json::json!(502),
+        ),
+        ParameterMetadata::optional(
+            "read_timeout_ms",
+            "Response Timeout (ms)",
+            "RTU over TCP: time to wait for a response frame",
+            ParameterType::Integer,
+            serde_json::json!(1000),
+        ),
+    ]);
+    parameters
+}
+
+protocol_plugin! {
+    /// Modbus TCP (igw::ModbusChannel)
+    pub struct ModbusTcpPlugin {
+        name: "modbus_tcp",
+        display_name: "Modbus TCP",
+        description: "Industrial Modbus TCP protocol (client, or server exposing RTDB points)",
+        parameters: modbus_tcp_parameters(),
+        mapping_columns: MODBUS_TCP_COLUMNS,
+    }
+}
+
+protocol_plugin! {
+    /// Modbus RTU over a serial line (igw::ModbusChannel)
+    pub struct ModbusRtuPlugin {
+        name: "modbus_rtu",
+        display_name: "Modbus RTU",
+        description: "Industrial Modbus RTU protocol over RS-485/RS-232, or RTU frames over TCP",
+        parameters: modbus_rtu_parameters(),
+        mapping_columns: MODBUS_COLUMNS,
+    }
+}
+
+protocol_plugin! {
+    /// Virtual channel computing points from expressions or signal generators
+    pub struct VirtualPlugin {
+        name: "virtual",
+        display_name: "Virtual",
+        description: "Virtual channel for testing and simulation, with per-point waveform generators",
+        mapping_columns: &[
+            MappingColumn::string("expression", "Value expression, e.g. P1 + P2 * 0.5"),
+            MappingColumn::string("waveform", "Signal generator (T, S)")
+                .choices(crate::core::channels::waveform::WAVEFORMS),
+            MappingColumn::float("amplitude", "Waveform amplitude (default 1)"),
+            MappingColumn::float("baseline", "Waveform baseline value (default 0)"),
+            MappingColumn::integer("period_ms", "Waveform period (default 60000)")
+                .range(1, 86_400_000),
+            MappingColumn::integer("phase_ms", "Shift of periodic waveforms")
+                .range(0, 86_400_000),
+            MappingColumn::float("duty", "Square: fraction of the period at the high level (default 0.5)"),
+            MappingColumn::float("step", "Random walk: largest change per poll"),
+            MappingColumn::string("csv", "Steps: file of duration_ms,value rows"),
+        ],
+    }
+}
+
+protocol_plugin! {
+    /// Digital inputs/outputs on GPIO pins
+    pub struct GpioPlugin {
+        name: "gpio",
+        aliases: &["di_do", "dido"],
+        display_name: "GPIO",
+        description: "Digital Input/Output via GPIO pins, with debounce, pulse counters and pulse outputs",
+        point_types: &[PointType::Telemetry, PointType::Signal, PointType::Control],
+        mapping_columns: &[
+            MappingColumn::integer("gpio_number", "GPIO pin number")
+                .required()
+                .range(0, 1023),
+            MappingColumn::integer(
+                "debounce_ms",
+                "Inputs and counters: time a new level must hold before it is accepted",
+            )
+            .range(0, 60_000),
+            MappingColumn::string("count_edge", "Telemetry counters: edges counted")
+                .choices(&["rising", "falling", "both"]),
+            MappingColumn::integer(
+                "pulse_ms",
+                "Controls: ON drives the output for this long, then switches it off",
+            )
+            .range(0, 3_600_000),
+        ],
+    }
+}
+
+fn can_parameters() -> Vec<ParameterMetadata> {
+    vec![
+        ParameterMetadata::optional(
+            "device",
+            "Interface",
+            "SocketCAN network interface",
+            ParameterType::String,
+            serde_json::json!("can0"),
+        ),
+        ParameterMetadata::optional(
+            "error_frames",
+            "Error Frames",
+            "Receive error frames to track bus-off and error-passive states",
+            ParameterType::Boolean,
+            serde_json::json!(true),
+        ),
+        ParameterMetadata::optional(
+            "write_timeout_ms",
+            "Write Timeout (ms)",
+            "Time to wait for room in the transmit queue",
+            ParameterType::Integer,
+            serde_json::json!(1000),
+        ),
+        ParameterMetadata::optional(
+            "dbc_file",
+            "DBC File",
+            "Signal database of points mapped by 'signal' (raw mode)",
+            ParameterType::String,
+            serde_json::json!(""),
+        ),
+        ParameterMetadata::optional(
+            "mode",
+            "Addressing",
+            "raw: points name CAN IDs; j1939: J1939 PGNs; canopen: CANopen objects",
+            ParameterType::String,
+            serde_json::json!("raw"),
+        ),
+        ParameterMetadata::optional(
+            "j1939_address",
+            "J1939 Address",
+            "Source address claimed by the channel",
+            ParameterType::Integer,
+            serde_json::json!(0x80),
+        ),
+        ParameterMetadata::optional(
+            "j1939_name",
+            "J1939 NAME",
+            "64-bit NAME used in the address claim (hex)",
+            ParameterType::String,
+            serde_json::json!("0x8000000000000000"),
+        ),
+        ParameterMetadata::optional(
+            "request_interval_ms",
+            "Request Interval (ms)",
+            "Interval between Request PGNs of on-request groups",
+            ParameterType::Integer,
+            serde_json::json!(1000),
+        ),
+        ParameterMetadata::optional(
+            "eds_files",
+            "EDS Files",
+            "CANopen EDS/DCF file of each node, as {\"node_id\": path}",
+            ParameterType::Object,
+            serde_json::json!({}),
+        ),
+        ParameterMetadata::optional(
+            "heartbeat_timeout_ms",
+            "Heartbeat Timeout (ms)",
+            "CANopen nodes without heartbeat for this long are offline",
+            ParameterType::Integer,
+            serde_json::json!(3000),
+        ),
+        ParameterMetadata::optional(
+            "sdo_timeout_ms",
+            "SDO Timeout (ms)",
+            "Time to wait for each CANopen SDO response",
+            ParameterType::Integer,
+            serde_json::json!(500),
+        ),
+        ParameterMetadata::optional(
+            "nmt_start",
+            "NMT Start",
+            "Switch all CANopen nodes to operational on connect",
+            ParameterType::Boolean,
+            serde_json::json!(false),
+        ),
+    ]
+}
+
+/// CAN bus signals over SocketCAN, addressed by CAN ID, J1939 PGN or
+/// CANopen object
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CanPlugin;
+
+impl CanPlugin {
+    const COLUMNS: &'static [MappingColumn] = &[
+        MappingColumn::integer("can_id", "CAN frame ID (raw mode)"),
+        MappingColumn::string(
+            "signal",
+            "DBC signal, as MESSAGE.SIGNAL or SIGNAL (raw mode)",
+        ),
+        MappingColumn::integer("pgn", "J1939 parameter group number").range(0, 0x3FFFF),
+        MappingColumn::integer("spn", "J1939 suspect parameter number"),
+        MappingColumn::integer("source_address", "J1939 sender of an input group").range(0, 253),
+        MappingColumn::integer("destination_address", "J1939 destination of a PDU1 command")
+            .range(0, 255),
+        MappingColumn::integer("priority", "J1939 priority of a command").range(0, 7),
+        MappingColumn::boolean("request", "J1939 group sent on request only")
+            .default_value("false"),
+        MappingColumn::integer("node_id", "CANopen node").range(1, 127),
+        MappingColumn::integer("index", "CANopen object index").range(0, 0xFFFF),
+        MappingColumn::integer("subindex", "CANopen object sub-index").range(0, 255),
+        MappingColumn::string(
+            "nmt",
+            "CANopen NMT state reported as 1 (online, operational, pre_operational, stopped)",
+        ),
+        // Signals of J1939 transport messages lie beyond the first 8 bytes
+        MappingColumn::integer("start_bit", "First bit of the signal (DBC numbering)")
+            .range(0, 14279),
+        MappingColumn::integer("bit_length", "Signal length in bits").range(1, 64),
+        MappingColumn::string("byte_order", "Signal byte order (intel or motorola)"),
+        MappingColumn::string("data_type", "Signal data type"),
+        MappingColumn::boolean("signed", "Signed signal").default_value("false"),
+        MappingColumn::boolean("extended", "29-bit ID below 0x800").default_value("false"),
+        MappingColumn::float("scale", "Raw value factor").default_value("1.0"),
+        MappingColumn::float("offset", "Raw value offset").default_value("0.0"),
+    ];
+}
+
+impl ProtocolPlugin for CanPlugin {
+    fn name(&self) -> &'static str {
+        "can"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "CAN Bus"
+    }
+
+    fn description(&self) -> &'static str {
+        "Controller Area Network (CAN) bus signals and SAE J1939 parameter groups"
+    }
+
+    fn parameters(&self) -> Vec<ParameterMetadata> {
+        can_parameters()
+    }
+
+    fn mapping_columns(&self) -> &'static [MappingColumn] {
+        Self::COLUMNS
+    }
+
+    /// A signal is addressed by `can_id` or a DBC `signal` (raw mode), `pgn`
+    /// (J1939 mode) or `node_id` (CANopen mode); CAN ID and PGN signals need
+    /// their layout
+    fn validate_mapping(
+        &self,
+        point_type: PointType,
+        point_id: u32,
+        mapping: &JsonValue,
+    ) -> Vec<String> {
+        let mut errors = validate_columns(self, point_type, point_id, mapping);
+        if mapping.as_object().is_none_or(|cells| cells.is_empty()) {
+            return errors;
+        }
+        let set = |key: &str| mapping.get(key).is_some_and(|v| !is_blank(v));
+        if set("can_id") || set("pgn") {
+            for key in ["start_bit", "bit_length"] {
+                // data_type implies the length of whole-byte signals
+                let implied = key == "bit_length" && set("data_type");
+                if !set(key) && !implied {
+                    errors.push(format!("Point {}: '{}' is required", point_id, key));
+                }
+            }
+        } else if set("node_id") {
+            if !set("index") && !set("nmt") {
+                errors.push(format!("Point {}: 'index' or 'nmt' is required", point_id));
+            }
+        } else if !set("signal") {
+            errors.push(format!(
+                "Point {}: 'can_id', 'signal', 'pgn' or 'node_id' is required",
+                point_id
+            ));
+        }
+        errors
+    }
+}
+
+fn builtin_plugins() -> Vec<Arc<dyn ProtocolPlugin>> {
+    vec![
+        Arc::new(ModbusTcpPlugin),
+        Arc::new(ModbusRtuPlugin),
+        Arc::new(VirtualPlugin),
+        Arc::new(GpioPlugin),
+        Arc::new(CanPlugin),
+        #[cfg(feature = "bacnet")]
+        Arc::new(crate::core::protocols::bacnet::BacnetPlugin),
+        #[cfg(feature = "dlt645")]
+        Arc::new(crate::core::protocols::dlt645::Dlt645Plugin),
+        #[cfg(feature = "dlms")]
+        Arc::new(crate::core::protocols::dlms::DlmsPlugin),
+        #[cfg(feature = "dnp3")]
+        Arc::new(crate::core::protocols::dnp3::Dnp3Plugin),
+        #[cfg(feature = "enip")]
+        Arc::new(crate::core::protocols::enip::EnipPlugin),
+        #[cfg(feature = "iec61850")]
+        Arc::new(crate::core::protocols::iec61850::MmsPlugin),
+        #[cfg(feature = "mqtt")]
+        Arc::new(crate::core::protocols::mqtt::MqttPlugin),
+        #[cfg(feature = "opcua")]
+        Arc::new(crate::core::protocols::opcua::OpcUaPlugin),
+        #[cfg(feature = "s7")]
+        Arc::new(crate::core::protocols::s7::S7Plugin),
+        #[cfg(feature = "snmp")]
+        Arc::new(crate::core::protocols::snmp::SnmpPlugin),
+    ]
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    protocol_plugin! {
+        struct TestPlugin {
+            name: "test_bus",
+            aliases: &["testbus"],
+            display_name: "Test Bus",
+            description: "Plugin used by the registry tests",
+            point_types: &[PointType::Telemetry],
+            parameters: vec![ParameterMetadata::required(
+                "host",
+                "Host",
+                "Device address",
+                ParameterType::String,
+            )],
+            mapping_columns: &[
+                MappingColumn::integer("address", "Register").required().range(0, 100),
+                MappingColumn::string("mode", "Access mode").choices(&["ro", "rw"]),
+                MappingColumn::float("gain", "Factor").default_value("1.0"),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_builtin_lookup_by_alias() {
+        let plugins = ProtocolPlugins::global();
+        assert_eq!(plugins.get("Modbus-TCP").unwrap().name(), "modbus_tcp");
+        assert_eq!(plugins.get("di_do").unwrap().name(), "gpio");
+        assert!(plugins.get("unknown").is_none());
+    }
+
+    #[test]
+    fn test_registered_plugin_validates_and_normalizes() {
+        register_protocol_plugins!(TestPlugin);
+        let plugin = ProtocolPlugins::global().get("testbus").unwrap();
+        assert_eq!(plugin.mapping_template(), "point_id,address,mode,gain");
+        assert_eq!(plugin.parameters().len(), 1);
+
+        let mapping = json!({"address": "7", "mode": "ro", "gain": ""});
+        assert!(plugin
+            .validate_mapping(PointType::Telemetry, 1, &mapping)
+            .is_empty());
+        assert_eq!(
+            plugin.normalize_mapping(&mapping),
+            json!({"address": 7, "mode": "ro", "gain": 1.0})
+        );
+
+        let errors = plugin.validate_mapping(
+            PointType::Control,
+            2,
+            &json!({"address": "x", "mode": "wo", "extra": 1}),
+        );
+        assert_eq!(errors.len(), 4, "{:?}", errors);
+        assert!(plugin
+            .validate_mapping(PointType::Control, 3, &json!({}))
+            .is_empty());
+    }
+}
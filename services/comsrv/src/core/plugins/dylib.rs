@@ -0,0 +1,815 @@
+//! Protocol plugins from shared libraries
+//!
+//! Proprietary customer protocols can ship as a `.so` dropped next to comsrv
+//! instead of a rebuilt binary. At startup every library in
+//! `COMSRV_PLUGIN_DIR` is opened and its `voltage_protocol_plugin` symbol is
+//! called. It returns a [`PluginVTable`]: the protocol's self-description plus
+//! a C ABI shim around the channel lifecycle (create, connect, poll, write,
+//! destroy). Channels whose protocol matches a loaded library run through
+//! [`DylibRuntime`], the same way igw protocols do.
+//!
+//! Only plain C types cross the boundary: configuration and points as JSON
+//! strings, values as (point type, point ID, value) records. Plugins can be
+//! written in C, C++ or Rust (`extern "C"`) without depending on comsrv's
+//! Rust ABI; `include/voltage_protocol_plugin.h` declares the interface.
+//!
+//! Contract for plugin authors:
+//! - Strings returned by the plugin stay valid as long as the library (vtable
+//!   strings) or the instance (`last_error`) lives
+//! - Calls on one instance are never concurrent; they may block (comsrv runs
+//!   them on the blocking thread pool)
+//! - Return codes: 0 is success, anything else failure (details via
+//!   `last_error`)
+//! - Every function entry is required; a table with a null entry is rejected
+//!   at load
+//!
+//! Libraries are never unloaded: a loaded plugin lives for the process.
+
+use std::collections::HashMap;
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+
+use async_trait::async_trait;
+use igw::core::traits::{DataEventReceiver, Diagnostics, PointFailure, PollResult};
+use igw::gateway::ChannelRuntime;
+use igw::{ConnectionState, DataBatch, DataPoint, GatewayError, Quality};
+use serde::Deserialize;
+use tracing::{debug, info, warn};
+
+use super::{ColumnKind, MappingColumn, ProtocolPlugin, ProtocolPlugins, ALL_POINT_TYPES};
+use crate::core::config::RuntimeChannelConfig;
+use crate::error::{ComSrvError, Result};
+use crate::utils::normalize_protocol_name;
+use voltage_model::PointType;
+
+/// ABI version the loader understands (`PluginVTable::abi_version`)
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Exported entry point: `const PluginVTable *voltage_protocol_plugin(void)`
+pub const PLUGIN_ENTRY_SYMBOL: &[u8] = b"voltage_protocol_plugin\0";
+
+/// Directory scanned for plugin libraries at startup
+pub const PLUGIN_DIR_ENV: &str = "COMSRV_PLUGIN_DIR";
+
+/// Value exchanged with a plugin
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PluginValue {
+    /// b'T', b'S', b'C' or b'A'
+    pub point_type: u8,
+    pub point_id: u32,
+    pub value: f64,
+    /// 0 = good, anything else = bad
+    pub quality: u8,
+}
+
+/// Function table exported by a plugin library
+#[repr(C)]
+pub struct PluginVTable {
+    /// Must equal [`PLUGIN_ABI_VERSION`]
+    pub abi_version: u32,
+    /// Protocol name stored in `channels.protocol`
+    pub name: *const c_char,
+    pub display_name: *const c_char,
+    pub description: *const c_char,
+    /// JSON array of mapping columns (as served by `GET /api/protocols/plugins`), may be null
+    pub mapping_columns: *const c_char,
+    /// JSON array of point types, e.g. `["T","S"]`; null means all four
+    pub point_types: *const c_char,
+    /// Create a channel instance (null on failure)
+    ///
+    /// `config` is the channel parameter object, `points` an array of
+    /// `{"point_type","point_id","signal_name","mapping"}` objects.
+    pub create: Option<CreateFn>,
+    pub connect: Option<InstanceFn>,
+    pub disconnect: Option<InstanceFn>,
+    /// Fill up to `capacity` values, return the count (negative on failure)
+    pub poll: Option<PollFn>,
+    /// Write one control (b'C') or adjustment (b'A') value
+    pub write: Option<WriteFn>,
+    /// Last error of the instance (may be null)
+    pub last_error: Option<LastErrorFn>,
+    pub destroy: Option<DestroyFn>,
+}
+
+// SAFETY: the table is immutable static data of the library
+unsafe impl Sync for PluginVTable {}
+
+// C signatures of the vtable entries
+pub type CreateFn = unsafe extern "C" fn(
+    channel_id: u32,
+    config: *const c_char,
+    points: *const c_char,
+) -> *mut c_void;
+pub type InstanceFn = unsafe extern "C" fn(instance: *mut c_void) -> i32;
+pub type PollFn =
+    unsafe extern "C" fn(instance: *mut c_void, out: *mut PluginValue, capacity: usize) -> isize;
+pub type WriteFn =
+    unsafe extern "C" fn(instance: *mut c_void, point_type: u8, point_id: u32, value: f64) -> i32;
+pub type LastErrorFn = unsafe extern "C" fn(instance: *mut c_void) -> *const c_char;
+pub type DestroyFn = unsafe extern "C" fn(instance: *mut c_void);
+
+/// Function entries of a validated [`PluginVTable`]
+#[derive(Clone, Copy)]
+struct PluginFns {
+    create: CreateFn,
+    connect: InstanceFn,
+    disconnect: InstanceFn,
+    poll: PollFn,
+    write: WriteFn,
+    last_error: LastErrorFn,
+    destroy: DestroyFn,
+}
+
+impl PluginFns {
+    /// Every entry of the table, or the name of the first null one
+    fn from_vtable(vtable: &PluginVTable) -> std::result::Result<Self, &'static str> {
+        Ok(Self {
+            create: vtable.create.ok_or("create")?,
+            connect: vtable.connect.ok_or("connect")?,
+            disconnect: vtable.disconnect.ok_or("disconnect")?,
+            poll: vtable.poll.ok_or("poll")?,
+            write: vtable.write.ok_or("write")?,
+            last_error: vtable.last_error.ok_or("last_error")?,
+            destroy: vtable.destroy.ok_or("destroy")?,
+        })
+    }
+}
+
+/// Mapping column as declared in the plugin's JSON
+#[derive(Debug, Deserialize)]
+struct ColumnSpec {
+    name: String,
+    kind: ColumnKind,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    required: bool,
+    #[serde(default)]
+    default: Option<String>,
+    #[serde(default)]
+    min: Option<i64>,
+    #[serde(default)]
+    max: Option<i64>,
+    #[serde(default)]
+    choices: Vec<String>,
+}
+
+fn leak(s: String) -> &'static str {
+    Box::leak(s.into_boxed_str())
+}
+
+impl ColumnSpec {
+    /// Plugins live for the process, so their descriptions are leaked once at load
+    fn into_static(self) -> MappingColumn {
+        let choices: Vec<&'static str> = self.choices.into_iter().map(leak).collect();
+        MappingColumn {
+            name: leak(self.name),
+            kind: self.kind,
+            description: leak(self.description),
+            required: self.required,
+            default: self.default.map(leak),
+            min: self.min,
+            max: self.max,
+            choices: Box::leak(choices.into_boxed_slice()),
+        }
+    }
+}
+
+/// Read a string owned by the plugin
+///
+/// # Safety
+/// `ptr` must be null or point to a NUL-terminated string.
+unsafe fn plugin_str(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    Some(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+}
+
+fn point_type_code(point_type: PointType) -> u8 {
+    point_type.as_str().as_bytes()[0]
+}
+
+fn point_type_from_code(code: u8) -> Option<PointType> {
+    match code {
+        b'T' => Some(PointType::Telemetry),
+        b'S' => Some(PointType::Signal),
+        b'C' => Some(PointType::Control),
+        b'A' => Some(PointType::Adjustment),
+        _ => None,
+    }
+}
+
+// ============================================================================
+// Loaded plugin
+// ============================================================================
+
+/// Protocol implemented by a shared library
+pub struct DylibProtocol {
+    /// Keeps the code behind `fns` mapped (None for in-process tables)
+    _library: Option<libloading::Library>,
+    fns: PluginFns,
+    path: PathBuf,
+    name: &'static str,
+    display_name: &'static str,
+    description: &'static str,
+    point_types: &'static [PointType],
+    mapping_columns: &'static [MappingColumn],
+}
+
+impl std::fmt::Debug for DylibProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DylibProtocol")
+            .field("name", &self.name)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl DylibProtocol {
+    /// Open a plugin library and read its description
+    pub fn load(path: &Path) -> Result<Self> {
+        // SAFETY: loading runs the library's initializers; plugins are trusted
+        // code installed by the operator.
+        let library = unsafe { libloading::Library::new(path) }
+            .map_err(|e| ComSrvError::ConfigError(format!("Plugin {}: {}", path.display(), e)))?;
+        let vtable = unsafe {
+            let entry: libloading::Symbol<unsafe extern "C" fn() -> *const PluginVTable> =
+                library.get(PLUGIN_ENTRY_SYMBOL).map_err(|e| {
+                    ComSrvError::ConfigError(format!("Plugin {}: {}", path.display(), e))
+                })?;
+            entry()
+        };
+        // SAFETY: the table is static data of the library, kept alive below
+        let vtable = unsafe { vtable.as_ref() }.ok_or_else(|| {
+            ComSrvError::ConfigError(format!("Plugin {}: null vtable", path.display()))
+        })?;
+        unsafe { Self::from_vtable(Some(library), vtable, path) }
+    }
+
+    /// Describe a plugin from its function table
+    ///
+    /// # Safety
+    /// The table and its strings must stay valid while `library` is loaded
+    /// (forever when `library` is None).
+    pub unsafe fn from_vtable(
+        library: Option<libloading::Library>,
+        vtable: &'static PluginVTable,
+        path: &Path,
+    ) -> Result<Self> {
+        let fail =
+            |msg: String| ComSrvError::ConfigError(format!("Plugin {}: {}", path.display(), msg));
+
+        if vtable.abi_version != PLUGIN_ABI_VERSION {
+            return Err(fail(format!(
+                "ABI version {} (expected {})",
+                vtable.abi_version, PLUGIN_ABI_VERSION
+            )));
+        }
+        let fns = PluginFns::from_vtable(vtable)
+            .map_err(|entry| fail(format!("vtable entry '{}' is null", entry)))?;
+        let name = plugin_str(vtable.name)
+            .filter(|n| !n.trim().is_empty())
+            .ok_or_else(|| fail("missing protocol name".to_string()))?;
+        let name = normalize_protocol_name(&name);
+        let display_name = plugin_str(vtable.display_name).unwrap_or_else(|| name.clone());
+        let description = plugin_str(vtable.description).unwrap_or_default();
+
+        let mapping_columns: &'static [MappingColumn] = match plugin_str(vtable.mapping_columns) {
+            Some(json) => {
+                let specs: Vec<ColumnSpec> = serde_json::from_str(&json)
+                    .map_err(|e| fail(format!("invalid mapping_columns: {}", e)))?;
+                let columns: Vec<MappingColumn> =
+                    specs.into_iter().map(ColumnSpec::into_static).collect();
+                Box::leak(columns.into_boxed_slice())
+            },
+            None => &[],
+        };
+        let point_types: &'static [PointType] = match plugin_str(vtable.point_types) {
+            Some(json) => {
+                let types: Vec<PointType> = serde_json::from_str(&json)
+                    .map_err(|e| fail(format!("invalid point_types: {}", e)))?;
+                Box::leak(types.into_boxed_slice())
+            },
+            None => ALL_POINT_TYPES,
+        };
+
+        Ok(Self {
+            _library: library,
+            fns,
+            path: path.to_path_buf(),
+            name: leak(name),
+            display_name: leak(display_name),
+            description: leak(description),
+            point_types,
+            mapping_columns,
+        })
+    }
+
+    /// Library the plugin was loaded from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Create the runtime of a channel using this protocol
+    pub fn create_runtime(
+        self: &Arc<Self>,
+        runtime_config: &RuntimeChannelConfig,
+    ) -> Result<Box<dyn ChannelRuntime>> {
+        let channel_id = runtime_config.id();
+        let config = serde_json::to_string(&runtime_config.base.parameters)
+            .map_err(|e| ComSrvError::ConfigError(e.to_string()))?;
+        let points = serde_json::to_string(&plugin_points(runtime_config))
+            .map_err(|e| ComSrvError::ConfigError(e.to_string()))?;
+        let (config, points) = match (CString::new(config), CString::new(points)) {
+            (Ok(config), Ok(points)) => (config, points),
+            _ => {
+                return Err(ComSrvError::ConfigError(format!(
+                    "Ch{} config contains NUL bytes",
+                    channel_id
+                )))
+            },
+        };
+
+        // SAFETY: both strings outlive the call; the plugin copies what it keeps
+        let instance = unsafe { (self.fns.create)(channel_id, config.as_ptr(), points.as_ptr()) };
+        if instance.is_null() {
+            return Err(ComSrvError::ConfigError(format!(
+                "Ch{}: plugin '{}' rejected the channel configuration",
+                channel_id, self.name
+            )));
+        }
+
+        Ok(Box::new(DylibRuntime {
+            id: channel_id,
+            name: runtime_config.name().to_string(),
+            protocol: Arc::clone(self),
+            instance: Instance(instance),
+            capacity: point_count(runtime_config).max(1),
+            diagnostics: Diagnostics::new(self.name),
+        }))
+    }
+}
+
+impl ProtocolPlugin for DylibProtocol {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn display_name(&self) -> &'static str {
+        self.display_name
+    }
+
+    fn description(&self) -> &'static str {
+        self.description
+    }
+
+    fn point_types(&self) -> &'static [PointType] {
+        self.point_types
+    }
+
+    fn mapping_columns(&self) -> &'static [MappingColumn] {
+        self.mapping_columns
+    }
+}
+
+fn point_count(config: &RuntimeChannelConfig) -> usize {
+    config.telemetry_points.len()
+        + config.signal_points.len()
+        + config.control_points.len()
+        + config.adjustment_points.len()
+}
+
+/// Points handed to `create`, with their parsed protocol mappings
+fn plugin_points(config: &RuntimeChannelConfig) -> Vec<serde_json::Value> {
+    config
+        .points()
+        .map(|(point_type, base)| {
+            let mapping = base
+                .protocol_mappings
+                .as_deref()
+                .and_then(|m| serde_json::from_str(m).ok())
+                .unwrap_or(serde_json::Value::Null);
+            let mut point = serde_json::Map::new();
+            point.insert("point_type".into(), point_type.as_str().into());
+            point.insert("point_id".into(), base.point_id.into());
+            point.insert("signal_name".into(), base.signal_name.clone().into());
+            point.insert("mapping".into(), mapping);
+            serde_json::Value::Object(point)
+        })
+        .collect()
+}
+
+// ============================================================================
+// Registry
+// ============================================================================
+
+fn loaded() -> &'static RwLock<HashMap<&'static str, Arc<DylibProtocol>>> {
+    static LOADED: OnceLock<RwLock<HashMap<&'static str, Arc<DylibProtocol>>>> = OnceLock::new();
+    LOADED.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Make a plugin available to channels and to [`ProtocolPlugins`]
+pub fn install(protocol: DylibProtocol) -> Arc<DylibProtocol> {
+    let protocol = Arc::new(protocol);
+    if let Ok(mut loaded) = loaded().write() {
+        loaded.insert(protocol.name, Arc::clone(&protocol));
+    }
+    let previous =
+        ProtocolPlugins::global().register(Arc::clone(&protocol) as Arc<dyn ProtocolPlugin>);
+    if previous.is_some() {
+        warn!(
+            "Plugin {} replaces the built-in '{}' protocol description",
+            protocol.path.display(),
+            protocol.name
+        );
+    }
+    protocol
+}
+
+/// Loaded plugin for a protocol name (any spelling)
+pub fn get(protocol: &str) -> Option<Arc<DylibProtocol>> {
+    let name = normalize_protocol_name(protocol);
+    loaded().read().ok()?.get(name.as_str()).cloned()
+}
+
+/// Names of the loaded plugins
+pub fn loaded_protocols() -> Vec<&'static str> {
+    let mut names: Vec<_> = loaded()
+        .read()
+        .map(|l| l.keys().copied().collect())
+        .unwrap_or_default();
+    names.sort_unstable();
+    names
+}
+
+/// Load every shared library in a directory
+///
+/// A library that fails to load is logged and skipped.
+pub fn load_dir(dir: &Path) -> Result<usize> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| ComSrvError::ConfigError(format!("Plugin dir {}: {}", dir.display(), e)))?;
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|ext| ext == std::env::consts::DLL_EXTENSION)
+        })
+        .collect();
+    paths.sort();
+
+    let mut count = 0;
+    for path in paths {
+        match DylibProtocol::load(&path) {
+            Ok(protocol) => {
+                let protocol = install(protocol);
+                info!(
+                    "Protocol plugin '{}' loaded from {}",
+                    protocol.name,
+                    path.display()
+                );
+                count += 1;
+            },
+            Err(e) => warn!("{}", e),
+        }
+    }
+    Ok(count)
+}
+
+/// Load plugins from `COMSRV_PLUGIN_DIR` (nothing when unset)
+pub fn load_from_env() -> usize {
+    let Ok(dir) = std::env::var(PLUGIN_DIR_ENV) else {
+        return 0;
+    };
+    match load_dir(Path::new(&dir)) {
+        Ok(count) => count,
+        Err(e) => {
+            warn!("{}", e);
+            0
+        },
+    }
+}
+
+// ============================================================================
+// Channel runtime
+// ============================================================================
+
+/// Plugin instance handle
+#[derive(Clone, Copy)]
+struct Instance(*mut c_void);
+
+// SAFETY: the ABI contract forbids concurrent calls on one instance, which
+// `&mut self` on the runtime guarantees; the pointer itself may move threads.
+unsafe impl Send for Instance {}
+unsafe impl Sync for Instance {}
+
+/// Channel runtime backed by a plugin instance
+pub struct DylibRuntime {
+    id: u32,
+    name: String,
+    protocol: Arc<DylibProtocol>,
+    instance: Instance,
+    /// Poll buffer size (configured points)
+    capacity: usize,
+    diagnostics: Diagnostics,
+}
+
+impl DylibRuntime {
+    /// Run a plugin call on the blocking pool
+    async fn call<T, F>(&self, f: F) -> igw::Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&PluginFns, *mut c_void) -> T + Send + 'static,
+    {
+        let protocol = Arc::clone(&self.protocol);
+        let instance = self.instance;
+        tokio::task::spawn_blocking(move || {
+            let instance = instance;
+            f(&protocol.fns, instance.0)
+        })
+        .await
+        .map_err(|e| GatewayError::Protocol(format!("{} plugin call: {}", self.protocol.name, e)))
+    }
+
+    /// Status code to result, fetching the plugin's error message
+    async fn check(&mut self, code: i32, action: &str) -> igw::Result<()> {
+        if code == 0 {
+            return Ok(());
+        }
+        let message = self
+            .call(|fns, instance| unsafe { plugin_str((fns.last_error)(instance)) })
+            .await?
+            .unwrap_or_else(|| format!("{} failed ({})", action, code));
+        self.diagnostics.error_count += 1;
+        self.diagnostics.last_error = Some(message.clone());
+        Err(GatewayError::Protocol(message))
+    }
+
+    async fn write(&mut self, point_type: PointType, values: &[(u32, f64)]) -> igw::Result<usize> {
+        let code = point_type_code(point_type);
+        let values: Vec<(u32, f64)> = values
+            .iter()
+            .map(|(internal_id, value)| (PointType::from_internal_id(*internal_id).1, *value))
+            .collect();
+        let results = self
+            .call(move |fns, instance| {
+                values
+                    .iter()
+                    .map(|(point_id, value)| unsafe {
+                        (fns.write)(instance, code, *point_id, *value)
+                    })
+                    .collect::<Vec<i32>>()
+            })
+            .await?;
+
+        let written = results.iter().filter(|r| **r == 0).count();
+        self.diagnostics.write_count += written as u64;
+        if let Some(failed) = results.iter().find(|r| **r != 0) {
+            if written == 0 {
+                self.check(*failed, "write").await?;
+            }
+        }
+        Ok(written)
+    }
+}
+
+impl Drop for DylibRuntime {
+    fn drop(&mut self) {
+        // SAFETY: the instance was created by this plugin and is not used again
+        unsafe { (self.protocol.fns.destroy)(self.instance.0) };
+        debug!("Ch{} plugin instance destroyed", self.id);
+    }
+}
+
+#[async_trait]
+impl ChannelRuntime for DylibRuntime {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn protocol(&self) -> &str {
+        self.protocol.name
+    }
+
+    fn is_event_driven(&self) -> bool {
+        false
+    }
+
+    async fn connect(&mut self) -> igw::Result<()> {
+        self.diagnostics.connection_state = ConnectionState::Connecting;
+        let code = self
+            .call(|fns, instance| unsafe { (fns.connect)(instance) })
+            .await?;
+        let result = self.check(code, "connect").await;
+        self.diagnostics.connection_state = if result.is_ok() {
+            ConnectionState::Connected
+        } else {
+            ConnectionState::Disconnected
+        };
+        result
+    }
+
+    async fn disconnect(&mut self) -> igw::Result<()> {
+        let code = self
+            .call(|fns, instance| unsafe { (fns.disconnect)(instance) })
+            .await?;
+        self.diagnostics.connection_state = ConnectionState::Disconnected;
+        self.check(code, "disconnect").await
+    }
+
+    async fn poll_once(&mut self) -> PollResult {
+        let capacity = self.capacity;
+        let polled = self
+            .call(move |fns, instance| {
+                let mut values = vec![PluginValue::default(); capacity];
+                let count = unsafe { (fns.poll)(instance, values.as_mut_ptr(), capacity) };
+                (count, values)
+            })
+            .await;
+
+        let (count, values) = match polled {
+            Ok(polled) => polled,
+            Err(e) => return PollResult::failed(vec![PointFailure::with_error(0, e.to_string())]),
+        };
+        if count < 0 {
+            let error = match self.check(count as i32, "poll").await {
+                Err(e) => e.to_string(),
+                Ok(()) => "poll failed".to_string(),
+            };
+            return PollResult::failed(vec![PointFailure::with_error(0, error)]);
+        }
+
+        let mut batch = DataBatch::with_capacity(count as usize);
+        let mut failures = Vec::new();
+        for value in values.iter().take((count as usize).min(capacity)) {
+            let Some(point_type) = point_type_from_code(value.point_type) else {
+                failures.push(PointFailure::new(value.point_id, "unknown point type"));
+                continue;
+            };
+            let point = DataPoint::new(point_type.to_internal_id(value.point_id), value.value);
+            batch.add(if value.quality == 0 {
+                point
+            } else {
+                point.with_quality(Quality::Bad)
+            });
+        }
+        self.diagnostics.read_count += 1;
+        PollResult::partial(batch, failures)
+    }
+
+    async fn write_control(&mut self, commands: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Control, commands).await
+    }
+
+    async fn write_adjustment(&mut self, adjustments: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Adjustment, adjustments).await
+    }
+
+    fn subscribe(&self) -> Option<DataEventReceiver> {
+        None
+    }
+
+    async fn start_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn stop_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn diagnostics(&self) -> igw::Result<Diagnostics> {
+        Ok(self.diagnostics.clone())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    static LAST_WRITE: AtomicU32 = AtomicU32::new(0);
+
+    unsafe extern "C" fn create(_: u32, _: *const c_char, points: *const c_char) -> *mut c_void {
+        let points: Vec<serde_json::Value> =
+            serde_json::from_str(CStr::from_ptr(points).to_str().unwrap()).unwrap();
+        Box::into_raw(Box::new(points.len())) as *mut c_void
+    }
+    unsafe extern "C" fn ok(_: *mut c_void) -> i32 {
+        0
+    }
+    unsafe extern "C" fn poll(_: *mut c_void, out: *mut PluginValue, capacity: usize) -> isize {
+        let out = std::slice::from_raw_parts_mut(out, capacity);
+        out[0] = PluginValue {
+            point_type: b'S',
+            point_id: 3,
+            value: 1.0,
+            quality: 0,
+        };
+        1
+    }
+    unsafe extern "C" fn write(_: *mut c_void, point_type: u8, point_id: u32, _: f64) -> i32 {
+        LAST_WRITE.store(point_id, Ordering::SeqCst);
+        if point_type == b'C' {
+            0
+        } else {
+            -1
+        }
+    }
+    unsafe extern "C" fn last_error(_: *mut c_void) -> *const c_char {
+        c"adjustments not supported".as_ptr()
+    }
+    unsafe extern "C" fn destroy(instance: *mut c_void) {
+        drop(Box::from_raw(instance as *mut usize));
+    }
+
+    static VTABLE: PluginVTable = PluginVTable {
+        abi_version: PLUGIN_ABI_VERSION,
+        name: c"Test-Dylib".as_ptr(),
+        display_name: c"Test Dylib".as_ptr(),
+        description: std::ptr::null(),
+        mapping_columns: c"[{\"name\":\"tag\",\"kind\":\"string\",\"required\":true}]".as_ptr(),
+        point_types: c"[\"S\",\"C\",\"A\"]".as_ptr(),
+        create: Some(create),
+        connect: Some(ok),
+        disconnect: Some(ok),
+        poll: Some(poll),
+        write: Some(write),
+        last_error: Some(last_error),
+        destroy: Some(destroy),
+    };
+
+    static NULL_POLL_VTABLE: PluginVTable = PluginVTable {
+        abi_version: PLUGIN_ABI_VERSION,
+        name: c"null-poll".as_ptr(),
+        display_name: std::ptr::null(),
+        description: std::ptr::null(),
+        mapping_columns: std::ptr::null(),
+        point_types: std::ptr::null(),
+        create: Some(create),
+        connect: Some(ok),
+        disconnect: Some(ok),
+        poll: None,
+        write: Some(write),
+        last_error: Some(last_error),
+        destroy: Some(destroy),
+    };
+
+    #[test]
+    fn test_rejects_null_vtable_entry() {
+        let err = unsafe { DylibProtocol::from_vtable(None, &NULL_POLL_VTABLE, Path::new("x.so")) }
+            .unwrap_err();
+        assert!(err.to_string().contains("'poll' is null"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_vtable_runtime_round_trip() {
+        let protocol =
+            unsafe { DylibProtocol::from_vtable(None, &VTABLE, Path::new("test.so")) }.unwrap();
+        assert_eq!(protocol.name(), "test_dylib");
+        assert_eq!(protocol.mapping_template(), "point_id,tag");
+        let protocol = install(protocol);
+        assert!(get("test-dylib").is_some());
+        assert!(ProtocolPlugins::global().get("test_dylib").is_some());
+
+        let mut config = RuntimeChannelConfig::from_base(
+            serde_json::from_value(serde_json::json!({
+                "id": 7, "name": "ext", "protocol": "test_dylib"
+            }))
+            .unwrap(),
+        );
+        config.signal_points.push(
+            serde_json::from_value(serde_json::json!({
+                "point_id": 3, "signal_name": "run", "protocol_mappings": "{\"tag\":\"R1\"}"
+            }))
+            .unwrap(),
+        );
+        let mut runtime = protocol.create_runtime(&config).unwrap();
+
+        runtime.connect().await.unwrap();
+        let result = runtime.poll_once().await;
+        let point = result.data.iter().next().unwrap();
+        assert_eq!(
+            PointType::from_internal_id(point.id),
+            (PointType::Signal, 3)
+        );
+
+        let control = PointType::Control.to_internal_id(5);
+        assert_eq!(runtime.write_control(&[(control, 1.0)]).await.unwrap(), 1);
+        assert_eq!(LAST_WRITE.load(Ordering::SeqCst), 5);
+
+        let adjustment = PointType::Adjustment.to_internal_id(6);
+        let err = runtime
+            .write_adjustment(&[(adjustment, 2.0)])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("adjustments not supported"));
+    }
+}
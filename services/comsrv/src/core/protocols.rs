@@ -0,0 +1,139 @@
+//! Protocol runtimes implemented in comsrv
+//!
+//! Protocols igw does not provide are implemented here as
+//! [`ChannelRuntime`](igw::gateway::ChannelRuntime)s, each behind its own
+//! feature, and wrapped by
+//! [`IgwChannelWrapper`](crate::core::channels::igw_bridge::IgwChannelWrapper)
+//! like the igw drivers. Tooling for protocols igw implements (SunSpec
+//! discovery over Modbus) lives here as well.
+//!
+//! Runtimes are built with `from_runtime_config`; points without a valid
+//! mapping are skipped with a warning. The helpers below are shared by the
+//! runtimes for mapping parsing and error bookkeeping.
+
+use std::fmt;
+
+use igw::core::traits::Diagnostics;
+use igw::{ConnectionState, GatewayError};
+use serde_json::Value as JsonValue;
+
+use crate::core::config::Point;
+
+#[cfg(feature = "bacnet")]
+pub mod bacnet; // BACnet/IP client
+#[cfg(all(feature = "can", target_os = "linux"))]
+pub mod can; // CAN bus over SocketCAN
+#[cfg(feature = "dlms")]
+pub mod dlms; // DLMS/COSEM (IEC 62056) client
+#[cfg(feature = "dlt645")]
+pub mod dlt645; // DL/T 645-2007 meter reading over serial
+#[cfg(feature = "dnp3")]
+pub mod dnp3; // DNP3 master over TCP
+#[cfg(feature = "enip")]
+pub mod enip; // EtherNet/IP (CIP) explicit messaging client
+#[cfg(feature = "iec61850")]
+pub mod iec61850; // IEC 61850 MMS client
+#[cfg(feature = "modbus")]
+pub mod modbus_blocks; // Coalescing of Modbus register reads into block requests
+#[cfg(feature = "modbus")]
+pub mod modbus_rtu_tcp; // Modbus RTU frames over a raw TCP stream
+#[cfg(feature = "modbus")]
+pub mod modbus_server; // Modbus TCP server (slave) mode
+#[cfg(feature = "mqtt")]
+pub mod mqtt; // MQTT subscriber
+#[cfg(feature = "opcua")]
+pub mod opcua; // OPC UA client
+#[cfg(feature = "s7")]
+pub mod s7; // Siemens S7comm client
+#[cfg(feature = "snmp")]
+pub mod snmp; // SNMP v2c/v3 manager
+#[cfg(feature = "modbus")]
+pub mod sunspec; // SunSpec model discovery over Modbus
+
+/// Wire format error of a protocol codec
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodecError(pub String);
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+/// Shorthand for codec error construction
+pub fn error(message: impl Into<String>) -> CodecError {
+    CodecError(message.into())
+}
+
+/// Protocol mapping of a point, `Null` when absent or not valid JSON
+pub fn point_mapping(point: &Point) -> JsonValue {
+    point
+        .protocol_mappings
+        .as_deref()
+        .and_then(|m| serde_json::from_str(m).ok())
+        .unwrap_or(JsonValue::Null)
+}
+
+/// Integer from a JSON number or numeric string (CSV imports keep strings)
+pub fn as_u64(value: &JsonValue) -> Option<u64> {
+    match value {
+        JsonValue::Number(n) => n.as_u64(),
+        JsonValue::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Flag of a mapping field (`true`, `"yes"`, `1`, ...)
+pub fn as_bool(value: &JsonValue) -> Option<bool> {
+    match value {
+        JsonValue::Bool(b) => Some(*b),
+        JsonValue::String(s) => match s.trim().to_lowercase().as_str() {
+            "true" | "1" | "yes" => Some(true),
+            "false" | "0" | "no" => Some(false),
+            _ => None,
+        },
+        JsonValue::Number(n) => n.as_u64().map(|n| n != 0),
+        _ => None,
+    }
+}
+
+/// Whether an error means the connection to the device is gone
+pub fn link_lost(error: &GatewayError) -> bool {
+    matches!(
+        error,
+        GatewayError::Connection(_)
+            | GatewayError::ConnectionTimeout(_)
+            | GatewayError::NotConnected
+    )
+}
+
+/// Count an error in the diagnostics of a runtime
+///
+/// Returns whether the link was lost, in which case the connection state is
+/// already `Disconnected` and the runtime drops its connection so the next
+/// `connect()` starts over.
+pub fn record_failure(diagnostics: &mut Diagnostics, error: &GatewayError) -> bool {
+    diagnostics.error_count += 1;
+    diagnostics.last_error = Some(error.to_string());
+    let lost = link_lost(error);
+    if lost {
+        diagnostics.connection_state = ConnectionState::Disconnected;
+    }
+    lost
+}
+
+/// Value of a point in a poll result (runtime tests)
+#[cfg(test)]
+pub fn polled_value(
+    result: &igw::core::traits::PollResult,
+    point_type: voltage_model::PointType,
+    id: u32,
+) -> Option<f64> {
+    result
+        .data
+        .iter()
+        .find(|p| p.id == point_type.to_internal_id(id))
+        .and_then(|p| p.value.as_f64())
+}
@@ -0,0 +1,1550 @@
+//! BACnet/IP client for building loads
+//!
+//! Points name an object by `object_type` and `instance` and one of its
+//! properties, `present_value` unless `property` says otherwise. Telemetry
+//! and signal points are read each poll with ReadPropertyMultiple, or one
+//! ReadProperty per property on devices without it. Points with `cov` set
+//! are instead updated from change of value notifications: the client
+//! subscribes to their objects with SubscribeCOV after connecting and
+//! renews the subscriptions at half their lifetime. Signal points may take
+//! one `bit` of a bit string such as `status_flags`.
+//!
+//! Control and adjustment points WriteProperty their property at
+//! `priority` (or the channel's `write_priority`), encoded as Real for
+//! analog, Enumerated for binary and Unsigned for multi-state objects unless
+//! `data_type` says otherwise. A command equal to the point's
+//! `relinquish_value` writes NULL instead, releasing the priority slot so a
+//! curtailed load returns to its normal schedule.
+//!
+//! Devices on another BACnet network (e.g. MS/TP controllers) are reached
+//! through the router at `host` with their `network` number and `mac`.
+//!
+//! ```yaml
+//! protocol: bacnet
+//! parameters:
+//!   host: 192.168.10.20
+//!   port: 47808
+//!   device_instance: 1001
+//!   cov_lifetime_s: 300
+//!   write_priority: 8
+//! ```
+
+pub mod codec;
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use igw::core::traits::{DataEventReceiver, Diagnostics, PointFailure, PollResult};
+use igw::gateway::ChannelRuntime;
+use igw::{ConnectionState, DataBatch, DataPoint, GatewayError};
+use serde_json::{json, Value as JsonValue};
+use tokio::net::UdpSocket;
+use tokio::time::{timeout_at, Instant};
+use tracing::{debug, info, warn};
+
+use self::codec::{property, service, Apdu, ObjectId, PropertyRef, Route, Value, ValueType};
+use crate::core::config::{Point, RuntimeChannelConfig};
+use crate::core::plugins::{MappingColumn, ParameterMetadata, ParameterType};
+use crate::core::protocols::{as_u64, record_failure};
+use crate::error::{ComSrvError, Result};
+use voltage_model::PointType;
+
+/// Protocol name stored in `channels.protocol`
+pub const PROTOCOL: &str = "bacnet";
+
+const DEFAULT_PORT: u16 = 0xBAC0;
+const DEFAULT_RESPONSE_TIMEOUT_MS: u64 = 3000;
+const DEFAULT_RETRIES: u64 = 1;
+const DEFAULT_MAX_PROPERTIES: u64 = 16;
+const DEFAULT_COV_LIFETIME_S: u64 = 300;
+/// Largest datagram on a BACnet/IP network
+const MAX_DATAGRAM: usize = 1500;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Channel parameters of a BACnet channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct BacnetConfig {
+    /// Device, or router of the device's network
+    pub host: String,
+    pub port: u16,
+    /// Local UDP port; 0 for an ephemeral port
+    pub local_port: u16,
+    /// Network and MAC address behind the router at `host`
+    pub route: Option<Route>,
+    /// Expected device instance; None accepts whichever device answers
+    pub device_instance: Option<u32>,
+    pub response_timeout: Duration,
+    /// Retransmissions of an unanswered request
+    pub retries: u32,
+    /// Properties per ReadPropertyMultiple request
+    pub max_properties: usize,
+    /// Lifetime of COV subscriptions in seconds
+    pub cov_lifetime: u32,
+    /// Ask for confirmed COV notifications
+    pub cov_confirmed: bool,
+    /// Priority of writes whose point sets none
+    pub write_priority: Option<u8>,
+}
+
+impl BacnetConfig {
+    pub fn from_parameters(parameters: &HashMap<String, JsonValue>) -> Result<Self> {
+        let config_error = |message: String| ComSrvError::ConfigError(message);
+        let optional_number = |key: &str| -> Result<Option<u64>> {
+            match parameters.get(key) {
+                None | Some(JsonValue::Null) => Ok(None),
+                Some(JsonValue::String(s)) if s.trim().is_empty() => Ok(None),
+                Some(value) => as_u64(value)
+                    .map(Some)
+                    .ok_or_else(|| config_error(format!("'{}' must be a number", key))),
+            }
+        };
+        let number = |key: &str, default: u64| -> Result<u64> {
+            Ok(optional_number(key)?.unwrap_or(default))
+        };
+        let text = |key: &str| {
+            parameters
+                .get(key)
+                .and_then(|v| match v {
+                    JsonValue::String(s) => Some(s.trim().to_string()),
+                    JsonValue::Number(n) => Some(n.to_string()),
+                    _ => None,
+                })
+                .filter(|v| !v.is_empty())
+        };
+        let in_range = |key: &str, value: u64, min: u64, max: u64| {
+            if (min..=max).contains(&value) {
+                Ok(value)
+            } else {
+                Err(config_error(format!("'{}' must be {}-{}", key, min, max)))
+            }
+        };
+
+        let host = text("host").ok_or_else(|| config_error("BACnet requires 'host'".into()))?;
+        let port = in_range("port", number("port", u64::from(DEFAULT_PORT))?, 1, 65535)? as u16;
+        let local_port = in_range("local_port", number("local_port", 0)?, 0, 65535)? as u16;
+
+        let route = match (optional_number("network")?, text("mac")) {
+            (None, None) => None,
+            (Some(network), Some(mac)) => Some(Route {
+                network: in_range("network", network, 1, 65534)? as u16,
+                address: parse_mac(&mac).ok_or_else(|| {
+                    config_error("'mac' must be an MS/TP station (0-254) or 1-7 hex octets".into())
+                })?,
+            }),
+            _ => {
+                return Err(config_error(
+                    "'network' and 'mac' address a routed device together".into(),
+                ))
+            },
+        };
+        let device_instance = optional_number("device_instance")?
+            .map(|v| {
+                in_range(
+                    "device_instance",
+                    v,
+                    0,
+                    u64::from(codec::WILDCARD_INSTANCE) - 1,
+                )
+            })
+            .transpose()?
+            .map(|v| v as u32);
+        let write_priority = optional_number("write_priority")?
+            .map(|v| in_range("write_priority", v, 1, 16))
+            .transpose()?
+            .map(|v| v as u8);
+        let cov_confirmed = match parameters.get("cov_confirmed") {
+            None | Some(JsonValue::Null) => false,
+            Some(value) => as_bool(value)
+                .ok_or_else(|| config_error("'cov_confirmed' must be true or false".into()))?,
+        };
+
+        Ok(Self {
+            host,
+            port,
+            local_port,
+            route,
+            device_instance,
+            response_timeout: Duration::from_millis(
+                number("response_timeout_ms", DEFAULT_RESPONSE_TIMEOUT_MS)?.max(1),
+            ),
+            retries: in_range("retries", number("retries", DEFAULT_RETRIES)?, 0, 10)? as u32,
+            max_properties: in_range(
+                "max_properties_per_request",
+                number("max_properties_per_request", DEFAULT_MAX_PROPERTIES)?,
+                1,
+                256,
+            )? as usize,
+            cov_lifetime: in_range(
+                "cov_lifetime_s",
+                number("cov_lifetime_s", DEFAULT_COV_LIFETIME_S)?,
+                60,
+                86_400,
+            )? as u32,
+            cov_confirmed,
+            write_priority,
+        })
+    }
+}
+
+/// Integer from a JSON number or numeric string (CSV imports keep strings)
+fn as_bool(value: &JsonValue) -> Option<bool> {
+    match value {
+        JsonValue::Bool(b) => Some(*b),
+        JsonValue::Number(n) => match n.as_u64() {
+            Some(0) => Some(false),
+            Some(1) => Some(true),
+            _ => None,
+        },
+        JsonValue::String(s) => match s.trim().to_ascii_lowercase().as_str() {
+            "true" | "1" | "yes" => Some(true),
+            "false" | "0" | "no" | "" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// MS/TP station number (`12`) or hex octets (`C0:A8:01:0A:BA:C0`)
+fn parse_mac(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim();
+    if text.len() <= 3 && text.chars().all(|c| c.is_ascii_digit()) {
+        return text
+            .parse::<u8>()
+            .ok()
+            .filter(|m| *m < 255)
+            .map(|m| vec![m]);
+    }
+    let digits: String = text
+        .trim_start_matches("0x")
+        .trim_start_matches("0X")
+        .chars()
+        .filter(|c| !matches!(c, ':' | '-' | ' '))
+        .collect();
+    if digits.is_empty() || !digits.len().is_multiple_of(2) || digits.len() > 14 {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(digits.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// ============================================================================
+// Point mapping
+// ============================================================================
+
+/// Value a point reads from a property
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Reading {
+    internal_id: u32,
+    /// Bit of the value (signal points)
+    bit: Option<u8>,
+}
+
+impl Reading {
+    fn decode(&self, values: &[Value]) -> std::result::Result<f64, String> {
+        let value = values.first().ok_or("empty property value")?;
+        match self.bit {
+            Some(bit) => value
+                .as_bits()
+                .map(|word| f64::from(((word >> bit) & 1) as u8))
+                .ok_or_else(|| format!("{:?} is not a bit field", value)),
+            None => value
+                .as_f64()
+                .ok_or_else(|| format!("{:?} is not numeric", value)),
+        }
+    }
+}
+
+/// Property a control or adjustment point writes
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Command {
+    target: PropertyRef,
+    value_type: ValueType,
+    priority: Option<u8>,
+    /// Command value that writes NULL to release the priority slot
+    relinquish: Option<f64>,
+}
+
+enum Mapped {
+    Read {
+        target: PropertyRef,
+        reading: Reading,
+        cov: bool,
+    },
+    Write(Command),
+}
+
+fn parse_point(point_type: PointType, point: &Point) -> std::result::Result<Mapped, String> {
+    let mapping: JsonValue = point
+        .protocol_mappings
+        .as_deref()
+        .and_then(|m| serde_json::from_str(m).ok())
+        .ok_or("missing BACnet mapping")?;
+    let text = |key: &str| {
+        mapping
+            .get(key)
+            .and_then(|v| match v {
+                JsonValue::String(s) => Some(s.trim().to_string()),
+                JsonValue::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .filter(|v| !v.is_empty())
+    };
+    let integer = |key: &str| -> std::result::Result<Option<u64>, String> {
+        text(key)
+            .map(|v| {
+                v.parse::<u64>()
+                    .map_err(|_| format!("'{}' must be a non-negative integer", key))
+            })
+            .transpose()
+    };
+
+    let object_type = ObjectId::parse_type(&text("object_type").ok_or("missing 'object_type'")?)
+        .map_err(|e| e.to_string())?;
+    let instance = integer("instance")?.ok_or("missing 'instance'")?;
+    let object = ObjectId::new(
+        object_type,
+        u32::try_from(instance).map_err(|_| "'instance' is out of range")?,
+    )
+    .map_err(|e| e.to_string())?;
+    let target = PropertyRef {
+        object,
+        property: text("property")
+            .map(|p| codec::parse_property(&p))
+            .transpose()
+            .map_err(|e| e.to_string())?
+            .unwrap_or(property::PRESENT_VALUE),
+        index: integer("array_index")?
+            .map(|i| u32::try_from(i).map_err(|_| "'array_index' is out of range"))
+            .transpose()?,
+    };
+
+    match point_type {
+        PointType::Telemetry | PointType::Signal => {
+            let bit = integer("bit")?
+                .map(|b| {
+                    u8::try_from(b)
+                        .ok()
+                        .filter(|b| *b < 64)
+                        .ok_or("'bit' must be 0-63")
+                })
+                .transpose()?;
+            let cov = match mapping.get("cov") {
+                None | Some(JsonValue::Null) => false,
+                Some(value) => as_bool(value).ok_or("'cov' must be true or false")?,
+            };
+            Ok(Mapped::Read {
+                target,
+                reading: Reading {
+                    internal_id: point_type.to_internal_id(point.point_id),
+                    bit: bit.filter(|_| point_type == PointType::Signal),
+                },
+                cov,
+            })
+        },
+        PointType::Control | PointType::Adjustment => {
+            let priority = integer("priority")?
+                .map(|p| {
+                    u8::try_from(p)
+                        .ok()
+                        .filter(|p| (1..=16).contains(p))
+                        .ok_or("'priority' must be 1-16")
+                })
+                .transpose()?;
+            let value_type = match text("data_type") {
+                Some(t) => ValueType::parse(&t).map_err(|e| e.to_string())?,
+                None => object.default_value_type(),
+            };
+            let relinquish = text("relinquish_value")
+                .map(|v| {
+                    v.parse::<f64>()
+                        .map_err(|_| "'relinquish_value' must be a number")
+                })
+                .transpose()?;
+            Ok(Mapped::Write(Command {
+                target,
+                value_type,
+                priority,
+                relinquish,
+            }))
+        },
+    }
+}
+
+// ============================================================================
+// Runtime
+// ============================================================================
+
+/// Answer of the device to a confirmed request
+enum Reply {
+    /// Service data of a Complex-ACK, empty for a Simple-ACK
+    Ack(Vec<u8>),
+    Error(u32, u32),
+    Reject(u8),
+    Abort(u8),
+}
+
+impl Reply {
+    fn describe(&self) -> String {
+        match self {
+            Reply::Ack(_) => "acknowledged".to_string(),
+            Reply::Error(class, code) => codec::error_text(*class, *code),
+            Reply::Reject(reason) => codec::reject_text(*reason),
+            Reply::Abort(reason) => codec::abort_text(*reason),
+        }
+    }
+
+    /// Service data of an acknowledgement, the failure otherwise
+    fn into_ack(self, what: &str) -> igw::Result<Vec<u8>> {
+        match self {
+            Reply::Ack(data) => Ok(data),
+            other => Err(GatewayError::Protocol(format!(
+                "{}: {}",
+                what,
+                other.describe()
+            ))),
+        }
+    }
+}
+
+/// Points of an object updated by COV notifications
+#[derive(Debug, Default)]
+struct Subscription {
+    readings: BTreeMap<PropertyRef, Vec<Reading>>,
+    /// Next renewal; None until subscribed
+    renew_at: Option<Instant>,
+}
+
+/// Outcome of reading one property
+type PropertyOutcome = (PropertyRef, std::result::Result<Vec<Value>, String>);
+
+/// BACnet/IP client of one device
+pub struct BacnetRuntime {
+    id: u32,
+    name: String,
+    config: BacnetConfig,
+    /// Properties read each poll -> points
+    reads: BTreeMap<PropertyRef, Vec<Reading>>,
+    /// Objects updated by COV notifications
+    cov: BTreeMap<ObjectId, Subscription>,
+    controls: HashMap<u32, Command>,
+    adjustments: HashMap<u32, Command>,
+    socket: Option<UdpSocket>,
+    invoke_id: u8,
+    /// Device answers ReadPropertyMultiple
+    rpm: bool,
+    /// Device object that answered the last connect
+    device: Option<ObjectId>,
+    /// Values from COV notifications since the last poll
+    notified: BTreeMap<u32, f64>,
+    notify_failures: Vec<PointFailure>,
+    notifications: u64,
+    diagnostics: Diagnostics,
+}
+
+impl BacnetRuntime {
+    /// Build the runtime of a `bacnet` channel
+    pub fn from_runtime_config(runtime_config: &RuntimeChannelConfig) -> Result<Self> {
+        let channel_id = runtime_config.id();
+        let config = BacnetConfig::from_parameters(&runtime_config.base.parameters)
+            .map_err(|e| ComSrvError::ConfigError(format!("Ch{}: {}", channel_id, e)))?;
+
+        let mut reads: BTreeMap<_, Vec<Reading>> = BTreeMap::new();
+        let mut cov: BTreeMap<ObjectId, Subscription> = BTreeMap::new();
+        let mut controls = HashMap::new();
+        let mut adjustments = HashMap::new();
+        for (point_type, point) in runtime_config.points() {
+            match parse_point(point_type, point) {
+                Ok(Mapped::Read {
+                    target,
+                    reading,
+                    cov: true,
+                }) => {
+                    cov.entry(target.object)
+                        .or_default()
+                        .readings
+                        .entry(target)
+                        .or_default()
+                        .push(reading);
+                },
+                Ok(Mapped::Read {
+                    target, reading, ..
+                }) => {
+                    reads.entry(target).or_default().push(reading);
+                },
+                Ok(Mapped::Write(command)) => {
+                    let commands = match point_type {
+                        PointType::Control => &mut controls,
+                        _ => &mut adjustments,
+                    };
+                    commands.insert(point.point_id, command);
+                },
+                Err(e) => warn!(
+                    "Ch{} {}{} skipped: {}",
+                    channel_id,
+                    point_type.as_str(),
+                    point.point_id,
+                    e
+                ),
+            }
+        }
+        debug!(
+            "Ch{} BACnet: {} polled properties, {} COV objects, {} controls, {} adjustments",
+            channel_id,
+            reads.len(),
+            cov.len(),
+            controls.len(),
+            adjustments.len()
+        );
+
+        Ok(Self {
+            id: channel_id,
+            name: runtime_config.name().to_string(),
+            config,
+            reads,
+            cov,
+            controls,
+            adjustments,
+            socket: None,
+            invoke_id: 0,
+            rpm: true,
+            device: None,
+            notified: BTreeMap::new(),
+            notify_failures: Vec::new(),
+            notifications: 0,
+            diagnostics: Diagnostics::new(PROTOCOL),
+        })
+    }
+
+    /// Record an error; connection failures drop the socket so the next
+    /// `connect()` identifies the device and subscribes again
+    fn fail(&mut self, error: GatewayError) -> GatewayError {
+        if record_failure(&mut self.diagnostics, &error) {
+            self.socket = None;
+        }
+        error
+    }
+
+    fn peer(&self) -> String {
+        format!("{}:{}", self.config.host, self.config.port)
+    }
+
+    /// Subscriber process identifier of this channel's COV subscriptions
+    fn process_id(&self) -> u32 {
+        self.id
+    }
+
+    async fn send(&mut self, apdu: &[u8], expecting_reply: bool) -> igw::Result<()> {
+        let frame = codec::encode_frame(self.config.route.as_ref(), expecting_reply, apdu);
+        let socket = self.socket.as_ref().ok_or(GatewayError::NotConnected)?;
+        socket
+            .send(&frame)
+            .await
+            .map_err(|e| GatewayError::Connection(format!("send: {}", e)))?;
+        Ok(())
+    }
+
+    /// Next datagram, or None when the deadline passes
+    async fn receive(&mut self, deadline: Instant) -> igw::Result<Option<Vec<u8>>> {
+        let socket = self.socket.as_ref().ok_or(GatewayError::NotConnected)?;
+        let mut buf = [0u8; MAX_DATAGRAM];
+        match timeout_at(deadline, socket.recv(&mut buf)).await {
+            Err(_) => Ok(None),
+            Ok(Ok(n)) => Ok(Some(buf[..n].to_vec())),
+            Ok(Err(e)) => Err(GatewayError::Connection(format!("receive: {}", e))),
+        }
+    }
+
+    /// Handle a datagram: COV notifications are stored (and acknowledged),
+    /// the reply to `awaiting` is returned, anything else is ignored
+    async fn dispatch(
+        &mut self,
+        datagram: &[u8],
+        awaiting: Option<u8>,
+    ) -> igw::Result<Option<Reply>> {
+        let apdu = match codec::decode_frame(datagram) {
+            Ok(Some(apdu)) => apdu,
+            Ok(None) => return Ok(None),
+            Err(e) => {
+                debug!("Ch{} discarded datagram: {}", self.id, e);
+                return Ok(None);
+            },
+        };
+        let apdu = match Apdu::parse(apdu) {
+            Ok(apdu) => apdu,
+            Err(e) => {
+                debug!("Ch{} discarded APDU: {}", self.id, e);
+                return Ok(None);
+            },
+        };
+        let reply = match apdu {
+            Apdu::UnconfirmedRequest {
+                service: service::UNCONFIRMED_COV_NOTIFICATION,
+                data,
+            } => {
+                self.on_notification(data);
+                None
+            },
+            Apdu::ConfirmedRequest {
+                invoke_id,
+                service: service::CONFIRMED_COV_NOTIFICATION,
+                data,
+            } => {
+                self.on_notification(data);
+                self.send(
+                    &codec::simple_ack(invoke_id, service::CONFIRMED_COV_NOTIFICATION),
+                    false,
+                )
+                .await?;
+                None
+            },
+            Apdu::SimpleAck { invoke_id, .. } if Some(invoke_id) == awaiting => {
+                Some(Reply::Ack(Vec::new()))
+            },
+            Apdu::ComplexAck {
+                invoke_id, data, ..
+            } if Some(invoke_id) == awaiting => Some(Reply::Ack(data.to_vec())),
+            Apdu::Error {
+                invoke_id,
+                class,
+                code,
+                ..
+            } if Some(invoke_id) == awaiting => Some(Reply::Error(class, code)),
+            Apdu::Reject { invoke_id, reason } if Some(invoke_id) == awaiting => {
+                Some(Reply::Reject(reason))
+            },
+            Apdu::Abort { invoke_id, reason } if Some(invoke_id) == awaiting => {
+                Some(Reply::Abort(reason))
+            },
+            other => {
+                debug!("Ch{} ignored {:?}", self.id, other);
+                None
+            },
+        };
+        Ok(reply)
+    }
+
+    fn on_notification(&mut self, data: &[u8]) {
+        let notification = match codec::parse_cov_notification(data) {
+            Ok(notification) => notification,
+            Err(e) => {
+                warn!("Ch{} invalid COV notification: {}", self.id, e);
+                return;
+            },
+        };
+        if notification.process_id != self.process_id() {
+            debug!(
+                "Ch{} COV notification of process {} ignored",
+                self.id, notification.process_id
+            );
+            return;
+        }
+        let Some(subscription) = self.cov.get(&notification.object) else {
+            return;
+        };
+        self.notifications += 1;
+        for (target, values) in &notification.values {
+            let Some(readings) = subscription.readings.get(target) else {
+                continue;
+            };
+            for reading in readings {
+                match reading.decode(values) {
+                    Ok(value) => {
+                        self.notified.insert(reading.internal_id, value);
+                    },
+                    Err(e) => self
+                        .notify_failures
+                        .push(PointFailure::with_error(reading.internal_id, e)),
+                }
+            }
+        }
+    }
+
+    /// Send a confirmed request and wait for its reply, retransmitting
+    /// after each response timeout
+    async fn request(&mut self, service: u8, params: &[u8]) -> igw::Result<Reply> {
+        for attempt in 0..=self.config.retries {
+            self.invoke_id = self.invoke_id.wrapping_add(1);
+            let invoke_id = self.invoke_id;
+            self.send(&codec::confirmed_request(invoke_id, service, params), true)
+                .await?;
+            let deadline = Instant::now() + self.config.response_timeout;
+            while let Some(datagram) = self.receive(deadline).await? {
+                if let Some(reply) = self.dispatch(&datagram, Some(invoke_id)).await? {
+                    return Ok(reply);
+                }
+            }
+            if attempt < self.config.retries {
+                debug!("Ch{} service {} unanswered, retrying", self.id, service);
+            }
+        }
+        Err(GatewayError::ReadTimeout)
+    }
+
+    /// Handle datagrams that arrived since the last request
+    async fn drain(&mut self) -> igw::Result<()> {
+        let mut buf = [0u8; MAX_DATAGRAM];
+        loop {
+            let socket = self.socket.as_ref().ok_or(GatewayError::NotConnected)?;
+            let datagram = match socket.try_recv(&mut buf) {
+                Ok(n) => buf[..n].to_vec(),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(GatewayError::Connection(format!("receive: {}", e))),
+            };
+            self.dispatch(&datagram, None).await?;
+        }
+    }
+
+    /// Read the device object identifier, checking the configured instance
+    async fn identify(&mut self) -> igw::Result<()> {
+        let target = PropertyRef {
+            object: ObjectId {
+                object_type: codec::DEVICE_TYPE,
+                instance: self
+                    .config
+                    .device_instance
+                    .unwrap_or(codec::WILDCARD_INSTANCE),
+            },
+            property: property::OBJECT_IDENTIFIER,
+            index: None,
+        };
+        let data = self
+            .request(service::READ_PROPERTY, &codec::read_property(&target))
+            .await?
+            .into_ack("device identification")?;
+        let (_, values) = codec::parse_read_property_ack(&data)
+            .map_err(|e| GatewayError::InvalidResponse(e.to_string()))?;
+        let Some(Value::ObjectId(device)) = values.first() else {
+            return Err(GatewayError::InvalidResponse(format!(
+                "device object identifier is {:?}",
+                values
+            )));
+        };
+        if let Some(expected) = self.config.device_instance {
+            if device.instance != expected {
+                return Err(GatewayError::Connection(format!(
+                    "{} is device {}, not {}",
+                    self.peer(),
+                    device.instance,
+                    expected
+                )));
+            }
+        }
+        self.device = Some(*device);
+        Ok(())
+    }
+
+    /// Subscribe to objects whose subscription is missing or due for renewal
+    ///
+    /// Objects the device refuses to report are polled instead.
+    async fn renew_subscriptions(&mut self) -> igw::Result<()> {
+        let now = Instant::now();
+        let due: Vec<ObjectId> = self
+            .cov
+            .iter()
+            .filter(|(_, s)| s.renew_at.is_none_or(|at| at <= now))
+            .map(|(object, _)| *object)
+            .collect();
+        let lifetime = self.config.cov_lifetime;
+        for object in due {
+            let params = codec::subscribe_cov(
+                self.process_id(),
+                object,
+                Some((self.config.cov_confirmed, lifetime)),
+            );
+            match self.request(service::SUBSCRIBE_COV, &params).await? {
+                Reply::Ack(_) => {
+                    if let Some(subscription) = self.cov.get_mut(&object) {
+                        subscription.renew_at =
+                            Some(Instant::now() + Duration::from_secs(u64::from(lifetime / 2)));
+                    }
+                },
+                refused => {
+                    warn!(
+                        "Ch{} COV subscription of {} refused ({}), polling it instead",
+                        self.id,
+                        object,
+                        refused.describe()
+                    );
+                    if let Some(subscription) = self.cov.remove(&object) {
+                        for (target, readings) in subscription.readings {
+                            self.reads.entry(target).or_default().extend(readings);
+                        }
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Read `targets`, each property with its own outcome
+    async fn read_chunk(&mut self, targets: &[PropertyRef]) -> igw::Result<Vec<PropertyOutcome>> {
+        if self.rpm && targets.len() > 1 {
+            let params = codec::read_property_multiple(targets);
+            match self
+                .request(service::READ_PROPERTY_MULTIPLE, &params)
+                .await?
+            {
+                Reply::Ack(data) => {
+                    let results = codec::parse_read_property_multiple_ack(&data)
+                        .map_err(|e| GatewayError::InvalidResponse(e.to_string()))?;
+                    return Ok(results
+                        .into_iter()
+                        .map(|r| {
+                            let outcome = r
+                                .result
+                                .map_err(|(class, code)| codec::error_text(class, code));
+                            (r.target, outcome)
+                        })
+                        .collect());
+                },
+                Reply::Reject(codec::REJECT_UNRECOGNIZED_SERVICE) => {
+                    info!(
+                        "Ch{} device has no ReadPropertyMultiple, reading properties one by one",
+                        self.id
+                    );
+                    self.rpm = false;
+                },
+                // Response too long, or one unknown object failing the request
+                other => debug!(
+                    "Ch{} ReadPropertyMultiple {}, reading properties one by one",
+                    self.id,
+                    other.describe()
+                ),
+            }
+        }
+
+        let mut outcomes = Vec::with_capacity(targets.len());
+        for target in targets {
+            let outcome = match self
+                .request(service::READ_PROPERTY, &codec::read_property(target))
+                .await?
+            {
+                Reply::Ack(data) => codec::parse_read_property_ack(&data)
+                    .map(|(_, values)| values)
+                    .map_err(|e| e.to_string()),
+                other => Err(other.describe()),
+            };
+            outcomes.push((*target, outcome));
+        }
+        Ok(outcomes)
+    }
+
+    async fn read_polled(&mut self, batch: &mut DataBatch, failures: &mut Vec<PointFailure>) {
+        let targets: Vec<PropertyRef> = self.reads.keys().copied().collect();
+        for chunk in targets.chunks(self.config.max_properties) {
+            match self.read_chunk(chunk).await {
+                Ok(outcomes) => {
+                    for (target, outcome) in outcomes {
+                        let Some(readings) = self.reads.get(&target) else {
+                            continue;
+                        };
+                        for reading in readings {
+                            let value = match &outcome {
+                                Ok(values) => reading.decode(values),
+                                Err(e) => Err(format!("{}: {}", target, e)),
+                            };
+                            match value {
+                                Ok(value) => batch.add(DataPoint::new(reading.internal_id, value)),
+                                Err(e) => {
+                                    failures.push(PointFailure::with_error(reading.internal_id, e))
+                                },
+                            }
+                        }
+                    }
+                },
+                Err(e) => {
+                    let e = self.fail(e);
+                    for target in chunk {
+                        failures.extend(
+                            self.reads
+                                .get(target)
+                                .into_iter()
+                                .flatten()
+                                .map(|r| PointFailure::with_error(r.internal_id, e.to_string())),
+                        );
+                    }
+                    if self.socket.is_none() {
+                        break;
+                    }
+                },
+            }
+        }
+    }
+
+    async fn operate(&mut self, command: Command, value: f64) -> igw::Result<()> {
+        let encoded = match command.relinquish {
+            Some(relinquish) if relinquish == value => Value::Null,
+            _ => command
+                .value_type
+                .value(value)
+                .map_err(|e| GatewayError::Protocol(format!("{}: {}", command.target, e)))?,
+        };
+        let params = codec::write_property_request(
+            &command.target,
+            &encoded,
+            command.priority.or(self.config.write_priority),
+        );
+        self.request(service::WRITE_PROPERTY, &params)
+            .await?
+            .into_ack(&command.target.to_string())
+            .map(|_| ())
+    }
+
+    async fn write(&mut self, point_type: PointType, values: &[(u32, f64)]) -> igw::Result<usize> {
+        let mut written = 0;
+        let mut last_error = None;
+        for &(internal_id, value) in values {
+            let point_id = PointType::from_internal_id(internal_id).1;
+            let commands = match point_type {
+                PointType::Control => &self.controls,
+                _ => &self.adjustments,
+            };
+            let Some(command) = commands.get(&point_id).copied() else {
+                last_error = Some(GatewayError::PointNotFound(format!(
+                    "{}{}",
+                    point_type.as_str(),
+                    point_id
+                )));
+                continue;
+            };
+            match self.operate(command, value).await {
+                Ok(()) => written += 1,
+                Err(e) => {
+                    let e = self.fail(e);
+                    if self.socket.is_none() {
+                        return Err(e);
+                    }
+                    last_error = Some(e);
+                },
+            }
+        }
+        self.diagnostics.write_count += written as u64;
+        match last_error {
+            Some(e) if written == 0 => Err(e),
+            _ => Ok(written),
+        }
+    }
+
+    async fn after_connect(&mut self) -> igw::Result<()> {
+        self.rpm = true;
+        self.identify().await?;
+        for subscription in self.cov.values_mut() {
+            subscription.renew_at = None;
+        }
+        self.renew_subscriptions().await
+    }
+}
+
+#[async_trait]
+impl ChannelRuntime for BacnetRuntime {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn protocol(&self) -> &str {
+        PROTOCOL
+    }
+
+    /// COV notifications are drained by `poll_once`
+    fn is_event_driven(&self) -> bool {
+        false
+    }
+
+    async fn connect(&mut self) -> igw::Result<()> {
+        self.diagnostics.connection_state = ConnectionState::Connecting;
+        let address = self.peer();
+        let socket = match UdpSocket::bind(("0.0.0.0", self.config.local_port)).await {
+            Ok(socket) => socket,
+            Err(e) => {
+                return Err(self.fail(GatewayError::Connection(format!(
+                    "bind UDP port {}: {}",
+                    self.config.local_port, e
+                ))))
+            },
+        };
+        if let Err(e) = socket
+            .connect((self.config.host.as_str(), self.config.port))
+            .await
+        {
+            return Err(self.fail(GatewayError::Connection(format!("{}: {}", address, e))));
+        }
+        self.socket = Some(socket);
+        self.notified.clear();
+        self.notify_failures.clear();
+
+        if let Err(e) = self.after_connect().await {
+            self.socket = None;
+            let e = match e {
+                GatewayError::Connection(_) => e,
+                other => GatewayError::Connection(format!("{}: {}", address, other)),
+            };
+            return Err(self.fail(e));
+        }
+        self.diagnostics.connection_state = ConnectionState::Connected;
+        info!(
+            "Ch{} BACnet connected to device {} at {}, {} COV subscriptions",
+            self.id,
+            self.device.map(|d| d.instance).unwrap_or_default(),
+            address,
+            self.cov.len()
+        );
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> igw::Result<()> {
+        // Best effort: cancel subscriptions without waiting for the device
+        let subscribed: Vec<ObjectId> = self
+            .cov
+            .iter()
+            .filter(|(_, s)| s.renew_at.is_some())
+            .map(|(object, _)| *object)
+            .collect();
+        if self.socket.is_some() {
+            for object in subscribed {
+                self.invoke_id = self.invoke_id.wrapping_add(1);
+                let cancel = codec::confirmed_request(
+                    self.invoke_id,
+                    service::SUBSCRIBE_COV,
+                    &codec::subscribe_cov(self.process_id(), object, None),
+                );
+                let _ = self.send(&cancel, true).await;
+            }
+        }
+        for subscription in self.cov.values_mut() {
+            subscription.renew_at = None;
+        }
+        self.socket = None;
+        self.diagnostics.connection_state = ConnectionState::Disconnected;
+        Ok(())
+    }
+
+    async fn poll_once(&mut self) -> PollResult {
+        if self.socket.is_none() {
+            return PollResult::failed(vec![PointFailure::new(0, "not connected")]);
+        }
+
+        let mut batch = DataBatch::default();
+        let mut failures = Vec::new();
+        let result = async {
+            self.renew_subscriptions().await?;
+            self.drain().await
+        }
+        .await;
+        if let Err(e) = result {
+            let e = self.fail(e);
+            failures.push(PointFailure::with_error(0, e.to_string()));
+        }
+        if self.socket.is_some() {
+            self.read_polled(&mut batch, &mut failures).await;
+        }
+
+        // Notifications received before a failure are still stored
+        for (internal_id, value) in std::mem::take(&mut self.notified) {
+            batch.add(DataPoint::new(internal_id, value));
+        }
+        failures.append(&mut self.notify_failures);
+        self.diagnostics.read_count += 1;
+        PollResult::partial(batch, failures)
+    }
+
+    async fn write_control(&mut self, commands: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Control, commands).await
+    }
+
+    async fn write_adjustment(&mut self, adjustments: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Adjustment, adjustments).await
+    }
+
+    fn subscribe(&self) -> Option<DataEventReceiver> {
+        None
+    }
+
+    async fn start_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn stop_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn diagnostics(&self) -> igw::Result<Diagnostics> {
+        let mut diagnostics = self.diagnostics.clone();
+        diagnostics.extra = json!({
+            "peer": self.peer(),
+            "device_instance": self.device.map(|d| d.instance),
+            "read_property_multiple": self.rpm,
+            "polled_properties": self.reads.len(),
+            "cov_objects": self.cov.len(),
+            "cov_subscribed": self.cov.values().filter(|s| s.renew_at.is_some()).count(),
+            "cov_notifications": self.notifications,
+        });
+        Ok(diagnostics)
+    }
+}
+
+// ============================================================================
+// Plugin metadata
+// ============================================================================
+
+fn parameters() -> Vec<ParameterMetadata> {
+    vec![
+        ParameterMetadata::required(
+            "host",
+            "Host",
+            "Device IP address, or the router of its network",
+            ParameterType::String,
+        ),
+        ParameterMetadata::optional(
+            "port",
+            "Port",
+            "BACnet/IP UDP port",
+            ParameterType::Integer,
+            json!(DEFAULT_PORT),
+        ),
+        ParameterMetadata::optional(
+            "device_instance",
+            "Device Instance",
+            "Expected device instance; empty accepts whichever device answers",
+            ParameterType::Integer,
+            json!(null),
+        ),
+        ParameterMetadata::optional(
+            "network",
+            "Network",
+            "Network number of a device behind a BACnet router",
+            ParameterType::Integer,
+            json!(null),
+        ),
+        ParameterMetadata::optional(
+            "mac",
+            "MAC Address",
+            "Address on that network: MS/TP station or hex octets",
+            ParameterType::String,
+            json!(""),
+        ),
+        ParameterMetadata::optional(
+            "local_port",
+            "Local Port",
+            "Local UDP port, 0 for any",
+            ParameterType::Integer,
+            json!(0),
+        ),
+        ParameterMetadata::optional(
+            "response_timeout_ms",
+            "Response Timeout (ms)",
+            "Time to wait for a device response",
+            ParameterType::Integer,
+            json!(DEFAULT_RESPONSE_TIMEOUT_MS),
+        ),
+        ParameterMetadata::optional(
+            "retries",
+            "Retries",
+            "Retransmissions of an unanswered request",
+            ParameterType::Integer,
+            json!(DEFAULT_RETRIES),
+        ),
+        ParameterMetadata::optional(
+            "max_properties_per_request",
+            "Properties per Request",
+            "Properties read by one ReadPropertyMultiple",
+            ParameterType::Integer,
+            json!(DEFAULT_MAX_PROPERTIES),
+        ),
+        ParameterMetadata::optional(
+            "cov_lifetime_s",
+            "COV Lifetime (s)",
+            "Lifetime of COV subscriptions, renewed at half",
+            ParameterType::Integer,
+            json!(DEFAULT_COV_LIFETIME_S),
+        ),
+        ParameterMetadata::optional(
+            "cov_confirmed",
+            "Confirmed COV",
+            "Ask for confirmed COV notifications",
+            ParameterType::Boolean,
+            json!(false),
+        ),
+        ParameterMetadata::optional(
+            "write_priority",
+            "Write Priority",
+            "Priority (1-16) of writes whose point sets none; empty uses 16",
+            ParameterType::Integer,
+            json!(null),
+        ),
+    ]
+}
+
+crate::protocol_plugin! {
+    /// BACnet/IP client (in-tree runtime)
+    pub struct BacnetPlugin {
+        name: PROTOCOL,
+        aliases: &["bacnet_ip", "bacnetip", "bip"],
+        display_name: "BACnet/IP",
+        description: "Building automation devices: ReadPropertyMultiple, COV and WriteProperty",
+        parameters: parameters(),
+        mapping_columns: &[
+            MappingColumn::string(
+                "object_type",
+                "Object type, e.g. analog-value, binary-output or AV",
+            )
+            .required(),
+            MappingColumn::integer("instance", "Object instance").required().range(0, 4_194_302),
+            MappingColumn::string("property", "Property, e.g. status_flags")
+                .default_value("present_value"),
+            MappingColumn::integer("array_index", "Element of an array property")
+                .range(0, 4_294_967_295),
+            MappingColumn::boolean("cov", "Update from COV notifications instead of polling"),
+            MappingColumn::integer("bit", "Bit of the value (signal points)").range(0, 63),
+            MappingColumn::integer("priority", "Write priority").range(1, 16),
+            MappingColumn::string("data_type", "Type of written values, defaults by object type")
+                .choices(&["boolean", "unsigned", "signed", "real", "double", "enumerated"]),
+            MappingColumn::float(
+                "relinquish_value",
+                "Command value that writes NULL, releasing the priority",
+            ),
+        ],
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use crate::core::protocols::polled_value;
+    use tokio::sync::mpsc;
+
+    const DEVICE: u32 = 1001;
+
+    fn point<T: serde::de::DeserializeOwned>(point_id: u32, mapping: JsonValue) -> T {
+        serde_json::from_value(json!({
+            "point_id": point_id,
+            "signal_name": format!("p{}", point_id),
+            "protocol_mappings": mapping.to_string(),
+        }))
+        .unwrap()
+    }
+
+    /// Present values and status flags of the fake device's objects
+    fn value_of(target: &PropertyRef) -> Option<Value> {
+        let object = (target.object.object_type, target.object.instance);
+        match (object, target.property) {
+            ((codec::DEVICE_TYPE, _), property::OBJECT_IDENTIFIER) => Some(Value::ObjectId(
+                ObjectId::new(codec::DEVICE_TYPE, DEVICE).unwrap(),
+            )),
+            ((2, 1), property::PRESENT_VALUE) => Some(Value::Real(21.5)),
+            ((2, 1), property::STATUS_FLAGS) => {
+                Some(Value::BitString(vec![false, true, false, false]))
+            },
+            ((0, 2), property::PRESENT_VALUE) => Some(Value::Real(18.25)),
+            ((5, 3), property::PRESENT_VALUE) => Some(Value::Enumerated(1)),
+            _ => None,
+        }
+    }
+
+    fn property_value(out: &mut Vec<u8>, number: u8, value: &Value) {
+        codec::write_opening(out, number);
+        value.encode(out);
+        codec::write_closing(out, number);
+    }
+
+    fn cov_notification(process_id: u32, object: ObjectId) -> Vec<u8> {
+        let mut data = vec![0x10, service::UNCONFIRMED_COV_NOTIFICATION];
+        codec::write_context_unsigned(&mut data, 0, u64::from(process_id));
+        codec::write_context_object_id(
+            &mut data,
+            1,
+            ObjectId::new(codec::DEVICE_TYPE, DEVICE).unwrap(),
+        );
+        codec::write_context_object_id(&mut data, 2, object);
+        codec::write_context_unsigned(&mut data, 3, 300);
+        codec::write_opening(&mut data, 4);
+        for property in [property::PRESENT_VALUE, property::STATUS_FLAGS] {
+            let target = PropertyRef {
+                object,
+                property,
+                index: None,
+            };
+            codec::write_context_unsigned(&mut data, 0, u64::from(property));
+            let value = value_of(&target).unwrap_or(Value::BitString(vec![false; 4]));
+            property_value(&mut data, 2, &value);
+        }
+        codec::write_closing(&mut data, 4);
+        data
+    }
+
+    /// Answer of a device that implements ReadPropertyMultiple or not
+    fn answer(
+        apdu: &[u8],
+        rpm: bool,
+        writes: &mpsc::UnboundedSender<(PropertyRef, Value, u64)>,
+    ) -> Vec<Vec<u8>> {
+        let Ok(Apdu::ConfirmedRequest {
+            invoke_id,
+            service,
+            data,
+        }) = Apdu::parse(apdu)
+        else {
+            return Vec::new();
+        };
+        let mut reader = codec::Reader::new(data);
+        let complex_ack = |body: Vec<u8>| [vec![0x30, invoke_id, service], body].concat();
+        let unknown_object = vec![0x50, invoke_id, service, 0x91, 0x01, 0x91, 0x1F];
+        match service {
+            service::READ_PROPERTY => {
+                let object = reader.context_object_id(0).unwrap();
+                let target = PropertyRef {
+                    object,
+                    property: reader.context_unsigned(1).unwrap() as u32,
+                    index: None,
+                };
+                match value_of(&target) {
+                    Some(value) => {
+                        let mut body = Vec::new();
+                        codec::write_context_object_id(&mut body, 0, object);
+                        codec::write_context_unsigned(&mut body, 1, u64::from(target.property));
+                        property_value(&mut body, 3, &value);
+                        vec![complex_ack(body)]
+                    },
+                    None => vec![unknown_object],
+                }
+            },
+            service::READ_PROPERTY_MULTIPLE if !rpm => {
+                vec![vec![0x60, invoke_id, codec::REJECT_UNRECOGNIZED_SERVICE]]
+            },
+            service::READ_PROPERTY_MULTIPLE => {
+                let mut body = Vec::new();
+                while !reader.is_empty() {
+                    let object = reader.context_object_id(0).unwrap();
+                    reader.opening(1).unwrap();
+                    codec::write_context_object_id(&mut body, 0, object);
+                    codec::write_opening(&mut body, 1);
+                    while !reader.at_closing(1) {
+                        let target = PropertyRef {
+                            object,
+                            property: reader.context_unsigned(0).unwrap() as u32,
+                            index: None,
+                        };
+                        codec::write_context_unsigned(&mut body, 2, u64::from(target.property));
+                        match value_of(&target) {
+                            Some(value) => property_value(&mut body, 4, &value),
+                            None => body.extend([0x5E, 0x91, 0x01, 0x91, 0x1F, 0x5F]),
+                        }
+                    }
+                    reader.closing(1).unwrap();
+                    codec::write_closing(&mut body, 1);
+                }
+                vec![complex_ack(body)]
+            },
+            service::SUBSCRIBE_COV => {
+                let process_id = reader.context_unsigned(0).unwrap() as u32;
+                let object = reader.context_object_id(1).unwrap();
+                if value_of(&PropertyRef {
+                    object,
+                    property: property::PRESENT_VALUE,
+                    index: None,
+                })
+                .is_none()
+                {
+                    return vec![unknown_object];
+                }
+                vec![
+                    codec::simple_ack(invoke_id, service),
+                    cov_notification(process_id, object),
+                ]
+            },
+            service::WRITE_PROPERTY => {
+                let object = reader.context_object_id(0).unwrap();
+                let property = reader.context_unsigned(1).unwrap() as u32;
+                reader.opening(3).unwrap();
+                let value = reader.values(3).unwrap().remove(0);
+                let priority = reader.optional_context_unsigned(4).unwrap().unwrap_or(16);
+                let target = PropertyRef {
+                    object,
+                    property,
+                    index: None,
+                };
+                writes.send((target, value, priority)).unwrap();
+                vec![codec::simple_ack(invoke_id, service)]
+            },
+            _ => vec![vec![0x60, invoke_id, codec::REJECT_UNRECOGNIZED_SERVICE]],
+        }
+    }
+
+    /// Fake device on a loopback UDP port; returns the port and its writes
+    async fn device(rpm: bool) -> (u16, mpsc::UnboundedReceiver<(PropertyRef, Value, u64)>) {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        let (writes, received) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut buf = [0u8; MAX_DATAGRAM];
+            while let Ok((n, from)) = socket.recv_from(&mut buf).await {
+                let Ok(Some(apdu)) = codec::decode_frame(&buf[..n]) else {
+                    continue;
+                };
+                for reply in answer(apdu, rpm, &writes) {
+                    let frame = codec::encode_frame(None, false, &reply);
+                    let _ = socket.send_to(&frame, from).await;
+                }
+            }
+        });
+        (port, received)
+    }
+
+    fn channel(port: u16) -> RuntimeChannelConfig {
+        let mut config = RuntimeChannelConfig::from_base(
+            serde_json::from_value(json!({
+                "id": 41,
+                "name": "ahu",
+                "protocol": PROTOCOL,
+                "parameters": {
+                    "host": "127.0.0.1",
+                    "port": port,
+                    "response_timeout_ms": 500,
+                    "retries": 0,
+                    "write_priority": "12",
+                },
+            }))
+            .unwrap(),
+        );
+        config.telemetry_points = vec![
+            point(1, json!({"object_type": "analog-value", "instance": 1})),
+            point(
+                2,
+                json!({"object_type": "AI", "instance": "2", "cov": "true"}),
+            ),
+            // Unknown object
+            point(3, json!({"object_type": "analog_value", "instance": 99})),
+            point(4, json!({"instance": 4})),
+        ];
+        config.signal_points = vec![
+            point(1, json!({"object_type": "binary-value", "instance": 3})),
+            point(
+                2,
+                json!({"object_type": "av", "instance": 1, "property": "status_flags", "bit": 1}),
+            ),
+        ];
+        config.control_points = vec![point(
+            1,
+            json!({"object_type": "binary-output", "instance": 5, "priority": 8}),
+        )];
+        config.adjustment_points = vec![point(
+            1,
+            json!({"object_type": "analog-value", "instance": 10, "relinquish_value": -1}),
+        )];
+        config
+    }
+
+    #[tokio::test]
+    async fn test_poll_cov_and_write() {
+        let (port, mut writes) = device(true).await;
+        let mut runtime = BacnetRuntime::from_runtime_config(&channel(port)).unwrap();
+        assert_eq!(runtime.reads.len(), 4);
+        assert_eq!(runtime.cov.len(), 1);
+        runtime.connect().await.unwrap();
+        assert_eq!(runtime.device.map(|d| d.instance), Some(DEVICE));
+
+        let result = runtime.poll_once().await;
+        assert_eq!(polled_value(&result, PointType::Telemetry, 1), Some(21.5));
+        assert_eq!(polled_value(&result, PointType::Telemetry, 2), Some(18.25));
+        assert_eq!(polled_value(&result, PointType::Signal, 1), Some(1.0));
+        assert_eq!(polled_value(&result, PointType::Signal, 2), Some(1.0));
+        assert_eq!(result.failures.len(), 1);
+        assert!(runtime.rpm);
+        assert_eq!(runtime.notifications, 1);
+
+        // COV values are only reported again after the next notification
+        let result = runtime.poll_once().await;
+        assert_eq!(polled_value(&result, PointType::Telemetry, 2), None);
+
+        let control = PointType::Control.to_internal_id(1);
+        assert_eq!(runtime.write_control(&[(control, 1.0)]).await.unwrap(), 1);
+        let (target, written, priority) = writes.recv().await.unwrap();
+        assert_eq!(target.object, ObjectId::new(4, 5).unwrap());
+        assert_eq!((written, priority), (Value::Enumerated(1), 8));
+
+        // Curtail, then release the priority slot
+        let adjustment = PointType::Adjustment.to_internal_id(1);
+        runtime
+            .write_adjustment(&[(adjustment, 12.5), (adjustment, -1.0)])
+            .await
+            .unwrap();
+        assert_eq!(writes.recv().await.unwrap().1, Value::Real(12.5));
+        assert_eq!(writes.recv().await.unwrap().1, Value::Null);
+        runtime.disconnect().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_property_fallback() {
+        let (port, _writes) = device(false).await;
+        let mut config = channel(port);
+        // Device refuses the subscription: the point is polled
+        config.telemetry_points = vec![
+            point(1, json!({"object_type": "av", "instance": 1})),
+            point(2, json!({"object_type": "av", "instance": 7, "cov": true})),
+        ];
+        let mut runtime = BacnetRuntime::from_runtime_config(&config).unwrap();
+        runtime.connect().await.unwrap();
+        assert!(runtime.cov.is_empty());
+        assert_eq!(runtime.reads.len(), 4);
+
+        let result = runtime.poll_once().await;
+        assert!(!runtime.rpm);
+        assert_eq!(polled_value(&result, PointType::Telemetry, 1), Some(21.5));
+        assert_eq!(polled_value(&result, PointType::Signal, 2), Some(1.0));
+        assert_eq!(result.failures.len(), 1);
+    }
+
+    #[test]
+    fn test_config_parameters() {
+        let parameters = |pairs: &[(&str, JsonValue)]| -> HashMap<String, JsonValue> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect()
+        };
+        let config = BacnetConfig::from_parameters(&parameters(&[
+            ("host", json!("10.0.0.20")),
+            ("network", json!(5)),
+            ("mac", json!("12")),
+            ("cov_confirmed", json!("yes")),
+        ]))
+        .unwrap();
+        assert_eq!(config.port, DEFAULT_PORT);
+        assert_eq!(
+            config.route,
+            Some(Route {
+                network: 5,
+                address: vec![12],
+            })
+        );
+        assert!(config.cov_confirmed);
+        assert_eq!(config.write_priority, None);
+
+        assert_eq!(
+            parse_mac("C0:A8:01:0A:BA:C0"),
+            Some(vec![0xC0, 0xA8, 0x01, 0x0A, 0xBA, 0xC0])
+        );
+        assert_eq!(parse_mac("255"), None);
+        // Network without MAC, priority out of range
+        assert!(BacnetConfig::from_parameters(&parameters(&[
+            ("host", json!("10.0.0.20")),
+            ("network", json!(5)),
+        ]))
+        .is_err());
+        assert!(BacnetConfig::from_parameters(&parameters(&[
+            ("host", json!("10.0.0.20")),
+            ("write_priority", json!(17)),
+        ]))
+        .is_err());
+    }
+}
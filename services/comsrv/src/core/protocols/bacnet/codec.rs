@@ -0,0 +1,1338 @@
+//! BACnet/IP wire format (ASHRAE 135 clauses 6, 20, 21 and Annex J)
+//!
+//! BVLC and NPDU headers for unicast to a device, optionally behind a
+//! router, unsegmented confirmed request and acknowledgement APDUs, and the
+//! tagged encoding of the services the client uses: ReadProperty,
+//! ReadPropertyMultiple, WriteProperty, SubscribeCOV and the COV
+//! notifications a subscription leads to.
+
+use std::fmt;
+
+use crate::core::protocols::{error, CodecError};
+
+type Result<T> = std::result::Result<T, CodecError>;
+
+/// BVLC type of BACnet/IP
+const BVLC_TYPE: u8 = 0x81;
+const BVLC_FORWARDED_NPDU: u8 = 0x04;
+const BVLC_ORIGINAL_UNICAST: u8 = 0x0A;
+const BVLC_ORIGINAL_BROADCAST: u8 = 0x0B;
+const NPDU_VERSION: u8 = 0x01;
+/// Max APDU 1476 octets (B/IP), no segmented responses
+const MAX_APDU_ACCEPTED: u8 = 0x05;
+/// Largest object instance; as a device instance it addresses whichever
+/// device receives the request
+pub const WILDCARD_INSTANCE: u32 = 0x3F_FFFF;
+
+/// Service choices
+pub mod service {
+    // Confirmed
+    pub const CONFIRMED_COV_NOTIFICATION: u8 = 1;
+    pub const SUBSCRIBE_COV: u8 = 5;
+    pub const READ_PROPERTY: u8 = 12;
+    pub const READ_PROPERTY_MULTIPLE: u8 = 14;
+    pub const WRITE_PROPERTY: u8 = 15;
+    // Unconfirmed
+    pub const UNCONFIRMED_COV_NOTIFICATION: u8 = 2;
+}
+
+/// Property identifiers
+pub mod property {
+    pub const EVENT_STATE: u32 = 36;
+    pub const OBJECT_IDENTIFIER: u32 = 75;
+    pub const OUT_OF_SERVICE: u32 = 81;
+    pub const PRESENT_VALUE: u32 = 85;
+    pub const PRIORITY_ARRAY: u32 = 87;
+    pub const RELIABILITY: u32 = 103;
+    pub const RELINQUISH_DEFAULT: u32 = 104;
+    pub const STATUS_FLAGS: u32 = 111;
+    pub const UNITS: u32 = 117;
+}
+
+/// Reject reason of a service the device does not implement
+pub const REJECT_UNRECOGNIZED_SERVICE: u8 = 9;
+
+pub const DEVICE_TYPE: u16 = 8;
+
+/// Object type names, short names and numbers
+const OBJECT_TYPES: &[(&str, &str, u16)] = &[
+    ("analog-input", "ai", 0),
+    ("analog-output", "ao", 1),
+    ("analog-value", "av", 2),
+    ("binary-input", "bi", 3),
+    ("binary-output", "bo", 4),
+    ("binary-value", "bv", 5),
+    ("device", "dev", DEVICE_TYPE),
+    ("loop", "lp", 12),
+    ("multi-state-input", "msi", 13),
+    ("multi-state-output", "mso", 14),
+    ("multi-state-value", "msv", 19),
+    ("accumulator", "acc", 23),
+];
+
+const PROPERTIES: &[(&str, u32)] = &[
+    ("present-value", property::PRESENT_VALUE),
+    ("status-flags", property::STATUS_FLAGS),
+    ("priority-array", property::PRIORITY_ARRAY),
+    ("relinquish-default", property::RELINQUISH_DEFAULT),
+    ("out-of-service", property::OUT_OF_SERVICE),
+    ("event-state", property::EVENT_STATE),
+    ("reliability", property::RELIABILITY),
+    ("units", property::UNITS),
+    ("object-identifier", property::OBJECT_IDENTIFIER),
+];
+
+/// Lowercase with `-` separators, so `Analog_Input` matches `analog-input`
+fn normalize(text: &str) -> String {
+    text.trim().to_ascii_lowercase().replace(['_', ' '], "-")
+}
+
+/// Property identifier from its name (`present_value`) or number
+pub fn parse_property(text: &str) -> Result<u32> {
+    let name = normalize(text);
+    if let Ok(number) = name.parse::<u32>() {
+        return (number <= 0x3F_FFFF)
+            .then_some(number)
+            .ok_or_else(|| error(format!("property {} is out of range", number)));
+    }
+    PROPERTIES
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map(|(_, number)| *number)
+        .ok_or_else(|| error(format!("unknown property '{}'", text.trim())))
+}
+
+fn property_name(number: u32) -> Option<&'static str> {
+    PROPERTIES
+        .iter()
+        .find(|(_, known)| *known == number)
+        .map(|(name, _)| *name)
+}
+
+// ============================================================================
+// Object and property references
+// ============================================================================
+
+/// Object identifier: 10-bit type and 22-bit instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ObjectId {
+    pub object_type: u16,
+    pub instance: u32,
+}
+
+impl ObjectId {
+    pub fn new(object_type: u16, instance: u32) -> Result<Self> {
+        if object_type > 0x3FF {
+            return Err(error(format!(
+                "object type {} is out of range",
+                object_type
+            )));
+        }
+        if instance > WILDCARD_INSTANCE {
+            return Err(error(format!("instance {} is out of range", instance)));
+        }
+        Ok(Self {
+            object_type,
+            instance,
+        })
+    }
+
+    /// Object type from its name (`analog-value`), short name (`AV`) or number
+    pub fn parse_type(text: &str) -> Result<u16> {
+        let name = normalize(text);
+        if let Ok(number) = name.parse::<u16>() {
+            return (number <= 0x3FF)
+                .then_some(number)
+                .ok_or_else(|| error(format!("object type {} is out of range", number)));
+        }
+        OBJECT_TYPES
+            .iter()
+            .find(|(long, short, _)| *long == name || *short == name)
+            .map(|(_, _, number)| *number)
+            .ok_or_else(|| error(format!("unknown object type '{}'", text.trim())))
+    }
+
+    pub fn encode(&self) -> u32 {
+        (u32::from(self.object_type) << 22) | self.instance
+    }
+
+    pub fn decode(raw: u32) -> Self {
+        Self {
+            object_type: (raw >> 22) as u16,
+            instance: raw & WILDCARD_INSTANCE,
+        }
+    }
+
+    /// Type of the values written to the present value of this object
+    pub fn default_value_type(&self) -> ValueType {
+        match self.object_type {
+            3..=5 => ValueType::Enumerated,
+            13 | 14 | 19 => ValueType::Unsigned,
+            _ => ValueType::Real,
+        }
+    }
+}
+
+impl fmt::Display for ObjectId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match OBJECT_TYPES.iter().find(|(_, _, n)| *n == self.object_type) {
+            Some((name, _, _)) => write!(f, "{}:{}", name, self.instance),
+            None => write!(f, "{}:{}", self.object_type, self.instance),
+        }
+    }
+}
+
+/// Property of an object, optionally one element of an array property
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PropertyRef {
+    pub object: ObjectId,
+    pub property: u32,
+    pub index: Option<u32>,
+}
+
+impl fmt::Display for PropertyRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/", self.object)?;
+        match property_name(self.property) {
+            Some(name) => f.write_str(name)?,
+            None => write!(f, "{}", self.property)?,
+        }
+        match self.index {
+            Some(index) => write!(f, "[{}]", index),
+            None => Ok(()),
+        }
+    }
+}
+
+// ============================================================================
+// Values
+// ============================================================================
+
+/// Application-tagged primitive value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Boolean(bool),
+    Unsigned(u64),
+    Signed(i64),
+    Real(f32),
+    Double(f64),
+    OctetString(Vec<u8>),
+    CharacterString(String),
+    /// Bits in order, bit 0 first
+    BitString(Vec<bool>),
+    Enumerated(u32),
+    Date([u8; 4]),
+    Time([u8; 4]),
+    ObjectId(ObjectId),
+}
+
+impl Value {
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Boolean(b) => Some(f64::from(u8::from(*b))),
+            Value::Unsigned(v) => Some(*v as f64),
+            Value::Signed(v) => Some(*v as f64),
+            Value::Real(v) => Some(f64::from(*v)),
+            Value::Double(v) => Some(*v),
+            Value::Enumerated(v) => Some(f64::from(*v)),
+            _ => None,
+        }
+    }
+
+    /// Bits of a bit string (bit 0 lowest) or of an integer value
+    pub fn as_bits(&self) -> Option<u64> {
+        match self {
+            Value::BitString(bits) => Some(
+                bits.iter()
+                    .take(64)
+                    .enumerate()
+                    .filter(|(_, set)| **set)
+                    .fold(0, |word, (i, _)| word | 1 << i),
+            ),
+            Value::Boolean(b) => Some(u64::from(*b)),
+            Value::Unsigned(v) => Some(*v),
+            Value::Signed(v) => Some(*v as u64),
+            Value::Enumerated(v) => Some(u64::from(*v)),
+            _ => None,
+        }
+    }
+
+    /// Append the application-tagged encoding
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Value::Null => write_tag(out, 0, false, 0),
+            Value::Boolean(b) => write_tag(out, 1, false, usize::from(*b)),
+            Value::Unsigned(v) => {
+                let bytes = unsigned_bytes(*v);
+                write_tag(out, 2, false, bytes.len());
+                out.extend(bytes);
+            },
+            Value::Signed(v) => {
+                let bytes = signed_bytes(*v);
+                write_tag(out, 3, false, bytes.len());
+                out.extend(bytes);
+            },
+            Value::Real(v) => {
+                write_tag(out, 4, false, 4);
+                out.extend(v.to_be_bytes());
+            },
+            Value::Double(v) => {
+                write_tag(out, 5, false, 8);
+                out.extend(v.to_be_bytes());
+            },
+            Value::OctetString(bytes) => {
+                write_tag(out, 6, false, bytes.len());
+                out.extend(bytes);
+            },
+            Value::CharacterString(text) => {
+                // Character set 0: UTF-8
+                write_tag(out, 7, false, text.len() + 1);
+                out.push(0);
+                out.extend(text.as_bytes());
+            },
+            Value::BitString(bits) => {
+                let bytes = bits.len().div_ceil(8);
+                write_tag(out, 8, false, bytes + 1);
+                out.push((bytes * 8 - bits.len()) as u8);
+                let start = out.len();
+                out.resize(start + bytes, 0);
+                for (i, _) in bits.iter().enumerate().filter(|(_, set)| **set) {
+                    out[start + i / 8] |= 0x80 >> (i % 8);
+                }
+            },
+            Value::Enumerated(v) => {
+                let bytes = unsigned_bytes(u64::from(*v));
+                write_tag(out, 9, false, bytes.len());
+                out.extend(bytes);
+            },
+            Value::Date(date) => {
+                write_tag(out, 10, false, 4);
+                out.extend(date);
+            },
+            Value::Time(time) => {
+                write_tag(out, 11, false, 4);
+                out.extend(time);
+            },
+            Value::ObjectId(id) => {
+                write_tag(out, 12, false, 4);
+                out.extend(id.encode().to_be_bytes());
+            },
+        }
+    }
+}
+
+/// Type of a written value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    Boolean,
+    Unsigned,
+    Signed,
+    Real,
+    Double,
+    Enumerated,
+}
+
+impl ValueType {
+    pub fn parse(text: &str) -> Result<Self> {
+        match normalize(text).as_str() {
+            "boolean" | "bool" => Ok(Self::Boolean),
+            "unsigned" | "unsigned-integer" => Ok(Self::Unsigned),
+            "signed" | "signed-integer" | "integer" => Ok(Self::Signed),
+            "real" | "float" => Ok(Self::Real),
+            "double" => Ok(Self::Double),
+            "enumerated" | "enum" => Ok(Self::Enumerated),
+            _ => Err(error(format!("unknown data type '{}'", text.trim()))),
+        }
+    }
+
+    /// Value of this type for a command value
+    pub fn value(&self, value: f64) -> Result<Value> {
+        let integer = |min: f64, max: f64| {
+            let rounded = value.round();
+            (rounded.is_finite() && rounded >= min && rounded <= max)
+                .then_some(rounded)
+                .ok_or_else(|| error(format!("{} is out of range for {:?}", value, self)))
+        };
+        Ok(match self {
+            Self::Boolean => Value::Boolean(value != 0.0),
+            Self::Unsigned => Value::Unsigned(integer(0.0, u32::MAX.into())? as u64),
+            Self::Signed => Value::Signed(integer(i32::MIN.into(), i32::MAX.into())? as i64),
+            Self::Enumerated => Value::Enumerated(integer(0.0, u32::MAX.into())? as u32),
+            Self::Real => {
+                if !value.is_finite() || value.abs() > f64::from(f32::MAX) {
+                    return Err(error(format!("{} is out of range for Real", value)));
+                }
+                Value::Real(value as f32)
+            },
+            Self::Double => Value::Double(value),
+        })
+    }
+}
+
+// ============================================================================
+// Tag encoding
+// ============================================================================
+
+/// Append a tag header: tag number, class and length (or boolean value)
+fn write_tag(out: &mut Vec<u8>, number: u8, context: bool, length: usize) {
+    let class = if context { 0x08 } else { 0x00 };
+    let (high, extended) = if number <= 14 {
+        (number << 4, None)
+    } else {
+        (0xF0, Some(number))
+    };
+    let lvt = if length <= 4 { length as u8 } else { 5 };
+    out.push(high | class | lvt);
+    out.extend(extended);
+    if length > 4 {
+        if length <= 253 {
+            out.push(length as u8);
+        } else if let Ok(length) = u16::try_from(length) {
+            out.push(254);
+            out.extend(length.to_be_bytes());
+        } else {
+            out.push(255);
+            out.extend((length as u32).to_be_bytes());
+        }
+    }
+}
+
+/// Opening tag of constructed context data
+pub fn write_opening(out: &mut Vec<u8>, number: u8) {
+    write_delimiter(out, number, 0x0E);
+}
+
+/// Closing tag of constructed context data
+pub fn write_closing(out: &mut Vec<u8>, number: u8) {
+    write_delimiter(out, number, 0x0F);
+}
+
+fn write_delimiter(out: &mut Vec<u8>, number: u8, low: u8) {
+    if number <= 14 {
+        out.push(number << 4 | low);
+    } else {
+        out.extend([0xF0 | low, number]);
+    }
+}
+
+/// Shortest big-endian encoding, at least one octet
+fn unsigned_bytes(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take(7).take_while(|b| **b == 0).count();
+    bytes[skip..].to_vec()
+}
+
+/// Shortest two's complement encoding
+fn signed_bytes(value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let mut skip = 0;
+    while skip < 7 {
+        let (byte, next) = (bytes[skip], bytes[skip + 1]);
+        let redundant = (byte == 0x00 && next & 0x80 == 0) || (byte == 0xFF && next & 0x80 != 0);
+        if !redundant {
+            break;
+        }
+        skip += 1;
+    }
+    bytes[skip..].to_vec()
+}
+
+pub fn write_context_unsigned(out: &mut Vec<u8>, number: u8, value: u64) {
+    let bytes = unsigned_bytes(value);
+    write_tag(out, number, true, bytes.len());
+    out.extend(bytes);
+}
+
+pub fn write_context_object_id(out: &mut Vec<u8>, number: u8, object: ObjectId) {
+    write_tag(out, number, true, 4);
+    out.extend(object.encode().to_be_bytes());
+}
+
+fn write_context_boolean(out: &mut Vec<u8>, number: u8, value: bool) {
+    write_tag(out, number, true, 1);
+    out.push(u8::from(value));
+}
+
+/// Property identifier and optional array index at context tags
+/// `number` and `number + 1`
+fn write_property(out: &mut Vec<u8>, number: u8, target: &PropertyRef) {
+    write_context_unsigned(out, number, u64::from(target.property));
+    if let Some(index) = target.index {
+        write_context_unsigned(out, number + 1, u64::from(index));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tag {
+    /// Application tag; the length, or the value of booleans
+    Application {
+        number: u8,
+        lvt: u32,
+    },
+    Context {
+        number: u8,
+        length: u32,
+    },
+    Opening(u8),
+    Closing(u8),
+}
+
+/// Cursor over tagged data
+pub struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn byte(&self, at: usize) -> Result<u8> {
+        self.data
+            .get(at)
+            .copied()
+            .ok_or_else(|| error("truncated tag"))
+    }
+
+    /// Tag at the cursor and the length of its header
+    fn header(&self) -> Result<(Tag, usize)> {
+        let first = self.byte(self.pos)?;
+        let mut used = 1;
+        let mut number = first >> 4;
+        if number == 15 {
+            number = self.byte(self.pos + 1)?;
+            used += 1;
+        }
+        let context = first & 0x08 != 0;
+        let lvt = first & 0x07;
+        if context && lvt == 6 {
+            return Ok((Tag::Opening(number), used));
+        }
+        if context && lvt == 7 {
+            return Ok((Tag::Closing(number), used));
+        }
+        let value = if lvt == 5 {
+            let extended = self.byte(self.pos + used)?;
+            used += 1;
+            match extended {
+                254 => {
+                    let value = u16::from_be_bytes([
+                        self.byte(self.pos + used)?,
+                        self.byte(self.pos + used + 1)?,
+                    ]);
+                    used += 2;
+                    u32::from(value)
+                },
+                255 => {
+                    let mut bytes = [0u8; 4];
+                    for (i, byte) in bytes.iter_mut().enumerate() {
+                        *byte = self.byte(self.pos + used + i)?;
+                    }
+                    used += 4;
+                    u32::from_be_bytes(bytes)
+                },
+                n => u32::from(n),
+            }
+        } else {
+            u32::from(lvt)
+        };
+        let tag = if context {
+            Tag::Context {
+                number,
+                length: value,
+            }
+        } else {
+            Tag::Application { number, lvt: value }
+        };
+        Ok((tag, used))
+    }
+
+    fn peek(&self) -> Option<Tag> {
+        self.header().ok().map(|(tag, _)| tag)
+    }
+
+    fn next_tag(&mut self) -> Result<Tag> {
+        let (tag, used) = self.header()?;
+        self.pos += used;
+        Ok(tag)
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + length)
+            .ok_or_else(|| error("truncated value"))?;
+        self.pos += length;
+        Ok(bytes)
+    }
+
+    pub fn at_opening(&self, number: u8) -> bool {
+        self.peek() == Some(Tag::Opening(number))
+    }
+
+    pub fn at_closing(&self, number: u8) -> bool {
+        self.peek() == Some(Tag::Closing(number))
+    }
+
+    pub fn opening(&mut self, number: u8) -> Result<()> {
+        match self.next_tag()? {
+            Tag::Opening(n) if n == number => Ok(()),
+            other => Err(error(format!(
+                "expected opening tag {}, found {:?}",
+                number, other
+            ))),
+        }
+    }
+
+    pub fn closing(&mut self, number: u8) -> Result<()> {
+        match self.next_tag()? {
+            Tag::Closing(n) if n == number => Ok(()),
+            other => Err(error(format!(
+                "expected closing tag {}, found {:?}",
+                number, other
+            ))),
+        }
+    }
+
+    /// Content of the primitive context tag `number`, if it is next
+    pub fn optional_context(&mut self, number: u8) -> Result<Option<&'a [u8]>> {
+        match self.peek() {
+            Some(Tag::Context { number: n, length }) if n == number => {
+                self.next_tag()?;
+                self.take(length as usize).map(Some)
+            },
+            _ => Ok(None),
+        }
+    }
+
+    pub fn context(&mut self, number: u8) -> Result<&'a [u8]> {
+        self.optional_context(number)?
+            .ok_or_else(|| error(format!("missing context tag {}", number)))
+    }
+
+    pub fn optional_context_unsigned(&mut self, number: u8) -> Result<Option<u64>> {
+        self.optional_context(number)?.map(be_unsigned).transpose()
+    }
+
+    pub fn context_unsigned(&mut self, number: u8) -> Result<u64> {
+        be_unsigned(self.context(number)?)
+    }
+
+    pub fn context_object_id(&mut self, number: u8) -> Result<ObjectId> {
+        let bytes: [u8; 4] = self
+            .context(number)?
+            .try_into()
+            .map_err(|_| error("object identifier is not 4 octets"))?;
+        Ok(ObjectId::decode(u32::from_be_bytes(bytes)))
+    }
+
+    /// Next application-tagged value
+    pub fn application(&mut self) -> Result<Value> {
+        let Tag::Application { number, lvt } = self.next_tag()? else {
+            return Err(error("expected an application tag"));
+        };
+        if number == 1 {
+            return Ok(Value::Boolean(lvt != 0));
+        }
+        let content = self.take(lvt as usize)?;
+        let fixed = |n: usize| {
+            (content.len() == n)
+                .then_some(content)
+                .ok_or_else(|| error(format!("application tag {} is not {} octets", number, n)))
+        };
+        Ok(match number {
+            0 => Value::Null,
+            2 => Value::Unsigned(be_unsigned(content)?),
+            3 => Value::Signed(be_signed(content)?),
+            4 => Value::Real(f32::from_be_bytes(fixed(4)?.try_into().unwrap_or([0; 4]))),
+            5 => Value::Double(f64::from_be_bytes(fixed(8)?.try_into().unwrap_or([0; 8]))),
+            6 => Value::OctetString(content.to_vec()),
+            7 => {
+                let (_charset, text) = content
+                    .split_first()
+                    .ok_or_else(|| error("character string without character set"))?;
+                Value::CharacterString(String::from_utf8_lossy(text).into_owned())
+            },
+            8 => {
+                let (unused, bytes) = content
+                    .split_first()
+                    .ok_or_else(|| error("bit string without unused bits"))?;
+                let count = (bytes.len() * 8).saturating_sub(usize::from(*unused));
+                Value::BitString(
+                    (0..count)
+                        .map(|i| bytes[i / 8] & (0x80 >> (i % 8)) != 0)
+                        .collect(),
+                )
+            },
+            9 => Value::Enumerated(
+                u32::try_from(be_unsigned(content)?)
+                    .map_err(|_| error("enumerated value is out of range"))?,
+            ),
+            10 => Value::Date(fixed(4)?.try_into().unwrap_or([0; 4])),
+            11 => Value::Time(fixed(4)?.try_into().unwrap_or([0; 4])),
+            12 => Value::ObjectId(ObjectId::decode(u32::from_be_bytes(
+                fixed(4)?.try_into().unwrap_or([0; 4]),
+            ))),
+            other => return Err(error(format!("reserved application tag {}", other))),
+        })
+    }
+
+    /// Application values up to and including the closing tag `number`
+    ///
+    /// Context-tagged parts of constructed values are skipped.
+    pub fn values(&mut self, number: u8) -> Result<Vec<Value>> {
+        let mut values = Vec::new();
+        loop {
+            match self.peek() {
+                None => return Err(error(format!("missing closing tag {}", number))),
+                Some(Tag::Closing(n)) if n == number => {
+                    self.next_tag()?;
+                    return Ok(values);
+                },
+                Some(Tag::Application { .. }) => values.push(self.application()?),
+                Some(Tag::Context { length, .. }) => {
+                    self.next_tag()?;
+                    self.take(length as usize)?;
+                },
+                Some(Tag::Opening(n)) => {
+                    self.next_tag()?;
+                    self.values(n)?;
+                },
+                Some(Tag::Closing(n)) => {
+                    return Err(error(format!("unexpected closing tag {}", n)));
+                },
+            }
+        }
+    }
+}
+
+fn be_unsigned(bytes: &[u8]) -> Result<u64> {
+    if bytes.is_empty() || bytes.len() > 8 {
+        return Err(error(format!("{}-octet unsigned value", bytes.len())));
+    }
+    Ok(bytes.iter().fold(0, |value, b| value << 8 | u64::from(*b)))
+}
+
+fn be_signed(bytes: &[u8]) -> Result<i64> {
+    let value = be_unsigned(bytes)?;
+    let bits = bytes.len() * 8;
+    Ok(if bits < 64 && value & (1 << (bits - 1)) != 0 {
+        (value | !0 << bits) as i64
+    } else {
+        value as i64
+    })
+}
+
+// ============================================================================
+// BVLC / NPDU
+// ============================================================================
+
+/// Remote network and MAC address of a device behind a BACnet router
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub network: u16,
+    pub address: Vec<u8>,
+}
+
+/// BACnet/IP datagram carrying `apdu` to the device
+pub fn encode_frame(route: Option<&Route>, expecting_reply: bool, apdu: &[u8]) -> Vec<u8> {
+    let mut control = 0u8;
+    if expecting_reply {
+        control |= 0x04;
+    }
+    let mut npdu = vec![NPDU_VERSION, control];
+    if let Some(route) = route {
+        npdu[1] |= 0x20;
+        npdu.extend(route.network.to_be_bytes());
+        npdu.push(route.address.len() as u8);
+        npdu.extend(&route.address);
+        // Hop count
+        npdu.push(0xFF);
+    }
+    npdu.extend(apdu);
+    let length = (npdu.len() + 4) as u16;
+    let mut frame = vec![BVLC_TYPE, BVLC_ORIGINAL_UNICAST];
+    frame.extend(length.to_be_bytes());
+    frame.extend(npdu);
+    frame
+}
+
+/// APDU of a BACnet/IP datagram
+///
+/// None for BVLC control functions and network layer messages.
+pub fn decode_frame(datagram: &[u8]) -> Result<Option<&[u8]>> {
+    let [BVLC_TYPE, function, high, low, rest @ ..] = datagram else {
+        return Err(error("not a BACnet/IP datagram"));
+    };
+    if usize::from(u16::from_be_bytes([*high, *low])) != datagram.len() {
+        return Err(error("BVLC length does not match the datagram"));
+    }
+    let npdu = match *function {
+        BVLC_ORIGINAL_UNICAST | BVLC_ORIGINAL_BROADCAST => rest,
+        // Original source B/IP address precedes the NPDU
+        BVLC_FORWARDED_NPDU => rest
+            .get(6..)
+            .ok_or_else(|| error("truncated forwarded NPDU"))?,
+        _ => return Ok(None),
+    };
+    let [NPDU_VERSION, control, rest @ ..] = npdu else {
+        return Err(error("unsupported NPDU version"));
+    };
+    let mut at = 0;
+    let skip_address = |at: &mut usize| -> Result<()> {
+        let length = *rest
+            .get(*at + 2)
+            .ok_or_else(|| error("truncated NPDU address"))?;
+        *at += 3 + usize::from(length);
+        Ok(())
+    };
+    let destination = control & 0x20 != 0;
+    if destination {
+        skip_address(&mut at)?;
+    }
+    if control & 0x08 != 0 {
+        skip_address(&mut at)?;
+    }
+    if destination {
+        at += 1;
+    }
+    if control & 0x80 != 0 {
+        return Ok(None);
+    }
+    rest.get(at..)
+        .filter(|apdu| !apdu.is_empty())
+        .map(Some)
+        .ok_or_else(|| error("truncated NPDU"))
+}
+
+// ============================================================================
+// APDU
+// ============================================================================
+
+/// Decoded APDU header; `data` holds the service parameters
+#[derive(Debug, Clone, PartialEq)]
+pub enum Apdu<'a> {
+    ConfirmedRequest {
+        invoke_id: u8,
+        service: u8,
+        data: &'a [u8],
+    },
+    UnconfirmedRequest {
+        service: u8,
+        data: &'a [u8],
+    },
+    SimpleAck {
+        invoke_id: u8,
+        service: u8,
+    },
+    ComplexAck {
+        invoke_id: u8,
+        service: u8,
+        data: &'a [u8],
+    },
+    Error {
+        invoke_id: u8,
+        service: u8,
+        class: u32,
+        code: u32,
+    },
+    Reject {
+        invoke_id: u8,
+        reason: u8,
+    },
+    Abort {
+        invoke_id: u8,
+        reason: u8,
+    },
+}
+
+impl<'a> Apdu<'a> {
+    /// Parse an APDU; segmented messages are not supported
+    pub fn parse(apdu: &'a [u8]) -> Result<Self> {
+        let truncated = || error("truncated APDU");
+        let first = *apdu.first().ok_or_else(truncated)?;
+        let byte = |at: usize| apdu.get(at).copied().ok_or_else(truncated);
+        let segmented = first & 0x08 != 0;
+        Ok(match first >> 4 {
+            0 if !segmented => Apdu::ConfirmedRequest {
+                invoke_id: byte(2)?,
+                service: byte(3)?,
+                data: &apdu[4..],
+            },
+            1 => Apdu::UnconfirmedRequest {
+                service: byte(1)?,
+                data: &apdu[2..],
+            },
+            2 => Apdu::SimpleAck {
+                invoke_id: byte(1)?,
+                service: byte(2)?,
+            },
+            3 if !segmented => Apdu::ComplexAck {
+                invoke_id: byte(1)?,
+                service: byte(2)?,
+                data: &apdu[3..],
+            },
+            5 => {
+                let mut reader = Reader::new(&apdu[3.min(apdu.len())..]);
+                // Some services wrap the error in context tag 0
+                if reader.at_opening(0) {
+                    reader.opening(0)?;
+                }
+                let enumerated = |reader: &mut Reader| match reader.application()? {
+                    Value::Enumerated(v) => Ok(v),
+                    other => Err(error(format!("error class/code {:?}", other))),
+                };
+                Apdu::Error {
+                    invoke_id: byte(1)?,
+                    service: byte(2)?,
+                    class: enumerated(&mut reader)?,
+                    code: enumerated(&mut reader)?,
+                }
+            },
+            6 => Apdu::Reject {
+                invoke_id: byte(1)?,
+                reason: byte(2)?,
+            },
+            7 => Apdu::Abort {
+                invoke_id: byte(1)?,
+                reason: byte(2)?,
+            },
+            0 | 3 => return Err(error("segmented messages are not supported")),
+            other => return Err(error(format!("unsupported PDU type {}", other))),
+        })
+    }
+}
+
+/// Confirmed request without segmentation
+pub fn confirmed_request(invoke_id: u8, service: u8, params: &[u8]) -> Vec<u8> {
+    [&[0x00, MAX_APDU_ACCEPTED, invoke_id, service][..], params].concat()
+}
+
+pub fn simple_ack(invoke_id: u8, service: u8) -> Vec<u8> {
+    vec![0x20, invoke_id, service]
+}
+
+/// ReadProperty parameters
+pub fn read_property(target: &PropertyRef) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_context_object_id(&mut out, 0, target.object);
+    write_property(&mut out, 1, target);
+    out
+}
+
+/// ReadPropertyMultiple parameters; consecutive properties of one object
+/// share its read access specification
+pub fn read_property_multiple(targets: &[PropertyRef]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut open: Option<ObjectId> = None;
+    for target in targets {
+        if open != Some(target.object) {
+            if open.is_some() {
+                write_closing(&mut out, 1);
+            }
+            write_context_object_id(&mut out, 0, target.object);
+            write_opening(&mut out, 1);
+            open = Some(target.object);
+        }
+        write_property(&mut out, 0, target);
+    }
+    if open.is_some() {
+        write_closing(&mut out, 1);
+    }
+    out
+}
+
+/// WriteProperty parameters
+pub fn write_property_request(
+    target: &PropertyRef,
+    value: &Value,
+    priority: Option<u8>,
+) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_context_object_id(&mut out, 0, target.object);
+    write_property(&mut out, 1, target);
+    write_opening(&mut out, 3);
+    value.encode(&mut out);
+    write_closing(&mut out, 3);
+    if let Some(priority) = priority {
+        write_context_unsigned(&mut out, 4, u64::from(priority));
+    }
+    out
+}
+
+/// SubscribeCOV parameters; without `subscription` (confirmed, lifetime in
+/// seconds) the subscription is cancelled
+pub fn subscribe_cov(
+    process_id: u32,
+    object: ObjectId,
+    subscription: Option<(bool, u32)>,
+) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_context_unsigned(&mut out, 0, u64::from(process_id));
+    write_context_object_id(&mut out, 1, object);
+    if let Some((confirmed, lifetime)) = subscription {
+        write_context_boolean(&mut out, 2, confirmed);
+        write_context_unsigned(&mut out, 3, u64::from(lifetime));
+    }
+    out
+}
+
+fn property_ref(object: ObjectId, property: u64, index: Option<u64>) -> Result<PropertyRef> {
+    let narrow =
+        |v: u64| u32::try_from(v).map_err(|_| error("property identifier is out of range"));
+    Ok(PropertyRef {
+        object,
+        property: narrow(property)?,
+        index: index.map(narrow).transpose()?,
+    })
+}
+
+/// Property and values of a ReadProperty-ACK
+pub fn parse_read_property_ack(data: &[u8]) -> Result<(PropertyRef, Vec<Value>)> {
+    let mut reader = Reader::new(data);
+    let object = reader.context_object_id(0)?;
+    let property = reader.context_unsigned(1)?;
+    let index = reader.optional_context_unsigned(2)?;
+    reader.opening(3)?;
+    let values = reader.values(3)?;
+    Ok((property_ref(object, property, index)?, values))
+}
+
+/// Result of one property in a ReadPropertyMultiple-ACK
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyResult {
+    pub target: PropertyRef,
+    /// Values, or the error class and code of the property
+    pub result: std::result::Result<Vec<Value>, (u32, u32)>,
+}
+
+pub fn parse_read_property_multiple_ack(data: &[u8]) -> Result<Vec<PropertyResult>> {
+    let mut reader = Reader::new(data);
+    let mut results = Vec::new();
+    while !reader.is_empty() {
+        let object = reader.context_object_id(0)?;
+        reader.opening(1)?;
+        while !reader.at_closing(1) {
+            let property = reader.context_unsigned(2)?;
+            let index = reader.optional_context_unsigned(3)?;
+            let target = property_ref(object, property, index)?;
+            let result = if reader.at_opening(4) {
+                reader.opening(4)?;
+                Ok(reader.values(4)?)
+            } else {
+                reader.opening(5)?;
+                let values = reader.values(5)?;
+                match values.as_slice() {
+                    [Value::Enumerated(class), Value::Enumerated(code)] => Err((*class, *code)),
+                    _ => return Err(error("malformed property access error")),
+                }
+            };
+            results.push(PropertyResult { target, result });
+        }
+        reader.closing(1)?;
+    }
+    Ok(results)
+}
+
+/// Confirmed or unconfirmed COV notification
+#[derive(Debug, Clone, PartialEq)]
+pub struct CovNotification {
+    pub process_id: u32,
+    pub device: ObjectId,
+    pub object: ObjectId,
+    /// Seconds until the subscription ends
+    pub time_remaining: u32,
+    pub values: Vec<(PropertyRef, Vec<Value>)>,
+}
+
+pub fn parse_cov_notification(data: &[u8]) -> Result<CovNotification> {
+    let mut reader = Reader::new(data);
+    let narrow = |v: u64| u32::try_from(v).map_err(|_| error("value is out of range"));
+    let process_id = narrow(reader.context_unsigned(0)?)?;
+    let device = reader.context_object_id(1)?;
+    let object = reader.context_object_id(2)?;
+    let time_remaining = narrow(reader.context_unsigned(3)?)?;
+    reader.opening(4)?;
+    let mut values = Vec::new();
+    while !reader.at_closing(4) {
+        let property = reader.context_unsigned(0)?;
+        let index = reader.optional_context_unsigned(1)?;
+        reader.opening(2)?;
+        let value = reader.values(2)?;
+        // Priority of commandable values
+        reader.optional_context(3)?;
+        values.push((property_ref(object, property, index)?, value));
+    }
+    reader.closing(4)?;
+    Ok(CovNotification {
+        process_id,
+        device,
+        object,
+        time_remaining,
+        values,
+    })
+}
+
+/// `class: code` of an Error-PDU or property access error
+pub fn error_text(class: u32, code: u32) -> String {
+    let class = match class {
+        0 => "device",
+        1 => "object",
+        2 => "property",
+        3 => "resources",
+        4 => "security",
+        5 => "services",
+        7 => "communication",
+        _ => return format!("error class {} code {}", class, code),
+    };
+    let code = match code {
+        0 => "other",
+        3 => "device-busy",
+        9 => "invalid-data-type",
+        27 => "read-access-denied",
+        29 => "service-request-denied",
+        31 => "unknown-object",
+        32 => "unknown-property",
+        37 => "value-out-of-range",
+        40 => "write-access-denied",
+        42 => "invalid-array-index",
+        43 => "cov-subscription-failed",
+        44 => "not-cov-property",
+        other => return format!("{} error {}", class, other),
+    };
+    format!("{}: {}", class, code)
+}
+
+pub fn reject_text(reason: u8) -> String {
+    match reason {
+        4 => "rejected: invalid tag".to_string(),
+        5 => "rejected: missing required parameter".to_string(),
+        6 => "rejected: parameter out of range".to_string(),
+        REJECT_UNRECOGNIZED_SERVICE => "rejected: unrecognized service".to_string(),
+        other => format!("rejected (reason {})", other),
+    }
+}
+
+pub fn abort_text(reason: u8) -> String {
+    match reason {
+        4 => "aborted: segmentation not supported".to_string(),
+        11 => "aborted: APDU too long".to_string(),
+        other => format!("aborted (reason {})", other),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    fn av(instance: u32) -> ObjectId {
+        ObjectId::new(2, instance).unwrap()
+    }
+
+    #[test]
+    fn test_object_and_property_names() {
+        assert_eq!(ObjectId::parse_type("Analog_Value"), Ok(2));
+        assert_eq!(ObjectId::parse_type("BO"), Ok(4));
+        assert_eq!(ObjectId::parse_type("130"), Ok(130));
+        assert!(ObjectId::parse_type("heater").is_err());
+        assert_eq!(parse_property("present_value"), Ok(85));
+        assert_eq!(parse_property("Status Flags"), Ok(111));
+
+        let id = ObjectId::new(DEVICE_TYPE, 1234).unwrap();
+        assert_eq!(id.encode(), 0x0200_04D2);
+        assert_eq!(ObjectId::decode(0x0200_04D2), id);
+        assert!(ObjectId::new(2, WILDCARD_INSTANCE + 1).is_err());
+        let target = PropertyRef {
+            object: av(3),
+            property: property::PRIORITY_ARRAY,
+            index: Some(8),
+        };
+        assert_eq!(target.to_string(), "analog-value:3/priority-array[8]");
+    }
+
+    #[test]
+    fn test_value_round_trip() {
+        let values = [
+            Value::Null,
+            Value::Boolean(true),
+            Value::Unsigned(0),
+            Value::Unsigned(70_000),
+            Value::Signed(-1),
+            Value::Signed(-129),
+            Value::Signed(128),
+            Value::Real(21.5),
+            Value::Double(-0.125),
+            Value::CharacterString("AHU-1 supply air".to_string()),
+            Value::BitString(vec![false, true, false, false]),
+            Value::Enumerated(1),
+            Value::ObjectId(av(7)),
+        ];
+        let mut encoded = Vec::new();
+        for value in &values {
+            value.encode(&mut encoded);
+        }
+        let mut reader = Reader::new(&encoded);
+        for value in &values {
+            assert_eq!(&reader.application().unwrap(), value);
+        }
+        assert!(reader.is_empty());
+
+        // Real 21.5, signed -129, status flags (fault set)
+        let mut bytes = Vec::new();
+        Value::Real(21.5).encode(&mut bytes);
+        assert_eq!(bytes, [0x44, 0x41, 0xAC, 0x00, 0x00]);
+        bytes.clear();
+        Value::Signed(-129).encode(&mut bytes);
+        assert_eq!(bytes, [0x32, 0xFF, 0x7F]);
+        bytes.clear();
+        Value::BitString(vec![false, true, false, false]).encode(&mut bytes);
+        assert_eq!(bytes, [0x82, 0x04, 0x40]);
+        assert_eq!(
+            Value::BitString(vec![false, true, false, false]).as_bits(),
+            Some(0b10)
+        );
+
+        assert_eq!(ValueType::Enumerated.value(1.0), Ok(Value::Enumerated(1)));
+        assert!(ValueType::Unsigned.value(-1.0).is_err());
+        assert_eq!(ValueType::parse("REAL"), Ok(ValueType::Real));
+    }
+
+    #[test]
+    fn test_frames() {
+        let apdu = confirmed_request(
+            1,
+            service::READ_PROPERTY,
+            &read_property(&PropertyRef {
+                object: av(3),
+                property: property::PRESENT_VALUE,
+                index: None,
+            }),
+        );
+        assert_eq!(
+            encode_frame(None, true, &apdu),
+            [
+                0x81, 0x0A, 0x00, 0x11, 0x01, 0x04, 0x00, 0x05, 0x01, 0x0C, 0x0C, 0x00, 0x80, 0x00,
+                0x03, 0x19, 0x55
+            ]
+        );
+
+        let route = Route {
+            network: 5,
+            address: vec![0x0A],
+        };
+        let frame = encode_frame(Some(&route), true, &apdu);
+        assert_eq!(&frame[4..11], [0x01, 0x24, 0x00, 0x05, 0x01, 0x0A, 0xFF]);
+        assert_eq!(decode_frame(&frame).unwrap(), Some(&apdu[..]));
+
+        // Reply of a router: source network 5, MAC 0x0A
+        let mut reply = vec![0x81, 0x0A, 0x00, 0x00, 0x01, 0x08, 0x00, 0x05, 0x01, 0x0A];
+        reply.extend(simple_ack(1, service::WRITE_PROPERTY));
+        let length = reply.len() as u16;
+        reply[2..4].copy_from_slice(&length.to_be_bytes());
+        assert_eq!(
+            Apdu::parse(decode_frame(&reply).unwrap().unwrap()).unwrap(),
+            Apdu::SimpleAck {
+                invoke_id: 1,
+                service: service::WRITE_PROPERTY
+            }
+        );
+        // Network layer message and BVLC result
+        assert_eq!(
+            decode_frame(&[0x81, 0x0A, 0x00, 0x07, 0x01, 0x80, 0x01]).unwrap(),
+            None
+        );
+        assert_eq!(
+            decode_frame(&[0x81, 0x00, 0x00, 0x06, 0x00, 0x00]).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_read_property_multiple() {
+        let targets = [
+            PropertyRef {
+                object: av(1),
+                property: property::PRESENT_VALUE,
+                index: None,
+            },
+            PropertyRef {
+                object: av(1),
+                property: property::STATUS_FLAGS,
+                index: None,
+            },
+            PropertyRef {
+                object: av(9),
+                property: property::PRESENT_VALUE,
+                index: None,
+            },
+        ];
+        assert_eq!(
+            read_property_multiple(&targets),
+            [
+                0x0C, 0x00, 0x80, 0x00, 0x01, 0x1E, 0x09, 0x55, 0x09, 0x6F, 0x1F, 0x0C, 0x00, 0x80,
+                0x00, 0x09, 0x1E, 0x09, 0x55, 0x1F
+            ]
+        );
+
+        // av:1 present value 21.5, av:9 unknown object
+        let ack = [
+            0x0C, 0x00, 0x80, 0x00, 0x01, 0x1E, 0x29, 0x55, 0x4E, 0x44, 0x41, 0xAC, 0x00, 0x00,
+            0x4F, 0x1F, 0x0C, 0x00, 0x80, 0x00, 0x09, 0x1E, 0x29, 0x55, 0x5E, 0x91, 0x01, 0x91,
+            0x1F, 0x5F, 0x1F,
+        ];
+        let results = parse_read_property_multiple_ack(&ack).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].target, targets[0]);
+        assert_eq!(results[0].result, Ok(vec![Value::Real(21.5)]));
+        assert_eq!(results[1].result, Err((1, 31)));
+        assert_eq!(error_text(1, 31), "object: unknown-object");
+    }
+
+    #[test]
+    fn test_cov_notification() {
+        let mut data = Vec::new();
+        write_context_unsigned(&mut data, 0, 7);
+        write_context_object_id(&mut data, 1, ObjectId::new(DEVICE_TYPE, 100).unwrap());
+        write_context_object_id(&mut data, 2, av(4));
+        write_context_unsigned(&mut data, 3, 290);
+        write_opening(&mut data, 4);
+        write_context_unsigned(&mut data, 0, u64::from(property::PRESENT_VALUE));
+        write_opening(&mut data, 2);
+        Value::Real(-3.5).encode(&mut data);
+        write_closing(&mut data, 2);
+        write_context_unsigned(&mut data, 0, u64::from(property::STATUS_FLAGS));
+        write_opening(&mut data, 2);
+        Value::BitString(vec![true, false, false, false]).encode(&mut data);
+        write_closing(&mut data, 2);
+        write_closing(&mut data, 4);
+
+        let notification = parse_cov_notification(&data).unwrap();
+        assert_eq!(notification.process_id, 7);
+        assert_eq!(notification.object, av(4));
+        assert_eq!(notification.time_remaining, 290);
+        assert_eq!(notification.values.len(), 2);
+        assert_eq!(notification.values[0].1, vec![Value::Real(-3.5)]);
+        assert_eq!(notification.values[1].1[0].as_bits(), Some(1));
+
+        assert_eq!(
+            subscribe_cov(7, av(4), Some((false, 300))),
+            [0x09, 0x07, 0x1C, 0x00, 0x80, 0x00, 0x04, 0x29, 0x00, 0x3A, 0x01, 0x2C]
+        );
+        assert_eq!(
+            Apdu::parse(&[0x50, 0x03, 0x05, 0x91, 0x05, 0x91, 0x2B]).unwrap(),
+            Apdu::Error {
+                invoke_id: 3,
+                service: service::SUBSCRIBE_COV,
+                class: 5,
+                code: 43
+            }
+        );
+    }
+}
@@ -0,0 +1,1753 @@
+//! CAN bus over SocketCAN
+//!
+//! Receives the data frames of all telemetry and signal points on a Linux
+//! CAN interface and decodes their signals; control and adjustment points
+//! encode their value into the signal of a transmitted frame. Signals follow
+//! the DBC layout (see [`codec`]).
+//!
+//! | Point type | Mapping                                          | Operation            |
+//! |------------|--------------------------------------------------|----------------------|
+//! | T / S      | `can_id`, `start_bit`, `bit_length`, `byte_order`, `data_type`, `scale`, `offset` | latest received frame |
+//! | C / A      | same                                             | frame transmission   |
+//!
+//! Only the IDs of mapped input signals are delivered to the socket: they
+//! are installed as kernel filters (`CAN_RAW_FILTER`), so unrelated bus
+//! traffic never reaches userspace. A transmitted frame carries the last
+//! value sent for every signal of that frame; commands to several signals
+//! of one frame in the same batch go out as a single frame.
+//!
+//! Instead of `can_id` and the layout, a point can name a signal of the
+//! channel's DBC file (`dbc_file`) in its `signal` column (see [`dbc`]).
+//! The DBC file of a running channel can be replaced without restarting it
+//! ([`DbcTables::reload`]): the signal tables are rebuilt and swapped in
+//! whole at the next poll.
+//!
+//! With `mode: j1939` points address SAE J1939 parameter groups instead of
+//! frame IDs (see [`j1939`]): inputs name a `pgn` and optionally the
+//! `source_address` of the sending ECU, transport protocol messages (TP.BAM,
+//! TP.CM RTS/CTS) are reassembled, and the channel claims `j1939_address`
+//! with its NAME before commands are sent from it. Groups marked `request`
+//! are polled with the Request PGN every `request_interval_ms`. Unsigned
+//! SPNs in the "not available" range are skipped and error indicators fail
+//! the point.
+//!
+//! With `mode: canopen` points name objects of CANopen nodes (see
+//! [`canopen`]): `node_id`, `index` and `subindex`. Inputs mapped into a
+//! transmit PDO of the node's EDS (`eds_files`) are decoded from the PDO
+//! frames, other inputs are read by SDO upload every poll and commands are
+//! written by SDO download. Signal points with `nmt` report the node's NMT
+//! state from its heartbeat; `nmt_start` starts all nodes on connect.
+//!
+//! Error frames are received as well. Error-passive and bus-off controller
+//! states fail the poll, which puts the channel in `Degraded` and, if the bus
+//! stays off, lets the reconnect logic reopen the socket. Other bus errors
+//! (missing ACK, arbitration loss, protocol violations) are counted in the
+//! channel diagnostics.
+//!
+//! ```yaml
+//! protocol: can
+//! parameters:
+//!   device: can0
+//!   error_frames: true       # receive error frames for the bus state
+//!   write_timeout_ms: 1000
+//!   dbc_file: /etc/voltage/can/bms.dbc
+//!   mode: j1939              # raw (default), j1939 or canopen
+//!   j1939_address: 0x80      # preferred source address
+//!   j1939_name: "0x8000000000000000"
+//!   request_interval_ms: 1000
+//!   eds_files: {"5": /etc/voltage/eds/io-module.eds}   # canopen
+//!   heartbeat_timeout_ms: 3000
+//!   sdo_timeout_ms: 500
+//!   nmt_start: false
+//! ```
+
+pub mod canopen;
+pub mod codec;
+pub mod dbc;
+pub mod eds;
+pub mod j1939;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use igw::core::traits::{DataEventReceiver, Diagnostics, PointFailure, PollResult};
+use igw::gateway::ChannelRuntime;
+use igw::{ConnectionState, DataBatch, DataPoint, GatewayError};
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+use socketcan::errors::ControllerProblem;
+use socketcan::{
+    CanDataFrame, CanError, CanFilter, CanFrame, CanSocket, EmbeddedFrame, ExtendedId, Frame, Id,
+    Socket, SocketOptions, StandardId,
+};
+use tokio::io::unix::AsyncFd;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use self::canopen::{
+    CanOpenConfig, CanOpenPoint, NmtCondition, NmtState, NodeMonitor, SdoError, SdoObject, SdoStep,
+    SdoTransfer,
+};
+use self::codec::{FrameId, Signal};
+use self::dbc::Dbc;
+use self::eds::Eds;
+use self::j1939::{Availability, J1939Config, J1939Stack, Name, PgnKey, SpnMapping};
+use crate::core::config::RuntimeChannelConfig;
+use crate::core::protocols::CodecError;
+use crate::core::protocols::{point_mapping, record_failure};
+use crate::error::{ComSrvError, Result};
+use voltage_model::PointType;
+
+/// Protocol name stored in `channels.protocol`
+pub const PROTOCOL: &str = "can";
+
+pub const DEFAULT_DEVICE: &str = "can0";
+const DEFAULT_WRITE_TIMEOUT_MS: u64 = 1000;
+
+/// Filters installed in the kernel; more IDs are selected in userspace
+const MAX_KERNEL_FILTERS: usize = 512;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Channel parameters of a CAN channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct CanConfig {
+    /// Network interface (`can0`, `vcan0`)
+    pub device: String,
+    /// Receive error frames to track the controller state
+    pub error_frames: bool,
+    pub write_timeout: Duration,
+    /// Signal database of `signal` point mappings (raw mode)
+    pub dbc_file: Option<PathBuf>,
+    pub mode: CanMode,
+}
+
+/// Addressing of the points of a channel
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum CanMode {
+    /// Points name CAN IDs
+    #[default]
+    Raw,
+    /// Points name J1939 parameter groups
+    J1939(J1939Config),
+    /// Points name objects of CANopen nodes
+    CanOpen(CanOpenConfig),
+}
+
+impl CanConfig {
+    pub fn from_parameters(parameters: &HashMap<String, JsonValue>) -> Self {
+        let device = ["device", "can_interface"]
+            .iter()
+            .find_map(|key| parameters.get(*key).and_then(|v| v.as_str()))
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .unwrap_or(DEFAULT_DEVICE)
+            .to_string();
+        let error_frames = match parameters.get("error_frames") {
+            Some(JsonValue::Bool(b)) => *b,
+            Some(JsonValue::String(s)) => !matches!(s.trim(), "false" | "0" | "no"),
+            _ => true,
+        };
+        let write_timeout_ms = match parameters.get("write_timeout_ms") {
+            Some(JsonValue::Number(n)) => n.as_u64(),
+            Some(JsonValue::String(s)) => s.trim().parse().ok(),
+            _ => None,
+        }
+        .unwrap_or(DEFAULT_WRITE_TIMEOUT_MS);
+        let mode = match parameters
+            .get("mode")
+            .and_then(|v| v.as_str())
+            .map(|mode| mode.trim().to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("j1939") => CanMode::J1939(j1939_config(parameters)),
+            Some("canopen") => CanMode::CanOpen(canopen_config(parameters)),
+            _ => CanMode::Raw,
+        };
+        let dbc_file = parameters
+            .get("dbc_file")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from);
+        Self {
+            device,
+            error_frames,
+            write_timeout: Duration::from_millis(write_timeout_ms.max(1)),
+            dbc_file,
+            mode,
+        }
+    }
+
+    pub fn j1939(&self) -> Option<&J1939Config> {
+        match &self.mode {
+            CanMode::J1939(config) => Some(config),
+            _ => None,
+        }
+    }
+
+    pub fn canopen(&self) -> Option<&CanOpenConfig> {
+        match &self.mode {
+            CanMode::CanOpen(config) => Some(config),
+            _ => None,
+        }
+    }
+}
+
+/// Channel parameters as a mapping object, for the mapping cell parsers
+fn parameter_object(parameters: &HashMap<String, JsonValue>) -> JsonValue {
+    JsonValue::Object(
+        parameters
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect(),
+    )
+}
+
+fn j1939_config(parameters: &HashMap<String, JsonValue>) -> J1939Config {
+    let mapping = parameter_object(parameters);
+    let integer = |key: &str| codec::mapping_integer(&mapping, key).ok().flatten();
+    let defaults = J1939Config::default();
+    J1939Config {
+        address: integer("j1939_address")
+            .and_then(|a| u8::try_from(a).ok())
+            .filter(|a| *a < j1939::NULL_ADDRESS)
+            .unwrap_or(defaults.address),
+        name: integer("j1939_name").map(Name).unwrap_or(defaults.name),
+        request_interval: integer("request_interval_ms")
+            .map(|ms| Duration::from_millis(ms.max(1)))
+            .unwrap_or(defaults.request_interval),
+    }
+}
+
+/// `eds_files` is an object of node ID to path, or `5=a.eds,6=b.eds`;
+/// entries with an invalid node ID are ignored
+fn canopen_config(parameters: &HashMap<String, JsonValue>) -> CanOpenConfig {
+    let mapping = parameter_object(parameters);
+    let integer = |key: &str| codec::mapping_integer(&mapping, key).ok().flatten();
+    let entries: Vec<(String, String)> = match parameters.get("eds_files") {
+        Some(JsonValue::Object(files)) => files
+            .iter()
+            .filter_map(|(node, path)| Some((node.clone(), path.as_str()?.to_string())))
+            .collect(),
+        Some(JsonValue::String(files)) => files
+            .split(',')
+            .filter_map(|entry| entry.split_once('='))
+            .map(|(node, path)| (node.to_string(), path.to_string()))
+            .collect(),
+        _ => Vec::new(),
+    };
+    let eds_files = entries
+        .into_iter()
+        .filter_map(|(node, path)| {
+            let node = codec::mapping_integer(&json!({ "node": node.trim() }), "node")
+                .ok()
+                .flatten()
+                .and_then(|node| u8::try_from(node).ok())
+                .filter(|node| (1..=canopen::MAX_NODE_ID).contains(node))?;
+            let path = path.trim();
+            (!path.is_empty()).then(|| (node, PathBuf::from(path)))
+        })
+        .collect();
+    let defaults = CanOpenConfig::default();
+    CanOpenConfig {
+        eds_files,
+        heartbeat_timeout: integer("heartbeat_timeout_ms")
+            .map(|ms| Duration::from_millis(ms.max(1)))
+            .unwrap_or(defaults.heartbeat_timeout),
+        sdo_timeout: integer("sdo_timeout_ms")
+            .map(|ms| Duration::from_millis(ms.max(1)))
+            .unwrap_or(defaults.sdo_timeout),
+        nmt_start: codec::mapping_flag(&mapping, "nmt_start"),
+    }
+}
+
+// ============================================================================
+// Bus state
+// ============================================================================
+
+/// Fault confinement state of the CAN controller, from error frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BusState {
+    #[default]
+    ErrorActive,
+    ErrorWarning,
+    ErrorPassive,
+    BusOff,
+}
+
+impl BusState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ErrorActive => "error_active",
+            Self::ErrorWarning => "error_warning",
+            Self::ErrorPassive => "error_passive",
+            Self::BusOff => "bus_off",
+        }
+    }
+
+    /// States in which the node no longer takes part in the bus reliably
+    pub fn is_fault(&self) -> bool {
+        matches!(self, Self::ErrorPassive | Self::BusOff)
+    }
+}
+
+/// Bus state and error counters maintained by the receive task
+#[derive(Debug, Default)]
+struct BusMonitor {
+    state: BusState,
+    error_frames: u64,
+    /// Error frames since the last poll
+    new_errors: u64,
+    last_error: Option<String>,
+}
+
+impl BusMonitor {
+    fn record(&mut self, error: &CanError) {
+        self.error_frames += 1;
+        self.new_errors += 1;
+        self.last_error = Some(error.to_string());
+        if let Some(state) = bus_state_of(error) {
+            self.state = state;
+        }
+    }
+
+    /// A data frame was received: a bus-off controller has recovered
+    fn frame_received(&mut self) {
+        if self.state == BusState::BusOff {
+            self.state = BusState::ErrorActive;
+        }
+    }
+}
+
+/// Controller state an error frame reports, if any
+fn bus_state_of(error: &CanError) -> Option<BusState> {
+    match error {
+        CanError::BusOff => Some(BusState::BusOff),
+        CanError::Restarted => Some(BusState::ErrorActive),
+        CanError::ControllerProblem(problem) => {
+            match problem {
+                ControllerProblem::ReceiveErrorWarning
+                | ControllerProblem::TransmitErrorWarning => Some(BusState::ErrorWarning),
+                ControllerProblem::ReceiveErrorPassive
+                | ControllerProblem::TransmitErrorPassive => Some(BusState::ErrorPassive),
+                ControllerProblem::Active => Some(BusState::ErrorActive),
+                _ => None,
+            }
+        },
+        _ => None,
+    }
+}
+
+/// Message the input signals are decoded from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum MessageKey {
+    Frame(FrameId),
+    /// J1939 parameter group, from one source or any
+    Pgn(PgnKey),
+}
+
+/// State shared between the runtime and its receive task
+#[derive(Debug, Default)]
+struct Shared {
+    /// Messages of the input signals
+    wanted: HashSet<MessageKey>,
+    /// Latest data of each input message received since the last poll
+    frames: HashMap<MessageKey, Vec<u8>>,
+    bus: BusMonitor,
+    /// Address claim and transport sessions of J1939 channels
+    j1939: Option<J1939Stack>,
+    /// Heartbeats and emergencies of CANopen channels
+    canopen: Option<NodeMonitor>,
+    /// Node whose SDO responses go to the running transfer
+    sdo: Option<(u8, mpsc::UnboundedSender<Vec<u8>>)>,
+    /// Socket error that ended the receive task
+    closed: Option<String>,
+}
+
+/// Input and output signals of a channel
+#[derive(Debug, Default)]
+struct SignalTables {
+    /// Input signals by message, as (internal point ID, signal)
+    inputs: HashMap<MessageKey, Vec<(u32, Signal)>>,
+    controls: HashMap<u32, Signal>,
+    adjustments: HashMap<u32, Signal>,
+    /// Data length of each output frame: the longest its signals need
+    frame_lens: HashMap<FrameId, usize>,
+}
+
+impl SignalTables {
+    fn insert(
+        &mut self,
+        point_type: PointType,
+        point_id: u32,
+        key: MessageKey,
+        signal: Signal,
+    ) -> std::result::Result<(), CodecError> {
+        match point_type {
+            PointType::Telemetry | PointType::Signal => {
+                self.inputs
+                    .entry(key)
+                    .or_default()
+                    .push((point_type.to_internal_id(point_id), signal));
+            },
+            PointType::Control | PointType::Adjustment => {
+                let len = signal.data_len()?;
+                let frame_len = self.frame_lens.entry(signal.frame).or_default();
+                *frame_len = (*frame_len).max(len);
+                let targets = match point_type {
+                    PointType::Control => &mut self.controls,
+                    _ => &mut self.adjustments,
+                };
+                targets.insert(point_id, signal);
+            },
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// DBC reload
+// ============================================================================
+
+/// Point of a raw channel, kept to rebuild the tables from another DBC
+#[derive(Debug, Clone)]
+struct RawPoint {
+    point_type: PointType,
+    point_id: u32,
+    mapping: JsonValue,
+}
+
+/// Signal of a raw point: its own layout with `can_id`, else the DBC
+/// signal named in `signal`
+fn raw_signal(mapping: &JsonValue, dbc: Option<&Dbc>) -> std::result::Result<Signal, CodecError> {
+    let name = mapping
+        .get("signal")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|name| !name.is_empty());
+    match name {
+        Some(name) if codec::mapping_integer(mapping, "can_id")?.is_none() => {
+            let dbc = dbc.ok_or_else(|| {
+                crate::core::protocols::error(format!("signal '{}' without a dbc_file", name))
+            })?;
+            Ok(dbc.signal(name)?.signal.clone())
+        },
+        _ => Signal::from_mapping(mapping),
+    }
+}
+
+/// Tables of the raw points and the signal each point resolved to, by
+/// internal point ID; unresolved points are skipped with a warning
+fn raw_tables(
+    channel_id: u32,
+    points: &[RawPoint],
+    dbc: Option<&Dbc>,
+) -> (SignalTables, BTreeMap<u32, Signal>) {
+    let mut tables = SignalTables::default();
+    let mut resolved = BTreeMap::new();
+    for point in points {
+        let inserted = raw_signal(&point.mapping, dbc).and_then(|signal| {
+            tables.insert(
+                point.point_type,
+                point.point_id,
+                MessageKey::Frame(signal.frame),
+                signal.clone(),
+            )?;
+            Ok(signal)
+        });
+        match inserted {
+            Ok(signal) => {
+                resolved.insert(point.point_type.to_internal_id(point.point_id), signal);
+            },
+            Err(e) => warn!(
+                "Ch{} {}{} skipped: {}",
+                channel_id,
+                point.point_type.as_str(),
+                point.point_id,
+                e
+            ),
+        }
+    }
+    (tables, resolved)
+}
+
+/// Points resolved by a DBC reload compared with the previous file
+#[derive(Debug, Clone, PartialEq, Serialize, utoipa::ToSchema)]
+pub struct DbcReloadReport {
+    pub channel_id: u32,
+    pub dbc_file: String,
+    /// Messages in the new file
+    pub messages: usize,
+    /// Signals of the new file the codec cannot represent
+    pub skipped_signals: usize,
+    /// Points with a signal after the reload
+    pub points: usize,
+    /// Points that now have a signal, e.g. `T12`
+    pub added: Vec<String>,
+    /// Points whose signal is gone; they are no longer updated
+    pub removed: Vec<String>,
+    /// Points whose signal layout or scaling changed
+    pub changed: Vec<String>,
+}
+
+#[derive(Debug)]
+struct DbcState {
+    file: Option<PathBuf>,
+    generation: u64,
+    tables: Arc<SignalTables>,
+    resolved: BTreeMap<u32, Signal>,
+}
+
+/// Signal tables of a running raw channel with the DBC file they were
+/// built from, shared between the runtime and the API
+#[derive(Debug)]
+pub struct DbcTables {
+    channel_id: u32,
+    points: Vec<RawPoint>,
+    state: Mutex<DbcState>,
+}
+
+fn dbc_registry() -> &'static DashMap<u32, Arc<DbcTables>> {
+    static REGISTRY: OnceLock<DashMap<u32, Arc<DbcTables>>> = OnceLock::new();
+    REGISTRY.get_or_init(DashMap::new)
+}
+
+impl DbcTables {
+    /// Tables of the running raw CAN channel `channel_id`
+    pub fn of_channel(channel_id: u32) -> Option<Arc<DbcTables>> {
+        dbc_registry()
+            .get(&channel_id)
+            .map(|entry| Arc::clone(entry.value()))
+    }
+
+    fn state(&self) -> MutexGuard<'_, DbcState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Current DBC file
+    pub fn file(&self) -> Option<PathBuf> {
+        self.state().file.clone()
+    }
+
+    /// Rebuild the tables from the DBC file at `path`
+    ///
+    /// A file that cannot be read or parsed leaves the tables unchanged.
+    /// The runtime switches to the new tables at its next poll or write.
+    pub fn reload(&self, path: &Path) -> Result<DbcReloadReport> {
+        let dbc = Dbc::load(path).map_err(|e| ComSrvError::ConfigError(e.to_string()))?;
+        let (tables, resolved) = raw_tables(self.channel_id, &self.points, Some(&dbc));
+        let label = |internal_id: &u32| {
+            let (point_type, point_id) = PointType::from_internal_id(*internal_id);
+            format!("{}{}", point_type.as_str(), point_id)
+        };
+
+        let mut state = self.state();
+        let added = resolved
+            .keys()
+            .filter(|id| !state.resolved.contains_key(id))
+            .map(label)
+            .collect();
+        let removed = state
+            .resolved
+            .keys()
+            .filter(|id| !resolved.contains_key(id))
+            .map(label)
+            .collect();
+        let changed = resolved
+            .iter()
+            .filter(|(id, signal)| state.resolved.get(id).is_some_and(|old| old != *signal))
+            .map(|(id, _)| label(id))
+            .collect();
+        let report = DbcReloadReport {
+            channel_id: self.channel_id,
+            dbc_file: path.display().to_string(),
+            messages: dbc.messages.len(),
+            skipped_signals: dbc.skipped,
+            points: resolved.len(),
+            added,
+            removed,
+            changed,
+        };
+        *state = DbcState {
+            file: Some(path.to_path_buf()),
+            generation: state.generation + 1,
+            tables: Arc::new(tables),
+            resolved,
+        };
+        info!(
+            "Ch{} DBC reloaded from {}: {} points, +{} -{} ~{}",
+            self.channel_id,
+            report.dbc_file,
+            report.points,
+            report.added.len(),
+            report.removed.len(),
+            report.changed.len()
+        );
+        Ok(report)
+    }
+
+    fn snapshot(&self) -> (u64, Arc<SignalTables>) {
+        let state = self.state();
+        (state.generation, Arc::clone(&state.tables))
+    }
+}
+
+// ============================================================================
+// Runtime
+// ============================================================================
+
+/// CAN channel on a SocketCAN interface
+pub struct SocketCanRuntime {
+    id: u32,
+    name: String,
+    config: CanConfig,
+    tables: Arc<SignalTables>,
+    /// Reloadable tables of raw channels, with the generation in use
+    dbc: Option<(Arc<DbcTables>, u64)>,
+    /// J1939 groups polled with the Request PGN
+    requests: Vec<PgnKey>,
+    last_request: Option<Instant>,
+    /// CANopen nodes of the points and EDS files
+    nodes: BTreeSet<u8>,
+    /// CANopen inputs read by SDO each poll, by internal point ID
+    sdo_inputs: Vec<(u32, SdoObject)>,
+    /// CANopen commands written by SDO, by internal point ID
+    sdo_outputs: HashMap<u32, SdoObject>,
+    /// NMT state signals, by internal point ID
+    nmt_inputs: Vec<(u32, u8, NmtCondition)>,
+    /// Data last sent in each output frame
+    tx: HashMap<FrameId, Vec<u8>>,
+    socket: Option<Arc<AsyncFd<CanSocket>>>,
+    shared: Arc<Mutex<Shared>>,
+    reader: Option<JoinHandle<()>>,
+    diagnostics: Diagnostics,
+}
+
+impl SocketCanRuntime {
+    /// Build the runtime of a `can` channel
+    pub fn from_runtime_config(runtime_config: &RuntimeChannelConfig) -> Result<Self> {
+        let channel_id = runtime_config.id();
+        let mut runtime = Self {
+            id: channel_id,
+            name: runtime_config.name().to_string(),
+            config: CanConfig::from_parameters(&runtime_config.base.parameters),
+            tables: Arc::new(SignalTables::default()),
+            dbc: None,
+            requests: Vec::new(),
+            last_request: None,
+            nodes: BTreeSet::new(),
+            sdo_inputs: Vec::new(),
+            sdo_outputs: HashMap::new(),
+            nmt_inputs: Vec::new(),
+            tx: HashMap::new(),
+            socket: None,
+            shared: Arc::new(Mutex::new(Shared::default())),
+            reader: None,
+            diagnostics: Diagnostics::new(PROTOCOL),
+        };
+
+        let j1939 = runtime.config.j1939().is_some();
+        if let CanMode::J1939(config) = &mut runtime.config.mode {
+            // Channels without their own NAME differ in the identity number
+            if !runtime_config.base.parameters.contains_key("j1939_name") {
+                config.name = Name(config.name.0 | u64::from(channel_id & 0x1F_FFFF));
+            }
+        }
+        let mut dictionaries = HashMap::new();
+        if let Some(config) = runtime.config.canopen() {
+            for (node, path) in &config.eds_files {
+                let eds = Eds::load(path).map_err(|e| {
+                    ComSrvError::ConfigError(format!("Ch{} node {}: {}", channel_id, node, e))
+                })?;
+                dictionaries.insert(*node, eds);
+            }
+            runtime.nodes.extend(config.eds_files.keys().copied());
+        }
+        let canopen = runtime.config.canopen().is_some();
+        let mut tables = SignalTables::default();
+        let mut raw_points = Vec::new();
+        for (point_type, point) in runtime_config.points() {
+            let mapping = point_mapping(point);
+            let output = matches!(point_type, PointType::Control | PointType::Adjustment);
+            let internal_id = point_type.to_internal_id(point.point_id);
+            let parsed = if j1939 {
+                SpnMapping::from_mapping(&mapping, output).map(|spn| {
+                    if spn.request && !output && !runtime.requests.contains(&spn.key) {
+                        runtime.requests.push(spn.key);
+                    }
+                    Some((MessageKey::Pgn(spn.key), spn.signal))
+                })
+            } else if canopen {
+                CanOpenPoint::from_mapping(&mapping, output, &dictionaries).map(|point| match point
+                {
+                    CanOpenPoint::Pdo(signal) => Some((MessageKey::Frame(signal.frame), signal)),
+                    CanOpenPoint::Sdo(object) => {
+                        runtime.nodes.insert(object.node);
+                        if output {
+                            runtime.sdo_outputs.insert(internal_id, object);
+                        } else {
+                            runtime.sdo_inputs.push((internal_id, object));
+                        }
+                        None
+                    },
+                    CanOpenPoint::Nmt { node, condition } => {
+                        runtime.nodes.insert(node);
+                        runtime.nmt_inputs.push((internal_id, node, condition));
+                        None
+                    },
+                })
+            } else {
+                // Resolved together below, and again on DBC reload
+                raw_points.push(RawPoint {
+                    point_type,
+                    point_id: point.point_id,
+                    mapping,
+                });
+                Ok(None)
+            };
+            let inserted = parsed.and_then(|parsed| match parsed {
+                Some((key, signal)) => tables.insert(point_type, point.point_id, key, signal),
+                None => Ok(()),
+            });
+            if let Err(e) = inserted {
+                warn!(
+                    "Ch{} {}{} skipped: {}",
+                    channel_id,
+                    point_type.as_str(),
+                    point.point_id,
+                    e
+                );
+            }
+        }
+        if !j1939 && !canopen {
+            let dbc =
+                match &runtime.config.dbc_file {
+                    Some(path) => Some(Dbc::load(path).map_err(|e| {
+                        ComSrvError::ConfigError(format!("Ch{}: {}", channel_id, e))
+                    })?),
+                    None => None,
+                };
+            let (raw, resolved) = raw_tables(channel_id, &raw_points, dbc.as_ref());
+            tables = raw;
+            let tables = Arc::new(std::mem::take(&mut tables));
+            let dbc_tables = Arc::new(DbcTables {
+                channel_id,
+                points: raw_points,
+                state: Mutex::new(DbcState {
+                    file: runtime.config.dbc_file.clone(),
+                    generation: 0,
+                    tables: Arc::clone(&tables),
+                    resolved,
+                }),
+            });
+            dbc_registry().insert(channel_id, Arc::clone(&dbc_tables));
+            runtime.dbc = Some((dbc_tables, 0));
+            runtime.set_tables(tables);
+        } else {
+            runtime.set_tables(Arc::new(tables));
+        }
+        debug!(
+            "Ch{} CAN {}: {} input frames, {} output frames, {} controls, {} adjustments, {} SDO objects",
+            channel_id,
+            runtime.config.device,
+            runtime.tables.inputs.len(),
+            runtime.tx.len(),
+            runtime.tables.controls.len(),
+            runtime.tables.adjustments.len(),
+            runtime.sdo_inputs.len() + runtime.sdo_outputs.len()
+        );
+        Ok(runtime)
+    }
+
+    /// Use `tables` for decoding and writing
+    ///
+    /// Output frames are sent with the length their signals need.
+    fn set_tables(&mut self, tables: Arc<SignalTables>) {
+        for (frame, len) in &tables.frame_lens {
+            let data = self.tx.entry(*frame).or_default();
+            if data.len() < *len {
+                data.resize(*len, 0);
+            }
+        }
+        self.shared().wanted = tables.inputs.keys().copied().collect();
+        self.tables = tables;
+    }
+
+    /// Switch to the tables of a DBC reload, refiltering the open socket
+    fn refresh_tables(&mut self) {
+        let Some((dbc, generation)) = &self.dbc else {
+            return;
+        };
+        let (current, tables) = dbc.snapshot();
+        if current == *generation {
+            return;
+        }
+        self.dbc = self.dbc.take().map(|(dbc, _)| (dbc, current));
+        self.set_tables(tables);
+        self.shared().frames.clear();
+        if let Some(socket) = &self.socket {
+            if let Err(e) = self.apply_filters(socket.get_ref()) {
+                warn!("Ch{} CAN filter update failed: {}", self.id, e);
+            }
+        }
+        info!(
+            "Ch{} switched to the reloaded DBC tables: {} input frames",
+            self.id,
+            self.tables.inputs.len()
+        );
+    }
+
+    fn shared(&self) -> MutexGuard<'_, Shared> {
+        self.shared.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record an error; socket errors close the channel
+    fn fail(&mut self, error: GatewayError) -> GatewayError {
+        if record_failure(&mut self.diagnostics, &error) {
+            self.close();
+        }
+        error
+    }
+
+    fn close(&mut self) {
+        if let Some(reader) = self.reader.take() {
+            reader.abort();
+        }
+        self.socket = None;
+    }
+
+    /// Open the socket with the kernel filters of the input frames
+    fn open(&self) -> io::Result<CanSocket> {
+        let socket = CanSocket::open(&self.config.device)?;
+        socket.set_nonblocking(true)?;
+        self.apply_filters(&socket)?;
+        if self.config.error_frames {
+            socket.set_error_filter_accept_all()?;
+        } else {
+            socket.set_error_filter_drop_all()?;
+        }
+        Ok(socket)
+    }
+
+    fn apply_filters(&self, socket: &CanSocket) -> io::Result<()> {
+        let filters = if self.config.j1939().is_some() {
+            let keys: Vec<PgnKey> = self
+                .tables
+                .inputs
+                .keys()
+                .filter_map(|key| match key {
+                    MessageKey::Pgn(key) => Some(*key),
+                    MessageKey::Frame(_) => None,
+                })
+                .collect();
+            j1939::kernel_filters(&keys)
+        } else {
+            let mut ids: Vec<FrameId> = self
+                .tables
+                .inputs
+                .keys()
+                .filter_map(|key| match key {
+                    MessageKey::Frame(id) => Some(*id),
+                    MessageKey::Pgn(_) => None,
+                })
+                .collect();
+            if self.config.canopen().is_some() {
+                for node in &self.nodes {
+                    ids.extend(
+                        [
+                            canopen::EMERGENCY,
+                            canopen::SDO_RESPONSE,
+                            canopen::HEARTBEAT,
+                        ]
+                        .map(|function| canopen::cob_id(function, *node)),
+                    );
+                }
+            }
+            codec::kernel_filters(&ids, MAX_KERNEL_FILTERS)
+        };
+        if filters.is_empty() {
+            socket.set_filter_drop_all()?;
+        } else {
+            let filters: Vec<CanFilter> = filters
+                .into_iter()
+                .map(|(id, mask)| CanFilter::new(id, mask))
+                .collect();
+            socket.set_filters(&filters)?;
+        }
+        Ok(())
+    }
+
+    fn diagnostics_extra(&self, shared: &Shared) -> JsonValue {
+        let mut extra = json!({
+            "device": self.config.device,
+            "bus_state": shared.bus.state.as_str(),
+            "error_frames": shared.bus.error_frames,
+            "last_bus_error": shared.bus.last_error,
+            "input_frames": self.tables.inputs.len(),
+            "kernel_filters": self.tables.inputs.len() <= MAX_KERNEL_FILTERS,
+        });
+        if let Some((dbc, _)) = &self.dbc {
+            extra["dbc_file"] = json!(dbc.file().map(|path| path.display().to_string()));
+        }
+        if let Some(stack) = &shared.j1939 {
+            extra["j1939"] = json!({
+                "address": stack.address(),
+                "messages": stack.counters.messages,
+                "transport_messages": stack.counters.transport_messages,
+                "transport_aborts": stack.counters.transport_aborts,
+                "address_conflicts": stack.counters.address_conflicts,
+            });
+        }
+        if let (Some(monitor), Some(config)) = (&shared.canopen, self.config.canopen()) {
+            let now = Instant::now();
+            let nodes: serde_json::Map<String, JsonValue> = self
+                .nodes
+                .iter()
+                .map(|node| {
+                    let state = monitor.state(*node, now, config.heartbeat_timeout);
+                    let mut entry = json!({
+                        "nmt_state": state.map_or("offline", |state| state.as_str()),
+                    });
+                    if let Some(emergency) = monitor.emergencies.get(node) {
+                        entry["emergencies"] = json!(emergency.count);
+                        entry["last_emergency"] = json!(format!(
+                            "0x{:04X}/0x{:02X}",
+                            emergency.error_code, emergency.error_register
+                        ));
+                    }
+                    (node.to_string(), entry)
+                })
+                .collect();
+            extra["canopen"] = json!({
+                "nodes": nodes,
+                "sdo_objects": self.sdo_inputs.len() + self.sdo_outputs.len(),
+            });
+        }
+        extra
+    }
+
+    /// Transmit J1939 network management frames; failures are only logged
+    async fn send_replies(&self, socket: &AsyncFd<CanSocket>, replies: Vec<j1939::Reply>) {
+        for (frame_id, data) in replies {
+            if let Err(e) = send(socket, frame_id, &data, self.config.write_timeout).await {
+                debug!("Ch{} J1939 send {} failed: {}", self.id, frame_id, e);
+            }
+        }
+    }
+
+    /// Expire transport sessions and request the on-request groups
+    async fn j1939_housekeeping(&mut self, socket: &AsyncFd<CanSocket>) {
+        let now = Instant::now();
+        let due = self.config.j1939().is_some_and(|config| {
+            !self.requests.is_empty()
+                && self
+                    .last_request
+                    .is_none_or(|last| now.duration_since(last) >= config.request_interval)
+        });
+        let replies = {
+            let mut shared = self.shared();
+            let Some(stack) = shared.j1939.as_mut() else {
+                return;
+            };
+            let mut replies = stack.expire(now);
+            if due {
+                replies.extend(self.requests.iter().filter_map(|key| {
+                    stack.request(key.pgn, key.address.unwrap_or(j1939::GLOBAL_ADDRESS))
+                }));
+            }
+            replies
+        };
+        if due {
+            self.last_request = Some(now);
+        }
+        self.send_replies(socket, replies).await;
+    }
+
+    /// Run one SDO transfer; the client aborts it on a timeout or a
+    /// response it cannot continue with
+    async fn sdo(
+        &self,
+        socket: &AsyncFd<CanSocket>,
+        mut transfer: SdoTransfer,
+    ) -> igw::Result<Vec<u8>> {
+        let timeout = self
+            .config
+            .canopen()
+            .map_or_else(|| CanOpenConfig::default().sdo_timeout, |c| c.sdo_timeout);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        self.shared().sdo = Some((transfer.node(), tx));
+        let result = async {
+            let mut request = transfer.start();
+            loop {
+                send(
+                    socket,
+                    transfer.request_id(),
+                    &request,
+                    self.config.write_timeout,
+                )
+                .await?;
+                let (response, abort) = match tokio::time::timeout(timeout, rx.recv()).await {
+                    Ok(Some(response)) => (response, None),
+                    Ok(None) => return Err(GatewayError::NotConnected),
+                    Err(_) => (Vec::new(), Some(canopen::ABORT_TIMEOUT)),
+                };
+                let error = match abort {
+                    Some(code) => SdoError::Abort(code),
+                    None => match transfer.on_response(&response) {
+                        Ok(SdoStep::Request(next)) => {
+                            request = next;
+                            continue;
+                        },
+                        Ok(SdoStep::Done(data)) => return Ok(data),
+                        Err(e) => e,
+                    },
+                };
+                let code = match (&error, abort) {
+                    (_, Some(code)) => Some(code),
+                    (SdoError::Unexpected(_), None) => Some(canopen::ABORT_COMMAND),
+                    // The server aborted the transfer itself
+                    (SdoError::Abort(_), None) => None,
+                };
+                if let Some(code) = code {
+                    let frame = transfer.abort(code);
+                    if let Err(e) = send(
+                        socket,
+                        transfer.request_id(),
+                        &frame,
+                        self.config.write_timeout,
+                    )
+                    .await
+                    {
+                        debug!(
+                            "Ch{} SDO abort to node {} failed: {}",
+                            self.id,
+                            transfer.node(),
+                            e
+                        );
+                    }
+                }
+                return Err(match abort {
+                    Some(_) => GatewayError::ReadTimeout,
+                    None => GatewayError::Protocol(error.to_string()),
+                });
+            }
+        }
+        .await;
+        self.shared().sdo = None;
+        result
+    }
+
+    /// Read the SDO inputs of nodes that are not stopped
+    async fn poll_sdo(
+        &mut self,
+        socket: &AsyncFd<CanSocket>,
+        batch: &mut DataBatch,
+        failures: &mut Vec<PointFailure>,
+    ) {
+        let Some(heartbeat_timeout) = self.config.canopen().map(|c| c.heartbeat_timeout) else {
+            return;
+        };
+        let objects = self.sdo_inputs.clone();
+        for (internal_id, object) in objects {
+            let state =
+                self.shared().canopen.as_ref().and_then(|monitor| {
+                    monitor.state(object.node, Instant::now(), heartbeat_timeout)
+                });
+            if state == Some(NmtState::Stopped) {
+                failures.push(PointFailure::with_error(
+                    internal_id,
+                    format!("node {} is stopped", object.node),
+                ));
+                continue;
+            }
+            let transfer = SdoTransfer::upload(object.node, object.index, object.subindex);
+            match self.sdo(socket, transfer).await {
+                Ok(mut data) => {
+                    // Expedited responses may omit the unused bytes
+                    data.resize(data.len().max(codec::MAX_DATA_LEN), 0);
+                    match object.signal.decode(&data) {
+                        Ok(value) => batch.add(DataPoint::new(internal_id, value)),
+                        Err(e) => {
+                            failures.push(PointFailure::with_error(internal_id, e.to_string()))
+                        },
+                    }
+                },
+                Err(e) => {
+                    let e = self.fail(e);
+                    failures.push(PointFailure::with_error(
+                        internal_id,
+                        format!(
+                            "node {} 0x{:04X}sub{}: {}",
+                            object.node, object.index, object.subindex, e
+                        ),
+                    ));
+                    if self.socket.is_none() {
+                        return;
+                    }
+                },
+            }
+        }
+    }
+
+    /// Write `values`: SDO objects with one transfer each, signals by frame
+    async fn write(&mut self, point_type: PointType, values: &[(u32, f64)]) -> igw::Result<usize> {
+        if self.sdo_outputs.is_empty() {
+            return self.write_frames(point_type, values).await;
+        }
+        let Some(socket) = self.socket.clone() else {
+            return Err(GatewayError::NotConnected);
+        };
+        let key = |internal_id: u32| {
+            point_type.to_internal_id(PointType::from_internal_id(internal_id).1)
+        };
+        let (objects, signals): (Vec<_>, Vec<_>) = values
+            .iter()
+            .partition(|(internal_id, _)| self.sdo_outputs.contains_key(&key(*internal_id)));
+
+        let mut written = 0;
+        let mut last_error = None;
+        if !signals.is_empty() {
+            match self.write_frames(point_type, &signals).await {
+                Ok(count) => written += count,
+                Err(e) if self.socket.is_none() => return Err(e),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        for (internal_id, value) in objects {
+            let Some(object) = self.sdo_outputs.get(&key(internal_id)).cloned() else {
+                continue;
+            };
+            let mut data = vec![0; object.size().unwrap_or(codec::MAX_DATA_LEN)];
+            if let Err(e) = object.signal.encode(value, &mut data) {
+                last_error = Some(GatewayError::InvalidData(format!(
+                    "{}{}: {}",
+                    point_type.as_str(),
+                    PointType::from_internal_id(internal_id).1,
+                    e
+                )));
+                continue;
+            }
+            let transfer = SdoTransfer::download(object.node, object.index, object.subindex, data);
+            match self.sdo(&socket, transfer).await {
+                Ok(_) => {
+                    debug!(
+                        "Ch{} SDO wrote node {} 0x{:04X}sub{} = {}",
+                        self.id, object.node, object.index, object.subindex, value
+                    );
+                    written += 1;
+                    self.diagnostics.write_count += 1;
+                },
+                Err(e) => {
+                    let e = self.fail(e);
+                    if self.socket.is_none() {
+                        return Err(e);
+                    }
+                    last_error = Some(e);
+                },
+            }
+        }
+        match last_error {
+            Some(e) if written == 0 => Err(e),
+            _ => Ok(written),
+        }
+    }
+
+    /// Encode `values` into their frames and send each changed frame once
+    async fn write_frames(
+        &mut self,
+        point_type: PointType,
+        values: &[(u32, f64)],
+    ) -> igw::Result<usize> {
+        let Some(socket) = self.socket.clone() else {
+            return Err(GatewayError::NotConnected);
+        };
+        self.refresh_tables();
+        let mut frames: BTreeMap<FrameId, (Vec<u8>, usize)> = BTreeMap::new();
+        let mut last_error = None;
+        for &(internal_id, value) in values {
+            let point_id = PointType::from_internal_id(internal_id).1;
+            let targets = match point_type {
+                PointType::Control => &self.tables.controls,
+                _ => &self.tables.adjustments,
+            };
+            let Some(signal) = targets.get(&point_id) else {
+                last_error = Some(GatewayError::PointNotFound(format!(
+                    "{}{}",
+                    point_type.as_str(),
+                    point_id
+                )));
+                continue;
+            };
+            let (data, count) = frames
+                .entry(signal.frame)
+                .or_insert_with(|| (self.tx.get(&signal.frame).cloned().unwrap_or_default(), 0));
+            match signal.encode(value, data) {
+                Ok(()) => *count += 1,
+                Err(e) => {
+                    last_error = Some(GatewayError::InvalidData(format!(
+                        "{}{}: {}",
+                        point_type.as_str(),
+                        point_id,
+                        e
+                    )))
+                },
+            }
+        }
+
+        let mut written = 0;
+        for (frame_id, (data, count)) in frames {
+            if count == 0 {
+                continue;
+            }
+            // J1939 frames are sent from the claimed source address
+            let target = if self.config.j1939().is_some() {
+                let claimed = self
+                    .shared()
+                    .j1939
+                    .as_ref()
+                    .and_then(|stack| stack.output_frame(frame_id));
+                match claimed {
+                    Some(target) => target,
+                    None => {
+                        last_error = Some(GatewayError::Protocol(
+                            "J1939 source address not claimed".to_string(),
+                        ));
+                        continue;
+                    },
+                }
+            } else {
+                frame_id
+            };
+            match send(&socket, target, &data, self.config.write_timeout).await {
+                Ok(()) => {
+                    debug!("Ch{} CAN sent {} {:02X?}", self.id, target, data);
+                    self.tx.insert(frame_id, data);
+                    written += count;
+                },
+                Err(e) => {
+                    let e = self.fail(e);
+                    if self.socket.is_none() {
+                        return Err(e);
+                    }
+                    last_error = Some(e);
+                },
+            }
+        }
+        self.diagnostics.write_count += written as u64;
+        match last_error {
+            Some(e) if written == 0 => Err(e),
+            _ => Ok(written),
+        }
+    }
+}
+
+impl Drop for SocketCanRuntime {
+    fn drop(&mut self) {
+        self.close();
+        // A restarted channel may already have registered its own tables
+        if let Some((dbc, _)) = &self.dbc {
+            dbc_registry().remove_if(&self.id, |_, current| Arc::ptr_eq(current, dbc));
+        }
+    }
+}
+
+fn data_frame(frame_id: FrameId, data: &[u8]) -> igw::Result<CanDataFrame> {
+    let id = if frame_id.extended {
+        ExtendedId::new(frame_id.id).map(Id::Extended)
+    } else {
+        StandardId::new(frame_id.id as u16).map(Id::Standard)
+    }
+    .ok_or_else(|| GatewayError::InvalidAddress(frame_id.to_string()))?;
+    CanDataFrame::new(id, data)
+        .ok_or_else(|| GatewayError::InvalidData(format!("{} data too long", frame_id)))
+}
+
+/// Transmit one data frame, waiting for room in the socket queue
+async fn send(
+    socket: &AsyncFd<CanSocket>,
+    frame_id: FrameId,
+    data: &[u8],
+    timeout: Duration,
+) -> igw::Result<()> {
+    let frame = data_frame(frame_id, data)?;
+
+    let sent = tokio::time::timeout(timeout, async {
+        loop {
+            let mut guard = socket.writable().await?;
+            match guard.try_io(|fd| fd.get_ref().write_frame(&frame)) {
+                Ok(result) => return result,
+                Err(_would_block) => continue,
+            }
+        }
+    })
+    .await;
+    match sent {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => Err(socket_error(e)),
+        Err(_) => Err(GatewayError::WriteTimeout),
+    }
+}
+
+/// Interface down or removed closes the channel; other socket errors
+/// (full transmit queue) fail only the operation
+fn socket_error(error: io::Error) -> GatewayError {
+    match error.kind() {
+        io::ErrorKind::NetworkDown | io::ErrorKind::NotFound => {
+            GatewayError::Connection(error.to_string())
+        },
+        _ if error.raw_os_error() == Some(19) => GatewayError::Connection(error.to_string()), // ENODEV
+        _ => GatewayError::Io(error),
+    }
+}
+
+/// Receive task: keeps the latest data of the wanted messages and the bus
+/// state; J1939 channels also answer address claims and transport sessions,
+/// CANopen channels track heartbeats and hand SDO responses to the client
+async fn receive(channel_id: u32, socket: Arc<AsyncFd<CanSocket>>, shared: Arc<Mutex<Shared>>) {
+    loop {
+        let result = match socket.readable().await {
+            Ok(mut guard) => match guard.try_io(|fd| fd.get_ref().read_frame()) {
+                Ok(result) => result,
+                Err(_would_block) => continue,
+            },
+            Err(e) => Err(e),
+        };
+        let mut shared = shared.lock().unwrap_or_else(|e| e.into_inner());
+        match result {
+            Ok(CanFrame::Data(frame)) => {
+                shared.bus.frame_received();
+                let id = FrameId {
+                    id: frame.raw_id(),
+                    extended: frame.is_extended(),
+                };
+                let now = Instant::now();
+                if let Some(monitor) = shared.canopen.as_mut() {
+                    if monitor.receive(id, frame.data(), now) {
+                        continue;
+                    }
+                    let response = canopen::function_of(id.id)
+                        .filter(|(function, _)| !id.extended && *function == canopen::SDO_RESPONSE);
+                    if let Some((_, node)) = response {
+                        if let Some((_, waiter)) =
+                            shared.sdo.as_ref().filter(|(waiting, _)| *waiting == node)
+                        {
+                            // The transfer may just have timed out
+                            let _sent = waiter.send(frame.data().to_vec());
+                        }
+                        continue;
+                    }
+                }
+                let Some(stack) = shared.j1939.as_mut() else {
+                    // Kernel filters fall back to accept-all for large ID sets
+                    let key = MessageKey::Frame(id);
+                    if shared.wanted.contains(&key) {
+                        shared.frames.insert(key, frame.data().to_vec());
+                    }
+                    continue;
+                };
+                if !id.extended {
+                    continue;
+                }
+                let (message, replies) = stack.receive(id.id, frame.data(), now);
+                // Handshake frames must not wait for the next poll
+                for (reply_id, data) in replies {
+                    let sent = data_frame(reply_id, &data).and_then(|reply| {
+                        socket
+                            .get_ref()
+                            .write_frame(&reply)
+                            .map_err(GatewayError::Io)
+                    });
+                    if let Err(e) = sent {
+                        debug!("Ch{} J1939 send {} failed: {}", channel_id, reply_id, e);
+                    }
+                }
+                if let Some(message) = message {
+                    for address in [Some(message.source), None] {
+                        let key = MessageKey::Pgn(PgnKey {
+                            pgn: message.pgn,
+                            address,
+                        });
+                        if shared.wanted.contains(&key) {
+                            shared.frames.insert(key, message.data.clone());
+                        }
+                    }
+                }
+            },
+            Ok(CanFrame::Remote(_)) => {},
+            Ok(CanFrame::Error(frame)) => {
+                let error = frame.into_error();
+                debug!("Ch{} CAN error frame: {}", channel_id, error);
+                shared.bus.record(&error);
+            },
+            Err(e) => {
+                warn!("Ch{} CAN receive stopped: {}", channel_id, e);
+                shared.closed = Some(e.to_string());
+                return;
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl ChannelRuntime for SocketCanRuntime {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn protocol(&self) -> &str {
+        PROTOCOL
+    }
+
+    /// Received frames are drained by `poll_once`
+    fn is_event_driven(&self) -> bool {
+        false
+    }
+
+    async fn connect(&mut self) -> igw::Result<()> {
+        self.close();
+        self.diagnostics.connection_state = ConnectionState::Connecting;
+        let socket = match self.open().and_then(AsyncFd::new) {
+            Ok(socket) => Arc::new(socket),
+            Err(e) => {
+                return Err(self.fail(GatewayError::Connection(format!(
+                    "{}: {}",
+                    self.config.device, e
+                ))))
+            },
+        };
+        *self.shared() = Shared {
+            j1939: self.config.j1939().map(J1939Stack::new),
+            canopen: self.config.canopen().map(|_| NodeMonitor::default()),
+            ..Shared::default()
+        };
+        if self.config.canopen().is_some_and(|config| config.nmt_start) {
+            let (frame_id, data) = canopen::nmt_start_all();
+            let sent = data_frame(frame_id, &data).and_then(|frame| {
+                socket
+                    .get_ref()
+                    .write_frame(&frame)
+                    .map_err(GatewayError::Io)
+            });
+            if let Err(e) = sent {
+                warn!("Ch{} CANopen NMT start failed: {}", self.id, e);
+            }
+        }
+        if let Some(stack) = &self.shared().j1939 {
+            // The claim is sent before any command leaves the node
+            let (frame_id, data) = stack.claim();
+            let sent = data_frame(frame_id, &data).and_then(|frame| {
+                socket
+                    .get_ref()
+                    .write_frame(&frame)
+                    .map_err(GatewayError::Io)
+            });
+            if let Err(e) = sent {
+                warn!("Ch{} J1939 address claim failed: {}", self.id, e);
+            }
+        }
+        self.reader = Some(tokio::spawn(receive(
+            self.id,
+            Arc::clone(&socket),
+            Arc::clone(&self.shared),
+        )));
+        self.socket = Some(socket);
+        self.diagnostics.connection_state = ConnectionState::Connected;
+        info!(
+            "Ch{} CAN opened {}, {} input frames, {} output frames",
+            self.id,
+            self.config.device,
+            self.tables.inputs.len(),
+            self.tx.len()
+        );
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> igw::Result<()> {
+        self.close();
+        self.diagnostics.connection_state = ConnectionState::Disconnected;
+        Ok(())
+    }
+
+    async fn poll_once(&mut self) -> PollResult {
+        let Some(socket) = self.socket.clone() else {
+            return PollResult::failed(vec![PointFailure::new(0, "not connected")]);
+        };
+        self.refresh_tables();
+        if self.config.j1939().is_some() {
+            self.j1939_housekeeping(&socket).await;
+        }
+        let (frames, closed, bus_state, new_errors, last_error, extra) = {
+            let mut shared = self.shared();
+            let frames = std::mem::take(&mut shared.frames);
+            let new_errors = std::mem::take(&mut shared.bus.new_errors);
+            let extra = self.diagnostics_extra(&shared);
+            (
+                frames,
+                shared.closed.take(),
+                shared.bus.state,
+                new_errors,
+                shared.bus.last_error.clone(),
+                extra,
+            )
+        };
+        self.diagnostics.extra = extra;
+        if new_errors > 0 {
+            self.diagnostics.error_count += new_errors;
+            self.diagnostics.last_error = last_error;
+        }
+
+        let mut batch = DataBatch::default();
+        let mut failures = Vec::new();
+        let j1939 = self.config.j1939().is_some();
+        for (key, data) in &frames {
+            for (internal_id, signal) in self.tables.inputs.get(key).into_iter().flatten() {
+                if j1939 && signal.value_type == codec::ValueType::Unsigned {
+                    match signal
+                        .raw(data)
+                        .map(|raw| j1939::availability(raw, signal.bit_length))
+                    {
+                        Ok(Availability::NotAvailable) => continue,
+                        Ok(Availability::Error) => {
+                            failures.push(PointFailure::with_error(
+                                *internal_id,
+                                "J1939 error indicator".to_string(),
+                            ));
+                            continue;
+                        },
+                        Ok(Availability::Valid) | Err(_) => {},
+                    }
+                }
+                match signal.decode(data) {
+                    Ok(value) => batch.add(DataPoint::new(*internal_id, value)),
+                    Err(e) => failures.push(PointFailure::with_error(*internal_id, e.to_string())),
+                }
+            }
+        }
+
+        if let Some(config) = self.config.canopen() {
+            let now = Instant::now();
+            let shared = self.shared();
+            if let Some(monitor) = &shared.canopen {
+                for (internal_id, node, condition) in &self.nmt_inputs {
+                    let state = monitor.state(*node, now, config.heartbeat_timeout);
+                    let value = if condition.holds(state) { 1.0 } else { 0.0 };
+                    batch.add(DataPoint::new(*internal_id, value));
+                }
+            }
+        }
+        if closed.is_none() && !self.sdo_inputs.is_empty() {
+            self.poll_sdo(&socket, &mut batch, &mut failures).await;
+        }
+
+        // Frames received before the socket failed are still stored
+        if let Some(e) = closed {
+            let e = self.fail(GatewayError::Connection(e));
+            failures.push(PointFailure::with_error(0, e.to_string()));
+        } else if bus_state.is_fault() {
+            failures.push(PointFailure::with_error(
+                0,
+                format!("CAN bus {}", bus_state.as_str()),
+            ));
+        }
+        self.diagnostics.read_count += 1;
+        PollResult::partial(batch, failures)
+    }
+
+    async fn write_control(&mut self, commands: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Control, commands).await
+    }
+
+    async fn write_adjustment(&mut self, adjustments: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Adjustment, adjustments).await
+    }
+
+    fn subscribe(&self) -> Option<DataEventReceiver> {
+        None
+    }
+
+    async fn start_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn stop_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn diagnostics(&self) -> igw::Result<Diagnostics> {
+        Ok(self.diagnostics.clone())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bus_state_from_error_frames() {
+        let mut monitor = BusMonitor::default();
+        monitor.record(&CanError::ControllerProblem(
+            ControllerProblem::TransmitErrorWarning,
+        ));
+        assert_eq!(monitor.state, BusState::ErrorWarning);
+        assert!(!monitor.state.is_fault());
+
+        monitor.record(&CanError::NoAck);
+        assert_eq!(monitor.state, BusState::ErrorWarning);
+        monitor.record(&CanError::BusOff);
+        assert!(monitor.state.is_fault());
+        assert_eq!(monitor.error_frames, 3);
+        assert_eq!(monitor.last_error.as_deref(), Some("bus off"));
+
+        // Receiving again means the controller was restarted
+        monitor.frame_received();
+        assert_eq!(monitor.state, BusState::ErrorActive);
+        monitor.record(&CanError::ControllerProblem(
+            ControllerProblem::ReceiveErrorPassive,
+        ));
+        assert_eq!(monitor.state, BusState::ErrorPassive);
+        monitor.frame_received();
+        assert_eq!(monitor.state, BusState::ErrorPassive);
+        monitor.record(&CanError::Restarted);
+        assert_eq!(monitor.state, BusState::ErrorActive);
+    }
+
+    #[test]
+    fn test_config_defaults() {
+        let config = CanConfig::from_parameters(&HashMap::new());
+        assert_eq!(config.device, DEFAULT_DEVICE);
+        assert!(config.error_frames);
+
+        let parameters: HashMap<String, JsonValue> = [
+            ("can_interface".to_string(), json!("vcan0")),
+            ("error_frames".to_string(), json!(false)),
+            ("write_timeout_ms".to_string(), json!("250")),
+        ]
+        .into_iter()
+        .collect();
+        let config = CanConfig::from_parameters(&parameters);
+        assert_eq!(config.device, "vcan0");
+        assert!(!config.error_frames);
+        assert_eq!(config.write_timeout, Duration::from_millis(250));
+        assert_eq!(config.mode, CanMode::Raw);
+    }
+
+    #[test]
+    fn test_canopen_config() {
+        let parameters: HashMap<String, JsonValue> = [
+            ("mode".to_string(), json!("CANopen")),
+            (
+                "eds_files".to_string(),
+                json!("5=/etc/eds/io.eds, 0x10=/etc/eds/drive.eds, 200=/bad.eds"),
+            ),
+            ("sdo_timeout_ms".to_string(), json!(200)),
+            ("nmt_start".to_string(), json!("true")),
+        ]
+        .into_iter()
+        .collect();
+        let config = CanConfig::from_parameters(&parameters);
+        let canopen = config.canopen().unwrap();
+        assert_eq!(
+            canopen.eds_files,
+            [
+                (5, PathBuf::from("/etc/eds/io.eds")),
+                (16, PathBuf::from("/etc/eds/drive.eds")),
+            ]
+            .into_iter()
+            .collect()
+        );
+        assert_eq!(canopen.sdo_timeout, Duration::from_millis(200));
+        assert_eq!(
+            canopen.heartbeat_timeout,
+            CanOpenConfig::default().heartbeat_timeout
+        );
+        assert!(canopen.nmt_start);
+        assert!(config.j1939().is_none());
+    }
+
+    #[test]
+    fn test_dbc_reload_reports_points() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let first = dir.path().join("first.dbc");
+        let second = dir.path().join("second.dbc");
+        std::fs::write(
+            &first,
+            "BO_ 256 Status: 8 BMS\n \
+             SG_ Voltage : 0|16@1+ (0.1,0) [0|0] \"V\" X\n \
+             SG_ Current : 16|16@1- (0.1,0) [0|0] \"A\" X\n",
+        )
+        .unwrap();
+        std::fs::write(
+            &second,
+            "BO_ 256 Status: 8 BMS\n \
+             SG_ Voltage : 0|16@1+ (0.01,0) [0|0] \"V\" X\n \
+             SG_ Soc : 32|8@1+ (1,0) [0|100] \"%\" X\n",
+        )
+        .unwrap();
+
+        let point = |point_type, point_id, mapping| RawPoint {
+            point_type,
+            point_id,
+            mapping,
+        };
+        let points = vec![
+            point(PointType::Telemetry, 1, json!({"signal": "Status.Voltage"})),
+            point(PointType::Telemetry, 2, json!({"signal": "Current"})),
+            point(PointType::Telemetry, 3, json!({"signal": "Soc"})),
+            point(
+                PointType::Signal,
+                1,
+                json!({"can_id": 257, "start_bit": 0, "bit_length": 1}),
+            ),
+        ];
+        let dbc = Dbc::load(&first).unwrap();
+        let (tables, resolved) = raw_tables(9, &points, Some(&dbc));
+        let frame = MessageKey::Frame(FrameId::new(256, false).unwrap());
+        assert_eq!(tables.inputs[&frame].len(), 2);
+        assert_eq!(resolved.len(), 3);
+
+        let dbc_tables = DbcTables {
+            channel_id: 9,
+            points,
+            state: Mutex::new(DbcState {
+                file: Some(first),
+                generation: 0,
+                tables: Arc::new(tables),
+                resolved,
+            }),
+        };
+        let report = dbc_tables.reload(&second).unwrap();
+        assert_eq!(report.points, 3);
+        assert_eq!(report.added, vec!["T3"]);
+        assert_eq!(report.removed, vec!["T2"]);
+        assert_eq!(report.changed, vec!["T1"]);
+        let (generation, tables) = dbc_tables.snapshot();
+        assert_eq!(generation, 1);
+        let ids: Vec<u32> = tables.inputs[&frame].iter().map(|(id, _)| *id).collect();
+        assert_eq!(
+            ids,
+            vec![
+                PointType::Telemetry.to_internal_id(1),
+                PointType::Telemetry.to_internal_id(3)
+            ]
+        );
+
+        // A bad file keeps the current tables
+        assert!(dbc_tables.reload(&dir.path().join("missing.dbc")).is_err());
+        assert_eq!(dbc_tables.snapshot().0, 1);
+        assert_eq!(dbc_tables.file(), Some(second));
+    }
+}
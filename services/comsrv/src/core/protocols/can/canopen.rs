@@ -0,0 +1,704 @@
+//! CANopen (CiA 301) client
+//!
+//! The channel acts as CANopen master towards the nodes its points name:
+//!
+//! - Transmit PDOs are ordinary data frames: an object mapped into a TPDO of
+//!   the node's EDS (see [`eds`](super::eds)) becomes a little-endian
+//!   [`Signal`] of the PDO's COB-ID.
+//! - Objects not mapped into a PDO are read by SDO upload each poll, and
+//!   commands write objects by SDO download. Expedited and segmented
+//!   transfers are supported; [`SdoTransfer`] is the client state machine.
+//! - Heartbeat (`0x700 + node`) and emergency (`0x080 + node`) messages are
+//!   tracked by [`NodeMonitor`] for the NMT state points.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use serde_json::Value as JsonValue;
+
+use super::codec::{mapping_integer, FrameId, Signal, MAX_DATA_LEN};
+use super::eds::{self, Eds};
+use crate::core::protocols::{error, CodecError};
+
+type Result<T> = std::result::Result<T, CodecError>;
+
+/// Function codes of the pre-defined connection set (COB-ID = code + node)
+pub const NMT: u32 = 0x000;
+pub const EMERGENCY: u32 = 0x080;
+pub const SDO_RESPONSE: u32 = 0x580;
+pub const SDO_REQUEST: u32 = 0x600;
+pub const HEARTBEAT: u32 = 0x700;
+
+/// Highest node ID
+pub const MAX_NODE_ID: u8 = 127;
+
+pub const DEFAULT_HEARTBEAT_TIMEOUT_MS: u64 = 3000;
+pub const DEFAULT_SDO_TIMEOUT_MS: u64 = 500;
+
+/// SDO abort codes sent by the client
+pub const ABORT_TIMEOUT: u32 = 0x0504_0000;
+pub const ABORT_COMMAND: u32 = 0x0504_0001;
+
+/// CANopen part of a `mode: canopen` channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct CanOpenConfig {
+    /// Electronic data sheet of each node
+    pub eds_files: BTreeMap<u8, PathBuf>,
+    /// A node without heartbeat for this long is offline
+    pub heartbeat_timeout: Duration,
+    pub sdo_timeout: Duration,
+    /// Send NMT "start remote node" to all nodes on connect
+    pub nmt_start: bool,
+}
+
+impl Default for CanOpenConfig {
+    fn default() -> Self {
+        Self {
+            eds_files: BTreeMap::new(),
+            heartbeat_timeout: Duration::from_millis(DEFAULT_HEARTBEAT_TIMEOUT_MS),
+            sdo_timeout: Duration::from_millis(DEFAULT_SDO_TIMEOUT_MS),
+            nmt_start: false,
+        }
+    }
+}
+
+/// COB-ID of a function code for `node`
+pub fn cob_id(function: u32, node: u8) -> FrameId {
+    FrameId {
+        id: function + u32::from(node),
+        extended: false,
+    }
+}
+
+/// NMT "start remote node" addressed to all nodes
+pub fn nmt_start_all() -> (FrameId, [u8; 2]) {
+    (cob_id(NMT, 0), [0x01, 0x00])
+}
+
+// ============================================================================
+// NMT state
+// ============================================================================
+
+/// NMT state reported by a node's heartbeat
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NmtState {
+    BootUp,
+    Stopped,
+    Operational,
+    PreOperational,
+}
+
+impl NmtState {
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte & 0x7F {
+            0x00 => Some(Self::BootUp),
+            0x04 => Some(Self::Stopped),
+            0x05 => Some(Self::Operational),
+            0x7F => Some(Self::PreOperational),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::BootUp => "boot_up",
+            Self::Stopped => "stopped",
+            Self::Operational => "operational",
+            Self::PreOperational => "pre_operational",
+        }
+    }
+}
+
+/// What an NMT signal point reports as 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NmtCondition {
+    /// Heartbeat received within the timeout
+    Online,
+    /// Online in this state
+    State(NmtState),
+}
+
+impl NmtCondition {
+    fn parse(text: &str) -> Result<Self> {
+        match text.trim().to_ascii_lowercase().as_str() {
+            "online" | "alive" => Ok(Self::Online),
+            "operational" => Ok(Self::State(NmtState::Operational)),
+            "pre_operational" | "preoperational" => Ok(Self::State(NmtState::PreOperational)),
+            "stopped" => Ok(Self::State(NmtState::Stopped)),
+            other => Err(error(format!("unknown nmt condition '{}'", other))),
+        }
+    }
+
+    pub fn holds(&self, state: Option<NmtState>) -> bool {
+        match self {
+            Self::Online => state.is_some(),
+            Self::State(wanted) => state == Some(*wanted),
+        }
+    }
+}
+
+/// Last heartbeat of a node
+#[derive(Debug, Clone, Copy)]
+struct Heartbeat {
+    state: NmtState,
+    at: Instant,
+}
+
+/// Last emergency of a node
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Emergency {
+    pub error_code: u16,
+    pub error_register: u8,
+    pub count: u64,
+}
+
+/// Heartbeats and emergencies maintained by the receive task
+#[derive(Debug, Default)]
+pub struct NodeMonitor {
+    heartbeats: BTreeMap<u8, Heartbeat>,
+    pub emergencies: BTreeMap<u8, Emergency>,
+}
+
+impl NodeMonitor {
+    /// Record a heartbeat or emergency frame; other frames return false
+    pub fn receive(&mut self, id: FrameId, data: &[u8], now: Instant) -> bool {
+        if id.extended {
+            return false;
+        }
+        match function_of(id.id) {
+            Some((HEARTBEAT, node)) => {
+                if let Some(state) = data.first().copied().and_then(NmtState::from_byte) {
+                    self.heartbeats.insert(node, Heartbeat { state, at: now });
+                }
+                true
+            },
+            Some((EMERGENCY, node)) => {
+                let emergency = self.emergencies.entry(node).or_default();
+                emergency.error_code = u16::from_le_bytes([
+                    data.first().copied().unwrap_or_default(),
+                    data.get(1).copied().unwrap_or_default(),
+                ]);
+                emergency.error_register = data.get(2).copied().unwrap_or_default();
+                emergency.count += 1;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// State of `node`, or None without heartbeat within `timeout`
+    pub fn state(&self, node: u8, now: Instant, timeout: Duration) -> Option<NmtState> {
+        self.heartbeats
+            .get(&node)
+            .filter(|heartbeat| now.duration_since(heartbeat.at) <= timeout)
+            .map(|heartbeat| heartbeat.state)
+    }
+}
+
+/// (function code, node) of a standard COB-ID with a node part
+pub fn function_of(id: u32) -> Option<(u32, u8)> {
+    let function = id & 0x780;
+    let node = (id & 0x7F) as u8;
+    (node != 0 && id <= 0x7FF).then_some((function, node))
+}
+
+// ============================================================================
+// Point mapping
+// ============================================================================
+
+/// Object in a node's dictionary accessed by SDO
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdoObject {
+    pub node: u8,
+    pub index: u16,
+    pub subindex: u8,
+    /// Layout of the value from byte 0 of the transferred data
+    pub signal: Signal,
+}
+
+impl SdoObject {
+    /// Bytes transferred for the object
+    pub fn size(&self) -> Result<usize> {
+        self.signal.data_len()
+    }
+}
+
+/// Source or target of a `mode: canopen` point
+#[derive(Debug, Clone, PartialEq)]
+pub enum CanOpenPoint {
+    /// NMT state of a node as a signal
+    Nmt {
+        node: u8,
+        condition: NmtCondition,
+    },
+    /// Signal of a PDO frame
+    Pdo(Signal),
+    Sdo(SdoObject),
+}
+
+impl CanOpenPoint {
+    /// Point of a mapping: `node_id` with `nmt`, or `index`/`subindex`
+    /// (`data_type` defaults to the EDS entry's), or an explicit `can_id`
+    /// signal layout for PDOs the EDS does not describe
+    pub fn from_mapping(
+        mapping: &JsonValue,
+        output: bool,
+        dictionaries: &HashMap<u8, Eds>,
+    ) -> Result<Self> {
+        if mapping_integer(mapping, "can_id")?.is_some() {
+            return Signal::from_mapping(mapping).map(Self::Pdo);
+        }
+        let node = match mapping_integer(mapping, "node_id")? {
+            Some(node) if (1..=u64::from(MAX_NODE_ID)).contains(&node) => node as u8,
+            Some(node) => return Err(error(format!("node_id {} must be 1-127", node))),
+            None => return Err(error("missing 'node_id'")),
+        };
+        if let Some(condition) = mapping.get("nmt").and_then(|v| v.as_str()) {
+            if output {
+                return Err(error("NMT state points are read-only"));
+            }
+            return Ok(Self::Nmt {
+                node,
+                condition: NmtCondition::parse(condition)?,
+            });
+        }
+
+        let index = mapping_integer(mapping, "index")?.ok_or_else(|| error("missing 'index'"))?;
+        let index = u16::try_from(index)
+            .map_err(|_| error(format!("index 0x{:X} exceeds 16 bits", index)))?;
+        let subindex = mapping_integer(mapping, "subindex")?.unwrap_or(0);
+        let subindex = u8::try_from(subindex)
+            .map_err(|_| error(format!("subindex {} exceeds 255", subindex)))?;
+        let dictionary = dictionaries.get(&node);
+        let object = format!("node {} object 0x{:04X}sub{}", node, index, subindex);
+
+        let mut layout = mapping.as_object().cloned().unwrap_or_default();
+        let has_type = layout
+            .get("data_type")
+            .and_then(|v| v.as_str())
+            .is_some_and(|s| !s.trim().is_empty());
+        if !has_type {
+            let data_type = dictionary
+                .and_then(|eds| eds.entry(index, subindex))
+                .and_then(|entry| entry.data_type)
+                .and_then(eds::data_type_name)
+                .ok_or_else(|| error(format!("{}: missing 'data_type'", object)))?;
+            layout.insert("data_type".into(), data_type.into());
+        }
+        layout.insert("byte_order".into(), "intel".into());
+
+        // Inputs mapped into a TPDO are received without SDO traffic
+        let pdo = dictionary.filter(|_| !output).and_then(|eds| {
+            eds.tpdos(node).into_iter().find_map(|pdo| {
+                pdo.objects
+                    .iter()
+                    .find(|o| o.index == index && o.subindex == subindex)
+                    .map(|o| (pdo.cob_id, *o))
+            })
+        });
+        if let Some((cob_id, mapped)) = pdo {
+            layout.insert("start_bit".into(), mapped.offset.into());
+            layout.insert("bit_length".into(), mapped.bits.into());
+            let frame = FrameId::new(cob_id, false)?;
+            let signal = Signal::from_layout(&JsonValue::Object(layout), frame, MAX_DATA_LEN)
+                .map_err(|e| error(format!("{} in PDO {}: {}", object, frame, e)))?;
+            return Ok(Self::Pdo(signal));
+        }
+
+        layout.insert("start_bit".into(), 0.into());
+        let signal = Signal::from_layout(
+            &JsonValue::Object(layout),
+            cob_id(SDO_REQUEST, node),
+            MAX_DATA_LEN,
+        )
+        .map_err(|e| error(format!("{}: {}", object, e)))?;
+        Ok(Self::Sdo(SdoObject {
+            node,
+            index,
+            subindex,
+            signal,
+        }))
+    }
+}
+
+// ============================================================================
+// SDO client
+// ============================================================================
+
+/// Failure of an SDO transfer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SdoError {
+    /// Transfer aborted by the server
+    Abort(u32),
+    /// Response the client cannot continue with; the client aborts
+    Unexpected(String),
+}
+
+impl std::fmt::Display for SdoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Abort(code) => write!(f, "SDO abort 0x{:08X}: {}", code, abort_reason(*code)),
+            Self::Unexpected(reason) => write!(f, "SDO protocol error: {}", reason),
+        }
+    }
+}
+
+/// Description of the common CiA 301 abort codes
+pub fn abort_reason(code: u32) -> &'static str {
+    match code {
+        0x0503_0000 => "toggle bit not alternated",
+        0x0504_0000 => "SDO protocol timed out",
+        0x0504_0001 => "command specifier not valid",
+        0x0601_0000 => "unsupported access to an object",
+        0x0601_0001 => "attempt to read a write only object",
+        0x0601_0002 => "attempt to write a read only object",
+        0x0602_0000 => "object does not exist",
+        0x0604_0041 => "object cannot be mapped to the PDO",
+        0x0607_0010 => "data type does not match",
+        0x0607_0012 => "data type does not match, length too high",
+        0x0607_0013 => "data type does not match, length too low",
+        0x0609_0011 => "sub-index does not exist",
+        0x0609_0030 => "invalid value",
+        0x0609_0031 => "value too high",
+        0x0609_0032 => "value too low",
+        0x0800_0000 => "general error",
+        0x0800_0020 => "data cannot be transferred or stored",
+        0x0800_0021 => "data cannot be transferred because of local control",
+        0x0800_0022 => "data cannot be transferred in the present device state",
+        _ => "unknown abort code",
+    }
+}
+
+/// Next action of an SDO transfer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SdoStep {
+    /// Send this request and wait for the response
+    Request([u8; 8]),
+    /// Transfer complete, with the uploaded data (empty for downloads)
+    Done(Vec<u8>),
+}
+
+#[derive(Debug, Clone)]
+enum Direction {
+    Upload { size: Option<usize> },
+    Download { sent: usize },
+}
+
+/// Client side of one SDO upload or download
+#[derive(Debug, Clone)]
+pub struct SdoTransfer {
+    node: u8,
+    index: u16,
+    subindex: u8,
+    direction: Direction,
+    data: Vec<u8>,
+    toggle: bool,
+    /// The initiate response has been received
+    initiated: bool,
+}
+
+impl SdoTransfer {
+    pub fn upload(node: u8, index: u16, subindex: u8) -> Self {
+        Self {
+            node,
+            index,
+            subindex,
+            direction: Direction::Upload { size: None },
+            data: Vec::new(),
+            toggle: false,
+            initiated: false,
+        }
+    }
+
+    pub fn download(node: u8, index: u16, subindex: u8, data: Vec<u8>) -> Self {
+        Self {
+            node,
+            index,
+            subindex,
+            direction: Direction::Download { sent: 0 },
+            data,
+            toggle: false,
+            initiated: false,
+        }
+    }
+
+    pub fn node(&self) -> u8 {
+        self.node
+    }
+
+    pub fn request_id(&self) -> FrameId {
+        cob_id(SDO_REQUEST, self.node)
+    }
+
+    fn multiplexer(&self) -> [u8; 3] {
+        let [lo, hi] = self.index.to_le_bytes();
+        [lo, hi, self.subindex]
+    }
+
+    fn initiate_frame(&self, command: u8, payload: [u8; 4]) -> [u8; 8] {
+        let [lo, hi, sub] = self.multiplexer();
+        [
+            command, lo, hi, sub, payload[0], payload[1], payload[2], payload[3],
+        ]
+    }
+
+    /// Initiate request of the transfer
+    pub fn start(&self) -> [u8; 8] {
+        match self.direction {
+            Direction::Upload { .. } => self.initiate_frame(0x40, [0; 4]),
+            Direction::Download { .. } if self.data.len() <= 4 => {
+                // Expedited, size indicated: n = unused bytes
+                let mut payload = [0; 4];
+                payload[..self.data.len()].copy_from_slice(&self.data);
+                let n = (4 - self.data.len()) as u8;
+                self.initiate_frame(0x23 | (n << 2), payload)
+            },
+            Direction::Download { .. } => {
+                self.initiate_frame(0x21, (self.data.len() as u32).to_le_bytes())
+            },
+        }
+    }
+
+    /// Abort request of the transfer
+    pub fn abort(&self, code: u32) -> [u8; 8] {
+        self.initiate_frame(0x80, code.to_le_bytes())
+    }
+
+    /// Advance the transfer with a response of the server
+    pub fn on_response(&mut self, response: &[u8]) -> std::result::Result<SdoStep, SdoError> {
+        let unexpected = |reason: &str| Err(SdoError::Unexpected(reason.to_string()));
+        if response.len() < MAX_DATA_LEN {
+            return unexpected("short response");
+        }
+        let command = response[0];
+        if command >> 5 == 4 {
+            let code = u32::from_le_bytes([response[4], response[5], response[6], response[7]]);
+            return Err(SdoError::Abort(code));
+        }
+        if !self.initiated {
+            if response[1..4] != self.multiplexer() {
+                return unexpected("response to another object");
+            }
+            self.initiated = true;
+            return match self.direction {
+                Direction::Upload { .. } if command >> 5 == 2 => self.upload_initiated(response),
+                Direction::Download { .. } if command >> 5 == 3 => self.next_segment(),
+                _ => unexpected("unexpected command specifier"),
+            };
+        }
+
+        let toggle = command & 0x10 != 0;
+        match self.direction {
+            Direction::Upload { size } if command >> 5 == 0 => {
+                if toggle != self.toggle {
+                    return unexpected("toggle bit not alternated");
+                }
+                let unused = usize::from((command >> 1) & 0x07);
+                self.data.extend_from_slice(&response[1..8 - unused]);
+                self.toggle = !self.toggle;
+                if command & 0x01 == 0 {
+                    return Ok(SdoStep::Request([
+                        0x60 | (u8::from(self.toggle) << 4),
+                        0,
+                        0,
+                        0,
+                        0,
+                        0,
+                        0,
+                        0,
+                    ]));
+                }
+                if size.is_some_and(|size| size != self.data.len()) {
+                    return unexpected("uploaded length differs from the indicated size");
+                }
+                Ok(SdoStep::Done(std::mem::take(&mut self.data)))
+            },
+            Direction::Download { .. } if command >> 5 == 1 => {
+                if toggle != self.toggle {
+                    return unexpected("toggle bit not alternated");
+                }
+                self.toggle = !self.toggle;
+                self.next_segment()
+            },
+            _ => unexpected("unexpected command specifier"),
+        }
+    }
+
+    fn upload_initiated(&mut self, response: &[u8]) -> std::result::Result<SdoStep, SdoError> {
+        let command = response[0];
+        let expedited = command & 0x02 != 0;
+        let size_indicated = command & 0x01 != 0;
+        if expedited {
+            let len = if size_indicated {
+                4 - usize::from((command >> 2) & 0x03)
+            } else {
+                4
+            };
+            return Ok(SdoStep::Done(response[4..4 + len].to_vec()));
+        }
+        let size = size_indicated.then(|| {
+            u32::from_le_bytes([response[4], response[5], response[6], response[7]]) as usize
+        });
+        self.direction = Direction::Upload { size };
+        Ok(SdoStep::Request([0x60, 0, 0, 0, 0, 0, 0, 0]))
+    }
+
+    /// Next download segment, or done once all data is acknowledged
+    fn next_segment(&mut self) -> std::result::Result<SdoStep, SdoError> {
+        let Direction::Download { sent } = self.direction else {
+            return Err(SdoError::Unexpected("not a download".to_string()));
+        };
+        // Expedited downloads are complete with the initiate response
+        if self.data.len() <= 4 || sent >= self.data.len() {
+            return Ok(SdoStep::Done(Vec::new()));
+        }
+        let chunk = &self.data[sent..self.data.len().min(sent + 7)];
+        let last = sent + chunk.len() == self.data.len();
+        let mut frame = [0u8; 8];
+        frame[0] = (u8::from(self.toggle) << 4) | (((7 - chunk.len()) as u8) << 1) | u8::from(last);
+        frame[1..1 + chunk.len()].copy_from_slice(chunk);
+        self.direction = Direction::Download {
+            sent: sent + chunk.len(),
+        };
+        Ok(SdoStep::Request(frame))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sdo_expedited_upload_and_download() {
+        let mut upload = SdoTransfer::upload(5, 0x6401, 1);
+        assert_eq!(upload.request_id(), cob_id(SDO_REQUEST, 5));
+        assert_eq!(upload.start(), [0x40, 0x01, 0x64, 0x01, 0, 0, 0, 0]);
+        // 2 bytes indicated: n = 2
+        let step = upload
+            .on_response(&[0x4B, 0x01, 0x64, 0x01, 0x34, 0x12, 0, 0])
+            .unwrap();
+        assert_eq!(step, SdoStep::Done(vec![0x34, 0x12]));
+
+        let mut download = SdoTransfer::download(5, 0x2000, 0, vec![0xAA, 0xBB]);
+        assert_eq!(download.start(), [0x2B, 0x00, 0x20, 0x00, 0xAA, 0xBB, 0, 0]);
+        let step = download
+            .on_response(&[0x60, 0x00, 0x20, 0x00, 0, 0, 0, 0])
+            .unwrap();
+        assert_eq!(step, SdoStep::Done(Vec::new()));
+
+        let mut aborted = SdoTransfer::upload(5, 0x2001, 0);
+        let error = aborted
+            .on_response(&[0x80, 0x01, 0x20, 0x00, 0x00, 0x00, 0x02, 0x06])
+            .unwrap_err();
+        assert_eq!(error, SdoError::Abort(0x0602_0000));
+        assert!(error.to_string().contains("object does not exist"));
+    }
+
+    #[test]
+    fn test_sdo_segmented_transfers() {
+        let mut upload = SdoTransfer::upload(3, 0x1008, 0);
+        let step = upload
+            .on_response(&[0x41, 0x08, 0x10, 0x00, 10, 0, 0, 0])
+            .unwrap();
+        assert_eq!(step, SdoStep::Request([0x60, 0, 0, 0, 0, 0, 0, 0]));
+        let step = upload.on_response(&[0x00, 1, 2, 3, 4, 5, 6, 7]).unwrap();
+        assert_eq!(step, SdoStep::Request([0x70, 0, 0, 0, 0, 0, 0, 0]));
+        // Toggle 1, 4 unused bytes, last segment
+        let step = upload.on_response(&[0x19, 8, 9, 10, 0, 0, 0, 0]).unwrap();
+        assert_eq!(step, SdoStep::Done((1..=10).collect()));
+
+        let mut repeated = SdoTransfer::upload(3, 0x1008, 0);
+        repeated
+            .on_response(&[0x41, 0x08, 0x10, 0x00, 0, 0, 0, 0])
+            .unwrap();
+        repeated.on_response(&[0x00, 1, 2, 3, 4, 5, 6, 7]).unwrap();
+        assert!(matches!(
+            repeated.on_response(&[0x01, 1, 2, 3, 4, 5, 6, 7]),
+            Err(SdoError::Unexpected(_))
+        ));
+
+        let data: Vec<u8> = (1..=8).collect();
+        let mut download = SdoTransfer::download(3, 0x2100, 2, data);
+        assert_eq!(download.start(), [0x21, 0x00, 0x21, 0x02, 8, 0, 0, 0]);
+        let step = download
+            .on_response(&[0x60, 0x00, 0x21, 0x02, 0, 0, 0, 0])
+            .unwrap();
+        assert_eq!(step, SdoStep::Request([0x00, 1, 2, 3, 4, 5, 6, 7]));
+        let step = download.on_response(&[0x20, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+        // Toggle 1, 6 unused bytes, last segment
+        assert_eq!(step, SdoStep::Request([0x1D, 8, 0, 0, 0, 0, 0, 0]));
+        let step = download.on_response(&[0x30, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+        assert_eq!(step, SdoStep::Done(Vec::new()));
+    }
+
+    #[test]
+    fn test_point_mapping_and_node_monitor() {
+        let eds = Eds::parse(
+            "[1800sub1]\nDefaultValue=$NODEID+0x180\n\
+             [1A00sub0]\nDefaultValue=1\n\
+             [1A00sub1]\nDefaultValue=0x64010110\n\
+             [6401sub1]\nDataType=0x0003\n\
+             [2000]\nDataType=0x0008\n",
+        )
+        .unwrap();
+        let dictionaries: HashMap<u8, Eds> = [(5, eds)].into_iter().collect();
+
+        let pdo = CanOpenPoint::from_mapping(
+            &json!({"node_id": 5, "index": "0x6401", "subindex": 1, "scale": 0.1}),
+            false,
+            &dictionaries,
+        )
+        .unwrap();
+        let CanOpenPoint::Pdo(signal) = pdo else {
+            panic!("expected a PDO signal");
+        };
+        assert_eq!(signal.frame, cob_id(0x180, 5));
+        assert_eq!(signal.decode(&[0x18, 0xFC]).unwrap(), -100.0);
+
+        // Not mapped into a PDO, or written: SDO
+        let sdo = CanOpenPoint::from_mapping(
+            &json!({"node_id": 5, "index": 0x2000}),
+            true,
+            &dictionaries,
+        )
+        .unwrap();
+        let CanOpenPoint::Sdo(object) = sdo else {
+            panic!("expected an SDO object");
+        };
+        assert_eq!((object.index, object.subindex), (0x2000, 0));
+        assert_eq!(object.size().unwrap(), 4);
+        assert!(CanOpenPoint::from_mapping(
+            &json!({"node_id": 6, "index": 0x2000}),
+            false,
+            &dictionaries
+        )
+        .is_err());
+
+        let nmt = CanOpenPoint::from_mapping(
+            &json!({"node_id": 5, "nmt": "operational"}),
+            false,
+            &dictionaries,
+        )
+        .unwrap();
+        let CanOpenPoint::Nmt { node, condition } = nmt else {
+            panic!("expected an NMT point");
+        };
+        let mut monitor = NodeMonitor::default();
+        let start = Instant::now();
+        let timeout = Duration::from_millis(DEFAULT_HEARTBEAT_TIMEOUT_MS);
+        assert!(!condition.holds(monitor.state(node, start, timeout)));
+        assert!(monitor.receive(cob_id(HEARTBEAT, 5), &[0x05], start));
+        assert!(condition.holds(monitor.state(node, start, timeout)));
+        assert!(!condition.holds(monitor.state(node, start + timeout * 2, timeout)));
+        assert!(monitor.receive(
+            cob_id(EMERGENCY, 5),
+            &[0x10, 0x81, 0x11, 0, 0, 0, 0, 0],
+            start
+        ));
+        assert_eq!(monitor.emergencies[&5].error_code, 0x8110);
+        assert!(!monitor.receive(cob_id(0x180, 5), &[0], start));
+    }
+}
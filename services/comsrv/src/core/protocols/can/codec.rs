@@ -0,0 +1,490 @@
+//! CAN signal codec
+//!
+//! Signals are laid out the way DBC files describe them: `start_bit` counts
+//! from bit 0 of byte 0, Intel (little-endian) signals start at their least
+//! significant bit and Motorola (big-endian) signals at their most
+//! significant bit in the sawtooth bit numbering. Raw values are scaled with
+//! `physical = raw * scale + offset`.
+
+use serde_json::Value as JsonValue;
+
+use crate::core::protocols::{error, CodecError};
+
+/// Data bytes of a classic CAN frame
+pub const MAX_DATA_LEN: usize = 8;
+
+/// Extended frame flag of a SocketCAN ID word
+pub const EFF_FLAG: u32 = 0x8000_0000;
+
+/// Remote transmission request flag of a SocketCAN ID word
+pub const RTR_FLAG: u32 = 0x4000_0000;
+
+/// Valid bits of a 29-bit extended ID
+pub const EFF_MASK: u32 = 0x1FFF_FFFF;
+
+/// Valid bits of an 11-bit standard ID
+pub const SFF_MASK: u32 = 0x0000_07FF;
+
+type Result<T> = std::result::Result<T, CodecError>;
+
+/// CAN identifier with its format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FrameId {
+    pub id: u32,
+    pub extended: bool,
+}
+
+impl FrameId {
+    /// ID of a mapping: IDs above 0x7FF, IDs carrying the EFF flag and
+    /// `extended: true` mappings are 29-bit
+    pub fn new(raw: u32, extended: bool) -> Result<Self> {
+        let extended = extended || raw & EFF_FLAG != 0 || raw & !EFF_FLAG > SFF_MASK;
+        let id = raw & !EFF_FLAG;
+        if id > EFF_MASK {
+            return Err(error(format!("CAN ID 0x{:X} exceeds 29 bits", raw)));
+        }
+        Ok(Self { id, extended })
+    }
+
+    /// Kernel filter matching exactly this ID and format (data frames only)
+    pub fn filter(&self) -> (u32, u32) {
+        if self.extended {
+            (self.id | EFF_FLAG, EFF_MASK | EFF_FLAG | RTR_FLAG)
+        } else {
+            (self.id, SFF_MASK | EFF_FLAG | RTR_FLAG)
+        }
+    }
+}
+
+impl std::fmt::Display for FrameId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.extended {
+            write!(f, "0x{:08X}", self.id)
+        } else {
+            write!(f, "0x{:03X}", self.id)
+        }
+    }
+}
+
+/// Bit numbering of a signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// Little-endian; `start_bit` is the least significant bit
+    Intel,
+    /// Big-endian; `start_bit` is the most significant bit
+    Motorola,
+}
+
+/// Interpretation of the raw bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    Unsigned,
+    Signed,
+    Float32,
+    Float64,
+}
+
+/// One signal of a CAN frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signal {
+    pub frame: FrameId,
+    pub start_bit: u16,
+    pub bit_length: u16,
+    pub byte_order: ByteOrder,
+    pub value_type: ValueType,
+    pub scale: f64,
+    pub offset: f64,
+}
+
+impl Signal {
+    /// Signal of a point mapping (`can_id`, `start_bit`, `bit_length`,
+    /// `byte_order`, `data_type`, `signed`, `scale`, `offset`, `extended`)
+    pub fn from_mapping(mapping: &JsonValue) -> Result<Self> {
+        let raw_id =
+            mapping_integer(mapping, "can_id")?.ok_or_else(|| error("missing 'can_id'"))?;
+        let raw_id = u32::try_from(raw_id)
+            .map_err(|_| error(format!("CAN ID 0x{:X} exceeds 29 bits", raw_id)))?;
+        let frame = FrameId::new(raw_id, mapping_flag(mapping, "extended"))?;
+        Self::from_layout(mapping, frame, MAX_DATA_LEN)
+    }
+
+    /// Signal of `frame` laid out by the mapping columns other than the ID,
+    /// in a message of at most `max_len` bytes
+    pub fn from_layout(mapping: &JsonValue, frame: FrameId, max_len: usize) -> Result<Self> {
+        let integer = |key: &str| mapping_integer(mapping, key);
+        let float = |key: &str, default: f64| match mapping.get(key) {
+            Some(JsonValue::Number(n)) => n.as_f64().unwrap_or(default),
+            Some(JsonValue::String(s)) => s.trim().parse().unwrap_or(default),
+            _ => default,
+        };
+        let text = |key: &str| {
+            mapping
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(|s| s.trim().to_ascii_lowercase())
+                .filter(|s| !s.is_empty())
+        };
+        let flag = |key: &str| mapping_flag(mapping, key);
+
+        let byte_order = match text("byte_order").as_deref() {
+            None | Some("intel" | "little_endian" | "little" | "le") => ByteOrder::Intel,
+            Some("motorola" | "big_endian" | "big" | "be") => ByteOrder::Motorola,
+            Some(other) => return Err(error(format!("unknown byte_order '{}'", other))),
+        };
+        let (value_type, type_length) = match text("data_type").as_deref() {
+            None => (ValueType::Unsigned, None),
+            Some("bool" | "boolean") => (ValueType::Unsigned, Some(1)),
+            Some("uint8") => (ValueType::Unsigned, Some(8)),
+            Some("int8") => (ValueType::Signed, Some(8)),
+            Some("uint16") => (ValueType::Unsigned, Some(16)),
+            Some("int16") => (ValueType::Signed, Some(16)),
+            Some("uint32") => (ValueType::Unsigned, Some(32)),
+            Some("int32") => (ValueType::Signed, Some(32)),
+            Some("uint64") => (ValueType::Unsigned, Some(64)),
+            Some("int64") => (ValueType::Signed, Some(64)),
+            Some("unsigned") => (ValueType::Unsigned, None),
+            Some("signed") => (ValueType::Signed, None),
+            Some("float32" | "float") => (ValueType::Float32, Some(32)),
+            Some("float64" | "double") => (ValueType::Float64, Some(64)),
+            Some(other) => return Err(error(format!("unsupported data_type '{}'", other))),
+        };
+        let value_type = match value_type {
+            ValueType::Unsigned if flag("signed") => ValueType::Signed,
+            other => other,
+        };
+        let bit_length = match (integer("bit_length")?, type_length) {
+            (Some(length), _) => length,
+            (None, Some(length)) => length,
+            (None, None) => return Err(error("missing 'bit_length'")),
+        };
+        if !(1..=64).contains(&bit_length) {
+            return Err(error(format!("bit_length {} must be 1-64", bit_length)));
+        }
+        let float_length = match value_type {
+            ValueType::Float32 => Some(32),
+            ValueType::Float64 => Some(64),
+            _ => None,
+        };
+        if float_length.is_some_and(|length| length != bit_length) {
+            return Err(error(format!(
+                "{:?} signal must be {} bits, not {}",
+                value_type,
+                float_length.unwrap_or_default(),
+                bit_length
+            )));
+        }
+        let start_bit = integer("start_bit")?.ok_or_else(|| error("missing 'start_bit'"))?;
+
+        let scale = float("scale", 1.0);
+        if scale == 0.0 || !scale.is_finite() {
+            return Err(error("'scale' must be a non-zero number"));
+        }
+        let signal = Self {
+            frame,
+            start_bit: u16::try_from(start_bit).unwrap_or(u16::MAX),
+            bit_length: bit_length as u16,
+            byte_order,
+            value_type,
+            scale,
+            offset: float("offset", 0.0),
+        };
+        // Rejects signals running past the end of the message
+        signal.message_len(max_len)?;
+        Ok(signal)
+    }
+
+    /// Data bytes a frame needs to carry the signal
+    pub fn data_len(&self) -> Result<usize> {
+        self.message_len(MAX_DATA_LEN)
+    }
+
+    /// Data bytes a message of at most `max_len` bytes needs for the signal
+    pub fn message_len(&self, max_len: usize) -> Result<usize> {
+        let last = self.positions_msb_first().max().unwrap_or_default();
+        if last >= max_len * 8 {
+            return Err(error(format!(
+                "signal at bit {} with {} bits exceeds the {}-byte message",
+                self.start_bit, self.bit_length, max_len
+            )));
+        }
+        Ok(last / 8 + 1)
+    }
+
+    /// Raw bits of the signal in `data`, before sign extension and scaling
+    pub fn raw(&self, data: &[u8]) -> Result<u64> {
+        self.read_raw(data)
+    }
+
+    /// Physical value of the signal in `data`
+    pub fn decode(&self, data: &[u8]) -> Result<f64> {
+        let raw = self.read_raw(data)?;
+        let length = self.bit_length as u32;
+        let value = match self.value_type {
+            ValueType::Unsigned => raw as f64,
+            ValueType::Signed => {
+                let shift = 64 - length;
+                (((raw << shift) as i64) >> shift) as f64
+            },
+            ValueType::Float32 => f32::from_bits(raw as u32) as f64,
+            ValueType::Float64 => f64::from_bits(raw),
+        };
+        Ok(value * self.scale + self.offset)
+    }
+
+    /// Write the physical `value` into `data`, keeping the other bits
+    pub fn encode(&self, value: f64, data: &mut [u8]) -> Result<()> {
+        if !value.is_finite() {
+            return Err(error(format!("value {} is not a number", value)));
+        }
+        let scaled = (value - self.offset) / self.scale;
+        let length = self.bit_length as u32;
+        let raw = match self.value_type {
+            ValueType::Float32 => (scaled as f32).to_bits() as u64,
+            ValueType::Float64 => scaled.to_bits(),
+            ValueType::Unsigned => {
+                let raw = scaled.round();
+                let max = if length == 64 {
+                    u64::MAX as f64
+                } else {
+                    ((1u64 << length) - 1) as f64
+                };
+                if raw < 0.0 || raw > max {
+                    return Err(out_of_range(value, 0.0, max));
+                }
+                raw as u64
+            },
+            ValueType::Signed => {
+                let raw = scaled.round();
+                let max = (i64::MAX >> (64 - length)) as f64;
+                let min = -max - 1.0;
+                if raw < min || raw > max {
+                    return Err(out_of_range(value, min, max));
+                }
+                raw as i64 as u64
+            },
+        };
+        self.write_raw(raw, data)
+    }
+
+    fn read_raw(&self, data: &[u8]) -> Result<u64> {
+        let mut raw = 0u64;
+        for position in self.positions_msb_first() {
+            let byte = data.get(position / 8).ok_or_else(|| {
+                error(format!(
+                    "frame {} has {} bytes, signal needs bit {}",
+                    self.frame,
+                    data.len(),
+                    position
+                ))
+            })?;
+            raw = (raw << 1) | u64::from((byte >> (position % 8)) & 1);
+        }
+        Ok(raw)
+    }
+
+    fn write_raw(&self, raw: u64, data: &mut [u8]) -> Result<()> {
+        let length = self.bit_length as u32;
+        for (index, position) in self.positions_msb_first().enumerate() {
+            let len = data.len();
+            let byte = data.get_mut(position / 8).ok_or_else(|| {
+                error(format!(
+                    "frame {} has {} bytes, signal needs bit {}",
+                    self.frame, len, position
+                ))
+            })?;
+            let bit = (raw >> (length - 1 - index as u32)) & 1;
+            let mask = 1u8 << (position % 8);
+            if bit == 1 {
+                *byte |= mask;
+            } else {
+                *byte &= !mask;
+            }
+        }
+        Ok(())
+    }
+
+    /// Frame bit positions of the signal, most significant bit first
+    fn positions_msb_first(&self) -> impl Iterator<Item = usize> {
+        let start = self.start_bit as usize;
+        let length = self.bit_length as usize;
+        let order = self.byte_order;
+        let mut motorola = start;
+        (0..length).map(move |index| match order {
+            ByteOrder::Intel => start + length - 1 - index,
+            ByteOrder::Motorola => {
+                let position = motorola;
+                motorola = if position.is_multiple_of(8) {
+                    position + 15
+                } else {
+                    position - 1
+                };
+                position
+            },
+        })
+    }
+}
+
+/// Integer cell of a mapping: JSON number, decimal or `0x` hex string
+pub fn mapping_integer(mapping: &JsonValue, key: &str) -> Result<Option<u64>> {
+    match mapping.get(key) {
+        None | Some(JsonValue::Null) => Ok(None),
+        Some(JsonValue::Number(n)) => n
+            .as_u64()
+            .map(Some)
+            .ok_or_else(|| error(format!("'{}' must be a non-negative integer", key))),
+        Some(JsonValue::String(s)) => {
+            let s = s.trim();
+            if s.is_empty() {
+                return Ok(None);
+            }
+            let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+                Some(hex) => u64::from_str_radix(hex, 16).ok(),
+                None => s.parse().ok(),
+            };
+            parsed
+                .map(Some)
+                .ok_or_else(|| error(format!("'{}' is not an integer: {}", key, s)))
+        },
+        Some(other) => Err(error(format!("'{}' is not an integer: {}", key, other))),
+    }
+}
+
+/// Boolean cell of a mapping; missing cells are false
+pub fn mapping_flag(mapping: &JsonValue, key: &str) -> bool {
+    match mapping.get(key) {
+        Some(JsonValue::Bool(b)) => *b,
+        Some(JsonValue::String(s)) => matches!(s.trim(), "true" | "1" | "yes"),
+        Some(JsonValue::Number(n)) => n.as_u64().is_some_and(|n| n != 0),
+        _ => false,
+    }
+}
+
+fn out_of_range(value: f64, min: f64, max: f64) -> CodecError {
+    error(format!(
+        "value {} is outside the raw range {}..={}",
+        value, min, max
+    ))
+}
+
+/// Kernel filters (ID, mask) receiving the data frames of `ids`
+///
+/// No IDs drops everything; more IDs than `max_filters` accept all frames
+/// and leave the selection to userspace.
+pub fn kernel_filters(ids: &[FrameId], max_filters: usize) -> Vec<(u32, u32)> {
+    let mut ids = ids.to_vec();
+    ids.sort();
+    ids.dedup();
+    if ids.len() > max_filters {
+        return vec![(0, 0)];
+    }
+    ids.iter().map(FrameId::filter).collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_intel_and_motorola_layout() {
+        // 16-bit little-endian value at byte 1, as the igw CAN client read it
+        let intel = Signal::from_mapping(&json!({
+            "can_id": 0x351, "start_bit": 8, "bit_length": 16, "scale": 0.1
+        }))
+        .unwrap();
+        let data = [0x00, 0x34, 0x12, 0, 0, 0, 0, 0];
+        assert!((intel.decode(&data).unwrap() - 466.0).abs() < 1e-9);
+        assert_eq!(intel.data_len().unwrap(), 3);
+
+        // DBC "start_bit 7, 16 bits, @0": bytes 0-1 big-endian
+        let motorola = Signal::from_mapping(&json!({
+            "can_id": 0x351, "start_bit": 7, "bit_length": 16, "byte_order": "motorola"
+        }))
+        .unwrap();
+        assert_eq!(motorola.decode(&[0x12, 0x34]).unwrap(), 4660.0);
+        assert_eq!(motorola.data_len().unwrap(), 2);
+
+        // 12-bit Motorola signal across a byte boundary, MSB at bit 3
+        let nibble = Signal::from_mapping(&json!({
+            "can_id": 0x10, "start_bit": 3, "bit_length": 12, "byte_order": "big_endian"
+        }))
+        .unwrap();
+        assert_eq!(nibble.decode(&[0xFA, 0xBC]).unwrap(), 0xABC as f64);
+
+        let mut buffer = [0xFFu8; 2];
+        nibble.encode(0x123 as f64, &mut buffer).unwrap();
+        assert_eq!(buffer, [0xF1, 0x23]);
+
+        // Signal leaving the frame through the last byte
+        assert!(Signal::from_mapping(&json!({
+            "can_id": 1, "start_bit": 56, "bit_length": 16, "byte_order": "motorola"
+        }))
+        .is_err());
+        assert!(
+            Signal::from_mapping(&json!({"can_id": 1, "start_bit": 60, "bit_length": 8})).is_err()
+        );
+    }
+
+    #[test]
+    fn test_encode_round_trip_and_range() {
+        let signed = Signal::from_mapping(&json!({
+            "can_id": "0x18FF50E5", "start_bit": 4, "bit_length": 10,
+            "data_type": "int16", "scale": 0.5, "offset": -10
+        }))
+        .unwrap();
+        assert_eq!(
+            signed.frame,
+            FrameId {
+                id: 0x18FF50E5,
+                extended: true
+            }
+        );
+        assert_eq!(signed.value_type, ValueType::Signed);
+
+        let mut data = [0u8; 8];
+        signed.encode(-100.0, &mut data).unwrap();
+        assert_eq!(signed.decode(&data).unwrap(), -100.0);
+        // raw -512..=511 covers -266..=245.5
+        assert!(signed.encode(246.0, &mut data).is_err());
+        assert!(signed.encode(-266.0, &mut data).is_ok());
+
+        let float = Signal::from_mapping(&json!({
+            "can_id": 0x200, "start_bit": 32, "data_type": "float32"
+        }))
+        .unwrap();
+        float.encode(12.5, &mut data).unwrap();
+        assert_eq!(float.decode(&data).unwrap(), 12.5);
+        assert_eq!(signed.decode(&data).unwrap(), -266.0);
+
+        let flag =
+            Signal::from_mapping(&json!({"can_id": 5, "start_bit": 0, "bit_length": 1})).unwrap();
+        assert!(flag.encode(2.0, &mut data).is_err());
+        assert!(Signal::from_mapping(&json!({
+            "can_id": 5, "start_bit": 0, "bit_length": 16, "data_type": "float32"
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn test_kernel_filters() {
+        let standard = FrameId::new(0x351, false).unwrap();
+        let extended = FrameId::new(0x351, true).unwrap();
+        assert_eq!(
+            FrameId::new(0x8000_0100, false).unwrap(),
+            FrameId {
+                id: 0x100,
+                extended: true
+            }
+        );
+        assert!(FrameId::new(0x2000_0000, false).is_err());
+
+        let filters = kernel_filters(&[standard, extended, standard], 512);
+        assert_eq!(
+            filters,
+            vec![(0x351, 0xC000_07FF), (0x8000_0351, 0xDFFF_FFFF),]
+        );
+        assert_eq!(kernel_filters(&[standard, extended], 1), vec![(0, 0)]);
+        assert!(kernel_filters(&[], 512).is_empty());
+    }
+}
@@ -0,0 +1,305 @@
+//! DBC signal databases
+//!
+//! Raw CAN points can name a signal of the channel's DBC file (`signal`
+//! column, `MESSAGE.SIGNAL` or a signal name unique in the file) instead of
+//! spelling out its layout. Only what the codec can represent is read:
+//!
+//! ```text
+//! BO_ 2364540158 EEC1: 8 Engine
+//!  SG_ EngineSpeed : 24|16@1+ (0.125,0) [0|8031.875] "rpm" Vector__XXX
+//! SIG_VALTYPE_ 2364540158 EngineSpeed : 1;
+//! ```
+//!
+//! Message IDs with bit 31 set are 29-bit. Multiplexed signals and signals
+//! beyond the first 8 bytes are skipped.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::codec::{ByteOrder, FrameId, Signal, ValueType};
+use crate::core::protocols::{error, CodecError};
+
+type Result<T> = std::result::Result<T, CodecError>;
+
+/// Signal of a DBC message
+#[derive(Debug, Clone, PartialEq)]
+pub struct DbcSignal {
+    pub name: String,
+    pub signal: Signal,
+    pub unit: String,
+}
+
+/// Message (`BO_`) with its signals
+#[derive(Debug, Clone, PartialEq)]
+pub struct DbcMessage {
+    pub id: FrameId,
+    pub name: String,
+    pub signals: Vec<DbcSignal>,
+}
+
+/// Parsed DBC file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dbc {
+    pub messages: Vec<DbcMessage>,
+    /// Signals the codec cannot represent
+    pub skipped: usize,
+}
+
+impl Dbc {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| error(format!("DBC {}: {}", path.display(), e)))?;
+        Self::parse(&text).map_err(|e| error(format!("DBC {}: {}", path.display(), e)))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut dbc = Self::default();
+        // SIG_VALTYPE_ lines follow the messages; applied once all are read
+        let mut value_types: BTreeMap<(u32, String), ValueType> = BTreeMap::new();
+        let mut raw_ids: Vec<u32> = Vec::new();
+        // Whether the current message has a CAN ID; None before the first
+        let mut in_message: Option<bool> = None;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let context = |e: CodecError| error(format!("line {}: {}", number + 1, e));
+            if let Some(rest) = line.strip_prefix("BO_ ") {
+                let (raw_id, message) = parse_message(rest).map_err(context)?;
+                in_message = Some(message.is_some());
+                if let Some(message) = message {
+                    raw_ids.push(raw_id);
+                    dbc.messages.push(message);
+                }
+            } else if let Some(rest) = line.strip_prefix("SG_ ") {
+                match (in_message, dbc.messages.last_mut()) {
+                    (None, _) | (Some(true), None) => {
+                        return Err(error(format!("line {}: SG_ outside a message", number + 1)));
+                    },
+                    (Some(false), _) => dbc.skipped += 1,
+                    (Some(true), Some(message)) => {
+                        match parse_signal(rest, message.id).map_err(context)? {
+                            Some(signal) => message.signals.push(signal),
+                            None => dbc.skipped += 1,
+                        }
+                    },
+                }
+            } else if let Some(rest) = line.strip_prefix("SIG_VALTYPE_ ") {
+                let rest = rest.trim_end_matches(';');
+                let mut parts = rest.split_whitespace();
+                let (Some(id), Some(name), Some(kind)) = (
+                    parts.next().and_then(|id| id.parse::<u32>().ok()),
+                    parts.next(),
+                    parts.last(),
+                ) else {
+                    continue;
+                };
+                let value_type = match kind.trim_start_matches(':') {
+                    "1" => ValueType::Float32,
+                    "2" => ValueType::Float64,
+                    _ => continue,
+                };
+                value_types.insert((id, name.to_string()), value_type);
+            }
+        }
+        for ((raw_id, name), value_type) in value_types {
+            let Some(index) = raw_ids.iter().position(|id| *id == raw_id) else {
+                continue;
+            };
+            let Some(signal) = dbc.messages[index]
+                .signals
+                .iter_mut()
+                .find(|s| s.name == name)
+            else {
+                continue;
+            };
+            signal.signal.value_type = value_type;
+        }
+        Ok(dbc)
+    }
+
+    /// Signal named `MESSAGE.SIGNAL`, or a signal name unique in the file
+    pub fn signal(&self, name: &str) -> Result<&DbcSignal> {
+        let name = name.trim();
+        if let Some((message, signal)) = name.split_once('.') {
+            return self
+                .messages
+                .iter()
+                .filter(|m| m.name == message)
+                .flat_map(|m| &m.signals)
+                .find(|s| s.name == signal)
+                .ok_or_else(|| error(format!("DBC has no signal '{}'", name)));
+        }
+        let mut found = self
+            .messages
+            .iter()
+            .flat_map(|m| &m.signals)
+            .filter(|s| s.name == name);
+        match (found.next(), found.next()) {
+            (Some(signal), None) => Ok(signal),
+            (Some(_), Some(_)) => Err(error(format!(
+                "signal '{}' is in several messages; use MESSAGE.SIGNAL",
+                name
+            ))),
+            (None, _) => Err(error(format!("DBC has no signal '{}'", name))),
+        }
+    }
+}
+
+/// `2364540158 EEC1: 8 Engine`; None for pseudo messages without a CAN
+/// ID (`VECTOR__INDEPENDENT_SIG_MSG`)
+fn parse_message(rest: &str) -> Result<(u32, Option<DbcMessage>)> {
+    let (head, _) = rest
+        .split_once(':')
+        .ok_or_else(|| error("BO_ without ':'"))?;
+    let mut parts = head.split_whitespace();
+    let raw_id: u32 = parts
+        .next()
+        .and_then(|id| id.parse().ok())
+        .ok_or_else(|| error("BO_ without a numeric ID"))?;
+    let name = parts.next().ok_or_else(|| error("BO_ without a name"))?;
+    let Ok(id) = FrameId::new(raw_id, false) else {
+        return Ok((raw_id, None));
+    };
+    Ok((
+        raw_id,
+        Some(DbcMessage {
+            id,
+            name: name.to_string(),
+            signals: Vec::new(),
+        }),
+    ))
+}
+
+/// `EngineSpeed : 24|16@1+ (0.125,0) [0|8031.875] "rpm" Vector__XXX`;
+/// None for signals the codec cannot represent
+fn parse_signal(rest: &str, frame: FrameId) -> Result<Option<DbcSignal>> {
+    let (head, body) = rest
+        .split_once(':')
+        .ok_or_else(|| error("SG_ without ':'"))?;
+    let mut head = head.split_whitespace();
+    let name = head.next().ok_or_else(|| error("SG_ without a name"))?;
+    if head.next().is_some() {
+        // Multiplexor (M) or multiplexed (mN) signal
+        return Ok(None);
+    }
+
+    let body = body.trim();
+    let (layout, rest) = body
+        .split_once(' ')
+        .ok_or_else(|| error(format!("signal '{}' without scaling", name)))?;
+    let (position, format) = layout
+        .split_once('@')
+        .ok_or_else(|| error(format!("signal '{}': bad layout '{}'", name, layout)))?;
+    let (start_bit, bit_length) = position
+        .split_once('|')
+        .and_then(|(start, length)| Some((start.parse::<u16>().ok()?, length.parse::<u16>().ok()?)))
+        .ok_or_else(|| error(format!("signal '{}': bad layout '{}'", name, layout)))?;
+    let byte_order = match format.chars().next() {
+        Some('1') => ByteOrder::Intel,
+        Some('0') => ByteOrder::Motorola,
+        _ => return Err(error(format!("signal '{}': bad byte order", name))),
+    };
+    let value_type = match format.chars().nth(1) {
+        Some('-') => ValueType::Signed,
+        _ => ValueType::Unsigned,
+    };
+
+    let rest = rest.trim();
+    let factors = rest
+        .strip_prefix('(')
+        .and_then(|r| r.split_once(')'))
+        .map(|(factors, _)| factors)
+        .ok_or_else(|| error(format!("signal '{}' without (scale,offset)", name)))?;
+    let (scale, offset) = factors
+        .split_once(',')
+        .and_then(|(s, o)| Some((s.trim().parse::<f64>().ok()?, o.trim().parse::<f64>().ok()?)))
+        .ok_or_else(|| error(format!("signal '{}': bad (scale,offset)", name)))?;
+    let unit = rest
+        .split('"')
+        .nth(1)
+        .map(str::to_string)
+        .unwrap_or_default();
+
+    if !(1..=64).contains(&bit_length) || scale == 0.0 || !scale.is_finite() {
+        return Ok(None);
+    }
+    let signal = Signal {
+        frame,
+        start_bit,
+        bit_length,
+        byte_order,
+        value_type,
+        scale,
+        offset,
+    };
+    if signal.data_len().is_err() {
+        return Ok(None);
+    }
+    Ok(Some(DbcSignal {
+        name: name.to_string(),
+        signal,
+        unit,
+    }))
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    const DBC: &str = r#"
+VERSION ""
+
+BU_: Engine BMS
+
+BO_ 2364540158 EEC1: 8 Engine
+ SG_ EngineSpeed : 24|16@1+ (0.125,0) [0|8031.875] "rpm" Vector__XXX
+ SG_ Torque : 16|8@1- (1,-125) [-125|125] "%" Vector__XXX
+
+BO_ 500 BMS_Status: 8 BMS
+ SG_ Current : 7|16@0- (0.1,0) [-3276.8|3276.7] "A" Vector__XXX
+ SG_ Voltage : 16|32@1+ (1,0) [0|0] "V" Vector__XXX
+ SG_ Mode M : 48|8@1+ (1,0) [0|0] "" Vector__XXX
+ SG_ Torque : 56|8@1+ (1,0) [0|255] "" Vector__XXX
+
+BO_ 3221225472 VECTOR__INDEPENDENT_SIG_MSG: 0 Vector__XXX
+ SG_ Spare : 0|8@1+ (1,0) [0|0] "" Vector__XXX
+
+SIG_VALTYPE_ 500 Voltage : 1;
+"#;
+
+    #[test]
+    fn test_parse_messages_and_signals() {
+        let dbc = Dbc::parse(DBC).unwrap();
+        assert_eq!(dbc.messages.len(), 2);
+        // The multiplexor and the signal of the pseudo message
+        assert_eq!(dbc.skipped, 2);
+
+        let eec1 = &dbc.messages[0];
+        assert_eq!(eec1.id, FrameId::new(0x0CF0_04FE, true).unwrap());
+        let speed = dbc.signal("EngineSpeed").unwrap();
+        assert_eq!(speed.unit, "rpm");
+        assert_eq!(speed.signal.frame, eec1.id);
+        assert_eq!(
+            speed
+                .signal
+                .decode(&[0, 0, 0, 0x40, 0x1F, 0, 0, 0])
+                .unwrap(),
+            1000.0
+        );
+
+        let current = dbc.signal("BMS_Status.Current").unwrap();
+        assert_eq!(current.signal.byte_order, ByteOrder::Motorola);
+        assert_eq!(current.signal.decode(&[0xFF, 0x9C, 0, 0]).unwrap(), -10.0);
+        let voltage = dbc.signal("Voltage").unwrap();
+        assert_eq!(voltage.signal.value_type, ValueType::Float32);
+    }
+
+    #[test]
+    fn test_signal_lookup_errors() {
+        let dbc = Dbc::parse(DBC).unwrap();
+        assert!(dbc.signal("Torque").is_err());
+        assert_eq!(dbc.signal("EEC1.Torque").unwrap().signal.offset, -125.0);
+        assert!(dbc.signal("Missing").is_err());
+        assert!(Dbc::parse("SG_ X : 0|8@1+ (1,0) [0|0] \"\" X").is_err());
+    }
+}
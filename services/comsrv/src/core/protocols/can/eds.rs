@@ -0,0 +1,319 @@
+//! CANopen electronic data sheets (CiA 306)
+//!
+//! An EDS (or DCF) is an INI file describing the object dictionary of a
+//! device: `[6000sub1]`-style sections per entry with `DataType`,
+//! `AccessType` and `DefaultValue` (a DCF's `ParameterValue` takes
+//! precedence). Values may refer to the node ID as `$NODEID`, e.g.
+//! `$NODEID+0x180` for the COB-ID of TPDO1.
+//!
+//! Only what the CAN runtime needs is kept: the data type of each entry and
+//! the transmit PDOs (communication parameters 0x1800-0x19FF, mapping
+//! parameters 0x1A00-0x1BFF) with their mapped objects.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::core::protocols::{error, CodecError};
+
+type Result<T> = std::result::Result<T, CodecError>;
+
+/// CiA 301 data types the runtime can decode, as (code, codec data_type)
+const DATA_TYPES: &[(u16, &str)] = &[
+    (0x0001, "bool"),
+    (0x0002, "int8"),
+    (0x0003, "int16"),
+    (0x0004, "int32"),
+    (0x0005, "uint8"),
+    (0x0006, "uint16"),
+    (0x0007, "uint32"),
+    (0x0008, "float32"),
+    (0x0011, "float64"),
+    (0x0015, "int64"),
+    (0x001B, "uint64"),
+];
+
+/// Codec `data_type` of a CiA 301 data type code
+pub fn data_type_name(code: u16) -> Option<&'static str> {
+    DATA_TYPES
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, name)| *name)
+}
+
+/// One entry of the object dictionary
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EdsEntry {
+    pub name: String,
+    pub data_type: Option<u16>,
+    pub access: String,
+    /// `ParameterValue`, else `DefaultValue`, unevaluated
+    pub value: Option<String>,
+}
+
+/// Object mapped into a PDO
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MappedObject {
+    pub index: u16,
+    pub subindex: u8,
+    /// Bit offset in the PDO data
+    pub offset: u16,
+    pub bits: u8,
+}
+
+/// Transmit PDO of a node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pdo {
+    pub cob_id: u32,
+    pub objects: Vec<MappedObject>,
+}
+
+/// Parsed object dictionary
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Eds {
+    entries: BTreeMap<(u16, u8), EdsEntry>,
+}
+
+impl Eds {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| error(format!("EDS {}: {}", path.display(), e)))?;
+        Self::parse(&text).map_err(|e| error(format!("EDS {}: {}", path.display(), e)))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut entries = BTreeMap::new();
+        let mut configured = BTreeSet::new();
+        let mut current: Option<(u16, u8)> = None;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            if let Some(section) = line.strip_prefix('[') {
+                let section = section
+                    .strip_suffix(']')
+                    .ok_or_else(|| error(format!("line {}: unterminated section", number + 1)))?;
+                current = entry_key(section);
+                if let Some(key) = current {
+                    entries.entry(key).or_insert_with(EdsEntry::default);
+                }
+                continue;
+            }
+            let (Some(key), Some((name, value))) = (current, line.split_once('=')) else {
+                continue;
+            };
+            let Some(entry) = entries.get_mut(&key) else {
+                continue;
+            };
+            let value = value.trim();
+            match name.trim().to_ascii_lowercase().as_str() {
+                "parametername" => entry.name = value.to_string(),
+                "datatype" => entry.data_type = parse_number(value).map(|v| v as u16),
+                "accesstype" => entry.access = value.to_ascii_lowercase(),
+                "parametervalue" if !value.is_empty() => {
+                    entry.value = Some(value.to_string());
+                    configured.insert(key);
+                },
+                // A DCF's ParameterValue wins regardless of the key order
+                "defaultvalue" if !configured.contains(&key) => {
+                    entry.value = Some(value.to_string());
+                },
+                _ => {},
+            }
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn entry(&self, index: u16, subindex: u8) -> Option<&EdsEntry> {
+        self.entries.get(&(index, subindex))
+    }
+
+    /// Value of an entry evaluated for `node_id`
+    pub fn value(&self, index: u16, subindex: u8, node_id: u8) -> Option<u64> {
+        let raw = self.entry(index, subindex)?.value.as_deref()?;
+        evaluate(raw, node_id)
+    }
+
+    /// Valid transmit PDOs of the device at `node_id`
+    pub fn tpdos(&self, node_id: u8) -> Vec<Pdo> {
+        let mut pdos = Vec::new();
+        for n in 0..0x200u16 {
+            let Some(cob_id) = self.value(0x1800 + n, 1, node_id) else {
+                continue;
+            };
+            // Bit 31: PDO does not exist / is not valid
+            if cob_id & 0x8000_0000 != 0 {
+                continue;
+            }
+            let mapping = 0x1A00 + n;
+            let count = self.value(mapping, 0, node_id).unwrap_or(0).min(64) as u8;
+            let mut offset = 0u16;
+            let mut objects = Vec::new();
+            for sub in 1..=count {
+                let Some(entry) = self.value(mapping, sub, node_id) else {
+                    break;
+                };
+                let bits = entry as u8;
+                objects.push(MappedObject {
+                    index: (entry >> 16) as u16,
+                    subindex: (entry >> 8) as u8,
+                    offset,
+                    bits,
+                });
+                offset += u16::from(bits);
+            }
+            if !objects.is_empty() {
+                pdos.push(Pdo {
+                    cob_id: (cob_id & 0x1FFF_FFFF) as u32,
+                    objects,
+                });
+            }
+        }
+        pdos
+    }
+}
+
+/// `6000` -> (0x6000, 0), `1A00sub2` -> (0x1A00, 2); other sections -> None
+///
+/// A VAR object has no sub-index sections, so its own section is sub 0.
+fn entry_key(section: &str) -> Option<(u16, u8)> {
+    let lower = section.trim().to_ascii_lowercase();
+    let (index, sub) = match lower.split_once("sub") {
+        Some((index, sub)) => (index, u8::from_str_radix(sub, 16).ok()?),
+        None => (lower.as_str(), 0),
+    };
+    if index.len() != 4 {
+        return None;
+    }
+    Some((u16::from_str_radix(index, 16).ok()?, sub))
+}
+
+/// EDS number: decimal, `0x` hex or leading-zero octal
+fn parse_number(value: &str) -> Option<u64> {
+    let value = value.trim();
+    if let Some(hex) = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        u64::from_str_radix(hex, 16).ok()
+    } else if value.len() > 1 && value.starts_with('0') {
+        u64::from_str_radix(&value[1..], 8).ok()
+    } else {
+        value.parse().ok()
+    }
+}
+
+/// Evaluate a value with `$NODEID` terms (`$NODEID+0x180`, `0x200+$NODEID`)
+pub fn evaluate(value: &str, node_id: u8) -> Option<u64> {
+    value.split('+').try_fold(0u64, |sum, term| {
+        let term = term.trim();
+        let term = if term.eq_ignore_ascii_case("$NODEID") {
+            u64::from(node_id)
+        } else {
+            parse_number(term)?
+        };
+        Some(sum + term)
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    const EDS: &str = r#"
+[DeviceInfo]
+ProductName=IO module
+
+[1800]
+ParameterName=TPDO1 communication parameter
+ObjectType=0x9
+SubNumber=2
+
+[1800sub1]
+ParameterName=COB-ID
+DataType=0x0007
+AccessType=rw
+DefaultValue=$NODEID+0x180
+
+[1801sub1]
+ParameterName=COB-ID
+DataType=0x0007
+DefaultValue=0x80000280
+
+[1A00sub0]
+ParameterName=Number of mapped objects
+DataType=0x0005
+DefaultValue=2
+
+[1A00sub1]
+DataType=0x0007
+DefaultValue=0x60000108
+
+[1A00sub2]
+DataType=0x0007
+DefaultValue=0x64010210
+ParameterValue=0x64010110
+
+[6000sub1]
+ParameterName=Digital inputs 1-8
+DataType=0x0005
+AccessType=ro
+
+[6401sub1]
+ParameterName=Analog input 1
+DataType=0x0003
+AccessType=ro
+PDOMapping=1
+
+[2000]
+ParameterName=Setpoint
+ObjectType=0x7
+DataType=0x0008
+AccessType=rw
+DefaultValue=0
+"#;
+
+    #[test]
+    fn test_parse_entries_and_values() {
+        let eds = Eds::parse(EDS).unwrap();
+        let setpoint = eds.entry(0x2000, 0).unwrap();
+        assert_eq!(setpoint.name, "Setpoint");
+        assert_eq!(setpoint.data_type, Some(0x0008));
+        assert_eq!(data_type_name(0x0008), Some("float32"));
+        assert_eq!(eds.entry(0x6401, 1).unwrap().access, "ro");
+        assert_eq!(eds.value(0x1800, 1, 5), Some(0x185));
+        // ParameterValue (DCF) overrides DefaultValue
+        assert_eq!(eds.value(0x1A00, 2, 5), Some(0x6401_0110));
+
+        assert_eq!(evaluate("0x200 + $NODEID", 0x10), Some(0x210));
+        assert_eq!(evaluate("010", 1), Some(8));
+        assert_eq!(evaluate("$NODE", 1), None);
+    }
+
+    #[test]
+    fn test_tpdo_mapping() {
+        let eds = Eds::parse(EDS).unwrap();
+        // TPDO2 is disabled (bit 31), TPDO1 maps 8 + 16 bits
+        assert_eq!(
+            eds.tpdos(5),
+            vec![Pdo {
+                cob_id: 0x185,
+                objects: vec![
+                    MappedObject {
+                        index: 0x6000,
+                        subindex: 1,
+                        offset: 0,
+                        bits: 8
+                    },
+                    MappedObject {
+                        index: 0x6401,
+                        subindex: 1,
+                        offset: 8,
+                        bits: 16
+                    },
+                ],
+            }]
+        );
+    }
+}
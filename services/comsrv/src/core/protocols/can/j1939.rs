@@ -0,0 +1,925 @@
+//! SAE J1939 on top of the CAN runtime
+//!
+//! J1939 addresses parameter groups (PGNs) instead of frame IDs: the 29-bit
+//! identifier carries the priority, the PGN, the destination of PDU1 groups
+//! (PF below 240) and the source address. Parameter groups longer than eight
+//! bytes are split by the transport protocol (J1939-21):
+//!
+//! - TP.BAM: broadcast announce followed by TP.DT packets to the global
+//!   address, no handshake
+//! - TP.CM RTS/CTS: connection to this node; the receiver paces the sender
+//!   with clear-to-send windows and confirms with an end-of-message ACK
+//!
+//! Sessions time out after T1 (750 ms between data packets) or T2 (1250 ms
+//! after a CTS); connection-mode timeouts are aborted towards the sender.
+//!
+//! The node claims its source address with its 64-bit NAME (J1939-81). A
+//! competing claim for the same address is won by the lower NAME; the loser
+//! moves to a free address in 128-247 if its NAME is arbitrary address
+//! capable, otherwise it announces that it cannot claim and stops
+//! transmitting.
+//!
+//! SPNs are ordinary signals of the reassembled data (`start_bit`,
+//! `bit_length`, `data_type`, `scale`, `offset` as in [`super::codec`]).
+//! Unsigned SPNs carrying the J1939-71 "error" or "not available" ranges are
+//! reported by [`availability`].
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde_json::Value as JsonValue;
+
+use super::codec::{mapping_flag, mapping_integer, FrameId, Signal, MAX_DATA_LEN};
+use crate::core::protocols::{error, CodecError};
+
+type Result<T> = std::result::Result<T, CodecError>;
+
+/// Request (J1939-21)
+pub const PGN_REQUEST: u32 = 0xEA00;
+/// Acknowledgement (J1939-21)
+pub const PGN_ACKNOWLEDGEMENT: u32 = 0xE800;
+/// Address claimed / cannot claim (J1939-81)
+pub const PGN_ADDRESS_CLAIMED: u32 = 0xEE00;
+/// Transport protocol connection management
+pub const PGN_TP_CM: u32 = 0xEC00;
+/// Transport protocol data transfer
+pub const PGN_TP_DT: u32 = 0xEB00;
+
+pub const GLOBAL_ADDRESS: u8 = 0xFF;
+/// Source address of a node without a claimed address
+pub const NULL_ADDRESS: u8 = 0xFE;
+
+/// Largest parameter group the transport protocol carries (255 packets)
+pub const MAX_MESSAGE_LEN: usize = 1785;
+
+pub const DEFAULT_PRIORITY: u8 = 6;
+pub const DEFAULT_ADDRESS: u8 = 0x80;
+/// Arbitrary address capable, all other NAME fields zero
+pub const DEFAULT_NAME: u64 = 1 << 63;
+
+/// Addresses taken by arbitrary address capable nodes after a lost claim
+const DYNAMIC_ADDRESSES: std::ops::RangeInclusive<u8> = 128..=247;
+
+/// Maximum time between data packets
+const T1: Duration = Duration::from_millis(750);
+/// Maximum time from a CTS to the next data packet
+const T2: Duration = Duration::from_millis(1250);
+
+const TP_PRIORITY: u8 = 7;
+const CM_RTS: u8 = 16;
+const CM_CTS: u8 = 17;
+const CM_EOM_ACK: u8 = 19;
+const CM_BAM: u8 = 32;
+const CM_ABORT: u8 = 255;
+
+/// Abort reasons (J1939-21)
+const ABORT_BUSY: u8 = 1;
+const ABORT_TIMEOUT: u8 = 3;
+const ABORT_BAD_SEQUENCE: u8 = 7;
+
+/// PDU1 groups (PF below 240) are addressed to one destination
+pub fn is_pdu1(pgn: u32) -> bool {
+    (pgn >> 8) & 0xFF < 240
+}
+
+/// Identifier fields of a J1939 frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct J1939Id {
+    pub priority: u8,
+    /// PGN with the PS byte cleared for PDU1 groups
+    pub pgn: u32,
+    /// Destination of PDU1 groups, [`GLOBAL_ADDRESS`] for PDU2
+    pub destination: u8,
+    pub source: u8,
+}
+
+impl J1939Id {
+    pub fn new(priority: u8, pgn: u32, destination: u8, source: u8) -> Self {
+        let pdu1 = is_pdu1(pgn);
+        Self {
+            priority: priority & 0x07,
+            pgn: if pdu1 { pgn & 0x3FF00 } else { pgn & 0x3FFFF },
+            destination: if pdu1 { destination } else { GLOBAL_ADDRESS },
+            source,
+        }
+    }
+
+    /// Fields of a 29-bit identifier
+    pub fn from_raw(id: u32) -> Self {
+        let pgn = (id >> 8) & 0x3FFFF;
+        Self::new((id >> 26) as u8, pgn, (id >> 8) as u8, id as u8)
+    }
+
+    pub fn raw(&self) -> u32 {
+        let mut id = u32::from(self.priority) << 26 | self.pgn << 8 | u32::from(self.source);
+        if is_pdu1(self.pgn) {
+            id |= u32::from(self.destination) << 8;
+        }
+        id
+    }
+
+    pub fn frame_id(&self) -> FrameId {
+        FrameId {
+            id: self.raw(),
+            extended: true,
+        }
+    }
+}
+
+/// 64-bit NAME of a node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Name(pub u64);
+
+impl Name {
+    /// The node may move to another address after losing a claim
+    pub fn arbitrary_address_capable(&self) -> bool {
+        self.0 >> 63 == 1
+    }
+}
+
+// ============================================================================
+// Point mappings
+// ============================================================================
+
+/// Parameter group a J1939 point reads or writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PgnKey {
+    pub pgn: u32,
+    /// Sender of input groups (any sender when unset); destination of
+    /// output groups
+    pub address: Option<u8>,
+}
+
+/// SPN of a J1939 point mapping
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpnMapping {
+    pub key: PgnKey,
+    pub priority: u8,
+    /// Suspect parameter number, for diagnostics
+    pub spn: Option<u32>,
+    /// Group is sent on request only; polled with the Request PGN
+    pub request: bool,
+    /// Signal of the SPN; its frame is the identifier with source 0
+    pub signal: Signal,
+}
+
+impl SpnMapping {
+    /// Mapping of a point (`pgn`, `spn`, `source_address`,
+    /// `destination_address`, `priority`, `request` and the signal layout)
+    ///
+    /// `output` mappings address `destination_address` and must fit a single
+    /// frame; input mappings filter on `source_address` and may span a
+    /// transport protocol message.
+    pub fn from_mapping(mapping: &JsonValue, output: bool) -> Result<Self> {
+        let pgn = mapping_integer(mapping, "pgn")?.ok_or_else(|| error("missing 'pgn'"))?;
+        if pgn > 0x3FFFF {
+            return Err(error(format!("PGN {} exceeds 18 bits", pgn)));
+        }
+        let pgn = pgn as u32;
+        if is_pdu1(pgn) && pgn & 0xFF != 0 {
+            return Err(error(format!(
+                "PDU1 PGN 0x{:05X} must end in 00; use 'destination_address'",
+                pgn
+            )));
+        }
+        let address = |key: &str| -> Result<Option<u8>> {
+            match mapping_integer(mapping, key)? {
+                None => Ok(None),
+                Some(a) if a < u64::from(NULL_ADDRESS) => Ok(Some(a as u8)),
+                // Commands may be broadcast to all nodes
+                Some(0xFF) if output => Ok(Some(GLOBAL_ADDRESS)),
+                Some(a) => Err(error(format!("'{}' {} is not a node address", key, a))),
+            }
+        };
+        let address = if output {
+            address("destination_address")?
+        } else {
+            address("source_address")?
+        };
+        let priority = match mapping_integer(mapping, "priority")? {
+            None => DEFAULT_PRIORITY,
+            Some(p) if p <= 7 => p as u8,
+            Some(p) => return Err(error(format!("priority {} must be 0-7", p))),
+        };
+        let spn = mapping_integer(mapping, "spn")?
+            .map(|s| u32::try_from(s).map_err(|_| error(format!("SPN {} out of range", s))))
+            .transpose()?;
+
+        let destination = address.unwrap_or(GLOBAL_ADDRESS);
+        let frame = J1939Id::new(priority, pgn, destination, 0).frame_id();
+        let max_len = if output {
+            MAX_DATA_LEN
+        } else {
+            MAX_MESSAGE_LEN
+        };
+        let signal = Signal::from_layout(mapping, frame, max_len)?;
+        Ok(Self {
+            key: PgnKey { pgn, address },
+            priority,
+            spn,
+            request: mapping_flag(mapping, "request"),
+            signal,
+        })
+    }
+}
+
+/// J1939-71 state of an unsigned parameter value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Availability {
+    Valid,
+    /// Error indicator: the sender has a fault measuring the parameter
+    Error,
+    /// Not available, or the reserved range just below it
+    NotAvailable,
+}
+
+/// Classify the raw value of an unsigned SPN of `bit_length` bits
+///
+/// Discrete parameters shorter than a byte use the top two codes (error,
+/// not available); longer parameters are judged by their most significant
+/// byte: 0xFB-0xFD reserved, 0xFE error, 0xFF not available.
+pub fn availability(raw: u64, bit_length: u16) -> Availability {
+    let length = u32::from(bit_length);
+    if length < 2 {
+        return Availability::Valid;
+    }
+    if length < 8 {
+        let all_ones = (1u64 << length) - 1;
+        return match raw {
+            r if r == all_ones => Availability::NotAvailable,
+            r if r == all_ones - 1 => Availability::Error,
+            _ => Availability::Valid,
+        };
+    }
+    match (raw >> (length - 8)) as u8 {
+        0xFF | 0xFB..=0xFD => Availability::NotAvailable,
+        0xFE => Availability::Error,
+        _ => Availability::Valid,
+    }
+}
+
+/// Kernel filters (ID, mask) for the input groups and the network
+/// management and transport protocol groups
+pub fn kernel_filters(keys: &[PgnKey]) -> Vec<(u32, u32)> {
+    use super::codec::{EFF_FLAG, RTR_FLAG};
+    // Priority is ignored; PDU1 groups are accepted for every destination
+    let pdu1_mask = 0x03FF_0000;
+    let pdu2_mask = 0x03FF_FF00;
+    let mut filters: Vec<(u32, u32)> = [PGN_TP_CM, PGN_TP_DT, PGN_REQUEST, PGN_ADDRESS_CLAIMED]
+        .iter()
+        .map(|pgn| (pgn << 8, pdu1_mask))
+        .collect();
+    for key in keys {
+        let mask = if is_pdu1(key.pgn) {
+            pdu1_mask
+        } else {
+            pdu2_mask
+        };
+        let (id, mask) = match key.address {
+            Some(source) => (key.pgn << 8 | u32::from(source), mask | 0xFF),
+            None => (key.pgn << 8, mask),
+        };
+        filters.push((id, mask));
+    }
+    filters.sort();
+    filters.dedup();
+    filters
+        .into_iter()
+        .map(|(id, mask)| (id | EFF_FLAG, mask | EFF_FLAG | RTR_FLAG))
+        .collect()
+}
+
+// ============================================================================
+// Protocol stack
+// ============================================================================
+
+/// J1939 node parameters of a CAN channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct J1939Config {
+    /// Preferred source address
+    pub address: u8,
+    pub name: Name,
+    /// Interval between Request PGNs of on-request groups
+    pub request_interval: Duration,
+}
+
+impl Default for J1939Config {
+    fn default() -> Self {
+        Self {
+            address: DEFAULT_ADDRESS,
+            name: Name(DEFAULT_NAME),
+            request_interval: Duration::from_millis(1000),
+        }
+    }
+}
+
+/// Complete parameter group received from the bus
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub pgn: u32,
+    pub source: u8,
+    pub destination: u8,
+    pub data: Vec<u8>,
+}
+
+/// Frame the stack wants transmitted
+pub type Reply = (FrameId, Vec<u8>);
+
+#[derive(Debug)]
+struct Session {
+    pgn: u32,
+    size: usize,
+    packets: u8,
+    data: Vec<u8>,
+    /// Sequence number of the next data packet
+    next: u8,
+    /// Last sequence number of the current CTS window (RTS/CTS only)
+    window_end: Option<u8>,
+    /// Packets per CTS the sender accepts
+    window: u8,
+    deadline: Instant,
+}
+
+/// Counters reported in the channel diagnostics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counters {
+    pub messages: u64,
+    pub transport_messages: u64,
+    pub transport_aborts: u64,
+    pub address_conflicts: u64,
+}
+
+/// Address claim and transport protocol state of one node
+#[derive(Debug)]
+pub struct J1939Stack {
+    name: Name,
+    /// Claimed address; `None` after a lost claim without a free address
+    address: Option<u8>,
+    /// Addresses claimed by other nodes
+    claimed: HashMap<u8, Name>,
+    /// Transport sessions by (source, destination)
+    sessions: HashMap<(u8, u8), Session>,
+    pub counters: Counters,
+}
+
+impl J1939Stack {
+    pub fn new(config: &J1939Config) -> Self {
+        Self {
+            name: config.name,
+            address: Some(config.address),
+            claimed: HashMap::new(),
+            sessions: HashMap::new(),
+            counters: Counters::default(),
+        }
+    }
+
+    pub fn address(&self) -> Option<u8> {
+        self.address
+    }
+
+    /// Address claim of this node, or "cannot claim" once it lost its address
+    pub fn claim(&self) -> Reply {
+        let source = self.address.unwrap_or(NULL_ADDRESS);
+        let id = J1939Id::new(
+            DEFAULT_PRIORITY,
+            PGN_ADDRESS_CLAIMED,
+            GLOBAL_ADDRESS,
+            source,
+        );
+        (id.frame_id(), self.name.0.to_le_bytes().to_vec())
+    }
+
+    /// Request PGN asking `destination` for `pgn`
+    pub fn request(&self, pgn: u32, destination: u8) -> Option<Reply> {
+        let source = self.address?;
+        let id = J1939Id::new(DEFAULT_PRIORITY, PGN_REQUEST, destination, source);
+        Some((id.frame_id(), pgn.to_le_bytes()[..3].to_vec()))
+    }
+
+    /// Identifier of an output signal frame sent from the claimed address
+    pub fn output_frame(&self, template: FrameId) -> Option<FrameId> {
+        let source = self.address?;
+        Some(FrameId {
+            id: (template.id & !0xFF) | u32::from(source),
+            extended: true,
+        })
+    }
+
+    /// Process a received extended data frame
+    pub fn receive(
+        &mut self,
+        raw_id: u32,
+        data: &[u8],
+        now: Instant,
+    ) -> (Option<Message>, Vec<Reply>) {
+        let mut replies = self.expire(now);
+        let id = J1939Id::from_raw(raw_id);
+        let message = match id.pgn {
+            PGN_ADDRESS_CLAIMED => {
+                self.address_claimed(id.source, data, &mut replies);
+                None
+            },
+            PGN_REQUEST => {
+                self.requested(id, data, &mut replies);
+                None
+            },
+            PGN_TP_CM => {
+                self.connection_management(id, data, now, &mut replies);
+                None
+            },
+            PGN_TP_DT => self.data_transfer(id, data, now, &mut replies),
+            pgn => Some(Message {
+                pgn,
+                source: id.source,
+                destination: id.destination,
+                data: data.to_vec(),
+            }),
+        };
+        if message.is_some() {
+            self.counters.messages += 1;
+        }
+        (message, replies)
+    }
+
+    /// Drop timed-out sessions, aborting connections towards their sender
+    pub fn expire(&mut self, now: Instant) -> Vec<Reply> {
+        let expired: Vec<(u8, u8)> = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| session.deadline <= now)
+            .map(|(key, _)| *key)
+            .collect();
+        let mut replies = Vec::new();
+        for (source, destination) in expired {
+            if let Some(session) = self.sessions.remove(&(source, destination)) {
+                self.counters.transport_aborts += 1;
+                if destination != GLOBAL_ADDRESS {
+                    replies.extend(self.abort(source, session.pgn, ABORT_TIMEOUT));
+                }
+            }
+        }
+        replies
+    }
+
+    fn address_claimed(&mut self, source: u8, data: &[u8], replies: &mut Vec<Reply>) {
+        let Some(bytes) = data.get(..8) else {
+            return;
+        };
+        let mut name = [0u8; 8];
+        name.copy_from_slice(bytes);
+        let other = Name(u64::from_le_bytes(name));
+        if source == NULL_ADDRESS {
+            return;
+        }
+        self.claimed.insert(source, other);
+        if self.address != Some(source) || other == self.name {
+            return;
+        }
+        self.counters.address_conflicts += 1;
+        if self.name < other {
+            // Ours has priority: repeat the claim
+            replies.push(self.claim());
+            return;
+        }
+        self.address = if self.name.arbitrary_address_capable() {
+            DYNAMIC_ADDRESSES
+                .clone()
+                .find(|a| !self.claimed.contains_key(a))
+        } else {
+            None
+        };
+        self.sessions.clear();
+        replies.push(self.claim());
+    }
+
+    fn requested(&mut self, id: J1939Id, data: &[u8], replies: &mut Vec<Reply>) {
+        let Some(bytes) = data.get(..3) else {
+            return;
+        };
+        let pgn = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]);
+        let to_us = self.address.is_some_and(|a| a == id.destination);
+        if pgn == PGN_ADDRESS_CLAIMED {
+            if to_us || id.destination == GLOBAL_ADDRESS {
+                replies.push(self.claim());
+            }
+        } else if to_us {
+            // Nothing else is served on request: negative acknowledgement
+            if let Some(source) = self.address {
+                let ack = J1939Id::new(
+                    DEFAULT_PRIORITY,
+                    PGN_ACKNOWLEDGEMENT,
+                    GLOBAL_ADDRESS,
+                    source,
+                );
+                let pgn = pgn.to_le_bytes();
+                replies.push((
+                    ack.frame_id(),
+                    vec![1, 0xFF, 0xFF, 0xFF, id.source, pgn[0], pgn[1], pgn[2]],
+                ));
+            }
+        }
+    }
+
+    fn connection_management(
+        &mut self,
+        id: J1939Id,
+        data: &[u8],
+        now: Instant,
+        replies: &mut Vec<Reply>,
+    ) {
+        if data.len() < 8 {
+            return;
+        }
+        let size = usize::from(u16::from_le_bytes([data[1], data[2]]));
+        let packets = data[3];
+        let pgn = u32::from_le_bytes([data[5], data[6], data[7], 0]);
+        let to_us = self.address.is_some_and(|a| a == id.destination);
+        let valid = (MAX_DATA_LEN + 1..=MAX_MESSAGE_LEN).contains(&size)
+            && usize::from(packets) == size.div_ceil(7);
+        match data[0] {
+            CM_BAM if id.destination == GLOBAL_ADDRESS && valid => {
+                self.start(id.source, GLOBAL_ADDRESS, pgn, size, packets, 0, now);
+            },
+            CM_RTS if to_us => {
+                if !valid {
+                    replies.extend(self.abort(id.source, pgn, ABORT_BUSY));
+                    return;
+                }
+                // A new RTS replaces an unfinished session from that sender
+                let window = match data[4] {
+                    0 => packets,
+                    n => n.min(packets),
+                };
+                self.start(id.source, id.destination, pgn, size, packets, window, now);
+                replies.extend(self.clear_to_send(id.source, id.destination));
+            },
+            CM_ABORT => {
+                if self.sessions.remove(&(id.source, id.destination)).is_some() {
+                    self.counters.transport_aborts += 1;
+                }
+            },
+            _ => {},
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn start(
+        &mut self,
+        source: u8,
+        destination: u8,
+        pgn: u32,
+        size: usize,
+        packets: u8,
+        window: u8,
+        now: Instant,
+    ) {
+        self.sessions.insert(
+            (source, destination),
+            Session {
+                pgn,
+                size,
+                packets,
+                data: Vec::with_capacity(usize::from(packets) * 7),
+                next: 1,
+                window_end: None,
+                window,
+                deadline: now + T1,
+            },
+        );
+    }
+
+    fn data_transfer(
+        &mut self,
+        id: J1939Id,
+        data: &[u8],
+        now: Instant,
+        replies: &mut Vec<Reply>,
+    ) -> Option<Message> {
+        let key = (id.source, id.destination);
+        let session = self.sessions.get_mut(&key)?;
+        let connection = id.destination != GLOBAL_ADDRESS;
+        let sequence = *data.first()?;
+        if sequence != session.next {
+            let pgn = session.pgn;
+            self.sessions.remove(&key);
+            self.counters.transport_aborts += 1;
+            if connection {
+                replies.extend(self.abort(id.source, pgn, ABORT_BAD_SEQUENCE));
+            }
+            return None;
+        }
+        session
+            .data
+            .extend_from_slice(data.get(1..).unwrap_or_default());
+        session.next = session.next.wrapping_add(1);
+        session.deadline = now + T1;
+
+        if sequence == session.packets {
+            let session = self.sessions.remove(&key)?;
+            let mut payload = session.data;
+            payload.truncate(session.size);
+            if connection {
+                let pgn = session.pgn.to_le_bytes();
+                let size = (session.size as u16).to_le_bytes();
+                let ack = J1939Id::new(TP_PRIORITY, PGN_TP_CM, id.source, id.destination);
+                replies.push((
+                    ack.frame_id(),
+                    vec![
+                        CM_EOM_ACK,
+                        size[0],
+                        size[1],
+                        session.packets,
+                        0xFF,
+                        pgn[0],
+                        pgn[1],
+                        pgn[2],
+                    ],
+                ));
+            }
+            self.counters.transport_messages += 1;
+            return Some(Message {
+                pgn: session.pgn,
+                source: id.source,
+                destination: id.destination,
+                data: payload,
+            });
+        }
+        if connection && session.window_end == Some(sequence) {
+            replies.extend(self.clear_to_send(id.source, id.destination));
+            if let Some(session) = self.sessions.get_mut(&key) {
+                session.deadline = now + T2;
+            }
+        }
+        None
+    }
+
+    /// CTS for the next window of a connection
+    fn clear_to_send(&mut self, source: u8, destination: u8) -> Option<Reply> {
+        let session = self.sessions.get_mut(&(source, destination))?;
+        let remaining = session.packets - session.next + 1;
+        let count = session.window.min(remaining);
+        session.window_end = Some(session.next + count - 1);
+        let pgn = session.pgn.to_le_bytes();
+        let id = J1939Id::new(TP_PRIORITY, PGN_TP_CM, source, destination);
+        Some((
+            id.frame_id(),
+            vec![
+                CM_CTS,
+                count,
+                session.next,
+                0xFF,
+                0xFF,
+                pgn[0],
+                pgn[1],
+                pgn[2],
+            ],
+        ))
+    }
+
+    fn abort(&self, destination: u8, pgn: u32, reason: u8) -> Option<Reply> {
+        let source = self.address?;
+        let pgn = pgn.to_le_bytes();
+        let id = J1939Id::new(TP_PRIORITY, PGN_TP_CM, destination, source);
+        Some((
+            id.frame_id(),
+            vec![CM_ABORT, reason, 0xFF, 0xFF, 0xFF, pgn[0], pgn[1], pgn[2]],
+        ))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ENGINE: u8 = 0x00;
+    const US: u8 = 0x80;
+
+    fn raw(priority: u8, pgn: u32, destination: u8, source: u8) -> u32 {
+        J1939Id::new(priority, pgn, destination, source).raw()
+    }
+
+    #[test]
+    fn test_identifier_fields() {
+        // EEC1 from the engine: PDU2, priority 3
+        let eec1 = J1939Id::from_raw(0x0CF0_0400);
+        assert_eq!(eec1.priority, 3);
+        assert_eq!(eec1.pgn, 61444);
+        assert_eq!(eec1.destination, GLOBAL_ADDRESS);
+        assert_eq!(eec1.source, ENGINE);
+        assert_eq!(eec1.raw(), 0x0CF0_0400);
+
+        // TP.CM to address 0x80: PDU1 keeps the destination out of the PGN
+        let cm = J1939Id::from_raw(0x1CEC_8000);
+        assert_eq!(cm.pgn, PGN_TP_CM);
+        assert_eq!(cm.destination, US);
+        assert_eq!(cm.raw(), 0x1CEC_8000);
+    }
+
+    #[test]
+    fn test_spn_mapping() {
+        // SPN 190 engine speed: EEC1 bytes 4-5, 0.125 rpm/bit
+        let speed = SpnMapping::from_mapping(
+            &json!({"pgn": 61444, "spn": 190, "start_bit": 24, "data_type": "uint16", "scale": 0.125}),
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            speed.key,
+            PgnKey {
+                pgn: 61444,
+                address: None
+            }
+        );
+        let data = [0xFF, 0xFF, 0xFF, 0x68, 0x13, 0xFF, 0xFF, 0xFF];
+        assert_eq!(speed.signal.decode(&data).unwrap(), 621.0);
+        assert_eq!(
+            availability(speed.signal.raw(&data).unwrap(), 16),
+            Availability::Valid
+        );
+        assert_eq!(availability(0xFF12, 16), Availability::NotAvailable);
+        assert_eq!(availability(0xFE00, 16), Availability::Error);
+        assert_eq!(availability(0b10, 2), Availability::Error);
+        assert_eq!(availability(0b01, 2), Availability::Valid);
+
+        // Inputs may lie beyond byte 8 of a transport message
+        assert!(SpnMapping::from_mapping(
+            &json!({"pgn": "0xFEE5", "source_address": 0, "start_bit": 96, "bit_length": 32}),
+            false
+        )
+        .is_ok());
+        assert!(SpnMapping::from_mapping(
+            &json!({"pgn": "0xFEE5", "start_bit": 96, "bit_length": 32}),
+            true
+        )
+        .is_err());
+        // PDU1 PGNs carry the destination separately
+        assert!(SpnMapping::from_mapping(
+            &json!({"pgn": "0xEF21", "start_bit": 0, "bit_length": 8}),
+            true
+        )
+        .is_err());
+        let command = SpnMapping::from_mapping(
+            &json!({"pgn": "0xEF00", "destination_address": 0x21, "priority": 3, "start_bit": 0, "bit_length": 8}),
+            true,
+        )
+        .unwrap();
+        let stack = J1939Stack::new(&J1939Config::default());
+        assert_eq!(
+            stack.output_frame(command.signal.frame).unwrap().id,
+            0x0CEF_2180
+        );
+    }
+
+    #[test]
+    fn test_bam_reassembly() {
+        let mut stack = J1939Stack::new(&J1939Config::default());
+        let now = Instant::now();
+        let payload: Vec<u8> = (1..=17).collect();
+        let (message, replies) = stack.receive(
+            raw(7, PGN_TP_CM, GLOBAL_ADDRESS, ENGINE),
+            &[CM_BAM, 17, 0, 3, 0xFF, 0xE5, 0xFE, 0x00],
+            now,
+        );
+        assert!(message.is_none() && replies.is_empty());
+        for (index, chunk) in payload.chunks(7).enumerate() {
+            let mut packet = vec![index as u8 + 1];
+            packet.extend_from_slice(chunk);
+            packet.resize(8, 0xFF);
+            let (message, replies) =
+                stack.receive(raw(7, PGN_TP_DT, GLOBAL_ADDRESS, ENGINE), &packet, now);
+            assert!(replies.is_empty());
+            if index == 2 {
+                let message = message.unwrap();
+                assert_eq!(message.pgn, 0xFEE5);
+                assert_eq!(message.source, ENGINE);
+                assert_eq!(message.data, payload);
+            } else {
+                assert!(message.is_none());
+            }
+        }
+        assert_eq!(stack.counters.transport_messages, 1);
+
+        // A gap in the sequence drops the broadcast
+        stack.receive(
+            raw(7, PGN_TP_CM, GLOBAL_ADDRESS, ENGINE),
+            &[CM_BAM, 17, 0, 3, 0xFF, 0xE5, 0xFE, 0x00],
+            now,
+        );
+        stack.receive(raw(7, PGN_TP_DT, GLOBAL_ADDRESS, ENGINE), &[1; 8], now);
+        stack.receive(raw(7, PGN_TP_DT, GLOBAL_ADDRESS, ENGINE), &[3; 8], now);
+        assert_eq!(stack.counters.transport_aborts, 1);
+        assert!(stack.sessions.is_empty());
+    }
+
+    #[test]
+    fn test_rts_cts_session() {
+        let mut stack = J1939Stack::new(&J1939Config::default());
+        let now = Instant::now();
+        // 20 bytes in 3 packets, sender accepts 2 packets per CTS
+        let (_, replies) = stack.receive(
+            raw(7, PGN_TP_CM, US, ENGINE),
+            &[CM_RTS, 20, 0, 3, 2, 0xCA, 0xFE, 0x00],
+            now,
+        );
+        assert_eq!(
+            replies,
+            vec![(
+                J1939Id::new(7, PGN_TP_CM, ENGINE, US).frame_id(),
+                vec![CM_CTS, 2, 1, 0xFF, 0xFF, 0xCA, 0xFE, 0x00]
+            )]
+        );
+        let (_, replies) = stack.receive(
+            raw(7, PGN_TP_DT, US, ENGINE),
+            &[1, 0, 1, 2, 3, 4, 5, 6],
+            now,
+        );
+        assert!(replies.is_empty());
+        let (_, replies) = stack.receive(
+            raw(7, PGN_TP_DT, US, ENGINE),
+            &[2, 7, 8, 9, 10, 11, 12, 13],
+            now,
+        );
+        assert_eq!(replies[0].1[..3], [CM_CTS, 1, 3]);
+        let (message, replies) = stack.receive(
+            raw(7, PGN_TP_DT, US, ENGINE),
+            &[3, 14, 15, 16, 17, 18, 19, 0xFF],
+            now,
+        );
+        assert_eq!(message.unwrap().data, (0..20).collect::<Vec<u8>>());
+        assert_eq!(
+            replies[0].1,
+            vec![CM_EOM_ACK, 20, 0, 3, 0xFF, 0xCA, 0xFE, 0x00]
+        );
+
+        // A stalled sender is aborted with a timeout
+        stack.receive(
+            raw(7, PGN_TP_CM, US, ENGINE),
+            &[CM_RTS, 20, 0, 3, 0xFF, 0xCA, 0xFE, 0x00],
+            now,
+        );
+        let replies = stack.expire(now + T2);
+        assert_eq!(replies[0].1[..2], [CM_ABORT, ABORT_TIMEOUT]);
+        assert!(stack.sessions.is_empty());
+    }
+
+    #[test]
+    fn test_address_claim_contention() {
+        let config = J1939Config::default();
+        let mut stack = J1939Stack::new(&config);
+        let claim = |name: u64| name.to_le_bytes();
+        let now = Instant::now();
+
+        // A higher NAME claiming our address loses: we repeat our claim
+        let (_, replies) = stack.receive(
+            raw(6, PGN_ADDRESS_CLAIMED, GLOBAL_ADDRESS, US),
+            &claim(u64::MAX),
+            now,
+        );
+        assert_eq!(replies, vec![stack.claim()]);
+        assert_eq!(stack.address(), Some(US));
+
+        // A lower NAME wins; we move to the next free dynamic address
+        stack.receive(
+            raw(6, PGN_ADDRESS_CLAIMED, GLOBAL_ADDRESS, 0x81),
+            &claim(5),
+            now,
+        );
+        let (_, replies) = stack.receive(
+            raw(6, PGN_ADDRESS_CLAIMED, GLOBAL_ADDRESS, US),
+            &claim(1),
+            now,
+        );
+        assert_eq!(stack.address(), Some(0x82));
+        assert_eq!(replies[0].0.id & 0xFF, 0x82);
+        assert_eq!(stack.counters.address_conflicts, 2);
+
+        // Without the arbitrary address bit the node gives up
+        let mut fixed = J1939Stack::new(&J1939Config {
+            name: Name(0x10),
+            ..config
+        });
+        let (_, replies) = fixed.receive(
+            raw(6, PGN_ADDRESS_CLAIMED, GLOBAL_ADDRESS, US),
+            &claim(1),
+            now,
+        );
+        assert_eq!(fixed.address(), None);
+        assert_eq!(replies[0].0.id & 0xFF, u32::from(NULL_ADDRESS));
+        assert!(fixed
+            .output_frame(FrameId {
+                id: 0x18FF_0000,
+                extended: true
+            })
+            .is_none());
+
+        // Requests for the address claim are answered
+        let (_, replies) = stack.receive(
+            raw(6, PGN_REQUEST, GLOBAL_ADDRESS, 0x21),
+            &[0x00, 0xEE, 0x00],
+            now,
+        );
+        assert_eq!(replies, vec![stack.claim()]);
+    }
+}
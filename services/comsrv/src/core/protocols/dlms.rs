@@ -0,0 +1,1469 @@
+//! DLMS/COSEM (IEC 62056) client for smart meters
+//!
+//! Points name a COSEM object by its OBIS code with logical name
+//! referencing: `obis`, `class_id` and `attribute` select the value a point
+//! reads with GET, e.g. `1-0:1.8.0*255` attribute 2 of a register (class 3)
+//! for the active energy import. Register values (classes 3, 4 and 5) are
+//! scaled by the scaler of their `scaler_unit` attribute, read once after
+//! associating, unless the point gives its own `scaler`. Signal points take
+//! one `bit` of an integer or bit-string value. Control and adjustment
+//! points SET an attribute encoded as `data_type`, or invoke a `method`
+//! such as the remote disconnect of a disconnect control object.
+//!
+//! Two transports are supported:
+//! - `tcp`: the IEC 62056-47 wrapper on port 4059 (`host`, `port`)
+//! - `hdlc`: IEC 62056-46 HDLC framing, over TCP when `host` is set (e.g.
+//!   a serial server) or on a serial line (`device`, 9600 baud 8N1)
+//!
+//! The association authenticates with `authentication`: `none` (public
+//! client), `low` (password) or `high_sha256` (HLS with mechanism 6, using
+//! `secret` and the client `system_title`). Ciphered APDUs are not supported.
+//!
+//! ```yaml
+//! protocol: dlms
+//! parameters:
+//!   transport: tcp
+//!   host: 192.168.1.60
+//!   port: 4059
+//!   client_address: 1
+//!   server_address: 1
+//!   authentication: high_sha256
+//!   secret: "0123456789ABCDEF"
+//!   response_timeout_ms: 3000
+//! ```
+
+pub mod apdu;
+pub mod framing;
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use igw::core::traits::{DataEventReceiver, Diagnostics, PointFailure, PollResult};
+use igw::gateway::ChannelRuntime;
+use igw::{ConnectionState, DataBatch, DataPoint, GatewayError};
+use rand::RngCore;
+use serde_json::{json, Value as JsonValue};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{timeout_at, Instant};
+use tokio_serial::{DataBits, Parity, SerialStream, StopBits};
+use tracing::{debug, info, warn};
+
+use self::apdu::{Aare, Aarq, Data, DataType, Descriptor, GetResponse, Mechanism, Obis};
+use self::framing::{control, HdlcFrame, ServerAddress};
+use crate::core::config::{Point, RuntimeChannelConfig};
+use crate::core::plugins::{MappingColumn, ParameterMetadata, ParameterType};
+use crate::core::protocols::{as_u64, link_lost, record_failure};
+use crate::error::{ComSrvError, Result};
+use voltage_model::PointType;
+
+/// Protocol name stored in `channels.protocol`
+pub const PROTOCOL: &str = "dlms";
+
+const DEFAULT_PORT: u16 = 4059;
+const DEFAULT_BAUD_RATE: u32 = 9600;
+const DEFAULT_RESPONSE_TIMEOUT_MS: u64 = 3000;
+/// Public client, allowed to associate without authentication
+const PUBLIC_CLIENT: u8 = 16;
+/// Management client, the usual client of authenticated associations
+const MANAGEMENT_CLIENT: u8 = 1;
+/// Manufacturer "VEM", serial 1
+const DEFAULT_SYSTEM_TITLE: [u8; 8] = [b'V', b'E', b'M', 0, 0, 0, 0, 1];
+/// Largest APDU the client accepts
+const MAX_PDU: u16 = 0xFFFF;
+/// Interface classes whose attribute 2 is scaled by attribute 3
+const REGISTER_CLASSES: [u16; 3] = [3, 4, 5];
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Transport of the APDUs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    /// IEC 62056-47 TCP wrapper
+    Wrapper,
+    /// IEC 62056-46 HDLC, over TCP or a serial line
+    Hdlc,
+}
+
+/// Channel parameters of a DLMS channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct DlmsConfig {
+    pub transport: Transport,
+    /// TCP peer; None for HDLC on the serial `device`
+    pub host: Option<String>,
+    pub port: u16,
+    pub device: Option<String>,
+    pub baud_rate: u32,
+    pub data_bits: DataBits,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+    pub client_address: u8,
+    pub server: ServerAddress,
+    pub mechanism: Mechanism,
+    /// Password (low) or HLS secret (high)
+    pub secret: Vec<u8>,
+    pub system_title: [u8; 8],
+    pub response_timeout: Duration,
+}
+
+impl DlmsConfig {
+    pub fn from_parameters(parameters: &HashMap<String, JsonValue>) -> Result<Self> {
+        let config_error = |message: String| ComSrvError::ConfigError(message);
+        let optional_number = |key: &str| -> Result<Option<u64>> {
+            match parameters.get(key) {
+                None | Some(JsonValue::Null) => Ok(None),
+                Some(JsonValue::String(s)) if s.trim().is_empty() => Ok(None),
+                Some(value) => as_u64(value)
+                    .map(Some)
+                    .ok_or_else(|| config_error(format!("'{}' must be a number", key))),
+            }
+        };
+        let number = |key: &str, default: u64| -> Result<u64> {
+            Ok(optional_number(key)?.unwrap_or(default))
+        };
+        let text = |key: &str| {
+            parameters
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+
+        let transport = match text("transport").map(str::to_ascii_lowercase).as_deref() {
+            None | Some("tcp") | Some("wrapper") => Transport::Wrapper,
+            Some("hdlc") => Transport::Hdlc,
+            Some(other) => {
+                return Err(config_error(format!(
+                    "'transport' must be tcp or hdlc, not '{}'",
+                    other
+                )))
+            },
+        };
+        let host = text("host").map(str::to_string);
+        let device = text("device").map(str::to_string);
+        match (transport, &host, &device) {
+            (Transport::Wrapper, None, _) => {
+                return Err(config_error("DLMS over TCP requires 'host'".into()))
+            },
+            (Transport::Hdlc, None, None) => {
+                return Err(config_error(
+                    "DLMS over HDLC requires 'host' or the serial 'device'".into(),
+                ))
+            },
+            _ => {},
+        }
+
+        let port = u16::try_from(number("port", u64::from(DEFAULT_PORT))?)
+            .map_err(|_| config_error("'port' is out of range".into()))?;
+        let baud_rate = u32::try_from(number("baud_rate", u64::from(DEFAULT_BAUD_RATE))?)
+            .map_err(|_| config_error("'baud_rate' is out of range".into()))?;
+        let data_bits = match number("data_bits", 8)? {
+            7 => DataBits::Seven,
+            8 => DataBits::Eight,
+            _ => return Err(config_error("'data_bits' must be 7 or 8".into())),
+        };
+        let parity = match text("parity").map(str::to_ascii_lowercase).as_deref() {
+            None | Some("none") | Some("n") => Parity::None,
+            Some("even") | Some("e") => Parity::Even,
+            Some("odd") | Some("o") => Parity::Odd,
+            Some(other) => {
+                return Err(config_error(format!(
+                    "'parity' must be none, even or odd, not '{}'",
+                    other
+                )))
+            },
+        };
+        let stop_bits = match number("stop_bits", 1)? {
+            1 => StopBits::One,
+            2 => StopBits::Two,
+            _ => return Err(config_error("'stop_bits' must be 1 or 2".into())),
+        };
+
+        let mechanism = match text("authentication")
+            .map(str::to_ascii_lowercase)
+            .as_deref()
+        {
+            None | Some("none") | Some("lowest") => Mechanism::None,
+            Some("low") | Some("lls") => Mechanism::Low,
+            Some("high") | Some("hls") | Some("high_sha256") | Some("hls_sha256") => {
+                Mechanism::HighSha256
+            },
+            Some(other) => {
+                return Err(config_error(format!(
+                    "'authentication' must be none, low or high_sha256, not '{}'",
+                    other
+                )))
+            },
+        };
+        let secret = match mechanism {
+            Mechanism::None => Vec::new(),
+            Mechanism::Low => text("password")
+                .ok_or_else(|| config_error("low authentication requires 'password'".into()))?
+                .as_bytes()
+                .to_vec(),
+            Mechanism::HighSha256 => text("secret")
+                .ok_or_else(|| config_error("high authentication requires 'secret'".into()))?
+                .as_bytes()
+                .to_vec(),
+        };
+        let system_title = match text("system_title") {
+            None => DEFAULT_SYSTEM_TITLE,
+            Some(title) => parse_system_title(title).ok_or_else(|| {
+                config_error("'system_title' must be 16 hex digits (8 bytes)".into())
+            })?,
+        };
+
+        let default_client = match mechanism {
+            Mechanism::None => PUBLIC_CLIENT,
+            _ => MANAGEMENT_CLIENT,
+        };
+        let client_address = u8::try_from(number("client_address", u64::from(default_client))?)
+            .ok()
+            .filter(|a| *a <= 0x7F)
+            .ok_or_else(|| config_error("'client_address' must be 0-127".into()))?;
+        let address = |key: &str, value: u64| {
+            u16::try_from(value).map_err(|_| config_error(format!("'{}' is out of range", key)))
+        };
+        let server = ServerAddress {
+            logical: address("server_address", number("server_address", 1)?)?,
+            physical: optional_number("physical_address")?
+                .map(|a| address("physical_address", a))
+                .transpose()?,
+        };
+        if transport == Transport::Hdlc {
+            server.encode().map_err(|e| config_error(e.to_string()))?;
+        }
+
+        Ok(Self {
+            transport,
+            host,
+            port,
+            device,
+            baud_rate,
+            data_bits,
+            parity,
+            stop_bits,
+            client_address,
+            server,
+            mechanism,
+            secret,
+            system_title,
+            response_timeout: Duration::from_millis(
+                number("response_timeout_ms", DEFAULT_RESPONSE_TIMEOUT_MS)?.max(1),
+            ),
+        })
+    }
+}
+
+/// Integer from a JSON number or numeric string (CSV imports keep strings)
+fn parse_system_title(text: &str) -> Option<[u8; 8]> {
+    let digits = text.trim_start_matches("0x").trim_start_matches("0X");
+    if digits.len() != 16 {
+        return None;
+    }
+    let mut title = [0u8; 8];
+    for (i, byte) in title.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(digits.get(2 * i..2 * i + 2)?, 16).ok()?;
+    }
+    Some(title)
+}
+
+/// `value * 10^exponent`, dividing for negative exponents to keep decimal
+/// values exact
+fn scale(value: f64, exponent: i32) -> f64 {
+    if exponent >= 0 {
+        value * 10f64.powi(exponent)
+    } else {
+        value / 10f64.powi(-exponent)
+    }
+}
+
+// ============================================================================
+// Point mapping
+// ============================================================================
+
+/// Value a point reads from an attribute
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Reading {
+    internal_id: u32,
+    /// Bit of the value (signal points)
+    bit: Option<u8>,
+    /// Power of ten given by the point; None to use the register's
+    scaler: Option<i32>,
+}
+
+impl Reading {
+    fn decode(
+        &self,
+        data: &Data,
+        register_scaler: Option<i32>,
+    ) -> std::result::Result<f64, String> {
+        match self.bit {
+            Some(bit) => data
+                .as_bits()
+                .map(|word| f64::from(((word >> bit) & 1) as u8))
+                .ok_or_else(|| format!("{:?} is not a bit field", data)),
+            None => {
+                let value = data
+                    .as_f64()
+                    .ok_or_else(|| format!("{:?} is not numeric", data))?;
+                Ok(scale(value, self.scaler.or(register_scaler).unwrap_or(0)))
+            },
+        }
+    }
+}
+
+/// Attribute a control or adjustment point sets, or method it invokes
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Command {
+    descriptor: Descriptor,
+    method: bool,
+    /// Type of the written value; methods without one take integer 0
+    data_type: Option<DataType>,
+    scaler: i32,
+}
+
+enum Mapped {
+    Read(Descriptor, Reading),
+    Write(Command),
+}
+
+fn parse_point(point_type: PointType, point: &Point) -> std::result::Result<Mapped, String> {
+    let mapping: JsonValue = point
+        .protocol_mappings
+        .as_deref()
+        .and_then(|m| serde_json::from_str(m).ok())
+        .ok_or("missing DLMS mapping")?;
+    let text = |key: &str| {
+        mapping
+            .get(key)
+            .and_then(|v| match v {
+                JsonValue::String(s) => Some(s.trim().to_string()),
+                JsonValue::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .filter(|v| !v.is_empty())
+    };
+    let integer = |key: &str| -> std::result::Result<Option<i64>, String> {
+        text(key)
+            .map(|v| {
+                v.parse::<i64>()
+                    .map_err(|_| format!("'{}' must be an integer", key))
+            })
+            .transpose()
+    };
+
+    let obis = Obis::parse(&text("obis").ok_or("missing 'obis'")?).map_err(|e| e.to_string())?;
+    let default_class = match point_type {
+        PointType::Telemetry => 3,
+        _ => 1,
+    };
+    let class_id = u16::try_from(integer("class_id")?.unwrap_or(default_class))
+        .map_err(|_| "'class_id' is out of range")?;
+    let index = |key: &str, value: i64| {
+        i8::try_from(value)
+            .ok()
+            .filter(|v| *v > 0)
+            .ok_or_else(|| format!("'{}' must be 1-127", key))
+    };
+    let attribute = index("attribute", integer("attribute")?.unwrap_or(2))?;
+    let scaler = integer("scaler")?
+        .map(|s| i32::try_from(s).ok().filter(|s| s.abs() <= 18))
+        .map(|s| s.ok_or("'scaler' must be a power of ten between -18 and 18"))
+        .transpose()?;
+    let bit = integer("bit")?
+        .map(|b| {
+            u8::try_from(b)
+                .ok()
+                .filter(|b| *b < 64)
+                .ok_or("'bit' must be 0-63")
+        })
+        .transpose()?;
+
+    match point_type {
+        PointType::Telemetry | PointType::Signal => Ok(Mapped::Read(
+            Descriptor {
+                class_id,
+                obis,
+                index: attribute,
+            },
+            Reading {
+                internal_id: point_type.to_internal_id(point.point_id),
+                bit: bit.filter(|_| point_type == PointType::Signal),
+                scaler,
+            },
+        )),
+        PointType::Control | PointType::Adjustment => {
+            let method = integer("method")?.map(|m| index("method", m)).transpose()?;
+            let data_type = text("data_type")
+                .map(|t| DataType::parse(&t))
+                .transpose()
+                .map_err(|e| e.to_string())?;
+            if method.is_none() && data_type.is_none() {
+                return Err("'data_type' is required to set an attribute".to_string());
+            }
+            Ok(Mapped::Write(Command {
+                descriptor: Descriptor {
+                    class_id,
+                    obis,
+                    index: method.unwrap_or(attribute),
+                },
+                method: method.is_some(),
+                data_type,
+                scaler: scaler.unwrap_or(0),
+            }))
+        },
+    }
+}
+
+// ============================================================================
+// Runtime
+// ============================================================================
+
+/// Byte stream to the meter: TCP, the serial port, or a test double
+trait Link: AsyncRead + AsyncWrite + Send + Sync + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Sync + Unpin> Link for T {}
+
+/// DLMS/COSEM client of one meter
+pub struct DlmsRuntime {
+    id: u32,
+    name: String,
+    config: DlmsConfig,
+    /// HDLC addresses (server, client)
+    hdlc_addresses: (Vec<u8>, Vec<u8>),
+    /// Attribute -> points reading it
+    reads: BTreeMap<Descriptor, Vec<Reading>>,
+    controls: HashMap<u32, Command>,
+    adjustments: HashMap<u32, Command>,
+    /// Power of ten of register values, from their scaler_unit
+    scalers: HashMap<Descriptor, i32>,
+    link: Option<Box<dyn Link>>,
+    /// Bytes received beyond the last frame
+    pending: Vec<u8>,
+    /// HDLC send and receive sequence numbers
+    sequence: (u8, u8),
+    associated: bool,
+    diagnostics: Diagnostics,
+}
+
+impl DlmsRuntime {
+    /// Build the runtime of a `dlms` channel
+    pub fn from_runtime_config(runtime_config: &RuntimeChannelConfig) -> Result<Self> {
+        let channel_id = runtime_config.id();
+        let config = DlmsConfig::from_parameters(&runtime_config.base.parameters)
+            .map_err(|e| ComSrvError::ConfigError(format!("Ch{}: {}", channel_id, e)))?;
+        let hdlc_addresses = match config.transport {
+            Transport::Hdlc => (
+                config.server.encode(),
+                framing::client_address(config.client_address),
+            ),
+            Transport::Wrapper => (Ok(Vec::new()), Ok(Vec::new())),
+        };
+        let hdlc_addresses = match hdlc_addresses {
+            (Ok(server), Ok(client)) => (server, client),
+            (Err(e), _) | (_, Err(e)) => {
+                return Err(ComSrvError::ConfigError(format!("Ch{}: {}", channel_id, e)))
+            },
+        };
+
+        let mut reads: BTreeMap<_, Vec<Reading>> = BTreeMap::new();
+        let mut controls = HashMap::new();
+        let mut adjustments = HashMap::new();
+        for (point_type, point) in runtime_config.points() {
+            match parse_point(point_type, point) {
+                Ok(Mapped::Read(descriptor, reading)) => {
+                    reads.entry(descriptor).or_default().push(reading);
+                },
+                Ok(Mapped::Write(command)) => {
+                    let commands = match point_type {
+                        PointType::Control => &mut controls,
+                        _ => &mut adjustments,
+                    };
+                    commands.insert(point.point_id, command);
+                },
+                Err(e) => warn!(
+                    "Ch{} {}{} skipped: {}",
+                    channel_id,
+                    point_type.as_str(),
+                    point.point_id,
+                    e
+                ),
+            }
+        }
+        debug!(
+            "Ch{} DLMS: {} reads, {} controls, {} adjustments",
+            channel_id,
+            reads.len(),
+            controls.len(),
+            adjustments.len()
+        );
+
+        Ok(Self {
+            id: channel_id,
+            name: runtime_config.name().to_string(),
+            config,
+            hdlc_addresses,
+            reads,
+            controls,
+            adjustments,
+            scalers: HashMap::new(),
+            link: None,
+            pending: Vec::new(),
+            sequence: (0, 0),
+            associated: false,
+            diagnostics: Diagnostics::new(PROTOCOL),
+        })
+    }
+
+    /// Record an error; connection failures drop the link so the next
+    /// `connect()` reconnects and associates again
+    fn fail(&mut self, error: GatewayError) -> GatewayError {
+        if record_failure(&mut self.diagnostics, &error) {
+            self.link = None;
+            self.associated = false;
+        }
+        error
+    }
+
+    fn peer(&self) -> String {
+        match (&self.config.host, &self.config.device) {
+            (Some(host), _) => format!("{}:{}", host, self.config.port),
+            (None, Some(device)) => device.clone(),
+            (None, None) => String::new(),
+        }
+    }
+
+    async fn open(&self) -> igw::Result<Box<dyn Link>> {
+        if self.config.host.is_some() {
+            let address = self.peer();
+            let stream =
+                tokio::time::timeout(self.config.response_timeout, TcpStream::connect(&address))
+                    .await
+                    .map_err(|_| {
+                        GatewayError::ConnectionTimeout(
+                            self.config.response_timeout.as_millis() as u64
+                        )
+                    })?
+                    .map_err(|e| GatewayError::Connection(format!("{}: {}", address, e)))?;
+            let _ = stream.set_nodelay(true);
+            return Ok(Box::new(stream));
+        }
+        let device = self.config.device.as_deref().unwrap_or_default();
+        let builder = tokio_serial::new(device, self.config.baud_rate)
+            .data_bits(self.config.data_bits)
+            .parity(self.config.parity)
+            .stop_bits(self.config.stop_bits);
+        let port = SerialStream::open(&builder)
+            .map_err(|e| GatewayError::Connection(format!("{}: {}", device, e)))?;
+        Ok(Box::new(port))
+    }
+
+    async fn send(&mut self, bytes: &[u8]) -> igw::Result<()> {
+        let link = self.link.as_mut().ok_or(GatewayError::NotConnected)?;
+        link.write_all(bytes)
+            .await
+            .map_err(|e| GatewayError::Connection(format!("send: {}", e)))?;
+        link.flush()
+            .await
+            .map_err(|e| GatewayError::Connection(format!("send: {}", e)))
+    }
+
+    /// Read more bytes into `pending`
+    async fn fill(&mut self, deadline: Instant) -> igw::Result<()> {
+        let link = self.link.as_mut().ok_or(GatewayError::NotConnected)?;
+        let mut buf = [0u8; 1024];
+        let n = timeout_at(deadline, link.read(&mut buf))
+            .await
+            .map_err(|_| GatewayError::ReadTimeout)?
+            .map_err(|e| GatewayError::Connection(format!("receive: {}", e)))?;
+        if n == 0 {
+            return Err(GatewayError::Connection(
+                "connection closed by the meter".to_string(),
+            ));
+        }
+        self.pending.extend_from_slice(&buf[..n]);
+        Ok(())
+    }
+
+    fn hdlc_frame(&self, control: u8, info: &[u8]) -> igw::Result<Vec<u8>> {
+        let (server, client) = &self.hdlc_addresses;
+        framing::encode(server, client, control, info)
+            .map_err(|e| GatewayError::Protocol(e.to_string()))
+    }
+
+    /// Next HDLC frame from the meter to this client
+    ///
+    /// Echoes and frames of other stations on the line are skipped.
+    async fn receive_frame(&mut self, deadline: Instant) -> igw::Result<HdlcFrame> {
+        loop {
+            match framing::decode(&self.pending) {
+                Ok(Some((frame, used))) => {
+                    self.pending.drain(..used);
+                    let (server, client) = &self.hdlc_addresses;
+                    if &frame.destination == client && &frame.source == server {
+                        return Ok(frame);
+                    }
+                },
+                Ok(None) => self.fill(deadline).await?,
+                Err(e) => {
+                    // Resynchronize on the next flag
+                    debug!("Ch{} discarded bytes: {}", self.id, e);
+                    self.pending.remove(0);
+                },
+            }
+        }
+    }
+
+    /// Send an unnumbered HDLC command (SNRM, DISC) and return the reply
+    async fn hdlc_command(&mut self, control: u8) -> igw::Result<HdlcFrame> {
+        let frame = self.hdlc_frame(control, &[])?;
+        let deadline = Instant::now() + self.config.response_timeout;
+        self.pending.clear();
+        self.send(&frame).await?;
+        self.receive_frame(deadline).await
+    }
+
+    /// Send a request APDU and return the response APDU
+    async fn exchange(&mut self, request: &[u8]) -> igw::Result<Vec<u8>> {
+        let deadline = Instant::now() + self.config.response_timeout;
+        self.pending.clear();
+        match self.config.transport {
+            Transport::Wrapper => {
+                let frame = framing::wrap(
+                    u16::from(self.config.client_address),
+                    self.config.server.logical,
+                    request,
+                )
+                .map_err(|e| GatewayError::Protocol(e.to_string()))?;
+                self.send(&frame).await?;
+                loop {
+                    match framing::unwrap(&self.pending) {
+                        Ok(Some((wrapped, used))) => {
+                            self.pending.drain(..used);
+                            return Ok(wrapped.apdu);
+                        },
+                        Ok(None) => self.fill(deadline).await?,
+                        // The stream is out of step; reconnect
+                        Err(e) => return Err(GatewayError::Connection(e.to_string())),
+                    }
+                }
+            },
+            Transport::Hdlc => {
+                let (send, receive) = self.sequence;
+                let info = [&framing::LLC_REQUEST[..], request].concat();
+                let frame = self.hdlc_frame(control::information(send, receive), &info)?;
+                self.sequence.0 = (send + 1) % 8;
+                self.send(&frame).await?;
+
+                let mut response = Vec::new();
+                loop {
+                    let frame = self.receive_frame(deadline).await?;
+                    if !control::is_information(frame.control) {
+                        return Err(GatewayError::Connection(format!(
+                            "HDLC link reset by the meter (control {:02X})",
+                            frame.control
+                        )));
+                    }
+                    self.sequence.1 = (control::send_sequence(frame.control) + 1) % 8;
+                    response.extend_from_slice(&frame.info);
+                    if !frame.segmented {
+                        break;
+                    }
+                    let ready = self.hdlc_frame(control::receive_ready(self.sequence.1), &[])?;
+                    self.send(&ready).await?;
+                }
+                response
+                    .strip_prefix(&framing::LLC_RESPONSE[..])
+                    .map(<[u8]>::to_vec)
+                    .ok_or_else(|| {
+                        GatewayError::InvalidResponse("response without LLC header".to_string())
+                    })
+            },
+        }
+    }
+
+    /// GET an attribute, following block transfer of long values
+    async fn get(&mut self, attribute: &Descriptor) -> igw::Result<Data> {
+        let invalid = |e: crate::core::protocols::CodecError| {
+            GatewayError::InvalidResponse(format!("{}/{}: {}", attribute.obis, attribute.index, e))
+        };
+        let mut response = self.exchange(&apdu::get_request(attribute)).await?;
+        let mut blocks = Vec::new();
+        loop {
+            match GetResponse::parse(&response).map_err(invalid)? {
+                GetResponse::Data(data) => return Ok(data),
+                GetResponse::Block { last, number, raw } => {
+                    blocks.extend_from_slice(&raw);
+                    if last {
+                        return Data::decode(&blocks).map(|(data, _)| data).map_err(invalid);
+                    }
+                    response = self.exchange(&apdu::get_next_request(number)).await?;
+                },
+            }
+        }
+    }
+
+    /// Open the application association, authenticating as configured
+    async fn associate(&mut self) -> igw::Result<()> {
+        let mechanism = self.config.mechanism;
+        let mut challenge = [0u8; 16];
+        let authentication_value = match mechanism {
+            Mechanism::None => Vec::new(),
+            Mechanism::Low => self.config.secret.clone(),
+            Mechanism::HighSha256 => {
+                rand::thread_rng().fill_bytes(&mut challenge);
+                challenge.to_vec()
+            },
+        };
+        let own_title = self.config.system_title;
+        let aarq = Aarq {
+            mechanism,
+            authentication_value: &authentication_value,
+            system_title: (mechanism == Mechanism::HighSha256).then_some(own_title),
+            max_pdu: MAX_PDU,
+        }
+        .encode();
+        let response = self.exchange(&aarq).await?;
+        let aare = Aare::parse(&response)
+            .map_err(|e| GatewayError::Connection(format!("association failed: {}", e)))?;
+        if !aare.accepted {
+            return Err(GatewayError::Connection(format!(
+                "association rejected: {}",
+                apdu::association_diagnostic(aare.diagnostic)
+            )));
+        }
+
+        if mechanism == Mechanism::HighSha256 {
+            let (Some(server_title), Some(server_challenge)) =
+                (aare.server_system_title, aare.challenge)
+            else {
+                return Err(GatewayError::Connection(
+                    "meter sent no HLS challenge".to_string(),
+                ));
+            };
+            let secret = &self.config.secret;
+            let proof = apdu::hls_sha256(
+                secret,
+                &own_title,
+                &server_title,
+                &server_challenge,
+                &challenge,
+            );
+            let expected = apdu::hls_sha256(
+                secret,
+                &server_title,
+                &own_title,
+                &challenge,
+                &server_challenge,
+            );
+            // reply_to_HLS_authentication of the current association
+            let method = Descriptor {
+                class_id: 15,
+                obis: Obis::ASSOCIATION_LN,
+                index: 1,
+            };
+            let response = self
+                .exchange(&apdu::action_request(
+                    &method,
+                    Some(&apdu::octet_string(&proof)),
+                ))
+                .await?;
+            let reply = apdu::parse_action_response(&response).map_err(|e| {
+                GatewayError::Connection(format!("HLS authentication failed: {}", e))
+            })?;
+            if reply != Some(Data::Octets(expected)) {
+                return Err(GatewayError::Connection(
+                    "meter failed HLS authentication".to_string(),
+                ));
+            }
+        }
+        self.associated = true;
+        Ok(())
+    }
+
+    /// Read the scaler of register values that points do not scale
+    /// themselves
+    async fn read_scalers(&mut self) -> igw::Result<()> {
+        let registers: Vec<Descriptor> = self
+            .reads
+            .iter()
+            .filter(|(descriptor, readings)| {
+                REGISTER_CLASSES.contains(&descriptor.class_id)
+                    && descriptor.index == 2
+                    && readings
+                        .iter()
+                        .any(|r| r.bit.is_none() && r.scaler.is_none())
+            })
+            .map(|(descriptor, _)| *descriptor)
+            .collect();
+        for value in registers {
+            let scaler_unit = Descriptor { index: 3, ..value };
+            match self.get(&scaler_unit).await {
+                Ok(Data::Structure(items)) => {
+                    if let Some(scaler) = items.first().and_then(Data::as_f64) {
+                        self.scalers.insert(value, scaler as i32);
+                    }
+                },
+                Ok(other) => warn!(
+                    "Ch{} {} scaler_unit is {:?}, not a structure",
+                    self.id, value.obis, other
+                ),
+                Err(e) if link_lost(&e) => return Err(e),
+                Err(e) => warn!("Ch{} {} scaler unavailable: {}", self.id, value.obis, e),
+            }
+        }
+        Ok(())
+    }
+
+    async fn after_connect(&mut self) -> igw::Result<()> {
+        self.associated = false;
+        if self.config.transport == Transport::Hdlc {
+            self.sequence = (0, 0);
+            let reply = self.hdlc_command(control::SNRM).await?;
+            if reply.control != control::UA {
+                return Err(GatewayError::Connection(format!(
+                    "meter refused the HDLC connection (control {:02X})",
+                    reply.control
+                )));
+            }
+        }
+        self.associate().await?;
+        self.read_scalers().await
+    }
+
+    /// Use an already open byte stream instead of TCP or the serial port
+    #[cfg(test)]
+    fn attach(&mut self, link: impl Link + 'static) {
+        self.link = Some(Box::new(link));
+        self.diagnostics.connection_state = ConnectionState::Connected;
+    }
+
+    async fn operate(&mut self, command: Command, value: f64) -> igw::Result<()> {
+        let mut encoded = Vec::new();
+        match command.data_type {
+            Some(data_type) => data_type
+                .encode(scale(value, -command.scaler), &mut encoded)
+                .map_err(|e| GatewayError::Protocol(e.to_string()))?,
+            None => encoded.extend([15, 0]),
+        }
+        let result = if command.method {
+            let response = self
+                .exchange(&apdu::action_request(&command.descriptor, Some(&encoded)))
+                .await?;
+            apdu::parse_action_response(&response).map(|_| ())
+        } else {
+            let response = self
+                .exchange(&apdu::set_request(&command.descriptor, &encoded))
+                .await?;
+            apdu::parse_set_response(&response)
+        };
+        result.map_err(|e| {
+            GatewayError::Protocol(format!(
+                "{}/{}: {}",
+                command.descriptor.obis, command.descriptor.index, e
+            ))
+        })
+    }
+
+    async fn write(&mut self, point_type: PointType, values: &[(u32, f64)]) -> igw::Result<usize> {
+        let mut written = 0;
+        let mut last_error = None;
+        for &(internal_id, value) in values {
+            let point_id = PointType::from_internal_id(internal_id).1;
+            let commands = match point_type {
+                PointType::Control => &self.controls,
+                _ => &self.adjustments,
+            };
+            let Some(command) = commands.get(&point_id).copied() else {
+                last_error = Some(GatewayError::PointNotFound(format!(
+                    "{}{}",
+                    point_type.as_str(),
+                    point_id
+                )));
+                continue;
+            };
+            match self.operate(command, value).await {
+                Ok(()) => written += 1,
+                Err(e) => {
+                    let e = self.fail(e);
+                    if self.link.is_none() {
+                        return Err(e);
+                    }
+                    last_error = Some(e);
+                },
+            }
+        }
+        self.diagnostics.write_count += written as u64;
+        match last_error {
+            Some(e) if written == 0 => Err(e),
+            _ => Ok(written),
+        }
+    }
+}
+
+#[async_trait]
+impl ChannelRuntime for DlmsRuntime {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn protocol(&self) -> &str {
+        PROTOCOL
+    }
+
+    fn is_event_driven(&self) -> bool {
+        false
+    }
+
+    async fn connect(&mut self) -> igw::Result<()> {
+        self.diagnostics.connection_state = ConnectionState::Connecting;
+        let link = match self.open().await {
+            Ok(link) => link,
+            Err(e) => return Err(self.fail(e)),
+        };
+        self.link = Some(link);
+        self.pending.clear();
+        if let Err(e) = self.after_connect().await {
+            self.link = None;
+            self.associated = false;
+            return Err(self.fail(e));
+        }
+        self.diagnostics.connection_state = ConnectionState::Connected;
+        info!(
+            "Ch{} DLMS associated with {} ({:?})",
+            self.id,
+            self.peer(),
+            self.config.mechanism
+        );
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> igw::Result<()> {
+        if self.link.is_some() {
+            // Best effort: the meter also drops the association with the link
+            if self.associated {
+                let _ = self.exchange(&apdu::release_request()).await;
+            }
+            if self.config.transport == Transport::Hdlc {
+                let _ = self.hdlc_command(control::DISC).await;
+            }
+        }
+        if let Some(mut link) = self.link.take() {
+            let _ = link.shutdown().await;
+        }
+        self.associated = false;
+        self.diagnostics.connection_state = ConnectionState::Disconnected;
+        Ok(())
+    }
+
+    async fn poll_once(&mut self) -> PollResult {
+        if self.link.is_none() {
+            return PollResult::failed(vec![PointFailure::new(0, "not connected")]);
+        }
+
+        let reads: Vec<_> = self
+            .reads
+            .iter()
+            .map(|(descriptor, readings)| (*descriptor, readings.clone()))
+            .collect();
+        let mut batch = DataBatch::default();
+        let mut failures = Vec::new();
+        for (descriptor, readings) in reads {
+            match self.get(&descriptor).await {
+                Ok(data) => {
+                    let register_scaler = self.scalers.get(&descriptor).copied();
+                    for reading in &readings {
+                        match reading.decode(&data, register_scaler) {
+                            Ok(value) => batch.add(DataPoint::new(reading.internal_id, value)),
+                            Err(e) => {
+                                failures.push(PointFailure::with_error(reading.internal_id, e))
+                            },
+                        }
+                    }
+                },
+                Err(e) => {
+                    let e = self.fail(e);
+                    failures.extend(
+                        readings
+                            .iter()
+                            .map(|r| PointFailure::with_error(r.internal_id, e.to_string())),
+                    );
+                    if self.link.is_none() {
+                        break;
+                    }
+                },
+            }
+        }
+        self.diagnostics.read_count += 1;
+        PollResult::partial(batch, failures)
+    }
+
+    async fn write_control(&mut self, commands: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Control, commands).await
+    }
+
+    async fn write_adjustment(&mut self, adjustments: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Adjustment, adjustments).await
+    }
+
+    fn subscribe(&self) -> Option<DataEventReceiver> {
+        None
+    }
+
+    async fn start_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn stop_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn diagnostics(&self) -> igw::Result<Diagnostics> {
+        let mut diagnostics = self.diagnostics.clone();
+        diagnostics.extra = json!({
+            "peer": self.peer(),
+            "transport": match self.config.transport {
+                Transport::Wrapper => "tcp",
+                Transport::Hdlc => "hdlc",
+            },
+            "authentication": format!("{:?}", self.config.mechanism),
+            "associated": self.associated,
+            "reads": self.reads.len(),
+            "register_scalers": self.scalers.len(),
+        });
+        Ok(diagnostics)
+    }
+}
+
+// ============================================================================
+// Plugin metadata
+// ============================================================================
+
+fn parameters() -> Vec<ParameterMetadata> {
+    vec![
+        ParameterMetadata::optional(
+            "transport",
+            "Transport",
+            "tcp (IEC 62056-47 wrapper) or hdlc",
+            ParameterType::String,
+            json!("tcp"),
+        ),
+        ParameterMetadata::optional(
+            "host",
+            "Host",
+            "Meter or serial server IP address; required for tcp",
+            ParameterType::String,
+            json!(""),
+        ),
+        ParameterMetadata::optional(
+            "port",
+            "Port",
+            "TCP port",
+            ParameterType::Integer,
+            json!(DEFAULT_PORT),
+        ),
+        ParameterMetadata::optional(
+            "device",
+            "Serial Device",
+            "Serial port of HDLC without a host, e.g. /dev/ttyUSB0",
+            ParameterType::String,
+            json!(""),
+        ),
+        ParameterMetadata::optional(
+            "baud_rate",
+            "Baud Rate",
+            "Line speed",
+            ParameterType::Integer,
+            json!(DEFAULT_BAUD_RATE),
+        ),
+        ParameterMetadata::optional(
+            "data_bits",
+            "Data Bits",
+            "7 or 8",
+            ParameterType::Integer,
+            json!(8),
+        ),
+        ParameterMetadata::optional(
+            "parity",
+            "Parity",
+            "none, even or odd",
+            ParameterType::String,
+            json!("none"),
+        ),
+        ParameterMetadata::optional(
+            "stop_bits",
+            "Stop Bits",
+            "1 or 2",
+            ParameterType::Integer,
+            json!(1),
+        ),
+        ParameterMetadata::optional(
+            "client_address",
+            "Client Address",
+            "Client SAP; defaults to 16 (public) without authentication, else 1",
+            ParameterType::Integer,
+            json!(PUBLIC_CLIENT),
+        ),
+        ParameterMetadata::optional(
+            "server_address",
+            "Server Address",
+            "Logical device of the meter (server SAP)",
+            ParameterType::Integer,
+            json!(1),
+        ),
+        ParameterMetadata::optional(
+            "physical_address",
+            "Physical Address",
+            "HDLC lower address of the meter on a multi-drop line",
+            ParameterType::Integer,
+            json!(null),
+        ),
+        ParameterMetadata::optional(
+            "authentication",
+            "Authentication",
+            "none, low (password) or high_sha256",
+            ParameterType::String,
+            json!("none"),
+        ),
+        ParameterMetadata::optional(
+            "password",
+            "Password",
+            "Low level security password",
+            ParameterType::String,
+            json!(""),
+        ),
+        ParameterMetadata::optional(
+            "secret",
+            "HLS Secret",
+            "High level security secret",
+            ParameterType::String,
+            json!(""),
+        ),
+        ParameterMetadata::optional(
+            "system_title",
+            "System Title",
+            "Client system title for HLS, 16 hex digits",
+            ParameterType::String,
+            json!("56454D0000000001"),
+        ),
+        ParameterMetadata::optional(
+            "response_timeout_ms",
+            "Response Timeout (ms)",
+            "Time to wait for a meter response",
+            ParameterType::Integer,
+            json!(DEFAULT_RESPONSE_TIMEOUT_MS),
+        ),
+    ]
+}
+
+crate::protocol_plugin! {
+    /// DLMS/COSEM client (in-tree runtime)
+    pub struct DlmsPlugin {
+        name: PROTOCOL,
+        aliases: &["dlms_cosem", "cosem", "iec62056"],
+        display_name: "DLMS/COSEM",
+        description: "IEC 62056 smart meters by OBIS code over HDLC or the TCP wrapper",
+        parameters: parameters(),
+        mapping_columns: &[
+            MappingColumn::string("obis", "OBIS code, e.g. 1-0:1.8.0*255").required(),
+            MappingColumn::integer(
+                "class_id",
+                "Interface class, defaults to 3 (register) for telemetry and 1 (data) otherwise",
+            )
+            .range(1, 65535),
+            MappingColumn::integer("attribute", "Attribute index").range(1, 127).default_value("2"),
+            MappingColumn::integer("method", "Method invoked instead of setting the attribute")
+                .range(1, 127),
+            MappingColumn::integer("scaler", "Power of ten of the value; registers use their own")
+                .range(-18, 18),
+            MappingColumn::integer("bit", "Bit of the value (signal points)").range(0, 63),
+            MappingColumn::string("data_type", "Type of written values").choices(&[
+                "boolean",
+                "integer",
+                "long",
+                "double-long",
+                "long64",
+                "unsigned",
+                "long-unsigned",
+                "double-long-unsigned",
+                "long64-unsigned",
+                "enum",
+                "float32",
+                "float64",
+            ]),
+        ],
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use tokio::io::DuplexStream;
+
+    const SECRET: &str = "0123456789ABCDEF";
+    const METER_TITLE: [u8; 8] = [b'M', b'M', b'M', 0, 0, 0, 0, 7];
+    const METER_CHALLENGE: [u8; 16] = [0x5A; 16];
+
+    fn point<T: serde::de::DeserializeOwned>(point_id: u32, mapping: JsonValue) -> T {
+        serde_json::from_value(json!({
+            "point_id": point_id,
+            "signal_name": format!("p{}", point_id),
+            "protocol_mappings": mapping.to_string(),
+        }))
+        .unwrap()
+    }
+
+    /// Response of a meter with HLS-SHA256 and a few objects
+    fn answer(request: &[u8], client_challenge: &mut Vec<u8>) -> Vec<u8> {
+        match request {
+            [apdu::tag::AARQ, ..] => {
+                let at = request
+                    .windows(4)
+                    .position(|w| w == [0xAC, 0x12, 0x80, 0x10])
+                    .unwrap();
+                *client_challenge = request[at + 4..at + 20].to_vec();
+                let mut body = vec![
+                    0xA1, 0x09, 0x06, 0x07, 0x60, 0x85, 0x74, 0x05, 0x08, 0x01, 0x01, 0xA2, 0x03,
+                    0x02, 0x01, 0x00, 0xA3, 0x05, 0xA1, 0x03, 0x02, 0x01, 0x0E, 0xA4, 0x0A, 0x04,
+                    0x08,
+                ];
+                body.extend(METER_TITLE);
+                body.extend([0xAA, 0x12, 0x80, 0x10]);
+                body.extend(METER_CHALLENGE);
+                body.extend([
+                    0xBE, 0x10, 0x04, 0x0E, 0x08, 0x00, 0x06, 0x5F, 0x1F, 0x04, 0x00, 0x00, 0x18,
+                    0x1D, 0x04, 0x00, 0x00, 0x07,
+                ]);
+                [vec![apdu::tag::AARE, body.len() as u8], body].concat()
+            },
+            // reply_to_HLS_authentication: OBIS, method, parameter flag, 09 20
+            [apdu::tag::ACTION_REQUEST, 0x01, _, 0x00, 0x0F, rest @ ..] => {
+                let proof = &rest[10..];
+                let secret = SECRET.as_bytes();
+                let client_title = DEFAULT_SYSTEM_TITLE;
+                let expected = apdu::hls_sha256(
+                    secret,
+                    &client_title,
+                    &METER_TITLE,
+                    &METER_CHALLENGE,
+                    client_challenge,
+                );
+                if proof != expected.as_slice() {
+                    return vec![0xC7, 0x01, 0xC1, 0x03, 0x00];
+                }
+                let reply = apdu::hls_sha256(
+                    secret,
+                    &METER_TITLE,
+                    &client_title,
+                    client_challenge,
+                    &METER_CHALLENGE,
+                );
+                [
+                    vec![0xC7, 0x01, 0xC1, 0x00, 0x01, 0x00],
+                    apdu::octet_string(&reply),
+                ]
+                .concat()
+            },
+            // Disconnect control methods
+            [apdu::tag::ACTION_REQUEST, 0x01, _, 0x00, 0x46, ..] => {
+                vec![0xC7, 0x01, 0xC1, 0x00, 0x00]
+            },
+            [apdu::tag::GET_REQUEST, 0x01, _, _, _, obis @ .., attribute, 0x00] => {
+                match (obis, attribute) {
+                    ([1, 0, 1, 8, 0, 255], 2) => {
+                        vec![0xC4, 0x01, 0xC1, 0x00, 0x06, 0x00, 0x01, 0xE2, 0x40]
+                    },
+                    // scaler -1, unit Wh
+                    ([1, 0, 1, 8, 0, 255], 3) => {
+                        vec![0xC4, 0x01, 0xC1, 0x00, 0x02, 0x02, 0x0F, 0xFF, 0x16, 0x1E]
+                    },
+                    ([0, 0, 96, 5, 0, 255], 2) => vec![0xC4, 0x01, 0xC1, 0x00, 0x11, 0x05],
+                    // First of two blocks
+                    ([0, 0, 96, 8, 0, 255], 2) => {
+                        vec![0xC4, 0x02, 0xC1, 0x00, 0, 0, 0, 1, 0x00, 0x02, 0x06, 0x00]
+                    },
+                    _ => vec![0xC4, 0x01, 0xC1, 0x01, 0x04],
+                }
+            },
+            [apdu::tag::GET_REQUEST, 0x02, _, 0, 0, 0, 1] => {
+                vec![
+                    0xC4, 0x02, 0xC1, 0x01, 0, 0, 0, 2, 0x00, 0x03, 0x01, 0xE2, 0x40,
+                ]
+            },
+            [apdu::tag::SET_REQUEST, ..] => vec![0xC5, 0x01, 0xC1, 0x00],
+            [apdu::tag::RLRQ, ..] => vec![0x63, 0x03, 0x80, 0x01, 0x00],
+            _ => vec![apdu::tag::EXCEPTION_RESPONSE, 0x01, 0x02],
+        }
+    }
+
+    /// Meter on the TCP wrapper
+    async fn meter(mut stream: DuplexStream) {
+        let mut client_challenge = Vec::new();
+        let mut pending = Vec::new();
+        let mut buf = [0u8; 512];
+        loop {
+            let Ok(n) = stream.read(&mut buf).await else {
+                return;
+            };
+            if n == 0 {
+                return;
+            }
+            pending.extend_from_slice(&buf[..n]);
+            while let Some((request, used)) = framing::unwrap(&pending).unwrap() {
+                pending.drain(..used);
+                let reply = answer(&request.apdu, &mut client_challenge);
+                let frame = framing::wrap(request.destination, request.source, &reply).unwrap();
+                stream.write_all(&frame).await.unwrap();
+            }
+        }
+    }
+
+    fn channel(secret: &str) -> RuntimeChannelConfig {
+        let mut config = RuntimeChannelConfig::from_base(
+            serde_json::from_value(json!({
+                "id": 31,
+                "name": "meter",
+                "protocol": PROTOCOL,
+                "parameters": {
+                    "host": "127.0.0.1",
+                    "authentication": "high_sha256",
+                    "secret": secret,
+                    "response_timeout_ms": 2000,
+                },
+            }))
+            .unwrap(),
+        );
+        config.telemetry_points = vec![
+            point(1, json!({"obis": "1-0:1.8.0*255"})),
+            // Block transfer
+            point(2, json!({"obis": "0.0.96.8.0.255", "class_id": 1})),
+            // Object undefined
+            point(3, json!({"obis": "1.0.2.8.0.255"})),
+            point(4, json!({"class_id": 1})),
+        ];
+        config.signal_points = vec![
+            point(1, json!({"obis": "0-0:96.5.0*255", "bit": 2})),
+            point(2, json!({"obis": "0-0:96.5.0*255", "bit": 1})),
+        ];
+        config.control_points = vec![point(
+            1,
+            json!({"obis": "0-0:96.3.10*255", "class_id": 70, "method": 1}),
+        )];
+        config.adjustment_points = vec![
+            point(
+                1,
+                json!({"obis": "1-0:0.8.0*255", "data_type": "double-long-unsigned"}),
+            ),
+            // Attribute without a type: skipped
+            point(2, json!({"obis": "1-0:0.8.0*255"})),
+        ];
+        config
+    }
+
+    #[tokio::test]
+    async fn test_hls_association_and_poll() {
+        let mut runtime = DlmsRuntime::from_runtime_config(&channel(SECRET)).unwrap();
+        assert_eq!(runtime.reads.len(), 4);
+        assert_eq!(runtime.adjustments.len(), 1);
+        let (client, server) = tokio::io::duplex(4096);
+        tokio::spawn(meter(server));
+        runtime.attach(client);
+        runtime.after_connect().await.unwrap();
+        assert!(runtime.associated);
+        assert_eq!(runtime.scalers.len(), 1);
+
+        let result = runtime.poll_once().await;
+        let value = |point_type: PointType, id: u32| {
+            result
+                .data
+                .iter()
+                .find(|p| p.id == point_type.to_internal_id(id))
+                .map(|p| p.value.as_f64().unwrap())
+        };
+        assert_eq!(value(PointType::Telemetry, 1), Some(12345.6));
+        assert_eq!(value(PointType::Telemetry, 2), Some(123_456.0));
+        assert_eq!(value(PointType::Signal, 1), Some(1.0));
+        assert_eq!(value(PointType::Signal, 2), Some(0.0));
+        assert_eq!(result.failures.len(), 1);
+
+        let control = PointType::Control.to_internal_id(1);
+        assert_eq!(runtime.write_control(&[(control, 1.0)]).await.unwrap(), 1);
+        let adjustment = PointType::Adjustment.to_internal_id(1);
+        assert_eq!(
+            runtime
+                .write_adjustment(&[(adjustment, 900.0)])
+                .await
+                .unwrap(),
+            1
+        );
+        assert!(runtime
+            .write_adjustment(&[(adjustment, -1.0)])
+            .await
+            .is_err());
+        runtime.disconnect().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_hls_wrong_secret() {
+        let mut runtime = DlmsRuntime::from_runtime_config(&channel("wrong")).unwrap();
+        let (client, server) = tokio::io::duplex(4096);
+        tokio::spawn(meter(server));
+        runtime.attach(client);
+        let error = runtime.after_connect().await.unwrap_err();
+        assert!(error.to_string().contains("HLS authentication failed"));
+        assert!(!runtime.associated);
+    }
+
+    #[test]
+    fn test_config_parameters() {
+        let parameters = |pairs: &[(&str, JsonValue)]| -> HashMap<String, JsonValue> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect()
+        };
+        let config = DlmsConfig::from_parameters(&parameters(&[
+            ("transport", json!("HDLC")),
+            ("device", json!("/dev/ttyUSB0")),
+            ("physical_address", json!("17")),
+            ("authentication", json!("low")),
+            ("password", json!("12345678")),
+        ]))
+        .unwrap();
+        assert_eq!(config.transport, Transport::Hdlc);
+        assert_eq!(config.baud_rate, DEFAULT_BAUD_RATE);
+        assert_eq!(config.client_address, MANAGEMENT_CLIENT);
+        assert_eq!(
+            config.server,
+            ServerAddress {
+                logical: 1,
+                physical: Some(17),
+            }
+        );
+        assert_eq!(config.secret, b"12345678");
+
+        // TCP needs a host, authentication needs its secret
+        assert!(
+            DlmsConfig::from_parameters(&parameters(&[("device", json!("/dev/ttyS1"))])).is_err()
+        );
+        assert!(DlmsConfig::from_parameters(&parameters(&[
+            ("host", json!("10.0.0.5")),
+            ("authentication", json!("high_sha256")),
+        ]))
+        .is_err());
+        assert_eq!(
+            parse_system_title("4D4D4D0000BC614E"),
+            Some([0x4D, 0x4D, 0x4D, 0, 0, 0xBC, 0x61, 0x4E])
+        );
+        assert_eq!(scale(123_456.0, -1), 12345.6);
+    }
+}
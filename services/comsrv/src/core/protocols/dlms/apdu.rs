@@ -0,0 +1,813 @@
+//! DLMS/COSEM application layer with logical name (LN) referencing
+//!
+//! Builds and parses the APDUs a client needs: association (AARQ/AARE),
+//! GET/SET/ACTION-Normal with block transfer for long GET responses, and
+//! the release request. Attribute values are A-XDR encoded [`Data`].
+//! Ciphered APDUs are not supported.
+
+use std::fmt;
+
+use sha2::{Digest, Sha256};
+
+use crate::core::protocols::{error, CodecError};
+
+type Result<T> = std::result::Result<T, CodecError>;
+
+/// APDU tags
+pub mod tag {
+    pub const AARQ: u8 = 0x60;
+    pub const AARE: u8 = 0x61;
+    pub const RLRQ: u8 = 0x62;
+    pub const GET_REQUEST: u8 = 0xC0;
+    pub const SET_REQUEST: u8 = 0xC1;
+    pub const ACTION_REQUEST: u8 = 0xC3;
+    pub const GET_RESPONSE: u8 = 0xC4;
+    pub const SET_RESPONSE: u8 = 0xC5;
+    pub const ACTION_RESPONSE: u8 = 0xC7;
+    pub const EXCEPTION_RESPONSE: u8 = 0xD8;
+    pub const CONFIRMED_SERVICE_ERROR: u8 = 0x0E;
+}
+
+/// Invoke-id-and-priority: invoke id 1, confirmed, high priority
+const INVOKE_ID: u8 = 0xC1;
+
+/// Nesting limit of decoded arrays and structures
+const MAX_DEPTH: usize = 16;
+
+// ============================================================================
+// OBIS codes
+// ============================================================================
+
+/// OBIS code (logical name) of a COSEM object, e.g. `1-0:1.8.0*255`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Obis(pub [u8; 6]);
+
+impl Obis {
+    /// Current association object (class 15)
+    pub const ASSOCIATION_LN: Self = Self([0, 0, 40, 0, 0, 255]);
+
+    /// `1-0:1.8.0*255`, `1.0.1.8.0.255` or the five groups A-E with F = 255
+    pub fn parse(text: &str) -> Result<Self> {
+        let groups: Vec<&str> = text
+            .trim()
+            .split(['-', ':', '.', '*', ','])
+            .map(str::trim)
+            .collect();
+        let invalid = || error(format!("invalid OBIS code '{}'", text.trim()));
+        if !(5..=6).contains(&groups.len()) {
+            return Err(invalid());
+        }
+        let mut obis = [255u8; 6];
+        for (value, group) in obis.iter_mut().zip(&groups) {
+            *value = group.parse().map_err(|_| invalid())?;
+        }
+        Ok(Self(obis))
+    }
+}
+
+impl fmt::Display for Obis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{}-{}:{}.{}.{}*{}", a, b, c, d, e, g)
+    }
+}
+
+/// Attribute (or method) of a COSEM object
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Descriptor {
+    pub class_id: u16,
+    pub obis: Obis,
+    /// Attribute or method index
+    pub index: i8,
+}
+
+impl Descriptor {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.class_id.to_be_bytes());
+        out.extend_from_slice(&self.obis.0);
+        out.push(self.index as u8);
+    }
+}
+
+// ============================================================================
+// A-XDR data
+// ============================================================================
+
+/// A-XDR `Data` value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Data {
+    Null,
+    Boolean(bool),
+    /// integer, long, double-long, long64
+    Integer(i64),
+    /// unsigned, long-unsigned, double-long-unsigned, long64-unsigned
+    Unsigned(u64),
+    Enum(u8),
+    Float(f64),
+    /// octet-string, and date-time/date/time in their octet form
+    Octets(Vec<u8>),
+    /// visible-string and utf8-string
+    Text(String),
+    BitString {
+        bits: usize,
+        bytes: Vec<u8>,
+    },
+    Array(Vec<Data>),
+    Structure(Vec<Data>),
+}
+
+impl Data {
+    /// Decode one value at the start of `buf`; returns it and its length
+    pub fn decode(buf: &[u8]) -> Result<(Self, usize)> {
+        let mut pos = 0;
+        let data = decode_data(buf, &mut pos, 0)?;
+        Ok((data, pos))
+    }
+
+    /// Numeric value of scalars; None for strings and containers
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Boolean(b) => Some(f64::from(u8::from(*b))),
+            Self::Integer(v) => Some(*v as f64),
+            Self::Unsigned(v) => Some(*v as f64),
+            Self::Enum(v) => Some(f64::from(*v)),
+            Self::Float(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Value as a bit field, for signal points reading one bit
+    pub fn as_bits(&self) -> Option<u64> {
+        match self {
+            Self::Boolean(b) => Some(u64::from(*b)),
+            Self::Integer(v) => Some(*v as u64),
+            Self::Unsigned(v) => Some(*v),
+            Self::Enum(v) => Some(u64::from(*v)),
+            // First bit of the string is bit 0
+            Self::BitString { bytes, .. } => {
+                Some(bytes.iter().take(8).enumerate().fold(0, |word, (i, b)| {
+                    word | (u64::from(b.reverse_bits()) << (8 * i))
+                }))
+            },
+            _ => None,
+        }
+    }
+}
+
+fn take<'a>(buf: &'a [u8], pos: &mut usize, len: usize) -> Result<&'a [u8]> {
+    let bytes = buf
+        .get(*pos..*pos + len)
+        .ok_or_else(|| error("truncated A-XDR data"))?;
+    *pos += len;
+    Ok(bytes)
+}
+
+/// BER length: one byte below 0x80, else 0x8N followed by N bytes
+pub fn decode_length(buf: &[u8], pos: &mut usize) -> Result<usize> {
+    let first = take(buf, pos, 1)?[0];
+    if first < 0x80 {
+        return Ok(usize::from(first));
+    }
+    let count = usize::from(first & 0x7F);
+    if count == 0 || count > 4 {
+        return Err(error(format!("unsupported length form {:02X}", first)));
+    }
+    Ok(take(buf, pos, count)?
+        .iter()
+        .fold(0, |len, b| (len << 8) | usize::from(*b)))
+}
+
+/// A-XDR octet-string value
+pub fn octet_string(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len() + 3);
+    out.push(9);
+    encode_length(bytes.len(), &mut out);
+    out.extend_from_slice(bytes);
+    out
+}
+
+pub fn encode_length(len: usize, out: &mut Vec<u8>) {
+    if len < 0x80 {
+        out.push(len as u8);
+    } else if len <= 0xFF {
+        out.extend([0x81, len as u8]);
+    } else {
+        out.push(0x82);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    }
+}
+
+fn decode_data(buf: &[u8], pos: &mut usize, depth: usize) -> Result<Data> {
+    if depth > MAX_DEPTH {
+        return Err(error("A-XDR data nested too deeply"));
+    }
+    let kind = take(buf, pos, 1)?[0];
+    let fixed = |pos: &mut usize, len: usize| take(buf, pos, len);
+    let be = |bytes: &[u8]| bytes.iter().fold(0u64, |v, b| (v << 8) | u64::from(*b));
+    Ok(match kind {
+        0 => Data::Null,
+        1 | 2 => {
+            let count = decode_length(buf, pos)?;
+            let mut items = Vec::with_capacity(count.min(64));
+            for _ in 0..count {
+                items.push(decode_data(buf, pos, depth + 1)?);
+            }
+            if kind == 1 {
+                Data::Array(items)
+            } else {
+                Data::Structure(items)
+            }
+        },
+        3 => Data::Boolean(fixed(pos, 1)?[0] != 0),
+        4 => {
+            let bits = decode_length(buf, pos)?;
+            Data::BitString {
+                bits,
+                bytes: fixed(pos, bits.div_ceil(8))?.to_vec(),
+            }
+        },
+        5 => Data::Integer(i64::from(i32::from_be_bytes(
+            fixed(pos, 4)?.try_into().unwrap_or_default(),
+        ))),
+        6 => Data::Unsigned(be(fixed(pos, 4)?)),
+        9 | 25 | 26 | 27 => {
+            let len = match kind {
+                9 => decode_length(buf, pos)?,
+                25 => 12,
+                26 => 5,
+                _ => 4,
+            };
+            Data::Octets(fixed(pos, len)?.to_vec())
+        },
+        10 | 12 => {
+            let len = decode_length(buf, pos)?;
+            Data::Text(String::from_utf8_lossy(fixed(pos, len)?).into_owned())
+        },
+        13 | 15 => Data::Integer(i64::from(fixed(pos, 1)?[0] as i8)),
+        16 => Data::Integer(i64::from(i16::from_be_bytes(
+            fixed(pos, 2)?.try_into().unwrap_or_default(),
+        ))),
+        17 => Data::Unsigned(be(fixed(pos, 1)?)),
+        18 => Data::Unsigned(be(fixed(pos, 2)?)),
+        20 => Data::Integer(be(fixed(pos, 8)?) as i64),
+        21 => Data::Unsigned(be(fixed(pos, 8)?)),
+        22 => Data::Enum(fixed(pos, 1)?[0]),
+        23 => Data::Float(f64::from(f32::from_be_bytes(
+            fixed(pos, 4)?.try_into().unwrap_or_default(),
+        ))),
+        24 => Data::Float(f64::from_be_bytes(
+            fixed(pos, 8)?.try_into().unwrap_or_default(),
+        )),
+        other => return Err(error(format!("unsupported A-XDR type {}", other))),
+    })
+}
+
+/// A-XDR type of a written value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataType {
+    Boolean,
+    Integer,
+    Long,
+    DoubleLong,
+    Long64,
+    Unsigned,
+    LongUnsigned,
+    DoubleLongUnsigned,
+    Long64Unsigned,
+    Enum,
+    Float32,
+    Float64,
+}
+
+impl DataType {
+    /// Blue Book name, e.g. `long-unsigned`
+    pub fn parse(name: &str) -> Result<Self> {
+        Ok(
+            match name.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+                "boolean" | "bool" => Self::Boolean,
+                "integer" | "int8" => Self::Integer,
+                "long" | "int16" => Self::Long,
+                "double-long" | "int32" => Self::DoubleLong,
+                "long64" | "int64" => Self::Long64,
+                "unsigned" | "uint8" => Self::Unsigned,
+                "long-unsigned" | "uint16" => Self::LongUnsigned,
+                "double-long-unsigned" | "uint32" => Self::DoubleLongUnsigned,
+                "long64-unsigned" | "uint64" => Self::Long64Unsigned,
+                "enum" => Self::Enum,
+                "float32" => Self::Float32,
+                "float64" => Self::Float64,
+                other => return Err(error(format!("unsupported data type '{}'", other))),
+            },
+        )
+    }
+
+    /// Encode `value` as this type
+    pub fn encode(&self, value: f64, out: &mut Vec<u8>) -> Result<()> {
+        let integer = |min: f64, max: f64| -> Result<i128> {
+            let rounded = value.round();
+            if !(min..=max).contains(&rounded) {
+                return Err(error(format!("{} is out of range for {:?}", value, self)));
+            }
+            Ok(rounded as i128)
+        };
+        match self {
+            Self::Boolean => out.extend([3, u8::from(value != 0.0)]),
+            Self::Integer => out.extend([15, integer(i8::MIN.into(), i8::MAX.into())? as i8 as u8]),
+            Self::Long => {
+                out.push(16);
+                out.extend((integer(i16::MIN.into(), i16::MAX.into())? as i16).to_be_bytes());
+            },
+            Self::DoubleLong => {
+                out.push(5);
+                out.extend((integer(i32::MIN.into(), i32::MAX.into())? as i32).to_be_bytes());
+            },
+            Self::Long64 => {
+                out.push(20);
+                out.extend((integer(i64::MIN as f64, i64::MAX as f64)? as i64).to_be_bytes());
+            },
+            Self::Unsigned => out.extend([17, integer(0.0, u8::MAX.into())? as u8]),
+            Self::LongUnsigned => {
+                out.push(18);
+                out.extend((integer(0.0, u16::MAX.into())? as u16).to_be_bytes());
+            },
+            Self::DoubleLongUnsigned => {
+                out.push(6);
+                out.extend((integer(0.0, u32::MAX.into())? as u32).to_be_bytes());
+            },
+            Self::Long64Unsigned => {
+                out.push(21);
+                out.extend((integer(0.0, u64::MAX as f64)? as u64).to_be_bytes());
+            },
+            Self::Enum => out.extend([22, integer(0.0, u8::MAX.into())? as u8]),
+            Self::Float32 => {
+                out.push(23);
+                out.extend((value as f32).to_be_bytes());
+            },
+            Self::Float64 => {
+                out.push(24);
+                out.extend(value.to_be_bytes());
+            },
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Association
+// ============================================================================
+
+/// Authentication mechanism of the association
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mechanism {
+    /// Lowest level: no authentication
+    None,
+    /// Low level security: password
+    Low,
+    /// High level security with SHA-256 (mechanism id 6)
+    HighSha256,
+}
+
+impl Mechanism {
+    fn id(&self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Low => 1,
+            Self::HighSha256 => 6,
+        }
+    }
+}
+
+/// Application context: logical name referencing, no ciphering
+const CONTEXT_LN: [u8; 7] = [0x60, 0x85, 0x74, 0x05, 0x08, 0x01, 0x01];
+/// Prefix of the authentication mechanism names
+const MECHANISM_PREFIX: [u8; 6] = [0x60, 0x85, 0x74, 0x05, 0x08, 0x02];
+/// Proposed conformance: get, set, action, selective access, block transfer
+/// with get
+const CONFORMANCE: [u8; 3] = [0x00, 0x18, 0x1D];
+
+/// Append a BER element
+fn element(tag: u8, content: &[u8], out: &mut Vec<u8>) {
+    out.push(tag);
+    encode_length(content.len(), out);
+    out.extend_from_slice(content);
+}
+
+/// Association request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Aarq<'a> {
+    pub mechanism: Mechanism,
+    /// Password (low) or client challenge CtoS (high)
+    pub authentication_value: &'a [u8],
+    /// Client system title, sent with HLS
+    pub system_title: Option<[u8; 8]>,
+    pub max_pdu: u16,
+}
+
+impl Aarq<'_> {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        let mut context = Vec::new();
+        element(0x06, &CONTEXT_LN, &mut context);
+        element(0xA1, &context, &mut body);
+        if let Some(title) = self.system_title {
+            let mut calling = Vec::new();
+            element(0x04, &title, &mut calling);
+            element(0xA6, &calling, &mut body);
+        }
+        if self.mechanism != Mechanism::None {
+            // sender-acse-requirements: authentication
+            element(0x8A, &[0x07, 0x80], &mut body);
+            let mut name = MECHANISM_PREFIX.to_vec();
+            name.push(self.mechanism.id());
+            element(0x8B, &name, &mut body);
+            let mut value = Vec::new();
+            element(0x80, self.authentication_value, &mut value);
+            element(0xAC, &value, &mut body);
+        }
+
+        // xDLMS InitiateRequest: no dedicated key, response allowed,
+        // no quality of service, DLMS version 6
+        let mut initiate = vec![0x01, 0x00, 0x00, 0x00, 0x06, 0x5F, 0x1F, 0x04, 0x00];
+        initiate.extend_from_slice(&CONFORMANCE);
+        initiate.extend_from_slice(&self.max_pdu.to_be_bytes());
+        let mut user = Vec::new();
+        element(0x04, &initiate, &mut user);
+        element(0xBE, &user, &mut body);
+
+        let mut apdu = Vec::with_capacity(body.len() + 2);
+        element(tag::AARQ, &body, &mut apdu);
+        apdu
+    }
+}
+
+/// Association response
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Aare {
+    pub accepted: bool,
+    /// result-source-diagnostic (e.g. 13 = authentication failure)
+    pub diagnostic: u8,
+    pub server_system_title: Option<Vec<u8>>,
+    /// Server challenge StoC (HLS)
+    pub challenge: Option<Vec<u8>>,
+    /// Negotiated server max receive PDU size
+    pub max_pdu: Option<u16>,
+}
+
+/// BER elements of a constructed value: (tag, content)
+fn elements(buf: &[u8]) -> Result<Vec<(u8, &[u8])>> {
+    let mut items = Vec::new();
+    let mut pos = 0;
+    while pos < buf.len() {
+        let tag = buf[pos];
+        pos += 1;
+        let len = decode_length(buf, &mut pos)?;
+        items.push((tag, take(buf, &mut pos, len)?));
+    }
+    Ok(items)
+}
+
+/// Last primitive value inside a constructed element (`A2 03 02 01 00` -> [00])
+fn inner_value(content: &[u8]) -> Option<&[u8]> {
+    let mut content = content;
+    loop {
+        let (tag, inner) = *elements(content).ok()?.first()?;
+        if tag & 0x20 == 0 {
+            return Some(inner);
+        }
+        content = inner;
+    }
+}
+
+impl Aare {
+    pub fn parse(apdu: &[u8]) -> Result<Self> {
+        let (&tag, _) = apdu.split_first().ok_or_else(|| error("empty AARE"))?;
+        if tag != tag::AARE {
+            return Err(error(format!("expected AARE, got APDU {:02X}", tag)));
+        }
+        let Some((_, body)) = elements(apdu)?.into_iter().next() else {
+            return Err(error("empty AARE"));
+        };
+
+        let mut aare = Self::default();
+        for (tag, content) in elements(body)? {
+            match tag {
+                0xA2 => aare.accepted = inner_value(content) == Some(&[0x00][..]),
+                0xA3 => {
+                    aare.diagnostic = inner_value(content)
+                        .and_then(|v| v.last().copied())
+                        .unwrap_or(0)
+                },
+                0xA4 => aare.server_system_title = inner_value(content).map(<[u8]>::to_vec),
+                0xAA => aare.challenge = inner_value(content).map(<[u8]>::to_vec),
+                0xBE => {
+                    let initiate = inner_value(content).unwrap_or_default();
+                    match initiate.first() {
+                        // InitiateResponse: ..., conformance, max PDU, VAA name
+                        Some(0x08) if initiate.len() >= 4 => {
+                            let at = initiate.len() - 4;
+                            aare.max_pdu =
+                                Some(u16::from_be_bytes([initiate[at], initiate[at + 1]]));
+                        },
+                        Some(&tag::CONFIRMED_SERVICE_ERROR) => {
+                            return Err(error("meter rejected the xDLMS initiate request"))
+                        },
+                        _ => {},
+                    }
+                },
+                _ => {},
+            }
+        }
+        Ok(aare)
+    }
+}
+
+/// Text of an association diagnostic
+pub fn association_diagnostic(diagnostic: u8) -> &'static str {
+    match diagnostic {
+        1 => "no reason given",
+        2 => "application context name not supported",
+        11 => "authentication mechanism name not recognised",
+        12 => "authentication mechanism name required",
+        13 => "authentication failure",
+        14 => "authentication required",
+        _ => "rejected",
+    }
+}
+
+/// HLS-SHA256 response to a challenge
+///
+/// The client proves the secret with `f(StoC)`, taking its own system title
+/// first; the server answers with `f(CtoS)` with the titles swapped.
+pub fn hls_sha256(
+    secret: &[u8],
+    own_title: &[u8],
+    peer_title: &[u8],
+    challenge: &[u8],
+    own_challenge: &[u8],
+) -> Vec<u8> {
+    let mut hash = Sha256::new();
+    hash.update(secret);
+    hash.update(own_title);
+    hash.update(peer_title);
+    hash.update(challenge);
+    hash.update(own_challenge);
+    hash.finalize().to_vec()
+}
+
+/// Release request (normal)
+pub fn release_request() -> Vec<u8> {
+    vec![tag::RLRQ, 0x03, 0x80, 0x01, 0x00]
+}
+
+// ============================================================================
+// GET / SET / ACTION
+// ============================================================================
+
+/// GET-Request-Normal of an attribute
+pub fn get_request(attribute: &Descriptor) -> Vec<u8> {
+    let mut apdu = vec![tag::GET_REQUEST, 0x01, INVOKE_ID];
+    attribute.encode(&mut apdu);
+    // No selective access
+    apdu.push(0x00);
+    apdu
+}
+
+/// GET-Request-Next for the block after `block`
+pub fn get_next_request(block: u32) -> Vec<u8> {
+    let mut apdu = vec![tag::GET_REQUEST, 0x02, INVOKE_ID];
+    apdu.extend_from_slice(&block.to_be_bytes());
+    apdu
+}
+
+/// SET-Request-Normal of an attribute with an encoded value
+pub fn set_request(attribute: &Descriptor, value: &[u8]) -> Vec<u8> {
+    let mut apdu = vec![tag::SET_REQUEST, 0x01, INVOKE_ID];
+    attribute.encode(&mut apdu);
+    apdu.push(0x00);
+    apdu.extend_from_slice(value);
+    apdu
+}
+
+/// ACTION-Request-Normal of a method with an encoded parameter
+pub fn action_request(method: &Descriptor, parameter: Option<&[u8]>) -> Vec<u8> {
+    let mut apdu = vec![tag::ACTION_REQUEST, 0x01, INVOKE_ID];
+    method.encode(&mut apdu);
+    match parameter {
+        Some(parameter) => {
+            apdu.push(0x01);
+            apdu.extend_from_slice(parameter);
+        },
+        None => apdu.push(0x00),
+    }
+    apdu
+}
+
+/// Text of a data-access-result
+pub fn access_result(code: u8) -> String {
+    let text = match code {
+        0 => "success",
+        1 => "hardware fault",
+        2 => "temporary failure",
+        3 => "read-write denied",
+        4 => "object undefined",
+        9 => "object class inconsistent",
+        11 => "object unavailable",
+        12 => "type unmatched",
+        13 => "scope of access violated",
+        14 => "data block unavailable",
+        15 => "long get aborted",
+        16 => "no long get in progress",
+        250 => "other reason",
+        _ => return format!("data access result {}", code),
+    };
+    text.to_string()
+}
+
+/// Error of an exception or confirmed-service-error APDU, if it is one
+fn service_error(apdu: &[u8]) -> Option<CodecError> {
+    match apdu.first() {
+        Some(&tag::EXCEPTION_RESPONSE) => Some(error(format!(
+            "exception response (state {}, service {})",
+            apdu.get(1).copied().unwrap_or(0),
+            apdu.get(2).copied().unwrap_or(0)
+        ))),
+        Some(&tag::CONFIRMED_SERVICE_ERROR) => Some(error(format!(
+            "confirmed service error {:02X?}",
+            apdu.get(1..).unwrap_or_default()
+        ))),
+        _ => None,
+    }
+}
+
+/// Parsed GET response
+#[derive(Debug, Clone, PartialEq)]
+pub enum GetResponse {
+    Data(Data),
+    /// One block of a long response
+    Block {
+        last: bool,
+        number: u32,
+        raw: Vec<u8>,
+    },
+}
+
+impl GetResponse {
+    pub fn parse(apdu: &[u8]) -> Result<Self> {
+        if let Some(e) = service_error(apdu) {
+            return Err(e);
+        }
+        match apdu {
+            [tag::GET_RESPONSE, 0x01, _, 0x00, data @ ..] => Ok(Self::Data(Data::decode(data)?.0)),
+            [tag::GET_RESPONSE, 0x01, _, 0x01, code, ..] => Err(error(access_result(*code))),
+            [tag::GET_RESPONSE, 0x02, _, last, n0, n1, n2, n3, 0x00, rest @ ..] => {
+                let mut pos = 0;
+                let len = decode_length(rest, &mut pos)?;
+                Ok(Self::Block {
+                    last: *last != 0,
+                    number: u32::from_be_bytes([*n0, *n1, *n2, *n3]),
+                    raw: take(rest, &mut pos, len)?.to_vec(),
+                })
+            },
+            [tag::GET_RESPONSE, 0x02, _, _, _, _, _, _, 0x01, code, ..] => {
+                Err(error(access_result(*code)))
+            },
+            _ => Err(error(format!(
+                "unexpected GET response {:02X?}",
+                &apdu[..apdu.len().min(4)]
+            ))),
+        }
+    }
+}
+
+/// Result of a SET-Response-Normal
+pub fn parse_set_response(apdu: &[u8]) -> Result<()> {
+    if let Some(e) = service_error(apdu) {
+        return Err(e);
+    }
+    match apdu {
+        [tag::SET_RESPONSE, 0x01, _, 0x00, ..] => Ok(()),
+        [tag::SET_RESPONSE, 0x01, _, code, ..] => Err(error(access_result(*code))),
+        _ => Err(error("unexpected SET response")),
+    }
+}
+
+/// Return value of an ACTION-Response-Normal
+pub fn parse_action_response(apdu: &[u8]) -> Result<Option<Data>> {
+    if let Some(e) = service_error(apdu) {
+        return Err(e);
+    }
+    match apdu {
+        [tag::ACTION_RESPONSE, 0x01, _, 0x00, 0x01, 0x00, data @ ..] => {
+            Ok(Some(Data::decode(data)?.0))
+        },
+        [tag::ACTION_RESPONSE, 0x01, _, 0x00, 0x01, 0x01, code, ..] => {
+            Err(error(access_result(*code)))
+        },
+        [tag::ACTION_RESPONSE, 0x01, _, 0x00, ..] => Ok(None),
+        [tag::ACTION_RESPONSE, 0x01, _, code, ..] => Err(error(format!("action result {}", code))),
+        _ => Err(error("unexpected ACTION response")),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_obis_and_data() {
+        let obis = Obis::parse("1-0:1.8.0*255").unwrap();
+        assert_eq!(obis, Obis([1, 0, 1, 8, 0, 255]));
+        assert_eq!(Obis::parse("1.0.1.8.0").unwrap(), obis);
+        assert_eq!(obis.to_string(), "1-0:1.8.0*255");
+        assert!(Obis::parse("1.0.1.8").is_err());
+        assert!(Obis::parse("1.0.1.8.0.256").is_err());
+
+        // structure { scaler -2, unit 27 (W) }
+        let (data, used) = Data::decode(&[0x02, 0x02, 0x0F, 0xFE, 0x16, 0x1B, 0xFF]).unwrap();
+        assert_eq!(used, 6);
+        assert_eq!(
+            data,
+            Data::Structure(vec![Data::Integer(-2), Data::Enum(27)])
+        );
+        let (data, _) = Data::decode(&[0x06, 0x00, 0x01, 0xE2, 0x40]).unwrap();
+        assert_eq!(data.as_f64(), Some(123_456.0));
+        let (data, _) = Data::decode(&[0x04, 0x0A, 0x80, 0x40]).unwrap();
+        assert_eq!(data.as_bits(), Some(0x201));
+        assert!(Data::decode(&[0x12, 0x00]).is_err());
+
+        let mut out = Vec::new();
+        DataType::parse("long_unsigned")
+            .unwrap()
+            .encode(230.0, &mut out)
+            .unwrap();
+        assert_eq!(out, [0x12, 0x00, 0xE6]);
+        assert!(DataType::Unsigned.encode(300.0, &mut out).is_err());
+    }
+
+    #[test]
+    fn test_association_apdus() {
+        let aarq = Aarq {
+            mechanism: Mechanism::Low,
+            authentication_value: b"12345678",
+            system_title: None,
+            max_pdu: 0xFFFF,
+        }
+        .encode();
+        assert_eq!(aarq[0], tag::AARQ);
+        assert_eq!(usize::from(aarq[1]), aarq.len() - 2);
+        assert!(aarq
+            .windows(9)
+            .any(|w| w == [0x8B, 0x07, 0x60, 0x85, 0x74, 0x05, 0x08, 0x02, 0x01]));
+        assert!(aarq.windows(8).any(|w| w == b"12345678"));
+
+        // Accepted, HLS challenge, InitiateResponse with max PDU 0x01F4
+        let aare = [
+            0x61, 0x41, 0xA1, 0x09, 0x06, 0x07, 0x60, 0x85, 0x74, 0x05, 0x08, 0x01, 0x01, 0xA2,
+            0x03, 0x02, 0x01, 0x00, 0xA3, 0x05, 0xA1, 0x03, 0x02, 0x01, 0x0E, 0xA4, 0x0A, 0x04,
+            0x08, 0x4D, 0x4D, 0x4D, 0x00, 0x00, 0x00, 0x00, 0x01, 0xAA, 0x0A, 0x80, 0x08, 0x50,
+            0x36, 0x77, 0x52, 0x4A, 0x32, 0x31, 0x46, 0xBE, 0x10, 0x04, 0x0E, 0x08, 0x00, 0x06,
+            0x5F, 0x1F, 0x04, 0x00, 0x00, 0x18, 0x1D, 0x01, 0xF4, 0x00, 0x07,
+        ];
+        let aare = Aare::parse(&aare).unwrap();
+        assert!(aare.accepted);
+        assert_eq!(aare.diagnostic, 14);
+        assert_eq!(aare.challenge.as_deref(), Some(&b"P6wRJ21F"[..]));
+        assert_eq!(aare.server_system_title.unwrap().len(), 8);
+        assert_eq!(aare.max_pdu, Some(0x01F4));
+    }
+
+    #[test]
+    fn test_get_set_responses() {
+        let register = Descriptor {
+            class_id: 3,
+            obis: Obis([1, 0, 1, 8, 0, 255]),
+            index: 2,
+        };
+        assert_eq!(
+            get_request(&register),
+            [0xC0, 0x01, 0xC1, 0x00, 0x03, 1, 0, 1, 8, 0, 255, 0x02, 0x00]
+        );
+        assert_eq!(
+            GetResponse::parse(&[0xC4, 0x01, 0xC1, 0x00, 0x11, 0x07]).unwrap(),
+            GetResponse::Data(Data::Unsigned(7))
+        );
+        let denied = GetResponse::parse(&[0xC4, 0x01, 0xC1, 0x01, 0x03]).unwrap_err();
+        assert_eq!(denied.to_string(), "read-write denied");
+        assert_eq!(
+            GetResponse::parse(&[0xC4, 0x02, 0xC1, 0x00, 0, 0, 0, 1, 0x00, 0x02, 0x01, 0x02])
+                .unwrap(),
+            GetResponse::Block {
+                last: false,
+                number: 1,
+                raw: vec![0x01, 0x02],
+            }
+        );
+        assert!(parse_set_response(&[0xC5, 0x01, 0xC1, 0x00]).is_ok());
+        assert!(parse_set_response(&[0xD8, 0x01, 0x02]).is_err());
+        assert_eq!(
+            parse_action_response(&[0xC7, 0x01, 0xC1, 0x00, 0x01, 0x00, 0x09, 0x01, 0xAB]).unwrap(),
+            Some(Data::Octets(vec![0xAB]))
+        );
+    }
+}
@@ -0,0 +1,352 @@
+//! DLMS/COSEM transports: HDLC (IEC 62056-46) and the TCP wrapper (IEC 62056-47)
+//!
+//! HDLC frames are frame format type 3 with HCS/FCS (CRC-16/X-25):
+//!
+//! ```text
+//! 7E | A0+len | dest addr | src addr | control | [HCS | LLC + APDU] | FCS | 7E
+//! ```
+//!
+//! The server (meter) address has an upper (logical device) and an optional
+//! lower (physical device) part of 1, 2 or 4 bytes in total; the client
+//! address is one byte. Long responses arrive as segments (format bit 0x08)
+//! that the client acknowledges with RR.
+//!
+//! The wrapper puts an 8-byte header in front of each APDU:
+//!
+//! ```text
+//! version 0x0001 | source wPort | destination wPort | APDU length
+//! ```
+
+use common::bytes::checksum::CRC16_X25;
+
+use crate::core::protocols::{error, CodecError};
+
+type Result<T> = std::result::Result<T, CodecError>;
+
+// ============================================================================
+// HDLC
+// ============================================================================
+
+pub const FLAG: u8 = 0x7E;
+/// Frame format type 3
+const FORMAT: u8 = 0xA0;
+/// More segments follow
+const SEGMENTED: u8 = 0x08;
+/// Largest frame the 11-bit length field allows
+pub const MAX_FRAME_LEN: usize = 0x7FF + 2;
+
+/// LLC header of client requests
+pub const LLC_REQUEST: [u8; 3] = [0xE6, 0xE6, 0x00];
+/// LLC header of server responses
+pub const LLC_RESPONSE: [u8; 3] = [0xE6, 0xE7, 0x00];
+
+/// HDLC control field values (poll/final bit set)
+pub mod control {
+    /// Set normal response mode
+    pub const SNRM: u8 = 0x93;
+    pub const UA: u8 = 0x73;
+    pub const DISC: u8 = 0x53;
+    /// Disconnected mode
+    pub const DM: u8 = 0x1F;
+    /// Frame reject
+    pub const FRMR: u8 = 0x97;
+
+    /// Information frame with send and receive sequence numbers
+    pub fn information(send: u8, receive: u8) -> u8 {
+        ((receive & 0x07) << 5) | 0x10 | ((send & 0x07) << 1)
+    }
+
+    /// Receive ready: ask for the next segment
+    pub fn receive_ready(receive: u8) -> u8 {
+        ((receive & 0x07) << 5) | 0x11
+    }
+
+    pub fn is_information(control: u8) -> bool {
+        control & 0x01 == 0
+    }
+
+    /// Send sequence number of an information frame
+    pub fn send_sequence(control: u8) -> u8 {
+        (control >> 1) & 0x07
+    }
+}
+
+/// HDLC address of the server (meter)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerAddress {
+    /// Logical device, 1 = management logical device
+    pub logical: u16,
+    /// Physical device on a multi-drop bus
+    pub physical: Option<u16>,
+}
+
+impl ServerAddress {
+    /// Address bytes, each carrying 7 bits, the last with bit 0 set
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let mut parts = Vec::with_capacity(4);
+        match self.physical {
+            None => {
+                if self.logical > 0x7F {
+                    return Err(error("logical address above 127 needs a physical address"));
+                }
+                parts.push(self.logical);
+            },
+            Some(physical) if self.logical <= 0x7F && physical <= 0x7F => {
+                parts.extend([self.logical, physical]);
+            },
+            Some(physical) => {
+                if self.logical > 0x3FFF || physical > 0x3FFF {
+                    return Err(error("HDLC addresses are limited to 14 bits"));
+                }
+                parts.extend([
+                    self.logical >> 7,
+                    self.logical & 0x7F,
+                    physical >> 7,
+                    physical & 0x7F,
+                ]);
+            },
+        }
+        let last = parts.len() - 1;
+        Ok(parts
+            .iter()
+            .enumerate()
+            .map(|(i, part)| ((*part as u8) << 1) | u8::from(i == last))
+            .collect())
+    }
+}
+
+/// One-byte client address
+pub fn client_address(address: u8) -> Result<Vec<u8>> {
+    if address > 0x7F {
+        return Err(error("client address must be 0-127"));
+    }
+    Ok(vec![(address << 1) | 1])
+}
+
+/// HDLC frame without the flags
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HdlcFrame {
+    pub destination: Vec<u8>,
+    pub source: Vec<u8>,
+    pub control: u8,
+    /// More segments of the same information follow
+    pub segmented: bool,
+    pub info: Vec<u8>,
+}
+
+pub fn encode(destination: &[u8], source: &[u8], control: u8, info: &[u8]) -> Result<Vec<u8>> {
+    let header_len = 2 + destination.len() + source.len() + 1;
+    let len = header_len + if info.is_empty() { 0 } else { 2 + info.len() } + 2;
+    if len > 0x7FF {
+        return Err(error(format!("HDLC frame of {} bytes is too long", len)));
+    }
+
+    let mut frame = Vec::with_capacity(len + 2);
+    frame.push(FLAG);
+    frame.push(FORMAT | (len >> 8) as u8);
+    frame.push(len as u8);
+    frame.extend_from_slice(destination);
+    frame.extend_from_slice(source);
+    frame.push(control);
+    if !info.is_empty() {
+        let hcs = CRC16_X25.checksum(&frame[1..]);
+        frame.extend_from_slice(&hcs.to_le_bytes());
+        frame.extend_from_slice(info);
+    }
+    let fcs = CRC16_X25.checksum(&frame[1..]);
+    frame.extend_from_slice(&fcs.to_le_bytes());
+    frame.push(FLAG);
+    Ok(frame)
+}
+
+/// Bytes of an HDLC address: up to and including the byte with bit 0 set
+fn address_len(bytes: &[u8]) -> Result<usize> {
+    bytes
+        .iter()
+        .take(4)
+        .position(|b| b & 1 == 1)
+        .map(|i| i + 1)
+        .ok_or_else(|| error("unterminated HDLC address"))
+}
+
+/// Decode the first frame of `buf`
+///
+/// Bytes before the opening flag are skipped. Returns the frame and the
+/// bytes consumed, or None until the frame is complete.
+pub fn decode(buf: &[u8]) -> Result<Option<(HdlcFrame, usize)>> {
+    let Some(mut start) = buf.iter().position(|b| *b == FLAG) else {
+        return Ok(None);
+    };
+    // Frames may share or repeat the flag between them
+    while buf.get(start + 1) == Some(&FLAG) {
+        start += 1;
+    }
+    let (Some(format), Some(len_low)) = (buf.get(start + 1), buf.get(start + 2)) else {
+        return Ok(None);
+    };
+    if format & 0xF0 != FORMAT {
+        return Err(error(format!("HDLC frame format {:02X}", format)));
+    }
+    let len = (usize::from(format & 0x07) << 8) | usize::from(*len_low);
+    let end = start + len + 1;
+    if buf.len() <= end {
+        return Ok(None);
+    }
+    if buf[end] != FLAG {
+        return Err(error("HDLC frame without closing flag"));
+    }
+
+    let body = &buf[start + 1..end];
+    if body.len() < 7 {
+        return Err(error("HDLC frame too short"));
+    }
+    let (content, fcs) = body.split_at(body.len() - 2);
+    if CRC16_X25.checksum(content).to_le_bytes() != fcs {
+        return Err(error("HDLC FCS mismatch"));
+    }
+    let destination_len = address_len(&content[2..])?;
+    let source_start = 2 + destination_len;
+    let source_len = address_len(content.get(source_start..).unwrap_or_default())?;
+    let control_at = source_start + source_len;
+    let control = *content
+        .get(control_at)
+        .ok_or_else(|| error("HDLC frame without control field"))?;
+
+    let info = if content.len() > control_at + 1 {
+        let hcs_end = control_at + 3;
+        let hcs = content
+            .get(control_at + 1..hcs_end)
+            .ok_or_else(|| error("HDLC frame without HCS"))?;
+        if CRC16_X25.checksum(&content[..control_at + 1]).to_le_bytes() != hcs {
+            return Err(error("HDLC HCS mismatch"));
+        }
+        content[hcs_end..].to_vec()
+    } else {
+        Vec::new()
+    };
+
+    Ok(Some((
+        HdlcFrame {
+            destination: content[2..source_start].to_vec(),
+            source: content[source_start..control_at].to_vec(),
+            control,
+            segmented: format & SEGMENTED != 0,
+            info,
+        },
+        end + 1,
+    )))
+}
+
+// ============================================================================
+// TCP wrapper
+// ============================================================================
+
+const WRAPPER_VERSION: u16 = 0x0001;
+pub const WRAPPER_HEADER_LEN: usize = 8;
+
+/// Wrap an APDU for the TCP transport
+pub fn wrap(source: u16, destination: u16, apdu: &[u8]) -> Result<Vec<u8>> {
+    let len = u16::try_from(apdu.len()).map_err(|_| error("APDU too long for the wrapper"))?;
+    let mut frame = Vec::with_capacity(WRAPPER_HEADER_LEN + apdu.len());
+    frame.extend_from_slice(&WRAPPER_VERSION.to_be_bytes());
+    frame.extend_from_slice(&source.to_be_bytes());
+    frame.extend_from_slice(&destination.to_be_bytes());
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(apdu);
+    Ok(frame)
+}
+
+/// Wrapped APDU with its source and destination wPorts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wrapped {
+    pub source: u16,
+    pub destination: u16,
+    pub apdu: Vec<u8>,
+}
+
+/// Decode the first wrapped APDU of `buf`; None until it is complete
+pub fn unwrap(buf: &[u8]) -> Result<Option<(Wrapped, usize)>> {
+    if buf.len() < WRAPPER_HEADER_LEN {
+        return Ok(None);
+    }
+    let word = |at: usize| u16::from_be_bytes([buf[at], buf[at + 1]]);
+    if word(0) != WRAPPER_VERSION {
+        return Err(error(format!("wrapper version {:04X}", word(0))));
+    }
+    let end = WRAPPER_HEADER_LEN + usize::from(word(6));
+    if buf.len() < end {
+        return Ok(None);
+    }
+    Ok(Some((
+        Wrapped {
+            source: word(2),
+            destination: word(4),
+            apdu: buf[WRAPPER_HEADER_LEN..end].to_vec(),
+        },
+        end,
+    )))
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hdlc_snrm_and_round_trip() {
+        // SNRM from the public client (16) to logical device 1, physical 17
+        let server = ServerAddress {
+            logical: 1,
+            physical: Some(17),
+        }
+        .encode()
+        .unwrap();
+        assert_eq!(server, [0x02, 0x23]);
+        let client = client_address(16).unwrap();
+        let snrm = encode(&server, &client, control::SNRM, &[]).unwrap();
+        assert_eq!(
+            snrm,
+            [0x7E, 0xA0, 0x08, 0x02, 0x23, 0x21, 0x93, 0xBD, 0x64, 0x7E]
+        );
+        // One-byte server address: the frame of the Green Book example
+        let logical = ServerAddress {
+            logical: 1,
+            physical: None,
+        };
+        assert_eq!(
+            encode(&logical.encode().unwrap(), &client, control::SNRM, &[]).unwrap(),
+            [0x7E, 0xA0, 0x07, 0x03, 0x21, 0x93, 0x0F, 0x01, 0x7E]
+        );
+
+        let info = [LLC_REQUEST.as_slice(), &[0xC0, 0x01, 0xC1]].concat();
+        let frame = encode(&server, &client, control::information(0, 0), &info).unwrap();
+        // Leading noise and a doubled flag are skipped
+        let wire = [&[0x00, 0x7E][..], &frame, &[0x7E]].concat();
+        let (decoded, used) = decode(&wire).unwrap().unwrap();
+        assert_eq!(used, frame.len() + 2);
+        assert_eq!(decoded.destination, server);
+        assert_eq!(decoded.source, client);
+        assert_eq!(decoded.control, 0x10);
+        assert!(!decoded.segmented);
+        assert_eq!(decoded.info, info);
+
+        assert!(decode(&frame[..frame.len() - 1]).unwrap().is_none());
+        let mut corrupt = frame.clone();
+        corrupt[12] ^= 0xFF;
+        assert!(decode(&corrupt).is_err());
+    }
+
+    #[test]
+    fn test_wrapper_round_trip() {
+        let frame = wrap(16, 1, &[0xC0, 0x01]).unwrap();
+        assert_eq!(
+            frame,
+            [0x00, 0x01, 0x00, 0x10, 0x00, 0x01, 0x00, 0x02, 0xC0, 0x01]
+        );
+        let (wrapped, used) = unwrap(&frame).unwrap().unwrap();
+        assert_eq!((wrapped.source, wrapped.destination), (16, 1));
+        assert_eq!(wrapped.apdu, [0xC0, 0x01]);
+        assert_eq!(used, frame.len());
+        assert!(unwrap(&frame[..9]).unwrap().is_none());
+    }
+}
@@ -0,0 +1,810 @@
+//! DL/T 645-2007 meter reading over RS-485
+//!
+//! Reads Chinese electricity meters by data identifier (DI): every point
+//! names a meter and a `data_id`, e.g. `02010100` for the phase A voltage,
+//! and the meter answers with a BCD value. The value layout is known for the
+//! common identifiers (energy, voltage, current, power, power factor,
+//! frequency, status words) and can be given for others as `data_format`
+//! (`XXX.XXX`). Signal points take one `bit` of a status word.
+//!
+//! Points without `meter_address` use the channel's, and a channel without
+//! one probes the address of the only meter on the bus when connecting.
+//! With `time_sync_interval_s` the meter clocks are set by broadcast after
+//! connecting and then at that interval. DL/T 645 has no commands the
+//! four-remote model maps to, so channels are read-only.
+//!
+//! The serial line is opened like a Modbus RTU one (`device`, `baud_rate`,
+//! `data_bits`, `parity`, `stop_bits`) with the standard's defaults of
+//! 2400 baud, 8 data bits, even parity.
+//!
+//! ```yaml
+//! protocol: dlt645
+//! parameters:
+//!   device: /dev/ttyS1
+//!   baud_rate: 2400
+//!   parity: even
+//!   meter_address: "202312345678"   # omit to probe
+//!   response_timeout_ms: 1000
+//!   time_sync_interval_s: 86400
+//! ```
+
+pub mod codec;
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{Datelike, Local, Timelike};
+use igw::core::traits::{DataEventReceiver, Diagnostics, PointFailure, PollResult};
+use igw::gateway::ChannelRuntime;
+use igw::{ConnectionState, DataBatch, DataPoint, GatewayError};
+use serde_json::{json, Value as JsonValue};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::time::{timeout_at, Instant};
+use tokio_serial::{DataBits, Parity, SerialStream, StopBits};
+use tracing::{debug, info, warn};
+
+use self::codec::{Address, DataFormat, Frame};
+use crate::core::config::{Point, RuntimeChannelConfig};
+use crate::core::plugins::{MappingColumn, ParameterMetadata, ParameterType};
+use crate::core::protocols::{as_u64, record_failure};
+use crate::error::{ComSrvError, Result};
+use voltage_model::PointType;
+
+/// Protocol name stored in `channels.protocol`
+pub const PROTOCOL: &str = "dlt645";
+
+const DEFAULT_BAUD_RATE: u32 = 2400;
+const DEFAULT_RESPONSE_TIMEOUT_MS: u64 = 1000;
+const DEFAULT_PREAMBLE: usize = 4;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Channel parameters of a DL/T 645 channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dlt645Config {
+    pub device: String,
+    pub baud_rate: u32,
+    pub data_bits: DataBits,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+    pub response_timeout: Duration,
+    /// Wake-up bytes sent before each request
+    pub preamble: usize,
+    /// Meter of points without their own address; None to probe
+    pub meter_address: Option<Address>,
+    pub time_sync_interval: Option<Duration>,
+}
+
+impl Dlt645Config {
+    pub fn from_parameters(parameters: &HashMap<String, JsonValue>) -> Result<Self> {
+        let config_error = |message: String| ComSrvError::ConfigError(message);
+        let device = parameters
+            .get("device")
+            .and_then(|v| v.as_str())
+            .filter(|d| !d.trim().is_empty())
+            .ok_or_else(|| config_error("DL/T 645 channel requires the serial 'device'".into()))?
+            .trim()
+            .to_string();
+        let number = |key: &str, default: u64| -> Result<u64> {
+            match parameters.get(key) {
+                None | Some(JsonValue::Null) => Ok(default),
+                Some(value) => {
+                    as_u64(value).ok_or_else(|| config_error(format!("'{}' must be a number", key)))
+                },
+            }
+        };
+        let text = |key: &str| {
+            parameters
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+
+        let baud_rate = u32::try_from(number("baud_rate", u64::from(DEFAULT_BAUD_RATE))?)
+            .map_err(|_| config_error("'baud_rate' is out of range".into()))?;
+        let data_bits = match number("data_bits", 8)? {
+            5 => DataBits::Five,
+            6 => DataBits::Six,
+            7 => DataBits::Seven,
+            8 => DataBits::Eight,
+            _ => return Err(config_error("'data_bits' must be 5-8".into())),
+        };
+        let parity = match text("parity").map(str::to_ascii_lowercase).as_deref() {
+            None | Some("even") | Some("e") => Parity::Even,
+            Some("odd") | Some("o") => Parity::Odd,
+            Some("none") | Some("n") => Parity::None,
+            Some(other) => {
+                return Err(config_error(format!(
+                    "'parity' must be even, odd or none, not '{}'",
+                    other
+                )))
+            },
+        };
+        let stop_bits = match number("stop_bits", 1)? {
+            1 => StopBits::One,
+            2 => StopBits::Two,
+            _ => return Err(config_error("'stop_bits' must be 1 or 2".into())),
+        };
+        let meter_address = text("meter_address")
+            .filter(|a| !a.eq_ignore_ascii_case("auto"))
+            .map(Address::parse)
+            .transpose()
+            .map_err(|e| config_error(e.to_string()))?;
+        let time_sync_interval = Some(number("time_sync_interval_s", 0)?)
+            .filter(|s| *s > 0)
+            .map(Duration::from_secs);
+
+        Ok(Self {
+            device,
+            baud_rate,
+            data_bits,
+            parity,
+            stop_bits,
+            response_timeout: Duration::from_millis(
+                number("response_timeout_ms", DEFAULT_RESPONSE_TIMEOUT_MS)?.max(1),
+            ),
+            preamble: number("preamble", DEFAULT_PREAMBLE as u64)?.min(16) as usize,
+            meter_address,
+            time_sync_interval,
+        })
+    }
+}
+
+// ============================================================================
+// Point mapping
+// ============================================================================
+
+/// Value a point reads
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Reading {
+    internal_id: u32,
+    format: DataFormat,
+    /// Bit of a status word (signal points)
+    bit: Option<u8>,
+}
+
+impl Reading {
+    fn decode(&self, bytes: &[u8]) -> std::result::Result<f64, String> {
+        match self.bit {
+            Some(bit) => {
+                let word = self.format.word(bytes).map_err(|e| e.to_string())?;
+                Ok(f64::from(((word >> bit) & 1) as u8))
+            },
+            None => self.format.decode(bytes).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Meter (None: the channel's or the probed one) and DI of a point
+fn parse_point(
+    point_type: PointType,
+    point: &Point,
+) -> std::result::Result<(Option<Address>, u32, Reading), String> {
+    let mapping: JsonValue = point
+        .protocol_mappings
+        .as_deref()
+        .and_then(|m| serde_json::from_str(m).ok())
+        .ok_or("missing DL/T 645 mapping")?;
+    let text = |key: &str| {
+        mapping
+            .get(key)
+            .and_then(|v| match v {
+                JsonValue::String(s) => Some(s.trim().to_string()),
+                JsonValue::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .filter(|v| !v.is_empty())
+    };
+
+    let data_id = text("data_id").ok_or("missing 'data_id'")?;
+    let digits = data_id.trim_start_matches("0x").trim_start_matches("0X");
+    let data_id = u32::from_str_radix(digits, 16)
+        .ok()
+        .filter(|_| digits.len() == 8)
+        .ok_or_else(|| format!("'data_id' {} must be 8 hex digits", data_id))?;
+    let address = text("meter_address")
+        .map(|a| Address::parse(&a))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let signed = mapping
+        .get("signed")
+        .map(|v| v.as_bool().unwrap_or_else(|| v == "true" || v == "1"));
+    let format = match text("data_format") {
+        Some(format) => {
+            DataFormat::parse(&format, signed.unwrap_or(false)).map_err(|e| e.to_string())?
+        },
+        None => DataFormat::of_data_id(data_id)
+            .map(|format| DataFormat {
+                signed: signed.unwrap_or(format.signed),
+                ..format
+            })
+            .ok_or_else(|| format!("unknown data_id {:08X}: set 'data_format'", data_id))?,
+    };
+    let bit = match (point_type, text("bit")) {
+        (PointType::Signal, Some(bit)) => Some(
+            bit.parse::<u8>()
+                .ok()
+                .filter(|b| usize::from(*b) < format.len * 8)
+                .ok_or_else(|| format!("'bit' must be below {}", format.len * 8))?,
+        ),
+        (PointType::Signal | PointType::Telemetry, _) => None,
+        _ => return Err("DL/T 645 channels are read-only".to_string()),
+    };
+    Ok((
+        address,
+        data_id,
+        Reading {
+            internal_id: point_type.to_internal_id(point.point_id),
+            format,
+            bit,
+        },
+    ))
+}
+
+// ============================================================================
+// Runtime
+// ============================================================================
+
+/// Byte stream to the meters: the serial port, or a test double
+trait Link: AsyncRead + AsyncWrite + Send + Sync + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Sync + Unpin> Link for T {}
+
+/// DL/T 645 master on a serial line
+pub struct Dlt645Runtime {
+    id: u32,
+    name: String,
+    config: Dlt645Config,
+    /// (meter, DI) -> points reading it; None = the default meter
+    reads: BTreeMap<(Option<Address>, u32), Vec<Reading>>,
+    /// Address found by probing the bus
+    probed: Option<Address>,
+    link: Option<Box<dyn Link>>,
+    /// Bytes received beyond the last frame
+    pending: Vec<u8>,
+    last_time_sync: Option<Instant>,
+    diagnostics: Diagnostics,
+}
+
+impl Dlt645Runtime {
+    /// Build the runtime of a `dlt645` channel
+    pub fn from_runtime_config(runtime_config: &RuntimeChannelConfig) -> Result<Self> {
+        let channel_id = runtime_config.id();
+        let config = Dlt645Config::from_parameters(&runtime_config.base.parameters)
+            .map_err(|e| ComSrvError::ConfigError(format!("Ch{}: {}", channel_id, e)))?;
+
+        let mut reads: BTreeMap<_, Vec<Reading>> = BTreeMap::new();
+        for (point_type, point) in runtime_config.points() {
+            match parse_point(point_type, point) {
+                Ok((address, data_id, reading)) => {
+                    let address = address.or(config.meter_address);
+                    reads.entry((address, data_id)).or_default().push(reading);
+                },
+                Err(e) => warn!(
+                    "Ch{} {}{} skipped: {}",
+                    channel_id,
+                    point_type.as_str(),
+                    point.point_id,
+                    e
+                ),
+            }
+        }
+        debug!(
+            "Ch{} DL/T 645 {}: {} reads",
+            channel_id,
+            config.device,
+            reads.len()
+        );
+
+        Ok(Self {
+            id: channel_id,
+            name: runtime_config.name().to_string(),
+            config,
+            reads,
+            probed: None,
+            link: None,
+            pending: Vec::new(),
+            last_time_sync: None,
+            diagnostics: Diagnostics::new(PROTOCOL),
+        })
+    }
+
+    /// Record an error; connection failures drop the link so the next
+    /// `connect()` reopens the port
+    fn fail(&mut self, error: GatewayError) -> GatewayError {
+        if record_failure(&mut self.diagnostics, &error) {
+            self.link = None;
+        }
+        error
+    }
+
+    fn open_serial(&self) -> igw::Result<SerialStream> {
+        let builder = tokio_serial::new(&self.config.device, self.config.baud_rate)
+            .data_bits(self.config.data_bits)
+            .parity(self.config.parity)
+            .stop_bits(self.config.stop_bits);
+        SerialStream::open(&builder)
+            .map_err(|e| GatewayError::Connection(format!("{}: {}", self.config.device, e)))
+    }
+
+    async fn send(&mut self, frame: &[u8]) -> igw::Result<()> {
+        let link = self.link.as_mut().ok_or(GatewayError::NotConnected)?;
+        let mut wire = vec![codec::WAKE_UP; self.config.preamble];
+        wire.extend_from_slice(frame);
+        link.write_all(&wire)
+            .await
+            .map_err(|e| GatewayError::Connection(format!("send: {}", e)))?;
+        link.flush()
+            .await
+            .map_err(|e| GatewayError::Connection(format!("send: {}", e)))
+    }
+
+    /// Send a request and wait for the response of a meter at `address`
+    ///
+    /// Echoes of the request and frames of other meters are skipped.
+    async fn request(&mut self, address: Address, frame: &[u8]) -> igw::Result<Frame> {
+        let deadline = Instant::now() + self.config.response_timeout;
+        self.pending.clear();
+        self.send(frame).await?;
+        let link = self.link.as_mut().ok_or(GatewayError::NotConnected)?;
+        let mut buf = [0u8; codec::MAX_FRAME_LEN];
+        loop {
+            match codec::decode(&self.pending) {
+                Ok(Some((frame, used))) => {
+                    self.pending.drain(..used);
+                    if frame.is_response() && address.matches(&frame.address) {
+                        return Ok(frame);
+                    }
+                    continue;
+                },
+                Ok(None) => {},
+                Err(e) => {
+                    // Resynchronize on the next start byte
+                    debug!("Ch{} discarded bytes: {}", self.id, e);
+                    self.pending.remove(0);
+                    continue;
+                },
+            }
+            let n = timeout_at(deadline, link.read(&mut buf))
+                .await
+                .map_err(|_| GatewayError::ReadTimeout)?
+                .map_err(|e| GatewayError::Connection(format!("receive: {}", e)))?;
+            if n == 0 {
+                return Err(GatewayError::Connection("serial port closed".to_string()));
+            }
+            self.pending.extend_from_slice(&buf[..n]);
+        }
+    }
+
+    /// Address of the only meter on the bus
+    async fn probe_address(&mut self) -> igw::Result<Address> {
+        let request =
+            codec::read_address_request().map_err(|e| GatewayError::Protocol(e.to_string()))?;
+        let response = self.request(Address::WILDCARD, &request).await?;
+        codec::parse_address_response(&response)
+            .map_err(|e| GatewayError::InvalidResponse(e.to_string()))
+    }
+
+    /// Broadcast the local time to all meters (no response)
+    async fn sync_time(&mut self) -> igw::Result<()> {
+        let now = Local::now();
+        let request = codec::time_sync_request((
+            (now.year() % 100) as u8,
+            now.month() as u8,
+            now.day() as u8,
+            now.hour() as u8,
+            now.minute() as u8,
+            now.second() as u8,
+        ))
+        .map_err(|e| GatewayError::Protocol(e.to_string()))?;
+        self.send(&request).await?;
+        self.last_time_sync = Some(Instant::now());
+        debug!("Ch{} DL/T 645 time broadcast {}", self.id, now);
+        Ok(())
+    }
+
+    fn time_sync_due(&self) -> bool {
+        match (self.config.time_sync_interval, self.last_time_sync) {
+            (Some(_), None) => true,
+            (Some(interval), Some(last)) => last.elapsed() >= interval,
+            (None, _) => false,
+        }
+    }
+
+    async fn read(&mut self, address: Address, data_id: u32) -> igw::Result<Vec<u8>> {
+        let request = codec::read_request(address, data_id)
+            .map_err(|e| GatewayError::Protocol(e.to_string()))?;
+        let response = self.request(address, &request).await?;
+        codec::parse_read_response(data_id, &response)
+            .map_err(|e| GatewayError::InvalidResponse(e.to_string()))
+    }
+
+    /// Use an already open byte stream instead of the serial port
+    #[cfg(test)]
+    fn attach(&mut self, link: impl Link + 'static) {
+        self.link = Some(Box::new(link));
+        self.diagnostics.connection_state = ConnectionState::Connected;
+    }
+
+    async fn after_connect(&mut self) -> igw::Result<()> {
+        let needs_probe = self.reads.keys().any(|(address, _)| address.is_none());
+        if needs_probe && self.probed.is_none() {
+            let address = self.probe_address().await.map_err(|e| {
+                GatewayError::Connection(format!("meter address probe failed: {}", e))
+            })?;
+            info!("Ch{} DL/T 645 meter {} found", self.id, address);
+            self.probed = Some(address);
+        }
+        if self.config.time_sync_interval.is_some() {
+            self.sync_time().await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChannelRuntime for Dlt645Runtime {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn protocol(&self) -> &str {
+        PROTOCOL
+    }
+
+    fn is_event_driven(&self) -> bool {
+        false
+    }
+
+    async fn connect(&mut self) -> igw::Result<()> {
+        self.diagnostics.connection_state = ConnectionState::Connecting;
+        let port = match self.open_serial() {
+            Ok(port) => port,
+            Err(e) => return Err(self.fail(e)),
+        };
+        self.link = Some(Box::new(port));
+        self.pending.clear();
+        if let Err(e) = self.after_connect().await {
+            self.link = None;
+            return Err(self.fail(e));
+        }
+        self.diagnostics.connection_state = ConnectionState::Connected;
+        info!(
+            "Ch{} DL/T 645 opened {} at {} baud",
+            self.id, self.config.device, self.config.baud_rate
+        );
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> igw::Result<()> {
+        self.link = None;
+        self.diagnostics.connection_state = ConnectionState::Disconnected;
+        Ok(())
+    }
+
+    async fn poll_once(&mut self) -> PollResult {
+        if self.link.is_none() {
+            return PollResult::failed(vec![PointFailure::new(0, "not connected")]);
+        }
+        if self.time_sync_due() {
+            if let Err(e) = self.sync_time().await {
+                let e = self.fail(e);
+                warn!("Ch{} DL/T 645 time sync failed: {}", self.id, e);
+            }
+        }
+
+        let reads: Vec<_> = self
+            .reads
+            .iter()
+            .map(|(key, readings)| (*key, readings.clone()))
+            .collect();
+        let mut batch = DataBatch::default();
+        let mut failures = Vec::new();
+        for ((address, data_id), readings) in reads {
+            let Some(address) = address.or(self.probed) else {
+                failures.extend(
+                    readings
+                        .iter()
+                        .map(|r| PointFailure::new(r.internal_id, "meter address unknown")),
+                );
+                continue;
+            };
+            match self.read(address, data_id).await {
+                Ok(bytes) => {
+                    for reading in &readings {
+                        match reading.decode(&bytes) {
+                            Ok(value) => batch.add(DataPoint::new(reading.internal_id, value)),
+                            Err(e) => {
+                                failures.push(PointFailure::with_error(reading.internal_id, e))
+                            },
+                        }
+                    }
+                },
+                Err(e) => {
+                    let e = self.fail(e);
+                    failures.extend(
+                        readings
+                            .iter()
+                            .map(|r| PointFailure::with_error(r.internal_id, e.to_string())),
+                    );
+                    if self.link.is_none() {
+                        break;
+                    }
+                },
+            }
+        }
+        self.diagnostics.read_count += 1;
+        PollResult::partial(batch, failures)
+    }
+
+    async fn write_control(&mut self, _commands: &[(u32, f64)]) -> igw::Result<usize> {
+        Err(GatewayError::Unsupported(
+            "DL/T 645 channels are read-only".to_string(),
+        ))
+    }
+
+    async fn write_adjustment(&mut self, _adjustments: &[(u32, f64)]) -> igw::Result<usize> {
+        Err(GatewayError::Unsupported(
+            "DL/T 645 channels are read-only".to_string(),
+        ))
+    }
+
+    fn subscribe(&self) -> Option<DataEventReceiver> {
+        None
+    }
+
+    async fn start_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn stop_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn diagnostics(&self) -> igw::Result<Diagnostics> {
+        let mut diagnostics = self.diagnostics.clone();
+        diagnostics.extra = json!({
+            "device": self.config.device,
+            "meter_address": self
+                .config
+                .meter_address
+                .or(self.probed)
+                .map(|a| a.to_string()),
+            "probed": self.probed.is_some(),
+            "reads": self.reads.len(),
+            "time_sync_age_s": self.last_time_sync.map(|t| t.elapsed().as_secs()),
+        });
+        Ok(diagnostics)
+    }
+}
+
+// ============================================================================
+// Plugin metadata
+// ============================================================================
+
+fn parameters() -> Vec<ParameterMetadata> {
+    vec![
+        ParameterMetadata::required(
+            "device",
+            "Serial Device",
+            "RS-485 port, e.g. /dev/ttyS1",
+            ParameterType::String,
+        ),
+        ParameterMetadata::optional(
+            "baud_rate",
+            "Baud Rate",
+            "Line speed",
+            ParameterType::Integer,
+            json!(DEFAULT_BAUD_RATE),
+        ),
+        ParameterMetadata::optional(
+            "data_bits",
+            "Data Bits",
+            "Data bits per character",
+            ParameterType::Integer,
+            json!(8),
+        ),
+        ParameterMetadata::optional(
+            "parity",
+            "Parity",
+            "even, odd or none",
+            ParameterType::String,
+            json!("even"),
+        ),
+        ParameterMetadata::optional(
+            "stop_bits",
+            "Stop Bits",
+            "1 or 2",
+            ParameterType::Integer,
+            json!(1),
+        ),
+        ParameterMetadata::optional(
+            "meter_address",
+            "Meter Address",
+            "12-digit address of points without their own; empty to probe the bus",
+            ParameterType::String,
+            json!(""),
+        ),
+        ParameterMetadata::optional(
+            "response_timeout_ms",
+            "Response Timeout (ms)",
+            "Time to wait for a meter response",
+            ParameterType::Integer,
+            json!(DEFAULT_RESPONSE_TIMEOUT_MS),
+        ),
+        ParameterMetadata::optional(
+            "preamble",
+            "Wake-up Bytes",
+            "0xFE bytes sent before each request",
+            ParameterType::Integer,
+            json!(DEFAULT_PREAMBLE),
+        ),
+        ParameterMetadata::optional(
+            "time_sync_interval_s",
+            "Time Sync Interval (s)",
+            "Broadcast the clock to all meters at this interval; 0 disables",
+            ParameterType::Integer,
+            json!(0),
+        ),
+    ]
+}
+
+crate::protocol_plugin! {
+    /// DL/T 645-2007 meter reading (in-tree runtime)
+    pub struct Dlt645Plugin {
+        name: PROTOCOL,
+        aliases: &["dlt645_2007", "dl/t645", "dl_t645"],
+        display_name: "DL/T 645-2007",
+        description: "Chinese electricity meters read by data identifier over RS-485",
+        point_types: &[PointType::Telemetry, PointType::Signal],
+        parameters: parameters(),
+        mapping_columns: &[
+            MappingColumn::string("data_id", "Data identifier DI3..DI0 in hex, e.g. 02010100")
+                .required(),
+            MappingColumn::string("meter_address", "12-digit meter address, defaults to the channel's"),
+            MappingColumn::string("data_format", "Value layout, e.g. XXX.XXX; known for common DIs"),
+            MappingColumn::boolean("signed", "Sign in the top bit"),
+            MappingColumn::integer("bit", "Bit of a status word (signal points)").range(0, 63),
+        ],
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use tokio::io::DuplexStream;
+
+    fn point<T: serde::de::DeserializeOwned>(point_id: u32, mapping: JsonValue) -> T {
+        serde_json::from_value(json!({
+            "point_id": point_id,
+            "signal_name": format!("p{}", point_id),
+            "protocol_mappings": mapping.to_string(),
+        }))
+        .unwrap()
+    }
+
+    /// Meter 202312345678 answering address and data reads
+    async fn meter(mut stream: DuplexStream) {
+        let address = Address::parse("202312345678").unwrap();
+        let values: HashMap<u32, Vec<u8>> = HashMap::from([
+            (0x0201_0100, vec![0x01, 0x22]),       // 220.1 V
+            (0x0202_0100, vec![0x00, 0x25, 0x81]), // -12.5 A
+            (0x0400_0501, vec![0x10, 0xA0]),       // bits 4, 13 and 15 set
+        ]);
+        let mut pending = Vec::new();
+        let mut buf = [0u8; 64];
+        loop {
+            let Ok(n) = stream.read(&mut buf).await else {
+                return;
+            };
+            if n == 0 {
+                return;
+            }
+            pending.extend_from_slice(&buf[..n]);
+            while let Some((request, used)) = codec::decode(&pending).unwrap() {
+                pending.drain(..used);
+                let reply = match request.function() {
+                    codec::control::READ_ADDRESS => (0x93, address.0.to_vec()),
+                    codec::control::READ_DATA => {
+                        let id = u32::from_le_bytes(request.data[..4].try_into().unwrap());
+                        match values.get(&id) {
+                            Some(value) => (0x91, [&request.data[..4], value].concat()),
+                            None => (0xD1, vec![0x02]),
+                        }
+                    },
+                    // Broadcasts are not answered
+                    _ => continue,
+                };
+                let frame = codec::encode(address, reply.0, &reply.1).unwrap();
+                stream.write_all(&[codec::WAKE_UP; 2]).await.unwrap();
+                stream.write_all(&frame).await.unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_probe_and_poll() {
+        let mut config = RuntimeChannelConfig::from_base(
+            serde_json::from_value(json!({
+                "id": 21,
+                "name": "meters",
+                "protocol": PROTOCOL,
+                "parameters": {
+                    "device": "/dev/null",
+                    "response_timeout_ms": 2000,
+                    "time_sync_interval_s": 3600,
+                },
+            }))
+            .unwrap(),
+        );
+        config.telemetry_points = vec![
+            point(1, json!({"data_id": "02010100"})),
+            point(2, json!({"data_id": "0x02020100"})),
+            point(3, json!({"data_id": "02010200"})),
+            // Unknown DI without a format: skipped
+            point(4, json!({"data_id": "04000101"})),
+        ];
+        config.signal_points = vec![
+            point(1, json!({"data_id": "04000501", "bit": 4})),
+            point(2, json!({"data_id": "04000501", "bit": 5})),
+        ];
+        config.control_points = vec![point(1, json!({"data_id": "04000501"}))];
+
+        let mut runtime = Dlt645Runtime::from_runtime_config(&config).unwrap();
+        assert_eq!(runtime.reads.len(), 4);
+        let (client, server) = tokio::io::duplex(1024);
+        tokio::spawn(meter(server));
+        runtime.attach(client);
+        runtime.after_connect().await.unwrap();
+        assert_eq!(runtime.probed.unwrap().to_string(), "202312345678");
+        assert!(runtime.last_time_sync.is_some());
+
+        let result = runtime.poll_once().await;
+        let value = |point_type: PointType, id: u32| {
+            result
+                .data
+                .iter()
+                .find(|p| p.id == point_type.to_internal_id(id))
+                .map(|p| p.value.as_f64().unwrap())
+        };
+        assert_eq!(value(PointType::Telemetry, 1), Some(220.1));
+        assert_eq!(value(PointType::Telemetry, 2), Some(-12.5));
+        assert_eq!(value(PointType::Signal, 1), Some(1.0));
+        assert_eq!(value(PointType::Signal, 2), Some(0.0));
+        // Phase B voltage: "no data requested"
+        assert_eq!(result.failures.len(), 1);
+        assert!(runtime
+            .write_control(&[(PointType::Control.to_internal_id(1), 1.0)])
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_config_parameters() {
+        let parameters: HashMap<String, JsonValue> = [
+            ("device".to_string(), json!("/dev/ttyS1")),
+            ("parity".to_string(), json!("None")),
+            ("meter_address".to_string(), json!("12345678")),
+        ]
+        .into_iter()
+        .collect();
+        let config = Dlt645Config::from_parameters(&parameters).unwrap();
+        assert_eq!(config.baud_rate, DEFAULT_BAUD_RATE);
+        assert_eq!(config.parity, Parity::None);
+        assert_eq!(
+            config.meter_address,
+            Some(Address::parse("000012345678").unwrap())
+        );
+        assert_eq!(config.time_sync_interval, None);
+        assert!(Dlt645Config::from_parameters(&HashMap::new()).is_err());
+    }
+}
@@ -0,0 +1,454 @@
+//! DL/T 645-2007 frame encoding
+//!
+//! ```text
+//! 68 A0 A1 A2 A3 A4 A5 68 C L DATA.. CS 16
+//! ```
+//!
+//! The meter address is 12 BCD digits sent low byte first; data bytes are
+//! sent plus 0x33 and CS is the byte sum from the first 0x68. Masters may
+//! send 0xFE wake-up bytes before a frame and meters may answer with them.
+//! Values are BCD, low byte first, with a sign in the top bit of signed
+//! quantities.
+
+use std::fmt;
+
+use common::bytes::checksum::sum8;
+
+use crate::core::protocols::{error, CodecError};
+
+type Result<T> = std::result::Result<T, CodecError>;
+
+pub const START: u8 = 0x68;
+pub const END: u8 = 0x16;
+/// Wake-up byte sent before a frame
+pub const WAKE_UP: u8 = 0xFE;
+/// Added to every data byte on the wire
+const DATA_OFFSET: u8 = 0x33;
+
+/// Start, address, start, control and length
+const HEADER_LEN: usize = 10;
+/// Longest frame: header, 255 data bytes, CS and end
+pub const MAX_FRAME_LEN: usize = HEADER_LEN + 255 + 2;
+
+/// Control codes (master requests; responses set [`RESPONSE`])
+pub mod control {
+    pub const BROADCAST_TIME: u8 = 0x08;
+    pub const READ_DATA: u8 = 0x11;
+    pub const READ_ADDRESS: u8 = 0x13;
+    /// Direction bit: slave to master
+    pub const RESPONSE: u8 = 0x80;
+    /// Abnormal response
+    pub const ERROR: u8 = 0x40;
+    /// More data follows in another frame
+    pub const FOLLOWS: u8 = 0x20;
+    /// Function bits
+    pub const FUNCTION: u8 = 0x1F;
+}
+
+// ============================================================================
+// Address
+// ============================================================================
+
+/// Meter address, as sent on the wire (low byte first)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Address(pub [u8; 6]);
+
+impl Address {
+    /// Any meter: only valid with a single meter on the bus
+    pub const WILDCARD: Self = Self([0xAA; 6]);
+    /// All meters, no response
+    pub const BROADCAST: Self = Self([0x99; 6]);
+
+    /// 12 digits as printed on the meter, `A` for wildcard digits;
+    /// shorter numbers are padded with leading zeros
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        if text.is_empty() || text.len() > 12 {
+            return Err(error(format!(
+                "meter address '{}' must be 1-12 digits",
+                text
+            )));
+        }
+        let digits: Vec<u8> = format!("{:0>12}", text)
+            .chars()
+            .map(|c| match c {
+                '0'..='9' => Ok(c as u8 - b'0'),
+                'a' | 'A' => Ok(0x0A),
+                _ => Err(error(format!("meter address '{}' is not BCD", text))),
+            })
+            .collect::<Result<_>>()?;
+        let mut bytes = [0u8; 6];
+        for (i, pair) in digits.chunks(2).enumerate() {
+            bytes[5 - i] = (pair[0] << 4) | pair[1];
+        }
+        Ok(Self(bytes))
+    }
+
+    /// Whether a frame from `other` answers a request to `self`
+    pub fn matches(&self, other: &Address) -> bool {
+        self.0
+            .iter()
+            .zip(other.0)
+            .all(|(wanted, got)| *wanted == 0xAA || *wanted == got)
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0.iter().rev() {
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Frames
+// ============================================================================
+
+/// Decoded frame; `data` without the 0x33 offset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub address: Address,
+    pub control: u8,
+    pub data: Vec<u8>,
+}
+
+impl Frame {
+    pub fn is_response(&self) -> bool {
+        self.control & control::RESPONSE != 0
+    }
+
+    pub fn function(&self) -> u8 {
+        self.control & control::FUNCTION
+    }
+}
+
+pub fn encode(address: Address, control: u8, data: &[u8]) -> Result<Vec<u8>> {
+    let len = u8::try_from(data.len()).map_err(|_| error("frame data over 255 bytes"))?;
+    let mut frame = Vec::with_capacity(HEADER_LEN + data.len() + 2);
+    frame.push(START);
+    frame.extend_from_slice(&address.0);
+    frame.extend_from_slice(&[START, control, len]);
+    frame.extend(data.iter().map(|b| b.wrapping_add(DATA_OFFSET)));
+    frame.push(sum8(&frame));
+    frame.push(END);
+    Ok(frame)
+}
+
+/// First complete frame in `buf` and the bytes it used, skipping wake-up
+/// bytes and noise before it; None until enough bytes arrived
+pub fn decode(buf: &[u8]) -> Result<Option<(Frame, usize)>> {
+    let Some(start) = buf.iter().position(|b| *b == START) else {
+        return Ok(None);
+    };
+    let frame = &buf[start..];
+    if frame.len() < HEADER_LEN {
+        return Ok(None);
+    }
+    if frame[7] != START {
+        return Err(error("second start byte missing"));
+    }
+    let len = usize::from(frame[9]);
+    let total = HEADER_LEN + len + 2;
+    if frame.len() < total {
+        return Ok(None);
+    }
+    let checksum = sum8(&frame[..HEADER_LEN + len]);
+    if frame[HEADER_LEN + len] != checksum {
+        return Err(error(format!(
+            "checksum {:02X}, expected {:02X}",
+            frame[HEADER_LEN + len],
+            checksum
+        )));
+    }
+    if frame[total - 1] != END {
+        return Err(error("end byte missing"));
+    }
+    let mut address = [0u8; 6];
+    address.copy_from_slice(&frame[1..7]);
+    Ok(Some((
+        Frame {
+            address: Address(address),
+            control: frame[8],
+            data: frame[HEADER_LEN..HEADER_LEN + len]
+                .iter()
+                .map(|b| b.wrapping_sub(DATA_OFFSET))
+                .collect(),
+        },
+        start + total,
+    )))
+}
+
+/// Read one data identifier
+pub fn read_request(address: Address, data_id: u32) -> Result<Vec<u8>> {
+    encode(address, control::READ_DATA, &data_id.to_le_bytes())
+}
+
+/// Ask the only meter on the bus for its address
+pub fn read_address_request() -> Result<Vec<u8>> {
+    encode(Address::WILDCARD, control::READ_ADDRESS, &[])
+}
+
+/// Set the clock of all meters; `time` is (year % 100, month, day, hour,
+/// minute, second)
+pub fn time_sync_request(time: (u8, u8, u8, u8, u8, u8)) -> Result<Vec<u8>> {
+    let (year, month, day, hour, minute, second) = time;
+    let data = [second, minute, hour, day, month, year].map(to_bcd);
+    encode(Address::BROADCAST, control::BROADCAST_TIME, &data)
+}
+
+/// Error bits of an abnormal response
+fn error_reason(code: u8) -> String {
+    const REASONS: &[(u8, &str)] = &[
+        (0x02, "no data requested"),
+        (0x04, "password error or unauthorized"),
+        (0x08, "baud rate cannot be changed"),
+        (0x10, "annual time zones exceeded"),
+        (0x20, "daily time periods exceeded"),
+        (0x40, "tariffs exceeded"),
+    ];
+    let reasons: Vec<&str> = REASONS
+        .iter()
+        .filter(|(bit, _)| code & bit != 0)
+        .map(|(_, reason)| *reason)
+        .collect();
+    if reasons.is_empty() {
+        format!("meter error 0x{:02X}", code)
+    } else {
+        reasons.join(", ")
+    }
+}
+
+/// Value bytes of the response to a [`read_request`] for `data_id`
+pub fn parse_read_response(data_id: u32, frame: &Frame) -> Result<Vec<u8>> {
+    if frame.function() != control::READ_DATA || !frame.is_response() {
+        return Err(error(format!(
+            "unexpected control code 0x{:02X}",
+            frame.control
+        )));
+    }
+    if frame.control & control::ERROR != 0 {
+        return Err(error(error_reason(
+            frame.data.first().copied().unwrap_or(0),
+        )));
+    }
+    let (id, value) = frame
+        .data
+        .split_at_checked(4)
+        .ok_or_else(|| error("response without a data identifier"))?;
+    let id = u32::from_le_bytes([id[0], id[1], id[2], id[3]]);
+    if id != data_id {
+        return Err(error(format!(
+            "response for {:08X} to a read of {:08X}",
+            id, data_id
+        )));
+    }
+    Ok(value.to_vec())
+}
+
+/// Meter address of the response to a [`read_address_request`]
+pub fn parse_address_response(frame: &Frame) -> Result<Address> {
+    if frame.function() != control::READ_ADDRESS
+        || !frame.is_response()
+        || frame.control & control::ERROR != 0
+    {
+        return Err(error(format!(
+            "unexpected control code 0x{:02X}",
+            frame.control
+        )));
+    }
+    let bytes: [u8; 6] = frame
+        .data
+        .get(..6)
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| error("address response without an address"))?;
+    Ok(Address(bytes))
+}
+
+// ============================================================================
+// Values
+// ============================================================================
+
+fn to_bcd(value: u8) -> u8 {
+    ((value / 10) << 4) | (value % 10)
+}
+
+/// Layout of a BCD value, e.g. `XXX.XXX` (3 bytes, 3 decimals)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataFormat {
+    pub len: usize,
+    pub decimals: u8,
+    /// Sign in the top bit of the last byte
+    pub signed: bool,
+}
+
+impl DataFormat {
+    const fn new(digits: usize, decimals: u8, signed: bool) -> Self {
+        Self {
+            len: digits / 2,
+            decimals,
+            signed,
+        }
+    }
+
+    /// Format as written in the standard's tables (`XXXXXX.XX`)
+    pub fn parse(text: &str, signed: bool) -> Result<Self> {
+        let text = text.trim();
+        let (integer, fraction) = text.split_once('.').unwrap_or((text, ""));
+        let valid = |part: &str| part.chars().all(|c| c.eq_ignore_ascii_case(&'x'));
+        let digits = integer.len() + fraction.len();
+        if !valid(integer) || !valid(fraction) || digits == 0 || digits % 2 != 0 || digits > 16 {
+            return Err(error(format!(
+                "data format '{}' must be an even number of X digits up to 16",
+                text
+            )));
+        }
+        Ok(Self::new(digits, fraction.len() as u8, signed))
+    }
+
+    /// Format of the common identifiers of the standard
+    pub fn of_data_id(data_id: u32) -> Option<Self> {
+        let [_, di1, di2, di3] = data_id.to_le_bytes();
+        Some(match (di3, di2, di1) {
+            // Combined active energy may run backwards
+            (0x00, 0x00, _) => Self::new(8, 2, true),
+            // Energy (kWh, kvarh, kVAh)
+            (0x00, _, _) => Self::new(8, 2, false),
+            (0x02, 0x01, _) => Self::new(4, 1, false), // voltage, V
+            (0x02, 0x02, _) => Self::new(6, 3, true),  // current, A
+            (0x02, 0x03..=0x05, _) => Self::new(6, 4, true), // kW, kvar, kVA
+            (0x02, 0x06, _) => Self::new(4, 3, true),  // power factor
+            (0x02, 0x07, _) => Self::new(4, 1, false), // phase angle, °
+            (0x02, 0x80, _) => match data_id {
+                0x0280_0001 => Self::new(6, 3, true),  // neutral current, A
+                0x0280_0002 => Self::new(4, 2, false), // frequency, Hz
+                0x0280_0007 => Self::new(4, 1, true),  // meter temperature, °C
+                _ => return None,
+            },
+            // Status words
+            (0x04, 0x00, 0x05) => Self::new(4, 0, false),
+            _ => return None,
+        })
+    }
+
+    /// Integer of the BCD digits (the sign bit cleared) and the sign
+    fn digits(&self, bytes: &[u8]) -> Result<(u64, bool)> {
+        let bytes = bytes.get(..self.len).ok_or_else(|| {
+            error(format!(
+                "value of {} bytes, expected {}",
+                bytes.len(),
+                self.len
+            ))
+        })?;
+        let mut negative = false;
+        let mut value = 0u64;
+        for (i, byte) in bytes.iter().enumerate().rev() {
+            let mut byte = *byte;
+            if self.signed && i + 1 == self.len {
+                negative = byte & 0x80 != 0;
+                byte &= 0x7F;
+            }
+            let (high, low) = (byte >> 4, byte & 0x0F);
+            if high > 9 || low > 9 {
+                return Err(error(format!("invalid BCD byte {:02X}", byte)));
+            }
+            value = value * 100 + u64::from(high * 10 + low);
+        }
+        Ok((value, negative))
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> Result<f64> {
+        let (value, negative) = self.digits(bytes)?;
+        let value = value as f64 / 10f64.powi(i32::from(self.decimals));
+        Ok(if negative { -value } else { value })
+    }
+
+    /// Bytes as a binary little-endian word, for status words
+    pub fn word(&self, bytes: &[u8]) -> Result<u64> {
+        let bytes = bytes.get(..self.len).ok_or_else(|| {
+            error(format!(
+                "value of {} bytes, expected {}",
+                bytes.len(),
+                self.len
+            ))
+        })?;
+        Ok(bytes
+            .iter()
+            .rev()
+            .fold(0u64, |word, byte| (word << 8) | u64::from(*byte)))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_round_trip() {
+        let address = Address::parse("202312345678").unwrap();
+        assert_eq!(address.0, [0x78, 0x56, 0x34, 0x12, 0x23, 0x20]);
+        assert_eq!(address.to_string(), "202312345678");
+
+        let request = read_request(address, 0x0201_0100).unwrap();
+        assert_eq!(
+            request,
+            [
+                0x68, 0x78, 0x56, 0x34, 0x12, 0x23, 0x20, 0x68, 0x11, 0x04, 0x33, 0x34, 0x34, 0x35,
+                0x0C, 0x16
+            ]
+        );
+
+        // Wake-up bytes, then the voltage response 220.1 V
+        let response = encode(
+            address,
+            control::RESPONSE | control::READ_DATA,
+            &[0x00, 0x01, 0x01, 0x02, 0x01, 0x22],
+        )
+        .unwrap();
+        let mut wire = vec![WAKE_UP; 4];
+        wire.extend_from_slice(&response);
+        assert_eq!(decode(&wire[..10]).unwrap(), None);
+        let (frame, used) = decode(&wire).unwrap().unwrap();
+        assert_eq!(used, wire.len());
+        let value = parse_read_response(0x0201_0100, &frame).unwrap();
+        let format = DataFormat::of_data_id(0x0201_0100).unwrap();
+        assert_eq!(format.decode(&value).unwrap(), 220.1);
+        assert!(parse_read_response(0x0202_0100, &frame).is_err());
+
+        let mut corrupt = response.clone();
+        corrupt[11] ^= 1;
+        assert!(decode(&corrupt).is_err());
+    }
+
+    #[test]
+    fn test_values_and_errors() {
+        let current = DataFormat::of_data_id(0x0202_0100).unwrap();
+        assert_eq!(current.decode(&[0x00, 0x25, 0x81]).unwrap(), -12.5);
+        let energy = DataFormat::parse("XXXXXX.XX", false).unwrap();
+        assert_eq!(energy.len, 4);
+        assert_eq!(energy.decode(&[0x99, 0x78, 0x56, 0x34]).unwrap(), 345678.99);
+        assert!(energy.decode(&[0xEE, 0, 0, 0]).is_err());
+        assert!(DataFormat::parse("XXX.X.X", false).is_err());
+        assert!(DataFormat::parse("XXX", false).is_err());
+
+        let error_frame = Frame {
+            address: Address::WILDCARD,
+            control: 0xD1,
+            data: vec![0x02],
+        };
+        assert_eq!(
+            parse_read_response(0x0201_0100, &error_frame)
+                .unwrap_err()
+                .to_string(),
+            "no data requested"
+        );
+        assert_eq!(
+            time_sync_request((26, 10, 17, 8, 30, 5)).unwrap()[10..16],
+            [0x05, 0x30, 0x08, 0x17, 0x10, 0x26].map(|b: u8| b + 0x33)
+        );
+        assert!(Address::WILDCARD.matches(&Address::parse("1").unwrap()));
+        assert!(Address::parse("12345678901X").is_err());
+    }
+}
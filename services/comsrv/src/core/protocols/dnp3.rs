@@ -0,0 +1,954 @@
+//! DNP3 master over TCP
+//!
+//! Polls an outstation with class 0123 integrity reads and maps the returned
+//! objects onto the four-remote point model:
+//!
+//! | Point type | DNP3 objects (`object` mapping column)                               |
+//! |------------|----------------------------------------------------------------------|
+//! | T          | `analog_input` (g30/g32), `counter` (g20-g23), `analog_output_status` (g40/g42) |
+//! | S          | `binary_input` (g1/g2), `double_bit_input` (g3/g4), `binary_output_status` (g10/g11) |
+//! | C          | `crob` (g12v1), `analog_output` (g41)                                |
+//! | A          | `analog_output` (g41), `crob` (g12v1)                                |
+//!
+//! Each point maps to one object `index`; the `object` column defaults to
+//! the first entry of its row. Commands use direct operate, or
+//! select-before-operate when the channel sets `select_before_operate`.
+//! Unsolicited responses received between polls are confirmed and merged
+//! into the next poll result.
+//!
+//! ```yaml
+//! protocol: dnp3_tcp
+//! parameters:
+//!   host: 192.168.1.20
+//!   port: 20000
+//!   local_address: 1       # master
+//!   remote_address: 1024   # outstation
+//! ```
+
+pub mod codec;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use igw::core::traits::{DataEventReceiver, Diagnostics, PointFailure, PollResult};
+use igw::gateway::ChannelRuntime;
+use igw::{ConnectionState, DataBatch, DataPoint, GatewayError, Quality};
+use serde_json::{json, Value as JsonValue};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{timeout_at, Instant};
+use tracing::{debug, info, warn};
+
+use self::codec::{function, Crob, FrameReader, ObjectKind, ObjectValue, Reassembler, Response};
+use crate::core::config::RuntimeChannelConfig;
+use crate::core::plugins::{MappingColumn, ParameterMetadata, ParameterType};
+use crate::core::protocols::{as_bool, as_u64, point_mapping, record_failure};
+use crate::error::{ComSrvError, Result};
+use voltage_model::PointType;
+
+/// Protocol name stored in `channels.protocol`
+pub const PROTOCOL: &str = "dnp3_tcp";
+
+const DEFAULT_PORT: u16 = 20000;
+const DEFAULT_LOCAL_ADDRESS: u16 = 1;
+const DEFAULT_REMOTE_ADDRESS: u16 = 1024;
+const DEFAULT_RESPONSE_TIMEOUT_MS: u64 = 5000;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Channel parameters of a DNP3 TCP channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dnp3Config {
+    pub host: String,
+    pub port: u16,
+    /// Link address of this master
+    pub local_address: u16,
+    /// Link address of the outstation
+    pub remote_address: u16,
+    pub response_timeout: Duration,
+    /// SELECT then OPERATE instead of DIRECT_OPERATE
+    pub select_before_operate: bool,
+}
+
+impl Dnp3Config {
+    pub fn from_parameters(parameters: &HashMap<String, JsonValue>) -> Result<Self> {
+        let host = parameters
+            .get("host")
+            .and_then(|v| v.as_str())
+            .filter(|h| !h.trim().is_empty())
+            .ok_or_else(|| ComSrvError::ConfigError("DNP3 channel requires 'host'".into()))?
+            .trim()
+            .to_string();
+        let address = |key: &str, default: u16| -> Result<u16> {
+            match parameters.get(key) {
+                None | Some(JsonValue::Null) => Ok(default),
+                Some(value) => as_u64(value)
+                    .and_then(|v| u16::try_from(v).ok())
+                    .ok_or_else(|| {
+                        ComSrvError::ConfigError(format!("DNP3 '{}' must be 0-65535", key))
+                    }),
+            }
+        };
+
+        Ok(Self {
+            host,
+            port: address("port", DEFAULT_PORT)?,
+            local_address: address("local_address", DEFAULT_LOCAL_ADDRESS)?,
+            remote_address: address("remote_address", DEFAULT_REMOTE_ADDRESS)?,
+            response_timeout: Duration::from_millis(
+                parameters
+                    .get("response_timeout_ms")
+                    .and_then(as_u64)
+                    .unwrap_or(DEFAULT_RESPONSE_TIMEOUT_MS)
+                    .max(1),
+            ),
+            select_before_operate: parameters
+                .get("select_before_operate")
+                .and_then(as_bool)
+                .unwrap_or(false),
+        })
+    }
+}
+
+// ============================================================================
+// Point mapping
+// ============================================================================
+
+/// How a CROB expresses a control value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlCode {
+    /// Non-zero = LATCH_ON, zero = LATCH_OFF
+    Latch,
+    /// Non-zero = PULSE_ON, zero = PULSE_OFF
+    Pulse,
+    /// Non-zero = CLOSE, zero = TRIP (pulse on)
+    TripClose,
+}
+
+impl ControlCode {
+    fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "latch" => Some(Self::Latch),
+            "pulse" => Some(Self::Pulse),
+            "trip_close" => Some(Self::TripClose),
+            _ => None,
+        }
+    }
+
+    fn code(self, value: f64) -> u8 {
+        use codec::control_code::*;
+        let on = value != 0.0;
+        match (self, on) {
+            (Self::Latch, true) => LATCH_ON,
+            (Self::Latch, false) => LATCH_OFF,
+            (Self::Pulse, true) => PULSE_ON,
+            (Self::Pulse, false) => PULSE_OFF,
+            (Self::TripClose, true) => CLOSE_PULSE_ON,
+            (Self::TripClose, false) => TRIP_PULSE_ON,
+        }
+    }
+}
+
+/// Output a control or adjustment point operates
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Output {
+    Crob {
+        index: u16,
+        code: ControlCode,
+        on_time_ms: u32,
+        off_time_ms: u32,
+    },
+    Analog {
+        index: u16,
+        variation: u8,
+    },
+}
+
+fn object_kind(name: &str) -> Option<ObjectKind> {
+    Some(match name.trim().to_lowercase().as_str() {
+        "binary_input" => ObjectKind::BinaryInput,
+        "double_bit_input" => ObjectKind::DoubleBitInput,
+        "binary_output_status" => ObjectKind::BinaryOutputStatus,
+        "analog_input" => ObjectKind::AnalogInput,
+        "counter" => ObjectKind::Counter,
+        "analog_output_status" => ObjectKind::AnalogOutputStatus,
+        "crob" => ObjectKind::Crob,
+        "analog_output" => ObjectKind::AnalogOutput,
+        _ => return None,
+    })
+}
+
+/// Objects a point type may map to, default first
+fn allowed_objects(point_type: PointType) -> &'static [ObjectKind] {
+    match point_type {
+        PointType::Telemetry => &[
+            ObjectKind::AnalogInput,
+            ObjectKind::Counter,
+            ObjectKind::AnalogOutputStatus,
+        ],
+        PointType::Signal => &[
+            ObjectKind::BinaryInput,
+            ObjectKind::DoubleBitInput,
+            ObjectKind::BinaryOutputStatus,
+        ],
+        PointType::Control => &[ObjectKind::Crob, ObjectKind::AnalogOutput],
+        PointType::Adjustment => &[ObjectKind::AnalogOutput, ObjectKind::Crob],
+    }
+}
+
+/// Parsed mapping of one point
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Binding {
+    Input(ObjectKind, u16),
+    Output(Output),
+}
+
+fn parse_binding(
+    point_type: PointType,
+    mapping: &JsonValue,
+) -> std::result::Result<Binding, String> {
+    let field = |key: &str| mapping.get(key).filter(|v| !v.is_null() && *v != "");
+    let number = |key: &str, max: u64, default: u64| -> std::result::Result<u64, String> {
+        match field(key) {
+            None => Ok(default),
+            Some(value) => as_u64(value)
+                .filter(|v| *v <= max)
+                .ok_or_else(|| format!("'{}' must be 0-{}", key, max)),
+        }
+    };
+
+    let index = match field("index") {
+        None => return Err("missing 'index'".to_string()),
+        Some(_) => number("index", u16::MAX as u64, 0)? as u16,
+    };
+    let allowed = allowed_objects(point_type);
+    let object = match field("object") {
+        None => allowed[0],
+        Some(value) => value
+            .as_str()
+            .and_then(object_kind)
+            .filter(|kind| allowed.contains(kind))
+            .ok_or_else(|| format!("object {} is not valid for {} points", value, point_type))?,
+    };
+
+    Ok(match object {
+        ObjectKind::Crob => {
+            let code = match field("control_code") {
+                None => ControlCode::Latch,
+                Some(value) => value
+                    .as_str()
+                    .and_then(ControlCode::parse)
+                    .ok_or_else(|| format!("unknown control_code {}", value))?,
+            };
+            Binding::Output(Output::Crob {
+                index,
+                code,
+                on_time_ms: number("on_time_ms", u32::MAX as u64, 1000)? as u32,
+                off_time_ms: number("off_time_ms", u32::MAX as u64, 0)? as u32,
+            })
+        },
+        ObjectKind::AnalogOutput => {
+            let variation = number("variation", 4, 3)? as u8;
+            if variation == 0 {
+                return Err("'variation' must be 1-4".to_string());
+            }
+            Binding::Output(Output::Analog { index, variation })
+        },
+        kind => Binding::Input(kind, index),
+    })
+}
+
+// ============================================================================
+// Runtime
+// ============================================================================
+
+/// DNP3 TCP master channel
+pub struct Dnp3Runtime {
+    id: u32,
+    name: String,
+    config: Dnp3Config,
+    /// (object, index) -> internal point IDs
+    inputs: HashMap<(ObjectKind, u32), Vec<u32>>,
+    controls: HashMap<u32, Output>,
+    adjustments: HashMap<u32, Output>,
+    stream: Option<TcpStream>,
+    reader: FrameReader,
+    reassembler: Reassembler,
+    app_seq: u8,
+    transport_seq: u8,
+    /// Objects from unsolicited responses, reported with the next poll
+    unsolicited: Vec<ObjectValue>,
+    diagnostics: Diagnostics,
+}
+
+impl Dnp3Runtime {
+    /// Build the runtime of a `dnp3_tcp` channel
+    pub fn from_runtime_config(runtime_config: &RuntimeChannelConfig) -> Result<Self> {
+        let channel_id = runtime_config.id();
+        let config = Dnp3Config::from_parameters(&runtime_config.base.parameters)
+            .map_err(|e| ComSrvError::ConfigError(format!("Ch{}: {}", channel_id, e)))?;
+
+        let mut runtime = Self {
+            id: channel_id,
+            name: runtime_config.name().to_string(),
+            config,
+            inputs: HashMap::new(),
+            controls: HashMap::new(),
+            adjustments: HashMap::new(),
+            stream: None,
+            reader: FrameReader::new(),
+            reassembler: Reassembler::default(),
+            app_seq: 0,
+            transport_seq: 0,
+            unsolicited: Vec::new(),
+            diagnostics: Diagnostics::new(PROTOCOL),
+        };
+
+        for (point_type, point) in runtime_config.points() {
+            let binding = match parse_binding(point_type, &point_mapping(point)) {
+                Ok(binding) => binding,
+                Err(e) => {
+                    warn!(
+                        "Ch{} {}{} skipped: {}",
+                        channel_id,
+                        point_type.as_str(),
+                        point.point_id,
+                        e
+                    );
+                    continue;
+                },
+            };
+            match (binding, point_type) {
+                (Binding::Input(kind, index), _) => runtime
+                    .inputs
+                    .entry((kind, index as u32))
+                    .or_default()
+                    .push(point_type.to_internal_id(point.point_id)),
+                (Binding::Output(output), PointType::Control) => {
+                    runtime.controls.insert(point.point_id, output);
+                },
+                (Binding::Output(output), _) => {
+                    runtime.adjustments.insert(point.point_id, output);
+                },
+            }
+        }
+        debug!(
+            "Ch{} DNP3 {}:{} points: {} inputs, {} controls, {} adjustments",
+            channel_id,
+            runtime.config.host,
+            runtime.config.port,
+            runtime.inputs.len(),
+            runtime.controls.len(),
+            runtime.adjustments.len()
+        );
+        Ok(runtime)
+    }
+
+    fn next_app_seq(&mut self) -> u8 {
+        let seq = self.app_seq;
+        self.app_seq = (self.app_seq + 1) & 0x0F;
+        seq
+    }
+
+    /// Record an error; connection failures drop the stream so the next
+    /// `connect()` starts over
+    fn fail(&mut self, error: GatewayError) -> GatewayError {
+        if record_failure(&mut self.diagnostics, &error) {
+            self.stream = None;
+        }
+        error
+    }
+
+    async fn send(&mut self, fragment: &[u8]) -> igw::Result<()> {
+        let mut bytes = Vec::new();
+        for segment in codec::segment(fragment, &mut self.transport_seq) {
+            bytes.extend(codec::encode_frame(
+                codec::LINK_CONTROL_MASTER,
+                self.config.remote_address,
+                self.config.local_address,
+                &segment,
+            ));
+        }
+        let stream = self.stream.as_mut().ok_or(GatewayError::NotConnected)?;
+        stream
+            .write_all(&bytes)
+            .await
+            .map_err(|e| GatewayError::Connection(format!("send: {}", e)))
+    }
+
+    /// Next application fragment from the outstation
+    async fn receive(&mut self, deadline: Instant) -> igw::Result<Response> {
+        let mut buf = [0u8; 1024];
+        loop {
+            while let Some(frame) = self.reader.next_frame() {
+                let frame = match frame {
+                    Ok(frame) => frame,
+                    Err(e) => {
+                        debug!("Ch{} dropped DNP3 frame: {}", self.id, e);
+                        self.reassembler.reset();
+                        continue;
+                    },
+                };
+                if !frame.is_user_data() || frame.source != self.config.remote_address {
+                    continue;
+                }
+                if let Some(fragment) = self.reassembler.push(&frame.data) {
+                    return Response::parse(&fragment)
+                        .map_err(|e| GatewayError::InvalidResponse(e.to_string()));
+                }
+            }
+
+            let timeout_ms = self.config.response_timeout.as_millis() as u64;
+            let stream = self.stream.as_mut().ok_or(GatewayError::NotConnected)?;
+            let n = timeout_at(deadline, stream.read(&mut buf))
+                .await
+                .map_err(|_| GatewayError::ConnectionTimeout(timeout_ms))?
+                .map_err(|e| GatewayError::Connection(format!("receive: {}", e)))?;
+            if n == 0 {
+                return Err(GatewayError::Connection(
+                    "outstation closed the connection".to_string(),
+                ));
+            }
+            self.reader.push(&buf[..n]);
+        }
+    }
+
+    /// Send a request and collect its (possibly multi-fragment) response
+    async fn request(&mut self, fragment: Vec<u8>) -> igw::Result<Vec<Response>> {
+        let seq = fragment[0] & 0x0F;
+        self.send(&fragment).await?;
+
+        let deadline = Instant::now() + self.config.response_timeout;
+        let mut expected = seq;
+        let mut responses: Vec<Response> = Vec::new();
+        loop {
+            let response = self.receive(deadline).await?;
+            if response.is_unsolicited() {
+                self.accept_unsolicited(&response).await?;
+                continue;
+            }
+            let first = response.control & codec::APP_FIR != 0;
+            if response.function != function::RESPONSE
+                || response.seq() != expected
+                || first != responses.is_empty()
+            {
+                debug!(
+                    "Ch{} ignored DNP3 fragment (fc 0x{:02X}, seq {})",
+                    self.id,
+                    response.function,
+                    response.seq()
+                );
+                continue;
+            }
+            if response.needs_confirm() {
+                self.send(&codec::confirm(response.seq(), false)).await?;
+            }
+            let last = response.is_final();
+            responses.push(response);
+            if last {
+                break;
+            }
+            expected = (expected + 1) & 0x0F;
+        }
+
+        if responses
+            .iter()
+            .any(|r| r.iin & codec::IIN_DEVICE_RESTART != 0)
+        {
+            info!("Ch{} DNP3 outstation restarted, clearing IIN1.7", self.id);
+            let seq = self.next_app_seq();
+            Box::pin(self.request(codec::clear_restart(seq))).await?;
+        }
+        Ok(responses)
+    }
+
+    async fn accept_unsolicited(&mut self, response: &Response) -> igw::Result<()> {
+        if response.needs_confirm() {
+            self.send(&codec::confirm(response.seq(), true)).await?;
+        }
+        match codec::parse_objects(&response.objects) {
+            Ok(values) => self.unsolicited.extend(values),
+            Err(e) => warn!("Ch{} bad DNP3 unsolicited response: {}", self.id, e),
+        }
+        Ok(())
+    }
+
+    /// Values of one response mapped to points
+    fn collect(&self, values: &[ObjectValue], batch: &mut DataBatch) {
+        for value in values {
+            let Some(ids) = self.inputs.get(&(value.kind, value.index)) else {
+                continue;
+            };
+            for &id in ids {
+                let point = DataPoint::new(id, value.value);
+                batch.add(if value.online {
+                    point
+                } else {
+                    point.with_quality(Quality::Bad)
+                });
+            }
+        }
+    }
+
+    /// Operate one output and check the echoed status
+    async fn operate(&mut self, output: Output, value: f64) -> igw::Result<()> {
+        let build = |seq: u8, function: u8| -> igw::Result<Vec<u8>> {
+            match output {
+                Output::Crob {
+                    index,
+                    code,
+                    on_time_ms,
+                    off_time_ms,
+                } => Ok(codec::crob_request(
+                    seq,
+                    function,
+                    index,
+                    Crob {
+                        code: code.code(value),
+                        count: 1,
+                        on_time_ms,
+                        off_time_ms,
+                    },
+                )),
+                Output::Analog { index, variation } => {
+                    codec::analog_output_request(seq, function, index, variation, value)
+                        .map_err(|e| GatewayError::InvalidData(e.to_string()))
+                },
+            }
+        };
+
+        let functions: &[u8] = if self.config.select_before_operate {
+            &[function::SELECT, function::OPERATE]
+        } else {
+            &[function::DIRECT_OPERATE]
+        };
+        for &function in functions {
+            let seq = self.next_app_seq();
+            let responses = self.request(build(seq, function)?).await?;
+            let status = responses
+                .iter()
+                .flat_map(|r| codec::parse_objects(&r.objects).unwrap_or_default())
+                .find_map(|v| v.status);
+            match status {
+                Some(0) => {},
+                Some(status) => {
+                    return Err(GatewayError::Protocol(format!(
+                        "command rejected (fc 0x{:02X}, status {})",
+                        function, status
+                    )))
+                },
+                None => {
+                    return Err(GatewayError::InvalidResponse(
+                        "command response without status".to_string(),
+                    ))
+                },
+            }
+        }
+        Ok(())
+    }
+
+    async fn write(&mut self, point_type: PointType, values: &[(u32, f64)]) -> igw::Result<usize> {
+        let mut written = 0;
+        let mut last_error = None;
+        for &(internal_id, value) in values {
+            let point_id = PointType::from_internal_id(internal_id).1;
+            let outputs = match point_type {
+                PointType::Control => &self.controls,
+                _ => &self.adjustments,
+            };
+            let Some(output) = outputs.get(&point_id).copied() else {
+                last_error = Some(GatewayError::PointNotFound(format!(
+                    "{}{}",
+                    point_type.as_str(),
+                    point_id
+                )));
+                continue;
+            };
+            match self.operate(output, value).await {
+                Ok(()) => written += 1,
+                Err(e) => {
+                    let e = self.fail(e);
+                    if self.stream.is_none() {
+                        return Err(e);
+                    }
+                    last_error = Some(e);
+                },
+            }
+        }
+        self.diagnostics.write_count += written as u64;
+        match last_error {
+            Some(e) if written == 0 => Err(e),
+            _ => Ok(written),
+        }
+    }
+}
+
+#[async_trait]
+impl ChannelRuntime for Dnp3Runtime {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn protocol(&self) -> &str {
+        PROTOCOL
+    }
+
+    fn is_event_driven(&self) -> bool {
+        false
+    }
+
+    async fn connect(&mut self) -> igw::Result<()> {
+        self.diagnostics.connection_state = ConnectionState::Connecting;
+        let address = format!("{}:{}", self.config.host, self.config.port);
+        let timeout_ms = self.config.response_timeout.as_millis() as u64;
+        let connected =
+            tokio::time::timeout(self.config.response_timeout, TcpStream::connect(&address)).await;
+        let stream = match connected {
+            Ok(Ok(stream)) => stream,
+            Ok(Err(e)) => {
+                return Err(self.fail(GatewayError::Connection(format!("{}: {}", address, e))))
+            },
+            Err(_) => return Err(self.fail(GatewayError::ConnectionTimeout(timeout_ms))),
+        };
+        let _ = stream.set_nodelay(true);
+        self.stream = Some(stream);
+        self.reader.clear();
+        self.reassembler.reset();
+        self.diagnostics.connection_state = ConnectionState::Connected;
+        info!("Ch{} DNP3 connected to {}", self.id, address);
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> igw::Result<()> {
+        if let Some(mut stream) = self.stream.take() {
+            let _ = stream.shutdown().await;
+        }
+        self.diagnostics.connection_state = ConnectionState::Disconnected;
+        Ok(())
+    }
+
+    async fn poll_once(&mut self) -> PollResult {
+        if self.stream.is_none() {
+            return PollResult::failed(vec![PointFailure::new(0, "not connected")]);
+        }
+        let seq = self.next_app_seq();
+        let responses = match self.request(codec::integrity_poll(seq)).await {
+            Ok(responses) => responses,
+            Err(e) => {
+                let e = self.fail(e);
+                return PollResult::failed(vec![PointFailure::with_error(0, e.to_string())]);
+            },
+        };
+
+        let mut batch = DataBatch::with_capacity(self.inputs.len());
+        let mut failures = Vec::new();
+        let unsolicited = std::mem::take(&mut self.unsolicited);
+        self.collect(&unsolicited, &mut batch);
+        for response in &responses {
+            match codec::parse_objects(&response.objects) {
+                Ok(values) => self.collect(&values, &mut batch),
+                Err(e) => {
+                    self.diagnostics.error_count += 1;
+                    self.diagnostics.last_error = Some(e.to_string());
+                    failures.push(PointFailure::with_error(0, e.to_string()));
+                },
+            }
+        }
+        self.diagnostics.read_count += 1;
+        PollResult::partial(batch, failures)
+    }
+
+    async fn write_control(&mut self, commands: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Control, commands).await
+    }
+
+    async fn write_adjustment(&mut self, adjustments: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Adjustment, adjustments).await
+    }
+
+    fn subscribe(&self) -> Option<DataEventReceiver> {
+        None
+    }
+
+    async fn start_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn stop_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn diagnostics(&self) -> igw::Result<Diagnostics> {
+        Ok(self.diagnostics.clone())
+    }
+}
+
+// ============================================================================
+// Plugin descriptor
+// ============================================================================
+
+fn parameters() -> Vec<ParameterMetadata> {
+    vec![
+        ParameterMetadata::required(
+            "host",
+            "Host",
+            "Outstation IP address",
+            ParameterType::String,
+        ),
+        ParameterMetadata::optional(
+            "port",
+            "Port",
+            "Outstation TCP port",
+            ParameterType::Integer,
+            json!(DEFAULT_PORT),
+        ),
+        ParameterMetadata::optional(
+            "local_address",
+            "Master Address",
+            "Link address of this master",
+            ParameterType::Integer,
+            json!(DEFAULT_LOCAL_ADDRESS),
+        ),
+        ParameterMetadata::optional(
+            "remote_address",
+            "Outstation Address",
+            "Link address of the outstation",
+            ParameterType::Integer,
+            json!(DEFAULT_REMOTE_ADDRESS),
+        ),
+        ParameterMetadata::optional(
+            "response_timeout_ms",
+            "Response Timeout (ms)",
+            "Time to wait for a complete response",
+            ParameterType::Integer,
+            json!(DEFAULT_RESPONSE_TIMEOUT_MS),
+        ),
+        ParameterMetadata::optional(
+            "select_before_operate",
+            "Select Before Operate",
+            "Use SELECT/OPERATE instead of DIRECT_OPERATE for commands",
+            ParameterType::Boolean,
+            json!(false),
+        ),
+        ParameterMetadata::optional(
+            "poll_interval_ms",
+            "Poll Interval (ms)",
+            "Integrity poll interval",
+            ParameterType::Integer,
+            json!(1000),
+        ),
+    ]
+}
+
+crate::protocol_plugin! {
+    /// DNP3 master over TCP (in-tree runtime)
+    pub struct Dnp3Plugin {
+        name: PROTOCOL,
+        aliases: &["dnp3", "dnp"],
+        display_name: "DNP3 (TCP)",
+        description: "DNP3 master polling an outstation over TCP/IP",
+        parameters: parameters(),
+        mapping_columns: &[
+            MappingColumn::integer("index", "DNP3 point index")
+                .required()
+                .range(0, 65535),
+            MappingColumn::string("object", "Object type, defaults by point type").choices(&[
+                "analog_input",
+                "counter",
+                "analog_output_status",
+                "binary_input",
+                "double_bit_input",
+                "binary_output_status",
+                "crob",
+                "analog_output",
+            ]),
+            MappingColumn::string("control_code", "CROB operation")
+                .choices(&["latch", "pulse", "trip_close"])
+                .default_value("latch"),
+            MappingColumn::integer("on_time_ms", "CROB on time").default_value("1000"),
+            MappingColumn::integer("off_time_ms", "CROB off time").default_value("0"),
+            MappingColumn::integer("variation", "Analog output variation (1=i32, 2=i16, 3=f32, 4=f64)")
+                .range(1, 4)
+                .default_value("3"),
+        ],
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn point<T: serde::de::DeserializeOwned>(point_id: u32, mapping: JsonValue) -> T {
+        serde_json::from_value(json!({
+            "point_id": point_id,
+            "signal_name": format!("p{}", point_id),
+            "protocol_mappings": mapping.to_string(),
+        }))
+        .unwrap()
+    }
+
+    fn runtime_config(port: u16) -> RuntimeChannelConfig {
+        let mut config = RuntimeChannelConfig::from_base(
+            serde_json::from_value(json!({
+                "id": 7,
+                "name": "outstation",
+                "protocol": PROTOCOL,
+                "parameters": {"host": "127.0.0.1", "port": port, "response_timeout_ms": 2000},
+            }))
+            .unwrap(),
+        );
+        config.telemetry_points = vec![point(1, json!({"index": 3}))];
+        config.signal_points = vec![
+            point(1, json!({"index": "0", "object": "binary_input"})),
+            point(2, json!({"index": 1, "object": "crob"})),
+        ];
+        config.control_points = vec![point(1, json!({"index": 5, "control_code": "pulse"}))];
+        config
+    }
+
+    #[test]
+    fn test_binding_defaults_and_validation() {
+        assert_eq!(
+            parse_binding(PointType::Telemetry, &json!({"index": 3})),
+            Ok(Binding::Input(ObjectKind::AnalogInput, 3))
+        );
+        assert_eq!(
+            parse_binding(
+                PointType::Adjustment,
+                &json!({"index": 2, "variation": "1"})
+            ),
+            Ok(Binding::Output(Output::Analog {
+                index: 2,
+                variation: 1
+            }))
+        );
+        assert!(parse_binding(PointType::Signal, &json!({})).is_err());
+        assert!(parse_binding(PointType::Signal, &json!({"index": 70000})).is_err());
+        assert!(parse_binding(PointType::Signal, &json!({"index": 1, "object": "crob"})).is_err());
+    }
+
+    /// Outstation reading one request fragment per call
+    struct Outstation {
+        stream: TcpStream,
+        reader: FrameReader,
+        reassembler: Reassembler,
+        transport_seq: u8,
+    }
+
+    impl Outstation {
+        async fn request(&mut self) -> Vec<u8> {
+            let mut buf = [0u8; 512];
+            loop {
+                while let Some(frame) = self.reader.next_frame() {
+                    let frame = frame.unwrap();
+                    assert_eq!((frame.destination, frame.source), (1024, 1));
+                    if let Some(fragment) = self.reassembler.push(&frame.data) {
+                        return fragment;
+                    }
+                }
+                let n = self.stream.read(&mut buf).await.unwrap();
+                assert!(n > 0, "master closed the connection");
+                self.reader.push(&buf[..n]);
+            }
+        }
+
+        async fn respond(&mut self, control: u8, function: u8, iin: u16, objects: &[u8]) {
+            let mut fragment = vec![control, function];
+            fragment.extend_from_slice(&iin.to_le_bytes());
+            fragment.extend_from_slice(objects);
+            for segment in codec::segment(&fragment, &mut self.transport_seq) {
+                let frame = codec::encode_frame(0x44, 1, 1024, &segment);
+                self.stream.write_all(&frame).await.unwrap();
+            }
+        }
+    }
+
+    async fn outstation(listener: &TcpListener) -> Outstation {
+        let (stream, _) = listener.accept().await.unwrap();
+        Outstation {
+            stream,
+            reader: FrameReader::new(),
+            reassembler: Reassembler::default(),
+            transport_seq: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_poll_and_operate_against_outstation() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut master = Dnp3Runtime::from_runtime_config(&runtime_config(port)).unwrap();
+        // The CROB mapping on a signal point is rejected, the rest is kept
+        assert_eq!(master.inputs.len(), 2);
+
+        let server = tokio::spawn(async move {
+            let mut os = outstation(&listener).await;
+
+            // Integrity poll: restart flag set, binary input 0 ON, analog 3 = 21.5
+            let request = os.request().await;
+            assert_eq!(request[1], function::READ);
+            let seq = request[0] & 0x0F;
+            let mut objects = vec![1, 2, 0x00, 0, 0, 0x81, 30, 5, 0x00, 3, 3, 0x01];
+            objects.extend_from_slice(&21.5f32.to_le_bytes());
+            os.respond(
+                0xC0 | codec::APP_CON | seq,
+                function::RESPONSE,
+                0x0080,
+                &objects,
+            )
+            .await;
+            let confirm = os.request().await;
+            assert_eq!(confirm, codec::confirm(seq, false));
+
+            // IIN1.7 clear
+            let write = os.request().await;
+            assert_eq!(&write[1..], &[function::WRITE, 80, 1, 0x00, 7, 7, 0x00]);
+            os.respond(0xC0 | (write[0] & 0x0F), function::RESPONSE, 0, &[])
+                .await;
+
+            // Direct operate of a pulse CROB on index 5, echoed back
+            let operate = os.request().await;
+            assert_eq!(operate[1], function::DIRECT_OPERATE);
+            assert_eq!(operate[7..10], [5, 0, codec::control_code::PULSE_ON]);
+            os.respond(
+                0xC0 | (operate[0] & 0x0F),
+                function::RESPONSE,
+                0,
+                &operate[2..],
+            )
+            .await;
+        });
+
+        master.connect().await.unwrap();
+        let result = master.poll_once().await;
+        assert!(!result.has_failures());
+        let points: HashMap<u32, f64> = result
+            .data
+            .iter()
+            .map(|p| (p.id, p.value.as_f64().unwrap()))
+            .collect();
+        assert_eq!(points[&PointType::Telemetry.to_internal_id(1)], 21.5);
+        assert_eq!(points[&PointType::Signal.to_internal_id(1)], 1.0);
+
+        let written = master
+            .write_control(&[(PointType::Control.to_internal_id(1), 1.0)])
+            .await
+            .unwrap();
+        assert_eq!(written, 1);
+        server.await.unwrap();
+
+        // The outstation hung up: the next poll fails and drops the stream
+        let result = master.poll_once().await;
+        assert!(result.has_failures());
+        assert!(master.stream.is_none());
+    }
+}
@@ -0,0 +1,802 @@
+//! DNP3 link, transport and application layer encoding
+//!
+//! Only what a polling master needs: FT3 link frames carrying unconfirmed
+//! user data, single-byte transport headers, and the application objects of
+//! integrity polls, binary/analog inputs and outputs, counters and their
+//! events, CROB and analog output commands.
+
+use common::bytes::checksum::CRC16_DNP;
+
+use crate::core::protocols::{error, CodecError};
+
+/// Link frame start octets
+pub const START: [u8; 2] = [0x05, 0x64];
+
+/// Link header length on the wire (start, length, control, addresses, CRC)
+pub const HEADER_LEN: usize = 10;
+
+/// User data octets per link frame
+pub const MAX_FRAME_DATA: usize = 250;
+
+/// User data octets per CRC block
+const BLOCK_LEN: usize = 16;
+
+/// Link control: master to outstation, primary, unconfirmed user data
+pub const LINK_CONTROL_MASTER: u8 = 0xC4;
+
+/// Link function code of (unconfirmed) user data
+const LINK_FUNC_UNCONFIRMED_USER_DATA: u8 = 0x04;
+/// Link function code of confirmed user data
+const LINK_FUNC_CONFIRMED_USER_DATA: u8 = 0x03;
+
+/// Transport header bits
+pub const TRANSPORT_FIN: u8 = 0x80;
+pub const TRANSPORT_FIR: u8 = 0x40;
+
+/// Application control bits
+pub const APP_FIR: u8 = 0x80;
+pub const APP_FIN: u8 = 0x40;
+pub const APP_CON: u8 = 0x20;
+pub const APP_UNS: u8 = 0x10;
+
+/// Application function codes
+pub mod function {
+    pub const CONFIRM: u8 = 0x00;
+    pub const READ: u8 = 0x01;
+    pub const WRITE: u8 = 0x02;
+    pub const SELECT: u8 = 0x03;
+    pub const OPERATE: u8 = 0x04;
+    pub const DIRECT_OPERATE: u8 = 0x05;
+    pub const RESPONSE: u8 = 0x81;
+    pub const UNSOLICITED_RESPONSE: u8 = 0x82;
+}
+
+/// IIN1.7: the outstation restarted and waits for the master to clear it
+pub const IIN_DEVICE_RESTART: u16 = 0x0080;
+
+// ============================================================================
+// Link layer
+// ============================================================================
+
+fn push_crc(out: &mut Vec<u8>, data: &[u8]) {
+    out.extend_from_slice(&CRC16_DNP.checksum(data).to_le_bytes());
+}
+
+/// Decoded link frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkFrame {
+    pub control: u8,
+    pub destination: u16,
+    pub source: u16,
+    pub data: Vec<u8>,
+}
+
+impl LinkFrame {
+    /// Whether the frame carries transport segments
+    pub fn is_user_data(&self) -> bool {
+        let function = self.control & 0x0F;
+        self.control & 0x40 != 0
+            && (function == LINK_FUNC_UNCONFIRMED_USER_DATA
+                || function == LINK_FUNC_CONFIRMED_USER_DATA)
+    }
+}
+
+/// Encode a link frame (`data` at most [`MAX_FRAME_DATA`] octets)
+pub fn encode_frame(control: u8, destination: u16, source: u16, data: &[u8]) -> Vec<u8> {
+    debug_assert!(data.len() <= MAX_FRAME_DATA);
+    let blocks = data.len().div_ceil(BLOCK_LEN);
+    let mut out = Vec::with_capacity(HEADER_LEN + data.len() + blocks * 2);
+    out.extend_from_slice(&START);
+    out.push((5 + data.len()) as u8);
+    out.push(control);
+    out.extend_from_slice(&destination.to_le_bytes());
+    out.extend_from_slice(&source.to_le_bytes());
+    let header = out.clone();
+    push_crc(&mut out, &header);
+    for block in data.chunks(BLOCK_LEN) {
+        out.extend_from_slice(block);
+        push_crc(&mut out, block);
+    }
+    out
+}
+
+/// Incremental link frame parser over a byte stream
+#[derive(Debug, Default)]
+pub struct FrameReader {
+    buffer: Vec<u8>,
+}
+
+impl FrameReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    /// Next complete frame; `None` until more bytes are needed
+    ///
+    /// Garbage before a start sequence and frames with a bad header CRC are
+    /// skipped; a bad data block CRC drops the frame and is reported.
+    pub fn next_frame(&mut self) -> Option<Result<LinkFrame, CodecError>> {
+        loop {
+            let start = self.buffer.windows(2).position(|w| w == START);
+            match start {
+                Some(0) => {},
+                Some(offset) => {
+                    self.buffer.drain(..offset);
+                },
+                None => {
+                    // Keep a trailing 0x05, it may start the next frame
+                    let keep = usize::from(self.buffer.last() == Some(&START[0]));
+                    let len = self.buffer.len();
+                    self.buffer.drain(..len - keep);
+                    return None;
+                },
+            }
+            if self.buffer.len() < HEADER_LEN {
+                return None;
+            }
+            let header = &self.buffer[..8];
+            let crc = u16::from_le_bytes([self.buffer[8], self.buffer[9]]);
+            let length = self.buffer[2] as usize;
+            if CRC16_DNP.checksum(header) != crc || length < 5 {
+                self.buffer.drain(..1);
+                continue;
+            }
+
+            let data_len = length - 5;
+            let total = HEADER_LEN + data_len + data_len.div_ceil(BLOCK_LEN) * 2;
+            if self.buffer.len() < total {
+                return None;
+            }
+            let frame: Vec<u8> = self.buffer.drain(..total).collect();
+            return Some(decode_body(&frame, data_len));
+        }
+    }
+}
+
+fn decode_body(frame: &[u8], data_len: usize) -> Result<LinkFrame, CodecError> {
+    let mut data = Vec::with_capacity(data_len);
+    let mut rest = &frame[HEADER_LEN..];
+    let mut remaining = data_len;
+    while remaining > 0 {
+        let n = remaining.min(BLOCK_LEN);
+        let (block, tail) = rest.split_at(n);
+        let crc = u16::from_le_bytes([tail[0], tail[1]]);
+        if CRC16_DNP.checksum(block) != crc {
+            return Err(error("link frame data CRC mismatch"));
+        }
+        data.extend_from_slice(block);
+        rest = &tail[2..];
+        remaining -= n;
+    }
+    Ok(LinkFrame {
+        control: frame[3],
+        destination: u16::from_le_bytes([frame[4], frame[5]]),
+        source: u16::from_le_bytes([frame[6], frame[7]]),
+        data,
+    })
+}
+
+// ============================================================================
+// Transport layer
+// ============================================================================
+
+/// Split an application fragment into transport segments
+///
+/// `seq` is the transport sequence of the first segment; the next free one
+/// is written back.
+pub fn segment(fragment: &[u8], seq: &mut u8) -> Vec<Vec<u8>> {
+    let chunks: Vec<&[u8]> = fragment.chunks(MAX_FRAME_DATA - 1).collect();
+    let last = chunks.len().saturating_sub(1);
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut header = *seq & 0x3F;
+            if i == 0 {
+                header |= TRANSPORT_FIR;
+            }
+            if i == last {
+                header |= TRANSPORT_FIN;
+            }
+            *seq = (*seq + 1) & 0x3F;
+            let mut out = Vec::with_capacity(chunk.len() + 1);
+            out.push(header);
+            out.extend_from_slice(chunk);
+            out
+        })
+        .collect()
+}
+
+/// Rebuilds application fragments from transport segments
+#[derive(Debug, Default)]
+pub struct Reassembler {
+    buffer: Vec<u8>,
+    active: bool,
+}
+
+impl Reassembler {
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.active = false;
+    }
+
+    /// Add a segment; returns the fragment once its last segment arrived
+    pub fn push(&mut self, segment: &[u8]) -> Option<Vec<u8>> {
+        let (&header, payload) = segment.split_first()?;
+        if header & TRANSPORT_FIR != 0 {
+            self.buffer.clear();
+            self.active = true;
+        } else if !self.active {
+            return None;
+        }
+        self.buffer.extend_from_slice(payload);
+        if header & TRANSPORT_FIN != 0 {
+            self.active = false;
+            return Some(std::mem::take(&mut self.buffer));
+        }
+        None
+    }
+}
+
+// ============================================================================
+// Application layer: requests
+// ============================================================================
+
+fn request_header(seq: u8, function: u8) -> Vec<u8> {
+    vec![APP_FIR | APP_FIN | (seq & 0x0F), function]
+}
+
+/// Integrity poll: event classes 1-3, then class 0 (all static data)
+pub fn integrity_poll(seq: u8) -> Vec<u8> {
+    let mut out = request_header(seq, function::READ);
+    for variation in [2, 3, 4, 1] {
+        out.extend_from_slice(&[60, variation, 0x06]);
+    }
+    out
+}
+
+/// Clear IIN1.7 (device restart) by writing g80v1 index 7
+pub fn clear_restart(seq: u8) -> Vec<u8> {
+    let mut out = request_header(seq, function::WRITE);
+    out.extend_from_slice(&[80, 1, 0x00, 7, 7, 0x00]);
+    out
+}
+
+/// Application confirm of a response fragment
+pub fn confirm(seq: u8, unsolicited: bool) -> Vec<u8> {
+    let mut control = APP_FIR | APP_FIN | (seq & 0x0F);
+    if unsolicited {
+        control |= APP_UNS;
+    }
+    vec![control, function::CONFIRM]
+}
+
+/// CROB operation (g12v1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crob {
+    /// Control code (operation type, trip/close, queue/clear bits)
+    pub code: u8,
+    pub count: u8,
+    pub on_time_ms: u32,
+    pub off_time_ms: u32,
+}
+
+/// CROB control codes
+pub mod control_code {
+    pub const PULSE_ON: u8 = 0x01;
+    pub const PULSE_OFF: u8 = 0x02;
+    pub const LATCH_ON: u8 = 0x03;
+    pub const LATCH_OFF: u8 = 0x04;
+    pub const CLOSE_PULSE_ON: u8 = 0x41;
+    pub const TRIP_PULSE_ON: u8 = 0x81;
+}
+
+/// Select/operate/direct-operate request for one CROB (qualifier 0x28)
+pub fn crob_request(seq: u8, function: u8, index: u16, crob: Crob) -> Vec<u8> {
+    let mut out = request_header(seq, function);
+    out.extend_from_slice(&[12, 1, 0x28, 1, 0]);
+    out.extend_from_slice(&index.to_le_bytes());
+    out.push(crob.code);
+    out.push(crob.count);
+    out.extend_from_slice(&crob.on_time_ms.to_le_bytes());
+    out.extend_from_slice(&crob.off_time_ms.to_le_bytes());
+    out.push(0);
+    out
+}
+
+/// Select/operate/direct-operate request for one analog output (g41, qualifier 0x28)
+///
+/// Variation 1 = int32, 2 = int16, 3 = float32, 4 = float64.
+pub fn analog_output_request(
+    seq: u8,
+    function: u8,
+    index: u16,
+    variation: u8,
+    value: f64,
+) -> Result<Vec<u8>, CodecError> {
+    let mut out = request_header(seq, function);
+    out.extend_from_slice(&[41, variation, 0x28, 1, 0]);
+    out.extend_from_slice(&index.to_le_bytes());
+    match variation {
+        1 => out.extend_from_slice(&(value.round() as i32).to_le_bytes()),
+        2 => out.extend_from_slice(&(value.round() as i16).to_le_bytes()),
+        3 => out.extend_from_slice(&(value as f32).to_le_bytes()),
+        4 => out.extend_from_slice(&value.to_le_bytes()),
+        other => return Err(error(format!("unsupported g41 variation {}", other))),
+    }
+    out.push(0);
+    Ok(out)
+}
+
+// ============================================================================
+// Application layer: responses
+// ============================================================================
+
+/// Parsed response fragment header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub control: u8,
+    pub function: u8,
+    /// IIN1 in the low byte, IIN2 in the high byte
+    pub iin: u16,
+    pub objects: Vec<u8>,
+}
+
+impl Response {
+    pub fn parse(fragment: &[u8]) -> Result<Self, CodecError> {
+        if fragment.len() < 4 {
+            return Err(error(format!(
+                "response fragment too short ({} octets)",
+                fragment.len()
+            )));
+        }
+        Ok(Self {
+            control: fragment[0],
+            function: fragment[1],
+            iin: u16::from_le_bytes([fragment[2], fragment[3]]),
+            objects: fragment[4..].to_vec(),
+        })
+    }
+
+    pub fn seq(&self) -> u8 {
+        self.control & 0x0F
+    }
+
+    pub fn is_final(&self) -> bool {
+        self.control & APP_FIN != 0
+    }
+
+    pub fn needs_confirm(&self) -> bool {
+        self.control & APP_CON != 0
+    }
+
+    pub fn is_unsolicited(&self) -> bool {
+        self.function == function::UNSOLICITED_RESPONSE
+    }
+}
+
+/// Point class an object belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ObjectKind {
+    /// g1/g2
+    BinaryInput,
+    /// g3/g4
+    DoubleBitInput,
+    /// g10/g11
+    BinaryOutputStatus,
+    /// g20-g23
+    Counter,
+    /// g30/g32
+    AnalogInput,
+    /// g40/g42
+    AnalogOutputStatus,
+    /// g12 (command echo)
+    Crob,
+    /// g41 (command echo)
+    AnalogOutput,
+}
+
+/// One decoded object
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ObjectValue {
+    pub kind: ObjectKind,
+    pub index: u32,
+    pub value: f64,
+    /// ONLINE flag (true for objects without flags)
+    pub online: bool,
+    /// Command status (g12/g41 echoes)
+    pub status: Option<u8>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Encoding {
+    StateBit,
+    DoubleBit,
+    U16,
+    U32,
+    I16,
+    I32,
+    F32,
+    F64,
+    Crob,
+    Skip,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    kind: Option<ObjectKind>,
+    size: usize,
+    /// Starts with a flags octet
+    flags: bool,
+    encoding: Encoding,
+}
+
+const fn layout_of(kind: ObjectKind, size: usize, flags: bool, encoding: Encoding) -> Layout {
+    Layout {
+        kind: Some(kind),
+        size,
+        flags,
+        encoding,
+    }
+}
+
+/// Fixed-size object layouts; values follow the flags octet, times follow the value
+fn layout(group: u8, variation: u8) -> Option<Layout> {
+    use Encoding::*;
+    use ObjectKind::*;
+    Some(match (group, variation) {
+        (1, 2) | (2, 1) => layout_of(BinaryInput, 1, true, StateBit),
+        (2, 2) => layout_of(BinaryInput, 7, true, StateBit),
+        (2, 3) => layout_of(BinaryInput, 3, true, StateBit),
+        (3, 2) | (4, 1) => layout_of(DoubleBitInput, 1, true, DoubleBit),
+        (4, 2) => layout_of(DoubleBitInput, 7, true, DoubleBit),
+        (4, 3) => layout_of(DoubleBitInput, 3, true, DoubleBit),
+        (10, 2) | (11, 1) => layout_of(BinaryOutputStatus, 1, true, StateBit),
+        (11, 2) => layout_of(BinaryOutputStatus, 7, true, StateBit),
+        (12, 1) => layout_of(ObjectKind::Crob, 11, false, Encoding::Crob),
+        (20..=23, 1) => layout_of(Counter, 5, true, U32),
+        (20..=23, 2) => layout_of(Counter, 3, true, U16),
+        (20, 5) | (21, 9) => layout_of(Counter, 4, false, U32),
+        (20, 6) | (21, 10) => layout_of(Counter, 2, false, U16),
+        (21..=23, 5) => layout_of(Counter, 11, true, U32),
+        (21..=23, 6) => layout_of(Counter, 9, true, U16),
+        (30 | 32, 1) => layout_of(AnalogInput, 5, true, I32),
+        (30 | 32, 2) => layout_of(AnalogInput, 3, true, I16),
+        (30, 3) => layout_of(AnalogInput, 4, false, I32),
+        (30, 4) => layout_of(AnalogInput, 2, false, I16),
+        (30 | 32, 5) => layout_of(AnalogInput, 5, true, F32),
+        (30 | 32, 6) => layout_of(AnalogInput, 9, true, F64),
+        (32, 3) => layout_of(AnalogInput, 11, true, I32),
+        (32, 4) => layout_of(AnalogInput, 9, true, I16),
+        (32, 7) => layout_of(AnalogInput, 11, true, F32),
+        (32, 8) => layout_of(AnalogInput, 15, true, F64),
+        (40 | 42, 1) => layout_of(AnalogOutputStatus, 5, true, I32),
+        (40 | 42, 2) => layout_of(AnalogOutputStatus, 3, true, I16),
+        (40, 3) | (42, 5) => layout_of(AnalogOutputStatus, 5, true, F32),
+        (40, 4) | (42, 6) => layout_of(AnalogOutputStatus, 9, true, F64),
+        (42, 3) => layout_of(AnalogOutputStatus, 11, true, I32),
+        (42, 4) => layout_of(AnalogOutputStatus, 9, true, I16),
+        (42, 7) => layout_of(AnalogOutputStatus, 11, true, F32),
+        (42, 8) => layout_of(AnalogOutputStatus, 15, true, F64),
+        (41, 1) => layout_of(AnalogOutput, 5, false, I32),
+        (41, 2) => layout_of(AnalogOutput, 3, false, I16),
+        (41, 3) => layout_of(AnalogOutput, 5, false, F32),
+        (41, 4) => layout_of(AnalogOutput, 9, false, F64),
+        // Time, common time of occurrence and delay: no point data
+        (50, 1) | (51, 1) | (51, 2) => Layout {
+            kind: None,
+            size: 6,
+            flags: false,
+            encoding: Skip,
+        },
+        (52, 1) | (52, 2) => Layout {
+            kind: None,
+            size: 2,
+            flags: false,
+            encoding: Skip,
+        },
+        _ => return None,
+    })
+}
+
+/// Packed objects: (kind, bits per object)
+fn packed(group: u8, variation: u8) -> Option<(Option<ObjectKind>, usize)> {
+    match (group, variation) {
+        (1, 1) => Some((Some(ObjectKind::BinaryInput), 1)),
+        (3, 1) => Some((Some(ObjectKind::DoubleBitInput), 2)),
+        (10, 1) => Some((Some(ObjectKind::BinaryOutputStatus), 1)),
+        (80, 1) => Some((None, 1)),
+        _ => None,
+    }
+}
+
+/// Double-bit state to a signal value: determined ON/OFF map to 1/0
+fn double_bit(state: u8) -> (f64, bool) {
+    match state & 0x03 {
+        0b10 => (1.0, true),
+        0b01 => (0.0, true),
+        other => (other as f64, false),
+    }
+}
+
+fn decode(layout: &Layout, bytes: &[u8]) -> (f64, bool, Option<u8>) {
+    let flags = if layout.flags { bytes[0] } else { 0x01 };
+    let online = flags & 0x01 != 0;
+    let v = if layout.flags { &bytes[1..] } else { bytes };
+    let value = match layout.encoding {
+        Encoding::StateBit => f64::from((flags >> 7) & 1),
+        Encoding::DoubleBit => {
+            let (value, determined) = double_bit(flags >> 6);
+            return (value, online && determined, None);
+        },
+        Encoding::U16 => u16::from_le_bytes([v[0], v[1]]) as f64,
+        Encoding::U32 => u32::from_le_bytes([v[0], v[1], v[2], v[3]]) as f64,
+        Encoding::I16 => i16::from_le_bytes([v[0], v[1]]) as f64,
+        Encoding::I32 => i32::from_le_bytes([v[0], v[1], v[2], v[3]]) as f64,
+        Encoding::F32 => f32::from_le_bytes([v[0], v[1], v[2], v[3]]) as f64,
+        Encoding::F64 => f64::from_le_bytes([v[0], v[1], v[2], v[3], v[4], v[5], v[6], v[7]]),
+        Encoding::Crob => {
+            return (f64::from(bytes[0]), true, Some(bytes[10]));
+        },
+        Encoding::Skip => 0.0,
+    };
+    let status =
+        matches!(layout.kind, Some(ObjectKind::AnalogOutput)).then(|| bytes[layout.size - 1]);
+    (value, online, status)
+}
+
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], CodecError> {
+        let end = self.pos + n;
+        if end > self.data.len() {
+            return Err(error(format!(
+                "object data truncated at offset {} (need {} octets)",
+                self.pos, n
+            )));
+        }
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn uint(&mut self, width: usize) -> Result<u32, CodecError> {
+        let bytes = self.take(width)?;
+        Ok(bytes
+            .iter()
+            .rev()
+            .fold(0u32, |acc, b| (acc << 8) | u32::from(*b)))
+    }
+}
+
+/// Decode the object headers and values of a response
+pub fn parse_objects(data: &[u8]) -> Result<Vec<ObjectValue>, CodecError> {
+    let mut cursor = Cursor { data, pos: 0 };
+    let mut values = Vec::new();
+
+    while cursor.pos < data.len() {
+        let header = cursor.take(3)?;
+        let (group, variation, qualifier) = (header[0], header[1], header[2]);
+        let prefix_code = (qualifier >> 4) & 0x07;
+        let range_code = qualifier & 0x0F;
+
+        // Range: explicit start/stop or a count of objects
+        let (start, count) = match range_code {
+            0x00..=0x02 => {
+                let width = 1 << range_code;
+                let start = cursor.uint(width)?;
+                let stop = cursor.uint(width)?;
+                if stop < start {
+                    return Err(error(format!(
+                        "g{}v{}: stop {} before start {}",
+                        group, variation, stop, start
+                    )));
+                }
+                (Some(start), (stop - start) as usize + 1)
+            },
+            0x06 => (None, 0),
+            0x07..=0x09 => (None, cursor.uint(1 << (range_code - 7))? as usize),
+            _ => {
+                return Err(error(format!(
+                    "g{}v{}: unsupported qualifier 0x{:02X}",
+                    group, variation, qualifier
+                )))
+            },
+        };
+        let prefix_width = match prefix_code {
+            0 => 0,
+            1..=3 => 1 << (prefix_code - 1),
+            _ => {
+                return Err(error(format!(
+                    "g{}v{}: unsupported qualifier 0x{:02X}",
+                    group, variation, qualifier
+                )))
+            },
+        };
+        let index_of =
+            |i: usize, prefix: Option<u32>| prefix.unwrap_or_else(|| start.unwrap_or(0) + i as u32);
+
+        if let Some((kind, bits)) = packed(group, variation) {
+            if prefix_width != 0 {
+                return Err(error(format!(
+                    "g{}v{}: index prefix on packed objects",
+                    group, variation
+                )));
+            }
+            let bytes = cursor.take((count * bits).div_ceil(8))?;
+            if let Some(kind) = kind {
+                let per_byte = 8 / bits;
+                for i in 0..count {
+                    let raw = (bytes[i / per_byte] >> ((i % per_byte) * bits)) & ((1 << bits) - 1);
+                    let (value, online) = if bits == 2 {
+                        double_bit(raw)
+                    } else {
+                        (f64::from(raw), true)
+                    };
+                    values.push(ObjectValue {
+                        kind,
+                        index: index_of(i, None),
+                        value,
+                        online,
+                        status: None,
+                    });
+                }
+            }
+            continue;
+        }
+
+        let Some(layout) = layout(group, variation) else {
+            return Err(error(format!(
+                "unsupported object g{}v{}",
+                group, variation
+            )));
+        };
+        for i in 0..count {
+            let prefix = if prefix_width > 0 {
+                Some(cursor.uint(prefix_width)?)
+            } else {
+                None
+            };
+            let bytes = cursor.take(layout.size)?;
+            if let Some(kind) = layout.kind {
+                let (value, online, status) = decode(&layout, bytes);
+                values.push(ObjectValue {
+                    kind,
+                    index: index_of(i, prefix),
+                    value,
+                    online,
+                    status,
+                });
+            }
+        }
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_header_crc() {
+        // Reset link states, destination 1, source 1024 (IEEE 1815 example)
+        let frame = encode_frame(0xC0, 1, 1024, &[]);
+        assert_eq!(
+            frame,
+            vec![0x05, 0x64, 0x05, 0xC0, 0x01, 0x00, 0x00, 0x04, 0xE9, 0x21]
+        );
+    }
+
+    #[test]
+    fn test_frame_round_trip_through_reader() {
+        let data: Vec<u8> = (0..40).collect();
+        let frame = encode_frame(LINK_CONTROL_MASTER, 1024, 1, &data);
+        // 10 header + 40 data + 3 block CRCs
+        assert_eq!(frame.len(), 10 + 40 + 6);
+
+        let mut reader = FrameReader::new();
+        reader.push(&[0xFF, 0x05]);
+        reader.push(&frame[..20]);
+        assert!(reader.next_frame().is_none());
+        reader.push(&frame[20..]);
+        let decoded = reader.next_frame().unwrap().unwrap();
+        assert_eq!(decoded.destination, 1024);
+        assert_eq!(decoded.source, 1);
+        assert_eq!(decoded.data, data);
+        assert!(decoded.is_user_data());
+        assert!(reader.next_frame().is_none());
+
+        let mut corrupt = frame.clone();
+        corrupt[12] ^= 0xFF;
+        reader.push(&corrupt);
+        assert!(reader.next_frame().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_segment_and_reassemble() {
+        let fragment: Vec<u8> = (0..600).map(|i| i as u8).collect();
+        let mut seq = 62;
+        let segments = segment(&fragment, &mut seq);
+        assert_eq!(segments.len(), 3);
+        assert_eq!(seq, 1);
+        assert_eq!(segments[0][0], TRANSPORT_FIR | 62);
+        assert_eq!(segments[2][0], TRANSPORT_FIN);
+
+        let mut reassembler = Reassembler::default();
+        assert!(reassembler.push(&segments[0]).is_none());
+        assert!(reassembler.push(&segments[1]).is_none());
+        assert_eq!(reassembler.push(&segments[2]).unwrap(), fragment);
+        // A continuation without a first segment is dropped
+        assert!(reassembler.push(&segments[2]).is_none());
+    }
+
+    #[test]
+    fn test_parse_static_objects() {
+        let mut objects = vec![
+            // g1v2 indices 0-1 (ON online, OFF online)
+            1, 2, 0x00, 0, 1, 0x81, 0x01, // g30v5 index 3 (float 12.5, online)
+            30, 5, 0x00, 3, 3, 0x01,
+        ];
+        objects.extend_from_slice(&12.5f32.to_le_bytes());
+        // g1v1 packed, indices 0-9, bits 0 and 9 set
+        objects.extend_from_slice(&[1, 1, 0x00, 0, 9, 0x01, 0x02]);
+        // g32v1 event, count 1 with 16-bit index 700, offline
+        objects.extend_from_slice(&[32, 1, 0x28, 1, 0, 0xBC, 0x02, 0x00]);
+        objects.extend_from_slice(&(-5i32).to_le_bytes());
+        // g3v2 double-bit DETERMINED_ON at index 4
+        objects.extend_from_slice(&[3, 2, 0x00, 4, 4, 0x81]);
+
+        let values = parse_objects(&objects).unwrap();
+        assert_eq!(values.len(), 2 + 1 + 10 + 1 + 1);
+        assert_eq!(values[0].value, 1.0);
+        assert_eq!(values[1].value, 0.0);
+        assert_eq!(values[2].kind, ObjectKind::AnalogInput);
+        assert_eq!((values[2].index, values[2].value), (3, 12.5));
+        assert_eq!(values[3].value, 1.0);
+        assert_eq!((values[12].index, values[12].value), (9, 1.0));
+        let event = values[13];
+        assert_eq!((event.index, event.value, event.online), (700, -5.0, false));
+        assert_eq!(values[14].kind, ObjectKind::DoubleBitInput);
+        assert_eq!(values[14].value, 1.0);
+
+        assert!(parse_objects(&[99, 1, 0x00, 0, 0]).is_err());
+        assert!(parse_objects(&[30, 1, 0x00, 0, 0, 0x01]).is_err());
+    }
+
+    #[test]
+    fn test_command_requests() {
+        let crob = Crob {
+            code: control_code::LATCH_ON,
+            count: 1,
+            on_time_ms: 0,
+            off_time_ms: 0,
+        };
+        let request = crob_request(3, function::DIRECT_OPERATE, 0x0102, crob);
+        assert_eq!(&request[..9], &[0xC3, 0x05, 12, 1, 0x28, 1, 0, 0x02, 0x01]);
+        assert_eq!(request.len(), 2 + 3 + 2 + 2 + 11);
+
+        // The echo parses back with its status
+        let echo = parse_objects(&request[2..]).unwrap();
+        assert_eq!(echo[0].kind, ObjectKind::Crob);
+        assert_eq!(echo[0].status, Some(0));
+
+        let request = analog_output_request(0, function::SELECT, 7, 3, 42.5).unwrap();
+        let echo = parse_objects(&request[2..]).unwrap();
+        assert_eq!((echo[0].index, echo[0].value), (7, 42.5));
+        assert_eq!(echo[0].status, Some(0));
+        assert!(analog_output_request(0, function::SELECT, 7, 9, 1.0).is_err());
+    }
+}
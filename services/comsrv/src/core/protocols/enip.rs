@@ -0,0 +1,1068 @@
+//! EtherNet/IP explicit-messaging client for Logix controllers
+//!
+//! Points name a controller tag symbolically in their `tag` mapping
+//! column, so a point table is a CSV of tag names:
+//! - `Tank_Level`, `Program:Main.Count`: controller or program tags
+//! - `Pump1.Status.Running`: a member of a UDT (any depth)
+//! - `Temps[3]`, `Line[2].Pump.Speed`: array elements and their members
+//! - `Status.3` (or the `bit` column): one bit of an integer tag
+//!
+//! Tags are read each poll with Read Tag requests bundled into Multiple
+//! Service Packets. Control and adjustment points write their tag with
+//! Write Tag, as the `data_type` of the mapping or the type the controller
+//! reported when the tag was read; bits are written with Read-Modify-Write
+//! so the other bits keep their value. Requests are unconnected messages,
+//! routed along `route_path` to the CPU (`1,0`: backplane, slot 0; empty
+//! for controllers addressed directly such as Micro800).
+//!
+//! ```yaml
+//! protocol: enip
+//! parameters:
+//!   host: 192.168.1.20
+//!   route_path: "1,0"
+//!   response_timeout_ms: 3000
+//! ```
+
+pub mod codec;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use igw::core::traits::{DataEventReceiver, Diagnostics, PointFailure, PollResult};
+use igw::gateway::ChannelRuntime;
+use igw::{ConnectionState, DataBatch, DataPoint, GatewayError};
+use serde_json::{json, Value as JsonValue};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{timeout_at, Instant};
+use tracing::{debug, info, warn};
+
+use self::codec::{command, status, DataType, Encapsulation, Reply, Request, TagPath};
+use crate::core::config::{Point, RuntimeChannelConfig};
+use crate::core::plugins::{MappingColumn, ParameterMetadata, ParameterType};
+use crate::core::protocols::record_failure;
+use crate::error::{ComSrvError, Result};
+use voltage_model::PointType;
+
+/// Protocol name stored in `channels.protocol`
+pub const PROTOCOL: &str = "enip";
+
+const DEFAULT_PORT: u16 = 44818;
+const DEFAULT_ROUTE: &str = "1,0";
+const DEFAULT_RESPONSE_TIMEOUT_MS: u64 = 3000;
+const DEFAULT_MAX_TAGS: u64 = 20;
+/// Room left in an unconnected message for Unconnected Send and packet
+/// headers
+const ROUTING_OVERHEAD: usize = 48;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Channel parameters of an EtherNet/IP channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnipConfig {
+    pub host: String,
+    pub port: u16,
+    /// Encoded route to the CPU; empty addresses the adapter itself
+    pub route: Vec<u8>,
+    pub route_text: String,
+    pub response_timeout: Duration,
+    /// Read Tag requests per Multiple Service Packet
+    pub max_tags: usize,
+}
+
+impl EnipConfig {
+    pub fn from_parameters(parameters: &HashMap<String, JsonValue>) -> Result<Self> {
+        let config_error = |message: String| ComSrvError::ConfigError(message);
+        let text = |key: &str| {
+            parameters.get(key).and_then(|v| match v {
+                JsonValue::String(s) => Some(s.trim().to_string()),
+                JsonValue::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+        };
+        let number = |key: &str, default: u64| -> Result<u64> {
+            match parameters.get(key) {
+                None | Some(JsonValue::Null) => Ok(default),
+                Some(JsonValue::String(s)) if s.trim().is_empty() => Ok(default),
+                Some(JsonValue::Number(n)) => n
+                    .as_u64()
+                    .ok_or_else(|| config_error(format!("'{}' must be a number", key))),
+                Some(JsonValue::String(s)) => s
+                    .trim()
+                    .parse()
+                    .map_err(|_| config_error(format!("'{}' must be a number", key))),
+                Some(_) => Err(config_error(format!("'{}' must be a number", key))),
+            }
+        };
+
+        let host = text("host")
+            .filter(|h| !h.is_empty())
+            .ok_or_else(|| config_error("EtherNet/IP requires 'host'".into()))?;
+        let port = number("port", u64::from(DEFAULT_PORT))?;
+        if !(1..=65535).contains(&port) {
+            return Err(config_error("'port' must be 1-65535".into()));
+        }
+        let route_text = text("route_path").unwrap_or_else(|| DEFAULT_ROUTE.to_string());
+        let route = codec::parse_route(&route_text).map_err(|e| config_error(e.to_string()))?;
+        let max_tags = number("max_tags_per_request", DEFAULT_MAX_TAGS)?;
+        if !(1..=100).contains(&max_tags) {
+            return Err(config_error("'max_tags_per_request' must be 1-100".into()));
+        }
+
+        Ok(Self {
+            host,
+            port: port as u16,
+            route,
+            route_text,
+            response_timeout: Duration::from_millis(
+                number("response_timeout_ms", DEFAULT_RESPONSE_TIMEOUT_MS)?.max(1),
+            ),
+            max_tags: max_tags as usize,
+        })
+    }
+}
+
+// ============================================================================
+// Point mapping
+// ============================================================================
+
+/// Point reading a tag
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Reading {
+    internal_id: u32,
+    bit: Option<u8>,
+}
+
+/// Tag a control or adjustment point writes
+#[derive(Debug, Clone, PartialEq)]
+struct Command {
+    tag: TagPath,
+    bit: Option<u8>,
+    data_type: Option<DataType>,
+}
+
+fn parse_point(point: &Point) -> std::result::Result<Command, String> {
+    let mapping: JsonValue = point
+        .protocol_mappings
+        .as_deref()
+        .and_then(|m| serde_json::from_str(m).ok())
+        .ok_or("missing EtherNet/IP mapping")?;
+    let text = |key: &str| {
+        mapping
+            .get(key)
+            .and_then(|v| match v {
+                JsonValue::String(s) => Some(s.trim().to_string()),
+                JsonValue::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .filter(|v| !v.is_empty())
+    };
+
+    let mut tag =
+        TagPath::parse(&text("tag").ok_or("missing 'tag'")?).map_err(|e| e.to_string())?;
+    let column_bit = text("bit")
+        .map(|b| {
+            b.parse::<u8>()
+                .ok()
+                .filter(|b| *b <= 63)
+                .ok_or("'bit' must be 0-63")
+        })
+        .transpose()?;
+    let bit = match (tag.bit.take(), column_bit) {
+        (Some(_), Some(_)) => return Err("bit given both in 'tag' and 'bit'".into()),
+        (bit, column) => bit.or(column),
+    };
+    let data_type = text("data_type")
+        .map(|t| DataType::parse(&t))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    if let (Some(_), Some(data_type)) = (bit, data_type) {
+        if !data_type.is_integer() {
+            return Err(format!(
+                "bit access needs an integer tag, not {}",
+                data_type.name()
+            ));
+        }
+    }
+    Ok(Command {
+        tag,
+        bit,
+        data_type,
+    })
+}
+
+// ============================================================================
+// Runtime
+// ============================================================================
+
+/// EtherNet/IP client of one controller
+pub struct EnipRuntime {
+    id: u32,
+    name: String,
+    config: EnipConfig,
+    /// Tags read each poll, in point table order
+    reads: Vec<(TagPath, Vec<Reading>)>,
+    controls: HashMap<u32, Command>,
+    adjustments: HashMap<u32, Command>,
+    stream: Option<TcpStream>,
+    session: u32,
+    sender_context: u64,
+    /// Types the controller reported for tags
+    types: HashMap<TagPath, DataType>,
+    /// Cleared when the target rejects Multiple Service Packets
+    multiple_service: bool,
+    diagnostics: Diagnostics,
+}
+
+impl EnipRuntime {
+    /// Build the runtime of an `enip` channel
+    pub fn from_runtime_config(runtime_config: &RuntimeChannelConfig) -> Result<Self> {
+        let channel_id = runtime_config.id();
+        let config = EnipConfig::from_parameters(&runtime_config.base.parameters)
+            .map_err(|e| ComSrvError::ConfigError(format!("Ch{}: {}", channel_id, e)))?;
+
+        let mut reads: Vec<(TagPath, Vec<Reading>)> = Vec::new();
+        let mut index: HashMap<TagPath, usize> = HashMap::new();
+        let mut controls = HashMap::new();
+        let mut adjustments = HashMap::new();
+        for (point_type, point) in runtime_config.points() {
+            let command = match parse_point(point) {
+                Ok(command) => command,
+                Err(e) => {
+                    warn!(
+                        "Ch{} {}{} skipped: {}",
+                        channel_id,
+                        point_type.as_str(),
+                        point.point_id,
+                        e
+                    );
+                    continue;
+                },
+            };
+            match point_type {
+                PointType::Telemetry | PointType::Signal => {
+                    let reading = Reading {
+                        internal_id: point_type.to_internal_id(point.point_id),
+                        bit: command.bit,
+                    };
+                    let slot = *index.entry(command.tag.clone()).or_insert_with(|| {
+                        reads.push((command.tag, Vec::new()));
+                        reads.len() - 1
+                    });
+                    reads[slot].1.push(reading);
+                },
+                PointType::Control => {
+                    controls.insert(point.point_id, command);
+                },
+                PointType::Adjustment => {
+                    adjustments.insert(point.point_id, command);
+                },
+            }
+        }
+        debug!(
+            "Ch{} EtherNet/IP: {} tags, {} controls, {} adjustments",
+            channel_id,
+            reads.len(),
+            controls.len(),
+            adjustments.len()
+        );
+
+        Ok(Self {
+            id: channel_id,
+            name: runtime_config.name().to_string(),
+            config,
+            reads,
+            controls,
+            adjustments,
+            stream: None,
+            session: 0,
+            sender_context: 0,
+            types: HashMap::new(),
+            multiple_service: true,
+            diagnostics: Diagnostics::new(PROTOCOL),
+        })
+    }
+
+    /// Record an error; connection failures drop the session
+    fn fail(&mut self, error: GatewayError) -> GatewayError {
+        if record_failure(&mut self.diagnostics, &error) {
+            self.stream = None;
+            self.session = 0;
+        }
+        error
+    }
+
+    fn peer(&self) -> String {
+        format!("{}:{}", self.config.host, self.config.port)
+    }
+
+    fn timeout_error(&self) -> GatewayError {
+        GatewayError::ConnectionTimeout(self.config.response_timeout.as_millis() as u64)
+    }
+
+    /// Send an encapsulation packet and read the reply to it
+    async fn transact(&mut self, command: u16, data: Vec<u8>) -> igw::Result<Encapsulation> {
+        self.sender_context = self.sender_context.wrapping_add(1);
+        let context = self.sender_context.to_le_bytes();
+        let packet = Encapsulation::new(command, self.session, context, data).encode();
+        let deadline = Instant::now() + self.config.response_timeout;
+        let timeout_ms = self.config.response_timeout.as_millis() as u64;
+        let stream = self.stream.as_mut().ok_or(GatewayError::NotConnected)?;
+        stream
+            .write_all(&packet)
+            .await
+            .map_err(|e| GatewayError::Connection(format!("send: {}", e)))?;
+
+        // TCP keeps replies in order; a reply that does not echo the
+        // context means the stream is out of step and is dropped
+        let mut header = [0u8; codec::HEADER_LEN];
+        timeout_at(deadline, stream.read_exact(&mut header))
+            .await
+            .map_err(|_| GatewayError::ConnectionTimeout(timeout_ms))?
+            .map_err(|e| GatewayError::Connection(format!("receive: {}", e)))?;
+        let (mut reply, len) = Encapsulation::decode_header(&header);
+        let mut data = vec![0u8; len];
+        timeout_at(deadline, stream.read_exact(&mut data))
+            .await
+            .map_err(|_| GatewayError::ConnectionTimeout(timeout_ms))?
+            .map_err(|e| GatewayError::Connection(format!("receive: {}", e)))?;
+        reply.data = data;
+
+        if reply.command != command || reply.context != context {
+            return Err(GatewayError::Connection(format!(
+                "unexpected encapsulation reply 0x{:04X}",
+                reply.command
+            )));
+        }
+        if reply.status != 0 {
+            let text = codec::encapsulation_status_text(reply.status);
+            return Err(
+                if command == command::REGISTER_SESSION || reply.status == 0x64 {
+                    GatewayError::Connection(text)
+                } else {
+                    GatewayError::Protocol(text)
+                },
+            );
+        }
+        Ok(reply)
+    }
+
+    /// Send a CIP request to the controller and return its reply
+    async fn request(&mut self, request: &Request) -> igw::Result<Reply> {
+        let routed = match self.config.route.is_empty() {
+            true => request.clone(),
+            false => request.unconnected_send(&self.config.route),
+        };
+        let timeout_secs = self.config.response_timeout.as_secs().clamp(1, 60) as u16;
+        let reply = self
+            .transact(
+                command::SEND_RR_DATA,
+                codec::send_rr_data(&routed.encode(), timeout_secs),
+            )
+            .await?;
+        let message = codec::rr_data_message(&reply.data)
+            .map_err(|e| GatewayError::Protocol(e.to_string()))?;
+        let reply = Reply::decode(message).map_err(|e| GatewayError::Protocol(e.to_string()))?;
+
+        // A routing failure is answered by the Connection Manager itself
+        if reply.service == codec::service::UNCONNECTED_SEND | codec::service::REPLY {
+            return Err(GatewayError::Protocol(format!(
+                "route {}: {}",
+                self.config.route_text,
+                reply.status_text()
+            )));
+        }
+        if reply.service != request.service | codec::service::REPLY {
+            return Err(GatewayError::Protocol(format!(
+                "reply to service 0x{:02X} instead of 0x{:02X}",
+                reply.service & !codec::service::REPLY,
+                request.service
+            )));
+        }
+        Ok(reply)
+    }
+
+    /// Read `tags`, each with its own outcome
+    async fn read_tags(
+        &mut self,
+        tags: &[TagPath],
+    ) -> igw::Result<Vec<std::result::Result<Reply, String>>> {
+        let requests: Vec<Request> = tags.iter().map(Request::read_tag).collect();
+        if requests.len() > 1 && self.multiple_service {
+            let reply = self.request(&Request::multiple(&requests)).await?;
+            match reply.status {
+                status::SUCCESS | status::EMBEDDED_SERVICE_ERROR => {
+                    let replies = reply
+                        .split_multiple()
+                        .map_err(|e| GatewayError::Protocol(e.to_string()))?;
+                    if replies.len() != tags.len() {
+                        return Err(GatewayError::Protocol(format!(
+                            "{} replies to {} requests",
+                            replies.len(),
+                            tags.len()
+                        )));
+                    }
+                    return Ok(replies.into_iter().map(Ok).collect());
+                },
+                status::SERVICE_NOT_SUPPORTED => {
+                    info!(
+                        "Ch{} target has no Multiple Service Packet, reading tags one by one",
+                        self.id
+                    );
+                    self.multiple_service = false;
+                },
+                _ => {
+                    return Err(GatewayError::Protocol(format!(
+                        "Multiple Service Packet: {}",
+                        reply.status_text()
+                    )))
+                },
+            }
+        }
+
+        let mut replies = Vec::with_capacity(requests.len());
+        for request in &requests {
+            replies.push(Ok(self.request(request).await?));
+        }
+        Ok(replies)
+    }
+
+    /// Tags of the next requests, within the count and size limits
+    fn chunks(&self) -> Vec<Vec<TagPath>> {
+        let limit = codec::MAX_UNCONNECTED_MESSAGE - ROUTING_OVERHEAD;
+        let mut chunks: Vec<Vec<TagPath>> = Vec::new();
+        let mut size = 0;
+        for (tag, _) in &self.reads {
+            // Request plus its offset in the packet
+            let request_size = Request::read_tag(tag).encode().len() + 2;
+            match chunks.last_mut() {
+                Some(chunk)
+                    if chunk.len() < self.config.max_tags && size + request_size <= limit =>
+                {
+                    chunk.push(tag.clone());
+                    size += request_size;
+                },
+                _ => {
+                    chunks.push(vec![tag.clone()]);
+                    size = request_size;
+                },
+            }
+        }
+        chunks
+    }
+
+    fn decode_reading(
+        &mut self,
+        tag: &TagPath,
+        reply: &Reply,
+        reading: &Reading,
+    ) -> std::result::Result<f64, String> {
+        if !reply.is_success() {
+            return Err(reply.status_text());
+        }
+        let (data_type, data) = reply.tag_value().map_err(|e| e.to_string())?;
+        self.types.insert(tag.clone(), data_type);
+        match reading.bit {
+            Some(bit) if data_type.is_integer() && usize::from(bit) < data_type.size() * 8 => {
+                let raw = data_type.decode_bits(data).map_err(|e| e.to_string())?;
+                Ok(f64::from(((raw >> bit) & 1) as u8))
+            },
+            Some(bit) => Err(format!("no bit {} in a {} tag", bit, data_type.name())),
+            None => data_type.decode(data).map_err(|e| e.to_string()),
+        }
+    }
+
+    async fn read_all(&mut self, batch: &mut DataBatch, failures: &mut Vec<PointFailure>) {
+        let index: HashMap<TagPath, usize> = self
+            .reads
+            .iter()
+            .enumerate()
+            .map(|(i, (tag, _))| (tag.clone(), i))
+            .collect();
+        for chunk in self.chunks() {
+            match self.read_tags(&chunk).await {
+                Ok(replies) => {
+                    for (tag, reply) in chunk.iter().zip(replies) {
+                        let readings = self.reads[index[tag]].1.clone();
+                        for reading in &readings {
+                            let value = match &reply {
+                                Ok(reply) => self.decode_reading(tag, reply, reading),
+                                Err(e) => Err(e.clone()),
+                            };
+                            match value {
+                                Ok(value) => batch.add(DataPoint::new(reading.internal_id, value)),
+                                Err(e) => failures.push(PointFailure::with_error(
+                                    reading.internal_id,
+                                    format!("{}: {}", tag, e),
+                                )),
+                            }
+                        }
+                    }
+                },
+                Err(e) => {
+                    let e = self.fail(e);
+                    for tag in &chunk {
+                        failures.extend(
+                            self.reads[index[tag]]
+                                .1
+                                .iter()
+                                .map(|r| PointFailure::with_error(r.internal_id, e.to_string())),
+                        );
+                    }
+                    if self.stream.is_none() {
+                        break;
+                    }
+                },
+            }
+        }
+    }
+
+    /// Type of a written tag: the mapping's, else the controller's
+    async fn data_type_of(&mut self, command: &Command) -> igw::Result<DataType> {
+        if let Some(data_type) = command.data_type {
+            return Ok(data_type);
+        }
+        if let Some(data_type) = self.types.get(&command.tag) {
+            return Ok(*data_type);
+        }
+        let reply = self.request(&Request::read_tag(&command.tag)).await?;
+        if !reply.is_success() {
+            return Err(GatewayError::Protocol(format!(
+                "{}: {}",
+                command.tag,
+                reply.status_text()
+            )));
+        }
+        let (data_type, _) = reply
+            .tag_value()
+            .map_err(|e| GatewayError::Protocol(format!("{}: {}", command.tag, e)))?;
+        self.types.insert(command.tag.clone(), data_type);
+        Ok(data_type)
+    }
+
+    async fn write_tag(&mut self, command: &Command, value: f64) -> igw::Result<()> {
+        let data_type = self.data_type_of(command).await?;
+        let request = match command.bit {
+            Some(bit) => {
+                if !data_type.is_integer() || usize::from(bit) >= data_type.size() * 8 {
+                    return Err(GatewayError::Protocol(format!(
+                        "{}: no bit {} in a {} tag",
+                        command.tag,
+                        bit,
+                        data_type.name()
+                    )));
+                }
+                Request::write_bit(&command.tag, data_type, bit, value != 0.0)
+            },
+            None => {
+                let data = data_type
+                    .encode(value)
+                    .map_err(|e| GatewayError::Protocol(format!("{}: {}", command.tag, e)))?;
+                Request::write_tag(&command.tag, data_type, &data)
+            },
+        };
+        let reply = self.request(&request).await?;
+        if !reply.is_success() {
+            return Err(GatewayError::Protocol(format!(
+                "write {}: {}",
+                command.tag,
+                reply.status_text()
+            )));
+        }
+        Ok(())
+    }
+
+    async fn write(&mut self, point_type: PointType, values: &[(u32, f64)]) -> igw::Result<usize> {
+        let mut written = 0;
+        let mut last_error = None;
+        for &(internal_id, value) in values {
+            let point_id = PointType::from_internal_id(internal_id).1;
+            let commands = match point_type {
+                PointType::Control => &self.controls,
+                _ => &self.adjustments,
+            };
+            let Some(command) = commands.get(&point_id).cloned() else {
+                last_error = Some(GatewayError::PointNotFound(format!(
+                    "{}{}",
+                    point_type.as_str(),
+                    point_id
+                )));
+                continue;
+            };
+            match self.write_tag(&command, value).await {
+                Ok(()) => written += 1,
+                Err(e) => {
+                    let e = self.fail(e);
+                    if self.stream.is_none() {
+                        return Err(e);
+                    }
+                    last_error = Some(e);
+                },
+            }
+        }
+        self.diagnostics.write_count += written as u64;
+        match last_error {
+            Some(e) if written == 0 => Err(e),
+            _ => Ok(written),
+        }
+    }
+
+    async fn register_session(&mut self) -> igw::Result<()> {
+        self.session = 0;
+        let reply = self
+            .transact(command::REGISTER_SESSION, codec::register_session_data())
+            .await?;
+        if reply.session == 0 {
+            return Err(GatewayError::Connection(
+                "RegisterSession returned no session handle".to_string(),
+            ));
+        }
+        self.session = reply.session;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChannelRuntime for EnipRuntime {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn protocol(&self) -> &str {
+        PROTOCOL
+    }
+
+    fn is_event_driven(&self) -> bool {
+        false
+    }
+
+    async fn connect(&mut self) -> igw::Result<()> {
+        self.diagnostics.connection_state = ConnectionState::Connecting;
+        let address = self.peer();
+        let stream =
+            match tokio::time::timeout(self.config.response_timeout, TcpStream::connect(&address))
+                .await
+            {
+                Ok(Ok(stream)) => stream,
+                Ok(Err(e)) => {
+                    return Err(self.fail(GatewayError::Connection(format!("{}: {}", address, e))))
+                },
+                Err(_) => {
+                    let e = self.timeout_error();
+                    return Err(self.fail(e));
+                },
+            };
+        let _ = stream.set_nodelay(true);
+        self.stream = Some(stream);
+
+        if let Err(e) = self.register_session().await {
+            self.stream = None;
+            let e = match e {
+                GatewayError::Connection(_) | GatewayError::ConnectionTimeout(_) => e,
+                other => GatewayError::Connection(format!("{}: {}", address, other)),
+            };
+            return Err(self.fail(e));
+        }
+        self.multiple_service = true;
+        self.diagnostics.connection_state = ConnectionState::Connected;
+        info!(
+            "Ch{} EtherNet/IP session 0x{:08X} with {} (route '{}')",
+            self.id, self.session, address, self.config.route_text
+        );
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> igw::Result<()> {
+        if self.stream.is_some() && self.session != 0 {
+            // UnRegisterSession has no reply
+            self.sender_context = self.sender_context.wrapping_add(1);
+            let packet = Encapsulation::new(
+                command::UNREGISTER_SESSION,
+                self.session,
+                self.sender_context.to_le_bytes(),
+                Vec::new(),
+            )
+            .encode();
+            if let Some(stream) = self.stream.as_mut() {
+                let _ = stream.write_all(&packet).await;
+            }
+        }
+        if let Some(mut stream) = self.stream.take() {
+            let _ = stream.shutdown().await;
+        }
+        self.session = 0;
+        self.diagnostics.connection_state = ConnectionState::Disconnected;
+        Ok(())
+    }
+
+    async fn poll_once(&mut self) -> PollResult {
+        if self.stream.is_none() {
+            return PollResult::failed(vec![PointFailure::new(0, "not connected")]);
+        }
+        let mut batch = DataBatch::default();
+        let mut failures = Vec::new();
+        self.read_all(&mut batch, &mut failures).await;
+        self.diagnostics.read_count += 1;
+        PollResult::partial(batch, failures)
+    }
+
+    async fn write_control(&mut self, commands: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Control, commands).await
+    }
+
+    async fn write_adjustment(&mut self, adjustments: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Adjustment, adjustments).await
+    }
+
+    fn subscribe(&self) -> Option<DataEventReceiver> {
+        None
+    }
+
+    async fn start_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn stop_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn diagnostics(&self) -> igw::Result<Diagnostics> {
+        let mut diagnostics = self.diagnostics.clone();
+        diagnostics.extra = json!({
+            "peer": self.peer(),
+            "route_path": self.config.route_text,
+            "session": (self.session != 0).then(|| format!("0x{:08X}", self.session)),
+            "tags": self.reads.len(),
+            "multiple_service_packet": self.multiple_service,
+        });
+        Ok(diagnostics)
+    }
+}
+
+// ============================================================================
+// Plugin metadata
+// ============================================================================
+
+fn parameters() -> Vec<ParameterMetadata> {
+    vec![
+        ParameterMetadata::required(
+            "host",
+            "Host",
+            "IP address of the controller or its Ethernet module",
+            ParameterType::String,
+        ),
+        ParameterMetadata::optional(
+            "port",
+            "Port",
+            "EtherNet/IP TCP port",
+            ParameterType::Integer,
+            json!(DEFAULT_PORT),
+        ),
+        ParameterMetadata::optional(
+            "route_path",
+            "Route Path",
+            "Port,link pairs to the CPU: 1,0 for backplane slot 0; empty for Micro800",
+            ParameterType::String,
+            json!(DEFAULT_ROUTE),
+        ),
+        ParameterMetadata::optional(
+            "response_timeout_ms",
+            "Response Timeout (ms)",
+            "Time to wait for a controller reply",
+            ParameterType::Integer,
+            json!(DEFAULT_RESPONSE_TIMEOUT_MS),
+        ),
+        ParameterMetadata::optional(
+            "max_tags_per_request",
+            "Tags per Request",
+            "Read Tag requests bundled in one Multiple Service Packet",
+            ParameterType::Integer,
+            json!(DEFAULT_MAX_TAGS),
+        ),
+    ]
+}
+
+crate::protocol_plugin! {
+    /// EtherNet/IP (CIP) client for Logix controllers (in-tree runtime)
+    pub struct EnipPlugin {
+        name: PROTOCOL,
+        aliases: &["ethernet_ip", "ethernetip", "eip", "cip"],
+        display_name: "EtherNet/IP",
+        description: "Allen-Bradley Logix tags by symbolic name, including UDT members and array elements",
+        parameters: parameters(),
+        mapping_columns: &[
+            MappingColumn::string("tag", "Tag name, e.g. Program:Main.Pump[1].Speed or Status.3")
+                .required(),
+            MappingColumn::integer("bit", "Bit of an integer tag").range(0, 63),
+            MappingColumn::string("data_type", "CIP type of written values; read tags report their own")
+                .choices(&[
+                    "BOOL", "SINT", "INT", "DINT", "LINT", "USINT", "UINT", "UDINT", "ULINT",
+                    "REAL", "LREAL", "BYTE", "WORD", "DWORD", "LWORD",
+                ]),
+        ],
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use crate::core::protocols::polled_value;
+    use codec::service;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+
+    const SESSION: u32 = 0x1234_5678;
+
+    fn point<T: serde::de::DeserializeOwned>(point_id: u32, mapping: JsonValue) -> T {
+        serde_json::from_value(json!({
+            "point_id": point_id,
+            "signal_name": format!("p{}", point_id),
+            "protocol_mappings": mapping.to_string(),
+        }))
+        .unwrap()
+    }
+
+    /// Tags of the fake controller: path -> (type, value bytes)
+    type Tags = HashMap<Vec<u8>, (DataType, Vec<u8>)>;
+
+    fn tags() -> Tags {
+        let tag = |name: &str| TagPath::parse(name).unwrap().encode();
+        HashMap::from([
+            (
+                tag("Tank.Level"),
+                (DataType::Real, 72.5f32.to_le_bytes().to_vec()),
+            ),
+            (tag("Line[2].Pump.Running"), (DataType::Bool, vec![0xFF])),
+            (
+                tag("Program:Main.Count"),
+                (DataType::Dint, (-7i32).to_le_bytes().to_vec()),
+            ),
+            (
+                tag("Status"),
+                (DataType::Int, 0b1000u16.to_le_bytes().to_vec()),
+            ),
+            (
+                tag("Setpoint"),
+                (DataType::Real, 0f32.to_le_bytes().to_vec()),
+            ),
+        ])
+    }
+
+    fn handle(
+        request: &Request,
+        tags: &mut Tags,
+        writes: &mpsc::UnboundedSender<(Vec<u8>, Vec<u8>)>,
+    ) -> Reply {
+        match request.service {
+            service::MULTIPLE_SERVICE_PACKET => {
+                let replies: Vec<Vec<u8>> = codec::split_packet(&request.data)
+                    .unwrap()
+                    .into_iter()
+                    .map(|r| handle(&Request::decode(r).unwrap(), tags, writes).encode())
+                    .collect();
+                let failed = replies.iter().any(|r| r[2] != 0);
+                let mut data = (replies.len() as u16).to_le_bytes().to_vec();
+                let mut offset = 2 + 2 * replies.len();
+                for reply in &replies {
+                    data.extend_from_slice(&(offset as u16).to_le_bytes());
+                    offset += reply.len();
+                }
+                data.extend(replies.concat());
+                let mut reply = Reply::success(request.service, data);
+                if failed {
+                    reply.status = status::EMBEDDED_SERVICE_ERROR;
+                }
+                reply
+            },
+            service::READ_TAG => match tags.get(&request.path) {
+                Some((data_type, value)) => Reply::success(
+                    request.service,
+                    [&data_type.code().to_le_bytes()[..], value].concat(),
+                ),
+                None => Reply::error(request.service, 0x04, Vec::new()),
+            },
+            service::WRITE_TAG => match tags.get_mut(&request.path) {
+                Some((data_type, value)) if request.data[..2] == data_type.code().to_le_bytes() => {
+                    *value = request.data[4..].to_vec();
+                    writes.send((request.path.clone(), value.clone())).unwrap();
+                    Reply::success(request.service, Vec::new())
+                },
+                Some(_) => Reply::error(request.service, 0xFF, vec![0x2107]),
+                None => Reply::error(request.service, 0x04, Vec::new()),
+            },
+            service::READ_MODIFY_WRITE_TAG => {
+                let (_, value) = tags.get_mut(&request.path).unwrap();
+                let size = usize::from(u16::from_le_bytes([request.data[0], request.data[1]]));
+                let (or_mask, and_mask) = request.data[2..].split_at(size);
+                for i in 0..size {
+                    value[i] = (value[i] | or_mask[i]) & and_mask[i];
+                }
+                writes.send((request.path.clone(), value.clone())).unwrap();
+                Reply::success(request.service, Vec::new())
+            },
+            other => Reply::error(other, status::SERVICE_NOT_SUPPORTED, Vec::new()),
+        }
+    }
+
+    /// Fake Logix controller behind backplane slot 0
+    async fn controller() -> (u16, mpsc::UnboundedReceiver<(Vec<u8>, Vec<u8>)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (writes, received) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut tags = tags();
+            loop {
+                let mut header = [0u8; codec::HEADER_LEN];
+                if stream.read_exact(&mut header).await.is_err() {
+                    return;
+                }
+                let (packet, len) = Encapsulation::decode_header(&header);
+                let mut data = vec![0u8; len];
+                stream.read_exact(&mut data).await.unwrap();
+                let reply_data = match packet.command {
+                    command::REGISTER_SESSION => data,
+                    command::SEND_RR_DATA => {
+                        assert_eq!(packet.session, SESSION);
+                        let mut request =
+                            Request::decode(codec::rr_data_message(&data).unwrap()).unwrap();
+                        assert_eq!(request.service, service::UNCONNECTED_SEND);
+                        let len =
+                            usize::from(u16::from_le_bytes([request.data[2], request.data[3]]));
+                        request = Request::decode(&request.data[4..4 + len]).unwrap();
+                        let reply = handle(&request, &mut tags, &writes);
+                        codec::send_rr_data(&reply.encode(), 0)
+                    },
+                    _ => return,
+                };
+                let reply = Encapsulation::new(packet.command, SESSION, packet.context, reply_data);
+                stream.write_all(&reply.encode()).await.unwrap();
+            }
+        });
+        (port, received)
+    }
+
+    fn channel(port: u16) -> RuntimeChannelConfig {
+        let mut config = RuntimeChannelConfig::from_base(
+            serde_json::from_value(json!({
+                "id": 61,
+                "name": "plc",
+                "protocol": PROTOCOL,
+                "parameters": {
+                    "host": "127.0.0.1",
+                    "port": port,
+                    "response_timeout_ms": 1000,
+                    "max_tags_per_request": "3",
+                },
+            }))
+            .unwrap(),
+        );
+        config.telemetry_points = vec![
+            point(1, json!({"tag": "Tank.Level"})),
+            point(2, json!({"tag": "Program:Main.Count"})),
+            point(3, json!({"tag": "Missing"})),
+            point(4, json!({"tag": "Bad Tag"})),
+        ];
+        config.signal_points = vec![
+            point(1, json!({"tag": "Line[2].Pump.Running"})),
+            point(2, json!({"tag": "Status.3"})),
+            point(3, json!({"tag": "Status", "bit": 0})),
+        ];
+        config.control_points = vec![point(1, json!({"tag": "Status", "bit": "1"}))];
+        config.adjustment_points = vec![
+            point(1, json!({"tag": "Setpoint"})),
+            point(2, json!({"tag": "Setpoint", "data_type": "DINT"})),
+        ];
+        config
+    }
+
+    #[tokio::test]
+    async fn test_read_and_write_tags() {
+        let (port, mut writes) = controller().await;
+        let mut runtime = EnipRuntime::from_runtime_config(&channel(port)).unwrap();
+        // Status.3 and Status bit 0 share one read
+        assert_eq!(runtime.reads.len(), 5);
+        runtime.connect().await.unwrap();
+        assert_eq!(runtime.session, SESSION);
+
+        // 5 tags in packets of 3
+        let result = runtime.poll_once().await;
+        assert_eq!(polled_value(&result, PointType::Telemetry, 1), Some(72.5));
+        assert_eq!(polled_value(&result, PointType::Telemetry, 2), Some(-7.0));
+        assert_eq!(polled_value(&result, PointType::Signal, 1), Some(1.0));
+        assert_eq!(polled_value(&result, PointType::Signal, 2), Some(1.0));
+        assert_eq!(polled_value(&result, PointType::Signal, 3), Some(0.0));
+        assert_eq!(result.failures.len(), 1);
+        assert!(result.failures[0].error.contains("unknown tag"));
+
+        // Bit 1 of Status set with Read-Modify-Write
+        let control = PointType::Control.to_internal_id(1);
+        assert_eq!(runtime.write_control(&[(control, 1.0)]).await.unwrap(), 1);
+        let (path, value) = writes.recv().await.unwrap();
+        assert_eq!(path, TagPath::parse("Status").unwrap().encode());
+        assert_eq!(value, 0b1010u16.to_le_bytes());
+
+        // Type learned from the controller
+        let adjustment = PointType::Adjustment.to_internal_id(1);
+        assert_eq!(
+            runtime
+                .write_adjustment(&[(adjustment, 42.5)])
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(writes.recv().await.unwrap().1, 42.5f32.to_le_bytes());
+
+        // A mapped type the controller rejects
+        let error = runtime
+            .write_adjustment(&[(PointType::Adjustment.to_internal_id(2), 1.0)])
+            .await
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("data type mismatch"),
+            "{}",
+            error
+        );
+
+        runtime.disconnect().await.unwrap();
+        assert!(runtime.poll_once().await.failures[0]
+            .error
+            .contains("not connected"));
+    }
+
+    #[test]
+    fn test_config_parameters() {
+        let parameters = |value: JsonValue| -> HashMap<String, JsonValue> {
+            serde_json::from_value(value).unwrap()
+        };
+        let config = EnipConfig::from_parameters(&parameters(json!({"host": "10.0.0.2"}))).unwrap();
+        assert_eq!(config.port, DEFAULT_PORT);
+        assert_eq!(config.route, [1, 0]);
+        let direct =
+            EnipConfig::from_parameters(&parameters(json!({"host": "10.0.0.2", "route_path": ""})))
+                .unwrap();
+        assert!(direct.route.is_empty());
+        for invalid in [
+            json!({}),
+            json!({"host": "h", "route_path": "1"}),
+            json!({"host": "h", "max_tags_per_request": 0}),
+        ] {
+            assert!(EnipConfig::from_parameters(&parameters(invalid)).is_err());
+        }
+
+        let twice: Point = point(1, json!({"tag": "Status.2", "bit": 2}));
+        assert!(parse_point(&twice).is_err());
+        let real_bit: Point = point(2, json!({"tag": "Level", "bit": 1, "data_type": "REAL"}));
+        assert!(parse_point(&real_bit).is_err());
+        let member: Point = point(3, json!({"tag": "Line[2].Pump.Running"}));
+        assert_eq!(parse_point(&member).unwrap().tag.segments.len(), 4);
+    }
+}
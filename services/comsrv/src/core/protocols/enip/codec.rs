@@ -0,0 +1,869 @@
+//! EtherNet/IP encapsulation and CIP explicit messages (CIP Vol. 1 and 2)
+//!
+//! Session registration and SendRRData encapsulation over TCP, the
+//! Unconnected Send of the Connection Manager that routes a request across
+//! the backplane to a controller, Multiple Service Packets, and the Logix
+//! tag services: Read Tag, Write Tag and Read-Modify-Write Tag addressed by
+//! symbolic segments.
+
+use std::fmt;
+
+use crate::core::protocols::{error, CodecError};
+
+type Result<T> = std::result::Result<T, CodecError>;
+
+/// Encapsulation header length
+pub const HEADER_LEN: usize = 24;
+/// Largest unconnected message a Logix controller accepts
+pub const MAX_UNCONNECTED_MESSAGE: usize = 504;
+
+/// Encapsulation commands
+pub mod command {
+    pub const REGISTER_SESSION: u16 = 0x0065;
+    pub const UNREGISTER_SESSION: u16 = 0x0066;
+    pub const SEND_RR_DATA: u16 = 0x006F;
+}
+
+/// CIP services
+pub mod service {
+    pub const MULTIPLE_SERVICE_PACKET: u8 = 0x0A;
+    pub const READ_TAG: u8 = 0x4C;
+    pub const WRITE_TAG: u8 = 0x4D;
+    pub const READ_MODIFY_WRITE_TAG: u8 = 0x4E;
+    pub const UNCONNECTED_SEND: u8 = 0x52;
+    /// Set in the service code of a reply
+    pub const REPLY: u8 = 0x80;
+}
+
+/// CIP general status codes the client acts on
+pub mod status {
+    pub const SUCCESS: u8 = 0x00;
+    pub const SERVICE_NOT_SUPPORTED: u8 = 0x08;
+    pub const EMBEDDED_SERVICE_ERROR: u8 = 0x1E;
+}
+
+const ITEM_NULL_ADDRESS: u16 = 0x0000;
+const ITEM_UNCONNECTED_DATA: u16 = 0x00B2;
+/// Abbreviated type of a structure in tag service data
+const STRUCTURE_TYPE: u16 = 0x02A0;
+/// Class 2 (Message Router) instance 1
+const MESSAGE_ROUTER_PATH: [u8; 4] = [0x20, 0x02, 0x24, 0x01];
+/// Class 6 (Connection Manager) instance 1
+const CONNECTION_MANAGER_PATH: [u8; 4] = [0x20, 0x06, 0x24, 0x01];
+
+// ============================================================================
+// Encapsulation
+// ============================================================================
+
+/// Encapsulation packet (CIP Vol. 2, 2-3)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Encapsulation {
+    pub command: u16,
+    pub session: u32,
+    pub status: u32,
+    pub context: [u8; 8],
+    pub data: Vec<u8>,
+}
+
+impl Encapsulation {
+    pub fn new(command: u16, session: u32, context: [u8; 8], data: Vec<u8>) -> Self {
+        Self {
+            command,
+            session,
+            status: 0,
+            context,
+            data,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_LEN + self.data.len());
+        out.extend_from_slice(&self.command.to_le_bytes());
+        out.extend_from_slice(&(self.data.len() as u16).to_le_bytes());
+        out.extend_from_slice(&self.session.to_le_bytes());
+        out.extend_from_slice(&self.status.to_le_bytes());
+        out.extend_from_slice(&self.context);
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&self.data);
+        out
+    }
+
+    /// Decode a header; returns the packet without data and the data length
+    pub fn decode_header(header: &[u8; HEADER_LEN]) -> (Self, usize) {
+        let u16_at = |i: usize| u16::from_le_bytes([header[i], header[i + 1]]);
+        let u32_at =
+            |i: usize| u32::from_le_bytes([header[i], header[i + 1], header[i + 2], header[i + 3]]);
+        let mut context = [0u8; 8];
+        context.copy_from_slice(&header[12..20]);
+        let packet = Self {
+            command: u16_at(0),
+            session: u32_at(4),
+            status: u32_at(8),
+            context,
+            data: Vec::new(),
+        };
+        (packet, usize::from(u16_at(2)))
+    }
+}
+
+/// Data of RegisterSession: protocol version 1, no options
+pub fn register_session_data() -> Vec<u8> {
+    vec![0x01, 0x00, 0x00, 0x00]
+}
+
+/// Text of an encapsulation status
+pub fn encapsulation_status_text(status: u32) -> String {
+    match status {
+        0x0001 => "invalid or unsupported command".to_string(),
+        0x0002 => "insufficient memory".to_string(),
+        0x0003 => "incorrect data".to_string(),
+        0x0064 => "invalid session handle".to_string(),
+        0x0065 => "invalid length".to_string(),
+        0x0069 => "unsupported protocol revision".to_string(),
+        other => format!("encapsulation status 0x{:04X}", other),
+    }
+}
+
+/// Data of SendRRData carrying an unconnected CIP message
+pub fn send_rr_data(message: &[u8], timeout_secs: u16) -> Vec<u8> {
+    let mut out = Vec::with_capacity(16 + message.len());
+    out.extend_from_slice(&0u32.to_le_bytes()); // interface handle: CIP
+    out.extend_from_slice(&timeout_secs.to_le_bytes());
+    out.extend_from_slice(&2u16.to_le_bytes()); // item count
+    out.extend_from_slice(&ITEM_NULL_ADDRESS.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes());
+    out.extend_from_slice(&ITEM_UNCONNECTED_DATA.to_le_bytes());
+    out.extend_from_slice(&(message.len() as u16).to_le_bytes());
+    out.extend_from_slice(message);
+    out
+}
+
+/// CIP message of the unconnected data item of SendRRData data
+pub fn rr_data_message(data: &[u8]) -> Result<&[u8]> {
+    let mut rest = data
+        .get(6..)
+        .ok_or_else(|| error("SendRRData data too short"))?;
+    let count = read_u16(&mut rest)?;
+    for _ in 0..count {
+        let item_type = read_u16(&mut rest)?;
+        let len = usize::from(read_u16(&mut rest)?);
+        let item = take(&mut rest, len)?;
+        if item_type == ITEM_UNCONNECTED_DATA {
+            return Ok(item);
+        }
+    }
+    Err(error("SendRRData without an unconnected data item"))
+}
+
+fn read_u16(buf: &mut &[u8]) -> Result<u16> {
+    let bytes = take(buf, 2)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn take<'a>(buf: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if buf.len() < len {
+        return Err(error("truncated CIP message"));
+    }
+    let (head, tail) = buf.split_at(len);
+    *buf = tail;
+    Ok(head)
+}
+
+// ============================================================================
+// Tag paths
+// ============================================================================
+
+/// Segment of a tag path
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Segment {
+    /// Tag or member name (ANSI extended symbolic segment)
+    Symbol(String),
+    /// Array index
+    Element(u32),
+}
+
+/// Symbolic address of a tag, a UDT member or an array element
+///
+/// `Program:Main.Line[2].Pump.Speed` is `Program:Main` (program-scoped
+/// tags keep the prefix in their first segment), `Line`, element 2, `Pump`
+/// and `Speed`. A numeric last part (`Status.3`) selects a bit of an
+/// integer tag.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TagPath {
+    pub segments: Vec<Segment>,
+    pub bit: Option<u8>,
+}
+
+impl TagPath {
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        let invalid = |why: &str| error(format!("invalid tag '{}': {}", text, why));
+        if text.is_empty() {
+            return Err(invalid("empty"));
+        }
+
+        // Split on dots outside brackets
+        let mut parts = Vec::new();
+        let mut depth = 0usize;
+        let mut start = 0;
+        for (i, c) in text.char_indices() {
+            match c {
+                '[' => depth += 1,
+                ']' => {
+                    depth = depth
+                        .checked_sub(1)
+                        .ok_or_else(|| invalid("unbalanced ']'"))?
+                },
+                '.' if depth == 0 => {
+                    parts.push(&text[start..i]);
+                    start = i + 1;
+                },
+                _ => {},
+            }
+        }
+        if depth != 0 {
+            return Err(invalid("unbalanced '['"));
+        }
+        parts.push(&text[start..]);
+
+        let mut segments = Vec::new();
+        let mut bit = None;
+        let last = parts.len() - 1;
+        for (index, part) in parts.into_iter().enumerate() {
+            if bit.is_some() {
+                return Err(invalid("a bit number must be last"));
+            }
+            if index > 0 && !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()) {
+                let number: u8 = part.parse().map_err(|_| invalid("bit out of range"))?;
+                if number > 63 || index != last {
+                    return Err(invalid("bit must be 0-63 and last"));
+                }
+                bit = Some(number);
+                continue;
+            }
+
+            let (name, indexes) = match part.split_once('[') {
+                Some((name, rest)) => {
+                    let inner = rest
+                        .strip_suffix(']')
+                        .ok_or_else(|| invalid("text after ']'"))?;
+                    (name, Some(inner))
+                },
+                None => (part, None),
+            };
+            let valid_name = !name.is_empty()
+                && name.len() <= 255
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':');
+            if !valid_name {
+                return Err(invalid("names use A-Z, a-z, 0-9, '_' and ':'"));
+            }
+            segments.push(Segment::Symbol(name.to_string()));
+            if let Some(inner) = indexes {
+                for index in inner.split(',') {
+                    let index = index
+                        .trim()
+                        .parse()
+                        .map_err(|_| invalid("array index must be a number"))?;
+                    segments.push(Segment::Element(index));
+                }
+            }
+        }
+        Ok(Self { segments, bit })
+    }
+
+    /// Request path of the tag (the bit is not part of it)
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Symbol(name) => {
+                    out.push(0x91);
+                    out.push(name.len() as u8);
+                    out.extend_from_slice(name.as_bytes());
+                    if name.len() % 2 == 1 {
+                        out.push(0);
+                    }
+                },
+                Segment::Element(index) => match *index {
+                    i if i <= 0xFF => out.extend_from_slice(&[0x28, i as u8]),
+                    i if i <= 0xFFFF => {
+                        out.extend_from_slice(&[0x29, 0x00]);
+                        out.extend_from_slice(&(i as u16).to_le_bytes());
+                    },
+                    i => {
+                        out.extend_from_slice(&[0x2A, 0x00]);
+                        out.extend_from_slice(&i.to_le_bytes());
+                    },
+                },
+            }
+        }
+        out
+    }
+}
+
+impl fmt::Display for TagPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+        let mut in_index = false;
+        for segment in &self.segments {
+            match segment {
+                Segment::Symbol(name) => {
+                    if in_index {
+                        f.write_str("]")?;
+                        in_index = false;
+                    }
+                    if !first {
+                        f.write_str(".")?;
+                    }
+                    f.write_str(name)?;
+                },
+                Segment::Element(index) => {
+                    f.write_str(if in_index { "," } else { "[" })?;
+                    write!(f, "{}", index)?;
+                    in_index = true;
+                },
+            }
+            first = false;
+        }
+        if in_index {
+            f.write_str("]")?;
+        }
+        if let Some(bit) = self.bit {
+            write!(f, ".{}", bit)?;
+        }
+        Ok(())
+    }
+}
+
+/// Route from the adapter to the controller, e.g. `1,0` for backplane
+/// port 1, slot 0, or `1,0,2,10.0.0.7` to continue through an Ethernet
+/// module in slot 0
+pub fn parse_route(text: &str) -> Result<Vec<u8>> {
+    let parts: Vec<&str> = text
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .collect();
+    if !parts.len().is_multiple_of(2) {
+        return Err(error(format!(
+            "route '{}' must be port,link pairs",
+            text.trim()
+        )));
+    }
+    let mut out = Vec::new();
+    for pair in parts.chunks(2) {
+        let port: u8 = pair[0]
+            .parse()
+            .ok()
+            .filter(|p| (1..=14).contains(p))
+            .ok_or_else(|| error(format!("route port '{}' must be 1-14", pair[0])))?;
+        match pair[1].parse::<u8>() {
+            Ok(link) => out.extend_from_slice(&[port, link]),
+            Err(_) => {
+                // Extended link address (an IP address)
+                let address = pair[1].as_bytes();
+                out.push(0x10 | port);
+                out.push(address.len() as u8);
+                out.extend_from_slice(address);
+                if address.len() % 2 == 1 {
+                    out.push(0);
+                }
+            },
+        }
+    }
+    Ok(out)
+}
+
+// ============================================================================
+// Data types
+// ============================================================================
+
+/// Atomic CIP data types of Logix tags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DataType {
+    Bool,
+    Sint,
+    Int,
+    Dint,
+    Lint,
+    Usint,
+    Uint,
+    Udint,
+    Ulint,
+    Real,
+    Lreal,
+    Byte,
+    Word,
+    Dword,
+    Lword,
+}
+
+impl DataType {
+    const ALL: [(DataType, u16, &'static str); 15] = [
+        (DataType::Bool, 0xC1, "BOOL"),
+        (DataType::Sint, 0xC2, "SINT"),
+        (DataType::Int, 0xC3, "INT"),
+        (DataType::Dint, 0xC4, "DINT"),
+        (DataType::Lint, 0xC5, "LINT"),
+        (DataType::Usint, 0xC6, "USINT"),
+        (DataType::Uint, 0xC7, "UINT"),
+        (DataType::Udint, 0xC8, "UDINT"),
+        (DataType::Ulint, 0xC9, "ULINT"),
+        (DataType::Real, 0xCA, "REAL"),
+        (DataType::Lreal, 0xCB, "LREAL"),
+        (DataType::Byte, 0xD1, "BYTE"),
+        (DataType::Word, 0xD2, "WORD"),
+        (DataType::Dword, 0xD3, "DWORD"),
+        (DataType::Lword, 0xD4, "LWORD"),
+    ];
+
+    pub fn code(&self) -> u16 {
+        Self::ALL
+            .iter()
+            .find(|(t, _, _)| t == self)
+            .map_or(0, |(_, code, _)| *code)
+    }
+
+    pub fn name(&self) -> &'static str {
+        Self::ALL
+            .iter()
+            .find(|(t, _, _)| t == self)
+            .map_or("", |(_, _, name)| name)
+    }
+
+    pub fn from_code(code: u16) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|(_, c, _)| *c == code)
+            .map(|(t, _, _)| *t)
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let upper = text.trim().to_ascii_uppercase();
+        Self::ALL
+            .iter()
+            .find(|(_, _, name)| *name == upper)
+            .map(|(t, _, _)| *t)
+            .ok_or_else(|| {
+                error(format!(
+                    "unknown CIP data type '{}' (BOOL, SINT, INT, DINT, LINT, USINT, UINT, UDINT, ULINT, REAL, LREAL, BYTE, WORD, DWORD, LWORD)",
+                    text.trim()
+                ))
+            })
+    }
+
+    /// Encoded size in octets
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Bool | Self::Sint | Self::Usint | Self::Byte => 1,
+            Self::Int | Self::Uint | Self::Word => 2,
+            Self::Dint | Self::Udint | Self::Real | Self::Dword => 4,
+            Self::Lint | Self::Ulint | Self::Lreal | Self::Lword => 8,
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        !matches!(self, Self::Real | Self::Lreal | Self::Bool)
+    }
+
+    /// Raw integer of a value, for bit access
+    pub fn decode_bits(&self, data: &[u8]) -> Result<u64> {
+        let data = data
+            .get(..self.size())
+            .ok_or_else(|| error(format!("{} value truncated", self.name())))?;
+        let mut bytes = [0u8; 8];
+        bytes[..data.len()].copy_from_slice(data);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    pub fn decode(&self, data: &[u8]) -> Result<f64> {
+        let raw = self.decode_bits(data)?;
+        Ok(match self {
+            Self::Bool => f64::from(u8::from(raw != 0)),
+            Self::Sint => f64::from(raw as u8 as i8),
+            Self::Int => f64::from(raw as u16 as i16),
+            Self::Dint => f64::from(raw as u32 as i32),
+            Self::Lint => raw as i64 as f64,
+            Self::Real => f64::from(f32::from_bits(raw as u32)),
+            Self::Lreal => f64::from_bits(raw),
+            Self::Usint | Self::Uint | Self::Udint | Self::Ulint => raw as f64,
+            Self::Byte | Self::Word | Self::Dword | Self::Lword => raw as f64,
+        })
+    }
+
+    /// Encode a written value; integer types require whole numbers in range
+    pub fn encode(&self, value: f64) -> Result<Vec<u8>> {
+        match self {
+            Self::Real => return Ok((value as f32).to_le_bytes().to_vec()),
+            Self::Lreal => return Ok(value.to_le_bytes().to_vec()),
+            Self::Bool => return Ok(vec![if value != 0.0 { 0xFF } else { 0x00 }]),
+            _ => {},
+        }
+        let signed = matches!(self, Self::Sint | Self::Int | Self::Dint | Self::Lint);
+        let bits = self.size() as u32 * 8;
+        let (min, max) = if signed {
+            (
+                -(2f64.powi(bits as i32 - 1)),
+                2f64.powi(bits as i32 - 1) - 1.0,
+            )
+        } else {
+            (0.0, 2f64.powi(bits as i32) - 1.0)
+        };
+        if !value.is_finite() || value.fract() != 0.0 || value < min || value > max {
+            return Err(error(format!("{} does not fit {}", value, self.name())));
+        }
+        let raw = if signed {
+            value as i64 as u64
+        } else {
+            value as u64
+        };
+        Ok(raw.to_le_bytes()[..self.size()].to_vec())
+    }
+}
+
+// ============================================================================
+// Messages
+// ============================================================================
+
+/// CIP request (Message Router request format)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub service: u8,
+    pub path: Vec<u8>,
+    pub data: Vec<u8>,
+}
+
+impl Request {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(2 + self.path.len() + self.data.len());
+        out.push(self.service);
+        out.push((self.path.len() / 2) as u8);
+        out.extend_from_slice(&self.path);
+        out.extend_from_slice(&self.data);
+        out
+    }
+
+    pub fn decode(message: &[u8]) -> Result<Self> {
+        let mut rest = message;
+        let header = take(&mut rest, 2)?;
+        let path = take(&mut rest, usize::from(header[1]) * 2)?.to_vec();
+        Ok(Self {
+            service: header[0],
+            path,
+            data: rest.to_vec(),
+        })
+    }
+
+    /// Read one element of a tag
+    pub fn read_tag(tag: &TagPath) -> Self {
+        Self {
+            service: service::READ_TAG,
+            path: tag.encode(),
+            data: 1u16.to_le_bytes().to_vec(),
+        }
+    }
+
+    /// Write one element of an atomic tag
+    pub fn write_tag(tag: &TagPath, data_type: DataType, value: &[u8]) -> Self {
+        let mut data = Vec::with_capacity(4 + value.len());
+        data.extend_from_slice(&data_type.code().to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(value);
+        Self {
+            service: service::WRITE_TAG,
+            path: tag.encode(),
+            data,
+        }
+    }
+
+    /// Set or clear one bit of an integer tag, leaving the others
+    pub fn write_bit(tag: &TagPath, data_type: DataType, bit: u8, on: bool) -> Self {
+        let size = data_type.size();
+        let mask = 1u64 << bit;
+        let (or_mask, and_mask) = if on { (mask, u64::MAX) } else { (0, !mask) };
+        let mut data = Vec::with_capacity(2 + size * 2);
+        data.extend_from_slice(&(size as u16).to_le_bytes());
+        data.extend_from_slice(&or_mask.to_le_bytes()[..size]);
+        data.extend_from_slice(&and_mask.to_le_bytes()[..size]);
+        Self {
+            service: service::READ_MODIFY_WRITE_TAG,
+            path: tag.encode(),
+            data,
+        }
+    }
+
+    /// Multiple Service Packet of `requests` to the Message Router
+    pub fn multiple(requests: &[Request]) -> Self {
+        let encoded: Vec<Vec<u8>> = requests.iter().map(Request::encode).collect();
+        let mut data = Vec::new();
+        data.extend_from_slice(&(encoded.len() as u16).to_le_bytes());
+        let mut offset = 2 + 2 * encoded.len();
+        for request in &encoded {
+            data.extend_from_slice(&(offset as u16).to_le_bytes());
+            offset += request.len();
+        }
+        for request in encoded {
+            data.extend_from_slice(&request);
+        }
+        Self {
+            service: service::MULTIPLE_SERVICE_PACKET,
+            path: MESSAGE_ROUTER_PATH.to_vec(),
+            data,
+        }
+    }
+
+    /// Unconnected Send of this request along `route`
+    pub fn unconnected_send(&self, route: &[u8]) -> Self {
+        let message = self.encode();
+        let mut data = Vec::with_capacity(8 + message.len() + route.len());
+        // Priority/time tick and time-out ticks: 2^10 ms x 5 ≈ 5 s
+        data.extend_from_slice(&[0x0A, 0x05]);
+        data.extend_from_slice(&(message.len() as u16).to_le_bytes());
+        data.extend_from_slice(&message);
+        if message.len() % 2 == 1 {
+            data.push(0);
+        }
+        data.push((route.len() / 2) as u8);
+        data.push(0);
+        data.extend_from_slice(route);
+        Self {
+            service: service::UNCONNECTED_SEND,
+            path: CONNECTION_MANAGER_PATH.to_vec(),
+            data,
+        }
+    }
+}
+
+/// CIP reply (Message Router response format)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reply {
+    pub service: u8,
+    pub status: u8,
+    pub ext_status: Vec<u16>,
+    pub data: Vec<u8>,
+}
+
+impl Reply {
+    pub fn success(service: u8, data: Vec<u8>) -> Self {
+        Self::error(service, status::SUCCESS, Vec::new()).with_data(data)
+    }
+
+    pub fn error(service: u8, status: u8, ext_status: Vec<u16>) -> Self {
+        Self {
+            service: service | service::REPLY,
+            status,
+            ext_status,
+            data: Vec::new(),
+        }
+    }
+
+    fn with_data(mut self, data: Vec<u8>) -> Self {
+        self.data = data;
+        self
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = vec![self.service, 0, self.status, self.ext_status.len() as u8];
+        for ext in &self.ext_status {
+            out.extend_from_slice(&ext.to_le_bytes());
+        }
+        out.extend_from_slice(&self.data);
+        out
+    }
+
+    pub fn decode(message: &[u8]) -> Result<Self> {
+        let mut rest = message;
+        let header = take(&mut rest, 4)?;
+        let mut ext_status = Vec::with_capacity(usize::from(header[3]));
+        for _ in 0..header[3] {
+            ext_status.push(read_u16(&mut rest)?);
+        }
+        Ok(Self {
+            service: header[0],
+            status: header[2],
+            ext_status,
+            data: rest.to_vec(),
+        })
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.status == status::SUCCESS
+    }
+
+    /// Error text of a failed reply
+    pub fn status_text(&self) -> String {
+        status_text(self.status, self.ext_status.first().copied())
+    }
+
+    /// Replies embedded in a Multiple Service Packet reply
+    pub fn split_multiple(&self) -> Result<Vec<Reply>> {
+        split_packet(&self.data)?
+            .into_iter()
+            .map(Reply::decode)
+            .collect()
+    }
+
+    /// Type and value of a Read Tag reply
+    pub fn tag_value(&self) -> Result<(DataType, &[u8])> {
+        let mut rest = self.data.as_slice();
+        let code = read_u16(&mut rest)?;
+        if code == STRUCTURE_TYPE {
+            return Err(error("tag is a structure; map one of its members"));
+        }
+        let data_type = DataType::from_code(code)
+            .ok_or_else(|| error(format!("unsupported CIP data type 0x{:04X}", code)))?;
+        if rest.len() < data_type.size() {
+            return Err(error(format!("{} value truncated", data_type.name())));
+        }
+        Ok((data_type, rest))
+    }
+}
+
+/// Requests embedded in Multiple Service Packet data
+pub fn split_packet(data: &[u8]) -> Result<Vec<&[u8]>> {
+    let mut rest = data;
+    let count = usize::from(read_u16(&mut rest)?);
+    let mut offsets = Vec::with_capacity(count + 1);
+    for _ in 0..count {
+        offsets.push(usize::from(read_u16(&mut rest)?));
+    }
+    offsets.push(data.len());
+    offsets
+        .windows(2)
+        .map(|w| {
+            data.get(w[0]..w[1])
+                .ok_or_else(|| error("invalid Multiple Service Packet offset"))
+        })
+        .collect()
+}
+
+/// Text of a CIP general status and its first extended status
+pub fn status_text(general: u8, extended: Option<u16>) -> String {
+    let text = match general {
+        0x01 => "connection failure",
+        0x04 => "path segment error (unknown tag)",
+        0x05 => "path destination unknown (unknown tag or member)",
+        0x06 => "partial transfer",
+        0x08 => "service not supported",
+        0x0E => "attribute not settable",
+        0x10 => "device state conflict (controller mode)",
+        0x13 => "not enough data",
+        0x15 => "too much data",
+        0x1E => "embedded service error",
+        0x20 => "invalid parameter",
+        0xFF => match extended {
+            Some(0x2105) => return "general error: index out of range".to_string(),
+            Some(0x2107) => return "general error: data type mismatch".to_string(),
+            _ => "general error",
+        },
+        _ => "",
+    };
+    let mut out = if text.is_empty() {
+        format!("CIP status 0x{:02X}", general)
+    } else {
+        format!("{} (0x{:02X})", text, general)
+    };
+    if let Some(ext) = extended {
+        out.push_str(&format!(", extended 0x{:04X}", ext));
+    }
+    out
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_path() {
+        let tag = TagPath::parse("Program:Main.Line[2].Pump.Speed").unwrap();
+        assert_eq!(
+            tag.segments,
+            vec![
+                Segment::Symbol("Program:Main".into()),
+                Segment::Symbol("Line".into()),
+                Segment::Element(2),
+                Segment::Symbol("Pump".into()),
+                Segment::Symbol("Speed".into()),
+            ]
+        );
+        assert_eq!(tag.to_string(), "Program:Main.Line[2].Pump.Speed");
+        // Odd-length names are padded to whole words
+        assert_eq!(
+            TagPath::parse("Tank[300]").unwrap().encode(),
+            [0x91, 4, b'T', b'a', b'n', b'k', 0x29, 0x00, 0x2C, 0x01]
+        );
+        assert_eq!(
+            TagPath::parse("Pump").unwrap().encode(),
+            [0x91, 4, b'P', b'u', b'm', b'p']
+        );
+        assert_eq!(TagPath::parse("Abc").unwrap().encode().len(), 6);
+
+        let bit = TagPath::parse("Status.3").unwrap();
+        assert_eq!(bit.bit, Some(3));
+        assert_eq!(bit.segments.len(), 1);
+        assert_eq!(TagPath::parse("Grid[1, 2]").unwrap().segments.len(), 3);
+        for invalid in ["", "A..B", "A.3.B", "Tank[1", "Tank]", "A.64", "Bad-Name"] {
+            assert!(TagPath::parse(invalid).is_err(), "{}", invalid);
+        }
+
+        assert_eq!(parse_route("1,0").unwrap(), [1, 0]);
+        assert_eq!(
+            parse_route("1,2,2,10.0.0.7").unwrap(),
+            [1, 2, 0x12, 8, b'1', b'0', b'.', b'0', b'.', b'0', b'.', b'7']
+        );
+        assert!(parse_route("1").is_err());
+    }
+
+    #[test]
+    fn test_data_types() {
+        assert_eq!(DataType::Dint.decode(&(-5i32).to_le_bytes()).unwrap(), -5.0);
+        assert_eq!(DataType::Real.decode(&1.5f32.to_le_bytes()).unwrap(), 1.5);
+        assert_eq!(DataType::Bool.decode(&[0xFF]).unwrap(), 1.0);
+        assert_eq!(DataType::Uint.decode(&[0xFF, 0xFF]).unwrap(), 65535.0);
+        assert_eq!(DataType::Int.encode(-2.0).unwrap(), [0xFE, 0xFF]);
+        assert!(DataType::Sint.encode(200.0).is_err());
+        assert!(DataType::Dint.encode(1.5).is_err());
+        assert_eq!(DataType::parse("lreal").unwrap(), DataType::Lreal);
+        assert_eq!(DataType::from_code(0xC4), Some(DataType::Dint));
+    }
+
+    #[test]
+    fn test_messages() {
+        // Read Tag "Pump", routed to slot 0 of the backplane
+        let read = Request::read_tag(&TagPath::parse("Pump").unwrap());
+        let routed = read.unconnected_send(&[1, 0]).encode();
+        assert_eq!(
+            routed,
+            [
+                0x52, 0x02, 0x20, 0x06, 0x24, 0x01, 0x0A, 0x05, 0x0A, 0x00, 0x4C, 0x03, 0x91, 0x04,
+                b'P', b'u', b'm', b'p', 0x01, 0x00, 0x01, 0x00, 0x01, 0x00
+            ]
+        );
+
+        let packet = Request::multiple(&[read.clone(), read.clone()]);
+        let parts = split_packet(&packet.data).unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(Request::decode(parts[1]).unwrap(), read);
+
+        let reply = Reply::success(
+            service::READ_TAG,
+            [&0xCAu16.to_le_bytes()[..], &2.5f32.to_le_bytes()].concat(),
+        );
+        let decoded = Reply::decode(&reply.encode()).unwrap();
+        let (data_type, value) = decoded.tag_value().unwrap();
+        assert_eq!(data_type, DataType::Real);
+        assert_eq!(data_type.decode(value).unwrap(), 2.5);
+
+        let failed = Reply::decode(&Reply::error(0x4C, 0xFF, vec![0x2107]).encode()).unwrap();
+        assert_eq!(failed.status_text(), "general error: data type mismatch");
+
+        let rmw = Request::write_bit(&TagPath::parse("S").unwrap(), DataType::Int, 3, false);
+        assert_eq!(&rmw.data, &[2, 0, 0, 0, 0xF7, 0xFF]);
+
+        let message = send_rr_data(&[1, 2, 3], 5);
+        assert_eq!(rr_data_message(&message).unwrap(), [1, 2, 3]);
+    }
+}
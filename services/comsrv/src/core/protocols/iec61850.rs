@@ -0,0 +1,1030 @@
+//! IEC 61850 MMS client
+//!
+//! Associates with a relay or bay controller over ISO-on-TCP (port 102),
+//! reads data attributes with batched MMS Read requests and writes controls
+//! and settings with MMS Write.
+//!
+//! Each point maps to an object `reference` (`LD/LN.DO[.DA...]`) and its
+//! functional constraint `fc`; the MMS variable is `LN$FC$DO$DA` in domain
+//! `LD`. Reading a data object or a structured attribute yields its first
+//! member (e.g. `stVal`, `mag.f`).
+//!
+//! | Point type | `fc`                          | Operation                       |
+//! |------------|-------------------------------|---------------------------------|
+//! | T / S      | MX, ST, SP, SV, CF, DC, SG, SE, EX, BL | read                   |
+//! | C / A      | CO (data object reference)    | `Oper` with `ctlVal`, direct or SBO (normal security) |
+//! | C / A      | SP, SV, CF, DC, SE, BL        | write of the attribute          |
+//!
+//! Point tables can be generated from the device's ICD/SCL file, see
+//! [`scl`].
+//!
+//! ```yaml
+//! protocol: iec61850
+//! parameters:
+//!   host: 192.168.1.30
+//!   port: 102
+//!   originator: voltage-ems   # orIdent of controls
+//! ```
+
+pub mod mms;
+pub mod scl;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use igw::core::traits::{DataEventReceiver, Diagnostics, PointFailure, PollResult};
+use igw::gateway::ChannelRuntime;
+use igw::{ConnectionState, DataBatch, DataPoint, GatewayError};
+use serde_json::{json, Value as JsonValue};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{timeout_at, Instant};
+use tracing::{debug, info, warn};
+
+use self::mms::{AccessResult, MmsData, ObjectName, Pdu, ResponseBody, Tpdu, TpktReader};
+use crate::core::config::RuntimeChannelConfig;
+use crate::core::plugins::{MappingColumn, ParameterMetadata, ParameterType};
+use crate::core::protocols::{as_u64, point_mapping, record_failure};
+use crate::error::{ComSrvError, Result};
+use voltage_model::PointType;
+
+/// Protocol name stored in `channels.protocol`
+pub const PROTOCOL: &str = "iec61850";
+
+const DEFAULT_PORT: u16 = 102;
+const DEFAULT_RESPONSE_TIMEOUT_MS: u64 = 5000;
+const DEFAULT_MAX_READ_BATCH: u64 = 32;
+const DEFAULT_ORIGINATOR: &str = "voltage-ems";
+/// orCat remote-control
+const DEFAULT_ORIGINATOR_CATEGORY: u64 = 3;
+
+/// Functional constraints of readable points
+const READ_FCS: &[&str] = &["MX", "ST", "SP", "SV", "CF", "DC", "SG", "SE", "EX", "BL"];
+/// Functional constraints of written points besides CO
+const WRITE_FCS: &[&str] = &["SP", "SV", "CF", "DC", "SE", "BL"];
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Channel parameters of an IEC 61850 channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct MmsConfig {
+    pub host: String,
+    pub port: u16,
+    pub response_timeout: Duration,
+    /// Variables per Read request
+    pub max_read_batch: usize,
+    /// `orIdent` of controls
+    pub originator: String,
+    /// `orCat` of controls
+    pub originator_category: i64,
+}
+
+impl MmsConfig {
+    pub fn from_parameters(parameters: &HashMap<String, JsonValue>) -> Result<Self> {
+        let host = parameters
+            .get("host")
+            .and_then(|v| v.as_str())
+            .filter(|h| !h.trim().is_empty())
+            .ok_or_else(|| ComSrvError::ConfigError("IEC 61850 channel requires 'host'".into()))?
+            .trim()
+            .to_string();
+        let number = |key: &str, default: u64, max: u64| -> Result<u64> {
+            match parameters.get(key) {
+                None | Some(JsonValue::Null) => Ok(default),
+                Some(value) => as_u64(value).filter(|v| *v <= max).ok_or_else(|| {
+                    ComSrvError::ConfigError(format!("IEC 61850 '{}' must be 0-{}", key, max))
+                }),
+            }
+        };
+
+        Ok(Self {
+            host,
+            port: number("port", DEFAULT_PORT as u64, u16::MAX as u64)? as u16,
+            response_timeout: Duration::from_millis(
+                number(
+                    "response_timeout_ms",
+                    DEFAULT_RESPONSE_TIMEOUT_MS,
+                    u32::MAX as u64,
+                )?
+                .max(1),
+            ),
+            max_read_batch: number("max_read_batch", DEFAULT_MAX_READ_BATCH, 1000)?.max(1) as usize,
+            originator: parameters
+                .get("originator")
+                .and_then(|v| v.as_str())
+                .unwrap_or(DEFAULT_ORIGINATOR)
+                .to_string(),
+            originator_category: number("originator_category", DEFAULT_ORIGINATOR_CATEGORY, 8)?
+                as i64,
+        })
+    }
+}
+
+// ============================================================================
+// Point mapping
+// ============================================================================
+
+/// MMS type a written value is encoded as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    Boolean,
+    Int,
+    Uint,
+    Float32,
+    Float64,
+}
+
+impl ValueType {
+    fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "boolean" | "bool" => Some(Self::Boolean),
+            "int" => Some(Self::Int),
+            "uint" => Some(Self::Uint),
+            "float32" | "float" => Some(Self::Float32),
+            "float64" => Some(Self::Float64),
+            _ => None,
+        }
+    }
+
+    fn data(self, value: f64) -> MmsData {
+        match self {
+            Self::Boolean => MmsData::Boolean(value != 0.0),
+            Self::Int => MmsData::Integer(value.round() as i64),
+            Self::Uint => MmsData::Unsigned(value.round().max(0.0) as u64),
+            Self::Float32 => MmsData::Float32(value as f32),
+            Self::Float64 => MmsData::Float64(value),
+        }
+    }
+}
+
+/// Parsed mapping of one point
+#[derive(Debug, Clone, PartialEq)]
+enum Binding {
+    Read(ObjectName),
+    /// Operate a controllable data object
+    Control {
+        oper: ObjectName,
+        /// `SBO` attribute read to select first
+        select: Option<ObjectName>,
+        value_type: ValueType,
+    },
+    Write {
+        name: ObjectName,
+        value_type: ValueType,
+    },
+}
+
+/// MMS variable of an object reference
+///
+/// `LD/LN.DO.DA` with FC `MX` is item `LN$MX$DO$DA` of domain `LD`.
+pub fn object_name(reference: &str, fc: &str) -> std::result::Result<ObjectName, String> {
+    let (domain, path) = reference
+        .trim()
+        .split_once('/')
+        .ok_or_else(|| format!("reference {} is not LD/LN.DO[.DA]", reference))?;
+    let mut parts = path.split('.');
+    let ln = parts.next().unwrap_or_default();
+    let mut item = format!("{}${}", ln, fc);
+    let mut depth = 0;
+    for part in parts {
+        item.push('$');
+        item.push_str(part);
+        depth += 1;
+    }
+    let valid = |name: &str| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    if !valid(domain) || !valid(ln) || depth == 0 || !item.split('$').all(valid) {
+        return Err(format!("reference {} is not LD/LN.DO[.DA]", reference));
+    }
+    if domain.len() > 64 || item.len() > 64 {
+        return Err(format!(
+            "reference {} exceeds the 64 character MMS name limit",
+            reference
+        ));
+    }
+    Ok(ObjectName {
+        domain: domain.to_string(),
+        item,
+    })
+}
+
+fn parse_binding(
+    point_type: PointType,
+    mapping: &JsonValue,
+) -> std::result::Result<Binding, String> {
+    let field = |key: &str| {
+        mapping
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    };
+    let reference = field("reference").ok_or("missing 'reference'")?;
+    let fc = field("fc").ok_or("missing 'fc'")?.to_uppercase();
+    let writable = matches!(point_type, PointType::Control | PointType::Adjustment);
+
+    if !writable {
+        if !READ_FCS.contains(&fc.as_str()) {
+            return Err(format!("fc {} is not readable", fc));
+        }
+        return Ok(Binding::Read(object_name(reference, &fc)?));
+    }
+
+    let value_type = match field("value_type") {
+        Some(name) => {
+            ValueType::parse(name).ok_or_else(|| format!("unknown value_type {}", name))?
+        },
+        None if point_type == PointType::Control => ValueType::Boolean,
+        None => ValueType::Float32,
+    };
+    if fc == "CO" {
+        // The data object is operated; tolerate references down to ctlVal
+        let reference = reference.strip_suffix(".ctlVal").unwrap_or(reference);
+        let reference = reference.strip_suffix(".Oper").unwrap_or(reference);
+        let select = match field("ctl_model").unwrap_or("direct") {
+            "direct" => None,
+            "sbo" => Some(object_name(&format!("{}.SBO", reference), "CO")?),
+            other => return Err(format!("unknown ctl_model {}", other)),
+        };
+        return Ok(Binding::Control {
+            oper: object_name(&format!("{}.Oper", reference), "CO")?,
+            select,
+            value_type,
+        });
+    }
+    if !WRITE_FCS.contains(&fc.as_str()) {
+        return Err(format!("fc {} is not writable", fc));
+    }
+    Ok(Binding::Write {
+        name: object_name(reference, &fc)?,
+        value_type,
+    })
+}
+
+// ============================================================================
+// Runtime
+// ============================================================================
+
+/// IEC 61850 MMS client channel
+pub struct MmsRuntime {
+    id: u32,
+    name: String,
+    config: MmsConfig,
+    /// Variables read by polls with their internal point IDs
+    reads: Vec<(ObjectName, Vec<u32>)>,
+    controls: HashMap<u32, Binding>,
+    adjustments: HashMap<u32, Binding>,
+    stream: Option<TcpStream>,
+    reader: TpktReader,
+    invoke_id: u32,
+    ctl_num: u8,
+    diagnostics: Diagnostics,
+}
+
+impl MmsRuntime {
+    /// Build the runtime of an `iec61850` channel
+    pub fn from_runtime_config(runtime_config: &RuntimeChannelConfig) -> Result<Self> {
+        let channel_id = runtime_config.id();
+        let config = MmsConfig::from_parameters(&runtime_config.base.parameters)
+            .map_err(|e| ComSrvError::ConfigError(format!("Ch{}: {}", channel_id, e)))?;
+
+        let mut runtime = Self {
+            id: channel_id,
+            name: runtime_config.name().to_string(),
+            config,
+            reads: Vec::new(),
+            controls: HashMap::new(),
+            adjustments: HashMap::new(),
+            stream: None,
+            reader: TpktReader::new(),
+            invoke_id: 0,
+            ctl_num: 0,
+            diagnostics: Diagnostics::new(PROTOCOL),
+        };
+
+        let mut read_index: HashMap<ObjectName, usize> = HashMap::new();
+        for (point_type, point) in runtime_config.points() {
+            let binding = match parse_binding(point_type, &point_mapping(point)) {
+                Ok(binding) => binding,
+                Err(e) => {
+                    warn!(
+                        "Ch{} {}{} skipped: {}",
+                        channel_id,
+                        point_type.as_str(),
+                        point.point_id,
+                        e
+                    );
+                    continue;
+                },
+            };
+            match (binding, point_type) {
+                (Binding::Read(name), _) => {
+                    let internal_id = point_type.to_internal_id(point.point_id);
+                    match read_index.get(&name) {
+                        Some(&index) => runtime.reads[index].1.push(internal_id),
+                        None => {
+                            read_index.insert(name.clone(), runtime.reads.len());
+                            runtime.reads.push((name, vec![internal_id]));
+                        },
+                    }
+                },
+                (binding, PointType::Control) => {
+                    runtime.controls.insert(point.point_id, binding);
+                },
+                (binding, _) => {
+                    runtime.adjustments.insert(point.point_id, binding);
+                },
+            }
+        }
+        debug!(
+            "Ch{} IEC 61850 {}:{} points: {} variables, {} controls, {} adjustments",
+            channel_id,
+            runtime.config.host,
+            runtime.config.port,
+            runtime.reads.len(),
+            runtime.controls.len(),
+            runtime.adjustments.len()
+        );
+        Ok(runtime)
+    }
+
+    /// Record an error; connection failures drop the stream so the next
+    /// `connect()` associates again
+    fn fail(&mut self, error: GatewayError) -> GatewayError {
+        if record_failure(&mut self.diagnostics, &error) {
+            self.stream = None;
+        }
+        error
+    }
+
+    async fn send(&mut self, bytes: &[u8]) -> igw::Result<()> {
+        let stream = self.stream.as_mut().ok_or(GatewayError::NotConnected)?;
+        stream
+            .write_all(bytes)
+            .await
+            .map_err(|e| GatewayError::Connection(format!("send: {}", e)))
+    }
+
+    async fn receive(&mut self, deadline: Instant) -> igw::Result<Tpdu> {
+        let mut buf = [0u8; 4096];
+        loop {
+            match self.reader.next_tpdu() {
+                Some(Ok(Tpdu::Disconnect(reason))) => {
+                    return Err(GatewayError::Connection(format!("server sent {}", reason)))
+                },
+                Some(Ok(tpdu)) => return Ok(tpdu),
+                Some(Err(e)) => return Err(GatewayError::Connection(e.to_string())),
+                None => {},
+            }
+            let timeout_ms = self.config.response_timeout.as_millis() as u64;
+            let stream = self.stream.as_mut().ok_or(GatewayError::NotConnected)?;
+            let n = timeout_at(deadline, stream.read(&mut buf))
+                .await
+                .map_err(|_| GatewayError::ConnectionTimeout(timeout_ms))?
+                .map_err(|e| GatewayError::Connection(format!("receive: {}", e)))?;
+            if n == 0 {
+                return Err(GatewayError::Connection(
+                    "server closed the connection".to_string(),
+                ));
+            }
+            self.reader.push(&buf[..n]);
+        }
+    }
+
+    /// Next MMS PDU from the server
+    async fn receive_pdu(&mut self, deadline: Instant) -> igw::Result<Pdu> {
+        let spdu = match self.receive(deadline).await? {
+            Tpdu::Data(spdu) => spdu,
+            other => {
+                return Err(GatewayError::InvalidResponse(format!(
+                    "unexpected {:?}",
+                    other
+                )))
+            },
+        };
+        if mms::is_release(&spdu) {
+            let reason = mms::mms_pdu(&spdu)
+                .err()
+                .map(|e| e.to_string())
+                .unwrap_or_default();
+            return Err(GatewayError::Connection(reason));
+        }
+        mms::mms_pdu(&spdu)
+            .and_then(mms::parse_pdu)
+            .map_err(|e| GatewayError::InvalidResponse(e.to_string()))
+    }
+
+    /// COTP connection and MMS association on a fresh stream
+    async fn associate(&mut self) -> igw::Result<()> {
+        let deadline = Instant::now() + self.config.response_timeout;
+        self.send(&mms::connect_request()).await?;
+        match self.receive(deadline).await? {
+            Tpdu::ConnectConfirm => {},
+            other => {
+                return Err(GatewayError::Connection(format!(
+                    "expected COTP connect confirm, got {:?}",
+                    other
+                )))
+            },
+        }
+        self.send(&mms::data_packets(&mms::associate_request()))
+            .await?;
+        let spdu = match self.receive(deadline).await? {
+            Tpdu::Data(spdu) => spdu,
+            other => {
+                return Err(GatewayError::Connection(format!(
+                    "unexpected {:?} during association",
+                    other
+                )))
+            },
+        };
+        match mms::mms_pdu(&spdu).and_then(mms::parse_pdu) {
+            Ok(Pdu::InitiateResponse) => Ok(()),
+            Ok(Pdu::Error { message, .. }) => Err(GatewayError::Connection(message)),
+            Ok(other) => Err(GatewayError::Connection(format!(
+                "unexpected {:?} during association",
+                other
+            ))),
+            Err(e) => Err(GatewayError::Connection(e.to_string())),
+        }
+    }
+
+    /// Send a confirmed request and wait for its response
+    async fn call(&mut self, build: impl FnOnce(u32) -> Vec<u8>) -> igw::Result<ResponseBody> {
+        self.invoke_id = self.invoke_id.wrapping_add(1);
+        let invoke_id = self.invoke_id;
+        let spdu = mms::data_request(&build(invoke_id));
+        self.send(&mms::data_packets(&spdu)).await?;
+
+        let deadline = Instant::now() + self.config.response_timeout;
+        loop {
+            match self.receive_pdu(deadline).await? {
+                Pdu::Response {
+                    invoke_id: id,
+                    body,
+                } if id == invoke_id => return Ok(body),
+                Pdu::Error {
+                    invoke_id: Some(id),
+                    message,
+                } if id == invoke_id => return Err(GatewayError::Protocol(message)),
+                Pdu::Error {
+                    invoke_id: None,
+                    message,
+                } => return Err(GatewayError::Protocol(message)),
+                other => debug!("Ch{} ignored MMS PDU {:?}", self.id, other),
+            }
+        }
+    }
+
+    async fn read(&mut self, names: &[ObjectName]) -> igw::Result<Vec<AccessResult>> {
+        match self.call(|id| mms::read_request(id, names)).await? {
+            ResponseBody::Read(results) if results.len() == names.len() => Ok(results),
+            ResponseBody::Read(results) => Err(GatewayError::InvalidResponse(format!(
+                "{} results for {} variables",
+                results.len(),
+                names.len()
+            ))),
+            other => Err(GatewayError::InvalidResponse(format!(
+                "unexpected response {:?}",
+                other
+            ))),
+        }
+    }
+
+    async fn write_variable(&mut self, name: &ObjectName, data: &MmsData) -> igw::Result<()> {
+        match self.call(|id| mms::write_request(id, name, data)).await? {
+            ResponseBody::Write(results) => match results.first() {
+                Some(None) => Ok(()),
+                Some(Some(code)) => Err(GatewayError::Protocol(format!(
+                    "write {} failed: {}",
+                    name,
+                    mms::access_error_name(*code)
+                ))),
+                None => Err(GatewayError::InvalidResponse(
+                    "empty write response".to_string(),
+                )),
+            },
+            other => Err(GatewayError::InvalidResponse(format!(
+                "unexpected response {:?}",
+                other
+            ))),
+        }
+    }
+
+    async fn operate(&mut self, binding: &Binding, value: f64) -> igw::Result<()> {
+        match binding {
+            Binding::Control {
+                oper,
+                select,
+                value_type,
+            } => {
+                if let Some(select) = select {
+                    let selected = self.read(std::slice::from_ref(select)).await?;
+                    match selected.first() {
+                        Some(AccessResult::Success(MmsData::VisibleString(s))) if !s.is_empty() => {
+                        },
+                        _ => {
+                            return Err(GatewayError::Protocol(format!(
+                                "select of {} refused",
+                                select
+                            )))
+                        },
+                    }
+                }
+                self.ctl_num = self.ctl_num.wrapping_add(1);
+                let oper_value = MmsData::Structure(vec![
+                    value_type.data(value),
+                    MmsData::Structure(vec![
+                        MmsData::Integer(self.config.originator_category),
+                        MmsData::OctetString(self.config.originator.as_bytes().to_vec()),
+                    ]),
+                    MmsData::Unsigned(self.ctl_num as u64),
+                    MmsData::utc_now(),
+                    MmsData::Boolean(false), // Test
+                    MmsData::BitString {
+                        bits: 2,
+                        bytes: vec![0],
+                    }, // Check
+                ]);
+                self.write_variable(oper, &oper_value).await
+            },
+            Binding::Write { name, value_type } => {
+                self.write_variable(name, &value_type.data(value)).await
+            },
+            Binding::Read(name) => Err(GatewayError::Protocol(format!("{} is not writable", name))),
+        }
+    }
+
+    async fn write(&mut self, point_type: PointType, values: &[(u32, f64)]) -> igw::Result<usize> {
+        let mut written = 0;
+        let mut last_error = None;
+        for &(internal_id, value) in values {
+            let point_id = PointType::from_internal_id(internal_id).1;
+            let bindings = match point_type {
+                PointType::Control => &self.controls,
+                _ => &self.adjustments,
+            };
+            let Some(binding) = bindings.get(&point_id).cloned() else {
+                last_error = Some(GatewayError::PointNotFound(format!(
+                    "{}{}",
+                    point_type.as_str(),
+                    point_id
+                )));
+                continue;
+            };
+            match self.operate(&binding, value).await {
+                Ok(()) => written += 1,
+                Err(e) => {
+                    let e = self.fail(e);
+                    if self.stream.is_none() {
+                        return Err(e);
+                    }
+                    last_error = Some(e);
+                },
+            }
+        }
+        self.diagnostics.write_count += written as u64;
+        match last_error {
+            Some(e) if written == 0 => Err(e),
+            _ => Ok(written),
+        }
+    }
+}
+
+#[async_trait]
+impl ChannelRuntime for MmsRuntime {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn protocol(&self) -> &str {
+        PROTOCOL
+    }
+
+    fn is_event_driven(&self) -> bool {
+        false
+    }
+
+    async fn connect(&mut self) -> igw::Result<()> {
+        self.diagnostics.connection_state = ConnectionState::Connecting;
+        let address = format!("{}:{}", self.config.host, self.config.port);
+        let timeout_ms = self.config.response_timeout.as_millis() as u64;
+        let connected =
+            tokio::time::timeout(self.config.response_timeout, TcpStream::connect(&address)).await;
+        let stream = match connected {
+            Ok(Ok(stream)) => stream,
+            Ok(Err(e)) => {
+                return Err(self.fail(GatewayError::Connection(format!("{}: {}", address, e))))
+            },
+            Err(_) => return Err(self.fail(GatewayError::ConnectionTimeout(timeout_ms))),
+        };
+        let _ = stream.set_nodelay(true);
+        self.stream = Some(stream);
+        self.reader.clear();
+        if let Err(e) = self.associate().await {
+            let e = match e {
+                GatewayError::ConnectionTimeout(_) | GatewayError::Connection(_) => e,
+                other => GatewayError::Connection(other.to_string()),
+            };
+            return Err(self.fail(e));
+        }
+        self.diagnostics.connection_state = ConnectionState::Connected;
+        info!("Ch{} IEC 61850 associated with {}", self.id, address);
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> igw::Result<()> {
+        if let Some(mut stream) = self.stream.take() {
+            let _ = stream.shutdown().await;
+        }
+        self.diagnostics.connection_state = ConnectionState::Disconnected;
+        Ok(())
+    }
+
+    async fn poll_once(&mut self) -> PollResult {
+        if self.stream.is_none() {
+            return PollResult::failed(vec![PointFailure::new(0, "not connected")]);
+        }
+        let mut batch = DataBatch::default();
+        let mut failures = Vec::new();
+        let batches: Vec<Vec<(ObjectName, Vec<u32>)>> = self
+            .reads
+            .chunks(self.config.max_read_batch)
+            .map(|chunk| chunk.to_vec())
+            .collect();
+
+        for chunk in batches {
+            let names: Vec<ObjectName> = chunk.iter().map(|(name, _)| name.clone()).collect();
+            let results = match self.read(&names).await {
+                Ok(results) => results,
+                Err(e) => {
+                    let e = self.fail(e);
+                    if self.stream.is_none() {
+                        failures.push(PointFailure::with_error(0, e.to_string()));
+                        break;
+                    }
+                    for (_, ids) in &chunk {
+                        failures.extend(
+                            ids.iter()
+                                .map(|&id| PointFailure::with_error(id, e.to_string())),
+                        );
+                    }
+                    continue;
+                },
+            };
+            for ((name, ids), result) in chunk.iter().zip(results) {
+                let value = match result {
+                    AccessResult::Success(data) => data
+                        .as_f64()
+                        .ok_or_else(|| format!("{}: no numeric value in {:?}", name, data)),
+                    AccessResult::Failure(code) => {
+                        Err(format!("{}: {}", name, mms::access_error_name(code)))
+                    },
+                };
+                match value {
+                    Ok(value) => {
+                        for &id in ids {
+                            batch.add(DataPoint::new(id, value));
+                        }
+                    },
+                    Err(e) => failures.extend(
+                        ids.iter()
+                            .map(|&id| PointFailure::with_error(id, e.clone())),
+                    ),
+                }
+            }
+        }
+        self.diagnostics.read_count += 1;
+        PollResult::partial(batch, failures)
+    }
+
+    async fn write_control(&mut self, commands: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Control, commands).await
+    }
+
+    async fn write_adjustment(&mut self, adjustments: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Adjustment, adjustments).await
+    }
+
+    fn subscribe(&self) -> Option<DataEventReceiver> {
+        None
+    }
+
+    async fn start_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn stop_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn diagnostics(&self) -> igw::Result<Diagnostics> {
+        Ok(self.diagnostics.clone())
+    }
+}
+
+// ============================================================================
+// Plugin descriptor
+// ============================================================================
+
+fn parameters() -> Vec<ParameterMetadata> {
+    vec![
+        ParameterMetadata::required("host", "Host", "IED IP address", ParameterType::String),
+        ParameterMetadata::optional(
+            "port",
+            "Port",
+            "ISO-on-TCP port",
+            ParameterType::Integer,
+            json!(DEFAULT_PORT),
+        ),
+        ParameterMetadata::optional(
+            "response_timeout_ms",
+            "Response Timeout (ms)",
+            "Time to wait for association and service responses",
+            ParameterType::Integer,
+            json!(DEFAULT_RESPONSE_TIMEOUT_MS),
+        ),
+        ParameterMetadata::optional(
+            "max_read_batch",
+            "Max Read Batch",
+            "Variables per MMS Read request",
+            ParameterType::Integer,
+            json!(DEFAULT_MAX_READ_BATCH),
+        ),
+        ParameterMetadata::optional(
+            "originator",
+            "Originator",
+            "orIdent sent with controls",
+            ParameterType::String,
+            json!(DEFAULT_ORIGINATOR),
+        ),
+        ParameterMetadata::optional(
+            "originator_category",
+            "Originator Category",
+            "orCat sent with controls (2 = station, 3 = remote)",
+            ParameterType::Integer,
+            json!(DEFAULT_ORIGINATOR_CATEGORY),
+        ),
+        ParameterMetadata::optional(
+            "poll_interval_ms",
+            "Poll Interval (ms)",
+            "Read cycle interval",
+            ParameterType::Integer,
+            json!(1000),
+        ),
+    ]
+}
+
+crate::protocol_plugin! {
+    /// IEC 61850 MMS client (in-tree runtime)
+    pub struct MmsPlugin {
+        name: PROTOCOL,
+        aliases: &["iec_61850", "mms"],
+        display_name: "IEC 61850 (MMS)",
+        description: "IEC 61850 client reading data attributes and operating controls over MMS",
+        parameters: parameters(),
+        mapping_columns: &[
+            MappingColumn::string("reference", "Object reference LD/LN.DO[.DA]").required(),
+            MappingColumn::string("fc", "Functional constraint")
+                .required()
+                .choices(&["MX", "ST", "CO", "SP", "SV", "CF", "DC", "SG", "SE", "EX", "BL"]),
+            MappingColumn::string("value_type", "Type of written values")
+                .choices(&["boolean", "int", "uint", "float32", "float64"]),
+            MappingColumn::string("ctl_model", "Control model of CO points")
+                .choices(&["direct", "sbo"])
+                .default_value("direct"),
+        ],
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn point<T: serde::de::DeserializeOwned>(point_id: u32, mapping: JsonValue) -> T {
+        serde_json::from_value(json!({
+            "point_id": point_id,
+            "signal_name": format!("p{}", point_id),
+            "protocol_mappings": mapping.to_string(),
+        }))
+        .unwrap()
+    }
+
+    fn runtime_config(port: u16) -> RuntimeChannelConfig {
+        let mut config = RuntimeChannelConfig::from_base(
+            serde_json::from_value(json!({
+                "id": 8,
+                "name": "relay",
+                "protocol": PROTOCOL,
+                "parameters": {"host": "127.0.0.1", "port": port, "response_timeout_ms": 2000},
+            }))
+            .unwrap(),
+        );
+        config.telemetry_points = vec![
+            point(
+                1,
+                json!({"reference": "RELAY1CTRL/MMXU1.TotW.mag.f", "fc": "MX"}),
+            ),
+            point(
+                2,
+                json!({"reference": "RELAY1CTRL/MMXU1.Hz.mag.f", "fc": "mx"}),
+            ),
+        ];
+        config.signal_points = vec![
+            point(
+                1,
+                json!({"reference": "RELAY1CTRL/Q0CSWI1.Pos.stVal", "fc": "ST"}),
+            ),
+            point(
+                2,
+                json!({"reference": "RELAY1CTRL/Q0CSWI1.Pos", "fc": "CO"}),
+            ),
+        ];
+        config.control_points = vec![point(
+            1,
+            json!({"reference": "RELAY1CTRL/Q0CSWI1.Pos", "fc": "CO"}),
+        )];
+        config
+    }
+
+    #[test]
+    fn test_object_names_and_bindings() {
+        let name = object_name("IED1LD0/MMXU1.TotW.mag.f", "MX").unwrap();
+        assert_eq!(name.domain, "IED1LD0");
+        assert_eq!(name.item, "MMXU1$MX$TotW$mag$f");
+        assert!(object_name("MMXU1.TotW", "MX").is_err());
+        assert!(object_name("LD/MMXU1", "MX").is_err());
+        assert!(object_name("LD/MMXU1..mag", "MX").is_err());
+
+        let control = parse_binding(
+            PointType::Control,
+            &json!({"reference": "LD/CSWI1.Pos.Oper.ctlVal", "fc": "CO", "ctl_model": "sbo"}),
+        )
+        .unwrap();
+        assert_eq!(
+            control,
+            Binding::Control {
+                oper: object_name("LD/CSWI1.Pos.Oper", "CO").unwrap(),
+                select: Some(object_name("LD/CSWI1.Pos.SBO", "CO").unwrap()),
+                value_type: ValueType::Boolean,
+            }
+        );
+        assert_eq!(
+            parse_binding(
+                PointType::Adjustment,
+                &json!({"reference": "LD/ZINV1.WSpt.setMag.f", "fc": "SP"})
+            ),
+            Ok(Binding::Write {
+                name: object_name("LD/ZINV1.WSpt.setMag.f", "SP").unwrap(),
+                value_type: ValueType::Float32,
+            })
+        );
+        assert!(parse_binding(
+            PointType::Telemetry,
+            &json!({"reference": "LD/X1.Y", "fc": "CO"})
+        )
+        .is_err());
+        assert!(parse_binding(
+            PointType::Adjustment,
+            &json!({"reference": "LD/X1.Y", "fc": "MX"})
+        )
+        .is_err());
+    }
+
+    /// Server answering one request per call
+    struct Server {
+        stream: TcpStream,
+        reader: TpktReader,
+    }
+
+    impl Server {
+        async fn receive(&mut self) -> Tpdu {
+            let mut buf = [0u8; 2048];
+            loop {
+                if let Some(tpdu) = self.reader.next_tpdu() {
+                    return tpdu.unwrap();
+                }
+                let n = self.stream.read(&mut buf).await.unwrap();
+                assert!(n > 0, "client closed the connection");
+                self.reader.push(&buf[..n]);
+            }
+        }
+
+        /// Next MMS request as (invoke ID, service element)
+        async fn request(&mut self) -> (u8, Vec<u8>) {
+            let Tpdu::Data(spdu) = self.receive().await else {
+                panic!("expected data");
+            };
+            let pdu = mms::mms_pdu(&spdu).unwrap();
+            let (request, _) = mms::read_tlv(pdu).unwrap();
+            assert_eq!(request.tag, 0xA0);
+            let elements = mms::children(request.content).unwrap();
+            let service = mms::tlv(elements[1].tag, elements[1].content);
+            (elements[0].content[0], service)
+        }
+
+        async fn respond(&mut self, invoke_id: u8, service: Vec<u8>) {
+            let pdu = mms::tlv(0xA1, &[mms::tlv(0x02, &[invoke_id]), service].concat());
+            let packets = mms::data_packets(&mms::data_request(&pdu));
+            self.stream.write_all(&packets).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_poll_and_operate_against_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut client = MmsRuntime::from_runtime_config(&runtime_config(port)).unwrap();
+        // The CO mapping on a signal point is rejected, the rest is kept
+        assert_eq!(client.reads.len(), 3);
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut server = Server {
+                stream,
+                reader: TpktReader::new(),
+            };
+
+            // COTP connect, then association
+            assert_eq!(server.receive().await, Tpdu::ConnectRequest);
+            server
+                .stream
+                .write_all(&[3, 0, 0, 11, 6, 0xD0, 0, 1, 0, 1, 0])
+                .await
+                .unwrap();
+            let Tpdu::Data(connect) = server.receive().await else {
+                panic!("expected CONNECT");
+            };
+            assert_eq!(connect[0], mms::SPDU_CONNECT);
+            let accept = mms::associate_response(&mms::tlv(0xA9, &mms::tlv(0x80, &[0x10])));
+            server
+                .stream
+                .write_all(&mms::data_packets(&accept))
+                .await
+                .unwrap();
+
+            // Read of three variables: TotW, Hz (not found) and Pos.stVal
+            let (invoke_id, read) = server.request().await;
+            assert_eq!(read[0], 0xA4);
+            assert!(read.windows(19).any(|w| w == b"MMXU1$MX$TotW$mag$f"));
+            let results = [
+                MmsData::Float32(-120.5).encode(),
+                mms::tlv(0x80, &[10]),
+                MmsData::BitString {
+                    bits: 2,
+                    bytes: vec![0x80],
+                }
+                .encode(),
+            ]
+            .concat();
+            server
+                .respond(invoke_id, mms::tlv(0xA4, &mms::tlv(0xA1, &results)))
+                .await;
+
+            // Direct operate of Pos
+            let (invoke_id, write) = server.request().await;
+            assert_eq!(write[0], 0xA5);
+            assert!(write.windows(17).any(|w| w == b"Q0CSWI1$CO$Pos$Op"));
+            server
+                .respond(invoke_id, mms::tlv(0xA5, &mms::tlv(0x81, &[])))
+                .await;
+        });
+
+        client.connect().await.unwrap();
+        let result = client.poll_once().await;
+        let points: HashMap<u32, f64> = result
+            .data
+            .iter()
+            .map(|p| (p.id, p.value.as_f64().unwrap()))
+            .collect();
+        assert_eq!(points[&PointType::Telemetry.to_internal_id(1)], -120.5);
+        assert_eq!(points[&PointType::Signal.to_internal_id(1)], 1.0);
+        assert_eq!(result.failures.len(), 1);
+        assert_eq!(
+            result.failures[0].point_id,
+            PointType::Telemetry.to_internal_id(2)
+        );
+
+        let written = client
+            .write_control(&[(PointType::Control.to_internal_id(1), 1.0)])
+            .await
+            .unwrap();
+        assert_eq!(written, 1);
+        server.await.unwrap();
+
+        // The server hung up: the next poll fails and drops the stream
+        let result = client.poll_once().await;
+        assert!(result.has_failures());
+        assert!(client.stream.is_none());
+    }
+}
@@ -0,0 +1,1132 @@
+//! ISO stack and MMS encoding for an IEC 61850 client
+//!
+//! Only what a polling client needs: RFC 1006 TPKT framing, ISO 8073 class 0
+//! transport, the kernel functional units of the ISO session and presentation
+//! layers, the ACSE association, and the MMS Initiate, Read and Write
+//! services. Every PDU used here is BER with single-octet tags.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::core::protocols::{error, CodecError};
+
+/// TPKT version octet
+const TPKT_VERSION: u8 = 3;
+
+/// COTP TPDU codes
+const COTP_CR: u8 = 0xE0;
+const COTP_CC: u8 = 0xD0;
+const COTP_DR: u8 = 0x80;
+const COTP_ER: u8 = 0x70;
+const COTP_DT: u8 = 0xF0;
+const COTP_EOT: u8 = 0x80;
+
+/// Negotiated TPDU size (code 0x0A = 1024 octets)
+const TPDU_SIZE_CODE: u8 = 0x0A;
+const TPDU_SIZE: usize = 1024;
+
+/// Session SPDU identifiers
+pub const SPDU_DATA: u8 = 0x01;
+pub const SPDU_FINISH: u8 = 0x09;
+pub const SPDU_REFUSE: u8 = 0x0C;
+pub const SPDU_CONNECT: u8 = 0x0D;
+pub const SPDU_ACCEPT: u8 = 0x0E;
+pub const SPDU_ABORT: u8 = 0x19;
+
+/// Presentation context identifiers
+const ACSE_CONTEXT_ID: u8 = 1;
+const MMS_CONTEXT_ID: u8 = 3;
+
+/// Abstract syntax of ACSE (2.2.1.0.1)
+const ACSE_SYNTAX: &[u8] = &[0x52, 0x01, 0x00, 0x01];
+/// Abstract syntax of MMS (1.0.9506.2.1)
+const MMS_SYNTAX: &[u8] = &[0x28, 0xCA, 0x22, 0x02, 0x01];
+/// Basic encoding rules transfer syntax (2.1.1)
+const BER_SYNTAX: &[u8] = &[0x51, 0x01];
+/// MMS application context name (1.0.9506.2.3)
+const MMS_CONTEXT_NAME: &[u8] = &[0x28, 0xCA, 0x22, 0x02, 0x03];
+
+/// Session and presentation selectors (defaults of IEC 61850 servers)
+const SESSION_SELECTOR: &[u8] = &[0x00, 0x01];
+const PRESENTATION_SELECTOR: &[u8] = &[0x00, 0x00, 0x00, 0x01];
+
+/// MMS PDU size proposed in the Initiate request
+pub const MAX_PDU_SIZE: u32 = 65000;
+
+/// Services supported by this client: read, write, getNameList,
+/// getVariableAccessAttributes, informationReport, conclude
+const SERVICES_SUPPORTED: [u8; 11] = [
+    0xEE, 0x1C, 0x00, 0x00, 0x04, 0x08, 0x00, 0x00, 0x79, 0xEF, 0x18,
+];
+
+// ============================================================================
+// BER
+// ============================================================================
+
+fn encode_length(len: usize, out: &mut Vec<u8>) {
+    if len < 0x80 {
+        out.push(len as u8);
+    } else if len <= 0xFF {
+        out.extend_from_slice(&[0x81, len as u8]);
+    } else if len <= 0xFFFF {
+        out.push(0x82);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        out.push(0x83);
+        out.extend_from_slice(&(len as u32).to_be_bytes()[1..]);
+    }
+}
+
+/// Encode one BER element
+pub fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(content.len() + 4);
+    out.push(tag);
+    encode_length(content.len(), &mut out);
+    out.extend_from_slice(content);
+    out
+}
+
+/// One decoded BER element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tlv<'a> {
+    pub tag: u8,
+    pub content: &'a [u8],
+}
+
+/// Decode the first BER element of `buf` and return it with the rest
+pub fn read_tlv(buf: &[u8]) -> Result<(Tlv<'_>, &[u8]), CodecError> {
+    let (&tag, rest) = buf
+        .split_first()
+        .ok_or_else(|| error("truncated BER element"))?;
+    if tag & 0x1F == 0x1F {
+        return Err(error(format!("multi-octet BER tag 0x{:02X}", tag)));
+    }
+    let (&first, rest) = rest
+        .split_first()
+        .ok_or_else(|| error("truncated BER length"))?;
+    let (len, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let n = (first & 0x7F) as usize;
+        if n == 0 || n > 4 || rest.len() < n {
+            return Err(error(format!(
+                "unsupported BER length of tag 0x{:02X}",
+                tag
+            )));
+        }
+        let len = rest[..n]
+            .iter()
+            .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+        (len, &rest[n..])
+    };
+    if rest.len() < len {
+        return Err(error(format!("BER element 0x{:02X} truncated", tag)));
+    }
+    Ok((
+        Tlv {
+            tag,
+            content: &rest[..len],
+        },
+        &rest[len..],
+    ))
+}
+
+/// All elements of a constructed element's content
+pub fn children(content: &[u8]) -> Result<Vec<Tlv<'_>>, CodecError> {
+    let mut elements = Vec::new();
+    let mut rest = content;
+    while !rest.is_empty() {
+        let (element, next) = read_tlv(rest)?;
+        elements.push(element);
+        rest = next;
+    }
+    Ok(elements)
+}
+
+/// Content of the first child with `tag`
+fn child(content: &[u8], tag: u8) -> Result<&[u8], CodecError> {
+    children(content)?
+        .into_iter()
+        .find(|e| e.tag == tag)
+        .map(|e| e.content)
+        .ok_or_else(|| error(format!("missing BER element 0x{:02X}", tag)))
+}
+
+fn encode_integer(value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let mut start = 0;
+    while start < 7
+        && ((bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xFF && bytes[start + 1] & 0x80 != 0))
+    {
+        start += 1;
+    }
+    bytes[start..].to_vec()
+}
+
+fn encode_unsigned(value: u64) -> Vec<u8> {
+    let mut bytes = vec![0];
+    bytes.extend_from_slice(&value.to_be_bytes());
+    let mut start = 0;
+    while start < 8 && bytes[start] == 0 && bytes[start + 1] & 0x80 == 0 {
+        start += 1;
+    }
+    bytes.split_off(start)
+}
+
+fn decode_integer(content: &[u8]) -> Result<i64, CodecError> {
+    if content.is_empty() || content.len() > 8 {
+        return Err(error(format!("bad INTEGER length {}", content.len())));
+    }
+    let init = if content[0] & 0x80 != 0 { -1i64 } else { 0 };
+    Ok(content.iter().fold(init, |acc, b| (acc << 8) | *b as i64))
+}
+
+fn decode_unsigned(content: &[u8]) -> Result<u64, CodecError> {
+    let content = match content {
+        [0, rest @ ..] if !rest.is_empty() => rest,
+        _ => content,
+    };
+    if content.is_empty() || content.len() > 8 {
+        return Err(error(format!("bad Unsigned length {}", content.len())));
+    }
+    Ok(content.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64))
+}
+
+// ============================================================================
+// TPKT / COTP
+// ============================================================================
+
+fn tpkt(cotp: &[u8]) -> Vec<u8> {
+    let len = (cotp.len() + 4) as u16;
+    let mut packet = vec![TPKT_VERSION, 0];
+    packet.extend_from_slice(&len.to_be_bytes());
+    packet.extend_from_slice(cotp);
+    packet
+}
+
+/// COTP connection request (class 0, TPDU size 1024, TSEL 0001 both ways)
+pub fn connect_request() -> Vec<u8> {
+    tpkt(&[
+        0x11,
+        COTP_CR,
+        0x00,
+        0x00, // destination reference
+        0x00,
+        0x01, // source reference
+        0x00, // class 0
+        0xC0,
+        0x01,
+        TPDU_SIZE_CODE,
+        0xC1,
+        0x02,
+        0x00,
+        0x01,
+        0xC2,
+        0x02,
+        0x00,
+        0x01,
+    ])
+}
+
+/// Data TPDUs carrying one SPDU, segmented to the TPDU size
+pub fn data_packets(spdu: &[u8]) -> Vec<u8> {
+    let max_data = TPDU_SIZE - 3;
+    let mut packets = Vec::with_capacity(spdu.len() + 8);
+    let mut chunks = spdu.chunks(max_data).peekable();
+    if chunks.peek().is_none() {
+        packets.extend(tpkt(&[0x02, COTP_DT, COTP_EOT]));
+    }
+    while let Some(chunk) = chunks.next() {
+        let eot = if chunks.peek().is_none() { COTP_EOT } else { 0 };
+        let mut dt = vec![0x02, COTP_DT, eot];
+        dt.extend_from_slice(chunk);
+        packets.extend(tpkt(&dt));
+    }
+    packets
+}
+
+/// Transport data unit received from the server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Tpdu {
+    ConnectRequest,
+    ConnectConfirm,
+    /// One complete SPDU (segments up to EOT joined)
+    Data(Vec<u8>),
+    /// Disconnect request or TPDU error
+    Disconnect(String),
+}
+
+/// Splits a byte stream into TPKT packets and reassembles COTP data
+#[derive(Debug, Default)]
+pub struct TpktReader {
+    buf: Vec<u8>,
+    data: Vec<u8>,
+}
+
+impl TpktReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    pub fn clear(&mut self) {
+        self.buf.clear();
+        self.data.clear();
+    }
+
+    /// Next complete TPDU, `None` until enough bytes arrived
+    ///
+    /// A malformed packet clears the reader, as the stream cannot be
+    /// resynchronized.
+    pub fn next_tpdu(&mut self) -> Option<Result<Tpdu, CodecError>> {
+        loop {
+            if self.buf.len() < 4 {
+                return None;
+            }
+            let len = u16::from_be_bytes([self.buf[2], self.buf[3]]) as usize;
+            if self.buf[0] != TPKT_VERSION || len < 7 {
+                self.clear();
+                return Some(Err(error("bad TPKT header")));
+            }
+            if self.buf.len() < len {
+                return None;
+            }
+            let packet: Vec<u8> = self.buf.drain(..len).collect();
+            let cotp = &packet[4..];
+            let header_len = cotp[0] as usize;
+            if header_len < 1 || cotp.len() < header_len + 1 {
+                self.clear();
+                return Some(Err(error("bad COTP header")));
+            }
+            match cotp[1] & 0xF0 {
+                COTP_CR => return Some(Ok(Tpdu::ConnectRequest)),
+                COTP_CC => return Some(Ok(Tpdu::ConnectConfirm)),
+                COTP_DT if header_len >= 2 => {
+                    self.data.extend_from_slice(&cotp[header_len + 1..]);
+                    if cotp[2] & COTP_EOT != 0 {
+                        return Some(Ok(Tpdu::Data(std::mem::take(&mut self.data))));
+                    }
+                },
+                COTP_DR => return Some(Ok(Tpdu::Disconnect("disconnect request".to_string()))),
+                COTP_ER => return Some(Ok(Tpdu::Disconnect("TPDU error".to_string()))),
+                _ => {},
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Session / presentation / ACSE
+// ============================================================================
+
+fn session_length(len: usize, out: &mut Vec<u8>) {
+    if len < 0xFF {
+        out.push(len as u8);
+    } else {
+        out.push(0xFF);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    }
+}
+
+fn read_session_length(buf: &[u8]) -> Result<(usize, &[u8]), CodecError> {
+    match buf {
+        [0xFF, hi, lo, rest @ ..] => Ok((u16::from_be_bytes([*hi, *lo]) as usize, rest)),
+        [len, rest @ ..] if *len != 0xFF => Ok((*len as usize, rest)),
+        _ => Err(error("truncated SPDU length")),
+    }
+}
+
+/// Presentation data value in the given context
+fn presentation_data(context_id: u8, value: &[u8]) -> Vec<u8> {
+    tlv(
+        0x61,
+        &tlv(0x30, &[tlv(0x02, &[context_id]), tlv(0xA0, value)].concat()),
+    )
+}
+
+fn presentation_context(id: u8, abstract_syntax: &[u8]) -> Vec<u8> {
+    tlv(
+        0x30,
+        &[
+            tlv(0x02, &[id]),
+            tlv(0x06, abstract_syntax),
+            tlv(0x30, &tlv(0x06, BER_SYNTAX)),
+        ]
+        .concat(),
+    )
+}
+
+/// Session CONNECT carrying presentation CP, ACSE AARQ and MMS Initiate
+pub fn associate_request() -> Vec<u8> {
+    let initiate = tlv(
+        0xA8,
+        &[
+            tlv(0x80, &encode_integer(MAX_PDU_SIZE as i64)),
+            tlv(0x81, &[5]),  // max outstanding calling
+            tlv(0x82, &[5]),  // max outstanding called
+            tlv(0x83, &[10]), // data structure nesting level
+            tlv(
+                0xA4,
+                &[
+                    tlv(0x80, &[1]),                // version
+                    tlv(0x81, &[0x05, 0xF1, 0x00]), // parameter CBB
+                    tlv(0x82, &[&[0x03][..], &SERVICES_SUPPORTED].concat()),
+                ]
+                .concat(),
+            ),
+        ]
+        .concat(),
+    );
+    let aarq = tlv(
+        0x60,
+        &[
+            tlv(0xA1, &tlv(0x06, MMS_CONTEXT_NAME)),
+            tlv(
+                0xBE,
+                &tlv(
+                    0x28,
+                    &[tlv(0x02, &[MMS_CONTEXT_ID]), tlv(0xA0, &initiate)].concat(),
+                ),
+            ),
+        ]
+        .concat(),
+    );
+    let cp = tlv(
+        0x31,
+        &[
+            tlv(0xA0, &tlv(0x80, &[1])), // normal mode
+            tlv(
+                0xA2,
+                &[
+                    tlv(0x81, PRESENTATION_SELECTOR),
+                    tlv(0x82, PRESENTATION_SELECTOR),
+                    tlv(
+                        0xA4,
+                        &[
+                            presentation_context(ACSE_CONTEXT_ID, ACSE_SYNTAX),
+                            presentation_context(MMS_CONTEXT_ID, MMS_SYNTAX),
+                        ]
+                        .concat(),
+                    ),
+                    presentation_data(ACSE_CONTEXT_ID, &aarq),
+                ]
+                .concat(),
+            ),
+        ]
+        .concat(),
+    );
+
+    let mut parameters = vec![
+        0x05, 0x06, 0x13, 0x01, 0x00, 0x16, 0x01, 0x02, // protocol options, version 2
+        0x14, 0x02, 0x00, 0x02, // session requirements: duplex
+        0x33, 0x02, // calling session selector
+    ];
+    parameters.extend_from_slice(SESSION_SELECTOR);
+    parameters.extend_from_slice(&[0x34, 0x02]); // called session selector
+    parameters.extend_from_slice(SESSION_SELECTOR);
+    parameters.push(0xC1);
+    session_length(cp.len(), &mut parameters);
+    parameters.extend_from_slice(&cp);
+
+    let mut spdu = vec![SPDU_CONNECT];
+    session_length(parameters.len(), &mut spdu);
+    spdu.extend_from_slice(&parameters);
+    spdu
+}
+
+/// Session GIVE TOKENS + DATA TRANSFER carrying one MMS PDU
+pub fn data_request(mms: &[u8]) -> Vec<u8> {
+    let mut spdu = vec![SPDU_DATA, 0x00, SPDU_DATA, 0x00];
+    spdu.extend(presentation_data(MMS_CONTEXT_ID, mms));
+    spdu
+}
+
+/// Whether an SPDU ends the association (refuse, finish, abort)
+pub fn is_release(spdu: &[u8]) -> bool {
+    matches!(
+        spdu.first(),
+        Some(&SPDU_REFUSE) | Some(&SPDU_FINISH) | Some(&SPDU_ABORT)
+    )
+}
+
+/// MMS PDU carried by a session ACCEPT or DATA TRANSFER SPDU
+pub fn mms_pdu(spdu: &[u8]) -> Result<&[u8], CodecError> {
+    let (&kind, rest) = spdu.split_first().ok_or_else(|| error("empty SPDU"))?;
+    match kind {
+        SPDU_DATA => {
+            // GIVE TOKENS, then DATA TRANSFER, then user data
+            let (len, rest) = read_session_length(rest)?;
+            let rest = rest.get(len..).ok_or_else(|| error("truncated SPDU"))?;
+            match rest.split_first() {
+                Some((&SPDU_DATA, rest)) => {
+                    let (len, rest) = read_session_length(rest)?;
+                    let user_data = rest.get(len..).ok_or_else(|| error("truncated SPDU"))?;
+                    presentation_value(user_data)
+                },
+                _ => Err(error("expected DATA TRANSFER SPDU")),
+            }
+        },
+        SPDU_ACCEPT => {
+            let (len, rest) = read_session_length(rest)?;
+            let mut parameters = rest.get(..len).ok_or_else(|| error("truncated SPDU"))?;
+            while let Some((&code, rest)) = parameters.split_first() {
+                let (len, rest) = read_session_length(rest)?;
+                let value = rest
+                    .get(..len)
+                    .ok_or_else(|| error("truncated SPDU parameter"))?;
+                if code == 0xC1 {
+                    return acse_value(presentation_value(value)?);
+                }
+                parameters = &rest[len..];
+            }
+            Err(error("ACCEPT SPDU without user data"))
+        },
+        SPDU_REFUSE => Err(error("session connection refused")),
+        SPDU_FINISH => Err(error("server released the association")),
+        SPDU_ABORT => Err(error("server aborted the association")),
+        kind => Err(error(format!("unexpected SPDU 0x{:02X}", kind))),
+    }
+}
+
+/// Value of the first presentation data value of a CPA or TD PPDU
+fn presentation_value(ppdu: &[u8]) -> Result<&[u8], CodecError> {
+    let (top, _) = read_tlv(ppdu)?;
+    let user_data = match top.tag {
+        0x61 => top.content,
+        0x31 => child(child(top.content, 0xA2)?, 0x61)?,
+        tag => return Err(error(format!("unexpected PPDU 0x{:02X}", tag))),
+    };
+    child(child(user_data, 0x30)?, 0xA0)
+}
+
+/// MMS PDU inside an AARE, checking the association result
+fn acse_value(apdu: &[u8]) -> Result<&[u8], CodecError> {
+    let (aare, _) = read_tlv(apdu)?;
+    if aare.tag != 0x61 {
+        return Err(error(format!("unexpected ACSE APDU 0x{:02X}", aare.tag)));
+    }
+    let (result, _) = read_tlv(child(aare.content, 0xA2)?)?;
+    match decode_integer(result.content)? {
+        0 => {},
+        result => {
+            return Err(error(format!(
+                "association rejected by the server (result {})",
+                result
+            )))
+        },
+    }
+    child(child(child(aare.content, 0xBE)?, 0x28)?, 0xA0)
+}
+
+// ============================================================================
+// MMS data
+// ============================================================================
+
+/// MMS Data value
+#[derive(Debug, Clone, PartialEq)]
+pub enum MmsData {
+    Array(Vec<MmsData>),
+    Structure(Vec<MmsData>),
+    Boolean(bool),
+    BitString {
+        bits: usize,
+        bytes: Vec<u8>,
+    },
+    Integer(i64),
+    Unsigned(u64),
+    Float32(f32),
+    Float64(f64),
+    OctetString(Vec<u8>),
+    VisibleString(String),
+    /// Seconds since 1970, binary fraction of a second (24 bits), time quality
+    UtcTime {
+        seconds: u32,
+        fraction: u32,
+        quality: u8,
+    },
+    /// Type this client does not decode (tag kept)
+    Other(u8),
+}
+
+impl MmsData {
+    /// Current time, time quality "10 bits of accuracy"
+    pub fn utc_now() -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Self::UtcTime {
+            seconds: now.as_secs() as u32,
+            fraction: ((now.subsec_nanos() as u64) << 24) as u32 / 1_000_000_000,
+            quality: 0x0A,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let list = |items: &[MmsData]| -> Vec<u8> { items.iter().flat_map(Self::encode).collect() };
+        match self {
+            Self::Array(items) => tlv(0xA1, &list(items)),
+            Self::Structure(items) => tlv(0xA2, &list(items)),
+            Self::Boolean(value) => tlv(0x83, &[*value as u8]),
+            Self::BitString { bits, bytes } => {
+                let unused = (bytes.len() * 8).saturating_sub(*bits).min(7) as u8;
+                tlv(0x84, &[&[unused][..], bytes].concat())
+            },
+            Self::Integer(value) => tlv(0x85, &encode_integer(*value)),
+            Self::Unsigned(value) => tlv(0x86, &encode_unsigned(*value)),
+            Self::Float32(value) => tlv(0x87, &[&[8][..], &value.to_be_bytes()].concat()),
+            Self::Float64(value) => tlv(0x87, &[&[11][..], &value.to_be_bytes()].concat()),
+            Self::OctetString(bytes) => tlv(0x89, bytes),
+            Self::VisibleString(text) => tlv(0x8A, text.as_bytes()),
+            Self::UtcTime {
+                seconds,
+                fraction,
+                quality,
+            } => {
+                let mut content = seconds.to_be_bytes().to_vec();
+                content.extend_from_slice(&fraction.to_be_bytes()[1..]);
+                content.push(*quality);
+                tlv(0x91, &content)
+            },
+            Self::Other(tag) => tlv(*tag, &[]),
+        }
+    }
+
+    pub fn decode(element: Tlv<'_>) -> Result<Self, CodecError> {
+        let content = element.content;
+        let list = |content| -> Result<Vec<MmsData>, CodecError> {
+            children(content)?.into_iter().map(Self::decode).collect()
+        };
+        Ok(match element.tag {
+            0xA1 => Self::Array(list(content)?),
+            0xA2 => Self::Structure(list(content)?),
+            0x83 => Self::Boolean(*content.first().ok_or_else(|| error("empty boolean"))? != 0),
+            0x84 => {
+                let (&unused, bytes) = content
+                    .split_first()
+                    .ok_or_else(|| error("empty bit-string"))?;
+                if unused > 7 {
+                    return Err(error("bad bit-string padding"));
+                }
+                Self::BitString {
+                    bits: (bytes.len() * 8).saturating_sub(unused as usize),
+                    bytes: bytes.to_vec(),
+                }
+            },
+            0x85 => Self::Integer(decode_integer(content)?),
+            0x86 => Self::Unsigned(decode_unsigned(content)?),
+            0x87 => match content {
+                [_, a, b, c, d] => Self::Float32(f32::from_be_bytes([*a, *b, *c, *d])),
+                [_, rest @ ..] if rest.len() == 8 => {
+                    let mut bytes = [0u8; 8];
+                    bytes.copy_from_slice(rest);
+                    Self::Float64(f64::from_be_bytes(bytes))
+                },
+                _ => {
+                    return Err(error(format!(
+                        "bad floating-point length {}",
+                        content.len()
+                    )))
+                },
+            },
+            0x89 => Self::OctetString(content.to_vec()),
+            0x8A | 0x90 => Self::VisibleString(String::from_utf8_lossy(content).into_owned()),
+            0x91 => match content {
+                [s0, s1, s2, s3, f0, f1, f2, quality] => Self::UtcTime {
+                    seconds: u32::from_be_bytes([*s0, *s1, *s2, *s3]),
+                    fraction: u32::from_be_bytes([0, *f0, *f1, *f2]),
+                    quality: *quality,
+                },
+                _ => return Err(error("bad utc-time length")),
+            },
+            tag => Self::Other(tag),
+        })
+    }
+
+    /// Numeric value of a point
+    ///
+    /// Booleans read as 0/1. A two-bit string is a Dbpos (on = 1, off = 0,
+    /// intermediate and bad states have no value); longer bit strings read
+    /// as an integer with bit 0 as the least significant bit. A structure
+    /// reads as its first member, e.g. `stVal` of a whole data object or `f`
+    /// of an AnalogueValue.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Boolean(value) => Some(if *value { 1.0 } else { 0.0 }),
+            Self::Integer(value) => Some(*value as f64),
+            Self::Unsigned(value) => Some(*value as f64),
+            Self::Float32(value) => Some(*value as f64),
+            Self::Float64(value) => Some(*value),
+            Self::BitString { bits: 2, bytes } => match bytes.first().map(|b| b >> 6) {
+                Some(0b10) => Some(1.0),
+                Some(0b01) => Some(0.0),
+                _ => None,
+            },
+            Self::BitString { bits, bytes } if *bits <= 64 => Some(
+                (0..*bits)
+                    .filter(|i| bytes[i / 8] & (0x80 >> (i % 8)) != 0)
+                    .fold(0u64, |acc, i| acc | 1 << i) as f64,
+            ),
+            Self::Structure(members) => members.first().and_then(Self::as_f64),
+            _ => None,
+        }
+    }
+}
+
+// ============================================================================
+// MMS services
+// ============================================================================
+
+/// Domain-specific MMS variable name (`LD`, `LN$FC$DO$DA`)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ObjectName {
+    pub domain: String,
+    pub item: String,
+}
+
+impl std::fmt::Display for ObjectName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.domain, self.item)
+    }
+}
+
+/// listOfVariable of a VariableAccessSpecification
+fn variable_list(names: &[ObjectName]) -> Vec<u8> {
+    let variables: Vec<u8> = names
+        .iter()
+        .flat_map(|name| {
+            let object_name = tlv(
+                0xA1,
+                &[
+                    tlv(0x1A, name.domain.as_bytes()),
+                    tlv(0x1A, name.item.as_bytes()),
+                ]
+                .concat(),
+            );
+            tlv(0x30, &tlv(0xA0, &object_name))
+        })
+        .collect();
+    tlv(0xA0, &variables)
+}
+
+fn confirmed_request(invoke_id: u32, service: &[u8]) -> Vec<u8> {
+    tlv(
+        0xA0,
+        &[
+            tlv(0x02, &encode_unsigned(invoke_id as u64)),
+            service.to_vec(),
+        ]
+        .concat(),
+    )
+}
+
+/// Read request for a list of variables
+pub fn read_request(invoke_id: u32, names: &[ObjectName]) -> Vec<u8> {
+    confirmed_request(invoke_id, &tlv(0xA4, &tlv(0xA1, &variable_list(names))))
+}
+
+/// Write request for one variable
+pub fn write_request(invoke_id: u32, name: &ObjectName, data: &MmsData) -> Vec<u8> {
+    confirmed_request(
+        invoke_id,
+        &tlv(
+            0xA5,
+            &[
+                variable_list(std::slice::from_ref(name)),
+                tlv(0xA0, &data.encode()),
+            ]
+            .concat(),
+        ),
+    )
+}
+
+/// Result of reading one variable
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccessResult {
+    Success(MmsData),
+    /// DataAccessError code
+    Failure(u8),
+}
+
+/// Body of a confirmed response
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResponseBody {
+    Read(Vec<AccessResult>),
+    /// Per variable: `None` on success, DataAccessError code on failure
+    Write(Vec<Option<u8>>),
+    /// Response to a service this client does not decode
+    Other(u8),
+}
+
+/// MMS PDU received from the server
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pdu {
+    Response {
+        invoke_id: u32,
+        body: ResponseBody,
+    },
+    /// Confirmed error or reject
+    Error {
+        invoke_id: Option<u32>,
+        message: String,
+    },
+    InitiateResponse,
+    /// Unconfirmed PDU (information report), unused by a polling client
+    Unconfirmed,
+}
+
+const ERROR_CLASSES: [&str; 13] = [
+    "vmd-state",
+    "application-reference",
+    "definition",
+    "resource",
+    "service",
+    "service-preempt",
+    "time-resolution",
+    "access",
+    "initiate",
+    "conclude",
+    "cancel",
+    "file",
+    "others",
+];
+
+/// Name of a DataAccessError code
+pub fn access_error_name(code: u8) -> &'static str {
+    match code {
+        0 => "object-invalidated",
+        1 => "hardware-fault",
+        2 => "temporarily-unavailable",
+        3 => "object-access-denied",
+        4 => "object-undefined",
+        5 => "invalid-address",
+        6 => "type-unsupported",
+        7 => "type-inconsistent",
+        8 => "object-attribute-inconsistent",
+        9 => "object-access-unsupported",
+        10 => "object-non-existent",
+        11 => "object-value-invalid",
+        _ => "unknown-error",
+    }
+}
+
+/// Decode an MMS PDU
+pub fn parse_pdu(bytes: &[u8]) -> Result<Pdu, CodecError> {
+    let (pdu, _) = read_tlv(bytes)?;
+    match pdu.tag {
+        // confirmed-ResponsePDU
+        0xA1 => {
+            let elements = children(pdu.content)?;
+            let (invoke, service) = match elements.as_slice() {
+                [invoke, service, ..] if invoke.tag == 0x02 => (invoke, service),
+                _ => return Err(error("malformed confirmed response")),
+            };
+            let body = match service.tag {
+                0xA4 => {
+                    let results = child(service.content, 0xA1)?;
+                    ResponseBody::Read(
+                        children(results)?
+                            .into_iter()
+                            .map(|result| match result.tag {
+                                0x80 => Ok(AccessResult::Failure(
+                                    result.content.last().copied().unwrap_or(0),
+                                )),
+                                _ => MmsData::decode(result).map(AccessResult::Success),
+                            })
+                            .collect::<Result<_, _>>()?,
+                    )
+                },
+                0xA5 => ResponseBody::Write(
+                    children(service.content)?
+                        .into_iter()
+                        .map(|result| match result.tag {
+                            0x80 => Some(result.content.last().copied().unwrap_or(0)),
+                            _ => None,
+                        })
+                        .collect(),
+                ),
+                tag => ResponseBody::Other(tag),
+            };
+            Ok(Pdu::Response {
+                invoke_id: decode_unsigned(invoke.content)? as u32,
+                body,
+            })
+        },
+        // confirmed-ErrorPDU
+        0xA2 => {
+            let elements = children(pdu.content)?;
+            let invoke_id = elements
+                .iter()
+                .find(|e| e.tag == 0x80)
+                .map(|e| decode_unsigned(e.content))
+                .transpose()?
+                .map(|id| id as u32);
+            let class = elements
+                .iter()
+                .find(|e| e.tag == 0xA2)
+                .and_then(|e| child(e.content, 0xA0).ok())
+                .and_then(|class| read_tlv(class).ok())
+                .map(|(class, _)| {
+                    let name = ERROR_CLASSES
+                        .get((class.tag & 0x1F) as usize)
+                        .copied()
+                        .unwrap_or("unknown");
+                    format!(
+                        "{} error {}",
+                        name,
+                        decode_integer(class.content).unwrap_or(-1)
+                    )
+                })
+                .unwrap_or_else(|| "service error".to_string());
+            Ok(Pdu::Error {
+                invoke_id,
+                message: class,
+            })
+        },
+        // RejectPDU
+        0xA4 => {
+            let elements = children(pdu.content)?;
+            let invoke_id = elements
+                .iter()
+                .find(|e| e.tag == 0x80)
+                .map(|e| decode_unsigned(e.content))
+                .transpose()?
+                .map(|id| id as u32);
+            let reason = elements
+                .iter()
+                .find(|e| e.tag != 0x80)
+                .map(|e| {
+                    format!(
+                        "request rejected (reason {}:{})",
+                        e.tag & 0x1F,
+                        decode_integer(e.content).unwrap_or(-1)
+                    )
+                })
+                .unwrap_or_else(|| "request rejected".to_string());
+            Ok(Pdu::Error {
+                invoke_id,
+                message: reason,
+            })
+        },
+        0xA3 => Ok(Pdu::Unconfirmed),
+        0xA9 => Ok(Pdu::InitiateResponse),
+        0xAA => Ok(Pdu::Error {
+            invoke_id: None,
+            message: "initiate rejected".to_string(),
+        }),
+        tag => Err(error(format!("unexpected MMS PDU 0x{:02X}", tag))),
+    }
+}
+
+/// Session ACCEPT an IEC 61850 server answers a CONNECT with (test servers)
+#[cfg(test)]
+pub fn associate_response(initiate_response: &[u8]) -> Vec<u8> {
+    let aare = tlv(
+        0x61,
+        &[
+            tlv(0xA1, &tlv(0x06, MMS_CONTEXT_NAME)),
+            tlv(0xA2, &tlv(0x02, &[0])),
+            tlv(
+                0xBE,
+                &tlv(
+                    0x28,
+                    &[tlv(0x02, &[MMS_CONTEXT_ID]), tlv(0xA0, initiate_response)].concat(),
+                ),
+            ),
+        ]
+        .concat(),
+    );
+    let cpa = tlv(
+        0x31,
+        &[
+            tlv(0xA0, &tlv(0x80, &[1])),
+            tlv(
+                0xA2,
+                &[
+                    tlv(0x83, PRESENTATION_SELECTOR),
+                    presentation_data(ACSE_CONTEXT_ID, &aare),
+                ]
+                .concat(),
+            ),
+        ]
+        .concat(),
+    );
+    let mut parameters = vec![0x05, 0x06, 0x13, 0x01, 0x00, 0x16, 0x01, 0x02, 0xC1];
+    session_length(cpa.len(), &mut parameters);
+    parameters.extend_from_slice(&cpa);
+    let mut spdu = vec![SPDU_ACCEPT];
+    session_length(parameters.len(), &mut spdu);
+    spdu.extend_from_slice(&parameters);
+    spdu
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    fn name(domain: &str, item: &str) -> ObjectName {
+        ObjectName {
+            domain: domain.to_string(),
+            item: item.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_ber_lengths_and_integers() {
+        let long = tlv(0x04, &[0u8; 300]);
+        assert_eq!(&long[..4], &[0x04, 0x82, 0x01, 0x2C]);
+        let (element, rest) = read_tlv(&long).unwrap();
+        assert_eq!(element.content.len(), 300);
+        assert!(rest.is_empty());
+
+        assert_eq!(encode_integer(0), vec![0x00]);
+        assert_eq!(encode_integer(128), vec![0x00, 0x80]);
+        assert_eq!(encode_integer(-129), vec![0xFF, 0x7F]);
+        assert_eq!(decode_integer(&[0xFF, 0x7F]).unwrap(), -129);
+        assert_eq!(encode_unsigned(255), vec![0x00, 0xFF]);
+        assert_eq!(decode_unsigned(&[0x00, 0xFF]).unwrap(), 255);
+        assert!(read_tlv(&[0x30, 0x05, 0x01]).is_err());
+    }
+
+    #[test]
+    fn test_read_request_encoding() {
+        let request = read_request(1, &[name("IED1LD0", "MMXU1$MX$TotW$mag$f")]);
+        let mut expected = vec![
+            0xA0, 0x2D, 0x02, 0x01, 0x01, // confirmed request, invoke 1
+            0xA4, 0x28, 0xA1, 0x26, 0xA0, 0x24, // read, listOfVariable
+            0x30, 0x22, 0xA0, 0x20, 0xA1, 0x1E, // name, domain-specific
+            0x1A, 0x07,
+        ];
+        expected.extend_from_slice(b"IED1LD0");
+        expected.extend_from_slice(&[0x1A, 0x13]);
+        expected.extend_from_slice(b"MMXU1$MX$TotW$mag$f");
+        assert_eq!(request, expected);
+    }
+
+    #[test]
+    fn test_data_roundtrip_and_values() {
+        let oper = MmsData::Structure(vec![
+            MmsData::Boolean(true),
+            MmsData::Structure(vec![
+                MmsData::Integer(3),
+                MmsData::OctetString(b"ems".to_vec()),
+            ]),
+            MmsData::Unsigned(200),
+            MmsData::UtcTime {
+                seconds: 1_700_000_000,
+                fraction: 0x800000,
+                quality: 0x0A,
+            },
+            MmsData::Boolean(false),
+            MmsData::BitString {
+                bits: 2,
+                bytes: vec![0],
+            },
+        ]);
+        let encoded = oper.encode();
+        let (element, _) = read_tlv(&encoded).unwrap();
+        assert_eq!(MmsData::decode(element).unwrap(), oper);
+
+        let float = MmsData::Float32(21.5).encode();
+        assert_eq!(
+            float,
+            [&[0x87, 0x05, 0x08][..], &21.5f32.to_be_bytes()].concat()
+        );
+        let on = MmsData::BitString {
+            bits: 2,
+            bytes: vec![0x80],
+        };
+        assert_eq!(on.as_f64(), Some(1.0));
+        let intermediate = MmsData::BitString {
+            bits: 2,
+            bytes: vec![0x00],
+        };
+        assert_eq!(intermediate.as_f64(), None);
+        let mag = MmsData::Structure(vec![MmsData::Structure(vec![MmsData::Float32(5.0)])]);
+        assert_eq!(mag.as_f64(), Some(5.0));
+    }
+
+    #[test]
+    fn test_association_and_data_framing() {
+        let spdu = associate_response(&tlv(0xA9, &tlv(0x80, &[0x01, 0x00])));
+        assert_eq!(
+            parse_pdu(mms_pdu(&spdu).unwrap()).unwrap(),
+            Pdu::InitiateResponse
+        );
+
+        // Request framing as the server sees it
+        let connect = associate_request();
+        assert_eq!(connect[0], SPDU_CONNECT);
+        let read = read_request(7, &[name("LD", "LLN0$ST$Mod$stVal")]);
+        assert_eq!(mms_pdu(&data_request(&read)).unwrap(), read.as_slice());
+
+        // Segmented data TPDUs are joined
+        let big = data_request(&vec![0x55; 3000]);
+        let mut reader = TpktReader::new();
+        reader.push(&data_packets(&big));
+        assert_eq!(reader.next_tpdu(), Some(Ok(Tpdu::Data(big))));
+        assert_eq!(reader.next_tpdu(), None);
+
+        assert!(mms_pdu(&[SPDU_ABORT, 0x00]).is_err());
+        assert!(is_release(&[SPDU_FINISH, 0x00]));
+    }
+
+    #[test]
+    fn test_parse_responses() {
+        let response = tlv(
+            0xA1,
+            &[
+                tlv(0x02, &[0x07]),
+                tlv(
+                    0xA4,
+                    &tlv(
+                        0xA1,
+                        &[MmsData::Float32(1.5).encode(), tlv(0x80, &[10])].concat(),
+                    ),
+                ),
+            ]
+            .concat(),
+        );
+        assert_eq!(
+            parse_pdu(&response).unwrap(),
+            Pdu::Response {
+                invoke_id: 7,
+                body: ResponseBody::Read(vec![
+                    AccessResult::Success(MmsData::Float32(1.5)),
+                    AccessResult::Failure(10),
+                ]),
+            }
+        );
+
+        let write = tlv(
+            0xA1,
+            &[tlv(0x02, &[0x08]), tlv(0xA5, &tlv(0x81, &[]))].concat(),
+        );
+        assert_eq!(
+            parse_pdu(&write).unwrap(),
+            Pdu::Response {
+                invoke_id: 8,
+                body: ResponseBody::Write(vec![None]),
+            }
+        );
+
+        let error = tlv(
+            0xA2,
+            &[
+                tlv(0x80, &[0x09]),
+                tlv(0xA2, &tlv(0xA0, &tlv(0x87, &[0x02]))),
+            ]
+            .concat(),
+        );
+        assert_eq!(
+            parse_pdu(&error).unwrap(),
+            Pdu::Error {
+                invoke_id: Some(9),
+                message: "access error 2".to_string(),
+            }
+        );
+    }
+}
@@ -0,0 +1,515 @@
+//! SCL (IEC 61850-6) data model extraction
+//!
+//! Reads the IED sections of an ICD/CID/SCD file, resolves every logical
+//! node through the `DataTypeTemplates` and lists the data attributes a
+//! channel can map, each with the point type it suggests:
+//!
+//! | FC    | Basic type               | Point type |
+//! |-------|--------------------------|------------|
+//! | MX    | numeric                  | T          |
+//! | ST    | BOOLEAN, Dbpos           | S          |
+//! | ST    | numeric, Enum            | T          |
+//! | SP/SE | numeric                  | A          |
+//! | CO    | `Oper.ctlVal` BOOLEAN    | C (data object reference) |
+//! | CO    | `Oper.ctlVal` numeric    | A (data object reference) |
+//!
+//! Quality (`q`) and timestamp (`t`) attributes are not listed.
+
+use std::collections::HashMap;
+
+use quick_xml::encoding::Decoder;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use voltage_model::PointType;
+
+use crate::core::protocols::{error, CodecError};
+
+/// Data attribute of an IED
+#[derive(Debug, Clone, PartialEq)]
+pub struct SclAttribute {
+    pub ied: String,
+    /// `LD/LN.DO[.DA...]` object reference (data object for controls)
+    pub reference: String,
+    /// Functional constraint
+    pub fc: String,
+    /// SCL basic type (`ctlVal` type for controls)
+    pub b_type: String,
+    pub point_type: PointType,
+    /// `desc` of the data object, if any
+    pub description: String,
+}
+
+impl SclAttribute {
+    /// comsrv data type of the attribute
+    pub fn data_type(&self) -> &'static str {
+        match self.b_type.as_str() {
+            "BOOLEAN" | "Dbpos" => "bool",
+            "INT8" => "int8",
+            "INT16" => "int16",
+            "INT32" | "Enum" => "int32",
+            "INT64" => "int64",
+            "INT8U" => "uint8",
+            "INT16U" => "uint16",
+            "INT24U" | "INT32U" => "uint32",
+            "FLOAT64" => "float64",
+            _ => "float32",
+        }
+    }
+
+    /// `value_type` mapping column of a written attribute
+    pub fn value_type(&self) -> &'static str {
+        match self.b_type.as_str() {
+            "BOOLEAN" => "boolean",
+            "INT8U" | "INT16U" | "INT24U" | "INT32U" => "uint",
+            "INT8" | "INT16" | "INT32" | "INT64" | "Enum" => "int",
+            "FLOAT64" => "float64",
+            _ => "float32",
+        }
+    }
+}
+
+fn is_numeric(b_type: &str) -> bool {
+    matches!(
+        b_type,
+        "INT8"
+            | "INT16"
+            | "INT32"
+            | "INT64"
+            | "INT8U"
+            | "INT16U"
+            | "INT24U"
+            | "INT32U"
+            | "FLOAT32"
+            | "FLOAT64"
+            | "Enum"
+    )
+}
+
+/// Point type suggested for a (non-CO) attribute
+fn point_type(fc: &str, b_type: &str) -> Option<PointType> {
+    let binary = matches!(b_type, "BOOLEAN" | "Dbpos");
+    match fc {
+        "MX" if is_numeric(b_type) => Some(PointType::Telemetry),
+        "ST" if binary => Some(PointType::Signal),
+        "ST" if is_numeric(b_type) => Some(PointType::Telemetry),
+        "SP" | "SE" if is_numeric(b_type) => Some(PointType::Adjustment),
+        _ => None,
+    }
+}
+
+// ============================================================================
+// XML tree
+// ============================================================================
+
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+}
+
+impl Element {
+    fn from_start(start: &BytesStart<'_>, decoder: Decoder) -> Result<Self, CodecError> {
+        let mut attributes = Vec::new();
+        for attribute in start.attributes() {
+            let attribute = attribute.map_err(|e| error(format!("SCL attribute: {}", e)))?;
+            let value = attribute
+                .decode_and_unescape_value(decoder)
+                .map_err(|e| error(format!("SCL attribute: {}", e)))?;
+            attributes.push((
+                String::from_utf8_lossy(attribute.key.local_name().as_ref()).into_owned(),
+                value.into_owned(),
+            ));
+        }
+        Ok(Self {
+            name: String::from_utf8_lossy(start.local_name().as_ref()).into_owned(),
+            attributes,
+            children: Vec::new(),
+        })
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+}
+
+fn parse_document(xml: &str) -> Result<Element, CodecError> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut stack = vec![Element::default()];
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(start)) => stack.push(Element::from_start(&start, reader.decoder())?),
+            Ok(Event::Empty(start)) => {
+                let element = Element::from_start(&start, reader.decoder())?;
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(element);
+                }
+            },
+            Ok(Event::End(_)) => {
+                let element = stack.pop();
+                match (element, stack.last_mut()) {
+                    (Some(element), Some(parent)) => parent.children.push(element),
+                    _ => return Err(error("SCL: unbalanced end tag")),
+                }
+            },
+            Ok(Event::Eof) => break,
+            Ok(_) => {},
+            Err(e) => {
+                return Err(error(format!(
+                    "SCL: {} at position {}",
+                    e,
+                    reader.error_position()
+                )))
+            },
+        }
+    }
+    match (stack.pop(), stack.is_empty()) {
+        (Some(document), true) => document
+            .children
+            .into_iter()
+            .find(|e| e.name == "SCL")
+            .ok_or_else(|| error("not an SCL file (no <SCL> root)")),
+        _ => Err(error("SCL: unclosed elements")),
+    }
+}
+
+// ============================================================================
+// Data model
+// ============================================================================
+
+/// Data object being walked
+struct Scope<'s> {
+    ied: &'s str,
+    description: &'s str,
+}
+
+impl Scope<'_> {
+    fn push(
+        &self,
+        out: &mut Vec<SclAttribute>,
+        reference: String,
+        fc: &str,
+        b_type: &str,
+        point_type: Option<PointType>,
+    ) {
+        if let Some(point_type) = point_type {
+            out.push(SclAttribute {
+                ied: self.ied.to_string(),
+                reference,
+                fc: fc.to_string(),
+                b_type: b_type.to_string(),
+                point_type,
+                description: self.description.to_string(),
+            });
+        }
+    }
+}
+
+struct Templates<'a> {
+    ln_types: HashMap<&'a str, &'a Element>,
+    do_types: HashMap<&'a str, &'a Element>,
+    da_types: HashMap<&'a str, &'a Element>,
+}
+
+/// Nesting limit of data object / attribute types
+const MAX_DEPTH: usize = 8;
+
+impl<'a> Templates<'a> {
+    fn new(scl: &'a Element) -> Self {
+        let mut templates = Self {
+            ln_types: HashMap::new(),
+            do_types: HashMap::new(),
+            da_types: HashMap::new(),
+        };
+        for section in scl.children_named("DataTypeTemplates") {
+            for element in &section.children {
+                let Some(id) = element.attr("id") else {
+                    continue;
+                };
+                let types = match element.name.as_str() {
+                    "LNodeType" => &mut templates.ln_types,
+                    "DOType" => &mut templates.do_types,
+                    "DAType" => &mut templates.da_types,
+                    _ => continue,
+                };
+                types.insert(id, element);
+            }
+        }
+        templates
+    }
+
+    /// Attributes of a data object type below `path`
+    fn walk_do(
+        &self,
+        type_id: &str,
+        path: &str,
+        scope: &Scope<'_>,
+        depth: usize,
+        out: &mut Vec<SclAttribute>,
+    ) {
+        let Some(do_type) = self.do_types.get(type_id) else {
+            return;
+        };
+        if depth > MAX_DEPTH {
+            return;
+        }
+        for element in &do_type.children {
+            let Some(name) = element.attr("name") else {
+                continue;
+            };
+            match element.name.as_str() {
+                "SDO" => {
+                    if let Some(sdo_type) = element.attr("type") {
+                        let path = format!("{}.{}", path, name);
+                        self.walk_do(sdo_type, &path, scope, depth + 1, out);
+                    }
+                },
+                "DA" => {
+                    let fc = element.attr("fc").unwrap_or_default();
+                    let b_type = element.attr("bType").unwrap_or_default();
+                    if fc == "CO" {
+                        // Controls address the data object, typed by Oper.ctlVal
+                        if name == "Oper" {
+                            let ctl_val = element
+                                .attr("type")
+                                .and_then(|t| self.da_types.get(t))
+                                .and_then(|t| {
+                                    t.children_named("BDA")
+                                        .find(|bda| bda.attr("name") == Some("ctlVal"))
+                                })
+                                .and_then(|bda| bda.attr("bType"));
+                            let point_type = match ctl_val {
+                                Some("BOOLEAN") => Some(PointType::Control),
+                                Some(b_type) if is_numeric(b_type) => Some(PointType::Adjustment),
+                                _ => None,
+                            };
+                            scope.push(
+                                out,
+                                path.to_string(),
+                                fc,
+                                ctl_val.unwrap_or_default(),
+                                point_type,
+                            );
+                        }
+                        continue;
+                    }
+                    if matches!(name, "q" | "t") {
+                        continue;
+                    }
+                    let path = format!("{}.{}", path, name);
+                    match (b_type, element.attr("type")) {
+                        ("Struct", Some(da_type)) => {
+                            self.walk_da(da_type, &path, fc, scope, depth + 1, out)
+                        },
+                        _ => scope.push(out, path, fc, b_type, point_type(fc, b_type)),
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+
+    /// Leaves of a structured attribute type below `path`
+    fn walk_da(
+        &self,
+        type_id: &str,
+        path: &str,
+        fc: &str,
+        scope: &Scope<'_>,
+        depth: usize,
+        out: &mut Vec<SclAttribute>,
+    ) {
+        let Some(da_type) = self.da_types.get(type_id) else {
+            return;
+        };
+        if depth > MAX_DEPTH {
+            return;
+        }
+        for bda in da_type.children_named("BDA") {
+            let Some(name) = bda.attr("name") else {
+                continue;
+            };
+            let b_type = bda.attr("bType").unwrap_or_default();
+            let path = format!("{}.{}", path, name);
+            match (b_type, bda.attr("type")) {
+                ("Struct", Some(sub_type)) => {
+                    self.walk_da(sub_type, &path, fc, scope, depth + 1, out)
+                },
+                _ => scope.push(out, path, fc, b_type, point_type(fc, b_type)),
+            }
+        }
+    }
+}
+
+/// Mappable data attributes of the IEDs in an SCL file
+///
+/// `ied` restricts the result to one IED of a multi-IED (SCD) file.
+/// Attributes are listed in document order; those without a suggested point
+/// type are left out.
+pub fn parse_scl(xml: &str, ied: Option<&str>) -> Result<Vec<SclAttribute>, CodecError> {
+    let scl = parse_document(xml)?;
+    let templates = Templates::new(&scl);
+    let mut attributes = Vec::new();
+
+    for ied_element in scl.children_named("IED") {
+        let ied_name = ied_element.attr("name").unwrap_or_default();
+        if ied.is_some_and(|name| name != ied_name) {
+            continue;
+        }
+        let servers = ied_element
+            .children_named("AccessPoint")
+            .flat_map(|ap| ap.children_named("Server"));
+        for ld in servers.flat_map(|server| server.children_named("LDevice")) {
+            let ld_name = match ld.attr("ldName") {
+                Some(name) => name.to_string(),
+                None => format!("{}{}", ied_name, ld.attr("inst").unwrap_or_default()),
+            };
+            let nodes = ld
+                .children
+                .iter()
+                .filter(|e| e.name == "LN0" || e.name == "LN");
+            for ln in nodes {
+                let ln_name = format!(
+                    "{}{}{}",
+                    ln.attr("prefix").unwrap_or_default(),
+                    ln.attr("lnClass").unwrap_or_default(),
+                    ln.attr("inst").unwrap_or_default()
+                );
+                let Some(ln_type) = ln.attr("lnType").and_then(|t| templates.ln_types.get(t))
+                else {
+                    continue;
+                };
+                // Instance descriptions (DOI desc) take precedence
+                let instance_desc: HashMap<&str, &str> = ln
+                    .children_named("DOI")
+                    .filter_map(|doi| Some((doi.attr("name")?, doi.attr("desc")?)))
+                    .collect();
+
+                for data_object in ln_type.children_named("DO") {
+                    let (Some(do_name), Some(do_type)) =
+                        (data_object.attr("name"), data_object.attr("type"))
+                    else {
+                        continue;
+                    };
+                    let description = instance_desc
+                        .get(do_name)
+                        .copied()
+                        .or(data_object.attr("desc"))
+                        .unwrap_or_default();
+                    let path = format!("{}/{}.{}", ld_name, ln_name, do_name);
+                    let scope = Scope {
+                        ied: ied_name,
+                        description,
+                    };
+                    templates.walk_do(do_type, &path, &scope, 0, &mut attributes);
+                }
+            }
+        }
+    }
+    Ok(attributes)
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    const ICD: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<SCL xmlns="http://www.iec.ch/61850/2003/SCL">
+  <IED name="RELAY1">
+    <AccessPoint name="AP1">
+      <Server>
+        <LDevice inst="CTRL">
+          <LN0 lnClass="LLN0" inst="" lnType="LLN0_T"/>
+          <LN prefix="Q0" lnClass="CSWI" inst="1" lnType="CSWI_T">
+            <DOI name="Pos" desc="Breaker position"/>
+          </LN>
+          <LN lnClass="MMXU" inst="1" lnType="MMXU_T"/>
+        </LDevice>
+      </Server>
+    </AccessPoint>
+  </IED>
+  <DataTypeTemplates>
+    <LNodeType id="LLN0_T" lnClass="LLN0">
+      <DO name="Beh" type="ENS_T"/>
+    </LNodeType>
+    <LNodeType id="CSWI_T" lnClass="CSWI">
+      <DO name="Pos" type="DPC_T"/>
+    </LNodeType>
+    <LNodeType id="MMXU_T" lnClass="MMXU">
+      <DO name="TotW" type="MV_T" desc="Total active power"/>
+      <DO name="PhV" type="WYE_T"/>
+    </LNodeType>
+    <DOType id="ENS_T" cdc="ENS">
+      <DA name="stVal" bType="Enum" type="Beh" fc="ST"/>
+      <DA name="q" bType="Quality" fc="ST"/>
+    </DOType>
+    <DOType id="DPC_T" cdc="DPC">
+      <DA name="stVal" bType="Dbpos" fc="ST"/>
+      <DA name="q" bType="Quality" fc="ST"/>
+      <DA name="t" bType="Timestamp" fc="ST"/>
+      <DA name="Oper" bType="Struct" type="Oper_T" fc="CO"/>
+      <DA name="ctlModel" bType="Enum" type="CtlModels" fc="CF"/>
+    </DOType>
+    <DOType id="MV_T" cdc="MV">
+      <DA name="mag" bType="Struct" type="AV_T" fc="MX"/>
+      <DA name="q" bType="Quality" fc="MX"/>
+    </DOType>
+    <DOType id="CMV_T" cdc="CMV">
+      <DA name="cVal" bType="Struct" type="Vector_T" fc="MX"/>
+    </DOType>
+    <DOType id="WYE_T" cdc="WYE">
+      <SDO name="phsA" type="CMV_T"/>
+    </DOType>
+    <DAType id="AV_T">
+      <BDA name="f" bType="FLOAT32"/>
+    </DAType>
+    <DAType id="Vector_T">
+      <BDA name="mag" bType="Struct" type="AV_T"/>
+    </DAType>
+    <DAType id="Oper_T">
+      <BDA name="ctlVal" bType="BOOLEAN"/>
+      <BDA name="ctlNum" bType="INT8U"/>
+    </DAType>
+  </DataTypeTemplates>
+</SCL>"#;
+
+    #[test]
+    fn test_parse_scl_attributes() {
+        let attributes = parse_scl(ICD, None).unwrap();
+        let listed: Vec<(&str, &str, PointType)> = attributes
+            .iter()
+            .map(|a| (a.reference.as_str(), a.fc.as_str(), a.point_type))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                ("RELAY1CTRL/LLN0.Beh.stVal", "ST", PointType::Telemetry),
+                ("RELAY1CTRL/Q0CSWI1.Pos.stVal", "ST", PointType::Signal),
+                ("RELAY1CTRL/Q0CSWI1.Pos", "CO", PointType::Control),
+                ("RELAY1CTRL/MMXU1.TotW.mag.f", "MX", PointType::Telemetry),
+                (
+                    "RELAY1CTRL/MMXU1.PhV.phsA.cVal.mag.f",
+                    "MX",
+                    PointType::Telemetry
+                ),
+            ]
+        );
+        assert_eq!(attributes[1].description, "Breaker position");
+        assert_eq!(attributes[1].data_type(), "bool");
+        assert_eq!(attributes[2].value_type(), "boolean");
+        assert_eq!(attributes[3].description, "Total active power");
+
+        assert!(parse_scl(ICD, Some("OTHER")).unwrap().is_empty());
+        assert!(parse_scl("<Other/>", None).is_err());
+        assert!(parse_scl("<SCL><IED>", None).is_err());
+    }
+}
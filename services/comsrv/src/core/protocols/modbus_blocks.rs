@@ -0,0 +1,241 @@
+//! Modbus block read planning
+//!
+//! Groups the registers of a point table into as few read requests as the
+//! protocol allows: points of the same unit and table are merged into one
+//! block when the unmapped registers between them do not exceed `max_gap`
+//! and the block stays within `max_batch_size` registers (125 registers or
+//! 2000 bits per request at most). Overlapping points share a block.
+//!
+//! Gap registers are read and ignored. Devices that reject reads across
+//! unmapped addresses need `max_gap: 0`.
+//!
+//! ```yaml
+//! parameters:
+//!   max_batch_size: 125   # registers per request
+//!   max_gap: 10           # unmapped registers bridged inside a block
+//! ```
+
+use std::collections::HashMap;
+
+use serde_json::Value as JsonValue;
+
+use super::modbus_server::codec::Table;
+
+/// Most registers one read request can return (FC 3/4)
+pub const MAX_READ_REGISTERS: u16 = 125;
+
+/// Most bits one read request can return (FC 1/2)
+pub const MAX_READ_BITS: u16 = 2000;
+
+pub const DEFAULT_MAX_GAP: u16 = 10;
+
+/// Limits of a block read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockConfig {
+    /// Registers per request; bit tables scale it by 16
+    pub max_batch_size: u16,
+    /// Unmapped registers (or bits) bridged inside a block
+    pub max_gap: u16,
+}
+
+impl Default for BlockConfig {
+    fn default() -> Self {
+        Self {
+            max_batch_size: MAX_READ_REGISTERS,
+            max_gap: DEFAULT_MAX_GAP,
+        }
+    }
+}
+
+impl BlockConfig {
+    /// `max_batch_size` and `max_gap` channel parameters; out-of-range
+    /// values are clamped to what a request can carry
+    pub fn from_parameters(parameters: &HashMap<String, JsonValue>) -> Self {
+        let number = |key: &str| {
+            parameters.get(key).and_then(|v| match v {
+                JsonValue::Number(n) => n.as_u64(),
+                JsonValue::String(s) => s.trim().parse().ok(),
+                _ => None,
+            })
+        };
+        let defaults = Self::default();
+        Self {
+            max_batch_size: number("max_batch_size").map_or(defaults.max_batch_size, |n| {
+                n.clamp(1, MAX_READ_REGISTERS as u64) as u16
+            }),
+            max_gap: number("max_gap").map_or(defaults.max_gap, |n| n.min(u16::MAX as u64) as u16),
+        }
+    }
+
+    /// Largest block of a table, in registers or bits
+    fn max_quantity(&self, table: Table) -> u16 {
+        if table.is_bits() {
+            self.max_batch_size.saturating_mul(16).min(MAX_READ_BITS)
+        } else {
+            self.max_batch_size.min(MAX_READ_REGISTERS)
+        }
+    }
+}
+
+/// Registers (or bits) a point occupies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub unit: u8,
+    pub table: Table,
+    pub address: u16,
+    pub quantity: u16,
+}
+
+impl Span {
+    fn end(&self) -> u32 {
+        self.address as u32 + self.quantity as u32
+    }
+}
+
+/// One read request covering several points
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadBlock {
+    pub unit: u8,
+    pub table: Table,
+    pub address: u16,
+    pub quantity: u16,
+    /// Index of each member span and its offset in the block
+    pub members: Vec<(usize, u16)>,
+}
+
+impl ReadBlock {
+    /// Words (or bits) of a member within the values read for the block
+    pub fn slice<'a>(&self, values: &'a [u16], offset: u16, quantity: u16) -> Option<&'a [u16]> {
+        values.get(offset as usize..offset as usize + quantity as usize)
+    }
+}
+
+/// Plan the block reads covering `spans`, ordered by unit, table and address
+pub fn plan(spans: &[Span], config: BlockConfig) -> Vec<ReadBlock> {
+    let mut order: Vec<usize> = (0..spans.len()).collect();
+    order.sort_by_key(|&i| {
+        let span = &spans[i];
+        (
+            span.unit,
+            table_rank(span.table),
+            span.address,
+            span.quantity,
+        )
+    });
+
+    let mut blocks: Vec<ReadBlock> = Vec::new();
+    let mut block_end = 0u32;
+    for index in order {
+        let span = spans[index];
+        if let Some(block) = blocks.last_mut() {
+            let start = block.address as u32;
+            let end = block_end.max(span.end());
+            let mergeable = block.unit == span.unit
+                && block.table == span.table
+                && span.address as u32 <= block_end + config.max_gap as u32
+                && end - start <= config.max_quantity(span.table) as u32;
+            if mergeable {
+                block
+                    .members
+                    .push((index, (span.address as u32 - start) as u16));
+                block.quantity = (end - start) as u16;
+                block_end = end;
+                continue;
+            }
+        }
+        blocks.push(ReadBlock {
+            unit: span.unit,
+            table: span.table,
+            address: span.address,
+            quantity: span.quantity,
+            members: vec![(index, 0)],
+        });
+        block_end = span.end();
+    }
+    blocks
+}
+
+fn table_rank(table: Table) -> u8 {
+    match table {
+        Table::Coils => 0,
+        Table::DiscreteInputs => 1,
+        Table::HoldingRegisters => 2,
+        Table::InputRegisters => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holding(unit: u8, address: u16, quantity: u16) -> Span {
+        Span {
+            unit,
+            table: Table::HoldingRegisters,
+            address,
+            quantity,
+        }
+    }
+
+    #[test]
+    fn test_plan_merges_nearby_registers() {
+        let spans = [
+            holding(1, 10, 2),
+            holding(1, 0, 1),
+            holding(1, 1, 2),
+            holding(1, 30, 1), // 17 registers past the block: new block
+            holding(2, 2, 1),  // other unit
+            Span {
+                unit: 1,
+                table: Table::Coils,
+                address: 0,
+                quantity: 1,
+            },
+            holding(1, 11, 1), // overlaps the first span
+        ];
+        let blocks = plan(&spans, BlockConfig::default());
+        let summary: Vec<_> = blocks
+            .iter()
+            .map(|b| (b.unit, b.table, b.address, b.quantity))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, Table::Coils, 0, 1),
+                (1, Table::HoldingRegisters, 0, 12),
+                (1, Table::HoldingRegisters, 30, 1),
+                (2, Table::HoldingRegisters, 2, 1),
+            ]
+        );
+        assert_eq!(blocks[1].members, vec![(1, 0), (2, 1), (0, 10), (6, 11)]);
+        assert_eq!(blocks[1].slice(&[0; 12], 10, 2), Some(&[0u16, 0][..]));
+
+        // Without gap tolerance only contiguous registers merge
+        let strict = BlockConfig {
+            max_gap: 0,
+            ..BlockConfig::default()
+        };
+        assert_eq!(plan(&spans, strict).len(), 5);
+    }
+
+    #[test]
+    fn test_plan_respects_max_batch_size() {
+        let spans: Vec<Span> = (0..300).map(|a| holding(1, a, 1)).collect();
+        let blocks = plan(&spans, BlockConfig::default());
+        assert_eq!(
+            blocks.iter().map(|b| b.quantity).collect::<Vec<_>>(),
+            vec![125, 125, 50]
+        );
+
+        let parameters: HashMap<String, JsonValue> = [
+            ("max_batch_size".to_string(), serde_json::json!(500)),
+            ("max_gap".to_string(), serde_json::json!("2")),
+        ]
+        .into_iter()
+        .collect();
+        let config = BlockConfig::from_parameters(&parameters);
+        assert_eq!(config.max_batch_size, 125);
+        assert_eq!(config.max_gap, 2);
+        assert_eq!(config.max_quantity(Table::Coils), MAX_READ_BITS);
+    }
+}
@@ -0,0 +1,787 @@
+//! Modbus RTU over TCP
+//!
+//! Serial device servers in transparent mode forward raw RTU frames over a
+//! TCP connection instead of translating them to Modbus TCP (MBAP) frames.
+//! A Modbus channel with `communication_mode: rtu_over_tcp` polls through
+//! such a server: every request is an RTU ADU (unit ID, PDU, CRC-16) written
+//! to the TcpStream, and responses are checked against their CRC. Point
+//! mappings are the same as for the other Modbus modes.
+//!
+//! Inputs are read in blocks planned by [`modbus_blocks`](super::modbus_blocks);
+//! when a device rejects a block read (e.g. across unmapped registers) its
+//! points are read one by one.
+//!
+//! RTU has no transaction ID, so requests are strictly sequential; bytes
+//! left over from a timed-out request are discarded before the next one.
+//!
+//! ```yaml
+//! protocol: modbus_rtu          # or modbus_tcp
+//! parameters:
+//!   communication_mode: rtu_over_tcp
+//!   host: 192.168.1.30          # device server
+//!   port: 4001
+//!   read_timeout_ms: 1000      # response timeout
+//!   max_batch_size: 125         # registers per block read
+//!   max_gap: 10                 # unmapped registers bridged inside a block
+//! ```
+
+pub mod codec;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use igw::core::traits::{DataEventReceiver, Diagnostics, PointFailure, PollResult};
+use igw::gateway::ChannelRuntime;
+use igw::{ConnectionState, DataBatch, DataPoint, GatewayError};
+use serde_json::Value as JsonValue;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{timeout_at, Instant};
+use tracing::{debug, info, warn};
+use voltage_model::PointType;
+
+use super::modbus_blocks::{self, BlockConfig, ReadBlock, Span};
+use super::modbus_server::codec::{self as registers, ByteOrder, DataType, Table};
+use crate::core::config::{Point, RuntimeChannelConfig};
+use crate::core::protocols::{as_u64, record_failure};
+use crate::error::{ComSrvError, Result};
+
+/// Parameter selecting the communication mode of a Modbus channel
+pub const MODE_PARAMETER: &str = "communication_mode";
+
+const DEFAULT_PORT: u16 = 502;
+const DEFAULT_READ_TIMEOUT_MS: u64 = 1000;
+
+/// How a Modbus client reaches its devices
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommunicationMode {
+    /// MBAP-framed Modbus TCP (igw client)
+    Tcp,
+    /// RTU frames on a serial line (igw client)
+    Rtu,
+    /// RTU frames over a raw TCP stream (this runtime)
+    RtuOverTcp,
+}
+
+impl CommunicationMode {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "tcp" => Some(Self::Tcp),
+            "rtu" | "serial" => Some(Self::Rtu),
+            "rtu_over_tcp" | "rtu_tcp" => Some(Self::RtuOverTcp),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Tcp => "tcp",
+            Self::Rtu => "rtu",
+            Self::RtuOverTcp => "rtu_over_tcp",
+        }
+    }
+
+    /// Mode of a `modbus_tcp`/`modbus_rtu` channel: `communication_mode`
+    /// when set, otherwise the one the protocol names
+    pub fn of_channel(protocol: &str, parameters: &HashMap<String, JsonValue>) -> Result<Self> {
+        let default = if protocol == "modbus_rtu" {
+            Self::Rtu
+        } else {
+            Self::Tcp
+        };
+        let mode = match parameters.get(MODE_PARAMETER) {
+            None | Some(JsonValue::Null) => return Ok(default),
+            Some(value) => value.as_str().and_then(Self::parse).ok_or_else(|| {
+                ComSrvError::ConfigError(format!(
+                    "'{}' must be tcp, rtu or rtu_over_tcp",
+                    MODE_PARAMETER
+                ))
+            })?,
+        };
+        if mode != default && mode != Self::RtuOverTcp {
+            return Err(ComSrvError::ConfigError(format!(
+                "{} channels cannot use communication mode '{}'",
+                protocol,
+                mode.as_str()
+            )));
+        }
+        Ok(mode)
+    }
+}
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Channel parameters of an RTU-over-TCP channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct RtuOverTcpConfig {
+    pub host: String,
+    pub port: u16,
+    pub response_timeout: Duration,
+}
+
+impl RtuOverTcpConfig {
+    pub fn from_parameters(parameters: &HashMap<String, JsonValue>) -> Result<Self> {
+        let host = parameters
+            .get("host")
+            .and_then(|v| v.as_str())
+            .filter(|h| !h.trim().is_empty())
+            .ok_or_else(|| {
+                ComSrvError::ConfigError("RTU over TCP requires the device server 'host'".into())
+            })?
+            .trim()
+            .to_string();
+        let port = match parameters.get("port") {
+            None | Some(JsonValue::Null) => DEFAULT_PORT,
+            Some(value) => as_u64(value)
+                .and_then(|v| u16::try_from(v).ok())
+                .ok_or_else(|| ComSrvError::ConfigError("'port' must be 0-65535".into()))?,
+        };
+        let response_timeout_ms = parameters
+            .get("read_timeout_ms")
+            .and_then(as_u64)
+            .unwrap_or(DEFAULT_READ_TIMEOUT_MS)
+            .max(1);
+        Ok(Self {
+            host,
+            port,
+            response_timeout: Duration::from_millis(response_timeout_ms),
+        })
+    }
+
+    fn address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+// ============================================================================
+// Point mapping
+// ============================================================================
+
+/// Register location of a point
+#[derive(Debug, Clone, PartialEq)]
+struct Register {
+    point_type: PointType,
+    point_id: u32,
+    unit: u8,
+    table: Table,
+    address: u16,
+    data_type: DataType,
+    byte_order: ByteOrder,
+    /// Bit of a boolean stored in a register
+    bit: Option<u8>,
+}
+
+impl Register {
+    fn from_point(point_type: PointType, point: &Point) -> std::result::Result<Self, String> {
+        let mapping: JsonValue = point
+            .protocol_mappings
+            .as_deref()
+            .and_then(|m| serde_json::from_str(m).ok())
+            .ok_or("missing Modbus mapping")?;
+        let number = |key: &str| mapping.get(key).and_then(as_u64);
+        let text = |key: &str| {
+            mapping
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+
+        let unit = number("slave_id")
+            .and_then(|n| u8::try_from(n).ok())
+            .ok_or("missing/invalid 'slave_id'")?;
+        let code = number("function_code").ok_or("missing 'function_code'")?;
+        let table = u8::try_from(code)
+            .ok()
+            .and_then(Table::from_function_code)
+            .ok_or_else(|| format!("unsupported function code {}", code))?;
+        let address = number("register_address")
+            .and_then(|n| u16::try_from(n).ok())
+            .ok_or("missing/invalid 'register_address'")?;
+        if point_type.is_action() && !table.is_writable() {
+            return Err(format!(
+                "{} points cannot write to {:?}",
+                point_type.as_str(),
+                table
+            ));
+        }
+
+        let data_type = if table.is_bits() {
+            DataType::Bool
+        } else {
+            DataType::parse(text("data_type").unwrap_or("uint16")).map_err(|e| e.to_string())?
+        };
+        let byte_order =
+            ByteOrder::parse(text("byte_order").unwrap_or("ABCD")).map_err(|e| e.to_string())?;
+        let bit = match (data_type, table.is_bits()) {
+            (DataType::Bool, false) => Some(
+                number("bit_position")
+                    .unwrap_or(0)
+                    .try_into()
+                    .ok()
+                    .filter(|b: &u8| *b < 16)
+                    .ok_or("'bit_position' must be 0-15")?,
+            ),
+            _ => None,
+        };
+        if address as u32 + data_type.words() as u32 > 0x1_0000 {
+            return Err(format!("register {} runs past the address space", address));
+        }
+
+        Ok(Self {
+            point_type,
+            point_id: point.point_id,
+            unit,
+            table,
+            address,
+            data_type,
+            byte_order,
+            bit,
+        })
+    }
+
+    /// Registers (or bits) read for the point
+    fn quantity(&self) -> u16 {
+        if self.table.is_bits() {
+            1
+        } else {
+            self.data_type.words()
+        }
+    }
+
+    fn span(&self) -> Span {
+        Span {
+            unit: self.unit,
+            table: self.table,
+            address: self.address,
+            quantity: self.quantity(),
+        }
+    }
+
+    /// Point value of the words read at its address
+    fn decode(&self, words: &[u16]) -> f64 {
+        match self.bit {
+            Some(bit) => f64::from((words[0] >> bit) & 1),
+            None if self.table.is_bits() => f64::from(words[0]),
+            None => registers::decode(words, self.data_type, self.byte_order),
+        }
+    }
+}
+
+// ============================================================================
+// Runtime
+// ============================================================================
+
+/// Modbus client sending RTU frames through a TCP device server
+pub struct ModbusRtuOverTcpRuntime {
+    id: u32,
+    name: String,
+    protocol: String,
+    config: RtuOverTcpConfig,
+    /// Telemetry and signal points
+    inputs: Vec<Register>,
+    /// Block reads covering `inputs`
+    blocks: Vec<ReadBlock>,
+    /// (point type, point ID) -> control/adjustment register
+    outputs: HashMap<(PointType, u32), Register>,
+    stream: Option<TcpStream>,
+    diagnostics: Diagnostics,
+}
+
+impl ModbusRtuOverTcpRuntime {
+    /// Build the runtime of a Modbus channel in `rtu_over_tcp` mode
+    pub fn from_runtime_config(runtime_config: &RuntimeChannelConfig) -> Result<Self> {
+        let channel_id = runtime_config.id();
+        let config = RtuOverTcpConfig::from_parameters(&runtime_config.base.parameters)
+            .map_err(|e| ComSrvError::ConfigError(format!("Ch{}: {}", channel_id, e)))?;
+
+        let mut inputs = Vec::new();
+        let mut outputs = HashMap::new();
+        for (point_type, point) in runtime_config.points() {
+            match Register::from_point(point_type, point) {
+                Ok(register) if point_type.is_action() => {
+                    outputs.insert((point_type, point.point_id), register);
+                },
+                Ok(register) => inputs.push(register),
+                Err(e) => warn!(
+                    "Ch{} {}{} skipped: {}",
+                    channel_id,
+                    point_type.as_str(),
+                    point.point_id,
+                    e
+                ),
+            }
+        }
+        let spans: Vec<Span> = inputs.iter().map(Register::span).collect();
+        let blocks = modbus_blocks::plan(
+            &spans,
+            BlockConfig::from_parameters(&runtime_config.base.parameters),
+        );
+        debug!(
+            "Ch{} Modbus RTU over TCP {}: {} inputs in {} reads, {} outputs",
+            channel_id,
+            config.address(),
+            inputs.len(),
+            blocks.len(),
+            outputs.len()
+        );
+
+        let protocol = crate::utils::normalize_protocol_name(runtime_config.protocol());
+        Ok(Self {
+            id: channel_id,
+            name: runtime_config.name().to_string(),
+            diagnostics: Diagnostics::new(&protocol),
+            protocol,
+            config,
+            inputs,
+            blocks,
+            outputs,
+            stream: None,
+        })
+    }
+
+    /// Record an error; connection failures drop the stream so the next
+    /// `connect()` starts over
+    fn fail(&mut self, error: GatewayError) -> GatewayError {
+        if record_failure(&mut self.diagnostics, &error) {
+            self.stream = None;
+        }
+        error
+    }
+
+    /// Send a request PDU to a unit and return the response PDU
+    async fn request(&mut self, unit: u8, pdu: &[u8]) -> igw::Result<Vec<u8>> {
+        let deadline = Instant::now() + self.config.response_timeout;
+        let stream = self.stream.as_mut().ok_or(GatewayError::NotConnected)?;
+
+        // Drop a late response to an earlier request
+        let mut stale = [0u8; codec::MAX_ADU_LEN];
+        while let Ok(n) = stream.try_read(&mut stale) {
+            if n == 0 {
+                return Err(GatewayError::Connection(
+                    "device server closed the connection".to_string(),
+                ));
+            }
+            debug!("Ch{} discarded {} stale bytes", self.id, n);
+        }
+
+        stream
+            .write_all(&codec::encode_adu(unit, pdu))
+            .await
+            .map_err(|e| GatewayError::Connection(format!("send: {}", e)))?;
+
+        let mut frame: Vec<u8> = Vec::with_capacity(codec::MAX_ADU_LEN);
+        let mut buf = [0u8; codec::MAX_ADU_LEN];
+        loop {
+            if let Some(len) = codec::response_len(&frame) {
+                let len = len.map_err(|e| GatewayError::InvalidResponse(e.to_string()))?;
+                if frame.len() >= len {
+                    let (from, response) = codec::decode_adu(&frame[..len])
+                        .map_err(|e| GatewayError::InvalidResponse(e.to_string()))?;
+                    if from != unit {
+                        return Err(GatewayError::InvalidResponse(format!(
+                            "response from unit {} to a request for unit {}",
+                            from, unit
+                        )));
+                    }
+                    return Ok(response.to_vec());
+                }
+            }
+            let n = timeout_at(deadline, stream.read(&mut buf))
+                .await
+                .map_err(|_| GatewayError::ReadTimeout)?
+                .map_err(|e| GatewayError::Connection(format!("receive: {}", e)))?;
+            if n == 0 {
+                return Err(GatewayError::Connection(
+                    "device server closed the connection".to_string(),
+                ));
+            }
+            frame.extend_from_slice(&buf[..n]);
+        }
+    }
+
+    /// Words (bits as 0/1) of a table range
+    async fn read_range(
+        &mut self,
+        unit: u8,
+        table: Table,
+        address: u16,
+        quantity: u16,
+    ) -> igw::Result<Vec<u16>> {
+        let pdu = codec::read_request(table, address, quantity);
+        let response = self.request(unit, &pdu).await?;
+        codec::parse_read_response(&pdu, &response)
+            .map_err(|e| GatewayError::InvalidResponse(e.to_string()))
+    }
+
+    async fn read(&mut self, register: &Register) -> igw::Result<f64> {
+        let words = self
+            .read_range(
+                register.unit,
+                register.table,
+                register.address,
+                register.quantity(),
+            )
+            .await?;
+        Ok(register.decode(&words))
+    }
+
+    /// Read the points of a block one by one
+    async fn read_members(
+        &mut self,
+        block: &ReadBlock,
+        batch: &mut DataBatch,
+        failures: &mut Vec<PointFailure>,
+    ) {
+        for &(index, _) in &block.members {
+            let register = self.inputs[index].clone();
+            let internal_id = register.point_type.to_internal_id(register.point_id);
+            match self.read(&register).await {
+                Ok(value) => batch.add(DataPoint::new(internal_id, value)),
+                Err(e) => {
+                    let e = self.fail(e);
+                    failures.push(PointFailure::with_error(internal_id, e.to_string()));
+                    if self.stream.is_none() {
+                        return;
+                    }
+                },
+            }
+        }
+    }
+
+    async fn write_register(&mut self, register: &Register, value: f64) -> igw::Result<()> {
+        let pdu = match (register.table, register.bit) {
+            (Table::Coils, _) => codec::write_coil_request(register.address, value != 0.0),
+            (_, Some(bit)) => {
+                // Read-modify-write of a bit in a holding register
+                let current = codec::read_request(register.table, register.address, 1);
+                let response = self.request(register.unit, &current).await?;
+                let word = codec::parse_read_response(&current, &response)
+                    .map_err(|e| GatewayError::InvalidResponse(e.to_string()))?[0];
+                let word = if value != 0.0 {
+                    word | (1 << bit)
+                } else {
+                    word & !(1 << bit)
+                };
+                codec::write_registers_request(register.address, &[word])
+            },
+            _ => codec::write_registers_request(
+                register.address,
+                &registers::encode(value, register.data_type, register.byte_order),
+            ),
+        };
+        let response = self.request(register.unit, &pdu).await?;
+        codec::check_write_response(&pdu, &response)
+            .map_err(|e| GatewayError::InvalidResponse(e.to_string()))
+    }
+
+    async fn write(&mut self, point_type: PointType, values: &[(u32, f64)]) -> igw::Result<usize> {
+        if self.stream.is_none() {
+            return Err(GatewayError::NotConnected);
+        }
+        let mut written = 0;
+        let mut last_error = None;
+        for &(internal_id, value) in values {
+            let point_id = PointType::from_internal_id(internal_id).1;
+            let Some(register) = self.outputs.get(&(point_type, point_id)).cloned() else {
+                last_error = Some(GatewayError::PointNotFound(format!(
+                    "{}{}",
+                    point_type.as_str(),
+                    point_id
+                )));
+                continue;
+            };
+            match self.write_register(&register, value).await {
+                Ok(()) => written += 1,
+                Err(e) => {
+                    let e = self.fail(e);
+                    if self.stream.is_none() {
+                        return Err(e);
+                    }
+                    last_error = Some(e);
+                },
+            }
+        }
+        self.diagnostics.write_count += written as u64;
+        match last_error {
+            Some(e) if written == 0 => Err(e),
+            _ => Ok(written),
+        }
+    }
+}
+
+#[async_trait]
+impl ChannelRuntime for ModbusRtuOverTcpRuntime {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn protocol(&self) -> &str {
+        &self.protocol
+    }
+
+    fn is_event_driven(&self) -> bool {
+        false
+    }
+
+    async fn connect(&mut self) -> igw::Result<()> {
+        self.diagnostics.connection_state = ConnectionState::Connecting;
+        let address = self.config.address();
+        let timeout_ms = self.config.response_timeout.as_millis() as u64;
+        let connected =
+            tokio::time::timeout(self.config.response_timeout, TcpStream::connect(&address)).await;
+        let stream = match connected {
+            Ok(Ok(stream)) => stream,
+            Ok(Err(e)) => {
+                return Err(self.fail(GatewayError::Connection(format!("{}: {}", address, e))))
+            },
+            Err(_) => return Err(self.fail(GatewayError::ConnectionTimeout(timeout_ms))),
+        };
+        let _ = stream.set_nodelay(true);
+        self.stream = Some(stream);
+        self.diagnostics.connection_state = ConnectionState::Connected;
+        info!("Ch{} Modbus RTU over TCP connected to {}", self.id, address);
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> igw::Result<()> {
+        if let Some(mut stream) = self.stream.take() {
+            let _ = stream.shutdown().await;
+        }
+        self.diagnostics.connection_state = ConnectionState::Disconnected;
+        Ok(())
+    }
+
+    async fn poll_once(&mut self) -> PollResult {
+        if self.stream.is_none() {
+            return PollResult::failed(vec![PointFailure::new(0, "not connected")]);
+        }
+        let blocks = self.blocks.clone();
+        let mut batch = DataBatch::with_capacity(self.inputs.len());
+        let mut failures = Vec::new();
+        for block in &blocks {
+            let read = self
+                .read_range(block.unit, block.table, block.address, block.quantity)
+                .await;
+            match read {
+                Ok(words) => {
+                    for &(index, offset) in &block.members {
+                        let register = &self.inputs[index];
+                        let internal_id = register.point_type.to_internal_id(register.point_id);
+                        match block.slice(&words, offset, register.quantity()) {
+                            Some(words) => {
+                                batch.add(DataPoint::new(internal_id, register.decode(words)))
+                            },
+                            None => failures.push(PointFailure::new(internal_id, "short response")),
+                        }
+                    }
+                },
+                Err(e) => {
+                    let e = self.fail(e);
+                    if self.stream.is_some() && block.members.len() > 1 {
+                        debug!(
+                            "Ch{} block read unit {} {:?} {}+{} failed ({}), reading points",
+                            self.id, block.unit, block.table, block.address, block.quantity, e
+                        );
+                        self.read_members(block, &mut batch, &mut failures).await;
+                    } else {
+                        failures.extend(block.members.iter().map(|&(index, _)| {
+                            let register = &self.inputs[index];
+                            PointFailure::with_error(
+                                register.point_type.to_internal_id(register.point_id),
+                                e.to_string(),
+                            )
+                        }));
+                    }
+                    if self.stream.is_none() {
+                        break;
+                    }
+                },
+            }
+        }
+        self.diagnostics.read_count += 1;
+        PollResult::partial(batch, failures)
+    }
+
+    async fn write_control(&mut self, commands: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Control, commands).await
+    }
+
+    async fn write_adjustment(&mut self, adjustments: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Adjustment, adjustments).await
+    }
+
+    fn subscribe(&self) -> Option<DataEventReceiver> {
+        None
+    }
+
+    async fn start_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn stop_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn diagnostics(&self) -> igw::Result<Diagnostics> {
+        let mut diagnostics = self.diagnostics.clone();
+        diagnostics.extra = serde_json::json!({
+            "communication_mode": CommunicationMode::RtuOverTcp.as_str(),
+            "device_server": self.config.address(),
+        });
+        Ok(diagnostics)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tokio::net::TcpListener;
+
+    fn point<T: serde::de::DeserializeOwned>(point_id: u32, mapping: JsonValue) -> T {
+        serde_json::from_value(json!({
+            "point_id": point_id,
+            "signal_name": format!("p{}", point_id),
+            "protocol_mappings": mapping.to_string(),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_communication_mode() {
+        let params = |mode: JsonValue| HashMap::from([(MODE_PARAMETER.to_string(), mode)]);
+        assert_eq!(
+            CommunicationMode::of_channel("modbus_rtu", &HashMap::new()).unwrap(),
+            CommunicationMode::Rtu
+        );
+        assert_eq!(
+            CommunicationMode::of_channel("modbus_tcp", &params(json!("RTU-over-TCP"))).unwrap(),
+            CommunicationMode::RtuOverTcp
+        );
+        assert_eq!(
+            CommunicationMode::of_channel("modbus_rtu", &params(json!("rtu_over_tcp"))).unwrap(),
+            CommunicationMode::RtuOverTcp
+        );
+        assert!(CommunicationMode::of_channel("modbus_tcp", &params(json!("rtu"))).is_err());
+        assert!(CommunicationMode::of_channel("modbus_rtu", &params(json!("udp"))).is_err());
+    }
+
+    /// Device server answering RTU requests for unit 3 from a register map
+    async fn device_server(listener: TcpListener) {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut holding: HashMap<u16, u16> = HashMap::from([(0, 0x4148), (1, 0x0000), (5, 42)]);
+        let mut buf = [0u8; 256];
+        loop {
+            let Ok(n) = stream.read(&mut buf).await else {
+                return;
+            };
+            if n == 0 {
+                return;
+            }
+            let (unit, pdu) = codec::decode_adu(&buf[..n]).unwrap();
+            let address = u16::from_be_bytes([pdu[1], pdu[2]]);
+            let field = u16::from_be_bytes([pdu[3], pdu[4]]);
+            let response = match pdu[0] {
+                0x03 => {
+                    let mut out = vec![0x03, (field * 2) as u8];
+                    for a in address..address + field {
+                        out.extend(holding.get(&a).copied().unwrap_or(0).to_be_bytes());
+                    }
+                    out
+                },
+                0x06 => {
+                    holding.insert(address, field);
+                    pdu.to_vec()
+                },
+                code => vec![code | 0x80, 0x01],
+            };
+            // Split the response to exercise reassembly
+            let adu = codec::encode_adu(unit, &response);
+            stream.write_all(&adu[..2]).await.unwrap();
+            stream.write_all(&adu[2..]).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_poll_and_write_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(device_server(listener));
+
+        let mut config = RuntimeChannelConfig::from_base(
+            serde_json::from_value(json!({
+                "id": 12,
+                "name": "meter_bus",
+                "protocol": "modbus_rtu",
+                "parameters": {
+                    "communication_mode": "rtu_over_tcp",
+                    "host": "127.0.0.1",
+                    "port": port,
+                    "read_timeout_ms": 2000,
+                },
+            }))
+            .unwrap(),
+        );
+        config.telemetry_points = vec![
+            point(
+                1,
+                json!({"slave_id": 3, "function_code": 3, "register_address": 0,
+                       "data_type": "float32"}),
+            ),
+            // Input registers are not served: exception response
+            point(
+                2,
+                json!({"slave_id": 3, "function_code": 4, "register_address": 0}),
+            ),
+        ];
+        config.signal_points = vec![point(
+            1,
+            json!({"slave_id": 3, "function_code": 3, "register_address": 5,
+                   "data_type": "bool", "bit_position": 1}),
+        )];
+        config.adjustment_points = vec![point(
+            1,
+            json!({"slave_id": 3, "function_code": 6, "register_address": 5}),
+        )];
+
+        let mut runtime = ModbusRtuOverTcpRuntime::from_runtime_config(&config).unwrap();
+        assert_eq!(runtime.protocol(), "modbus_rtu");
+        // Holding registers 0-1 and 5 share one read
+        assert_eq!(runtime.blocks.len(), 2);
+        runtime.connect().await.unwrap();
+
+        let result = runtime.poll_once().await;
+        let value = |point_type: PointType| {
+            result
+                .data
+                .iter()
+                .find(|p| p.id == point_type.to_internal_id(1))
+                .map(|p| p.value.as_f64().unwrap())
+        };
+        assert_eq!(value(PointType::Telemetry), Some(12.5));
+        assert_eq!(value(PointType::Signal), Some(1.0));
+        assert_eq!(result.failures.len(), 1);
+
+        let id = PointType::Adjustment.to_internal_id(1);
+        assert_eq!(runtime.write_adjustment(&[(id, 4.0)]).await.unwrap(), 1);
+        let result = runtime.poll_once().await;
+        assert_eq!(
+            result
+                .data
+                .iter()
+                .find(|p| p.id == PointType::Signal.to_internal_id(1))
+                .map(|p| p.value.as_f64().unwrap()),
+            Some(0.0)
+        );
+        runtime.disconnect().await.unwrap();
+    }
+}
@@ -0,0 +1,225 @@
+//! Modbus RTU framing
+//!
+//! RTU ADUs (unit ID, PDU, CRC-16 low byte first) and the client request and
+//! response PDUs. A TCP stream has no inter-frame silence, so the length of
+//! a response is derived from its function code and byte count.
+
+use common::bytes::checksum::CRC16_MODBUS;
+
+use crate::core::protocols::modbus_server::codec::{function, Table};
+use crate::core::protocols::{error, CodecError};
+
+/// Largest RTU ADU: unit ID, 253-byte PDU, CRC
+pub const MAX_ADU_LEN: usize = 256;
+
+/// RTU frame of a request PDU
+pub fn encode_adu(unit: u8, pdu: &[u8]) -> Vec<u8> {
+    let mut adu = Vec::with_capacity(pdu.len() + 3);
+    adu.push(unit);
+    adu.extend_from_slice(pdu);
+    let crc = CRC16_MODBUS.checksum(&adu);
+    adu.extend_from_slice(&crc.to_le_bytes());
+    adu
+}
+
+/// Length of the response frame at the start of `buf`, once enough of it
+/// has arrived to tell
+pub fn response_len(buf: &[u8]) -> Option<Result<usize, CodecError>> {
+    let code = *buf.get(1)?;
+    if code & 0x80 != 0 {
+        // unit, function, exception code, CRC
+        return Some(Ok(5));
+    }
+    match code {
+        function::READ_COILS
+        | function::READ_DISCRETE_INPUTS
+        | function::READ_HOLDING_REGISTERS
+        | function::READ_INPUT_REGISTERS => buf.get(2).map(|count| Ok(5 + *count as usize)),
+        function::WRITE_SINGLE_COIL
+        | function::WRITE_SINGLE_REGISTER
+        | function::WRITE_MULTIPLE_COILS
+        | function::WRITE_MULTIPLE_REGISTERS => Some(Ok(8)),
+        other => Some(Err(error(format!(
+            "unexpected function code 0x{:02X}",
+            other
+        )))),
+    }
+}
+
+/// Check the CRC of a complete frame; returns its unit ID and PDU
+pub fn decode_adu(frame: &[u8]) -> Result<(u8, &[u8]), CodecError> {
+    if frame.len() < 4 {
+        return Err(error("RTU frame too short"));
+    }
+    let (body, crc) = frame.split_at(frame.len() - 2);
+    let expected = CRC16_MODBUS.checksum(body);
+    let received = u16::from_le_bytes([crc[0], crc[1]]);
+    if received != expected {
+        return Err(error(format!(
+            "CRC mismatch: received 0x{:04X}, computed 0x{:04X}",
+            received, expected
+        )));
+    }
+    Ok((body[0], &body[1..]))
+}
+
+// ============================================================================
+// Requests
+// ============================================================================
+
+/// Read request of `quantity` bits or registers
+pub fn read_request(table: Table, address: u16, quantity: u16) -> Vec<u8> {
+    let code = match table {
+        Table::Coils => function::READ_COILS,
+        Table::DiscreteInputs => function::READ_DISCRETE_INPUTS,
+        Table::HoldingRegisters => function::READ_HOLDING_REGISTERS,
+        Table::InputRegisters => function::READ_INPUT_REGISTERS,
+    };
+    let mut pdu = vec![code];
+    pdu.extend_from_slice(&address.to_be_bytes());
+    pdu.extend_from_slice(&quantity.to_be_bytes());
+    pdu
+}
+
+/// Write single coil (function 5)
+pub fn write_coil_request(address: u16, on: bool) -> Vec<u8> {
+    let mut pdu = vec![function::WRITE_SINGLE_COIL];
+    pdu.extend_from_slice(&address.to_be_bytes());
+    pdu.extend_from_slice(&(if on { 0xFF00u16 } else { 0 }).to_be_bytes());
+    pdu
+}
+
+/// Write registers: function 6 for one word, 16 for several
+pub fn write_registers_request(address: u16, words: &[u16]) -> Vec<u8> {
+    if let [word] = words {
+        let mut pdu = vec![function::WRITE_SINGLE_REGISTER];
+        pdu.extend_from_slice(&address.to_be_bytes());
+        pdu.extend_from_slice(&word.to_be_bytes());
+        return pdu;
+    }
+    let mut pdu = vec![function::WRITE_MULTIPLE_REGISTERS];
+    pdu.extend_from_slice(&address.to_be_bytes());
+    pdu.extend_from_slice(&(words.len() as u16).to_be_bytes());
+    pdu.push((words.len() * 2) as u8);
+    for word in words {
+        pdu.extend_from_slice(&word.to_be_bytes());
+    }
+    pdu
+}
+
+// ============================================================================
+// Responses
+// ============================================================================
+
+/// Reject exception responses and responses to another function
+fn check_function(request: &[u8], response: &[u8]) -> Result<(), CodecError> {
+    let (Some(&sent), Some(&received)) = (request.first(), response.first()) else {
+        return Err(error("empty PDU"));
+    };
+    if received == sent | 0x80 {
+        return Err(error(format!(
+            "exception 0x{:02X} to function 0x{:02X}",
+            response.get(1).copied().unwrap_or(0),
+            sent
+        )));
+    }
+    if received != sent {
+        return Err(error(format!(
+            "response to function 0x{:02X} received for 0x{:02X}",
+            received, sent
+        )));
+    }
+    Ok(())
+}
+
+/// Values of a read response: bits (0/1) or register words
+pub fn parse_read_response(request: &[u8], response: &[u8]) -> Result<Vec<u16>, CodecError> {
+    check_function(request, response)?;
+    let quantity = u16::from_be_bytes([request[3], request[4]]) as usize;
+    let table = Table::from_function_code(request[0]).ok_or_else(|| error("not a read"))?;
+    let expected = if table.is_bits() {
+        quantity.div_ceil(8)
+    } else {
+        quantity * 2
+    };
+    let data = &response[1..];
+    if data.first().map(|&n| n as usize) != Some(expected) || data.len() != expected + 1 {
+        return Err(error(format!(
+            "read response carries {} bytes, expected {}",
+            data.len().saturating_sub(1),
+            expected
+        )));
+    }
+    let data = &data[1..];
+    Ok(if table.is_bits() {
+        (0..quantity)
+            .map(|i| ((data[i / 8] >> (i % 8)) & 1) as u16)
+            .collect()
+    } else {
+        data.chunks_exact(2)
+            .map(|w| u16::from_be_bytes([w[0], w[1]]))
+            .collect()
+    })
+}
+
+/// Check a write response: it echoes address and value/quantity
+pub fn check_write_response(request: &[u8], response: &[u8]) -> Result<(), CodecError> {
+    check_function(request, response)?;
+    if response.len() != 5 || response[1..5] != request[1..5] {
+        return Err(error("write response does not match the request"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adu_round_trip() {
+        // Read holding registers 0-1 of unit 1: well-known frame 01 03 00 00 00 02 C4 0B
+        let adu = encode_adu(1, &read_request(Table::HoldingRegisters, 0, 2));
+        assert_eq!(adu, vec![0x01, 0x03, 0x00, 0x00, 0x00, 0x02, 0xC4, 0x0B]);
+        assert_eq!(response_len(&adu[..1]), None);
+
+        let response = encode_adu(1, &[0x03, 0x04, 0x00, 0x0A, 0x01, 0x02]);
+        assert_eq!(response_len(&response[..2]), None);
+        assert_eq!(response_len(&response), Some(Ok(response.len())));
+        let (unit, pdu) = decode_adu(&response).unwrap();
+        assert_eq!(unit, 1);
+        assert_eq!(
+            parse_read_response(&adu[1..6], pdu).unwrap(),
+            vec![0x000A, 0x0102]
+        );
+
+        let mut corrupted = response.clone();
+        corrupted[3] ^= 0xFF;
+        assert!(decode_adu(&corrupted).is_err());
+    }
+
+    #[test]
+    fn test_responses() {
+        let read = read_request(Table::Coils, 10, 10);
+        assert_eq!(
+            parse_read_response(&read, &[0x01, 0x02, 0b0000_0101, 0b10]).unwrap(),
+            vec![1, 0, 1, 0, 0, 0, 0, 0, 0, 1]
+        );
+        assert!(parse_read_response(&read, &[0x01, 0x01, 0x05]).is_err());
+        assert!(parse_read_response(&read, &[0x81, 0x02]).is_err());
+        assert_eq!(response_len(&[0x01, 0x81]), Some(Ok(5)));
+
+        let write = write_registers_request(100, &[1, 2]);
+        assert_eq!(write, vec![0x10, 0x00, 0x64, 0x00, 0x02, 0x04, 0, 1, 0, 2]);
+        assert!(check_write_response(&write, &[0x10, 0x00, 0x64, 0x00, 0x02]).is_ok());
+        assert!(check_write_response(&write, &[0x10, 0x00, 0x65, 0x00, 0x02]).is_err());
+
+        let coil = write_coil_request(3, true);
+        assert_eq!(coil, vec![0x05, 0x00, 0x03, 0xFF, 0x00]);
+        assert!(check_write_response(&coil, &coil).is_ok());
+        assert_eq!(
+            write_registers_request(7, &[9])[0],
+            function::WRITE_SINGLE_REGISTER
+        );
+    }
+}
@@ -0,0 +1,1188 @@
+//! Modbus TCP server (slave) mode
+//!
+//! A `modbus_tcp` channel with `mode: server` listens for legacy masters
+//! instead of polling a device. Each point of the channel exposes one RTDB
+//! point (its `source`) at the register its Modbus mapping names; the
+//! sources are mirrored into a register image on every poll and masters read
+//! from that image.
+//!
+//! Writes from masters to control and adjustment points go through the
+//! Write-Triggers-Routing path like any other command: instance actions are
+//! written with [`voltage_routing::set_action_point`] (M2C routing, overrides
+//! and guards apply), channel points with
+//! [`voltage_rtdb::helpers::write_point_auto_trigger`] (point hash + TODO
+//! queue). The master gets its response once the write is routed.
+//!
+//! | Point type | Tables (function code)             | Source                          | Master access |
+//! |------------|------------------------------------|---------------------------------|---------------|
+//! | T          | input (4) / holding (3) registers  | `inst:{id}:M:{p}`, any `comsrv` | read          |
+//! | S          | discrete inputs (2), coils (1), registers (bit) | as T               | read          |
+//! | C          | coils (1/5/15), holding registers  | `inst:{id}:A:{p}`, `comsrv:{id}:C/A:{p}` | read/write |
+//! | A          | holding registers (3/6/16)         | as C                            | read/write    |
+//!
+//! `slave_id` is the unit ID the register answers to; `data_type`,
+//! `byte_order` and `bit_position` encode the value as on the client side.
+//! Values are encoded unscaled, so fractional values need a float type.
+//!
+//! ```yaml
+//! protocol: modbus_tcp
+//! parameters:
+//!   mode: server
+//!   host: 0.0.0.0          # listen address
+//!   port: 1502
+//!   max_connections: 8
+//!   read_only: false       # reject all writes
+//!   poll_interval_ms: 500  # source mirror period
+//! ```
+
+pub mod codec;
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use igw::core::traits::{DataEventReceiver, Diagnostics, PointFailure, PollResult};
+use igw::gateway::ChannelRuntime;
+use igw::{ConnectionState, DataBatch, DataPoint, GatewayError};
+use serde_json::Value as JsonValue;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use voltage_model::{KeySpaceConfig, PointType};
+use voltage_rtdb::{RoutingCache, Rtdb};
+
+use self::codec::{exception, ByteOrder, DataType, Frame, FrameReader, Request, Table};
+use crate::core::config::{Point, RuntimeChannelConfig};
+use crate::error::{ComSrvError, Result};
+
+/// Protocol name reported by the runtime
+pub const PROTOCOL: &str = "modbus_tcp";
+
+/// Value of the `mode` parameter selecting this runtime
+pub const SERVER_MODE: &str = "server";
+
+const DEFAULT_HOST: &str = "0.0.0.0";
+const DEFAULT_PORT: u16 = 502;
+const DEFAULT_MAX_CONNECTIONS: usize = 8;
+
+/// Whether channel parameters select server mode
+pub fn is_server_mode(parameters: &HashMap<String, JsonValue>) -> bool {
+    parameters
+        .get("mode")
+        .and_then(|v| v.as_str())
+        .is_some_and(|mode| mode.trim().eq_ignore_ascii_case(SERVER_MODE))
+}
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Channel parameters of a server-mode channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModbusServerConfig {
+    pub host: String,
+    pub port: u16,
+    pub max_connections: usize,
+    pub read_only: bool,
+}
+
+impl ModbusServerConfig {
+    pub fn from_parameters(parameters: &HashMap<String, JsonValue>) -> Result<Self> {
+        let number = |key: &str, default: u64, max: u64| -> Result<u64> {
+            match parameters.get(key) {
+                None | Some(JsonValue::Null) => Ok(default),
+                Some(value) => value
+                    .as_u64()
+                    .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+                    .filter(|v| *v <= max)
+                    .ok_or_else(|| {
+                        ComSrvError::ConfigError(format!(
+                            "Modbus server '{}' must be 0-{}",
+                            key, max
+                        ))
+                    }),
+            }
+        };
+        Ok(Self {
+            host: parameters
+                .get("host")
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .unwrap_or(DEFAULT_HOST)
+                .to_string(),
+            port: number("port", DEFAULT_PORT as u64, u16::MAX as u64)? as u16,
+            max_connections: number("max_connections", DEFAULT_MAX_CONNECTIONS as u64, 1024)?.max(1)
+                as usize,
+            read_only: match parameters.get("read_only") {
+                Some(JsonValue::Bool(b)) => *b,
+                Some(JsonValue::String(s)) => matches!(s.trim(), "true" | "1" | "yes"),
+                Some(JsonValue::Number(n)) => n.as_u64().is_some_and(|n| n != 0),
+                _ => false,
+            },
+        })
+    }
+
+    fn bind_address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+// ============================================================================
+// Register map
+// ============================================================================
+
+/// RTDB point exposed by a register
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// `inst:{id}:M:{point}` or `inst:{id}:A:{point}`
+    Instance {
+        instance_id: u32,
+        action: bool,
+        point_id: String,
+    },
+    /// `comsrv:{id}:{T|S|C|A}:{point}`
+    Channel {
+        channel_id: u32,
+        point_type: PointType,
+        point_id: u32,
+    },
+}
+
+impl Source {
+    pub fn parse(text: &str) -> std::result::Result<Self, String> {
+        let parts: Vec<&str> = text.trim().split(':').collect();
+        let invalid = || {
+            format!(
+                "source '{}' must be inst:<id>:M|A:<point> or comsrv:<id>:T|S|C|A:<point>",
+                text
+            )
+        };
+        let [prefix, id, kind, point] = parts[..] else {
+            return Err(invalid());
+        };
+        let id: u32 = id.parse().map_err(|_| invalid())?;
+        match prefix {
+            "inst" => {
+                let action = match kind {
+                    "M" => false,
+                    "A" => true,
+                    _ => return Err(invalid()),
+                };
+                if point.is_empty() {
+                    return Err(invalid());
+                }
+                Ok(Self::Instance {
+                    instance_id: id,
+                    action,
+                    point_id: point.to_string(),
+                })
+            },
+            "comsrv" => Ok(Self::Channel {
+                channel_id: id,
+                point_type: PointType::from_str(kind).ok_or_else(invalid)?,
+                point_id: point.parse().map_err(|_| invalid())?,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Whether a master write can be routed to the source
+    pub fn is_writable(&self) -> bool {
+        match self {
+            Self::Instance { action, .. } => *action,
+            Self::Channel { point_type, .. } => point_type.is_action(),
+        }
+    }
+
+    /// (hash key, field) of the source value
+    fn location(&self) -> (String, String) {
+        let keyspace = KeySpaceConfig::production_cached();
+        match self {
+            Self::Instance {
+                instance_id,
+                action,
+                point_id,
+            } => {
+                let key = if *action {
+                    keyspace.instance_action_key(*instance_id)
+                } else {
+                    keyspace.instance_measurement_key(*instance_id)
+                };
+                (key, point_id.clone())
+            },
+            Self::Channel {
+                channel_id,
+                point_type,
+                point_id,
+            } => (
+                keyspace.channel_key(*channel_id, *point_type),
+                point_id.to_string(),
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (key, field) = self.location();
+        write!(f, "{}:{}", key, field)
+    }
+}
+
+/// One exposed point
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    point_type: PointType,
+    point_id: u32,
+    unit: u8,
+    table: Table,
+    address: u16,
+    data_type: DataType,
+    byte_order: ByteOrder,
+    /// Bit of a boolean stored in a register
+    bit: Option<u8>,
+    source: Source,
+}
+
+impl Entry {
+    fn from_point(point_type: PointType, point: &Point) -> std::result::Result<Self, String> {
+        let mapping: JsonValue = point
+            .protocol_mappings
+            .as_deref()
+            .and_then(|m| serde_json::from_str(m).ok())
+            .ok_or("missing Modbus mapping")?;
+        let number = |key: &str| {
+            mapping.get(key).and_then(|v| {
+                v.as_u64()
+                    .or_else(|| v.as_str().and_then(|s| s.trim().parse().ok()))
+            })
+        };
+        let text = |key: &str| {
+            mapping
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+
+        let unit = number("slave_id")
+            .and_then(|n| u8::try_from(n).ok())
+            .ok_or("missing/invalid 'slave_id'")?;
+        let code = number("function_code").ok_or("missing 'function_code'")?;
+        let table = u8::try_from(code)
+            .ok()
+            .and_then(Table::from_function_code)
+            .ok_or_else(|| format!("unsupported function code {}", code))?;
+        let address = number("register_address")
+            .and_then(|n| u16::try_from(n).ok())
+            .ok_or("missing/invalid 'register_address'")?;
+        let source = Source::parse(text("source").ok_or("missing 'source'")?)?;
+
+        let allowed = match point_type {
+            PointType::Telemetry => !table.is_bits(),
+            PointType::Signal => true,
+            PointType::Control => table.is_writable(),
+            PointType::Adjustment => table == Table::HoldingRegisters,
+        };
+        if !allowed {
+            return Err(format!(
+                "{} points cannot be served from {:?}",
+                point_type.as_str(),
+                table
+            ));
+        }
+        if point_type.is_action() && !source.is_writable() {
+            return Err(format!(
+                "source {} of a writable point is read-only",
+                source
+            ));
+        }
+
+        let data_type = if table.is_bits() {
+            DataType::Bool
+        } else {
+            DataType::parse(text("data_type").unwrap_or("uint16")).map_err(|e| e.to_string())?
+        };
+        let byte_order =
+            ByteOrder::parse(text("byte_order").unwrap_or("ABCD")).map_err(|e| e.to_string())?;
+        let bit = match (data_type, table.is_bits()) {
+            (DataType::Bool, false) => Some(
+                number("bit_position")
+                    .unwrap_or(0)
+                    .try_into()
+                    .ok()
+                    .filter(|b: &u8| *b < 16)
+                    .ok_or("'bit_position' must be 0-15")?,
+            ),
+            _ => None,
+        };
+        if address as u32 + data_type.words() as u32 > 0x1_0000 {
+            return Err(format!("register {} runs past the address space", address));
+        }
+
+        Ok(Self {
+            point_type,
+            point_id: point.point_id,
+            unit,
+            table,
+            address,
+            data_type,
+            byte_order,
+            bit,
+            source,
+        })
+    }
+
+    fn addresses(&self) -> std::ops::Range<u32> {
+        self.address as u32..self.address as u32 + self.data_type.words() as u32
+    }
+
+    fn overlaps(&self, other: &Entry) -> bool {
+        if self.unit != other.unit || self.table != other.table {
+            return false;
+        }
+        let (a, b) = (self.addresses(), other.addresses());
+        let shared = a.start < b.end && b.start < a.end;
+        // Booleans may share a register on different bits
+        shared && !matches!((self.bit, other.bit), (Some(x), Some(y)) if x != y)
+    }
+
+    fn writable(&self) -> bool {
+        self.point_type.is_action()
+    }
+}
+
+/// Register words of each (unit, table, address)
+type Image = HashMap<(u8, Table, u16), u16>;
+
+/// Store a value in the image
+fn store(image: &mut Image, entry: &Entry, value: f64) {
+    if let Some(bit) = entry.bit {
+        let word = image
+            .entry((entry.unit, entry.table, entry.address))
+            .or_insert(0);
+        if value != 0.0 {
+            *word |= 1 << bit;
+        } else {
+            *word &= !(1 << bit);
+        }
+        return;
+    }
+    let words = codec::encode(value, entry.data_type, entry.byte_order);
+    for (offset, word) in words.into_iter().enumerate() {
+        image.insert(
+            (entry.unit, entry.table, entry.address + offset as u16),
+            word,
+        );
+    }
+}
+
+/// Value of an entry in the image
+fn load(image: &Image, entry: &Entry) -> f64 {
+    let word = |address: u16| {
+        image
+            .get(&(entry.unit, entry.table, address))
+            .copied()
+            .unwrap_or(0)
+    };
+    if let Some(bit) = entry.bit {
+        return f64::from((word(entry.address) >> bit) & 1);
+    }
+    let words: Vec<u16> = (0..entry.data_type.words())
+        .map(|offset| word(entry.address + offset))
+        .collect();
+    codec::decode(&words, entry.data_type, entry.byte_order)
+}
+
+// ============================================================================
+// Server
+// ============================================================================
+
+struct Shared<R: Rtdb> {
+    channel_id: u32,
+    read_only: bool,
+    entries: Vec<Entry>,
+    image: RwLock<Image>,
+    rtdb: Arc<R>,
+    routing_cache: Arc<RoutingCache>,
+    connections: AtomicUsize,
+    requests: AtomicU64,
+    writes: AtomicU64,
+}
+
+impl<R: Rtdb> Shared<R> {
+    /// Mirror the sources into the image; returns the value (or the reason
+    /// there is none) of every entry
+    async fn sync(&self) -> Vec<std::result::Result<f64, String>> {
+        let mut groups: BTreeMap<String, Vec<(usize, String)>> = BTreeMap::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            let (key, field) = entry.source.location();
+            groups.entry(key).or_default().push((index, field));
+        }
+
+        let mut values = vec![Err("not read".to_string()); self.entries.len()];
+        for (key, fields) in groups {
+            let names: Vec<&str> = fields.iter().map(|(_, f)| f.as_str()).collect();
+            match self.rtdb.hash_mget(&key, &names).await {
+                Ok(found) => {
+                    for ((index, _), value) in fields.iter().zip(found) {
+                        values[*index] = value
+                            .and_then(|v| String::from_utf8_lossy(&v).trim().parse::<f64>().ok())
+                            .ok_or_else(|| {
+                                format!("source {} has no value", self.entries[*index].source)
+                            });
+                    }
+                },
+                Err(e) => {
+                    for (index, _) in &fields {
+                        values[*index] = Err(format!("read {}: {}", key, e));
+                    }
+                },
+            }
+        }
+
+        if let Ok(mut image) = self.image.write() {
+            for (entry, value) in self.entries.iter().zip(&values) {
+                if let Ok(value) = value {
+                    store(&mut image, entry, *value);
+                }
+            }
+        }
+        values
+    }
+
+    /// Route a written value to the source of an entry
+    async fn route(&self, entry: &Entry, value: f64) -> anyhow::Result<()> {
+        match &entry.source {
+            Source::Instance {
+                instance_id,
+                point_id,
+                ..
+            } => {
+                let outcome = voltage_routing::set_action_point(
+                    self.rtdb.as_ref(),
+                    &self.routing_cache,
+                    *instance_id,
+                    point_id,
+                    value,
+                )
+                .await?;
+                debug!(
+                    "Ch{} Modbus write {} = {}: {}",
+                    self.channel_id, entry.source, value, outcome.status
+                );
+            },
+            Source::Channel {
+                channel_id,
+                point_type,
+                point_id,
+            } => {
+                voltage_rtdb::helpers::write_point_auto_trigger(
+                    self.rtdb.as_ref(),
+                    KeySpaceConfig::production_cached(),
+                    *channel_id,
+                    *point_type,
+                    *point_id,
+                    value,
+                )
+                .await?;
+            },
+        }
+        self.writes.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Store and route the value of an entry (master write or command)
+    async fn write_entry(&self, index: usize, value: f64) -> anyhow::Result<()> {
+        let entry = &self.entries[index];
+        self.route(entry, value).await?;
+        if let Ok(mut image) = self.image.write() {
+            store(&mut image, entry, value);
+        }
+        info!(
+            "Ch{} Modbus {}{} = {} -> {}",
+            self.channel_id,
+            entry.point_type.as_str(),
+            entry.point_id,
+            value,
+            entry.source
+        );
+        Ok(())
+    }
+
+    /// Response PDU of a request PDU
+    async fn handle(&self, unit: u8, pdu: &[u8]) -> Vec<u8> {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        let requested = pdu.first().copied().unwrap_or(0);
+        if !self.entries.iter().any(|e| e.unit == unit) {
+            return codec::exception_response(requested, exception::GATEWAY_TARGET_FAILED);
+        }
+        let request = match codec::parse_request(pdu) {
+            Ok(request) => request,
+            Err(code) => return codec::exception_response(requested, code),
+        };
+        let result = match request {
+            Request::Read {
+                function,
+                table,
+                address,
+                quantity,
+            } => self.read(unit, function, table, address, quantity),
+            Request::Write {
+                table,
+                address,
+                values,
+                ..
+            } => self
+                .write(unit, table, address, &values)
+                .await
+                .map(|()| codec::write_response(pdu)),
+        };
+        result.unwrap_or_else(|code| codec::exception_response(requested, code))
+    }
+
+    fn read(
+        &self,
+        unit: u8,
+        function: u8,
+        table: Table,
+        address: u16,
+        quantity: u16,
+    ) -> std::result::Result<Vec<u8>, u8> {
+        let range = address as u32..address as u32 + quantity as u32;
+        let mapped = self.entries.iter().any(|e| {
+            e.unit == unit && e.table == table && e.addresses().any(|a| range.contains(&a))
+        });
+        if !mapped {
+            return Err(exception::ILLEGAL_DATA_ADDRESS);
+        }
+        let image = self
+            .image
+            .read()
+            .map_err(|_| exception::SERVER_DEVICE_FAILURE)?;
+        // Unmapped addresses inside the range read as zero
+        let values: Vec<u16> = range
+            .map(|a| image.get(&(unit, table, a as u16)).copied().unwrap_or(0))
+            .collect();
+        Ok(codec::read_response(function, table, &values))
+    }
+
+    async fn write(
+        &self,
+        unit: u8,
+        table: Table,
+        address: u16,
+        values: &[u16],
+    ) -> std::result::Result<(), u8> {
+        if self.read_only {
+            return Err(exception::ILLEGAL_FUNCTION);
+        }
+        let range = address as u32..address as u32 + values.len() as u32;
+        let touched: Vec<usize> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| {
+                e.unit == unit && e.table == table && e.addresses().any(|a| range.contains(&a))
+            })
+            .map(|(index, _)| index)
+            .collect();
+        // Every written register must belong to writable points only
+        let covered = range.clone().all(|a| {
+            let owners: Vec<&Entry> = touched
+                .iter()
+                .map(|i| &self.entries[*i])
+                .filter(|e| e.addresses().contains(&a))
+                .collect();
+            !owners.is_empty() && owners.iter().all(|e| e.writable())
+        });
+        if !covered {
+            return Err(exception::ILLEGAL_DATA_ADDRESS);
+        }
+
+        // Apply to a copy, then route the resulting value of each point
+        let mut image = self
+            .image
+            .read()
+            .map_err(|_| exception::SERVER_DEVICE_FAILURE)?
+            .clone();
+        for (offset, value) in values.iter().enumerate() {
+            image.insert((unit, table, address + offset as u16), *value);
+        }
+        for index in touched {
+            let value = load(&image, &self.entries[index]);
+            if let Err(e) = self.write_entry(index, value).await {
+                warn!(
+                    "Ch{} Modbus write to {} failed: {}",
+                    self.channel_id, self.entries[index].source, e
+                );
+                return Err(exception::SERVER_DEVICE_FAILURE);
+            }
+        }
+        Ok(())
+    }
+
+    /// Serve one master until it disconnects or the channel stops
+    async fn serve(
+        &self,
+        mut stream: TcpStream,
+        shutdown: CancellationToken,
+    ) -> std::io::Result<()> {
+        let mut reader = FrameReader::new();
+        let mut buf = [0u8; 1024];
+        loop {
+            while let Some(frame) = reader.next_frame() {
+                let frame = frame.map_err(|e| std::io::Error::other(e.to_string()))?;
+                let pdu = self.handle(frame.unit_id, &frame.pdu).await;
+                let response = Frame { pdu, ..frame };
+                stream.write_all(&response.encode()).await?;
+            }
+            let n = tokio::select! {
+                _ = shutdown.cancelled() => return Ok(()),
+                read = stream.read(&mut buf) => read?,
+            };
+            if n == 0 {
+                return Ok(());
+            }
+            reader.push(&buf[..n]);
+        }
+    }
+}
+
+/// Modbus TCP server channel
+pub struct ModbusServerRuntime<R: Rtdb> {
+    id: u32,
+    name: String,
+    config: ModbusServerConfig,
+    shared: Arc<Shared<R>>,
+    /// Listener task of the running server
+    shutdown: Option<CancellationToken>,
+    local_addr: Option<SocketAddr>,
+    diagnostics: Diagnostics,
+}
+
+impl<R: Rtdb + 'static> ModbusServerRuntime<R> {
+    /// Build the runtime of a server-mode `modbus_tcp` channel
+    ///
+    /// Points without a valid mapping, or overlapping an earlier point, are
+    /// skipped with a warning.
+    pub fn from_runtime_config(
+        runtime_config: &RuntimeChannelConfig,
+        rtdb: Arc<R>,
+        routing_cache: Arc<RoutingCache>,
+    ) -> Result<Self> {
+        let channel_id = runtime_config.id();
+        let config = ModbusServerConfig::from_parameters(&runtime_config.base.parameters)
+            .map_err(|e| ComSrvError::ConfigError(format!("Ch{}: {}", channel_id, e)))?;
+
+        let mut entries: Vec<Entry> = Vec::new();
+        for (point_type, point) in runtime_config.points() {
+            let parsed = Entry::from_point(point_type, point).and_then(|entry| {
+                match entries.iter().find(|e| e.overlaps(&entry)) {
+                    Some(other) => Err(format!(
+                        "overlaps {}{} at unit {} {:?} {}",
+                        other.point_type.as_str(),
+                        other.point_id,
+                        other.unit,
+                        other.table,
+                        other.address
+                    )),
+                    None => Ok(entry),
+                }
+            });
+            match parsed {
+                Ok(entry) => entries.push(entry),
+                Err(e) => warn!(
+                    "Ch{} {}{} skipped: {}",
+                    channel_id,
+                    point_type.as_str(),
+                    point.point_id,
+                    e
+                ),
+            }
+        }
+        debug!(
+            "Ch{} Modbus server {}: {} points",
+            channel_id,
+            config.bind_address(),
+            entries.len()
+        );
+
+        Ok(Self {
+            id: channel_id,
+            name: runtime_config.name().to_string(),
+            shared: Arc::new(Shared {
+                channel_id,
+                read_only: config.read_only,
+                entries,
+                image: RwLock::new(Image::new()),
+                rtdb,
+                routing_cache,
+                connections: AtomicUsize::new(0),
+                requests: AtomicU64::new(0),
+                writes: AtomicU64::new(0),
+            }),
+            config,
+            shutdown: None,
+            local_addr: None,
+            diagnostics: Diagnostics::new(PROTOCOL),
+        })
+    }
+
+    /// Address the server listens on while connected
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    async fn write(&mut self, point_type: PointType, values: &[(u32, f64)]) -> igw::Result<usize> {
+        let mut written = 0;
+        let mut last_error = None;
+        for &(internal_id, value) in values {
+            let point_id = PointType::from_internal_id(internal_id).1;
+            let Some(index) = self
+                .shared
+                .entries
+                .iter()
+                .position(|e| e.point_type == point_type && e.point_id == point_id)
+            else {
+                last_error = Some(GatewayError::PointNotFound(format!(
+                    "{}{}",
+                    point_type.as_str(),
+                    point_id
+                )));
+                continue;
+            };
+            match self.shared.write_entry(index, value).await {
+                Ok(()) => written += 1,
+                Err(e) => last_error = Some(GatewayError::Protocol(e.to_string())),
+            }
+        }
+        self.diagnostics.write_count += written as u64;
+        match last_error {
+            Some(e) if written == 0 => Err(e),
+            _ => Ok(written),
+        }
+    }
+}
+
+impl<R: Rtdb> Drop for ModbusServerRuntime<R> {
+    fn drop(&mut self) {
+        if let Some(token) = self.shutdown.take() {
+            token.cancel();
+        }
+    }
+}
+
+#[async_trait]
+impl<R: Rtdb + 'static> ChannelRuntime for ModbusServerRuntime<R> {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn protocol(&self) -> &str {
+        PROTOCOL
+    }
+
+    /// Sources are mirrored by `poll_once`
+    fn is_event_driven(&self) -> bool {
+        false
+    }
+
+    /// Bind the listener and start accepting masters
+    async fn connect(&mut self) -> igw::Result<()> {
+        if self.shutdown.is_some() {
+            return Ok(());
+        }
+        let address = self.config.bind_address();
+        let listener = match TcpListener::bind(&address).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                let e = GatewayError::Connection(format!("bind {}: {}", address, e));
+                self.diagnostics.error_count += 1;
+                self.diagnostics.last_error = Some(e.to_string());
+                return Err(e);
+            },
+        };
+        self.local_addr = listener.local_addr().ok();
+        self.shared.sync().await;
+
+        let token = CancellationToken::new();
+        let shared = Arc::clone(&self.shared);
+        let shutdown = token.clone();
+        let max_connections = self.config.max_connections;
+        let channel_id = self.id;
+        tokio::spawn(async move {
+            loop {
+                let (stream, peer) = tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    accepted = listener.accept() => match accepted {
+                        Ok(accepted) => accepted,
+                        Err(e) => {
+                            warn!("Ch{} Modbus server accept: {}", channel_id, e);
+                            continue;
+                        },
+                    },
+                };
+                if shared.connections.load(Ordering::Relaxed) >= max_connections {
+                    warn!(
+                        "Ch{} Modbus master {} refused: {} connections open",
+                        channel_id, peer, max_connections
+                    );
+                    continue;
+                }
+                debug!("Ch{} Modbus master connected from {}", channel_id, peer);
+                let _ = stream.set_nodelay(true);
+                shared.connections.fetch_add(1, Ordering::Relaxed);
+                let shared = Arc::clone(&shared);
+                let shutdown = shutdown.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = shared.serve(stream, shutdown).await {
+                        debug!("Ch{} Modbus master {} closed: {}", channel_id, peer, e);
+                    }
+                    shared.connections.fetch_sub(1, Ordering::Relaxed);
+                });
+            }
+        });
+
+        self.shutdown = Some(token);
+        self.diagnostics.connection_state = ConnectionState::Connected;
+        info!(
+            "Ch{} Modbus server listening on {} ({} points)",
+            self.id,
+            self.local_addr.map(|a| a.to_string()).unwrap_or(address),
+            self.shared.entries.len()
+        );
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> igw::Result<()> {
+        if let Some(token) = self.shutdown.take() {
+            token.cancel();
+        }
+        self.local_addr = None;
+        self.diagnostics.connection_state = ConnectionState::Disconnected;
+        Ok(())
+    }
+
+    /// Mirror the sources and report the exposed telemetry and signals
+    async fn poll_once(&mut self) -> PollResult {
+        let values = self.shared.sync().await;
+        let mut batch = DataBatch::default();
+        let mut failures = Vec::new();
+        for (entry, value) in self.shared.entries.iter().zip(values) {
+            if entry.point_type.is_action() {
+                continue;
+            }
+            let internal_id = entry.point_type.to_internal_id(entry.point_id);
+            match value {
+                Ok(value) => batch.add(DataPoint::new(internal_id, value)),
+                Err(e) => failures.push(PointFailure::with_error(internal_id, e)),
+            }
+        }
+        self.diagnostics.read_count += 1;
+        PollResult::partial(batch, failures)
+    }
+
+    async fn write_control(&mut self, commands: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Control, commands).await
+    }
+
+    async fn write_adjustment(&mut self, adjustments: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Adjustment, adjustments).await
+    }
+
+    fn subscribe(&self) -> Option<DataEventReceiver> {
+        None
+    }
+
+    async fn start_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn stop_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn diagnostics(&self) -> igw::Result<Diagnostics> {
+        let mut diagnostics = self.diagnostics.clone();
+        diagnostics.write_count = self.shared.writes.load(Ordering::Relaxed);
+        diagnostics.extra = serde_json::json!({
+            "mode": SERVER_MODE,
+            "listen": self.local_addr.map(|a| a.to_string()),
+            "connections": self.shared.connections.load(Ordering::Relaxed),
+            "requests": self.shared.requests.load(Ordering::Relaxed),
+        });
+        Ok(diagnostics)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use serde_json::json;
+    use voltage_rtdb::MemoryRtdb;
+
+    fn point<T: serde::de::DeserializeOwned>(point_id: u32, mapping: JsonValue) -> T {
+        serde_json::from_value(json!({
+            "point_id": point_id,
+            "signal_name": format!("p{}", point_id),
+            "protocol_mappings": mapping.to_string(),
+        }))
+        .unwrap()
+    }
+
+    fn runtime_config() -> RuntimeChannelConfig {
+        let mut config = RuntimeChannelConfig::from_base(
+            serde_json::from_value(json!({
+                "id": 40,
+                "name": "scada_gateway",
+                "protocol": "modbus_tcp",
+                "parameters": {"mode": "server", "host": "127.0.0.1", "port": 0},
+            }))
+            .unwrap(),
+        );
+        config.telemetry_points = vec![
+            point(
+                1,
+                json!({"slave_id": 1, "function_code": 4, "register_address": 0,
+                       "data_type": "float32", "source": "inst:5:M:1"}),
+            ),
+            // Overlaps T1 and is skipped
+            point(
+                2,
+                json!({"slave_id": 1, "function_code": 4, "register_address": 1,
+                       "source": "inst:5:M:2"}),
+            ),
+            point(
+                3,
+                json!({"slave_id": 1, "function_code": 4, "register_address": 2,
+                       "data_type": "int16", "source": "inst:5:M:9"}),
+            ),
+        ];
+        config.signal_points = vec![
+            point(
+                1,
+                json!({"slave_id": 1, "function_code": 2, "register_address": 0,
+                       "source": "comsrv:9:S:2"}),
+            ),
+            point(
+                2,
+                json!({"slave_id": 1, "function_code": 3, "register_address": 5,
+                       "data_type": "bool", "bit_position": 3, "source": "comsrv:9:S:3"}),
+            ),
+        ];
+        config.control_points = vec![
+            point(
+                1,
+                json!({"slave_id": 1, "function_code": 5, "register_address": 0,
+                       "source": "comsrv:9:C:1"}),
+            ),
+            // Writable points need a writable source
+            point(
+                2,
+                json!({"slave_id": 1, "function_code": 5, "register_address": 1,
+                       "source": "inst:5:M:1"}),
+            ),
+        ];
+        config.adjustment_points = vec![point(
+            1,
+            json!({"slave_id": 1, "function_code": 6, "register_address": 20,
+                   "data_type": "int16", "source": "inst:5:A:7"}),
+        )];
+        config
+    }
+
+    #[test]
+    fn test_config_and_register_map() {
+        let rtdb = Arc::new(MemoryRtdb::new());
+        let runtime = ModbusServerRuntime::from_runtime_config(
+            &runtime_config(),
+            rtdb,
+            Arc::new(RoutingCache::new()),
+        )
+        .unwrap();
+        let points: Vec<(PointType, u32)> = runtime
+            .shared
+            .entries
+            .iter()
+            .map(|e| (e.point_type, e.point_id))
+            .collect();
+        assert_eq!(
+            points,
+            vec![
+                (PointType::Telemetry, 1),
+                (PointType::Telemetry, 3),
+                (PointType::Signal, 1),
+                (PointType::Signal, 2),
+                (PointType::Control, 1),
+                (PointType::Adjustment, 1),
+            ]
+        );
+        assert_eq!(runtime.config.max_connections, DEFAULT_MAX_CONNECTIONS);
+
+        assert_eq!(
+            Source::parse("inst:5:A:setpoint").unwrap(),
+            Source::Instance {
+                instance_id: 5,
+                action: true,
+                point_id: "setpoint".to_string()
+            }
+        );
+        assert_eq!(
+            Source::parse("comsrv:9:T:4").unwrap().to_string(),
+            "comsrv:9:T:4"
+        );
+        assert_eq!(
+            Source::parse("inst:5:M:power").unwrap().to_string(),
+            "inst:5:M:power"
+        );
+        assert!(Source::parse("inst:5:X:1").is_err());
+        assert!(Source::parse("comsrv:9:T").is_err());
+        assert!(is_server_mode(
+            &serde_json::from_value(json!({"mode": "Server"})).unwrap()
+        ));
+    }
+
+    async fn request(stream: &mut TcpStream, transaction_id: u16, unit: u8, pdu: &[u8]) -> Vec<u8> {
+        let frame = Frame {
+            transaction_id,
+            unit_id: unit,
+            pdu: pdu.to_vec(),
+        };
+        stream.write_all(&frame.encode()).await.unwrap();
+        let mut reader = FrameReader::new();
+        let mut buf = [0u8; 512];
+        loop {
+            if let Some(response) = reader.next_frame() {
+                let response = response.unwrap();
+                assert_eq!(response.transaction_id, transaction_id);
+                return response.pdu;
+            }
+            let n = stream.read(&mut buf).await.unwrap();
+            assert!(n > 0, "server closed the connection");
+            reader.push(&buf[..n]);
+        }
+    }
+
+    async fn stored(rtdb: &MemoryRtdb, key: &str, field: &str) -> f64 {
+        let bytes = rtdb.hash_get(key, field).await.unwrap().unwrap();
+        String::from_utf8_lossy(&bytes).parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_masters_read_and_write_through_routing() {
+        let rtdb = Arc::new(MemoryRtdb::new());
+        rtdb.hash_set("inst:5:M", "1", Bytes::from("230.5"))
+            .await
+            .unwrap();
+        rtdb.hash_set("inst:5:M", "9", Bytes::from("-12"))
+            .await
+            .unwrap();
+        rtdb.hash_set("comsrv:9:S", "2", Bytes::from("1"))
+            .await
+            .unwrap();
+        rtdb.hash_set("comsrv:9:S", "3", Bytes::from("1"))
+            .await
+            .unwrap();
+        rtdb.hash_set("inst:5:A", "7", Bytes::from("3"))
+            .await
+            .unwrap();
+
+        let mut runtime = ModbusServerRuntime::from_runtime_config(
+            &runtime_config(),
+            Arc::clone(&rtdb),
+            Arc::new(RoutingCache::new()),
+        )
+        .unwrap();
+        runtime.connect().await.unwrap();
+
+        let result = runtime.poll_once().await;
+        let values: HashMap<u32, f64> = result
+            .data
+            .iter()
+            .map(|p| (p.id, p.value.as_f64().unwrap()))
+            .collect();
+        assert_eq!(values[&PointType::Telemetry.to_internal_id(1)], 230.5);
+        assert_eq!(values[&PointType::Signal.to_internal_id(2)], 1.0);
+        // C1's source has no value yet; only T/S are reported
+        assert!(result.failures.is_empty());
+
+        let mut master = TcpStream::connect(runtime.local_addr().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            request(&mut master, 1, 1, &[4, 0, 0, 0, 3]).await,
+            vec![4, 6, 0x43, 0x66, 0x80, 0x00, 0xFF, 0xF4]
+        );
+        assert_eq!(
+            request(&mut master, 2, 1, &[2, 0, 0, 0, 1]).await,
+            vec![2, 1, 1]
+        );
+        assert_eq!(
+            request(&mut master, 3, 1, &[3, 0, 5, 0, 1]).await,
+            vec![3, 2, 0, 0b1000]
+        );
+
+        // Adjustment write is routed to the instance action
+        assert_eq!(
+            request(&mut master, 4, 1, &[6, 0, 20, 0xFF, 0xFE]).await,
+            vec![6, 0, 20, 0xFF, 0xFE]
+        );
+        assert_eq!(stored(&rtdb, "inst:5:A", "7").await, -2.0);
+        // Control write goes to the channel hash and its TODO queue
+        assert_eq!(
+            request(&mut master, 5, 1, &[5, 0, 0, 0xFF, 0]).await,
+            vec![5, 0, 0, 0xFF, 0]
+        );
+        assert_eq!(stored(&rtdb, "comsrv:9:C", "1").await, 1.0);
+        assert_eq!(
+            rtdb.list_range("comsrv:9:C:TODO", 0, -1)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            request(&mut master, 6, 1, &[1, 0, 0, 0, 1]).await,
+            vec![1, 1, 1]
+        );
+
+        // Read-only, unmapped and unknown-unit accesses fail
+        assert_eq!(
+            request(&mut master, 7, 1, &[6, 0, 0, 0, 1]).await,
+            vec![0x86, exception::ILLEGAL_DATA_ADDRESS]
+        );
+        assert_eq!(
+            request(&mut master, 8, 1, &[3, 0, 100, 0, 1]).await,
+            vec![0x83, exception::ILLEGAL_DATA_ADDRESS]
+        );
+        assert_eq!(
+            request(&mut master, 9, 7, &[3, 0, 5, 0, 1]).await,
+            vec![0x83, exception::GATEWAY_TARGET_FAILED]
+        );
+
+        // Commands to the channel's own points take the same path
+        let written = runtime
+            .write_adjustment(&[(PointType::Adjustment.to_internal_id(1), 42.0)])
+            .await
+            .unwrap();
+        assert_eq!(written, 1);
+        assert_eq!(
+            request(&mut master, 10, 1, &[3, 0, 20, 0, 1]).await,
+            vec![3, 2, 0, 42]
+        );
+        assert_eq!(runtime.diagnostics().await.unwrap().write_count, 3);
+
+        runtime.disconnect().await.unwrap();
+    }
+}
@@ -0,0 +1,587 @@
+//! Modbus TCP server wire format
+//!
+//! MBAP framing, request parsing and response building for the eight data
+//! access functions, and the register encoding of point values.
+
+use crate::core::protocols::{error, CodecError};
+
+/// MBAP header: transaction ID, protocol ID, length, unit ID
+pub const MBAP_HEADER_LEN: usize = 7;
+
+/// Largest PDU of a Modbus TCP frame
+pub const MAX_PDU_LEN: usize = 253;
+
+/// Function codes
+pub mod function {
+    pub const READ_COILS: u8 = 0x01;
+    pub const READ_DISCRETE_INPUTS: u8 = 0x02;
+    pub const READ_HOLDING_REGISTERS: u8 = 0x03;
+    pub const READ_INPUT_REGISTERS: u8 = 0x04;
+    pub const WRITE_SINGLE_COIL: u8 = 0x05;
+    pub const WRITE_SINGLE_REGISTER: u8 = 0x06;
+    pub const WRITE_MULTIPLE_COILS: u8 = 0x0F;
+    pub const WRITE_MULTIPLE_REGISTERS: u8 = 0x10;
+}
+
+/// Exception codes
+pub mod exception {
+    pub const ILLEGAL_FUNCTION: u8 = 0x01;
+    pub const ILLEGAL_DATA_ADDRESS: u8 = 0x02;
+    pub const ILLEGAL_DATA_VALUE: u8 = 0x03;
+    pub const SERVER_DEVICE_FAILURE: u8 = 0x04;
+    pub const GATEWAY_TARGET_FAILED: u8 = 0x0B;
+}
+
+/// Quantity limits of the specification
+const MAX_READ_BITS: u16 = 2000;
+const MAX_READ_REGISTERS: u16 = 125;
+const MAX_WRITE_BITS: u16 = 1968;
+const MAX_WRITE_REGISTERS: u16 = 123;
+
+// ============================================================================
+// Framing
+// ============================================================================
+
+/// One request or response frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub transaction_id: u16,
+    pub unit_id: u8,
+    pub pdu: Vec<u8>,
+}
+
+impl Frame {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(MBAP_HEADER_LEN + self.pdu.len());
+        out.extend_from_slice(&self.transaction_id.to_be_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out.extend_from_slice(&(self.pdu.len() as u16 + 1).to_be_bytes());
+        out.push(self.unit_id);
+        out.extend_from_slice(&self.pdu);
+        out
+    }
+}
+
+/// Splits the received byte stream into frames
+#[derive(Debug, Default)]
+pub struct FrameReader {
+    buf: Vec<u8>,
+}
+
+impl FrameReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Next complete frame; an error means the stream is out of sync
+    pub fn next_frame(&mut self) -> Option<Result<Frame, CodecError>> {
+        if self.buf.len() < MBAP_HEADER_LEN {
+            return None;
+        }
+        let protocol_id = u16::from_be_bytes([self.buf[2], self.buf[3]]);
+        let length = u16::from_be_bytes([self.buf[4], self.buf[5]]) as usize;
+        if protocol_id != 0 {
+            return Some(Err(error(format!(
+                "protocol ID {} is not Modbus",
+                protocol_id
+            ))));
+        }
+        if !(2..=MAX_PDU_LEN + 1).contains(&length) {
+            return Some(Err(error(format!("invalid MBAP length {}", length))));
+        }
+        let total = MBAP_HEADER_LEN - 1 + length;
+        if self.buf.len() < total {
+            return None;
+        }
+        let frame = Frame {
+            transaction_id: u16::from_be_bytes([self.buf[0], self.buf[1]]),
+            unit_id: self.buf[6],
+            pdu: self.buf[MBAP_HEADER_LEN..total].to_vec(),
+        };
+        self.buf.drain(..total);
+        Some(Ok(frame))
+    }
+}
+
+// ============================================================================
+// Requests
+// ============================================================================
+
+/// Data table of the Modbus data model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Table {
+    Coils,
+    DiscreteInputs,
+    HoldingRegisters,
+    InputRegisters,
+}
+
+impl Table {
+    /// Table a function code of a point mapping reads or writes
+    pub fn from_function_code(code: u8) -> Option<Self> {
+        match code {
+            function::READ_COILS | function::WRITE_SINGLE_COIL | function::WRITE_MULTIPLE_COILS => {
+                Some(Self::Coils)
+            },
+            function::READ_DISCRETE_INPUTS => Some(Self::DiscreteInputs),
+            function::READ_HOLDING_REGISTERS
+            | function::WRITE_SINGLE_REGISTER
+            | function::WRITE_MULTIPLE_REGISTERS => Some(Self::HoldingRegisters),
+            function::READ_INPUT_REGISTERS => Some(Self::InputRegisters),
+            _ => None,
+        }
+    }
+
+    /// Single-bit table (coils, discrete inputs)
+    pub fn is_bits(self) -> bool {
+        matches!(self, Self::Coils | Self::DiscreteInputs)
+    }
+
+    /// Table masters can write
+    pub fn is_writable(self) -> bool {
+        matches!(self, Self::Coils | Self::HoldingRegisters)
+    }
+}
+
+/// Parsed request PDU
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+    Read {
+        function: u8,
+        table: Table,
+        address: u16,
+        quantity: u16,
+    },
+    Write {
+        function: u8,
+        table: Table,
+        address: u16,
+        /// Bits (0/1) or register words
+        values: Vec<u16>,
+    },
+}
+
+impl Request {
+    pub fn function(&self) -> u8 {
+        match self {
+            Self::Read { function, .. } | Self::Write { function, .. } => *function,
+        }
+    }
+}
+
+fn word(pdu: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*pdu.get(at)?, *pdu.get(at + 1)?]))
+}
+
+/// Parse a request PDU; `Err` is the exception code to answer with
+pub fn parse_request(pdu: &[u8]) -> Result<Request, u8> {
+    let function = *pdu.first().ok_or(exception::ILLEGAL_FUNCTION)?;
+    let malformed = exception::ILLEGAL_DATA_VALUE;
+    let address = word(pdu, 1).ok_or(malformed)?;
+    let field = word(pdu, 3).ok_or(malformed)?;
+
+    let read = |table: Table, max: u16| {
+        if field == 0 || field > max || pdu.len() != 5 {
+            return Err(malformed);
+        }
+        if address as u32 + field as u32 > 0x1_0000 {
+            return Err(exception::ILLEGAL_DATA_ADDRESS);
+        }
+        Ok(Request::Read {
+            function,
+            table,
+            address,
+            quantity: field,
+        })
+    };
+
+    match function {
+        function::READ_COILS => read(Table::Coils, MAX_READ_BITS),
+        function::READ_DISCRETE_INPUTS => read(Table::DiscreteInputs, MAX_READ_BITS),
+        function::READ_HOLDING_REGISTERS => read(Table::HoldingRegisters, MAX_READ_REGISTERS),
+        function::READ_INPUT_REGISTERS => read(Table::InputRegisters, MAX_READ_REGISTERS),
+        function::WRITE_SINGLE_COIL => {
+            let value = match field {
+                0xFF00 => 1,
+                0x0000 => 0,
+                _ => return Err(malformed),
+            };
+            Ok(Request::Write {
+                function,
+                table: Table::Coils,
+                address,
+                values: vec![value],
+            })
+        },
+        function::WRITE_SINGLE_REGISTER => Ok(Request::Write {
+            function,
+            table: Table::HoldingRegisters,
+            address,
+            values: vec![field],
+        }),
+        function::WRITE_MULTIPLE_COILS | function::WRITE_MULTIPLE_REGISTERS => {
+            let bits = function == function::WRITE_MULTIPLE_COILS;
+            let (max, byte_count) = if bits {
+                (MAX_WRITE_BITS, field.div_ceil(8) as usize)
+            } else {
+                (MAX_WRITE_REGISTERS, field as usize * 2)
+            };
+            if field == 0
+                || field > max
+                || pdu.get(5).map(|&n| n as usize) != Some(byte_count)
+                || pdu.len() != 6 + byte_count
+            {
+                return Err(malformed);
+            }
+            if address as u32 + field as u32 > 0x1_0000 {
+                return Err(exception::ILLEGAL_DATA_ADDRESS);
+            }
+            let data = &pdu[6..];
+            let values = if bits {
+                (0..field as usize)
+                    .map(|i| ((data[i / 8] >> (i % 8)) & 1) as u16)
+                    .collect()
+            } else {
+                data.chunks_exact(2)
+                    .map(|w| u16::from_be_bytes([w[0], w[1]]))
+                    .collect()
+            };
+            Ok(Request::Write {
+                function,
+                table: if bits {
+                    Table::Coils
+                } else {
+                    Table::HoldingRegisters
+                },
+                address,
+                values,
+            })
+        },
+        _ => Err(exception::ILLEGAL_FUNCTION),
+    }
+}
+
+// ============================================================================
+// Responses
+// ============================================================================
+
+/// Response to a read: packed bits or register words
+pub fn read_response(function: u8, table: Table, values: &[u16]) -> Vec<u8> {
+    if table.is_bits() {
+        let mut packed = vec![0u8; values.len().div_ceil(8)];
+        for (i, _) in values.iter().enumerate().filter(|(_, v)| **v != 0) {
+            packed[i / 8] |= 1 << (i % 8);
+        }
+        let mut pdu = vec![function, packed.len() as u8];
+        pdu.extend_from_slice(&packed);
+        pdu
+    } else {
+        let mut pdu = vec![function, (values.len() * 2) as u8];
+        for value in values {
+            pdu.extend_from_slice(&value.to_be_bytes());
+        }
+        pdu
+    }
+}
+
+/// Response to a write (single writes echo the request)
+pub fn write_response(request_pdu: &[u8]) -> Vec<u8> {
+    request_pdu[..5.min(request_pdu.len())].to_vec()
+}
+
+pub fn exception_response(function: u8, code: u8) -> Vec<u8> {
+    vec![function | 0x80, code]
+}
+
+// ============================================================================
+// Register encoding
+// ============================================================================
+
+/// Register data type of a point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataType {
+    Bool,
+    Uint16,
+    Int16,
+    Uint32,
+    Int32,
+    Float32,
+    Float64,
+}
+
+impl DataType {
+    pub fn parse(name: &str) -> Result<Self, CodecError> {
+        Ok(match name.trim().to_ascii_lowercase().as_str() {
+            "bool" | "boolean" => Self::Bool,
+            "uint16" | "u16" => Self::Uint16,
+            "int16" | "i16" => Self::Int16,
+            "uint32" | "u32" => Self::Uint32,
+            "int32" | "i32" => Self::Int32,
+            "float32" | "f32" | "float" => Self::Float32,
+            "float64" | "f64" | "double" => Self::Float64,
+            other => return Err(error(format!("unsupported data type '{}'", other))),
+        })
+    }
+
+    /// Registers a value occupies
+    pub fn words(self) -> u16 {
+        match self {
+            Self::Bool | Self::Uint16 | Self::Int16 => 1,
+            Self::Uint32 | Self::Int32 | Self::Float32 => 2,
+            Self::Float64 => 4,
+        }
+    }
+}
+
+/// Byte order of multi-byte values (letters name the big-endian bytes)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// Big endian
+    Abcd,
+    /// Little endian
+    Dcba,
+    /// Bytes swapped within each word
+    Badc,
+    /// Words swapped
+    Cdab,
+}
+
+impl ByteOrder {
+    pub fn parse(name: &str) -> Result<Self, CodecError> {
+        Ok(match name.trim().to_ascii_uppercase().as_str() {
+            "ABCD" | "AB" | "ABCDEFGH" => Self::Abcd,
+            "DCBA" | "BA" | "HGFEDCBA" => Self::Dcba,
+            "BADC" | "BADCFEHG" => Self::Badc,
+            "CDAB" | "GHEFCDAB" => Self::Cdab,
+            other => return Err(error(format!("unsupported byte order '{}'", other))),
+        })
+    }
+
+    /// Reorder big-endian bytes to wire order (and back: every order is its own inverse)
+    fn apply(self, bytes: &mut [u8]) {
+        match self {
+            Self::Abcd => {},
+            Self::Dcba => bytes.reverse(),
+            Self::Badc => bytes.chunks_exact_mut(2).for_each(|w| w.swap(0, 1)),
+            Self::Cdab => {
+                let words: Vec<[u8; 2]> =
+                    bytes.chunks_exact(2).rev().map(|w| [w[0], w[1]]).collect();
+                for (chunk, w) in bytes.chunks_exact_mut(2).zip(words) {
+                    chunk.copy_from_slice(&w);
+                }
+            },
+        }
+    }
+}
+
+/// Registers holding `value`; integers are rounded and saturated
+pub fn encode(value: f64, data_type: DataType, order: ByteOrder) -> Vec<u16> {
+    let mut bytes = match data_type {
+        DataType::Bool => (u16::from(value != 0.0)).to_be_bytes().to_vec(),
+        DataType::Uint16 => (value.round() as u16).to_be_bytes().to_vec(),
+        DataType::Int16 => (value.round() as i16).to_be_bytes().to_vec(),
+        DataType::Uint32 => (value.round() as u32).to_be_bytes().to_vec(),
+        DataType::Int32 => (value.round() as i32).to_be_bytes().to_vec(),
+        DataType::Float32 => (value as f32).to_be_bytes().to_vec(),
+        DataType::Float64 => value.to_be_bytes().to_vec(),
+    };
+    order.apply(&mut bytes);
+    bytes
+        .chunks_exact(2)
+        .map(|w| u16::from_be_bytes([w[0], w[1]]))
+        .collect()
+}
+
+/// Value of registers written in `data_type`; `words` must hold
+/// [`DataType::words`] registers
+pub fn decode(words: &[u16], data_type: DataType, order: ByteOrder) -> f64 {
+    let mut bytes: Vec<u8> = words.iter().flat_map(|w| w.to_be_bytes()).collect();
+    order.apply(&mut bytes);
+    let array = |n: usize| -> [u8; 8] {
+        let mut out = [0u8; 8];
+        out[..n].copy_from_slice(&bytes[..n]);
+        out
+    };
+    match data_type {
+        DataType::Bool => f64::from(u8::from(u16::from_be_bytes([bytes[0], bytes[1]]) != 0)),
+        DataType::Uint16 => u16::from_be_bytes([bytes[0], bytes[1]]) as f64,
+        DataType::Int16 => i16::from_be_bytes([bytes[0], bytes[1]]) as f64,
+        DataType::Uint32 => {
+            let b = array(4);
+            u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as f64
+        },
+        DataType::Int32 => {
+            let b = array(4);
+            i32::from_be_bytes([b[0], b[1], b[2], b[3]]) as f64
+        },
+        DataType::Float32 => {
+            let b = array(4);
+            f32::from_be_bytes([b[0], b[1], b[2], b[3]]) as f64
+        },
+        DataType::Float64 => f64::from_be_bytes(array(8)),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_reader_splits_stream() {
+        let first = Frame {
+            transaction_id: 7,
+            unit_id: 1,
+            pdu: vec![3, 0, 0, 0, 2],
+        };
+        let second = Frame {
+            transaction_id: 8,
+            unit_id: 2,
+            pdu: vec![6, 0, 1, 0, 9],
+        };
+        let mut bytes = first.encode();
+        bytes.extend(second.encode());
+
+        let mut reader = FrameReader::new();
+        reader.push(&bytes[..10]);
+        assert!(reader.next_frame().is_none());
+        reader.push(&bytes[10..]);
+        assert_eq!(reader.next_frame().unwrap().unwrap(), first);
+        assert_eq!(reader.next_frame().unwrap().unwrap(), second);
+        assert!(reader.next_frame().is_none());
+
+        reader.push(&[0, 1, 0, 5, 0, 2, 1]);
+        assert!(reader.next_frame().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_parse_requests() {
+        assert_eq!(
+            parse_request(&[3, 0, 10, 0, 2]),
+            Ok(Request::Read {
+                function: 3,
+                table: Table::HoldingRegisters,
+                address: 10,
+                quantity: 2
+            })
+        );
+        assert_eq!(
+            parse_request(&[5, 0, 4, 0xFF, 0]),
+            Ok(Request::Write {
+                function: 5,
+                table: Table::Coils,
+                address: 4,
+                values: vec![1]
+            })
+        );
+        assert_eq!(
+            parse_request(&[15, 0, 0, 0, 10, 2, 0b0000_0101, 0b10]),
+            Ok(Request::Write {
+                function: 15,
+                table: Table::Coils,
+                address: 0,
+                values: vec![1, 0, 1, 0, 0, 0, 0, 0, 0, 1]
+            })
+        );
+        assert_eq!(
+            parse_request(&[16, 0, 1, 0, 2, 4, 0, 1, 0xAB, 0xCD]),
+            Ok(Request::Write {
+                function: 16,
+                table: Table::HoldingRegisters,
+                address: 1,
+                values: vec![1, 0xABCD]
+            })
+        );
+
+        assert_eq!(
+            parse_request(&[3, 0, 0, 0, 126]),
+            Err(exception::ILLEGAL_DATA_VALUE)
+        );
+        assert_eq!(
+            parse_request(&[5, 0, 0, 0x12, 0]),
+            Err(exception::ILLEGAL_DATA_VALUE)
+        );
+        assert_eq!(
+            parse_request(&[16, 0, 0, 0, 2, 3, 0, 1, 0]),
+            Err(exception::ILLEGAL_DATA_VALUE)
+        );
+        assert_eq!(
+            parse_request(&[3, 0xFF, 0xFF, 0, 2]),
+            Err(exception::ILLEGAL_DATA_ADDRESS)
+        );
+        assert_eq!(
+            parse_request(&[0x2B, 0, 0, 0, 0]),
+            Err(exception::ILLEGAL_FUNCTION)
+        );
+    }
+
+    #[test]
+    fn test_read_responses() {
+        assert_eq!(
+            read_response(1, Table::Coils, &[1, 0, 1, 1, 0, 0, 0, 0, 1]),
+            vec![1, 2, 0b0000_1101, 1]
+        );
+        assert_eq!(
+            read_response(3, Table::HoldingRegisters, &[0x1234, 5]),
+            vec![3, 4, 0x12, 0x34, 0, 5]
+        );
+        assert_eq!(exception_response(3, 2), vec![0x83, 2]);
+    }
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let orders = [
+            ByteOrder::Abcd,
+            ByteOrder::Dcba,
+            ByteOrder::Badc,
+            ByteOrder::Cdab,
+        ];
+        for order in orders {
+            for (data_type, value) in [
+                (DataType::Uint16, 65535.0),
+                (DataType::Int16, -1234.0),
+                (DataType::Uint32, 4_000_000_000.0),
+                (DataType::Int32, -70000.0),
+                (DataType::Float32, 230.5),
+                (DataType::Float64, -0.125),
+                (DataType::Bool, 1.0),
+            ] {
+                let words = encode(value, data_type, order);
+                assert_eq!(words.len(), data_type.words() as usize);
+                assert_eq!(
+                    decode(&words, data_type, order),
+                    value,
+                    "{:?} {:?}",
+                    data_type,
+                    order
+                );
+            }
+        }
+
+        assert_eq!(
+            encode(230.5, DataType::Float32, ByteOrder::Abcd),
+            vec![0x4366, 0x8000]
+        );
+        assert_eq!(
+            encode(230.5, DataType::Float32, ByteOrder::Cdab),
+            vec![0x8000, 0x4366]
+        );
+        assert_eq!(
+            encode(230.5, DataType::Float32, ByteOrder::Badc),
+            vec![0x6643, 0x0080]
+        );
+        assert_eq!(
+            encode(230.5, DataType::Float32, ByteOrder::Dcba),
+            vec![0x0080, 0x6643]
+        );
+        // Out-of-range values saturate
+        assert_eq!(encode(-5.0, DataType::Uint16, ByteOrder::Abcd), vec![0]);
+        assert_eq!(encode(1e6, DataType::Int16, ByteOrder::Abcd), vec![0x7FFF]);
+        assert!(DataType::parse("string").is_err());
+        assert!(ByteOrder::parse("ACBD").is_err());
+    }
+}
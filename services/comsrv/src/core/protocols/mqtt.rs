@@ -0,0 +1,1085 @@
+//! MQTT subscriber
+//!
+//! Connects to a broker with MQTT 3.1.1, subscribes to the topics of all
+//! telemetry and signal points and extracts their values from the received
+//! JSON payloads with a JSONPath expression. Messages are collected on each
+//! poll and the latest value of each point is stored; retained messages
+//! delivered on subscription give the initial values.
+//!
+//! Control and adjustment points publish their value to a command topic,
+//! wrapped in a JSON document shaped by their `json_path` (`$.setpoint`
+//! publishes `{"setpoint": 12.5}`; the root `$` publishes the bare number).
+//!
+//! | Point type | Mapping                                   | Operation               |
+//! |------------|-------------------------------------------|-------------------------|
+//! | T / S      | `topic` (filter, `+`/`#` allowed), `json_path` | value from PUBLISH |
+//! | C / A      | `topic` (default: `command_topic`), `json_path` | PUBLISH of the value |
+//!
+//! Payloads that are not JSON are read as plain numbers (`12.5`, `true`,
+//! `ON`) when the point's path is the root.
+//!
+//! ```yaml
+//! protocol: mqtt
+//! parameters:
+//!   host: 127.0.0.1
+//!   port: 1883
+//!   client_id: comsrv-ems1   # default comsrv-<channel id>
+//!   username: sensor         # omit for anonymous
+//!   password: secret
+//!   qos: 1
+//!   command_topic: site/pcs1/cmd
+//! ```
+
+pub mod codec;
+pub mod json_path;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use igw::core::traits::{DataEventReceiver, Diagnostics, PointFailure, PollResult};
+use igw::gateway::ChannelRuntime;
+use igw::{ConnectionState, DataBatch, DataPoint, GatewayError};
+use serde_json::{json, Value as JsonValue};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{timeout_at, Instant};
+use tracing::{debug, info, warn};
+
+use self::codec::{Connect, Packet, PacketReader};
+use self::json_path::JsonPath;
+use crate::core::config::RuntimeChannelConfig;
+use crate::core::plugins::{MappingColumn, ParameterMetadata, ParameterType};
+use crate::core::protocols::{as_bool, as_u64, point_mapping, record_failure};
+use crate::error::{ComSrvError, Result};
+use voltage_model::PointType;
+
+/// Protocol name stored in `channels.protocol`
+pub const PROTOCOL: &str = "mqtt";
+
+const DEFAULT_PORT: u16 = 1883;
+const DEFAULT_KEEP_ALIVE_SECS: u64 = 60;
+const DEFAULT_QOS: u64 = 0;
+const DEFAULT_RESPONSE_TIMEOUT_MS: u64 = 5000;
+const DEFAULT_RECEIVE_WAIT_MS: u64 = 100;
+
+/// Topic filters per SUBSCRIBE packet
+const SUBSCRIBE_BATCH: usize = 100;
+
+/// Messages kept between polls; older ones are dropped first
+const MAX_PENDING_MESSAGES: usize = 10_000;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Channel parameters of an MQTT channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct MqttConfig {
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub keep_alive_secs: u16,
+    pub clean_session: bool,
+    /// QoS of subscriptions and command publishes (0 or 1)
+    pub qos: u8,
+    /// Topic of control/adjustment points without their own `topic`
+    pub command_topic: Option<String>,
+    pub retain_commands: bool,
+    pub response_timeout: Duration,
+    /// Time a poll waits for messages
+    pub receive_wait: Duration,
+}
+
+impl MqttConfig {
+    pub fn from_parameters(
+        channel_id: u32,
+        parameters: &HashMap<String, JsonValue>,
+    ) -> Result<Self> {
+        let text = |key: &str| {
+            parameters
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+        let number = |key: &str, default: u64, max: u64| -> Result<u64> {
+            match parameters.get(key) {
+                None | Some(JsonValue::Null) => Ok(default),
+                Some(value) => as_u64(value).filter(|v| *v <= max).ok_or_else(|| {
+                    ComSrvError::ConfigError(format!("MQTT '{}' must be 0-{}", key, max))
+                }),
+            }
+        };
+        let flag =
+            |key: &str, default: bool| parameters.get(key).and_then(as_bool).unwrap_or(default);
+
+        let host = text("host")
+            .ok_or_else(|| ComSrvError::ConfigError("MQTT channel requires 'host'".into()))?
+            .to_string();
+        let command_topic = text("command_topic").map(str::to_string);
+        if let Some(topic) = &command_topic {
+            if topic.contains(['+', '#']) {
+                return Err(ComSrvError::ConfigError(format!(
+                    "MQTT command_topic '{}' must not contain wildcards",
+                    topic
+                )));
+            }
+        }
+
+        Ok(Self {
+            host,
+            port: number("port", DEFAULT_PORT as u64, u16::MAX as u64)? as u16,
+            client_id: text("client_id")
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("comsrv-{}", channel_id)),
+            username: text("username").map(str::to_string),
+            password: parameters
+                .get("password")
+                .and_then(|v| v.as_str())
+                .filter(|p| !p.is_empty())
+                .map(str::to_string),
+            keep_alive_secs: number("keep_alive_secs", DEFAULT_KEEP_ALIVE_SECS, u16::MAX as u64)?
+                as u16,
+            clean_session: flag("clean_session", true),
+            qos: number("qos", DEFAULT_QOS, 1)? as u8,
+            command_topic,
+            retain_commands: flag("retain_commands", false),
+            response_timeout: Duration::from_millis(
+                number(
+                    "response_timeout_ms",
+                    DEFAULT_RESPONSE_TIMEOUT_MS,
+                    u32::MAX as u64,
+                )?
+                .max(1),
+            ),
+            receive_wait: Duration::from_millis(number(
+                "receive_wait_ms",
+                DEFAULT_RECEIVE_WAIT_MS,
+                60_000,
+            )?),
+        })
+    }
+}
+
+// ============================================================================
+// Point mapping
+// ============================================================================
+
+/// Subscribed topic filter and the points read from its messages
+#[derive(Debug, Clone, PartialEq)]
+struct Subscription {
+    filter: String,
+    /// (path of the value, internal point ID)
+    bindings: Vec<(JsonPath, u32)>,
+}
+
+/// Published command of a control or adjustment point
+#[derive(Debug, Clone, PartialEq)]
+struct Command {
+    topic: String,
+    path: JsonPath,
+}
+
+impl Command {
+    fn payload(&self, value: f64) -> Vec<u8> {
+        let number = serde_json::Number::from_f64(value)
+            .map(JsonValue::Number)
+            .unwrap_or(JsonValue::Null);
+        if self.path.is_root() {
+            return number.to_string().into_bytes();
+        }
+        self.path.build(number).to_string().into_bytes()
+    }
+}
+
+fn mapping_text<'a>(mapping: &'a JsonValue, key: &str) -> Option<&'a str> {
+    mapping
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+fn parse_path(mapping: &JsonValue) -> std::result::Result<JsonPath, String> {
+    match mapping_text(mapping, "json_path") {
+        Some(path) => path.parse(),
+        None => Ok(JsonPath::root()),
+    }
+}
+
+/// Values of one message for every binding; `Err` carries the reason a
+/// binding has no value
+fn extract(
+    payload: &[u8],
+    bindings: &[(JsonPath, u32)],
+) -> Vec<(u32, std::result::Result<f64, String>)> {
+    let text = String::from_utf8_lossy(payload);
+    let document = serde_json::from_str::<JsonValue>(&text).ok();
+    bindings
+        .iter()
+        .map(|(path, id)| {
+            let value = match &document {
+                Some(document) => match path.select(document) {
+                    Some(value) => json_path::as_number(value)
+                        .ok_or_else(|| format!("{} is not numeric: {}", path, value)),
+                    None => Err(format!("{} not found in payload", path)),
+                },
+                None if path.is_root() => json_path::parse_number(&text)
+                    .ok_or_else(|| "payload is not a number".to_string()),
+                None => Err("payload is not JSON".to_string()),
+            };
+            (*id, value)
+        })
+        .collect()
+}
+
+// ============================================================================
+// Runtime
+// ============================================================================
+
+/// MQTT subscriber channel
+pub struct MqttRuntime {
+    id: u32,
+    name: String,
+    config: MqttConfig,
+    subscriptions: Vec<Subscription>,
+    controls: HashMap<u32, Command>,
+    adjustments: HashMap<u32, Command>,
+    stream: Option<TcpStream>,
+    reader: PacketReader,
+    next_packet_id: u16,
+    last_sent: Instant,
+    /// Time the unanswered PINGREQ was sent
+    ping_sent: Option<Instant>,
+    /// Messages received outside a poll (retained on subscribe, during
+    /// command acknowledgement), as (topic, payload)
+    pending: Vec<(String, Vec<u8>)>,
+    /// Points whose subscription the broker refused, reported by the next poll
+    rejected: Vec<(u32, String)>,
+    diagnostics: Diagnostics,
+}
+
+impl MqttRuntime {
+    /// Build the runtime of an `mqtt` channel
+    pub fn from_runtime_config(runtime_config: &RuntimeChannelConfig) -> Result<Self> {
+        let channel_id = runtime_config.id();
+        let config = MqttConfig::from_parameters(channel_id, &runtime_config.base.parameters)
+            .map_err(|e| ComSrvError::ConfigError(format!("Ch{}: {}", channel_id, e)))?;
+
+        let mut runtime = Self {
+            id: channel_id,
+            name: runtime_config.name().to_string(),
+            config,
+            subscriptions: Vec::new(),
+            controls: HashMap::new(),
+            adjustments: HashMap::new(),
+            stream: None,
+            reader: PacketReader::new(),
+            next_packet_id: 0,
+            last_sent: Instant::now(),
+            ping_sent: None,
+            pending: Vec::new(),
+            rejected: Vec::new(),
+            diagnostics: Diagnostics::new(PROTOCOL),
+        };
+
+        for (point_type, point) in runtime_config.points() {
+            let mapping = point_mapping(point);
+            let parsed = parse_path(&mapping).and_then(|path| {
+                let topic = mapping_text(&mapping, "topic");
+                match point_type {
+                    PointType::Telemetry | PointType::Signal => {
+                        let filter = topic.ok_or("missing 'topic'")?;
+                        if !codec::is_valid_filter(filter) {
+                            return Err(format!("invalid topic filter '{}'", filter));
+                        }
+                        runtime.bind(filter, path, point_type.to_internal_id(point.point_id));
+                        Ok(())
+                    },
+                    PointType::Control | PointType::Adjustment => {
+                        let topic = topic
+                            .or(runtime.config.command_topic.as_deref())
+                            .ok_or("missing 'topic' and no channel command_topic")?;
+                        if topic.contains(['+', '#']) {
+                            return Err(format!("command topic '{}' has wildcards", topic));
+                        }
+                        let command = Command {
+                            topic: topic.to_string(),
+                            path,
+                        };
+                        let targets = match point_type {
+                            PointType::Control => &mut runtime.controls,
+                            _ => &mut runtime.adjustments,
+                        };
+                        targets.insert(point.point_id, command);
+                        Ok(())
+                    },
+                }
+            });
+            if let Err(e) = parsed {
+                warn!(
+                    "Ch{} {}{} skipped: {}",
+                    channel_id,
+                    point_type.as_str(),
+                    point.point_id,
+                    e
+                );
+            }
+        }
+        debug!(
+            "Ch{} MQTT {}:{} points: {} topic filters, {} controls, {} adjustments",
+            channel_id,
+            runtime.config.host,
+            runtime.config.port,
+            runtime.subscriptions.len(),
+            runtime.controls.len(),
+            runtime.adjustments.len()
+        );
+        Ok(runtime)
+    }
+
+    fn bind(&mut self, filter: &str, path: JsonPath, internal_id: u32) {
+        match self.subscriptions.iter_mut().find(|s| s.filter == filter) {
+            Some(subscription) => subscription.bindings.push((path, internal_id)),
+            None => self.subscriptions.push(Subscription {
+                filter: filter.to_string(),
+                bindings: vec![(path, internal_id)],
+            }),
+        }
+    }
+
+    fn packet_id(&mut self) -> u16 {
+        // Packet identifiers are non-zero
+        self.next_packet_id = self.next_packet_id.wrapping_add(1).max(1);
+        self.next_packet_id
+    }
+
+    /// Record an error; connection failures drop the stream so the next
+    /// `connect()` starts over
+    fn fail(&mut self, error: GatewayError) -> GatewayError {
+        if record_failure(&mut self.diagnostics, &error) {
+            self.stream = None;
+        }
+        error
+    }
+
+    async fn send(&mut self, bytes: &[u8]) -> igw::Result<()> {
+        let stream = self.stream.as_mut().ok_or(GatewayError::NotConnected)?;
+        stream
+            .write_all(bytes)
+            .await
+            .map_err(|e| GatewayError::Connection(format!("send: {}", e)))?;
+        self.last_sent = Instant::now();
+        Ok(())
+    }
+
+    /// Next packet from the broker, `None` once `deadline` passes
+    ///
+    /// Incoming PUBLISH packets are acknowledged and queued in `pending`,
+    /// PINGRESP clears the keep-alive; both are not returned.
+    async fn next_packet(&mut self, deadline: Instant) -> igw::Result<Option<Packet>> {
+        let mut buf = [0u8; 4096];
+        loop {
+            while let Some(packet) = self.reader.next_packet() {
+                let packet = packet.map_err(|e| GatewayError::Connection(e.to_string()))?;
+                match packet {
+                    Packet::Publish {
+                        topic,
+                        payload,
+                        packet_id,
+                        ..
+                    } => {
+                        if let Some(packet_id) = packet_id {
+                            self.send(&codec::puback(packet_id)).await?;
+                        }
+                        if self.pending.len() >= MAX_PENDING_MESSAGES {
+                            self.pending.remove(0);
+                        }
+                        self.pending.push((topic, payload));
+                    },
+                    Packet::PingResp => self.ping_sent = None,
+                    other => return Ok(Some(other)),
+                }
+            }
+
+            let stream = self.stream.as_mut().ok_or(GatewayError::NotConnected)?;
+            let n = match timeout_at(deadline, stream.read(&mut buf)).await {
+                Ok(read) => {
+                    read.map_err(|e| GatewayError::Connection(format!("receive: {}", e)))?
+                },
+                Err(_) => return Ok(None),
+            };
+            if n == 0 {
+                return Err(GatewayError::Connection(
+                    "broker closed the connection".to_string(),
+                ));
+            }
+            self.reader.push(&buf[..n]);
+        }
+    }
+
+    /// Wait for a specific acknowledgement within the response timeout
+    async fn expect<T>(
+        &mut self,
+        what: &str,
+        mut matches: impl FnMut(&Packet) -> Option<T>,
+    ) -> igw::Result<T> {
+        let deadline = Instant::now() + self.config.response_timeout;
+        loop {
+            match self.next_packet(deadline).await? {
+                Some(packet) => match matches(&packet) {
+                    Some(result) => return Ok(result),
+                    None => debug!("Ch{} ignored MQTT packet {:?}", self.id, packet),
+                },
+                None => {
+                    debug!("Ch{} MQTT timed out waiting for {}", self.id, what);
+                    return Err(GatewayError::ConnectionTimeout(
+                        self.config.response_timeout.as_millis() as u64,
+                    ));
+                },
+            }
+        }
+    }
+
+    /// CONNECT, then SUBSCRIBE to every topic filter
+    async fn handshake(&mut self) -> igw::Result<()> {
+        let connect = codec::connect(&Connect {
+            client_id: &self.config.client_id,
+            username: self.config.username.as_deref(),
+            password: self.config.password.as_deref(),
+            keep_alive_secs: self.config.keep_alive_secs,
+            clean_session: self.config.clean_session,
+        });
+        self.send(&connect).await?;
+        let return_code = self
+            .expect("CONNACK", |p| match p {
+                Packet::ConnAck { return_code, .. } => Some(*return_code),
+                _ => None,
+            })
+            .await?;
+        if return_code != 0 {
+            return Err(GatewayError::Connection(format!(
+                "broker refused connection: {}",
+                codec::connack_reason(return_code)
+            )));
+        }
+
+        self.rejected.clear();
+        let qos = self.config.qos;
+        for batch in 0..self.subscriptions.len().div_ceil(SUBSCRIBE_BATCH) {
+            let range = batch * SUBSCRIBE_BATCH
+                ..((batch + 1) * SUBSCRIBE_BATCH).min(self.subscriptions.len());
+            let packet_id = self.packet_id();
+            let filters: Vec<(&str, u8)> = self.subscriptions[range.clone()]
+                .iter()
+                .map(|s| (s.filter.as_str(), qos))
+                .collect();
+            let request = codec::subscribe(packet_id, &filters);
+            self.send(&request).await?;
+            let codes = self
+                .expect("SUBACK", |p| match p {
+                    Packet::SubAck {
+                        packet_id: id,
+                        return_codes,
+                    } if *id == packet_id => Some(return_codes.clone()),
+                    _ => None,
+                })
+                .await?;
+            for (subscription, code) in self.subscriptions[range].iter().zip(codes) {
+                if code == codec::SUBACK_FAILURE {
+                    warn!(
+                        "Ch{} MQTT broker refused subscription to {}",
+                        self.id, subscription.filter
+                    );
+                    let message = format!("subscription to {} refused", subscription.filter);
+                    self.rejected.extend(
+                        subscription
+                            .bindings
+                            .iter()
+                            .map(|(_, id)| (*id, message.clone())),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Send PINGREQ when idle for half the keep-alive; a PINGREQ left
+    /// unanswered for the response timeout means a half-open connection
+    async fn keep_alive(&mut self) -> igw::Result<()> {
+        if self.config.keep_alive_secs == 0 {
+            return Ok(());
+        }
+        if let Some(sent) = self.ping_sent {
+            if sent.elapsed() > self.config.response_timeout {
+                return Err(GatewayError::Connection(
+                    "no PINGRESP from broker".to_string(),
+                ));
+            }
+            return Ok(());
+        }
+        let interval = Duration::from_secs(self.config.keep_alive_secs as u64) / 2;
+        if self.last_sent.elapsed() >= interval {
+            self.send(&codec::pingreq()).await?;
+            self.ping_sent = Some(Instant::now());
+        }
+        Ok(())
+    }
+
+    /// Latest values of the pending messages
+    fn collect(&mut self, batch: &mut DataBatch, failures: &mut Vec<PointFailure>) {
+        let mut values: HashMap<u32, f64> = HashMap::new();
+        let mut errors: HashMap<u32, String> = HashMap::new();
+        for (topic, payload) in std::mem::take(&mut self.pending) {
+            let mut matched = false;
+            for subscription in &self.subscriptions {
+                if !codec::topic_matches(&subscription.filter, &topic) {
+                    continue;
+                }
+                matched = true;
+                for (id, value) in extract(&payload, &subscription.bindings) {
+                    match value {
+                        Ok(value) => {
+                            errors.remove(&id);
+                            values.insert(id, value);
+                        },
+                        Err(e) => {
+                            values.remove(&id);
+                            errors.insert(id, format!("{}: {}", topic, e));
+                        },
+                    }
+                }
+            }
+            if !matched {
+                debug!("Ch{} MQTT message on unmapped topic {}", self.id, topic);
+            }
+        }
+        for (id, value) in values {
+            batch.add(DataPoint::new(id, value));
+        }
+        failures.extend(
+            errors
+                .into_iter()
+                .map(|(id, e)| PointFailure::with_error(id, e)),
+        );
+    }
+
+    async fn write(&mut self, point_type: PointType, values: &[(u32, f64)]) -> igw::Result<usize> {
+        let mut written = 0;
+        let mut last_error = None;
+        for &(internal_id, value) in values {
+            let point_id = PointType::from_internal_id(internal_id).1;
+            let targets = match point_type {
+                PointType::Control => &self.controls,
+                _ => &self.adjustments,
+            };
+            let Some(command) = targets.get(&point_id) else {
+                last_error = Some(GatewayError::PointNotFound(format!(
+                    "{}{}",
+                    point_type.as_str(),
+                    point_id
+                )));
+                continue;
+            };
+            let topic = command.topic.clone();
+            let payload = command.payload(value);
+            if let Err(e) = self.publish(&topic, &payload).await {
+                let e = self.fail(e);
+                if self.stream.is_none() {
+                    return Err(e);
+                }
+                last_error = Some(e);
+                continue;
+            }
+            written += 1;
+        }
+        self.diagnostics.write_count += written as u64;
+        match last_error {
+            Some(e) if written == 0 => Err(e),
+            _ => Ok(written),
+        }
+    }
+
+    /// PUBLISH one command; QoS 1 waits for the PUBACK
+    async fn publish(&mut self, topic: &str, payload: &[u8]) -> igw::Result<()> {
+        let qos = self.config.qos;
+        let packet_id = if qos > 0 { self.packet_id() } else { 0 };
+        let packet = codec::publish(topic, payload, qos, self.config.retain_commands, packet_id);
+        self.send(&packet).await?;
+        if qos > 0 {
+            self.expect("PUBACK", |p| match p {
+                Packet::PubAck { packet_id: id } if *id == packet_id => Some(()),
+                _ => None,
+            })
+            .await?;
+        }
+        debug!(
+            "Ch{} MQTT published {} to {}",
+            self.id,
+            String::from_utf8_lossy(payload),
+            topic
+        );
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChannelRuntime for MqttRuntime {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn protocol(&self) -> &str {
+        PROTOCOL
+    }
+
+    /// Messages are drained by `poll_once`
+    fn is_event_driven(&self) -> bool {
+        false
+    }
+
+    async fn connect(&mut self) -> igw::Result<()> {
+        self.diagnostics.connection_state = ConnectionState::Connecting;
+        let address = format!("{}:{}", self.config.host, self.config.port);
+        let timeout_ms = self.config.response_timeout.as_millis() as u64;
+        let connected =
+            tokio::time::timeout(self.config.response_timeout, TcpStream::connect(&address)).await;
+        let stream = match connected {
+            Ok(Ok(stream)) => stream,
+            Ok(Err(e)) => {
+                return Err(self.fail(GatewayError::Connection(format!("{}: {}", address, e))))
+            },
+            Err(_) => return Err(self.fail(GatewayError::ConnectionTimeout(timeout_ms))),
+        };
+        let _ = stream.set_nodelay(true);
+        self.stream = Some(stream);
+        self.reader.clear();
+        self.pending.clear();
+        self.ping_sent = None;
+
+        if let Err(e) = self.handshake().await {
+            let e = match e {
+                GatewayError::Connection(_) | GatewayError::ConnectionTimeout(_) => e,
+                other => GatewayError::Connection(other.to_string()),
+            };
+            return Err(self.fail(e));
+        }
+        self.diagnostics.connection_state = ConnectionState::Connected;
+        info!(
+            "Ch{} MQTT connected to {} as {}, {} topic filters",
+            self.id,
+            address,
+            self.config.client_id,
+            self.subscriptions.len()
+        );
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> igw::Result<()> {
+        if self.stream.is_some() {
+            let _ = self.send(&codec::disconnect()).await;
+        }
+        if let Some(mut stream) = self.stream.take() {
+            let _ = stream.shutdown().await;
+        }
+        self.diagnostics.connection_state = ConnectionState::Disconnected;
+        Ok(())
+    }
+
+    async fn poll_once(&mut self) -> PollResult {
+        if self.stream.is_none() {
+            return PollResult::failed(vec![PointFailure::new(0, "not connected")]);
+        }
+        let mut failures: Vec<PointFailure> = self
+            .rejected
+            .drain(..)
+            .map(|(id, e)| PointFailure::with_error(id, e))
+            .collect();
+
+        let deadline = Instant::now() + self.config.receive_wait;
+        let result: igw::Result<()> = async {
+            self.keep_alive().await?;
+            while let Some(packet) = self.next_packet(deadline).await? {
+                debug!("Ch{} ignored MQTT packet {:?}", self.id, packet);
+            }
+            Ok(())
+        }
+        .await;
+
+        // Messages read before a failure are still stored
+        let mut batch = DataBatch::default();
+        self.collect(&mut batch, &mut failures);
+        if let Err(e) = result {
+            let e = self.fail(e);
+            failures.push(PointFailure::with_error(0, e.to_string()));
+        }
+        self.diagnostics.read_count += 1;
+        PollResult::partial(batch, failures)
+    }
+
+    async fn write_control(&mut self, commands: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Control, commands).await
+    }
+
+    async fn write_adjustment(&mut self, adjustments: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Adjustment, adjustments).await
+    }
+
+    fn subscribe(&self) -> Option<DataEventReceiver> {
+        None
+    }
+
+    async fn start_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn stop_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn diagnostics(&self) -> igw::Result<Diagnostics> {
+        Ok(self.diagnostics.clone())
+    }
+}
+
+// ============================================================================
+// Plugin descriptor
+// ============================================================================
+
+fn parameters() -> Vec<ParameterMetadata> {
+    vec![
+        ParameterMetadata::required("host", "Host", "Broker address", ParameterType::String),
+        ParameterMetadata::optional(
+            "port",
+            "Port",
+            "Broker TCP port",
+            ParameterType::Integer,
+            json!(DEFAULT_PORT),
+        ),
+        ParameterMetadata::optional(
+            "client_id",
+            "Client ID",
+            "Client identifier (comsrv-<channel id> when empty)",
+            ParameterType::String,
+            json!(""),
+        ),
+        ParameterMetadata::optional(
+            "username",
+            "Username",
+            "User name; anonymous when empty",
+            ParameterType::String,
+            json!(""),
+        ),
+        ParameterMetadata::optional(
+            "password",
+            "Password",
+            "Password of the user",
+            ParameterType::String,
+            json!(""),
+        ),
+        ParameterMetadata::optional(
+            "qos",
+            "QoS",
+            "QoS of subscriptions and command publishes (0 or 1)",
+            ParameterType::Integer,
+            json!(DEFAULT_QOS),
+        ),
+        ParameterMetadata::optional(
+            "command_topic",
+            "Command Topic",
+            "Topic of control/adjustment points without their own topic",
+            ParameterType::String,
+            json!(""),
+        ),
+        ParameterMetadata::optional(
+            "retain_commands",
+            "Retain Commands",
+            "Publish commands with the retain flag",
+            ParameterType::Boolean,
+            json!(false),
+        ),
+        ParameterMetadata::optional(
+            "clean_session",
+            "Clean Session",
+            "Start without the broker's stored session state",
+            ParameterType::Boolean,
+            json!(true),
+        ),
+        ParameterMetadata::optional(
+            "keep_alive_secs",
+            "Keep-Alive (s)",
+            "Keep-alive interval; 0 disables PINGREQ",
+            ParameterType::Integer,
+            json!(DEFAULT_KEEP_ALIVE_SECS),
+        ),
+        ParameterMetadata::optional(
+            "response_timeout_ms",
+            "Response Timeout (ms)",
+            "Time to wait for CONNACK, SUBACK, PUBACK and PINGRESP",
+            ParameterType::Integer,
+            json!(DEFAULT_RESPONSE_TIMEOUT_MS),
+        ),
+        ParameterMetadata::optional(
+            "receive_wait_ms",
+            "Receive Wait (ms)",
+            "Time each poll waits for messages",
+            ParameterType::Integer,
+            json!(DEFAULT_RECEIVE_WAIT_MS),
+        ),
+        ParameterMetadata::optional(
+            "poll_interval_ms",
+            "Poll Interval (ms)",
+            "Interval of collecting received messages",
+            ParameterType::Integer,
+            json!(1000),
+        ),
+    ]
+}
+
+crate::protocol_plugin! {
+    /// MQTT subscriber (in-tree runtime)
+    pub struct MqttPlugin {
+        name: PROTOCOL,
+        aliases: &["mqtt_protocol"],
+        display_name: "MQTT",
+        description: "MQTT subscriber mapping JSON payloads to points and publishing commands",
+        parameters: parameters(),
+        mapping_columns: &[
+            MappingColumn::string(
+                "topic",
+                "Topic (filter with +/# for T/S; command_topic when empty for C/A)",
+            ),
+            MappingColumn::string("json_path", "JSONPath of the value, e.g. $.data.power")
+                .default_value("$"),
+        ],
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn point<T: serde::de::DeserializeOwned>(point_id: u32, mapping: JsonValue) -> T {
+        serde_json::from_value(json!({
+            "point_id": point_id,
+            "signal_name": format!("p{}", point_id),
+            "protocol_mappings": mapping.to_string(),
+        }))
+        .unwrap()
+    }
+
+    fn runtime_config(port: u16) -> RuntimeChannelConfig {
+        let mut config = RuntimeChannelConfig::from_base(
+            serde_json::from_value(json!({
+                "id": 4,
+                "name": "sensors",
+                "protocol": PROTOCOL,
+                "parameters": {
+                    "host": "127.0.0.1",
+                    "port": port,
+                    "username": "sensor",
+                    "password": "secret",
+                    "qos": 1,
+                    "command_topic": "site/cmd",
+                    "response_timeout_ms": 2000,
+                    "receive_wait_ms": 300,
+                },
+            }))
+            .unwrap(),
+        );
+        config.telemetry_points = vec![
+            point(1, json!({"topic": "site/+/meter", "json_path": "$.p"})),
+            point(2, json!({"topic": "site/+/meter", "json_path": "$.q[1]"})),
+            point(3, json!({"topic": "site/denied"})),
+            point(4, json!({"topic": "site/a/#/x"})),
+        ];
+        config.signal_points = vec![point(1, json!({"topic": "site/door"}))];
+        config.control_points = vec![point(1, json!({"json_path": "$.on"}))];
+        config.adjustment_points = vec![point(
+            1,
+            json!({"topic": "site/pcs/set", "json_path": "$.setpoint.kw"}),
+        )];
+        config
+    }
+
+    #[test]
+    fn test_config_and_mapping() {
+        let parameters =
+            |v: JsonValue| -> HashMap<String, JsonValue> { serde_json::from_value(v).unwrap() };
+        let config =
+            MqttConfig::from_parameters(7, &parameters(json!({"host": "broker", "qos": "1"})))
+                .unwrap();
+        assert_eq!(config.port, 1883);
+        assert_eq!(config.client_id, "comsrv-7");
+        assert_eq!(config.qos, 1);
+        assert!(config.clean_session);
+        assert!(
+            MqttConfig::from_parameters(7, &parameters(json!({"host": "b", "qos": 2}))).is_err()
+        );
+        assert!(MqttConfig::from_parameters(
+            7,
+            &parameters(json!({"host": "b", "command_topic": "a/#"}))
+        )
+        .is_err());
+        assert!(MqttConfig::from_parameters(7, &parameters(json!({}))).is_err());
+
+        let runtime = MqttRuntime::from_runtime_config(&runtime_config(1883)).unwrap();
+        // Points on the same filter share one subscription; the invalid
+        // filter is skipped
+        assert_eq!(runtime.subscriptions.len(), 3);
+        assert_eq!(runtime.subscriptions[0].bindings.len(), 2);
+        assert_eq!(runtime.controls[&1].topic, "site/cmd");
+        assert_eq!(
+            runtime.adjustments[&1].payload(12.5),
+            br#"{"setpoint":{"kw":12.5}}"#.to_vec()
+        );
+        let root = Command {
+            topic: "t".to_string(),
+            path: JsonPath::root(),
+        };
+        assert_eq!(root.payload(1.0), b"1.0".to_vec());
+    }
+
+    #[test]
+    fn test_extract_values() {
+        let bindings = vec![
+            ("$.p".parse().unwrap(), 1),
+            ("$.state".parse().unwrap(), 2),
+            ("$.missing".parse().unwrap(), 3),
+        ];
+        let values = extract(br#"{"p": 3.5, "state": "ON"}"#, &bindings);
+        assert_eq!(values[0], (1, Ok(3.5)));
+        assert_eq!(values[1], (2, Ok(1.0)));
+        assert!(values[2].1.is_err());
+
+        let root = vec![(JsonPath::root(), 9)];
+        assert_eq!(extract(b" 42 ", &root), vec![(9, Ok(42.0))]);
+        assert_eq!(extract(b"off", &root), vec![(9, Ok(0.0))]);
+        assert!(extract(b"n/a", &root)[0].1.is_err());
+        assert!(extract(b"n/a", &bindings[..1])[0].1.is_err());
+    }
+
+    /// Next packet the client sent to the broker
+    async fn read_packet(stream: &mut TcpStream, reader: &mut PacketReader) -> Packet {
+        let mut buf = [0u8; 1024];
+        loop {
+            if let Some(packet) = reader.next_packet() {
+                return packet.unwrap();
+            }
+            let n = stream.read(&mut buf).await.unwrap();
+            assert!(n > 0, "client closed the connection");
+            reader.push(&buf[..n]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_receive_and_publish_against_broker() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut client = MqttRuntime::from_runtime_config(&runtime_config(port)).unwrap();
+
+        let broker = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut reader = PacketReader::new();
+
+            let connect = read_packet(&mut stream, &mut reader).await;
+            assert_eq!(
+                connect,
+                Packet::Other {
+                    packet_type: codec::packet_type::CONNECT
+                }
+            );
+            stream.write_all(&[0x20, 2, 0, 0]).await.unwrap();
+
+            let subscribe = read_packet(&mut stream, &mut reader).await;
+            assert_eq!(
+                subscribe,
+                Packet::Other {
+                    packet_type: codec::packet_type::SUBSCRIBE
+                }
+            );
+            // A retained message arrives before the SUBACK
+            stream
+                .write_all(&codec::publish("site/door", b"true", 0, true, 0))
+                .await
+                .unwrap();
+            stream
+                .write_all(&[0x90, 5, 0, 1, 1, codec::SUBACK_FAILURE, 0])
+                .await
+                .unwrap();
+
+            // Two readings; the second wins. QoS 1 is acknowledged.
+            stream
+                .write_all(&codec::publish(
+                    "site/m1/meter",
+                    br#"{"p": 1, "q": [0, 2]}"#,
+                    0,
+                    false,
+                    0,
+                ))
+                .await
+                .unwrap();
+            stream
+                .write_all(&codec::publish(
+                    "site/m1/meter",
+                    br#"{"p": 5.5, "q": [0]}"#,
+                    1,
+                    false,
+                    42,
+                ))
+                .await
+                .unwrap();
+            let puback = read_packet(&mut stream, &mut reader).await;
+            assert_eq!(puback, Packet::PubAck { packet_id: 42 });
+
+            // Adjustment command with QoS 1
+            let publish = read_packet(&mut stream, &mut reader).await;
+            assert_eq!(
+                publish,
+                Packet::Publish {
+                    topic: "site/pcs/set".to_string(),
+                    payload: br#"{"setpoint":{"kw":30.0}}"#.to_vec(),
+                    qos: 1,
+                    retain: false,
+                    packet_id: Some(2),
+                }
+            );
+            stream.write_all(&codec::puback(2)).await.unwrap();
+        });
+
+        client.connect().await.unwrap();
+        let result = client.poll_once().await;
+        let points: HashMap<u32, f64> = result
+            .data
+            .iter()
+            .map(|p| (p.id, p.value.as_f64().unwrap()))
+            .collect();
+        assert_eq!(points[&PointType::Signal.to_internal_id(1)], 1.0);
+        assert_eq!(points[&PointType::Telemetry.to_internal_id(1)], 5.5);
+        assert!(!points.contains_key(&PointType::Telemetry.to_internal_id(2)));
+        let failed: Vec<u32> = result.failures.iter().map(|f| f.point_id).collect();
+        assert!(failed.contains(&PointType::Telemetry.to_internal_id(2)));
+        assert!(failed.contains(&PointType::Telemetry.to_internal_id(3)));
+
+        let written = client
+            .write_adjustment(&[(PointType::Adjustment.to_internal_id(1), 30.0)])
+            .await
+            .unwrap();
+        assert_eq!(written, 1);
+        broker.await.unwrap();
+
+        // The broker hung up: the next poll fails and drops the stream
+        let result = client.poll_once().await;
+        assert!(result.has_failures());
+        assert!(client.stream.is_none());
+    }
+}
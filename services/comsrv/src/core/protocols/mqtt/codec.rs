@@ -0,0 +1,435 @@
+//! MQTT 3.1.1 packet encoding
+//!
+//! Only the packets a subscribing client exchanges with a broker: CONNECT /
+//! CONNACK, SUBSCRIBE / SUBACK, PUBLISH with QoS 0 and 1 and its PUBACK,
+//! PINGREQ / PINGRESP and DISCONNECT.
+
+use crate::core::protocols::{error, CodecError};
+
+/// Protocol level of MQTT 3.1.1
+const PROTOCOL_LEVEL: u8 = 4;
+
+/// Largest remaining length a fixed header can express
+const MAX_REMAINING_LENGTH: usize = 268_435_455;
+
+/// Control packet types (high nibble of the first octet)
+pub mod packet_type {
+    pub const CONNECT: u8 = 1;
+    pub const CONNACK: u8 = 2;
+    pub const PUBLISH: u8 = 3;
+    pub const PUBACK: u8 = 4;
+    pub const SUBSCRIBE: u8 = 8;
+    pub const SUBACK: u8 = 9;
+    pub const PINGREQ: u8 = 12;
+    pub const PINGRESP: u8 = 13;
+    pub const DISCONNECT: u8 = 14;
+}
+
+/// SUBACK return code of a refused subscription
+pub const SUBACK_FAILURE: u8 = 0x80;
+
+/// Text of a CONNACK return code
+pub fn connack_reason(code: u8) -> &'static str {
+    match code {
+        0 => "accepted",
+        1 => "unacceptable protocol version",
+        2 => "identifier rejected",
+        3 => "server unavailable",
+        4 => "bad user name or password",
+        5 => "not authorized",
+        _ => "unknown return code",
+    }
+}
+
+// ============================================================================
+// Encoding
+// ============================================================================
+
+fn put_remaining_length(out: &mut Vec<u8>, mut len: usize) {
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+}
+
+fn put_str(out: &mut Vec<u8>, s: &str) {
+    put_bytes(out, s.as_bytes());
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&(bytes.len() as u16).to_be_bytes());
+    out.extend_from_slice(bytes);
+}
+
+/// Fixed header followed by `body`
+fn packet(first: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(body.len() + 5);
+    out.push(first);
+    put_remaining_length(&mut out, body.len());
+    out.extend_from_slice(body);
+    out
+}
+
+/// Parameters of a CONNECT packet
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Connect<'a> {
+    pub client_id: &'a str,
+    pub username: Option<&'a str>,
+    pub password: Option<&'a str>,
+    pub keep_alive_secs: u16,
+    pub clean_session: bool,
+}
+
+pub fn connect(params: &Connect<'_>) -> Vec<u8> {
+    let mut flags = 0u8;
+    if params.clean_session {
+        flags |= 0x02;
+    }
+    if params.username.is_some() {
+        flags |= 0x80;
+        // A password without a user name is not allowed in 3.1.1
+        if params.password.is_some() {
+            flags |= 0x40;
+        }
+    }
+
+    let mut body = Vec::new();
+    put_str(&mut body, "MQTT");
+    body.push(PROTOCOL_LEVEL);
+    body.push(flags);
+    body.extend_from_slice(&params.keep_alive_secs.to_be_bytes());
+    put_str(&mut body, params.client_id);
+    if let Some(user) = params.username {
+        put_str(&mut body, user);
+        if let Some(password) = params.password {
+            put_bytes(&mut body, password.as_bytes());
+        }
+    }
+    packet(packet_type::CONNECT << 4, &body)
+}
+
+/// SUBSCRIBE for `filters` as (topic filter, requested QoS)
+pub fn subscribe(packet_id: u16, filters: &[(&str, u8)]) -> Vec<u8> {
+    let mut body = packet_id.to_be_bytes().to_vec();
+    for (filter, qos) in filters {
+        put_str(&mut body, filter);
+        body.push(*qos);
+    }
+    // Bits 3-0 of SUBSCRIBE are reserved as 0b0010
+    packet((packet_type::SUBSCRIBE << 4) | 0x02, &body)
+}
+
+/// PUBLISH; `packet_id` is required for QoS 1
+pub fn publish(topic: &str, payload: &[u8], qos: u8, retain: bool, packet_id: u16) -> Vec<u8> {
+    let mut body = Vec::with_capacity(topic.len() + payload.len() + 4);
+    put_str(&mut body, topic);
+    if qos > 0 {
+        body.extend_from_slice(&packet_id.to_be_bytes());
+    }
+    body.extend_from_slice(payload);
+    let first = (packet_type::PUBLISH << 4) | (qos.min(1) << 1) | u8::from(retain);
+    packet(first, &body)
+}
+
+pub fn puback(packet_id: u16) -> Vec<u8> {
+    packet(packet_type::PUBACK << 4, &packet_id.to_be_bytes())
+}
+
+pub fn pingreq() -> Vec<u8> {
+    packet(packet_type::PINGREQ << 4, &[])
+}
+
+pub fn disconnect() -> Vec<u8> {
+    packet(packet_type::DISCONNECT << 4, &[])
+}
+
+// ============================================================================
+// Decoding
+// ============================================================================
+
+/// Packet received from the broker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Packet {
+    ConnAck {
+        session_present: bool,
+        return_code: u8,
+    },
+    Publish {
+        topic: String,
+        payload: Vec<u8>,
+        qos: u8,
+        retain: bool,
+        packet_id: Option<u16>,
+    },
+    PubAck {
+        packet_id: u16,
+    },
+    SubAck {
+        packet_id: u16,
+        return_codes: Vec<u8>,
+    },
+    PingResp,
+    /// Any other packet type, ignored by a client
+    Other {
+        packet_type: u8,
+    },
+}
+
+fn u16_at(body: &[u8], at: usize) -> Result<u16, CodecError> {
+    body.get(at..at + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        .ok_or_else(|| error("packet too short"))
+}
+
+impl Packet {
+    /// Decode a packet from its first octet and variable header + payload
+    pub fn parse(first: u8, body: &[u8]) -> Result<Self, CodecError> {
+        let kind = first >> 4;
+        Ok(match kind {
+            packet_type::CONNACK => {
+                if body.len() != 2 {
+                    return Err(error("CONNACK must be 2 octets"));
+                }
+                Self::ConnAck {
+                    session_present: body[0] & 0x01 != 0,
+                    return_code: body[1],
+                }
+            },
+            packet_type::PUBLISH => {
+                let qos = (first >> 1) & 0x03;
+                if qos == 3 {
+                    return Err(error("PUBLISH with QoS 3"));
+                }
+                let topic_len = u16_at(body, 0)? as usize;
+                let topic = body
+                    .get(2..2 + topic_len)
+                    .ok_or_else(|| error("PUBLISH topic truncated"))?;
+                let topic = std::str::from_utf8(topic)
+                    .map_err(|_| error("PUBLISH topic is not UTF-8"))?
+                    .to_string();
+                let mut at = 2 + topic_len;
+                let packet_id = if qos > 0 {
+                    let id = u16_at(body, at)?;
+                    at += 2;
+                    Some(id)
+                } else {
+                    None
+                };
+                Self::Publish {
+                    topic,
+                    payload: body[at..].to_vec(),
+                    qos,
+                    retain: first & 0x01 != 0,
+                    packet_id,
+                }
+            },
+            packet_type::PUBACK => Self::PubAck {
+                packet_id: u16_at(body, 0)?,
+            },
+            packet_type::SUBACK => Self::SubAck {
+                packet_id: u16_at(body, 0)?,
+                return_codes: body[2..].to_vec(),
+            },
+            packet_type::PINGRESP => Self::PingResp,
+            packet_type => Self::Other { packet_type },
+        })
+    }
+}
+
+/// Splits a byte stream into packets
+#[derive(Debug, Default)]
+pub struct PacketReader {
+    buffer: Vec<u8>,
+}
+
+impl PacketReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    /// Next complete packet; `None` until more bytes are needed
+    ///
+    /// A malformed remaining length cannot be resynchronized and is
+    /// reported; the caller should drop the connection.
+    pub fn next_packet(&mut self) -> Option<Result<Packet, CodecError>> {
+        let first = *self.buffer.first()?;
+        let mut len = 0usize;
+        let mut multiplier = 1usize;
+        let mut header_len = 1;
+        loop {
+            let byte = *self.buffer.get(header_len)?;
+            header_len += 1;
+            len += (byte & 0x7F) as usize * multiplier;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            multiplier *= 128;
+            if header_len > 4 {
+                self.buffer.clear();
+                return Some(Err(error("malformed remaining length")));
+            }
+        }
+        if len > MAX_REMAINING_LENGTH {
+            self.buffer.clear();
+            return Some(Err(error("remaining length too large")));
+        }
+        if self.buffer.len() < header_len + len {
+            return None;
+        }
+        let packet: Vec<u8> = self.buffer.drain(..header_len + len).collect();
+        Some(Packet::parse(first, &packet[header_len..]))
+    }
+}
+
+// ============================================================================
+// Topic filters
+// ============================================================================
+
+/// Whether `topic` matches a subscription `filter` with `+` / `#` wildcards
+pub fn topic_matches(filter: &str, topic: &str) -> bool {
+    // Wildcards do not match topics starting with '$' ($SYS/...)
+    if topic.starts_with('$') && (filter.starts_with('+') || filter.starts_with('#')) {
+        return false;
+    }
+    let mut filter_levels = filter.split('/');
+    let mut topic_levels = topic.split('/');
+    loop {
+        match (filter_levels.next(), topic_levels.next()) {
+            (Some("#"), _) => return true,
+            (Some("+"), Some(_)) => {},
+            (Some(f), Some(t)) if f == t => {},
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+/// Whether `filter` is a valid subscription topic filter
+pub fn is_valid_filter(filter: &str) -> bool {
+    if filter.is_empty() || filter.len() > u16::MAX as usize {
+        return false;
+    }
+    let levels: Vec<&str> = filter.split('/').collect();
+    levels.iter().enumerate().all(|(i, level)| match *level {
+        "#" => i == levels.len() - 1,
+        "+" => true,
+        level => !level.contains(['#', '+']),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_one(bytes: &[u8]) -> Packet {
+        let mut reader = PacketReader::new();
+        reader.push(bytes);
+        reader.next_packet().unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_connect_encoding() {
+        let bytes = connect(&Connect {
+            client_id: "c1",
+            username: Some("u"),
+            password: Some("p"),
+            keep_alive_secs: 60,
+            clean_session: true,
+        });
+        assert_eq!(
+            bytes,
+            [
+                0x10, 20, 0, 4, b'M', b'Q', b'T', b'T', 4, 0xC2, 0, 60, 0, 2, b'c', b'1', 0, 1,
+                b'u', 0, 1, b'p'
+            ]
+        );
+    }
+
+    #[test]
+    fn test_publish_round_trip_and_split_reads() {
+        let payload = vec![b'x'; 200];
+        let bytes = publish("a/b", &payload, 1, true, 7);
+        // Remaining length 2 + 3 + 2 + 200 = 207 takes two octets
+        assert_eq!(&bytes[..3], &[0x33, 0xCF, 0x01]);
+
+        let mut reader = PacketReader::new();
+        reader.push(&bytes[..2]);
+        assert!(reader.next_packet().is_none());
+        reader.push(&bytes[2..]);
+        reader.push(&pingreq());
+        assert_eq!(
+            reader.next_packet().unwrap().unwrap(),
+            Packet::Publish {
+                topic: "a/b".to_string(),
+                payload,
+                qos: 1,
+                retain: true,
+                packet_id: Some(7),
+            }
+        );
+        assert_eq!(
+            reader.next_packet().unwrap().unwrap(),
+            Packet::Other {
+                packet_type: packet_type::PINGREQ
+            }
+        );
+        assert!(reader.next_packet().is_none());
+    }
+
+    #[test]
+    fn test_broker_packets() {
+        assert_eq!(
+            read_one(&[0x20, 2, 1, 5]),
+            Packet::ConnAck {
+                session_present: true,
+                return_code: 5
+            }
+        );
+        assert_eq!(
+            read_one(&[0x90, 4, 0, 3, 1, SUBACK_FAILURE]),
+            Packet::SubAck {
+                packet_id: 3,
+                return_codes: vec![1, SUBACK_FAILURE]
+            }
+        );
+        assert_eq!(read_one(&[0xD0, 0]), Packet::PingResp);
+        assert_eq!(
+            subscribe(3, &[("s/#", 1)]),
+            [0x82, 8, 0, 3, 0, 3, b's', b'/', b'#', 1]
+        );
+
+        let mut reader = PacketReader::new();
+        reader.push(&[0x30, 0xFF, 0xFF, 0xFF, 0xFF, 0x01]);
+        assert!(reader.next_packet().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_topic_filters() {
+        assert!(topic_matches("site/+/power", "site/pcs1/power"));
+        assert!(topic_matches("site/#", "site/pcs1/power"));
+        assert!(topic_matches("site/#", "site"));
+        assert!(!topic_matches("site/+", "site/pcs1/power"));
+        assert!(!topic_matches("#", "$SYS/uptime"));
+        assert!(topic_matches("a/b", "a/b"));
+
+        assert!(is_valid_filter("a/+/#"));
+        assert!(!is_valid_filter("a/#/b"));
+        assert!(!is_valid_filter("a/b+"));
+        assert!(!is_valid_filter(""));
+    }
+}
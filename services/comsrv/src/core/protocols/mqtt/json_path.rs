@@ -0,0 +1,241 @@
+//! JSONPath subset for MQTT payloads
+//!
+//! Supports the root `$`, member access (`.name`, `['name']`, `["name"]`)
+//! and array indexes (`[0]`, `[-1]` from the end). Filters, slices and
+//! recursive descent are not needed to address a value in a sensor payload
+//! and are rejected. The leading `$` may be omitted (`data.power`).
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::{Map, Value as JsonValue};
+
+/// One step of a path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Member(String),
+    Index(i64),
+}
+
+/// Parsed JSONPath
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+impl JsonPath {
+    /// The root path `$`
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Value the path selects in `document`
+    pub fn select<'a>(&self, document: &'a JsonValue) -> Option<&'a JsonValue> {
+        self.segments
+            .iter()
+            .try_fold(document, |value, segment| match segment {
+                Segment::Member(name) => value.get(name),
+                Segment::Index(index) => {
+                    let array = value.as_array()?;
+                    let index = if *index < 0 {
+                        array.len().checked_sub(index.unsigned_abs() as usize)?
+                    } else {
+                        *index as usize
+                    };
+                    array.get(index)
+                },
+            })
+    }
+
+    /// Document in which the path selects `value`
+    ///
+    /// Members become nested objects; an index `n` becomes an array with
+    /// `value` at position `n` and nulls before it (negative indexes at 0).
+    pub fn build(&self, value: JsonValue) -> JsonValue {
+        self.segments
+            .iter()
+            .rev()
+            .fold(value, |inner, segment| match segment {
+                Segment::Member(name) => {
+                    let mut object = Map::new();
+                    object.insert(name.clone(), inner);
+                    JsonValue::Object(object)
+                },
+                Segment::Index(index) => {
+                    let mut array = vec![JsonValue::Null; (*index).max(0) as usize];
+                    array.push(inner);
+                    JsonValue::Array(array)
+                },
+            })
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("$")?;
+        for segment in &self.segments {
+            match segment {
+                Segment::Member(name)
+                    if !name.is_empty()
+                        && name.chars().all(|c| c.is_alphanumeric() || c == '_') =>
+                {
+                    write!(f, ".{}", name)?
+                },
+                Segment::Member(name) => write!(f, "['{}']", name.replace('\'', "\\'"))?,
+                Segment::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| format!("invalid JSONPath '{}': {}", s, reason);
+        let text = s.trim();
+        // "data.power" is read as "$.data.power"
+        let normalized = match text.strip_prefix('$') {
+            Some(rest) => rest.to_string(),
+            None if text.is_empty() || text.starts_with('[') => text.to_string(),
+            None => format!(".{}", text),
+        };
+
+        let mut segments = Vec::new();
+        let mut rest = normalized.as_str();
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('.') {
+                if after.starts_with('.') {
+                    return Err(invalid("recursive descent is not supported"));
+                }
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                let name = &after[..end];
+                if name.is_empty() {
+                    return Err(invalid("empty member name"));
+                }
+                if name == "*" {
+                    return Err(invalid("wildcards are not supported"));
+                }
+                segments.push(Segment::Member(name.to_string()));
+                rest = &after[end..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let quote = after.chars().next().filter(|c| *c == '\'' || *c == '"');
+                if let Some(quote) = quote {
+                    let body = &after[1..];
+                    let mut name = String::new();
+                    let mut chars = body.char_indices();
+                    let mut end = None;
+                    while let Some((i, c)) = chars.next() {
+                        match c {
+                            '\\' => {
+                                if let Some((_, escaped)) = chars.next() {
+                                    name.push(escaped);
+                                }
+                            },
+                            c if c == quote => {
+                                end = Some(i);
+                                break;
+                            },
+                            c => name.push(c),
+                        }
+                    }
+                    let end = end.ok_or_else(|| invalid("unterminated quoted name"))?;
+                    rest = body[end + 1..]
+                        .strip_prefix(']')
+                        .ok_or_else(|| invalid("expected ']'"))?;
+                    segments.push(Segment::Member(name));
+                } else {
+                    let end = after.find(']').ok_or_else(|| invalid("expected ']'"))?;
+                    let index = after[..end]
+                        .trim()
+                        .parse::<i64>()
+                        .map_err(|_| invalid("only integer indexes are supported"))?;
+                    segments.push(Segment::Index(index));
+                    rest = &after[end + 1..];
+                }
+            } else {
+                return Err(invalid("expected '.' or '['"));
+            }
+        }
+        Ok(Self { segments })
+    }
+}
+
+/// Number of a JSON value: numbers, booleans (1/0) and numeric strings
+pub fn as_number(value: &JsonValue) -> Option<f64> {
+    match value {
+        JsonValue::Number(n) => n.as_f64(),
+        JsonValue::Bool(b) => Some(f64::from(u8::from(*b))),
+        JsonValue::String(s) => parse_number(s),
+        _ => None,
+    }
+}
+
+/// Number of a plain text payload ("12.5", "true", "ON")
+pub fn parse_number(text: &str) -> Option<f64> {
+    let text = text.trim();
+    match text.to_ascii_lowercase().as_str() {
+        "true" | "on" => Some(1.0),
+        "false" | "off" => Some(0.0),
+        _ => text.parse::<f64>().ok().filter(|v| v.is_finite()),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn path(s: &str) -> JsonPath {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert!(path("$").is_root());
+        assert!(path("").is_root());
+        assert_eq!(path("$.data.power").to_string(), "$.data.power");
+        assert_eq!(path("data.power"), path("$.data.power"));
+        assert_eq!(path("$['a b'][2].c").to_string(), "$['a b'][2].c");
+        assert_eq!(path("[0]"), path("$[0]"));
+        for bad in ["$..a", "$.a[*]", "$.*", "$a", "$.a[", "$['a"] {
+            assert!(bad.parse::<JsonPath>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_select() {
+        let doc = json!({"data": {"values": [1, 2.5, {"on": true}]}, "a b": "7"});
+        assert_eq!(path("$.data.values[1]").select(&doc), Some(&json!(2.5)));
+        assert_eq!(
+            path("$.data.values[-1].on").select(&doc),
+            Some(&json!(true))
+        );
+        assert_eq!(path("$[\"a b\"]").select(&doc), Some(&json!("7")));
+        assert_eq!(path("$.data.values[5]").select(&doc), None);
+        assert_eq!(path("$.missing").select(&doc), None);
+        assert_eq!(path("$").select(&doc), Some(&doc));
+    }
+
+    #[test]
+    fn test_build_and_numbers() {
+        assert_eq!(
+            path("$.cmd.setpoint").build(json!(5)),
+            json!({"cmd": {"setpoint": 5}})
+        );
+        assert_eq!(path("$.v[1]").build(json!(1)), json!({"v": [null, 1]}));
+        assert_eq!(path("$").build(json!(3)), json!(3));
+
+        assert_eq!(as_number(&json!("12.5")), Some(12.5));
+        assert_eq!(as_number(&json!(false)), Some(0.0));
+        assert_eq!(as_number(&json!(null)), None);
+        assert_eq!(parse_number(" ON "), Some(1.0));
+        assert_eq!(parse_number("NaN"), None);
+    }
+}
@@ -0,0 +1,1327 @@
+//! OPC UA client
+//!
+//! Connects to an OPC UA server over UA-TCP (`opc.tcp://`), activates a
+//! session and creates one subscription monitoring the Value attribute of
+//! every telemetry and signal node. Data change notifications are collected
+//! with Publish requests on each poll, so only changed values are stored.
+//! Control and adjustment points write the Value attribute of their node.
+//!
+//! Only SecurityPolicy None is implemented; the server must expose an
+//! unsecured endpoint. Users authenticate anonymously or with a user name
+//! and password the endpoint accepts without encryption.
+//!
+//! | Point type | Mapping                       | Operation                    |
+//! |------------|-------------------------------|------------------------------|
+//! | T / S      | `node_id`                     | monitored item (data change) |
+//! | C / A      | `node_id`, `value_type`       | Write of the Value attribute |
+//!
+//! Node IDs use the standard text form (`ns=2;s=PCS1.ActivePower`,
+//! `ns=3;i=1001`); the node tree can be listed with
+//! `POST /api/protocols/opcua/browse`.
+//!
+//! ```yaml
+//! protocol: opcua
+//! parameters:
+//!   endpoint_url: opc.tcp://192.168.1.40:4840/server
+//!   security_policy: None
+//!   username: operator      # omit for anonymous
+//!   password: secret
+//!   publishing_interval_ms: 500
+//! ```
+
+pub mod client;
+pub mod codec;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use igw::core::traits::{DataEventReceiver, Diagnostics, PointFailure, PollResult};
+use igw::gateway::ChannelRuntime;
+use igw::{ConnectionState, DataBatch, DataPoint, GatewayError, Quality};
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+use self::client::Session;
+use self::codec::{
+    attribute, builtin, status, status_name, NodeId, Notification, Response, Variant,
+};
+use crate::core::config::RuntimeChannelConfig;
+use crate::core::plugins::{MappingColumn, ParameterMetadata, ParameterType};
+use crate::core::protocols::{as_u64, point_mapping, record_failure};
+use crate::error::{ComSrvError, Result};
+use voltage_model::PointType;
+
+/// Protocol name stored in `channels.protocol`
+pub const PROTOCOL: &str = "opcua";
+
+const DEFAULT_PORT: u16 = 4840;
+const DEFAULT_RESPONSE_TIMEOUT_MS: u64 = 5000;
+const DEFAULT_SESSION_TIMEOUT_MS: u64 = 60_000;
+const DEFAULT_PUBLISHING_INTERVAL_MS: u64 = 1000;
+const DEFAULT_KEEP_ALIVE_COUNT: u64 = 10;
+const DEFAULT_PUBLISH_WAIT_MS: u64 = 100;
+
+/// Publish requests kept queued at the server
+const MAX_OUTSTANDING_PUBLISH: usize = 2;
+
+/// Monitored items per CreateMonitoredItems request
+const MONITORED_ITEM_BATCH: usize = 200;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// User identity of the session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Auth {
+    Anonymous,
+    UserName { user: String, password: String },
+}
+
+/// Channel parameters of an OPC UA channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpcUaConfig {
+    pub endpoint_url: String,
+    pub host: String,
+    pub port: u16,
+    pub auth: Auth,
+    /// User token policy to use instead of the first matching one
+    pub user_token_policy_id: Option<String>,
+    pub response_timeout: Duration,
+    pub session_timeout_ms: u64,
+    pub publishing_interval_ms: u64,
+    pub sampling_interval_ms: u64,
+    /// Publishing intervals without notifications before a keep-alive
+    pub keep_alive_count: u32,
+    /// Time a poll waits for notifications
+    pub publish_wait: Duration,
+}
+
+impl OpcUaConfig {
+    pub fn from_parameters(parameters: &HashMap<String, JsonValue>) -> Result<Self> {
+        let text = |key: &str| {
+            parameters
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+        let number = |key: &str, default: u64, max: u64| -> Result<u64> {
+            match parameters.get(key) {
+                None | Some(JsonValue::Null) => Ok(default),
+                Some(value) => as_u64(value).filter(|v| *v <= max).ok_or_else(|| {
+                    ComSrvError::ConfigError(format!("OPC UA '{}' must be 0-{}", key, max))
+                }),
+            }
+        };
+
+        let endpoint_url = text("endpoint_url")
+            .ok_or_else(|| {
+                ComSrvError::ConfigError("OPC UA channel requires 'endpoint_url'".into())
+            })?
+            .to_string();
+        let (host, port) = parse_endpoint_url(&endpoint_url).map_err(ComSrvError::ConfigError)?;
+
+        let policy = text("security_policy").unwrap_or("None");
+        if policy != "None" && policy != codec::SECURITY_POLICY_NONE {
+            return Err(ComSrvError::ConfigError(format!(
+                "OPC UA security policy '{}' is not supported: only None is available \
+                 (no signing/encryption), use an unsecured server endpoint",
+                policy
+            )));
+        }
+        let mode = text("security_mode").unwrap_or("None");
+        if !mode.eq_ignore_ascii_case("none") {
+            return Err(ComSrvError::ConfigError(format!(
+                "OPC UA security mode '{}' is not supported: only None is available",
+                mode
+            )));
+        }
+
+        let auth = match text("username") {
+            Some(user) => Auth::UserName {
+                user: user.to_string(),
+                password: parameters
+                    .get("password")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+            },
+            None => Auth::Anonymous,
+        };
+
+        let publishing_interval_ms = number(
+            "publishing_interval_ms",
+            DEFAULT_PUBLISHING_INTERVAL_MS,
+            u32::MAX as u64,
+        )?
+        .max(10);
+        Ok(Self {
+            endpoint_url,
+            host,
+            port,
+            auth,
+            user_token_policy_id: text("user_token_policy_id").map(str::to_string),
+            response_timeout: Duration::from_millis(
+                number(
+                    "response_timeout_ms",
+                    DEFAULT_RESPONSE_TIMEOUT_MS,
+                    u32::MAX as u64,
+                )?
+                .max(1),
+            ),
+            session_timeout_ms: number(
+                "session_timeout_ms",
+                DEFAULT_SESSION_TIMEOUT_MS,
+                u32::MAX as u64,
+            )?
+            .max(1000),
+            publishing_interval_ms,
+            sampling_interval_ms: number(
+                "sampling_interval_ms",
+                publishing_interval_ms,
+                u32::MAX as u64,
+            )?,
+            keep_alive_count: number("keep_alive_count", DEFAULT_KEEP_ALIVE_COUNT, 1000)?.max(1)
+                as u32,
+            publish_wait: Duration::from_millis(number(
+                "publish_wait_ms",
+                DEFAULT_PUBLISH_WAIT_MS,
+                60_000,
+            )?),
+        })
+    }
+}
+
+/// Host and port of an `opc.tcp://host[:port][/path]` URL
+fn parse_endpoint_url(url: &str) -> std::result::Result<(String, u16), String> {
+    let invalid = || {
+        format!(
+            "OPC UA endpoint_url '{}' is not opc.tcp://host[:port][/path]",
+            url
+        )
+    };
+    let rest = url.strip_prefix("opc.tcp://").ok_or_else(invalid)?;
+    let authority = rest.split('/').next().unwrap_or_default();
+    let (host, port) = match authority.strip_prefix('[') {
+        // [IPv6]:port
+        Some(v6) => {
+            let (host, after) = v6.split_once(']').ok_or_else(invalid)?;
+            (host, after.strip_prefix(':'))
+        },
+        None => match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    if host.is_empty() {
+        return Err(invalid());
+    }
+    let port = match port {
+        Some(port) => port.parse().map_err(|_| invalid())?,
+        None => DEFAULT_PORT,
+    };
+    Ok((host.to_string(), port))
+}
+
+// ============================================================================
+// Point mapping
+// ============================================================================
+
+/// Built-in type a written value is encoded as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    Boolean,
+    SByte,
+    Byte,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Int64,
+    UInt64,
+    Float,
+    Double,
+}
+
+impl ValueType {
+    fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "boolean" | "bool" => Some(Self::Boolean),
+            "sbyte" => Some(Self::SByte),
+            "byte" => Some(Self::Byte),
+            "int16" => Some(Self::Int16),
+            "uint16" => Some(Self::UInt16),
+            "int32" => Some(Self::Int32),
+            "uint32" => Some(Self::UInt32),
+            "int64" => Some(Self::Int64),
+            "uint64" => Some(Self::UInt64),
+            "float" | "float32" => Some(Self::Float),
+            "double" | "float64" => Some(Self::Double),
+            _ => None,
+        }
+    }
+
+    /// Type of a built-in data type ID
+    fn from_builtin(type_id: u8) -> Option<Self> {
+        Some(match type_id {
+            builtin::BOOLEAN => Self::Boolean,
+            builtin::SBYTE => Self::SByte,
+            builtin::BYTE => Self::Byte,
+            builtin::INT16 => Self::Int16,
+            builtin::UINT16 => Self::UInt16,
+            builtin::INT32 => Self::Int32,
+            builtin::UINT32 => Self::UInt32,
+            builtin::INT64 => Self::Int64,
+            builtin::UINT64 => Self::UInt64,
+            builtin::FLOAT => Self::Float,
+            builtin::DOUBLE => Self::Double,
+            _ => return None,
+        })
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Boolean => "boolean",
+            Self::SByte => "sbyte",
+            Self::Byte => "byte",
+            Self::Int16 => "int16",
+            Self::UInt16 => "uint16",
+            Self::Int32 => "int32",
+            Self::UInt32 => "uint32",
+            Self::Int64 => "int64",
+            Self::UInt64 => "uint64",
+            Self::Float => "float",
+            Self::Double => "double",
+        }
+    }
+
+    /// Variant of a value; integers are rounded and saturated
+    fn variant(self, value: f64) -> Variant {
+        let int = value.round();
+        match self {
+            Self::Boolean => Variant::Boolean(value != 0.0),
+            Self::SByte => Variant::SByte(int as i8),
+            Self::Byte => Variant::Byte(int as u8),
+            Self::Int16 => Variant::Int16(int as i16),
+            Self::UInt16 => Variant::UInt16(int as u16),
+            Self::Int32 => Variant::Int32(int as i32),
+            Self::UInt32 => Variant::UInt32(int as u32),
+            Self::Int64 => Variant::Int64(int as i64),
+            Self::UInt64 => Variant::UInt64(int as u64),
+            Self::Float => Variant::Float(value as f32),
+            Self::Double => Variant::Double(value),
+        }
+    }
+}
+
+/// Written node of a control or adjustment point
+#[derive(Debug, Clone, PartialEq)]
+struct Target {
+    node: NodeId,
+    value_type: ValueType,
+}
+
+fn parse_target(point_type: PointType, mapping: &JsonValue) -> std::result::Result<Target, String> {
+    let field = |key: &str| {
+        mapping
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    };
+    let node = field("node_id")
+        .ok_or("missing 'node_id'")?
+        .parse::<NodeId>()
+        .map_err(|e| e.to_string())?;
+    let value_type = match field("value_type") {
+        Some(name) => {
+            ValueType::parse(name).ok_or_else(|| format!("unknown value_type {}", name))?
+        },
+        None if point_type == PointType::Control => ValueType::Boolean,
+        None => ValueType::Double,
+    };
+    Ok(Target { node, value_type })
+}
+
+/// Point quality of a status code
+fn quality(code: u32) -> Quality {
+    if codec::is_good(code) {
+        return Quality::Good;
+    }
+    if codec::is_uncertain(code) {
+        return Quality::Uncertain;
+    }
+    match code & 0xFFFF_0000 {
+        status::BAD_NOT_CONNECTED => Quality::NotConnected,
+        status::BAD_DEVICE_FAILURE => Quality::DeviceFailure,
+        status::BAD_SENSOR_FAILURE => Quality::SensorFailure,
+        _ => Quality::Bad,
+    }
+}
+
+// ============================================================================
+// Runtime
+// ============================================================================
+
+/// OPC UA client channel
+pub struct OpcUaRuntime {
+    id: u32,
+    name: String,
+    config: OpcUaConfig,
+    /// Monitored nodes with their internal point IDs; the client handle of
+    /// an item is its index
+    monitored: Vec<(NodeId, Vec<u32>)>,
+    controls: HashMap<u32, Target>,
+    adjustments: HashMap<u32, Target>,
+    session: Option<Session>,
+    subscription_id: u32,
+    /// Notifications to acknowledge with the next Publish
+    acks: Vec<(u32, u32)>,
+    /// Monitored items the server refused, reported by the next poll
+    rejected: Vec<(u32, String)>,
+    diagnostics: Diagnostics,
+}
+
+impl OpcUaRuntime {
+    /// Build the runtime of an `opcua` channel
+    pub fn from_runtime_config(runtime_config: &RuntimeChannelConfig) -> Result<Self> {
+        let channel_id = runtime_config.id();
+        let config = OpcUaConfig::from_parameters(&runtime_config.base.parameters)
+            .map_err(|e| ComSrvError::ConfigError(format!("Ch{}: {}", channel_id, e)))?;
+
+        let mut runtime = Self {
+            id: channel_id,
+            name: runtime_config.name().to_string(),
+            config,
+            monitored: Vec::new(),
+            controls: HashMap::new(),
+            adjustments: HashMap::new(),
+            session: None,
+            subscription_id: 0,
+            acks: Vec::new(),
+            rejected: Vec::new(),
+            diagnostics: Diagnostics::new(PROTOCOL),
+        };
+
+        let mut monitored_index: HashMap<NodeId, usize> = HashMap::new();
+        for (point_type, point) in runtime_config.points() {
+            let target = match parse_target(point_type, &point_mapping(point)) {
+                Ok(target) => target,
+                Err(e) => {
+                    warn!(
+                        "Ch{} {}{} skipped: {}",
+                        channel_id,
+                        point_type.as_str(),
+                        point.point_id,
+                        e
+                    );
+                    continue;
+                },
+            };
+            match point_type {
+                PointType::Telemetry | PointType::Signal => {
+                    let internal_id = point_type.to_internal_id(point.point_id);
+                    match monitored_index.get(&target.node) {
+                        Some(&index) => runtime.monitored[index].1.push(internal_id),
+                        None => {
+                            monitored_index.insert(target.node.clone(), runtime.monitored.len());
+                            runtime.monitored.push((target.node, vec![internal_id]));
+                        },
+                    }
+                },
+                PointType::Control => {
+                    runtime.controls.insert(point.point_id, target);
+                },
+                PointType::Adjustment => {
+                    runtime.adjustments.insert(point.point_id, target);
+                },
+            }
+        }
+        debug!(
+            "Ch{} OPC UA {} points: {} monitored nodes, {} controls, {} adjustments",
+            channel_id,
+            runtime.config.endpoint_url,
+            runtime.monitored.len(),
+            runtime.controls.len(),
+            runtime.adjustments.len()
+        );
+        Ok(runtime)
+    }
+
+    /// Record an error; connection failures drop the session so the next
+    /// `connect()` starts over
+    fn fail(&mut self, error: GatewayError) -> GatewayError {
+        if record_failure(&mut self.diagnostics, &error) {
+            self.session = None;
+        }
+        error
+    }
+
+    /// Create the subscription and its monitored items
+    async fn subscribe_items(&mut self, session: &mut Session) -> igw::Result<()> {
+        self.subscription_id = session
+            .create_subscription(
+                self.config.publishing_interval_ms,
+                self.config.keep_alive_count,
+            )
+            .await?;
+        self.rejected.clear();
+        for (batch_index, chunk) in self.monitored.chunks(MONITORED_ITEM_BATCH).enumerate() {
+            let first = batch_index * MONITORED_ITEM_BATCH;
+            let items: Vec<(NodeId, u32)> = chunk
+                .iter()
+                .enumerate()
+                .map(|(i, (node, _))| (node.clone(), (first + i) as u32))
+                .collect();
+            let results = session
+                .create_monitored_items(
+                    self.subscription_id,
+                    &items,
+                    self.config.sampling_interval_ms,
+                )
+                .await?;
+            for ((node, ids), code) in chunk.iter().zip(results) {
+                if !codec::is_good(code) {
+                    warn!(
+                        "Ch{} OPC UA node {} not monitored: {}",
+                        self.id,
+                        node,
+                        status_name(code)
+                    );
+                    let message = format!("{}: {}", node, status_name(code));
+                    self.rejected
+                        .extend(ids.iter().map(|&id| (id, message.clone())));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Values of a data change notification
+    fn apply_data_change(
+        &self,
+        items: Vec<(u32, codec::DataValue)>,
+        batch: &mut DataBatch,
+        failures: &mut Vec<PointFailure>,
+    ) {
+        for (client_handle, value) in items {
+            let Some((node, ids)) = self.monitored.get(client_handle as usize) else {
+                debug!("Ch{} unknown client handle {}", self.id, client_handle);
+                continue;
+            };
+            let number = value.value.as_ref().and_then(Variant::as_f64);
+            let Some(number) = number else {
+                let reason = if codec::is_good(value.status) {
+                    format!("{}: no numeric value in {:?}", node, value.value)
+                } else {
+                    format!("{}: {}", node, status_name(value.status))
+                };
+                failures.extend(
+                    ids.iter()
+                        .map(|&id| PointFailure::with_error(id, reason.clone())),
+                );
+                continue;
+            };
+            let source_timestamp = value.source_timestamp.and_then(codec::date_time_to_utc);
+            for &id in ids {
+                let mut point = DataPoint::new(id, number).with_quality(quality(value.status));
+                if let Some(ts) = source_timestamp {
+                    point = point.with_source_timestamp(ts);
+                }
+                batch.add(point);
+            }
+        }
+    }
+
+    async fn write(&mut self, point_type: PointType, values: &[(u32, f64)]) -> igw::Result<usize> {
+        let mut writes = Vec::new();
+        let mut last_error = None;
+        for &(internal_id, value) in values {
+            let point_id = PointType::from_internal_id(internal_id).1;
+            let targets = match point_type {
+                PointType::Control => &self.controls,
+                _ => &self.adjustments,
+            };
+            match targets.get(&point_id) {
+                Some(target) => {
+                    writes.push((target.node.clone(), target.value_type.variant(value)))
+                },
+                None => {
+                    last_error = Some(GatewayError::PointNotFound(format!(
+                        "{}{}",
+                        point_type.as_str(),
+                        point_id
+                    )))
+                },
+            }
+        }
+        if writes.is_empty() {
+            return Err(last_error
+                .unwrap_or_else(|| GatewayError::InvalidData("no values to write".to_string())));
+        }
+
+        let session = self.session.as_mut().ok_or(GatewayError::NotConnected)?;
+        let results = match session.write(&writes).await {
+            Ok(results) => results,
+            Err(e) => return Err(self.fail(e)),
+        };
+        let mut written = 0;
+        for ((node, _), code) in writes.iter().zip(results) {
+            if codec::is_good(code) {
+                written += 1;
+            } else {
+                last_error = Some(self.fail(GatewayError::Protocol(format!(
+                    "write {} failed: {}",
+                    node,
+                    status_name(code)
+                ))));
+            }
+        }
+        self.diagnostics.write_count += written as u64;
+        match last_error {
+            Some(e) if written == 0 => Err(e),
+            _ => Ok(written),
+        }
+    }
+}
+
+#[async_trait]
+impl ChannelRuntime for OpcUaRuntime {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn protocol(&self) -> &str {
+        PROTOCOL
+    }
+
+    /// Notifications are pulled by `poll_once`
+    fn is_event_driven(&self) -> bool {
+        false
+    }
+
+    async fn connect(&mut self) -> igw::Result<()> {
+        self.diagnostics.connection_state = ConnectionState::Connecting;
+        self.session = None;
+        self.acks.clear();
+        let mut session = match Session::connect(&self.config).await {
+            Ok(session) => session,
+            Err(e) => {
+                let e = match e {
+                    GatewayError::ConnectionTimeout(_) | GatewayError::Connection(_) => e,
+                    other => GatewayError::Connection(other.to_string()),
+                };
+                return Err(self.fail(e));
+            },
+        };
+        if let Err(e) = self.subscribe_items(&mut session).await {
+            session.close().await;
+            return Err(self.fail(GatewayError::Connection(format!("subscribe: {}", e))));
+        }
+        self.session = Some(session);
+        self.diagnostics.connection_state = ConnectionState::Connected;
+        info!(
+            "Ch{} OPC UA session on {} monitoring {} nodes",
+            self.id,
+            self.config.endpoint_url,
+            self.monitored.len()
+        );
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> igw::Result<()> {
+        if let Some(session) = self.session.take() {
+            session.close().await;
+        }
+        self.diagnostics.connection_state = ConnectionState::Disconnected;
+        Ok(())
+    }
+
+    async fn poll_once(&mut self) -> PollResult {
+        let Some(mut session) = self.session.take() else {
+            return PollResult::failed(vec![PointFailure::new(0, "not connected")]);
+        };
+        let mut batch = DataBatch::default();
+        let mut failures: Vec<PointFailure> = self
+            .rejected
+            .drain(..)
+            .map(|(id, e)| PointFailure::with_error(id, e))
+            .collect();
+
+        let result: igw::Result<()> = async {
+            while session.outstanding_publishes() < MAX_OUTSTANDING_PUBLISH {
+                let acks = std::mem::take(&mut self.acks);
+                session.publish(&acks).await?;
+            }
+            let mut deadline = Instant::now() + self.config.publish_wait;
+            while let Some(response) = session.next_publish(deadline).await? {
+                match response {
+                    Response::Publish {
+                        subscription_id,
+                        sequence_number,
+                        notifications,
+                        ..
+                    } => {
+                        if !notifications.is_empty() {
+                            self.acks.push((subscription_id, sequence_number));
+                        }
+                        for notification in notifications {
+                            match notification {
+                                Notification::DataChange(items) => {
+                                    self.apply_data_change(items, &mut batch, &mut failures)
+                                },
+                                Notification::StatusChange(code) => {
+                                    return Err(GatewayError::Connection(format!(
+                                        "subscription status changed: {}",
+                                        status_name(code)
+                                    )))
+                                },
+                                Notification::Other => {},
+                            }
+                        }
+                    },
+                    Response::ServiceFault { header }
+                        if client::is_transient_publish_fault(header.service_result) => {},
+                    Response::ServiceFault { header } => {
+                        return Err(match client::service_error(header.service_result) {
+                            GatewayError::Protocol(e) => {
+                                GatewayError::Connection(format!("publish: {}", e))
+                            },
+                            e => e,
+                        });
+                    },
+                    other => debug!("Ch{} ignored publish response {:?}", self.id, other),
+                }
+                // Take what else has arrived, then return
+                deadline = Instant::now();
+                if session.outstanding_publishes() < MAX_OUTSTANDING_PUBLISH {
+                    let acks = std::mem::take(&mut self.acks);
+                    session.publish(&acks).await?;
+                }
+            }
+            Ok(())
+        }
+        .await;
+
+        self.session = Some(session);
+        if let Err(e) = result {
+            let e = self.fail(e);
+            failures.push(PointFailure::with_error(0, e.to_string()));
+        }
+        self.diagnostics.read_count += 1;
+        PollResult::partial(batch, failures)
+    }
+
+    async fn write_control(&mut self, commands: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Control, commands).await
+    }
+
+    async fn write_adjustment(&mut self, adjustments: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Adjustment, adjustments).await
+    }
+
+    fn subscribe(&self) -> Option<DataEventReceiver> {
+        None
+    }
+
+    async fn start_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn stop_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn diagnostics(&self) -> igw::Result<Diagnostics> {
+        Ok(self.diagnostics.clone())
+    }
+}
+
+// ============================================================================
+// Browse
+// ============================================================================
+
+/// Node found by browsing
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct BrowseNode {
+    pub node_id: String,
+    pub browse_name: String,
+    pub display_name: String,
+    /// `object`, `variable`, `method`, ...
+    pub node_class: String,
+    /// Built-in data type of variables (e.g. `double`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_type: Option<String>,
+    /// Suggested point type of variables ("T" or "S")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub point_type: Option<String>,
+}
+
+fn node_class_name(class: u32) -> String {
+    match class {
+        1 => "object",
+        2 => "variable",
+        4 => "method",
+        8 => "object_type",
+        16 => "variable_type",
+        32 => "reference_type",
+        64 => "data_type",
+        128 => "view",
+        _ => return format!("class_{}", class),
+    }
+    .to_string()
+}
+
+/// Children of a node (the Objects folder by default) on a server
+///
+/// Opens a short-lived session with the channel parameters; variables are
+/// returned with their data type and the suggested point type.
+pub async fn browse(
+    parameters: &HashMap<String, JsonValue>,
+    node_id: Option<&str>,
+) -> Result<Vec<BrowseNode>> {
+    let config = OpcUaConfig::from_parameters(parameters)?;
+    let node = match node_id.map(str::trim).filter(|n| !n.is_empty()) {
+        Some(id) => id
+            .parse::<NodeId>()
+            .map_err(|e| ComSrvError::ConfigError(e.to_string()))?,
+        None => client::objects_folder(),
+    };
+    let protocol_error = |e: GatewayError| ComSrvError::ProtocolError(e.to_string());
+
+    let mut session = Session::connect(&config).await.map_err(protocol_error)?;
+    let result = async {
+        let references = session.browse(&node).await?;
+        let variables: Vec<(NodeId, u32)> = references
+            .iter()
+            .filter(|r| r.node_class == 2)
+            .map(|r| (r.node_id.clone(), attribute::DATA_TYPE))
+            .collect();
+        let mut data_types = HashMap::new();
+        if !variables.is_empty() {
+            let values = session.read(&variables).await?;
+            for ((node, _), value) in variables.into_iter().zip(values) {
+                if let Some(Variant::NodeId(data_type)) = value.value {
+                    data_types.insert(node, data_type);
+                }
+            }
+        }
+        Ok(references
+            .into_iter()
+            .map(|r| {
+                let data_type = data_types.get(&r.node_id);
+                let value_type = data_type
+                    .and_then(client::builtin_data_type)
+                    .and_then(ValueType::from_builtin);
+                let point_type = value_type.map(|t| match t {
+                    ValueType::Boolean => "S",
+                    _ => "T",
+                });
+                BrowseNode {
+                    node_id: r.node_id.to_string(),
+                    browse_name: r.browse_name,
+                    display_name: r.display_name,
+                    node_class: node_class_name(r.node_class),
+                    value_type: value_type
+                        .map(|t| t.as_str().to_string())
+                        .or_else(|| data_type.map(NodeId::to_string)),
+                    point_type: point_type.map(str::to_string),
+                }
+            })
+            .collect())
+    }
+    .await;
+    session.close().await;
+    result.map_err(protocol_error)
+}
+
+// ============================================================================
+// Plugin descriptor
+// ============================================================================
+
+fn parameters() -> Vec<ParameterMetadata> {
+    vec![
+        ParameterMetadata::required(
+            "endpoint_url",
+            "Endpoint URL",
+            "Server endpoint, opc.tcp://host[:port][/path]",
+            ParameterType::String,
+        ),
+        ParameterMetadata::optional(
+            "security_policy",
+            "Security Policy",
+            "Only None (unsecured endpoint) is supported",
+            ParameterType::String,
+            json!("None"),
+        ),
+        ParameterMetadata::optional(
+            "username",
+            "Username",
+            "User name; anonymous when empty",
+            ParameterType::String,
+            json!(""),
+        ),
+        ParameterMetadata::optional(
+            "password",
+            "Password",
+            "Password of the user",
+            ParameterType::String,
+            json!(""),
+        ),
+        ParameterMetadata::optional(
+            "user_token_policy_id",
+            "User Token Policy",
+            "Policy ID of the identity token (first matching one when empty)",
+            ParameterType::String,
+            json!(""),
+        ),
+        ParameterMetadata::optional(
+            "publishing_interval_ms",
+            "Publishing Interval (ms)",
+            "Subscription publishing interval",
+            ParameterType::Integer,
+            json!(DEFAULT_PUBLISHING_INTERVAL_MS),
+        ),
+        ParameterMetadata::optional(
+            "sampling_interval_ms",
+            "Sampling Interval (ms)",
+            "Sampling interval of monitored items (publishing interval when empty)",
+            ParameterType::Integer,
+            JsonValue::Null,
+        ),
+        ParameterMetadata::optional(
+            "keep_alive_count",
+            "Keep-Alive Count",
+            "Publishing intervals without changes before a keep-alive",
+            ParameterType::Integer,
+            json!(DEFAULT_KEEP_ALIVE_COUNT),
+        ),
+        ParameterMetadata::optional(
+            "session_timeout_ms",
+            "Session Timeout (ms)",
+            "Requested session timeout",
+            ParameterType::Integer,
+            json!(DEFAULT_SESSION_TIMEOUT_MS),
+        ),
+        ParameterMetadata::optional(
+            "response_timeout_ms",
+            "Response Timeout (ms)",
+            "Time to wait for service responses",
+            ParameterType::Integer,
+            json!(DEFAULT_RESPONSE_TIMEOUT_MS),
+        ),
+        ParameterMetadata::optional(
+            "publish_wait_ms",
+            "Publish Wait (ms)",
+            "Time each poll waits for data change notifications",
+            ParameterType::Integer,
+            json!(DEFAULT_PUBLISH_WAIT_MS),
+        ),
+        ParameterMetadata::optional(
+            "poll_interval_ms",
+            "Poll Interval (ms)",
+            "Interval of collecting notifications",
+            ParameterType::Integer,
+            json!(1000),
+        ),
+    ]
+}
+
+crate::protocol_plugin! {
+    /// OPC UA client (in-tree runtime)
+    pub struct OpcUaPlugin {
+        name: PROTOCOL,
+        aliases: &["opc_ua", "opc ua"],
+        display_name: "OPC UA",
+        description: "OPC UA client monitoring node values by subscription and writing nodes",
+        parameters: parameters(),
+        mapping_columns: &[
+            MappingColumn::string("node_id", "Node ID, e.g. ns=2;s=PCS1.ActivePower").required(),
+            MappingColumn::string("value_type", "Type of written values")
+                .choices(&[
+                    "boolean", "sbyte", "byte", "int16", "uint16", "int32", "uint32", "int64",
+                    "uint64", "float", "double",
+                ]),
+        ],
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::codec::{ids, Assembler, ChunkReader, Encoder, Incoming, SecureChannel};
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    fn point<T: serde::de::DeserializeOwned>(point_id: u32, mapping: JsonValue) -> T {
+        serde_json::from_value(json!({
+            "point_id": point_id,
+            "signal_name": format!("p{}", point_id),
+            "protocol_mappings": mapping.to_string(),
+        }))
+        .unwrap()
+    }
+
+    fn runtime_config(port: u16) -> RuntimeChannelConfig {
+        let mut config = RuntimeChannelConfig::from_base(
+            serde_json::from_value(json!({
+                "id": 9,
+                "name": "plc",
+                "protocol": PROTOCOL,
+                "parameters": {
+                    "endpoint_url": format!("opc.tcp://127.0.0.1:{}/ua", port),
+                    "username": "operator",
+                    "password": "secret",
+                    "response_timeout_ms": 2000,
+                    "publish_wait_ms": 2000,
+                },
+            }))
+            .unwrap(),
+        );
+        config.telemetry_points = vec![
+            point(1, json!({"node_id": "ns=2;s=PCS1.P"})),
+            point(2, json!({"node_id": "ns=2;s=Missing"})),
+            point(3, json!({"node_id": "ns=2;s=PCS1.P"})),
+        ];
+        config.signal_points = vec![
+            point(1, json!({"node_id": "ns=2;i=7"})),
+            point(2, json!({"node_id": "ns=2;x=bad"})),
+        ];
+        config.adjustment_points = vec![point(
+            1,
+            json!({"node_id": "ns=2;s=PCS1.PSet", "value_type": "float"}),
+        )];
+        config
+    }
+
+    #[test]
+    fn test_config_parsing() {
+        let parameters =
+            |v: JsonValue| -> HashMap<String, JsonValue> { serde_json::from_value(v).unwrap() };
+        let config = OpcUaConfig::from_parameters(&parameters(json!({
+            "endpoint_url": "opc.tcp://plc.local/server",
+            "publishing_interval_ms": "250",
+        })))
+        .unwrap();
+        assert_eq!((config.host.as_str(), config.port), ("plc.local", 4840));
+        assert_eq!(config.auth, Auth::Anonymous);
+        assert_eq!(config.sampling_interval_ms, 250);
+
+        assert_eq!(
+            parse_endpoint_url("opc.tcp://[::1]:4841"),
+            Ok(("::1".to_string(), 4841))
+        );
+        for bad in ["http://plc", "opc.tcp://", "opc.tcp://plc:x"] {
+            assert!(parse_endpoint_url(bad).is_err(), "{}", bad);
+        }
+        let secured = OpcUaConfig::from_parameters(&parameters(json!({
+            "endpoint_url": "opc.tcp://plc",
+            "security_policy": "Basic256Sha256",
+        })));
+        assert!(secured.unwrap_err().to_string().contains("only None"));
+        assert!(OpcUaConfig::from_parameters(&parameters(json!({}))).is_err());
+    }
+
+    /// Server side of one connection
+    struct Server {
+        stream: TcpStream,
+        reader: ChunkReader,
+        assembler: Assembler,
+        channel: SecureChannel,
+    }
+
+    impl Server {
+        async fn incoming(&mut self) -> (Vec<u8>, Incoming) {
+            let mut buf = [0u8; 4096];
+            loop {
+                if let Some(chunk) = self.reader.next_chunk() {
+                    let chunk = chunk.unwrap();
+                    if &chunk.message_type == b"HEL" {
+                        return (
+                            chunk.body,
+                            Incoming::Acknowledge {
+                                receive_buffer_size: 0,
+                            },
+                        );
+                    }
+                    if let Some(incoming) = self.assembler.push(chunk).unwrap() {
+                        return (Vec::new(), incoming);
+                    }
+                    continue;
+                }
+                let n = self.stream.read(&mut buf).await.unwrap();
+                assert!(n > 0, "client closed the connection");
+                self.reader.push(&buf[..n]);
+            }
+        }
+
+        /// Next request as (request ID, type ID, handle, fields)
+        async fn request(&mut self) -> (u32, u32, u32, Vec<u8>) {
+            let (_, incoming) = self.incoming().await;
+            let Incoming::Message { request_id, body } = incoming else {
+                panic!("expected a message");
+            };
+            let (type_id, handle, fields) = codec::parse_request_header(&body).unwrap();
+            (request_id, type_id, handle, fields.remaining().to_vec())
+        }
+
+        async fn respond(&mut self, request_id: u32, type_id: u32, handle: u32, fields: &[u8]) {
+            let body = codec::response(type_id, handle, fields);
+            let bytes = if type_id == ids::OPEN_SECURE_CHANNEL_RESPONSE {
+                self.channel.open(request_id, &body)
+            } else {
+                self.channel.message(request_id, &body)
+            };
+            self.stream.write_all(&bytes).await.unwrap();
+        }
+
+        /// Answer the next request, checking its type
+        async fn answer(&mut self, expected: u32, response: u32, fields: &[u8]) -> Vec<u8> {
+            let (request_id, type_id, handle, request) = self.request().await;
+            assert_eq!(type_id, expected);
+            self.respond(request_id, response, handle, fields).await;
+            request
+        }
+    }
+
+    fn endpoint_fields() -> Vec<u8> {
+        let mut e = Encoder::new();
+        e.string(Some("opc.tcp://127.0.0.1/ua"))
+            // Application description
+            .string(Some("urn:server"))
+            .string(None)
+            .u8(0)
+            .u32(0)
+            .string(None)
+            .string(None)
+            .array_len(0)
+            .byte_string(None)
+            .u32(1)
+            .string(Some(codec::SECURITY_POLICY_NONE))
+            .array_len(2)
+            .string(Some("anon"))
+            .u32(0)
+            .string(None)
+            .string(None)
+            .string(None)
+            .string(Some("user_plain"))
+            .u32(1)
+            .string(None)
+            .string(None)
+            .string(None)
+            .string(None)
+            .u8(0);
+        e.into_bytes()
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_publish_and_write_against_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut client = OpcUaRuntime::from_runtime_config(&runtime_config(port)).unwrap();
+        // Two telemetry points share a node; the malformed node ID is skipped
+        assert_eq!(client.monitored.len(), 3);
+        assert_eq!(client.monitored[0].1.len(), 2);
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut server = Server {
+                stream,
+                reader: ChunkReader::new(),
+                assembler: Assembler::new(),
+                channel: SecureChannel::new(),
+            };
+
+            let (hello, _) = server.incoming().await;
+            assert!(hello.windows(3).any(|w| w == b"/ua"));
+            let mut ack = Encoder::new();
+            ack.u32(0).u32(65536).u32(65536).u32(0).u32(0);
+            let ack = ack.into_bytes();
+            let mut bytes = b"ACKF".to_vec();
+            bytes.extend(((8 + ack.len()) as u32).to_le_bytes());
+            bytes.extend(ack);
+            server.stream.write_all(&bytes).await.unwrap();
+
+            server.channel.channel_id = 5;
+            server.channel.token_id = 1;
+            let mut opened = Encoder::new();
+            opened
+                .u32(0)
+                .u32(5)
+                .u32(1)
+                .i64(0)
+                .u32(600_000)
+                .byte_string(None);
+            server
+                .answer(
+                    ids::OPEN_SECURE_CHANNEL_REQUEST,
+                    ids::OPEN_SECURE_CHANNEL_RESPONSE,
+                    &opened.into_bytes(),
+                )
+                .await;
+
+            let mut created = Encoder::new();
+            created
+                .node_id(&NodeId::numeric(1, 1))
+                .node_id(&"b=dG9rZW4=".parse().unwrap())
+                .f64(60000.0)
+                .byte_string(None)
+                .byte_string(None)
+                .array_len(1)
+                .bytes(&endpoint_fields())
+                .array_len(0)
+                .string(None)
+                .byte_string(None)
+                .u32(0);
+            server
+                .answer(
+                    ids::CREATE_SESSION_REQUEST,
+                    ids::CREATE_SESSION_RESPONSE,
+                    &created.into_bytes(),
+                )
+                .await;
+
+            // The user name token carries the policy of the None endpoint
+            let mut activated = Encoder::new();
+            activated.byte_string(None).array_len(0).array_len(0);
+            let activate = server
+                .answer(
+                    ids::ACTIVATE_SESSION_REQUEST,
+                    ids::ACTIVATE_SESSION_RESPONSE,
+                    &activated.into_bytes(),
+                )
+                .await;
+            assert!(activate.windows(10).any(|w| w == b"user_plain"));
+            assert!(activate.windows(6).any(|w| w == b"secret"));
+
+            let mut subscribed = Encoder::new();
+            subscribed.u32(77).f64(1000.0).u32(30).u32(10);
+            server
+                .answer(
+                    ids::CREATE_SUBSCRIPTION_REQUEST,
+                    ids::CREATE_SUBSCRIPTION_RESPONSE,
+                    &subscribed.into_bytes(),
+                )
+                .await;
+
+            let mut monitored = Encoder::new();
+            monitored.array_len(3);
+            for code in [status::GOOD, 0x8034_0000, status::GOOD] {
+                monitored
+                    .u32(code)
+                    .u32(1)
+                    .f64(1000.0)
+                    .u32(1)
+                    .null_extension_object();
+            }
+            monitored.array_len(0);
+            let request = server
+                .answer(
+                    ids::CREATE_MONITORED_ITEMS_REQUEST,
+                    ids::CREATE_MONITORED_ITEMS_RESPONSE,
+                    &monitored.into_bytes(),
+                )
+                .await;
+            assert_eq!(&request[..4], &77u32.to_le_bytes());
+
+            // Two Publish requests; the first is answered with a data change
+            let (request_id, type_id, handle, _) = server.request().await;
+            assert_eq!(type_id, ids::PUBLISH_REQUEST);
+            let (_, type_id, _, _) = server.request().await;
+            assert_eq!(type_id, ids::PUBLISH_REQUEST);
+            let mut dcn = Encoder::new();
+            dcn.array_len(2)
+                .u32(0)
+                .u8(0x01)
+                .variant(&Variant::Double(-50.5))
+                .u32(2)
+                .u8(0x03)
+                .variant(&Variant::Boolean(true))
+                .u32(0x4000_0000)
+                .array_len(0);
+            let mut published = Encoder::new();
+            published
+                .u32(77)
+                .array_len(0)
+                .bool(false)
+                .u32(1)
+                .i64(0)
+                .array_len(1)
+                .extension_object(ids::DATA_CHANGE_NOTIFICATION, &dcn.into_bytes())
+                .array_len(0)
+                .array_len(0);
+            server
+                .respond(
+                    request_id,
+                    ids::PUBLISH_RESPONSE,
+                    handle,
+                    &published.into_bytes(),
+                )
+                .await;
+
+            // A replacement Publish acknowledges sequence number 1
+            let (_, type_id, _, publish) = server.request().await;
+            assert_eq!(type_id, ids::PUBLISH_REQUEST);
+            assert_eq!(publish, [1, 0, 0, 0, 77, 0, 0, 0, 1, 0, 0, 0]);
+
+            // Write of the adjustment as Float
+            let mut written = Encoder::new();
+            written.array_len(1).u32(status::GOOD).array_len(0);
+            let write = server
+                .answer(
+                    ids::WRITE_REQUEST,
+                    ids::WRITE_RESPONSE,
+                    &written.into_bytes(),
+                )
+                .await;
+            let mut expected = Encoder::new();
+            expected.value(&Variant::Float(12.5));
+            let expected = expected.into_bytes();
+            assert!(write.windows(expected.len()).any(|w| w == expected));
+        });
+
+        client.connect().await.unwrap();
+        let result = client.poll_once().await;
+        let points: HashMap<u32, (f64, Quality)> = result
+            .data
+            .iter()
+            .map(|p| (p.id, (p.value.as_f64().unwrap(), p.quality)))
+            .collect();
+        assert_eq!(
+            points[&PointType::Telemetry.to_internal_id(1)],
+            (-50.5, Quality::Good)
+        );
+        assert_eq!(points[&PointType::Telemetry.to_internal_id(3)].0, -50.5);
+        assert_eq!(
+            points[&PointType::Signal.to_internal_id(1)],
+            (1.0, Quality::Uncertain)
+        );
+        // The unknown node is reported once
+        assert_eq!(result.failures.len(), 1);
+        assert_eq!(
+            result.failures[0].point_id,
+            PointType::Telemetry.to_internal_id(2)
+        );
+
+        let written = client
+            .write_adjustment(&[(PointType::Adjustment.to_internal_id(1), 12.5)])
+            .await
+            .unwrap();
+        assert_eq!(written, 1);
+        server.await.unwrap();
+
+        // The server hung up: the next poll fails and drops the session
+        let result = client.poll_once().await;
+        assert!(result.has_failures());
+        assert!(client.session.is_none());
+    }
+}
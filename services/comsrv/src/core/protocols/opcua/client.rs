@@ -0,0 +1,496 @@
+//! OPC UA client session over UA-TCP
+//!
+//! Opens an unsecured secure channel, creates and activates a session and
+//! runs service calls on it. Publish requests are sent without waiting;
+//! their responses are queued while other calls are in flight and taken
+//! with [`Session::next_publish`].
+
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+
+use igw::GatewayError;
+use rand::RngCore;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{timeout_at, Instant};
+use tracing::debug;
+
+use super::codec::{
+    self, ids, status, status_name, ChunkReader, DataValue, Identity, Incoming, NodeId, Reference,
+    RequestHeader, Response, SecureChannel, Variant, SECURITY_MODE_NONE, SECURITY_POLICY_NONE,
+};
+use super::{Auth, OpcUaConfig};
+
+/// Requested secure channel lifetime
+const CHANNEL_LIFETIME_MS: u32 = 3_600_000;
+
+/// Application name announced in CreateSession
+const APPLICATION_NAME: &str = "VoltageEMS comsrv";
+
+/// Largest Browse continuation chain followed
+const MAX_BROWSE_NEXT: usize = 100;
+
+/// Error of a failed service result
+///
+/// Results invalidating the session or channel are connection errors so
+/// the runtime connects again.
+pub fn service_error(code: u32) -> GatewayError {
+    match code {
+        status::BAD_SESSION_ID_INVALID
+        | status::BAD_SESSION_CLOSED
+        | status::BAD_SESSION_NOT_ACTIVATED
+        | status::BAD_COMMUNICATION_ERROR
+        | 0x8022_0000 // BadSecureChannelIdInvalid
+        | 0x800C_0000 // BadShutdown
+        | 0x800D_0000 // BadServerNotConnected
+        => GatewayError::Connection(status_name(code)),
+        _ => GatewayError::Protocol(status_name(code)),
+    }
+}
+
+fn unexpected(response: &Response) -> GatewayError {
+    GatewayError::InvalidResponse(format!("unexpected response {:?}", response))
+}
+
+/// Activated session on a secure channel
+pub struct Session {
+    stream: TcpStream,
+    reader: ChunkReader,
+    assembler: codec::Assembler,
+    channel: SecureChannel,
+    auth_token: NodeId,
+    handle: u32,
+    timeout: Duration,
+    /// Channel token renewal time
+    renew_at: Instant,
+    /// Handles of Publish requests awaiting their response
+    publish_handles: HashSet<u32>,
+    /// Publish responses received while waiting for other responses
+    publishes: VecDeque<Response>,
+}
+
+impl Session {
+    /// Connect, open the channel, create and activate the session
+    pub async fn connect(config: &OpcUaConfig) -> igw::Result<Self> {
+        let address = format!("{}:{}", config.host, config.port);
+        let timeout_ms = config.response_timeout.as_millis() as u64;
+        let stream =
+            match tokio::time::timeout(config.response_timeout, TcpStream::connect(&address)).await
+            {
+                Ok(Ok(stream)) => stream,
+                Ok(Err(e)) => return Err(GatewayError::Connection(format!("{}: {}", address, e))),
+                Err(_) => return Err(GatewayError::ConnectionTimeout(timeout_ms)),
+            };
+        let _ = stream.set_nodelay(true);
+
+        let mut session = Self {
+            stream,
+            reader: ChunkReader::new(),
+            assembler: codec::Assembler::new(),
+            channel: SecureChannel::new(),
+            auth_token: NodeId::null(),
+            handle: 0,
+            timeout: config.response_timeout,
+            renew_at: Instant::now(),
+            publish_handles: HashSet::new(),
+            publishes: VecDeque::new(),
+        };
+
+        let deadline = Instant::now() + config.response_timeout;
+        session.send(&codec::hello(&config.endpoint_url)).await?;
+        match session.receive(deadline).await? {
+            Incoming::Acknowledge {
+                receive_buffer_size,
+            } => session.channel.max_chunk = (receive_buffer_size as usize).max(8192),
+            other => {
+                return Err(GatewayError::Connection(format!(
+                    "expected Acknowledge, got {:?}",
+                    other
+                )))
+            },
+        }
+        session.open_channel(false).await?;
+
+        let mut nonce = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let session_timeout = config.session_timeout_ms as f64;
+        let endpoint_url = config.endpoint_url.clone();
+        let (auth_token, endpoints) = match session
+            .call(|header| {
+                codec::create_session(
+                    header,
+                    &endpoint_url,
+                    APPLICATION_NAME,
+                    session_timeout,
+                    &nonce,
+                )
+            })
+            .await?
+        {
+            Response::CreateSession {
+                auth_token,
+                endpoints,
+                ..
+            } => (auth_token, endpoints),
+            other => return Err(unexpected(&other)),
+        };
+        session.auth_token = auth_token;
+
+        let identity = identity(config, &endpoints)?;
+        match session
+            .call(|header| codec::activate_session(header, &identity))
+            .await?
+        {
+            Response::ActivateSession { .. } => Ok(session),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    fn next_header(&mut self) -> RequestHeader {
+        self.handle = self.handle.wrapping_add(1).max(1);
+        RequestHeader {
+            auth_token: self.auth_token.clone(),
+            handle: self.handle,
+            timeout_hint_ms: self.timeout.as_millis() as u32,
+        }
+    }
+
+    async fn send(&mut self, bytes: &[u8]) -> igw::Result<()> {
+        self.stream
+            .write_all(bytes)
+            .await
+            .map_err(|e| GatewayError::Connection(format!("send: {}", e)))
+    }
+
+    /// Next complete message; `ConnectionTimeout` once `deadline` passes
+    async fn receive(&mut self, deadline: Instant) -> igw::Result<Incoming> {
+        let mut buf = [0u8; 8192];
+        loop {
+            while let Some(chunk) = self.reader.next_chunk() {
+                let chunk = chunk.map_err(|e| GatewayError::Connection(e.to_string()))?;
+                match self.assembler.push(chunk) {
+                    Ok(Some(Incoming::Error { status, reason })) => {
+                        return Err(GatewayError::Connection(format!(
+                            "server error {}: {}",
+                            status_name(status),
+                            reason
+                        )))
+                    },
+                    Ok(Some(incoming)) => return Ok(incoming),
+                    Ok(None) => {},
+                    Err(e) => return Err(GatewayError::Connection(e.to_string())),
+                }
+            }
+            let n = timeout_at(deadline, self.stream.read(&mut buf))
+                .await
+                .map_err(|_| GatewayError::ConnectionTimeout(self.timeout.as_millis() as u64))?
+                .map_err(|e| GatewayError::Connection(format!("receive: {}", e)))?;
+            if n == 0 {
+                return Err(GatewayError::Connection(
+                    "server closed the connection".to_string(),
+                ));
+            }
+            self.reader.push(&buf[..n]);
+        }
+    }
+
+    async fn receive_response(&mut self, deadline: Instant) -> igw::Result<Response> {
+        match self.receive(deadline).await? {
+            Incoming::Message { body, .. } => codec::parse_response(&body)
+                .map_err(|e| GatewayError::InvalidResponse(e.to_string())),
+            other => Err(GatewayError::Connection(format!("unexpected {:?}", other))),
+        }
+    }
+
+    /// Send a request and wait for its response, queueing publish responses
+    async fn request(
+        &mut self,
+        open: bool,
+        build: impl FnOnce(&RequestHeader) -> Vec<u8>,
+    ) -> igw::Result<Response> {
+        let header = self.next_header();
+        let request_id = self.channel.next_request_id();
+        let body = build(&header);
+        let bytes = if open {
+            self.channel.open(request_id, &body)
+        } else {
+            self.channel.message(request_id, &body)
+        };
+        self.send(&bytes).await?;
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let response = self.receive_response(deadline).await?;
+            let handle = response.header().request_handle;
+            if handle == header.handle {
+                return match response {
+                    Response::ServiceFault { header } => Err(service_error(header.service_result)),
+                    response => Ok(response),
+                };
+            }
+            if self.publish_handles.remove(&handle) {
+                self.publishes.push_back(response);
+            } else {
+                debug!("OPC UA response to unknown request {} ignored", handle);
+            }
+        }
+    }
+
+    async fn call(
+        &mut self,
+        build: impl FnOnce(&RequestHeader) -> Vec<u8>,
+    ) -> igw::Result<Response> {
+        if Instant::now() >= self.renew_at {
+            self.open_channel(true).await?;
+        }
+        self.request(false, build).await
+    }
+
+    /// Issue or renew the channel security token
+    async fn open_channel(&mut self, renew: bool) -> igw::Result<()> {
+        match self
+            .request(true, |header| {
+                codec::open_secure_channel(header, renew, CHANNEL_LIFETIME_MS)
+            })
+            .await
+        {
+            Ok(Response::OpenSecureChannel {
+                channel_id,
+                token_id,
+                lifetime_ms,
+                ..
+            }) => {
+                self.channel.channel_id = channel_id;
+                self.channel.token_id = token_id;
+                let lifetime = Duration::from_millis(lifetime_ms.max(10_000) as u64);
+                self.renew_at = Instant::now() + lifetime.mul_f32(0.75);
+                Ok(())
+            },
+            Ok(other) => Err(unexpected(&other)),
+            Err(GatewayError::Protocol(e)) => Err(GatewayError::Connection(e)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Forward hierarchical references of a node
+    pub async fn browse(&mut self, node: &NodeId) -> igw::Result<Vec<Reference>> {
+        let nodes = [node.clone()];
+        let mut response = self.call(|header| codec::browse(header, &nodes)).await?;
+        let mut references = Vec::new();
+        for _ in 0..MAX_BROWSE_NEXT {
+            let result = match response {
+                Response::Browse { mut results, .. } if results.len() == 1 => results.remove(0),
+                other => return Err(unexpected(&other)),
+            };
+            if !codec::is_good(result.status) {
+                return Err(GatewayError::Protocol(format!(
+                    "browse {}: {}",
+                    node,
+                    status_name(result.status)
+                )));
+            }
+            references.extend(result.references);
+            let Some(continuation) = result.continuation_point else {
+                return Ok(references);
+            };
+            let points = [continuation];
+            response = self
+                .call(|header| codec::browse_next(header, &points))
+                .await?;
+        }
+        Ok(references)
+    }
+
+    /// Read attributes of nodes
+    pub async fn read(&mut self, nodes: &[(NodeId, u32)]) -> igw::Result<Vec<DataValue>> {
+        match self.call(|header| codec::read(header, nodes)).await? {
+            Response::Read { results, .. } if results.len() == nodes.len() => Ok(results),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    /// Write node values; one status per value
+    pub async fn write(&mut self, values: &[(NodeId, Variant)]) -> igw::Result<Vec<u32>> {
+        match self.call(|header| codec::write(header, values)).await? {
+            Response::Write { results, .. } if results.len() == values.len() => Ok(results),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    pub async fn create_subscription(
+        &mut self,
+        publishing_interval_ms: u64,
+        keep_alive_count: u32,
+    ) -> igw::Result<u32> {
+        match self
+            .call(|header| {
+                codec::create_subscription(header, publishing_interval_ms as f64, keep_alive_count)
+            })
+            .await?
+        {
+            Response::CreateSubscription {
+                subscription_id, ..
+            } => Ok(subscription_id),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    /// Monitor node values; one status per (node, client handle)
+    pub async fn create_monitored_items(
+        &mut self,
+        subscription_id: u32,
+        items: &[(NodeId, u32)],
+        sampling_interval_ms: u64,
+    ) -> igw::Result<Vec<u32>> {
+        match self
+            .call(|header| {
+                codec::create_monitored_items(
+                    header,
+                    subscription_id,
+                    items,
+                    sampling_interval_ms as f64,
+                )
+            })
+            .await?
+        {
+            Response::CreateMonitoredItems { results, .. } if results.len() == items.len() => {
+                Ok(results.into_iter().map(|(status, _)| status).collect())
+            },
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    /// Send a Publish request without waiting for its response
+    pub async fn publish(&mut self, acks: &[(u32, u32)]) -> igw::Result<()> {
+        if Instant::now() >= self.renew_at {
+            self.open_channel(true).await?;
+        }
+        let mut header = self.next_header();
+        // The server holds Publish requests until notifications are due
+        header.timeout_hint_ms = 0;
+        let request_id = self.channel.next_request_id();
+        let bytes = self
+            .channel
+            .message(request_id, &codec::publish(&header, acks));
+        self.send(&bytes).await?;
+        self.publish_handles.insert(header.handle);
+        Ok(())
+    }
+
+    /// Publish requests awaiting their response
+    pub fn outstanding_publishes(&self) -> usize {
+        self.publish_handles.len() + self.publishes.len()
+    }
+
+    /// Next Publish response (or fault), `None` if none arrives by `deadline`
+    pub async fn next_publish(&mut self, deadline: Instant) -> igw::Result<Option<Response>> {
+        if let Some(response) = self.publishes.pop_front() {
+            return Ok(Some(response));
+        }
+        while !self.publish_handles.is_empty() {
+            let response = match self.receive_response(deadline).await {
+                Ok(response) => response,
+                Err(GatewayError::ConnectionTimeout(_)) => return Ok(None),
+                Err(e) => return Err(e),
+            };
+            if self
+                .publish_handles
+                .remove(&response.header().request_handle)
+            {
+                return Ok(Some(response));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Close the session and the channel
+    pub async fn close(mut self) {
+        self.timeout = self.timeout.min(Duration::from_secs(1));
+        let _ = self.call(codec::close_session).await;
+        let header = self.next_header();
+        let request_id = self.channel.next_request_id();
+        let bytes = self
+            .channel
+            .close(request_id, &codec::close_secure_channel(&header));
+        let _ = self.send(&bytes).await;
+        let _ = self.stream.shutdown().await;
+    }
+}
+
+/// User identity offered by the server's None endpoints
+fn identity(config: &OpcUaConfig, endpoints: &[codec::Endpoint]) -> igw::Result<Identity> {
+    let token_type = match config.auth {
+        Auth::Anonymous => 0,
+        Auth::UserName { .. } => 1,
+    };
+    let unsecured: Vec<&codec::Endpoint> = endpoints
+        .iter()
+        .filter(|e| {
+            e.security_mode == SECURITY_MODE_NONE && e.security_policy_uri == SECURITY_POLICY_NONE
+        })
+        .collect();
+    // Servers may return only secured endpoints; their token policy IDs still apply
+    let candidates: Vec<&codec::Endpoint> = if unsecured.is_empty() {
+        endpoints.iter().collect()
+    } else {
+        unsecured
+    };
+    let policy = candidates
+        .iter()
+        .flat_map(|e| e.user_tokens.iter())
+        .find(|p| {
+            p.token_type == token_type
+                && config
+                    .user_token_policy_id
+                    .as_ref()
+                    .is_none_or(|id| *id == p.policy_id)
+        });
+
+    let policy_id = match (policy, &config.user_token_policy_id) {
+        (Some(policy), _) => policy.policy_id.clone(),
+        (None, Some(id)) => id.clone(),
+        // Servers commonly use these IDs when they list no policy
+        (None, None) if token_type == 0 => "anonymous".to_string(),
+        (None, None) => "username".to_string(),
+    };
+    match &config.auth {
+        Auth::Anonymous => Ok(Identity::Anonymous { policy_id }),
+        Auth::UserName { user, password } => {
+            if let Some(uri) = policy.and_then(|p| p.security_policy_uri.as_deref()) {
+                if uri != SECURITY_POLICY_NONE {
+                    return Err(GatewayError::Unsupported(format!(
+                        "user token policy '{}' requires an encrypted password ({}), \
+                         which needs a secured channel",
+                        policy_id, uri
+                    )));
+                }
+            }
+            Ok(Identity::UserName {
+                policy_id,
+                user: user.clone(),
+                password: password.clone(),
+            })
+        },
+    }
+}
+
+/// True for Publish faults that only mean "retry later"
+pub fn is_transient_publish_fault(code: u32) -> bool {
+    matches!(
+        code,
+        status::BAD_TIMEOUT | status::BAD_TOO_MANY_PUBLISH_REQUESTS
+    )
+}
+
+/// Data type node IDs of the numeric built-in types (Boolean..Double)
+pub fn builtin_data_type(node: &NodeId) -> Option<u8> {
+    match node.identifier {
+        codec::Identifier::Numeric(id @ 1..=11) if node.namespace == 0 => Some(id as u8),
+        _ => None,
+    }
+}
+
+/// Root of browsing when no node is given
+pub fn objects_folder() -> NodeId {
+    NodeId::numeric(0, ids::OBJECTS_FOLDER)
+}
@@ -0,0 +1,1937 @@
+//! OPC UA binary encoding and UA-TCP framing
+//!
+//! Covers what a client with SecurityPolicy None needs: HEL/ACK/ERR, secure
+//! channel open/close with message chunking, and the session, browse, read,
+//! write, subscription and publish services. Requests are encoded field by
+//! field; responses decode what the client uses and skip the rest.
+//!
+//! The server side (`runtime::opcua_server`) reuses the framing and adds the
+//! Acknowledge/Error messages and response headers at the end of this file.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use base64::Engine as _;
+
+use crate::core::protocols::{error, CodecError};
+
+type Result<T> = std::result::Result<T, CodecError>;
+
+/// SecurityPolicy URI of unsecured channels
+pub const SECURITY_POLICY_NONE: &str = "http://opcfoundation.org/UA/SecurityPolicy#None";
+
+/// MessageSecurityMode None
+pub const SECURITY_MODE_NONE: u32 = 1;
+
+/// Receive buffer announced in HEL (also the largest chunk accepted)
+pub const RECEIVE_BUFFER_SIZE: u32 = 65536;
+
+/// Largest chunk accepted from a misbehaving peer
+const MAX_CHUNK_SIZE: usize = 1 << 20;
+
+/// Largest reassembled message
+const MAX_MESSAGE_SIZE: usize = 16 << 20;
+
+/// MSG/CLO chunk header: type, size, channel, token, sequence, request
+const SYMMETRIC_HEADER_LEN: usize = 24;
+
+/// Nesting depth of variants and data values decoded before giving up
+const MAX_DEPTH: usize = 16;
+
+/// Binary encoding IDs of the services and structures used (namespace 0)
+pub mod ids {
+    pub const SERVICE_FAULT: u32 = 397;
+    pub const FIND_SERVERS_REQUEST: u32 = 422;
+    pub const FIND_SERVERS_RESPONSE: u32 = 425;
+    pub const GET_ENDPOINTS_REQUEST: u32 = 428;
+    pub const GET_ENDPOINTS_RESPONSE: u32 = 431;
+    pub const OPEN_SECURE_CHANNEL_REQUEST: u32 = 446;
+    pub const OPEN_SECURE_CHANNEL_RESPONSE: u32 = 449;
+    pub const CLOSE_SECURE_CHANNEL_REQUEST: u32 = 452;
+    pub const CREATE_SESSION_REQUEST: u32 = 461;
+    pub const CREATE_SESSION_RESPONSE: u32 = 464;
+    pub const ACTIVATE_SESSION_REQUEST: u32 = 467;
+    pub const ACTIVATE_SESSION_RESPONSE: u32 = 470;
+    pub const CLOSE_SESSION_REQUEST: u32 = 473;
+    pub const CLOSE_SESSION_RESPONSE: u32 = 476;
+    pub const BROWSE_REQUEST: u32 = 527;
+    pub const BROWSE_RESPONSE: u32 = 530;
+    pub const BROWSE_NEXT_REQUEST: u32 = 533;
+    pub const BROWSE_NEXT_RESPONSE: u32 = 536;
+    pub const READ_REQUEST: u32 = 631;
+    pub const READ_RESPONSE: u32 = 634;
+    pub const WRITE_REQUEST: u32 = 673;
+    pub const WRITE_RESPONSE: u32 = 676;
+    pub const CREATE_MONITORED_ITEMS_REQUEST: u32 = 751;
+    pub const CREATE_MONITORED_ITEMS_RESPONSE: u32 = 754;
+    pub const DELETE_MONITORED_ITEMS_REQUEST: u32 = 781;
+    pub const DELETE_MONITORED_ITEMS_RESPONSE: u32 = 784;
+    pub const CREATE_SUBSCRIPTION_REQUEST: u32 = 787;
+    pub const CREATE_SUBSCRIPTION_RESPONSE: u32 = 790;
+    pub const PUBLISH_REQUEST: u32 = 826;
+    pub const PUBLISH_RESPONSE: u32 = 829;
+    pub const REPUBLISH_REQUEST: u32 = 832;
+    pub const REPUBLISH_RESPONSE: u32 = 835;
+    pub const DELETE_SUBSCRIPTIONS_REQUEST: u32 = 847;
+    pub const DELETE_SUBSCRIPTIONS_RESPONSE: u32 = 850;
+    pub const ANONYMOUS_IDENTITY_TOKEN: u32 = 321;
+    pub const USER_NAME_IDENTITY_TOKEN: u32 = 324;
+    pub const DATA_CHANGE_NOTIFICATION: u32 = 811;
+    pub const STATUS_CHANGE_NOTIFICATION: u32 = 820;
+
+    /// Node IDs
+    pub const BOOLEAN: u32 = 1;
+    pub const DOUBLE: u32 = 11;
+    pub const HIERARCHICAL_REFERENCES: u32 = 33;
+    pub const ORGANIZES: u32 = 35;
+    pub const HAS_COMPONENT: u32 = 47;
+    pub const BASE_OBJECT_TYPE: u32 = 58;
+    pub const FOLDER_TYPE: u32 = 61;
+    pub const BASE_DATA_VARIABLE_TYPE: u32 = 63;
+    pub const ROOT_FOLDER: u32 = 84;
+    pub const OBJECTS_FOLDER: u32 = 85;
+}
+
+/// Attribute IDs
+pub mod attribute {
+    pub const NODE_ID: u32 = 1;
+    pub const NODE_CLASS: u32 = 2;
+    pub const BROWSE_NAME: u32 = 3;
+    pub const DISPLAY_NAME: u32 = 4;
+    pub const DESCRIPTION: u32 = 5;
+    pub const WRITE_MASK: u32 = 6;
+    pub const USER_WRITE_MASK: u32 = 7;
+    pub const EVENT_NOTIFIER: u32 = 12;
+    pub const VALUE: u32 = 13;
+    pub const DATA_TYPE: u32 = 14;
+    pub const VALUE_RANK: u32 = 15;
+    pub const ACCESS_LEVEL: u32 = 17;
+    pub const USER_ACCESS_LEVEL: u32 = 18;
+    pub const HISTORIZING: u32 = 20;
+}
+
+// ============================================================================
+// Status codes
+// ============================================================================
+
+pub mod status {
+    pub const GOOD: u32 = 0;
+    pub const BAD_INTERNAL_ERROR: u32 = 0x8002_0000;
+    pub const BAD_DECODING_ERROR: u32 = 0x8007_0000;
+    pub const BAD_TIMEOUT: u32 = 0x800A_0000;
+    pub const BAD_SERVICE_UNSUPPORTED: u32 = 0x800B_0000;
+    pub const BAD_NOTHING_TO_DO: u32 = 0x800F_0000;
+    pub const BAD_USER_ACCESS_DENIED: u32 = 0x801F_0000;
+    pub const BAD_IDENTITY_TOKEN_INVALID: u32 = 0x8020_0000;
+    pub const BAD_IDENTITY_TOKEN_REJECTED: u32 = 0x8021_0000;
+    pub const BAD_WAITING_FOR_INITIAL_DATA: u32 = 0x8032_0000;
+    pub const BAD_NODE_ID_UNKNOWN: u32 = 0x8034_0000;
+    pub const BAD_ATTRIBUTE_ID_INVALID: u32 = 0x8035_0000;
+    pub const BAD_NOT_WRITABLE: u32 = 0x803B_0000;
+    pub const BAD_MONITORED_ITEM_ID_INVALID: u32 = 0x8042_0000;
+    pub const BAD_CONTINUATION_POINT_INVALID: u32 = 0x804A_0000;
+    pub const BAD_SECURITY_POLICY_REJECTED: u32 = 0x8055_0000;
+    pub const BAD_TYPE_MISMATCH: u32 = 0x8074_0000;
+    pub const BAD_MESSAGE_NOT_AVAILABLE: u32 = 0x807B_0000;
+    pub const BAD_TCP_MESSAGE_TYPE_INVALID: u32 = 0x807E_0000;
+    pub const BAD_SESSION_ID_INVALID: u32 = 0x8025_0000;
+    pub const BAD_SESSION_CLOSED: u32 = 0x8026_0000;
+    pub const BAD_SESSION_NOT_ACTIVATED: u32 = 0x8027_0000;
+    pub const BAD_SUBSCRIPTION_ID_INVALID: u32 = 0x8028_0000;
+    pub const BAD_COMMUNICATION_ERROR: u32 = 0x8005_0000;
+    pub const BAD_NOT_CONNECTED: u32 = 0x808A_0000;
+    pub const BAD_DEVICE_FAILURE: u32 = 0x808B_0000;
+    pub const BAD_SENSOR_FAILURE: u32 = 0x808C_0000;
+    pub const BAD_TOO_MANY_PUBLISH_REQUESTS: u32 = 0x8078_0000;
+    pub const BAD_NO_SUBSCRIPTION: u32 = 0x8079_0000;
+}
+
+pub fn is_good(code: u32) -> bool {
+    code >> 30 == 0
+}
+
+pub fn is_uncertain(code: u32) -> bool {
+    code >> 30 == 1
+}
+
+/// Symbolic name of common status codes (hex otherwise)
+pub fn status_name(code: u32) -> String {
+    let name = match code & 0xFFFF_0000 {
+        0 => "Good",
+        0x8001_0000 => "BadUnexpectedError",
+        0x8002_0000 => "BadInternalError",
+        0x8003_0000 => "BadOutOfMemory",
+        0x8005_0000 => "BadCommunicationError",
+        0x800A_0000 => "BadTimeout",
+        0x8007_0000 => "BadDecodingError",
+        0x800B_0000 => "BadServiceUnsupported",
+        0x800C_0000 => "BadShutdown",
+        0x800D_0000 => "BadServerNotConnected",
+        0x800F_0000 => "BadNothingToDo",
+        0x8010_0000 => "BadTooManyOperations",
+        0x801F_0000 => "BadUserAccessDenied",
+        0x8020_0000 => "BadIdentityTokenInvalid",
+        0x8021_0000 => "BadIdentityTokenRejected",
+        0x8022_0000 => "BadSecureChannelIdInvalid",
+        0x8025_0000 => "BadSessionIdInvalid",
+        0x8026_0000 => "BadSessionClosed",
+        0x8027_0000 => "BadSessionNotActivated",
+        0x8028_0000 => "BadSubscriptionIdInvalid",
+        0x8032_0000 => "BadWaitingForInitialData",
+        0x8033_0000 => "BadNodeIdInvalid",
+        0x8034_0000 => "BadNodeIdUnknown",
+        0x8035_0000 => "BadAttributeIdInvalid",
+        0x803A_0000 => "BadNotReadable",
+        0x803B_0000 => "BadNotWritable",
+        0x803C_0000 => "BadOutOfRange",
+        0x8042_0000 => "BadMonitoredItemIdInvalid",
+        0x804A_0000 => "BadContinuationPointInvalid",
+        0x8055_0000 => "BadSecurityPolicyRejected",
+        0x8074_0000 => "BadTypeMismatch",
+        0x8078_0000 => "BadTooManyPublishRequests",
+        0x8079_0000 => "BadNoSubscription",
+        0x807B_0000 => "BadMessageNotAvailable",
+        0x807E_0000 => "BadTcpMessageTypeInvalid",
+        0x8083_0000 => "BadTcpEndpointUrlInvalid",
+        0x808A_0000 => "BadNotConnected",
+        0x808B_0000 => "BadDeviceFailure",
+        0x808C_0000 => "BadSensorFailure",
+        _ => return format!("0x{:08X}", code),
+    };
+    name.to_string()
+}
+
+// ============================================================================
+// Node IDs
+// ============================================================================
+
+/// Identifier part of a node ID
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Identifier {
+    Numeric(u32),
+    String(String),
+    /// Canonical (text order) bytes
+    Guid([u8; 16]),
+    Opaque(Vec<u8>),
+}
+
+/// Node ID in the standard text form (`ns=2;s=Pump.Speed`, `i=85`)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    pub namespace: u16,
+    pub identifier: Identifier,
+}
+
+impl NodeId {
+    pub fn numeric(namespace: u16, id: u32) -> Self {
+        Self {
+            namespace,
+            identifier: Identifier::Numeric(id),
+        }
+    }
+
+    /// `i=0`, used for absent node IDs
+    pub fn null() -> Self {
+        Self::numeric(0, 0)
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.namespace == 0 && self.identifier == Identifier::Numeric(0)
+    }
+}
+
+impl FromStr for NodeId {
+    type Err = CodecError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let invalid = || error(format!("invalid node ID '{}'", s));
+        let (namespace, rest) = match s.strip_prefix("ns=") {
+            Some(rest) => {
+                let (ns, rest) = rest.split_once(';').ok_or_else(invalid)?;
+                (ns.parse().map_err(|_| invalid())?, rest)
+            },
+            None => (0, s),
+        };
+        let (kind, value) = (rest.get(..2), rest.get(2..).unwrap_or_default());
+        let identifier = match kind {
+            Some("i=") => Identifier::Numeric(value.parse().map_err(|_| invalid())?),
+            Some("s=") if !value.is_empty() => Identifier::String(value.to_string()),
+            Some("g=") => Identifier::Guid(parse_guid(value).ok_or_else(invalid)?),
+            Some("b=") => Identifier::Opaque(
+                base64::engine::general_purpose::STANDARD
+                    .decode(value)
+                    .map_err(|_| invalid())?,
+            ),
+            _ => return Err(invalid()),
+        };
+        Ok(Self {
+            namespace,
+            identifier,
+        })
+    }
+}
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.namespace != 0 {
+            write!(f, "ns={};", self.namespace)?;
+        }
+        match &self.identifier {
+            Identifier::Numeric(id) => write!(f, "i={}", id),
+            Identifier::String(s) => write!(f, "s={}", s),
+            Identifier::Guid(g) => write!(
+                f,
+                "g={}-{}-{}-{}-{}",
+                hex(&g[..4]),
+                hex(&g[4..6]),
+                hex(&g[6..8]),
+                hex(&g[8..10]),
+                hex(&g[10..])
+            ),
+            Identifier::Opaque(b) => write!(
+                f,
+                "b={}",
+                base64::engine::general_purpose::STANDARD.encode(b)
+            ),
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn parse_guid(text: &str) -> Option<[u8; 16]> {
+    let digits: String = text.chars().filter(|c| *c != '-').collect();
+    if digits.len() != 32 || text.len() != 36 {
+        return None;
+    }
+    let mut guid = [0u8; 16];
+    for (i, byte) in guid.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(digits.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(guid)
+}
+
+// ============================================================================
+// Values
+// ============================================================================
+
+/// Built-in type IDs of variants
+pub mod builtin {
+    pub const BOOLEAN: u8 = 1;
+    pub const SBYTE: u8 = 2;
+    pub const BYTE: u8 = 3;
+    pub const INT16: u8 = 4;
+    pub const UINT16: u8 = 5;
+    pub const INT32: u8 = 6;
+    pub const UINT32: u8 = 7;
+    pub const INT64: u8 = 8;
+    pub const UINT64: u8 = 9;
+    pub const FLOAT: u8 = 10;
+    pub const DOUBLE: u8 = 11;
+    pub const STRING: u8 = 12;
+    pub const DATE_TIME: u8 = 13;
+    pub const NODE_ID: u8 = 17;
+    pub const STATUS_CODE: u8 = 19;
+    pub const LOCALIZED_TEXT: u8 = 21;
+}
+
+/// Decoded variant; types the client does not use keep only their type ID
+#[derive(Debug, Clone, PartialEq)]
+pub enum Variant {
+    Empty,
+    Boolean(bool),
+    SByte(i8),
+    Byte(u8),
+    Int16(i16),
+    UInt16(u16),
+    Int32(i32),
+    UInt32(u32),
+    Int64(i64),
+    UInt64(u64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    DateTime(i64),
+    NodeId(NodeId),
+    StatusCode(u32),
+    LocalizedText(String),
+    Array { type_id: u8, len: usize },
+    Other(u8),
+}
+
+impl Variant {
+    /// Numeric value of numeric and boolean scalars
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Self::Boolean(v) => Some(if v { 1.0 } else { 0.0 }),
+            Self::SByte(v) => Some(v as f64),
+            Self::Byte(v) => Some(v as f64),
+            Self::Int16(v) => Some(v as f64),
+            Self::UInt16(v) => Some(v as f64),
+            Self::Int32(v) => Some(v as f64),
+            Self::UInt32(v) => Some(v as f64),
+            Self::Int64(v) => Some(v as f64),
+            Self::UInt64(v) => Some(v as f64),
+            Self::Float(v) => Some(v as f64),
+            Self::Double(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+/// Value with status and source time
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataValue {
+    pub value: Option<Variant>,
+    pub status: u32,
+    pub source_timestamp: Option<i64>,
+}
+
+/// Milliseconds between 1601-01-01 (DateTime epoch) and 1970-01-01
+const EPOCH_OFFSET_MS: i64 = 11_644_473_600_000;
+
+/// DateTime (100 ns ticks since 1601-01-01) of now
+pub fn date_time_now() -> i64 {
+    date_time_from_millis(chrono::Utc::now().timestamp_millis())
+}
+
+/// DateTime of a Unix time in milliseconds
+pub fn date_time_from_millis(ms: i64) -> i64 {
+    (ms + EPOCH_OFFSET_MS) * 10_000
+}
+
+/// UTC time of a DateTime; 0 means absent
+pub fn date_time_to_utc(ticks: i64) -> Option<chrono::DateTime<chrono::Utc>> {
+    if ticks <= 0 {
+        return None;
+    }
+    chrono::DateTime::from_timestamp_millis(ticks / 10_000 - EPOCH_OFFSET_MS)
+}
+
+// ============================================================================
+// Encoder
+// ============================================================================
+
+/// Little-endian OPC UA binary encoder
+#[derive(Debug, Default)]
+pub struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    pub fn bytes(&mut self, v: &[u8]) -> &mut Self {
+        self.buf.extend_from_slice(v);
+        self
+    }
+
+    pub fn u8(&mut self, v: u8) -> &mut Self {
+        self.buf.push(v);
+        self
+    }
+
+    pub fn bool(&mut self, v: bool) -> &mut Self {
+        self.u8(v as u8)
+    }
+
+    pub fn u16(&mut self, v: u16) -> &mut Self {
+        self.bytes(&v.to_le_bytes())
+    }
+
+    pub fn u32(&mut self, v: u32) -> &mut Self {
+        self.bytes(&v.to_le_bytes())
+    }
+
+    pub fn i32(&mut self, v: i32) -> &mut Self {
+        self.bytes(&v.to_le_bytes())
+    }
+
+    pub fn i64(&mut self, v: i64) -> &mut Self {
+        self.bytes(&v.to_le_bytes())
+    }
+
+    pub fn f64(&mut self, v: f64) -> &mut Self {
+        self.bytes(&v.to_le_bytes())
+    }
+
+    /// String; `None` is the null string
+    pub fn string(&mut self, v: Option<&str>) -> &mut Self {
+        self.byte_string(v.map(str::as_bytes))
+    }
+
+    pub fn byte_string(&mut self, v: Option<&[u8]>) -> &mut Self {
+        match v {
+            Some(v) => self.i32(v.len() as i32).bytes(v),
+            None => self.i32(-1),
+        }
+    }
+
+    /// Array length prefix
+    pub fn array_len(&mut self, len: usize) -> &mut Self {
+        self.i32(len as i32)
+    }
+
+    pub fn node_id(&mut self, id: &NodeId) -> &mut Self {
+        let ns = id.namespace;
+        match &id.identifier {
+            Identifier::Numeric(v) if ns == 0 && *v <= 0xFF => self.u8(0x00).u8(*v as u8),
+            Identifier::Numeric(v) if ns <= 0xFF && *v <= 0xFFFF => {
+                self.u8(0x01).u8(ns as u8).u16(*v as u16)
+            },
+            Identifier::Numeric(v) => self.u8(0x02).u16(ns).u32(*v),
+            Identifier::String(s) => self.u8(0x03).u16(ns).string(Some(s)),
+            Identifier::Guid(g) => {
+                self.u8(0x04).u16(ns);
+                self.bytes(&[g[3], g[2], g[1], g[0], g[5], g[4], g[7], g[6]])
+                    .bytes(&g[8..])
+            },
+            Identifier::Opaque(b) => self.u8(0x05).u16(ns).byte_string(Some(b)),
+        }
+    }
+
+    /// Encoding ID of a namespace 0 structure
+    pub fn type_id(&mut self, id: u32) -> &mut Self {
+        self.node_id(&NodeId::numeric(0, id))
+    }
+
+    /// ExtensionObject without body
+    pub fn null_extension_object(&mut self) -> &mut Self {
+        self.u8(0x00).u8(0x00).u8(0x00)
+    }
+
+    /// ExtensionObject with a binary body
+    pub fn extension_object(&mut self, type_id: u32, body: &[u8]) -> &mut Self {
+        self.type_id(type_id).u8(0x01).byte_string(Some(body))
+    }
+
+    /// Scalar variant (types the client writes)
+    pub fn variant(&mut self, v: &Variant) -> &mut Self {
+        match v {
+            Variant::Boolean(v) => self.u8(builtin::BOOLEAN).bool(*v),
+            Variant::SByte(v) => self.u8(builtin::SBYTE).u8(*v as u8),
+            Variant::Byte(v) => self.u8(builtin::BYTE).u8(*v),
+            Variant::Int16(v) => self.u8(builtin::INT16).bytes(&v.to_le_bytes()),
+            Variant::UInt16(v) => self.u8(builtin::UINT16).u16(*v),
+            Variant::Int32(v) => self.u8(builtin::INT32).i32(*v),
+            Variant::UInt32(v) => self.u8(builtin::UINT32).u32(*v),
+            Variant::Int64(v) => self.u8(builtin::INT64).i64(*v),
+            Variant::UInt64(v) => self.u8(builtin::UINT64).bytes(&v.to_le_bytes()),
+            Variant::Float(v) => self.u8(builtin::FLOAT).bytes(&v.to_le_bytes()),
+            Variant::Double(v) => self.u8(builtin::DOUBLE).f64(*v),
+            Variant::String(v) => self.u8(builtin::STRING).string(Some(v)),
+            Variant::DateTime(v) => self.u8(builtin::DATE_TIME).i64(*v),
+            Variant::NodeId(v) => self.u8(builtin::NODE_ID).node_id(v),
+            Variant::StatusCode(v) => self.u8(builtin::STATUS_CODE).u32(*v),
+            Variant::LocalizedText(v) => self.u8(builtin::LOCALIZED_TEXT).u8(0x02).string(Some(v)),
+            Variant::Empty | Variant::Array { .. } | Variant::Other(_) => self.u8(0),
+        }
+    }
+
+    /// DataValue carrying only a value
+    pub fn value(&mut self, v: &Variant) -> &mut Self {
+        self.u8(0x01).variant(v)
+    }
+
+    /// DataValue; a good status and absent parts are left out of the mask
+    pub fn data_value(&mut self, v: &DataValue) -> &mut Self {
+        let mask = v.value.is_some() as u8
+            | ((v.status != status::GOOD) as u8) << 1
+            | (v.source_timestamp.is_some() as u8) << 2;
+        self.u8(mask);
+        if let Some(value) = &v.value {
+            self.variant(value);
+        }
+        if v.status != status::GOOD {
+            self.u32(v.status);
+        }
+        if let Some(ts) = v.source_timestamp {
+            self.i64(ts);
+        }
+        self
+    }
+
+    pub fn qualified_name(&mut self, namespace: u16, name: &str) -> &mut Self {
+        self.u16(namespace).string(Some(name))
+    }
+
+    /// LocalizedText without locale
+    pub fn localized_text(&mut self, text: &str) -> &mut Self {
+        self.u8(0x02).string(Some(text))
+    }
+
+    pub fn request_header(&mut self, header: &RequestHeader) -> &mut Self {
+        self.node_id(&header.auth_token)
+            .i64(date_time_now())
+            .u32(header.handle)
+            .u32(0) // returnDiagnostics
+            .string(None) // auditEntryId
+            .u32(header.timeout_hint_ms)
+            .null_extension_object()
+    }
+}
+
+// ============================================================================
+// Decoder
+// ============================================================================
+
+/// Little-endian OPC UA binary decoder
+#[derive(Debug, Clone)]
+pub struct Decoder<'a> {
+    data: &'a [u8],
+    depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, depth: 0 }
+    }
+
+    pub fn remaining(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.data.len() < n {
+            return Err(error("message truncated"));
+        }
+        let (head, rest) = self.data.split_at(n);
+        self.data = rest;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    pub fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn bool(&mut self) -> Result<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    pub fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    pub fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    pub fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+
+    pub fn i64(&mut self) -> Result<i64> {
+        Ok(i64::from_le_bytes(self.array()?))
+    }
+
+    pub fn f64(&mut self) -> Result<f64> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    pub fn byte_string(&mut self) -> Result<Option<&'a [u8]>> {
+        match self.i32()? {
+            n if n < 0 => Ok(None),
+            n => self.take(n as usize).map(Some),
+        }
+    }
+
+    pub fn string(&mut self) -> Result<Option<String>> {
+        Ok(self
+            .byte_string()?
+            .map(|b| String::from_utf8_lossy(b).into_owned()))
+    }
+
+    /// Array length; null arrays are empty
+    pub fn array_len(&mut self) -> Result<usize> {
+        let n = self.i32()?;
+        if n < 0 {
+            return Ok(0);
+        }
+        // Every element takes at least one byte
+        if n as usize > self.data.len() {
+            return Err(error(format!("array of {} elements truncated", n)));
+        }
+        Ok(n as usize)
+    }
+
+    pub fn vec<T>(&mut self, mut item: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        let n = self.array_len()?;
+        (0..n).map(|_| item(self)).collect()
+    }
+
+    pub fn node_id(&mut self) -> Result<NodeId> {
+        let encoding = self.u8()?;
+        self.node_id_body(encoding & 0x3F)
+    }
+
+    fn node_id_body(&mut self, encoding: u8) -> Result<NodeId> {
+        let (namespace, identifier) = match encoding {
+            0x00 => (0, Identifier::Numeric(self.u8()? as u32)),
+            0x01 => (self.u8()? as u16, Identifier::Numeric(self.u16()? as u32)),
+            0x02 => (self.u16()?, Identifier::Numeric(self.u32()?)),
+            0x03 => (
+                self.u16()?,
+                Identifier::String(self.string()?.unwrap_or_default()),
+            ),
+            0x04 => {
+                let ns = self.u16()?;
+                let b = self.take(16)?;
+                let mut g = [0u8; 16];
+                g[..8].copy_from_slice(&[b[3], b[2], b[1], b[0], b[5], b[4], b[7], b[6]]);
+                g[8..].copy_from_slice(&b[8..]);
+                (ns, Identifier::Guid(g))
+            },
+            0x05 => (
+                self.u16()?,
+                Identifier::Opaque(self.byte_string()?.unwrap_or_default().to_vec()),
+            ),
+            other => return Err(error(format!("unknown node ID encoding {}", other))),
+        };
+        Ok(NodeId {
+            namespace,
+            identifier,
+        })
+    }
+
+    /// ExpandedNodeId; namespace URI and server index are dropped
+    pub fn expanded_node_id(&mut self) -> Result<NodeId> {
+        let encoding = self.u8()?;
+        let id = self.node_id_body(encoding & 0x3F)?;
+        if encoding & 0x80 != 0 {
+            self.string()?;
+        }
+        if encoding & 0x40 != 0 {
+            self.u32()?;
+        }
+        Ok(id)
+    }
+
+    pub fn qualified_name(&mut self) -> Result<(u16, String)> {
+        Ok((self.u16()?, self.string()?.unwrap_or_default()))
+    }
+
+    /// Text of a LocalizedText
+    pub fn localized_text(&mut self) -> Result<String> {
+        let mask = self.u8()?;
+        if mask & 0x01 != 0 {
+            self.string()?;
+        }
+        Ok(if mask & 0x02 != 0 {
+            self.string()?.unwrap_or_default()
+        } else {
+            String::new()
+        })
+    }
+
+    /// ExtensionObject as (encoding ID, binary body)
+    pub fn extension_object(&mut self) -> Result<(NodeId, Option<&'a [u8]>)> {
+        let type_id = self.node_id()?;
+        let body = match self.u8()? {
+            0x00 => None,
+            0x01 => self.byte_string()?,
+            0x02 => {
+                self.byte_string()?; // XML body
+                None
+            },
+            other => {
+                return Err(error(format!(
+                    "unknown extension object encoding {}",
+                    other
+                )))
+            },
+        };
+        Ok((type_id, body))
+    }
+
+    pub fn diagnostic_info(&mut self) -> Result<()> {
+        self.nested(|d| {
+            let mask = d.u8()?;
+            for bit in [0x01, 0x02, 0x04, 0x08] {
+                if mask & bit != 0 {
+                    d.i32()?;
+                }
+            }
+            if mask & 0x10 != 0 {
+                d.string()?;
+            }
+            if mask & 0x20 != 0 {
+                d.u32()?;
+            }
+            if mask & 0x40 != 0 {
+                d.diagnostic_info()?;
+            }
+            Ok(())
+        })
+    }
+
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.depth >= MAX_DEPTH {
+            return Err(error("structure nested too deeply"));
+        }
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    pub fn variant(&mut self) -> Result<Variant> {
+        self.nested(|d| {
+            let mask = d.u8()?;
+            let type_id = mask & 0x3F;
+            if mask & 0x80 != 0 {
+                let len = d.array_len()?;
+                for _ in 0..len {
+                    d.scalar(type_id)?;
+                }
+                if mask & 0x40 != 0 {
+                    let dims = d.array_len()?;
+                    d.take(dims * 4)?;
+                }
+                return Ok(Variant::Array { type_id, len });
+            }
+            d.scalar(type_id)
+        })
+    }
+
+    fn scalar(&mut self, type_id: u8) -> Result<Variant> {
+        Ok(match type_id {
+            0 => Variant::Empty,
+            builtin::BOOLEAN => Variant::Boolean(self.bool()?),
+            builtin::SBYTE => Variant::SByte(self.u8()? as i8),
+            builtin::BYTE => Variant::Byte(self.u8()?),
+            builtin::INT16 => Variant::Int16(i16::from_le_bytes(self.array()?)),
+            builtin::UINT16 => Variant::UInt16(self.u16()?),
+            builtin::INT32 => Variant::Int32(self.i32()?),
+            builtin::UINT32 => Variant::UInt32(self.u32()?),
+            builtin::INT64 => Variant::Int64(self.i64()?),
+            builtin::UINT64 => Variant::UInt64(u64::from_le_bytes(self.array()?)),
+            builtin::FLOAT => Variant::Float(f32::from_le_bytes(self.array()?)),
+            builtin::DOUBLE => Variant::Double(self.f64()?),
+            builtin::STRING => Variant::String(self.string()?.unwrap_or_default()),
+            builtin::DATE_TIME => Variant::DateTime(self.i64()?),
+            14 => {
+                self.take(16)?; // Guid
+                Variant::Other(14)
+            },
+            15 | 16 => {
+                self.byte_string()?; // ByteString, XmlElement
+                Variant::Other(type_id)
+            },
+            builtin::NODE_ID => Variant::NodeId(self.node_id()?),
+            18 => {
+                self.expanded_node_id()?;
+                Variant::Other(18)
+            },
+            builtin::STATUS_CODE => Variant::StatusCode(self.u32()?),
+            20 => {
+                self.qualified_name()?;
+                Variant::Other(20)
+            },
+            builtin::LOCALIZED_TEXT => Variant::LocalizedText(self.localized_text()?),
+            22 => {
+                self.extension_object()?;
+                Variant::Other(22)
+            },
+            23 => {
+                self.data_value()?;
+                Variant::Other(23)
+            },
+            24 => {
+                self.variant()?;
+                Variant::Other(24)
+            },
+            25 => {
+                self.diagnostic_info()?;
+                Variant::Other(25)
+            },
+            other => return Err(error(format!("unknown built-in type {}", other))),
+        })
+    }
+
+    pub fn data_value(&mut self) -> Result<DataValue> {
+        self.nested(|d| {
+            let mask = d.u8()?;
+            let value = if mask & 0x01 != 0 {
+                Some(d.variant()?)
+            } else {
+                None
+            };
+            let status = if mask & 0x02 != 0 {
+                d.u32()?
+            } else {
+                status::GOOD
+            };
+            let source_timestamp = if mask & 0x04 != 0 {
+                Some(d.i64()?)
+            } else {
+                None
+            };
+            if mask & 0x08 != 0 {
+                d.i64()?;
+            }
+            if mask & 0x10 != 0 {
+                d.u16()?;
+            }
+            if mask & 0x20 != 0 {
+                d.u16()?;
+            }
+            Ok(DataValue {
+                value,
+                status,
+                source_timestamp,
+            })
+        })
+    }
+
+    pub fn response_header(&mut self) -> Result<ResponseHeader> {
+        self.i64()?; // timestamp
+        let request_handle = self.u32()?;
+        let service_result = self.u32()?;
+        self.diagnostic_info()?;
+        self.vec(|d| d.string())?; // stringTable
+        self.extension_object()?;
+        Ok(ResponseHeader {
+            request_handle,
+            service_result,
+        })
+    }
+}
+
+// ============================================================================
+// UA-TCP framing
+// ============================================================================
+
+/// Chunk of a UA-TCP message (header stripped)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    /// `HEL`, `ACK`, `ERR`, `OPN`, `MSG`, `CLO`
+    pub message_type: [u8; 3],
+    /// `F` final, `C` intermediate, `A` abort
+    pub chunk_type: u8,
+    pub body: Vec<u8>,
+}
+
+fn chunk(message_type: &[u8; 3], chunk_type: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(8 + body.len());
+    out.extend_from_slice(message_type);
+    out.push(chunk_type);
+    out.extend_from_slice(&((8 + body.len()) as u32).to_le_bytes());
+    out.extend_from_slice(body);
+    out
+}
+
+/// Splits the received byte stream into chunks
+#[derive(Debug, Default)]
+pub struct ChunkReader {
+    buf: Vec<u8>,
+}
+
+impl ChunkReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    pub fn clear(&mut self) {
+        self.buf.clear();
+    }
+
+    pub fn next_chunk(&mut self) -> Option<Result<Chunk>> {
+        if self.buf.len() < 8 {
+            return None;
+        }
+        let size =
+            u32::from_le_bytes([self.buf[4], self.buf[5], self.buf[6], self.buf[7]]) as usize;
+        if !(8..=MAX_CHUNK_SIZE).contains(&size) {
+            self.buf.clear();
+            return Some(Err(error(format!("invalid chunk size {}", size))));
+        }
+        if self.buf.len() < size {
+            return None;
+        }
+        let rest = self.buf.split_off(size);
+        let raw = std::mem::replace(&mut self.buf, rest);
+        Some(Ok(Chunk {
+            message_type: [raw[0], raw[1], raw[2]],
+            chunk_type: raw[3],
+            body: raw[8..].to_vec(),
+        }))
+    }
+}
+
+/// Hello message opening a connection
+pub fn hello(endpoint_url: &str) -> Vec<u8> {
+    let mut body = Encoder::new();
+    body.u32(0) // protocol version
+        .u32(RECEIVE_BUFFER_SIZE)
+        .u32(RECEIVE_BUFFER_SIZE) // send buffer
+        .u32(MAX_MESSAGE_SIZE as u32)
+        .u32(0) // max chunk count
+        .string(Some(endpoint_url));
+    chunk(b"HEL", b'F', &body.into_bytes())
+}
+
+/// Receive buffer size of the server from its Acknowledge
+pub fn acknowledge_receive_buffer(body: &[u8]) -> Result<u32> {
+    let mut d = Decoder::new(body);
+    d.u32()?; // protocol version
+    d.u32()
+}
+
+/// Status and reason of an Error message
+pub fn error_message(body: &[u8]) -> (u32, String) {
+    let mut d = Decoder::new(body);
+    let code = d.u32().unwrap_or_default();
+    let reason = d.string().ok().flatten().unwrap_or_default();
+    (code, reason)
+}
+
+/// Sequence state of a secure channel (SecurityPolicy None)
+#[derive(Debug)]
+pub struct SecureChannel {
+    pub channel_id: u32,
+    pub token_id: u32,
+    sequence: u32,
+    request_id: u32,
+    /// Largest chunk the server accepts
+    pub max_chunk: usize,
+}
+
+impl Default for SecureChannel {
+    fn default() -> Self {
+        Self {
+            channel_id: 0,
+            token_id: 0,
+            sequence: 0,
+            request_id: 0,
+            max_chunk: 8192,
+        }
+    }
+}
+
+impl SecureChannel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn next_sequence(&mut self) -> u32 {
+        // Wraps below 4294966271 as required by the spec
+        self.sequence = if self.sequence >= u32::MAX - 1024 {
+            1
+        } else {
+            self.sequence + 1
+        };
+        self.sequence
+    }
+
+    pub fn next_request_id(&mut self) -> u32 {
+        self.request_id = self.request_id.wrapping_add(1).max(1);
+        self.request_id
+    }
+
+    /// OPN chunk carrying an OpenSecureChannel request
+    pub fn open(&mut self, request_id: u32, body: &[u8]) -> Vec<u8> {
+        let mut e = Encoder::new();
+        e.u32(self.channel_id)
+            .string(Some(SECURITY_POLICY_NONE))
+            .byte_string(None) // sender certificate
+            .byte_string(None) // receiver certificate thumbprint
+            .u32(self.next_sequence())
+            .u32(request_id)
+            .bytes(body);
+        chunk(b"OPN", b'F', &e.into_bytes())
+    }
+
+    /// MSG chunks of a service request, split to the server's buffer size
+    pub fn message(&mut self, request_id: u32, body: &[u8]) -> Vec<u8> {
+        self.symmetric(b"MSG", request_id, body)
+    }
+
+    /// CLO chunk closing the channel
+    pub fn close(&mut self, request_id: u32, body: &[u8]) -> Vec<u8> {
+        self.symmetric(b"CLO", request_id, body)
+    }
+
+    fn symmetric(&mut self, message_type: &[u8; 3], request_id: u32, body: &[u8]) -> Vec<u8> {
+        let per_chunk = self
+            .max_chunk
+            .saturating_sub(SYMMETRIC_HEADER_LEN)
+            .max(1024);
+        let parts: Vec<&[u8]> = if body.is_empty() {
+            vec![body]
+        } else {
+            body.chunks(per_chunk).collect()
+        };
+        let mut out = Vec::with_capacity(body.len() + parts.len() * SYMMETRIC_HEADER_LEN);
+        for (i, part) in parts.iter().enumerate() {
+            let chunk_type = if i + 1 == parts.len() { b'F' } else { b'C' };
+            let mut e = Encoder::new();
+            e.u32(self.channel_id)
+                .u32(self.token_id)
+                .u32(self.next_sequence())
+                .u32(request_id)
+                .bytes(part);
+            out.extend(chunk(message_type, chunk_type, &e.into_bytes()));
+        }
+        out
+    }
+}
+
+/// Complete message or connection error from the server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Incoming {
+    Acknowledge { receive_buffer_size: u32 },
+    Message { request_id: u32, body: Vec<u8> },
+    Error { status: u32, reason: String },
+}
+
+/// Reassembles service messages from chunks
+#[derive(Debug, Default)]
+pub struct Assembler {
+    partial: HashMap<u32, Vec<u8>>,
+}
+
+impl Assembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.partial.clear();
+    }
+
+    /// Add a chunk; returns the message it completes
+    pub fn push(&mut self, chunk: Chunk) -> Result<Option<Incoming>> {
+        let mut d = Decoder::new(&chunk.body);
+        match &chunk.message_type {
+            b"ACK" => {
+                return Ok(Some(Incoming::Acknowledge {
+                    receive_buffer_size: acknowledge_receive_buffer(&chunk.body)?,
+                }))
+            },
+            b"ERR" => {
+                let (status, reason) = error_message(&chunk.body);
+                return Ok(Some(Incoming::Error { status, reason }));
+            },
+            b"OPN" => {
+                d.u32()?; // channel ID
+                d.string()?; // security policy
+                d.byte_string()?;
+                d.byte_string()?;
+            },
+            b"MSG" | b"CLO" => {
+                d.u32()?; // channel ID
+                d.u32()?; // token ID
+            },
+            other => {
+                return Err(error(format!(
+                    "unexpected message type {}",
+                    String::from_utf8_lossy(other)
+                )))
+            },
+        }
+        d.u32()?; // sequence number
+        let request_id = d.u32()?;
+        let body = d.remaining();
+
+        match chunk.chunk_type {
+            b'C' => {
+                let partial = self.partial.entry(request_id).or_default();
+                partial.extend_from_slice(body);
+                if partial.len() > MAX_MESSAGE_SIZE {
+                    self.partial.remove(&request_id);
+                    return Err(error("message too large"));
+                }
+                Ok(None)
+            },
+            b'F' => {
+                let mut message = self.partial.remove(&request_id).unwrap_or_default();
+                message.extend_from_slice(body);
+                Ok(Some(Incoming::Message {
+                    request_id,
+                    body: message,
+                }))
+            },
+            b'A' => {
+                self.partial.remove(&request_id);
+                let (status, reason) = error_message(body);
+                Ok(Some(Incoming::Error { status, reason }))
+            },
+            other => Err(error(format!("unknown chunk type {}", other as char))),
+        }
+    }
+}
+
+// ============================================================================
+// Requests
+// ============================================================================
+
+/// Common request fields
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestHeader {
+    pub auth_token: NodeId,
+    pub handle: u32,
+    pub timeout_hint_ms: u32,
+}
+
+/// User identity of a session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Identity {
+    Anonymous {
+        policy_id: String,
+    },
+    /// Plain password (only sent over policies without encryption)
+    UserName {
+        policy_id: String,
+        user: String,
+        password: String,
+    },
+}
+
+fn request(type_id: u32, header: &RequestHeader) -> Encoder {
+    let mut e = Encoder::new();
+    e.type_id(type_id).request_header(header);
+    e
+}
+
+/// OpenSecureChannel (`renew` keeps the channel ID)
+pub fn open_secure_channel(header: &RequestHeader, renew: bool, lifetime_ms: u32) -> Vec<u8> {
+    let mut e = request(ids::OPEN_SECURE_CHANNEL_REQUEST, header);
+    e.u32(0) // client protocol version
+        .u32(renew as u32)
+        .u32(SECURITY_MODE_NONE)
+        .byte_string(Some(&[]))
+        .u32(lifetime_ms);
+    e.into_bytes()
+}
+
+pub fn close_secure_channel(header: &RequestHeader) -> Vec<u8> {
+    request(ids::CLOSE_SECURE_CHANNEL_REQUEST, header).into_bytes()
+}
+
+pub fn create_session(
+    header: &RequestHeader,
+    endpoint_url: &str,
+    application_name: &str,
+    session_timeout_ms: f64,
+    nonce: &[u8],
+) -> Vec<u8> {
+    let mut e = request(ids::CREATE_SESSION_REQUEST, header);
+    // ApplicationDescription
+    e.string(Some(&format!("urn:voltage-ems:{}", application_name)))
+        .string(Some("urn:voltage-ems:comsrv"))
+        .u8(0x02)
+        .string(Some(application_name))
+        .u32(1) // Client
+        .string(None)
+        .string(None)
+        .array_len(0);
+    e.string(None) // server URI
+        .string(Some(endpoint_url))
+        .string(Some(application_name))
+        .byte_string(Some(nonce))
+        .byte_string(None) // client certificate
+        .f64(session_timeout_ms)
+        .u32(0); // max response size
+    e.into_bytes()
+}
+
+pub fn activate_session(header: &RequestHeader, identity: &Identity) -> Vec<u8> {
+    let mut token = Encoder::new();
+    let type_id = match identity {
+        Identity::Anonymous { policy_id } => {
+            token.string(Some(policy_id));
+            ids::ANONYMOUS_IDENTITY_TOKEN
+        },
+        Identity::UserName {
+            policy_id,
+            user,
+            password,
+        } => {
+            token
+                .string(Some(policy_id))
+                .string(Some(user))
+                .byte_string(Some(password.as_bytes()))
+                .string(None); // encryption algorithm
+            ids::USER_NAME_IDENTITY_TOKEN
+        },
+    };
+    let mut e = request(ids::ACTIVATE_SESSION_REQUEST, header);
+    e.string(None) // client signature
+        .byte_string(None)
+        .array_len(0) // software certificates
+        .array_len(0) // locale IDs
+        .extension_object(type_id, &token.into_bytes())
+        .string(None) // user token signature
+        .byte_string(None);
+    e.into_bytes()
+}
+
+pub fn close_session(header: &RequestHeader) -> Vec<u8> {
+    let mut e = request(ids::CLOSE_SESSION_REQUEST, header);
+    e.bool(true); // delete subscriptions
+    e.into_bytes()
+}
+
+/// Forward hierarchical references of nodes, all result fields
+pub fn browse(header: &RequestHeader, nodes: &[NodeId]) -> Vec<u8> {
+    let mut e = request(ids::BROWSE_REQUEST, header);
+    e.node_id(&NodeId::null()).i64(0).u32(0); // view
+    e.u32(0).array_len(nodes.len()); // max references per node
+    for node in nodes {
+        e.node_id(node)
+            .u32(0) // forward
+            .node_id(&NodeId::numeric(0, ids::HIERARCHICAL_REFERENCES))
+            .bool(true)
+            .u32(0) // all node classes
+            .u32(0x3F);
+    }
+    e.into_bytes()
+}
+
+pub fn browse_next(header: &RequestHeader, continuation_points: &[Vec<u8>]) -> Vec<u8> {
+    let mut e = request(ids::BROWSE_NEXT_REQUEST, header);
+    e.bool(false).array_len(continuation_points.len());
+    for point in continuation_points {
+        e.byte_string(Some(point));
+    }
+    e.into_bytes()
+}
+
+pub fn read(header: &RequestHeader, nodes: &[(NodeId, u32)]) -> Vec<u8> {
+    let mut e = request(ids::READ_REQUEST, header);
+    e.f64(0.0).u32(3).array_len(nodes.len()); // max age, timestamps: neither
+    for (node, attribute) in nodes {
+        e.node_id(node)
+            .u32(*attribute)
+            .string(None)
+            .u16(0)
+            .string(None);
+    }
+    e.into_bytes()
+}
+
+pub fn write(header: &RequestHeader, values: &[(NodeId, Variant)]) -> Vec<u8> {
+    let mut e = request(ids::WRITE_REQUEST, header);
+    e.array_len(values.len());
+    for (node, value) in values {
+        e.node_id(node)
+            .u32(attribute::VALUE)
+            .string(None)
+            .value(value);
+    }
+    e.into_bytes()
+}
+
+pub fn create_subscription(
+    header: &RequestHeader,
+    publishing_interval_ms: f64,
+    keep_alive_count: u32,
+) -> Vec<u8> {
+    let mut e = request(ids::CREATE_SUBSCRIPTION_REQUEST, header);
+    e.f64(publishing_interval_ms)
+        .u32(keep_alive_count * 3) // lifetime count
+        .u32(keep_alive_count)
+        .u32(0) // no limit of notifications per publish
+        .bool(true)
+        .u8(0);
+    e.into_bytes()
+}
+
+/// Reporting Value monitored items; `items` are (node, client handle)
+pub fn create_monitored_items(
+    header: &RequestHeader,
+    subscription_id: u32,
+    items: &[(NodeId, u32)],
+    sampling_interval_ms: f64,
+) -> Vec<u8> {
+    let mut e = request(ids::CREATE_MONITORED_ITEMS_REQUEST, header);
+    e.u32(subscription_id).u32(0).array_len(items.len()); // timestamps: source
+    for (node, client_handle) in items {
+        e.node_id(node)
+            .u32(attribute::VALUE)
+            .string(None)
+            .u16(0)
+            .string(None)
+            .u32(2) // Reporting
+            .u32(*client_handle)
+            .f64(sampling_interval_ms)
+            .null_extension_object()
+            .u32(1) // queue size
+            .bool(true);
+    }
+    e.into_bytes()
+}
+
+/// Publish acknowledging (subscription, sequence number) pairs
+pub fn publish(header: &RequestHeader, acks: &[(u32, u32)]) -> Vec<u8> {
+    let mut e = request(ids::PUBLISH_REQUEST, header);
+    e.array_len(acks.len());
+    for (subscription_id, sequence_number) in acks {
+        e.u32(*subscription_id).u32(*sequence_number);
+    }
+    e.into_bytes()
+}
+
+// ============================================================================
+// Responses
+// ============================================================================
+
+/// Common response fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseHeader {
+    pub request_handle: u32,
+    pub service_result: u32,
+}
+
+/// User token policy offered by an endpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserTokenPolicy {
+    pub policy_id: String,
+    /// 0 anonymous, 1 user name, 2 certificate, 3 issued token
+    pub token_type: u32,
+    /// Policy securing the token (`None`: the channel's policy)
+    pub security_policy_uri: Option<String>,
+}
+
+/// Endpoint returned by CreateSession
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+    pub security_mode: u32,
+    pub security_policy_uri: String,
+    pub user_tokens: Vec<UserTokenPolicy>,
+}
+
+/// Reference returned by Browse
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub node_id: NodeId,
+    pub browse_name: String,
+    pub display_name: String,
+    /// 1 object, 2 variable, 4 method, ...
+    pub node_class: u32,
+}
+
+/// Result of browsing one node
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrowseResult {
+    pub status: u32,
+    pub continuation_point: Option<Vec<u8>>,
+    pub references: Vec<Reference>,
+}
+
+/// Notification of a publish response
+#[derive(Debug, Clone, PartialEq)]
+pub enum Notification {
+    /// (client handle, value)
+    DataChange(Vec<(u32, DataValue)>),
+    StatusChange(u32),
+    Other,
+}
+
+/// Decoded service response
+#[derive(Debug, Clone, PartialEq)]
+pub enum Response {
+    OpenSecureChannel {
+        header: ResponseHeader,
+        channel_id: u32,
+        token_id: u32,
+        lifetime_ms: u32,
+    },
+    CreateSession {
+        header: ResponseHeader,
+        auth_token: NodeId,
+        endpoints: Vec<Endpoint>,
+    },
+    ActivateSession {
+        header: ResponseHeader,
+    },
+    Browse {
+        header: ResponseHeader,
+        results: Vec<BrowseResult>,
+    },
+    Read {
+        header: ResponseHeader,
+        results: Vec<DataValue>,
+    },
+    Write {
+        header: ResponseHeader,
+        results: Vec<u32>,
+    },
+    CreateSubscription {
+        header: ResponseHeader,
+        subscription_id: u32,
+    },
+    /// (status, monitored item ID) per item
+    CreateMonitoredItems {
+        header: ResponseHeader,
+        results: Vec<(u32, u32)>,
+    },
+    Publish {
+        header: ResponseHeader,
+        subscription_id: u32,
+        sequence_number: u32,
+        notifications: Vec<Notification>,
+    },
+    ServiceFault {
+        header: ResponseHeader,
+    },
+    /// Response the client does not decode (e.g. CloseSession)
+    Other {
+        header: ResponseHeader,
+        type_id: NodeId,
+    },
+}
+
+impl Response {
+    pub fn header(&self) -> ResponseHeader {
+        match self {
+            Self::OpenSecureChannel { header, .. }
+            | Self::CreateSession { header, .. }
+            | Self::ActivateSession { header }
+            | Self::Browse { header, .. }
+            | Self::Read { header, .. }
+            | Self::Write { header, .. }
+            | Self::CreateSubscription { header, .. }
+            | Self::CreateMonitoredItems { header, .. }
+            | Self::Publish { header, .. }
+            | Self::ServiceFault { header }
+            | Self::Other { header, .. } => *header,
+        }
+    }
+}
+
+/// Skip an ApplicationDescription
+pub fn application_description(d: &mut Decoder) -> Result<()> {
+    d.string()?; // application URI
+    d.string()?; // product URI
+    d.localized_text()?;
+    d.u32()?; // application type
+    d.string()?; // gateway server URI
+    d.string()?; // discovery profile URI
+    d.vec(|d| d.string())?;
+    Ok(())
+}
+
+fn endpoint(d: &mut Decoder) -> Result<Endpoint> {
+    d.string()?; // endpoint URL
+    application_description(d)?;
+    d.byte_string()?; // server certificate
+    let security_mode = d.u32()?;
+    let security_policy_uri = d.string()?.unwrap_or_default();
+    let user_tokens = d.vec(|d| {
+        let policy_id = d.string()?.unwrap_or_default();
+        let token_type = d.u32()?;
+        d.string()?; // issued token type
+        d.string()?; // issuer endpoint URL
+        let security_policy_uri = d.string()?.filter(|s| !s.is_empty());
+        Ok(UserTokenPolicy {
+            policy_id,
+            token_type,
+            security_policy_uri,
+        })
+    })?;
+    d.string()?; // transport profile URI
+    d.u8()?; // security level
+    Ok(Endpoint {
+        security_mode,
+        security_policy_uri,
+        user_tokens,
+    })
+}
+
+fn browse_result(d: &mut Decoder) -> Result<BrowseResult> {
+    let status = d.u32()?;
+    let continuation_point = d
+        .byte_string()?
+        .filter(|c| !c.is_empty())
+        .map(<[u8]>::to_vec);
+    let references = d.vec(|d| {
+        d.node_id()?; // reference type
+        d.bool()?; // is forward
+        let node_id = d.expanded_node_id()?;
+        let (_, browse_name) = d.qualified_name()?;
+        let display_name = d.localized_text()?;
+        let node_class = d.u32()?;
+        d.expanded_node_id()?; // type definition
+        Ok(Reference {
+            node_id,
+            browse_name,
+            display_name,
+            node_class,
+        })
+    })?;
+    Ok(BrowseResult {
+        status,
+        continuation_point,
+        references,
+    })
+}
+
+fn notification(type_id: &NodeId, body: Option<&[u8]>) -> Result<Notification> {
+    let Some(body) = body else {
+        return Ok(Notification::Other);
+    };
+    let mut d = Decoder::new(body);
+    match type_id.identifier {
+        Identifier::Numeric(ids::DATA_CHANGE_NOTIFICATION) if type_id.namespace == 0 => {
+            let items = d.vec(|d| Ok((d.u32()?, d.data_value()?)))?;
+            Ok(Notification::DataChange(items))
+        },
+        Identifier::Numeric(ids::STATUS_CHANGE_NOTIFICATION) if type_id.namespace == 0 => {
+            Ok(Notification::StatusChange(d.u32()?))
+        },
+        _ => Ok(Notification::Other),
+    }
+}
+
+/// Decode a service response message body
+pub fn parse_response(body: &[u8]) -> Result<Response> {
+    let mut d = Decoder::new(body);
+    let type_id = d.node_id()?;
+    let header = d.response_header()?;
+    let numeric = match type_id.identifier {
+        Identifier::Numeric(id) if type_id.namespace == 0 => id,
+        _ => return Ok(Response::Other { header, type_id }),
+    };
+    // Failed services carry no body
+    if numeric == ids::SERVICE_FAULT || header.service_result & 0x8000_0000 != 0 {
+        return Ok(Response::ServiceFault { header });
+    }
+    Ok(match numeric {
+        ids::OPEN_SECURE_CHANNEL_RESPONSE => {
+            d.u32()?; // server protocol version
+            let channel_id = d.u32()?;
+            let token_id = d.u32()?;
+            d.i64()?; // created at
+            let lifetime_ms = d.u32()?;
+            Response::OpenSecureChannel {
+                header,
+                channel_id,
+                token_id,
+                lifetime_ms,
+            }
+        },
+        ids::CREATE_SESSION_RESPONSE => {
+            d.node_id()?; // session ID
+            let auth_token = d.node_id()?;
+            d.f64()?; // revised timeout
+            d.byte_string()?; // server nonce
+            d.byte_string()?; // server certificate
+            let endpoints = d.vec(endpoint)?;
+            Response::CreateSession {
+                header,
+                auth_token,
+                endpoints,
+            }
+        },
+        ids::ACTIVATE_SESSION_RESPONSE => Response::ActivateSession { header },
+        ids::BROWSE_RESPONSE | ids::BROWSE_NEXT_RESPONSE => Response::Browse {
+            header,
+            results: d.vec(browse_result)?,
+        },
+        ids::READ_RESPONSE => Response::Read {
+            header,
+            results: d.vec(|d| d.data_value())?,
+        },
+        ids::WRITE_RESPONSE => Response::Write {
+            header,
+            results: d.vec(|d| d.u32())?,
+        },
+        ids::CREATE_SUBSCRIPTION_RESPONSE => Response::CreateSubscription {
+            header,
+            subscription_id: d.u32()?,
+        },
+        ids::CREATE_MONITORED_ITEMS_RESPONSE => Response::CreateMonitoredItems {
+            header,
+            results: d.vec(|d| {
+                let status = d.u32()?;
+                let item_id = d.u32()?;
+                d.f64()?; // revised sampling interval
+                d.u32()?; // revised queue size
+                d.extension_object()?; // filter result
+                Ok((status, item_id))
+            })?,
+        },
+        ids::PUBLISH_RESPONSE => {
+            let subscription_id = d.u32()?;
+            d.vec(|d| d.u32())?; // available sequence numbers
+            d.bool()?; // more notifications
+            let sequence_number = d.u32()?;
+            d.i64()?; // publish time
+            let notifications = d.vec(|d| {
+                let (type_id, body) = d.extension_object()?;
+                notification(&type_id, body)
+            })?;
+            Response::Publish {
+                header,
+                subscription_id,
+                sequence_number,
+                notifications,
+            }
+        },
+        _ => Response::Other { header, type_id },
+    })
+}
+
+// ============================================================================
+// Server side
+// ============================================================================
+
+/// Receive buffer size and endpoint URL of a Hello message
+pub fn parse_hello(body: &[u8]) -> Result<(u32, String)> {
+    let mut d = Decoder::new(body);
+    d.u32()?; // protocol version
+    let receive_buffer_size = d.u32()?;
+    d.u32()?; // send buffer
+    d.u32()?; // max message size
+    d.u32()?; // max chunk count
+    Ok((receive_buffer_size, d.string()?.unwrap_or_default()))
+}
+
+/// Acknowledge answering a Hello
+pub fn acknowledge(send_buffer_size: u32) -> Vec<u8> {
+    let mut body = Encoder::new();
+    body.u32(0) // protocol version
+        .u32(RECEIVE_BUFFER_SIZE)
+        .u32(send_buffer_size)
+        .u32(MAX_MESSAGE_SIZE as u32)
+        .u32(0); // max chunk count
+    chunk(b"ACK", b'F', &body.into_bytes())
+}
+
+/// Error message sent before closing a connection
+pub fn error_chunk(code: u32, reason: &str) -> Vec<u8> {
+    let mut body = Encoder::new();
+    body.u32(code).string(Some(reason));
+    chunk(b"ERR", b'F', &body.into_bytes())
+}
+
+/// Type ID, auth token and request handle of a request body, with the
+/// decoder positioned at the service fields
+pub fn parse_request(body: &[u8]) -> Result<(u32, NodeId, u32, Decoder<'_>)> {
+    let mut d = Decoder::new(body);
+    let type_id = match d.node_id()?.identifier {
+        Identifier::Numeric(id) => id,
+        _ => return Err(error("non-numeric type ID")),
+    };
+    let auth_token = d.node_id()?;
+    d.i64()?;
+    let handle = d.u32()?;
+    d.u32()?;
+    d.string()?;
+    d.u32()?;
+    d.extension_object()?;
+    Ok((type_id, auth_token, handle, d))
+}
+
+/// Type ID and request handle of a request body (what a server reads first)
+#[cfg(test)]
+pub fn parse_request_header(body: &[u8]) -> Result<(u32, u32, Decoder<'_>)> {
+    let (type_id, _, handle, d) = parse_request(body)?;
+    Ok((type_id, handle, d))
+}
+
+fn response_header(type_id: u32, handle: u32, service_result: u32) -> Encoder {
+    let mut e = Encoder::new();
+    e.type_id(type_id)
+        .i64(date_time_now())
+        .u32(handle)
+        .u32(service_result)
+        .u8(0) // diagnostics
+        .array_len(0)
+        .null_extension_object();
+    e
+}
+
+/// Response body with a good header
+pub fn response(type_id: u32, handle: u32, fields: &[u8]) -> Vec<u8> {
+    let mut e = response_header(type_id, handle, status::GOOD);
+    e.bytes(fields);
+    e.into_bytes()
+}
+
+/// ServiceFault answering a failed request
+pub fn service_fault(handle: u32, code: u32) -> Vec<u8> {
+    response_header(ids::SERVICE_FAULT, handle, code).into_bytes()
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_id_text_and_binary_forms() {
+        for (text, wire) in [
+            ("i=85", vec![0x00, 85]),
+            ("ns=2;i=1001", vec![0x01, 2, 0xE9, 0x03]),
+            (
+                "ns=300;i=70000",
+                vec![0x02, 0x2C, 0x01, 0x70, 0x11, 0x01, 0x00],
+            ),
+            (
+                "ns=1;s=Pump",
+                vec![0x03, 1, 0, 4, 0, 0, 0, b'P', b'u', b'm', b'p'],
+            ),
+        ] {
+            let id: NodeId = text.parse().unwrap();
+            assert_eq!(id.to_string(), text);
+            let mut e = Encoder::new();
+            e.node_id(&id);
+            let bytes = e.into_bytes();
+            assert_eq!(bytes, wire, "{}", text);
+            assert_eq!(Decoder::new(&bytes).node_id().unwrap(), id);
+        }
+
+        let guid: NodeId = "ns=1;g=72962b91-fa75-4ae6-8d28-b404dc7daf63"
+            .parse()
+            .unwrap();
+        let mut e = Encoder::new();
+        e.node_id(&guid);
+        let bytes = e.into_bytes();
+        assert_eq!(&bytes[3..7], &[0x91, 0x2B, 0x96, 0x72]); // Data1 little-endian
+        assert_eq!(Decoder::new(&bytes).node_id().unwrap(), guid);
+        assert_eq!(
+            guid.to_string(),
+            "ns=1;g=72962b91-fa75-4ae6-8d28-b404dc7daf63"
+        );
+
+        let opaque: NodeId = "ns=1;b=AQID".parse().unwrap();
+        assert_eq!(opaque.identifier, Identifier::Opaque(vec![1, 2, 3]));
+        for bad in ["", "ns=1", "x=1", "ns=a;i=1", "i=x", "ns=1;s=", "g=123"] {
+            assert!(bad.parse::<NodeId>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_variants_and_data_values() {
+        let mut e = Encoder::new();
+        e.u8(0x07) // value, status, source timestamp
+            .variant(&Variant::Float(12.5))
+            .u32(0x4000_0000)
+            .i64(42);
+        let bytes = e.into_bytes();
+        let value = Decoder::new(&bytes).data_value().unwrap();
+        assert_eq!(value.value.as_ref().and_then(Variant::as_f64), Some(12.5));
+        assert!(is_uncertain(value.status));
+        assert_eq!(value.source_timestamp, Some(42));
+
+        // Arrays and unused types are skipped, keeping the stream aligned
+        let mut e = Encoder::new();
+        e.u8(0x80 | builtin::INT32).array_len(2).i32(1).i32(2);
+        e.u8(20).u16(1).string(Some("Name")); // QualifiedName
+        e.variant(&Variant::Boolean(true));
+        let bytes = e.into_bytes();
+        let mut d = Decoder::new(&bytes);
+        assert_eq!(
+            d.variant().unwrap(),
+            Variant::Array {
+                type_id: builtin::INT32,
+                len: 2
+            }
+        );
+        assert_eq!(d.variant().unwrap(), Variant::Other(20));
+        assert_eq!(d.variant().unwrap().as_f64(), Some(1.0));
+        assert!(d.remaining().is_empty());
+
+        // Hostile nesting is refused instead of overflowing the stack
+        let nested = [vec![24u8; 64], vec![0]].concat();
+        assert!(Decoder::new(&nested).variant().is_err());
+    }
+
+    #[test]
+    fn test_chunking_and_reassembly() {
+        let mut channel = SecureChannel::new();
+        channel.channel_id = 7;
+        channel.token_id = 1;
+        channel.max_chunk = 1024 + SYMMETRIC_HEADER_LEN;
+        let body: Vec<u8> = (0..2500u32).map(|i| i as u8).collect();
+        let bytes = channel.message(9, &body);
+
+        let mut reader = ChunkReader::new();
+        reader.push(&bytes);
+        let mut assembler = Assembler::new();
+        let mut chunks = 0;
+        let mut complete = None;
+        while let Some(chunk) = reader.next_chunk() {
+            let chunk = chunk.unwrap();
+            assert_eq!(&chunk.message_type, b"MSG");
+            chunks += 1;
+            if let Some(message) = assembler.push(chunk).unwrap() {
+                complete = Some(message);
+            }
+        }
+        assert_eq!(chunks, 3);
+        assert_eq!(
+            complete,
+            Some(Incoming::Message {
+                request_id: 9,
+                body
+            })
+        );
+    }
+
+    #[test]
+    fn test_publish_response_decoding() {
+        let mut dcn = Encoder::new();
+        dcn.array_len(2)
+            .u32(0)
+            .u8(0x01)
+            .variant(&Variant::Double(230.1))
+            .u32(1)
+            .u8(0x03)
+            .variant(&Variant::Boolean(true))
+            .u32(status::BAD_SENSOR_FAILURE)
+            .array_len(0);
+        let mut fields = Encoder::new();
+        fields
+            .u32(5) // subscription
+            .array_len(1)
+            .u32(3) // available
+            .bool(false)
+            .u32(3) // sequence number
+            .i64(0)
+            .array_len(1)
+            .extension_object(ids::DATA_CHANGE_NOTIFICATION, &dcn.into_bytes())
+            .array_len(0)
+            .array_len(0);
+        let body = response(ids::PUBLISH_RESPONSE, 12, &fields.into_bytes());
+        let Response::Publish {
+            header,
+            subscription_id,
+            sequence_number,
+            notifications,
+        } = parse_response(&body).unwrap()
+        else {
+            panic!("expected publish response");
+        };
+        assert_eq!(header.request_handle, 12);
+        assert_eq!((subscription_id, sequence_number), (5, 3));
+        let Notification::DataChange(items) = &notifications[0] else {
+            panic!("expected data change");
+        };
+        assert_eq!(items[0].1.value, Some(Variant::Double(230.1)));
+        assert_eq!(items[1].1.status, status::BAD_SENSOR_FAILURE);
+        assert_eq!(status_name(items[1].1.status), "BadSensorFailure");
+    }
+}
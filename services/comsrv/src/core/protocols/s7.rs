@@ -0,0 +1,1179 @@
+//! Siemens S7comm client for S7-300/400 and S7-1200/1500 CPUs
+//!
+//! Points address CPU memory with their mapping columns:
+//! - `area`: `DB` (default), `I`, `Q` or `M`
+//! - `db`: data block number of `DB` points
+//! - `offset`: byte offset of the value
+//! - `bit`: bit 0-7 of the byte at `offset`, for BOOL points
+//! - `data_type`: S7 type of the value; BOOL when only `bit` is given
+//!
+//! so `DB10.DBD4` as a REAL maps to `db=10, offset=4, data_type=REAL` and
+//! `DB10.DBX2.3` to `db=10, offset=2, bit=3`.
+//!
+//! Each poll reads the points as byte ranges: points of one data block or
+//! area no more than `max_gap` bytes apart share a range, and ranges are
+//! bundled into Read Var jobs within the PDU length negotiated with the
+//! CPU. Control and adjustment points are written with Write Var, BOOLs as
+//! single bits so the rest of the byte is untouched.
+//!
+//! S7-1200/1500 CPUs answer only with PUT/GET access enabled, and only for
+//! data blocks without optimized block access.
+//!
+//! ```yaml
+//! protocol: s7
+//! parameters:
+//!   host: 192.168.0.10
+//!   rack: 0
+//!   slot: 1            # S7-1200/1500: 1 (or 0), S7-300: 2
+//!   max_gap: 16
+//! ```
+
+pub mod codec;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use igw::core::traits::{DataEventReceiver, Diagnostics, PointFailure, PollResult};
+use igw::gateway::ChannelRuntime;
+use igw::{ConnectionState, DataBatch, DataPoint, GatewayError};
+use serde_json::{json, Value as JsonValue};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{timeout_at, Instant};
+use tracing::{debug, info, warn};
+
+use self::codec::{rosctr, Address, Area, DataType, Pdu};
+use crate::core::config::{Point, RuntimeChannelConfig};
+use crate::core::plugins::{MappingColumn, ParameterMetadata, ParameterType};
+use crate::core::protocols::record_failure;
+use crate::error::{ComSrvError, Result};
+use voltage_model::PointType;
+
+/// Protocol name stored in `channels.protocol`
+pub const PROTOCOL: &str = "s7";
+
+const DEFAULT_PORT: u16 = 102;
+const DEFAULT_RACK: u64 = 0;
+const DEFAULT_SLOT: u64 = 1;
+const DEFAULT_RESPONSE_TIMEOUT_MS: u64 = 3000;
+const DEFAULT_MAX_GAP: u64 = 16;
+/// TSAP of the client side (PG, rack 0, slot 0)
+const LOCAL_TSAP: u16 = 0x0100;
+/// Highest byte offset of a 24-bit bit address
+const MAX_OFFSET: u64 = 0x1F_FFFF;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Resource the connection takes on the CPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionType {
+    /// Programming device
+    Pg,
+    /// Operator panel
+    Op,
+    /// S7 basic communication
+    Basic,
+}
+
+impl ConnectionType {
+    fn parse(text: &str) -> Option<Self> {
+        match text.trim().to_ascii_lowercase().as_str() {
+            "pg" => Some(Self::Pg),
+            "op" => Some(Self::Op),
+            "basic" | "s7_basic" => Some(Self::Basic),
+            _ => None,
+        }
+    }
+
+    fn code(&self) -> u8 {
+        match self {
+            Self::Pg => 0x01,
+            Self::Op => 0x02,
+            Self::Basic => 0x03,
+        }
+    }
+}
+
+/// Channel parameters of an S7 channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct S7Config {
+    pub host: String,
+    pub port: u16,
+    pub rack: u8,
+    pub slot: u8,
+    pub connection_type: ConnectionType,
+    pub response_timeout: Duration,
+    /// Unmapped bytes bridged inside a read range
+    pub max_gap: u32,
+}
+
+impl S7Config {
+    pub fn from_parameters(parameters: &HashMap<String, JsonValue>) -> Result<Self> {
+        let config_error = |message: String| ComSrvError::ConfigError(message);
+        let text = |key: &str| {
+            parameters.get(key).and_then(|v| match v {
+                JsonValue::String(s) => Some(s.trim().to_string()),
+                JsonValue::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+        };
+        let number = |key: &str, default: u64| -> Result<u64> {
+            match parameters.get(key) {
+                None | Some(JsonValue::Null) => Ok(default),
+                Some(JsonValue::String(s)) if s.trim().is_empty() => Ok(default),
+                Some(JsonValue::Number(n)) => n
+                    .as_u64()
+                    .ok_or_else(|| config_error(format!("'{}' must be a number", key))),
+                Some(JsonValue::String(s)) => s
+                    .trim()
+                    .parse()
+                    .map_err(|_| config_error(format!("'{}' must be a number", key))),
+                Some(_) => Err(config_error(format!("'{}' must be a number", key))),
+            }
+        };
+
+        let host = text("host")
+            .filter(|h| !h.is_empty())
+            .ok_or_else(|| config_error("S7 requires 'host'".into()))?;
+        let port = number("port", u64::from(DEFAULT_PORT))?;
+        if !(1..=65535).contains(&port) {
+            return Err(config_error("'port' must be 1-65535".into()));
+        }
+        let rack = number("rack", DEFAULT_RACK)?;
+        if rack > 7 {
+            return Err(config_error("'rack' must be 0-7".into()));
+        }
+        let slot = number("slot", DEFAULT_SLOT)?;
+        if slot > 31 {
+            return Err(config_error("'slot' must be 0-31".into()));
+        }
+        let connection_type = match text("connection_type").filter(|t| !t.is_empty()) {
+            None => ConnectionType::Pg,
+            Some(t) => ConnectionType::parse(&t).ok_or_else(|| {
+                config_error(format!("unknown connection_type '{}' (pg, op, basic)", t))
+            })?,
+        };
+
+        Ok(Self {
+            host,
+            port: port as u16,
+            rack: rack as u8,
+            slot: slot as u8,
+            connection_type,
+            response_timeout: Duration::from_millis(
+                number("response_timeout_ms", DEFAULT_RESPONSE_TIMEOUT_MS)?.max(1),
+            ),
+            max_gap: number("max_gap", DEFAULT_MAX_GAP)?.min(u64::from(u16::MAX)) as u32,
+        })
+    }
+
+    /// TSAP of the CPU: connection type, then rack and slot
+    pub fn remote_tsap(&self) -> u16 {
+        u16::from_be_bytes([self.connection_type.code(), (self.rack << 5) | self.slot])
+    }
+}
+
+// ============================================================================
+// Point mapping
+// ============================================================================
+
+/// Value in CPU memory
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Variable {
+    address: Address,
+    /// Bit of a BOOL
+    bit: u8,
+    data_type: DataType,
+}
+
+impl Variable {
+    fn end(&self) -> u32 {
+        self.address.offset + self.data_type.size() as u32
+    }
+}
+
+impl std::fmt::Display for Variable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.data_type {
+            DataType::Bool => write!(f, "{}.{}", self.address, self.bit),
+            data_type => write!(f, "{} {}", self.address, data_type.name()),
+        }
+    }
+}
+
+fn parse_point(point: &Point) -> std::result::Result<Variable, String> {
+    let mapping: JsonValue = point
+        .protocol_mappings
+        .as_deref()
+        .and_then(|m| serde_json::from_str(m).ok())
+        .ok_or("missing S7 mapping")?;
+    let text = |key: &str| {
+        mapping
+            .get(key)
+            .and_then(|v| match v {
+                JsonValue::String(s) => Some(s.trim().to_string()),
+                JsonValue::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .filter(|v| !v.is_empty())
+    };
+    let number = |key: &str, max: u64| {
+        text(key)
+            .map(|v| {
+                v.parse::<u64>()
+                    .ok()
+                    .filter(|n| *n <= max)
+                    .ok_or(format!("'{}' must be 0-{}", key, max))
+            })
+            .transpose()
+    };
+
+    let area = text("area")
+        .map(|a| Area::parse(&a))
+        .transpose()
+        .map_err(|e| e.to_string())?
+        .unwrap_or(Area::Db);
+    let db = match (area, number("db", u64::from(u16::MAX))?) {
+        (Area::Db, Some(db)) if db > 0 => db as u16,
+        (Area::Db, _) => return Err("DB points need 'db' 1-65535".into()),
+        (_, _) => 0,
+    };
+    let offset = number("offset", MAX_OFFSET)?.ok_or("missing 'offset'")? as u32;
+    let bit = number("bit", 7)?;
+    let data_type = text("data_type")
+        .map(|t| DataType::parse(&t))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let data_type = match (bit, data_type) {
+        (Some(_), None | Some(DataType::Bool)) | (None, Some(DataType::Bool)) => DataType::Bool,
+        (Some(_), Some(other)) => {
+            return Err(format!("'bit' addresses BOOLs, not {}", other.name()))
+        },
+        (None, Some(data_type)) => data_type,
+        (None, None) => return Err("missing 'data_type'".into()),
+    };
+    Ok(Variable {
+        address: Address { area, db, offset },
+        bit: bit.unwrap_or(0) as u8,
+        data_type,
+    })
+}
+
+// ============================================================================
+// Read planning
+// ============================================================================
+
+/// Point read each poll
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Reading {
+    internal_id: u32,
+    variable: Variable,
+}
+
+/// Bytes read as one Read Var item, with the points inside
+#[derive(Debug, Clone, PartialEq)]
+struct Range {
+    start: Address,
+    len: u16,
+    readings: Vec<Reading>,
+}
+
+/// Merge readings into ranges of at most `max_len` bytes
+fn plan_ranges(readings: &[Reading], max_gap: u32, max_len: usize) -> Vec<Range> {
+    let mut sorted = readings.to_vec();
+    sorted.sort_by_key(|r| (r.variable.address, r.variable.bit));
+    let mut ranges: Vec<Range> = Vec::new();
+    for reading in sorted {
+        let variable = reading.variable;
+        match ranges.last_mut() {
+            Some(range)
+                if range.start.area == variable.address.area
+                    && range.start.db == variable.address.db
+                    && variable.address.offset
+                        <= range.start.offset + u32::from(range.len) + max_gap
+                    && (variable.end() - range.start.offset) as usize <= max_len =>
+            {
+                let end = variable
+                    .end()
+                    .max(range.start.offset + u32::from(range.len));
+                range.len = (end - range.start.offset) as u16;
+                range.readings.push(reading);
+            },
+            _ => ranges.push(Range {
+                start: variable.address,
+                len: variable.data_type.size() as u16,
+                readings: vec![reading],
+            }),
+        }
+    }
+    ranges
+}
+
+/// Ranges of each Read Var job within the PDU length
+fn plan_jobs(ranges: &[Range], pdu_len: usize) -> Vec<Vec<usize>> {
+    let mut jobs: Vec<Vec<usize>> = Vec::new();
+    for (i, range) in ranges.iter().enumerate() {
+        let fits = jobs.last().is_some_and(|job| {
+            job.len() < codec::MAX_READ_ITEMS
+                && codec::read_request_len(job.len() + 1) <= pdu_len
+                && codec::read_reply_len(
+                    job.iter()
+                        .map(|j| usize::from(ranges[*j].len))
+                        .chain([usize::from(range.len)]),
+                ) <= pdu_len
+        });
+        match jobs.last_mut() {
+            Some(job) if fits => job.push(i),
+            _ => jobs.push(vec![i]),
+        }
+    }
+    jobs
+}
+
+// ============================================================================
+// Runtime
+// ============================================================================
+
+/// S7comm client of one CPU
+pub struct S7Runtime {
+    id: u32,
+    name: String,
+    config: S7Config,
+    readings: Vec<Reading>,
+    controls: HashMap<u32, Variable>,
+    adjustments: HashMap<u32, Variable>,
+    stream: Option<TcpStream>,
+    /// PDU length granted by the CPU
+    pdu_len: u16,
+    reference: u16,
+    /// Read plan for the granted PDU length
+    ranges: Vec<Range>,
+    jobs: Vec<Vec<usize>>,
+    diagnostics: Diagnostics,
+}
+
+impl S7Runtime {
+    /// Build the runtime of an `s7` channel
+    pub fn from_runtime_config(runtime_config: &RuntimeChannelConfig) -> Result<Self> {
+        let channel_id = runtime_config.id();
+        let config = S7Config::from_parameters(&runtime_config.base.parameters)
+            .map_err(|e| ComSrvError::ConfigError(format!("Ch{}: {}", channel_id, e)))?;
+
+        let mut readings = Vec::new();
+        let mut controls = HashMap::new();
+        let mut adjustments = HashMap::new();
+        for (point_type, point) in runtime_config.points() {
+            let variable = match parse_point(point) {
+                Ok(variable) => variable,
+                Err(e) => {
+                    warn!(
+                        "Ch{} {}{} skipped: {}",
+                        channel_id,
+                        point_type.as_str(),
+                        point.point_id,
+                        e
+                    );
+                    continue;
+                },
+            };
+            match point_type {
+                PointType::Telemetry | PointType::Signal => readings.push(Reading {
+                    internal_id: point_type.to_internal_id(point.point_id),
+                    variable,
+                }),
+                PointType::Control => {
+                    controls.insert(point.point_id, variable);
+                },
+                PointType::Adjustment => {
+                    adjustments.insert(point.point_id, variable);
+                },
+            }
+        }
+        debug!(
+            "Ch{} S7: {} readings, {} controls, {} adjustments",
+            channel_id,
+            readings.len(),
+            controls.len(),
+            adjustments.len()
+        );
+
+        Ok(Self {
+            id: channel_id,
+            name: runtime_config.name().to_string(),
+            config,
+            readings,
+            controls,
+            adjustments,
+            stream: None,
+            pdu_len: codec::REQUESTED_PDU,
+            reference: 0,
+            ranges: Vec::new(),
+            jobs: Vec::new(),
+            diagnostics: Diagnostics::new(PROTOCOL),
+        })
+    }
+
+    /// Record an error; connection failures drop the connection
+    fn fail(&mut self, error: GatewayError) -> GatewayError {
+        if record_failure(&mut self.diagnostics, &error) {
+            self.stream = None;
+        }
+        error
+    }
+
+    fn peer(&self) -> String {
+        format!("{}:{}", self.config.host, self.config.port)
+    }
+
+    fn timeout_error(&self) -> GatewayError {
+        GatewayError::ConnectionTimeout(self.config.response_timeout.as_millis() as u64)
+    }
+
+    async fn send(&mut self, tpdu: &[u8]) -> igw::Result<()> {
+        let stream = self.stream.as_mut().ok_or(GatewayError::NotConnected)?;
+        stream
+            .write_all(&codec::tpkt(tpdu))
+            .await
+            .map_err(|e| GatewayError::Connection(format!("send: {}", e)))
+    }
+
+    async fn receive_tpdu(&mut self, deadline: Instant) -> igw::Result<Vec<u8>> {
+        let timeout_ms = self.config.response_timeout.as_millis() as u64;
+        let stream = self.stream.as_mut().ok_or(GatewayError::NotConnected)?;
+        let mut header = [0u8; codec::TPKT_HEADER_LEN];
+        timeout_at(deadline, stream.read_exact(&mut header))
+            .await
+            .map_err(|_| GatewayError::ConnectionTimeout(timeout_ms))?
+            .map_err(|e| GatewayError::Connection(format!("receive: {}", e)))?;
+        let len = codec::tpkt_payload_len(&header)
+            .map_err(|e| GatewayError::Connection(e.to_string()))?;
+        let mut tpdu = vec![0u8; len];
+        timeout_at(deadline, stream.read_exact(&mut tpdu))
+            .await
+            .map_err(|_| GatewayError::ConnectionTimeout(timeout_ms))?
+            .map_err(|e| GatewayError::Connection(format!("receive: {}", e)))?;
+        Ok(tpdu)
+    }
+
+    /// Send a job and return the ack-data PDU answering it
+    async fn exchange(&mut self, mut job: Pdu) -> igw::Result<Pdu> {
+        self.reference = self.reference.wrapping_add(1).max(1);
+        job.reference = self.reference;
+        let deadline = Instant::now() + self.config.response_timeout;
+        self.send(&codec::data_tpdu(&job.encode())).await?;
+
+        // A PDU may span several data TPDUs
+        let mut pdu = Vec::new();
+        loop {
+            let tpdu = self.receive_tpdu(deadline).await?;
+            let (payload, last) =
+                codec::data_payload(&tpdu).map_err(|e| GatewayError::Connection(e.to_string()))?;
+            pdu.extend_from_slice(payload);
+            if last {
+                break;
+            }
+        }
+        let reply = Pdu::decode(&pdu).map_err(|e| GatewayError::Connection(e.to_string()))?;
+
+        // Replies arrive in order; another reference means the stream is
+        // out of step and is dropped
+        if reply.reference != job.reference {
+            return Err(GatewayError::Connection(format!(
+                "reply to PDU {} while waiting for {}",
+                reply.reference, job.reference
+            )));
+        }
+        if reply.error != (0, 0) {
+            return Err(GatewayError::Protocol(codec::header_error_text(
+                reply.error.0,
+                reply.error.1,
+            )));
+        }
+        if reply.rosctr != rosctr::ACK_DATA || reply.function() != job.function() {
+            return Err(GatewayError::Protocol(format!(
+                "unexpected reply (PDU type {}, function {:?}) to function 0x{:02X}",
+                reply.rosctr,
+                reply.function(),
+                job.function().unwrap_or_default()
+            )));
+        }
+        Ok(reply)
+    }
+
+    /// Open the ISO transport connection and negotiate the PDU length
+    async fn establish(&mut self) -> igw::Result<()> {
+        let deadline = Instant::now() + self.config.response_timeout;
+        self.send(&codec::connection_request(
+            LOCAL_TSAP,
+            self.config.remote_tsap(),
+        ))
+        .await?;
+        let confirm = self.receive_tpdu(deadline).await?;
+        codec::check_connection_confirm(&confirm)
+            .map_err(|e| GatewayError::Connection(e.to_string()))?;
+
+        let reply = self
+            .exchange(Pdu::setup_communication(codec::REQUESTED_PDU))
+            .await?;
+        let granted = reply
+            .negotiated_pdu()
+            .map_err(|e| GatewayError::Protocol(e.to_string()))?;
+        // Below this not even one 8-byte value fits a Read Var reply
+        if granted < 32 {
+            return Err(GatewayError::Protocol(format!(
+                "CPU grants a PDU of {} octets",
+                granted
+            )));
+        }
+        self.pdu_len = granted.min(codec::REQUESTED_PDU);
+        Ok(())
+    }
+
+    fn plan(&mut self) {
+        let pdu_len = usize::from(self.pdu_len);
+        let max_len = pdu_len - codec::ACK_HEADER_LEN - 2 - codec::DATA_ITEM_HEADER_LEN;
+        self.ranges = plan_ranges(&self.readings, self.config.max_gap, max_len);
+        self.jobs = plan_jobs(&self.ranges, pdu_len);
+        debug!(
+            "Ch{} S7: {} readings in {} ranges, {} jobs (PDU {})",
+            self.id,
+            self.readings.len(),
+            self.ranges.len(),
+            self.jobs.len(),
+            self.pdu_len
+        );
+    }
+
+    async fn read_all(&mut self, batch: &mut DataBatch, failures: &mut Vec<PointFailure>) {
+        for job in self.jobs.clone() {
+            let items: Vec<(Address, u16)> = job
+                .iter()
+                .map(|i| (self.ranges[*i].start, self.ranges[*i].len))
+                .collect();
+            let results = match self
+                .exchange(Pdu::read_var(&items))
+                .await
+                .and_then(|reply| {
+                    let results = reply
+                        .read_results()
+                        .map_err(|e| GatewayError::Protocol(e.to_string()))?;
+                    match results.len() == items.len() {
+                        true => Ok(results),
+                        false => Err(GatewayError::Protocol(format!(
+                            "{} items in reply to {}",
+                            results.len(),
+                            items.len()
+                        ))),
+                    }
+                }) {
+                Ok(results) => results,
+                Err(e) => {
+                    let e = self.fail(e);
+                    for i in &job {
+                        failures.extend(
+                            self.ranges[*i]
+                                .readings
+                                .iter()
+                                .map(|r| PointFailure::with_error(r.internal_id, e.to_string())),
+                        );
+                    }
+                    if self.stream.is_none() {
+                        break;
+                    }
+                    continue;
+                },
+            };
+
+            for (i, result) in job.iter().zip(results) {
+                let range = &self.ranges[*i];
+                for reading in &range.readings {
+                    let variable = reading.variable;
+                    let value = match &result {
+                        Ok(data) => {
+                            let start = (variable.address.offset - range.start.offset) as usize;
+                            variable
+                                .data_type
+                                .decode(data.get(start..).unwrap_or_default(), variable.bit)
+                                .map_err(|e| e.to_string())
+                        },
+                        Err(code) => Err(codec::item_error_text(*code)),
+                    };
+                    match value {
+                        Ok(value) => batch.add(DataPoint::new(reading.internal_id, value)),
+                        Err(e) => failures.push(PointFailure::with_error(
+                            reading.internal_id,
+                            format!("{}: {}", variable, e),
+                        )),
+                    }
+                }
+            }
+        }
+    }
+
+    async fn write_variable(&mut self, variable: &Variable, value: f64) -> igw::Result<()> {
+        let job = match variable.data_type {
+            DataType::Bool => Pdu::write_bit(&variable.address, variable.bit, value != 0.0),
+            data_type => {
+                let data = data_type
+                    .encode(value)
+                    .map_err(|e| GatewayError::Protocol(format!("{}: {}", variable, e)))?;
+                Pdu::write_bytes(&variable.address, &data)
+            },
+        };
+        let reply = self.exchange(job).await?;
+        let codes = reply
+            .write_results()
+            .map_err(|e| GatewayError::Protocol(e.to_string()))?;
+        match codes.first() {
+            Some(&codec::ITEM_SUCCESS) => Ok(()),
+            Some(code) => Err(GatewayError::Protocol(format!(
+                "write {}: {}",
+                variable,
+                codec::item_error_text(*code)
+            ))),
+            None => Err(GatewayError::Protocol(format!(
+                "write {}: empty reply",
+                variable
+            ))),
+        }
+    }
+
+    async fn write(&mut self, point_type: PointType, values: &[(u32, f64)]) -> igw::Result<usize> {
+        let mut written = 0;
+        let mut last_error = None;
+        for &(internal_id, value) in values {
+            let point_id = PointType::from_internal_id(internal_id).1;
+            let variables = match point_type {
+                PointType::Control => &self.controls,
+                _ => &self.adjustments,
+            };
+            let Some(variable) = variables.get(&point_id).copied() else {
+                last_error = Some(GatewayError::PointNotFound(format!(
+                    "{}{}",
+                    point_type.as_str(),
+                    point_id
+                )));
+                continue;
+            };
+            match self.write_variable(&variable, value).await {
+                Ok(()) => written += 1,
+                Err(e) => {
+                    let e = self.fail(e);
+                    if self.stream.is_none() {
+                        return Err(e);
+                    }
+                    last_error = Some(e);
+                },
+            }
+        }
+        self.diagnostics.write_count += written as u64;
+        match last_error {
+            Some(e) if written == 0 => Err(e),
+            _ => Ok(written),
+        }
+    }
+}
+
+#[async_trait]
+impl ChannelRuntime for S7Runtime {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn protocol(&self) -> &str {
+        PROTOCOL
+    }
+
+    fn is_event_driven(&self) -> bool {
+        false
+    }
+
+    async fn connect(&mut self) -> igw::Result<()> {
+        self.diagnostics.connection_state = ConnectionState::Connecting;
+        let address = self.peer();
+        let stream =
+            match tokio::time::timeout(self.config.response_timeout, TcpStream::connect(&address))
+                .await
+            {
+                Ok(Ok(stream)) => stream,
+                Ok(Err(e)) => {
+                    return Err(self.fail(GatewayError::Connection(format!("{}: {}", address, e))))
+                },
+                Err(_) => {
+                    let e = self.timeout_error();
+                    return Err(self.fail(e));
+                },
+            };
+        let _ = stream.set_nodelay(true);
+        self.stream = Some(stream);
+
+        if let Err(e) = self.establish().await {
+            self.stream = None;
+            let e = match e {
+                GatewayError::Connection(_) | GatewayError::ConnectionTimeout(_) => e,
+                other => GatewayError::Connection(format!("{}: {}", address, other)),
+            };
+            return Err(self.fail(e));
+        }
+        self.plan();
+        self.diagnostics.connection_state = ConnectionState::Connected;
+        info!(
+            "Ch{} S7 connected to {} (rack {}, slot {}, PDU {})",
+            self.id, address, self.config.rack, self.config.slot, self.pdu_len
+        );
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> igw::Result<()> {
+        if let Some(mut stream) = self.stream.take() {
+            let _ = stream.shutdown().await;
+        }
+        self.diagnostics.connection_state = ConnectionState::Disconnected;
+        Ok(())
+    }
+
+    async fn poll_once(&mut self) -> PollResult {
+        if self.stream.is_none() {
+            return PollResult::failed(vec![PointFailure::new(0, "not connected")]);
+        }
+        let mut batch = DataBatch::default();
+        let mut failures = Vec::new();
+        self.read_all(&mut batch, &mut failures).await;
+        self.diagnostics.read_count += 1;
+        PollResult::partial(batch, failures)
+    }
+
+    async fn write_control(&mut self, commands: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Control, commands).await
+    }
+
+    async fn write_adjustment(&mut self, adjustments: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Adjustment, adjustments).await
+    }
+
+    fn subscribe(&self) -> Option<DataEventReceiver> {
+        None
+    }
+
+    async fn start_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn stop_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn diagnostics(&self) -> igw::Result<Diagnostics> {
+        let mut diagnostics = self.diagnostics.clone();
+        diagnostics.extra = json!({
+            "peer": self.peer(),
+            "rack": self.config.rack,
+            "slot": self.config.slot,
+            "pdu_length": self.stream.as_ref().map(|_| self.pdu_len),
+            "ranges": self.ranges.len(),
+            "read_jobs": self.jobs.len(),
+        });
+        Ok(diagnostics)
+    }
+}
+
+// ============================================================================
+// Plugin metadata
+// ============================================================================
+
+fn parameters() -> Vec<ParameterMetadata> {
+    vec![
+        ParameterMetadata::required(
+            "host",
+            "Host",
+            "IP address of the CPU or its communication processor",
+            ParameterType::String,
+        ),
+        ParameterMetadata::optional(
+            "port",
+            "Port",
+            "ISO-on-TCP port",
+            ParameterType::Integer,
+            json!(DEFAULT_PORT),
+        ),
+        ParameterMetadata::optional(
+            "rack",
+            "Rack",
+            "Rack of the CPU",
+            ParameterType::Integer,
+            json!(DEFAULT_RACK),
+        ),
+        ParameterMetadata::optional(
+            "slot",
+            "Slot",
+            "Slot of the CPU: 1 (or 0) for S7-1200/1500, 2 for S7-300",
+            ParameterType::Integer,
+            json!(DEFAULT_SLOT),
+        ),
+        ParameterMetadata::optional(
+            "connection_type",
+            "Connection Type",
+            "CPU connection resource: pg, op or basic",
+            ParameterType::String,
+            json!("pg"),
+        ),
+        ParameterMetadata::optional(
+            "response_timeout_ms",
+            "Response Timeout (ms)",
+            "Time to wait for a CPU reply",
+            ParameterType::Integer,
+            json!(DEFAULT_RESPONSE_TIMEOUT_MS),
+        ),
+        ParameterMetadata::optional(
+            "max_gap",
+            "Max Gap (bytes)",
+            "Unmapped bytes read between points to share one range",
+            ParameterType::Integer,
+            json!(DEFAULT_MAX_GAP),
+        ),
+    ]
+}
+
+crate::protocol_plugin! {
+    /// Siemens S7comm client for S7-300/400/1200/1500 CPUs (in-tree runtime)
+    pub struct S7Plugin {
+        name: PROTOCOL,
+        aliases: &["s7comm", "siemens_s7", "siemens"],
+        display_name: "Siemens S7",
+        description: "S7-300/400/1200/1500 data blocks, inputs, outputs and flags by byte offset and bit",
+        parameters: parameters(),
+        mapping_columns: &[
+            MappingColumn::string("area", "Memory area; DB when empty").choices(&["DB", "I", "Q", "M"]),
+            MappingColumn::integer("db", "Data block number of DB points").range(1, 65535),
+            MappingColumn::integer("offset", "Byte offset of the value")
+                .range(0, MAX_OFFSET as i64)
+                .required(),
+            MappingColumn::integer("bit", "Bit of the byte, for BOOL points").range(0, 7),
+            MappingColumn::string("data_type", "S7 type of the value; BOOL when only bit is given")
+                .choices(&[
+                    "BOOL", "BYTE", "SINT", "USINT", "WORD", "INT", "UINT", "DWORD", "DINT",
+                    "UDINT", "REAL", "LREAL",
+                ]),
+        ],
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use crate::core::protocols::polled_value;
+    use codec::function;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+
+    /// PDU length the fake CPU grants
+    const GRANTED_PDU: u16 = 240;
+
+    fn point<T: serde::de::DeserializeOwned>(point_id: u32, mapping: JsonValue) -> T {
+        serde_json::from_value(json!({
+            "point_id": point_id,
+            "signal_name": format!("p{}", point_id),
+            "protocol_mappings": mapping.to_string(),
+        }))
+        .unwrap()
+    }
+
+    /// Memory of the fake CPU: (area code, DB) -> bytes
+    type Memory = HashMap<(u8, u16), Vec<u8>>;
+
+    fn memory() -> Memory {
+        let mut db10 = vec![0u8; 300];
+        db10[0..4].copy_from_slice(&72.5f32.to_be_bytes());
+        db10[4..6].copy_from_slice(&(-7i16).to_be_bytes());
+        db10[6] = 0b1000;
+        db10[250..254].copy_from_slice(&1234u32.to_be_bytes());
+        HashMap::from([
+            ((Area::Db.code(), 10), db10),
+            ((Area::Flags.code(), 0), vec![0u8; 16]),
+        ])
+    }
+
+    fn respond(job: &Pdu, memory: &mut Memory, writes: &mpsc::UnboundedSender<Vec<u8>>) -> Pdu {
+        let mut reply = Pdu {
+            rosctr: rosctr::ACK_DATA,
+            reference: job.reference,
+            error: (0, 0),
+            parameter: job.parameter[..2].to_vec(),
+            data: Vec::new(),
+        };
+        let items = job.parameter[2..].chunks(codec::ITEM_SPEC_LEN);
+        let locate = |spec: &[u8]| {
+            let bit_address = u32::from_be_bytes([0, spec[9], spec[10], spec[11]]);
+            (
+                (spec[8], u16::from_be_bytes([spec[6], spec[7]])),
+                (bit_address >> 3) as usize,
+                bit_address & 7,
+                usize::from(u16::from_be_bytes([spec[4], spec[5]])),
+            )
+        };
+        match job.function() {
+            Some(function::SETUP_COMMUNICATION) => {
+                reply.parameter = job.parameter.clone();
+                reply.parameter[6..8].copy_from_slice(&GRANTED_PDU.to_be_bytes());
+            },
+            Some(function::READ_VAR) => {
+                let count = items.len();
+                for (i, spec) in items.enumerate() {
+                    let (key, offset, _, len) = locate(spec);
+                    match memory.get(&key).and_then(|m| m.get(offset..offset + len)) {
+                        Some(data) => {
+                            reply.data.extend_from_slice(&[0xFF, 0x04]);
+                            reply
+                                .data
+                                .extend_from_slice(&((len * 8) as u16).to_be_bytes());
+                            reply.data.extend_from_slice(data);
+                            if len % 2 == 1 && i + 1 < count {
+                                reply.data.push(0);
+                            }
+                        },
+                        None => reply.data.extend_from_slice(&[0x0A, 0x00, 0x00, 0x00]),
+                    }
+                }
+            },
+            Some(function::WRITE_VAR) => {
+                let (key, offset, bit, len) = locate(&job.parameter[2..]);
+                let value = &job.data[4..];
+                match memory.get_mut(&key) {
+                    Some(bytes) if job.parameter[5] == 0x01 => {
+                        bytes[offset] = (bytes[offset] & !(1 << bit)) | (value[0] << bit);
+                        writes.send(vec![bytes[offset]]).unwrap();
+                        reply.data.push(0xFF);
+                    },
+                    Some(bytes) if offset + len <= bytes.len() => {
+                        bytes[offset..offset + len].copy_from_slice(value);
+                        writes.send(value.to_vec()).unwrap();
+                        reply.data.push(0xFF);
+                    },
+                    _ => reply.data.push(0x05),
+                }
+            },
+            _ => reply.error = (0x81, 0x04),
+        }
+        reply
+    }
+
+    /// Fake CPU at rack 0, slot 1
+    async fn cpu() -> (
+        u16,
+        mpsc::UnboundedReceiver<Vec<u8>>,
+        mpsc::UnboundedReceiver<usize>,
+    ) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (writes, written) = mpsc::unbounded_channel();
+        let (jobs, job_sizes) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut memory = memory();
+            loop {
+                let mut header = [0u8; codec::TPKT_HEADER_LEN];
+                if stream.read_exact(&mut header).await.is_err() {
+                    return;
+                }
+                let mut tpdu = vec![0u8; codec::tpkt_payload_len(&header).unwrap()];
+                stream.read_exact(&mut tpdu).await.unwrap();
+                let reply = if tpdu[1] == 0xE0 {
+                    // Remote TSAP: PG, rack 0, slot 1
+                    assert_eq!(tpdu[tpdu.len() - 2..], [0x01, 0x01]);
+                    let mut confirm = tpdu.clone();
+                    confirm[1] = 0xD0;
+                    confirm
+                } else {
+                    let (payload, _) = codec::data_payload(&tpdu).unwrap();
+                    let job = Pdu::decode(payload).unwrap();
+                    assert!(payload.len() <= usize::from(GRANTED_PDU) || job.reference == 1);
+                    let reply = respond(&job, &mut memory, &writes).encode();
+                    assert!(reply.len() <= usize::from(GRANTED_PDU));
+                    if job.function() == Some(function::READ_VAR) {
+                        jobs.send(usize::from(job.parameter[1])).unwrap();
+                    }
+                    codec::data_tpdu(&reply)
+                };
+                stream.write_all(&codec::tpkt(&reply)).await.unwrap();
+            }
+        });
+        (port, written, job_sizes)
+    }
+
+    fn channel(port: u16) -> RuntimeChannelConfig {
+        let mut config = RuntimeChannelConfig::from_base(
+            serde_json::from_value(json!({
+                "id": 71,
+                "name": "plc",
+                "protocol": PROTOCOL,
+                "parameters": {
+                    "host": "127.0.0.1",
+                    "port": port,
+                    "response_timeout_ms": 1000,
+                },
+            }))
+            .unwrap(),
+        );
+        config.telemetry_points = vec![
+            point(1, json!({"db": 10, "offset": 0, "data_type": "REAL"})),
+            point(2, json!({"db": "10", "offset": "4", "data_type": "INT"})),
+            point(3, json!({"db": 10, "offset": 250, "data_type": "UDINT"})),
+            point(4, json!({"db": 99, "offset": 0, "data_type": "WORD"})),
+            point(5, json!({"db": 10, "offset": 0})),
+        ];
+        config.signal_points = vec![
+            point(1, json!({"db": 10, "offset": 6, "bit": 3})),
+            point(2, json!({"db": 10, "offset": 6, "bit": 2})),
+            point(3, json!({"area": "M", "offset": 1, "bit": 0})),
+        ];
+        config.control_points = vec![point(1, json!({"area": "M", "offset": 1, "bit": 0}))];
+        config.adjustment_points = vec![
+            point(1, json!({"db": 10, "offset": 0, "data_type": "REAL"})),
+            point(2, json!({"db": 10, "offset": 4, "data_type": "BYTE"})),
+            point(3, json!({"db": 10, "offset": 298, "data_type": "DINT"})),
+        ];
+        config
+    }
+
+    #[tokio::test]
+    async fn test_read_and_write() {
+        let (port, mut writes, mut jobs) = cpu().await;
+        let mut runtime = S7Runtime::from_runtime_config(&channel(port)).unwrap();
+        // T5 has no data type
+        assert_eq!(runtime.readings.len(), 7);
+        runtime.connect().await.unwrap();
+        assert_eq!(runtime.pdu_len, GRANTED_PDU);
+        // DB10.0-7 merged, DB10.250 too far, DB99, M1
+        assert_eq!(runtime.ranges.len(), 4);
+
+        let result = runtime.poll_once().await;
+        assert_eq!(jobs.recv().await.unwrap(), 4);
+        assert_eq!(polled_value(&result, PointType::Telemetry, 1), Some(72.5));
+        assert_eq!(polled_value(&result, PointType::Telemetry, 2), Some(-7.0));
+        assert_eq!(polled_value(&result, PointType::Telemetry, 3), Some(1234.0));
+        assert_eq!(polled_value(&result, PointType::Signal, 1), Some(1.0));
+        assert_eq!(polled_value(&result, PointType::Signal, 2), Some(0.0));
+        assert_eq!(polled_value(&result, PointType::Signal, 3), Some(0.0));
+        assert_eq!(result.failures.len(), 1);
+        assert!(result.failures[0].error.contains("object does not exist"));
+
+        // Bit write leaves the other bits of M1
+        let control = PointType::Control.to_internal_id(1);
+        assert_eq!(runtime.write_control(&[(control, 1.0)]).await.unwrap(), 1);
+        assert_eq!(writes.recv().await.unwrap(), [0b1]);
+        let adjustment = PointType::Adjustment.to_internal_id(1);
+        assert_eq!(
+            runtime
+                .write_adjustment(&[(adjustment, 42.5)])
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(writes.recv().await.unwrap(), 42.5f32.to_be_bytes());
+        let result = runtime.poll_once().await;
+        assert_eq!(polled_value(&result, PointType::Telemetry, 1), Some(42.5));
+        assert_eq!(polled_value(&result, PointType::Signal, 3), Some(1.0));
+
+        // Out of the BYTE range, then past the end of DB10
+        let error = runtime
+            .write_adjustment(&[(PointType::Adjustment.to_internal_id(2), 300.0)])
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("does not fit BYTE"), "{}", error);
+        let error = runtime
+            .write_adjustment(&[(PointType::Adjustment.to_internal_id(3), 1.0)])
+            .await
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("address out of range"),
+            "{}",
+            error
+        );
+
+        runtime.disconnect().await.unwrap();
+        assert!(runtime.poll_once().await.failures[0]
+            .error
+            .contains("not connected"));
+    }
+
+    #[test]
+    fn test_plan() {
+        let reading = |id: u32, offset: u32, data_type: DataType| Reading {
+            internal_id: id,
+            variable: Variable {
+                address: Address {
+                    area: Area::Db,
+                    db: 1,
+                    offset,
+                },
+                bit: 0,
+                data_type,
+            },
+        };
+        let readings: Vec<Reading> = (0..30)
+            .map(|i| reading(i, i * 100, DataType::Lreal))
+            .chain([
+                reading(30, 8, DataType::Int),
+                reading(31, 4, DataType::Real),
+            ])
+            .collect();
+        let ranges = plan_ranges(&readings, 16, 100);
+        // 0-10 in one range, every other 100 bytes apart
+        assert_eq!(ranges.len(), 30);
+        assert_eq!((ranges[0].start.offset, ranges[0].len), (0, 10));
+        assert_eq!(ranges[0].readings.len(), 3);
+        assert!(plan_ranges(&readings, 0, 100)[0].len == 10);
+        assert_eq!(plan_ranges(&readings, 200, 100).len(), 30);
+
+        // 20 items at most per job, and no more than the PDU holds
+        let jobs = plan_jobs(&ranges, 960);
+        assert_eq!(jobs.iter().map(Vec::len).collect::<Vec<_>>(), [20, 10]);
+        for job in plan_jobs(&ranges, 240) {
+            let lengths = job.iter().map(|i| usize::from(ranges[*i].len));
+            assert!(codec::read_reply_len(lengths) <= 240);
+            assert!(codec::read_request_len(job.len()) <= 240);
+        }
+    }
+
+    #[test]
+    fn test_config_parameters() {
+        let parameters = |value: JsonValue| -> HashMap<String, JsonValue> {
+            serde_json::from_value(value).unwrap()
+        };
+        let config = S7Config::from_parameters(&parameters(json!({"host": "10.0.0.2"}))).unwrap();
+        assert_eq!(config.port, DEFAULT_PORT);
+        assert_eq!(config.remote_tsap(), 0x0101);
+        let s7_300 = S7Config::from_parameters(&parameters(
+            json!({"host": "10.0.0.2", "rack": 0, "slot": "2", "connection_type": "OP"}),
+        ))
+        .unwrap();
+        assert_eq!(s7_300.remote_tsap(), 0x0202);
+        for invalid in [
+            json!({}),
+            json!({"host": "h", "slot": 32}),
+            json!({"host": "h", "connection_type": "hmi"}),
+        ] {
+            assert!(S7Config::from_parameters(&parameters(invalid)).is_err());
+        }
+
+        let bit_word: Point = point(
+            1,
+            json!({"db": 1, "offset": 0, "bit": 1, "data_type": "WORD"}),
+        );
+        assert!(parse_point(&bit_word).is_err());
+        let no_db: Point = point(2, json!({"offset": 0, "data_type": "INT"}));
+        assert!(parse_point(&no_db).is_err());
+        let input: Point = point(3, json!({"area": "E", "offset": 2, "bit": 5}));
+        let variable = parse_point(&input).unwrap();
+        assert_eq!(variable.to_string(), "I2.5");
+        assert_eq!(variable.address.db, 0);
+    }
+}
@@ -0,0 +1,660 @@
+//! S7comm over ISO-on-TCP (RFC 1006 and ISO 8073 class 0)
+//!
+//! TPKT framing, the COTP connection and data TPDUs, the S7 PDU header,
+//! Setup Communication, and the Read Var and Write Var jobs that address
+//! process image, flag and data block memory by byte offset and bit.
+//! Values are big-endian as in the CPU.
+
+use std::fmt;
+
+use crate::core::protocols::{error, CodecError};
+
+type Result<T> = std::result::Result<T, CodecError>;
+
+/// TPKT header length
+pub const TPKT_HEADER_LEN: usize = 4;
+/// PDU length the client asks for; CPUs answer with what they accept
+pub const REQUESTED_PDU: u16 = 480;
+/// Items in one Read Var job (S7-300 accepts no more)
+pub const MAX_READ_ITEMS: usize = 20;
+/// Header of a job PDU
+pub const JOB_HEADER_LEN: usize = 10;
+/// Header of an ack-data PDU, with error class and code
+pub const ACK_HEADER_LEN: usize = 12;
+/// Address specification of a variable in a Read Var or Write Var job
+pub const ITEM_SPEC_LEN: usize = 12;
+/// Return code, transport size and length ahead of read data
+pub const DATA_ITEM_HEADER_LEN: usize = 4;
+
+const PROTOCOL_ID: u8 = 0x32;
+const TPKT_VERSION: u8 = 0x03;
+/// COTP TPDU codes
+const COTP_CR: u8 = 0xE0;
+const COTP_CC: u8 = 0xD0;
+const COTP_DT: u8 = 0xF0;
+/// Last data TPDU of a PDU
+const COTP_EOT: u8 = 0x80;
+/// TPDU size parameter: 1024 octets
+const TPDU_SIZE_1024: u8 = 0x0A;
+
+/// PDU types (ROSCTR)
+pub mod rosctr {
+    pub const JOB: u8 = 0x01;
+    pub const ACK_DATA: u8 = 0x03;
+}
+
+/// Job functions
+pub mod function {
+    pub const READ_VAR: u8 = 0x04;
+    pub const WRITE_VAR: u8 = 0x05;
+    pub const SETUP_COMMUNICATION: u8 = 0xF0;
+}
+
+/// Transport sizes of an item specification
+mod transport {
+    pub const BIT: u8 = 0x01;
+    pub const BYTE: u8 = 0x02;
+}
+
+/// Transport sizes of a data item
+mod data_transport {
+    pub const BIT: u8 = 0x03;
+    pub const BYTE_WORD_DWORD: u8 = 0x04;
+    pub const INTEGER: u8 = 0x05;
+}
+
+/// Return code of a successful item
+pub const ITEM_SUCCESS: u8 = 0xFF;
+
+/// Text of a Read Var or Write Var item return code
+pub fn item_error_text(code: u8) -> String {
+    match code {
+        0x01 => "hardware fault".to_string(),
+        0x03 => "access denied".to_string(),
+        0x05 => "address out of range".to_string(),
+        0x06 => "data type not supported".to_string(),
+        0x07 => "data type inconsistent".to_string(),
+        0x0A => "object does not exist".to_string(),
+        other => format!("item error 0x{:02X}", other),
+    }
+}
+
+/// Text of the error class and code of an ack-data PDU
+pub fn header_error_text(class: u8, code: u8) -> String {
+    match (class, code) {
+        (0x81, 0x04) => {
+            "context not supported (enable PUT/GET access in the CPU protection settings)"
+                .to_string()
+        },
+        (0x85, 0x00) => "PDU too long for the CPU".to_string(),
+        (0x87, _) => format!("access error 0x{:02X}", code),
+        _ => format!("error class 0x{:02X} code 0x{:02X}", class, code),
+    }
+}
+
+// ============================================================================
+// ISO-on-TCP framing
+// ============================================================================
+
+/// Wrap a COTP TPDU in a TPKT
+pub fn tpkt(tpdu: &[u8]) -> Vec<u8> {
+    let len = (TPKT_HEADER_LEN + tpdu.len()) as u16;
+    let mut packet = vec![TPKT_VERSION, 0x00];
+    packet.extend_from_slice(&len.to_be_bytes());
+    packet.extend_from_slice(tpdu);
+    packet
+}
+
+/// Length of the TPDU following a TPKT header
+pub fn tpkt_payload_len(header: &[u8; TPKT_HEADER_LEN]) -> Result<usize> {
+    if header[0] != TPKT_VERSION {
+        return Err(error(format!("not a TPKT (version {})", header[0])));
+    }
+    let len = usize::from(u16::from_be_bytes([header[2], header[3]]));
+    len.checked_sub(TPKT_HEADER_LEN)
+        .filter(|len| *len >= 2)
+        .ok_or_else(|| error(format!("TPKT length {} too short", len)))
+}
+
+/// COTP connection request from `local_tsap` to `remote_tsap`
+pub fn connection_request(local_tsap: u16, remote_tsap: u16) -> Vec<u8> {
+    let mut tpdu = vec![
+        0x00, // length indicator, set below
+        COTP_CR,
+        0x00,
+        0x00, // destination reference
+        0x00,
+        0x01, // source reference
+        0x00, // class 0
+        0xC0,
+        1,
+        TPDU_SIZE_1024,
+        0xC1,
+        2,
+    ];
+    tpdu.extend_from_slice(&local_tsap.to_be_bytes());
+    tpdu.extend_from_slice(&[0xC2, 2]);
+    tpdu.extend_from_slice(&remote_tsap.to_be_bytes());
+    tpdu[0] = (tpdu.len() - 1) as u8;
+    tpdu
+}
+
+/// Check that a TPDU confirms the connection
+pub fn check_connection_confirm(tpdu: &[u8]) -> Result<()> {
+    match tpdu.get(1) {
+        Some(&COTP_CC) => Ok(()),
+        // Disconnect request: the CPU refused the TSAP
+        Some(0x80) => Err(error("connection refused (check rack, slot and TSAP)")),
+        Some(code) => Err(error(format!(
+            "unexpected COTP TPDU 0x{:02X} to a connection request",
+            code
+        ))),
+        None => Err(error("empty COTP TPDU")),
+    }
+}
+
+/// COTP data TPDU carrying a whole PDU
+pub fn data_tpdu(pdu: &[u8]) -> Vec<u8> {
+    let mut tpdu = vec![0x02, COTP_DT, COTP_EOT];
+    tpdu.extend_from_slice(pdu);
+    tpdu
+}
+
+/// Payload of a data TPDU and whether it ends the PDU
+pub fn data_payload(tpdu: &[u8]) -> Result<(&[u8], bool)> {
+    let header_len = usize::from(*tpdu.first().ok_or_else(|| error("empty COTP TPDU"))?) + 1;
+    match (tpdu.get(1), tpdu.get(2)) {
+        (Some(&COTP_DT), Some(&number)) if tpdu.len() >= header_len => {
+            Ok((&tpdu[header_len..], number & COTP_EOT != 0))
+        },
+        (Some(&COTP_DT), _) => Err(error("COTP data TPDU truncated")),
+        (Some(code), _) => Err(error(format!("COTP TPDU 0x{:02X} instead of data", code))),
+        (None, _) => Err(error("COTP TPDU truncated")),
+    }
+}
+
+// ============================================================================
+// Addressing and data types
+// ============================================================================
+
+/// Memory area of a variable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Area {
+    /// Process image inputs (I / E)
+    Inputs,
+    /// Process image outputs (Q / A)
+    Outputs,
+    /// Flags (M)
+    Flags,
+    /// Data block
+    Db,
+}
+
+impl Area {
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::Inputs => 0x81,
+            Self::Outputs => 0x82,
+            Self::Flags => 0x83,
+            Self::Db => 0x84,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Inputs => "I",
+            Self::Outputs => "Q",
+            Self::Flags => "M",
+            Self::Db => "DB",
+        }
+    }
+
+    /// Parse an area name; German mnemonics (E, A) are accepted
+    pub fn parse(text: &str) -> Result<Self> {
+        match text.trim().to_ascii_uppercase().as_str() {
+            "DB" => Ok(Self::Db),
+            "I" | "E" => Ok(Self::Inputs),
+            "Q" | "A" => Ok(Self::Outputs),
+            "M" => Ok(Self::Flags),
+            _ => Err(error(format!(
+                "unknown S7 area '{}' (DB, I, Q, M)",
+                text.trim()
+            ))),
+        }
+    }
+}
+
+/// Start of a variable: area, data block number (0 outside DBs) and byte
+/// offset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Address {
+    pub area: Area,
+    pub db: u16,
+    pub offset: u32,
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.area {
+            Area::Db => write!(f, "DB{}.{}", self.db, self.offset),
+            area => write!(f, "{}{}", area.name(), self.offset),
+        }
+    }
+}
+
+/// Item specification of `len` units of `transport` at a bit address
+fn item_spec(transport: u8, len: u16, address: &Address, bit: u8) -> [u8; ITEM_SPEC_LEN] {
+    let bit_address = (address.offset << 3) | u32::from(bit & 0x07);
+    let db = match address.area {
+        Area::Db => address.db,
+        _ => 0,
+    };
+    let [_, a, b, c] = bit_address.to_be_bytes();
+    let [len_hi, len_lo] = len.to_be_bytes();
+    let [db_hi, db_lo] = db.to_be_bytes();
+    [
+        0x12,
+        0x0A,
+        0x10, // S7ANY
+        transport,
+        len_hi,
+        len_lo,
+        db_hi,
+        db_lo,
+        address.area.code(),
+        a,
+        b,
+        c,
+    ]
+}
+
+/// S7 elementary data types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DataType {
+    Bool,
+    Byte,
+    Sint,
+    Usint,
+    Word,
+    Int,
+    Uint,
+    Dword,
+    Dint,
+    Udint,
+    Real,
+    Lreal,
+}
+
+impl DataType {
+    const ALL: [(DataType, &'static str); 12] = [
+        (DataType::Bool, "BOOL"),
+        (DataType::Byte, "BYTE"),
+        (DataType::Sint, "SINT"),
+        (DataType::Usint, "USINT"),
+        (DataType::Word, "WORD"),
+        (DataType::Int, "INT"),
+        (DataType::Uint, "UINT"),
+        (DataType::Dword, "DWORD"),
+        (DataType::Dint, "DINT"),
+        (DataType::Udint, "UDINT"),
+        (DataType::Real, "REAL"),
+        (DataType::Lreal, "LREAL"),
+    ];
+
+    pub fn name(&self) -> &'static str {
+        Self::ALL
+            .iter()
+            .find(|(t, _)| t == self)
+            .map_or("", |(_, name)| name)
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let upper = text.trim().to_ascii_uppercase();
+        Self::ALL
+            .iter()
+            .find(|(_, name)| *name == upper)
+            .map(|(t, _)| *t)
+            .ok_or_else(|| {
+                error(format!(
+                    "unknown S7 data type '{}' (BOOL, BYTE, SINT, USINT, WORD, INT, UINT, DWORD, DINT, UDINT, REAL, LREAL)",
+                    text.trim()
+                ))
+            })
+    }
+
+    /// Octets read for a value; a BOOL reads the byte holding its bit
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Bool | Self::Byte | Self::Sint | Self::Usint => 1,
+            Self::Word | Self::Int | Self::Uint => 2,
+            Self::Dword | Self::Dint | Self::Udint | Self::Real => 4,
+            Self::Lreal => 8,
+        }
+    }
+
+    /// Decode a value; BOOLs take the bit of the byte
+    pub fn decode(&self, data: &[u8], bit: u8) -> Result<f64> {
+        let data = data
+            .get(..self.size())
+            .ok_or_else(|| error(format!("{} value truncated", self.name())))?;
+        let mut bytes = [0u8; 8];
+        bytes[8 - data.len()..].copy_from_slice(data);
+        let raw = u64::from_be_bytes(bytes);
+        Ok(match self {
+            Self::Bool => f64::from(((raw >> (bit & 0x07)) & 1) as u8),
+            Self::Sint => f64::from(raw as u8 as i8),
+            Self::Int => f64::from(raw as u16 as i16),
+            Self::Dint => f64::from(raw as u32 as i32),
+            Self::Real => f64::from(f32::from_bits(raw as u32)),
+            Self::Lreal => f64::from_bits(raw),
+            Self::Byte | Self::Usint | Self::Word | Self::Uint | Self::Dword | Self::Udint => {
+                raw as f64
+            },
+        })
+    }
+
+    /// Encode a written value; integer types require whole numbers in range
+    pub fn encode(&self, value: f64) -> Result<Vec<u8>> {
+        match self {
+            Self::Real => return Ok((value as f32).to_be_bytes().to_vec()),
+            Self::Lreal => return Ok(value.to_be_bytes().to_vec()),
+            Self::Bool => return Ok(vec![u8::from(value != 0.0)]),
+            _ => {},
+        }
+        let signed = matches!(self, Self::Sint | Self::Int | Self::Dint);
+        let bits = self.size() as i32 * 8;
+        let (min, max) = if signed {
+            (-(2f64.powi(bits - 1)), 2f64.powi(bits - 1) - 1.0)
+        } else {
+            (0.0, 2f64.powi(bits) - 1.0)
+        };
+        if !value.is_finite() || value.fract() != 0.0 || value < min || value > max {
+            return Err(error(format!("{} does not fit {}", value, self.name())));
+        }
+        let raw = if signed {
+            value as i64 as u64
+        } else {
+            value as u64
+        };
+        Ok(raw.to_be_bytes()[8 - self.size()..].to_vec())
+    }
+}
+
+// ============================================================================
+// PDUs
+// ============================================================================
+
+/// S7 PDU: header, parameter and data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pdu {
+    pub rosctr: u8,
+    pub reference: u16,
+    /// Error class and code of an ack-data PDU
+    pub error: (u8, u8),
+    pub parameter: Vec<u8>,
+    pub data: Vec<u8>,
+}
+
+impl Pdu {
+    fn job(parameter: Vec<u8>, data: Vec<u8>) -> Self {
+        Self {
+            rosctr: rosctr::JOB,
+            reference: 0,
+            error: (0, 0),
+            parameter,
+            data,
+        }
+    }
+
+    /// Setup Communication asking for `pdu_len`
+    pub fn setup_communication(pdu_len: u16) -> Self {
+        let mut parameter = vec![function::SETUP_COMMUNICATION, 0x00, 0x00, 0x01, 0x00, 0x01];
+        parameter.extend_from_slice(&pdu_len.to_be_bytes());
+        Self::job(parameter, Vec::new())
+    }
+
+    /// Read Var of byte ranges
+    pub fn read_var(ranges: &[(Address, u16)]) -> Self {
+        let mut parameter = vec![function::READ_VAR, ranges.len() as u8];
+        for (address, len) in ranges {
+            parameter.extend_from_slice(&item_spec(transport::BYTE, *len, address, 0));
+        }
+        Self::job(parameter, Vec::new())
+    }
+
+    /// Write Var of one bit
+    pub fn write_bit(address: &Address, bit: u8, value: bool) -> Self {
+        let mut parameter = vec![function::WRITE_VAR, 1];
+        parameter.extend_from_slice(&item_spec(transport::BIT, 1, address, bit));
+        let data = vec![0x00, data_transport::BIT, 0x00, 0x01, u8::from(value)];
+        Self::job(parameter, data)
+    }
+
+    /// Write Var of the bytes of a value
+    pub fn write_bytes(address: &Address, value: &[u8]) -> Self {
+        let mut parameter = vec![function::WRITE_VAR, 1];
+        parameter.extend_from_slice(&item_spec(transport::BYTE, value.len() as u16, address, 0));
+        let mut data = vec![0x00, data_transport::BYTE_WORD_DWORD];
+        data.extend_from_slice(&((value.len() * 8) as u16).to_be_bytes());
+        data.extend_from_slice(value);
+        Self::job(parameter, data)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut pdu = vec![PROTOCOL_ID, self.rosctr, 0x00, 0x00];
+        pdu.extend_from_slice(&self.reference.to_be_bytes());
+        pdu.extend_from_slice(&(self.parameter.len() as u16).to_be_bytes());
+        pdu.extend_from_slice(&(self.data.len() as u16).to_be_bytes());
+        if self.rosctr == rosctr::ACK_DATA {
+            pdu.extend_from_slice(&[self.error.0, self.error.1]);
+        }
+        pdu.extend_from_slice(&self.parameter);
+        pdu.extend_from_slice(&self.data);
+        pdu
+    }
+
+    pub fn decode(pdu: &[u8]) -> Result<Self> {
+        if pdu.len() < JOB_HEADER_LEN || pdu[0] != PROTOCOL_ID {
+            return Err(error("not an S7 PDU"));
+        }
+        let rosctr = pdu[1];
+        let header_len = match rosctr {
+            0x02 | rosctr::ACK_DATA => ACK_HEADER_LEN,
+            _ => JOB_HEADER_LEN,
+        };
+        if pdu.len() < header_len {
+            return Err(error("S7 header truncated"));
+        }
+        let word = |i: usize| usize::from(u16::from_be_bytes([pdu[i], pdu[i + 1]]));
+        let (parameter_len, data_len) = (word(6), word(8));
+        let body = &pdu[header_len..];
+        if body.len() < parameter_len + data_len {
+            return Err(error(format!(
+                "S7 PDU of {} octets announces {}",
+                body.len(),
+                parameter_len + data_len
+            )));
+        }
+        Ok(Self {
+            rosctr,
+            reference: word(4) as u16,
+            error: match header_len {
+                ACK_HEADER_LEN => (pdu[10], pdu[11]),
+                _ => (0, 0),
+            },
+            parameter: body[..parameter_len].to_vec(),
+            data: body[parameter_len..parameter_len + data_len].to_vec(),
+        })
+    }
+
+    /// Function code of the parameter
+    pub fn function(&self) -> Option<u8> {
+        self.parameter.first().copied()
+    }
+
+    /// PDU length granted in a Setup Communication reply
+    pub fn negotiated_pdu(&self) -> Result<u16> {
+        match self.parameter.as_slice() {
+            [function::SETUP_COMMUNICATION, _, _, _, _, _, hi, lo, ..] => {
+                Ok(u16::from_be_bytes([*hi, *lo]))
+            },
+            _ => Err(error("Setup Communication reply truncated")),
+        }
+    }
+
+    /// Data of each item of a Read Var reply, or its return code
+    pub fn read_results(&self) -> Result<Vec<std::result::Result<Vec<u8>, u8>>> {
+        let count = match self.parameter.as_slice() {
+            [function::READ_VAR, count, ..] => usize::from(*count),
+            _ => return Err(error("not a Read Var reply")),
+        };
+        let mut results = Vec::with_capacity(count);
+        let mut data = self.data.as_slice();
+        for i in 0..count {
+            let [code, transport, hi, lo, rest @ ..] = data else {
+                return Err(error(format!("Read Var reply ends before item {}", i + 1)));
+            };
+            if *code != ITEM_SUCCESS {
+                results.push(Err(*code));
+                data = rest;
+                continue;
+            }
+            let len = usize::from(u16::from_be_bytes([*hi, *lo]));
+            let len = match *transport {
+                data_transport::BIT | data_transport::BYTE_WORD_DWORD | data_transport::INTEGER => {
+                    len.div_ceil(8)
+                },
+                _ => len,
+            };
+            let value = rest
+                .get(..len)
+                .ok_or_else(|| error(format!("Read Var item {} truncated", i + 1)))?;
+            results.push(Ok(value.to_vec()));
+            // Items but the last are padded to an even length
+            let padded = (len + len % 2).min(rest.len());
+            data = &rest[padded..];
+        }
+        Ok(results)
+    }
+
+    /// Return codes of the items of a Write Var reply
+    pub fn write_results(&self) -> Result<Vec<u8>> {
+        match self.parameter.as_slice() {
+            [function::WRITE_VAR, count, ..] => self
+                .data
+                .get(..usize::from(*count))
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| error("Write Var reply truncated")),
+            _ => Err(error("not a Write Var reply")),
+        }
+    }
+}
+
+/// Size of a Read Var job of `items` ranges
+pub fn read_request_len(items: usize) -> usize {
+    JOB_HEADER_LEN + 2 + items * ITEM_SPEC_LEN
+}
+
+/// Size of the reply to a Read Var job of ranges of these lengths
+pub fn read_reply_len(lengths: impl IntoIterator<Item = usize>) -> usize {
+    ACK_HEADER_LEN
+        + 2
+        + lengths
+            .into_iter()
+            .map(|len| DATA_ITEM_HEADER_LEN + len + len % 2)
+            .sum::<usize>()
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_request() {
+        let cr = connection_request(0x0100, 0x0102);
+        assert_eq!(
+            tpkt(&cr),
+            [
+                0x03, 0x00, 0x00, 0x16, 0x11, 0xE0, 0x00, 0x00, 0x00, 0x01, 0x00, 0xC0, 0x01, 0x0A,
+                0xC1, 0x02, 0x01, 0x00, 0xC2, 0x02, 0x01, 0x02
+            ]
+        );
+        assert!(check_connection_confirm(&[0x11, COTP_CC]).is_ok());
+        assert!(check_connection_confirm(&[0x06, 0x80])
+            .unwrap_err()
+            .to_string()
+            .contains("refused"));
+    }
+
+    #[test]
+    fn test_read_var() {
+        let address = Address {
+            area: Area::Db,
+            db: 10,
+            offset: 4,
+        };
+        let mut pdu = Pdu::read_var(&[(address, 6)]);
+        pdu.reference = 7;
+        assert_eq!(
+            pdu.encode(),
+            [
+                0x32, 0x01, 0x00, 0x00, 0x00, 0x07, 0x00, 0x0E, 0x00, 0x00, 0x04, 0x01, 0x12, 0x0A,
+                0x10, 0x02, 0x00, 0x06, 0x00, 0x0A, 0x84, 0x00, 0x00, 0x20
+            ]
+        );
+        assert_eq!(pdu.encode().len(), read_request_len(1));
+
+        // Three bytes (padded), a missing DB, then a word
+        let reply = Pdu {
+            rosctr: rosctr::ACK_DATA,
+            reference: 7,
+            error: (0, 0),
+            parameter: vec![function::READ_VAR, 3],
+            data: vec![
+                0xFF, 0x04, 0x00, 0x18, 1, 2, 3, 0x00, 0x0A, 0x00, 0x00, 0x00, 0xFF, 0x04, 0x00,
+                0x10, 0xAB, 0xCD,
+            ],
+        };
+        let decoded = Pdu::decode(&reply.encode()).unwrap();
+        assert_eq!(decoded, reply);
+        assert_eq!(
+            decoded.read_results().unwrap(),
+            vec![Ok(vec![1, 2, 3]), Err(0x0A), Ok(vec![0xAB, 0xCD])]
+        );
+        assert_eq!(read_reply_len([3, 0, 2]), reply.encode().len());
+    }
+
+    #[test]
+    fn test_write_var() {
+        let address = Address {
+            area: Area::Flags,
+            db: 5,
+            offset: 2,
+        };
+        let pdu = Pdu::write_bit(&address, 3, true);
+        // Bit address 2.3, DB number cleared outside data blocks
+        assert_eq!(
+            &pdu.parameter[2..],
+            [0x12, 0x0A, 0x10, 0x01, 0x00, 0x01, 0x00, 0x00, 0x83, 0x00, 0x00, 0x13]
+        );
+        assert_eq!(pdu.data, [0x00, 0x03, 0x00, 0x01, 0x01]);
+
+        let pdu = Pdu::write_bytes(&address, &DataType::Int.encode(-2.0).unwrap());
+        assert_eq!(pdu.data, [0x00, 0x04, 0x00, 0x10, 0xFF, 0xFE]);
+    }
+
+    #[test]
+    fn test_data_types() {
+        assert_eq!(DataType::Real.decode(&[0x42, 0x91, 0, 0], 0).unwrap(), 72.5);
+        assert_eq!(DataType::Int.decode(&[0xFF, 0xF9], 0).unwrap(), -7.0);
+        assert_eq!(DataType::Bool.decode(&[0b1000], 3).unwrap(), 1.0);
+        assert_eq!(DataType::Bool.decode(&[0b1000], 2).unwrap(), 0.0);
+        assert_eq!(DataType::Dint.encode(-7.0).unwrap(), (-7i32).to_be_bytes());
+        assert!(DataType::Byte.encode(256.0).is_err());
+        assert!(DataType::Uint.encode(1.5).is_err());
+        assert_eq!(DataType::parse("real").unwrap(), DataType::Real);
+        assert!(DataType::parse("STRING").is_err());
+        assert_eq!(Area::parse("e").unwrap(), Area::Inputs);
+    }
+}
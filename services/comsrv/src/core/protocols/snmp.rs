@@ -0,0 +1,1525 @@
+//! SNMP manager for network and power devices
+//!
+//! Each point maps one OID. Telemetry and signal points are read each poll
+//! with GetRequests of up to `max_oids_per_request` variables; counters,
+//! gauges, integers and octet strings holding a number (common in UPS MIBs)
+//! are accepted. A signal point with `on_value` is 1 while its variable
+//! equals that value, e.g. `upsOutputSource` = 5 (battery). Control and
+//! adjustment points write their OID with a SetRequest, as INTEGER unless
+//! `set_type` says otherwise.
+//!
+//! SNMPv2c uses `community` (and `write_community` for SETs, if set).
+//! SNMPv3 uses the user-based security model: the agent's engine is
+//! discovered on connect, and messages are authenticated (MD5, SHA, SHA256)
+//! and encrypted (DES, AES) as the user's `security_level` requires.
+//!
+//! ```yaml
+//! protocol: snmp
+//! parameters:
+//!   host: 192.168.10.30
+//!   version: 3
+//!   username: ems
+//!   auth_protocol: SHA
+//!   auth_password: "..."
+//!   priv_protocol: AES
+//!   priv_password: "..."
+//! ```
+
+pub mod codec;
+pub mod usm;
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use igw::core::traits::{DataEventReceiver, Diagnostics, PointFailure, PollResult};
+use igw::gateway::ChannelRuntime;
+use igw::{ConnectionState, DataBatch, DataPoint, GatewayError};
+use serde_json::{json, Value as JsonValue};
+use tokio::net::UdpSocket;
+use tokio::time::{timeout_at, Instant};
+use tracing::{debug, info, warn};
+
+use self::codec::{flags, pdu, MsgData, Oid, Pdu, ScopedPdu, SetType, UsmParameters, V3Message};
+use self::usm::{AuthProtocol, PrivProtocol};
+use crate::core::config::{Point, RuntimeChannelConfig};
+use crate::core::plugins::{MappingColumn, ParameterMetadata, ParameterType};
+use crate::core::protocols::record_failure;
+use crate::error::{ComSrvError, Result};
+use voltage_model::PointType;
+
+/// Protocol name stored in `channels.protocol`
+pub const PROTOCOL: &str = "snmp";
+
+const DEFAULT_PORT: u16 = 161;
+const DEFAULT_COMMUNITY: &str = "public";
+const DEFAULT_RESPONSE_TIMEOUT_MS: u64 = 3000;
+const DEFAULT_RETRIES: u64 = 1;
+const DEFAULT_MAX_OIDS: u64 = 20;
+/// msgMaxSize announced to v3 agents
+const MAX_MESSAGE_SIZE: i32 = 65507;
+const MAX_DATAGRAM: usize = 65535;
+
+/// `sysUpTime.0`, read on connect to check the agent answers
+const SYS_UPTIME: [u32; 9] = [1, 3, 6, 1, 2, 1, 1, 3, 0];
+/// `usmStats` counters an agent reports security errors with (RFC 3414 5)
+const USM_STATS: [u32; 9] = [1, 3, 6, 1, 6, 3, 15, 1, 1];
+const NOT_IN_TIME_WINDOWS: u32 = 2;
+const UNKNOWN_ENGINE_IDS: u32 = 4;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Security level of an SNMPv3 user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecurityLevel {
+    NoAuthNoPriv,
+    AuthNoPriv,
+    AuthPriv,
+}
+
+impl SecurityLevel {
+    fn parse(text: &str) -> Option<Self> {
+        match text
+            .trim()
+            .to_ascii_lowercase()
+            .replace(['-', '_'], "")
+            .as_str()
+        {
+            "noauthnopriv" | "noauth" => Some(Self::NoAuthNoPriv),
+            "authnopriv" | "auth" => Some(Self::AuthNoPriv),
+            "authpriv" | "priv" => Some(Self::AuthPriv),
+            _ => None,
+        }
+    }
+}
+
+/// SNMPv3 user of a channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserSecurity {
+    pub user_name: String,
+    pub auth: Option<(AuthProtocol, String)>,
+    /// Privacy requires authentication
+    pub privacy: Option<(PrivProtocol, String)>,
+    pub context_name: String,
+}
+
+/// Protocol version and its credentials
+#[derive(Debug, Clone, PartialEq)]
+pub enum Security {
+    V2c {
+        community: String,
+        write_community: String,
+    },
+    V3(UserSecurity),
+}
+
+/// Channel parameters of an SNMP channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnmpConfig {
+    pub host: String,
+    pub port: u16,
+    pub security: Security,
+    pub response_timeout: Duration,
+    /// Retransmissions of an unanswered request
+    pub retries: u32,
+    /// Variables per GetRequest
+    pub max_oids: usize,
+}
+
+impl SnmpConfig {
+    pub fn from_parameters(parameters: &HashMap<String, JsonValue>) -> Result<Self> {
+        let config_error = |message: String| ComSrvError::ConfigError(message);
+        let text = |key: &str| {
+            parameters
+                .get(key)
+                .and_then(|v| match v {
+                    JsonValue::String(s) => Some(s.trim().to_string()),
+                    JsonValue::Number(n) => Some(n.to_string()),
+                    _ => None,
+                })
+                .filter(|v| !v.is_empty())
+        };
+        let number = |key: &str, default: u64| -> Result<u64> {
+            match parameters.get(key) {
+                None | Some(JsonValue::Null) => Ok(default),
+                Some(JsonValue::String(s)) if s.trim().is_empty() => Ok(default),
+                Some(JsonValue::Number(n)) => n
+                    .as_u64()
+                    .ok_or_else(|| config_error(format!("'{}' must be a number", key))),
+                Some(JsonValue::String(s)) => s
+                    .trim()
+                    .parse()
+                    .map_err(|_| config_error(format!("'{}' must be a number", key))),
+                Some(_) => Err(config_error(format!("'{}' must be a number", key))),
+            }
+        };
+        let in_range = |key: &str, value: u64, min: u64, max: u64| {
+            if (min..=max).contains(&value) {
+                Ok(value)
+            } else {
+                Err(config_error(format!("'{}' must be {}-{}", key, min, max)))
+            }
+        };
+
+        let host = text("host").ok_or_else(|| config_error("SNMP requires 'host'".into()))?;
+        let port = in_range("port", number("port", u64::from(DEFAULT_PORT))?, 1, 65535)? as u16;
+
+        let version = text("version").unwrap_or_else(|| "2c".to_string());
+        let security = match version.to_ascii_lowercase().trim_start_matches('v') {
+            "2" | "2c" => {
+                let community = text("community").unwrap_or_else(|| DEFAULT_COMMUNITY.to_string());
+                Security::V2c {
+                    write_community: text("write_community").unwrap_or_else(|| community.clone()),
+                    community,
+                }
+            },
+            "3" => Security::V3(Self::user(&text)?),
+            other => {
+                return Err(config_error(format!(
+                    "SNMP 'version' must be 2c or 3, not '{}'",
+                    other
+                )))
+            },
+        };
+
+        Ok(Self {
+            host,
+            port,
+            security,
+            response_timeout: Duration::from_millis(
+                number("response_timeout_ms", DEFAULT_RESPONSE_TIMEOUT_MS)?.max(1),
+            ),
+            retries: in_range("retries", number("retries", DEFAULT_RETRIES)?, 0, 10)? as u32,
+            max_oids: in_range(
+                "max_oids_per_request",
+                number("max_oids_per_request", DEFAULT_MAX_OIDS)?,
+                1,
+                128,
+            )? as usize,
+        })
+    }
+
+    fn user(text: &dyn Fn(&str) -> Option<String>) -> Result<UserSecurity> {
+        let config_error = |message: String| ComSrvError::ConfigError(message);
+        let user_name =
+            text("username").ok_or_else(|| config_error("SNMPv3 requires 'username'".into()))?;
+        let auth_password = text("auth_password");
+        let priv_password = text("priv_password");
+        let level = match text("security_level") {
+            Some(level) => SecurityLevel::parse(&level).ok_or_else(|| {
+                config_error(format!(
+                    "'security_level' must be noAuthNoPriv, authNoPriv or authPriv, not '{}'",
+                    level
+                ))
+            })?,
+            None if priv_password.is_some() => SecurityLevel::AuthPriv,
+            None if auth_password.is_some() => SecurityLevel::AuthNoPriv,
+            None => SecurityLevel::NoAuthNoPriv,
+        };
+        let password = |key: &str, value: Option<String>| match value {
+            Some(p) if p.len() >= usm::MIN_PASSWORD_LEN => Ok(p),
+            Some(_) => Err(config_error(format!(
+                "'{}' must have at least {} characters",
+                key,
+                usm::MIN_PASSWORD_LEN
+            ))),
+            None => Err(config_error(format!(
+                "security level {:?} requires '{}'",
+                level, key
+            ))),
+        };
+
+        let auth = match level {
+            SecurityLevel::NoAuthNoPriv => None,
+            _ => {
+                let protocol =
+                    AuthProtocol::parse(&text("auth_protocol").unwrap_or_else(|| "SHA".into()))
+                        .map_err(|e| config_error(e.to_string()))?;
+                Some((protocol, password("auth_password", auth_password)?))
+            },
+        };
+        let privacy = match level {
+            SecurityLevel::AuthPriv => {
+                let protocol =
+                    PrivProtocol::parse(&text("priv_protocol").unwrap_or_else(|| "AES".into()))
+                        .map_err(|e| config_error(e.to_string()))?;
+                Some((protocol, password("priv_password", priv_password)?))
+            },
+            _ => None,
+        };
+        Ok(UserSecurity {
+            user_name,
+            auth,
+            privacy,
+            context_name: text("context_name").unwrap_or_default(),
+        })
+    }
+}
+
+// ============================================================================
+// Point mapping
+// ============================================================================
+
+/// Value a point reads from a variable
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Reading {
+    internal_id: u32,
+    /// Signal is 1 while the variable equals this value
+    on_value: Option<f64>,
+}
+
+impl Reading {
+    fn decode(&self, value: &codec::Value) -> std::result::Result<f64, String> {
+        if let Some(exception) = value.exception() {
+            return Err(exception.to_string());
+        }
+        let number = value
+            .as_f64()
+            .ok_or_else(|| format!("{:?} is not numeric", value))?;
+        Ok(match self.on_value {
+            Some(on) => f64::from(u8::from(number == on)),
+            None => number,
+        })
+    }
+}
+
+/// Variable a control or adjustment point writes
+#[derive(Debug, Clone, PartialEq)]
+struct Command {
+    oid: Oid,
+    set_type: SetType,
+}
+
+enum Mapped {
+    Read(Oid, Reading),
+    Write(Command),
+}
+
+fn parse_point(point_type: PointType, point: &Point) -> std::result::Result<Mapped, String> {
+    let mapping: JsonValue = point
+        .protocol_mappings
+        .as_deref()
+        .and_then(|m| serde_json::from_str(m).ok())
+        .ok_or("missing SNMP mapping")?;
+    let text = |key: &str| {
+        mapping
+            .get(key)
+            .and_then(|v| match v {
+                JsonValue::String(s) => Some(s.trim().to_string()),
+                JsonValue::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .filter(|v| !v.is_empty())
+    };
+    let oid = Oid::parse(&text("oid").ok_or("missing 'oid'")?).map_err(|e| e.to_string())?;
+
+    match point_type {
+        PointType::Telemetry | PointType::Signal => {
+            let on_value = text("on_value")
+                .filter(|_| point_type == PointType::Signal)
+                .map(|v| v.parse::<f64>().map_err(|_| "'on_value' must be a number"))
+                .transpose()?;
+            Ok(Mapped::Read(
+                oid,
+                Reading {
+                    internal_id: point_type.to_internal_id(point.point_id),
+                    on_value,
+                },
+            ))
+        },
+        PointType::Control | PointType::Adjustment => Ok(Mapped::Write(Command {
+            oid,
+            set_type: text("set_type")
+                .map(|t| SetType::parse(&t))
+                .transpose()
+                .map_err(|e| e.to_string())?
+                .unwrap_or(SetType::Integer),
+        })),
+    }
+}
+
+// ============================================================================
+// Runtime
+// ============================================================================
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Authoritative engine of the agent (SNMPv3)
+#[derive(Debug, Clone)]
+struct Engine {
+    id: Vec<u8>,
+    boots: u32,
+    time: u32,
+    /// When `time` was received
+    at: Instant,
+    auth_key: Vec<u8>,
+    priv_key: Vec<u8>,
+}
+
+impl Engine {
+    fn time_now(&self) -> u32 {
+        self.time
+            .saturating_add(self.at.elapsed().as_secs().min(u64::from(u32::MAX)) as u32)
+    }
+}
+
+/// Answer of the agent to a request
+enum Reply {
+    Response(Pdu),
+    /// usmStats counter the agent reported (SNMPv3)
+    Report(u32),
+}
+
+/// SNMP manager of one agent
+pub struct SnmpRuntime {
+    id: u32,
+    name: String,
+    config: SnmpConfig,
+    /// Variables read each poll -> points
+    reads: BTreeMap<Oid, Vec<Reading>>,
+    controls: HashMap<u32, Command>,
+    adjustments: HashMap<u32, Command>,
+    socket: Option<UdpSocket>,
+    request_id: i32,
+    /// Password keys (Ku) of the v3 user, derived on first connect
+    password_keys: Option<(Vec<u8>, Vec<u8>)>,
+    engine: Option<Engine>,
+    /// Salt of the next encrypted message
+    salt: u64,
+    /// `sysUpTime.0` read on connect, in hundredths of a second
+    sys_uptime: Option<f64>,
+    diagnostics: Diagnostics,
+}
+
+impl SnmpRuntime {
+    /// Build the runtime of an `snmp` channel
+    pub fn from_runtime_config(runtime_config: &RuntimeChannelConfig) -> Result<Self> {
+        let channel_id = runtime_config.id();
+        let config = SnmpConfig::from_parameters(&runtime_config.base.parameters)
+            .map_err(|e| ComSrvError::ConfigError(format!("Ch{}: {}", channel_id, e)))?;
+
+        let mut reads: BTreeMap<_, Vec<Reading>> = BTreeMap::new();
+        let mut controls = HashMap::new();
+        let mut adjustments = HashMap::new();
+        for (point_type, point) in runtime_config.points() {
+            match parse_point(point_type, point) {
+                Ok(Mapped::Read(oid, reading)) => reads.entry(oid).or_default().push(reading),
+                Ok(Mapped::Write(command)) => {
+                    let commands = match point_type {
+                        PointType::Control => &mut controls,
+                        _ => &mut adjustments,
+                    };
+                    commands.insert(point.point_id, command);
+                },
+                Err(e) => warn!(
+                    "Ch{} {}{} skipped: {}",
+                    channel_id,
+                    point_type.as_str(),
+                    point.point_id,
+                    e
+                ),
+            }
+        }
+        debug!(
+            "Ch{} SNMP: {} OIDs, {} controls, {} adjustments",
+            channel_id,
+            reads.len(),
+            controls.len(),
+            adjustments.len()
+        );
+
+        Ok(Self {
+            id: channel_id,
+            name: runtime_config.name().to_string(),
+            config,
+            reads,
+            controls,
+            adjustments,
+            socket: None,
+            request_id: rand::random::<u16>() as i32,
+            password_keys: None,
+            engine: None,
+            salt: rand::random(),
+            sys_uptime: None,
+            diagnostics: Diagnostics::new(PROTOCOL),
+        })
+    }
+
+    /// Record an error; connection failures drop the socket so the next
+    /// `connect()` discovers the agent again
+    fn fail(&mut self, error: GatewayError) -> GatewayError {
+        if record_failure(&mut self.diagnostics, &error) {
+            self.socket = None;
+            self.engine = None;
+        }
+        error
+    }
+
+    fn peer(&self) -> String {
+        format!("{}:{}", self.config.host, self.config.port)
+    }
+
+    fn user(&self) -> Option<&UserSecurity> {
+        match &self.config.security {
+            Security::V3(user) => Some(user),
+            Security::V2c { .. } => None,
+        }
+    }
+
+    fn next_request_id(&mut self) -> i32 {
+        self.request_id = self.request_id.wrapping_add(1) & i32::MAX;
+        self.request_id
+    }
+
+    /// Encode `pdu` as the message of the configured version
+    fn encode(&mut self, pdu: &Pdu) -> igw::Result<Vec<u8>> {
+        let protocol_error =
+            |e: crate::core::protocols::CodecError| GatewayError::Protocol(e.to_string());
+        let user = match &self.config.security {
+            Security::V2c {
+                community,
+                write_community,
+            } => {
+                let community = if pdu.kind == pdu::SET {
+                    write_community
+                } else {
+                    community
+                };
+                return Ok(codec::encode_community(community.as_bytes(), pdu));
+            },
+            Security::V3(user) => user.clone(),
+        };
+        let engine = self.engine.clone().ok_or(GatewayError::NotConnected)?;
+        let scoped = ScopedPdu {
+            context_engine_id: engine.id.clone(),
+            context_name: user.context_name.clone().into_bytes(),
+            pdu: pdu.clone(),
+        };
+        let mut message = V3Message {
+            msg_id: pdu.request_id,
+            max_size: MAX_MESSAGE_SIZE,
+            flags: flags::REPORTABLE,
+            usm: UsmParameters {
+                engine_id: engine.id.clone(),
+                engine_boots: engine.boots,
+                engine_time: engine.time_now(),
+                user_name: user.user_name.clone().into_bytes(),
+                ..Default::default()
+            },
+            data: MsgData::Plain(scoped.clone()),
+        };
+        if let Some((protocol, _)) = &user.auth {
+            message.flags |= flags::AUTH;
+            message.usm.auth_params = vec![0; protocol.mac_len()];
+        }
+        if let Some((protocol, _)) = &user.privacy {
+            self.salt = self.salt.wrapping_add(1);
+            let (ciphertext, params) = protocol
+                .encrypt(
+                    &engine.priv_key,
+                    engine.boots,
+                    message.usm.engine_time,
+                    self.salt,
+                    &scoped.encode(),
+                )
+                .map_err(protocol_error)?;
+            message.flags |= flags::PRIV;
+            message.usm.priv_params = params;
+            message.data = MsgData::Encrypted(ciphertext);
+        }
+
+        let mut bytes = message.encode();
+        if let Some((protocol, _)) = &user.auth {
+            let range = codec::auth_params_range(&bytes).map_err(protocol_error)?;
+            let mac = protocol
+                .mac(&engine.auth_key, &bytes)
+                .map_err(protocol_error)?;
+            bytes[range].copy_from_slice(&mac);
+        }
+        Ok(bytes)
+    }
+
+    /// Decode a datagram into (request-id, reply); None for datagrams that
+    /// are not a valid answer
+    fn decode(&mut self, datagram: &[u8]) -> Option<(i32, Reply)> {
+        let result = match self.user().cloned() {
+            None => codec::decode_community(datagram)
+                .map(|(_, pdu)| (pdu.request_id, Reply::Response(pdu)))
+                .map_err(|e| e.to_string()),
+            Some(user) => self.decode_v3(&user, datagram),
+        };
+        match result {
+            Ok(reply) => Some(reply),
+            Err(e) => {
+                debug!("Ch{} discarded datagram: {}", self.id, e);
+                None
+            },
+        }
+    }
+
+    fn decode_v3(
+        &mut self,
+        user: &UserSecurity,
+        datagram: &[u8],
+    ) -> std::result::Result<(i32, Reply), String> {
+        let message = V3Message::decode(datagram).map_err(|e| e.to_string())?;
+        let authenticated = message.flags & flags::AUTH != 0;
+        if authenticated {
+            let (protocol, engine) = match (&user.auth, &self.engine) {
+                (Some((protocol, _)), Some(engine)) => (protocol, engine),
+                _ => return Err("authenticated message before discovery".into()),
+            };
+            let range = codec::auth_params_range(datagram).map_err(|e| e.to_string())?;
+            let mut zeroed = datagram.to_vec();
+            zeroed[range.clone()].fill(0);
+            let mac = protocol
+                .mac(&engine.auth_key, &zeroed)
+                .map_err(|e| e.to_string())?;
+            if mac != datagram[range] {
+                return Err("wrong digest".into());
+            }
+        }
+
+        let scoped = match message.data {
+            MsgData::Plain(scoped) => scoped,
+            MsgData::Encrypted(ciphertext) => {
+                let (protocol, engine) = match (&user.privacy, &self.engine) {
+                    (Some((protocol, _)), Some(engine)) if authenticated => (protocol, engine),
+                    _ => return Err("unexpected encrypted message".into()),
+                };
+                let plaintext = protocol
+                    .decrypt(
+                        &engine.priv_key,
+                        message.usm.engine_boots,
+                        message.usm.engine_time,
+                        &message.usm.priv_params,
+                        &ciphertext,
+                    )
+                    .map_err(|e| e.to_string())?;
+                ScopedPdu::decode(&plaintext).map_err(|e| format!("decryption: {}", e))?
+            },
+        };
+
+        // Authenticated messages carry the agent's current clock
+        if authenticated {
+            if let Some(engine) = self.engine.as_mut() {
+                engine.boots = message.usm.engine_boots;
+                engine.time = message.usm.engine_time;
+                engine.at = Instant::now();
+            }
+        }
+
+        match scoped.pdu.kind {
+            pdu::REPORT => {
+                let counter = scoped
+                    .pdu
+                    .varbinds
+                    .first()
+                    .and_then(
+                        |(oid, _)| match oid.0.split_at(USM_STATS.len().min(oid.0.len())) {
+                            (prefix, [counter, ..]) if prefix == USM_STATS => Some(*counter),
+                            _ => None,
+                        },
+                    )
+                    .unwrap_or(0);
+                // Reports of discovery and time synchronization carry the engine
+                if counter == UNKNOWN_ENGINE_IDS || counter == NOT_IN_TIME_WINDOWS {
+                    self.on_engine_report(&message.usm);
+                }
+                Ok((message.msg_id, Reply::Report(counter)))
+            },
+            _ if user.auth.is_some() && !authenticated => {
+                Err("unauthenticated response to an authenticated request".into())
+            },
+            _ => Ok((message.msg_id, Reply::Response(scoped.pdu))),
+        }
+    }
+
+    /// Adopt the engine ID, boots and time an agent reported
+    fn on_engine_report(&mut self, usm: &UsmParameters) {
+        if usm.engine_id.is_empty() {
+            return;
+        }
+        if let Some(engine) = self.engine.as_mut().filter(|e| e.id == usm.engine_id) {
+            engine.boots = usm.engine_boots;
+            engine.time = usm.engine_time;
+            engine.at = Instant::now();
+            return;
+        }
+        let Some(user) = self.user().cloned() else {
+            return;
+        };
+        let (auth_ku, priv_ku) = self.password_keys.get_or_insert_with(|| {
+            let protocol = user.auth.as_ref().map(|(p, _)| *p);
+            let key = |password: Option<&String>| match (protocol, password) {
+                (Some(protocol), Some(password)) => protocol.password_key(password.as_bytes()),
+                _ => Vec::new(),
+            };
+            (
+                key(user.auth.as_ref().map(|(_, p)| p)),
+                key(user.privacy.as_ref().map(|(_, p)| p)),
+            )
+        });
+        let localize = |key: &[u8]| match &user.auth {
+            Some((protocol, _)) if !key.is_empty() => protocol.localize(key, &usm.engine_id),
+            _ => Vec::new(),
+        };
+        self.engine = Some(Engine {
+            id: usm.engine_id.clone(),
+            boots: usm.engine_boots,
+            time: usm.engine_time,
+            at: Instant::now(),
+            auth_key: localize(auth_ku),
+            priv_key: localize(priv_ku),
+        });
+    }
+
+    /// Send a request and wait for its answer, retransmitting after each
+    /// response timeout
+    async fn exchange(&mut self, message: &[u8], request_id: i32) -> igw::Result<Reply> {
+        for attempt in 0..=self.config.retries {
+            let socket = self.socket.as_ref().ok_or(GatewayError::NotConnected)?;
+            socket
+                .send(message)
+                .await
+                .map_err(|e| GatewayError::Connection(format!("send: {}", e)))?;
+            let deadline = Instant::now() + self.config.response_timeout;
+            let mut buf = vec![0u8; MAX_DATAGRAM];
+            loop {
+                let socket = self.socket.as_ref().ok_or(GatewayError::NotConnected)?;
+                let n = match timeout_at(deadline, socket.recv(&mut buf)).await {
+                    Err(_) => break,
+                    Ok(Ok(n)) => n,
+                    Ok(Err(e)) => return Err(GatewayError::Connection(format!("receive: {}", e))),
+                };
+                match self.decode(&buf[..n]) {
+                    Some((id, reply)) if id == request_id => return Ok(reply),
+                    Some((id, _)) => debug!("Ch{} stale answer {} ignored", self.id, id),
+                    None => {},
+                }
+            }
+            if attempt < self.config.retries {
+                debug!("Ch{} request {} unanswered, retrying", self.id, request_id);
+            }
+        }
+        Err(GatewayError::ReadTimeout)
+    }
+
+    /// Send `pdu` and return the response
+    ///
+    /// A v3 agent that reports the engine clock or ID out of date is
+    /// answered with the values it reported, once.
+    async fn request(&mut self, mut pdu: Pdu) -> igw::Result<Pdu> {
+        for _ in 0..2 {
+            pdu.request_id = self.next_request_id();
+            let message = self.encode(&pdu)?;
+            match self.exchange(&message, pdu.request_id).await? {
+                Reply::Response(response) => return Ok(response),
+                Reply::Report(NOT_IN_TIME_WINDOWS | UNKNOWN_ENGINE_IDS) => continue,
+                Reply::Report(counter) => return Err(report_error(counter)),
+            }
+        }
+        Err(GatewayError::Connection(format!(
+            "{}: engine time not synchronized",
+            self.peer()
+        )))
+    }
+
+    /// Learn the agent's engine ID, boots and time (RFC 3414 4)
+    async fn discover(&mut self) -> igw::Result<()> {
+        let request_id = self.next_request_id();
+        let probe = V3Message {
+            msg_id: request_id,
+            max_size: MAX_MESSAGE_SIZE,
+            flags: flags::REPORTABLE,
+            usm: UsmParameters::default(),
+            data: MsgData::Plain(ScopedPdu {
+                context_engine_id: Vec::new(),
+                context_name: Vec::new(),
+                pdu: Pdu::get(request_id, &[]),
+            }),
+        };
+        self.engine = None;
+        match self.exchange(&probe.encode(), request_id).await? {
+            Reply::Report(_) if self.engine.is_some() => Ok(()),
+            _ => Err(GatewayError::Connection(format!(
+                "{}: no engine discovery report",
+                self.peer()
+            ))),
+        }
+    }
+
+    async fn get(&mut self, oids: &[Oid]) -> igw::Result<Pdu> {
+        self.request(Pdu::get(0, oids)).await
+    }
+
+    /// Read `oids`, each with its own outcome
+    async fn read_chunk(
+        &mut self,
+        oids: &[Oid],
+    ) -> igw::Result<Vec<(Oid, std::result::Result<codec::Value, String>)>> {
+        let response = self.get(oids).await?;
+        if response.error_status == 0 {
+            let mut values: HashMap<Oid, codec::Value> = response.varbinds.into_iter().collect();
+            return Ok(oids
+                .iter()
+                .map(|oid| {
+                    let value = values
+                        .remove(oid)
+                        .ok_or_else(|| "missing from response".to_string());
+                    (oid.clone(), value)
+                })
+                .collect());
+        }
+        if oids.len() == 1 {
+            let error = codec::error_status_text(response.error_status).to_string();
+            return Ok(vec![(oids[0].clone(), Err(error))]);
+        }
+
+        // tooBig, or a v1-style agent failing the whole request for one
+        // variable: read the variables one by one
+        debug!(
+            "Ch{} GetRequest of {} OIDs: {}, reading them one by one",
+            self.id,
+            oids.len(),
+            codec::error_status_text(response.error_status)
+        );
+        let mut outcomes = Vec::with_capacity(oids.len());
+        for oid in oids {
+            outcomes.extend(Box::pin(self.read_chunk(std::slice::from_ref(oid))).await?);
+        }
+        Ok(outcomes)
+    }
+
+    async fn read_all(&mut self, batch: &mut DataBatch, failures: &mut Vec<PointFailure>) {
+        let oids: Vec<Oid> = self.reads.keys().cloned().collect();
+        for chunk in oids.chunks(self.config.max_oids) {
+            match self.read_chunk(chunk).await {
+                Ok(outcomes) => {
+                    for (oid, outcome) in outcomes {
+                        let Some(readings) = self.reads.get(&oid) else {
+                            continue;
+                        };
+                        for reading in readings {
+                            let value = match &outcome {
+                                Ok(value) => reading.decode(value),
+                                Err(e) => Err(e.clone()),
+                            };
+                            match value {
+                                Ok(value) => batch.add(DataPoint::new(reading.internal_id, value)),
+                                Err(e) => failures.push(PointFailure::with_error(
+                                    reading.internal_id,
+                                    format!("{}: {}", oid, e),
+                                )),
+                            }
+                        }
+                    }
+                },
+                Err(e) => {
+                    let e = self.fail(e);
+                    for oid in chunk {
+                        failures.extend(
+                            self.reads
+                                .get(oid)
+                                .into_iter()
+                                .flatten()
+                                .map(|r| PointFailure::with_error(r.internal_id, e.to_string())),
+                        );
+                    }
+                    if self.socket.is_none() {
+                        break;
+                    }
+                },
+            }
+        }
+    }
+
+    async fn set(&mut self, command: &Command, value: f64) -> igw::Result<()> {
+        let value = command
+            .set_type
+            .value(value)
+            .map_err(|e| GatewayError::Protocol(format!("{}: {}", command.oid, e)))?;
+        let response = self
+            .request(Pdu::set(0, vec![(command.oid.clone(), value)]))
+            .await?;
+        if response.error_status != 0 {
+            return Err(GatewayError::Protocol(format!(
+                "SET {}: {}",
+                command.oid,
+                codec::error_status_text(response.error_status)
+            )));
+        }
+        Ok(())
+    }
+
+    async fn write(&mut self, point_type: PointType, values: &[(u32, f64)]) -> igw::Result<usize> {
+        let mut written = 0;
+        let mut last_error = None;
+        for &(internal_id, value) in values {
+            let point_id = PointType::from_internal_id(internal_id).1;
+            let commands = match point_type {
+                PointType::Control => &self.controls,
+                _ => &self.adjustments,
+            };
+            let Some(command) = commands.get(&point_id).cloned() else {
+                last_error = Some(GatewayError::PointNotFound(format!(
+                    "{}{}",
+                    point_type.as_str(),
+                    point_id
+                )));
+                continue;
+            };
+            match self.set(&command, value).await {
+                Ok(()) => written += 1,
+                Err(e) => {
+                    let e = self.fail(e);
+                    if self.socket.is_none() {
+                        return Err(e);
+                    }
+                    last_error = Some(e);
+                },
+            }
+        }
+        self.diagnostics.write_count += written as u64;
+        match last_error {
+            Some(e) if written == 0 => Err(e),
+            _ => Ok(written),
+        }
+    }
+
+    async fn after_connect(&mut self) -> igw::Result<()> {
+        if self.user().is_some() {
+            self.discover().await?;
+        }
+        let response = self.get(&[Oid(SYS_UPTIME.to_vec())]).await?;
+        self.sys_uptime = response
+            .varbinds
+            .first()
+            .and_then(|(_, value)| value.as_f64());
+        Ok(())
+    }
+}
+
+/// Error of a usmStats report (RFC 3414 5)
+fn report_error(counter: u32) -> GatewayError {
+    let reason = match counter {
+        1 => "unsupported security level",
+        3 => "unknown user name",
+        5 => "wrong digest (check auth protocol and password)",
+        6 => "decryption error (check privacy protocol and password)",
+        _ => "report",
+    };
+    GatewayError::Connection(format!("SNMPv3 {}", reason))
+}
+
+#[async_trait]
+impl ChannelRuntime for SnmpRuntime {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn protocol(&self) -> &str {
+        PROTOCOL
+    }
+
+    fn is_event_driven(&self) -> bool {
+        false
+    }
+
+    async fn connect(&mut self) -> igw::Result<()> {
+        self.diagnostics.connection_state = ConnectionState::Connecting;
+        let address = self.peer();
+        let socket = match UdpSocket::bind(("0.0.0.0", 0)).await {
+            Ok(socket) => socket,
+            Err(e) => return Err(self.fail(GatewayError::Connection(format!("bind: {}", e)))),
+        };
+        if let Err(e) = socket
+            .connect((self.config.host.as_str(), self.config.port))
+            .await
+        {
+            return Err(self.fail(GatewayError::Connection(format!("{}: {}", address, e))));
+        }
+        self.socket = Some(socket);
+
+        if let Err(e) = self.after_connect().await {
+            self.socket = None;
+            self.engine = None;
+            let e = match e {
+                GatewayError::Connection(_) => e,
+                other => GatewayError::Connection(format!("{}: {}", address, other)),
+            };
+            return Err(self.fail(e));
+        }
+        self.diagnostics.connection_state = ConnectionState::Connected;
+        info!(
+            "Ch{} SNMP connected to {}{}",
+            self.id,
+            address,
+            self.engine
+                .as_ref()
+                .map(|e| format!(", engine {}", hex(&e.id)))
+                .unwrap_or_default()
+        );
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> igw::Result<()> {
+        self.socket = None;
+        self.engine = None;
+        self.diagnostics.connection_state = ConnectionState::Disconnected;
+        Ok(())
+    }
+
+    async fn poll_once(&mut self) -> PollResult {
+        if self.socket.is_none() {
+            return PollResult::failed(vec![PointFailure::new(0, "not connected")]);
+        }
+        let mut batch = DataBatch::default();
+        let mut failures = Vec::new();
+        self.read_all(&mut batch, &mut failures).await;
+        self.diagnostics.read_count += 1;
+        PollResult::partial(batch, failures)
+    }
+
+    async fn write_control(&mut self, commands: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Control, commands).await
+    }
+
+    async fn write_adjustment(&mut self, adjustments: &[(u32, f64)]) -> igw::Result<usize> {
+        self.write(PointType::Adjustment, adjustments).await
+    }
+
+    fn subscribe(&self) -> Option<DataEventReceiver> {
+        None
+    }
+
+    async fn start_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn stop_events(&mut self) -> igw::Result<()> {
+        Ok(())
+    }
+
+    async fn diagnostics(&self) -> igw::Result<Diagnostics> {
+        let mut diagnostics = self.diagnostics.clone();
+        diagnostics.extra = json!({
+            "peer": self.peer(),
+            "version": if self.user().is_some() { "3" } else { "2c" },
+            "engine_id": self.engine.as_ref().map(|e| hex(&e.id)),
+            "sys_uptime_ticks": self.sys_uptime,
+            "polled_oids": self.reads.len(),
+        });
+        Ok(diagnostics)
+    }
+}
+
+// ============================================================================
+// Plugin metadata
+// ============================================================================
+
+fn parameters() -> Vec<ParameterMetadata> {
+    vec![
+        ParameterMetadata::required("host", "Host", "Agent IP address", ParameterType::String),
+        ParameterMetadata::optional(
+            "port",
+            "Port",
+            "Agent UDP port",
+            ParameterType::Integer,
+            json!(DEFAULT_PORT),
+        ),
+        ParameterMetadata::optional(
+            "version",
+            "Version",
+            "SNMP version: 2c or 3",
+            ParameterType::String,
+            json!("2c"),
+        ),
+        ParameterMetadata::optional(
+            "community",
+            "Community",
+            "Read community (v2c)",
+            ParameterType::String,
+            json!(DEFAULT_COMMUNITY),
+        ),
+        ParameterMetadata::optional(
+            "write_community",
+            "Write Community",
+            "Community of SET requests (v2c); empty uses the read community",
+            ParameterType::String,
+            json!(""),
+        ),
+        ParameterMetadata::optional(
+            "username",
+            "User Name",
+            "USM user (v3)",
+            ParameterType::String,
+            json!(""),
+        ),
+        ParameterMetadata::optional(
+            "security_level",
+            "Security Level",
+            "noAuthNoPriv, authNoPriv or authPriv (v3); empty follows the passwords given",
+            ParameterType::String,
+            json!(""),
+        ),
+        ParameterMetadata::optional(
+            "auth_protocol",
+            "Auth Protocol",
+            "MD5, SHA or SHA256 (v3)",
+            ParameterType::String,
+            json!("SHA"),
+        ),
+        ParameterMetadata::optional(
+            "auth_password",
+            "Auth Password",
+            "Authentication password, at least 8 characters (v3)",
+            ParameterType::String,
+            json!(""),
+        ),
+        ParameterMetadata::optional(
+            "priv_protocol",
+            "Privacy Protocol",
+            "DES or AES (v3)",
+            ParameterType::String,
+            json!("AES"),
+        ),
+        ParameterMetadata::optional(
+            "priv_password",
+            "Privacy Password",
+            "Encryption password, at least 8 characters (v3)",
+            ParameterType::String,
+            json!(""),
+        ),
+        ParameterMetadata::optional(
+            "context_name",
+            "Context Name",
+            "SNMPv3 context (v3)",
+            ParameterType::String,
+            json!(""),
+        ),
+        ParameterMetadata::optional(
+            "response_timeout_ms",
+            "Response Timeout (ms)",
+            "Time to wait for an agent response",
+            ParameterType::Integer,
+            json!(DEFAULT_RESPONSE_TIMEOUT_MS),
+        ),
+        ParameterMetadata::optional(
+            "retries",
+            "Retries",
+            "Retransmissions of an unanswered request",
+            ParameterType::Integer,
+            json!(DEFAULT_RETRIES),
+        ),
+        ParameterMetadata::optional(
+            "max_oids_per_request",
+            "OIDs per Request",
+            "Variables read by one GetRequest",
+            ParameterType::Integer,
+            json!(DEFAULT_MAX_OIDS),
+        ),
+    ]
+}
+
+crate::protocol_plugin! {
+    /// SNMP v2c/v3 manager (in-tree runtime)
+    pub struct SnmpPlugin {
+        name: PROTOCOL,
+        aliases: &["snmp_v2c", "snmpv2c", "snmpv3"],
+        display_name: "SNMP",
+        description: "UPS, switches and PDUs: GET polling and SET controls over SNMP v2c/v3",
+        parameters: parameters(),
+        mapping_columns: &[
+            MappingColumn::string("oid", "Object identifier, e.g. 1.3.6.1.2.1.33.1.2.4.0")
+                .required(),
+            MappingColumn::float("on_value", "Signal is 1 while the variable equals this value"),
+            MappingColumn::string("set_type", "Syntax of written values")
+                .default_value("integer")
+                .choices(&["integer", "unsigned", "timeticks", "string"]),
+        ],
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use crate::core::protocols::polled_value;
+    use codec::Value;
+    use tokio::sync::mpsc;
+
+    const ENGINE_ID: [u8; 9] = [0x80, 0x00, 0x1F, 0x88, 0x80, 0x01, 0x02, 0x03, 0x04];
+
+    fn point<T: serde::de::DeserializeOwned>(point_id: u32, mapping: JsonValue) -> T {
+        serde_json::from_value(json!({
+            "point_id": point_id,
+            "signal_name": format!("p{}", point_id),
+            "protocol_mappings": mapping.to_string(),
+        }))
+        .unwrap()
+    }
+
+    fn oid(text: &str) -> Oid {
+        Oid::parse(text).unwrap()
+    }
+
+    /// Variables of the fake UPS
+    fn value_of(name: &Oid) -> Value {
+        match name.to_string().as_str() {
+            "1.3.6.1.2.1.1.3.0" => Value::TimeTicks(123_456),
+            // upsBatteryVoltage (0.1 V), upsOutputSource, upsInputLineBads
+            "1.3.6.1.2.1.33.1.2.5.0" => Value::Integer(545),
+            "1.3.6.1.2.1.33.1.4.1.0" => Value::Integer(5),
+            "1.3.6.1.2.1.33.1.3.1.0" => Value::Counter32(2),
+            "1.3.6.1.4.1.9999.1.0" => Value::OctetString(b"230.5".to_vec()),
+            _ => Value::NoSuchObject,
+        }
+    }
+
+    fn answer(request: &Pdu, sets: &mpsc::UnboundedSender<(Oid, Value)>) -> Pdu {
+        let mut response = request.clone();
+        response.kind = pdu::RESPONSE;
+        match request.kind {
+            pdu::GET => {
+                // The agent only takes 2 variables per request
+                if request.varbinds.len() > 2 {
+                    response.error_status = 1;
+                    return response;
+                }
+                for (name, value) in response.varbinds.iter_mut() {
+                    *value = value_of(name);
+                }
+            },
+            pdu::SET => {
+                for (i, (name, value)) in request.varbinds.iter().enumerate() {
+                    if name.to_string().starts_with("1.3.6.1.2.1.33.1.8") {
+                        sets.send((name.clone(), value.clone())).unwrap();
+                    } else {
+                        response.error_status = 17;
+                        response.error_index = i as u32 + 1;
+                    }
+                }
+            },
+            _ => response.error_status = 5,
+        }
+        response
+    }
+
+    /// SNMPv3 agent side of the USM for one user
+    struct Agent {
+        user: UserSecurity,
+        auth_key: Vec<u8>,
+        priv_key: Vec<u8>,
+    }
+
+    impl Agent {
+        fn new(user: UserSecurity) -> Self {
+            let (auth, _) = user.auth.clone().unwrap();
+            let key =
+                |password: &str| auth.localize(&auth.password_key(password.as_bytes()), &ENGINE_ID);
+            Self {
+                auth_key: key(&user.auth.as_ref().unwrap().1),
+                priv_key: key(&user.privacy.as_ref().unwrap().1),
+                user,
+            }
+        }
+
+        fn handle(&self, datagram: &[u8], sets: &mpsc::UnboundedSender<(Oid, Value)>) -> Vec<u8> {
+            let request = V3Message::decode(datagram).unwrap();
+            let (auth, _) = self.user.auth.clone().unwrap();
+            let (privacy, _) = self.user.privacy.clone().unwrap();
+            let mut usm = UsmParameters {
+                engine_id: ENGINE_ID.to_vec(),
+                engine_boots: 7,
+                engine_time: 5000,
+                user_name: request.usm.user_name.clone(),
+                ..Default::default()
+            };
+            let report = |counter: u32, usm: UsmParameters| {
+                let mut name = USM_STATS.to_vec();
+                name.extend([counter, 0]);
+                V3Message {
+                    msg_id: request.msg_id,
+                    max_size: MAX_MESSAGE_SIZE,
+                    flags: 0,
+                    usm,
+                    data: MsgData::Plain(ScopedPdu {
+                        context_engine_id: ENGINE_ID.to_vec(),
+                        context_name: Vec::new(),
+                        pdu: Pdu {
+                            kind: pdu::REPORT,
+                            request_id: request.msg_id,
+                            error_status: 0,
+                            error_index: 0,
+                            varbinds: vec![(Oid(name), Value::Counter32(1))],
+                        },
+                    }),
+                }
+                .encode()
+            };
+            if request.usm.engine_id.is_empty() {
+                // Discovery: engine clock deliberately left out
+                usm.engine_time = 0;
+                usm.engine_boots = 0;
+                return report(UNKNOWN_ENGINE_IDS, usm);
+            }
+
+            let range = codec::auth_params_range(datagram).unwrap();
+            let mut zeroed = datagram.to_vec();
+            zeroed[range.clone()].fill(0);
+            if auth.mac(&self.auth_key, &zeroed).unwrap() != datagram[range] {
+                return report(5, usm);
+            }
+            if request.usm.engine_time.abs_diff(5000) > 150 {
+                return report(NOT_IN_TIME_WINDOWS, usm);
+            }
+            let MsgData::Encrypted(ciphertext) = &request.data else {
+                return report(1, usm);
+            };
+            let plaintext = privacy
+                .decrypt(
+                    &self.priv_key,
+                    request.usm.engine_boots,
+                    request.usm.engine_time,
+                    &request.usm.priv_params,
+                    ciphertext,
+                )
+                .unwrap();
+            let scoped = ScopedPdu::decode(&plaintext).unwrap();
+            let response = ScopedPdu {
+                pdu: answer(&scoped.pdu, sets),
+                ..scoped
+            };
+            let (ciphertext, params) = privacy
+                .encrypt(&self.priv_key, 7, 5000, 99, &response.encode())
+                .unwrap();
+            usm.auth_params = vec![0; auth.mac_len()];
+            usm.priv_params = params;
+            let mut bytes = V3Message {
+                msg_id: request.msg_id,
+                max_size: MAX_MESSAGE_SIZE,
+                flags: flags::AUTH | flags::PRIV,
+                usm,
+                data: MsgData::Encrypted(ciphertext),
+            }
+            .encode();
+            let range = codec::auth_params_range(&bytes).unwrap();
+            let mac = auth.mac(&self.auth_key, &bytes).unwrap();
+            bytes[range].copy_from_slice(&mac);
+            bytes
+        }
+    }
+
+    /// Fake agent on a loopback UDP port; returns the port and its SETs
+    async fn agent(user: Option<UserSecurity>) -> (u16, mpsc::UnboundedReceiver<(Oid, Value)>) {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        let (sets, received) = mpsc::unbounded_channel();
+        let agent = user.map(Agent::new);
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; MAX_DATAGRAM];
+            while let Ok((n, from)) = socket.recv_from(&mut buf).await {
+                let reply = match &agent {
+                    Some(agent) => agent.handle(&buf[..n], &sets),
+                    None => {
+                        let (community, request) = codec::decode_community(&buf[..n]).unwrap();
+                        let expected: &[u8] = if request.kind == pdu::SET {
+                            b"private"
+                        } else {
+                            b"public"
+                        };
+                        if community != expected {
+                            continue;
+                        }
+                        codec::encode_community(&community, &answer(&request, &sets))
+                    },
+                };
+                let _ = socket.send_to(&reply, from).await;
+            }
+        });
+        (port, received)
+    }
+
+    fn channel(port: u16, parameters: JsonValue) -> RuntimeChannelConfig {
+        let mut all = json!({
+            "host": "127.0.0.1",
+            "port": port,
+            "response_timeout_ms": 500,
+            "retries": 0,
+            "max_oids_per_request": "4",
+        });
+        all.as_object_mut()
+            .unwrap()
+            .extend(parameters.as_object().unwrap().clone());
+        let mut config = RuntimeChannelConfig::from_base(
+            serde_json::from_value(json!({
+                "id": 52,
+                "name": "ups",
+                "protocol": PROTOCOL,
+                "parameters": all,
+            }))
+            .unwrap(),
+        );
+        config.telemetry_points = vec![
+            point(1, json!({"oid": "1.3.6.1.2.1.33.1.2.5.0"})),
+            point(2, json!({"oid": ".1.3.6.1.2.1.33.1.3.1.0"})),
+            point(3, json!({"oid": "1.3.6.1.4.1.9999.1.0"})),
+            point(4, json!({"oid": "1.3.6.1.4.1.9999.2.0"})),
+            point(5, json!({"oid": "not an oid"})),
+        ];
+        config.signal_points = vec![point(
+            1,
+            json!({"oid": "1.3.6.1.2.1.33.1.4.1.0", "on_value": 5}),
+        )];
+        // upsShutdownAfterDelay, upsAutoRestart
+        config.control_points = vec![point(1, json!({"oid": "1.3.6.1.2.1.33.1.8.2.0"}))];
+        config.adjustment_points = vec![
+            point(
+                1,
+                json!({"oid": "1.3.6.1.2.1.33.1.8.1.0", "set_type": "gauge"}),
+            ),
+            point(2, json!({"oid": "1.3.6.1.2.1.1.5.0", "set_type": "string"})),
+        ];
+        config
+    }
+
+    async fn exercise(runtime: &mut SnmpRuntime, sets: &mut mpsc::UnboundedReceiver<(Oid, Value)>) {
+        runtime.connect().await.unwrap();
+        assert_eq!(runtime.sys_uptime, Some(123_456.0));
+
+        // 5 OIDs in chunks of 4, which the agent refuses as tooBig
+        let result = runtime.poll_once().await;
+        assert_eq!(polled_value(&result, PointType::Telemetry, 1), Some(545.0));
+        assert_eq!(polled_value(&result, PointType::Telemetry, 2), Some(2.0));
+        assert_eq!(polled_value(&result, PointType::Telemetry, 3), Some(230.5));
+        assert_eq!(polled_value(&result, PointType::Signal, 1), Some(1.0));
+        assert_eq!(result.failures.len(), 1);
+        assert!(result.failures[0].error.contains("noSuchObject"));
+
+        let control = PointType::Control.to_internal_id(1);
+        assert_eq!(runtime.write_control(&[(control, 1.0)]).await.unwrap(), 1);
+        assert_eq!(
+            sets.recv().await.unwrap(),
+            (oid("1.3.6.1.2.1.33.1.8.2.0"), Value::Integer(1))
+        );
+        let adjustment = PointType::Adjustment.to_internal_id(1);
+        assert_eq!(
+            runtime
+                .write_adjustment(&[(adjustment, 60.0)])
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(sets.recv().await.unwrap().1, Value::Gauge32(60));
+
+        // Read-only variable
+        let error = runtime
+            .write_adjustment(&[(PointType::Adjustment.to_internal_id(2), 1.0)])
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("notWritable"));
+    }
+
+    #[tokio::test]
+    async fn test_v2c_poll_and_set() {
+        let (port, mut sets) = agent(None).await;
+        let config = channel(port, json!({"write_community": "private"}));
+        let mut runtime = SnmpRuntime::from_runtime_config(&config).unwrap();
+        assert_eq!(runtime.reads.len(), 5);
+        exercise(&mut runtime, &mut sets).await;
+    }
+
+    /// USM user the fake agent accepts
+    fn user(parameters: JsonValue) -> UserSecurity {
+        match SnmpConfig::from_parameters(&channel(161, parameters).base.parameters)
+            .unwrap()
+            .security
+        {
+            Security::V3(user) => user,
+            Security::V2c { .. } => panic!("v3 expected"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_v3_auth_priv() {
+        for (auth, privacy) in [("SHA", "AES"), ("MD5", "DES"), ("SHA256", "AES")] {
+            let parameters = json!({
+                "version": "v3",
+                "username": "ems",
+                "auth_protocol": auth,
+                "auth_password": "authpass123",
+                "priv_protocol": privacy,
+                "priv_password": "privpass123",
+            });
+            let (port, mut sets) = agent(Some(user(parameters.clone()))).await;
+            let mut runtime = SnmpRuntime::from_runtime_config(&channel(port, parameters)).unwrap();
+            exercise(&mut runtime, &mut sets).await;
+            assert_eq!(runtime.engine.as_ref().unwrap().boots, 7);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_v3_wrong_password() {
+        let parameters = |password: &str| {
+            json!({
+                "version": 3,
+                "username": "ems",
+                "auth_password": password,
+                "priv_password": "privpass123",
+            })
+        };
+        let (port, _sets) = agent(Some(user(parameters("authpass123")))).await;
+        let mut runtime =
+            SnmpRuntime::from_runtime_config(&channel(port, parameters("wrongpass123"))).unwrap();
+        let error = runtime.connect().await.unwrap_err();
+        assert!(error.to_string().contains("wrong digest"), "{}", error);
+    }
+
+    #[test]
+    fn test_config_parameters() {
+        let parameters = |value: JsonValue| -> HashMap<String, JsonValue> {
+            serde_json::from_value(value).unwrap()
+        };
+        let config = SnmpConfig::from_parameters(&parameters(json!({"host": "10.0.0.5"}))).unwrap();
+        assert_eq!(config.port, DEFAULT_PORT);
+        assert_eq!(
+            config.security,
+            Security::V2c {
+                community: "public".into(),
+                write_community: "public".into()
+            }
+        );
+
+        let config = SnmpConfig::from_parameters(&parameters(json!({
+            "host": "10.0.0.5",
+            "version": "3",
+            "username": "monitor",
+            "auth_password": "authpass123",
+        })))
+        .unwrap();
+        let Security::V3(user) = config.security else {
+            panic!("v3 expected");
+        };
+        assert_eq!(user.auth.unwrap().0, AuthProtocol::Sha1);
+        assert!(user.privacy.is_none());
+
+        for invalid in [
+            json!({"host": "h", "version": "1"}),
+            json!({"host": "h", "version": "3"}),
+            json!({"host": "h", "version": "3", "username": "u", "auth_password": "short"}),
+            json!({"host": "h", "version": "3", "username": "u", "security_level": "authPriv",
+                   "auth_password": "authpass123"}),
+        ] {
+            assert!(SnmpConfig::from_parameters(&parameters(invalid)).is_err());
+        }
+    }
+}
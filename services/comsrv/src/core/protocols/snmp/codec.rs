@@ -0,0 +1,894 @@
+//! SNMP message encoding
+//!
+//! BER elements with single-octet tags, the RFC 3416 PDUs a manager sends
+//! and receives, community-based v2c messages (RFC 1901) and v3 messages with
+//! the user-based security model parameters (RFC 3412, RFC 3414). Keys,
+//! digests and encryption of v3 messages are in [`super::usm`].
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::core::protocols::{error, CodecError};
+
+type Result<T> = std::result::Result<T, CodecError>;
+
+/// PDU types
+pub mod pdu {
+    pub const GET: u8 = 0xA0;
+    pub const GET_NEXT: u8 = 0xA1;
+    pub const RESPONSE: u8 = 0xA2;
+    pub const SET: u8 = 0xA3;
+    pub const REPORT: u8 = 0xA8;
+}
+
+mod tag {
+    pub const INTEGER: u8 = 0x02;
+    pub const OCTET_STRING: u8 = 0x04;
+    pub const NULL: u8 = 0x05;
+    pub const OBJECT_IDENTIFIER: u8 = 0x06;
+    pub const SEQUENCE: u8 = 0x30;
+    pub const IP_ADDRESS: u8 = 0x40;
+    pub const COUNTER32: u8 = 0x41;
+    pub const GAUGE32: u8 = 0x42;
+    pub const TIMETICKS: u8 = 0x43;
+    pub const OPAQUE: u8 = 0x44;
+    pub const COUNTER64: u8 = 0x46;
+    pub const NO_SUCH_OBJECT: u8 = 0x80;
+    pub const NO_SUCH_INSTANCE: u8 = 0x81;
+    pub const END_OF_MIB_VIEW: u8 = 0x82;
+}
+
+/// msgVersion of community-based SNMPv2c
+pub const VERSION_2C: i64 = 1;
+/// msgVersion of SNMPv3
+pub const VERSION_3: i64 = 3;
+/// msgSecurityModel of the user-based security model
+pub const USM_SECURITY_MODEL: i64 = 3;
+
+/// msgFlags bits
+pub mod flags {
+    pub const AUTH: u8 = 0x01;
+    pub const PRIV: u8 = 0x02;
+    pub const REPORTABLE: u8 = 0x04;
+}
+
+// ============================================================================
+// BER
+// ============================================================================
+
+fn encode_length(len: usize, out: &mut Vec<u8>) {
+    if len < 0x80 {
+        out.push(len as u8);
+    } else if len <= 0xFF {
+        out.extend_from_slice(&[0x81, len as u8]);
+    } else if len <= 0xFFFF {
+        out.push(0x82);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        out.push(0x83);
+        out.extend_from_slice(&(len as u32).to_be_bytes()[1..]);
+    }
+}
+
+/// Encode one BER element
+pub fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(content.len() + 4);
+    out.push(tag);
+    encode_length(content.len(), &mut out);
+    out.extend_from_slice(content);
+    out
+}
+
+/// One decoded BER element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tlv<'a> {
+    pub tag: u8,
+    pub content: &'a [u8],
+}
+
+/// Decode the first BER element of `buf` and return it with the rest
+pub fn read_tlv(buf: &[u8]) -> Result<(Tlv<'_>, &[u8])> {
+    let (&tag, rest) = buf
+        .split_first()
+        .ok_or_else(|| error("truncated BER element"))?;
+    if tag & 0x1F == 0x1F {
+        return Err(error(format!("multi-octet BER tag 0x{:02X}", tag)));
+    }
+    let (&first, rest) = rest
+        .split_first()
+        .ok_or_else(|| error("truncated BER length"))?;
+    let (len, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let n = (first & 0x7F) as usize;
+        if n == 0 || n > 4 || rest.len() < n {
+            return Err(error(format!(
+                "unsupported BER length of tag 0x{:02X}",
+                tag
+            )));
+        }
+        let len = rest[..n]
+            .iter()
+            .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+        (len, &rest[n..])
+    };
+    if rest.len() < len {
+        return Err(error(format!("BER element 0x{:02X} truncated", tag)));
+    }
+    Ok((
+        Tlv {
+            tag,
+            content: &rest[..len],
+        },
+        &rest[len..],
+    ))
+}
+
+/// Sequential reader of a constructed element's children
+struct Children<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Children<'a> {
+    fn of(element: Tlv<'a>, expected: u8, what: &str) -> Result<Self> {
+        if element.tag != expected {
+            return Err(error(format!(
+                "{}: expected tag 0x{:02X}, got 0x{:02X}",
+                what, expected, element.tag
+            )));
+        }
+        Ok(Self {
+            rest: element.content,
+        })
+    }
+
+    fn next(&mut self, what: &str) -> Result<Tlv<'a>> {
+        if self.rest.is_empty() {
+            return Err(error(format!("missing {}", what)));
+        }
+        let (element, rest) = read_tlv(self.rest)?;
+        self.rest = rest;
+        Ok(element)
+    }
+
+    fn expect(&mut self, tag: u8, what: &str) -> Result<&'a [u8]> {
+        let element = self.next(what)?;
+        if element.tag != tag {
+            return Err(error(format!(
+                "{}: expected tag 0x{:02X}, got 0x{:02X}",
+                what, tag, element.tag
+            )));
+        }
+        Ok(element.content)
+    }
+
+    fn integer(&mut self, what: &str) -> Result<i64> {
+        decode_integer(self.expect(tag::INTEGER, what)?)
+    }
+
+    fn octets(&mut self, what: &str) -> Result<&'a [u8]> {
+        self.expect(tag::OCTET_STRING, what)
+    }
+}
+
+fn encode_integer(value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let mut start = 0;
+    while start < 7
+        && ((bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xFF && bytes[start + 1] & 0x80 != 0))
+    {
+        start += 1;
+    }
+    bytes[start..].to_vec()
+}
+
+fn encode_unsigned(value: u64) -> Vec<u8> {
+    let mut bytes = vec![0];
+    bytes.extend_from_slice(&value.to_be_bytes());
+    let mut start = 0;
+    while start < 8 && bytes[start] == 0 && bytes[start + 1] & 0x80 == 0 {
+        start += 1;
+    }
+    bytes.split_off(start)
+}
+
+fn decode_integer(content: &[u8]) -> Result<i64> {
+    if content.is_empty() || content.len() > 8 {
+        return Err(error(format!("bad INTEGER length {}", content.len())));
+    }
+    let init = if content[0] & 0x80 != 0 { -1i64 } else { 0 };
+    Ok(content.iter().fold(init, |acc, b| (acc << 8) | *b as i64))
+}
+
+fn decode_unsigned(content: &[u8]) -> Result<u64> {
+    let content = match content {
+        [0, rest @ ..] if !rest.is_empty() => rest,
+        _ => content,
+    };
+    if content.is_empty() || content.len() > 8 {
+        return Err(error(format!("bad unsigned length {}", content.len())));
+    }
+    Ok(content.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64))
+}
+
+fn integer(value: i64) -> Vec<u8> {
+    tlv(tag::INTEGER, &encode_integer(value))
+}
+
+fn octets(value: &[u8]) -> Vec<u8> {
+    tlv(tag::OCTET_STRING, value)
+}
+
+fn sequence(parts: &[&[u8]]) -> Vec<u8> {
+    tlv(tag::SEQUENCE, &parts.concat())
+}
+
+// ============================================================================
+// Object identifiers and values
+// ============================================================================
+
+/// Object identifier, e.g. `1.3.6.1.2.1.1.3.0`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Oid(pub Vec<u32>);
+
+impl Oid {
+    /// Parse dotted notation; a leading dot is accepted
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim().trim_start_matches('.');
+        let arcs = text
+            .split('.')
+            .map(|arc| arc.trim().parse::<u32>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| error(format!("invalid OID '{}'", text)))?;
+        if arcs.len() < 2 || arcs[0] > 2 || (arcs[0] < 2 && arcs[1] > 39) {
+            return Err(error(format!("invalid OID '{}'", text)));
+        }
+        Ok(Self(arcs))
+    }
+
+    fn encode_content(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.0.len() + 4);
+        let first = self.0[0] * 40 + self.0[1];
+        for arc in std::iter::once(first).chain(self.0[2..].iter().copied()) {
+            let mut groups = vec![(arc & 0x7F) as u8];
+            let mut rest = arc >> 7;
+            while rest > 0 {
+                groups.push((rest & 0x7F) as u8 | 0x80);
+                rest >>= 7;
+            }
+            out.extend(groups.iter().rev());
+        }
+        out
+    }
+
+    fn decode_content(content: &[u8]) -> Result<Self> {
+        let mut arcs = Vec::with_capacity(content.len() + 1);
+        let mut arc: u64 = 0;
+        for (i, byte) in content.iter().enumerate() {
+            arc = (arc << 7) | u64::from(byte & 0x7F);
+            if arc > u64::from(u32::MAX) {
+                return Err(error("OID arc out of range"));
+            }
+            if byte & 0x80 != 0 {
+                if i + 1 == content.len() {
+                    return Err(error("truncated OID"));
+                }
+                continue;
+            }
+            if arcs.is_empty() {
+                let first = (arc / 40).min(2);
+                arcs.push(first as u32);
+                arcs.push((arc - first * 40) as u32);
+            } else {
+                arcs.push(arc as u32);
+            }
+            arc = 0;
+        }
+        if arcs.is_empty() {
+            return Err(error("empty OID"));
+        }
+        Ok(Self(arcs))
+    }
+}
+
+impl fmt::Display for Oid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, arc) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            write!(f, "{}", arc)?;
+        }
+        Ok(())
+    }
+}
+
+/// Value of a variable binding
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    OctetString(Vec<u8>),
+    ObjectId(Oid),
+    IpAddress([u8; 4]),
+    Counter32(u32),
+    Gauge32(u32),
+    TimeTicks(u32),
+    Opaque(Vec<u8>),
+    Counter64(u64),
+    NoSuchObject,
+    NoSuchInstance,
+    EndOfMibView,
+}
+
+impl Value {
+    /// Numeric value; octet strings holding a number (common in UPS MIBs)
+    /// are parsed
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Integer(v) => Some(*v as f64),
+            Value::Counter32(v) | Value::Gauge32(v) | Value::TimeTicks(v) => Some(f64::from(*v)),
+            Value::Counter64(v) => Some(*v as f64),
+            Value::OctetString(bytes) => std::str::from_utf8(bytes).ok().and_then(|s| {
+                s.trim_matches(|c: char| c.is_whitespace() || c == '\0')
+                    .parse()
+                    .ok()
+            }),
+            _ => None,
+        }
+    }
+
+    /// Why the agent returned no value, for the exception values
+    pub fn exception(&self) -> Option<&'static str> {
+        match self {
+            Value::NoSuchObject => Some("noSuchObject"),
+            Value::NoSuchInstance => Some("noSuchInstance"),
+            Value::EndOfMibView => Some("endOfMibView"),
+            _ => None,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Value::Null => tlv(tag::NULL, &[]),
+            Value::Integer(v) => integer(*v),
+            Value::OctetString(v) => octets(v),
+            Value::ObjectId(oid) => tlv(tag::OBJECT_IDENTIFIER, &oid.encode_content()),
+            Value::IpAddress(v) => tlv(tag::IP_ADDRESS, v),
+            Value::Counter32(v) => tlv(tag::COUNTER32, &encode_unsigned(u64::from(*v))),
+            Value::Gauge32(v) => tlv(tag::GAUGE32, &encode_unsigned(u64::from(*v))),
+            Value::TimeTicks(v) => tlv(tag::TIMETICKS, &encode_unsigned(u64::from(*v))),
+            Value::Opaque(v) => tlv(tag::OPAQUE, v),
+            Value::Counter64(v) => tlv(tag::COUNTER64, &encode_unsigned(*v)),
+            Value::NoSuchObject => tlv(tag::NO_SUCH_OBJECT, &[]),
+            Value::NoSuchInstance => tlv(tag::NO_SUCH_INSTANCE, &[]),
+            Value::EndOfMibView => tlv(tag::END_OF_MIB_VIEW, &[]),
+        }
+    }
+
+    fn decode(element: Tlv<'_>) -> Result<Self> {
+        let unsigned32 = |content: &[u8]| -> Result<u32> {
+            u32::try_from(decode_unsigned(content)?).map_err(|_| error("32-bit value out of range"))
+        };
+        Ok(match element.tag {
+            tag::NULL => Value::Null,
+            tag::INTEGER => Value::Integer(decode_integer(element.content)?),
+            tag::OCTET_STRING => Value::OctetString(element.content.to_vec()),
+            tag::OBJECT_IDENTIFIER => Value::ObjectId(Oid::decode_content(element.content)?),
+            tag::IP_ADDRESS => Value::IpAddress(
+                element
+                    .content
+                    .try_into()
+                    .map_err(|_| error("IpAddress must be 4 octets"))?,
+            ),
+            tag::COUNTER32 => Value::Counter32(unsigned32(element.content)?),
+            tag::GAUGE32 => Value::Gauge32(unsigned32(element.content)?),
+            tag::TIMETICKS => Value::TimeTicks(unsigned32(element.content)?),
+            tag::OPAQUE => Value::Opaque(element.content.to_vec()),
+            tag::COUNTER64 => Value::Counter64(decode_unsigned(element.content)?),
+            tag::NO_SUCH_OBJECT => Value::NoSuchObject,
+            tag::NO_SUCH_INSTANCE => Value::NoSuchInstance,
+            tag::END_OF_MIB_VIEW => Value::EndOfMibView,
+            other => return Err(error(format!("unknown value tag 0x{:02X}", other))),
+        })
+    }
+}
+
+/// Syntax of values written by SET
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetType {
+    /// INTEGER (enumerations, most writable objects)
+    Integer,
+    /// Gauge32 / Unsigned32
+    Unsigned,
+    TimeTicks,
+    /// Decimal text of the value
+    OctetString,
+}
+
+impl SetType {
+    pub fn parse(text: &str) -> Result<Self> {
+        match text
+            .trim()
+            .to_ascii_lowercase()
+            .replace(['-', ' '], "_")
+            .as_str()
+        {
+            "integer" | "int" | "integer32" | "i" => Ok(Self::Integer),
+            "unsigned" | "unsigned32" | "gauge" | "gauge32" | "u" => Ok(Self::Unsigned),
+            "timeticks" | "t" => Ok(Self::TimeTicks),
+            "octet_string" | "string" | "s" => Ok(Self::OctetString),
+            other => Err(error(format!(
+                "unknown SET type '{}' (integer, unsigned, timeticks, string)",
+                other
+            ))),
+        }
+    }
+
+    /// Value written for a command; integer syntaxes require whole numbers
+    pub fn value(&self, value: f64) -> Result<Value> {
+        let whole = || {
+            if value.is_finite() && value.fract() == 0.0 {
+                Ok(value)
+            } else {
+                Err(error(format!("{} is not a whole number", value)))
+            }
+        };
+        let unsigned32 = || {
+            let v = whole()?;
+            if (0.0..=f64::from(u32::MAX)).contains(&v) {
+                Ok(v as u32)
+            } else {
+                Err(error(format!("{} is out of the unsigned range", value)))
+            }
+        };
+        Ok(match self {
+            SetType::Integer => {
+                let v = whole()?;
+                if !(f64::from(i32::MIN)..=f64::from(i32::MAX)).contains(&v) {
+                    return Err(error(format!("{} is out of the INTEGER range", value)));
+                }
+                Value::Integer(v as i64)
+            },
+            SetType::Unsigned => Value::Gauge32(unsigned32()?),
+            SetType::TimeTicks => Value::TimeTicks(unsigned32()?),
+            SetType::OctetString => Value::OctetString(value.to_string().into_bytes()),
+        })
+    }
+}
+
+// ============================================================================
+// PDUs
+// ============================================================================
+
+/// GetRequest, SetRequest, Response or Report PDU
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pdu {
+    pub kind: u8,
+    pub request_id: i32,
+    pub error_status: u32,
+    /// 1-based index of the failing variable binding, 0 for none
+    pub error_index: u32,
+    pub varbinds: Vec<(Oid, Value)>,
+}
+
+impl Pdu {
+    pub fn get(request_id: i32, oids: &[Oid]) -> Self {
+        Self {
+            kind: pdu::GET,
+            request_id,
+            error_status: 0,
+            error_index: 0,
+            varbinds: oids.iter().map(|oid| (oid.clone(), Value::Null)).collect(),
+        }
+    }
+
+    pub fn set(request_id: i32, varbinds: Vec<(Oid, Value)>) -> Self {
+        Self {
+            kind: pdu::SET,
+            request_id,
+            error_status: 0,
+            error_index: 0,
+            varbinds,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let varbinds: Vec<u8> = self
+            .varbinds
+            .iter()
+            .flat_map(|(oid, value)| {
+                sequence(&[
+                    &tlv(tag::OBJECT_IDENTIFIER, &oid.encode_content()),
+                    &value.encode(),
+                ])
+            })
+            .collect();
+        tlv(
+            self.kind,
+            &[
+                integer(i64::from(self.request_id)),
+                integer(i64::from(self.error_status)),
+                integer(i64::from(self.error_index)),
+                tlv(tag::SEQUENCE, &varbinds),
+            ]
+            .concat(),
+        )
+    }
+
+    pub fn decode(element: Tlv<'_>) -> Result<Self> {
+        if !(0xA0..=0xA8).contains(&element.tag) {
+            return Err(error(format!("unknown PDU type 0x{:02X}", element.tag)));
+        }
+        let mut fields = Children::of(element, element.tag, "PDU")?;
+        let request_id = fields.integer("request-id")? as i32;
+        let error_status = fields.integer("error-status")?.max(0) as u32;
+        let error_index = fields.integer("error-index")?.max(0) as u32;
+        let mut list = Children::of(fields.next("variable-bindings")?, tag::SEQUENCE, "varbinds")?;
+        let mut varbinds = Vec::new();
+        while !list.rest.is_empty() {
+            let mut varbind = Children::of(list.next("varbind")?, tag::SEQUENCE, "varbind")?;
+            let oid = Oid::decode_content(varbind.expect(tag::OBJECT_IDENTIFIER, "name")?)?;
+            let value = Value::decode(varbind.next("value")?)?;
+            varbinds.push((oid, value));
+        }
+        Ok(Self {
+            kind: element.tag,
+            request_id,
+            error_status,
+            error_index,
+            varbinds,
+        })
+    }
+}
+
+/// RFC 3416 error-status name
+pub fn error_status_text(status: u32) -> &'static str {
+    match status {
+        0 => "noError",
+        1 => "tooBig",
+        2 => "noSuchName",
+        3 => "badValue",
+        4 => "readOnly",
+        5 => "genErr",
+        6 => "noAccess",
+        7 => "wrongType",
+        8 => "wrongLength",
+        9 => "wrongEncoding",
+        10 => "wrongValue",
+        11 => "noCreation",
+        12 => "inconsistentValue",
+        13 => "resourceUnavailable",
+        14 => "commitFailed",
+        15 => "undoFailed",
+        16 => "authorizationError",
+        17 => "notWritable",
+        18 => "inconsistentName",
+        _ => "unknown error",
+    }
+}
+
+// ============================================================================
+// Messages
+// ============================================================================
+
+/// SNMPv2c message
+pub fn encode_community(community: &[u8], pdu: &Pdu) -> Vec<u8> {
+    sequence(&[&integer(VERSION_2C), &octets(community), &pdu.encode()])
+}
+
+/// Version of a message, read before decoding the rest
+pub fn message_version(datagram: &[u8]) -> Result<i64> {
+    let (element, _) = read_tlv(datagram)?;
+    Children::of(element, tag::SEQUENCE, "message")?.integer("msgVersion")
+}
+
+/// Community and PDU of an SNMPv2c message
+pub fn decode_community(datagram: &[u8]) -> Result<(Vec<u8>, Pdu)> {
+    let (element, _) = read_tlv(datagram)?;
+    let mut message = Children::of(element, tag::SEQUENCE, "message")?;
+    let version = message.integer("version")?;
+    if version != VERSION_2C {
+        return Err(error(format!(
+            "not an SNMPv2c message (version {})",
+            version
+        )));
+    }
+    let community = message.octets("community")?.to_vec();
+    let pdu = Pdu::decode(message.next("PDU")?)?;
+    Ok((community, pdu))
+}
+
+/// User-based security model parameters (RFC 3414 2.4)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UsmParameters {
+    pub engine_id: Vec<u8>,
+    pub engine_boots: u32,
+    pub engine_time: u32,
+    pub user_name: Vec<u8>,
+    pub auth_params: Vec<u8>,
+    pub priv_params: Vec<u8>,
+}
+
+impl UsmParameters {
+    fn encode(&self) -> Vec<u8> {
+        sequence(&[
+            &octets(&self.engine_id),
+            &integer(i64::from(self.engine_boots)),
+            &integer(i64::from(self.engine_time)),
+            &octets(&self.user_name),
+            &octets(&self.auth_params),
+            &octets(&self.priv_params),
+        ])
+    }
+
+    fn decode(content: &[u8]) -> Result<Self> {
+        let (element, _) = read_tlv(content)?;
+        let mut fields = Children::of(element, tag::SEQUENCE, "USM parameters")?;
+        let clamp = |v: i64| v.clamp(0, i64::from(i32::MAX)) as u32;
+        Ok(Self {
+            engine_id: fields.octets("msgAuthoritativeEngineID")?.to_vec(),
+            engine_boots: clamp(fields.integer("msgAuthoritativeEngineBoots")?),
+            engine_time: clamp(fields.integer("msgAuthoritativeEngineTime")?),
+            user_name: fields.octets("msgUserName")?.to_vec(),
+            auth_params: fields.octets("msgAuthenticationParameters")?.to_vec(),
+            priv_params: fields.octets("msgPrivacyParameters")?.to_vec(),
+        })
+    }
+}
+
+/// Context and PDU of a v3 message
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScopedPdu {
+    pub context_engine_id: Vec<u8>,
+    pub context_name: Vec<u8>,
+    pub pdu: Pdu,
+}
+
+impl ScopedPdu {
+    pub fn encode(&self) -> Vec<u8> {
+        sequence(&[
+            &octets(&self.context_engine_id),
+            &octets(&self.context_name),
+            &self.pdu.encode(),
+        ])
+    }
+
+    /// Decode the scoped PDU at the start of `data`; trailing bytes (DES
+    /// padding) are ignored
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let (element, _) = read_tlv(data)?;
+        let mut fields = Children::of(element, tag::SEQUENCE, "scopedPDU")?;
+        Ok(Self {
+            context_engine_id: fields.octets("contextEngineID")?.to_vec(),
+            context_name: fields.octets("contextName")?.to_vec(),
+            pdu: Pdu::decode(fields.next("data")?)?,
+        })
+    }
+}
+
+/// Scoped PDU of a v3 message, in clear or encrypted
+#[derive(Debug, Clone, PartialEq)]
+pub enum MsgData {
+    Plain(ScopedPdu),
+    Encrypted(Vec<u8>),
+}
+
+/// SNMPv3 message with USM security parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct V3Message {
+    pub msg_id: i32,
+    pub max_size: i32,
+    pub flags: u8,
+    pub usm: UsmParameters,
+    pub data: MsgData,
+}
+
+impl V3Message {
+    pub fn encode(&self) -> Vec<u8> {
+        let header = sequence(&[
+            &integer(i64::from(self.msg_id)),
+            &integer(i64::from(self.max_size)),
+            &octets(&[self.flags]),
+            &integer(USM_SECURITY_MODEL),
+        ]);
+        let data = match &self.data {
+            MsgData::Plain(scoped) => scoped.encode(),
+            MsgData::Encrypted(bytes) => octets(bytes),
+        };
+        sequence(&[
+            &integer(VERSION_3),
+            &header,
+            &octets(&self.usm.encode()),
+            &data,
+        ])
+    }
+
+    pub fn decode(datagram: &[u8]) -> Result<Self> {
+        let (element, _) = read_tlv(datagram)?;
+        let mut message = Children::of(element, tag::SEQUENCE, "message")?;
+        let version = message.integer("msgVersion")?;
+        if version != VERSION_3 {
+            return Err(error(format!(
+                "not an SNMPv3 message (version {})",
+                version
+            )));
+        }
+        let mut header = Children::of(message.next("msgGlobalData")?, tag::SEQUENCE, "header")?;
+        let msg_id = header.integer("msgID")? as i32;
+        let max_size = header.integer("msgMaxSize")? as i32;
+        let flags = *header
+            .octets("msgFlags")?
+            .first()
+            .ok_or_else(|| error("empty msgFlags"))?;
+        let model = header.integer("msgSecurityModel")?;
+        if model != USM_SECURITY_MODEL {
+            return Err(error(format!("unsupported security model {}", model)));
+        }
+        let usm = UsmParameters::decode(message.octets("msgSecurityParameters")?)?;
+        let data = message.next("msgData")?;
+        let data = match data.tag {
+            tag::OCTET_STRING => MsgData::Encrypted(data.content.to_vec()),
+            _ => MsgData::Plain(ScopedPdu::decode(&tlv(data.tag, data.content))?),
+        };
+        Ok(Self {
+            msg_id,
+            max_size,
+            flags,
+            usm,
+            data,
+        })
+    }
+}
+
+/// Position of msgAuthenticationParameters in an encoded v3 message
+///
+/// The digest is computed over the whole message with these octets zeroed.
+pub fn auth_params_range(message: &[u8]) -> Result<Range<usize>> {
+    let (element, _) = read_tlv(message)?;
+    let mut fields = Children::of(element, tag::SEQUENCE, "message")?;
+    fields.next("msgVersion")?;
+    fields.next("msgGlobalData")?;
+    let (usm, _) = read_tlv(fields.octets("msgSecurityParameters")?)?;
+    let mut usm = Children::of(usm, tag::SEQUENCE, "USM parameters")?;
+    for what in ["engine ID", "engine boots", "engine time", "user name"] {
+        usm.next(what)?;
+    }
+    let auth = usm.octets("msgAuthenticationParameters")?;
+    let start = auth.as_ptr() as usize - message.as_ptr() as usize;
+    Ok(start..start + auth.len())
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oid_encoding() {
+        let oid = Oid::parse(".1.3.6.1.2.1.33.1.2.4.0").unwrap();
+        assert_eq!(oid.to_string(), "1.3.6.1.2.1.33.1.2.4.0");
+        let encoded = Value::ObjectId(oid.clone()).encode();
+        assert_eq!(
+            encoded,
+            [0x06, 0x0A, 0x2B, 0x06, 0x01, 0x02, 0x01, 0x21, 0x01, 0x02, 0x04, 0x00]
+        );
+        // Multi-octet arcs (enterprise 318, APC)
+        let apc = Oid::parse("1.3.6.1.4.1.318.1.1.1.2.2.1.0").unwrap();
+        let encoded = Value::ObjectId(apc.clone()).encode();
+        assert_eq!(&encoded[7..9], [0x82, 0x3E]);
+        let (element, _) = read_tlv(&encoded).unwrap();
+        assert_eq!(Value::decode(element).unwrap(), Value::ObjectId(apc));
+
+        assert!(Oid::parse("1.3.x").is_err());
+        assert!(Oid::parse("5.1").is_err());
+    }
+
+    #[test]
+    fn test_community_get() {
+        // snmpget -v2c -c public host sysUpTime.0, request-id 1
+        let sys_uptime = Oid::parse("1.3.6.1.2.1.1.3.0").unwrap();
+        let message = encode_community(b"public", &Pdu::get(1, std::slice::from_ref(&sys_uptime)));
+        assert_eq!(
+            message,
+            [
+                0x30, 0x26, 0x02, 0x01, 0x01, 0x04, 0x06, b'p', b'u', b'b', b'l', b'i', b'c', 0xA0,
+                0x19, 0x02, 0x01, 0x01, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00, 0x30, 0x0E, 0x30, 0x0C,
+                0x06, 0x08, 0x2B, 0x06, 0x01, 0x02, 0x01, 0x01, 0x03, 0x00, 0x05, 0x00
+            ]
+        );
+
+        let response = Pdu {
+            kind: pdu::RESPONSE,
+            request_id: 1,
+            error_status: 0,
+            error_index: 0,
+            varbinds: vec![
+                (sys_uptime, Value::TimeTicks(4_294_967_295)),
+                (
+                    Oid::parse("1.3.6.1.2.1.2.2.1.10.1").unwrap(),
+                    Value::Counter32(7),
+                ),
+                (
+                    Oid::parse("1.3.6.1.2.1.31.1.1.1.6.1").unwrap(),
+                    Value::Counter64(1 << 40),
+                ),
+                (
+                    Oid::parse("1.3.6.1.4.1.1.2").unwrap(),
+                    Value::OctetString(b"230.5".to_vec()),
+                ),
+                (Oid::parse("1.3.6.1.4.1.1.3").unwrap(), Value::Integer(-40)),
+                (
+                    Oid::parse("1.3.6.1.4.1.1.4").unwrap(),
+                    Value::NoSuchInstance,
+                ),
+            ],
+        };
+        let (community, decoded) =
+            decode_community(&encode_community(b"public", &response)).unwrap();
+        assert_eq!(community, b"public");
+        assert_eq!(decoded, response);
+        let values: Vec<Option<f64>> = decoded.varbinds.iter().map(|(_, v)| v.as_f64()).collect();
+        assert_eq!(
+            values,
+            [
+                Some(4_294_967_295.0),
+                Some(7.0),
+                Some((1u64 << 40) as f64),
+                Some(230.5),
+                Some(-40.0),
+                None
+            ]
+        );
+        assert_eq!(decoded.varbinds[5].1.exception(), Some("noSuchInstance"));
+    }
+
+    #[test]
+    fn test_set_types() {
+        assert_eq!(SetType::parse("Integer32").unwrap(), SetType::Integer);
+        assert_eq!(SetType::parse("gauge").unwrap(), SetType::Unsigned);
+        assert_eq!(SetType::Integer.value(-3.0).unwrap(), Value::Integer(-3));
+        assert!(SetType::Integer.value(1.5).is_err());
+        assert!(SetType::Unsigned.value(-1.0).is_err());
+        assert_eq!(
+            SetType::OctetString.value(2.0).unwrap(),
+            Value::OctetString(b"2".to_vec())
+        );
+        assert_eq!(
+            Value::Gauge32(0x8000_0000).encode(),
+            [0x42, 0x05, 0x00, 0x80, 0x00, 0x00, 0x00]
+        );
+    }
+
+    #[test]
+    fn test_v3_message() {
+        let message = V3Message {
+            msg_id: 0x1234,
+            max_size: 65507,
+            flags: flags::AUTH | flags::REPORTABLE,
+            usm: UsmParameters {
+                engine_id: vec![0x80, 0x00, 0x1F, 0x88, 0x04],
+                engine_boots: 3,
+                engine_time: 1000,
+                user_name: b"ems".to_vec(),
+                auth_params: vec![0; 12],
+                priv_params: Vec::new(),
+            },
+            data: MsgData::Plain(ScopedPdu {
+                context_engine_id: vec![0x80, 0x00, 0x1F, 0x88, 0x04],
+                context_name: Vec::new(),
+                pdu: Pdu::get(7, &[Oid::parse("1.3.6.1.2.1.1.3.0").unwrap()]),
+            }),
+        };
+        let encoded = message.encode();
+        assert_eq!(message_version(&encoded).unwrap(), VERSION_3);
+        assert_eq!(V3Message::decode(&encoded).unwrap(), message);
+
+        let range = auth_params_range(&encoded).unwrap();
+        assert_eq!(range.len(), 12);
+        assert!(encoded[range.clone()].iter().all(|b| *b == 0));
+        // Octet string header right before the parameters
+        assert_eq!(encoded[range.start - 2..range.start], [0x04, 0x0C]);
+    }
+}
@@ -0,0 +1,655 @@
+//! SunSpec model discovery for Modbus devices
+//!
+//! SunSpec devices expose a chain of model blocks behind the `SunS` marker
+//! (at register 40000, 50000 or 0). [`discover`] finds the marker, walks the
+//! chain up to the end model and generates the telemetry and signal points
+//! of the inverter and meter models (see [`models`]) with `modbus_tcp`
+//! mappings, so a channel needs no hand-written point table.
+//!
+//! Integer models carry a scale factor register per value group; it is read
+//! once during discovery and becomes the point's `scale` (SunSpec devices
+//! keep their scale factors fixed). Points the device reports as not
+//! implemented are left out.
+//!
+//! Discovery opens its own short-lived Modbus TCP connection and only reads
+//! holding registers (function 03), so it runs next to a live channel.
+
+pub mod models;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tracing::debug;
+use voltage_model::PointType;
+
+use self::models::{Kind, ModelSpec};
+use crate::error::{ComSrvError, Result};
+
+/// "SunS" in two registers
+pub const MARKER: [u16; 2] = [0x5375, 0x6E53];
+
+/// Base addresses probed for the marker, in order
+pub const BASE_ADDRESSES: [u16; 3] = [40000, 50000, 0];
+
+/// ID of the end model
+pub const END_MODEL: u16 = 0xFFFF;
+
+/// Models walked before the chain is considered broken
+const MAX_MODELS: usize = 64;
+
+/// Registers per read request
+const MAX_READ: u16 = 125;
+
+/// Parameters of a discovery run (the channel parameters plus `base_address`)
+#[derive(Debug, Clone)]
+pub struct DiscoveryConfig {
+    pub host: String,
+    pub port: u16,
+    pub slave_id: u8,
+    /// Marker address; the [`BASE_ADDRESSES`] are probed when absent
+    pub base_address: Option<u16>,
+    pub timeout: Duration,
+}
+
+impl DiscoveryConfig {
+    /// Read from `modbus_tcp` channel parameters
+    pub fn from_parameters(parameters: &HashMap<String, JsonValue>) -> Result<Self> {
+        let uint = |key: &str| parameters.get(key).and_then(JsonValue::as_u64);
+        let text = |key: &str| {
+            parameters
+                .get(key)
+                .and_then(JsonValue::as_str)
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+        };
+        let bounded = |key: &str, max: u64| -> Result<Option<u64>> {
+            match uint(key) {
+                Some(n) if n > max => Err(ComSrvError::ConfigError(format!(
+                    "{} must be at most {}",
+                    key, max
+                ))),
+                n => Ok(n),
+            }
+        };
+
+        Ok(Self {
+            host: text("host")
+                .ok_or_else(|| ComSrvError::ConfigError("host is required".into()))?
+                .to_string(),
+            port: bounded("port", u16::MAX as u64)?.unwrap_or(502) as u16,
+            slave_id: bounded("slave_id", 247)?.unwrap_or(1) as u8,
+            base_address: bounded("base_address", u16::MAX as u64)?.map(|n| n as u16),
+            timeout: Duration::from_millis(uint("timeout_ms").unwrap_or(3000)),
+        })
+    }
+}
+
+/// Nameplate of a common model (1)
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DeviceInfo {
+    pub manufacturer: String,
+    pub model: String,
+    pub options: String,
+    pub version: String,
+    pub serial_number: String,
+    pub device_address: u16,
+}
+
+/// One model block found on the device
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoveredModel {
+    pub id: u16,
+    pub name: &'static str,
+    /// Address of the model ID register
+    pub address: u16,
+    /// Data registers (the `L` of the block)
+    pub length: u16,
+    /// Nameplate, for common models
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<DeviceInfo>,
+    /// Whether points are generated for the model
+    pub supported: bool,
+    /// Data registers read during discovery (supported models only)
+    #[serde(skip)]
+    pub data: Vec<u16>,
+}
+
+impl DiscoveredModel {
+    /// First data register
+    pub fn data_address(&self) -> u16 {
+        self.address + 2
+    }
+}
+
+/// Result of a discovery run
+#[derive(Debug, Clone, Serialize)]
+pub struct Discovery {
+    pub slave_id: u8,
+    /// Address of the `SunS` marker
+    pub base_address: u16,
+    pub models: Vec<DiscoveredModel>,
+}
+
+/// A point generated from a model
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SunSpecPoint {
+    pub point_type: PointType,
+    pub signal_name: String,
+    pub description: String,
+    pub unit: String,
+    pub scale: f64,
+    pub data_type: &'static str,
+    pub register_address: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bit_position: Option<u8>,
+}
+
+impl SunSpecPoint {
+    /// `protocol_mapping` of a `modbus_tcp` / `modbus_rtu` point
+    pub fn mapping(&self, slave_id: u8) -> JsonValue {
+        let mut mapping = json!({
+            "slave_id": slave_id,
+            "function_code": 3,
+            "register_address": self.register_address,
+            "data_type": self.data_type,
+            "byte_order": "ABCD",
+        });
+        if let Some(bit) = self.bit_position {
+            mapping["bit_position"] = bit.into();
+        }
+        mapping
+    }
+}
+
+impl Discovery {
+    /// Telemetry and signal points of the supported models
+    ///
+    /// Names are `<model>_<point>` (`103_W`); a model present more than
+    /// once gets its occurrence appended (`201_2_W`).
+    pub fn points(&self) -> Vec<SunSpecPoint> {
+        let mut seen: HashMap<u16, usize> = HashMap::new();
+        let mut points = Vec::new();
+        for model in &self.models {
+            let Some(spec) = models::model_spec(model.id) else {
+                continue;
+            };
+            let occurrence = seen.entry(model.id).or_default();
+            *occurrence += 1;
+            let prefix = if self.models.iter().filter(|m| m.id == model.id).count() > 1 {
+                format!("{}_{}", model.id, occurrence)
+            } else {
+                model.id.to_string()
+            };
+            model_points(model, &spec, &prefix, &mut points);
+        }
+        points
+    }
+}
+
+fn model_points(
+    model: &DiscoveredModel,
+    spec: &ModelSpec,
+    prefix: &str,
+    points: &mut Vec<SunSpecPoint>,
+) {
+    let register = |offset: u16| model.data.get(usize::from(offset)).copied();
+    for point in spec.points {
+        if point.phase > spec.phases || point.offset + point.kind.size() > model.length {
+            continue;
+        }
+        let Some(raw) = register(point.offset) else {
+            continue;
+        };
+        let low = register(point.offset + 1).unwrap_or(0);
+        if !implemented(point.kind, raw, low) {
+            continue;
+        }
+        let address = model.data_address() + point.offset;
+
+        if point.kind == Kind::Bitfield32 {
+            // Bits 0-15 are in the second (low) register
+            for (bit, name) in spec.events {
+                let (register_address, bit_position) = if *bit < 16 {
+                    (address + 1, *bit)
+                } else {
+                    (address, bit - 16)
+                };
+                points.push(SunSpecPoint {
+                    point_type: PointType::Signal,
+                    signal_name: format!("{}_{}_{}", prefix, point.name, name),
+                    description: format!(
+                        "{} {} {}",
+                        models::model_name(model.id),
+                        point.label,
+                        name
+                    ),
+                    unit: String::new(),
+                    scale: 1.0,
+                    data_type: point.kind.data_type(),
+                    register_address,
+                    bit_position: Some(bit_position),
+                });
+            }
+            continue;
+        }
+
+        let scale = match point.sf.and_then(register) {
+            Some(sf) => match scale_factor(sf) {
+                Some(scale) => scale,
+                // Value group not implemented
+                None => continue,
+            },
+            None => 1.0,
+        };
+        points.push(SunSpecPoint {
+            point_type: PointType::Telemetry,
+            signal_name: format!("{}_{}", prefix, point.name),
+            description: format!("{} {}", models::model_name(model.id), point.label),
+            unit: point.unit.to_string(),
+            scale,
+            data_type: point.kind.data_type(),
+            register_address: address,
+            bit_position: None,
+        });
+    }
+}
+
+/// Whether a value differs from the "not implemented" pattern of its kind
+fn implemented(kind: Kind, high: u16, low: u16) -> bool {
+    match kind {
+        Kind::Uint16 | Kind::Enum16 => high != 0xFFFF,
+        Kind::Int16 => high != 0x8000,
+        // An accumulator of 0 is not accumulating yet, not unsupported
+        Kind::Acc32 => true,
+        Kind::Float32 => !f32::from_bits((u32::from(high) << 16) | u32::from(low)).is_nan(),
+        Kind::Bitfield32 => (high, low) != (0xFFFF, 0xFFFF),
+    }
+}
+
+/// Multiplier of a `sunssf` register, `None` when not implemented
+fn scale_factor(raw: u16) -> Option<f64> {
+    let exponent = raw as i16;
+    if exponent == i16::MIN || !(-10..=10).contains(&exponent) {
+        return None;
+    }
+    // Divide for negative exponents: 1.0 / 100.0 is exactly 0.01, 10^-2 is not
+    let magnitude = 10f64.powi(i32::from(exponent.unsigned_abs()));
+    Some(if exponent < 0 {
+        1.0 / magnitude
+    } else {
+        magnitude
+    })
+}
+
+/// Modbus TCP master reading holding registers
+pub struct RegisterReader {
+    stream: TcpStream,
+    transaction: u16,
+    timeout: Duration,
+}
+
+impl RegisterReader {
+    pub async fn connect(host: &str, port: u16, timeout_after: Duration) -> Result<Self> {
+        let stream = timeout(timeout_after, TcpStream::connect((host, port)))
+            .await
+            .map_err(|_| ComSrvError::TimeoutError(format!("connect to {}:{}", host, port)))?
+            .map_err(|e| ComSrvError::ConnectionError(format!("{}:{}: {}", host, port, e)))?;
+        Ok(Self {
+            stream,
+            transaction: 0,
+            timeout: timeout_after,
+        })
+    }
+
+    /// Read `count` holding registers in requests of at most [`MAX_READ`]
+    pub async fn read(&mut self, slave_id: u8, address: u16, count: u16) -> Result<Vec<u16>> {
+        let mut registers = Vec::with_capacity(usize::from(count));
+        let mut offset = 0;
+        while offset < count {
+            let quantity = (count - offset).min(MAX_READ);
+            let block = timeout(
+                self.timeout,
+                self.read_block(slave_id, address + offset, quantity),
+            )
+            .await
+            .map_err(|_| {
+                ComSrvError::TimeoutError(format!("read of register {}", address + offset))
+            })??;
+            registers.extend(block);
+            offset += quantity;
+        }
+        Ok(registers)
+    }
+
+    async fn read_block(&mut self, slave_id: u8, address: u16, quantity: u16) -> Result<Vec<u16>> {
+        let io_error = |e: std::io::Error| ComSrvError::ConnectionError(e.to_string());
+        self.transaction = self.transaction.wrapping_add(1);
+        let mut request = Vec::with_capacity(12);
+        request.extend(self.transaction.to_be_bytes());
+        request.extend([0, 0, 0, 6, slave_id, 0x03]);
+        request.extend(address.to_be_bytes());
+        request.extend(quantity.to_be_bytes());
+        self.stream.write_all(&request).await.map_err(io_error)?;
+
+        loop {
+            let mut header = [0u8; 7];
+            self.stream
+                .read_exact(&mut header)
+                .await
+                .map_err(io_error)?;
+            let length = usize::from(u16::from_be_bytes([header[4], header[5]]));
+            if length < 2 {
+                return Err(ComSrvError::ProtocolError(format!(
+                    "invalid MBAP length {}",
+                    length
+                )));
+            }
+            let mut pdu = vec![0u8; length - 1];
+            self.stream.read_exact(&mut pdu).await.map_err(io_error)?;
+            // Late answer to an earlier (timed out) request
+            if u16::from_be_bytes([header[0], header[1]]) != self.transaction {
+                continue;
+            }
+
+            return match pdu.as_slice() {
+                [0x83, code, ..] => Err(ComSrvError::ProtocolError(format!(
+                    "exception {} reading register {}",
+                    code, address
+                ))),
+                [0x03, byte_count, data @ ..]
+                    if usize::from(*byte_count) == data.len()
+                        && data.len() == usize::from(quantity) * 2 =>
+                {
+                    Ok(data
+                        .chunks_exact(2)
+                        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                        .collect())
+                },
+                _ => Err(ComSrvError::ProtocolError(format!(
+                    "malformed response reading {} registers at {}",
+                    quantity, address
+                ))),
+            };
+        }
+    }
+}
+
+fn decode_string(registers: &[u16]) -> String {
+    let bytes: Vec<u8> = registers.iter().flat_map(|r| r.to_be_bytes()).collect();
+    String::from_utf8_lossy(&bytes)
+        .trim_end_matches(['\0', ' '])
+        .to_string()
+}
+
+fn device_info(data: &[u16]) -> DeviceInfo {
+    let string = |offset: u16, len: u16| {
+        let start = usize::from(offset).min(data.len());
+        let end = usize::from(offset + len).min(data.len());
+        decode_string(&data[start..end])
+    };
+    let mut fields = models::common::STRINGS
+        .iter()
+        .map(|(_, offset, len)| string(*offset, *len));
+    DeviceInfo {
+        manufacturer: fields.next().unwrap_or_default(),
+        model: fields.next().unwrap_or_default(),
+        options: fields.next().unwrap_or_default(),
+        version: fields.next().unwrap_or_default(),
+        serial_number: fields.next().unwrap_or_default(),
+        device_address: data
+            .get(usize::from(models::common::DEVICE_ADDRESS))
+            .copied()
+            .unwrap_or_default(),
+    }
+}
+
+/// Find the marker and walk the model chain of one slave
+pub async fn scan(
+    reader: &mut RegisterReader,
+    slave_id: u8,
+    base_address: Option<u16>,
+) -> Result<Discovery> {
+    let candidates = match base_address {
+        Some(address) => vec![address],
+        None => BASE_ADDRESSES.to_vec(),
+    };
+    let mut base = None;
+    for address in candidates {
+        match reader.read(slave_id, address, 2).await {
+            Ok(registers) if registers == MARKER => {
+                base = Some(address);
+                break;
+            },
+            Ok(_) => debug!("No SunSpec marker at {}", address),
+            // Exceptions (illegal address) are expected on the wrong bases
+            Err(e) => debug!("No SunSpec marker at {}: {}", address, e),
+        }
+    }
+    let base_address = base.ok_or_else(|| {
+        ComSrvError::ProtocolError(format!("no SunSpec marker found on slave {}", slave_id))
+    })?;
+
+    let mut models = Vec::new();
+    let mut address = base_address + 2;
+    loop {
+        if models.len() == MAX_MODELS {
+            return Err(ComSrvError::ProtocolError(format!(
+                "more than {} SunSpec models, chain not terminated",
+                MAX_MODELS
+            )));
+        }
+        let header = reader.read(slave_id, address, 2).await?;
+        let (id, length) = (header[0], header[1]);
+        if id == END_MODEL {
+            break;
+        }
+        let next = address
+            .checked_add(2)
+            .and_then(|a| a.checked_add(length))
+            .ok_or_else(|| {
+                ComSrvError::ProtocolError(format!(
+                    "SunSpec model {} at {} runs past the register space",
+                    id, address
+                ))
+            })?;
+
+        let supported = models::model_spec(id).is_some();
+        let data = if supported || id == 1 {
+            reader.read(slave_id, address + 2, length).await?
+        } else {
+            Vec::new()
+        };
+        let device = (id == 1).then(|| device_info(&data));
+        debug!("SunSpec model {} (L={}) at {}", id, length, address);
+        models.push(DiscoveredModel {
+            id,
+            name: models::model_name(id),
+            address,
+            length,
+            device,
+            supported,
+            data: if supported { data } else { Vec::new() },
+        });
+        address = next;
+    }
+
+    Ok(Discovery {
+        slave_id,
+        base_address,
+        models,
+    })
+}
+
+/// Connect with channel parameters and discover the device's models
+pub async fn discover(parameters: &HashMap<String, JsonValue>) -> Result<Discovery> {
+    let config = DiscoveryConfig::from_parameters(parameters)?;
+    let mut reader = RegisterReader::connect(&config.host, config.port, config.timeout).await?;
+    scan(&mut reader, config.slave_id, config.base_address).await
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn string_registers(text: &str, len: usize) -> Vec<u16> {
+        let mut bytes = text.as_bytes().to_vec();
+        bytes.resize(len * 2, 0);
+        bytes
+            .chunks(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect()
+    }
+
+    /// Register image: common model, three phase inverter, single phase meter, vendor model
+    fn device_image(base: u16) -> HashMap<u16, u16> {
+        let mut registers = MARKER.to_vec();
+
+        let mut common = Vec::new();
+        common.extend(string_registers("Acme", 16));
+        common.extend(string_registers("PV-10K", 16));
+        common.extend(string_registers("", 8));
+        common.extend(string_registers("1.2.3", 8));
+        common.extend(string_registers("SN0001", 16));
+        common.extend([1, 0x8000]);
+        registers.extend([1, common.len() as u16]);
+        registers.extend(common);
+
+        let mut inverter = vec![0u16; 50];
+        inverter[4] = (-2i16) as u16; // A_SF
+        inverter[11] = (-1i16) as u16; // V_SF
+        inverter[12] = 5000; // W
+        inverter[13] = 1; // W_SF
+        inverter[14] = 5000;
+        inverter[15] = (-2i16) as u16; // Hz_SF
+        inverter[17] = 0x8000; // VA_SF not implemented
+        inverter[31] = 0x8000; // TmpCab not implemented
+        inverter[36] = 4; // St
+        registers.extend([103, 50]);
+        registers.extend(inverter);
+
+        let meter = vec![0u16; 105];
+        registers.extend([201, 105]);
+        registers.extend(meter);
+
+        registers.extend([64001, 3, 7, 7, 7]);
+        registers.extend([END_MODEL, 0]);
+
+        registers
+            .into_iter()
+            .enumerate()
+            .map(|(i, r)| (base + i as u16, r))
+            .collect()
+    }
+
+    /// Modbus TCP server answering FC03 from a register image
+    async fn fake_device(image: HashMap<u16, u16>) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut header = [0u8; 7];
+            while stream.read_exact(&mut header).await.is_ok() {
+                let length = u16::from_be_bytes([header[4], header[5]]) as usize;
+                let mut pdu = vec![0u8; length - 1];
+                stream.read_exact(&mut pdu).await.unwrap();
+                let address = u16::from_be_bytes([pdu[1], pdu[2]]);
+                let quantity = u16::from_be_bytes([pdu[3], pdu[4]]);
+                let values: Option<Vec<u16>> = (0..quantity)
+                    .map(|i| image.get(&(address + i)).copied())
+                    .collect();
+                let response = match values {
+                    Some(values) => {
+                        let mut body = vec![0x03, (values.len() * 2) as u8];
+                        body.extend(values.iter().flat_map(|v| v.to_be_bytes()));
+                        body
+                    },
+                    None => vec![0x83, 0x02],
+                };
+                let mut frame = header[..4].to_vec();
+                frame.extend(((response.len() + 1) as u16).to_be_bytes());
+                frame.push(header[6]);
+                frame.extend(response);
+                stream.write_all(&frame).await.unwrap();
+            }
+        });
+        port
+    }
+
+    fn parameters(port: u16) -> HashMap<String, JsonValue> {
+        serde_json::from_value(json!({"host": "127.0.0.1", "port": port, "timeout_ms": 1000}))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_discover_walks_model_chain() {
+        let port = fake_device(device_image(40000)).await;
+        let discovery = discover(&parameters(port)).await.unwrap();
+
+        assert_eq!(discovery.base_address, 40000);
+        let ids: Vec<u16> = discovery.models.iter().map(|m| m.id).collect();
+        assert_eq!(ids, vec![1, 103, 201, 64001]);
+        let device = discovery.models[0].device.as_ref().unwrap();
+        assert_eq!(device.manufacturer, "Acme");
+        assert_eq!(device.model, "PV-10K");
+        assert_eq!(device.serial_number, "SN0001");
+        assert_eq!(discovery.models[1].address, 40000 + 2 + 2 + 66);
+        assert!(discovery.models[1].supported);
+        assert!(!discovery.models[3].supported);
+    }
+
+    #[tokio::test]
+    async fn test_generated_points() {
+        let port = fake_device(device_image(0)).await;
+        let discovery = discover(&parameters(port)).await.unwrap();
+        assert_eq!(discovery.base_address, 0);
+        let points = discovery.points();
+        let point = |name: &str| points.iter().find(|p| p.signal_name == name);
+
+        let inverter_data = discovery.models[1].data_address();
+        let power = point("103_W").unwrap();
+        assert_eq!(power.point_type, PointType::Telemetry);
+        assert_eq!(power.scale, 10.0);
+        assert_eq!(power.register_address, inverter_data + 12);
+        assert_eq!(power.data_type, "int16");
+        assert_eq!(point("103_Hz").unwrap().scale, 0.01);
+        assert_eq!(point("103_PhVphC").unwrap().scale, 0.1);
+        // Not implemented: scale factor or value
+        assert!(point("103_VA").is_none());
+        assert!(point("103_TmpCab").is_none());
+
+        let fault = point("103_Evt1_GROUND_FAULT").unwrap();
+        assert_eq!(fault.point_type, PointType::Signal);
+        assert_eq!(fault.register_address, inverter_data + 39);
+        assert_eq!(fault.bit_position, Some(0));
+        assert_eq!(
+            fault.mapping(1),
+            json!({"slave_id": 1, "function_code": 3, "register_address": inverter_data + 39,
+                   "data_type": "uint16", "byte_order": "ABCD", "bit_position": 0})
+        );
+
+        // Single phase meter: no phase B/C points
+        assert!(point("201_AphA").is_some());
+        assert!(point("201_AphB").is_none());
+        assert!(point("201_PPV").is_none());
+        assert!(point("201_Evt_POWER_FAILURE").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_discover_without_marker() {
+        let port = fake_device(HashMap::from([(40000, 1), (40001, 2)])).await;
+        let err = discover(&parameters(port)).await.unwrap_err();
+        assert!(err.to_string().contains("no SunSpec marker"));
+
+        assert!(DiscoveryConfig::from_parameters(&HashMap::new()).is_err());
+        let mut params = parameters(502);
+        params.insert("slave_id".into(), json!(300));
+        assert!(DiscoveryConfig::from_parameters(&params).is_err());
+    }
+}
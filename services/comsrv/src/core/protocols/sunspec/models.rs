@@ -0,0 +1,417 @@
+//! SunSpec model definitions
+//!
+//! Point layouts of the models comsrv generates points for: the common
+//! model, the inverter models (integer + scale factor and float) and the
+//! AC meter models. Offsets are relative to the first data register of a
+//! model (after its ID and length). Other models are reported by discovery
+//! but produce no points.
+
+/// Register encoding of a SunSpec point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Uint16,
+    Int16,
+    /// Accumulator, read as uint32
+    Acc32,
+    Float32,
+    /// Enumerated state, read as uint16
+    Enum16,
+    /// Event flags, one signal per defined bit
+    Bitfield32,
+}
+
+impl Kind {
+    /// Registers the value occupies
+    pub fn size(self) -> u16 {
+        match self {
+            Self::Uint16 | Self::Int16 | Self::Enum16 => 1,
+            Self::Acc32 | Self::Float32 | Self::Bitfield32 => 2,
+        }
+    }
+
+    /// `data_type` of the Modbus point mapping
+    pub fn data_type(self) -> &'static str {
+        match self {
+            Self::Uint16 | Self::Enum16 | Self::Bitfield32 => "uint16",
+            Self::Int16 => "int16",
+            Self::Acc32 => "uint32",
+            Self::Float32 => "float32",
+        }
+    }
+}
+
+/// One point of a model
+#[derive(Debug, Clone, Copy)]
+pub struct PointSpec {
+    /// SunSpec point name
+    pub name: &'static str,
+    pub offset: u16,
+    pub kind: Kind,
+    /// Offset of the scale factor register (integer models)
+    pub sf: Option<u16>,
+    pub unit: &'static str,
+    /// Phase the point belongs to (0 = whole device)
+    pub phase: u8,
+    pub label: &'static str,
+}
+
+/// Layout of a model comsrv generates points for
+#[derive(Debug)]
+pub struct ModelSpec {
+    pub id: u16,
+    /// Phases measured (1, 2 or 3); per-phase points beyond it are skipped
+    pub phases: u8,
+    pub points: &'static [PointSpec],
+    /// Named bits of the event bitfields
+    pub events: &'static [(u8, &'static str)],
+}
+
+/// Display name of a SunSpec model ID
+pub fn model_name(id: u16) -> &'static str {
+    match id {
+        1 => "Common",
+        101 => "Inverter (single phase)",
+        102 => "Inverter (split phase)",
+        103 => "Inverter (three phase)",
+        111 => "Inverter (single phase, float)",
+        112 => "Inverter (split phase, float)",
+        113 => "Inverter (three phase, float)",
+        120 => "Nameplate",
+        121 => "Basic settings",
+        122 => "Measurements status",
+        123 => "Immediate controls",
+        124 => "Storage",
+        160 => "Multiple MPPT",
+        201 => "Meter (single phase)",
+        202 => "Meter (split phase)",
+        203 => "Meter (wye, three phase)",
+        204 => "Meter (delta, three phase)",
+        211 => "Meter (single phase, float)",
+        212 => "Meter (split phase, float)",
+        213 => "Meter (wye, three phase, float)",
+        214 => "Meter (delta, three phase, float)",
+        802 => "Battery",
+        64000..=65534 => "Vendor",
+        _ => "Unknown",
+    }
+}
+
+/// Layout of a model, if points can be generated for it
+pub fn model_spec(id: u16) -> Option<ModelSpec> {
+    let (points, events): (&'static [PointSpec], &'static [(u8, &'static str)]) = match id {
+        101..=103 => (INVERTER, INVERTER_EVENTS),
+        111..=113 => (INVERTER_FLOAT, INVERTER_EVENTS),
+        201..=204 => (METER, METER_EVENTS),
+        211..=214 => (METER_FLOAT, METER_EVENTS),
+        _ => return None,
+    };
+    let phases = match id % 10 {
+        1 => 1,
+        2 => 2,
+        _ => 3,
+    };
+    Some(ModelSpec {
+        id,
+        phases,
+        points,
+        events,
+    })
+}
+
+/// Offsets of the common model (1) strings
+pub mod common {
+    /// (name, offset, registers)
+    pub const STRINGS: &[(&str, u16, u16)] = &[
+        ("manufacturer", 0, 16),
+        ("model", 16, 16),
+        ("options", 32, 8),
+        ("version", 40, 8),
+        ("serial_number", 48, 16),
+    ];
+    /// Device address register
+    pub const DEVICE_ADDRESS: u16 = 64;
+}
+
+const fn int(
+    name: &'static str,
+    offset: u16,
+    kind: Kind,
+    sf: u16,
+    unit: &'static str,
+    phase: u8,
+    label: &'static str,
+) -> PointSpec {
+    PointSpec {
+        name,
+        offset,
+        kind,
+        sf: Some(sf),
+        unit,
+        phase,
+        label,
+    }
+}
+
+const fn float(
+    name: &'static str,
+    offset: u16,
+    unit: &'static str,
+    phase: u8,
+    label: &'static str,
+) -> PointSpec {
+    PointSpec {
+        name,
+        offset,
+        kind: Kind::Float32,
+        sf: None,
+        unit,
+        phase,
+        label,
+    }
+}
+
+const fn plain(name: &'static str, offset: u16, kind: Kind, label: &'static str) -> PointSpec {
+    PointSpec {
+        name,
+        offset,
+        kind,
+        sf: None,
+        unit: "",
+        phase: 0,
+        label,
+    }
+}
+
+use Kind::{Acc32, Bitfield32, Enum16, Int16, Uint16};
+
+/// Models 101-103 (L = 50)
+const INVERTER: &[PointSpec] = &[
+    int("A", 0, Uint16, 4, "A", 0, "AC current"),
+    int("AphA", 1, Uint16, 4, "A", 1, "Phase A current"),
+    int("AphB", 2, Uint16, 4, "A", 2, "Phase B current"),
+    int("AphC", 3, Uint16, 4, "A", 3, "Phase C current"),
+    int("PPVphAB", 5, Uint16, 11, "V", 2, "Phase voltage AB"),
+    int("PPVphBC", 6, Uint16, 11, "V", 3, "Phase voltage BC"),
+    int("PPVphCA", 7, Uint16, 11, "V", 3, "Phase voltage CA"),
+    int("PhVphA", 8, Uint16, 11, "V", 1, "Phase voltage AN"),
+    int("PhVphB", 9, Uint16, 11, "V", 2, "Phase voltage BN"),
+    int("PhVphC", 10, Uint16, 11, "V", 3, "Phase voltage CN"),
+    int("W", 12, Int16, 13, "W", 0, "AC power"),
+    int("Hz", 14, Uint16, 15, "Hz", 0, "Line frequency"),
+    int("VA", 16, Int16, 17, "VA", 0, "AC apparent power"),
+    int("VAr", 18, Int16, 19, "var", 0, "AC reactive power"),
+    int("PF", 20, Int16, 21, "%", 0, "Power factor"),
+    int("WH", 22, Acc32, 24, "Wh", 0, "AC energy"),
+    int("DCA", 25, Uint16, 26, "A", 0, "DC current"),
+    int("DCV", 27, Uint16, 28, "V", 0, "DC voltage"),
+    int("DCW", 29, Int16, 30, "W", 0, "DC power"),
+    int("TmpCab", 31, Int16, 35, "C", 0, "Cabinet temperature"),
+    int("TmpSnk", 32, Int16, 35, "C", 0, "Heat sink temperature"),
+    int("TmpTrns", 33, Int16, 35, "C", 0, "Transformer temperature"),
+    int("TmpOt", 34, Int16, 35, "C", 0, "Other temperature"),
+    plain("St", 36, Enum16, "Operating state"),
+    plain("StVnd", 37, Enum16, "Vendor operating state"),
+    plain("Evt1", 38, Bitfield32, "Event flags"),
+];
+
+/// Models 111-113 (L = 60)
+const INVERTER_FLOAT: &[PointSpec] = &[
+    float("A", 0, "A", 0, "AC current"),
+    float("AphA", 2, "A", 1, "Phase A current"),
+    float("AphB", 4, "A", 2, "Phase B current"),
+    float("AphC", 6, "A", 3, "Phase C current"),
+    float("PPVphAB", 8, "V", 2, "Phase voltage AB"),
+    float("PPVphBC", 10, "V", 3, "Phase voltage BC"),
+    float("PPVphCA", 12, "V", 3, "Phase voltage CA"),
+    float("PhVphA", 14, "V", 1, "Phase voltage AN"),
+    float("PhVphB", 16, "V", 2, "Phase voltage BN"),
+    float("PhVphC", 18, "V", 3, "Phase voltage CN"),
+    float("W", 20, "W", 0, "AC power"),
+    float("Hz", 22, "Hz", 0, "Line frequency"),
+    float("VA", 24, "VA", 0, "AC apparent power"),
+    float("VAr", 26, "var", 0, "AC reactive power"),
+    float("PF", 28, "%", 0, "Power factor"),
+    float("WH", 30, "Wh", 0, "AC energy"),
+    float("DCA", 32, "A", 0, "DC current"),
+    float("DCV", 34, "V", 0, "DC voltage"),
+    float("DCW", 36, "W", 0, "DC power"),
+    float("TmpCab", 38, "C", 0, "Cabinet temperature"),
+    float("TmpSnk", 40, "C", 0, "Heat sink temperature"),
+    float("TmpTrns", 42, "C", 0, "Transformer temperature"),
+    float("TmpOt", 44, "C", 0, "Other temperature"),
+    plain("St", 46, Enum16, "Operating state"),
+    plain("StVnd", 47, Enum16, "Vendor operating state"),
+    plain("Evt1", 48, Bitfield32, "Event flags"),
+];
+
+/// Evt1 bits of the inverter models
+const INVERTER_EVENTS: &[(u8, &str)] = &[
+    (0, "GROUND_FAULT"),
+    (1, "DC_OVER_VOLT"),
+    (2, "AC_DISCONNECT"),
+    (3, "DC_DISCONNECT"),
+    (4, "GRID_DISCONNECT"),
+    (5, "CABINET_OPEN"),
+    (6, "MANUAL_SHUTDOWN"),
+    (7, "OVER_TEMP"),
+    (8, "OVER_FREQUENCY"),
+    (9, "UNDER_FREQUENCY"),
+    (10, "AC_OVER_VOLT"),
+    (11, "AC_UNDER_VOLT"),
+    (12, "BLOWN_STRING_FUSE"),
+    (13, "UNDER_TEMP"),
+    (14, "MEMORY_LOSS"),
+    (15, "HW_TEST_FAILURE"),
+];
+
+/// Models 201-204 (L = 105)
+const METER: &[PointSpec] = &[
+    int("A", 0, Int16, 4, "A", 0, "Total AC current"),
+    int("AphA", 1, Int16, 4, "A", 1, "Phase A current"),
+    int("AphB", 2, Int16, 4, "A", 2, "Phase B current"),
+    int("AphC", 3, Int16, 4, "A", 3, "Phase C current"),
+    int("PhV", 5, Int16, 13, "V", 0, "Line to neutral voltage"),
+    int("PhVphA", 6, Int16, 13, "V", 1, "Phase voltage AN"),
+    int("PhVphB", 7, Int16, 13, "V", 2, "Phase voltage BN"),
+    int("PhVphC", 8, Int16, 13, "V", 3, "Phase voltage CN"),
+    int("PPV", 9, Int16, 13, "V", 2, "Line to line voltage"),
+    int("PPVphAB", 10, Int16, 13, "V", 2, "Phase voltage AB"),
+    int("PPVphBC", 11, Int16, 13, "V", 3, "Phase voltage BC"),
+    int("PPVphCA", 12, Int16, 13, "V", 3, "Phase voltage CA"),
+    int("Hz", 14, Int16, 15, "Hz", 0, "Frequency"),
+    int("W", 16, Int16, 20, "W", 0, "Total real power"),
+    int("WphA", 17, Int16, 20, "W", 1, "Phase A real power"),
+    int("WphB", 18, Int16, 20, "W", 2, "Phase B real power"),
+    int("WphC", 19, Int16, 20, "W", 3, "Phase C real power"),
+    int("VA", 21, Int16, 25, "VA", 0, "Total apparent power"),
+    int("VAR", 26, Int16, 30, "var", 0, "Total reactive power"),
+    int("PF", 31, Int16, 35, "%", 0, "Average power factor"),
+    int(
+        "TotWhExp",
+        36,
+        Acc32,
+        52,
+        "Wh",
+        0,
+        "Total real energy exported",
+    ),
+    int(
+        "TotWhImp",
+        44,
+        Acc32,
+        52,
+        "Wh",
+        0,
+        "Total real energy imported",
+    ),
+    int(
+        "TotVAhExp",
+        53,
+        Acc32,
+        69,
+        "VAh",
+        0,
+        "Total apparent energy exported",
+    ),
+    int(
+        "TotVAhImp",
+        61,
+        Acc32,
+        69,
+        "VAh",
+        0,
+        "Total apparent energy imported",
+    ),
+    int(
+        "TotVArhImpQ1",
+        70,
+        Acc32,
+        102,
+        "varh",
+        0,
+        "Reactive energy imported Q1",
+    ),
+    int(
+        "TotVArhImpQ2",
+        78,
+        Acc32,
+        102,
+        "varh",
+        0,
+        "Reactive energy imported Q2",
+    ),
+    int(
+        "TotVArhExpQ3",
+        86,
+        Acc32,
+        102,
+        "varh",
+        0,
+        "Reactive energy exported Q3",
+    ),
+    int(
+        "TotVArhExpQ4",
+        94,
+        Acc32,
+        102,
+        "varh",
+        0,
+        "Reactive energy exported Q4",
+    ),
+    plain("Evt", 103, Bitfield32, "Meter event flags"),
+];
+
+/// Models 211-214 (L = 124)
+const METER_FLOAT: &[PointSpec] = &[
+    float("A", 0, "A", 0, "Total AC current"),
+    float("AphA", 2, "A", 1, "Phase A current"),
+    float("AphB", 4, "A", 2, "Phase B current"),
+    float("AphC", 6, "A", 3, "Phase C current"),
+    float("PhV", 8, "V", 0, "Line to neutral voltage"),
+    float("PhVphA", 10, "V", 1, "Phase voltage AN"),
+    float("PhVphB", 12, "V", 2, "Phase voltage BN"),
+    float("PhVphC", 14, "V", 3, "Phase voltage CN"),
+    float("PPV", 16, "V", 2, "Line to line voltage"),
+    float("PPVphAB", 18, "V", 2, "Phase voltage AB"),
+    float("PPVphBC", 20, "V", 3, "Phase voltage BC"),
+    float("PPVphCA", 22, "V", 3, "Phase voltage CA"),
+    float("Hz", 24, "Hz", 0, "Frequency"),
+    float("W", 26, "W", 0, "Total real power"),
+    float("WphA", 28, "W", 1, "Phase A real power"),
+    float("WphB", 30, "W", 2, "Phase B real power"),
+    float("WphC", 32, "W", 3, "Phase C real power"),
+    float("VA", 34, "VA", 0, "Total apparent power"),
+    float("VAR", 42, "var", 0, "Total reactive power"),
+    float("PF", 50, "%", 0, "Average power factor"),
+    float("TotWhExp", 58, "Wh", 0, "Total real energy exported"),
+    float("TotWhImp", 66, "Wh", 0, "Total real energy imported"),
+    float("TotVAhExp", 74, "VAh", 0, "Total apparent energy exported"),
+    float("TotVAhImp", 82, "VAh", 0, "Total apparent energy imported"),
+    float("TotVArhImpQ1", 90, "varh", 0, "Reactive energy imported Q1"),
+    float("TotVArhImpQ2", 98, "varh", 0, "Reactive energy imported Q2"),
+    float(
+        "TotVArhExpQ3",
+        106,
+        "varh",
+        0,
+        "Reactive energy exported Q3",
+    ),
+    float(
+        "TotVArhExpQ4",
+        114,
+        "varh",
+        0,
+        "Reactive energy exported Q4",
+    ),
+    plain("Evt", 122, Bitfield32, "Meter event flags"),
+];
+
+/// Evt bits of the meter models
+const METER_EVENTS: &[(u8, &str)] = &[
+    (2, "POWER_FAILURE"),
+    (3, "UNDER_VOLTAGE"),
+    (4, "LOW_PF"),
+    (5, "OVER_CURRENT"),
+    (6, "OVER_VOLTAGE"),
+    (7, "MISSING_SENSOR"),
+];
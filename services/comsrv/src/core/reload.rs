@@ -369,6 +369,7 @@ impl<R: Rtdb> ChannelManager<R> {
 
         // 2. Atomic update of cache (thread-safe, lock-free swap)
         routing_cache.update(maps.c2m, maps.m2c, maps.c2c);
+        voltage_routing::publish::set_policies(maps.c2m_publish);
 
         debug!("Routing cache updated successfully");
 
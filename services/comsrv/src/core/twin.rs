@@ -0,0 +1,858 @@
+//! Virtual twin generation from captured channel sessions
+//!
+//! Decodes a captured Modbus session (request/response frames) with the
+//! channel's point mappings and produces an equivalent `virtual` protocol
+//! channel: same points, typical values, update timing and a replay timeline
+//! of value changes. Used to clone a misbehaving site device into the lab.
+//!
+//! Capture format is JSON Lines, one frame per line:
+//!
+//! ```text
+//! {"ts_ms": 1718000000000, "dir": "tx", "hex": "00 01 00 00 00 06 01 03 00 64 00 02"}
+//! {"ts_ms": 1718000000012, "dir": "rx", "hex": "00 01 00 00 00 07 01 03 04 41 C8 00 00"}
+//! ```
+
+use common::bytes::checksum::CRC16_MODBUS;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::BufRead;
+use voltage_model::PointType;
+
+use crate::core::config::{
+    ChannelConfig, ChannelCore, ChannelLoggingConfig, Point, RuntimeChannelConfig, VirtualMapping,
+};
+use crate::error::{ComSrvError, Result};
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+/// Frame direction as seen from comsrv
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameDirection {
+    /// Sent by comsrv (requests, writes)
+    #[serde(alias = "send", alias = "out")]
+    Tx,
+    /// Received from the device (responses)
+    #[serde(alias = "recv", alias = "in")]
+    Rx,
+}
+
+/// One captured raw frame
+#[derive(Debug, Clone, Deserialize)]
+pub struct CapturedFrame {
+    /// Capture timestamp in milliseconds
+    #[serde(alias = "ts")]
+    pub ts_ms: u64,
+    pub dir: FrameDirection,
+    /// Frame bytes as hex (separators ignored)
+    pub hex: String,
+}
+
+impl CapturedFrame {
+    fn bytes(&self) -> Option<Vec<u8>> {
+        let digits: Vec<u8> = self.hex.bytes().filter(|b| b.is_ascii_hexdigit()).collect();
+        if !digits.len().is_multiple_of(2) {
+            return None;
+        }
+        digits
+            .chunks(2)
+            .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+            .collect()
+    }
+}
+
+/// Parse a JSON Lines capture; blank lines and `#` comments are skipped
+pub fn parse_capture(reader: impl BufRead) -> Result<Vec<CapturedFrame>> {
+    let mut frames = Vec::new();
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| ComSrvError::IoError(e.to_string()))?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let frame: CapturedFrame = serde_json::from_str(line)
+            .map_err(|e| ComSrvError::DataError(format!("Capture line {}: {}", idx + 1, e)))?;
+        frames.push(frame);
+    }
+    frames.sort_by_key(|f| f.ts_ms);
+    Ok(frames)
+}
+
+/// Capture decoding statistics
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct CaptureStats {
+    pub frames: usize,
+    /// Frames that could not be decoded (bad hex, CRC, truncated)
+    pub invalid_frames: usize,
+    pub read_requests: usize,
+    pub read_responses: usize,
+    pub write_requests: usize,
+    pub exceptions: usize,
+    /// Read requests without a response in the capture
+    pub unanswered: usize,
+    /// Points that never appeared in the capture (no initial value)
+    pub points_without_samples: usize,
+    /// Capture duration in milliseconds
+    pub duration_ms: u64,
+}
+
+/// Value change of one point, relative to the start of the capture
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ReplayEvent {
+    pub offset_ms: u64,
+    pub telemetry_type: String,
+    pub point_id: u32,
+    pub value: f64,
+}
+
+/// Point of the twin with its derived virtual mapping
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct TwinPoint {
+    pub signal_name: String,
+    /// Number of decoded samples
+    pub samples: usize,
+    pub mapping: VirtualMapping,
+}
+
+/// Generated virtual replay configuration
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct VirtualTwin {
+    /// Channel entry for comsrv.yaml (protocol `virtual`)
+    pub channel: ChannelConfig,
+    pub points: Vec<TwinPoint>,
+    pub replay: Vec<ReplayEvent>,
+    pub stats: CaptureStats,
+    /// Config files to drop into `comsrv/` (path -> content)
+    pub files: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Framing {
+    Tcp,
+    Rtu,
+}
+
+/// Modbus data table addressed by a function code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Table {
+    Coils,
+    DiscreteInputs,
+    Holding,
+    Input,
+}
+
+impl Table {
+    fn from_function_code(fc: u8) -> Option<Self> {
+        match fc {
+            1 | 5 | 15 => Some(Self::Coils),
+            2 => Some(Self::DiscreteInputs),
+            3 | 6 | 16 => Some(Self::Holding),
+            4 => Some(Self::Input),
+            _ => None,
+        }
+    }
+
+    fn is_bits(self) -> bool {
+        matches!(self, Self::Coils | Self::DiscreteInputs)
+    }
+}
+
+/// Values of a contiguous address range observed at one instant
+#[derive(Debug)]
+struct Block {
+    ts_ms: u64,
+    unit: u8,
+    table: Table,
+    start: u16,
+    /// Registers, or 0/1 per address for bit tables
+    values: Vec<u16>,
+}
+
+#[derive(Debug)]
+struct PendingRead {
+    ts_ms: u64,
+    unit: u8,
+    fc: u8,
+    start: u16,
+    quantity: u16,
+}
+
+/// Modbus address part of a point's protocol_mappings
+#[derive(Debug, Deserialize)]
+struct ModbusAddress {
+    slave_id: u8,
+    function_code: u8,
+    register_address: u16,
+    #[serde(default = "default_data_type")]
+    data_type: String,
+    #[serde(default = "default_byte_order")]
+    byte_order: String,
+    #[serde(default)]
+    bit_position: Option<u8>,
+}
+
+fn default_data_type() -> String {
+    "uint16".to_string()
+}
+
+fn default_byte_order() -> String {
+    "ABCD".to_string()
+}
+
+/// Point definition fields needed to regenerate the point tables
+struct SourcePoint<'a> {
+    point_type: PointType,
+    base: &'a Point,
+    scale: f64,
+    offset: f64,
+    reverse: bool,
+    data_type: &'a str,
+}
+
+impl RuntimeChannelConfig {
+    fn twin_sources(&self) -> Vec<SourcePoint<'_>> {
+        let mut sources = Vec::new();
+        sources.extend(self.telemetry_points.iter().map(|p| SourcePoint {
+            point_type: PointType::Telemetry,
+            base: &p.base,
+            scale: p.scale,
+            offset: p.offset,
+            reverse: p.reverse,
+            data_type: &p.data_type,
+        }));
+        sources.extend(self.signal_points.iter().map(|p| SourcePoint {
+            point_type: PointType::Signal,
+            base: &p.base,
+            scale: 1.0,
+            offset: 0.0,
+            reverse: p.reverse,
+            data_type: "bool",
+        }));
+        sources.extend(self.control_points.iter().map(|p| SourcePoint {
+            point_type: PointType::Control,
+            base: &p.base,
+            scale: 1.0,
+            offset: 0.0,
+            reverse: p.reverse,
+            data_type: "bool",
+        }));
+        sources.extend(self.adjustment_points.iter().map(|p| SourcePoint {
+            point_type: PointType::Adjustment,
+            base: &p.base,
+            scale: p.scale,
+            offset: p.offset,
+            reverse: false,
+            data_type: &p.data_type,
+        }));
+        sources
+    }
+}
+
+/// Build a virtual twin of a Modbus channel from a captured session
+pub fn generate_virtual_twin(
+    runtime_config: &RuntimeChannelConfig,
+    frames: &[CapturedFrame],
+) -> Result<VirtualTwin> {
+    let framing = match runtime_config.base.protocol() {
+        "modbus_tcp" => Framing::Tcp,
+        "modbus_rtu" => Framing::Rtu,
+        other => {
+            return Err(ComSrvError::ProtocolError(format!(
+                "Virtual twin generation supports modbus_tcp and modbus_rtu, not '{}'",
+                other
+            )))
+        },
+    };
+
+    let mut stats = CaptureStats {
+        frames: frames.len(),
+        ..Default::default()
+    };
+    let (blocks, poll_interval_ms) = decode_session(framing, frames, &mut stats);
+    let start_ms = frames.first().map(|f| f.ts_ms).unwrap_or_default();
+    stats.duration_ms = frames
+        .last()
+        .map(|f| f.ts_ms - start_ms)
+        .unwrap_or_default();
+
+    let channel_id = runtime_config.id();
+    let sources = runtime_config.twin_sources();
+    let mut points = Vec::new();
+    let mut replay = Vec::new();
+
+    for source in &sources {
+        let address = source
+            .base
+            .protocol_mappings
+            .as_deref()
+            .and_then(|json| serde_json::from_str::<ModbusAddress>(json).ok());
+        let samples = address
+            .map(|address| point_samples(&address, &blocks))
+            .unwrap_or_default();
+        if samples.is_empty() {
+            stats.points_without_samples += 1;
+        }
+
+        let telemetry_type = source.point_type.as_str().to_string();
+        let mut last = None;
+        for (ts_ms, value) in &samples {
+            if last != Some(*value) {
+                replay.push(ReplayEvent {
+                    offset_ms: ts_ms - start_ms,
+                    telemetry_type: telemetry_type.clone(),
+                    point_id: source.base.point_id,
+                    value: *value,
+                });
+                last = Some(*value);
+            }
+        }
+
+        let analog = matches!(
+            source.point_type,
+            PointType::Telemetry | PointType::Adjustment
+        );
+        points.push(TwinPoint {
+            signal_name: source.base.signal_name.clone(),
+            samples: samples.len(),
+            mapping: VirtualMapping {
+                channel_id,
+                point_id: source.base.point_id,
+                telemetry_type,
+                expression: None,
+                update_interval: median(samples.windows(2).map(|w| w[1].0 - w[0].0).collect())
+                    .map(|ms| ms as u32),
+                initial_value: typical_value(&samples),
+                noise_range: if analog { noise_range(&samples) } else { None },
+            },
+        });
+    }
+    replay.sort_by_key(|e| e.offset_ms);
+
+    let mut parameters = HashMap::new();
+    if let Some(ms) = poll_interval_ms {
+        parameters.insert("poll_interval_ms".to_string(), serde_json::Value::from(ms));
+    }
+    let channel = ChannelConfig {
+        core: ChannelCore {
+            id: channel_id,
+            name: format!("{}_twin", runtime_config.name()),
+            description: Some(format!(
+                "Virtual twin of channel {} generated from a {} ms capture",
+                channel_id, stats.duration_ms
+            )),
+            protocol: "virtual".to_string(),
+            enabled: true,
+        },
+        parameters,
+        logging: ChannelLoggingConfig::default(),
+    };
+
+    let mut twin = VirtualTwin {
+        channel,
+        points,
+        replay,
+        stats,
+        files: BTreeMap::new(),
+    };
+    twin.files = twin_files(&twin, &sources)?;
+    Ok(twin)
+}
+
+/// Decode frames into value blocks; also returns the median polling period
+fn decode_session(
+    framing: Framing,
+    frames: &[CapturedFrame],
+    stats: &mut CaptureStats,
+) -> (Vec<Block>, Option<u64>) {
+    let mut blocks = Vec::new();
+    // TCP pairs by transaction ID; RTU is strictly request/response
+    let mut pending: HashMap<Option<u16>, PendingRead> = HashMap::new();
+    let mut last_poll: HashMap<(u8, u8, u16), u64> = HashMap::new();
+    let mut poll_periods = Vec::new();
+
+    for frame in frames {
+        let Some((transaction, unit, pdu)) = frame.bytes().and_then(|b| split_adu(framing, &b))
+        else {
+            stats.invalid_frames += 1;
+            continue;
+        };
+        let fc = pdu[0];
+
+        match frame.dir {
+            FrameDirection::Tx => match fc {
+                1..=4 if pdu.len() >= 5 => {
+                    let start = be_u16(&pdu[1..3]);
+                    let quantity = be_u16(&pdu[3..5]);
+                    stats.read_requests += 1;
+                    if let Some(prev) = last_poll.insert((unit, fc, start), frame.ts_ms) {
+                        poll_periods.push(frame.ts_ms - prev);
+                    }
+                    let request = PendingRead {
+                        ts_ms: frame.ts_ms,
+                        unit,
+                        fc,
+                        start,
+                        quantity,
+                    };
+                    if pending.insert(transaction, request).is_some() {
+                        stats.unanswered += 1;
+                    }
+                },
+                5 | 6 | 15 | 16 => match decode_write(fc, &pdu[1..]) {
+                    Some((start, values)) => {
+                        stats.write_requests += 1;
+                        blocks.extend(Table::from_function_code(fc).map(|table| Block {
+                            ts_ms: frame.ts_ms,
+                            unit,
+                            table,
+                            start,
+                            values,
+                        }));
+                    },
+                    None => stats.invalid_frames += 1,
+                },
+                _ => {},
+            },
+            FrameDirection::Rx => {
+                if fc & 0x80 != 0 {
+                    stats.exceptions += 1;
+                    pending.remove(&transaction);
+                    continue;
+                }
+                if !(1..=4).contains(&fc) {
+                    continue;
+                }
+                let Some(request) = pending.remove(&transaction) else {
+                    continue;
+                };
+                let Some(values) = decode_read_response(&request, unit, fc, &pdu[1..]) else {
+                    stats.invalid_frames += 1;
+                    continue;
+                };
+                stats.read_responses += 1;
+                blocks.extend(Table::from_function_code(fc).map(|table| Block {
+                    ts_ms: request.ts_ms,
+                    unit,
+                    table,
+                    start: request.start,
+                    values,
+                }));
+            },
+        }
+    }
+    stats.unanswered += pending.len();
+
+    (blocks, median(poll_periods))
+}
+
+/// Split an ADU into (transaction id, unit id, PDU)
+fn split_adu(framing: Framing, bytes: &[u8]) -> Option<(Option<u16>, u8, Vec<u8>)> {
+    match framing {
+        Framing::Tcp => {
+            if bytes.len() < 8 {
+                return None;
+            }
+            let length = be_u16(&bytes[4..6]) as usize;
+            let pdu = bytes.get(7..6 + length)?;
+            Some((Some(be_u16(&bytes[0..2])), bytes[6], pdu.to_vec()))
+        },
+        Framing::Rtu => {
+            if bytes.len() < 4 {
+                return None;
+            }
+            let (body, crc) = bytes.split_at(bytes.len() - 2);
+            if CRC16_MODBUS.checksum(body) != u16::from_le_bytes([crc[0], crc[1]]) {
+                return None;
+            }
+            Some((None, body[0], body[1..].to_vec()))
+        },
+    }
+}
+
+fn decode_read_response(request: &PendingRead, unit: u8, fc: u8, data: &[u8]) -> Option<Vec<u16>> {
+    if request.unit != unit || request.fc != fc {
+        return None;
+    }
+    let byte_count = *data.first()? as usize;
+    let payload = data.get(1..1 + byte_count)?;
+    let quantity = request.quantity as usize;
+
+    if fc <= 2 {
+        (payload.len() * 8 >= quantity).then(|| unpack_bits(payload, quantity))
+    } else {
+        (payload.len() >= quantity * 2)
+            .then(|| payload.chunks(2).take(quantity).map(be_u16).collect())
+    }
+}
+
+/// Decode a write request body into (start address, values)
+fn decode_write(fc: u8, body: &[u8]) -> Option<(u16, Vec<u16>)> {
+    let start = be_u16(body.get(0..2)?);
+    match fc {
+        5 => Some((start, vec![u16::from(be_u16(body.get(2..4)?) == 0xFF00)])),
+        6 => Some((start, vec![be_u16(body.get(2..4)?)])),
+        15 => {
+            let quantity = be_u16(body.get(2..4)?) as usize;
+            let bytes = body.get(5..5 + *body.get(4)? as usize)?;
+            Some((start, unpack_bits(bytes, quantity)))
+        },
+        16 => {
+            let quantity = be_u16(body.get(2..4)?) as usize;
+            let bytes = body.get(5..5 + quantity * 2)?;
+            Some((start, bytes.chunks(2).map(be_u16).collect()))
+        },
+        _ => None,
+    }
+}
+
+fn unpack_bits(bytes: &[u8], quantity: usize) -> Vec<u16> {
+    (0..quantity)
+        .map(|i| u16::from(bytes[i / 8] >> (i % 8) & 1 == 1))
+        .collect()
+}
+
+fn be_u16(bytes: &[u8]) -> u16 {
+    u16::from_be_bytes([bytes[0], bytes[1]])
+}
+
+/// Raw (unscaled) samples of one point in capture order
+fn point_samples(address: &ModbusAddress, blocks: &[Block]) -> Vec<(u64, f64)> {
+    let Some(table) = Table::from_function_code(address.function_code) else {
+        return Vec::new();
+    };
+    let data_type = address.data_type.to_lowercase();
+    let words = register_count(&data_type);
+
+    blocks
+        .iter()
+        .filter(|b| b.unit == address.slave_id && b.table == table)
+        .filter_map(|block| {
+            let index = address.register_address.checked_sub(block.start)? as usize;
+            let width = if table.is_bits() { 1 } else { words };
+            let values = block.values.get(index..index + width)?;
+            let value = if table.is_bits() {
+                f64::from(values[0])
+            } else {
+                decode_registers(
+                    values,
+                    &data_type,
+                    &address.byte_order,
+                    address.bit_position,
+                )?
+            };
+            Some((block.ts_ms, value))
+        })
+        .collect()
+}
+
+fn register_count(data_type: &str) -> usize {
+    match data_type {
+        "uint32" | "int32" | "float32" | "float" | "u32" | "i32" | "f32" => 2,
+        "uint64" | "int64" | "float64" | "double" | "u64" | "i64" | "f64" => 4,
+        _ => 1,
+    }
+}
+
+/// Decode registers with the channel's byte order (ABCD, DCBA, BADC, CDAB)
+fn decode_registers(
+    registers: &[u16],
+    data_type: &str,
+    byte_order: &str,
+    bit_position: Option<u8>,
+) -> Option<f64> {
+    let mut words: Vec<u16> = registers.to_vec();
+    let order = byte_order.to_uppercase();
+    if matches!(
+        order.as_str(),
+        "CDAB" | "DCBA" | "BYTE_SWAP" | "LITTLE_ENDIAN" | "LE"
+    ) {
+        words.reverse();
+    }
+    if matches!(
+        order.as_str(),
+        "BADC" | "DCBA" | "WORD_SWAP" | "LITTLE_ENDIAN" | "LE" | "BA"
+    ) {
+        words = words.iter().map(|w| w.swap_bytes()).collect();
+    }
+    let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_be_bytes()).collect();
+
+    let value = match data_type {
+        "bool" | "boolean" | "bit" => f64::from((words[0] >> bit_position.unwrap_or(0)) & 1),
+        "int16" | "i16" => f64::from(words[0] as i16),
+        "uint32" | "u32" => f64::from(u32::from_be_bytes(bytes.get(..4)?.try_into().ok()?)),
+        "int32" | "i32" => f64::from(i32::from_be_bytes(bytes.get(..4)?.try_into().ok()?)),
+        "float32" | "float" | "f32" => {
+            f64::from(f32::from_be_bytes(bytes.get(..4)?.try_into().ok()?))
+        },
+        "uint64" | "u64" => u64::from_be_bytes(bytes.get(..8)?.try_into().ok()?) as f64,
+        "int64" | "i64" => i64::from_be_bytes(bytes.get(..8)?.try_into().ok()?) as f64,
+        "float64" | "double" | "f64" => f64::from_be_bytes(bytes.get(..8)?.try_into().ok()?),
+        _ => match bit_position {
+            Some(bit) => f64::from((words[0] >> bit) & 1),
+            None => f64::from(words[0]),
+        },
+    };
+    value.is_finite().then_some(value)
+}
+
+/// Most frequent sample value (the device's typical response)
+fn typical_value(samples: &[(u64, f64)]) -> Option<f64> {
+    let mut counts: Vec<(f64, usize)> = Vec::new();
+    for (_, value) in samples {
+        match counts.iter_mut().find(|(v, _)| v == value) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((*value, 1)),
+        }
+    }
+    // First seen wins ties so the twin starts like the capture did
+    counts
+        .iter()
+        .fold(None, |best: Option<(f64, usize)>, &(v, c)| match best {
+            Some((_, best_count)) if best_count >= c => best,
+            _ => Some((v, c)),
+        })
+        .map(|(v, _)| v)
+}
+
+/// Half of the observed spread, or None for constant values
+fn noise_range(samples: &[(u64, f64)]) -> Option<f64> {
+    let (min, max) = samples
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), (_, v)| {
+            (lo.min(*v), hi.max(*v))
+        });
+    (max > min).then(|| (max - min) / 2.0)
+}
+
+fn median(mut values: Vec<u64>) -> Option<u64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    Some(values[values.len() / 2])
+}
+
+/// Render channel YAML, point tables, virtual mappings and replay timeline
+fn twin_files(twin: &VirtualTwin, sources: &[SourcePoint<'_>]) -> Result<BTreeMap<String, String>> {
+    let csv_error = |e: csv::Error| ComSrvError::DataError(e.to_string());
+    let channel_id = twin.channel.id();
+    let mut files = BTreeMap::new();
+
+    let channel_yaml = serde_yaml::to_string(&vec![&twin.channel])
+        .map_err(|e| ComSrvError::DataError(e.to_string()))?;
+    files.insert("channel.yaml".to_string(), channel_yaml);
+
+    for (point_type, label) in [
+        (PointType::Telemetry, "telemetry"),
+        (PointType::Signal, "signal"),
+        (PointType::Control, "control"),
+        (PointType::Adjustment, "adjustment"),
+    ] {
+        let rows: Vec<(&SourcePoint<'_>, &TwinPoint)> = sources
+            .iter()
+            .zip(&twin.points)
+            .filter(|(s, _)| s.point_type == point_type)
+            .collect();
+        if rows.is_empty() {
+            continue;
+        }
+
+        let mut points = csv::Writer::from_writer(Vec::new());
+        points
+            .write_record([
+                "point_id",
+                "signal_name",
+                "scale",
+                "offset",
+                "unit",
+                "reverse",
+                "data_type",
+                "description",
+            ])
+            .map_err(csv_error)?;
+        let mut mappings = csv::Writer::from_writer(Vec::new());
+        mappings
+            .write_record([
+                "point_id",
+                "expression",
+                "update_interval",
+                "initial_value",
+                "noise_range",
+            ])
+            .map_err(csv_error)?;
+
+        let optional = |v: Option<String>| v.unwrap_or_default();
+        for (source, point) in rows {
+            points
+                .write_record([
+                    source.base.point_id.to_string(),
+                    source.base.signal_name.clone(),
+                    source.scale.to_string(),
+                    source.offset.to_string(),
+                    optional(source.base.unit.clone()),
+                    source.reverse.to_string(),
+                    source.data_type.to_string(),
+                    optional(source.base.description.clone()),
+                ])
+                .map_err(csv_error)?;
+            mappings
+                .write_record([
+                    point.mapping.point_id.to_string(),
+                    optional(point.mapping.expression.clone()),
+                    optional(point.mapping.update_interval.map(|v| v.to_string())),
+                    optional(point.mapping.initial_value.map(|v| v.to_string())),
+                    optional(point.mapping.noise_range.map(|v| v.to_string())),
+                ])
+                .map_err(csv_error)?;
+        }
+
+        files.insert(format!("{}/{}.csv", channel_id, label), csv_string(points)?);
+        files.insert(
+            format!("{}/mapping/{}_mapping.csv", channel_id, label),
+            csv_string(mappings)?,
+        );
+    }
+
+    let mut replay = csv::Writer::from_writer(Vec::new());
+    replay
+        .write_record(["offset_ms", "telemetry_type", "point_id", "value"])
+        .map_err(csv_error)?;
+    for event in &twin.replay {
+        replay
+            .write_record([
+                event.offset_ms.to_string(),
+                event.telemetry_type.clone(),
+                event.point_id.to_string(),
+                event.value.to_string(),
+            ])
+            .map_err(csv_error)?;
+    }
+    files.insert(format!("{}/replay.csv", channel_id), csv_string(replay)?);
+
+    Ok(files)
+}
+
+fn csv_string(writer: csv::Writer<Vec<u8>>) -> Result<String> {
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| ComSrvError::DataError(e.to_string()))?;
+    String::from_utf8(bytes).map_err(|e| ComSrvError::DataError(e.to_string()))
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use crate::core::config::{SignalPoint, TelemetryPoint};
+
+    fn frame(ts_ms: u64, dir: &str, hex: &str) -> String {
+        format!(
+            r#"{{"ts_ms": {}, "dir": "{}", "hex": "{}"}}"#,
+            ts_ms, dir, hex
+        )
+    }
+
+    fn runtime_config() -> RuntimeChannelConfig {
+        let base: ChannelConfig = serde_json::from_value(serde_json::json!({
+            "id": 7, "name": "pcs", "protocol": "modbus_tcp"
+        }))
+        .unwrap();
+        let mut config = RuntimeChannelConfig::from_base(base);
+        config.telemetry_points.push(
+            serde_json::from_value::<TelemetryPoint>(serde_json::json!({
+                "point_id": 1, "signal_name": "Power", "scale": 0.1, "unit": "kW",
+                "data_type": "float32",
+                "protocol_mappings": serde_json::json!({
+                    "slave_id": 1, "function_code": 3, "register_address": 100,
+                    "data_type": "float32", "byte_order": "ABCD"
+                })
+                .to_string()
+            }))
+            .unwrap(),
+        );
+        config.signal_points.push(
+            serde_json::from_value::<SignalPoint>(serde_json::json!({
+                "point_id": 1, "signal_name": "Running",
+                "protocol_mappings": serde_json::json!({
+                    "slave_id": 1, "function_code": 3, "register_address": 102,
+                    "data_type": "uint16", "bit_position": 1
+                })
+                .to_string()
+            }))
+            .unwrap(),
+        );
+        config
+    }
+
+    #[test]
+    fn test_decode_registers_byte_orders() {
+        // 25.0f32 = 0x41C8_0000
+        assert_eq!(
+            decode_registers(&[0x41C8, 0x0000], "float32", "ABCD", None),
+            Some(25.0)
+        );
+        assert_eq!(
+            decode_registers(&[0x0000, 0x41C8], "float32", "CDAB", None),
+            Some(25.0)
+        );
+        assert_eq!(
+            decode_registers(&[0xC841, 0x0000], "float32", "BADC", None),
+            Some(25.0)
+        );
+        assert_eq!(
+            decode_registers(&[0xFFFE], "int16", "ABCD", None),
+            Some(-2.0)
+        );
+        assert_eq!(
+            decode_registers(&[0b10], "uint16", "ABCD", Some(1)),
+            Some(1.0)
+        );
+    }
+
+    #[test]
+    fn test_generate_virtual_twin_from_tcp_capture() {
+        // Three polls of holding registers 100..103: power 25.0, 25.0, 30.0; running bit set
+        let capture = [
+            frame(1000, "tx", "00 01 00 00 00 06 01 03 00 64 00 03"),
+            frame(1010, "rx", "00 01 00 00 00 09 01 03 06 41 C8 00 00 00 02"),
+            frame(2000, "tx", "00 02 00 00 00 06 01 03 00 64 00 03"),
+            frame(2010, "rx", "00 02 00 00 00 09 01 03 06 41 C8 00 00 00 02"),
+            frame(3000, "tx", "00 03 00 00 00 06 01 03 00 64 00 03"),
+            frame(3010, "rx", "00 03 00 00 00 09 01 03 06 41 F0 00 00 00 00"),
+            frame(4000, "tx", "00 04 00 00 00 06 01 03 00 64 00 03"),
+            frame(4010, "rx", "00 04 00 00 00 03 01 83 02"),
+        ]
+        .join("\n");
+        let frames = parse_capture(capture.as_bytes()).unwrap();
+        let twin = generate_virtual_twin(&runtime_config(), &frames).unwrap();
+
+        assert_eq!(twin.channel.protocol(), "virtual");
+        assert_eq!(twin.channel.parameters["poll_interval_ms"], 1000);
+        assert_eq!(twin.stats.read_responses, 3);
+        assert_eq!(twin.stats.exceptions, 1);
+        assert_eq!(twin.stats.unanswered, 0);
+
+        let power = &twin.points[0].mapping;
+        assert_eq!(power.initial_value, Some(25.0));
+        assert_eq!(power.noise_range, Some(2.5));
+        assert_eq!(power.update_interval, Some(1000));
+
+        let running = &twin.points[1].mapping;
+        assert_eq!(running.telemetry_type, "S");
+        assert_eq!(running.initial_value, Some(1.0));
+        assert_eq!(running.noise_range, None);
+
+        // Initial values for both points, then the two changes at the third poll
+        assert_eq!(twin.replay.len(), 4);
+        assert_eq!(twin.replay[3].offset_ms, 2000);
+
+        assert!(twin.files["7/telemetry.csv"].contains("1,Power,0.1,0,kW,false,float32"));
+        assert!(twin.files["7/mapping/telemetry_mapping.csv"].contains("1,,1000,25,2.5"));
+        assert!(twin.files.contains_key("7/replay.csv"));
+    }
+}
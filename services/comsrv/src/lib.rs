@@ -20,23 +20,34 @@ pub mod api {
         pub mod channel_handlers;
         pub mod channel_management_handlers;
         pub mod control_handlers;
+        pub mod dead_letter_handlers;
+        pub mod force_handlers;
         pub mod health;
         pub mod mapping_handlers;
         pub mod point_handlers;
         pub mod protocol_handlers;
+        pub mod webhook_handlers;
     }
 }
 
 // Inline module declarations to avoid extra thin shell files
 pub mod core {
+    pub mod archive;
     pub mod bootstrap;
     pub mod channels;
     pub mod config;
+    pub mod plugins;
+    pub mod protocols;
     pub mod reload;
+    pub mod twin;
 }
 
 pub mod store;
 
+// In-process fixture for cross-service tests (common::test_utils::services)
+#[cfg(any(test, feature = "test-fixtures"))]
+pub mod test_fixtures;
+
 // Re-export IGW DataStore implementation
 pub use store::RedisDataStore;
 
@@ -53,6 +64,8 @@ pub mod runtime {
 
     pub mod cleanup_provider;
     pub mod lifecycle;
+    #[cfg(feature = "opcua")]
+    pub mod opcua_server;
     pub mod reconnect;
 
     #[cfg(test)]
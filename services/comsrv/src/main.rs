@@ -6,17 +6,16 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 
 use axum::serve;
-use clap::Parser;
 #[cfg(feature = "swagger-ui")]
 use comsrv::api::routes::ComsrvApiDoc;
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 #[cfg(feature = "swagger-ui")]
 use utoipa::OpenApi;
 #[cfg(feature = "swagger-ui")]
 use utoipa_swagger_ui::SwaggerUi;
 
-use common::service_bootstrap::ServiceInfo;
+use common::service_bootstrap::{Dependency, DependencyWaitConfig, DependencyWaiter, ServiceInfo};
 use comsrv::core::config::DEFAULT_PORT;
 use errors::VoltageResult;
 
@@ -28,8 +27,8 @@ use comsrv::{
     },
     cleanup_provider::ComsrvCleanupProvider,
     core::{
-        bootstrap::{self, Args},
-        channels::ChannelManager,
+        bootstrap,
+        channels::{ChannelManager, Redundancy, RedundancyConfig},
         config::ConfigManager,
     },
     error::ComSrvError,
@@ -41,47 +40,67 @@ use voltage_rtdb::{is_shm_available, ChannelToSlotIndex, SharedConfig, SharedVec
 
 #[tokio::main]
 async fn main() -> VoltageResult<()> {
-    // Parse arguments and initialize
-    let args = Args::parse();
-    let service_args = args.clone().into();
-
     let service_info = ServiceInfo::new(
         "comsrv",
         "Industrial Communication Service - Multi-Protocol Support",
         DEFAULT_PORT,
     );
 
+    // Parse arguments and initialize
+    let service_args = bootstrap::parse_args(&service_info);
+    service_args
+        .apply_env()
+        .map_err(|e| ComSrvError::ConfigError(format!("--config: {}", e)))?;
+
     // Bootstrap: logging (API logging enabled by default), banner, system checks
     // Note: Config not loaded yet, use VOLTAGE_LOG_DIR env or default
     bootstrap::initialize_logging(&service_args, &service_info, None)?;
     // Enable SIGHUP-triggered log reopen
     common::logging::enable_sighup_log_reopen();
-    if !args.no_color {
+    if !service_args.no_color {
         common::service_bootstrap::print_startup_banner(&service_info);
     }
     bootstrap::check_system_requirements()?;
 
+    let db_path = service_args.get_db_path("comsrv");
+
     // Validation mode: validate and exit
-    if args.validate {
-        bootstrap::validate_configuration().await?;
+    if service_args.validate {
+        bootstrap::validate_configuration(&service_info, &db_path).await?;
         info!("Validation completed successfully");
         return Ok(());
     }
 
     // Load configuration from unified database
-    let db_path = service_args.get_db_path("comsrv");
     info!(
         "Loading configuration from unified SQLite database: {}",
         db_path
     );
-    let config_manager = Arc::new(ConfigManager::load().await?);
-    let app_config = config_manager.config();
+
+    // Wait for the configuration database before loading config
+    let dependency_waiter = DependencyWaiter::new(DependencyWaitConfig::from_env());
+    dependency_waiter
+        .wait_for(&[
+            Dependency::ConfigPresent(db_path.clone().into()),
+            Dependency::Database(db_path.clone().into()),
+        ])
+        .await
+        .map_err(|e| ComSrvError::ConfigError(e.to_string()))?;
 
     // Create SQLite pool for API endpoints
     let sqlite_pool = sqlx::SqlitePool::connect(&format!("sqlite:{}", db_path))
         .await
         .map_err(|e| ComSrvError::ConfigError(format!("Failed to create SQLite pool: {}", e)))?;
 
+    // Bring the schema up to date before reading configuration
+    comsrv::core::config::migrations::migrator()
+        .run(&sqlite_pool)
+        .await
+        .map_err(|e| ComSrvError::ConfigError(format!("Schema migration failed: {:#}", e)))?;
+
+    let config_manager = Arc::new(ConfigManager::load().await?);
+    let app_config = config_manager.config();
+
     // Calculate dynamic Redis connection pool size based on channel count
     let channel_count = app_config.channels.len();
     let max_connections = (channel_count * 2 + 30).max(50); // Minimum 50 connections
@@ -90,15 +109,20 @@ async fn main() -> VoltageResult<()> {
         channel_count, max_connections
     );
 
+    // Wait for Redis within the same startup deadline (--redis-url > DB > ENV)
+    let configured_redis_url = service_args.redis_url_or(Some(app_config.redis.url.clone()));
+    dependency_waiter
+        .wait_for(&[Dependency::redis(configured_redis_url.clone())])
+        .await
+        .map_err(|e| ComSrvError::ConfigError(e.to_string()))?;
+
     // Setup Redis connection with custom pool configuration
     let mut redis_config = common::redis::RedisPoolConfig::from_url(&app_config.redis.url);
     redis_config.max_connections = max_connections as u32;
 
-    let (redis_url, redis_client) = common::bootstrap_database::setup_redis_with_config(
-        Some(app_config.redis.url.clone()),
-        redis_config,
-    )
-    .await?;
+    let (redis_url, redis_client) =
+        common::bootstrap_database::setup_redis_with_config(configured_redis_url, redis_config)
+            .await?;
 
     // ============ Phase 1: Create initial rtdb for cleanup ============
     // Reuse the existing connection pool instead of creating a new one
@@ -120,6 +144,39 @@ async fn main() -> VoltageResult<()> {
         },
     }
 
+    // Protocol plugins shipped as shared libraries (COMSRV_PLUGIN_DIR)
+    #[cfg(feature = "dylib-plugins")]
+    {
+        let loaded = comsrv::core::plugins::dylib::load_from_env();
+        if loaded > 0 {
+            info!("Loaded {} protocol plugins", loaded);
+        }
+    }
+
+    // Command result webhooks (registered per API client)
+    match comsrv::core::channels::CommandWebhooks::global()
+        .load(&sqlite_pool)
+        .await
+    {
+        Ok(0) => {},
+        Ok(count) => info!("Loaded {} command webhooks", count),
+        Err(e) => warn!("Failed to load command webhooks: {}", e),
+    }
+
+    // Cross-check routing against channels/points before loading it
+    common::config_consistency::run_startup_check(&sqlite_pool, "comsrv").await;
+
+    // Resource profile: buffer/batch sizes and startup concurrency
+    common::service_bootstrap::init_resource_profile(&service_info, &sqlite_pool).await;
+
+    // Feature flags: global/service config plus runtime overrides from Redis
+    common::service_bootstrap::init_feature_flags(
+        &service_info,
+        &sqlite_pool,
+        Some(Arc::clone(&redis_client)),
+    )
+    .await;
+
     // ============ Phase 2: Load routing configuration from unified database ============
     info!("Loading routing cache from unified database...");
     let routing_cache = {
@@ -129,6 +186,10 @@ async fn main() -> VoltageResult<()> {
             .map_err(|e| ComSrvError::ConfigError(format!("Failed to load routing: {}", e)))?;
 
         info!("Loaded routing cache: {} total routes", maps.total_routes());
+        if !maps.c2m_publish.is_empty() {
+            info!("{} C2M routes publish by exception", maps.c2m_publish.len());
+        }
+        voltage_routing::publish::set_policies(maps.c2m_publish);
 
         Arc::new(voltage_rtdb::RoutingCache::from_maps(
             maps.c2m, maps.m2c, maps.c2c,
@@ -340,11 +401,25 @@ async fn main() -> VoltageResult<()> {
     // Use concrete type (native AFIT requires static dispatch)
     let rtdb: Arc<voltage_rtdb::RedisRtdb> = Arc::new(redis_rtdb);
 
+    // OPC UA server mirroring the channel hashes (service_config opcua_server.*)
+    #[cfg(feature = "opcua")]
+    match comsrv::runtime::opcua_server::start_from_config(
+        &sqlite_pool,
+        Arc::clone(&rtdb),
+        shutdown_token.clone(),
+    )
+    .await
+    {
+        Ok(Some(server)) => debug!("OPC UA server bound to {}", server.local_addr),
+        Ok(None) => {},
+        Err(e) => error!("OPC UA server not started: {}", e),
+    }
+
     // Create channel manager with optional shared memory and CommandTxCache support
     // Lock-free architecture - no RwLock wrapper needed
     // Removed VecRtdb - SharedMemory + Redis two-tier architecture
     let channel_manager = Arc::new(ChannelManager::with_shared_memory(
-        rtdb,
+        Arc::clone(&rtdb),
         routing_cache,
         sqlite_pool.clone(),
         shared_writer,
@@ -354,7 +429,7 @@ async fn main() -> VoltageResult<()> {
 
     // Determine bind address and start server
     let bind_address = bootstrap::determine_bind_address(
-        args.bind_address,
+        service_args.bind_address.clone(),
         &app_config.api.host,
         app_config.api.port,
     );
@@ -367,6 +442,16 @@ async fn main() -> VoltageResult<()> {
         info!("Redis storage enabled at: {}", app_config.redis.url);
     }
 
+    // Active/standby election with a peer instance (service_config redundancy.*)
+    let redundancy_handle = Redundancy::global()
+        .start(
+            RedundancyConfig::load(&sqlite_pool).await?,
+            rtdb,
+            Arc::clone(&channel_manager),
+            shutdown_token.clone(),
+        )
+        .await;
+
     // Start communication channels
     let configured_count =
         start_communication_service(config_manager.clone(), Arc::clone(&channel_manager)).await?;
@@ -419,7 +504,10 @@ async fn main() -> VoltageResult<()> {
     info!("API server listening on http://{}", addr);
     info!("Health check: http://{}/health", addr);
 
-    let server = serve(listener, app);
+    let server = serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    );
     let server_token = shutdown_token.clone();
     let server_handle = tokio::spawn(async move {
         let shutdown = async move { server_token.cancelled().await };
@@ -439,6 +527,10 @@ async fn main() -> VoltageResult<()> {
         warning_handle,
     )
     .await;
+    // Hand the lease over right away instead of letting it expire
+    if let Some(handle) = redundancy_handle {
+        let _ = tokio::time::timeout(std::time::Duration::from_secs(2), handle).await;
+    }
 
     Ok(())
 }
@@ -3,7 +3,7 @@
 //! Provides orchestration functions for service startup, shutdown, and maintenance tasks
 //! as part of the runtime orchestration layer
 
-use crate::core::channels::ChannelManager;
+use crate::core::channels::{ChannelManager, Redundancy};
 use crate::core::config::ConfigManager;
 use crate::error::Result;
 use std::sync::Arc;
@@ -134,8 +134,9 @@ pub(crate) async fn start_communication_service_generic<R: Rtdb + 'static>(
         );
     }
 
-    // Create all channels concurrently to improve startup performance.
-    use futures::future::join_all;
+    // Create channels concurrently, bounded by the resource profile.
+    use futures::stream::{self, StreamExt};
+    let concurrency = common::resource_profile::tuning().startup_concurrency;
 
     // First create all channel instances concurrently without holding the lock.
     let channel_futures: Vec<_> = configs
@@ -174,7 +175,10 @@ pub(crate) async fn start_communication_service_generic<R: Rtdb + 'static>(
         .collect();
 
     // Wait for all channels to be created.
-    let results = join_all(channel_futures).await;
+    let results: Vec<_> = stream::iter(channel_futures)
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
 
     // Summarize successful and failed channel creations.
     let mut successful_channels = 0;
@@ -211,16 +215,20 @@ pub(crate) async fn start_communication_service_generic<R: Rtdb + 'static>(
     tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 
     // Phase 2: Establish connections for all channels in batch
-    info!("Starting connection phase for all initialized channels...");
-    // Direct access without RwLock (lock-free)
-    match channel_manager.connect_all_channels().await {
-        Ok(()) => {
-            info!("All channel connections completed successfully");
-        },
-        Err(e) => {
-            error!("Some channel connections failed: {}", e);
-            // Connection failure should not prevent service startup, continue running
-        },
+    if Redundancy::global().is_standby() {
+        info!("Standby node: channel connections deferred until takeover");
+    } else {
+        info!("Starting connection phase for all initialized channels...");
+        // Direct access without RwLock (lock-free)
+        match channel_manager.connect_all_channels().await {
+            Ok(()) => {
+                info!("All channel connections completed successfully");
+            },
+            Err(e) => {
+                error!("Some channel connections failed: {}", e);
+                // Connection failure should not prevent service startup, continue running
+            },
+        }
     }
 
     info!(
@@ -333,6 +341,14 @@ pub(crate) async fn shutdown_handler_generic<R: Rtdb + 'static>(
         .map(|channel_id| {
             let channel_manager = Arc::clone(&channel_manager);
             async move {
+                // Finish in-flight commands before tearing the channel down
+                if let Err(e) = channel_manager
+                    .drain_channel(channel_id, common::timeouts::SHUTDOWN_TIMEOUT)
+                    .await
+                {
+                    warn!("Ch{} drain failed: {}", channel_id, e);
+                }
+
                 // Direct access without RwLock (lock-free)
                 let result = channel_manager.remove_channel(channel_id).await;
 
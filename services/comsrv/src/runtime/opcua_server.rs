@@ -0,0 +1,1901 @@
+//! OPC UA server exposing comsrv points northbound
+//!
+//! Mirrors the realtime hashes (`comsrv:{id}:T/S/C/A`) into an OPC UA
+//! address space so SCADA clients can browse, read and subscribe to channel
+//! points. Writes to control and adjustment variables go through the same
+//! path as `POST /api/channels/{id}/write`: the channel hash is updated and
+//! the command is queued on the channel's TODO queue.
+//!
+//! ```text
+//! Objects (i=85)
+//! └── pcs1             ns=1;s=1001          channel
+//!     ├── Telemetry    ns=1;s=1001:T        folder
+//!     │   └── P        ns=1;s=1001:T:1      Double, read only
+//!     ├── Signal       ns=1;s=1001:S        Boolean, read only
+//!     ├── Control      ns=1;s=1001:C        Boolean, writable
+//!     └── Adjustment   ns=1;s=1001:A        Double, writable
+//! ```
+//!
+//! Only SecurityPolicy None is offered. Sessions live as long as their
+//! connection, and the address space is built from SQLite at startup, so
+//! channels added later appear after a restart.
+//!
+//! Configured in `service_config` (service `comsrv`):
+//!
+//! | Key                            | Default   |                                  |
+//! |--------------------------------|-----------|----------------------------------|
+//! | `opcua_server.enabled`         | `false`   |                                  |
+//! | `opcua_server.host`            | `0.0.0.0` |                                  |
+//! | `opcua_server.port`            | `4840`    |                                  |
+//! | `opcua_server.sync_interval_ms`| `500`     | RTDB mirror period               |
+//! | `opcua_server.read_only`       | `false`   | reject all writes                |
+//! | `opcua_server.username`        | -         | require this user (no anonymous) |
+//! | `opcua_server.password`        | -         |                                  |
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use rand::RngCore;
+use sqlx::SqlitePool;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use voltage_model::{KeySpaceConfig, PointType};
+use voltage_rtdb::Rtdb;
+
+use crate::core::protocols::opcua::codec::{
+    self, attribute, builtin, ids, status, DataValue, Decoder, Encoder, Identifier, Incoming,
+    NodeId, SecureChannel, Variant, SECURITY_MODE_NONE, SECURITY_POLICY_NONE,
+};
+use crate::core::protocols::CodecError;
+use crate::error::{ComSrvError, Result};
+
+const DEFAULT_PORT: u16 = 4840;
+const DEFAULT_SYNC_INTERVAL_MS: u64 = 500;
+
+/// Namespace of the channel and point nodes
+const NAMESPACE: u16 = 1;
+const NAMESPACE_URI: &str = "urn:voltage-ems:comsrv";
+
+/// Standard NamespaceArray variable of the Server object
+const NAMESPACE_ARRAY: u32 = 2255;
+
+const APPLICATION_NAME: &str = "VoltageEMS comsrv";
+const TRANSPORT_PROFILE: &str = "http://opcfoundation.org/UA-Profile/Transport/uatcp-uasc-uabinary";
+
+/// Limits of what a client can ask for
+const MIN_PUBLISHING_INTERVAL_MS: f64 = 100.0;
+const MAX_KEEP_ALIVE_COUNT: u32 = 100;
+const MAX_QUEUED_PUBLISH: usize = 10;
+const MAX_CONTINUATION_POINTS: usize = 16;
+const SESSION_TIMEOUT_MS: f64 = 60_000.0;
+const CHANNEL_LIFETIME_MS: u32 = 3_600_000;
+
+/// Period of the subscription check on each connection
+const PUBLISH_TICK: Duration = Duration::from_millis(50);
+
+/// Node classes
+const NODE_CLASS_OBJECT: u32 = 1;
+const NODE_CLASS_VARIABLE: u32 = 2;
+
+/// Access level bits (CurrentRead, CurrentWrite)
+const ACCESS_READ: u8 = 0x01;
+const ACCESS_READ_WRITE: u8 = 0x03;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Server settings from `service_config`
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpcUaServerConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub sync_interval: Duration,
+    pub read_only: bool,
+    /// Required user name and password; anonymous sessions otherwise
+    pub credentials: Option<(String, String)>,
+}
+
+impl Default for OpcUaServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "0.0.0.0".to_string(),
+            port: DEFAULT_PORT,
+            sync_interval: Duration::from_millis(DEFAULT_SYNC_INTERVAL_MS),
+            read_only: false,
+            credentials: None,
+        }
+    }
+}
+
+impl OpcUaServerConfig {
+    /// Parse `(key, value)` rows; keys without the `opcua_server.` prefix are ignored
+    pub fn from_rows<I>(rows: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut config = Self::default();
+        let (mut username, mut password) = (None, None);
+        for (key, value) in rows {
+            let Some(key) = key.strip_prefix("opcua_server.") else {
+                continue;
+            };
+            let value = value.trim();
+            let invalid = || {
+                ComSrvError::ConfigError(format!("opcua_server.{}: invalid value '{}'", key, value))
+            };
+            let flag = || match value.to_ascii_lowercase().as_str() {
+                "true" | "1" | "yes" | "on" => Ok(true),
+                "false" | "0" | "no" | "off" => Ok(false),
+                _ => Err(invalid()),
+            };
+            match key {
+                "enabled" => config.enabled = flag()?,
+                "read_only" => config.read_only = flag()?,
+                "host" => config.host = value.to_string(),
+                "port" => config.port = value.parse().map_err(|_| invalid())?,
+                "sync_interval_ms" => {
+                    let ms: u64 = value.parse().map_err(|_| invalid())?;
+                    config.sync_interval = Duration::from_millis(ms.max(50));
+                },
+                "username" if !value.is_empty() => username = Some(value.to_string()),
+                "password" => password = Some(value.to_string()),
+                _ => debug!("Unknown OPC UA server setting opcua_server.{}", key),
+            }
+        }
+        config.credentials = match (username, password) {
+            (Some(user), password) => Some((user, password.unwrap_or_default())),
+            (None, Some(_)) => {
+                return Err(ComSrvError::ConfigError(
+                    "opcua_server.password is set without opcua_server.username".to_string(),
+                ))
+            },
+            (None, None) => None,
+        };
+        Ok(config)
+    }
+
+    pub async fn load(pool: &SqlitePool) -> Result<Self> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT key, value FROM service_config \
+             WHERE service_name = 'comsrv' AND key LIKE 'opcua_server.%'",
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            ComSrvError::ConfigError(format!("Failed to load OPC UA server config: {}", e))
+        })?;
+        Self::from_rows(rows)
+    }
+
+    fn bind_address(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+}
+
+// ============================================================================
+// Address space
+// ============================================================================
+
+/// Point exposed as a variable
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointNode {
+    pub point_type: PointType,
+    pub point_id: u32,
+    pub name: String,
+    pub unit: Option<String>,
+}
+
+/// Channel exposed as an object with one folder per point type
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelNodes {
+    pub channel_id: u32,
+    pub name: String,
+    pub points: Vec<PointNode>,
+}
+
+/// Channels and points of the SQLite configuration
+pub async fn load_channels(pool: &SqlitePool) -> Result<Vec<ChannelNodes>> {
+    let db_error =
+        |e: sqlx::Error| ComSrvError::ConfigError(format!("Failed to load points: {}", e));
+    let channels: Vec<(i64, String)> = sqlx::query_as(
+        "SELECT channel_id, name FROM channels WHERE enabled = 1 ORDER BY channel_id",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(db_error)?;
+    let mut channels: BTreeMap<u32, ChannelNodes> = channels
+        .into_iter()
+        .map(|(id, name)| {
+            (
+                id as u32,
+                ChannelNodes {
+                    channel_id: id as u32,
+                    name,
+                    points: Vec::new(),
+                },
+            )
+        })
+        .collect();
+
+    for (point_type, table) in [
+        (PointType::Telemetry, "telemetry_points"),
+        (PointType::Signal, "signal_points"),
+        (PointType::Control, "control_points"),
+        (PointType::Adjustment, "adjustment_points"),
+    ] {
+        let rows: Vec<(i64, i64, String, Option<String>)> = sqlx::query_as(&format!(
+            "SELECT channel_id, point_id, signal_name, unit FROM {} ORDER BY channel_id, point_id",
+            table
+        ))
+        .fetch_all(pool)
+        .await
+        .map_err(db_error)?;
+        for (channel_id, point_id, name, unit) in rows {
+            if let Some(channel) = channels.get_mut(&(channel_id as u32)) {
+                channel.points.push(PointNode {
+                    point_type,
+                    point_id: point_id as u32,
+                    name,
+                    unit: unit.filter(|u| !u.is_empty()),
+                });
+            }
+        }
+    }
+    Ok(channels.into_values().collect())
+}
+
+/// RTDB address of a variable: (channel, type, point)
+type PointKey = (u32, PointType, u32);
+
+#[derive(Debug, Clone, PartialEq)]
+enum NodeKind {
+    Folder,
+    Channel,
+    Variable(PointKey),
+    /// Server NamespaceArray
+    Namespaces,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    kind: NodeKind,
+    browse_name: (u16, String),
+    display_name: String,
+    description: String,
+    /// Parent and the reference type from it
+    parent: Option<(u32, NodeId)>,
+    /// (reference type, child)
+    children: Vec<(u32, NodeId)>,
+}
+
+impl Node {
+    fn node_class(&self) -> u32 {
+        match self.kind {
+            NodeKind::Folder | NodeKind::Channel => NODE_CLASS_OBJECT,
+            NodeKind::Variable(_) | NodeKind::Namespaces => NODE_CLASS_VARIABLE,
+        }
+    }
+
+    fn type_definition(&self) -> u32 {
+        match self.kind {
+            NodeKind::Folder => ids::FOLDER_TYPE,
+            NodeKind::Channel => ids::BASE_OBJECT_TYPE,
+            NodeKind::Variable(_) | NodeKind::Namespaces => ids::BASE_DATA_VARIABLE_TYPE,
+        }
+    }
+}
+
+fn type_name(point_type: PointType) -> &'static str {
+    match point_type {
+        PointType::Telemetry => "Telemetry",
+        PointType::Signal => "Signal",
+        PointType::Control => "Control",
+        PointType::Adjustment => "Adjustment",
+    }
+}
+
+/// Signals and controls are booleans, telemetry and adjustments doubles
+fn is_boolean(point_type: PointType) -> bool {
+    point_type.is_digital()
+}
+
+fn variable_value(point_type: PointType, value: f64) -> Variant {
+    if is_boolean(point_type) {
+        Variant::Boolean(value != 0.0)
+    } else {
+        Variant::Double(value)
+    }
+}
+
+/// Node ID of a channel, a point type folder or a point
+pub fn point_node_id(channel_id: u32, point: Option<(PointType, Option<u32>)>) -> NodeId {
+    let text = match point {
+        None => channel_id.to_string(),
+        Some((point_type, None)) => format!("{}:{}", channel_id, point_type.as_str()),
+        Some((point_type, Some(point_id))) => {
+            format!("{}:{}:{}", channel_id, point_type.as_str(), point_id)
+        },
+    };
+    NodeId {
+        namespace: NAMESPACE,
+        identifier: Identifier::String(text),
+    }
+}
+
+/// Browsable node tree and the RTDB keys it mirrors
+#[derive(Debug, Default)]
+struct AddressSpace {
+    nodes: HashMap<NodeId, Node>,
+    /// Point IDs to mirror per channel hash
+    groups: HashMap<(u32, PointType), Vec<u32>>,
+}
+
+impl AddressSpace {
+    fn build(channels: &[ChannelNodes]) -> Self {
+        let mut space = Self::default();
+        let root = NodeId::numeric(0, ids::ROOT_FOLDER);
+        let objects = NodeId::numeric(0, ids::OBJECTS_FOLDER);
+        space.insert(root.clone(), NodeKind::Folder, (0, "Root"), "", None);
+        space.insert(
+            objects.clone(),
+            NodeKind::Folder,
+            (0, "Objects"),
+            "",
+            Some((ids::ORGANIZES, root)),
+        );
+        space.insert(
+            NodeId::numeric(0, NAMESPACE_ARRAY),
+            NodeKind::Namespaces,
+            (0, "NamespaceArray"),
+            "",
+            None,
+        );
+
+        for channel in channels {
+            let channel_node = point_node_id(channel.channel_id, None);
+            space.insert(
+                channel_node.clone(),
+                NodeKind::Channel,
+                (NAMESPACE, &channel.name),
+                &format!("Channel {}", channel.channel_id),
+                Some((ids::ORGANIZES, objects.clone())),
+            );
+            for point_type in [
+                PointType::Telemetry,
+                PointType::Signal,
+                PointType::Control,
+                PointType::Adjustment,
+            ] {
+                let folder = point_node_id(channel.channel_id, Some((point_type, None)));
+                space.insert(
+                    folder.clone(),
+                    NodeKind::Folder,
+                    (NAMESPACE, type_name(point_type)),
+                    "",
+                    Some((ids::ORGANIZES, channel_node.clone())),
+                );
+                for point in channel.points.iter().filter(|p| p.point_type == point_type) {
+                    let key = (channel.channel_id, point_type, point.point_id);
+                    space.insert(
+                        point_node_id(channel.channel_id, Some((point_type, Some(point.point_id)))),
+                        NodeKind::Variable(key),
+                        (NAMESPACE, &point.name),
+                        point.unit.as_deref().unwrap_or_default(),
+                        Some((ids::HAS_COMPONENT, folder.clone())),
+                    );
+                    space
+                        .groups
+                        .entry((channel.channel_id, point_type))
+                        .or_default()
+                        .push(point.point_id);
+                }
+            }
+        }
+        space
+    }
+
+    fn insert(
+        &mut self,
+        id: NodeId,
+        kind: NodeKind,
+        browse_name: (u16, &str),
+        description: &str,
+        parent: Option<(u32, NodeId)>,
+    ) {
+        if let Some((reference, parent)) = &parent {
+            if let Some(node) = self.nodes.get_mut(parent) {
+                node.children.push((*reference, id.clone()));
+            }
+        }
+        self.nodes.insert(
+            id,
+            Node {
+                kind,
+                browse_name: (browse_name.0, browse_name.1.to_string()),
+                display_name: browse_name.1.to_string(),
+                description: description.to_string(),
+                parent,
+                children: Vec::new(),
+            },
+        );
+    }
+
+    fn variable_count(&self) -> usize {
+        self.groups.values().map(Vec::len).sum()
+    }
+}
+
+// ============================================================================
+// Server
+// ============================================================================
+
+/// Mirrored point value
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sample {
+    value: f64,
+    timestamp_ms: i64,
+}
+
+struct Shared<R: Rtdb> {
+    config: OpcUaServerConfig,
+    space: AddressSpace,
+    values: RwLock<HashMap<PointKey, Sample>>,
+    rtdb: Arc<R>,
+    next_channel_id: AtomicU32,
+}
+
+impl<R: Rtdb> Shared<R> {
+    fn sample(&self, key: &PointKey) -> Option<Sample> {
+        self.values.read().ok()?.get(key).copied()
+    }
+
+    /// Copy the channel hashes into the mirror
+    async fn sync(&self) {
+        let keyspace = KeySpaceConfig::production_cached();
+        for ((channel_id, point_type), point_ids) in &self.space.groups {
+            let key = keyspace.channel_key(*channel_id, *point_type);
+            let values = match self.rtdb.hash_get_all(&key).await {
+                Ok(values) => values,
+                Err(e) => {
+                    debug!("OPC UA server sync of {}: {}", key, e);
+                    continue;
+                },
+            };
+            if values.is_empty() {
+                continue;
+            }
+            let timestamps = self
+                .rtdb
+                .hash_get_all(&keyspace.channel_ts_key(*channel_id, *point_type))
+                .await
+                .unwrap_or_default();
+            let now = chrono::Utc::now().timestamp_millis();
+            let Ok(mut mirror) = self.values.write() else {
+                return;
+            };
+            for point_id in point_ids {
+                let field = point_id.to_string();
+                let Some(value) = values
+                    .get(&field)
+                    .and_then(|v| std::str::from_utf8(v).ok())
+                    .and_then(|v| v.trim().parse::<f64>().ok())
+                else {
+                    continue;
+                };
+                let timestamp_ms = timestamps
+                    .get(&field)
+                    .and_then(|v| std::str::from_utf8(v).ok())
+                    .and_then(|v| v.trim().parse::<i64>().ok())
+                    .unwrap_or(now);
+                mirror.insert(
+                    (*channel_id, *point_type, *point_id),
+                    Sample {
+                        value,
+                        timestamp_ms,
+                    },
+                );
+            }
+        }
+    }
+
+    fn writable(&self, key: &PointKey) -> bool {
+        !self.config.read_only && key.1.is_action()
+    }
+
+    /// Write a control or adjustment like the REST write API
+    async fn write_point(&self, key: PointKey, value: f64) -> u32 {
+        let (channel_id, point_type, point_id) = key;
+        match voltage_rtdb::helpers::write_point_auto_trigger(
+            self.rtdb.as_ref(),
+            KeySpaceConfig::production_cached(),
+            channel_id,
+            point_type,
+            point_id,
+            value,
+        )
+        .await
+        {
+            Ok(timestamp_ms) => {
+                if let Ok(mut mirror) = self.values.write() {
+                    mirror.insert(
+                        key,
+                        Sample {
+                            value,
+                            timestamp_ms,
+                        },
+                    );
+                }
+                info!(
+                    "OPC UA write Ch{}:{:?}:{} = {}",
+                    channel_id, point_type, point_id, value
+                );
+                status::GOOD
+            },
+            Err(e) => {
+                warn!(
+                    "OPC UA write Ch{}:{:?}:{}: {}",
+                    channel_id, point_type, point_id, e
+                );
+                status::BAD_INTERNAL_ERROR
+            },
+        }
+    }
+}
+
+/// Running OPC UA server
+pub struct OpcUaServer {
+    pub local_addr: std::net::SocketAddr,
+    pub handle: JoinHandle<()>,
+}
+
+impl OpcUaServer {
+    /// Bind the listener and start accepting connections and mirroring values
+    pub async fn start<R: Rtdb + 'static>(
+        config: OpcUaServerConfig,
+        channels: &[ChannelNodes],
+        rtdb: Arc<R>,
+        shutdown: CancellationToken,
+    ) -> Result<Self> {
+        let address = config.bind_address();
+        let listener = TcpListener::bind(&address).await.map_err(|e| {
+            ComSrvError::ConnectionError(format!("OPC UA server bind {}: {}", address, e))
+        })?;
+        let local_addr = listener
+            .local_addr()
+            .map_err(|e| ComSrvError::ConnectionError(e.to_string()))?;
+
+        let space = AddressSpace::build(channels);
+        info!(
+            "OPC UA server listening on opc.tcp://{} ({} channels, {} variables)",
+            local_addr,
+            channels.len(),
+            space.variable_count()
+        );
+        let shared = Arc::new(Shared {
+            config,
+            space,
+            values: RwLock::new(HashMap::new()),
+            rtdb,
+            next_channel_id: AtomicU32::new(1),
+        });
+        shared.sync().await;
+
+        let handle = tokio::spawn(async move {
+            let mut sync = tokio::time::interval(shared.config.sync_interval);
+            sync.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = sync.tick() => shared.sync().await,
+                    accepted = listener.accept() => match accepted {
+                        Ok((stream, peer)) => {
+                            debug!("OPC UA client connected from {}", peer);
+                            let _ = stream.set_nodelay(true);
+                            let connection = Connection::new(Arc::clone(&shared), stream);
+                            let token = shutdown.clone();
+                            tokio::spawn(async move {
+                                if let Err(e) = connection.run(token).await {
+                                    debug!("OPC UA connection from {} closed: {}", peer, e);
+                                }
+                            });
+                        },
+                        Err(e) => warn!("OPC UA server accept: {}", e),
+                    },
+                }
+            }
+            info!("OPC UA server stopped");
+        });
+        Ok(Self { local_addr, handle })
+    }
+}
+
+/// Start the server when enabled in `service_config`; `None` when disabled
+pub async fn start_from_config<R: Rtdb + 'static>(
+    pool: &SqlitePool,
+    rtdb: Arc<R>,
+    shutdown: CancellationToken,
+) -> Result<Option<OpcUaServer>> {
+    let config = OpcUaServerConfig::load(pool).await?;
+    if !config.enabled {
+        return Ok(None);
+    }
+    let channels = load_channels(pool).await?;
+    OpcUaServer::start(config, &channels, rtdb, shutdown)
+        .await
+        .map(Some)
+}
+
+// ============================================================================
+// Connections and sessions
+// ============================================================================
+
+#[derive(Debug)]
+struct MonitoredItem {
+    client_handle: u32,
+    key: PointKey,
+    reporting: bool,
+    /// Last value sent to the client
+    last: Option<Sample>,
+}
+
+#[derive(Debug)]
+struct Subscription {
+    interval: Duration,
+    keep_alive_count: u32,
+    next_due: Instant,
+    /// Publishing cycles since the last message
+    idle_cycles: u32,
+    sequence: u32,
+    items: BTreeMap<u32, MonitoredItem>,
+}
+
+#[derive(Debug)]
+struct Session {
+    auth_token: NodeId,
+    activated: bool,
+    subscriptions: BTreeMap<u32, Subscription>,
+    /// Page size and browse results left over per continuation point
+    continuations: HashMap<Vec<u8>, (usize, Vec<NodeReference>)>,
+    next_id: u32,
+}
+
+impl Session {
+    fn next_id(&mut self) -> u32 {
+        self.next_id = self.next_id.wrapping_add(1).max(1);
+        self.next_id
+    }
+}
+
+/// Reference of a browse result
+#[derive(Debug, Clone)]
+struct NodeReference {
+    reference_type: u32,
+    forward: bool,
+    target: NodeId,
+}
+
+/// Connection error; the connection is closed after it
+#[derive(Debug)]
+struct Closed(String);
+
+impl std::fmt::Display for Closed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+struct Connection<R: Rtdb> {
+    shared: Arc<Shared<R>>,
+    stream: TcpStream,
+    reader: codec::ChunkReader,
+    assembler: codec::Assembler,
+    channel: SecureChannel,
+    endpoint_url: String,
+    session: Option<Session>,
+    /// Publish requests awaiting notifications: (request ID, handle, acks)
+    publishes: VecDeque<(u32, u32, usize)>,
+}
+
+impl<R: Rtdb> Connection<R> {
+    fn new(shared: Arc<Shared<R>>, stream: TcpStream) -> Self {
+        Self {
+            shared,
+            stream,
+            reader: codec::ChunkReader::new(),
+            assembler: codec::Assembler::new(),
+            channel: SecureChannel::new(),
+            endpoint_url: String::new(),
+            session: None,
+            publishes: VecDeque::new(),
+        }
+    }
+
+    async fn run(mut self, shutdown: CancellationToken) -> std::result::Result<(), Closed> {
+        let mut buf = vec![0u8; 8192];
+        let mut tick = tokio::time::interval(PUBLISH_TICK);
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => {
+                    let _ = self.stream.write_all(&codec::error_chunk(0x800C_0000, "BadShutdown")).await;
+                    return Ok(());
+                },
+                _ = tick.tick() => self.publish_due().await?,
+                read = self.stream.read(&mut buf) => {
+                    let n = read.map_err(|e| Closed(e.to_string()))?;
+                    if n == 0 {
+                        return Ok(());
+                    }
+                    self.reader.push(&buf[..n]);
+                    while let Some(chunk) = self.reader.next_chunk() {
+                        let chunk = chunk.map_err(|e| Closed(e.to_string()))?;
+                        if !self.handle_chunk(chunk).await? {
+                            return Ok(());
+                        }
+                    }
+                },
+            }
+        }
+    }
+
+    async fn send(&mut self, bytes: &[u8]) -> std::result::Result<(), Closed> {
+        self.stream
+            .write_all(bytes)
+            .await
+            .map_err(|e| Closed(e.to_string()))
+    }
+
+    async fn fail(&mut self, code: u32, reason: &str) -> Closed {
+        let _ = self.send(&codec::error_chunk(code, reason)).await;
+        Closed(format!("{}: {}", codec::status_name(code), reason))
+    }
+
+    async fn respond(&mut self, request_id: u32, body: &[u8]) -> std::result::Result<(), Closed> {
+        let bytes = self.channel.message(request_id, body);
+        self.send(&bytes).await
+    }
+
+    /// Process a chunk; `false` once the client closed the channel
+    async fn handle_chunk(&mut self, chunk: codec::Chunk) -> std::result::Result<bool, Closed> {
+        if &chunk.message_type == b"HEL" {
+            let (receive_buffer_size, url) = match codec::parse_hello(&chunk.body) {
+                Ok(hello) => hello,
+                Err(e) => return Err(self.fail(status::BAD_DECODING_ERROR, &e.to_string()).await),
+            };
+            self.channel.max_chunk = (receive_buffer_size as usize).max(8192);
+            self.endpoint_url = url;
+            let ack = codec::acknowledge(receive_buffer_size.min(codec::RECEIVE_BUFFER_SIZE));
+            self.send(&ack).await?;
+            return Ok(true);
+        }
+        let opening = &chunk.message_type == b"OPN";
+        let incoming = match self.assembler.push(chunk) {
+            Ok(Some(incoming)) => incoming,
+            Ok(None) => return Ok(true),
+            Err(e) => {
+                return Err(self
+                    .fail(status::BAD_TCP_MESSAGE_TYPE_INVALID, &e.to_string())
+                    .await)
+            },
+        };
+        let (request_id, body) = match incoming {
+            Incoming::Message { request_id, body } => (request_id, body),
+            Incoming::Error { reason, .. } => return Err(Closed(reason)),
+            Incoming::Acknowledge { .. } => {
+                return Err(self
+                    .fail(status::BAD_TCP_MESSAGE_TYPE_INVALID, "unexpected ACK")
+                    .await)
+            },
+        };
+        let (type_id, auth_token, handle, fields) = match codec::parse_request(&body) {
+            Ok(request) => request,
+            Err(e) => return Err(self.fail(status::BAD_DECODING_ERROR, &e.to_string()).await),
+        };
+
+        if opening {
+            return self
+                .open_channel(request_id, handle, fields)
+                .await
+                .map(|_| true);
+        }
+        if type_id == ids::CLOSE_SECURE_CHANNEL_REQUEST {
+            return Ok(false);
+        }
+        let response = match self
+            .service(type_id, &auth_token, handle, request_id, fields)
+            .await
+        {
+            Ok(Some(response)) => response,
+            Ok(None) => return Ok(true), // queued Publish
+            Err(code) => codec::service_fault(handle, code),
+        };
+        self.respond(request_id, &response).await?;
+        Ok(true)
+    }
+
+    async fn open_channel(
+        &mut self,
+        request_id: u32,
+        handle: u32,
+        mut d: Decoder<'_>,
+    ) -> std::result::Result<(), Closed> {
+        let parsed = (|| {
+            d.u32()?; // client protocol version
+            let renew = d.u32()? == 1;
+            let mode = d.u32()?;
+            d.byte_string()?; // client nonce
+            let lifetime = d.u32()?;
+            Ok::<_, CodecError>((renew, mode, lifetime))
+        })();
+        let (renew, mode, lifetime) = match parsed {
+            Ok(parsed) => parsed,
+            Err(e) => return Err(self.fail(status::BAD_DECODING_ERROR, &e.to_string()).await),
+        };
+        if mode != SECURITY_MODE_NONE {
+            return Err(self
+                .fail(
+                    status::BAD_SECURITY_POLICY_REJECTED,
+                    "only SecurityPolicy None is supported",
+                )
+                .await);
+        }
+        if renew && self.channel.channel_id != 0 {
+            self.channel.token_id = self.channel.token_id.wrapping_add(1).max(1);
+        } else {
+            self.channel.channel_id = self.shared.next_channel_id.fetch_add(1, Ordering::Relaxed);
+            self.channel.token_id = 1;
+        }
+        let lifetime = if lifetime == 0 {
+            CHANNEL_LIFETIME_MS
+        } else {
+            lifetime.clamp(10_000, CHANNEL_LIFETIME_MS)
+        };
+        let mut e = Encoder::new();
+        e.u32(0) // server protocol version
+            .u32(self.channel.channel_id)
+            .u32(self.channel.token_id)
+            .i64(codec::date_time_now())
+            .u32(lifetime)
+            .byte_string(Some(&[]));
+        let body = codec::response(ids::OPEN_SECURE_CHANNEL_RESPONSE, handle, &e.into_bytes());
+        let bytes = self.channel.open(request_id, &body);
+        self.send(&bytes).await
+    }
+
+    /// Handle a service request; `Ok(None)` for Publish requests held back
+    async fn service(
+        &mut self,
+        type_id: u32,
+        auth_token: &NodeId,
+        handle: u32,
+        request_id: u32,
+        mut d: Decoder<'_>,
+    ) -> std::result::Result<Option<Vec<u8>>, u32> {
+        // Discovery and session creation work without a session
+        match type_id {
+            ids::GET_ENDPOINTS_REQUEST => {
+                let mut e = Encoder::new();
+                e.array_len(1);
+                self.endpoint(&mut e);
+                return Ok(Some(codec::response(
+                    ids::GET_ENDPOINTS_RESPONSE,
+                    handle,
+                    &e.into_bytes(),
+                )));
+            },
+            ids::FIND_SERVERS_REQUEST => {
+                let mut e = Encoder::new();
+                e.array_len(1);
+                self.application_description(&mut e);
+                return Ok(Some(codec::response(
+                    ids::FIND_SERVERS_RESPONSE,
+                    handle,
+                    &e.into_bytes(),
+                )));
+            },
+            ids::CREATE_SESSION_REQUEST => return Ok(Some(self.create_session(handle))),
+            _ => {},
+        }
+
+        let session = match &mut self.session {
+            Some(session) if session.auth_token == *auth_token => session,
+            _ => return Err(status::BAD_SESSION_ID_INVALID),
+        };
+        if type_id == ids::ACTIVATE_SESSION_REQUEST {
+            let code =
+                activate(&self.shared.config, &mut d).map_err(|_| status::BAD_DECODING_ERROR)?;
+            if code != status::GOOD {
+                return Err(code);
+            }
+            session.activated = true;
+            let mut e = Encoder::new();
+            e.byte_string(Some(&nonce())).array_len(0).array_len(0);
+            return Ok(Some(codec::response(
+                ids::ACTIVATE_SESSION_RESPONSE,
+                handle,
+                &e.into_bytes(),
+            )));
+        }
+        if !session.activated {
+            return Err(status::BAD_SESSION_NOT_ACTIVATED);
+        }
+
+        let decoding = |_| status::BAD_DECODING_ERROR;
+        let response = match type_id {
+            ids::CLOSE_SESSION_REQUEST => {
+                self.session = None;
+                self.publishes.clear();
+                codec::response(ids::CLOSE_SESSION_RESPONSE, handle, &[])
+            },
+            ids::BROWSE_REQUEST => {
+                let fields = browse(&self.shared.space, session, &mut d).map_err(decoding)?;
+                codec::response(ids::BROWSE_RESPONSE, handle, &fields)
+            },
+            ids::BROWSE_NEXT_REQUEST => {
+                let fields = browse_next(&self.shared.space, session, &mut d).map_err(decoding)?;
+                codec::response(ids::BROWSE_NEXT_RESPONSE, handle, &fields)
+            },
+            ids::READ_REQUEST => {
+                let fields = read(&self.shared, &mut d).map_err(decoding)?;
+                codec::response(ids::READ_RESPONSE, handle, &fields)
+            },
+            ids::WRITE_REQUEST => {
+                let fields = write(&self.shared, &mut d).await.map_err(decoding)?;
+                codec::response(ids::WRITE_RESPONSE, handle, &fields)
+            },
+            ids::CREATE_SUBSCRIPTION_REQUEST => {
+                let fields = create_subscription(session, &mut d).map_err(decoding)?;
+                codec::response(ids::CREATE_SUBSCRIPTION_RESPONSE, handle, &fields)
+            },
+            ids::CREATE_MONITORED_ITEMS_REQUEST => {
+                let fields = create_monitored_items(&self.shared.space, session, &mut d)?;
+                codec::response(ids::CREATE_MONITORED_ITEMS_RESPONSE, handle, &fields)
+            },
+            ids::DELETE_MONITORED_ITEMS_REQUEST => {
+                let fields = delete_monitored_items(session, &mut d).map_err(decoding)?;
+                codec::response(ids::DELETE_MONITORED_ITEMS_RESPONSE, handle, &fields)
+            },
+            ids::DELETE_SUBSCRIPTIONS_REQUEST => {
+                let deleted = d.vec(|d| d.u32()).map_err(decoding)?;
+                let mut e = Encoder::new();
+                e.array_len(deleted.len());
+                for id in deleted {
+                    e.u32(match session.subscriptions.remove(&id) {
+                        Some(_) => status::GOOD,
+                        None => status::BAD_SUBSCRIPTION_ID_INVALID,
+                    });
+                }
+                e.array_len(0);
+                codec::response(ids::DELETE_SUBSCRIPTIONS_RESPONSE, handle, &e.into_bytes())
+            },
+            ids::PUBLISH_REQUEST => {
+                // Sequence numbers are not kept for republishing; acks are accepted as is
+                let acks = d.vec(|d| Ok((d.u32()?, d.u32()?))).map_err(decoding)?;
+                if session.subscriptions.is_empty() {
+                    return Err(status::BAD_NO_SUBSCRIPTION);
+                }
+                self.publishes.push_back((request_id, handle, acks.len()));
+                if self.publishes.len() > MAX_QUEUED_PUBLISH {
+                    if let Some((request_id, handle, _)) = self.publishes.pop_front() {
+                        let fault =
+                            codec::service_fault(handle, status::BAD_TOO_MANY_PUBLISH_REQUESTS);
+                        let bytes = self.channel.message(request_id, &fault);
+                        let _ = self.stream.write_all(&bytes).await;
+                    }
+                }
+                return Ok(None);
+            },
+            ids::REPUBLISH_REQUEST => return Err(status::BAD_MESSAGE_NOT_AVAILABLE),
+            _ => return Err(status::BAD_SERVICE_UNSUPPORTED),
+        };
+        Ok(Some(response))
+    }
+
+    fn application_description(&self, e: &mut Encoder) {
+        e.string(Some(NAMESPACE_URI))
+            .string(Some(NAMESPACE_URI))
+            .localized_text(APPLICATION_NAME)
+            .u32(0) // Server
+            .string(None)
+            .string(None)
+            .array_len(1)
+            .string(Some(&self.endpoint_url));
+    }
+
+    fn endpoint(&self, e: &mut Encoder) {
+        e.string(Some(&self.endpoint_url));
+        self.application_description(e);
+        e.byte_string(None) // server certificate
+            .u32(SECURITY_MODE_NONE)
+            .string(Some(SECURITY_POLICY_NONE))
+            .array_len(1);
+        let (policy_id, token_type) = match self.shared.config.credentials {
+            Some(_) => ("username", 1),
+            None => ("anonymous", 0),
+        };
+        e.string(Some(policy_id))
+            .u32(token_type)
+            .string(None)
+            .string(None)
+            .string(None);
+        e.string(Some(TRANSPORT_PROFILE)).u8(0);
+    }
+
+    fn create_session(&mut self, handle: u32) -> Vec<u8> {
+        if self.session.is_some() {
+            // One session per connection: a new one replaces the old
+            self.publishes.clear();
+        }
+        let mut token = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut token);
+        let auth_token = NodeId {
+            namespace: 0,
+            identifier: Identifier::Opaque(token.to_vec()),
+        };
+        let session_id = NodeId::numeric(NAMESPACE, self.channel.channel_id);
+        self.session = Some(Session {
+            auth_token: auth_token.clone(),
+            activated: false,
+            subscriptions: BTreeMap::new(),
+            continuations: HashMap::new(),
+            next_id: 0,
+        });
+
+        let mut e = Encoder::new();
+        e.node_id(&session_id)
+            .node_id(&auth_token)
+            .f64(SESSION_TIMEOUT_MS)
+            .byte_string(Some(&nonce()))
+            .byte_string(None) // server certificate
+            .array_len(1);
+        self.endpoint(&mut e);
+        e.array_len(0) // software certificates
+            .string(None) // signature algorithm
+            .byte_string(None)
+            .u32(0); // max request size
+        codec::response(ids::CREATE_SESSION_RESPONSE, handle, &e.into_bytes())
+    }
+
+    /// Answer held Publish requests for subscriptions whose interval elapsed
+    async fn publish_due(&mut self) -> std::result::Result<(), Closed> {
+        let Some(session) = &mut self.session else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        let mut messages = Vec::new();
+        for (id, subscription) in session.subscriptions.iter_mut() {
+            if subscription.next_due > now {
+                continue;
+            }
+            subscription.next_due = now + subscription.interval;
+            if messages.len() >= self.publishes.len() {
+                // No Publish request to answer with; changes wait for the next cycle
+                continue;
+            }
+            let changes = changes(&self.shared, subscription);
+            subscription.idle_cycles += 1;
+            if changes.is_empty() && subscription.idle_cycles < subscription.keep_alive_count {
+                continue;
+            }
+            subscription.idle_cycles = 0;
+            let sequence = if changes.is_empty() {
+                // Keep-alive carries the next sequence number without using it
+                subscription.sequence.wrapping_add(1).max(1)
+            } else {
+                subscription.sequence = subscription.sequence.wrapping_add(1).max(1);
+                subscription.sequence
+            };
+            messages.push((*id, sequence, changes));
+        }
+        for (id, sequence, changes) in messages {
+            let Some((request_id, handle, acks)) = self.publishes.pop_front() else {
+                break;
+            };
+            let fields = notification_message(id, sequence, &changes, acks);
+            let body = codec::response(ids::PUBLISH_RESPONSE, handle, &fields);
+            self.respond(request_id, &body).await?;
+        }
+        Ok(())
+    }
+}
+
+type DecodeResult<T> = std::result::Result<T, CodecError>;
+
+fn nonce() -> [u8; 32] {
+    let mut nonce = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    nonce
+}
+
+/// Status of the identity token of an ActivateSession request
+fn activate(config: &OpcUaServerConfig, d: &mut Decoder) -> DecodeResult<u32> {
+    d.string()?; // client signature algorithm
+    d.byte_string()?;
+    d.vec(|d| {
+        d.byte_string()?;
+        d.byte_string()
+    })?; // software certificates
+    d.vec(|d| d.string())?; // locale IDs
+    let (type_id, body) = d.extension_object()?;
+    let mut token = Decoder::new(body.unwrap_or_default());
+    let identity = match type_id.identifier {
+        Identifier::Numeric(ids::ANONYMOUS_IDENTITY_TOKEN) => None,
+        Identifier::Numeric(ids::USER_NAME_IDENTITY_TOKEN) => {
+            token.string()?; // policy ID
+            let user = token.string()?.unwrap_or_default();
+            let password = token.byte_string()?.unwrap_or_default();
+            let algorithm = token.string()?.unwrap_or_default();
+            if !algorithm.is_empty() {
+                return Ok(status::BAD_IDENTITY_TOKEN_INVALID);
+            }
+            Some((user, String::from_utf8_lossy(password).into_owned()))
+        },
+        // No token is an anonymous user
+        Identifier::Numeric(0) if body.is_none() => None,
+        _ => return Ok(status::BAD_IDENTITY_TOKEN_INVALID),
+    };
+    Ok(match (&config.credentials, identity) {
+        (None, None) => status::GOOD,
+        (None, Some(_)) => status::BAD_IDENTITY_TOKEN_REJECTED,
+        (Some(_), None) => status::BAD_IDENTITY_TOKEN_REJECTED,
+        (Some(expected), Some(given)) if *expected == given => status::GOOD,
+        (Some(_), Some(_)) => status::BAD_USER_ACCESS_DENIED,
+    })
+}
+
+/// ReadValueId as (node, attribute)
+fn read_value_id(d: &mut Decoder) -> DecodeResult<(NodeId, u32)> {
+    let node = d.node_id()?;
+    let attribute = d.u32()?;
+    d.string()?; // index range
+    d.qualified_name()?; // data encoding
+    Ok((node, attribute))
+}
+
+fn point_data_value(shared: &Shared<impl Rtdb>, key: &PointKey, timestamps: bool) -> DataValue {
+    match shared.sample(key) {
+        Some(sample) => DataValue {
+            value: Some(variable_value(key.1, sample.value)),
+            status: status::GOOD,
+            source_timestamp: timestamps.then(|| codec::date_time_from_millis(sample.timestamp_ms)),
+        },
+        None => DataValue {
+            value: None,
+            status: status::BAD_WAITING_FOR_INITIAL_DATA,
+            source_timestamp: None,
+        },
+    }
+}
+
+fn bad(code: u32) -> DataValue {
+    DataValue {
+        value: None,
+        status: code,
+        source_timestamp: None,
+    }
+}
+
+fn good(value: Variant) -> DataValue {
+    DataValue {
+        value: Some(value),
+        status: status::GOOD,
+        source_timestamp: None,
+    }
+}
+
+/// Encode the value of a node attribute as a DataValue
+fn read_attribute(
+    shared: &Shared<impl Rtdb>,
+    e: &mut Encoder,
+    node_id: &NodeId,
+    attr: u32,
+    timestamps: bool,
+) {
+    let Some(node) = shared.space.nodes.get(node_id) else {
+        e.data_value(&bad(status::BAD_NODE_ID_UNKNOWN));
+        return;
+    };
+    let variable = node.node_class() == NODE_CLASS_VARIABLE;
+    let value = match attr {
+        attribute::NODE_ID => good(Variant::NodeId(node_id.clone())),
+        attribute::NODE_CLASS => good(Variant::Int32(node.node_class() as i32)),
+        attribute::BROWSE_NAME => {
+            // QualifiedName has no Variant of its own
+            let (namespace, name) = &node.browse_name;
+            e.u8(0x01).u8(20).qualified_name(*namespace, name);
+            return;
+        },
+        attribute::DISPLAY_NAME => good(Variant::LocalizedText(node.display_name.clone())),
+        attribute::DESCRIPTION => good(Variant::LocalizedText(node.description.clone())),
+        attribute::WRITE_MASK | attribute::USER_WRITE_MASK => good(Variant::UInt32(0)),
+        attribute::EVENT_NOTIFIER if !variable => good(Variant::Byte(0)),
+        attribute::VALUE => match &node.kind {
+            NodeKind::Variable(key) => point_data_value(shared, key, timestamps),
+            NodeKind::Namespaces => {
+                let uris = ["http://opcfoundation.org/UA/", NAMESPACE_URI];
+                e.u8(0x01).u8(0x80 | builtin::STRING).array_len(uris.len());
+                for uri in uris {
+                    e.string(Some(uri));
+                }
+                return;
+            },
+            _ => bad(status::BAD_ATTRIBUTE_ID_INVALID),
+        },
+        attribute::DATA_TYPE if variable => good(Variant::NodeId(NodeId::numeric(
+            0,
+            match &node.kind {
+                NodeKind::Variable(key) if is_boolean(key.1) => ids::BOOLEAN,
+                NodeKind::Variable(_) => ids::DOUBLE,
+                _ => builtin::STRING as u32,
+            },
+        ))),
+        attribute::VALUE_RANK if variable => good(Variant::Int32(match node.kind {
+            NodeKind::Namespaces => 1,
+            _ => -1, // scalar
+        })),
+        attribute::ACCESS_LEVEL | attribute::USER_ACCESS_LEVEL if variable => {
+            good(Variant::Byte(match &node.kind {
+                NodeKind::Variable(key) if shared.writable(key) => ACCESS_READ_WRITE,
+                _ => ACCESS_READ,
+            }))
+        },
+        attribute::HISTORIZING if variable => good(Variant::Boolean(false)),
+        _ => bad(status::BAD_ATTRIBUTE_ID_INVALID),
+    };
+    e.data_value(&value);
+}
+
+fn read(shared: &Shared<impl Rtdb>, d: &mut Decoder) -> DecodeResult<Vec<u8>> {
+    d.f64()?; // max age: values are always the mirrored ones
+              // 0 source, 1 server, 2 both, 3 neither
+    let timestamps = matches!(d.u32()?, 0 | 2);
+    let nodes = d.vec(read_value_id)?;
+    let mut e = Encoder::new();
+    e.array_len(nodes.len());
+    for (node, attr) in &nodes {
+        read_attribute(shared, &mut e, node, *attr, timestamps);
+    }
+    e.array_len(0);
+    Ok(e.into_bytes())
+}
+
+async fn write(shared: &Shared<impl Rtdb>, d: &mut Decoder<'_>) -> DecodeResult<Vec<u8>> {
+    let values = d.vec(|d| {
+        let node = d.node_id()?;
+        let attr = d.u32()?;
+        d.string()?; // index range
+        Ok((node, attr, d.data_value()?))
+    })?;
+    let mut results = Vec::with_capacity(values.len());
+    for (node, attr, value) in values {
+        let code = match shared.space.nodes.get(&node).map(|n| &n.kind) {
+            None => status::BAD_NODE_ID_UNKNOWN,
+            Some(NodeKind::Variable(key)) if attr == attribute::VALUE && shared.writable(key) => {
+                match value.value.as_ref().and_then(Variant::as_f64) {
+                    Some(v) => shared.write_point(*key, v).await,
+                    None => status::BAD_TYPE_MISMATCH,
+                }
+            },
+            Some(_) => status::BAD_NOT_WRITABLE,
+        };
+        results.push(code);
+    }
+    let mut e = Encoder::new();
+    e.array_len(results.len());
+    for code in results {
+        e.u32(code);
+    }
+    e.array_len(0);
+    Ok(e.into_bytes())
+}
+
+/// References of a node matching a BrowseDescription
+fn references(
+    node: &Node,
+    direction: u32,
+    reference_type: &NodeId,
+    class_mask: u32,
+    space: &AddressSpace,
+) -> Vec<NodeReference> {
+    let type_matches = |reference: u32| {
+        if reference_type.is_null() {
+            return true;
+        }
+        match (reference_type.namespace, &reference_type.identifier) {
+            // References, HierarchicalReferences
+            (0, Identifier::Numeric(31 | ids::HIERARCHICAL_REFERENCES)) => true,
+            (0, Identifier::Numeric(id)) => *id == reference,
+            _ => false,
+        }
+    };
+    let class_matches = |target: &NodeId| {
+        class_mask == 0
+            || space
+                .nodes
+                .get(target)
+                .is_some_and(|n| n.node_class() & class_mask != 0)
+    };
+    let mut out = Vec::new();
+    if direction != 1 {
+        for (reference, child) in &node.children {
+            if type_matches(*reference) && class_matches(child) {
+                out.push(NodeReference {
+                    reference_type: *reference,
+                    forward: true,
+                    target: child.clone(),
+                });
+            }
+        }
+    }
+    if direction != 0 {
+        if let Some((reference, parent)) = &node.parent {
+            if type_matches(*reference) && class_matches(parent) {
+                out.push(NodeReference {
+                    reference_type: *reference,
+                    forward: false,
+                    target: parent.clone(),
+                });
+            }
+        }
+    }
+    out
+}
+
+/// Encode one BrowseResult, keeping the rest behind a continuation point
+fn browse_result(
+    space: &AddressSpace,
+    session: &mut Session,
+    e: &mut Encoder,
+    mut refs: Vec<NodeReference>,
+    max: usize,
+) {
+    let rest = if max > 0 && refs.len() > max {
+        refs.split_off(max)
+    } else {
+        Vec::new()
+    };
+    e.u32(status::GOOD);
+    if rest.is_empty() || session.continuations.len() >= MAX_CONTINUATION_POINTS {
+        e.byte_string(None);
+    } else {
+        let point = session.next_id().to_le_bytes().to_vec();
+        e.byte_string(Some(&point));
+        session.continuations.insert(point, (max, rest));
+    }
+    e.array_len(refs.len());
+    for reference in refs {
+        let target = space.nodes.get(&reference.target);
+        e.node_id(&NodeId::numeric(0, reference.reference_type))
+            .bool(reference.forward)
+            .node_id(&reference.target);
+        match target {
+            Some(target) => {
+                e.qualified_name(target.browse_name.0, &target.browse_name.1)
+                    .localized_text(&target.display_name)
+                    .u32(target.node_class())
+                    .node_id(&NodeId::numeric(0, target.type_definition()));
+            },
+            None => {
+                e.qualified_name(0, "")
+                    .localized_text("")
+                    .u32(0)
+                    .node_id(&NodeId::null());
+            },
+        }
+    }
+}
+
+fn browse(space: &AddressSpace, session: &mut Session, d: &mut Decoder) -> DecodeResult<Vec<u8>> {
+    d.node_id()?; // view
+    d.i64()?;
+    d.u32()?;
+    let max = d.u32()? as usize;
+    let descriptions = d.vec(|d| {
+        let node = d.node_id()?;
+        let direction = d.u32()?;
+        let reference_type = d.node_id()?;
+        d.bool()?; // include subtypes
+        let class_mask = d.u32()?;
+        d.u32()?; // result mask: every field is always filled
+        Ok((node, direction, reference_type, class_mask))
+    })?;
+    let mut e = Encoder::new();
+    e.array_len(descriptions.len());
+    for (node, direction, reference_type, class_mask) in descriptions {
+        match space.nodes.get(&node) {
+            Some(found) => {
+                let refs = references(found, direction, &reference_type, class_mask, space);
+                browse_result(space, session, &mut e, refs, max);
+            },
+            None => {
+                e.u32(status::BAD_NODE_ID_UNKNOWN)
+                    .byte_string(None)
+                    .array_len(0);
+            },
+        }
+    }
+    e.array_len(0);
+    Ok(e.into_bytes())
+}
+
+fn browse_next(
+    space: &AddressSpace,
+    session: &mut Session,
+    d: &mut Decoder,
+) -> DecodeResult<Vec<u8>> {
+    let release = d.bool()?;
+    let points = d.vec(|d| Ok(d.byte_string()?.unwrap_or_default().to_vec()))?;
+    let mut e = Encoder::new();
+    e.array_len(points.len());
+    for point in points {
+        match session.continuations.remove(&point) {
+            Some(_) if release => {
+                e.u32(status::GOOD).byte_string(None).array_len(0);
+            },
+            Some((max, refs)) => browse_result(space, session, &mut e, refs, max),
+            None => {
+                e.u32(status::BAD_CONTINUATION_POINT_INVALID)
+                    .byte_string(None)
+                    .array_len(0);
+            },
+        }
+    }
+    e.array_len(0);
+    Ok(e.into_bytes())
+}
+
+fn create_subscription(session: &mut Session, d: &mut Decoder) -> DecodeResult<Vec<u8>> {
+    let interval_ms = d.f64()?;
+    d.u32()?; // lifetime count: sessions end with their connection
+    let keep_alive_count = d.u32()?;
+    d.u32()?; // max notifications per publish
+    d.bool()?; // publishing enabled
+    d.u8()?; // priority
+
+    let interval_ms = if interval_ms.is_finite() {
+        interval_ms.max(MIN_PUBLISHING_INTERVAL_MS)
+    } else {
+        MIN_PUBLISHING_INTERVAL_MS
+    };
+    let keep_alive_count = keep_alive_count.clamp(1, MAX_KEEP_ALIVE_COUNT);
+    let interval = Duration::from_millis(interval_ms as u64);
+    let id = session.next_id();
+    session.subscriptions.insert(
+        id,
+        Subscription {
+            interval,
+            keep_alive_count,
+            next_due: Instant::now() + interval,
+            idle_cycles: 0,
+            sequence: 0,
+            items: BTreeMap::new(),
+        },
+    );
+    let mut e = Encoder::new();
+    e.u32(id)
+        .f64(interval_ms)
+        .u32(keep_alive_count * 3)
+        .u32(keep_alive_count);
+    Ok(e.into_bytes())
+}
+
+fn create_monitored_items(
+    space: &AddressSpace,
+    session: &mut Session,
+    d: &mut Decoder,
+) -> std::result::Result<Vec<u8>, u32> {
+    let decode = |d: &mut Decoder| -> DecodeResult<_> {
+        let subscription_id = d.u32()?;
+        d.u32()?; // timestamps to return: source timestamps are always sent
+        let requests = d.vec(|d| {
+            let (node, attr) = read_value_id(d)?;
+            let mode = d.u32()?;
+            let client_handle = d.u32()?;
+            let sampling = d.f64()?;
+            d.extension_object()?; // filter
+            d.u32()?; // queue size
+            d.bool()?; // discard oldest
+            Ok((node, attr, mode, client_handle, sampling))
+        })?;
+        Ok((subscription_id, requests))
+    };
+    let (subscription_id, requests) = decode(d).map_err(|_| status::BAD_DECODING_ERROR)?;
+    if !session.subscriptions.contains_key(&subscription_id) {
+        return Err(status::BAD_SUBSCRIPTION_ID_INVALID);
+    }
+    if requests.is_empty() {
+        return Err(status::BAD_NOTHING_TO_DO);
+    }
+
+    let mut e = Encoder::new();
+    e.array_len(requests.len());
+    for (node, attr, mode, client_handle, sampling) in requests {
+        let key = match space.nodes.get(&node).map(|n| &n.kind) {
+            Some(NodeKind::Variable(key)) if attr == attribute::VALUE => *key,
+            Some(_) => {
+                e.u32(status::BAD_ATTRIBUTE_ID_INVALID)
+                    .u32(0)
+                    .f64(0.0)
+                    .u32(0)
+                    .null_extension_object();
+                continue;
+            },
+            None => {
+                e.u32(status::BAD_NODE_ID_UNKNOWN)
+                    .u32(0)
+                    .f64(0.0)
+                    .u32(0)
+                    .null_extension_object();
+                continue;
+            },
+        };
+        let item_id = session.next_id();
+        if let Some(subscription) = session.subscriptions.get_mut(&subscription_id) {
+            subscription.items.insert(
+                item_id,
+                MonitoredItem {
+                    client_handle,
+                    key,
+                    reporting: mode == 2,
+                    last: None,
+                },
+            );
+        }
+        e.u32(status::GOOD)
+            .u32(item_id)
+            .f64(sampling.max(0.0))
+            .u32(1) // queue size
+            .null_extension_object();
+    }
+    e.array_len(0);
+    Ok(e.into_bytes())
+}
+
+fn delete_monitored_items(session: &mut Session, d: &mut Decoder) -> DecodeResult<Vec<u8>> {
+    let subscription_id = d.u32()?;
+    let items = d.vec(|d| d.u32())?;
+    let subscription = session.subscriptions.get_mut(&subscription_id);
+    let mut e = Encoder::new();
+    e.array_len(items.len());
+    match subscription {
+        Some(subscription) => {
+            for item in items {
+                e.u32(match subscription.items.remove(&item) {
+                    Some(_) => status::GOOD,
+                    None => status::BAD_MONITORED_ITEM_ID_INVALID,
+                });
+            }
+        },
+        None => {
+            for _ in items {
+                e.u32(status::BAD_SUBSCRIPTION_ID_INVALID);
+            }
+        },
+    }
+    e.array_len(0);
+    Ok(e.into_bytes())
+}
+
+/// Changed values of the reporting items, marked as sent
+fn changes(shared: &Shared<impl Rtdb>, subscription: &mut Subscription) -> Vec<(u32, DataValue)> {
+    let mut out = Vec::new();
+    for item in subscription.items.values_mut().filter(|i| i.reporting) {
+        let sample = shared.sample(&item.key);
+        if sample.is_none() || sample == item.last {
+            continue;
+        }
+        item.last = sample;
+        out.push((
+            item.client_handle,
+            point_data_value(shared, &item.key, true),
+        ));
+    }
+    out
+}
+
+/// PublishResponse fields carrying a data change (or a keep-alive)
+fn notification_message(
+    subscription_id: u32,
+    sequence: u32,
+    changes: &[(u32, DataValue)],
+    acks: usize,
+) -> Vec<u8> {
+    let mut e = Encoder::new();
+    e.u32(subscription_id)
+        .array_len(0) // available sequence numbers: no republishing
+        .bool(false)
+        .u32(sequence)
+        .i64(codec::date_time_now());
+    if changes.is_empty() {
+        e.array_len(0);
+    } else {
+        let mut dcn = Encoder::new();
+        dcn.array_len(changes.len());
+        for (client_handle, value) in changes {
+            dcn.u32(*client_handle).data_value(value);
+        }
+        dcn.array_len(0);
+        e.array_len(1)
+            .extension_object(ids::DATA_CHANGE_NOTIFICATION, &dcn.into_bytes());
+    }
+    e.array_len(acks);
+    for _ in 0..acks {
+        e.u32(status::GOOD);
+    }
+    e.array_len(0);
+    e.into_bytes()
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use crate::core::protocols::opcua::client::Session as ClientSession;
+    use crate::core::protocols::opcua::codec::{Notification, Response};
+    use crate::core::protocols::opcua::OpcUaConfig;
+    use bytes::Bytes;
+    use voltage_rtdb::MemoryRtdb;
+
+    fn rows(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_config_rows() {
+        let config = OpcUaServerConfig::from_rows(rows(&[
+            ("opcua_server.enabled", "true"),
+            ("opcua_server.port", "4850"),
+            ("opcua_server.username", "scada"),
+            ("opcua_server.password", "pw"),
+            ("api.port", "6001"),
+        ]))
+        .unwrap();
+        assert!(config.enabled);
+        assert_eq!(config.port, 4850);
+        assert_eq!(
+            config.credentials,
+            Some(("scada".to_string(), "pw".to_string()))
+        );
+        assert_eq!(config.bind_address(), "0.0.0.0:4850");
+
+        assert!(!OpcUaServerConfig::from_rows(Vec::new()).unwrap().enabled);
+        assert!(OpcUaServerConfig::from_rows(rows(&[("opcua_server.port", "x")])).is_err());
+        assert!(OpcUaServerConfig::from_rows(rows(&[("opcua_server.password", "pw")])).is_err());
+    }
+
+    fn channels() -> Vec<ChannelNodes> {
+        let point = |point_type, point_id, name: &str| PointNode {
+            point_type,
+            point_id,
+            name: name.to_string(),
+            unit: None,
+        };
+        vec![ChannelNodes {
+            channel_id: 1001,
+            name: "pcs1".to_string(),
+            points: vec![
+                point(PointType::Telemetry, 1, "P"),
+                point(PointType::Telemetry, 2, "Q"),
+                point(PointType::Signal, 1, "Running"),
+                point(PointType::Adjustment, 1, "PSet"),
+            ],
+        }]
+    }
+
+    async fn client(port: u16) -> ClientSession {
+        let parameters: HashMap<String, serde_json::Value> = serde_json::from_value(
+            serde_json::json!({ "endpoint_url": format!("opc.tcp://127.0.0.1:{}", port) }),
+        )
+        .unwrap();
+        ClientSession::connect(&OpcUaConfig::from_parameters(&parameters).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_browse_read_write_and_subscribe() {
+        let rtdb = Arc::new(MemoryRtdb::new());
+        let keyspace = KeySpaceConfig::production_cached();
+        let telemetry = keyspace.channel_key(1001, PointType::Telemetry);
+        rtdb.hash_set(&telemetry, "1", Bytes::from("230.5"))
+            .await
+            .unwrap();
+        rtdb.hash_set(
+            &keyspace.channel_key(1001, PointType::Signal),
+            "1",
+            Bytes::from("1"),
+        )
+        .await
+        .unwrap();
+
+        let config = OpcUaServerConfig {
+            enabled: true,
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            sync_interval: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let shutdown = CancellationToken::new();
+        let server = OpcUaServer::start(config, &channels(), Arc::clone(&rtdb), shutdown.clone())
+            .await
+            .unwrap();
+        let mut session = client(server.local_addr.port()).await;
+
+        // Objects -> channel -> point type folders -> variables
+        let objects = session
+            .browse(&NodeId::numeric(0, ids::OBJECTS_FOLDER))
+            .await
+            .unwrap();
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0].browse_name, "pcs1");
+        assert_eq!(objects[0].node_id, point_node_id(1001, None));
+        let folders = session.browse(&objects[0].node_id).await.unwrap();
+        let names: Vec<&str> = folders.iter().map(|r| r.browse_name.as_str()).collect();
+        assert_eq!(names, ["Telemetry", "Signal", "Control", "Adjustment"]);
+        let points = session.browse(&folders[0].node_id).await.unwrap();
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].node_class, NODE_CLASS_VARIABLE);
+
+        let p = point_node_id(1001, Some((PointType::Telemetry, Some(1))));
+        let q = point_node_id(1001, Some((PointType::Telemetry, Some(2))));
+        let running = point_node_id(1001, Some((PointType::Signal, Some(1))));
+        let values = session
+            .read(&[
+                (p.clone(), attribute::VALUE),
+                (q.clone(), attribute::VALUE),
+                (running.clone(), attribute::VALUE),
+                (NodeId::numeric(NAMESPACE, 9), attribute::VALUE),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(values[0].value, Some(Variant::Double(230.5)));
+        assert_eq!(values[1].status, status::BAD_WAITING_FOR_INITIAL_DATA);
+        assert_eq!(values[2].value, Some(Variant::Boolean(true)));
+        assert_eq!(values[3].status, status::BAD_NODE_ID_UNKNOWN);
+
+        // Adjustments are queued like REST writes; telemetry is read only
+        let p_set = point_node_id(1001, Some((PointType::Adjustment, Some(1))));
+        let results = session
+            .write(&[
+                (p_set.clone(), Variant::Float(42.0)),
+                (p.clone(), Variant::Double(1.0)),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(results, [status::GOOD, status::BAD_NOT_WRITABLE]);
+        let todo = rtdb
+            .list_range(&keyspace.todo_queue_key(1001, PointType::Adjustment), 0, -1)
+            .await
+            .unwrap();
+        assert_eq!(todo.len(), 1);
+        let access = session
+            .read(&[
+                (p_set, attribute::ACCESS_LEVEL),
+                (p.clone(), attribute::ACCESS_LEVEL),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(access[0].value, Some(Variant::Byte(ACCESS_READ_WRITE)));
+        assert_eq!(access[1].value, Some(Variant::Byte(ACCESS_READ)));
+
+        // Subscription: the current value first, then changes from the RTDB
+        let subscription = session.create_subscription(100, 10).await.unwrap();
+        let statuses = session
+            .create_monitored_items(
+                subscription,
+                &[(p, 7), (folders[0].node_id.clone(), 8)],
+                100,
+            )
+            .await
+            .unwrap();
+        assert_eq!(statuses, [status::GOOD, status::BAD_ATTRIBUTE_ID_INVALID]);
+
+        let next_change = async |session: &mut ClientSession, acks: &[(u32, u32)]| {
+            session.publish(acks).await.unwrap();
+            let deadline = Instant::now() + Duration::from_secs(3);
+            loop {
+                let response = session.next_publish(deadline).await.unwrap().unwrap();
+                let Response::Publish {
+                    subscription_id,
+                    sequence_number,
+                    notifications,
+                    ..
+                } = response
+                else {
+                    panic!("expected a publish response, got {:?}", response);
+                };
+                assert_eq!(subscription_id, subscription);
+                if let Some(Notification::DataChange(items)) = notifications.first() {
+                    return (sequence_number, items.clone());
+                }
+                session.publish(&[]).await.unwrap();
+            }
+        };
+        let (sequence, items) = next_change(&mut session, &[]).await;
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].0, 7);
+        assert_eq!(items[0].1.value, Some(Variant::Double(230.5)));
+        assert!(items[0].1.source_timestamp.is_some());
+
+        rtdb.hash_set(&telemetry, "1", Bytes::from("231"))
+            .await
+            .unwrap();
+        let (next, items) = next_change(&mut session, &[(subscription, sequence)]).await;
+        assert_eq!(next, sequence + 1);
+        assert_eq!(items[0].1.value, Some(Variant::Double(231.0)));
+
+        session.close().await;
+        shutdown.cancel();
+        server.handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_user_name_required_and_read_only() {
+        let config = OpcUaServerConfig {
+            enabled: true,
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            read_only: true,
+            credentials: Some(("scada".to_string(), "pw".to_string())),
+            ..Default::default()
+        };
+        let shutdown = CancellationToken::new();
+        let server = OpcUaServer::start(
+            config,
+            &channels(),
+            Arc::new(MemoryRtdb::new()),
+            shutdown.clone(),
+        )
+        .await
+        .unwrap();
+        let port = server.local_addr.port();
+        let parameters = |auth: serde_json::Value| -> OpcUaConfig {
+            let mut value =
+                serde_json::json!({ "endpoint_url": format!("opc.tcp://127.0.0.1:{}", port) });
+            value
+                .as_object_mut()
+                .unwrap()
+                .extend(auth.as_object().unwrap().clone());
+            OpcUaConfig::from_parameters(&serde_json::from_value(value).unwrap()).unwrap()
+        };
+
+        // Anonymous and wrong passwords are refused
+        assert!(ClientSession::connect(&parameters(serde_json::json!({})))
+            .await
+            .is_err());
+        assert!(ClientSession::connect(&parameters(
+            serde_json::json!({"username": "scada", "password": "nope"})
+        ))
+        .await
+        .is_err());
+
+        let mut session = ClientSession::connect(&parameters(
+            serde_json::json!({"username": "scada", "password": "pw"}),
+        ))
+        .await
+        .unwrap();
+        let p_set = point_node_id(1001, Some((PointType::Adjustment, Some(1))));
+        let results = session
+            .write(&[(p_set, Variant::Double(1.0))])
+            .await
+            .unwrap();
+        assert_eq!(results, [status::BAD_NOT_WRITABLE]);
+        shutdown.cancel();
+        server.handle.await.unwrap();
+    }
+}
@@ -20,4 +20,4 @@
 
 mod redis_store;
 
-pub use redis_store::RedisDataStore;
+pub use redis_store::{RedisDataStore, KEEPALIVE_POINT_ID};
@@ -30,12 +30,18 @@ use igw::core::error::Result as IgwResult;
 use igw::core::point::PointConfig;
 use igw::core::traits::{DataEvent, DataEventReceiver, DataEventSender};
 
+use crate::core::channels::ForcedPoints;
 use voltage_model::{KeySpaceConfig, PointType};
 use voltage_routing::ChannelPointUpdate;
 use voltage_rtdb::{
     ChannelToSlotIndex, RoutingCache, Rtdb, SharedVecRtdbWriter, WriteBuffer, WriteBufferConfig,
 };
 
+/// Internal id reserved for the channel keepalive probe point.
+///
+/// Probe reads only prove the link is alive and are never persisted.
+pub const KEEPALIVE_POINT_ID: u32 = u32::MAX;
+
 /// Redis-backed data store for VoltageEMS.
 ///
 /// This is the bridge between IGW protocols and the VoltageEMS Redis storage.
@@ -84,12 +90,17 @@ impl<R: Rtdb> RedisDataStore<R> {
     ///
     /// Note: Removed VecRtdb - using SharedMemory + Redis two-tier architecture
     pub fn new(rtdb: Arc<R>, routing_cache: Arc<RoutingCache>) -> Self {
+        let tuning = common::resource_profile::tuning();
         // Create single broadcast channel - all subscribers share this sender
-        let (event_sender, _) = tokio::sync::broadcast::channel(1024);
+        let (event_sender, _) = tokio::sync::broadcast::channel(tuning.event_buffer_size);
+        let write_buffer = WriteBuffer::new(WriteBufferConfig {
+            flush_interval_ms: tuning.write_flush_interval_ms,
+            max_fields_per_key: tuning.write_batch_size,
+        });
         Self {
             rtdb,
             routing_cache,
-            write_buffer: Arc::new(WriteBuffer::new(WriteBufferConfig::default())),
+            write_buffer: Arc::new(write_buffer),
             shared_writer: None,
             channel_index: None,
             point_configs: DashMap::new(),
@@ -100,6 +111,11 @@ impl<R: Rtdb> RedisDataStore<R> {
         }
     }
 
+    /// RTDB backing this store
+    pub fn rtdb(&self) -> &Arc<R> {
+        &self.rtdb
+    }
+
     /// Set shared memory writer and channel index for high-performance writes.
     ///
     /// When enabled, writes go directly to shared memory using pre-computed
@@ -170,11 +186,18 @@ impl<R: Rtdb> RedisDataStore<R> {
         let mut updates = Vec::with_capacity(batch.len());
 
         for point in batch.iter() {
+            // Keepalive probe reads carry no point data
+            if point.id == KEEPALIVE_POINT_ID {
+                continue;
+            }
+
             // Decode internal_id to get point_type and original point_id
             let (point_type, original_point_id) = PointType::from_internal_id(point.id);
 
-            // IGW returns already-transformed values
-            let value = point.value.as_f64().unwrap_or(0.0);
+            // IGW returns already-transformed values; forced points keep their forced value
+            let value = ForcedPoints::global()
+                .substitute(channel_id, point_type, original_point_id)
+                .unwrap_or_else(|| point.value.as_f64().unwrap_or(0.0));
 
             debug!(
                 "[{:?}] Point {} (internal_id={}): value={:.2}",
@@ -0,0 +1,142 @@
+//! In-process comsrv for cross-service tests
+//!
+//! [`ComsrvFixture`] loads the channels of a [`FixtureEnv`] database, starts
+//! them on its `MemoryRtdb` and serves the full API. Seed virtual channels
+//! with [`FixtureEnv::add_channel`]; other protocols start as well but need
+//! a reachable device.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::Router;
+use common::test_utils::services::{FixtureEnv, ServiceFixture};
+use voltage_rtdb::MemoryRtdb;
+
+use crate::api::command_cache::CommandTxCache;
+use crate::api::routes::create_api_routes_generic;
+use crate::core::channels::ChannelManager;
+use crate::core::config::ConfigManager;
+use crate::runtime::lifecycle::start_communication_service_generic;
+
+/// comsrv API with the channels of the fixture database running
+pub struct ComsrvFixture;
+
+#[async_trait(?Send)]
+impl ServiceFixture for ComsrvFixture {
+    const NAME: &'static str = "comsrv";
+    type State = Arc<ChannelManager<MemoryRtdb>>;
+
+    async fn start(env: &FixtureEnv) -> Result<(Router, Self::State)> {
+        let config_manager = Arc::new(ConfigManager::from_sqlite(env.db_path()).await?);
+        let command_tx_cache = Arc::new(CommandTxCache::new());
+        let channel_manager = Arc::new(ChannelManager::with_shared_memory(
+            Arc::clone(env.rtdb()),
+            Arc::clone(env.routing_cache()),
+            env.pool().clone(),
+            None,
+            None,
+            Some(Arc::clone(&command_tx_cache)),
+        ));
+        start_communication_service_generic(config_manager, Arc::clone(&channel_manager)).await?;
+
+        let router = create_api_routes_generic(
+            Arc::clone(&channel_manager),
+            Arc::clone(env.rtdb()),
+            env.pool().clone(),
+            command_tx_cache,
+        );
+        Ok((router, channel_manager))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use common::test_utils::services::{ServiceStack, VirtualChannel};
+    use modsrv::test_fixtures::{ModsrvFixture, RulesrvFixture};
+    use serde_json::{json, Value};
+    use std::time::Duration;
+    use voltage_model::{KeySpaceConfig, PointType};
+    use voltage_rtdb::Rtdb;
+
+    type Stack = ServiceStack<ComsrvFixture, ModsrvFixture, RulesrvFixture>;
+
+    async fn stack() -> Stack {
+        let env = FixtureEnv::new().await.unwrap();
+        env.add_channel(
+            &VirtualChannel::new(1001, "pcs_link")
+                .point(PointType::Telemetry, 1, "P")
+                .point(PointType::Adjustment, 1, "P_set"),
+        )
+        .await
+        .unwrap();
+        env.add_instance(1, "pcs_01", "PCS").await.unwrap();
+        env.route_measurement(1, 1, 1001, PointType::Telemetry, 1)
+            .await
+            .unwrap();
+        env.route_action(1, 1, 1001, PointType::Adjustment, 1)
+            .await
+            .unwrap();
+        ServiceStack::start(env).await.unwrap()
+    }
+
+    async fn get(url: String) -> Value {
+        let response = reqwest::get(&url).await.unwrap();
+        assert!(response.status().is_success(), "GET {}", url);
+        response.json().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_services_run_together() {
+        let stack = stack().await;
+        assert_eq!(
+            get(stack.comsrv.url("/api/channels/1001/status")).await["data"]["connected"],
+            json!(true)
+        );
+        let instance = get(stack.modsrv.url("/api/instances/1")).await;
+        assert_eq!(
+            instance["data"]["instance"]["instance_name"],
+            json!("pcs_01")
+        );
+        get(stack.rulesrv.url("/api/scheduler/status")).await;
+
+        // modsrv action -> M2C route -> TODO queue -> comsrv channel
+        let response = reqwest::Client::new()
+            .post(stack.modsrv.url("/api/instances/1/action"))
+            .json(&json!({"point_id": "1", "value": 42.5}))
+            .send()
+            .await
+            .unwrap();
+        assert!(
+            response.status().is_success(),
+            "{}",
+            response.text().await.unwrap()
+        );
+
+        let keyspace = KeySpaceConfig::production_cached();
+        let rtdb = stack.env.rtdb();
+        let todo = keyspace.todo_queue_key(1001, PointType::Adjustment);
+        let mut drained = false;
+        for _ in 0..50 {
+            if rtdb.list_range(&todo, 0, -1).await.unwrap().is_empty() {
+                drained = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(drained, "comsrv did not consume the routed action");
+        let value = rtdb
+            .hash_get(&keyspace.channel_key(1001, PointType::Adjustment), "1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            std::str::from_utf8(&value).unwrap().parse::<f64>().unwrap(),
+            42.5
+        );
+
+        stack.shutdown().await;
+    }
+}
@@ -127,15 +127,43 @@ pub fn normalize_protocol_name(name: &str) -> String {
             "iec104".to_string()
         },
 
+        // IEC 61850 variations
+        "iec61850" | "iec_61850" | "mms" => "iec61850".to_string(),
+
         // gRPC variations
         "grpc" | "g_rpc" => "grpc".to_string(),
 
         // MQTT variations
         "mqtt" | "mqtt_protocol" => "mqtt".to_string(),
 
+        // DL/T 645 variations
+        "dlt645" | "dlt_645" | "dl_t645" | "dl_t_645" | "dl/t645" | "dl/t_645" | "dlt645_2007" => {
+            "dlt645".to_string()
+        },
+
+        // BACnet variations
+        "bacnet" | "bacnet_ip" | "bacnet/ip" | "bacnetip" | "bip" => "bacnet".to_string(),
+
+        // DLMS/COSEM variations
+        "dlms" | "dlms_cosem" | "dlms/cosem" | "cosem" | "iec62056" | "iec_62056" => {
+            "dlms".to_string()
+        },
+
+        // DNP3 variations
+        "dnp3" | "dnp3_tcp" | "dnp" | "dnp_tcp" => "dnp3_tcp".to_string(),
+
+        // EtherNet/IP variations
+        "enip" | "ethernet_ip" | "ethernet/ip" | "ethernetip" | "eip" | "cip" => "enip".to_string(),
+
         // OPC UA variations
         "opcua" | "opc_ua" | "opc ua" => "opcua".to_string(),
 
+        // Siemens S7 variations
+        "s7" | "s7comm" | "s7_comm" | "siemens_s7" | "siemens" => "s7".to_string(),
+
+        // SNMP variations
+        "snmp" | "snmp_v2c" | "snmpv2c" | "snmp_v3" | "snmpv3" => "snmp".to_string(),
+
         // Default: return cleaned name with underscores
         _ => normalized,
     }
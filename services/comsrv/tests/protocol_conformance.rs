@@ -0,0 +1,91 @@
+//! Protocol conformance tests
+//!
+//! Runs the comlink-conformance scenario battery (timeouts, partial frames,
+//! reconnect, concurrent read/write) against the protocol runtimes comsrv
+//! creates, using scripted loopback endpoints instead of real devices.
+//!
+//! The scenarios wait out real protocol timeouts, so they are opt-in:
+//! `cargo test -p comsrv --test protocol_conformance -- --ignored`
+//!
+//! All scenarios drive Modbus channels, so the file needs the `modbus` feature.
+
+#![cfg(feature = "modbus")]
+#![allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+
+use comlink_conformance::modbus::{ModbusRtuResponder, ModbusTcpResponder};
+use comlink_conformance::ConformanceSuite;
+use comsrv::core::channels::igw_bridge::{
+    convert_to_modbus_point_configs, create_modbus_channel, create_modbus_rtu_channel,
+};
+use comsrv::core::config::{ChannelConfig, ControlPoint, RuntimeChannelConfig, TelemetryPoint};
+use comsrv::core::protocols::modbus_blocks::BlockConfig;
+use voltage_model::PointType;
+
+/// One holding-register telemetry point and one coil control point on unit 1
+fn modbus_runtime_config(protocol: &str) -> RuntimeChannelConfig {
+    let base: ChannelConfig = serde_json::from_value(serde_json::json!({
+        "id": 1, "name": "conformance", "protocol": protocol
+    }))
+    .unwrap();
+    let mut config = RuntimeChannelConfig::from_base(base);
+    config.telemetry_points.push(
+        serde_json::from_value::<TelemetryPoint>(serde_json::json!({
+            "point_id": 1, "signal_name": "Voltage",
+            "protocol_mappings": serde_json::json!({
+                "slave_id": 1, "function_code": 3, "register_address": 0, "data_type": "uint16"
+            })
+            .to_string()
+        }))
+        .unwrap(),
+    );
+    config.control_points.push(
+        serde_json::from_value::<ControlPoint>(serde_json::json!({
+            "point_id": 1, "signal_name": "Start",
+            "protocol_mappings": serde_json::json!({
+                "slave_id": 1, "function_code": 5, "register_address": 0
+            })
+            .to_string()
+        }))
+        .unwrap(),
+    );
+    config
+}
+
+#[tokio::test]
+#[ignore = "Exercises real protocol timeouts; run with --ignored"]
+async fn test_modbus_tcp_conformance() {
+    let points = convert_to_modbus_point_configs(&modbus_runtime_config("modbus_tcp"));
+
+    ConformanceSuite::tcp(
+        "modbus_tcp",
+        ModbusTcpResponder::default(),
+        move |address| {
+            let (host, port) = address.host_port().unwrap();
+            create_modbus_channel(1, &host, port, points.clone(), BlockConfig::default())
+        },
+    )
+    .with_write_commands(vec![(PointType::Control.to_internal_id(1), 1.0)])
+    .run()
+    .await
+    .assert_passed();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+#[ignore = "Exercises real protocol timeouts; run with --ignored"]
+async fn test_modbus_rtu_conformance() {
+    let points = convert_to_modbus_point_configs(&modbus_runtime_config("modbus_rtu"));
+
+    ConformanceSuite::serial(
+        "modbus_rtu",
+        ModbusRtuResponder::default(),
+        move |address| {
+            let device = address.device().unwrap().to_string_lossy();
+            create_modbus_rtu_channel(1, &device, 9600, points.clone(), BlockConfig::default())
+        },
+    )
+    .with_write_commands(vec![(PointType::Control.to_internal_id(1), 1.0)])
+    .run()
+    .await
+    .assert_passed();
+}
@@ -16,6 +16,8 @@ from ..services.query_service import query_service
 from ..services.data_collector import data_collector
 from ..services.data_storage import data_storage
 from ..services.scheduler import scheduler_service
+from ..services.destinations import destination_manager
+from ..services.retention import retention_manager
 from ..core.config import settings
 
 # 创建路由器
@@ -83,6 +85,38 @@ async def health_check():
         raise HTTPException(status_code=500, detail=f"健康检查失败: {e}")
 
 
+@router.get("/destinations", summary="写入目标状态")
+async def get_destinations_status():
+    """
+    获取各写入目标的缓冲和延迟指标
+    
+    **返回字段:**
+    - buffered: 缓冲区中待写入的数据条数
+    - lag_seconds: 缓冲区中最旧数据距今的秒数
+    - written / failed_batches / retries / dropped: 累计写入、失败批次、重试和丢弃计数
+    """
+    try:
+        return {"destinations": destination_manager.get_status()}
+    except Exception as e:
+        raise HTTPException(status_code=500, detail=f"获取写入目标状态失败: {e}")
+
+@router.get("/retention", summary="数据保留任务状态")
+async def get_retention_status():
+    """
+    获取分层保留策略和最近一次执行结果
+    
+    **返回字段:**
+    - backends: 各后端的层级配置（bucket、聚合粒度、保留时长）
+    - last_run_time / last_run_duration / last_run_success: 最近一次执行时间、耗时和结果
+    - last_results: 最近一次各后端的降采样点数、删除点数和回收量
+    - total_reclaimed: 累计回收量（InfluxDB为数据点数，Parquet为文件数和字节数）
+    """
+    try:
+        return retention_manager.get_status()
+    except Exception as e:
+        raise HTTPException(status_code=500, detail=f"获取数据保留状态失败: {e}")
+
+
 @router.get("/data/query", response_model=QueryResponse, summary="查询历史数据")
 async def query_history_data(
     redis_key: str = Query(..., description="Redis键，必填参数", example="comsrv:device001:sensors"),
@@ -169,6 +203,76 @@ async def query_history_data(
     except Exception as e:
         raise HTTPException(status_code=500, detail=f"查询失败: {e}")
 
+# 相对时间范围单位（秒）
+RANGE_UNITS = {"m": 60, "h": 3600, "d": 86400}
+
+# history 接口自动降采样时的目标点数
+HISTORY_TARGET_POINTS = 500
+
+def parse_range(range_str: str) -> timedelta:
+    """
+    解析相对时间范围，如 15m、1h、24h、7d
+    """
+    value, unit = range_str[:-1], range_str[-1:].lower()
+    if unit not in RANGE_UNITS or not value.isdigit() or int(value) <= 0:
+        raise HTTPException(
+            status_code=400,
+            detail=f"无效的时间范围: {range_str}。支持格式: 15m、1h、24h、7d"
+        )
+    span = timedelta(seconds=int(value) * RANGE_UNITS[unit])
+    if span > timedelta(days=settings.MAX_TIME_RANGE_DAYS):
+        raise HTTPException(
+            status_code=400,
+            detail=f"查询时间范围不能超过 {settings.MAX_TIME_RANGE_DAYS} 天"
+        )
+    return span
+
+@router.get("/history", response_model=QueryResponse, summary="按相对时间范围查询历史数据")
+async def query_history_range(
+    redis_key: str = Query(..., description="Redis键，必填参数", example="inst:1:M"),
+    point_id: str = Query(..., description="点位ID，必填参数", example="1"),
+    time_range: str = Query("1h", alias="range", description="相对时间范围（截至当前时间），如 15m、1h、24h、7d", example="1h"),
+    interval: Optional[int] = Query(None, ge=1, description="数据采样间隔（秒），不提供则按范围自动选择（约500个点）", example=60)
+):
+    """
+    查询单个点位最近一段时间的历史数据（供 modsrv 等服务代理调用）
+
+    **参数说明:**
+    - redis_key: Redis键，如 "inst:1:M"（必填）
+    - point_id: 点位ID，如 "1"（必填）
+    - range: 相对时间范围，默认 1h
+    - interval: 采样间隔（秒），默认按范围自动选择
+
+    **示例:**
+    ```
+    GET /hisApi/history?redis_key=inst:1:M&point_id=1&range=24h
+    ```
+    """
+    try:
+        span = parse_range(time_range)
+        end_time = datetime.now()
+        start_time = end_time - span
+
+        if interval is None:
+            interval = max(1, int(span.total_seconds()) // HISTORY_TARGET_POINTS)
+
+        request = QueryRequest(
+            start_time=start_time,
+            end_time=end_time,
+            redis_keys=[redis_key],
+            point_ids=[point_id],
+            sources=None,
+            interval=interval,
+            page=1,
+            page_size=settings.MAX_PAGE_SIZE
+        )
+        return query_service.query_history_data(request)
+
+    except HTTPException:
+        raise
+    except Exception as e:
+        raise HTTPException(status_code=500, detail=f"查询失败: {e}")
+
 @router.get("/data/statistics", response_model=StatisticsResponse, summary="查询统计数据")
 async def query_statistics(
     redis_key: str = Query(..., description="Redis键，必填参数", example="comsrv:device001:sensors"),
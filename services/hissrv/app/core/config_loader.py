@@ -10,6 +10,14 @@ from pathlib import Path
 from loguru import logger
 from .config import settings
 
+# 资源档位对应的历史写入默认值（与 common::resource_profile 的档位一致）
+PROFILE_DEFAULTS: Dict[str, Dict[str, int]] = {
+    'small': {'batch_size': 200, 'max_buffer_size': 10000},
+    'medium': {'batch_size': 1000, 'max_buffer_size': 100000},
+    'large': {'batch_size': 5000, 'max_buffer_size': 500000},
+}
+
+
 class ConfigLoader:
     """配置加载器"""
     
@@ -86,6 +94,10 @@ class ConfigLoader:
         return self.get_config('data_storage', {})
     
     
+    def get_destinations_config(self) -> List[Dict[str, Any]]:
+        """获取多目标写入配置"""
+        return self.get_config('destinations', []) or []
+    
     def get_redis_source_config(self) -> Dict[str, Any]:
         """获取Redis数据源配置"""
         return self.get_config('redis_source', {})
@@ -146,7 +158,8 @@ class ConfigLoader:
             'influxdb_bucket': self.get_config('influxdb.bucket'),
             'data_collection_interval': self.get_config('scheduler.data_collection.interval', 5),
             'data_flush_interval': self.get_config('scheduler.data_collection.flush_interval', 5),
-            'data_batch_size': self.get_config('scheduler.data_collection.batch_size', 1000),
+            'data_batch_size': self.get_data_batch_size(),
+            'profile': self.get_profile(),
             'redis_patterns_count': len(self.get_config('redis_source.subscribe_patterns', [])),
             'api_prefix': self.get_config('api.prefix', '/hisApi'),
             'config_file': str(Path(settings.CONFIG_DIR) / self.config_file)
@@ -162,12 +175,42 @@ class ConfigLoader:
     
     def get_data_batch_size(self) -> int:
         """获取数据批量大小"""
-        return self.get_config('scheduler.data_collection.batch_size', 1000)
+        return self.get_config('scheduler.data_collection.batch_size',
+                               self.get_profile_defaults()['batch_size'])
+
+    def get_profile(self) -> str:
+        """获取资源档位：hissrv.yaml > global.yaml > VOLTAGE_PROFILE > medium"""
+        candidates = [self.get_config('profile')]
+        global_path = Path(settings.CONFIG_DIR) / "global.yaml"
+        if global_path.exists():
+            try:
+                with open(global_path, 'r', encoding='utf-8') as file:
+                    candidates.append((yaml.safe_load(file) or {}).get('profile'))
+            except Exception as e:
+                logger.warning(f"读取全局配置失败: {e}")
+        candidates.append(os.environ.get('VOLTAGE_PROFILE'))
+
+        for candidate in candidates:
+            if not candidate:
+                continue
+            profile = str(candidate).strip().lower()
+            if profile in PROFILE_DEFAULTS:
+                return profile
+            logger.warning(f"未知资源档位 '{candidate}'，可选 small/medium/large")
+        return 'medium'
+
+    def get_profile_defaults(self) -> Dict[str, int]:
+        """获取当前资源档位的历史写入默认值"""
+        return PROFILE_DEFAULTS[self.get_profile()]
     
     def get_retention_policy(self) -> Dict[str, Any]:
         """获取数据保留策略"""
         return self.get_config('influxdb.retention_policy', {})
     
+    def get_retention_config(self) -> Dict[str, Any]:
+        """获取分层保留与降采样配置"""
+        return self.get_config('retention', {}) or {}
+    
     def get_api_pagination_config(self) -> Dict[str, Any]:
         """获取API分页配置"""
         return self.get_config('api.pagination', {})
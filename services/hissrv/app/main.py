@@ -15,6 +15,7 @@ from .core.config import settings
 from .core.config_loader import config_loader
 from .api.routes import router
 from .services.scheduler import scheduler_service
+from .services.destinations import destination_manager
 
 # 配置日志
 logger.remove()
@@ -68,6 +69,13 @@ async def lifespan(app: FastAPI):
     except Exception as e:
         logger.error(f"停止定时任务服务失败: {e}")
     
+    # 尽量写出各目标缓冲区中剩余的数据
+    try:
+        destination_manager.flush_all()
+        destination_manager.close()
+    except Exception as e:
+        logger.error(f"关闭写入目标失败: {e}")
+    
     logger.info("服务已关闭")
 
 # 创建FastAPI应用
@@ -13,6 +13,7 @@ class HistoryData(BaseModel):
     point_id: str = Field(..., description="点位ID")
     value: Any = Field(..., description="数值")
     source: str = Field(..., description="数据来源")
+    alias: Optional[str] = Field(None, description="点位别名（modsrv维护的稳定外部标识）")
     
     class Config:
         json_encoders = {
@@ -42,6 +43,7 @@ class QueryRequest(BaseModel):
     end_time: Optional[datetime] = Field(None, description="结束时间，如果不提供则默认为当前时间")
     redis_keys: Optional[List[str]] = Field(None, description="Redis键列表")
     point_ids: Optional[List[str]] = Field(None, description="点位ID列表")
+    aliases: Optional[List[str]] = Field(None, description="点位别名列表")
     sources: Optional[List[str]] = Field(None, description="数据来源列表")
     interval: int = Field(600, ge=1, description="数据采样间隔（秒），默认600秒（10分钟）")
     page: int = Field(1, ge=1, description="页码")
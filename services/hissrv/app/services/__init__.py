@@ -6,7 +6,10 @@ from .data_collector import data_collector
 from .data_storage import data_storage
 from .query_service import query_service
 from .scheduler import scheduler_service
+from .destinations import destination_manager
+from .retention import retention_manager
 
 __all__ = [
-    "data_collector", "data_storage", "query_service", "scheduler_service"
+    "data_collector", "data_storage", "query_service", "scheduler_service",
+    "destination_manager", "retention_manager"
 ]
@@ -1,10 +1,13 @@
 """
 数据收集服务
-定时从Redis获取数据
+定时从Redis获取数据；启用事件日志（redis_source.journal）时按日志中的每次写入采集
 """
 
 import re
 import json
+import fnmatch
+import socket
+import threading
 from typing import Dict, List, Optional, Any, Tuple
 from datetime import datetime
 from loguru import logger
@@ -13,6 +16,16 @@ from ..core.database import redis_manager
 from ..core.config_loader import config_loader
 from ..models.data_models import RedisDataPoint, HistoryData
 
+try:
+    from voltage_common.aliases import AliasResolver
+except ImportError:  # 单独构建的服务镜像不包含公共模块
+    AliasResolver = None
+
+try:
+    from voltage_common import journal
+except ImportError:
+    journal = None
+
 class DataCollector:
     """数据收集器"""
     
@@ -20,7 +33,47 @@ class DataCollector:
         self.redis_client = redis_manager.get_client()
         self.subscribe_patterns = config_loader.get_subscribe_patterns()
         self.exclude_patterns = config_loader.get_config('redis_source.filters.exclude_patterns', [])
+        self.alias_resolver = self._create_alias_resolver()
+        self.journal_config = config_loader.get_config('redis_source.journal', {}) or {}
+        self.journal_consumers = self._create_journal_consumers()
+        # 已采集、尚未确认的日志条目: stream -> [条目ID]
+        self._journal_ids: Dict[str, List[str]] = {}
+        self._journal_lock = threading.Lock()
+
+    def _create_alias_resolver(self):
+        """按 data_storage.aliases 配置创建点位别名解析器，未启用返回None"""
+        config = config_loader.get_config('data_storage.aliases', {}) or {}
+        if not config.get('enabled', False):
+            return None
+        if AliasResolver is None:
+            logger.warning("未找到公共模块 voltage_common，点位别名已禁用")
+            return None
+        return AliasResolver(redis_manager.get_client,
+                             scheme=config.get('scheme'),
+                             ttl=float(config.get('refresh', 60)))
         
+    def _create_journal_consumers(self) -> List[Any]:
+        """按 redis_source.journal 配置创建日志消费者，未启用返回空列表"""
+        if not self.journal_config.get('enabled', False):
+            return []
+        if journal is None:
+            logger.warning("未找到公共模块 voltage_common，事件日志已禁用，使用定时快照采集")
+            return []
+
+        group = self.journal_config.get('group') or 'hissrv'
+        consumer = self.journal_config.get('consumer') or socket.gethostname()
+        start_id = '0' if self.journal_config.get('start') == 'earliest' else '$'
+        streams = [journal.POINT_STREAM]
+        if self.journal_config.get('alarms', True):
+            streams.append(journal.ALARM_STREAM)
+        logger.info(f"事件日志采集已启用: {streams}, 消费组 {group}, 消费者 {consumer}")
+        return [journal.JournalConsumer(redis_manager.get_client, stream, group, consumer, start_id)
+                for stream in streams]
+
+    @property
+    def journal_enabled(self) -> bool:
+        return bool(self.journal_consumers)
+
     def parse_redis_key(self, key: str) -> Dict[str, str]:
         """解析Redis键 - 简化版本，直接使用Redis键"""
         try:
@@ -128,6 +181,9 @@ class DataCollector:
     
     def collect_all_data(self) -> List[HistoryData]:
         """收集所有数据"""
+        if self.journal_enabled:
+            return self.collect_from_journal()
+
         all_history_data = []
         
         logger.info(f"开始收集数据，监听 {len(self.subscribe_patterns)} 个模式")
@@ -143,6 +199,8 @@ class DataCollector:
                     parsed_key = self.parse_redis_key(data_point.key)
                     if parsed_key:
                         history_data = data_point.to_history_data(parsed_key)
+                        if self.alias_resolver is not None:
+                            history_data.alias = self.alias_resolver.alias(data_point.key, data_point.field)
                         all_history_data.append(history_data)
                         
             except Exception as e:
@@ -152,6 +210,83 @@ class DataCollector:
         logger.info(f"总共收集到 {len(all_history_data)} 条历史数据")
         return all_history_data
     
+    def collect_from_journal(self) -> List[HistoryData]:
+        """从事件日志读取新事件（含上次退出前未确认的事件）并转换为历史数据
+
+        条目ID暂存至 take_journal_ids() 取走，数据写入存储后由 ack_journal() 确认。
+        """
+        batch_size = int(self.journal_config.get('batch_size', 1000))
+        max_batches = int(self.journal_config.get('max_batches', 20))
+        all_history_data = []
+
+        for consumer in self.journal_consumers:
+            try:
+                for _ in range(max_batches):
+                    events = consumer.read(count=batch_size)
+                    if not events:
+                        break
+                    for event in events:
+                        all_history_data.extend(self._history_from_event(event))
+                    with self._journal_lock:
+                        self._journal_ids.setdefault(consumer.stream, []).extend(
+                            event.id for event in events)
+            except Exception as e:
+                logger.error(f"读取事件日志 {consumer.stream} 失败: {e}")
+
+        if all_history_data:
+            logger.debug(f"事件日志采集到 {len(all_history_data)} 条历史数据")
+        return all_history_data
+
+    def _history_from_event(self, event) -> List[HistoryData]:
+        """日志事件 -> 历史数据，不在订阅范围内的事件返回空列表"""
+        timestamp = event.timestamp.replace(tzinfo=None)  # 与快照采集一致使用UTC naive时间
+
+        if event.type == journal.POINT_WRITTEN:
+            key = event.key
+            if not any(fnmatch.fnmatchcase(key, pattern) for pattern in self.subscribe_patterns):
+                return []
+            if self.should_exclude_key(key):
+                return []
+            parsed_key = self.parse_redis_key(key)
+            history_data = []
+            for field, value in event.points.items():
+                if field.startswith('_'):
+                    continue
+                data = RedisDataPoint(key=key, field=field, value=self._convert_value(value),
+                                      timestamp=timestamp).to_history_data(parsed_key)
+                if self.alias_resolver is not None:
+                    data.alias = self.alias_resolver.alias(key, field)
+                history_data.append(data)
+            return history_data
+
+        if event.type in (journal.ALARM_RAISED, journal.ALARM_RECOVERED):
+            return [HistoryData(
+                timestamp=timestamp,
+                redis_key=f"alarm:{event.fields.get('rule_id', 'unknown')}",
+                point_id="active",
+                value=1 if event.type == journal.ALARM_RAISED else 0,
+                source="alarmsrv",
+            )]
+
+        return []
+
+    def take_journal_ids(self) -> Dict[str, List[str]]:
+        """取走自上次调用以来采集的日志条目ID"""
+        with self._journal_lock:
+            ids, self._journal_ids = self._journal_ids, {}
+        return ids
+
+    def ack_journal(self, ids: Dict[str, List[str]]):
+        """确认已写入存储的日志条目，失败时保留待确认（重启后重新投递）"""
+        for consumer in self.journal_consumers:
+            stream_ids = ids.get(consumer.stream)
+            if not stream_ids:
+                continue
+            try:
+                consumer.ack(stream_ids)
+            except Exception as e:
+                logger.error(f"确认事件日志 {consumer.stream} 失败: {e}")
+
     def get_latest_data(self, channel_id: str, data_type: str = None) -> Optional[Dict[str, Any]]:
         """获取最新数据 - channel_id就是完整的Redis键"""
         try:
@@ -40,6 +40,8 @@ class DataStorage:
             point.tag("redis_key", str(data.redis_key))
             point.tag("point_id", str(data.point_id))
             point.tag("source", data.source)
+            if data.alias:
+                point.tag("alias", data.alias)
             
             # 添加数值字段
             if isinstance(data.value, (int, float)):
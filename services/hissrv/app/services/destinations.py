@@ -0,0 +1,332 @@
+"""
+多目标写入服务
+同一批数据同时写入多个存储后端（如本地Parquet + 云端InfluxDB），
+每个目标拥有独立的缓冲区、重试策略和延迟指标；
+配置 masking: true 的目标在入队前按全局 masking 规则脱敏
+"""
+
+import threading
+import time
+from collections import deque
+from datetime import datetime, timezone
+from pathlib import Path
+from typing import Any, Deque, Dict, List, Optional
+
+from loguru import logger
+
+from ..core.config_loader import config_loader
+from ..core.influxdb import influxdb_manager
+from ..models.data_models import HistoryData
+from .data_storage import data_storage
+
+try:
+    from voltage_common.masking import Masker
+except ImportError:  # 单独构建的服务镜像不包含公共模块
+    Masker = None
+
+
+def create_masker() -> Optional[Any]:
+    """按全局 masking 配置创建脱敏器，未启用返回None"""
+    config = config_loader.get_config('masking', {}) or {}
+    if not config.get('enabled', False):
+        return None
+    if Masker is None:
+        raise RuntimeError("未找到公共模块 voltage_common，无法脱敏")
+    masker = Masker.from_config(config)
+    if masker is not None and not masker.salted:
+        logger.warning("脱敏未配置盐值，短编号等字段的哈希可被穷举还原")
+    return masker
+
+
+class Destination:
+    """写入目标基类，负责缓冲、重试和指标统计"""
+
+    def __init__(self, name: str, config: Dict[str, Any]):
+        self.name = name
+        self.type = config.get('type', 'unknown')
+        # 未显式配置时按资源档位取默认值
+        defaults = config_loader.get_profile_defaults()
+        self.batch_size = int(config.get('batch_size', defaults['batch_size']))
+
+        # 缓冲区满时丢弃最旧的数据，避免一个慢目标拖垮整个服务
+        self.max_buffer_size = int(config.get('max_buffer_size', defaults['max_buffer_size']))
+        self.buffer: Deque[HistoryData] = deque()
+        self.buffer_lock = threading.Lock()
+
+        # 重试配置（指数退避）
+        retry_config = config.get('retry', {}) or {}
+        self.max_retries = int(retry_config.get('max_retries', 5))
+        self.initial_backoff = float(retry_config.get('initial_backoff', 1))
+        self.max_backoff = float(retry_config.get('max_backoff', 60))
+        self.consecutive_failures = 0
+        self.next_attempt_at = 0.0
+
+        # 脱敏：数据离开边缘侧的目标（如共享给分析服务商的Parquet）开启
+        # 脱敏规则缺失时不加载该目标，避免原始数据外泄
+        self.masker = None
+        if config.get('masking', False):
+            self.masker = create_masker()
+            if self.masker is None:
+                raise RuntimeError("目标开启了脱敏，但全局 masking 未启用或无规则")
+
+        self.stats = {
+            "written": 0,
+            "failed_batches": 0,
+            "dropped": 0,
+            "retries": 0,
+            "last_success_time": None,
+            "last_error": None,
+        }
+
+    def write(self, data_list: List[HistoryData]) -> bool:
+        """写入一批数据到后端，由子类实现"""
+        raise NotImplementedError
+
+    def close(self):
+        """释放后端资源"""
+
+    def _mask(self, data_list: List[HistoryData]) -> List[HistoryData]:
+        """按点位（Redis键 + 点位ID）脱敏，被删除的点位不再写入"""
+        masked = []
+        for data in data_list:
+            keep, value = self.masker.mask_field(data.redis_key, data.point_id, data.value)
+            if not keep:
+                continue
+            masked.append(data if value is data.value else data.model_copy(update={"value": value}))
+        return masked
+
+    def enqueue(self, data_list: List[HistoryData]):
+        """将数据加入本目标的缓冲区"""
+        if self.masker is not None:
+            data_list = self._mask(data_list)
+        with self.buffer_lock:
+            self.buffer.extend(data_list)
+            overflow = len(self.buffer) - self.max_buffer_size
+            if overflow > 0:
+                for _ in range(overflow):
+                    self.buffer.popleft()
+                self.stats["dropped"] += overflow
+                logger.warning(f"目标 {self.name} 缓冲区已满，丢弃 {overflow} 条最旧数据")
+
+    def flush(self) -> int:
+        """刷新缓冲区，返回成功写入的条数"""
+        if time.monotonic() < self.next_attempt_at:
+            return 0
+
+        written = 0
+        while True:
+            with self.buffer_lock:
+                if not self.buffer:
+                    break
+                batch = [self.buffer[i] for i in range(min(self.batch_size, len(self.buffer)))]
+
+            try:
+                success = self.write(batch)
+                error = None if success else "写入返回失败"
+            except Exception as e:
+                success = False
+                error = str(e)
+
+            if not success:
+                self._record_failure(len(batch), error)
+                break
+
+            with self.buffer_lock:
+                for _ in range(len(batch)):
+                    self.buffer.popleft()
+            written += len(batch)
+            self.consecutive_failures = 0
+            self.stats["written"] += len(batch)
+            self.stats["last_success_time"] = datetime.utcnow()
+
+        return written
+
+    def _record_failure(self, batch_len: int, error: Optional[str]):
+        """记录写入失败并计算下次重试时间"""
+        self.consecutive_failures += 1
+        self.stats["failed_batches"] += 1
+        self.stats["last_error"] = error
+
+        if self.consecutive_failures > self.max_retries:
+            # 超过最大重试次数，丢弃当前批次以免阻塞后续数据
+            with self.buffer_lock:
+                dropped = min(batch_len, len(self.buffer))
+                for _ in range(dropped):
+                    self.buffer.popleft()
+            self.stats["dropped"] += dropped
+            self.consecutive_failures = 0
+            self.next_attempt_at = 0.0
+            logger.error(f"目标 {self.name} 重试 {self.max_retries} 次失败，丢弃 {dropped} 条数据: {error}")
+            return
+
+        backoff = min(self.initial_backoff * (2 ** (self.consecutive_failures - 1)), self.max_backoff)
+        self.next_attempt_at = time.monotonic() + backoff
+        self.stats["retries"] += 1
+        logger.warning(f"目标 {self.name} 写入失败，{backoff:.1f} 秒后重试: {error}")
+
+    def lag_seconds(self) -> float:
+        """缓冲区中最旧数据距今的秒数"""
+        with self.buffer_lock:
+            if not self.buffer:
+                return 0.0
+            oldest = self.buffer[0].timestamp
+        if oldest.tzinfo is None:
+            oldest = oldest.replace(tzinfo=timezone.utc)
+        return max((datetime.now(timezone.utc) - oldest).total_seconds(), 0.0)
+
+    def get_status(self) -> Dict[str, Any]:
+        """获取目标状态和指标"""
+        with self.buffer_lock:
+            buffered = len(self.buffer)
+        return {
+            "name": self.name,
+            "type": self.type,
+            "buffered": buffered,
+            "lag_seconds": round(self.lag_seconds(), 3),
+            "consecutive_failures": self.consecutive_failures,
+            **self.stats,
+        }
+
+
+class InfluxDBDestination(Destination):
+    """InfluxDB写入目标"""
+
+    def __init__(self, name: str, config: Dict[str, Any]):
+        super().__init__(name, config)
+        self.bucket = config.get('bucket')
+        self.client = None
+        self.write_api = None
+
+        # 未配置url时复用主InfluxDB连接
+        if config.get('url'):
+            from influxdb_client import InfluxDBClient
+            from influxdb_client.client.write_api import SYNCHRONOUS
+
+            self.client = InfluxDBClient(
+                url=config['url'],
+                token=config.get('token', ''),
+                org=config.get('org', ''),
+                timeout=int(config.get('timeout', 30)) * 1000
+            )
+            self.write_api = self.client.write_api(write_options=SYNCHRONOUS)
+
+    def write(self, data_list: List[HistoryData]) -> bool:
+        points = [data_storage.create_point_from_history_data(data) for data in data_list]
+        if self.write_api is None:
+            if self.bucket is None:
+                return influxdb_manager.write_points(points)
+            if influxdb_manager.write_api is None:
+                return False
+            influxdb_manager.write_api.write(bucket=self.bucket, record=points)
+            return True
+
+        self.write_api.write(bucket=self.bucket, record=points)
+        return True
+
+    def close(self):
+        if self.client:
+            self.client.close()
+
+
+class ParquetDestination(Destination):
+    """本地Parquet文件写入目标，每次写入生成一个文件"""
+
+    def __init__(self, name: str, config: Dict[str, Any]):
+        super().__init__(name, config)
+        self.path = Path(config.get('path', 'data/parquet'))
+        self.compression = config.get('compression', 'snappy')
+
+    def write(self, data_list: List[HistoryData]) -> bool:
+        import pandas as pd
+
+        rows = [{
+            "timestamp": data.timestamp,
+            "redis_key": data.redis_key,
+            "point_id": data.point_id,
+            "value": data.value if isinstance(data.value, (int, float)) else None,
+            "string_value": None if isinstance(data.value, (int, float)) else str(data.value),
+            "source": data.source,
+            "alias": data.alias,
+        } for data in data_list]
+
+        # 按日期分目录: {path}/2025-09-02/20250902T093000_123456.parquet
+        now = datetime.utcnow()
+        directory = self.path / now.strftime("%Y-%m-%d")
+        directory.mkdir(parents=True, exist_ok=True)
+        file_path = directory / f"{now.strftime('%Y%m%dT%H%M%S_%f')}.parquet"
+
+        pd.DataFrame(rows).to_parquet(file_path, compression=self.compression, index=False)
+        return True
+
+
+DESTINATION_TYPES = {
+    "influxdb": InfluxDBDestination,
+    "parquet": ParquetDestination,
+}
+
+
+class DestinationManager:
+    """多目标写入管理器"""
+
+    def __init__(self):
+        self.destinations: List[Destination] = []
+        self.load_destinations()
+
+    def load_destinations(self):
+        """从配置加载写入目标，未配置时使用默认InfluxDB"""
+        configs = config_loader.get_destinations_config()
+        if not configs:
+            configs = [{"name": "influxdb", "type": "influxdb"}]
+
+        destinations = []
+        for index, config in enumerate(configs):
+            if not config.get('enabled', True):
+                continue
+
+            dest_type = config.get('type')
+            dest_class = DESTINATION_TYPES.get(dest_type)
+            name = config.get('name') or f"{dest_type}-{index}"
+            if dest_class is None:
+                logger.error(f"未知的写入目标类型: {dest_type} ({name})")
+                continue
+
+            try:
+                destinations.append(dest_class(name, config))
+                logger.info(f"写入目标已加载: {name} ({dest_type})")
+            except Exception as e:
+                logger.error(f"写入目标初始化失败: {name}, {e}")
+
+        for destination in self.destinations:
+            destination.close()
+        self.destinations = destinations
+
+    def fan_out(self, data_list: List[HistoryData]):
+        """将同一批数据分发到所有目标的缓冲区"""
+        if not data_list:
+            return
+        for destination in self.destinations:
+            destination.enqueue(data_list)
+
+    def flush_all(self) -> Dict[str, int]:
+        """刷新所有目标，返回各目标写入条数"""
+        results = {}
+        for destination in self.destinations:
+            try:
+                results[destination.name] = destination.flush()
+            except Exception as e:
+                logger.error(f"目标 {destination.name} 刷新异常: {e}")
+                results[destination.name] = 0
+        return results
+
+    def get_status(self) -> List[Dict[str, Any]]:
+        """获取所有目标的状态"""
+        return [destination.get_status() for destination in self.destinations]
+
+    def close(self):
+        """关闭所有目标"""
+        for destination in self.destinations:
+            destination.close()
+
+
+# 全局多目标写入管理器实例
+destination_manager = DestinationManager()
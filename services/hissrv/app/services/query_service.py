@@ -46,6 +46,11 @@ class QueryService:
             point_filter = ' or '.join([f'r.point_id == "{pid}"' for pid in request.point_ids])
             filters.append(f'|> filter(fn: (r) => {point_filter})')
         
+        # 点位别名过滤（需开启 data_storage.aliases），点位重新编号前后的数据均可查到
+        if request.aliases:
+            alias_filter = ' or '.join([f'r.alias == "{alias}"' for alias in request.aliases])
+            filters.append(f'|> filter(fn: (r) => {alias_filter})')
+        
         # 数据来源过滤
         if request.sources:
             source_filter = ' or '.join([f'r.source == "{src}"' for src in request.sources])
@@ -167,7 +172,8 @@ class QueryService:
                 redis_key=redis_key,  # 直接使用Redis键
                 point_id=str(record.get('point_id', 'unknown')),
                 value=value,
-                source=record.get('source', 'unknown')
+                source=record.get('source', 'unknown'),
+                alias=record.get('alias')
             )
             
         except Exception as e:
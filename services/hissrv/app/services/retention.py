@@ -0,0 +1,350 @@
+"""
+数据保留与降采样服务
+按存储层级执行保留策略：原始数据 -> 1分钟聚合 -> 1小时聚合，
+每个层级独立设置保留时长，由各存储后端执行对应的降采样和删除任务
+"""
+
+import re
+import shutil
+import threading
+import time
+from datetime import datetime, timedelta, timezone
+from pathlib import Path
+from typing import Any, Dict, List, Optional
+
+from loguru import logger
+
+from ..core.config import settings
+from ..core.config_loader import config_loader
+from ..core.influxdb import influxdb_manager
+
+DURATION_UNITS = {
+    's': timedelta(seconds=1),
+    'm': timedelta(minutes=1),
+    'h': timedelta(hours=1),
+    'd': timedelta(days=1),
+    'w': timedelta(weeks=1),
+    'y': timedelta(days=365),
+}
+
+# 未配置层级时的默认策略: 原始数据30天，1分钟聚合1年，1小时聚合永久保留
+DEFAULT_TIERS = [
+    {"name": "raw", "retention": "30d"},
+    {"name": "1m", "every": "1m", "source": "raw", "retention": "1y"},
+    {"name": "1h", "every": "1h", "source": "1m", "retention": None},
+]
+
+
+def parse_duration(value: Any) -> Optional[timedelta]:
+    """解析时长字符串（如 "30d"、"1y"、"90m"），None/"forever"/"inf" 表示永久"""
+    if value is None:
+        return None
+    text = str(value).strip().lower()
+    if text in ('', 'forever', 'inf', 'infinite', 'none'):
+        return None
+    match = re.fullmatch(r'(\d+)\s*([smhdwy])', text)
+    if not match:
+        raise ValueError(f"无效的时长: {value}")
+    return int(match.group(1)) * DURATION_UNITS[match.group(2)]
+
+
+def truncate_time(moment: datetime, every: timedelta) -> datetime:
+    """将时间向下取整到聚合窗口边界，避免写入未结束窗口的聚合值"""
+    seconds = int(every.total_seconds())
+    epoch = int(moment.timestamp())
+    return datetime.fromtimestamp(epoch - epoch % seconds, tz=timezone.utc)
+
+
+def format_flux_time(moment: datetime) -> str:
+    """Flux时间字面量（RFC3339，UTC）"""
+    return moment.astimezone(timezone.utc).strftime('%Y-%m-%dT%H:%M:%SZ')
+
+
+class RetentionTier:
+    """存储层级：原始数据或某一粒度的聚合数据"""
+
+    def __init__(self, config: Dict[str, Any]):
+        self.name = str(config['name'])
+        self.every = parse_duration(config.get('every'))
+        self.source = config.get('source')
+        self.retention = parse_duration(config.get('retention'))
+        self.bucket = config.get('bucket')
+        self.aggregate = config.get('fn', 'mean')
+
+        if self.source and self.every is None:
+            raise ValueError(f"层级 {self.name} 配置了source但缺少every")
+
+    @property
+    def is_aggregate(self) -> bool:
+        return self.source is not None
+
+    def describe(self) -> Dict[str, Any]:
+        return {
+            "name": self.name,
+            "bucket": self.bucket,
+            "every": str(self.every) if self.every else None,
+            "source": self.source,
+            "retention": str(self.retention) if self.retention else "forever",
+        }
+
+
+class RetentionBackend:
+    """保留策略后端基类，负责降采样和过期数据删除"""
+
+    def __init__(self, name: str, config: Dict[str, Any]):
+        self.name = name
+        self.type = config.get('type', 'unknown')
+
+    def run(self, now: datetime) -> Dict[str, Any]:
+        """执行一轮保留任务，返回本轮结果（必须包含reclaimed字段）"""
+        raise NotImplementedError
+
+    def describe(self) -> Dict[str, Any]:
+        return {"name": self.name, "type": self.type}
+
+
+class InfluxDBRetentionBackend(RetentionBackend):
+    """InfluxDB后端：用Flux aggregateWindow降采样到聚合bucket，用delete API删除过期数据"""
+
+    def __init__(self, name: str, config: Dict[str, Any]):
+        super().__init__(name, config)
+        influxdb_config = config_loader.get_influxdb_config()
+        self.org = influxdb_config.get('org') or settings.INFLUXDB_ORG
+        raw_bucket = influxdb_config.get('bucket') or settings.INFLUXDB_BUCKET
+
+        # 每次降采样回溯的时长，需大于执行间隔以便补齐上次遗漏的窗口（重复写入同一窗口是幂等的）
+        self.lookback = parse_duration(config.get('downsample_lookback', '2d'))
+
+        self.tiers: List[RetentionTier] = []
+        for tier_config in config.get('tiers') or DEFAULT_TIERS:
+            tier = RetentionTier(tier_config)
+            if tier.bucket is None:
+                tier.bucket = raw_bucket if not tier.is_aggregate else f"{raw_bucket}_{tier.name}"
+            self.tiers.append(tier)
+
+        names = {tier.name for tier in self.tiers}
+        for tier in self.tiers:
+            if tier.is_aggregate and tier.source not in names:
+                raise ValueError(f"层级 {tier.name} 的source不存在: {tier.source}")
+
+    def _tier(self, name: str) -> RetentionTier:
+        return next(tier for tier in self.tiers if tier.name == name)
+
+    def _ensure_bucket(self, bucket_name: str):
+        """聚合bucket不存在时自动创建（不设置过期规则，由本服务负责删除）"""
+        buckets_api = influxdb_manager.client.buckets_api()
+        if buckets_api.find_bucket_by_name(bucket_name) is None:
+            buckets_api.create_bucket(bucket_name=bucket_name, org=self.org)
+            logger.info(f"创建聚合bucket: {bucket_name}")
+
+    def _count_points(self, bucket: str, stop: datetime) -> int:
+        """统计bucket中早于stop的数据点数，用于计算删除的数据量"""
+        query = f'''from(bucket: "{bucket}")
+|> range(start: 0, stop: {format_flux_time(stop)})
+|> count()
+|> group()
+|> sum()'''
+        result = influxdb_manager.query_data(query)
+        return int(result[0].get("_value", 0)) if result else 0
+
+    def _downsample(self, tier: RetentionTier, now: datetime) -> int:
+        """将source层级最近lookback时间内已结束的窗口聚合写入本层级，返回写入的点数"""
+        source = self._tier(tier.source)
+        stop = truncate_time(now, tier.every)
+        start = truncate_time(stop - self.lookback, tier.every)
+        every = f"{int(tier.every.total_seconds())}s"
+
+        # 只聚合数值字段；string_value等非数值字段不参与降采样
+        query = f'''from(bucket: "{source.bucket}")
+|> range(start: {format_flux_time(start)}, stop: {format_flux_time(stop)})
+|> filter(fn: (r) => r._field == "value")
+|> aggregateWindow(every: {every}, fn: {tier.aggregate}, createEmpty: false, timeSrc: "_start")
+|> to(bucket: "{tier.bucket}", org: "{self.org}")'''
+        return len(influxdb_manager.query_data(query))
+
+    def _expire(self, tier: RetentionTier, now: datetime) -> int:
+        """删除本层级中超过保留时长的数据，返回删除的点数"""
+        if tier.retention is None:
+            return 0
+        cutoff = now - tier.retention
+        deleted = self._count_points(tier.bucket, cutoff)
+        if deleted > 0:
+            influxdb_manager.client.delete_api().delete(
+                start=datetime(1970, 1, 1, tzinfo=timezone.utc),
+                stop=cutoff,
+                predicate='',
+                bucket=tier.bucket,
+                org=self.org,
+            )
+        return deleted
+
+    def run(self, now: datetime) -> Dict[str, Any]:
+        if influxdb_manager.client is None or influxdb_manager.query_api is None:
+            raise RuntimeError("InfluxDB未连接")
+
+        tiers: Dict[str, Dict[str, Any]] = {}
+        # 先降采样再删除，保证源数据过期前已经聚合到下一层级
+        for tier in self.tiers:
+            if tier.is_aggregate:
+                self._ensure_bucket(tier.bucket)
+                tiers[tier.name] = {"downsampled_points": self._downsample(tier, now)}
+            else:
+                tiers[tier.name] = {"downsampled_points": 0}
+
+        for tier in self.tiers:
+            tiers[tier.name]["deleted_points"] = self._expire(tier, now)
+
+        deleted = sum(result["deleted_points"] for result in tiers.values())
+        return {"tiers": tiers, "reclaimed": {"points": deleted}}
+
+    def describe(self) -> Dict[str, Any]:
+        return {
+            **super().describe(),
+            "tiers": [tier.describe() for tier in self.tiers],
+        }
+
+
+class ParquetRetentionBackend(RetentionBackend):
+    """本地Parquet后端：按日期目录删除过期文件（Parquet目标只写原始数据，不做降采样）"""
+
+    def __init__(self, name: str, config: Dict[str, Any]):
+        super().__init__(name, config)
+        self.path = Path(config.get('path', 'data/parquet'))
+        self.retention = parse_duration(config.get('retention', '30d'))
+
+    def run(self, now: datetime) -> Dict[str, Any]:
+        reclaimed = {"files": 0, "bytes": 0}
+        if self.retention is None or not self.path.exists():
+            return {"reclaimed": reclaimed}
+
+        cutoff_day = (now - self.retention).date()
+        for directory in sorted(self.path.iterdir()):
+            try:
+                day = datetime.strptime(directory.name, "%Y-%m-%d").date()
+            except ValueError:
+                continue
+            # 目录内全部是当天的数据，整天过期后才删除
+            if not directory.is_dir() or day >= cutoff_day:
+                continue
+            files = [f for f in directory.rglob('*') if f.is_file()]
+            reclaimed["files"] += len(files)
+            reclaimed["bytes"] += sum(f.stat().st_size for f in files)
+            shutil.rmtree(directory)
+            logger.info(f"删除过期Parquet目录: {directory}")
+
+        return {"reclaimed": reclaimed}
+
+    def describe(self) -> Dict[str, Any]:
+        return {
+            **super().describe(),
+            "path": str(self.path),
+            "retention": str(self.retention) if self.retention else "forever",
+        }
+
+
+RETENTION_BACKEND_TYPES = {
+    "influxdb": InfluxDBRetentionBackend,
+    "parquet": ParquetRetentionBackend,
+}
+
+
+class RetentionManager:
+    """数据保留管理器"""
+
+    def __init__(self):
+        self.enabled = False
+        self.run_at = "03:00"
+        self.backends: List[RetentionBackend] = []
+        self.run_lock = threading.Lock()
+
+        self.stats = {
+            "last_run_time": None,
+            "last_run_duration": None,
+            "last_run_success": None,
+            "last_results": {},
+            "total_runs": 0,
+            "total_reclaimed": {},
+            "errors": [],
+        }
+        self.load_config()
+
+    def load_config(self):
+        """从配置加载保留策略，未配置后端时使用默认InfluxDB层级"""
+        config = config_loader.get_retention_config()
+        self.enabled = bool(config.get('enabled', False))
+        self.run_at = str(config.get('run_at', '03:00'))
+
+        configs = config.get('backends') or [{"name": "influxdb", "type": "influxdb"}]
+        backends = []
+        for index, backend_config in enumerate(configs):
+            if not backend_config.get('enabled', True):
+                continue
+
+            backend_type = backend_config.get('type')
+            backend_class = RETENTION_BACKEND_TYPES.get(backend_type)
+            name = backend_config.get('name') or f"{backend_type}-{index}"
+            if backend_class is None:
+                logger.error(f"未知的保留策略后端类型: {backend_type} ({name})")
+                continue
+
+            try:
+                backends.append(backend_class(name, backend_config))
+                logger.info(f"保留策略后端已加载: {name} ({backend_type})")
+            except Exception as e:
+                logger.error(f"保留策略后端初始化失败: {name}, {e}")
+
+        self.backends = backends
+
+    def run(self) -> Dict[str, Any]:
+        """对所有后端执行一轮降采样和过期删除，返回各后端结果"""
+        if not self.run_lock.acquire(blocking=False):
+            logger.warning("保留任务正在执行，跳过本次触发")
+            return {}
+
+        try:
+            logger.info("开始执行数据保留任务")
+            start_time = time.time()
+            now = datetime.now(timezone.utc)
+            results: Dict[str, Any] = {}
+            success = True
+
+            for backend in self.backends:
+                try:
+                    result = backend.run(now)
+                    results[backend.name] = {"success": True, **result}
+                    totals = self.stats["total_reclaimed"].setdefault(backend.name, {})
+                    for unit, amount in result["reclaimed"].items():
+                        totals[unit] = totals.get(unit, 0) + amount
+                    logger.info(f"保留任务完成: {backend.name}, 回收 {result['reclaimed']}")
+                except Exception as e:
+                    success = False
+                    results[backend.name] = {"success": False, "error": str(e)}
+                    self.stats["errors"].append({
+                        "time": datetime.utcnow(),
+                        "error": f"{backend.name}: {e}",
+                        "type": "retention"
+                    })
+                    self.stats["errors"] = self.stats["errors"][-10:]
+                    logger.error(f"保留任务失败: {backend.name}, {e}")
+
+            self.stats["last_run_time"] = datetime.utcnow()
+            self.stats["last_run_duration"] = round(time.time() - start_time, 3)
+            self.stats["last_run_success"] = success
+            self.stats["last_results"] = results
+            self.stats["total_runs"] += 1
+            return results
+        finally:
+            self.run_lock.release()
+
+    def get_status(self) -> Dict[str, Any]:
+        """获取保留策略配置和最近一次执行结果"""
+        return {
+            "enabled": self.enabled,
+            "run_at": self.run_at,
+            "backends": [backend.describe() for backend in self.backends],
+            **self.stats,
+        }
+
+
+# 全局数据保留管理器实例
+retention_manager = RetentionManager()
@@ -17,6 +17,14 @@ from ..core.config_loader import config_loader
 from ..services.data_collector import data_collector
 from ..services.data_storage import data_storage
 from ..services.query_service import query_service
+from ..services.destinations import destination_manager
+from ..services.retention import retention_manager
+
+def _merge_ids(target: Dict[str, List[str]], ids: Dict[str, List[str]]):
+    """按 stream 合并日志条目ID"""
+    for stream, stream_ids in ids.items():
+        target.setdefault(stream, []).extend(stream_ids)
+
 
 class SchedulerService:
     """定时任务服务"""
@@ -28,6 +36,9 @@ class SchedulerService:
         # 数据缓冲区，用于暂存收集的数据
         self.data_buffer: List[Any] = []
         self.buffer_lock = threading.Lock()
+        # 事件日志条目ID: 缓冲区中数据对应的条目，以及已分发到写入目标、待全部写入后确认的条目
+        self.buffer_journal_ids: Dict[str, List[str]] = {}
+        self.unacked_journal_ids: Dict[str, List[str]] = {}
         
         self.stats = {
             "last_collection_time": None,
@@ -100,6 +111,11 @@ class SchedulerService:
             schedule.every().day.at("02:00").do(self._cleanup_old_data)
             logger.info(f"设置数据清理任务，时间: {cron_expr}")
         
+        # 分层保留与降采样任务
+        if retention_manager.enabled:
+            schedule.every().day.at(retention_manager.run_at).do(self._enforce_retention)
+            logger.info(f"设置数据保留任务，时间: {retention_manager.run_at}")
+        
         # 统计任务
         if config_loader.is_scheduler_task_enabled('statistics'):
             cron_expr = config_loader.get_config('scheduler.statistics.cron', '0 1 * * *')
@@ -181,13 +197,14 @@ class SchedulerService:
             
             # 收集数据
             history_data = data_collector.collect_all_data()
+            journal_ids = data_collector.take_journal_ids()
             
-            if not history_data:
-                logger.debug("没有收集到新数据")
-                return
-            
-            # 添加数据到缓冲区
+            # 添加数据到缓冲区（未订阅的日志条目也随之确认）
             with self.buffer_lock:
+                _merge_ids(self.buffer_journal_ids, journal_ids)
+                if not history_data:
+                    logger.debug("没有收集到新数据")
+                    return
                 self.data_buffer.extend(history_data)
                 self.stats["buffer_size"] = len(self.data_buffer)
             
@@ -210,43 +227,50 @@ class SchedulerService:
             })
     
     def _flush_buffer_to_storage(self):
-        """将缓冲区数据刷新到存储"""
+        """将缓冲区数据分发到所有写入目标并刷新"""
         try:
             # 获取缓冲区中的所有数据
             with self.buffer_lock:
-                if not self.data_buffer:
-                    logger.debug("缓冲区为空，跳过刷新")
-                    return
-                
                 data_to_flush = self.data_buffer.copy()
                 self.data_buffer.clear()
                 self.stats["buffer_size"] = 0
+                _merge_ids(self.unacked_journal_ids, self.buffer_journal_ids)
+                self.buffer_journal_ids = {}
+            
+            # 每个目标独立缓冲，即使本次没有新数据也要重试积压的数据
+            destination_manager.fan_out(data_to_flush)
             
-            logger.debug(f"开始刷新 {len(data_to_flush)} 条数据到InfluxDB")
             start_time = time.time()
+            results = destination_manager.flush_all()
+            stored = sum(results.values())
             
-            # 批量存储数据
-            result = data_storage.store_batch_data(data_to_flush)
+            # 所有目标缓冲区清空后才确认日志条目，未确认的条目在重启后重新投递
+            if self.unacked_journal_ids and all(
+                    status["buffered"] == 0 for status in destination_manager.get_status()):
+                data_collector.ack_journal(self.unacked_journal_ids)
+                self.unacked_journal_ids = {}
             
             # 更新统计信息
             elapsed_time = time.time() - start_time
-            self.stats["last_flush_time"] = datetime.utcnow()
-            self.stats["last_flush_count"] = len(data_to_flush)
-            self.stats["total_stored_points"] += result["success"]
+            if data_to_flush:
+                self.stats["last_flush_time"] = datetime.utcnow()
+                self.stats["last_flush_count"] = len(data_to_flush)
+            self.stats["total_stored_points"] += stored
             
-            logger.info(f"数据刷新完成: 刷新 {len(data_to_flush)} 条, "
-                       f"存储成功 {result['success']} 条, "
-                       f"失败 {result['failed']} 条, "
-                       f"耗时 {elapsed_time:.2f} 秒")
+            if data_to_flush or stored:
+                logger.info(f"数据刷新完成: 新增 {len(data_to_flush)} 条, "
+                           f"各目标写入 {results}, "
+                           f"耗时 {elapsed_time:.2f} 秒")
             
-            # 记录错误
-            if result["errors"]:
-                for error in result["errors"]:
+            # 记录失败目标的错误
+            for status in destination_manager.get_status():
+                if status["consecutive_failures"] > 0:
                     self.stats["errors"].append({
                         "time": datetime.utcnow(),
-                        "error": str(error),
+                        "error": f"{status['name']}: {status['last_error']}",
                         "type": "flush"
                     })
+            self.stats["errors"] = self.stats["errors"][-10:]
                 
         except Exception as e:
             logger.error(f"数据刷新任务失败: {e}")
@@ -333,6 +357,18 @@ class SchedulerService:
                 "type": "cleanup"
             })
     
+    def _enforce_retention(self):
+        """执行分层降采样和过期数据删除"""
+        results = retention_manager.run()
+        for name, result in results.items():
+            if not result["success"]:
+                self.stats["errors"].append({
+                    "time": datetime.utcnow(),
+                    "error": f"{name}: {result['error']}",
+                    "type": "retention"
+                })
+        self.stats["errors"] = self.stats["errors"][-10:]
+    
     def _generate_statistics(self):
         """生成统计数据"""
         try:
@@ -362,6 +398,12 @@ class SchedulerService:
         return {
             "is_running": self.is_running,
             "stats": self.stats.copy(),
+            "destinations": destination_manager.get_status(),
+            "retention": {
+                "enabled": retention_manager.enabled,
+                "last_run_time": retention_manager.stats["last_run_time"],
+                "last_run_success": retention_manager.stats["last_run_success"],
+            },
             "next_runs": {
                 job.job_func.__name__: job.next_run 
                 for job in schedule.jobs
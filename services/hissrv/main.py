@@ -12,6 +12,11 @@ from loguru import logger
 project_root = os.path.dirname(os.path.abspath(__file__))
 sys.path.insert(0, project_root)
 
+# 本地运行时加载公共模块（镜像中位于 /app/voltage_common）
+shared_dir = os.path.join(os.path.dirname(project_root), "python-services")
+if os.path.isdir(shared_dir) and shared_dir not in sys.path:
+    sys.path.append(shared_dir)
+
 def main():
     """主函数"""
     try:
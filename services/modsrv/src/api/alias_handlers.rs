@@ -0,0 +1,240 @@
+//! Point Alias Handlers
+//!
+//! Registry of stable external identifiers (customer codes, KKS, RDS-PP) for
+//! channel and instance points, and point lookup by alias or address.
+
+#![allow(clippy::disallowed_methods)] // json! macro used in multiple functions
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use common::SuccessResponse;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::app_state::AppState;
+use crate::error::ModSrvError;
+use crate::point_aliases::{PointAddress, PointAlias};
+use voltage_rtdb::Rtdb;
+
+/// Query parameters for listing aliases
+#[derive(Debug, Deserialize)]
+pub struct AliasListQuery {
+    /// Only aliases of this scheme
+    pub scheme: Option<String>,
+    /// Only aliases whose address starts with these segments, e.g. `inst:5` or `comsrv:1001:T`
+    pub target: Option<String>,
+}
+
+/// Query parameters for point lookup
+#[derive(Debug, Deserialize)]
+pub struct PointLookupQuery {
+    /// How to read the path: `alias` (default) or `address`
+    pub by: Option<String>,
+}
+
+/// List point aliases
+///
+/// @route GET /api/aliases?scheme={optional}&target={optional}
+/// @input State(state): `Arc<AppState>` - Application state
+/// @input Query(query): AliasListQuery - Optional scheme and address prefix filters
+/// @output `Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError>` - Aliases
+/// @status 200 - Success with total and aliases
+#[utoipa::path(
+    get,
+    path = "/api/aliases",
+    params(
+        ("scheme" = Option<String>, Query, description = "Alias scheme filter (custom, kks, rds-pp, ...)"),
+        ("target" = Option<String>, Query, description = "Address prefix filter, e.g. inst:5 or comsrv:1001:T")
+    ),
+    responses(
+        (status = 200, description = "Point aliases", body = serde_json::Value,
+            example = json!({
+                "total": 1,
+                "aliases": [
+                    {
+                        "alias": "=G1 MKA10 CE101",
+                        "scheme": "kks",
+                        "address": "inst:5:M:1",
+                        "description": "PCS active power",
+                        "updated_at": "2025-09-02 09:30:00"
+                    }
+                ]
+            })
+        )
+    ),
+    tag = "modsrv"
+)]
+pub async fn list_aliases(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AliasListQuery>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError> {
+    let aliases = state
+        .instance_manager
+        .list_point_aliases(query.scheme.as_deref(), query.target.as_deref())
+        .await?;
+
+    Ok(Json(SuccessResponse::new(json!({
+        "total": aliases.len(),
+        "aliases": aliases,
+    }))))
+}
+
+/// Create or re-point aliases
+///
+/// Upserts all aliases in one transaction. Re-pointing an existing alias to
+/// a new address is how a renumbered point is followed without breaking the
+/// integrations that use the alias.
+///
+/// @route POST /api/aliases
+/// @input State(state): `Arc<AppState>` - Application state
+/// @input Json(aliases): `Vec<PointAlias>` - Aliases to create or update
+/// @output `Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError>` - Upserted count
+/// @status 200 - Aliases saved
+/// @status 400 - Invalid alias, scheme or address, or unknown instance/channel
+/// @status 409 - Address already has another alias in the same scheme
+#[utoipa::path(
+    post,
+    path = "/api/aliases",
+    request_body = Vec<PointAlias>,
+    responses(
+        (status = 200, description = "Aliases saved", body = serde_json::Value,
+            example = json!({"upserted": 2})
+        ),
+        (status = 400, description = "Invalid alias, scheme or address"),
+        (status = 409, description = "Address already has another alias in the scheme")
+    ),
+    tag = "modsrv"
+)]
+pub async fn upsert_aliases(
+    State(state): State<Arc<AppState>>,
+    Json(aliases): Json<Vec<PointAlias>>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError> {
+    let upserted = state
+        .instance_manager
+        .upsert_point_aliases(&aliases)
+        .await?;
+
+    Ok(Json(SuccessResponse::new(json!({ "upserted": upserted }))))
+}
+
+/// Get a single alias
+///
+/// @route GET /api/aliases/{alias}
+/// @input Path(alias): String - Alias
+/// @output `Result<Json<SuccessResponse<PointAlias>>, ModSrvError>` - Alias
+/// @status 200 - Success
+/// @status 404 - Alias not found
+#[utoipa::path(
+    get,
+    path = "/api/aliases/{alias}",
+    params(("alias" = String, Path, description = "Point alias")),
+    responses(
+        (status = 200, description = "Point alias", body = PointAlias),
+        (status = 404, description = "Alias not found")
+    ),
+    tag = "modsrv"
+)]
+pub async fn get_alias(
+    State(state): State<Arc<AppState>>,
+    Path(alias): Path<String>,
+) -> Result<Json<SuccessResponse<PointAlias>>, ModSrvError> {
+    let alias = state.instance_manager.get_point_alias(&alias).await?;
+    Ok(Json(SuccessResponse::new(alias)))
+}
+
+/// Delete an alias
+///
+/// @route DELETE /api/aliases/{alias}
+/// @input Path(alias): String - Alias
+/// @output `Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError>` - Deleted alias
+/// @status 200 - Alias deleted
+/// @status 404 - Alias not found
+#[utoipa::path(
+    delete,
+    path = "/api/aliases/{alias}",
+    params(("alias" = String, Path, description = "Point alias")),
+    responses(
+        (status = 200, description = "Alias deleted", body = serde_json::Value,
+            example = json!({"deleted": "=G1 MKA10 CE101"})
+        ),
+        (status = 404, description = "Alias not found")
+    ),
+    tag = "modsrv"
+)]
+pub async fn delete_alias(
+    State(state): State<Arc<AppState>>,
+    Path(alias): Path<String>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError> {
+    state.instance_manager.delete_point_alias(&alias).await?;
+    Ok(Json(SuccessResponse::new(json!({ "deleted": alias }))))
+}
+
+/// Look up a point by alias or internal address
+///
+/// Returns the internal address, all aliases of the point and its current
+/// value, so integrations can read points without knowing their numbering.
+///
+/// @route GET /api/points/{point}?by={alias|address}
+/// @input Path(point): String - Alias (default) or address
+/// @input Query(query): PointLookupQuery - Lookup mode
+/// @output `Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError>` - Resolved point
+/// @status 200 - Success
+/// @status 400 - Invalid address or lookup mode
+/// @status 404 - Alias not found
+#[utoipa::path(
+    get,
+    path = "/api/points/{point}",
+    params(
+        ("point" = String, Path, description = "Point alias, or address with by=address"),
+        ("by" = Option<String>, Query, description = "alias (default) or address")
+    ),
+    responses(
+        (status = 200, description = "Resolved point", body = serde_json::Value,
+            example = json!({
+                "address": "inst:5:M:1",
+                "aliases": [
+                    {"alias": "=G1 MKA10 CE101", "scheme": "kks", "address": "inst:5:M:1"}
+                ],
+                "value": "650.5"
+            })
+        ),
+        (status = 400, description = "Invalid address or lookup mode"),
+        (status = 404, description = "Alias not found")
+    ),
+    tag = "modsrv"
+)]
+pub async fn get_point(
+    State(state): State<Arc<AppState>>,
+    Path(point): Path<String>,
+    Query(query): Query<PointLookupQuery>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError> {
+    let manager = &state.instance_manager;
+    let address: PointAddress = match query.by.as_deref().unwrap_or("alias") {
+        "alias" => manager.resolve_point_alias(&point).await?,
+        "address" => point.parse()?,
+        other => {
+            return Err(ModSrvError::InvalidData(format!(
+                "Unknown lookup mode '{}': use alias or address",
+                other
+            )))
+        },
+    };
+
+    let address_str = address.to_string();
+    let aliases = manager.list_point_aliases(None, Some(&address_str)).await?;
+    let value = manager
+        .rtdb
+        .hash_get(&address.hash_key(), &address.point_id().to_string())
+        .await
+        .map_err(|e| ModSrvError::RedisError(e.to_string()))?
+        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned());
+
+    Ok(Json(SuccessResponse::new(json!({
+        "address": address_str,
+        "aliases": aliases,
+        "value": value,
+    }))))
+}
@@ -8,6 +8,7 @@ use common::FourRemote;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 use utoipa::ToSchema;
+use validator::Validate;
 
 // Import Core types for zero-duplication architecture
 use crate::config::{Instance, InstanceCore};
@@ -42,6 +43,12 @@ where
 pub struct DataTypeQuery {
     #[serde(rename = "type")]
     pub data_type: Option<String>, // 'measurement', 'action', or null for both
+    /// Data version from a previous response; only points changed after it are returned
+    pub since: Option<u64>,
+    /// `alias` keys points by their alias instead of the point ID
+    pub by: Option<String>,
+    /// Alias scheme used with `by=alias` (default: first scheme with an alias)
+    pub scheme: Option<String>,
 }
 
 // === Parameter Management ===
@@ -67,7 +74,7 @@ pub enum RoutingType {
 ///
 /// Used in RoutingRequest to explicitly specify whether the point_id
 /// refers to a measurement point or an action point.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
 pub enum PointType {
     /// Measurement point routing
     #[serde(rename = "M")]
@@ -106,7 +113,9 @@ pub struct RoutingRequest {
 
 /// Request to create or update routing for a single point
 ///
-/// `channel_id`, `four_remote`, and `channel_point_id` can all be null to unbind the routing
+/// `channel_id`, `four_remote`, and `channel_point_id` can all be null to unbind the routing.
+/// `deadband`, `deadband_pct` and `refresh_secs` switch a measurement routing to
+/// report by exception (ignored for action routing).
 #[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct SinglePointRoutingRequest {
     #[schema(example = 1)]
@@ -118,6 +127,18 @@ pub struct SinglePointRoutingRequest {
     #[serde(default = "default_enabled")]
     #[schema(example = true)]
     pub enabled: bool,
+    /// Absolute deadband
+    #[serde(default)]
+    #[schema(example = 0.5)]
+    pub deadband: Option<f64>,
+    /// Deadband in percent of the last published value
+    #[serde(default)]
+    #[schema(example = 1.0)]
+    pub deadband_pct: Option<f64>,
+    /// Forced refresh interval in seconds (default 60, 0 = never)
+    #[serde(default)]
+    #[schema(example = 60)]
+    pub refresh_secs: Option<u32>,
 }
 
 /// Request to toggle routing enabled state for a single point
@@ -132,6 +153,39 @@ fn default_enabled() -> bool {
     true
 }
 
+/// Single cell edit from the routing matrix editor
+///
+/// Binds (or unbinds, when all channel fields are null/empty) one instance point
+/// to a channel point. Used by `PUT /api/routing/matrix`.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct RoutingMatrixEdit {
+    #[schema(example = 1)]
+    pub instance_id: u32,
+    /// Point type: "M" for measurement, "A" for action
+    #[schema(example = "M")]
+    pub point_type: PointType,
+    #[schema(example = 101)]
+    pub point_id: u32,
+    #[schema(example = 1)]
+    #[serde(default, deserialize_with = "deserialize_optional_i32")]
+    pub channel_id: Option<i32>,
+    #[schema(value_type = Option<String>, example = "T")]
+    #[serde(default, deserialize_with = "deserialize_optional_four_remote")]
+    pub four_remote: Option<FourRemote>,
+    #[schema(example = 101)]
+    #[serde(default, deserialize_with = "deserialize_optional_u32")]
+    pub channel_point_id: Option<u32>,
+    #[serde(default = "default_enabled")]
+    #[schema(example = true)]
+    pub enabled: bool,
+}
+
+/// Batch of routing matrix edits saved in one transaction
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct RoutingMatrixSaveRequest {
+    pub edits: Vec<RoutingMatrixEdit>,
+}
+
 // === Instance Management ===
 
 /// Request to create a new instance from a product template
@@ -145,11 +199,14 @@ pub struct CreateInstanceDto {
     pub product_name: String,
     #[schema(value_type = Object, example = json!({"rated_power": 5000.0, "manufacturer": "Huawei"}))]
     pub properties: Option<HashMap<String, serde_json::Value>>,
+    #[schema(value_type = Option<Object>, example = json!({"site": "S1", "feeder": "F1"}))]
+    #[serde(default)]
+    pub tags: Option<HashMap<String, String>>,
 }
 
 /// Request to update an existing instance
 ///
-/// Supports updating instance_name and/or properties.
+/// Supports updating instance_name, properties and/or tags.
 /// At least one field must be provided.
 #[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct UpdateInstanceDto {
@@ -160,6 +217,11 @@ pub struct UpdateInstanceDto {
     /// Updated properties (optional)
     #[schema(value_type = Option<Object>, example = json!({"rated_power": 5000.0, "manufacturer": "Huawei", "model": "SUN2000-5KTL-L1"}))]
     pub properties: Option<HashMap<String, serde_json::Value>>,
+
+    /// Replacement tag set (optional, replaces all existing tags)
+    #[schema(value_type = Option<Object>, example = json!({"site": "S1", "feeder": "F2"}))]
+    #[serde(default)]
+    pub tags: Option<HashMap<String, String>>,
 }
 
 /// Request to execute an action on an instance
@@ -174,6 +236,28 @@ pub struct ActionRequest {
     pub value: f64,
 }
 
+/// Longest override accepted by the API
+const MAX_OVERRIDE_SECS: u64 = voltage_routing::manual_override::MAX_OVERRIDE_DURATION.as_secs();
+
+/// Request to force an action point from the local HMI
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema, Validate)]
+pub struct OverrideRequest {
+    #[schema(example = 0.0)]
+    #[validate(custom(function = "common::extract::finite"))]
+    pub value: f64,
+    /// Operator holding the override
+    #[schema(example = "operator1")]
+    #[validate(custom(function = "common::extract::not_blank"))]
+    pub owner: String,
+    /// Override duration in seconds (max 86400)
+    #[schema(example = 1800)]
+    #[validate(range(min = 1, max = MAX_OVERRIDE_SECS))]
+    pub duration_secs: u64,
+    #[schema(example = "Inverter maintenance")]
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
 // === Calculation Requests ===
 
 /// Request to execute multiple calculations in batch
@@ -0,0 +1,261 @@
+//! Instance Point History API
+//!
+//! Shortcut to hissrv for instance point history: resolves the instance point
+//! to its storage key (`inst:{id}:M` / `inst:{id}:A`) and proxies the query to
+//! hissrv's `/history` API, so frontends only talk to modsrv for model data.
+
+#![allow(clippy::disallowed_methods)] // json! macro used in multiple functions
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use common::SuccessResponse;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use voltage_rtdb::KeySpaceConfig;
+
+use crate::app_state::AppState;
+use crate::error::ModSrvError;
+
+/// Default hissrv API base URL (overridable with `HISSRV_URL`)
+pub const DEFAULT_HISSRV_URL: &str = "http://localhost:6004/hisApi";
+
+/// Upper bound of a proxied history query
+const HISTORY_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Longest range hissrv accepts (`MAX_TIME_RANGE_DAYS`)
+const MAX_RANGE: Duration = Duration::from_secs(365 * 86400);
+
+/// HTTP client for the hissrv history API
+#[derive(Clone)]
+pub struct HistoryClient {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl HistoryClient {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(HISTORY_TIMEOUT)
+            .build()
+            .unwrap_or_else(|_| reqwest::Client::new());
+        Self {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Client for `HISSRV_URL`, falling back to [`DEFAULT_HISSRV_URL`]
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("HISSRV_URL").unwrap_or_else(|_| DEFAULT_HISSRV_URL.to_string()))
+    }
+
+    /// Query the history of one point over the last `range`
+    pub async fn query(
+        &self,
+        redis_key: &str,
+        point_id: u32,
+        range: &str,
+        interval: Option<u32>,
+    ) -> Result<serde_json::Value, ModSrvError> {
+        let point_id = point_id.to_string();
+        let mut params = vec![
+            ("redis_key", redis_key),
+            ("point_id", point_id.as_str()),
+            ("range", range),
+        ];
+        let interval = interval.map(|i| i.to_string());
+        if let Some(interval) = &interval {
+            params.push(("interval", interval.as_str()));
+        }
+
+        let response = self
+            .http
+            .get(format!("{}/history", self.base_url))
+            .query(&params)
+            .send()
+            .await
+            .map_err(|e| ModSrvError::InternalError(format!("hissrv unavailable: {}", e)))?;
+
+        let status = response.status();
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| ModSrvError::InternalError(format!("Invalid hissrv response: {}", e)))?;
+        if status.is_client_error() {
+            return Err(ModSrvError::InvalidData(format!(
+                "hissrv rejected query: {}",
+                body.get("detail").unwrap_or(&body)
+            )));
+        }
+        if !status.is_success() {
+            return Err(ModSrvError::InternalError(format!(
+                "hissrv query failed ({}): {}",
+                status,
+                body.get("detail").unwrap_or(&body)
+            )));
+        }
+        Ok(body)
+    }
+}
+
+/// Query parameters for point history
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    /// Relative range ending now: `15m`, `1h`, `24h`, `7d`
+    #[serde(default = "default_range")]
+    pub range: String,
+    /// `M` (measurement, default) or `A` (action)
+    #[serde(rename = "type")]
+    pub point_type: Option<String>,
+    /// Sampling interval in seconds (default: chosen by hissrv from the range)
+    pub interval: Option<u32>,
+}
+
+fn default_range() -> String {
+    "1h".to_string()
+}
+
+/// Parse a relative range (`15m`, `1h`, `7d`)
+fn parse_range(range: &str) -> Option<Duration> {
+    let (value, unit) = range.split_at(range.len().checked_sub(1)?);
+    let value: u64 = value.parse().ok().filter(|v| *v > 0)?;
+    let seconds = match unit {
+        "m" | "M" => value.checked_mul(60)?,
+        "h" | "H" => value.checked_mul(3600)?,
+        "d" | "D" => value.checked_mul(86400)?,
+        _ => return None,
+    };
+    Some(Duration::from_secs(seconds)).filter(|d| *d <= MAX_RANGE)
+}
+
+/// Get the history of an instance point from hissrv
+#[utoipa::path(
+    get,
+    path = "/api/instances/{id}/points/{point_id}/history",
+    params(
+        ("id" = u32, Path, description = "Instance ID"),
+        ("point_id" = u32, Path, description = "Measurement or action point ID"),
+        ("range" = Option<String>, Query, description = "Relative range ending now: 15m, 1h (default), 24h, 7d"),
+        ("type" = Option<String>, Query, description = "Point role: M (measurement, default) or A (action)"),
+        ("interval" = Option<u32>, Query, description = "Sampling interval in seconds")
+    ),
+    responses(
+        (status = 200, description = "Point history", body = serde_json::Value,
+            example = json!({
+                "success": true,
+                "data": {
+                    "instance_id": 1,
+                    "point_type": "M",
+                    "point_id": 1,
+                    "name": "DC Voltage",
+                    "unit": "V",
+                    "redis_key": "inst:1:M",
+                    "range": "1h",
+                    "history": {
+                        "status": "success",
+                        "data": [{"timestamp": "2025-11-26T10:00:00", "redis_key": "inst:1:M", "point_id": "1", "value": 652.1, "source": "modsrv"}],
+                        "total": 1
+                    }
+                }
+            })
+        ),
+        (status = 400, description = "Invalid range or point type"),
+        (status = 404, description = "Instance or point not found"),
+        (status = 500, description = "hissrv unavailable or query failed")
+    ),
+    tag = "modsrv"
+)]
+pub async fn get_point_history(
+    State(state): State<Arc<AppState>>,
+    Path((id, point_id)): Path<(u32, u32)>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError> {
+    if parse_range(&query.range).is_none() {
+        return Err(ModSrvError::InvalidData(format!(
+            "Invalid range '{}', expected e.g. 15m, 1h, 24h, 7d (max 365d)",
+            query.range
+        )));
+    }
+    let is_action = match query.point_type.as_deref() {
+        None | Some("M") | Some("m") => false,
+        Some("A") | Some("a") => true,
+        Some(other) => {
+            return Err(ModSrvError::InvalidData(format!(
+                "Invalid point type '{}', expected M or A",
+                other
+            )))
+        },
+    };
+
+    let instance = state
+        .instance_manager
+        .get_instance(id)
+        .await
+        .map_err(|_| ModSrvError::InstanceNotFound(id.to_string()))?;
+    let product = state
+        .product_loader
+        .get_product(instance.product_name())
+        .map_err(|e| ModSrvError::InternalError(e.to_string()))?;
+
+    let keyspace = KeySpaceConfig::production_cached();
+    let (name, unit, redis_key) = if is_action {
+        let point = product
+            .actions
+            .iter()
+            .find(|p| p.action_id == point_id)
+            .ok_or_else(|| {
+                ModSrvError::InstanceNotFound(format!("{} action point {}", id, point_id))
+            })?;
+        (&point.name, &point.unit, keyspace.instance_action_key(id))
+    } else {
+        let point = product
+            .measurements
+            .iter()
+            .find(|p| p.measurement_id == point_id)
+            .ok_or_else(|| {
+                ModSrvError::InstanceNotFound(format!("{} measurement point {}", id, point_id))
+            })?;
+        (
+            &point.name,
+            &point.unit,
+            keyspace.instance_measurement_key(id),
+        )
+    };
+
+    let history = state
+        .history_client
+        .query(&redis_key, point_id, &query.range, query.interval)
+        .await?;
+
+    Ok(Json(SuccessResponse::new(json!({
+        "instance_id": id,
+        "point_type": if is_action { "A" } else { "M" },
+        "point_id": point_id,
+        "name": name,
+        "unit": unit,
+        "redis_key": redis_key,
+        "range": query.range,
+        "history": history
+    }))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("15m"), Some(Duration::from_secs(900)));
+        assert_eq!(parse_range("1h"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_range("7d"), Some(Duration::from_secs(7 * 86400)));
+        assert_eq!(parse_range("0h"), None);
+        assert_eq!(parse_range("h"), None);
+        assert_eq!(parse_range(""), None);
+        assert_eq!(parse_range("1w"), None);
+        assert_eq!(parse_range("400d"), None);
+    }
+}
@@ -7,6 +7,7 @@
 use crate::config::CreateInstanceRequest;
 use axum::{
     extract::{Path, State},
+    http::{header, HeaderMap, HeaderName},
     response::Json,
 };
 use common::SuccessResponse;
@@ -18,6 +19,38 @@ use tracing::{error, info, warn};
 use crate::app_state::AppState;
 use crate::dto::{ActionRequest, CreateInstanceDto, UpdateInstanceDto};
 use crate::error::ModSrvError;
+use crate::instance_manager::VersionBump;
+
+/// `ETag` response header carrying the instance version
+pub type EtagHeader = [(HeaderName, String); 1];
+
+/// Build the `ETag` header for an instance version (strong validator, e.g. `"3"`)
+pub fn etag_header(version: u32) -> EtagHeader {
+    [(header::ETAG, format!("\"{}\"", version))]
+}
+
+/// Parse `If-Match` into an expected version
+///
+/// Accepts `"3"`, `W/"3"` or a bare `3`; a missing header or `*` means unconditional.
+fn parse_if_match(headers: &HeaderMap) -> Result<Option<u32>, ModSrvError> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(None);
+    };
+
+    let raw = value
+        .to_str()
+        .map_err(|_| ModSrvError::InvalidData("Invalid If-Match header".to_string()))?
+        .trim();
+    if raw == "*" {
+        return Ok(None);
+    }
+
+    raw.trim_start_matches("W/")
+        .trim_matches('"')
+        .parse::<u32>()
+        .map(Some)
+        .map_err(|_| ModSrvError::InvalidData(format!("Invalid If-Match version: {}", raw)))
+}
 
 /// Create a new model instance
 ///
@@ -74,6 +107,14 @@ pub async fn create_instance(
         }
     };
 
+    // Validate tags up front so an invalid tag does not leave a half-configured instance
+    if let Some(ref tags) = dto.tags {
+        for (tag_key, tag_value) in tags {
+            crate::instance_tags::validate_tag(tag_key, tag_value)
+                .map_err(|e| ModSrvError::InvalidData(e.to_string()))?;
+        }
+    }
+
     let req = CreateInstanceRequest {
         instance_id,
         instance_name: dto.instance_name,
@@ -82,10 +123,27 @@ pub async fn create_instance(
     };
 
     match state.instance_manager.create_instance(req).await {
-        Ok(instance) => Ok(Json(SuccessResponse::new(json!({
-            "instance": instance
-        })))),
+        Ok(mut instance) => {
+            if let Some(tags) = dto.tags.filter(|t| !t.is_empty()) {
+                state
+                    .instance_manager
+                    .set_instance_tags(instance_id, &tags)
+                    .await
+                    .map_err(|e| {
+                        ModSrvError::InternalError(format!("Failed to set instance tags: {}", e))
+                    })?;
+                instance.core.tags = tags;
+            }
+            Ok(Json(SuccessResponse::new(json!({
+                "instance": instance
+            }))))
+        },
         Err(e) => {
+            // Naming policy violations already carry the right error kind
+            let e = match e.downcast::<ModSrvError>() {
+                Ok(err) => return Err(err),
+                Err(e) => e,
+            };
             // Check for specific error types with improved messages
             let error_msg = e.to_string();
             if error_msg.contains("already exists") {
@@ -115,25 +173,32 @@ pub async fn create_instance(
     }
 }
 
-/// Update instance name and/or properties
+/// Update instance name, properties and/or tags
 ///
-/// Updates the instance_name and/or properties of an existing instance.
-/// At least one field (instance_name or properties) must be provided.
+/// Updates the instance_name, properties and/or tags of an existing instance.
+/// At least one field (instance_name, properties or tags) must be provided.
+/// Tags replace the existing tag set.
+///
+/// Optimistic concurrency: when `If-Match` carries the version from a previous
+/// `ETag`, the update is rejected with 412 if another edit happened in between.
 ///
 /// @route PUT /api/instances/{id}
 /// @input Path(id): u16 - Instance ID
+/// @input headers: HeaderMap - Optional If-Match version
 /// @input Json(dto): UpdateInstanceDto - Fields to update
-/// @output Result<Json<SuccessResponse<serde_json::Value>>, AppError> - Updated instance
+/// @output Result<(ETag, Json<SuccessResponse<serde_json::Value>>), AppError> - Updated instance
 /// @status 200 - Success with updated instance details
 /// @status 400 - No fields to update or invalid request
 /// @status 404 - Instance not found
 /// @status 409 - Instance name already exists (conflict)
+/// @status 412 - If-Match version is stale
 /// @status 500 - Database or Redis error
 #[utoipa::path(
     put,
     path = "/api/instances/{id}",
     params(
-        ("id" = u16, Path, description = "Instance ID")
+        ("id" = u16, Path, description = "Instance ID"),
+        ("If-Match" = Option<String>, Header, description = "Expected instance version from ETag (e.g., \"3\")")
     ),
     request_body = UpdateInstanceDto,
     responses(
@@ -148,6 +213,7 @@ pub async fn create_instance(
                         "manufacturer": "Huawei",
                         "model": "SUN2000-5KTL-L1"
                     },
+                    "version": 4,
                     "created_at": "2025-10-15T10:30:00Z",
                     "updated_at": "2025-10-20T14:25:00Z"
                 }
@@ -156,6 +222,7 @@ pub async fn create_instance(
         (status = 400, description = "No fields to update"),
         (status = 404, description = "Instance not found"),
         (status = 409, description = "Instance name already exists"),
+        (status = 412, description = "Instance was modified since the If-Match version"),
         (status = 500, description = "Database or Redis error")
     ),
     tag = "modsrv"
@@ -163,15 +230,23 @@ pub async fn create_instance(
 pub async fn update_instance(
     State(state): State<Arc<AppState>>,
     Path(id): Path<u32>,
+    headers: HeaderMap,
     Json(dto): Json<UpdateInstanceDto>,
-) -> Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError> {
+) -> Result<(EtagHeader, Json<SuccessResponse<serde_json::Value>>), ModSrvError> {
     // Validate: at least one field must be provided
-    if dto.instance_name.is_none() && dto.properties.is_none() {
+    if dto.instance_name.is_none() && dto.properties.is_none() && dto.tags.is_none() {
         return Err(ModSrvError::InvalidData(
-            "At least one field (instance_name or properties) must be provided".to_string(),
+            "At least one field (instance_name, properties or tags) must be provided".to_string(),
         ));
     }
 
+    if let Some(ref tags) = dto.tags {
+        for (tag_key, tag_value) in tags {
+            crate::instance_tags::validate_tag(tag_key, tag_value)
+                .map_err(|e| ModSrvError::InvalidData(e.to_string()))?;
+        }
+    }
+
     // Query current instance_name for logging and Redis operations
     let old_instance_name: String =
         match sqlx::query_scalar("SELECT instance_name FROM instances WHERE instance_id = ?")
@@ -183,6 +258,42 @@ pub async fn update_instance(
             Err(_) => return Err(ModSrvError::InstanceNotFound(id.to_string())),
         };
 
+    // Check the new name before claiming a version so a rejected rename has no side effects
+    if let Some(name) = dto
+        .instance_name
+        .as_deref()
+        .filter(|name| *name != old_instance_name)
+    {
+        state
+            .instance_manager
+            .check_instance_name(name, Some(id))
+            .await?;
+    }
+
+    // Claim the next version before writing; a stale If-Match fails here without side effects
+    let expected_version = parse_if_match(&headers)?;
+    let new_version = match state
+        .instance_manager
+        .bump_instance_version(id, expected_version)
+        .await
+    {
+        Ok(VersionBump::Bumped(version)) => version,
+        Ok(VersionBump::Mismatch { current }) => {
+            return Err(ModSrvError::VersionMismatch(format!(
+                "Instance {} is at version {}, If-Match expected {}",
+                id,
+                current,
+                expected_version.unwrap_or_default()
+            )));
+        },
+        Err(e) => {
+            return Err(ModSrvError::InternalError(format!(
+                "Failed to update instance version: {}",
+                e
+            )));
+        },
+    };
+
     // Determine the final instance name
     let new_instance_name = dto.instance_name.as_deref().unwrap_or(&old_instance_name);
     let is_renaming = dto.instance_name.is_some() && new_instance_name != old_instance_name;
@@ -195,6 +306,10 @@ pub async fn update_instance(
             .rename_instance(id, new_instance_name)
             .await
         {
+            let e = match e.downcast::<ModSrvError>() {
+                Ok(err) => return Err(err),
+                Err(e) => e,
+            };
             let error_msg = e.to_string();
             if error_msg.contains("already exists") {
                 return Err(ModSrvError::InstanceExists(format!(
@@ -266,30 +381,85 @@ pub async fn update_instance(
         }
     }
 
+    // Handle tags update (replaces the whole tag set)
+    if let Some(ref tags) = dto.tags {
+        if let Err(e) = state.instance_manager.set_instance_tags(id, tags).await {
+            error!("Failed to update tags for instance {}: {}", id, e);
+            return Err(ModSrvError::InternalError(format!(
+                "Database update failed: {}",
+                e
+            )));
+        }
+    }
+
     info!(
-        "Instance {} updated successfully (renamed: {}, properties: {})",
+        "Instance {} updated successfully (renamed: {}, properties: {}, tags: {})",
         id,
         is_renaming,
-        dto.properties.is_some()
+        dto.properties.is_some(),
+        dto.tags.is_some()
     );
 
     // Query and return updated instance
     match state.instance_manager.get_instance(id).await {
-        Ok(instance) => Ok(Json(SuccessResponse::new(json!({
-            "instance": instance
-        })))),
+        Ok(instance) => Ok((
+            etag_header(instance.version.unwrap_or(new_version)),
+            Json(SuccessResponse::new(json!({
+                "instance": instance
+            }))),
+        )),
         Err(e) => {
             error!("Failed to query updated instance {}: {}", id, e);
             // Update succeeded but query failed - return id as fallback
-            Ok(Json(SuccessResponse::new(json!({
-                "instance_id": id,
-                "instance_name": new_instance_name,
-                "message": "Instance updated successfully but failed to retrieve details"
-            }))))
+            Ok((
+                etag_header(new_version),
+                Json(SuccessResponse::new(json!({
+                    "instance_id": id,
+                    "instance_name": new_instance_name,
+                    "version": new_version,
+                    "message": "Instance updated successfully but failed to retrieve details"
+                }))),
+            ))
         },
     }
 }
 
+/// Get the instance naming policy
+///
+/// Returns the naming rules from the `naming` section of global config so
+/// clients (config-ui) can validate names before submitting them.
+///
+/// @route GET /api/naming-policy
+/// @input State(state): `Arc<AppState>` - Application state
+/// @output `Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError>` - Naming policy
+/// @status 200 - Success with policy per entity kind
+/// @status 500 - Invalid naming configuration
+#[utoipa::path(
+    get,
+    path = "/api/naming-policy",
+    responses(
+        (status = 200, description = "Naming policy", body = serde_json::Value,
+            example = json!({
+                "instance": {
+                    "pattern": "^[A-Za-z_][A-Za-z0-9_-]*$",
+                    "min_length": 1,
+                    "max_length": 64,
+                    "reserved": [],
+                    "case_sensitive": true,
+                    "unique": "kind"
+                }
+            })
+        )
+    ),
+    tag = "modsrv"
+)]
+pub async fn get_naming_policy(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError> {
+    let policy = state.instance_manager.naming_policy().await?;
+    Ok(Json(SuccessResponse::new(serde_json::to_value(policy)?)))
+}
+
 /// Delete an instance
 ///
 /// Removes an instance from both SQLite and Redis.
@@ -439,6 +609,10 @@ pub async fn reload_instances_from_db(
                 result.removed.len(),
                 result.errors.len()
             );
+            state
+                .search
+                .rebuild(&state.instance_manager.pool, &state.product_loader)
+                .await;
             Ok(Json(SuccessResponse::new(json!({
                 "message": "Instances reloaded successfully",
                 "result": result
@@ -468,6 +642,7 @@ pub async fn reload_instances_from_db(
 /// @output Result<Json<SuccessResponse<serde_json::Value>>, AppError> - Execution result
 /// @status 200 - Success confirmation
 /// @status 404 - Instance or action not found
+/// @status 409 - Action point under local override or blocked by an interlock
 /// @status 500 - Database error
 /// @side-effects Writes to Redis action keys and may trigger downstream routing
 #[utoipa::path(
@@ -482,7 +657,8 @@ pub async fn reload_instances_from_db(
             example = json!({
                 "message": "Action executed"
             })
-        )
+        ),
+        (status = 409, description = "Action point under local override or blocked by an interlock")
     ),
     tag = "modsrv"
 )]
@@ -503,6 +679,12 @@ pub async fn execute_instance_action(
             "value": req.value
         })))),
         Err(e) => {
+            if let Some(active) = e.downcast_ref::<voltage_routing::OverrideActive>() {
+                return Err(ModSrvError::PointOverridden(active.to_string()));
+            }
+            if let Some(violation) = e.downcast_ref::<voltage_routing::ConstraintViolation>() {
+                return Err(ModSrvError::ConstraintViolation(violation.to_string()));
+            }
             let error_msg = e.to_string();
             if error_msg.contains("not found") {
                 Err(ModSrvError::InternalError(format!(
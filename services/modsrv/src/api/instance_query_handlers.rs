@@ -6,7 +6,8 @@
 
 use axum::{
     extract::{Path, Query, RawQuery, State},
-    response::Json,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
 };
 use bytes::Bytes;
 use common::SuccessResponse;
@@ -18,15 +19,19 @@ use tracing::info;
 use utoipa::ToSchema;
 use voltage_rtdb::Rtdb;
 
+use crate::api::instance_management_handlers::{etag_header, EtagHeader};
 use crate::app_state::AppState;
 use crate::dto::{DataTypeQuery, InstancePointsResponse};
 use crate::error::ModSrvError;
+use crate::point_aliases::alias_instance_data;
 
 /// Pagination query parameters for listing instances
 #[derive(Debug, Deserialize)]
 pub struct PaginationQuery {
     /// Optional product filter
     pub product_name: Option<String>,
+    /// Optional tag filter, comma-separated `key:value` pairs (all must match)
+    pub tag: Option<String>,
     #[serde(default = "default_page")]
     pub page: u32,
     #[serde(default = "default_page_size")]
@@ -41,9 +46,9 @@ fn default_page_size() -> u32 {
     20
 }
 
-/// List instances with optional product filter and pagination
+/// List instances with optional product/tag filters and pagination
 ///
-/// @route GET /api/instances?product_name={optional}&page={optional}&page_size={optional}
+/// @route GET /api/instances?product_name={optional}&tag={optional}&page={optional}&page_size={optional}
 /// @input State(state): `Arc<AppState>` - Application state
 /// @input Query(query): PaginationQuery - Pagination and filter parameters
 /// @output Result<Json<SuccessResponse<serde_json::Value>>, AppError> - Paginated instances
@@ -54,6 +59,7 @@ fn default_page_size() -> u32 {
     path = "/api/instances",
     params(
         ("product_name" = Option<String>, Query, description = "Optional product filter"),
+        ("tag" = Option<String>, Query, description = "Optional tag filter, comma-separated key:value pairs (e.g., tag=feeder:F1,site:S1)"),
         ("page" = Option<u32>, Query, description = "Page number (default: 1)"),
         ("page_size" = Option<u32>, Query, description = "Items per page (default: 20, max: 100)")
     ),
@@ -71,6 +77,10 @@ fn default_page_size() -> u32 {
                         "properties": {
                             "rated_power": 5000.0,
                             "manufacturer": "Huawei"
+                        },
+                        "tags": {
+                            "site": "S1",
+                            "feeder": "F1"
                         }
                     },
                     {
@@ -95,10 +105,15 @@ pub async fn list_instances(
     let product_name = query.product_name.as_deref();
     let page = query.page.max(1); // Ensure page is at least 1
     let page_size = query.page_size.clamp(1, 100); // Limit to reasonable range
+    let tags = match query.tag.as_deref() {
+        Some(filter) => crate::instance_tags::parse_tag_filter(filter)
+            .map_err(|e| ModSrvError::InvalidData(e.to_string()))?,
+        None => Vec::new(),
+    };
 
     let result = state
         .instance_manager
-        .list_instances_paginated(product_name, page, page_size)
+        .list_instances_paginated(product_name, &tags, page, page_size)
         .await;
 
     match result {
@@ -326,9 +341,12 @@ pub async fn list_instances_slim(
 
 /// Get a specific instance by ID
 ///
+/// The response carries an `ETag` header with the instance version; send it back
+/// as `If-Match` on `PUT /api/instances/{id}` to detect concurrent edits.
+///
 /// @route GET /api/instances/{id}
 /// @input Path(id): u16 - Instance ID
-/// @output Result<Json<SuccessResponse<serde_json::Value>>, AppError> - Instance details
+/// @output Result<(ETag, Json<SuccessResponse<serde_json::Value>>), AppError> - Instance details
 /// @status 200 - Success with instance data
 /// @status 404 - Instance not found
 #[utoipa::path(
@@ -350,6 +368,7 @@ pub async fn list_instances_slim(
                         "model": "SUN2000-5KTL-L1",
                         "grid_type": "three_phase"
                     },
+                    "version": 3,
                     "created_at": "2025-10-15T10:30:00Z",
                     "updated_at": "2025-10-15T14:25:00Z"
                 }
@@ -361,11 +380,14 @@ pub async fn list_instances_slim(
 pub async fn get_instance(
     State(state): State<Arc<AppState>>,
     Path(id): Path<u32>,
-) -> Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError> {
+) -> Result<(EtagHeader, Json<SuccessResponse<serde_json::Value>>), ModSrvError> {
     match state.instance_manager.get_instance(id).await {
-        Ok(instance) => Ok(Json(SuccessResponse::new(json!({
-            "instance": instance
-        })))),
+        Ok(instance) => Ok((
+            etag_header(instance.version.unwrap_or(1)),
+            Json(SuccessResponse::new(json!({
+                "instance": instance
+            }))),
+        )),
         Err(e) => {
             if e.to_string().contains("not found") {
                 Err(ModSrvError::InstanceNotFound(id.to_string()))
@@ -379,15 +401,39 @@ pub async fn get_instance(
     }
 }
 
+/// Whether `If-None-Match` lists the given ETag (or `*`)
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .split(',')
+                .map(|tag| tag.trim().trim_start_matches("W/"))
+                .any(|tag| tag == "*" || tag == etag)
+        })
+}
+
 /// Get real-time data for an instance
 ///
-/// Returns current measurement, action, and property values from Redis.
+/// Returns current measurement, action, and property values from Redis,
+/// plus the active local overrides of action points.
+///
+/// The `ETag` carries a data version that only moves when a returned point
+/// changes. `If-None-Match` with the current ETag yields 304 without a body;
+/// `?since={version}` returns only the points changed after that version
+/// (`delta: true`), falling back to the full data if the version is unknown.
+/// `?by=alias` keys points by their alias (of `scheme`, if given) instead of
+/// the point ID; points without an alias keep their ID.
 ///
-/// @route GET /api/instances/{id}/data?data_type={optional}
+/// @route GET /api/instances/{id}/data?type={optional}&since={optional}&by={optional}&scheme={optional}
 /// @input Path(id): u16 - Instance ID
-/// @input Query(query): DataTypeQuery - Optional data type filter (M/A/P)
-/// @output Result<Json<SuccessResponse<serde_json::Value>>, AppError> - Instance data points
+/// @input Query(query): DataTypeQuery - Optional data type filter, delta base version and key mode
+/// @input headers: HeaderMap - Optional If-None-Match
+/// @output Result<Response, AppError> - Instance data points (or 304)
 /// @status 200 - Success with data points
+/// @status 304 - Unchanged since the If-None-Match version
+/// @status 400 - Unknown key mode
 /// @status 404 - Instance not found
 /// @status 500 - Database error
 #[utoipa::path(
@@ -395,7 +441,11 @@ pub async fn get_instance(
     path = "/api/instances/{id}/data",
     params(
         ("id" = u16, Path, description = "Instance ID"),
-        ("type" = Option<String>, Query, description = "Optional data type filter (measurement/action)")
+        ("type" = Option<String>, Query, description = "Optional data type filter (measurement/action)"),
+        ("since" = Option<u64>, Query, description = "Data version (ETag) of a previous response; return only points changed after it"),
+        ("by" = Option<String>, Query, description = "Point keys: id (default) or alias"),
+        ("scheme" = Option<String>, Query, description = "Alias scheme used with by=alias"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag of a previous response; 304 if unchanged")
     ),
     responses(
         (status = 200, description = "Instance data", body = serde_json::Value,
@@ -408,12 +458,22 @@ pub async fn get_instance(
                 "actions": {
                     "201": "4500.0"
                 },
+                "overrides": {
+                    "201": {
+                        "point_id": "201",
+                        "value": 4500.0,
+                        "owner": "operator1",
+                        "set_at": 1735689600000_i64,
+                        "expires_at": 1735691400000_i64
+                    }
+                },
                 "properties": {
                     "rated_power": 5000.0,
                     "manufacturer": "Huawei"
                 }
             })
-        )
+        ),
+        (status = 304, description = "Data unchanged since the If-None-Match ETag")
     ),
     tag = "modsrv"
 )]
@@ -421,13 +481,36 @@ pub async fn get_instance_data(
     State(state): State<Arc<AppState>>,
     Path(id): Path<u32>,
     Query(query): Query<DataTypeQuery>,
-) -> Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError> {
+    headers: HeaderMap,
+) -> Result<Response, ModSrvError> {
     match state
         .instance_manager
-        .get_instance_data(id, query.data_type.as_deref())
+        .get_instance_data_versioned(id, query.data_type.as_deref(), query.since)
         .await
     {
-        Ok(data) => Ok(Json(SuccessResponse::new(data))),
+        Ok(versioned) => {
+            let etag = format!("\"{}\"", versioned.version);
+            if if_none_match(&headers, &etag) {
+                return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+            }
+            let data = match query.by.as_deref() {
+                None | Some("id") => versioned.data,
+                Some("alias") => {
+                    let aliases = state
+                        .instance_manager
+                        .point_alias_map(&format!("inst:{}", id), query.scheme.as_deref())
+                        .await?;
+                    alias_instance_data(versioned.data, id, query.data_type.as_deref(), &aliases)
+                },
+                Some(other) => {
+                    return Err(ModSrvError::InvalidData(format!(
+                        "Unknown key mode '{}': use id or alias",
+                        other
+                    )))
+                },
+            };
+            Ok(([(header::ETAG, etag)], Json(SuccessResponse::new(data))).into_response())
+        },
         Err(e) => {
             let error_msg = e.to_string();
             if error_msg.contains("not found") {
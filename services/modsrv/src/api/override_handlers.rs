@@ -0,0 +1,278 @@
+//! Local Override Handlers
+//!
+//! Lets an operator force an action point from the local HMI. While the
+//! override is active, rule and dispatch writes to the point are refused
+//! (see `voltage_routing::manual_override`); it reverts to automatic control
+//! when released or expired. All changes are audited.
+
+#![allow(clippy::disallowed_methods)] // json! macro used in multiple functions
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use common::{SuccessResponse, ValidatedJson};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::error;
+use voltage_routing::manual_override;
+use voltage_routing::{ActionOverride, ConstraintViolation, OverrideActive};
+
+use crate::app_state::AppState;
+use crate::dto::OverrideRequest;
+use crate::error::ModSrvError;
+
+/// Query parameters for releasing an override
+#[derive(Debug, Deserialize)]
+pub struct ReleaseQuery {
+    /// Operator releasing the override (recorded in the audit log)
+    pub actor: Option<String>,
+}
+
+/// Query parameters for the override audit log
+#[derive(Debug, Deserialize)]
+pub struct OverrideLogQuery {
+    /// Maximum entries, newest first (default 100)
+    pub limit: Option<usize>,
+}
+
+/// Ensure the instance exists and defines the action point
+async fn check_action_point(state: &AppState, id: u32, point_id: &str) -> Result<(), ModSrvError> {
+    let instance = state
+        .instance_manager
+        .get_instance(id)
+        .await
+        .map_err(|_| ModSrvError::InstanceNotFound(id.to_string()))?;
+    let product = state
+        .product_loader
+        .get_product(instance.product_name())
+        .map_err(|e| ModSrvError::InternalError(e.to_string()))?;
+
+    let defined = point_id
+        .parse::<u32>()
+        .is_ok_and(|pid| product.actions.iter().any(|a| a.action_id == pid));
+    if !defined {
+        return Err(ModSrvError::InvalidData(format!(
+            "Instance {} has no action point {}",
+            id, point_id
+        )));
+    }
+    Ok(())
+}
+
+/// List active local overrides of an instance
+///
+/// @route GET /api/instances/{id}/overrides
+#[utoipa::path(
+    get,
+    path = "/api/instances/{id}/overrides",
+    params(
+        ("id" = u32, Path, description = "Instance ID")
+    ),
+    responses(
+        (status = 200, description = "Active overrides", body = serde_json::Value,
+            example = json!({
+                "success": true,
+                "data": [{
+                    "point_id": "1",
+                    "value": 0.0,
+                    "owner": "operator1",
+                    "reason": "Inverter maintenance",
+                    "set_at": 1735689600000_i64,
+                    "expires_at": 1735691400000_i64
+                }]
+            })
+        )
+    ),
+    tag = "modsrv"
+)]
+pub async fn list_overrides(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u32>,
+) -> Result<Json<SuccessResponse<Vec<ActionOverride>>>, ModSrvError> {
+    let overrides = manual_override::list_overrides(state.instance_manager.rtdb.as_ref(), id)
+        .await
+        .map_err(|e| ModSrvError::RedisError(format!("Failed to read overrides: {}", e)))?;
+    Ok(Json(SuccessResponse::new(overrides)))
+}
+
+/// Force an action point from the local HMI
+///
+/// Writes the value through the normal M2C route and blocks rule/dispatch
+/// writes to the point until the override is released or expires.
+///
+/// @route PUT /api/instances/{id}/overrides/{point_id}
+#[utoipa::path(
+    put,
+    path = "/api/instances/{id}/overrides/{point_id}",
+    params(
+        ("id" = u32, Path, description = "Instance ID"),
+        ("point_id" = String, Path, description = "Action point ID")
+    ),
+    request_body = crate::dto::OverrideRequest,
+    responses(
+        (status = 200, description = "Override set", body = serde_json::Value,
+            example = json!({
+                "success": true,
+                "data": {
+                    "override": {
+                        "point_id": "1",
+                        "value": 0.0,
+                        "owner": "operator1",
+                        "set_at": 1735689600000_i64,
+                        "expires_at": 1735691400000_i64
+                    },
+                    "routed": true
+                }
+            })
+        ),
+        (status = 400, description = "Invalid value, duration or action point"),
+        (status = 404, description = "Instance not found"),
+        (status = 409, description = "Point overridden by another operator or blocked by an interlock")
+    ),
+    tag = "modsrv"
+)]
+pub async fn set_override(
+    State(state): State<Arc<AppState>>,
+    Path((id, point_id)): Path<(u32, String)>,
+    ValidatedJson(req): ValidatedJson<OverrideRequest>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError> {
+    check_action_point(&state, id, &point_id).await?;
+
+    let mut record = ActionOverride::new(
+        point_id,
+        req.value,
+        req.owner.trim(),
+        Duration::from_secs(req.duration_secs),
+    );
+    if let Some(reason) = req.reason.filter(|r| !r.trim().is_empty()) {
+        record = record.with_reason(reason);
+    }
+
+    let outcome = manual_override::set_override(
+        state.instance_manager.rtdb.as_ref(),
+        state.instance_manager.routing_cache(),
+        id,
+        record.clone(),
+    )
+    .await
+    .map_err(|e| {
+        if let Some(active) = e.downcast_ref::<OverrideActive>() {
+            return ModSrvError::PointOverridden(active.to_string());
+        }
+        if let Some(violation) = e.downcast_ref::<ConstraintViolation>() {
+            return ModSrvError::ConstraintViolation(violation.to_string());
+        }
+        error!("Instance {} override {}: {}", id, record.point_id, e);
+        ModSrvError::InternalError(format!("Failed to set override: {}", e))
+    })?;
+
+    Ok(Json(SuccessResponse::new(json!({
+        "override": record,
+        "routed": outcome.routed
+    }))))
+}
+
+/// Release a local override; the point reverts to automatic control
+///
+/// @route DELETE /api/instances/{id}/overrides/{point_id}?actor={optional}
+#[utoipa::path(
+    delete,
+    path = "/api/instances/{id}/overrides/{point_id}",
+    params(
+        ("id" = u32, Path, description = "Instance ID"),
+        ("point_id" = String, Path, description = "Action point ID"),
+        ("actor" = Option<String>, Query, description = "Operator releasing the override (default: owner)")
+    ),
+    responses(
+        (status = 200, description = "Override released", body = serde_json::Value,
+            example = json!({
+                "success": true,
+                "data": {
+                    "point_id": "1",
+                    "value": 0.0,
+                    "owner": "operator1",
+                    "set_at": 1735689600000_i64,
+                    "expires_at": 1735691400000_i64
+                }
+            })
+        ),
+        (status = 404, description = "No active override")
+    ),
+    tag = "modsrv"
+)]
+pub async fn release_override(
+    State(state): State<Arc<AppState>>,
+    Path((id, point_id)): Path<(u32, String)>,
+    Query(query): Query<ReleaseQuery>,
+) -> Result<Json<SuccessResponse<ActionOverride>>, ModSrvError> {
+    let rtdb = state.instance_manager.rtdb.as_ref();
+    let current = manual_override::get_override(rtdb, id, &point_id)
+        .await
+        .map_err(|e| ModSrvError::RedisError(format!("Failed to read override: {}", e)))?;
+    let Some(current) = current else {
+        return Err(ModSrvError::InstanceNotFound(format!(
+            "{} has no active override on action point {}",
+            id, point_id
+        )));
+    };
+
+    let actor = query
+        .actor
+        .filter(|a| !a.trim().is_empty())
+        .unwrap_or_else(|| current.owner.clone());
+    let released = manual_override::release_override(rtdb, id, &point_id, &actor)
+        .await
+        .map_err(|e| ModSrvError::RedisError(format!("Failed to release override: {}", e)))?
+        .ok_or_else(|| {
+            ModSrvError::InstanceNotFound(format!(
+                "{} has no active override on action point {}",
+                id, point_id
+            ))
+        })?;
+    Ok(Json(SuccessResponse::new(released)))
+}
+
+/// Override audit log of an instance (set, release, expire; newest first)
+///
+/// @route GET /api/instances/{id}/overrides/log?limit={optional}
+#[utoipa::path(
+    get,
+    path = "/api/instances/{id}/overrides/log",
+    params(
+        ("id" = u32, Path, description = "Instance ID"),
+        ("limit" = Option<usize>, Query, description = "Maximum entries (default 100, max 500)")
+    ),
+    responses(
+        (status = 200, description = "Override audit entries", body = serde_json::Value,
+            example = json!({
+                "success": true,
+                "data": [{
+                    "event": "release",
+                    "point_id": "1",
+                    "value": 0.0,
+                    "owner": "operator1",
+                    "actor": "supervisor",
+                    "at": 1735690000000_i64
+                }]
+            })
+        )
+    ),
+    tag = "modsrv"
+)]
+pub async fn get_override_log(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u32>,
+    Query(query): Query<OverrideLogQuery>,
+) -> Result<Json<SuccessResponse<Vec<manual_override::OverrideAuditEntry>>>, ModSrvError> {
+    let entries = manual_override::audit_log(
+        state.instance_manager.rtdb.as_ref(),
+        id,
+        query.limit.unwrap_or(100),
+    )
+    .await
+    .map_err(|e| ModSrvError::RedisError(format!("Failed to read override log: {}", e)))?;
+    Ok(Json(SuccessResponse::new(entries)))
+}
@@ -0,0 +1,223 @@
+//! Command Recipe Handlers
+//!
+//! Lists the recipes of an instance's product, starts them and serves the
+//! step-by-step progress of their runs (see [`crate::recipe`]).
+
+#![allow(clippy::disallowed_methods)] // json! macro used in multiple functions
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use common::SuccessResponse;
+use std::sync::Arc;
+
+use crate::app_state::AppState;
+use crate::error::ModSrvError;
+use crate::recipe::{RecipeDef, RecipeRun};
+
+/// Ensure the instance exists (used when no recipes are configured)
+async fn check_instance(state: &AppState, id: u32) -> Result<(), ModSrvError> {
+    state
+        .instance_manager
+        .get_instance(id)
+        .await
+        .map(|_| ())
+        .map_err(|_| ModSrvError::InstanceNotFound(id.to_string()))
+}
+
+fn run_not_found(id: u32, run_id: &str) -> ModSrvError {
+    ModSrvError::RecipeNotFound(format!("instance {} has no run {}", id, run_id))
+}
+
+/// List the recipes of an instance
+///
+/// @route GET /api/instances/{id}/recipes
+#[utoipa::path(
+    get,
+    path = "/api/instances/{id}/recipes",
+    params(
+        ("id" = u32, Path, description = "Instance ID")
+    ),
+    responses(
+        (status = 200, description = "Recipes of the instance's product", body = serde_json::Value,
+            example = json!({
+                "success": true,
+                "data": [{
+                    "name": "start",
+                    "description": "Close DC breaker, then start the PCS",
+                    "steps": [
+                        {"name": "close_dc_breaker", "action": 3, "value": 1.0, "delay_ms": 0,
+                         "verify": {"require": "dc_closed == 1.0", "variables": {"dc_closed": "M:5"}, "timeout_ms": 5000}},
+                        {"name": "start_pcs", "action": 1, "value": 1.0, "delay_ms": 2000}
+                    ]
+                }]
+            })
+        ),
+        (status = 404, description = "Instance not found")
+    ),
+    tag = "modsrv"
+)]
+pub async fn list_recipes(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u32>,
+) -> Result<Json<SuccessResponse<Vec<RecipeDef>>>, ModSrvError> {
+    let recipes = match &state.recipe_engine {
+        Some(engine) => engine.recipes(id).await?,
+        None => {
+            check_instance(&state, id).await?;
+            Vec::new()
+        },
+    };
+    Ok(Json(SuccessResponse::new(recipes)))
+}
+
+/// Start a recipe on an instance
+///
+/// The steps run in the background; poll the returned run for progress.
+///
+/// @route POST /api/instances/{id}/recipes/{name}/execute
+#[utoipa::path(
+    post,
+    path = "/api/instances/{id}/recipes/{name}/execute",
+    params(
+        ("id" = u32, Path, description = "Instance ID"),
+        ("name" = String, Path, description = "Recipe name")
+    ),
+    responses(
+        (status = 200, description = "Run started", body = serde_json::Value,
+            example = json!({
+                "success": true,
+                "data": {
+                    "run_id": "recipe_1_1735689600000_0",
+                    "instance_id": 1,
+                    "recipe": "start",
+                    "status": "running",
+                    "started_at": 1735689600000_i64,
+                    "steps": [
+                        {"name": "close_dc_breaker", "action": 3, "value": 1.0, "status": "pending"},
+                        {"name": "start_pcs", "action": 1, "value": 1.0, "status": "pending"}
+                    ]
+                }
+            })
+        ),
+        (status = 404, description = "Instance or recipe not found"),
+        (status = 409, description = "Another recipe is running on the instance")
+    ),
+    tag = "modsrv"
+)]
+pub async fn execute_recipe(
+    State(state): State<Arc<AppState>>,
+    Path((id, name)): Path<(u32, String)>,
+) -> Result<Json<SuccessResponse<RecipeRun>>, ModSrvError> {
+    let Some(engine) = &state.recipe_engine else {
+        check_instance(&state, id).await?;
+        return Err(ModSrvError::RecipeNotFound(format!(
+            "instance {} has no recipe '{}'",
+            id, name
+        )));
+    };
+    let run = engine.execute(id, &name).await?;
+    Ok(Json(SuccessResponse::new(run)))
+}
+
+/// Recipe runs of an instance (newest first)
+///
+/// @route GET /api/instances/{id}/recipe-runs
+#[utoipa::path(
+    get,
+    path = "/api/instances/{id}/recipe-runs",
+    params(
+        ("id" = u32, Path, description = "Instance ID")
+    ),
+    responses(
+        (status = 200, description = "Runs kept in memory", body = serde_json::Value)
+    ),
+    tag = "modsrv"
+)]
+pub async fn list_recipe_runs(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u32>,
+) -> Result<Json<SuccessResponse<Vec<RecipeRun>>>, ModSrvError> {
+    let runs = state
+        .recipe_engine
+        .as_ref()
+        .map(|engine| engine.runs(id))
+        .unwrap_or_default();
+    Ok(Json(SuccessResponse::new(runs)))
+}
+
+/// Progress of a recipe run
+///
+/// @route GET /api/instances/{id}/recipe-runs/{run_id}
+#[utoipa::path(
+    get,
+    path = "/api/instances/{id}/recipe-runs/{run_id}",
+    params(
+        ("id" = u32, Path, description = "Instance ID"),
+        ("run_id" = String, Path, description = "Run ID")
+    ),
+    responses(
+        (status = 200, description = "Run progress", body = serde_json::Value,
+            example = json!({
+                "success": true,
+                "data": {
+                    "run_id": "recipe_1_1735689600000_0",
+                    "instance_id": 1,
+                    "recipe": "start",
+                    "status": "running",
+                    "current_step": 0,
+                    "started_at": 1735689600000_i64,
+                    "steps": [
+                        {"name": "close_dc_breaker", "action": 3, "value": 1.0, "status": "verifying",
+                         "write_status": "success", "started_at": 1735689600000_i64},
+                        {"name": "start_pcs", "action": 1, "value": 1.0, "status": "pending"}
+                    ]
+                }
+            })
+        ),
+        (status = 404, description = "Run not found")
+    ),
+    tag = "modsrv"
+)]
+pub async fn get_recipe_run(
+    State(state): State<Arc<AppState>>,
+    Path((id, run_id)): Path<(u32, String)>,
+) -> Result<Json<SuccessResponse<RecipeRun>>, ModSrvError> {
+    let run = state
+        .recipe_engine
+        .as_ref()
+        .and_then(|engine| engine.get(id, &run_id))
+        .ok_or_else(|| run_not_found(id, &run_id))?;
+    Ok(Json(SuccessResponse::new(run)))
+}
+
+/// Abort a recipe run
+///
+/// No further step is started; a write already in flight completes.
+///
+/// @route POST /api/instances/{id}/recipe-runs/{run_id}/abort
+#[utoipa::path(
+    post,
+    path = "/api/instances/{id}/recipe-runs/{run_id}/abort",
+    params(
+        ("id" = u32, Path, description = "Instance ID"),
+        ("run_id" = String, Path, description = "Run ID")
+    ),
+    responses(
+        (status = 200, description = "Run aborted (finished runs are returned unchanged)", body = serde_json::Value),
+        (status = 404, description = "Run not found")
+    ),
+    tag = "modsrv"
+)]
+pub async fn abort_recipe_run(
+    State(state): State<Arc<AppState>>,
+    Path((id, run_id)): Path<(u32, String)>,
+) -> Result<Json<SuccessResponse<RecipeRun>>, ModSrvError> {
+    let run = state
+        .recipe_engine
+        .as_ref()
+        .and_then(|engine| engine.abort(id, &run_id))
+        .ok_or_else(|| run_not_found(id, &run_id))?;
+    Ok(Json(SuccessResponse::new(run)))
+}
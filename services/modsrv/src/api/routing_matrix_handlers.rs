@@ -0,0 +1,717 @@
+//! Routing Matrix API Handlers
+//!
+//! Backend for the visual routing matrix editor. Presents instance points against
+//! channel points, detects channel points targeted by more than one instance point,
+//! and saves batches of edits through to `measurement_routing` / `action_routing`.
+
+#![allow(clippy::disallowed_methods)] // json! macro used in multiple functions
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use common::{FourRemote, SuccessResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::app_state::AppState;
+use crate::dto::{PointType, RoutingMatrixEdit, RoutingMatrixSaveRequest};
+use crate::error::ModSrvError;
+
+#[derive(Debug, Deserialize)]
+pub struct MatrixQuery {
+    pub instance_id: Option<u32>,
+    pub channel_id: Option<u32>,
+}
+
+/// Instance point key: (instance_id, point type, point_id)
+type InstancePointKey = (u32, PointType, u32);
+
+/// A bound link between an instance point and a channel point
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MatrixLink {
+    pub instance_id: u32,
+    pub instance_name: String,
+    pub point_type: PointType,
+    pub point_id: u32,
+    pub channel_id: u32,
+    pub four_remote: FourRemote,
+    pub channel_point_id: u32,
+    pub enabled: bool,
+}
+
+/// Instance point that takes part in a conflict
+#[derive(Debug, Clone, Serialize)]
+pub struct ConflictTarget {
+    pub instance_id: u32,
+    pub instance_name: String,
+    pub point_type: PointType,
+    pub point_id: u32,
+}
+
+/// A channel point mapped by more than one instance point
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutingConflict {
+    pub channel_id: u32,
+    pub four_remote: FourRemote,
+    pub channel_point_id: u32,
+    pub targets: Vec<ConflictTarget>,
+}
+
+impl RoutingConflict {
+    fn involves(&self, key: &InstancePointKey) -> bool {
+        self.targets
+            .iter()
+            .any(|t| (t.instance_id, t.point_type, t.point_id) == *key)
+    }
+
+    fn describe(&self) -> String {
+        let targets: Vec<String> = self
+            .targets
+            .iter()
+            .map(|t| {
+                let kind = match t.point_type {
+                    PointType::Measurement => "M",
+                    PointType::Action => "A",
+                };
+                format!("{}.{}{}", t.instance_name, kind, t.point_id)
+            })
+            .collect();
+        format!(
+            "channel {} {}:{} <- [{}]",
+            self.channel_id,
+            self.four_remote,
+            self.channel_point_id,
+            targets.join(", ")
+        )
+    }
+}
+
+/// Find channel points mapped by more than one enabled instance point
+///
+/// The routing cache keys C2M/M2C entries by channel point, so a channel point
+/// shared by several instance points would silently keep only one of them.
+pub fn detect_conflicts(links: &[MatrixLink]) -> Vec<RoutingConflict> {
+    let mut by_channel_point: BTreeMap<(u32, u8, u32), Vec<&MatrixLink>> = BTreeMap::new();
+    for link in links.iter().filter(|l| l.enabled) {
+        by_channel_point
+            .entry((
+                link.channel_id,
+                link.four_remote.to_u8(),
+                link.channel_point_id,
+            ))
+            .or_default()
+            .push(link);
+    }
+
+    by_channel_point
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|group| RoutingConflict {
+            channel_id: group[0].channel_id,
+            four_remote: group[0].four_remote,
+            channel_point_id: group[0].channel_point_id,
+            targets: group
+                .iter()
+                .map(|l| ConflictTarget {
+                    instance_id: l.instance_id,
+                    instance_name: l.instance_name.clone(),
+                    point_type: l.point_type,
+                    point_id: l.point_id,
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Apply matrix edits on top of the existing links
+///
+/// Edits with all channel fields unset remove the link; the rest replace it.
+/// Returns the resulting link set (sorted by instance point).
+pub fn apply_edits(
+    existing: Vec<MatrixLink>,
+    edits: &[(RoutingMatrixEdit, String)],
+) -> Vec<MatrixLink> {
+    let mut merged: BTreeMap<(u32, u8, u32), MatrixLink> = existing
+        .into_iter()
+        .map(|l| {
+            (
+                (l.instance_id, point_type_order(l.point_type), l.point_id),
+                l,
+            )
+        })
+        .collect();
+
+    for (edit, instance_name) in edits {
+        let key = (
+            edit.instance_id,
+            point_type_order(edit.point_type),
+            edit.point_id,
+        );
+        let channel_id = edit.channel_id.and_then(|id| u32::try_from(id).ok());
+        match (channel_id, edit.four_remote, edit.channel_point_id) {
+            (Some(channel_id), Some(four_remote), Some(channel_point_id)) => {
+                merged.insert(
+                    key,
+                    MatrixLink {
+                        instance_id: edit.instance_id,
+                        instance_name: instance_name.clone(),
+                        point_type: edit.point_type,
+                        point_id: edit.point_id,
+                        channel_id,
+                        four_remote,
+                        channel_point_id,
+                        enabled: edit.enabled,
+                    },
+                );
+            },
+            _ => {
+                merged.remove(&key);
+            },
+        }
+    }
+
+    merged.into_values().collect()
+}
+
+fn point_type_order(point_type: PointType) -> u8 {
+    match point_type {
+        PointType::Measurement => 0,
+        PointType::Action => 1,
+    }
+}
+
+/// Channel ID of an edit; negative IDs are rejected instead of wrapping
+fn edit_channel_id(
+    channel_id: i32,
+    instance_name: &str,
+    point_id: u32,
+) -> Result<u32, ModSrvError> {
+    u32::try_from(channel_id).map_err(|_| {
+        ModSrvError::InvalidRouting(format!(
+            "Invalid channel_id {} for {} point {}",
+            channel_id, instance_name, point_id
+        ))
+    })
+}
+
+/// Channel point table for a four-remote type
+fn channel_point_table(four_remote: FourRemote) -> &'static str {
+    match four_remote {
+        FourRemote::Telemetry => "telemetry_points",
+        FourRemote::Signal => "signal_points",
+        FourRemote::Control => "control_points",
+        FourRemote::Adjustment => "adjustment_points",
+    }
+}
+
+/// Load all bound links from the routing tables
+async fn load_links(pool: &SqlitePool) -> Result<Vec<MatrixLink>, ModSrvError> {
+    let measurement_rows = sqlx::query_as::<_, (u32, String, u32, u32, String, u32, bool)>(
+        r#"
+        SELECT instance_id, instance_name, measurement_id, channel_id, channel_type,
+               channel_point_id, enabled
+        FROM measurement_routing
+        WHERE channel_id IS NOT NULL AND channel_type IS NOT NULL AND channel_point_id IS NOT NULL
+        ORDER BY instance_id, measurement_id
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        ModSrvError::InternalError(format!("Failed to query measurement routing: {}", e))
+    })?;
+
+    let action_rows = sqlx::query_as::<_, (u32, String, u32, u32, String, u32, bool)>(
+        r#"
+        SELECT instance_id, instance_name, action_id, channel_id, channel_type,
+               channel_point_id, enabled
+        FROM action_routing
+        WHERE channel_id IS NOT NULL AND channel_type IS NOT NULL AND channel_point_id IS NOT NULL
+        ORDER BY instance_id, action_id
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| ModSrvError::InternalError(format!("Failed to query action routing: {}", e)))?;
+
+    let rows = measurement_rows
+        .into_iter()
+        .map(|row| (PointType::Measurement, row))
+        .chain(action_rows.into_iter().map(|row| (PointType::Action, row)));
+
+    let mut links = Vec::new();
+    for (
+        point_type,
+        (instance_id, instance_name, point_id, channel_id, channel_type, channel_point_id, enabled),
+    ) in rows
+    {
+        let Ok(four_remote) = channel_type.parse::<FourRemote>() else {
+            tracing::warn!(
+                "Skipping routing {}:{} with invalid channel_type '{}'",
+                instance_name,
+                point_id,
+                channel_type
+            );
+            continue;
+        };
+        links.push(MatrixLink {
+            instance_id,
+            instance_name,
+            point_type,
+            point_id,
+            channel_id,
+            four_remote,
+            channel_point_id,
+            enabled,
+        });
+    }
+
+    Ok(links)
+}
+
+/// Get the routing matrix
+///
+/// Returns instance points (rows), channel points (columns), the bound links
+/// between them and any channel points targeted by more than one instance point.
+///
+/// @route GET /api/routing/matrix
+/// @input Query(instance_id): Option<u32> - Restrict rows to one instance
+/// @input Query(channel_id): Option<u32> - Restrict columns to one channel
+/// @output `Json<SuccessResponse<Value>>` - Matrix rows, columns, links and conflicts
+/// @status 200 - Success with routing matrix
+/// @status 500 - Database error
+#[utoipa::path(
+    get,
+    path = "/api/routing/matrix",
+    params(
+        ("instance_id" = Option<u32>, Query, description = "Restrict rows to one instance"),
+        ("channel_id" = Option<u32>, Query, description = "Restrict columns to one channel")
+    ),
+    responses(
+        (status = 200, description = "Routing matrix", body = Value,
+            example = json!({
+                "instance_points": [
+                    {"instance_id": 1, "instance_name": "pcs_01", "point_type": "M", "point_id": 1, "name": "active_power", "unit": "kW"}
+                ],
+                "channel_points": [
+                    {"channel_id": 1, "four_remote": "T", "point_id": 101, "signal_name": "P_total"}
+                ],
+                "links": [
+                    {"instance_id": 1, "instance_name": "pcs_01", "point_type": "M", "point_id": 1,
+                     "channel_id": 1, "four_remote": "T", "channel_point_id": 101, "enabled": true}
+                ],
+                "conflicts": []
+            })
+        ),
+        (status = 500, description = "Database error")
+    ),
+    tag = "modsrv"
+)]
+pub async fn get_routing_matrix(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<MatrixQuery>,
+) -> Result<Json<SuccessResponse<Value>>, ModSrvError> {
+    let pool = &state.instance_manager.pool;
+
+    // Rows: instance points from product definitions
+    let instances = sqlx::query_as::<_, (u32, String, String)>(
+        r#"
+        SELECT instance_id, instance_name, product_name
+        FROM instances
+        WHERE (? IS NULL OR instance_id = ?)
+        ORDER BY instance_id
+        "#,
+    )
+    .bind(query.instance_id)
+    .bind(query.instance_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| ModSrvError::InternalError(format!("Failed to query instances: {}", e)))?;
+
+    let mut instance_points = Vec::new();
+    for (instance_id, instance_name, product_name) in &instances {
+        let product = match state.product_loader.get_product(product_name) {
+            Ok(p) => p,
+            Err(e) => {
+                tracing::warn!("Instance {} has no product points: {}", instance_name, e);
+                continue;
+            },
+        };
+        for m in &product.measurements {
+            instance_points.push(json!({
+                "instance_id": instance_id,
+                "instance_name": instance_name,
+                "point_type": PointType::Measurement,
+                "point_id": m.measurement_id,
+                "name": m.name,
+                "unit": m.unit
+            }));
+        }
+        for a in &product.actions {
+            instance_points.push(json!({
+                "instance_id": instance_id,
+                "instance_name": instance_name,
+                "point_type": PointType::Action,
+                "point_id": a.action_id,
+                "name": a.name,
+                "unit": a.unit
+            }));
+        }
+    }
+
+    // Columns: channel points from the four point tables
+    let mut channel_points = Vec::new();
+    for four_remote in [
+        FourRemote::Telemetry,
+        FourRemote::Signal,
+        FourRemote::Control,
+        FourRemote::Adjustment,
+    ] {
+        let sql = format!(
+            "SELECT channel_id, point_id, signal_name FROM {} \
+             WHERE (? IS NULL OR channel_id = ?) ORDER BY channel_id, point_id",
+            channel_point_table(four_remote)
+        );
+        let rows = sqlx::query_as::<_, (u32, u32, String)>(&sql)
+            .bind(query.channel_id)
+            .bind(query.channel_id)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| {
+                ModSrvError::InternalError(format!("Failed to query channel points: {}", e))
+            })?;
+        channel_points.extend(rows.into_iter().map(|(channel_id, point_id, signal_name)| {
+            json!({
+                "channel_id": channel_id,
+                "four_remote": four_remote,
+                "point_id": point_id,
+                "signal_name": signal_name
+            })
+        }));
+    }
+
+    // Conflicts are detected across all links so filtered views still show them
+    let all_links = load_links(pool).await?;
+    let matches_filter = |instance_id: u32, channel_id: u32| {
+        query.instance_id.is_none_or(|id| id == instance_id)
+            && query.channel_id.is_none_or(|id| id == channel_id)
+    };
+    let conflicts: Vec<RoutingConflict> = detect_conflicts(&all_links)
+        .into_iter()
+        .filter(|c| {
+            c.targets
+                .iter()
+                .any(|t| matches_filter(t.instance_id, c.channel_id))
+        })
+        .collect();
+    let links: Vec<MatrixLink> = all_links
+        .into_iter()
+        .filter(|l| matches_filter(l.instance_id, l.channel_id))
+        .collect();
+
+    Ok(Json(SuccessResponse::new(json!({
+        "instance_points": instance_points,
+        "channel_points": channel_points,
+        "links": links,
+        "conflicts": conflicts
+    }))))
+}
+
+/// Save routing matrix edits
+///
+/// Validates a batch of edits, rejects it if it would leave a channel point
+/// targeted by more than one instance point, then writes all edits in one
+/// transaction and refreshes the routing cache.
+///
+/// @route PUT /api/routing/matrix
+/// @input Json(request): RoutingMatrixSaveRequest - Batch of cell edits
+/// @output `Json<SuccessResponse<Value>>` - Number of saved edits per table
+/// @status 200 - All edits saved
+/// @status 400 - Invalid edit or routing conflict
+/// @status 404 - Instance not found
+/// @status 500 - Database error
+#[utoipa::path(
+    put,
+    path = "/api/routing/matrix",
+    request_body = RoutingMatrixSaveRequest,
+    responses(
+        (status = 200, description = "Routing matrix saved", body = Value,
+            example = json!({"saved": {"measurement": 2, "action": 1}})
+        ),
+        (status = 400, description = "Invalid edit or routing conflict"),
+        (status = 404, description = "Instance not found"),
+        (status = 500, description = "Database error")
+    ),
+    tag = "modsrv"
+)]
+pub async fn save_routing_matrix(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<RoutingMatrixSaveRequest>,
+) -> Result<Json<SuccessResponse<Value>>, ModSrvError> {
+    let pool = &state.instance_manager.pool;
+
+    if request.edits.is_empty() {
+        return Err(ModSrvError::InvalidData(
+            "No routing edits provided".to_string(),
+        ));
+    }
+
+    let instances: HashMap<u32, (String, String)> = sqlx::query_as::<_, (u32, String, String)>(
+        "SELECT instance_id, instance_name, product_name FROM instances",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| ModSrvError::InternalError(format!("Failed to query instances: {}", e)))?
+    .into_iter()
+    .map(|(id, name, product)| (id, (name, product)))
+    .collect();
+
+    let mut edited_keys = HashSet::new();
+    let mut edits = Vec::with_capacity(request.edits.len());
+    for edit in request.edits {
+        let (instance_name, product_name) = instances
+            .get(&edit.instance_id)
+            .ok_or_else(|| ModSrvError::InstanceNotFound(edit.instance_id.to_string()))?;
+
+        if !edited_keys.insert((edit.instance_id, edit.point_type, edit.point_id)) {
+            return Err(ModSrvError::InvalidRouting(format!(
+                "Duplicate edit for {} point {}",
+                instance_name, edit.point_id
+            )));
+        }
+
+        // Point must exist in the instance's product
+        let product = state
+            .product_loader
+            .get_product(product_name)
+            .map_err(|e| ModSrvError::InvalidData(e.to_string()))?;
+        let point_exists = match edit.point_type {
+            PointType::Measurement => product
+                .measurements
+                .iter()
+                .any(|m| m.measurement_id == edit.point_id),
+            PointType::Action => product.actions.iter().any(|a| a.action_id == edit.point_id),
+        };
+        if !point_exists {
+            return Err(ModSrvError::InvalidRouting(format!(
+                "Point {} not found in product {}",
+                edit.point_id, product_name
+            )));
+        }
+
+        match (edit.channel_id, edit.four_remote, edit.channel_point_id) {
+            (None, None, None) => {},
+            (Some(channel_id), Some(four_remote), Some(channel_point_id)) => {
+                let channel_id = edit_channel_id(channel_id, instance_name, edit.point_id)?;
+                let direction_ok = match edit.point_type {
+                    PointType::Measurement => four_remote.is_input(),
+                    PointType::Action => four_remote.is_output(),
+                };
+                if !direction_ok {
+                    return Err(ModSrvError::InvalidRouting(format!(
+                        "Invalid channel_type '{}' for {} point {}",
+                        four_remote, instance_name, edit.point_id
+                    )));
+                }
+
+                let sql = format!(
+                    "SELECT EXISTS(SELECT 1 FROM {} WHERE channel_id = ? AND point_id = ?)",
+                    channel_point_table(four_remote)
+                );
+                let channel_point_exists = sqlx::query_scalar::<_, bool>(&sql)
+                    .bind(channel_id)
+                    .bind(channel_point_id)
+                    .fetch_one(pool)
+                    .await
+                    .map_err(|e| {
+                        ModSrvError::InternalError(format!("Failed to query channel points: {}", e))
+                    })?;
+                if !channel_point_exists {
+                    return Err(ModSrvError::InvalidRouting(format!(
+                        "Channel point {}:{}:{} does not exist",
+                        channel_id, four_remote, channel_point_id
+                    )));
+                }
+            },
+            _ => {
+                return Err(ModSrvError::InvalidRouting(format!(
+                    "channel_id, four_remote and channel_point_id must be set together ({} point {})",
+                    instance_name, edit.point_id
+                )));
+            },
+        }
+
+        edits.push((edit, instance_name.clone()));
+    }
+
+    // Reject the batch if it introduces or touches a duplicate target
+    let merged = apply_edits(load_links(pool).await?, &edits);
+    let conflicts: Vec<String> = detect_conflicts(&merged)
+        .iter()
+        .filter(|c| edited_keys.iter().any(|key| c.involves(key)))
+        .map(RoutingConflict::describe)
+        .collect();
+    if !conflicts.is_empty() {
+        return Err(ModSrvError::InvalidRouting(format!(
+            "Routing conflicts: {}",
+            conflicts.join("; ")
+        )));
+    }
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| ModSrvError::InternalError(format!("Failed to start transaction: {}", e)))?;
+
+    let (mut measurement_saved, mut action_saved) = (0u64, 0u64);
+    for (edit, instance_name) in &edits {
+        let sql = match edit.point_type {
+            PointType::Measurement => {
+                r#"
+                INSERT INTO measurement_routing
+                (instance_id, instance_name, channel_id, channel_type, channel_point_id,
+                 measurement_id, enabled)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT(instance_id, measurement_id)
+                DO UPDATE SET
+                    channel_id = excluded.channel_id,
+                    channel_type = excluded.channel_type,
+                    channel_point_id = excluded.channel_point_id,
+                    enabled = excluded.enabled,
+                    updated_at = CURRENT_TIMESTAMP
+                "#
+            },
+            PointType::Action => {
+                r#"
+                INSERT INTO action_routing
+                (instance_id, instance_name, channel_id, channel_type, channel_point_id,
+                 action_id, enabled)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT(instance_id, action_id)
+                DO UPDATE SET
+                    channel_id = excluded.channel_id,
+                    channel_type = excluded.channel_type,
+                    channel_point_id = excluded.channel_point_id,
+                    enabled = excluded.enabled,
+                    updated_at = CURRENT_TIMESTAMP
+                "#
+            },
+        };
+
+        let result = sqlx::query(sql)
+            .bind(edit.instance_id as i32)
+            .bind(instance_name)
+            .bind(edit.channel_id)
+            .bind(edit.four_remote.map(|fr| fr.as_str()))
+            .bind(edit.channel_point_id)
+            .bind(edit.point_id)
+            .bind(edit.enabled)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| ModSrvError::InternalError(format!("Failed to save routing: {}", e)))?;
+
+        match edit.point_type {
+            PointType::Measurement => measurement_saved += result.rows_affected(),
+            PointType::Action => action_saved += result.rows_affected(),
+        }
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| ModSrvError::InternalError(format!("Failed to commit transaction: {}", e)))?;
+
+    // Refresh routing cache after successful database update
+    if let Err(e) =
+        crate::bootstrap::refresh_routing_cache(pool, state.instance_manager.routing_cache()).await
+    {
+        tracing::warn!("Failed to refresh routing cache after matrix save: {}", e);
+    }
+
+    Ok(Json(SuccessResponse::new(json!({
+        "saved": {
+            "measurement": measurement_saved,
+            "action": action_saved
+        }
+    }))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(instance_id: u32, point_id: u32, channel_point_id: u32) -> MatrixLink {
+        MatrixLink {
+            instance_id,
+            instance_name: format!("inst_{}", instance_id),
+            point_type: PointType::Measurement,
+            point_id,
+            channel_id: 1,
+            four_remote: FourRemote::Telemetry,
+            channel_point_id,
+            enabled: true,
+        }
+    }
+
+    fn edit(instance_id: u32, point_id: u32, channel_point_id: Option<u32>) -> RoutingMatrixEdit {
+        RoutingMatrixEdit {
+            instance_id,
+            point_type: PointType::Measurement,
+            point_id,
+            channel_id: channel_point_id.map(|_| 1),
+            four_remote: channel_point_id.map(|_| FourRemote::Telemetry),
+            channel_point_id,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_detect_conflicts_duplicate_target() {
+        let links = vec![link(1, 1, 101), link(2, 1, 101), link(1, 2, 102)];
+        let conflicts = detect_conflicts(&links);
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].channel_point_id, 101);
+        assert_eq!(conflicts[0].targets.len(), 2);
+    }
+
+    #[test]
+    fn test_detect_conflicts_ignores_disabled_and_other_types() {
+        let mut disabled = link(2, 1, 101);
+        disabled.enabled = false;
+        let mut signal = link(3, 1, 101);
+        signal.four_remote = FourRemote::Signal;
+
+        assert!(detect_conflicts(&[link(1, 1, 101), disabled, signal]).is_empty());
+    }
+
+    #[test]
+    fn test_edit_channel_id_range() {
+        assert_eq!(edit_channel_id(7, "inst_1", 1).unwrap(), 7);
+        for channel_id in [-1, i32::MIN] {
+            let err = edit_channel_id(channel_id, "inst_1", 1).unwrap_err();
+            assert!(matches!(err, ModSrvError::InvalidRouting(_)), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_apply_edits_rebind_and_unbind() {
+        let existing = vec![link(1, 1, 101), link(1, 2, 102)];
+        let edits = vec![
+            (edit(1, 1, Some(103)), "inst_1".to_string()),
+            (edit(1, 2, None), "inst_1".to_string()),
+            (edit(2, 1, Some(101)), "inst_2".to_string()),
+        ];
+
+        let merged = apply_edits(existing, &edits);
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!((merged[0].instance_id, merged[0].point_id), (1, 1));
+        assert_eq!(merged[0].channel_point_id, 103);
+        assert_eq!((merged[1].instance_id, merged[1].point_id), (2, 1));
+        assert!(detect_conflicts(&merged).is_empty());
+    }
+}
@@ -18,6 +18,7 @@ use std::sync::Arc;
 use crate::app_state::AppState;
 use crate::error::ModSrvError;
 use crate::redis_state::{self, RoutingDirection};
+use voltage_rtdb::{RouteEntry, RouteFilter, RouteTable, RoutingCache};
 
 #[derive(Debug, Deserialize)]
 pub struct RoutingQuery {
@@ -189,5 +190,150 @@ pub async fn get_routing_table_handler(
     }
 }
 
+/// Query parameters for routing cache diagnostics
+#[derive(Debug, Deserialize)]
+pub struct RoutingCacheQuery {
+    pub instance_id: Option<u32>,
+    pub channel_id: Option<u32>,
+    /// c2m, m2c or c2c
+    pub table: Option<String>,
+}
+
+/// Request body for routing cache miss tracing
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct RoutingCacheTraceRequest {
+    /// Record missed lookup keys in the ring buffer
+    pub enabled: bool,
+    /// Also reset hit/miss counters and recorded misses
+    #[serde(default)]
+    pub reset: bool,
+}
+
+/// Dump the in-memory routing cache
+///
+/// Returns hit/miss counters, the most recent missed keys (while tracing)
+/// and the cached entries for an instance or channel, together with the
+/// difference against the routes stored in SQLite. Use it to diagnose a route
+/// that exists in SQLite but is not applied at runtime.
+///
+/// @route GET /api/routing/cache?instance_id={optional}&channel_id={optional}&table={optional}
+/// @input State(state): `Arc<AppState>` - Application state
+/// @input Query(query): RoutingCacheQuery - Instance, channel and table filters
+/// @output `Result<Json<SuccessResponse<Value>>, ModSrvError>` - Cache diagnostics
+/// @status 200 - Success
+/// @status 400 - Unknown table
+/// @status 500 - Failed to load routes from SQLite
+#[utoipa::path(
+    get,
+    path = "/api/routing/cache",
+    params(
+        ("instance_id" = Option<u32>, Query, description = "Entries routed to or from this instance"),
+        ("channel_id" = Option<u32>, Query, description = "Entries routed to or from this channel"),
+        ("table" = Option<String>, Query, description = "c2m, m2c or c2c")
+    ),
+    responses(
+        (status = 200, description = "Routing cache diagnostics", body = serde_json::Value,
+            example = json!({
+                "stats": {
+                    "c2m_count": 120, "m2c_count": 8, "c2c_count": 0,
+                    "c2m_lookups": {"hits": 5120, "misses": 3},
+                    "m2c_lookups": {"hits": 12, "misses": 1},
+                    "c2c_lookups": {"hits": 0, "misses": 0},
+                    "miss_tracing": true
+                },
+                "recent_misses": [
+                    {"table": "m2c", "key": "5:A:7", "timestamp_ms": 1735689600000_u64}
+                ],
+                "entries": [
+                    {"table": "m2c", "source": "5:A:1", "target": "1001:A:3"}
+                ],
+                "sqlite": {
+                    "missing_from_cache": [
+                        {"table": "m2c", "source": "5:A:7", "target": "1001:A:9"}
+                    ],
+                    "stale_in_cache": []
+                }
+            })
+        ),
+        (status = 400, description = "Unknown table")
+    ),
+    tag = "modsrv"
+)]
+pub async fn get_routing_cache_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RoutingCacheQuery>,
+) -> Result<Json<SuccessResponse<Value>>, ModSrvError> {
+    let table = match query.table.as_deref() {
+        Some(table) => Some(RouteTable::parse(table).ok_or_else(|| {
+            ModSrvError::InvalidData(format!(
+                "Unknown routing table '{}'. Use c2m, m2c or c2c",
+                table
+            ))
+        })?),
+        None => None,
+    };
+    let filter = RouteFilter {
+        instance_id: query.instance_id,
+        channel_id: query.channel_id,
+        table,
+    };
+
+    let diagnostics = state.instance_manager.routing_cache().diagnostics(&filter);
+
+    // Compare with what a reload from SQLite would produce
+    let maps = voltage_routing::load_routing_maps(&state.instance_manager.pool)
+        .await
+        .map_err(|e| ModSrvError::InternalError(format!("Failed to load routing: {}", e)))?;
+    let stored = RoutingCache::from_maps(maps.c2m, maps.m2c, maps.c2c).entries(&filter);
+    let not_in = |entries: &[RouteEntry], other: &[RouteEntry]| -> Vec<RouteEntry> {
+        entries
+            .iter()
+            .filter(|e| !other.contains(e))
+            .cloned()
+            .collect()
+    };
+
+    Ok(Json(SuccessResponse::new(json!({
+        "stats": diagnostics.stats,
+        "recent_misses": diagnostics.recent_misses,
+        "sqlite": {
+            "missing_from_cache": not_in(&stored, &diagnostics.entries),
+            "stale_in_cache": not_in(&diagnostics.entries, &stored),
+        },
+        "entries": diagnostics.entries,
+    }))))
+}
+
+/// Enable or disable routing cache miss tracing
+///
+/// @route PUT /api/routing/cache/trace
+/// @input State(state): `Arc<AppState>` - Application state
+/// @input Json(req): RoutingCacheTraceRequest - Tracing switch and optional reset
+/// @output `Result<Json<SuccessResponse<Value>>, ModSrvError>` - Current cache stats
+/// @status 200 - Tracing updated
+#[utoipa::path(
+    put,
+    path = "/api/routing/cache/trace",
+    request_body = RoutingCacheTraceRequest,
+    responses(
+        (status = 200, description = "Tracing updated, current cache stats", body = serde_json::Value)
+    ),
+    tag = "modsrv"
+)]
+pub async fn set_routing_cache_trace_handler(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<RoutingCacheTraceRequest>,
+) -> Result<Json<SuccessResponse<Value>>, ModSrvError> {
+    let cache = state.instance_manager.routing_cache();
+    if req.reset {
+        cache.reset_lookup_stats();
+    }
+    cache.set_miss_tracing(req.enabled);
+
+    Ok(Json(SuccessResponse::new(json!({
+        "stats": cache.stats(),
+    }))))
+}
+
 // NOTE: get_channel_routing_handler has been removed as it is now replaced by:
 // - get_routing_by_channel_handler in global_routing_handlers.rs
@@ -0,0 +1,106 @@
+//! Global Search API Handlers
+//!
+//! Fuzzy search across channels, points, instances, products and rules.
+
+#![allow(clippy::disallowed_methods)] // json! macro used in multiple functions
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use common::SuccessResponse;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::app_state::AppState;
+use crate::error::ModSrvError;
+use crate::search_index::SearchKind;
+
+/// Default and maximum number of returned hits
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 100;
+
+/// Search query parameters
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    /// Search text; whitespace-separated tokens must all match
+    #[serde(default)]
+    pub q: String,
+    /// Optional kind filter, comma-separated (instance,channel,point,product,rule)
+    pub types: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Search channels, points, instances, products and rules
+///
+/// Matches names (exact, prefix, substring or in-order characters), ids,
+/// descriptions and the owning object (channel of a point, product of an
+/// instance). Each hit carries its kind, score and the API path of the object.
+///
+/// @route GET /api/search?q={text}&types={optional}&limit={optional}
+/// @input State(state): `Arc<AppState>` - Application state
+/// @input Query(query): SearchQuery - Search text, kind filter and limit
+/// @output Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError> - Ranked hits
+/// @status 200 - Success with query, total and results
+/// @status 400 - Unknown type in `types`
+#[utoipa::path(
+    get,
+    path = "/api/search",
+    params(
+        ("q" = String, Query, description = "Search text"),
+        ("types" = Option<String>, Query, description = "Optional kind filter, comma-separated (instance,channel,point,product,rule)"),
+        ("limit" = Option<usize>, Query, description = "Maximum number of results (default 20, max 100)")
+    ),
+    responses(
+        (status = 200, description = "Search results", body = serde_json::Value,
+            example = json!({
+                "query": "pcs volt",
+                "total": 1,
+                "results": [
+                    {
+                        "kind": "point",
+                        "id": "1001:T:1",
+                        "name": "PCS_DC_Voltage",
+                        "description": "DC bus voltage",
+                        "context": "PCS_Modbus T",
+                        "link": "/api/channels/1001/T/points/1",
+                        "score": 140
+                    }
+                ]
+            })
+        ),
+        (status = 400, description = "Unknown type filter")
+    ),
+    tag = "modsrv"
+)]
+pub async fn search(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError> {
+    let kinds = match query.types.as_deref().filter(|t| !t.trim().is_empty()) {
+        Some(types) => Some(
+            types
+                .split(',')
+                .map(|t| {
+                    SearchKind::parse(t)
+                        .ok_or_else(|| ModSrvError::InvalidData(format!("Unknown type: {}", t)))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        None => None,
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let index = state
+        .search
+        .fresh(&state.instance_manager.pool, &state.product_loader)
+        .await;
+    let (total, results) = index.search(&query.q, kinds.as_deref(), limit);
+
+    Ok(Json(SuccessResponse::new(json!({
+        "query": query.q,
+        "total": total,
+        "results": results,
+    }))))
+}
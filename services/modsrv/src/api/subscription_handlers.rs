@@ -0,0 +1,183 @@
+//! Data Subscription Handlers
+//!
+//! Webhook subscriptions to instance measurement changes, delivered by the
+//! change dispatcher (see [`crate::subscriptions`]).
+
+#![allow(clippy::disallowed_methods)] // json! macro used in multiple functions
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use common::SuccessResponse;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::app_state::AppState;
+use crate::error::ModSrvError;
+use crate::subscriptions::DataSubscription;
+
+/// List data subscriptions with their delivery counters
+///
+/// @route GET /api/subscriptions
+/// @input State(state): `Arc<AppState>` - Application state
+/// @output `Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError>` - Subscriptions
+/// @status 200 - Success with total and subscriptions
+#[utoipa::path(
+    get,
+    path = "/api/subscriptions",
+    responses(
+        (status = 200, description = "Data subscriptions", body = serde_json::Value,
+            example = json!({
+                "total": 1,
+                "subscriptions": [
+                    {
+                        "name": "scada",
+                        "url": "https://scada.local/voltage/changes",
+                        "instances": [5],
+                        "points": [1, 2],
+                        "min_interval_ms": 1000,
+                        "timeout_ms": 3000,
+                        "enabled": true,
+                        "status": {"sequence": 42, "pending": 0, "delivered": 41, "failed": 1}
+                    }
+                ]
+            })
+        )
+    ),
+    tag = "modsrv"
+)]
+pub async fn list_subscriptions(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError> {
+    let subscriptions: Vec<serde_json::Value> = state
+        .subscriptions
+        .list()
+        .await
+        .into_iter()
+        .map(|subscription| {
+            let status = state.subscriptions.status(&subscription.name);
+            let mut entry = json!(subscription);
+            entry["status"] = json!(status);
+            entry
+        })
+        .collect();
+
+    Ok(Json(SuccessResponse::new(json!({
+        "total": subscriptions.len(),
+        "subscriptions": subscriptions,
+    }))))
+}
+
+/// Create or replace a data subscription
+///
+/// Matching measurement changes are POSTed to `url` in batches, at most one
+/// per `min_interval_ms`. With a `secret`, each request carries an
+/// `X-Voltage-Signature` HMAC-SHA256 header over `{timestamp}.{body}` and the
+/// timestamp in `X-Voltage-Timestamp`.
+///
+/// @route POST /api/subscriptions
+/// @input State(state): `Arc<AppState>` - Application state
+/// @input Json(subscription): DataSubscription - Webhook and filter
+/// @output `Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError>` - Saved name
+/// @status 200 - Subscription saved
+/// @status 400 - Invalid name, URL or filter
+/// @status 404 - Unknown instance
+#[utoipa::path(
+    post,
+    path = "/api/subscriptions",
+    request_body = DataSubscription,
+    responses(
+        (status = 200, description = "Subscription saved", body = serde_json::Value,
+            example = json!({"saved": "scada"})
+        ),
+        (status = 400, description = "Invalid name, URL or filter"),
+        (status = 404, description = "Unknown instance")
+    ),
+    tag = "modsrv"
+)]
+pub async fn upsert_subscription(
+    State(state): State<Arc<AppState>>,
+    Json(subscription): Json<DataSubscription>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError> {
+    let pool = &state.instance_manager.pool;
+    for instance_id in &subscription.instances {
+        let exists: Option<i64> =
+            sqlx::query_scalar("SELECT instance_id FROM instances WHERE instance_id = ?")
+                .bind(instance_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| ModSrvError::DatabaseError(e.to_string()))?;
+        if exists.is_none() {
+            return Err(ModSrvError::InstanceNotFound(instance_id.to_string()));
+        }
+    }
+
+    let name = subscription.name.clone();
+    state.subscriptions.upsert(pool, subscription).await?;
+    Ok(Json(SuccessResponse::new(json!({ "saved": name }))))
+}
+
+/// Get a single data subscription
+///
+/// @route GET /api/subscriptions/{name}
+/// @input Path(name): String - Subscription name
+/// @output `Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError>` - Subscription and status
+/// @status 200 - Success
+/// @status 404 - Subscription not found
+#[utoipa::path(
+    get,
+    path = "/api/subscriptions/{name}",
+    params(("name" = String, Path, description = "Subscription name")),
+    responses(
+        (status = 200, description = "Subscription with delivery counters", body = serde_json::Value),
+        (status = 404, description = "Subscription not found")
+    ),
+    tag = "modsrv"
+)]
+pub async fn get_subscription(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError> {
+    let subscription = state
+        .subscriptions
+        .get(&name)
+        .await
+        .ok_or_else(|| ModSrvError::SubscriptionNotFound(name.clone()))?;
+    let mut entry = json!(subscription);
+    entry["status"] = json!(state.subscriptions.status(&name));
+    Ok(Json(SuccessResponse::new(entry)))
+}
+
+/// Delete a data subscription
+///
+/// @route DELETE /api/subscriptions/{name}
+/// @input Path(name): String - Subscription name
+/// @output `Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError>` - Deleted name
+/// @status 200 - Subscription deleted
+/// @status 404 - Subscription not found
+#[utoipa::path(
+    delete,
+    path = "/api/subscriptions/{name}",
+    params(("name" = String, Path, description = "Subscription name")),
+    responses(
+        (status = 200, description = "Subscription deleted", body = serde_json::Value,
+            example = json!({"deleted": "scada"})
+        ),
+        (status = 404, description = "Subscription not found")
+    ),
+    tag = "modsrv"
+)]
+pub async fn delete_subscription(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError> {
+    if !state
+        .subscriptions
+        .remove(&state.instance_manager.pool, &name)
+        .await?
+    {
+        return Err(ModSrvError::SubscriptionNotFound(name));
+    }
+    Ok(Json(SuccessResponse::new(json!({ "deleted": name }))))
+}
@@ -7,14 +7,19 @@ use std::sync::Arc;
 use dashmap::DashMap;
 use tracing::debug;
 
+use crate::api::history_handlers::HistoryClient;
 use crate::config::ModsrvConfig;
 use crate::error::ModSrvError;
 use crate::instance_manager::InstanceManager;
 use crate::product_loader::ProductLoader;
+use crate::ramp::RampEngine;
+use crate::recipe::RecipeEngine;
+use crate::search_index::SearchService;
+use crate::subscriptions::DataSubscriptions;
 use common::sqlite::SqliteClient;
-#[cfg(test)]
+#[cfg(any(test, feature = "test-fixtures"))]
 use voltage_rtdb::MemoryRtdb as TestRtdb;
-#[cfg(not(test))]
+#[cfg(not(any(test, feature = "test-fixtures")))]
 use voltage_rtdb::RedisRtdb as TestRtdb;
 
 /// Application state containing shared resources
@@ -34,6 +39,21 @@ pub struct AppState {
     /// Instance name → instance_id cache (for fast API lookups)
     /// Updated on: create, delete, rename operations
     pub name_to_id_cache: Arc<DashMap<String, u16>>,
+
+    /// hissrv client for point history queries
+    pub history_client: HistoryClient,
+
+    /// Setpoint ramp engine (`None` without configured ramp limits)
+    pub ramp_engine: Option<Arc<RampEngine<voltage_rtdb::RedisRtdb>>>,
+
+    /// Command recipe runner (`None` without configured recipes)
+    pub recipe_engine: Option<Arc<RecipeEngine<voltage_rtdb::RedisRtdb>>>,
+
+    /// Global search index (channels, points, instances, products, rules)
+    pub search: Arc<SearchService>,
+
+    /// Webhook subscriptions to measurement changes
+    pub subscriptions: Arc<DataSubscriptions>,
 }
 
 impl AppState {
@@ -50,6 +70,11 @@ impl AppState {
             product_loader,
             instance_manager,
             name_to_id_cache: Arc::new(DashMap::new()),
+            history_client: HistoryClient::from_env(),
+            ramp_engine: None,
+            recipe_engine: None,
+            search: Arc::new(SearchService::new()),
+            subscriptions: Arc::new(DataSubscriptions::default()),
         }
     }
 
@@ -5,13 +5,17 @@
 
 #[allow(unused_imports)] // Used at runtime but not in tests
 use crate::config::{ModsrvConfig, ModsrvQueries};
-use common::bootstrap_args::ServiceArgs;
+pub use common::bootstrap_args::ServiceArgs;
 use common::bootstrap_database::{setup_redis_connection, setup_sqlite_pool};
 use common::bootstrap_system::{check_system_requirements_with, SystemRequirements};
 use common::redis::RedisClient;
-use common::service_bootstrap::{get_service_port, ServiceInfo};
+use common::service_bootstrap::{
+    get_service_port, Dependency, DependencyWaitConfig, DependencyWaiter, ServiceInfo,
+};
 use common::sqlite::{ServiceConfigLoader, SqliteClient};
-use common::{ApiConfig, BaseServiceConfig, RedisConfig, DEFAULT_API_HOST, DEFAULT_REDIS_URL};
+use common::{
+    ApiConfig, BaseServiceConfig, RedisConfig, ValidationLevel, DEFAULT_API_HOST, DEFAULT_REDIS_URL,
+};
 use sqlx::SqlitePool;
 use std::sync::Arc;
 use tracing::{debug, error, info, warn};
@@ -21,7 +25,10 @@ use super::error::{ModSrvError, Result};
 
 use crate::app_state::AppState;
 use crate::instance_manager::InstanceManager;
+use crate::interlock::InterlockEngine;
 use crate::product_loader::ProductLoader;
+use crate::ramp::RampEngine;
+use crate::recipe::RecipeEngine;
 
 /// Initialize service info for unified bootstrap
 pub fn create_service_info() -> ServiceInfo {
@@ -32,17 +39,35 @@ pub fn create_service_info() -> ServiceInfo {
     )
 }
 
+/// modsrv-specific command-line flags (standard flags are in [`ServiceArgs`])
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct ModsrvArgs {
+    /// Start even when the database has no instances
+    #[arg(long, env = "MODSRV_ALLOW_EMPTY")]
+    pub allow_empty: bool,
+}
+
+/// Parse the command line and apply its environment overrides
+pub fn parse_args(service_info: &ServiceInfo) -> Result<(ServiceArgs, ModsrvArgs)> {
+    let (args, modsrv_args) = ServiceArgs::parse_with::<ModsrvArgs>(service_info);
+    args.apply_env()
+        .map_err(|e| ModSrvError::ConfigError(format!("--config: {}", e)))?;
+    Ok((args, modsrv_args))
+}
+
 /// Initialize logging and environment
-pub fn init_environment(service_info: &ServiceInfo) -> Result<()> {
+pub fn init_environment(service_info: &ServiceInfo, args: &ServiceArgs) -> Result<()> {
     // Load environment variables from .env file
     common::service_bootstrap::load_development_env();
 
     // Initialize logging using service_bootstrap (config not loaded yet, use env/default)
-    common::service_bootstrap::init_logging(service_info, None)
+    common::service_bootstrap::init_logging_at(service_info, None, args.parse_log_level())
         .map_err(|e| ModSrvError::ConfigError(format!("Failed to initialize logging: {}", e)))?;
 
     // Print startup banner using service_bootstrap
-    common::service_bootstrap::print_startup_banner(service_info);
+    if !args.no_color {
+        common::service_bootstrap::print_startup_banner(service_info);
+    }
 
     // Enable SIGHUP-triggered log reopen for long-running processes
     common::logging::enable_sighup_log_reopen();
@@ -112,6 +137,12 @@ pub async fn load_configuration(service_info: &ServiceInfo) -> Result<ModsrvConf
             .get("auto_load_instances")
             .and_then(|v| v.as_bool())
             .unwrap_or(true),
+        interlocks: crate::interlock::from_service_config(&service_config.extra_config)
+            .map_err(|e| ModSrvError::ConfigError(format!("{:#}", e)))?,
+        ramp_limits: crate::ramp::from_service_config(&service_config.extra_config)
+            .map_err(|e| ModSrvError::ConfigError(format!("{:#}", e)))?,
+        recipes: crate::recipe::from_service_config(&service_config.extra_config)
+            .map_err(|e| ModSrvError::ConfigError(format!("{:#}", e)))?,
     };
 
     debug!("Config loaded");
@@ -131,16 +162,14 @@ fn validate_configuration(config: &ModsrvConfig) -> Result<()> {
 
     let skip_full_check = std::env::var("SKIP_VALIDATION").is_ok();
     if !skip_full_check {
-        // Basic runtime validation
-        if config.api.port == 0 {
-            error!("Invalid port: 0");
-            return Err(ModSrvError::InvalidConfig(
-                "api.port: Port cannot be 0".to_string(),
-            ));
-        }
-        if config.redis.url.is_empty() {
-            error!("Redis URL missing");
-            return Err(ModSrvError::MissingConfig("redis.url".to_string()));
+        // Same declarative rules as `monarch validate`
+        let result = ModsrvConfig::rule_set()
+            .evaluate(config, ValidationLevel::Runtime)
+            .map_err(|e| ModSrvError::InvalidConfig(e.to_string()))?;
+        if !result.is_valid {
+            let errors = result.errors.join("; ");
+            error!("Invalid config: {}", errors);
+            return Err(ModSrvError::InvalidConfig(errors));
         }
         debug!("Config valid");
     }
@@ -193,13 +222,14 @@ where
 }
 
 /// Setup instance manager and sync instances
-#[cfg(test)]
+#[cfg(any(test, feature = "test-fixtures"))]
 #[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
 pub async fn setup_instance_manager(
     sqlite_pool: &SqlitePool,
     _rtdb: Arc<voltage_rtdb::RedisRtdb>,
     routing_cache: Arc<voltage_rtdb::RoutingCache>,
     product_loader: Arc<ProductLoader>,
+    _allow_empty: bool,
 ) -> Result<Arc<InstanceManager<voltage_rtdb::MemoryRtdb>>> {
     // Create MemoryRtdb for testing (ignore the injected RedisRtdb)
     let rtdb = Arc::new(voltage_rtdb::MemoryRtdb::new());
@@ -215,12 +245,13 @@ pub async fn setup_instance_manager(
     Ok(instance_manager)
 }
 
-#[cfg(not(test))]
+#[cfg(not(any(test, feature = "test-fixtures")))]
 pub async fn setup_instance_manager(
     sqlite_pool: &SqlitePool,
     rtdb: Arc<voltage_rtdb::RedisRtdb>,
     routing_cache: Arc<voltage_rtdb::RoutingCache>,
     product_loader: Arc<ProductLoader>,
+    allow_empty: bool,
 ) -> Result<Arc<InstanceManager<voltage_rtdb::RedisRtdb>>> {
     // RTDB is a pure storage abstraction
     // M2C routing is handled externally by voltage-routing library
@@ -240,10 +271,6 @@ pub async fn setup_instance_manager(
         .unwrap_or(0);
 
     if instance_count == 0 {
-        let allow_empty = std::env::var("MODSRV_ALLOW_EMPTY")
-            .unwrap_or_else(|_| "false".to_string())
-            .to_lowercase()
-            == "true";
         if allow_empty {
             warn!("No instances (ALLOW_EMPTY)");
         } else {
@@ -256,13 +283,7 @@ pub async fn setup_instance_manager(
 
     info!("{} instances loaded", instance_count);
 
-    // Initialize real-time data structures in Redis (M/A Hash + name mappings)
-    // Note: This does NOT sync metadata - only creates empty Hash structures for real-time data
-    if let Err(e) = instance_manager.sync_instances_to_redis().await {
-        error!("Redis init failed: {}", e);
-        // Service continues anyway (structures will be created on-demand)
-    }
-
+    // Redis structures for real-time data are created by `spawn_deferred_startup`
     Ok(instance_manager)
 }
 
@@ -398,10 +419,26 @@ pub async fn refresh_routing_cache(
     Ok(total_routes)
 }
 
+/// `--validate`: load and validate the configuration, cross-check the
+/// stored references, then return without starting anything
+pub async fn validate(service_info: &ServiceInfo, args: &ServiceArgs) -> Result<()> {
+    init_environment(service_info, args)?;
+    load_configuration(service_info).await?;
+    common::service_bootstrap::validate_consistency(service_info, &args.get_db_path("modsrv"))
+        .await
+        .map_err(|e| ModSrvError::InvalidConfig(e.to_string()))?;
+    info!("Validation completed successfully");
+    Ok(())
+}
+
 /// Create application state with all initialized components
-pub async fn create_app_state(service_info: &ServiceInfo) -> Result<Arc<AppState>> {
+pub async fn create_app_state(
+    service_info: &ServiceInfo,
+    args: &ServiceArgs,
+    modsrv_args: &ModsrvArgs,
+) -> Result<Arc<AppState>> {
     // Initialize environment
-    init_environment(service_info)?;
+    init_environment(service_info, args)?;
 
     // Check system requirements
     let requirements = SystemRequirements {
@@ -412,8 +449,28 @@ pub async fn create_app_state(service_info: &ServiceInfo) -> Result<Arc<AppState
     };
     check_system_requirements_with(requirements)?;
 
+    // Wait for the configuration database before loading config
+    let db_path = ServiceArgs::default().get_db_path("modsrv");
+    let dependency_waiter = DependencyWaiter::new(DependencyWaitConfig::from_env());
+    dependency_waiter
+        .wait_for(&[
+            Dependency::ConfigPresent(db_path.clone().into()),
+            Dependency::Database(db_path.into()),
+        ])
+        .await
+        .map_err(|e| ModSrvError::ConfigError(e.to_string()))?;
+
     // Load configuration
     let mut config = load_configuration(service_info).await?;
+    if let Some(url) = &args.redis_url {
+        config.redis.url = url.clone();
+    }
+
+    // Wait for Redis within the same startup deadline
+    dependency_waiter
+        .wait_for(&[Dependency::redis(Some(config.redis.url.clone()))])
+        .await
+        .map_err(|e| ModSrvError::ConfigError(e.to_string()))?;
 
     // Setup Redis using common function
     let (redis_url, redis_client) = setup_redis_with_config(&config).await?;
@@ -424,72 +481,166 @@ pub async fn create_app_state(service_info: &ServiceInfo) -> Result<Arc<AppState
     let sqlite_pool = setup_sqlite().await?;
     let sqlite_client = Some(Arc::new(SqliteClient::from_pool(sqlite_pool.clone())));
 
-    // ============ Phase 1: Load routing configuration from unified database ============
-    debug!("Loading routing config");
-
-    // Validate routing integrity before loading (check for orphan records)
-    validate_routing_integrity(&sqlite_pool).await?;
-
-    let routing_cache = {
-        // Load routing maps directly from the same SQLite pool via shared library
-        let maps = voltage_routing::load_routing_maps(&sqlite_pool).await?;
-
-        // Save lengths before moving maps
-        let c2m_len = maps.c2m.len();
-        let m2c_len = maps.m2c.len();
+    // Resource profile: buffer/batch sizes and startup concurrency
+    common::service_bootstrap::init_resource_profile(service_info, &sqlite_pool).await;
 
-        let cache = Arc::new(voltage_rtdb::RoutingCache::from_maps(
-            maps.c2m, maps.m2c, maps.c2c,
-        ));
-
-        info!("Routes: {} C2M, {} M2C", c2m_len, m2c_len);
-
-        cache
-    };
+    // Feature flags: global/service config plus runtime overrides from Redis
+    common::service_bootstrap::init_feature_flags(
+        service_info,
+        &sqlite_pool,
+        Some(Arc::clone(&redis_client)),
+    )
+    .await;
 
-    // ============ Phase 2: Create RTDB ============
     debug!("Creating RedisRtdb");
     let rtdb = Arc::new(voltage_rtdb::RedisRtdb::from_client(redis_client.clone()));
 
-    // ============ Phase 3: Use the single rtdb for all operations ============
-    // Perform Redis cleanup (uses basic methods, no routing triggered)
-    debug!("Redis cleanup");
-    let cleanup_provider = crate::cleanup_provider::ModsrvCleanupProvider::new(sqlite_pool.clone());
-    match voltage_rtdb::cleanup::cleanup_invalid_keys(&cleanup_provider, rtdb.as_ref()).await {
-        Ok(deleted) => {
-            if deleted > 0 {
-                info!("Redis cleanup: {} keys removed", deleted);
-            }
-        },
-        Err(e) => {
-            warn!("Redis cleanup failed: {}", e);
-        },
+    // Interlocks are checked before every action write in this process
+    let interlocks =
+        InterlockEngine::new(Arc::clone(&rtdb), sqlite_pool.clone(), &config.interlocks)
+            .map_err(|e| ModSrvError::ConfigError(format!("{:#}", e)))?;
+    if !interlocks.is_empty() {
+        info!("Interlocks: {}", interlocks.len());
+        voltage_routing::guard::install(Arc::new(interlocks));
     }
 
+    // Load products (applies pending schema migrations before any table is read)
+    let product_loader = load_products(&config, &sqlite_pool, &rtdb).await?;
+
+    // ============ Phase 1: Routing and name index (independent, run concurrently) ============
+    let started = std::time::Instant::now();
+    let validate = validate_routing_integrity(&sqlite_pool);
+    let load_routing = async {
+        let maps = voltage_routing::load_routing_maps(&sqlite_pool).await?;
+        info!("Routes: {} C2M, {} M2C", maps.c2m.len(), maps.m2c.len());
+        Ok::<_, ModSrvError>(Arc::new(voltage_rtdb::RoutingCache::from_maps(
+            maps.c2m, maps.m2c, maps.c2c,
+        )))
+    };
     // Rebuild instance name index for O(1) name→ID lookups
-    if let Err(e) = rebuild_instance_name_index(&sqlite_pool, rtdb.as_ref()).await {
-        warn!("Name index rebuild failed: {}", e);
-    }
+    let name_index = async {
+        if let Err(e) = rebuild_instance_name_index(&sqlite_pool, rtdb.as_ref()).await {
+            warn!("Name index rebuild failed: {}", e);
+        }
+        Ok(())
+    };
+    let ((), routing_cache, ()) = tokio::try_join!(validate, load_routing, name_index)?;
+    debug!("Routing warm-up: {:?}", started.elapsed());
 
-    // Load products (uses basic methods, no routing triggered)
-    let product_loader = load_products(&config, &sqlite_pool, &rtdb).await?;
+    // Limited setpoints are ramped by a background task (spawned by main)
+    let ramp_engine = RampEngine::new(
+        Arc::clone(&rtdb),
+        sqlite_pool.clone(),
+        Arc::clone(&routing_cache),
+        &config.ramp_limits,
+    )
+    .map_err(|e| ModSrvError::ConfigError(format!("{:#}", e)))?;
+    let ramp_engine = if ramp_engine.is_empty() {
+        None
+    } else {
+        info!("Ramp limits: {}", ramp_engine.len());
+        let ramp_engine = Arc::new(ramp_engine);
+        voltage_routing::ramp::install(ramp_engine.clone());
+        Some(ramp_engine)
+    };
+    let recipe_engine = RecipeEngine::new(
+        Arc::clone(&rtdb),
+        sqlite_pool.clone(),
+        Arc::clone(&routing_cache),
+        &config.recipes,
+    )
+    .map_err(|e| ModSrvError::ConfigError(format!("{:#}", e)))?;
+    let recipe_engine = if recipe_engine.is_empty() {
+        None
+    } else {
+        info!("Recipes: {}", recipe_engine.len());
+        Some(Arc::new(recipe_engine))
+    };
 
-    // Setup instance manager (routing handled externally by voltage-routing library)
+    // ============ Phase 2: Instance manager (routing handled by voltage-routing) ============
     let instance_manager = setup_instance_manager(
         &sqlite_pool,
         rtdb,
-        routing_cache.clone(),
+        routing_cache,
         Arc::clone(&product_loader),
+        modsrv_args.allow_empty,
     )
     .await?;
 
     // Create application state
-    Ok(Arc::new(AppState::new(
-        config,
-        sqlite_client,
-        product_loader,
-        instance_manager,
-    )))
+    let mut state = AppState::new(config, sqlite_client, product_loader, instance_manager);
+    state.ramp_engine = ramp_engine;
+    state.recipe_engine = recipe_engine;
+    match state.subscriptions.load(&sqlite_pool).await {
+        Ok(count) if count > 0 => info!("Data subscriptions: {}", count),
+        Ok(_) => {},
+        Err(e) => warn!("Data subscriptions not loaded: {}", e),
+    }
+    Ok(Arc::new(state))
+}
+
+/// Redis registrations in flight during the startup instance sync
+///
+/// Read from `MODSRV_STARTUP_CONCURRENCY`, defaulting to the resource
+/// profile's `startup_concurrency` (16 on `medium`).
+pub fn startup_concurrency() -> usize {
+    std::env::var("MODSRV_STARTUP_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|n: &usize| *n > 0)
+        .unwrap_or_else(|| common::resource_profile::tuning().startup_concurrency)
+}
+
+/// Run the non-critical startup steps once the API is listening
+///
+/// Redis key cleanup and the full instance sync can take tens of seconds
+/// with hundreds of instances; until the sync finishes, real-time structures
+/// of an instance are created on demand.
+pub fn spawn_deferred_startup(state: &Arc<AppState>) -> tokio::task::JoinHandle<()> {
+    let instance_manager = Arc::clone(&state.instance_manager);
+    let product_loader = Arc::clone(&state.product_loader);
+    let search = Arc::clone(&state.search);
+    tokio::spawn(async move {
+        let started = std::time::Instant::now();
+
+        // Perform Redis cleanup (uses basic methods, no routing triggered)
+        let cleanup_provider =
+            crate::cleanup_provider::ModsrvCleanupProvider::new(instance_manager.pool.clone());
+        match voltage_rtdb::cleanup::cleanup_invalid_keys(
+            &cleanup_provider,
+            instance_manager.rtdb.as_ref(),
+        )
+        .await
+        {
+            Ok(deleted) if deleted > 0 => info!("Redis cleanup: {} keys removed", deleted),
+            Ok(_) => {},
+            Err(e) => warn!("Redis cleanup failed: {}", e),
+        }
+
+        // Initialize real-time data structures in Redis (M/A Hash + name mappings)
+        if let Err(e) = instance_manager
+            .sync_instances_to_redis_with(startup_concurrency())
+            .await
+        {
+            error!("Redis init failed: {}", e);
+        }
+
+        match instance_manager.publish_point_aliases().await {
+            Ok(count) if count > 0 => info!("Point aliases: {} published", count),
+            Ok(_) => {},
+            Err(e) => warn!("Point alias sync failed: {}", e),
+        }
+
+        let indexed = search
+            .rebuild(&instance_manager.pool, &product_loader)
+            .await;
+        info!("Search index: {} entries", indexed);
+
+        info!(
+            "Deferred startup done in {:.1}s",
+            started.elapsed().as_secs_f64()
+        );
+    })
 }
 
 /// Rebuild instance name index from SQLite database
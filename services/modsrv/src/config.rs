@@ -3,7 +3,11 @@
 //! This module contains all modsrv-specific configuration types.
 
 use anyhow::Result;
-use common::{ApiConfig, BaseServiceConfig, RedisConfig, ValidationLevel, ValidationResult};
+use common::validation::{Operand, RuleSet, RuleSpec, ValidationRule};
+use common::{
+    ApiConfig, BaseServiceConfig, ComparisonOperator, RedisConfig, ValidationLevel,
+    ValidationResult,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use voltage_schema_macro::Schema;
@@ -58,6 +62,18 @@ pub struct ModsrvConfig {
     /// Whether to auto-load instances at startup
     #[serde(default = "bool_true")]
     pub auto_load_instances: bool,
+
+    /// Action interlocks per product (see [`crate::interlock`])
+    #[serde(default)]
+    pub interlocks: crate::interlock::InterlockConfig,
+
+    /// Setpoint ramp-rate limits per product (see [`crate::ramp`])
+    #[serde(default)]
+    pub ramp_limits: crate::ramp::RampConfig,
+
+    /// Command recipes per product (see [`crate::recipe`])
+    #[serde(default)]
+    pub recipes: crate::recipe::RecipeConfig,
 }
 
 impl Default for ModsrvConfig {
@@ -79,6 +95,9 @@ impl Default for ModsrvConfig {
             products_path: Some("config/modsrv/products".to_string()),
             instances_path: Some("config/modsrv/instances.yaml".to_string()),
             auto_load_instances: true,
+            interlocks: Default::default(),
+            ramp_limits: Default::default(),
+            recipes: Default::default(),
         }
     }
 }
@@ -113,6 +132,10 @@ struct InstanceRecord {
 
     properties: Option<String>, // JSON TEXT
 
+    // Optimistic concurrency version, incremented on every update (ETag / If-Match)
+    #[column(not_null, default = "1")]
+    version: u32,
+
     #[column(default = "CURRENT_TIMESTAMP")]
     created_at: String, // TIMESTAMP type
 
@@ -125,6 +148,54 @@ struct InstanceRecord {
 /// Instances table SQL (generated by Schema macro)
 pub const INSTANCES_TABLE: &str = InstanceRecord::CREATE_TABLE_SQL;
 
+/// Instance tags table record
+/// Arbitrary key/value labels (site, feeder, rack) attached to instances
+#[allow(dead_code)]
+#[derive(Schema)]
+#[table(name = "instance_tags", suffix = "PRIMARY KEY (instance_id, tag_key)")]
+struct InstanceTagRecord {
+    #[column(not_null, references = "instances(instance_id)", on_delete = "CASCADE")]
+    instance_id: u32,
+
+    #[column(not_null)]
+    tag_key: String,
+
+    #[column(not_null)]
+    tag_value: String,
+}
+
+/// Instance tags table SQL (generated by Schema macro)
+pub const INSTANCE_TAGS_TABLE: &str = InstanceTagRecord::CREATE_TABLE_SQL;
+
+/// Point aliases table record
+/// Stable external identifiers (customer codes, KKS, RDS-PP) for channel/instance points
+#[allow(dead_code)]
+#[derive(Schema)]
+#[table(name = "point_aliases", suffix = "UNIQUE(scheme, address)")]
+struct PointAliasRecord {
+    #[column(primary_key)]
+    alias: String,
+
+    // Naming scheme the alias belongs to (custom, kks, rds-pp, ...)
+    #[column(not_null, default = "custom")]
+    scheme: String,
+
+    // Internal address: `inst:{id}:{M|A}:{point}` or `comsrv:{id}:{T|S|C|A}:{point}`
+    #[column(not_null)]
+    address: String,
+
+    description: Option<String>,
+
+    #[column(default = "CURRENT_TIMESTAMP")]
+    created_at: String, // TIMESTAMP type
+
+    #[column(default = "CURRENT_TIMESTAMP")]
+    updated_at: String, // TIMESTAMP type
+}
+
+/// Point aliases table SQL (generated by Schema macro)
+pub const POINT_ALIASES_TABLE: &str = PointAliasRecord::CREATE_TABLE_SQL;
+
 /// Measurement routing table record
 /// Routes telemetry/signal points to measurement points (T/S → M)
 #[allow(dead_code)]
@@ -155,6 +226,15 @@ struct MeasurementRoutingRecord {
 
     description: Option<String>,
 
+    /// Report-by-exception absolute deadband (NULL = publish every update)
+    deadband: Option<f64>,
+
+    /// Report-by-exception deadband in percent of the last published value
+    deadband_pct: Option<f64>,
+
+    /// Forced refresh interval in seconds under report-by-exception (0 = never)
+    refresh_secs: Option<u32>,
+
     #[column(default = "true")]
     enabled: bool,
 
@@ -390,6 +470,10 @@ pub struct InstanceCore {
     /// Instance properties (key-value pairs supporting multiple types)
     #[serde(default)]
     pub properties: HashMap<String, serde_json::Value>,
+
+    /// Instance tags for grouping and search (e.g., feeder -> F1)
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
 }
 
 /// Instance definition for runtime devices
@@ -410,6 +494,10 @@ pub struct Instance {
     /// Creation timestamp
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Record version for optimistic concurrency (exposed as ETag)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
 }
 
 impl Instance {
@@ -486,7 +574,7 @@ impl common::ConfigValidator for ModsrvConfig {
     }
 
     fn validate_business(&self) -> Result<ValidationResult> {
-        let mut result = ValidationResult::new(ValidationLevel::Business);
+        let mut result = Self::rule_set().evaluate(self, ValidationLevel::Business)?;
 
         // Business rule: Warn about auto-loading instances
         if !self.auto_load_instances {
@@ -523,6 +611,51 @@ impl common::ConfigValidator for ModsrvConfig {
     }
 }
 
+impl ModsrvConfig {
+    /// Declarative rules shared by monarch validate and service startup
+    pub fn rule_set() -> RuleSet {
+        RuleSet::new("modsrv")
+            .rule(
+                RuleSpec::from(ValidationRule::Compare {
+                    left: "api.port".to_string(),
+                    op: ComparisonOperator::GreaterThan,
+                    right: Operand::Value(0.into()),
+                })
+                .message("api.port: Port cannot be 0"),
+            )
+            .rule(
+                RuleSpec::from(ValidationRule::Required {
+                    path: "redis.url".to_string(),
+                })
+                .message("redis.url: Redis URL missing"),
+            )
+    }
+}
+
+/// Declarative rules for instance channel routing
+///
+/// Evaluated against `{ "channels": [...], "routing": [...] }` where routing rows
+/// carry the owning `instance` name next to the `channel_routing.csv` columns.
+pub fn routing_rule_set() -> RuleSet {
+    RuleSet::new("routing")
+        .rule(
+            RuleSpec::from(ValidationRule::Reference {
+                path: "channel_id".to_string(),
+                target: "channels[*].id".to_string(),
+            })
+            .each("routing[*]")
+            .message("Instance {instance}: routing references unknown channel {value}"),
+        )
+        .rule(
+            RuleSpec::from(ValidationRule::OneOf {
+                path: "channel_type".to_string(),
+                values: vec!["T".into(), "S".into(), "C".into(), "A".into()],
+            })
+            .each("routing[*]")
+            .message("Instance {instance}: invalid channel_type '{value}', must be T, S, C, or A"),
+        )
+}
+
 /// Type alias for backward compatibility - use GenericValidator directly for new code
 pub type ModsrvValidator = common::GenericValidator<ModsrvConfig>;
 
@@ -618,6 +751,41 @@ impl InstanceRedisKeys {
         format!("inst:{}:name", instance_id)
     }
 
+    /// Helper: instance tags hash key `inst:{instance_id}:tags`
+    pub fn instance_tags(instance_id: u32) -> String {
+        format!("inst:{}:tags", instance_id)
+    }
+
+    /// Helper: tag index set key `inst:tag:{tag_key}:{tag_value}`
+    ///
+    /// Set members are instance IDs, usable directly as aggregation targets.
+    pub fn tag_index(tag_key: &str, tag_value: &str) -> String {
+        voltage_model::KeySpaceConfig::production_cached()
+            .instance_tag_index_key(tag_key, tag_value)
+    }
+
+    /// Helper: point alias hash key `modsrv:alias` (alias → address)
+    pub fn point_aliases() -> &'static str {
+        "modsrv:alias"
+    }
+
+    /// Helper: reverse alias hash key `modsrv:alias:by_point` (address → alias)
+    ///
+    /// Holds one alias per address, taken from the first scheme in name order.
+    pub fn alias_by_point() -> &'static str {
+        "modsrv:alias:by_point"
+    }
+
+    /// Helper: per-scheme reverse alias hash key `modsrv:alias:by_point:{scheme}`
+    pub fn alias_by_point_scheme(scheme: &str) -> String {
+        format!("modsrv:alias:by_point:{}", scheme)
+    }
+
+    /// Helper: alias scheme set key `modsrv:alias:schemes`
+    pub fn alias_schemes() -> &'static str {
+        "modsrv:alias:schemes"
+    }
+
     /// Helper: instance info key `instance:{instance_id}:info`
     pub fn instance_info(instance_id: u32) -> String {
         format!("instance:{}:info", instance_id)
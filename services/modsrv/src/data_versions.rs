@@ -0,0 +1,249 @@
+//! Change counters for instance real-time data
+//!
+//! `inst:{id}:M` / `inst:{id}:A` are written directly by comsrv routing, the
+//! rule engine and modsrv itself, so there is no writer-side counter to read.
+//! Instead every `GET /api/instances/{id}/data` diffs the hash it just read
+//! against the previous snapshot: points whose value changed get a new version
+//! from a process-wide counter. The counter starts at the current Unix time in
+//! milliseconds, so versions handed out before a restart stay below the new
+//! ones and a stale `since` simply falls back to a full response.
+
+use dashmap::DashMap;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Last seen value of one point
+struct PointState {
+    /// `None` once the point disappeared from the hash
+    value: Option<Value>,
+    version: u64,
+}
+
+/// Snapshot of one section (measurements / actions / overrides) of an instance
+struct SectionState {
+    /// Version of the first observation; older `since` values cannot be answered
+    baseline: u64,
+    /// Highest point version in the section
+    version: u64,
+    points: HashMap<String, PointState>,
+}
+
+/// Points of a section changed after a given version
+#[derive(Debug, Default, PartialEq)]
+pub struct SectionDelta {
+    pub changed: Map<String, Value>,
+    pub removed: Vec<String>,
+}
+
+/// Result of observing a section
+#[derive(Debug)]
+pub struct Observation {
+    /// Current section version
+    pub version: u64,
+    /// Changes since the requested version; `None` when no delta was requested
+    /// or the requested version is outside the tracked history
+    pub delta: Option<SectionDelta>,
+}
+
+/// Per-instance, per-section change tracker
+pub struct DataVersions {
+    next: AtomicU64,
+    sections: DashMap<(u32, String), SectionState>,
+}
+
+impl Default for DataVersions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DataVersions {
+    pub fn new() -> Self {
+        Self::starting_at(chrono::Utc::now().timestamp_millis().max(0) as u64)
+    }
+
+    /// Tracker whose first version is `start + 1`
+    pub fn starting_at(start: u64) -> Self {
+        Self {
+            next: AtomicU64::new(start),
+            sections: DashMap::new(),
+        }
+    }
+
+    /// Record the current contents of a section and return its version
+    ///
+    /// With `since`, also returns the points changed after that version, provided
+    /// it lies within the tracked history of the section.
+    pub fn observe(
+        &self,
+        instance_id: u32,
+        section: &str,
+        current: &Map<String, Value>,
+        since: Option<u64>,
+    ) -> Observation {
+        let mut fresh = false;
+        let mut entry = self
+            .sections
+            .entry((instance_id, section.to_string()))
+            .or_insert_with(|| {
+                fresh = true;
+                let version = self.bump();
+                SectionState {
+                    baseline: version,
+                    version,
+                    points: HashMap::new(),
+                }
+            });
+        let state = entry.value_mut();
+
+        // One version for everything that changed in this read
+        let mut new_version = None;
+        let mut next_version = || *new_version.get_or_insert_with(|| self.bump());
+
+        for (point, value) in current {
+            match state.points.get_mut(point) {
+                Some(known) if known.value.as_ref() == Some(value) => {},
+                Some(known) => {
+                    known.value = Some(value.clone());
+                    known.version = next_version();
+                },
+                None => {
+                    // On the first read everything belongs to the baseline
+                    let version = if fresh {
+                        state.baseline
+                    } else {
+                        next_version()
+                    };
+                    state.points.insert(
+                        point.clone(),
+                        PointState {
+                            value: Some(value.clone()),
+                            version,
+                        },
+                    );
+                },
+            }
+        }
+        for (point, known) in state.points.iter_mut() {
+            if known.value.is_some() && !current.contains_key(point) {
+                known.value = None;
+                known.version = next_version();
+            }
+        }
+        if let Some(version) = new_version {
+            state.version = version;
+        }
+
+        let delta = since
+            .filter(|since| (state.baseline..=state.version).contains(since))
+            .map(|since| {
+                let mut delta = SectionDelta::default();
+                for (point, known) in &state.points {
+                    if known.version <= since {
+                        continue;
+                    }
+                    match &known.value {
+                        Some(value) => {
+                            delta.changed.insert(point.clone(), value.clone());
+                        },
+                        None => delta.removed.push(point.clone()),
+                    }
+                }
+                delta.removed.sort();
+                delta
+            });
+
+        Observation {
+            version: state.version,
+            delta,
+        }
+    }
+
+    /// Drop all snapshots of an instance (e.g. after deletion)
+    pub fn forget(&self, instance_id: u32) {
+        self.sections.retain(|(id, _), _| *id != instance_id);
+    }
+
+    fn bump(&self) -> u64 {
+        self.next.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // json! macro
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn points(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().unwrap()
+    }
+
+    #[test]
+    fn test_version_only_moves_on_change() {
+        let versions = DataVersions::starting_at(100);
+        let data = points(json!({"1": "10.0", "2": "20.0"}));
+
+        let first = versions.observe(1, "measurements", &data, None);
+        assert_eq!(first.version, 101);
+        assert_eq!(
+            versions.observe(1, "measurements", &data, None).version,
+            101
+        );
+
+        let changed = points(json!({"1": "10.0", "2": "21.0"}));
+        assert_eq!(
+            versions.observe(1, "measurements", &changed, None).version,
+            102
+        );
+        // Other sections and instances are independent
+        assert_eq!(versions.observe(1, "actions", &data, None).version, 103);
+        assert_eq!(
+            versions.observe(1, "measurements", &changed, None).version,
+            102
+        );
+    }
+
+    #[test]
+    fn test_delta_since_version() {
+        let versions = DataVersions::starting_at(0);
+        let base = versions.observe(
+            7,
+            "measurements",
+            &points(json!({"1": "1", "2": "2", "3": "3"})),
+            None,
+        );
+
+        let next = points(json!({"1": "1", "2": "5", "4": "4"}));
+        let obs = versions.observe(7, "measurements", &next, Some(base.version));
+        let delta = obs.delta.unwrap();
+        assert_eq!(delta.changed, points(json!({"2": "5", "4": "4"})));
+        assert_eq!(delta.removed, vec!["3".to_string()]);
+
+        // Nothing changed since the latest version
+        let obs = versions.observe(7, "measurements", &next, Some(obs.version));
+        assert_eq!(obs.delta.unwrap(), SectionDelta::default());
+    }
+
+    #[test]
+    fn test_delta_outside_history_is_full() {
+        let versions = DataVersions::starting_at(1_000);
+        let data = points(json!({"1": "1"}));
+        // Version from before this tracker started (e.g. previous process)
+        assert!(versions
+            .observe(1, "actions", &data, Some(5))
+            .delta
+            .is_none());
+        // Version from the future
+        assert!(versions
+            .observe(1, "actions", &data, Some(9_999))
+            .delta
+            .is_none());
+
+        versions.forget(1);
+        let obs = versions.observe(1, "actions", &data, Some(1_001));
+        assert!(obs.delta.is_none());
+        assert_eq!(obs.version, 1_002);
+    }
+}
@@ -45,6 +45,21 @@ pub enum ModSrvError {
     #[error("Instance already exists: {0}")]
     InstanceExists(String),
 
+    #[error("Version mismatch: {0}")]
+    VersionMismatch(String),
+
+    #[error("Point overridden: {0}")]
+    PointOverridden(String),
+
+    #[error("Constraint violation: {0}")]
+    ConstraintViolation(String),
+
+    #[error("Point alias not found: {0}")]
+    AliasNotFound(String),
+
+    #[error("Data subscription not found: {0}")]
+    SubscriptionNotFound(String),
+
     // ============================================================================
     // Rule Engine Errors
     // ============================================================================
@@ -66,6 +81,15 @@ pub enum ModSrvError {
     #[error("Scheduler error: {0}")]
     SchedulerError(String),
 
+    // ============================================================================
+    // Recipe Errors
+    // ============================================================================
+    #[error("Recipe not found: {0}")]
+    RecipeNotFound(String),
+
+    #[error("Recipe already running: {0}")]
+    RecipeRunning(String),
+
     // ============================================================================
     // Validation Errors
     // ============================================================================
@@ -107,6 +131,11 @@ impl errors::VoltageErrorTrait for ModSrvError {
             // Instance
             Self::InstanceNotFound(_) => "MODSRV_INSTANCE_NOT_FOUND",
             Self::InstanceExists(_) => "MODSRV_INSTANCE_EXISTS",
+            Self::VersionMismatch(_) => "MODSRV_VERSION_MISMATCH",
+            Self::PointOverridden(_) => "MODSRV_POINT_OVERRIDDEN",
+            Self::ConstraintViolation(_) => "MODSRV_CONSTRAINT_VIOLATION",
+            Self::AliasNotFound(_) => "MODSRV_ALIAS_NOT_FOUND",
+            Self::SubscriptionNotFound(_) => "MODSRV_SUBSCRIPTION_NOT_FOUND",
 
             // Rule Engine
             Self::RuleNotFound(_) => "MODSRV_RULE_NOT_FOUND",
@@ -116,6 +145,10 @@ impl errors::VoltageErrorTrait for ModSrvError {
             Self::ExecutionError(_) => "MODSRV_EXECUTION_ERROR",
             Self::SchedulerError(_) => "MODSRV_SCHEDULER_ERROR",
 
+            // Recipes
+            Self::RecipeNotFound(_) => "MODSRV_RECIPE_NOT_FOUND",
+            Self::RecipeRunning(_) => "MODSRV_RECIPE_RUNNING",
+
             // Validation
             Self::InvalidData(_) => "MODSRV_INVALID_DATA",
             Self::InvalidRouting(_) => "MODSRV_INVALID_ROUTING",
@@ -141,10 +174,21 @@ impl errors::VoltageErrorTrait for ModSrvError {
             Self::DatabaseError(_) | Self::RedisError(_) => ErrorCategory::Database,
 
             // NotFound
-            Self::InstanceNotFound(_) | Self::RuleNotFound(_) => ErrorCategory::NotFound,
+            Self::InstanceNotFound(_)
+            | Self::AliasNotFound(_)
+            | Self::SubscriptionNotFound(_)
+            | Self::RuleNotFound(_)
+            | Self::RecipeNotFound(_) => ErrorCategory::NotFound,
 
             // Conflict
-            Self::InstanceExists(_) | Self::RuleExists(_) => ErrorCategory::Conflict,
+            Self::InstanceExists(_)
+            | Self::RuleExists(_)
+            | Self::PointOverridden(_)
+            | Self::ConstraintViolation(_)
+            | Self::RecipeRunning(_) => ErrorCategory::Conflict,
+
+            // Optimistic concurrency (If-Match)
+            Self::VersionMismatch(_) => ErrorCategory::PreconditionFailed,
 
             // Validation
             Self::InvalidData(_)
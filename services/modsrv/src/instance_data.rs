@@ -6,11 +6,21 @@
 #![allow(clippy::disallowed_methods)] // json! macro used in multiple functions
 
 use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
 use std::collections::HashMap;
 use tracing::debug;
 
 use crate::redis_state;
 
+/// Instance data together with its change version
+#[derive(Debug)]
+pub struct VersionedInstanceData {
+    /// Highest version over the returned sections (used as ETag)
+    pub version: u64,
+    /// Full data, or the delta document when `since` could be answered
+    pub data: Value,
+}
+
 use super::instance_manager::InstanceManager;
 use voltage_rtdb::Rtdb;
 
@@ -26,6 +36,87 @@ impl<R: Rtdb + 'static> InstanceManager<R> {
         Ok(data)
     }
 
+    /// Get instance real-time data with its change version
+    ///
+    /// With `since`, returns only the points changed after that version:
+    /// `{"delta": true, "since", "version", "<section>": {changed points},
+    /// "removed": {"<section>": [point ids]}}`. When `since` is outside the
+    /// tracked history (e.g. from before a restart) the full data is returned.
+    pub async fn get_instance_data_versioned(
+        &self,
+        instance_id: u32,
+        data_type: Option<&str>,
+        since: Option<u64>,
+    ) -> Result<VersionedInstanceData> {
+        let data =
+            redis_state::get_instance_data(self.rtdb.as_ref(), instance_id, data_type).await?;
+
+        // Filtered reads return a flat map of one section
+        let sections: Vec<(&str, Map<String, Value>)> = match (data_type, data) {
+            (Some("measurement"), Value::Object(points)) => vec![("measurements", points)],
+            (Some("action"), Value::Object(points)) => vec![("actions", points)],
+            (_, Value::Object(mut all)) => ["measurements", "actions", "overrides"]
+                .into_iter()
+                .map(|section| match all.remove(section) {
+                    Some(Value::Object(points)) => (section, points),
+                    _ => (section, Map::new()),
+                })
+                .collect(),
+            (_, other) => {
+                return Err(anyhow!("Unexpected instance data shape: {}", other));
+            },
+        };
+
+        let mut version = 0;
+        let mut changed = Map::new();
+        let mut removed = Map::new();
+        let mut complete_delta = since.is_some();
+        for (section, points) in &sections {
+            let observed = self
+                .data_versions
+                .observe(instance_id, section, points, since);
+            version = version.max(observed.version);
+            match observed.delta {
+                Some(delta) => {
+                    changed.insert(section.to_string(), Value::Object(delta.changed));
+                    if !delta.removed.is_empty() {
+                        removed.insert(section.to_string(), serde_json::json!(delta.removed));
+                    }
+                },
+                None => complete_delta = false,
+            }
+        }
+
+        let data = if complete_delta {
+            debug!(
+                "Instance {} data delta since {}: version {}",
+                instance_id,
+                since.unwrap_or_default(),
+                version
+            );
+            changed.insert("delta".to_string(), Value::Bool(true));
+            changed.insert("since".to_string(), serde_json::json!(since));
+            changed.insert("version".to_string(), serde_json::json!(version));
+            changed.insert("removed".to_string(), Value::Object(removed));
+            Value::Object(changed)
+        } else if data_type.is_some() {
+            sections
+                .into_iter()
+                .next()
+                .map(|(_, points)| Value::Object(points))
+                .unwrap_or_default()
+        } else {
+            Value::Object(
+                sections
+                    .into_iter()
+                    .map(|(section, points)| (section.to_string(), Value::Object(points)))
+                    .collect(),
+            )
+        };
+
+        Ok(VersionedInstanceData { version, data })
+    }
+
     /// Get instance point definitions from Redis (metadata, not real-time values)
     /// Load instance points with routing configuration (runtime merge)
     ///
@@ -379,7 +470,26 @@ impl<R: Rtdb + 'static> InstanceManager<R> {
         )
         .await?;
 
-        if outcome.routed {
+        if outcome.is_overridden() {
+            if let Some(active) = voltage_routing::manual_override::get_override(
+                self.rtdb.as_ref(),
+                instance_id,
+                action_id,
+            )
+            .await?
+            {
+                return Err(voltage_routing::OverrideActive(active).into());
+            }
+        }
+
+        if outcome.is_ramping() {
+            debug!(
+                "Action {} of instance {} ramping ({})",
+                action_id,
+                instance_id,
+                outcome.route_result.as_deref().unwrap_or_default()
+            );
+        } else if outcome.routed {
             debug!(
                 "Action {} routed to channel {} for instance {}",
                 action_id,
@@ -5,14 +5,16 @@
 //! - `instance_routing.rs` - Routing CRUD operations
 //! - `instance_redis_sync.rs` - Redis synchronization
 //! - `instance_data.rs` - Data loading and querying
+//! - `instance_tags.rs` - Tag storage and tag-based filtering
+//! - `instance_naming.rs` - Naming policy checks
 
 use crate::config::InstanceRedisKeys;
+use crate::data_versions::DataVersions;
 use anyhow::{anyhow, Result};
 use sqlx::SqlitePool;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{error, info, warn};
-use voltage_model::validate_instance_name;
 use voltage_rtdb::Rtdb;
 
 use crate::product_loader::{CreateInstanceRequest, Instance, ProductLoader};
@@ -44,19 +46,32 @@ fn build_instance_from_row(row: (u32, String, String, Option<String>, String)) -
             instance_name,
             product_name,
             properties,
+            tags: HashMap::new(),
         },
         measurement_mappings: None,
         action_mappings: None,
         created_at: None,
+        version: None,
     })
 }
 
+/// Outcome of an optimistic concurrency version bump
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionBump {
+    /// Version incremented; holds the new version
+    Bumped(u32),
+    /// Expected version was stale; holds the current version
+    Mismatch { current: u32 },
+}
+
 /// Instance Manager handles runtime instance lifecycle
 pub struct InstanceManager<R: Rtdb> {
     pub pool: SqlitePool,
     pub rtdb: Arc<R>,
     pub(crate) routing_cache: Arc<voltage_rtdb::RoutingCache>,
     pub(crate) product_loader: Arc<ProductLoader>,
+    /// Change counters behind the instance data ETag / `since` delta
+    pub(crate) data_versions: DataVersions,
 }
 
 impl<R: Rtdb + 'static> InstanceManager<R> {
@@ -71,6 +86,7 @@ impl<R: Rtdb + 'static> InstanceManager<R> {
             rtdb,
             routing_cache,
             product_loader,
+            data_versions: DataVersions::new(),
         }
     }
 
@@ -98,14 +114,12 @@ impl<R: Rtdb + 'static> InstanceManager<R> {
             req.instance_name, instance_id, req.product_name
         );
 
-        // 1. Validate instance name format
-        if let Err(e) = validate_instance_name(&req.instance_name) {
-            return Err(anyhow!("Invalid instance name: {}", e));
-        }
+        // 1. Validate instance name against the naming policy
+        // Note: Exact-name uniqueness is still enforced by the database UNIQUE constraint;
+        // the policy check adds case-insensitive and cross-kind uniqueness when configured.
+        self.check_instance_name(&req.instance_name, None).await?;
 
         // 2. Verify product exists (products are compile-time constants)
-        // Note: Name uniqueness is enforced by database UNIQUE constraint.
-        // We rely on the constraint rather than check-then-act to avoid race conditions.
         let product = self.product_loader.get_product(&req.product_name)?;
 
         // 3. Begin transaction for atomic creation
@@ -203,10 +217,12 @@ impl<R: Rtdb + 'static> InstanceManager<R> {
                 instance_name: req.instance_name,
                 product_name: req.product_name,
                 properties: req.properties,
+                tags: HashMap::new(),
             },
             measurement_mappings: Some(measurement_point_routings),
             action_mappings: Some(action_point_routings),
             created_at: Some(chrono::Utc::now()),
+            version: Some(1),
         })
     }
 
@@ -234,75 +250,80 @@ impl<R: Rtdb + 'static> InstanceManager<R> {
 
         let rows = query.fetch_all(&self.pool).await?;
 
-        let instances = rows
+        let mut instances = rows
             .into_iter()
             .map(build_instance_from_row)
             .collect::<Result<Vec<_>>>()?;
+        self.attach_tags(&mut instances).await?;
 
         Ok(instances)
     }
 
     /// List instances with pagination
+    ///
+    /// Optional filters: product name, and tags (instances must carry every tag).
     pub async fn list_instances_paginated(
         &self,
         product_name: Option<&str>,
+        tags: &[(String, String)],
         page: u32,
         page_size: u32,
     ) -> Result<(u32, Vec<Instance>)> {
         // Calculate offset
         let offset = (page - 1) * page_size;
 
-        // Get total count
-        let count_query = if let Some(pname) = product_name {
-            sqlx::query_as::<_, (i64,)>(
-                r#"
-                SELECT COUNT(*) FROM instances WHERE product_name = ?
-                "#,
-            )
-            .bind(pname)
+        // Build WHERE clause shared by count and data queries
+        let mut conditions = Vec::new();
+        if product_name.is_some() {
+            conditions.push("product_name = ?");
+        }
+        conditions.extend(crate::instance_tags::tag_filter_conditions(tags.len()));
+        let where_clause = if conditions.is_empty() {
+            String::new()
         } else {
-            sqlx::query_as::<_, (i64,)>(
-                r#"
-                SELECT COUNT(*) FROM instances
-                "#,
-            )
+            format!("WHERE {}", conditions.join(" AND "))
         };
 
+        // Get total count
+        let count_sql = format!("SELECT COUNT(*) FROM instances {}", where_clause);
+        let mut count_query = sqlx::query_as::<_, (i64,)>(&count_sql);
+        if let Some(pname) = product_name {
+            count_query = count_query.bind(pname);
+        }
+        for (tag_key, tag_value) in tags {
+            count_query = count_query.bind(tag_key).bind(tag_value);
+        }
+
         let (total,) = count_query.fetch_one(&self.pool).await?;
 
         // Get paginated data
-        let data_query = if let Some(pname) = product_name {
-            sqlx::query_as::<_, (u32, String, String, Option<String>, String)>(
-                r#"
-                SELECT instance_id, instance_name, product_name, properties, created_at
-                FROM instances
-                WHERE product_name = ?
-                ORDER BY instance_id ASC
-                LIMIT ? OFFSET ?
-                "#,
-            )
-            .bind(pname)
-            .bind(page_size as i64)
-            .bind(offset as i64)
-        } else {
-            sqlx::query_as::<_, (u32, String, String, Option<String>, String)>(
-                r#"
-                SELECT instance_id, instance_name, product_name, properties, created_at
-                FROM instances
-                ORDER BY instance_id ASC
-                LIMIT ? OFFSET ?
-                "#,
-            )
-            .bind(page_size as i64)
-            .bind(offset as i64)
-        };
+        let data_sql = format!(
+            r#"
+            SELECT instance_id, instance_name, product_name, properties, created_at
+            FROM instances
+            {}
+            ORDER BY instance_id ASC
+            LIMIT ? OFFSET ?
+            "#,
+            where_clause
+        );
+        let mut data_query =
+            sqlx::query_as::<_, (u32, String, String, Option<String>, String)>(&data_sql);
+        if let Some(pname) = product_name {
+            data_query = data_query.bind(pname);
+        }
+        for (tag_key, tag_value) in tags {
+            data_query = data_query.bind(tag_key).bind(tag_value);
+        }
+        let data_query = data_query.bind(page_size as i64).bind(offset as i64);
 
         let rows = data_query.fetch_all(&self.pool).await?;
 
-        let instances = rows
+        let mut instances = rows
             .into_iter()
             .map(build_instance_from_row)
             .collect::<Result<Vec<_>>>()?;
+        self.attach_tags(&mut instances).await?;
 
         let total_u32 = u32::try_from(total).unwrap_or(u32::MAX);
         Ok((total_u32, instances))
@@ -378,29 +399,23 @@ impl<R: Rtdb + 'static> InstanceManager<R> {
                 .await?
             };
 
-        let instances = rows
+        let mut instances = rows
             .into_iter()
             .map(build_instance_from_row)
             .collect::<Result<Vec<_>>>()?;
+        self.attach_tags(&mut instances).await?;
 
         let total_u32 = u32::try_from(total).unwrap_or(u32::MAX);
         Ok((total_u32, instances))
     }
 
     /// Rename an instance
+    ///
+    /// Fails with a `ModSrvError` when the new name violates the naming policy
+    /// or is already taken.
     pub async fn rename_instance(&self, instance_id: u32, new_name: &str) -> Result<()> {
-        // Check if new name already exists
-        let (count,): (i64,) = sqlx::query_as(
-            r#"SELECT COUNT(*) FROM instances WHERE instance_name = ? AND instance_id != ?"#,
-        )
-        .bind(new_name)
-        .bind(instance_id as i32)
-        .fetch_one(&self.pool)
-        .await?;
-
-        if count > 0 {
-            anyhow::bail!("Instance name '{}' already exists, cannot rename", new_name);
-        }
+        self.check_instance_name(new_name, Some(instance_id))
+            .await?;
 
         // Start transaction
         let mut tx = self.pool.begin().await?;
@@ -437,6 +452,44 @@ impl<R: Rtdb + 'static> InstanceManager<R> {
         Ok(())
     }
 
+    /// Increment the instance version, optionally only if it still matches `expected`
+    ///
+    /// The compare and increment happen in a single UPDATE so two concurrent
+    /// editors holding the same version cannot both succeed.
+    pub async fn bump_instance_version(
+        &self,
+        instance_id: u32,
+        expected: Option<u32>,
+    ) -> Result<VersionBump> {
+        let result = match expected {
+            Some(version) => sqlx::query(
+                "UPDATE instances SET version = version + 1 WHERE instance_id = ? AND version = ?",
+            )
+            .bind(instance_id as i32)
+            .bind(version as i64)
+            .execute(&self.pool)
+            .await?,
+            None => {
+                sqlx::query("UPDATE instances SET version = version + 1 WHERE instance_id = ?")
+                    .bind(instance_id as i32)
+                    .execute(&self.pool)
+                    .await?
+            },
+        };
+
+        let current: Option<u32> =
+            sqlx::query_scalar("SELECT version FROM instances WHERE instance_id = ?")
+                .bind(instance_id as i32)
+                .fetch_optional(&self.pool)
+                .await?;
+        let current = current.ok_or_else(|| anyhow!("Instance not found: {}", instance_id))?;
+
+        if result.rows_affected() == 0 {
+            return Ok(VersionBump::Mismatch { current });
+        }
+        Ok(VersionBump::Bumped(current))
+    }
+
     /// Get next available instance ID
     pub async fn get_next_instance_id(&self) -> Result<u32> {
         let row = sqlx::query_as::<_, (Option<i32>,)>("SELECT MAX(instance_id) FROM instances")
@@ -457,9 +510,9 @@ impl<R: Rtdb + 'static> InstanceManager<R> {
 
     /// Get instance by ID
     pub async fn get_instance(&self, instance_id: u32) -> Result<Instance> {
-        let row = sqlx::query_as::<_, (String, String, Option<String>, String)>(
+        let row = sqlx::query_as::<_, (String, String, Option<String>, String, u32)>(
             r#"
-            SELECT instance_name, product_name, properties, created_at
+            SELECT instance_name, product_name, properties, created_at, version
             FROM instances
             WHERE instance_id = ?
             "#,
@@ -470,7 +523,7 @@ impl<R: Rtdb + 'static> InstanceManager<R> {
 
         let row = row.ok_or_else(|| anyhow!("Instance not found: {}", instance_id))?;
 
-        let (instance_name, product_name, properties_json, _created_at) = row;
+        let (instance_name, product_name, properties_json, _created_at, version) = row;
         let properties = parse_properties_json(properties_json, instance_id)?;
 
         // Load point routings from routing tables and generate Redis keys dynamically
@@ -517,10 +570,12 @@ impl<R: Rtdb + 'static> InstanceManager<R> {
                 instance_name,
                 product_name,
                 properties,
+                tags: self.get_instance_tags(instance_id).await?,
             },
             measurement_mappings: Some(measurement_point_routings),
             action_mappings: Some(action_point_routings),
             created_at: None,
+            version: Some(version),
         })
     }
 
@@ -629,6 +684,8 @@ impl<R: Rtdb + 'static> InstanceManager<R> {
             return Err(anyhow!("Database transaction commit failed: {}", e));
         }
 
+        self.data_versions.forget(instance_id);
+
         // 5. Best effort remove from Redis (after commit, allow failure)
         if let Err(e) = self
             .unregister_instance_from_redis(instance_id, &instance_name)
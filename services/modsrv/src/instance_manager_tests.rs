@@ -563,3 +563,300 @@ async fn test_execute_action_negative_values() {
     let stored = rtdb.hash_get(&action_key, "1").await.unwrap().unwrap();
     assert_eq!(stored.as_ref(), b"-50.5");
 }
+
+// ==================== Instance Tags ====================
+
+#[tokio::test]
+async fn test_instance_tags_filter_and_redis_index() {
+    let (_temp_dir, pool) = create_test_database().await;
+    let product_loader = create_test_product_loader(pool.clone());
+    let rtdb = create_test_rtdb();
+    let routing_cache = Arc::new(voltage_rtdb::RoutingCache::new());
+    let manager = InstanceManager::new(pool.clone(), rtdb.clone(), routing_cache, product_loader);
+
+    for (id, name) in [(1001, "battery_f1"), (1002, "battery_f2")] {
+        manager
+            .create_instance(CreateInstanceRequest {
+                instance_id: id,
+                instance_name: name.to_string(),
+                product_name: "Battery".to_string(),
+                properties: HashMap::new(),
+            })
+            .await
+            .unwrap();
+    }
+
+    let tags_f1 = HashMap::from([
+        ("site".to_string(), "S1".to_string()),
+        ("feeder".to_string(), "F1".to_string()),
+    ]);
+    let tags_f2 = HashMap::from([
+        ("site".to_string(), "S1".to_string()),
+        ("feeder".to_string(), "F2".to_string()),
+    ]);
+    manager.set_instance_tags(1001, &tags_f1).await.unwrap();
+    manager.set_instance_tags(1002, &tags_f2).await.unwrap();
+
+    // Filter by one and by multiple tags
+    let site = vec![("site".to_string(), "S1".to_string())];
+    let (total, _) = manager
+        .list_instances_paginated(None, &site, 1, 20)
+        .await
+        .unwrap();
+    assert_eq!(total, 2);
+
+    let feeder = crate::instance_tags::parse_tag_filter("site:S1,feeder:F1").unwrap();
+    let (total, instances) = manager
+        .list_instances_paginated(None, &feeder, 1, 20)
+        .await
+        .unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(instances[0].instance_id(), 1001);
+    assert_eq!(instances[0].core.tags, tags_f1);
+    assert_eq!(
+        manager.instance_ids_by_tags(&feeder).await.unwrap(),
+        vec![1001]
+    );
+
+    // Redis index sets follow tag changes
+    use voltage_rtdb::Rtdb;
+    let f1_index = InstanceRedisKeys::tag_index("feeder", "F1");
+    assert_eq!(rtdb.smembers(&f1_index).await.unwrap(), vec!["1001"]);
+
+    manager.set_instance_tags(1001, &tags_f2).await.unwrap();
+    assert!(rtdb.smembers(&f1_index).await.unwrap().is_empty());
+    let mut f2_members = rtdb
+        .smembers(&InstanceRedisKeys::tag_index("feeder", "F2"))
+        .await
+        .unwrap();
+    f2_members.sort();
+    assert_eq!(f2_members, vec!["1001", "1002"]);
+
+    // Deleting the instance removes its tags from SQLite and Redis
+    manager.delete_instance(1002).await.unwrap();
+    assert_eq!(
+        rtdb.smembers(&InstanceRedisKeys::tag_index("feeder", "F2"))
+            .await
+            .unwrap(),
+        vec!["1001"]
+    );
+    assert!(manager.get_instance_tags(1002).await.unwrap().is_empty());
+
+    // Invalid tag keys are rejected
+    let bad = HashMap::from([("bad key".to_string(), "x".to_string())]);
+    assert!(manager.set_instance_tags(1001, &bad).await.is_err());
+}
+
+// ==================== Point Aliases ====================
+
+#[tokio::test]
+async fn test_point_aliases_repoint_and_redis_mirror() {
+    use crate::error::ModSrvError;
+    use crate::point_aliases::PointAlias;
+    use voltage_rtdb::Rtdb;
+
+    let (_temp_dir, pool) = create_test_database().await;
+    sqlx::query("INSERT INTO instances (instance_id, instance_name, product_name) VALUES (5, 'pcs_01', 'PCS')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO channels (channel_id, name, protocol) VALUES (1001, 'pcs_modbus', 'modbus_tcp')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let product_loader = create_test_product_loader(pool.clone());
+    let rtdb = create_test_rtdb();
+    let routing_cache = Arc::new(voltage_rtdb::RoutingCache::new());
+    let manager = InstanceManager::new(pool, rtdb.clone(), routing_cache, product_loader);
+
+    let alias = |alias: &str, scheme: &str, address: &str| PointAlias {
+        alias: alias.to_string(),
+        scheme: scheme.to_string(),
+        address: address.to_string(),
+        description: None,
+        updated_at: None,
+    };
+    manager
+        .upsert_point_aliases(&[
+            alias("PCS1.P", "custom", "inst:5:M:1"),
+            alias("=G1 MKA10 CE101", "kks", "inst:5:M:1"),
+            alias("PCS1.U_DC", "custom", "comsrv:1001:T:3"),
+        ])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        manager
+            .resolve_point_alias("PCS1.U_DC")
+            .await
+            .unwrap()
+            .to_string(),
+        "comsrv:1001:T:3"
+    );
+    assert_eq!(
+        manager
+            .list_point_aliases(None, Some("inst:5"))
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+    assert!(manager
+        .list_point_aliases(None, Some("inst:50"))
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Renumbered point: re-point the alias, integrations keep using it
+    manager
+        .upsert_point_aliases(&[alias("PCS1.P", "custom", "inst:5:M:11")])
+        .await
+        .unwrap();
+    let by_point = rtdb
+        .hash_get_all(InstanceRedisKeys::alias_by_point_scheme("custom").as_str())
+        .await
+        .unwrap();
+    assert_eq!(by_point.get("inst:5:M:11").unwrap().as_ref(), b"PCS1.P");
+    assert!(!by_point.contains_key("inst:5:M:1"));
+    let forward = rtdb
+        .hash_get(InstanceRedisKeys::point_aliases(), "PCS1.P")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(forward.as_ref(), b"inst:5:M:11");
+
+    // One alias per address and scheme; unknown targets are rejected
+    let taken = manager
+        .upsert_point_aliases(&[alias("OTHER", "custom", "inst:5:M:11")])
+        .await;
+    assert!(matches!(taken, Err(ModSrvError::ConstraintViolation(_))));
+    let unknown = manager
+        .upsert_point_aliases(&[alias("GHOST", "custom", "inst:9:M:1")])
+        .await;
+    assert!(matches!(unknown, Err(ModSrvError::InvalidData(_))));
+
+    manager.delete_point_alias("PCS1.P").await.unwrap();
+    assert!(matches!(
+        manager.resolve_point_alias("PCS1.P").await,
+        Err(ModSrvError::AliasNotFound(_))
+    ));
+    assert!(rtdb
+        .hash_get(InstanceRedisKeys::point_aliases(), "PCS1.P")
+        .await
+        .unwrap()
+        .is_none());
+}
+
+// ==================== Optimistic Concurrency ====================
+
+#[tokio::test]
+async fn test_bump_instance_version_detects_stale_edit() {
+    use crate::instance_manager::VersionBump;
+
+    let (_temp_dir, pool) = create_test_database().await;
+    let product_loader = create_test_product_loader(pool.clone());
+    let rtdb = create_test_rtdb();
+    let routing_cache = Arc::new(voltage_rtdb::RoutingCache::new());
+    let manager = InstanceManager::new(pool, rtdb, routing_cache, product_loader);
+
+    manager
+        .create_instance(CreateInstanceRequest {
+            instance_id: 1001,
+            instance_name: "versioned_battery".to_string(),
+            product_name: "Battery".to_string(),
+            properties: HashMap::new(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(manager.get_instance(1001).await.unwrap().version, Some(1));
+
+    // Two editors load version 1; the first save wins, the second is rejected
+    assert_eq!(
+        manager.bump_instance_version(1001, Some(1)).await.unwrap(),
+        VersionBump::Bumped(2)
+    );
+    assert_eq!(
+        manager.bump_instance_version(1001, Some(1)).await.unwrap(),
+        VersionBump::Mismatch { current: 2 }
+    );
+
+    // Unconditional updates always bump
+    assert_eq!(
+        manager.bump_instance_version(1001, None).await.unwrap(),
+        VersionBump::Bumped(3)
+    );
+    assert_eq!(manager.get_instance(1001).await.unwrap().version, Some(3));
+
+    assert!(manager.bump_instance_version(9999, None).await.is_err());
+}
+
+#[tokio::test]
+async fn test_naming_policy_from_global_config() {
+    use crate::error::ModSrvError;
+
+    let (_temp_dir, pool) = create_test_database().await;
+    sqlx::query("INSERT INTO instances (instance_id, instance_name, product_name) VALUES (5, 'PCS_01', 'PCS')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        "INSERT INTO channels (channel_id, name, protocol) VALUES (1001, 'BMS_01', 'modbus_tcp')",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let product_loader = create_test_product_loader(pool.clone());
+    let rtdb = create_test_rtdb();
+    let routing_cache = Arc::new(voltage_rtdb::RoutingCache::new());
+    let manager = InstanceManager::new(pool.clone(), rtdb, routing_cache, product_loader);
+
+    // Built-in rules without a naming section
+    assert!(manager.check_instance_name("pcs_01", None).await.is_ok());
+    assert!(matches!(
+        manager.check_instance_name("1pcs", None).await,
+        Err(ModSrvError::InvalidData(_))
+    ));
+
+    for (key, value) in [
+        ("naming.instance.pattern", "^[A-Z]{2,4}_[0-9]{2}$"),
+        ("naming.instance.reserved", r#"["ALL_00"]"#),
+        ("naming.instance.case_sensitive", "false"),
+        ("naming.instance.unique", "system"),
+    ] {
+        sqlx::query(
+            "INSERT INTO service_config (service_name, key, value) VALUES ('global', ?, ?)",
+        )
+        .bind(key)
+        .bind(value)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    assert!(manager.check_instance_name("ESS_01", None).await.is_ok());
+    assert!(matches!(
+        manager.check_instance_name("pcs_01", None).await,
+        Err(ModSrvError::InvalidData(_))
+    ));
+    assert!(matches!(
+        manager.check_instance_name("ALL_00", None).await,
+        Err(ModSrvError::InvalidData(_))
+    ));
+    assert!(matches!(
+        manager.check_instance_name("BMS_01", None).await,
+        Err(ModSrvError::ConstraintViolation(_))
+    ));
+
+    // Renaming an instance to its own name in another case is allowed,
+    // taking another instance's name is not
+    manager.rename_instance(5, "PCS_01").await.unwrap();
+    sqlx::query("INSERT INTO instances (instance_id, instance_name, product_name) VALUES (6, 'PCS_02', 'PCS')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let err = manager.rename_instance(6, "PCS_01").await.unwrap_err();
+    assert!(matches!(
+        err.downcast::<ModSrvError>().unwrap(),
+        ModSrvError::InstanceExists(_)
+    ));
+}
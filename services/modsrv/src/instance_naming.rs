@@ -0,0 +1,88 @@
+//! Instance Naming Policy
+//!
+//! Applies the deployment naming policy (`naming` section of global config,
+//! see `voltage_model::naming`) to instance creation and renaming. The policy
+//! is read from `service_config` on every check so a `monarch sync` takes
+//! effect without restarting modsrv.
+
+use voltage_model::{NamingPolicy, UniqueScope};
+
+use crate::error::{ModSrvError, Result};
+
+use super::instance_manager::InstanceManager;
+use voltage_rtdb::Rtdb;
+
+impl<R: Rtdb + 'static> InstanceManager<R> {
+    /// Load the naming policy from global config (defaults when not configured)
+    pub async fn naming_policy(&self) -> Result<NamingPolicy> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT key, value FROM service_config \
+             WHERE service_name = 'global' AND key LIKE 'naming.%'",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        NamingPolicy::from_config_rows(rows)
+            .map_err(|e| ModSrvError::ConfigError(format!("Naming policy: {}", e)))
+    }
+
+    /// Check an instance name against the naming policy
+    ///
+    /// Validates format and reserved words, then uniqueness within the policy
+    /// scope (instances, or instances and channels) honouring case sensitivity.
+    /// `exclude_instance` is the instance being renamed.
+    pub async fn check_instance_name(
+        &self,
+        name: &str,
+        exclude_instance: Option<u32>,
+    ) -> Result<()> {
+        let policy = self.naming_policy().await?.instance;
+        policy
+            .validate(name)
+            .map_err(|e| ModSrvError::InvalidData(e.to_string()))?;
+
+        let collate = if policy.case_sensitive {
+            ""
+        } else {
+            " COLLATE NOCASE"
+        };
+
+        let existing: Option<(String,)> = sqlx::query_as(&format!(
+            "SELECT instance_name FROM instances WHERE instance_name = ?{} AND instance_id != ?",
+            collate
+        ))
+        .bind(name)
+        .bind(exclude_instance.map_or(-1, i64::from))
+        .fetch_optional(&self.pool)
+        .await?;
+        if let Some((existing,)) = existing {
+            return Err(ModSrvError::InstanceExists(format!(
+                "Instance name '{}' is already in use{}",
+                name,
+                if existing == name {
+                    String::new()
+                } else {
+                    format!(" as '{}'", existing)
+                }
+            )));
+        }
+
+        if policy.unique == UniqueScope::System {
+            let channel: Option<(i64, String)> = sqlx::query_as(&format!(
+                "SELECT channel_id, name FROM channels WHERE name = ?{}",
+                collate
+            ))
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+            if let Some((channel_id, channel_name)) = channel {
+                return Err(ModSrvError::ConstraintViolation(format!(
+                    "Instance name '{}' is already used by channel {} ('{}')",
+                    name, channel_id, channel_name
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
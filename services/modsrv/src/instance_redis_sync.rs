@@ -4,6 +4,7 @@
 //! Extracted from instance_manager.rs for better code organization.
 
 use anyhow::{anyhow, Result};
+use futures::stream::{self, StreamExt};
 use std::collections::HashMap;
 use tracing::{debug, info, warn};
 
@@ -13,12 +14,23 @@ use crate::redis_state;
 use super::instance_manager::InstanceManager;
 use voltage_rtdb::Rtdb;
 
+/// Redis registrations in flight during a full instance sync
+pub const DEFAULT_SYNC_CONCURRENCY: usize = 16;
+
 impl<R: Rtdb + 'static> InstanceManager<R> {
-    /// Sync all instances from SQLite to Redis (called on startup)
+    /// Sync all instances from SQLite to Redis
     ///
     /// Optimized to use batch queries: 3 queries total instead of 1 + 3N
     /// (1 list_instances + 1 all_measurements + 1 all_actions)
     pub async fn sync_instances_to_redis(&self) -> Result<()> {
+        self.sync_instances_to_redis_with(DEFAULT_SYNC_CONCURRENCY)
+            .await
+    }
+
+    /// Sync all instances with at most `concurrency` Redis registrations in flight
+    ///
+    /// Logs progress in 10% steps, so a long startup sync stays visible.
+    pub async fn sync_instances_to_redis_with(&self, concurrency: usize) -> Result<()> {
         use std::sync::Arc;
 
         info!("Syncing instances from SQLite to Redis...");
@@ -47,6 +59,7 @@ impl<R: Rtdb + 'static> InstanceManager<R> {
             instance_name: String,
             product_name: String,
             properties: HashMap<String, serde_json::Value>,
+            tags: HashMap<String, String>,
             // Point routing mappings - Maps point IDs to Redis keys
             measurement_point_routings: HashMap<u32, String>,
             action_point_routings: HashMap<u32, String>,
@@ -103,21 +116,29 @@ impl<R: Rtdb + 'static> InstanceManager<R> {
                 instance_name: instance.instance_name().to_string(),
                 product_name: instance.product_name().to_string(),
                 properties: instance.core.properties.clone(),
+                tags: instance.core.tags.clone(),
                 measurement_point_routings, // from batch lookup
                 action_point_routings,      // from batch lookup
                 product,                    // Arc::clone, not deep clone
             });
         }
 
-        // Perform batch sync to Redis if we have data
+        // Register instances with bounded concurrency (bounds the Redis load
+        // instead of sleeping between chunks)
         if !batch_data.is_empty() {
-            info!("Batch syncing {} instances to Redis", batch_data.len());
+            let batch_total = batch_data.len();
+            let concurrency = concurrency.max(1);
+            info!(
+                "Syncing {} instances to Redis ({} in parallel)",
+                batch_total, concurrency
+            );
 
-            // Process in chunks to avoid overwhelming Redis
-            const BATCH_SIZE: usize = 50;
-            for chunk in batch_data.chunks(BATCH_SIZE) {
-                for payload in chunk {
-                    if let Err(e) = redis_state::register_instance(
+            let started = std::time::Instant::now();
+            let progress_step = (batch_total / 10).max(1);
+            let mut done = 0;
+            let mut registrations =
+                stream::iter(batch_data.into_iter().map(|payload| async move {
+                    let registered = redis_state::register_instance(
                         self.rtdb.as_ref(),
                         payload.instance_id,
                         &payload.instance_name,
@@ -129,18 +150,43 @@ impl<R: Rtdb + 'static> InstanceManager<R> {
                         &payload.product.actions,
                         None,
                     )
+                    .await;
+                    if let Err(e) = &registered {
+                        warn!(
+                            "Failed to sync instance {} to Redis: {}",
+                            payload.instance_name, e
+                        );
+                    }
+
+                    if let Err(e) = redis_state::set_instance_tags(
+                        self.rtdb.as_ref(),
+                        payload.instance_id,
+                        &payload.tags,
+                    )
                     .await
                     {
                         warn!(
-                            "Failed to sync instance {} to Redis: {}",
+                            "Failed to sync tags of instance {} to Redis: {}",
                             payload.instance_name, e
                         );
-                        failed_count += 1;
                     }
+                    registered.is_ok()
+                }))
+                .buffer_unordered(concurrency);
+
+            while let Some(registered) = registrations.next().await {
+                done += 1;
+                if !registered {
+                    failed_count += 1;
+                }
+                if done % progress_step == 0 || done == batch_total {
+                    info!(
+                        "Instance sync {}/{} ({:.1}s)",
+                        done,
+                        batch_total,
+                        started.elapsed().as_secs_f64()
+                    );
                 }
-
-                // Small delay between chunks to avoid overwhelming Redis
-                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
             }
         }
 
@@ -45,14 +45,17 @@ impl<R: Rtdb + 'static> InstanceManager<R> {
             r#"
             INSERT INTO measurement_routing
             (instance_id, instance_name, channel_id, channel_type, channel_point_id,
-             measurement_id, enabled)
-            VALUES (?, ?, ?, ?, ?, ?, ?)
+             measurement_id, enabled, deadband, deadband_pct, refresh_secs)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             ON CONFLICT(instance_id, measurement_id)
             DO UPDATE SET
                 channel_id = excluded.channel_id,
                 channel_type = excluded.channel_type,
                 channel_point_id = excluded.channel_point_id,
                 enabled = excluded.enabled,
+                deadband = excluded.deadband,
+                deadband_pct = excluded.deadband_pct,
+                refresh_secs = excluded.refresh_secs,
                 updated_at = CURRENT_TIMESTAMP
             "#,
         )
@@ -63,6 +66,9 @@ impl<R: Rtdb + 'static> InstanceManager<R> {
         .bind(request.channel_point_id)
         .bind(point_id)
         .bind(request.enabled)
+        .bind(request.deadband)
+        .bind(request.deadband_pct)
+        .bind(request.refresh_secs)
         .execute(&self.pool)
         .await?;
 
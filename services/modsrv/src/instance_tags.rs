@@ -0,0 +1,204 @@
+//! Instance Tags
+//!
+//! Arbitrary key/value tags on instances (site, feeder, rack).
+//! SQLite `instance_tags` is the source of truth; Redis keeps
+//! `inst:{id}:tags` hashes and `inst:tag:{key}:{value}` index sets,
+//! which rule selectors (`tag:{key}:{value}:M:{point}`) aggregate over.
+
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+use crate::product_loader::Instance;
+use crate::redis_state;
+
+use super::instance_manager::InstanceManager;
+use voltage_rtdb::Rtdb;
+
+/// Validate a tag key/value pair
+///
+/// Keys are restricted to `[A-Za-z0-9_-]` so that `key:value` filters stay unambiguous;
+/// values must be non-empty and must not contain commas (filter separator).
+pub fn validate_tag(tag_key: &str, tag_value: &str) -> Result<()> {
+    if tag_key.is_empty()
+        || !tag_key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(anyhow!(
+            "Invalid tag key '{}': use letters, digits, '_' or '-'",
+            tag_key
+        ));
+    }
+    if tag_value.is_empty() || tag_value.contains(',') {
+        return Err(anyhow!(
+            "Invalid tag value '{}' for key '{}': must be non-empty without ','",
+            tag_value,
+            tag_key
+        ));
+    }
+    Ok(())
+}
+
+/// Parse a tag filter such as `feeder:F1,site:S1` into (key, value) pairs
+pub fn parse_tag_filter(filter: &str) -> Result<Vec<(String, String)>> {
+    filter
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|pair| {
+            let (tag_key, tag_value) = pair
+                .split_once(':')
+                .ok_or_else(|| anyhow!("Invalid tag filter '{}': expected key:value", pair))?;
+            validate_tag(tag_key, tag_value)?;
+            Ok((tag_key.to_string(), tag_value.to_string()))
+        })
+        .collect()
+}
+
+/// SQL condition matching instances that carry every given tag
+///
+/// Produces one `instance_id IN (...)` clause per tag; bind key then value for each.
+pub(crate) fn tag_filter_conditions(tag_count: usize) -> Vec<&'static str> {
+    vec![
+        "instance_id IN (SELECT instance_id FROM instance_tags WHERE tag_key = ? AND tag_value = ?)";
+        tag_count
+    ]
+}
+
+impl<R: Rtdb + 'static> InstanceManager<R> {
+    /// Get tags of a single instance
+    pub async fn get_instance_tags(&self, instance_id: u32) -> Result<HashMap<String, String>> {
+        let rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT tag_key, tag_value FROM instance_tags WHERE instance_id = ?")
+                .bind(instance_id as i32)
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Fill the tags of already loaded instances in one query
+    pub(crate) async fn attach_tags(&self, instances: &mut [Instance]) -> Result<()> {
+        if instances.is_empty() {
+            return Ok(());
+        }
+
+        let placeholders = vec!["?"; instances.len()].join(", ");
+        let sql = format!(
+            "SELECT instance_id, tag_key, tag_value FROM instance_tags WHERE instance_id IN ({})",
+            placeholders
+        );
+        let mut query = sqlx::query_as::<_, (u32, String, String)>(&sql);
+        for instance in instances.iter() {
+            query = query.bind(instance.instance_id() as i32);
+        }
+
+        let mut tags_by_instance: HashMap<u32, HashMap<String, String>> = HashMap::new();
+        for (instance_id, tag_key, tag_value) in query.fetch_all(&self.pool).await? {
+            tags_by_instance
+                .entry(instance_id)
+                .or_default()
+                .insert(tag_key, tag_value);
+        }
+
+        for instance in instances.iter_mut() {
+            if let Some(tags) = tags_by_instance.remove(&instance.instance_id()) {
+                instance.core.tags = tags;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace all tags of an instance (SQLite first, Redis best effort)
+    pub async fn set_instance_tags(
+        &self,
+        instance_id: u32,
+        tags: &HashMap<String, String>,
+    ) -> Result<()> {
+        for (tag_key, tag_value) in tags {
+            validate_tag(tag_key, tag_value)?;
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        let (count,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM instances WHERE instance_id = ?")
+                .bind(instance_id as i32)
+                .fetch_one(&mut *tx)
+                .await?;
+        if count == 0 {
+            return Err(anyhow!("Instance not found: {}", instance_id));
+        }
+
+        sqlx::query("DELETE FROM instance_tags WHERE instance_id = ?")
+            .bind(instance_id as i32)
+            .execute(&mut *tx)
+            .await?;
+
+        for (tag_key, tag_value) in tags {
+            sqlx::query(
+                "INSERT INTO instance_tags (instance_id, tag_key, tag_value) VALUES (?, ?, ?)",
+            )
+            .bind(instance_id as i32)
+            .bind(tag_key)
+            .bind(tag_value)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        if let Err(e) = redis_state::set_instance_tags(self.rtdb.as_ref(), instance_id, tags).await
+        {
+            warn!(
+                "Instance {} tags updated in SQLite but Redis sync failed: {}. Will sync on next reload.",
+                instance_id, e
+            );
+        }
+
+        info!("Instance {} tags set: {:?}", instance_id, tags);
+        Ok(())
+    }
+
+    /// Resolve instance IDs carrying all given tags
+    pub async fn instance_ids_by_tags(&self, tags: &[(String, String)]) -> Result<Vec<u32>> {
+        let mut sql = "SELECT instance_id FROM instances".to_string();
+        if !tags.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&tag_filter_conditions(tags.len()).join(" AND "));
+        }
+        sql.push_str(" ORDER BY instance_id ASC");
+
+        let mut query = sqlx::query_as::<_, (u32,)>(&sql);
+        for (tag_key, tag_value) in tags {
+            query = query.bind(tag_key).bind(tag_value);
+        }
+
+        let rows = query.fetch_all(&self.pool).await?;
+        Ok(rows.into_iter().map(|(id,)| id).collect())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tag_filter() {
+        let tags = parse_tag_filter("feeder:F1, site:north:A").unwrap();
+        assert_eq!(
+            tags,
+            vec![
+                ("feeder".to_string(), "F1".to_string()),
+                ("site".to_string(), "north:A".to_string())
+            ]
+        );
+
+        assert!(parse_tag_filter("").unwrap().is_empty());
+        assert!(parse_tag_filter("feeder").is_err());
+        assert!(parse_tag_filter("bad key:F1").is_err());
+        assert!(parse_tag_filter("feeder:").is_err());
+    }
+}
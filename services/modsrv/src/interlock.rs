@@ -0,0 +1,495 @@
+//! Interlock Constraints for Action Points
+//!
+//! Interlocks are declared per product in `modsrv.yaml` and checked before
+//! any action write proceeds (rules, dispatch API, local override). The
+//! engine is installed as the process-wide `voltage_routing::guard`, so a
+//! refused write fails with a typed [`ConstraintViolation`].
+//!
+//! ```yaml
+//! interlocks:
+//!   PCS:
+//!     - name: pcs_power_within_transformer
+//!       action: 1                     # guarded action point
+//!       require: "value <= rating - other_load"
+//!       variables:
+//!         rating: "P:transformer_rating"  # instance property
+//!         other_load: "inst:9:M:1"        # point of another instance
+//!       message: "PCS setpoint exceeds transformer headroom"
+//!     - name: breaker_close_needs_voltage
+//!       action: 3
+//!       when: "value == 1.0"          # optional: only checked for closing
+//!       require: "voltage >= 360"
+//!       variables:
+//!         voltage: "M:2"              # measurement of the same instance
+//! ```
+//!
+//! `value` is the requested value. Variable sources are `M:{id}` / `A:{id}`
+//! (same instance), `P:{name}` (instance property) and `inst:{id}:{M|A}:{pid}`.
+//! All variables are floats, so equality needs float literals (`== 1.0`).
+//! An interlock whose variables cannot be read blocks the write.
+
+use anyhow::{anyhow, bail, Context, Result};
+use evalexpr::{ContextWithMutableVariables, HashMapContext, Node, Value};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use voltage_routing::guard::{ActionGuard, GuardFuture};
+use voltage_routing::ConstraintViolation;
+use voltage_rtdb::{KeySpaceConfig, Rtdb};
+
+/// Interlocks per product name
+pub type InterlockConfig = HashMap<String, Vec<InterlockDef>>;
+
+/// Variable holding the requested value
+pub const VALUE_VARIABLE: &str = "value";
+
+/// Interlock declaration of a product
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterlockDef {
+    pub name: String,
+    /// Guarded action point ID
+    pub action: u32,
+    /// Optional condition; the interlock only applies when it holds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+    /// Condition the write must satisfy
+    pub require: String,
+    /// Variable name -> source (`M:2`, `A:1`, `P:rating`, `inst:9:M:1`)
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+    /// Operator-facing reason shown on violation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Where an interlock variable is read from
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum VariableSource {
+    /// Point of the instance being written (`M:{id}` / `A:{id}`)
+    Local { action: bool, point_id: u32 },
+    /// Instance property (`P:{name}`)
+    Property(String),
+    /// Point of another instance (`inst:{id}:{M|A}:{pid}`)
+    Remote {
+        instance_id: u32,
+        action: bool,
+        point_id: u32,
+    },
+}
+
+impl VariableSource {
+    pub(crate) fn parse(source: &str) -> Result<Self> {
+        let parts: Vec<&str> = source.split(':').collect();
+        let role = |r: &str| match r {
+            "M" => Ok(false),
+            "A" => Ok(true),
+            other => Err(anyhow!("unknown point role '{}'", other)),
+        };
+        match parts.as_slice() {
+            ["P", name] if !name.is_empty() => Ok(Self::Property(name.to_string())),
+            [r, pid] => Ok(Self::Local {
+                action: role(r)?,
+                point_id: pid.parse()?,
+            }),
+            ["inst", id, r, pid] => Ok(Self::Remote {
+                instance_id: id.parse()?,
+                action: role(r)?,
+                point_id: pid.parse()?,
+            }),
+            _ => bail!("expected M:id, A:id, P:name or inst:id:M|A:id"),
+        }
+    }
+}
+
+/// Interlock with parsed expressions
+struct CompiledInterlock {
+    def: InterlockDef,
+    when: Option<Node>,
+    require: Node,
+    variables: Vec<(String, VariableSource)>,
+}
+
+impl CompiledInterlock {
+    fn compile(product: &str, def: InterlockDef) -> Result<Self> {
+        let ctx = || format!("interlock {}/{}", product, def.name);
+        let when = def
+            .when
+            .as_deref()
+            .map(evalexpr::build_operator_tree)
+            .transpose()
+            .with_context(|| format!("{}: invalid 'when'", ctx()))?;
+        let require = evalexpr::build_operator_tree(&def.require)
+            .with_context(|| format!("{}: invalid 'require'", ctx()))?;
+
+        let variables =
+            bind_variables(&def.variables, when.iter().chain([&require])).with_context(ctx)?;
+
+        Ok(Self {
+            def,
+            when,
+            require,
+            variables,
+        })
+    }
+}
+
+/// Evaluates the declared interlocks before action writes
+pub struct InterlockEngine<R: Rtdb> {
+    rtdb: Arc<R>,
+    pool: SqlitePool,
+    /// product -> action point -> interlocks
+    interlocks: HashMap<String, HashMap<u32, Vec<CompiledInterlock>>>,
+}
+
+impl<R: Rtdb> InterlockEngine<R> {
+    /// Compile the declarations; fails on the first invalid interlock
+    pub fn new(rtdb: Arc<R>, pool: SqlitePool, config: &InterlockConfig) -> Result<Self> {
+        let mut interlocks: HashMap<String, HashMap<u32, Vec<CompiledInterlock>>> = HashMap::new();
+        for (product, defs) in config {
+            for def in defs {
+                let compiled = CompiledInterlock::compile(product, def.clone())?;
+                interlocks
+                    .entry(product.clone())
+                    .or_default()
+                    .entry(def.action)
+                    .or_default()
+                    .push(compiled);
+            }
+        }
+        Ok(Self {
+            rtdb,
+            pool,
+            interlocks,
+        })
+    }
+
+    /// Number of declared interlocks
+    pub fn len(&self) -> usize {
+        self.interlocks
+            .values()
+            .flat_map(|points| points.values())
+            .map(Vec::len)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check a requested action write against the interlocks of the instance's product
+    pub async fn check_write(
+        &self,
+        instance_id: u32,
+        point_id: &str,
+        value: f64,
+    ) -> std::result::Result<(), ConstraintViolation> {
+        let Ok(action_id) = point_id.parse::<u32>() else {
+            return Ok(());
+        };
+        if self.interlocks.is_empty() {
+            return Ok(());
+        }
+
+        let violation = |name: &str, message: String| ConstraintViolation {
+            constraint: name.to_string(),
+            instance_id,
+            point_id: point_id.to_string(),
+            value,
+            message,
+        };
+
+        let profile = instance_profile(&self.pool, instance_id)
+            .await
+            .map_err(|e| violation("instance", format!("instance lookup failed: {}", e)))?;
+        // Instances without a record (e.g. virtual instances) have no interlocks
+        let Some((product, properties)) = profile else {
+            return Ok(());
+        };
+        let Some(interlocks) = self
+            .interlocks
+            .get(&product)
+            .and_then(|points| points.get(&action_id))
+        else {
+            return Ok(());
+        };
+
+        for interlock in interlocks {
+            let name = &interlock.def.name;
+            let mut context = HashMapContext::new();
+            let _ = context.set_value(VALUE_VARIABLE.to_string(), Value::Float(value));
+            for (var, source) in &interlock.variables {
+                let resolved =
+                    resolve_variable(self.rtdb.as_ref(), instance_id, source, &properties)
+                        .await
+                        .map_err(|e| violation(name, format!("{} unavailable: {}", var, e)))?;
+                let _ = context.set_value(var.clone(), Value::Float(resolved));
+            }
+
+            if let Some(when) = &interlock.when {
+                let applies = when
+                    .eval_boolean_with_context(&context)
+                    .map_err(|e| violation(name, format!("'when' failed: {}", e)))?;
+                if !applies {
+                    continue;
+                }
+            }
+            let satisfied = interlock
+                .require
+                .eval_boolean_with_context(&context)
+                .map_err(|e| violation(name, format!("'require' failed: {}", e)))?;
+            if !satisfied {
+                let message = interlock
+                    .def
+                    .message
+                    .clone()
+                    .unwrap_or_else(|| format!("requires {}", interlock.def.require));
+                tracing::warn!(
+                    "Interlock {} blocked instance {} action {} = {}",
+                    name,
+                    instance_id,
+                    point_id,
+                    value
+                );
+                return Err(violation(name, message));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse variable sources and check that `nodes` only use bound identifiers
+///
+/// `value` is reserved for the requested value and always bound.
+pub(crate) fn bind_variables<'a>(
+    variables: &HashMap<String, String>,
+    nodes: impl IntoIterator<Item = &'a Node>,
+) -> Result<Vec<(String, VariableSource)>> {
+    let mut bound = Vec::with_capacity(variables.len());
+    for (name, source) in variables {
+        if name == VALUE_VARIABLE {
+            bail!("'{}' is reserved for the requested value", name);
+        }
+        let parsed = VariableSource::parse(source)
+            .with_context(|| format!("variable {} = '{}'", name, source))?;
+        bound.push((name.clone(), parsed));
+    }
+
+    // Every identifier must be bound, otherwise the condition could never hold
+    for node in nodes {
+        for ident in node.iter_variable_identifiers() {
+            if ident != VALUE_VARIABLE && !variables.contains_key(ident) {
+                bail!("undeclared variable '{}'", ident);
+            }
+        }
+    }
+    Ok(bound)
+}
+
+/// Read a variable for an instance
+pub(crate) async fn resolve_variable<R: Rtdb>(
+    rtdb: &R,
+    instance_id: u32,
+    source: &VariableSource,
+    properties: &serde_json::Value,
+) -> Result<f64> {
+    let keyspace = KeySpaceConfig::production_cached();
+    let (key, point_id) = match source {
+        VariableSource::Property(name) => return numeric_property(properties, name),
+        VariableSource::Local { action, point_id } => (
+            if *action {
+                keyspace.instance_action_key(instance_id)
+            } else {
+                keyspace.instance_measurement_key(instance_id)
+            },
+            *point_id,
+        ),
+        VariableSource::Remote {
+            instance_id,
+            action,
+            point_id,
+        } => (
+            if *action {
+                keyspace.instance_action_key(*instance_id)
+            } else {
+                keyspace.instance_measurement_key(*instance_id)
+            },
+            *point_id,
+        ),
+    };
+
+    let raw = rtdb
+        .hash_get(&key, &point_id.to_string())
+        .await?
+        .ok_or_else(|| anyhow!("{}:{} has no value", key, point_id))?;
+    String::from_utf8_lossy(&raw)
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("{}:{} is not numeric", key, point_id))
+}
+
+/// Product name and properties of an instance (`None` if it has no record)
+pub(crate) async fn instance_profile(
+    pool: &SqlitePool,
+    instance_id: u32,
+) -> sqlx::Result<Option<(String, serde_json::Value)>> {
+    let row: Option<(String, Option<String>)> =
+        sqlx::query_as("SELECT product_name, properties FROM instances WHERE instance_id = ?")
+            .bind(instance_id as i64)
+            .fetch_optional(pool)
+            .await?;
+    Ok(row.map(|(product, properties)| {
+        let properties = properties
+            .as_deref()
+            .and_then(|p| serde_json::from_str(p).ok())
+            .unwrap_or_default();
+        (product, properties)
+    }))
+}
+
+/// Read an instance property as a number
+pub(crate) fn numeric_property(properties: &serde_json::Value, name: &str) -> Result<f64> {
+    match properties.get(name) {
+        Some(serde_json::Value::Number(n)) => n
+            .as_f64()
+            .ok_or_else(|| anyhow!("property {} not numeric", name)),
+        Some(serde_json::Value::Bool(b)) => Ok(if *b { 1.0 } else { 0.0 }),
+        Some(serde_json::Value::String(s)) => s
+            .trim()
+            .parse()
+            .map_err(|_| anyhow!("property {} not numeric", name)),
+        _ => Err(anyhow!("property {} not set", name)),
+    }
+}
+
+impl<R: Rtdb> ActionGuard for InterlockEngine<R> {
+    fn check<'a>(&'a self, instance_id: u32, point_id: &'a str, value: f64) -> GuardFuture<'a> {
+        Box::pin(self.check_write(instance_id, point_id, value))
+    }
+}
+
+/// Read the `interlocks.{product}` entries of the flattened service config
+pub fn from_service_config(extra_config: &serde_json::Value) -> Result<InterlockConfig> {
+    product_sections(extra_config, "interlocks")
+}
+
+/// Read the `{section}.{product}` lists of the flattened service config
+///
+/// monarch stores each product's list as a JSON array string.
+pub(crate) fn product_sections<T: serde::de::DeserializeOwned>(
+    extra_config: &serde_json::Value,
+    section: &str,
+) -> Result<HashMap<String, Vec<T>>> {
+    let mut config = HashMap::new();
+    let Some(entries) = extra_config.as_object() else {
+        return Ok(config);
+    };
+    let prefix = format!("{}.", section);
+    for (key, value) in entries {
+        let Some(product) = key.strip_prefix(&prefix) else {
+            continue;
+        };
+        let defs: Vec<T> = match value {
+            serde_json::Value::String(s) => serde_json::from_str(s),
+            other => serde_json::from_value(other.clone()),
+        }
+        .with_context(|| format!("Invalid {} for product {}", section, product))?;
+        config.insert(product.to_string(), defs);
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use voltage_rtdb::MemoryRtdb;
+
+    async fn setup() -> (Arc<MemoryRtdb>, SqlitePool) {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::query(
+            "CREATE TABLE instances (instance_id INTEGER PRIMARY KEY, product_name TEXT, properties TEXT)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO instances VALUES (1, 'PCS', '{\"transformer_rating\": 500}')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        (Arc::new(MemoryRtdb::new()), pool)
+    }
+
+    fn config(yaml: &str) -> InterlockConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_interlocks_block_violating_writes() {
+        let (rtdb, pool) = setup().await;
+        let interlocks = config(
+            r#"
+PCS:
+  - name: transformer_headroom
+    action: 1
+    require: "value <= rating - load"
+    variables: { rating: "P:transformer_rating", load: "inst:9:M:1" }
+    message: "exceeds transformer headroom"
+  - name: breaker_close_needs_voltage
+    action: 3
+    when: "value == 1.0"
+    require: "voltage >= 360"
+    variables: { voltage: "M:2" }
+"#,
+        );
+        let engine = InterlockEngine::new(Arc::clone(&rtdb), pool, &interlocks).unwrap();
+        assert_eq!(engine.len(), 2);
+
+        // Unreadable variables block the write
+        let err = engine.check_write(1, "1", 100.0).await.unwrap_err();
+        assert_eq!(err.constraint, "transformer_headroom");
+
+        rtdb.hash_set("inst:9:M", "1", Bytes::from("350"))
+            .await
+            .unwrap();
+        assert!(engine.check_write(1, "1", 150.0).await.is_ok());
+        let err = engine.check_write(1, "1", 151.0).await.unwrap_err();
+        assert_eq!(err.message, "exceeds transformer headroom");
+
+        rtdb.hash_set("inst:1:M", "2", Bytes::from("120.5"))
+            .await
+            .unwrap();
+        assert!(engine.check_write(1, "3", 0.0).await.is_ok()); // opening is not guarded
+        assert!(engine.check_write(1, "3", 1.0).await.is_err());
+
+        // Other points and unknown instances are not affected
+        assert!(engine.check_write(1, "2", 1e9).await.is_ok());
+        assert!(engine.check_write(42, "1", 1e9).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_declarations_are_rejected() {
+        let pool = SqlitePool::connect_lazy("sqlite::memory:").unwrap();
+        let rtdb = Arc::new(MemoryRtdb::new());
+        for yaml in [
+            "PCS: [{name: a, action: 1, require: \"(value < 1\"}]",
+            "PCS: [{name: a, action: 1, require: \"value < limit\"}]",
+            "PCS: [{name: a, action: 1, require: \"value < x\", variables: {x: \"Q:1\"}}]",
+            "PCS: [{name: a, action: 1, require: \"value < 1\", variables: {value: \"M:1\"}}]",
+        ] {
+            assert!(
+                InterlockEngine::new(Arc::clone(&rtdb), pool.clone(), &config(yaml)).is_err(),
+                "{}",
+                yaml
+            );
+        }
+
+        let extra = serde_json::json!({
+            "interlocks.PCS": "[{\"name\": \"a\", \"action\": 1, \"require\": \"value < 10\"}]",
+            "products_path": "config/modsrv/products"
+        });
+        let parsed = from_service_config(&extra).unwrap();
+        assert_eq!(parsed["PCS"][0].require, "value < 10");
+    }
+}
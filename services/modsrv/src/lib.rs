@@ -8,23 +8,36 @@ pub mod api {
     //! Organizes API handlers by functional domain under the `api/` directory.
     //!
     //! Handler groups:
-    //! - routing (management + query)
+    //! - routing (management + query + matrix editor)
     //! - instance (management + query + action)
     //! - product
     //! - health
+    //! - history (hissrv proxy)
+    //! - search (channels, points, instances, products, rules)
+    //! - point aliases (stable external identifiers)
+    //! - override (local HMI override of action points)
+    //! - recipes (command sequences of instances)
+    //! - subscriptions (webhooks for measurement changes)
     //! - single point APIs
     //! - admin (log level management)
     //! - cloud sync (cloud-edge synchronization)
     pub mod admin_handlers;
+    pub mod alias_handlers;
     pub mod cloud_sync;
     pub mod global_routing_handlers;
     pub mod health_handlers;
+    pub mod history_handlers;
     pub mod instance_management_handlers;
     pub mod instance_query_handlers;
+    pub mod override_handlers;
     pub mod product_handlers;
+    pub mod recipe_handlers;
     pub mod routing_management_handlers;
+    pub mod routing_matrix_handlers;
     pub mod routing_query_handlers;
+    pub mod search_handlers;
     pub mod single_point_handlers;
+    pub mod subscription_handlers;
 
     // Re-export dto/routes for convenience
     pub use crate::routes;
@@ -33,29 +46,43 @@ pub mod api {
 pub mod app_state;
 pub mod bootstrap;
 pub mod cleanup_provider;
+pub mod data_versions;
 #[path = "api/dto.rs"]
 pub mod dto;
 pub mod error;
 pub mod instance_manager;
+pub mod interlock;
 // Extension impl blocks for InstanceManager (split for maintainability)
 mod instance_data;
+mod instance_naming;
 mod instance_redis_sync;
 mod instance_routing;
+mod instance_tags;
+pub mod migrations;
+pub mod point_aliases;
 pub mod product_loader;
+pub mod ramp;
+pub mod recipe;
 pub mod redis_state;
 pub mod reload;
 pub mod routes;
 pub mod routing_loader;
+pub mod search_index;
+pub mod subscriptions;
 
 // Rule Engine - local routes module
 pub mod rule_routes;
 
+// In-process fixtures for cross-service tests (common::test_utils::services)
+#[cfg(feature = "test-fixtures")]
+pub mod test_fixtures;
+
 // Re-export Rule Engine types from voltage-rules library
 pub use voltage_rules::{
     delete_rule, extract_rule_flow, get_rule, get_rule_for_execution, list_rules, load_all_rules,
-    load_enabled_rules, set_rule_enabled, upsert_rule, ActionResult, Result as RuleResult,
-    RuleError, RuleExecutionResult, RuleExecutor, RuleScheduler, SchedulerStatus, TriggerConfig,
-    DEFAULT_TICK_MS,
+    load_enabled_rules, set_rule_enabled, upsert_rule, ActionResult, CalcLimits, ExecutionConfig,
+    FairnessPolicy, Result as RuleResult, RuleError, RuleExecutionResult, RuleExecutor,
+    RuleScheduler, SchedulerStatus, TriggerConfig, DEFAULT_TICK_MS,
 };
 
 // Re-export routing types from shared library
@@ -18,7 +18,7 @@ use modsrv::rule_routes::RuleApiDoc;
 use modsrv::{
     bootstrap, routes,
     rule_routes::{create_rule_routes, RuleEngineState},
-    Result, RuleScheduler, DEFAULT_TICK_MS,
+    CalcLimits, ExecutionConfig, Result, RuleScheduler, DEFAULT_TICK_MS,
 };
 use voltage_rtdb::{is_shm_available, SharedConfig, SharedVecRtdbReader};
 
@@ -26,13 +26,19 @@ use voltage_rtdb::{is_shm_available, SharedConfig, SharedVecRtdbReader};
 async fn main() -> Result<()> {
     // Create service info
     let service_info = bootstrap::create_service_info();
+    let (args, modsrv_args) = bootstrap::parse_args(&service_info)?;
+
+    // Validation mode: validate and exit
+    if args.validate {
+        return bootstrap::validate(&service_info, &args).await;
+    }
 
     // Initialize cancellation token for graceful shutdown
     let shutdown_token = CancellationToken::new();
     debug!("Shutdown token initialized");
 
     // Create application state with all initialized components
-    let state = bootstrap::create_app_state(&service_info).await?;
+    let state = bootstrap::create_app_state(&service_info, &args, &modsrv_args).await?;
 
     // Create API routes using the routes module
     let app = routes::create_routes(Arc::clone(&state));
@@ -57,6 +63,9 @@ async fn main() -> Result<()> {
     let rtdb = state.instance_manager.rtdb.clone();
     let routing_cache = state.instance_manager.routing_cache().clone();
 
+    // Cross-check routing and rule references against channels and instances
+    common::config_consistency::run_startup_check(&sqlite_pool, "modsrv").await;
+
     // Load tick_ms from global config (SQLite key-value table)
     let tick_ms: u64 = sqlx::query_scalar::<_, String>(
         "SELECT value FROM service_config WHERE service_name = 'global' AND key = 'rules.tick_ms'",
@@ -70,6 +79,57 @@ async fn main() -> Result<()> {
 
     debug!("Rule scheduler tick_ms: {}", tick_ms);
 
+    // Rule worker pool (rules.workers / rules.fairness / rules.max_per_tick)
+    let execution = {
+        async fn load_global(pool: &sqlx::SqlitePool, key: &str) -> Option<String> {
+            sqlx::query_scalar::<_, String>(
+                "SELECT value FROM service_config WHERE service_name = 'global' AND key = ?",
+            )
+            .bind(key)
+            .fetch_optional(pool)
+            .await
+            .ok()
+            .flatten()
+        }
+
+        let mut execution = ExecutionConfig::default();
+        if let Some(v) = load_global(&sqlite_pool, "rules.workers")
+            .await
+            .and_then(|s| s.parse().ok())
+        {
+            execution.workers = v;
+        }
+        if let Some(v) = load_global(&sqlite_pool, "rules.fairness").await {
+            match v.parse() {
+                Ok(policy) => execution.fairness = policy,
+                Err(e) => warn!("{}, using {}", e, execution.fairness.as_str()),
+            }
+        }
+        if let Some(v) = load_global(&sqlite_pool, "rules.max_per_tick")
+            .await
+            .and_then(|s| s.parse().ok())
+        {
+            execution.max_rules_per_tick = v;
+        }
+        execution
+    };
+
+    // Formula sandbox for calculation nodes (rules.calc_limits, JSON)
+    let calc_limits: CalcLimits = match sqlx::query_scalar::<_, String>(
+        "SELECT value FROM service_config WHERE service_name = 'global' AND key = 'rules.calc_limits'",
+    )
+    .fetch_optional(&sqlite_pool)
+    .await
+    .ok()
+    .flatten()
+    {
+        Some(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+            warn!("Invalid rules.calc_limits ({}), using defaults", e);
+            CalcLimits::default()
+        }),
+        None => CalcLimits::default(),
+    };
+
     // Initialize SharedVecRtdbReader for cross-process zero-copy reads
     // Uses smart path selection - works on any filesystem
     // Added retry mechanism for cold start race condition
@@ -168,14 +228,18 @@ async fn main() -> Result<()> {
     // Create rule scheduler with two-tier priority (SharedMemory > Redis)
     // Removed VecRtdb - using SharedMemory + Redis two-tier architecture
     let rule_log_root = PathBuf::from("logs/modsrv");
-    let scheduler = Arc::new(RuleScheduler::with_shared_reader(
-        rtdb,
-        routing_cache,
-        sqlite_pool.clone(),
-        tick_ms,
-        rule_log_root,
-        shared_reader,
-    ));
+    let scheduler = Arc::new(
+        RuleScheduler::with_shared_reader(
+            rtdb,
+            routing_cache,
+            sqlite_pool.clone(),
+            tick_ms,
+            rule_log_root,
+            shared_reader,
+        )
+        .with_execution_config(execution)
+        .with_calc_limits(calc_limits),
+    );
 
     // Load rules into scheduler
     match scheduler.load_rules().await {
@@ -191,10 +255,19 @@ async fn main() -> Result<()> {
     let app = app.merge(rule_routes);
 
     // Start HTTP service (model API + rule engine - port 6002)
-    let addr = SocketAddr::from(([0, 0, 0, 0], state.config.api.port));
+    let addr = match &args.bind_address {
+        Some(address) => address.parse::<SocketAddr>().map_err(|e| {
+            modsrv::ModSrvError::ConfigError(format!("Invalid bind address '{}': {}", address, e))
+        })?,
+        None => SocketAddr::from(([0, 0, 0, 0], state.config.api.port)),
+    };
 
     // Create socket for unified API (port 6002)
-    let socket = tokio::net::TcpSocket::new_v4()?;
+    let socket = if addr.is_ipv6() {
+        tokio::net::TcpSocket::new_v6()?
+    } else {
+        tokio::net::TcpSocket::new_v4()?
+    };
     socket.set_reuseaddr(true)?;
     socket.bind(addr)?;
     let listener = socket.listen(1024)?;
@@ -203,9 +276,16 @@ async fn main() -> Result<()> {
     info!("");
     info!("Model API endpoints (port {}):", state.config.api.port);
     info!("  GET /health - Health check");
+    info!("  GET /health/config - Configuration consistency report");
     info!("  GET/POST /api/instances - Instance management");
+    info!("  GET /api/naming-policy - Instance naming policy");
     info!("  GET /api/products - Product management");
     info!("  GET /api/instances/:id/data - Get instance data");
+    info!("  GET /api/search - Search channels, points, instances, products, rules");
+    info!("  GET/POST /api/aliases - Point alias registry (stable external IDs)");
+    info!("  GET /api/points/{{point}}?by=alias - Resolve point by alias or address");
+    info!("  GET /api/routing/cache - Routing cache diagnostics");
+    info!("  GET/POST /api/subscriptions - Webhooks for measurement changes");
     info!("  POST /api/instances/:id/sync - Sync measurement");
     info!("  POST /api/instances/:id/action - Execute action");
     info!("  POST /api/instances/sync/all - Sync all instances");
@@ -229,9 +309,12 @@ async fn main() -> Result<()> {
 
     // Spawn server task
     let server_task = async move {
-        if let Err(e) = axum::serve(listener, app)
-            .with_graceful_shutdown(shutdown_signal)
-            .await
+        if let Err(e) = axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+        )
+        .with_graceful_shutdown(shutdown_signal)
+        .await
         {
             error!("Server error: {}", e);
         }
@@ -253,6 +336,9 @@ async fn main() -> Result<()> {
     let server_handle = tokio::spawn(server_task);
     info!("Server started (port {})", state.config.api.port);
 
+    // Redis cleanup and instance sync run while the API is already serving
+    let deferred_handle = bootstrap::spawn_deferred_startup(&state);
+
     // Start rule scheduler in background
     let scheduler_handle = {
         let scheduler = Arc::clone(&scheduler);
@@ -262,6 +348,18 @@ async fn main() -> Result<()> {
     };
     info!("Rule scheduler started");
 
+    // Step ramped setpoints towards their targets
+    let ramp_handle = state
+        .ramp_engine
+        .clone()
+        .map(|ramp| tokio::spawn(ramp.run(shutdown_token.clone())));
+
+    // Push measurement changes to subscribed webhooks
+    let subscriptions_handle = tokio::spawn(Arc::clone(&state.subscriptions).run(
+        Arc::clone(&state.instance_manager.rtdb),
+        shutdown_token.clone(),
+    ));
+
     // Wait for shutdown signal (Ctrl+C or SIGTERM)
     common::shutdown::wait_for_shutdown().await;
     info!("Initiating graceful shutdown...");
@@ -293,9 +391,14 @@ async fn main() -> Result<()> {
         },
     }
 
-    // Abort warning monitor if still running
+    // Abort warning monitor and unfinished startup work if still running
     warning_handle.abort();
     let _ = warning_handle.await; // Ignore abort error
+    deferred_handle.abort();
+    if let Some(ramp_handle) = ramp_handle {
+        let _ = ramp_handle.await; // Stops on the cancelled token
+    }
+    let _ = subscriptions_handle.await; // Stops on the cancelled token
 
     info!("Model Service (with Rule Engine) shutdown complete");
     Ok(())
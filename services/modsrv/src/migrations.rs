@@ -0,0 +1,332 @@
+//! modsrv schema migrations
+//!
+//! Owns the instance, routing and calculation tables in `voltage.db`.
+//! Applied by modsrv on startup (together with the rule migrations, since
+//! modsrv hosts the rule engine) and by `monarch init`.
+//!
+//! Migration DDL is frozen rather than generated from the record structs in
+//! `config`; a column added to a record struct needs a new migration here.
+//! The baseline only creates what is missing, so it is safe on databases
+//! initialized before migrations existed.
+
+use anyhow::Result;
+use common::sqlite::migrations::{
+    has_column, Migration, MigrationFuture, MigrationReport, Migrator,
+};
+use sqlx::{SqliteConnection, SqlitePool};
+
+/// Ordered modsrv migrations
+pub static MIGRATIONS: &[Migration] = &[
+    Migration::sql(1, "baseline", BASELINE),
+    Migration::rust(
+        2,
+        "instances_version_column",
+        &[INSTANCES_VERSION],
+        instances_version_column,
+    ),
+    Migration::rust(
+        3,
+        "instances_parent_id",
+        &[INSTANCES_PARENT_ID],
+        instances_parent_id,
+    ),
+    Migration::sql(4, "point_aliases", POINT_ALIASES),
+    Migration::rust(
+        5,
+        "measurement_routing_publish",
+        &MEASUREMENT_ROUTING_PUBLISH,
+        measurement_routing_publish,
+    ),
+    Migration::sql(6, "data_subscriptions", DATA_SUBSCRIPTIONS_TABLE),
+];
+
+/// Migrator for the modsrv tables
+pub const fn migrator() -> Migrator {
+    Migrator::new("modsrv", MIGRATIONS)
+}
+
+/// Apply modsrv and rule migrations
+pub async fn migrate(pool: &SqlitePool) -> Result<Vec<MigrationReport>> {
+    Ok(vec![
+        migrator().run(pool).await?,
+        voltage_rules::migrations::migrator().run(pool).await?,
+    ])
+}
+
+/// Config tables, instances, routing, mappings and calculations
+const BASELINE: &str = r#"
+    CREATE TABLE IF NOT EXISTS service_config (
+        service_name TEXT NOT NULL,
+        key TEXT NOT NULL,
+        value TEXT NOT NULL,
+        type TEXT NOT NULL DEFAULT 'string',
+        description TEXT,
+        updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (service_name, key)
+    );
+
+    CREATE TABLE IF NOT EXISTS sync_metadata (
+        service TEXT PRIMARY KEY,
+        last_sync TEXT NOT NULL,
+        version TEXT
+    );
+
+    CREATE TABLE IF NOT EXISTS instances (
+        instance_id INTEGER PRIMARY KEY,
+        instance_name TEXT NOT NULL UNIQUE,
+        product_name TEXT NOT NULL,
+        properties TEXT,
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+
+    CREATE TABLE IF NOT EXISTS instance_tags (
+        instance_id INTEGER NOT NULL REFERENCES instances(instance_id) ON DELETE CASCADE,
+        tag_key TEXT NOT NULL,
+        tag_value TEXT NOT NULL,
+        PRIMARY KEY (instance_id, tag_key)
+    );
+
+    CREATE TABLE IF NOT EXISTS measurement_routing (
+        routing_id INTEGER PRIMARY KEY AUTOINCREMENT,
+        instance_id INTEGER NOT NULL REFERENCES instances(instance_id) ON DELETE CASCADE,
+        instance_name TEXT NOT NULL,
+        channel_id INTEGER REFERENCES channels(channel_id) ON DELETE SET NULL,
+        channel_type TEXT,
+        channel_point_id INTEGER,
+        measurement_id INTEGER NOT NULL,
+        description TEXT,
+        enabled BOOLEAN NOT NULL DEFAULT TRUE,
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        UNIQUE(instance_id, measurement_id),
+        CHECK(channel_type IN ('T','S'))
+    );
+
+    CREATE TABLE IF NOT EXISTS action_routing (
+        routing_id INTEGER PRIMARY KEY AUTOINCREMENT,
+        instance_id INTEGER NOT NULL REFERENCES instances(instance_id) ON DELETE CASCADE,
+        instance_name TEXT NOT NULL,
+        action_id INTEGER NOT NULL,
+        channel_id INTEGER REFERENCES channels(channel_id) ON DELETE SET NULL,
+        channel_type TEXT,
+        channel_point_id INTEGER,
+        description TEXT,
+        enabled BOOLEAN NOT NULL DEFAULT TRUE,
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        UNIQUE(instance_id, action_id),
+        CHECK(channel_type IN ('C','A'))
+    );
+
+    CREATE TABLE IF NOT EXISTS instance_mappings (
+        instance_id INTEGER,
+        point_type TEXT,  -- 'M' or 'A'
+        point_id INTEGER,
+        redis_key TEXT,
+        PRIMARY KEY (instance_id, point_type, point_id),
+        FOREIGN KEY (instance_id) REFERENCES instances(instance_id) ON DELETE CASCADE
+    );
+
+    -- Channel-instance point routing; each instance point has only one data source
+    CREATE TABLE IF NOT EXISTS point_mappings (
+        mapping_id INTEGER PRIMARY KEY AUTOINCREMENT,
+        instance_id INTEGER NOT NULL,
+        channel_id INTEGER NOT NULL,
+        channel_type TEXT NOT NULL CHECK(channel_type IN ('T','S','C','A')),
+        channel_point_id INTEGER NOT NULL,
+        instance_type TEXT NOT NULL CHECK(instance_type IN ('M','A')),
+        instance_point_id INTEGER NOT NULL,
+        description TEXT,
+        enabled BOOLEAN DEFAULT TRUE,
+        created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        UNIQUE(instance_id, instance_type, instance_point_id),
+        FOREIGN KEY (instance_id) REFERENCES instances(instance_id) ON DELETE CASCADE
+    );
+
+    -- Virtual/computed points
+    CREATE TABLE IF NOT EXISTS calculations (
+        calculation_id INTEGER PRIMARY KEY AUTOINCREMENT,
+        calculation_name TEXT NOT NULL UNIQUE,
+        description TEXT,
+        calculation_type TEXT NOT NULL,  -- JSON serialized CalculationType
+        output_inst INTEGER NOT NULL,    -- Output instance ID
+        output_type TEXT NOT NULL CHECK(output_type IN ('M', 'A')),
+        output_id INTEGER NOT NULL,      -- Output point ID
+        enabled BOOLEAN DEFAULT TRUE,
+        created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_instance_tags_kv ON instance_tags(tag_key, tag_value);
+    CREATE INDEX IF NOT EXISTS idx_measurement_routing_instance ON measurement_routing(instance_id);
+    CREATE INDEX IF NOT EXISTS idx_action_routing_instance ON action_routing(instance_id);
+    CREATE INDEX IF NOT EXISTS idx_mapping_channel ON point_mappings(channel_id, channel_type);
+    CREATE INDEX IF NOT EXISTS idx_mapping_instance ON point_mappings(instance_id);
+    CREATE INDEX IF NOT EXISTS idx_calc_output ON calculations(output_inst, output_type, output_id);
+"#;
+
+const INSTANCES_VERSION: &str =
+    "ALTER TABLE instances ADD COLUMN version INTEGER NOT NULL DEFAULT 1";
+
+const INSTANCES_PARENT_ID: &str = "ALTER TABLE instances ADD COLUMN parent_id INTEGER \
+     REFERENCES instances(instance_id) ON DELETE SET NULL";
+
+/// Stable external identifiers for channel and instance points
+const POINT_ALIASES: &str = r#"
+    CREATE TABLE IF NOT EXISTS point_aliases (
+        alias TEXT PRIMARY KEY,
+        scheme TEXT NOT NULL DEFAULT 'custom',
+        address TEXT NOT NULL,
+        description TEXT,
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        UNIQUE(scheme, address)
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_point_aliases_address ON point_aliases(address);
+"#;
+
+/// Report-by-exception columns read by the comsrv C2M publisher
+const MEASUREMENT_ROUTING_PUBLISH: [&str; 3] = [
+    "ALTER TABLE measurement_routing ADD COLUMN deadband REAL",
+    "ALTER TABLE measurement_routing ADD COLUMN deadband_pct REAL",
+    "ALTER TABLE measurement_routing ADD COLUMN refresh_secs INTEGER",
+];
+
+/// Webhook subscriptions of the change dispatcher
+const DATA_SUBSCRIPTIONS_TABLE: &str = r#"
+    CREATE TABLE IF NOT EXISTS data_subscriptions (
+        name TEXT PRIMARY KEY,
+        url TEXT NOT NULL,
+        secret TEXT,
+        instances TEXT NOT NULL,  -- JSON array of instance IDs
+        points TEXT,              -- JSON array of point IDs, NULL = all
+        min_interval_ms INTEGER NOT NULL DEFAULT 1000,
+        timeout_ms INTEGER NOT NULL DEFAULT 3000,
+        enabled BOOLEAN NOT NULL DEFAULT TRUE,
+        created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    )
+"#;
+
+/// Add the optimistic concurrency `version` column; existing rows start at 1
+fn instances_version_column(conn: &mut SqliteConnection) -> MigrationFuture<'_> {
+    Box::pin(async move {
+        if !has_column(&mut *conn, "instances", "version").await? {
+            sqlx::query(INSTANCES_VERSION).execute(&mut *conn).await?;
+        }
+        Ok(())
+    })
+}
+
+/// Add the topology `parent_id` column read by cloud sync
+///
+/// Databases created by `monarch init` never had it; those created by modsrv did.
+fn instances_parent_id(conn: &mut SqliteConnection) -> MigrationFuture<'_> {
+    Box::pin(async move {
+        if !has_column(&mut *conn, "instances", "parent_id").await? {
+            sqlx::query(INSTANCES_PARENT_ID).execute(&mut *conn).await?;
+        }
+        Ok(())
+    })
+}
+
+fn measurement_routing_publish(conn: &mut SqliteConnection) -> MigrationFuture<'_> {
+    Box::pin(async move {
+        for (column, ddl) in ["deadband", "deadband_pct", "refresh_secs"]
+            .into_iter()
+            .zip(MEASUREMENT_ROUTING_PUBLISH)
+        {
+            if !has_column(&mut *conn, "measurement_routing", column).await? {
+                sqlx::query(ddl).execute(&mut *conn).await?;
+            }
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_upgrades_pre_migration_instances_table() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::raw_sql(
+            "CREATE TABLE instances (instance_id INTEGER PRIMARY KEY, instance_name TEXT, \
+             product_name TEXT, properties TEXT);
+             INSERT INTO instances VALUES (1, 'pcs_01', 'PCS', NULL);",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let reports = migrate(&pool).await.unwrap();
+        assert_eq!(reports[0].applied, vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(reports[1].component, "rules");
+
+        let (version, parent): (i64, Option<i64>) =
+            sqlx::query_as("SELECT version, parent_id FROM instances WHERE instance_id = 1")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!((version, parent), (1, None));
+
+        let reports = migrate(&pool).await.unwrap();
+        assert!(reports.iter().all(|r| r.applied.is_empty()));
+    }
+
+    /// Every column of the record structs exists after migrating a new database
+    #[tokio::test]
+    async fn test_migrations_cover_record_structs() {
+        use crate::config::{
+            ACTION_ROUTING_TABLE, INSTANCES_TABLE, INSTANCE_TAGS_TABLE, MEASUREMENT_ROUTING_TABLE,
+            POINT_ALIASES_TABLE,
+        };
+
+        let connect = || {
+            sqlx::sqlite::SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect("sqlite::memory:")
+        };
+        let migrated = connect().await.unwrap();
+        migrator().run(&migrated).await.unwrap();
+        let generated = connect().await.unwrap();
+
+        for (table, ddl) in [
+            ("instances", INSTANCES_TABLE),
+            ("instance_tags", INSTANCE_TAGS_TABLE),
+            ("measurement_routing", MEASUREMENT_ROUTING_TABLE),
+            ("action_routing", ACTION_ROUTING_TABLE),
+            ("point_aliases", POINT_ALIASES_TABLE),
+        ] {
+            sqlx::query(ddl).execute(&generated).await.unwrap();
+            let columns = "SELECT name FROM pragma_table_info(?) ORDER BY name";
+            let expected: Vec<String> = sqlx::query_scalar(columns)
+                .bind(table)
+                .fetch_all(&generated)
+                .await
+                .unwrap();
+            let actual: Vec<String> = sqlx::query_scalar(columns)
+                .bind(table)
+                .fetch_all(&migrated)
+                .await
+                .unwrap();
+            for column in expected {
+                assert!(
+                    actual.contains(&column),
+                    "{}.{} has no migration",
+                    table,
+                    column
+                );
+            }
+        }
+    }
+}
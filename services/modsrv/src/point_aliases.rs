@@ -0,0 +1,531 @@
+//! Point Aliases
+//!
+//! Stable external identifiers (customer point codes, KKS / RDS-PP designations)
+//! for channel and instance points. SQLite `point_aliases` is the source of
+//! truth; Redis keeps `modsrv:alias` (alias → address) and the reverse
+//! `modsrv:alias:by_point[:{scheme}]` hashes so netsrv and hissrv can label
+//! their output. When points are renumbered only the alias address changes,
+//! integrations keep using the alias.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::config::InstanceRedisKeys;
+use crate::error::{ModSrvError, Result};
+use crate::redis_state;
+
+use super::instance_manager::InstanceManager;
+use voltage_model::{KeySpaceConfig, PointType};
+use voltage_rtdb::Rtdb;
+
+/// Scheme used when an alias does not name one
+pub const DEFAULT_SCHEME: &str = "custom";
+
+/// Maximum alias length
+const MAX_ALIAS_LEN: usize = 128;
+
+/// Internal address of a channel or instance point
+///
+/// Formatted as the runtime hash key plus the point ID:
+/// `inst:{id}:{M|A}:{point}` or `comsrv:{id}:{T|S|C|A}:{point}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PointAddress {
+    Instance {
+        instance_id: u32,
+        point_type: char,
+        point_id: u32,
+    },
+    Channel {
+        channel_id: u32,
+        point_type: char,
+        point_id: u32,
+    },
+}
+
+impl PointAddress {
+    /// Runtime hash holding the point value
+    pub fn hash_key(&self) -> String {
+        let keyspace = KeySpaceConfig::production_cached();
+        match *self {
+            Self::Instance {
+                instance_id,
+                point_type: 'A',
+                ..
+            } => keyspace.instance_action_key(instance_id),
+            Self::Instance { instance_id, .. } => keyspace.instance_measurement_key(instance_id),
+            Self::Channel {
+                channel_id,
+                point_type,
+                ..
+            } => {
+                // Channel addresses only parse with T/S/C/A
+                let point_type =
+                    PointType::from_str(&point_type.to_string()).unwrap_or(PointType::Telemetry);
+                keyspace.channel_key(channel_id, point_type)
+            },
+        }
+    }
+
+    /// Point ID (field in the runtime hash)
+    pub fn point_id(&self) -> u32 {
+        match *self {
+            Self::Instance { point_id, .. } | Self::Channel { point_id, .. } => point_id,
+        }
+    }
+}
+
+impl fmt::Display for PointAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.hash_key(), self.point_id())
+    }
+}
+
+impl FromStr for PointAddress {
+    type Err = ModSrvError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            ModSrvError::InvalidData(format!(
+                "Invalid point address '{}': expected inst:{{id}}:{{M|A}}:{{point}} \
+                 or comsrv:{{id}}:{{T|S|C|A}}:{{point}}",
+                s
+            ))
+        };
+        let parts: Vec<&str> = s.split(':').collect();
+        let [owner, id, point_type, point_id] = parts.as_slice() else {
+            return Err(invalid());
+        };
+        let id: u32 = id.parse().map_err(|_| invalid())?;
+        let point_id: u32 = point_id.parse().map_err(|_| invalid())?;
+        let point_type = match *point_type {
+            "M" | "A" | "T" | "S" | "C" => point_type.chars().next().unwrap_or_default(),
+            _ => return Err(invalid()),
+        };
+
+        match (*owner, point_type) {
+            ("inst", 'M' | 'A') => Ok(Self::Instance {
+                instance_id: id,
+                point_type,
+                point_id,
+            }),
+            ("comsrv", 'T' | 'S' | 'C' | 'A') => Ok(Self::Channel {
+                channel_id: id,
+                point_type,
+                point_id,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Alias of a channel or instance point
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct PointAlias {
+    /// External identifier, e.g. `=G1 MKA10 CE101` or `PLANT-PCS1-P`
+    pub alias: String,
+    /// Naming scheme (custom, kks, rds-pp, ...)
+    #[serde(default = "default_scheme")]
+    pub scheme: String,
+    /// Internal address: `inst:{id}:{M|A}:{point}` or `comsrv:{id}:{T|S|C|A}:{point}`
+    pub address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+fn default_scheme() -> String {
+    DEFAULT_SCHEME.to_string()
+}
+
+/// Validate an alias and its scheme
+///
+/// Aliases are trimmed, non-empty, at most 128 characters and free of commas
+/// (list separator), slashes (URL path segment) and control characters; inner
+/// spaces are allowed since KKS designations are often written with them.
+/// Schemes use `[a-z0-9_-]`.
+pub fn validate_alias(alias: &str, scheme: &str) -> Result<()> {
+    if alias.is_empty()
+        || alias.len() > MAX_ALIAS_LEN
+        || alias.trim() != alias
+        || alias
+            .chars()
+            .any(|c| c == ',' || c == '/' || c.is_control())
+    {
+        return Err(ModSrvError::InvalidData(format!(
+            "Invalid alias '{}': 1-{} characters without ',', '/' or surrounding spaces",
+            alias, MAX_ALIAS_LEN
+        )));
+    }
+    if scheme.is_empty()
+        || !scheme
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+    {
+        return Err(ModSrvError::InvalidData(format!(
+            "Invalid alias scheme '{}': use lowercase letters, digits, '_' or '-'",
+            scheme
+        )));
+    }
+    Ok(())
+}
+
+/// Replace point IDs by aliases in one section of instance data
+///
+/// `aliases` maps addresses to aliases; points without an alias keep their ID.
+fn alias_section(
+    points: Map<String, Value>,
+    hash_key: &str,
+    aliases: &HashMap<String, String>,
+) -> Map<String, Value> {
+    points
+        .into_iter()
+        .map(|(point, value)| {
+            let name = aliases
+                .get(&format!("{}:{}", hash_key, point))
+                .cloned()
+                .unwrap_or(point);
+            (name, value)
+        })
+        .collect()
+}
+
+fn alias_point_ids(ids: Value, hash_key: &str, aliases: &HashMap<String, String>) -> Value {
+    match ids {
+        Value::Array(ids) => Value::Array(
+            ids.into_iter()
+                .map(|id| match &id {
+                    Value::String(point) => aliases
+                        .get(&format!("{}:{}", hash_key, point))
+                        .map(|alias| Value::String(alias.clone()))
+                        .unwrap_or(id),
+                    _ => id,
+                })
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+/// Replace point IDs by aliases in instance data (full, filtered or delta)
+///
+/// `data_type` is the filter the data was read with; a filtered read is a
+/// flat map of one section.
+pub fn alias_instance_data(
+    data: Value,
+    instance_id: u32,
+    data_type: Option<&str>,
+    aliases: &HashMap<String, String>,
+) -> Value {
+    let measurements = InstanceRedisKeys::measurement_hash(instance_id);
+    let actions = InstanceRedisKeys::action_hash(instance_id);
+    let section_key = |section: &str| match section {
+        "measurements" => Some(&measurements),
+        "actions" | "overrides" => Some(&actions),
+        _ => None,
+    };
+
+    let Value::Object(mut data) = data else {
+        return data;
+    };
+    let is_delta = data.get("delta") == Some(&Value::Bool(true));
+    if !is_delta {
+        match data_type {
+            Some("measurement") => {
+                return Value::Object(alias_section(data, &measurements, aliases))
+            },
+            Some("action") => return Value::Object(alias_section(data, &actions, aliases)),
+            _ => {},
+        }
+    }
+
+    for (section, points) in data.iter_mut() {
+        if let (Some(hash_key), Value::Object(map)) = (section_key(section), &mut *points) {
+            *map = alias_section(std::mem::take(map), hash_key, aliases);
+        }
+    }
+    if let Some(Value::Object(removed)) = data.get_mut("removed") {
+        for (section, ids) in removed.iter_mut() {
+            if let Some(hash_key) = section_key(section) {
+                *ids = alias_point_ids(std::mem::take(ids), hash_key, aliases);
+            }
+        }
+    }
+    Value::Object(data)
+}
+
+impl<R: Rtdb + 'static> InstanceManager<R> {
+    /// List aliases, optionally filtered by scheme and address prefix
+    ///
+    /// `target` matches whole address segments: `inst:5` matches `inst:5:M:1`
+    /// but not `inst:50:M:1`.
+    pub async fn list_point_aliases(
+        &self,
+        scheme: Option<&str>,
+        target: Option<&str>,
+    ) -> Result<Vec<PointAlias>> {
+        let mut sql = "SELECT alias, scheme, address, description, updated_at \
+                       FROM point_aliases WHERE 1 = 1"
+            .to_string();
+        if scheme.is_some() {
+            sql.push_str(" AND scheme = ?");
+        }
+        if target.is_some() {
+            sql.push_str(" AND (address = ? OR address LIKE ? || ':%')");
+        }
+        sql.push_str(" ORDER BY scheme, alias");
+
+        let mut query = sqlx::query_as::<_, PointAlias>(&sql);
+        if let Some(scheme) = scheme {
+            query = query.bind(scheme);
+        }
+        if let Some(target) = target {
+            query = query.bind(target).bind(target);
+        }
+
+        Ok(query.fetch_all(&self.pool).await?)
+    }
+
+    /// Get a single alias
+    pub async fn get_point_alias(&self, alias: &str) -> Result<PointAlias> {
+        sqlx::query_as::<_, PointAlias>(
+            "SELECT alias, scheme, address, description, updated_at \
+             FROM point_aliases WHERE alias = ?",
+        )
+        .bind(alias)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| ModSrvError::AliasNotFound(alias.to_string()))
+    }
+
+    /// Create or re-point aliases in one transaction (SQLite first, Redis best effort)
+    ///
+    /// Re-pointing an existing alias is the intended way to follow a renumbered
+    /// point. Each address carries at most one alias per scheme.
+    pub async fn upsert_point_aliases(&self, aliases: &[PointAlias]) -> Result<usize> {
+        let mut addresses = Vec::with_capacity(aliases.len());
+        for alias in aliases {
+            validate_alias(&alias.alias, &alias.scheme)?;
+            addresses.push(alias.address.parse::<PointAddress>()?);
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        for (alias, address) in aliases.iter().zip(&addresses) {
+            let (table, column, id) = match *address {
+                PointAddress::Instance { instance_id, .. } => {
+                    ("instances", "instance_id", instance_id)
+                },
+                PointAddress::Channel { channel_id, .. } => ("channels", "channel_id", channel_id),
+            };
+            let (count,): (i64,) = sqlx::query_as(&format!(
+                "SELECT COUNT(*) FROM {} WHERE {} = ?",
+                table, column
+            ))
+            .bind(id as i64)
+            .fetch_one(&mut *tx)
+            .await?;
+            if count == 0 {
+                return Err(ModSrvError::InvalidData(format!(
+                    "Alias '{}' targets unknown {} {}",
+                    alias.alias, column, id
+                )));
+            }
+
+            let taken: Option<(String,)> = sqlx::query_as(
+                "SELECT alias FROM point_aliases WHERE scheme = ? AND address = ? AND alias != ?",
+            )
+            .bind(&alias.scheme)
+            .bind(address.to_string())
+            .bind(&alias.alias)
+            .fetch_optional(&mut *tx)
+            .await?;
+            if let Some((other,)) = taken {
+                return Err(ModSrvError::ConstraintViolation(format!(
+                    "{} already has alias '{}' in scheme '{}'",
+                    address, other, alias.scheme
+                )));
+            }
+
+            sqlx::query(
+                "INSERT INTO point_aliases (alias, scheme, address, description) \
+                 VALUES (?, ?, ?, ?) \
+                 ON CONFLICT(alias) DO UPDATE SET scheme = excluded.scheme, \
+                 address = excluded.address, description = excluded.description, \
+                 updated_at = CURRENT_TIMESTAMP",
+            )
+            .bind(&alias.alias)
+            .bind(&alias.scheme)
+            .bind(address.to_string())
+            .bind(&alias.description)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        self.publish_point_aliases_logged().await;
+        info!("Point aliases upserted: {}", aliases.len());
+        Ok(aliases.len())
+    }
+
+    /// Delete an alias
+    pub async fn delete_point_alias(&self, alias: &str) -> Result<()> {
+        let deleted = sqlx::query("DELETE FROM point_aliases WHERE alias = ?")
+            .bind(alias)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        if deleted == 0 {
+            return Err(ModSrvError::AliasNotFound(alias.to_string()));
+        }
+
+        self.publish_point_aliases_logged().await;
+        info!("Point alias deleted: {}", alias);
+        Ok(())
+    }
+
+    /// Resolve an alias to its internal address
+    pub async fn resolve_point_alias(&self, alias: &str) -> Result<PointAddress> {
+        self.get_point_alias(alias).await?.address.parse()
+    }
+
+    /// Address → alias map for addresses under `target`
+    ///
+    /// Without a scheme, the alias of the first scheme in name order wins
+    /// (same choice as the `modsrv:alias:by_point` hash).
+    pub async fn point_alias_map(
+        &self,
+        target: &str,
+        scheme: Option<&str>,
+    ) -> Result<HashMap<String, String>> {
+        let mut map = HashMap::new();
+        for alias in self.list_point_aliases(scheme, Some(target)).await? {
+            map.entry(alias.address).or_insert(alias.alias);
+        }
+        Ok(map)
+    }
+
+    /// Rewrite the Redis mirror of the alias registry from SQLite
+    pub async fn publish_point_aliases(&self) -> Result<usize> {
+        let rows: Vec<(String, String, String)> = sqlx::query_as(
+            "SELECT alias, scheme, address FROM point_aliases ORDER BY scheme, alias",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        redis_state::publish_point_aliases(self.rtdb.as_ref(), &rows).await?;
+        Ok(rows.len())
+    }
+
+    async fn publish_point_aliases_logged(&self) {
+        if let Err(e) = self.publish_point_aliases().await {
+            warn!(
+                "Point aliases updated in SQLite but Redis sync failed: {}. Will sync on next start.",
+                e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap and json! are acceptable
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_point_address_round_trip() {
+        for address in [
+            "inst:5:M:1",
+            "inst:5:A:20",
+            "comsrv:1001:T:3",
+            "comsrv:2:A:7",
+        ] {
+            assert_eq!(
+                address.parse::<PointAddress>().unwrap().to_string(),
+                address
+            );
+        }
+        let channel: PointAddress = "comsrv:1001:S:4".parse().unwrap();
+        assert_eq!(channel.hash_key(), "comsrv:1001:S");
+        assert_eq!(channel.point_id(), 4);
+        let action: PointAddress = "inst:5:A:20".parse().unwrap();
+        assert_eq!(
+            action.hash_key(),
+            KeySpaceConfig::production().instance_action_key(5)
+        );
+
+        for invalid in [
+            "inst:5:T:1",
+            "comsrv:1:M:1",
+            "inst:5:M",
+            "inst:x:M:1",
+            "modsrv:1:M:1",
+        ] {
+            assert!(invalid.parse::<PointAddress>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_validate_alias() {
+        assert!(validate_alias("=G1 MKA10 CE101", "kks").is_ok());
+        assert!(validate_alias("PLANT-PCS1-P", "rds-pp").is_ok());
+        assert!(validate_alias("", "custom").is_err());
+        assert!(validate_alias(" padded", "custom").is_err());
+        assert!(validate_alias("a,b", "custom").is_err());
+        assert!(validate_alias("a/b", "custom").is_err());
+        assert!(validate_alias("ok", "KKS").is_err());
+    }
+
+    #[test]
+    fn test_alias_instance_data() {
+        let aliases: HashMap<String, String> =
+            [("inst:5:M:1", "PCS1.P"), ("inst:5:A:2", "PCS1.P_SET")]
+                .into_iter()
+                .map(|(a, b)| (a.to_string(), b.to_string()))
+                .collect();
+
+        let full = alias_instance_data(
+            json!({
+                "measurements": {"1": "10.0", "3": "1.0"},
+                "actions": {"2": "5.0"},
+                "overrides": {"2": {"value": 5.0}}
+            }),
+            5,
+            None,
+            &aliases,
+        );
+        assert_eq!(
+            full,
+            json!({
+                "measurements": {"PCS1.P": "10.0", "3": "1.0"},
+                "actions": {"PCS1.P_SET": "5.0"},
+                "overrides": {"PCS1.P_SET": {"value": 5.0}}
+            })
+        );
+
+        let filtered = alias_instance_data(json!({"2": "5.0"}), 5, Some("action"), &aliases);
+        assert_eq!(filtered, json!({"PCS1.P_SET": "5.0"}));
+
+        let delta = alias_instance_data(
+            json!({
+                "delta": true,
+                "since": 1,
+                "version": 2,
+                "measurements": {},
+                "removed": {"measurements": ["1"]}
+            }),
+            5,
+            Some("measurement"),
+            &aliases,
+        );
+        assert_eq!(delta["removed"], json!({"measurements": ["PCS1.P"]}));
+        assert_eq!(delta["version"], json!(2));
+    }
+}
@@ -33,111 +33,13 @@ impl ProductLoader {
         Self { pool }
     }
 
-    /// Initialize database schema for instances and mappings
+    /// Bring the instance and rule tables up to date (see [`crate::migrations`])
     ///
-    /// Note: Product tables are no longer created - products come from compile-time definitions.
-    /// This method only creates instance-related tables.
+    /// Product tables are no longer created - products come from compile-time definitions.
     pub async fn init_schema(&self) -> Result<()> {
-        debug!("Init instance tables");
-
-        // Create instances table with parent_id for topology hierarchy
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS instances (
-                instance_id INTEGER PRIMARY KEY,
-                instance_name TEXT UNIQUE NOT NULL,
-                product_name TEXT NOT NULL,
-                parent_id INTEGER,
-                properties TEXT,  -- JSON format
-                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (parent_id) REFERENCES instances(instance_id) ON DELETE SET NULL
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Create instance mappings table for point-to-redis-key mappings
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS instance_mappings (
-                instance_id INTEGER,
-                point_type TEXT,  -- 'M' or 'A'
-                point_id INTEGER,
-                redis_key TEXT,
-                PRIMARY KEY (instance_id, point_type, point_id),
-                FOREIGN KEY (instance_id) REFERENCES instances(instance_id) ON DELETE CASCADE
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Create point mappings table for channel-instance point routing
-        // UNIQUE constraint ensures each instance point has only one data source
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS point_mappings (
-                mapping_id INTEGER PRIMARY KEY AUTOINCREMENT,
-                instance_id INTEGER NOT NULL,
-                channel_id INTEGER NOT NULL,
-                channel_type TEXT NOT NULL CHECK(channel_type IN ('T','S','C','A')),
-                channel_point_id INTEGER NOT NULL,
-                instance_type TEXT NOT NULL CHECK(instance_type IN ('M','A')),
-                instance_point_id INTEGER NOT NULL,
-                description TEXT,
-                enabled BOOLEAN DEFAULT TRUE,
-                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-                UNIQUE(instance_id, instance_type, instance_point_id),
-                FOREIGN KEY (instance_id) REFERENCES instances(instance_id) ON DELETE CASCADE
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Create indexes
-        sqlx::query(
-            "CREATE INDEX IF NOT EXISTS idx_mapping_channel ON point_mappings(channel_id, channel_type)",
-        )
-        .execute(&self.pool)
-        .await?;
-
-        sqlx::query(
-            "CREATE INDEX IF NOT EXISTS idx_mapping_instance ON point_mappings(instance_id)",
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Calculations table for virtual/computed points
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS calculations (
-                calculation_id INTEGER PRIMARY KEY AUTOINCREMENT,
-                calculation_name TEXT NOT NULL UNIQUE,
-                description TEXT,
-                calculation_type TEXT NOT NULL,  -- JSON serialized CalculationType
-                output_inst INTEGER NOT NULL,    -- Output instance ID
-                output_type TEXT NOT NULL CHECK(output_type IN ('M', 'A')),
-                output_id INTEGER NOT NULL,      -- Output point ID
-                enabled BOOLEAN DEFAULT TRUE,
-                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Index for efficient lookup by output point
-        sqlx::query(
-            "CREATE INDEX IF NOT EXISTS idx_calc_output ON calculations(output_inst, output_type, output_id)",
-        )
-        .execute(&self.pool)
-        .await?;
-
-        debug!("Instance tables ready");
+        for report in crate::migrations::migrate(&self.pool).await? {
+            debug!("{} schema at v{}", report.component, report.current_version);
+        }
         Ok(())
     }
 
@@ -0,0 +1,442 @@
+//! Ramp-Rate Limiting of Action Setpoints
+//!
+//! Grid codes limit how fast curtailment and power setpoints may change.
+//! Ramp limits are declared per product in `modsrv.yaml`; a requested step
+//! larger than one tick's allowance is not written directly but ramped by a
+//! background task, which writes the intermediate setpoints through normal
+//! routing (overrides and interlocks still apply to every step). The engine is
+//! installed as the process-wide `voltage_routing::ramp`.
+//!
+//! ```yaml
+//! ramp_limits:
+//!   PCS:
+//!     - action: 1                       # active power setpoint
+//!       rate_pct_per_min: 10            # max change per minute, % of nominal
+//!       nominal_property: rated_power   # instance property (or `nominal: 500`)
+//! ```
+//!
+//! A new request while a ramp is running retargets it. The ramp is cancelled
+//! when the point is overridden locally or a step is refused by an interlock.
+//! Points without a current value are written directly.
+
+use anyhow::{bail, Result};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use voltage_routing::ramp::{ActionRamp, RampFuture};
+use voltage_routing::RampStart;
+use voltage_rtdb::{KeySpaceConfig, RoutingCache, Rtdb};
+
+use crate::interlock::{instance_profile, numeric_property, product_sections};
+
+/// Ramp limits per product name
+pub type RampConfig = HashMap<String, Vec<RampLimit>>;
+
+/// Interval between ramp steps
+pub const RAMP_TICK: Duration = Duration::from_secs(1);
+
+/// Ramp limit of one action point of a product
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RampLimit {
+    /// Limited action point ID
+    pub action: u32,
+    /// Maximum change per minute in percent of nominal
+    pub rate_pct_per_min: f64,
+    /// Fixed nominal value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nominal: Option<f64>,
+    /// Instance property holding the nominal value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nominal_property: Option<String>,
+}
+
+impl RampLimit {
+    fn validate(&self, product: &str) -> Result<()> {
+        let ctx = format!("ramp limit {}/action {}", product, self.action);
+        if !(self.rate_pct_per_min.is_finite() && self.rate_pct_per_min > 0.0) {
+            bail!("{}: rate_pct_per_min must be positive", ctx);
+        }
+        match (self.nominal, &self.nominal_property) {
+            (Some(n), None) if n.is_finite() && n > 0.0 => Ok(()),
+            (Some(_), None) => bail!("{}: nominal must be positive", ctx),
+            (None, Some(p)) if !p.is_empty() => Ok(()),
+            _ => bail!(
+                "{}: exactly one of nominal / nominal_property required",
+                ctx
+            ),
+        }
+    }
+
+    /// Allowed change per second for an instance
+    fn rate_per_sec(&self, properties: &serde_json::Value) -> Result<f64> {
+        let nominal = match (&self.nominal, &self.nominal_property) {
+            (Some(n), _) => *n,
+            (None, Some(name)) => numeric_property(properties, name)?,
+            (None, None) => bail!("no nominal value"),
+        };
+        if !(nominal.is_finite() && nominal > 0.0) {
+            bail!("nominal value {} is not positive", nominal);
+        }
+        Ok(self.rate_pct_per_min / 100.0 * nominal / 60.0)
+    }
+}
+
+/// A ramp in progress
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ActiveRamp {
+    /// Last written setpoint
+    pub current: f64,
+    /// Requested setpoint
+    pub target: f64,
+    /// Allowed change per second
+    pub rate_per_sec: f64,
+}
+
+impl ActiveRamp {
+    /// Setpoint after `elapsed`, never overshooting the target
+    fn next(&self, elapsed: Duration) -> f64 {
+        let max_step = self.rate_per_sec * elapsed.as_secs_f64();
+        let remaining = self.target - self.current;
+        if remaining.abs() <= max_step {
+            self.target
+        } else {
+            self.current + max_step.copysign(remaining)
+        }
+    }
+}
+
+/// Ramps limited action writes towards their targets
+pub struct RampEngine<R: Rtdb> {
+    rtdb: Arc<R>,
+    pool: SqlitePool,
+    routing_cache: Arc<RoutingCache>,
+    /// product -> action point -> limit
+    limits: HashMap<String, HashMap<u32, RampLimit>>,
+    /// (instance_id, point_id) -> ramp
+    active: DashMap<(u32, String), ActiveRamp>,
+}
+
+impl<R: Rtdb> RampEngine<R> {
+    /// Validate the declarations; fails on the first invalid limit
+    pub fn new(
+        rtdb: Arc<R>,
+        pool: SqlitePool,
+        routing_cache: Arc<RoutingCache>,
+        config: &RampConfig,
+    ) -> Result<Self> {
+        let mut limits: HashMap<String, HashMap<u32, RampLimit>> = HashMap::new();
+        for (product, defs) in config {
+            for def in defs {
+                def.validate(product)?;
+                if limits
+                    .entry(product.clone())
+                    .or_default()
+                    .insert(def.action, def.clone())
+                    .is_some()
+                {
+                    bail!(
+                        "ramp limit {}/action {} declared twice",
+                        product,
+                        def.action
+                    );
+                }
+            }
+        }
+        Ok(Self {
+            rtdb,
+            pool,
+            routing_cache,
+            limits,
+            active: DashMap::new(),
+        })
+    }
+
+    /// Number of declared ramp limits
+    pub fn len(&self) -> usize {
+        self.limits.values().map(HashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Ramps in progress for an instance, ordered by point ID
+    pub fn active_ramps(&self, instance_id: u32) -> Vec<(String, ActiveRamp)> {
+        let mut ramps: Vec<_> = self
+            .active
+            .iter()
+            .filter(|e| e.key().0 == instance_id)
+            .map(|e| (e.key().1.clone(), *e.value()))
+            .collect();
+        ramps.sort_by(|a, b| a.0.cmp(&b.0));
+        ramps
+    }
+
+    /// Take over a requested write if it must be ramped
+    pub async fn start(&self, instance_id: u32, point_id: &str, value: f64) -> Option<RampStart> {
+        let action_id = point_id.parse::<u32>().ok()?;
+        if self.limits.is_empty() {
+            return None;
+        }
+
+        let key = (instance_id, point_id.to_string());
+        if let Some(mut ramp) = self.active.get_mut(&key) {
+            ramp.target = value;
+            return Some(RampStart {
+                from: ramp.current,
+                target: value,
+            });
+        }
+
+        let (product, properties) = match instance_profile(&self.pool, instance_id).await {
+            Ok(Some(profile)) => profile,
+            Ok(None) => return None,
+            Err(e) => {
+                warn!("Ramp lookup for instance {} failed: {}", instance_id, e);
+                return None;
+            },
+        };
+        let limit = self.limits.get(&product)?.get(&action_id)?;
+        let rate_per_sec = match limit.rate_per_sec(&properties) {
+            Ok(rate) => rate,
+            Err(e) => {
+                warn!(
+                    "Ramp limit of instance {} action {} unusable, writing directly: {:#}",
+                    instance_id, point_id, e
+                );
+                return None;
+            },
+        };
+        let current = self.current_value(instance_id, point_id).await?;
+        if (value - current).abs() <= rate_per_sec * RAMP_TICK.as_secs_f64() {
+            return None;
+        }
+
+        let ramp = ActiveRamp {
+            current,
+            target: value,
+            rate_per_sec,
+        };
+        // A concurrent request may have started the ramp meanwhile; keep its position
+        let from = self
+            .active
+            .entry(key)
+            .and_modify(|r| r.target = value)
+            .or_insert(ramp)
+            .current;
+        debug!(
+            "Instance {} action {} ramping {} -> {} ({}/s)",
+            instance_id, point_id, from, value, rate_per_sec
+        );
+        Some(RampStart {
+            from,
+            target: value,
+        })
+    }
+
+    async fn current_value(&self, instance_id: u32, point_id: &str) -> Option<f64> {
+        let key = KeySpaceConfig::production_cached().instance_action_key(instance_id);
+        let raw = self.rtdb.hash_get(&key, point_id).await.ok()??;
+        String::from_utf8_lossy(&raw)
+            .trim()
+            .parse()
+            .ok()
+            .filter(|v: &f64| v.is_finite())
+    }
+
+    /// Write the next setpoint of every active ramp
+    pub async fn step(&self, elapsed: Duration) {
+        let steps: Vec<((u32, String), f64)> = self
+            .active
+            .iter()
+            .map(|e| (e.key().clone(), e.value().next(elapsed)))
+            .collect();
+
+        for (key, next) in steps {
+            let (instance_id, point_id) = (key.0, key.1.as_str());
+            let result = voltage_routing::set_action_point_immediate(
+                self.rtdb.as_ref(),
+                &self.routing_cache,
+                instance_id,
+                point_id,
+                next,
+            )
+            .await;
+            match result {
+                Ok(outcome) if outcome.is_overridden() => {
+                    self.active.remove(&key);
+                    info!(
+                        "Ramp of instance {} action {} cancelled: point overridden",
+                        instance_id, point_id
+                    );
+                },
+                Ok(_) => {
+                    if let Some(mut ramp) = self.active.get_mut(&key) {
+                        ramp.current = next;
+                    }
+                    self.active.remove_if(&key, |_, r| r.current == r.target);
+                },
+                Err(e) => {
+                    self.active.remove(&key);
+                    warn!(
+                        "Ramp of instance {} action {} cancelled at {}: {}",
+                        instance_id, point_id, next, e
+                    );
+                },
+            }
+        }
+    }
+
+    /// Step the active ramps every [`RAMP_TICK`] until cancelled
+    pub async fn run(self: Arc<Self>, token: CancellationToken) {
+        let mut interval = tokio::time::interval(RAMP_TICK);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut last = Instant::now();
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => break,
+                _ = interval.tick() => {},
+            }
+            let now = Instant::now();
+            if !self.active.is_empty() {
+                self.step(now - last).await;
+            }
+            last = now;
+        }
+        if !self.active.is_empty() {
+            warn!(
+                "{} ramps stopped before reaching their target",
+                self.active.len()
+            );
+        }
+    }
+}
+
+impl<R: Rtdb> ActionRamp for RampEngine<R> {
+    fn intercept<'a>(&'a self, instance_id: u32, point_id: &'a str, value: f64) -> RampFuture<'a> {
+        Box::pin(self.start(instance_id, point_id, value))
+    }
+}
+
+/// Read the `ramp_limits.{product}` entries of the flattened service config
+pub fn from_service_config(extra_config: &serde_json::Value) -> Result<RampConfig> {
+    product_sections(extra_config, "ramp_limits")
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use voltage_rtdb::MemoryRtdb;
+
+    async fn setup() -> RampEngine<MemoryRtdb> {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::query(
+            "CREATE TABLE instances (instance_id INTEGER PRIMARY KEY, product_name TEXT, properties TEXT)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO instances VALUES (1, 'PCS', '{\"rated_power\": 600}')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let config: RampConfig = serde_yaml::from_str(
+            "PCS: [{action: 1, rate_pct_per_min: 10, nominal_property: rated_power}]",
+        )
+        .unwrap();
+        let rtdb = Arc::new(MemoryRtdb::new());
+        rtdb.hash_set("inst:1:A", "1", Bytes::from("0"))
+            .await
+            .unwrap();
+        RampEngine::new(rtdb, pool, Arc::new(RoutingCache::new()), &config).unwrap()
+    }
+
+    #[test]
+    fn test_ramp_step_never_overshoots() {
+        let ramp = ActiveRamp {
+            current: 0.0,
+            target: 10.0,
+            rate_per_sec: 4.0,
+        };
+        assert_eq!(ramp.next(Duration::from_secs(1)), 4.0);
+        assert_eq!(ramp.next(Duration::from_secs(3)), 10.0);
+        let down = ActiveRamp {
+            current: 10.0,
+            target: 0.0,
+            ..ramp
+        };
+        assert_eq!(down.next(Duration::from_millis(500)), 8.0);
+    }
+
+    #[tokio::test]
+    async fn test_large_steps_are_ramped_to_target() {
+        let engine = setup().await;
+        assert_eq!(engine.len(), 1);
+
+        // 10%/min of 600 = 1 per second: a step of 1 is written directly
+        assert_eq!(engine.start(1, "1", 1.0).await, None);
+        assert_eq!(engine.start(1, "2", 50.0).await, None); // unlimited point
+        assert_eq!(engine.start(42, "1", 50.0).await, None); // unknown instance
+
+        let start = engine.start(1, "1", 2.5).await.unwrap();
+        assert_eq!(start.from, 0.0);
+        assert_eq!(engine.active_ramps(1).len(), 1);
+
+        engine.step(Duration::from_secs(2)).await;
+        let raw = engine
+            .rtdb
+            .hash_get("inst:1:A", "1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&raw).parse::<f64>().unwrap(), 2.0);
+
+        // A new request retargets the running ramp
+        let start = engine.start(1, "1", 2.2).await.unwrap();
+        assert_eq!(start.from, 2.0);
+        engine.step(Duration::from_secs(1)).await;
+        assert!(engine.active_ramps(1).is_empty());
+        let raw = engine
+            .rtdb
+            .hash_get("inst:1:A", "1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&raw).parse::<f64>().unwrap(), 2.2);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_limits_are_rejected() {
+        let pool = SqlitePool::connect_lazy("sqlite::memory:").unwrap();
+        let rtdb = Arc::new(MemoryRtdb::new());
+        let cache = Arc::new(RoutingCache::new());
+        for yaml in [
+            "PCS: [{action: 1, rate_pct_per_min: 0, nominal: 100}]",
+            "PCS: [{action: 1, rate_pct_per_min: 10}]",
+            "PCS: [{action: 1, rate_pct_per_min: 10, nominal: -5}]",
+            "PCS: [{action: 1, rate_pct_per_min: 10, nominal: 100, nominal_property: p}]",
+            "PCS: [{action: 1, rate_pct_per_min: 10, nominal: 100}, {action: 1, rate_pct_per_min: 5, nominal: 100}]",
+        ] {
+            let config: RampConfig = serde_yaml::from_str(yaml).unwrap();
+            assert!(
+                RampEngine::new(Arc::clone(&rtdb), pool.clone(), Arc::clone(&cache), &config)
+                    .is_err(),
+                "{}",
+                yaml
+            );
+        }
+
+        let extra = serde_json::json!({
+            "ramp_limits.PCS": "[{\"action\": 1, \"rate_pct_per_min\": 10, \"nominal\": 500}]"
+        });
+        let parsed = from_service_config(&extra).unwrap();
+        assert_eq!(parsed["PCS"][0].nominal, Some(500.0));
+    }
+}
@@ -0,0 +1,816 @@
+//! Command Recipes of Instances
+//!
+//! A recipe is a named, ordered list of action writes declared per product in
+//! `modsrv.yaml`, such as the start sequence of a BESS. Each step writes one
+//! action point through normal routing (overrides, interlocks and ramps
+//! apply), waits its delay and then its verification condition before the
+//! next step runs.
+//!
+//! ```yaml
+//! recipes:
+//!   BESS:
+//!     - name: start
+//!       description: "Close DC breaker, then start the PCS"
+//!       steps:
+//!         - name: close_dc_breaker
+//!           action: 3
+//!           value: 1
+//!           verify:
+//!             require: "dc_closed == 1.0"
+//!             variables: { dc_closed: "M:5" }
+//!             timeout_ms: 5000
+//!         - name: start_pcs
+//!           action: 1
+//!           value: 1
+//!           delay_ms: 2000            # settle time after the write
+//! ```
+//!
+//! `POST /api/instances/{id}/recipes/{name}/execute` starts a run in the
+//! background; its step-by-step progress is served by
+//! `GET /api/instances/{id}/recipe-runs/{run_id}` until it is aborted or
+//! finishes. One recipe runs per instance at a time. A run fails at the first
+//! step whose write is refused or whose verification does not hold within its
+//! timeout; the remaining steps are skipped. Verification variables use the
+//! interlock sources (see [`crate::interlock`]), `value` is the written value.
+//!
+//! Runs live in memory only; finished runs are dropped oldest first once
+//! [`MAX_RUNS`] are kept.
+
+use anyhow::{bail, Context, Result};
+use evalexpr::{ContextWithMutableVariables, HashMapContext, Node, Value};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+use voltage_rtdb::{RoutingCache, Rtdb};
+
+use crate::error::ModSrvError;
+use crate::interlock::{
+    bind_variables, instance_profile, product_sections, resolve_variable, VariableSource,
+    VALUE_VARIABLE,
+};
+
+/// Recipes per product name
+pub type RecipeConfig = HashMap<String, Vec<RecipeDef>>;
+
+/// Runs kept before finished ones are dropped
+pub const MAX_RUNS: usize = 256;
+
+/// Interval between evaluations of a step verification
+pub const VERIFY_POLL: Duration = Duration::from_millis(200);
+
+fn default_verify_timeout_ms() -> u64 {
+    10_000
+}
+
+/// Recipe declaration of a product
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecipeDef {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub steps: Vec<RecipeStep>,
+}
+
+/// One action write of a recipe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecipeStep {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Action point ID
+    pub action: u32,
+    pub value: f64,
+    /// Wait after the write, before verification
+    #[serde(default)]
+    pub delay_ms: u64,
+    /// Condition that must hold before the next step
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verify: Option<StepVerify>,
+}
+
+/// Verification of a step
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepVerify {
+    pub require: String,
+    /// Variable name -> source (`M:2`, `A:1`, `P:rating`, `inst:9:M:1`)
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+    /// How long the condition may take to hold (default 10 s)
+    #[serde(default = "default_verify_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+/// Verification with parsed expression
+struct CompiledVerify {
+    require: Node,
+    variables: Vec<(String, VariableSource)>,
+    timeout: Duration,
+}
+
+/// Recipe with parsed verifications (one entry per step)
+struct CompiledRecipe {
+    def: RecipeDef,
+    verify: Vec<Option<CompiledVerify>>,
+}
+
+impl CompiledRecipe {
+    fn compile(product: &str, def: RecipeDef) -> Result<Self> {
+        let ctx = format!("recipe {}/{}", product, def.name);
+        if def.name.trim().is_empty() {
+            bail!("recipe of {}: name must not be empty", product);
+        }
+        if def.steps.is_empty() {
+            bail!("{}: no steps", ctx);
+        }
+
+        let mut verify = Vec::with_capacity(def.steps.len());
+        for (index, step) in def.steps.iter().enumerate() {
+            let step_ctx = || format!("{}: step {}", ctx, index + 1);
+            if !step.value.is_finite() {
+                bail!("{}: value must be finite", step_ctx());
+            }
+            let Some(v) = &step.verify else {
+                verify.push(None);
+                continue;
+            };
+            let require = evalexpr::build_operator_tree(&v.require)
+                .with_context(|| format!("{}: invalid 'require'", step_ctx()))?;
+            let variables = bind_variables(&v.variables, [&require]).with_context(step_ctx)?;
+            verify.push(Some(CompiledVerify {
+                require,
+                variables,
+                timeout: Duration::from_millis(v.timeout_ms),
+            }));
+        }
+        Ok(Self { def, verify })
+    }
+}
+
+/// Run lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Running,
+    Completed,
+    Failed,
+    Aborted,
+}
+
+impl RunStatus {
+    pub fn is_finished(self) -> bool {
+        self != Self::Running
+    }
+}
+
+/// Step lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Pending,
+    /// Writing or waiting for the delay
+    Running,
+    /// Waiting for the verification to hold
+    Verifying,
+    Completed,
+    Failed,
+    /// Not run because the recipe failed or was aborted first
+    Skipped,
+}
+
+/// Progress of one step
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepProgress {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub action: u32,
+    pub value: f64,
+    pub status: StepStatus,
+    /// Routing status of the write (`success`, `ramping`, ...)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub write_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// State of a recipe run
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecipeRun {
+    pub run_id: String,
+    pub instance_id: u32,
+    pub recipe: String,
+    pub status: RunStatus,
+    /// Index of the step in progress (or where the run stopped)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_step: Option<usize>,
+    /// Start time (milliseconds)
+    pub started_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub steps: Vec<StepProgress>,
+}
+
+impl RecipeRun {
+    fn step(&mut self, index: usize) -> Option<&mut StepProgress> {
+        self.steps.get_mut(index)
+    }
+
+    fn start_step(&mut self, index: usize) {
+        self.current_step = Some(index);
+        if let Some(step) = self.step(index) {
+            step.status = StepStatus::Running;
+            step.started_at = Some(now_ms());
+        }
+    }
+
+    fn finish_step(&mut self, index: usize) {
+        if let Some(step) = self.step(index) {
+            step.status = StepStatus::Completed;
+            step.finished_at = Some(now_ms());
+        }
+    }
+
+    /// End the run; unfinished steps are skipped
+    fn finish(&mut self, status: RunStatus, error: Option<String>) {
+        let now = now_ms();
+        self.status = status;
+        self.finished_at = Some(now);
+        self.error = error;
+        for step in &mut self.steps {
+            match step.status {
+                StepStatus::Pending => step.status = StepStatus::Skipped,
+                StepStatus::Running | StepStatus::Verifying => {
+                    step.status = if status == RunStatus::Failed {
+                        StepStatus::Failed
+                    } else {
+                        StepStatus::Skipped
+                    };
+                    step.finished_at = Some(now);
+                    step.error = self.error.clone();
+                },
+                _ => {},
+            }
+        }
+    }
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// Why a step stopped the run
+enum StepError {
+    Aborted,
+    Failed(String),
+}
+
+/// A run with its abort token
+struct RunSlot {
+    run: RecipeRun,
+    token: CancellationToken,
+}
+
+/// Executes the declared recipes of instances
+pub struct RecipeEngine<R: Rtdb> {
+    rtdb: Arc<R>,
+    pool: SqlitePool,
+    routing_cache: Arc<RoutingCache>,
+    /// product -> recipe name -> recipe
+    recipes: HashMap<String, HashMap<String, Arc<CompiledRecipe>>>,
+    runs: Mutex<VecDeque<RunSlot>>,
+    next_seq: AtomicU64,
+}
+
+impl<R: Rtdb + 'static> RecipeEngine<R> {
+    /// Compile the declarations; fails on the first invalid recipe
+    pub fn new(
+        rtdb: Arc<R>,
+        pool: SqlitePool,
+        routing_cache: Arc<RoutingCache>,
+        config: &RecipeConfig,
+    ) -> Result<Self> {
+        let mut recipes: HashMap<String, HashMap<String, Arc<CompiledRecipe>>> = HashMap::new();
+        for (product, defs) in config {
+            for def in defs {
+                let compiled = CompiledRecipe::compile(product, def.clone())?;
+                if recipes
+                    .entry(product.clone())
+                    .or_default()
+                    .insert(def.name.clone(), Arc::new(compiled))
+                    .is_some()
+                {
+                    bail!("recipe {}/{} declared twice", product, def.name);
+                }
+            }
+        }
+        Ok(Self {
+            rtdb,
+            pool,
+            routing_cache,
+            recipes,
+            runs: Mutex::new(VecDeque::new()),
+            next_seq: AtomicU64::new(0),
+        })
+    }
+
+    /// Number of declared recipes
+    pub fn len(&self) -> usize {
+        self.recipes.values().map(HashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    async fn profile(
+        &self,
+        instance_id: u32,
+    ) -> std::result::Result<(String, serde_json::Value), ModSrvError> {
+        instance_profile(&self.pool, instance_id)
+            .await
+            .map_err(|e| ModSrvError::DatabaseError(format!("Instance lookup failed: {}", e)))?
+            .ok_or_else(|| ModSrvError::InstanceNotFound(instance_id.to_string()))
+    }
+
+    /// Recipes of an instance's product, ordered by name
+    pub async fn recipes(
+        &self,
+        instance_id: u32,
+    ) -> std::result::Result<Vec<RecipeDef>, ModSrvError> {
+        let (product, _) = self.profile(instance_id).await?;
+        let mut defs: Vec<RecipeDef> = self
+            .recipes
+            .get(&product)
+            .map(|r| r.values().map(|c| c.def.clone()).collect())
+            .unwrap_or_default();
+        defs.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(defs)
+    }
+
+    /// Start a recipe on an instance; the steps run in the background
+    pub async fn execute(
+        self: &Arc<Self>,
+        instance_id: u32,
+        name: &str,
+    ) -> std::result::Result<RecipeRun, ModSrvError> {
+        let (product, properties) = self.profile(instance_id).await?;
+        let recipe = self
+            .recipes
+            .get(&product)
+            .and_then(|r| r.get(name))
+            .cloned()
+            .ok_or_else(|| {
+                ModSrvError::RecipeNotFound(format!("{} has no recipe '{}'", product, name))
+            })?;
+
+        let token = CancellationToken::new();
+        let run = {
+            let mut runs = self.runs.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(active) = runs
+                .iter()
+                .find(|s| s.run.instance_id == instance_id && !s.run.status.is_finished())
+            {
+                return Err(ModSrvError::RecipeRunning(format!(
+                    "instance {} is running recipe '{}' ({})",
+                    instance_id, active.run.recipe, active.run.run_id
+                )));
+            }
+            while runs.len() >= MAX_RUNS {
+                match runs.iter().position(|s| s.run.status.is_finished()) {
+                    Some(oldest) => {
+                        runs.remove(oldest);
+                    },
+                    None => break,
+                }
+            }
+
+            let started_at = now_ms();
+            let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+            let run = RecipeRun {
+                run_id: format!("recipe_{}_{}_{}", instance_id, started_at, seq),
+                instance_id,
+                recipe: name.to_string(),
+                status: RunStatus::Running,
+                current_step: None,
+                started_at,
+                finished_at: None,
+                error: None,
+                steps: recipe
+                    .def
+                    .steps
+                    .iter()
+                    .map(|s| StepProgress {
+                        name: s.name.clone(),
+                        action: s.action,
+                        value: s.value,
+                        status: StepStatus::Pending,
+                        write_status: None,
+                        started_at: None,
+                        finished_at: None,
+                        error: None,
+                    })
+                    .collect(),
+            };
+            runs.push_back(RunSlot {
+                run: run.clone(),
+                token: token.clone(),
+            });
+            run
+        };
+
+        info!(
+            "Instance {} recipe '{}' started ({})",
+            instance_id, name, run.run_id
+        );
+        tokio::spawn(Arc::clone(self).drive(
+            run.run_id.clone(),
+            instance_id,
+            properties,
+            recipe,
+            token,
+        ));
+        Ok(run)
+    }
+
+    /// Run of an instance
+    pub fn get(&self, instance_id: u32, run_id: &str) -> Option<RecipeRun> {
+        let runs = self.runs.lock().unwrap_or_else(PoisonError::into_inner);
+        runs.iter()
+            .find(|s| s.run.instance_id == instance_id && s.run.run_id == run_id)
+            .map(|s| s.run.clone())
+    }
+
+    /// Runs of an instance, newest first
+    pub fn runs(&self, instance_id: u32) -> Vec<RecipeRun> {
+        let runs = self.runs.lock().unwrap_or_else(PoisonError::into_inner);
+        runs.iter()
+            .rev()
+            .filter(|s| s.run.instance_id == instance_id)
+            .map(|s| s.run.clone())
+            .collect()
+    }
+
+    /// Abort a running recipe; a finished run is returned unchanged
+    ///
+    /// A write already in flight completes, no further step is started.
+    pub fn abort(&self, instance_id: u32, run_id: &str) -> Option<RecipeRun> {
+        let mut runs = self.runs.lock().unwrap_or_else(PoisonError::into_inner);
+        let slot = runs
+            .iter_mut()
+            .find(|s| s.run.instance_id == instance_id && s.run.run_id == run_id)?;
+        if !slot.run.status.is_finished() {
+            slot.token.cancel();
+            slot.run
+                .finish(RunStatus::Aborted, Some("aborted".to_string()));
+            info!(
+                "Instance {} recipe '{}' aborted ({})",
+                instance_id, slot.run.recipe, run_id
+            );
+        }
+        Some(slot.run.clone())
+    }
+
+    /// Update a run that is still running (aborted runs are left alone)
+    fn update(&self, run_id: &str, f: impl FnOnce(&mut RecipeRun)) {
+        let mut runs = self.runs.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(slot) = runs
+            .iter_mut()
+            .find(|s| s.run.run_id == run_id && !s.run.status.is_finished())
+        {
+            f(&mut slot.run);
+        }
+    }
+
+    async fn drive(
+        self: Arc<Self>,
+        run_id: String,
+        instance_id: u32,
+        properties: serde_json::Value,
+        recipe: Arc<CompiledRecipe>,
+        token: CancellationToken,
+    ) {
+        for (index, step) in recipe.def.steps.iter().enumerate() {
+            if token.is_cancelled() {
+                return;
+            }
+            self.update(&run_id, |run| run.start_step(index));
+            let verify = recipe.verify[index].as_ref();
+            match self
+                .run_step(
+                    &run_id,
+                    index,
+                    instance_id,
+                    &properties,
+                    step,
+                    verify,
+                    &token,
+                )
+                .await
+            {
+                Ok(()) => self.update(&run_id, |run| run.finish_step(index)),
+                Err(StepError::Aborted) => return,
+                Err(StepError::Failed(error)) => {
+                    warn!(
+                        "Instance {} recipe '{}' failed at step {}: {}",
+                        instance_id,
+                        recipe.def.name,
+                        index + 1,
+                        error
+                    );
+                    self.update(&run_id, |run| run.finish(RunStatus::Failed, Some(error)));
+                    return;
+                },
+            }
+        }
+        self.update(&run_id, |run| run.finish(RunStatus::Completed, None));
+        info!(
+            "Instance {} recipe '{}' completed ({})",
+            instance_id, recipe.def.name, run_id
+        );
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn run_step(
+        &self,
+        run_id: &str,
+        index: usize,
+        instance_id: u32,
+        properties: &serde_json::Value,
+        step: &RecipeStep,
+        verify: Option<&CompiledVerify>,
+        token: &CancellationToken,
+    ) -> std::result::Result<(), StepError> {
+        let point_id = step.action.to_string();
+        let outcome = voltage_routing::set_action_point(
+            self.rtdb.as_ref(),
+            &self.routing_cache,
+            instance_id,
+            &point_id,
+            step.value,
+        )
+        .await
+        .map_err(|e| StepError::Failed(format!("write of action {} refused: {}", point_id, e)))?;
+        self.update(run_id, |run| {
+            if let Some(progress) = run.step(index) {
+                progress.write_status = Some(outcome.status.clone());
+            }
+        });
+        if outcome.is_overridden() {
+            return Err(StepError::Failed(format!(
+                "action {} is under local override",
+                point_id
+            )));
+        }
+
+        if step.delay_ms > 0 {
+            tokio::select! {
+                _ = token.cancelled() => return Err(StepError::Aborted),
+                _ = tokio::time::sleep(Duration::from_millis(step.delay_ms)) => {},
+            }
+        }
+
+        let Some(verify) = verify else {
+            return Ok(());
+        };
+        self.update(run_id, |run| {
+            if let Some(progress) = run.step(index) {
+                progress.status = StepStatus::Verifying;
+            }
+        });
+        let deadline = Instant::now() + verify.timeout;
+        loop {
+            let last = match self
+                .check(instance_id, properties, step.value, verify)
+                .await
+            {
+                Ok(true) => return Ok(()),
+                Ok(false) => None,
+                Err(e) => Some(e),
+            };
+            if Instant::now() >= deadline {
+                let condition = step
+                    .verify
+                    .as_ref()
+                    .map(|v| v.require.as_str())
+                    .unwrap_or_default();
+                let mut error = format!(
+                    "'{}' not met within {} ms",
+                    condition,
+                    verify.timeout.as_millis()
+                );
+                if let Some(e) = last {
+                    error.push_str(&format!(" ({:#})", e));
+                }
+                return Err(StepError::Failed(error));
+            }
+            tokio::select! {
+                _ = token.cancelled() => return Err(StepError::Aborted),
+                _ = tokio::time::sleep(VERIFY_POLL) => {},
+            }
+        }
+    }
+
+    async fn check(
+        &self,
+        instance_id: u32,
+        properties: &serde_json::Value,
+        value: f64,
+        verify: &CompiledVerify,
+    ) -> Result<bool> {
+        let mut context = HashMapContext::new();
+        let _ = context.set_value(VALUE_VARIABLE.to_string(), Value::Float(value));
+        for (var, source) in &verify.variables {
+            let resolved = resolve_variable(self.rtdb.as_ref(), instance_id, source, properties)
+                .await
+                .with_context(|| format!("{} unavailable", var))?;
+            let _ = context.set_value(var.clone(), Value::Float(resolved));
+        }
+        Ok(verify.require.eval_boolean_with_context(&context)?)
+    }
+}
+
+/// Read the `recipes.{product}` entries of the flattened service config
+pub fn from_service_config(extra_config: &serde_json::Value) -> Result<RecipeConfig> {
+    product_sections(extra_config, "recipes")
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use voltage_rtdb::MemoryRtdb;
+
+    const BESS: &str = r#"
+BESS:
+  - name: start
+    steps:
+      - name: close_dc_breaker
+        action: 3
+        value: 1
+        verify:
+          require: "dc_closed == 1.0"
+          variables: { dc_closed: "M:5" }
+          timeout_ms: 1500
+      - name: start_pcs
+        action: 1
+        value: 1
+  - name: stop
+    steps:
+      - action: 1
+        value: 0
+        delay_ms: 60000
+"#;
+
+    async fn setup() -> Arc<RecipeEngine<MemoryRtdb>> {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::query(
+            "CREATE TABLE instances (instance_id INTEGER PRIMARY KEY, product_name TEXT, properties TEXT)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO instances VALUES (1, 'BESS', '{}')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let config: RecipeConfig = serde_yaml::from_str(BESS).unwrap();
+        let engine = RecipeEngine::new(
+            Arc::new(MemoryRtdb::new()),
+            pool,
+            Arc::new(RoutingCache::new()),
+            &config,
+        )
+        .unwrap();
+        Arc::new(engine)
+    }
+
+    async fn action(engine: &RecipeEngine<MemoryRtdb>, point_id: &str) -> Option<f64> {
+        let raw = engine.rtdb.hash_get("inst:1:A", point_id).await.unwrap()?;
+        String::from_utf8_lossy(&raw).parse().ok()
+    }
+
+    async fn wait_finished(engine: &RecipeEngine<MemoryRtdb>, run_id: &str) -> RecipeRun {
+        loop {
+            let run = engine.get(1, run_id).unwrap();
+            if run.status.is_finished() {
+                return run;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recipe_waits_for_verification() {
+        let engine = setup().await;
+        assert_eq!(engine.len(), 2);
+        let names: Vec<_> = engine
+            .recipes(1)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.name)
+            .collect();
+        assert_eq!(names, ["start", "stop"]);
+
+        let run = engine.execute(1, "start").await.unwrap();
+        assert_eq!(run.status, RunStatus::Running);
+        assert!(matches!(
+            engine.execute(1, "stop").await,
+            Err(ModSrvError::RecipeRunning(_))
+        ));
+
+        // Step 1 is written but the breaker has not reported closed yet
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(action(&engine, "3").await, Some(1.0));
+        assert_eq!(action(&engine, "1").await, None);
+        let progress = engine.get(1, &run.run_id).unwrap();
+        assert_eq!(progress.current_step, Some(0));
+        assert_eq!(progress.steps[0].status, StepStatus::Verifying);
+
+        engine
+            .rtdb
+            .hash_set("inst:1:M", "5", Bytes::from("1"))
+            .await
+            .unwrap();
+        let run = wait_finished(&engine, &run.run_id).await;
+        assert_eq!(run.status, RunStatus::Completed);
+        assert!(run.steps.iter().all(|s| s.status == StepStatus::Completed));
+        assert_eq!(action(&engine, "1").await, Some(1.0));
+        assert_eq!(engine.runs(1).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_verification_timeout_fails_run() {
+        let engine = setup().await;
+        let run = engine.execute(1, "start").await.unwrap();
+        let run = wait_finished(&engine, &run.run_id).await;
+        assert_eq!(run.status, RunStatus::Failed);
+        assert_eq!(run.current_step, Some(0));
+        assert_eq!(run.steps[0].status, StepStatus::Failed);
+        assert_eq!(run.steps[1].status, StepStatus::Skipped);
+        assert!(run.error.unwrap().contains("dc_closed == 1.0"));
+        assert_eq!(action(&engine, "1").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_abort_stops_run() {
+        let engine = setup().await;
+        let run = engine.execute(1, "stop").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(action(&engine, "1").await, Some(0.0));
+
+        let aborted = engine.abort(1, &run.run_id).unwrap();
+        assert_eq!(aborted.status, RunStatus::Aborted);
+        assert_eq!(aborted.steps[0].status, StepStatus::Skipped);
+        assert!(engine.abort(2, &run.run_id).is_none());
+
+        // The instance is free again
+        let next = engine.execute(1, "stop").await.unwrap();
+        assert_ne!(next.run_id, run.run_id);
+        assert!(matches!(
+            engine.execute(1, "missing").await,
+            Err(ModSrvError::RecipeNotFound(_))
+        ));
+        assert!(matches!(
+            engine.execute(42, "stop").await,
+            Err(ModSrvError::InstanceNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_recipes_are_rejected() {
+        let pool = SqlitePool::connect_lazy("sqlite::memory:").unwrap();
+        let rtdb = Arc::new(MemoryRtdb::new());
+        let cache = Arc::new(RoutingCache::new());
+        for yaml in [
+            "BESS: [{name: start, steps: []}]",
+            "BESS: [{name: start, steps: [{action: 1, value: 1, verify: {require: \"(x\"}}]}]",
+            "BESS: [{name: start, steps: [{action: 1, value: 1, verify: {require: \"x > 1\"}}]}]",
+            "BESS: [{name: start, steps: [{action: 1, value: 1}]}, {name: start, steps: [{action: 2, value: 1}]}]",
+        ] {
+            let config: RecipeConfig = serde_yaml::from_str(yaml).unwrap();
+            assert!(
+                RecipeEngine::new(Arc::clone(&rtdb), pool.clone(), Arc::clone(&cache), &config)
+                    .is_err(),
+                "{}",
+                yaml
+            );
+        }
+
+        let extra = serde_json::json!({
+            "recipes.BESS": "[{\"name\": \"stop\", \"steps\": [{\"action\": 1, \"value\": 0}]}]"
+        });
+        let parsed = from_service_config(&extra).unwrap();
+        assert_eq!(parsed["BESS"][0].steps[0].delay_ms, 0);
+    }
+}
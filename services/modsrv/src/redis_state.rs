@@ -425,12 +425,132 @@ where
     Ok(())
 }
 
+/// Replace instance tags in Redis.
+///
+/// Writes `inst:{id}:tags` and adds the instance ID to each
+/// `inst:tag:{key}:{value}` index set, after removing it from the
+/// index sets of its previous tags.
+pub async fn set_instance_tags<R>(
+    redis: &R,
+    instance_id: u32,
+    tags: &HashMap<String, String>,
+) -> Result<()>
+where
+    R: Rtdb,
+{
+    remove_instance_tags(redis, instance_id).await?;
+
+    if tags.is_empty() {
+        return Ok(());
+    }
+
+    let member = instance_id.to_string();
+    for (tag_key, tag_value) in tags {
+        redis
+            .sadd(&InstanceRedisKeys::tag_index(tag_key, tag_value), &member)
+            .await?;
+    }
+
+    let fields = tags
+        .iter()
+        .map(|(k, v)| (k.clone(), Bytes::from(v.clone())))
+        .collect();
+    redis
+        .hash_mset(&InstanceRedisKeys::instance_tags(instance_id), fields)
+        .await?;
+
+    Ok(())
+}
+
+/// Replace the Redis mirror of the point alias registry.
+///
+/// `aliases` are (alias, scheme, address) rows; the combined reverse hash keeps
+/// the first alias of an address, so callers pass them ordered by scheme.
+pub async fn publish_point_aliases<R>(redis: &R, aliases: &[(String, String, String)]) -> Result<()>
+where
+    R: Rtdb,
+{
+    for scheme in redis.smembers(InstanceRedisKeys::alias_schemes()).await? {
+        redis
+            .del(&InstanceRedisKeys::alias_by_point_scheme(&scheme))
+            .await?;
+    }
+    redis.del(InstanceRedisKeys::alias_schemes()).await?;
+    redis.del(InstanceRedisKeys::point_aliases()).await?;
+    redis.del(InstanceRedisKeys::alias_by_point()).await?;
+
+    if aliases.is_empty() {
+        return Ok(());
+    }
+
+    let mut by_scheme: HashMap<&str, Vec<(String, Bytes)>> = HashMap::new();
+    let mut by_point: HashMap<&str, &str> = HashMap::new();
+    for (alias, scheme, address) in aliases {
+        by_scheme
+            .entry(scheme)
+            .or_default()
+            .push((address.clone(), Bytes::from(alias.clone())));
+        by_point.entry(address).or_insert(alias);
+    }
+
+    redis
+        .hash_mset(
+            InstanceRedisKeys::point_aliases(),
+            aliases
+                .iter()
+                .map(|(alias, _, address)| (alias.clone(), Bytes::from(address.clone())))
+                .collect(),
+        )
+        .await?;
+    redis
+        .hash_mset(
+            InstanceRedisKeys::alias_by_point(),
+            by_point
+                .into_iter()
+                .map(|(address, alias)| (address.to_string(), Bytes::from(alias.to_string())))
+                .collect(),
+        )
+        .await?;
+    for (scheme, fields) in by_scheme {
+        redis
+            .hash_mset(&InstanceRedisKeys::alias_by_point_scheme(scheme), fields)
+            .await?;
+        redis
+            .sadd(InstanceRedisKeys::alias_schemes(), scheme)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Remove instance from all tag index sets and delete its tags hash.
+async fn remove_instance_tags<R>(redis: &R, instance_id: u32) -> Result<()>
+where
+    R: Rtdb,
+{
+    let tags_key = InstanceRedisKeys::instance_tags(instance_id);
+    let member = instance_id.to_string();
+
+    for (tag_key, tag_value) in redis.hash_get_all(&tags_key).await? {
+        let tag_value = String::from_utf8_lossy(&tag_value);
+        redis
+            .srem(&InstanceRedisKeys::tag_index(&tag_key, &tag_value), &member)
+            .await?;
+    }
+
+    redis.del(&tags_key).await?;
+    Ok(())
+}
+
 /// Delete instance-related Redis data and clean up routing mappings.
 /// EN: Remove Redis data related to an instance and clean up routing mappings.
 pub async fn unregister_instance<R>(redis: &R, instance_id: u32, instance_name: &str) -> Result<()>
 where
     R: Rtdb,
 {
+    // Remove from tag index sets before inst:{id}:tags is deleted below
+    remove_instance_tags(redis, instance_id).await?;
+
     // Delete real-time data keys (Redis = real-time data only)
     let keys_to_delete = vec![
         InstanceRedisKeys::measurement_hash(instance_id), // inst:{id}:M
@@ -644,9 +764,18 @@ where
                 }
             }
 
+            // Active local overrides, keyed by action point
+            let mut overrides = Map::new();
+            for active in
+                voltage_routing::manual_override::list_overrides(redis, instance_id).await?
+            {
+                overrides.insert(active.point_id.clone(), serde_json::to_value(&active)?);
+            }
+
             let mut result = Map::new();
             result.insert("measurements".to_string(), Value::Object(measurements));
             result.insert("actions".to_string(), Value::Object(actions));
+            result.insert("overrides".to_string(), Value::Object(overrides));
 
             Ok(Value::Object(result))
         },
@@ -225,10 +225,12 @@ mod tests {
                 instance_name: name.to_string(),
                 product_name: product.to_string(),
                 properties: std::collections::HashMap::new(),
+                tags: std::collections::HashMap::new(),
             },
             measurement_mappings: None,
             action_mappings: None,
             created_at: None,
+            version: None,
         }
     }
 }
@@ -3,7 +3,7 @@
 //! Central route definition for all Model Service API endpoints
 
 use axum::{
-    routing::{get, post},
+    routing::{get, post, put},
     Router,
 };
 use std::sync::Arc;
@@ -14,13 +14,23 @@ use utoipa::OpenApi;
 use crate::app_state::AppState;
 
 // Import handlers from api module
+use crate::api::alias_handlers::{
+    delete_alias, get_alias, get_point, list_aliases, upsert_aliases,
+};
 use crate::api::cloud_sync::export_instances;
 use crate::api::health_handlers::health_check;
+use crate::api::history_handlers::get_point_history;
+use crate::api::override_handlers::{
+    get_override_log, list_overrides, release_override, set_override,
+};
 use crate::api::product_handlers::{get_product_points, list_products};
+use crate::api::recipe_handlers::{
+    abort_recipe_run, execute_recipe, get_recipe_run, list_recipe_runs, list_recipes,
+};
 
 use crate::api::instance_management_handlers::{
-    create_instance, delete_instance, execute_instance_action, reload_instances_from_db,
-    sync_all_instances, sync_instance_measurement, update_instance,
+    create_instance, delete_instance, execute_instance_action, get_naming_policy,
+    reload_instances_from_db, sync_all_instances, sync_instance_measurement, update_instance,
 };
 use crate::api::instance_query_handlers::{
     get_instance, get_instance_data, get_instance_points, list_instances, list_instances_slim,
@@ -38,7 +48,15 @@ use crate::api::routing_management_handlers::{
     create_instance_routing, delete_instance_routing, update_instance_routing,
     validate_instance_routing,
 };
-use crate::api::routing_query_handlers::{get_instance_routing_handler, get_routing_table_handler};
+use crate::api::routing_matrix_handlers::{get_routing_matrix, save_routing_matrix};
+use crate::api::routing_query_handlers::{
+    get_instance_routing_handler, get_routing_cache_handler, get_routing_table_handler,
+    set_routing_cache_trace_handler,
+};
+use crate::api::search_handlers::search;
+use crate::api::subscription_handlers::{
+    delete_subscription, get_subscription, list_subscriptions, upsert_subscription,
+};
 
 use crate::api::single_point_handlers::{
     delete_action_routing, delete_measurement_routing, get_action_point, get_measurement_point,
@@ -46,7 +64,10 @@ use crate::api::single_point_handlers::{
     upsert_measurement_routing,
 };
 
-use common::admin_api::{get_log_level, set_log_level};
+use common::admin_api::{
+    get_config_report, get_log_level, list_feature_flags, set_feature_flag, set_log_level,
+};
+use common::rate_limit::{rate_limit, trusted_proxies_from_env, RateLimiter, RouteClass};
 
 // OpenAPI documentation - only compiled when swagger-ui feature is enabled
 #[cfg(feature = "swagger-ui")]
@@ -60,13 +81,26 @@ use common::admin_api::{get_log_level, set_log_level};
         crate::api::instance_query_handlers::get_instance,
         crate::api::instance_management_handlers::update_instance,
         crate::api::instance_management_handlers::delete_instance,
+        crate::api::instance_management_handlers::get_naming_policy,
         crate::api::instance_query_handlers::get_instance_data,
         crate::api::instance_query_handlers::get_instance_points,
+        crate::api::history_handlers::get_point_history,
+        crate::api::override_handlers::list_overrides,
+        crate::api::override_handlers::set_override,
+        crate::api::override_handlers::release_override,
+        crate::api::override_handlers::get_override_log,
+        crate::api::recipe_handlers::list_recipes,
+        crate::api::recipe_handlers::execute_recipe,
+        crate::api::recipe_handlers::list_recipe_runs,
+        crate::api::recipe_handlers::get_recipe_run,
+        crate::api::recipe_handlers::abort_recipe_run,
         crate::api::instance_management_handlers::sync_instance_measurement,
         crate::api::instance_management_handlers::execute_instance_action,
         crate::api::instance_query_handlers::set_instance_measurement,
         // Instance-level routing handlers (refactored for unified database)
         crate::api::routing_query_handlers::get_instance_routing_handler,
+        crate::api::routing_query_handlers::get_routing_cache_handler,
+        crate::api::routing_query_handlers::set_routing_cache_trace_handler,
         crate::api::routing_management_handlers::create_instance_routing,
         crate::api::routing_management_handlers::update_instance_routing,
         crate::api::routing_management_handlers::delete_instance_routing,
@@ -86,22 +120,44 @@ use common::admin_api::{get_log_level, set_log_level};
         crate::api::global_routing_handlers::get_routing_by_channel_handler,
         crate::api::global_routing_handlers::delete_instance_routing_handler,
         crate::api::global_routing_handlers::delete_channel_routing_handler,
+        // Routing matrix editor
+        crate::api::routing_matrix_handlers::get_routing_matrix,
+        crate::api::routing_matrix_handlers::save_routing_matrix,
         crate::api::product_handlers::list_products,
         crate::api::product_handlers::get_product_points,
+        // Global search
+        crate::api::search_handlers::search,
+        // Point aliases
+        crate::api::alias_handlers::list_aliases,
+        crate::api::alias_handlers::upsert_aliases,
+        crate::api::alias_handlers::get_alias,
+        crate::api::alias_handlers::delete_alias,
+        crate::api::alias_handlers::get_point,
+        // Data change subscriptions
+        crate::api::subscription_handlers::list_subscriptions,
+        crate::api::subscription_handlers::upsert_subscription,
+        crate::api::subscription_handlers::get_subscription,
+        crate::api::subscription_handlers::delete_subscription,
         // Cloud sync endpoints
         crate::api::cloud_sync::export_instances,
         // Admin endpoints
         common::admin_api::set_log_level,
-        common::admin_api::get_log_level
+        common::admin_api::get_log_level,
+        common::admin_api::get_config_report,
+        common::admin_api::list_feature_flags,
+        common::admin_api::set_feature_flag
     ),
     components(
         schemas(
             crate::dto::CreateInstanceDto,
             crate::dto::UpdateInstanceDto,
             crate::dto::ActionRequest,
+            crate::dto::OverrideRequest,
             crate::dto::RoutingRequest,
             crate::dto::SinglePointRoutingRequest,
             crate::dto::ToggleRoutingRequest,
+            crate::dto::RoutingMatrixEdit,
+            crate::dto::RoutingMatrixSaveRequest,
             crate::dto::RoutingUpdate,
             crate::dto::RoutingType,
             crate::dto::BatchExecuteRequest,
@@ -110,13 +166,25 @@ use common::admin_api::{get_log_level, set_log_level};
             crate::dto::EnergyRequest,
             crate::dto::TimeSeriesRequest,
             crate::api::instance_query_handlers::SetMeasurementRequest,
+            crate::api::routing_query_handlers::RoutingCacheTraceRequest,
+            crate::point_aliases::PointAlias,
+            crate::subscriptions::DataSubscription,
+            crate::subscriptions::ChangeBatch,
+            crate::subscriptions::PointChange,
+            crate::subscriptions::SubscriptionStatus,
             crate::config::Product,
             crate::config::MeasurementPoint,
             crate::config::ActionPoint,
             crate::config::PropertyTemplate,
             // Admin schemas
             common::admin_api::SetLogLevelRequest,
-            common::admin_api::LogLevelResponse
+            common::admin_api::LogLevelResponse,
+            common::admin_api::SetFeatureFlagRequest,
+            common::feature_flags::FlagStatus,
+            common::feature_flags::FlagSource,
+            common::config_consistency::ConsistencyReport,
+            common::config_consistency::ConsistencyIssue,
+            common::config_consistency::ConsistencySeverity
         )
     ),
     tags(
@@ -132,10 +200,12 @@ pub fn create_routes(state: Arc<AppState>) -> Router {
     Router::new()
         // Health check
         .route("/health", get(health_check))
+        .route("/health/config", get(get_config_report))
         // Instance management API
         .route("/api/instances", get(list_instances).post(create_instance))
         .route("/api/instances/list", get(list_instances_slim))
         .route("/api/instances/search", get(search_instances))
+        .route("/api/naming-policy", get(get_naming_policy))
         .route(
             "/api/instances/{id}",
             get(get_instance)
@@ -144,11 +214,26 @@ pub fn create_routes(state: Arc<AppState>) -> Router {
         )
         .route("/api/instances/{id}/data", get(get_instance_data))
         .route("/api/instances/{id}/points", get(get_instance_points))
+        .route("/api/instances/{id}/points/{point_id}/history", get(get_point_history))
         .route(
             "/api/instances/{id}/sync",
             post(sync_instance_measurement),
         )
         .route("/api/instances/{id}/action", post(execute_instance_action))
+        .route("/api/instances/{id}/overrides", get(list_overrides))
+        .route("/api/instances/{id}/overrides/log", get(get_override_log))
+        .route(
+            "/api/instances/{id}/overrides/{point_id}",
+            put(set_override).delete(release_override),
+        )
+        .route("/api/instances/{id}/recipes", get(list_recipes))
+        .route("/api/instances/{id}/recipes/{name}/execute", post(execute_recipe))
+        .route("/api/instances/{id}/recipe-runs", get(list_recipe_runs))
+        .route("/api/instances/{id}/recipe-runs/{run_id}", get(get_recipe_run))
+        .route(
+            "/api/instances/{id}/recipe-runs/{run_id}/abort",
+            post(abort_recipe_run),
+        )
         .route("/api/instances/{id}/measurement", post(set_instance_measurement))
         .route("/api/instances/sync/all", post(sync_all_instances))
         .route("/api/instances/reload", post(reload_instances_from_db))
@@ -197,10 +282,26 @@ pub fn create_routes(state: Arc<AppState>) -> Router {
         .route("/api/routing/by-channel/{channel_id}", get(get_routing_by_channel_handler))
         .route("/api/routing/instances/{id}", axum::routing::delete(global_delete_instance_routing))
         .route("/api/routing/channels/{channel_id}", axum::routing::delete(delete_channel_routing_handler))
+        // Routing matrix editor (instance points x channel points)
+        .route("/api/routing/matrix", get(get_routing_matrix).put(save_routing_matrix))
+        .route("/api/routing/cache", get(get_routing_cache_handler))
+        .route("/api/routing/cache/trace", axum::routing::put(set_routing_cache_trace_handler))
 
         // Product management endpoints (read-only)
         .route("/api/products", get(list_products))
         .route("/api/products/{product_name}/points", get(get_product_points))
+        // Global search (channels, points, instances, products, rules)
+        .route("/api/search", get(search))
+        // Point aliases (stable external identifiers)
+        .route("/api/aliases", get(list_aliases).post(upsert_aliases))
+        .route("/api/aliases/{alias}", get(get_alias).delete(delete_alias))
+        .route("/api/points/{point}", get(get_point))
+        // Webhook subscriptions to measurement changes
+        .route("/api/subscriptions", get(list_subscriptions).post(upsert_subscription))
+        .route(
+            "/api/subscriptions/{name}",
+            get(get_subscription).delete(delete_subscription),
+        )
         // Cloud sync endpoints
         .route("/api/instances/export", get(export_instances))
         // Admin endpoints (log level management)
@@ -208,7 +309,23 @@ pub fn create_routes(state: Arc<AppState>) -> Router {
             "/api/admin/logs/level",
             get(get_log_level).post(set_log_level),
         )
+        .route("/api/admin/features", get(list_feature_flags))
+        .route("/api/admin/features/{name}", put(set_feature_flag))
+        .layer(axum::middleware::from_fn_with_state(
+            api_rate_limiter(),
+            rate_limit,
+        ))
         // Apply HTTP request logging middleware
         .layer(axum::middleware::from_fn(common::logging::http_request_logger))
         .with_state(state)
 }
+
+/// Per-client limits of the public API (by client IP)
+fn api_rate_limiter() -> Arc<RateLimiter> {
+    Arc::new(
+        RateLimiter::new(vec![
+            RouteClass::new("action", 20, 10.0).post("/api/instances/*/action")
+        ])
+        .trust_proxies(trusted_proxies_from_env()),
+    )
+}
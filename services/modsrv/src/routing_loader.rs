@@ -53,6 +53,12 @@ pub struct MeasurementRouting {
     pub channel_point_id: Option<u32>,
     pub measurement_id: u32,
     pub description: Option<String>,
+    #[sqlx(default)]
+    pub deadband: Option<f64>,
+    #[sqlx(default)]
+    pub deadband_pct: Option<f64>,
+    #[sqlx(default)]
+    pub refresh_secs: Option<u32>,
     pub enabled: bool,
 }
 
@@ -12,7 +12,7 @@ use axum::{
     routing::{get, post},
     Router,
 };
-use common::{PaginatedResponse, SuccessResponse};
+use common::{Cursor, PaginatedResponse, SuccessResponse, ValidatedJson};
 use serde_json::json;
 use sqlx::SqlitePool;
 use std::sync::Arc;
@@ -20,7 +20,7 @@ use tracing::{debug, error, info, warn};
 #[cfg(feature = "swagger-ui")]
 use utoipa::OpenApi;
 use voltage_rtdb::traits::Rtdb;
-use voltage_rules::{self as rule_repository, RuleNode, RuleScheduler, RuleVariable};
+use voltage_rules::{self as rule_repository, RuleError, RuleNode, RuleScheduler, RuleVariable};
 
 /// Rule Engine state shared across handlers
 pub struct RuleEngineState<R: Rtdb> {
@@ -96,6 +96,9 @@ pub struct RuleListQuery {
     /// Items per page
     #[serde(default = "default_page_size")]
     pub page_size: usize,
+    /// Cursor from a previous page's `next_cursor` (takes precedence over `page`)
+    #[serde(default)]
+    pub cursor: Option<String>,
 }
 
 fn default_page() -> usize {
@@ -107,11 +110,12 @@ fn default_page_size() -> usize {
 }
 
 /// Request DTO for creating a new rule (empty shell, ID auto-generated)
-#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, validator::Validate)]
 #[cfg_attr(feature = "swagger-ui", derive(utoipa::ToSchema))]
 pub struct CreateRuleRequest {
     /// Rule name (required)
     #[cfg_attr(feature = "swagger-ui", schema(example = "Battery SOC Protection"))]
+    #[validate(custom(function = "common::extract::not_blank"))]
     pub name: String,
 
     /// Rule description (optional)
@@ -123,11 +127,12 @@ pub struct CreateRuleRequest {
 }
 
 /// Request DTO for updating an existing rule (all fields optional, partial update)
-#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, validator::Validate)]
 #[cfg_attr(feature = "swagger-ui", derive(utoipa::ToSchema))]
 pub struct UpdateRuleRequest {
     /// Rule name (optional)
     #[cfg_attr(feature = "swagger-ui", schema(example = "Battery SOC Protection v2"))]
+    #[validate(custom(function = "common::extract::not_blank"))]
     pub name: Option<String>,
 
     /// Rule description (optional)
@@ -146,6 +151,10 @@ pub struct UpdateRuleRequest {
     #[cfg_attr(feature = "swagger-ui", schema(example = 10000))]
     pub cooldown_ms: Option<u64>,
 
+    /// Shadow mode: execute and log actions without writing (optional)
+    #[cfg_attr(feature = "swagger-ui", schema(example = false))]
+    pub shadow: Option<bool>,
+
     /// Vue Flow complete data (nodes, edges, viewport)
     #[cfg_attr(feature = "swagger-ui", schema(value_type = Option<Object>))]
     pub flow_json: Option<serde_json::Value>,
@@ -157,7 +166,8 @@ pub struct UpdateRuleRequest {
     path = "/api/rules",
     params(
         ("page" = Option<usize>, Query, description = "Page number (default: 1)"),
-        ("page_size" = Option<usize>, Query, description = "Items per page (default: 20, max: 100)")
+        ("page_size" = Option<usize>, Query, description = "Items per page (default: 20, max: 100)"),
+        ("cursor" = Option<String>, Query, description = "Continue after a previous page's next_cursor")
     ),
     responses(
         (status = 200, description = "List rules (paginated)", body = common::PaginatedResponse<serde_json::Value>,
@@ -175,7 +185,8 @@ pub struct UpdateRuleRequest {
                     "has_previous": false
                 }
             })
-        )
+        ),
+        (status = 400, description = "Invalid cursor")
     ),
     tag = "rules"
 ))]
@@ -185,9 +196,28 @@ pub async fn list_rules<R: Rtdb + Send + Sync + 'static>(
 ) -> Result<Json<SuccessResponse<PaginatedResponse<serde_json::Value>>>, ModSrvError> {
     let page = query.page.max(1);
     let page_size = query.page_size.clamp(1, 100);
+    let after = query
+        .cursor
+        .as_deref()
+        .map(Cursor::decode)
+        .transpose()
+        .map_err(ModSrvError::InvalidData)?;
+
+    // Keyset paging continues after the previous page's last rule; the first
+    // page hands out a cursor too, so clients can switch to it right away
+    let keyset = after.is_some() || page == 1;
+    let result = if keyset {
+        rule_repository::list_rules_after(&state.pool, after.as_ref().map(Cursor::key), page_size)
+            .await
+            .map(|(rules, total, next)| (rules, total, next.map(Cursor::new)))
+    } else {
+        rule_repository::list_rules_paginated(&state.pool, page, page_size)
+            .await
+            .map(|(rules, total)| (rules, total, None))
+    };
 
-    match rule_repository::list_rules_paginated(&state.pool, page, page_size).await {
-        Ok((rules, total)) => {
+    match result {
+        Ok((rules, total, next)) => {
             // Only expose summary fields for list view
             let summaries: Vec<serde_json::Value> = rules
                 .into_iter()
@@ -201,9 +231,13 @@ pub async fn list_rules<R: Rtdb + Send + Sync + 'static>(
                 })
                 .collect();
 
-            let paginated = PaginatedResponse::new(summaries, total, page, page_size);
+            let mut paginated = PaginatedResponse::new(summaries, total, page, page_size);
+            if keyset {
+                paginated = paginated.with_next_cursor(next);
+            }
             Ok(Json(SuccessResponse::new(paginated)))
         },
+        Err(RuleError::InvalidFormat(e)) => Err(ModSrvError::InvalidData(e)),
         Err(e) => {
             error!("List rules err: {}", e);
             Err(ModSrvError::InternalError(
@@ -232,7 +266,7 @@ pub async fn list_rules<R: Rtdb + Send + Sync + 'static>(
 ))]
 pub async fn create_rule<R: Rtdb + Send + Sync + 'static>(
     State(state): State<Arc<RuleEngineState<R>>>,
-    Json(req): Json<CreateRuleRequest>,
+    ValidatedJson(req): ValidatedJson<CreateRuleRequest>,
 ) -> Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError> {
     // Get next sequential ID
     let next_id: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(id), 0) + 1 FROM rules")
@@ -316,7 +350,7 @@ pub async fn get_rule<R: Rtdb + Send + Sync + 'static>(
 pub async fn update_rule<R: Rtdb + Send + Sync + 'static>(
     State(state): State<Arc<RuleEngineState<R>>>,
     Path(id): Path<i64>,
-    Json(req): Json<UpdateRuleRequest>,
+    ValidatedJson(req): ValidatedJson<UpdateRuleRequest>,
 ) -> Result<Json<SuccessResponse<serde_json::Value>>, ModSrvError> {
     // Check rule exists (properly propagate database errors)
     let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM rules WHERE id = ?)")
@@ -349,6 +383,9 @@ pub async fn update_rule<R: Rtdb + Send + Sync + 'static>(
     if req.cooldown_ms.is_some() {
         updates.push("cooldown_ms = ?");
     }
+    if req.shadow.is_some() {
+        updates.push("shadow = ?");
+    }
     if req.flow_json.is_some() {
         updates.push("flow_json = ?");
         updates.push("nodes_json = ?"); // Also update compact format for execution
@@ -377,6 +414,9 @@ pub async fn update_rule<R: Rtdb + Send + Sync + 'static>(
     if let Some(cooldown) = req.cooldown_ms {
         query = query.bind(cooldown as i64);
     }
+    if let Some(shadow) = req.shadow {
+        query = query.bind(shadow);
+    }
     if let Some(flow) = &req.flow_json {
         // Bind flow_json (original Vue Flow data for editor)
         let flow_str = serde_json::to_string(flow)
@@ -524,8 +564,9 @@ pub async fn disable_rule<R: Rtdb + Send + Sync + 'static>(
                  "rule_id": "soc-strategy-001",
                  "execution_id": "manual-a1b2c3d4",
                  "success": true,
+                 "shadow": false,
                  "actions_executed": [
-                     { "target_type": "instance", "target_id": "pv_01", "point_type": "action", "point_id": 5, "value": 78.0, "success": true }
+                     { "target_type": "instance", "target_id": "pv_01", "point_type": "action", "point_id": 5, "value": 78.0, "success": true, "shadow": false }
                  ],
                  "execution_path": ["start", "switch-soc", "action-high", "end"],
                  "timestamp": "2024-01-01T12:00:00Z"
@@ -555,7 +596,8 @@ pub async fn execute_rule_now<R: Rtdb + Send + Sync + 'static>(
                 "point_type": a.point_type,
                 "point_id": a.point_id,
                 "value": a.value,
-                "success": a.success
+                "success": a.success,
+                "shadow": a.shadow
             })
         })
         .collect();
@@ -567,7 +609,9 @@ pub async fn execute_rule_now<R: Rtdb + Send + Sync + 'static>(
             "rule_id": result.rule_id,
             "execution_id": execution_id,
             "success": true,
+            "shadow": result.shadow,
             "actions_executed": action_results,
+            "http_calls": result.http_calls,
             "execution_path": result.execution_path,
             "timestamp": timestamp
         }))))
@@ -577,8 +621,10 @@ pub async fn execute_rule_now<R: Rtdb + Send + Sync + 'static>(
             "rule_id": result.rule_id,
             "execution_id": execution_id,
             "success": false,
+            "shadow": result.shadow,
             "error": result.error,
             "actions_executed": action_results,
+            "http_calls": result.http_calls,
             "execution_path": result.execution_path,
             "timestamp": timestamp
         }))))
@@ -603,7 +649,18 @@ pub async fn scheduler_status<R: Rtdb + Send + Sync + 'static>(
         "running": status.running,
         "total_rules": status.total_rules,
         "enabled_rules": status.enabled_rules,
-        "tick_interval_ms": status.tick_interval_ms
+        "shadow_rules": status.shadow_rules,
+        "tick_interval_ms": status.tick_interval_ms,
+        "workers": status.workers,
+        "fairness": status.fairness.as_str(),
+        "max_rules_per_tick": status.max_rules_per_tick,
+        "ticks": status.ticks,
+        "tick_overruns": status.tick_overruns,
+        "last_tick_ms": status.last_tick_ms,
+        "max_tick_ms": status.max_tick_ms,
+        "deferred_executions": status.deferred_executions,
+        "worker_rules": status.worker_rules,
+        "worker_executions": status.worker_executions
     }))))
 }
 
@@ -669,6 +726,9 @@ pub async fn get_rule_variables<R: Rtdb + Send + Sync + 'static>(
             },
             RuleNode::ChangeValue {
                 variables: vars, ..
+            }
+            | RuleNode::Http {
+                variables: vars, ..
             } => {
                 variables.extend(vars.iter().cloned());
             },
@@ -0,0 +1,456 @@
+//! Global search index
+//!
+//! In-memory index over channels, channel points, instances, products and
+//! rules for `GET /api/search`. Built from SQLite after startup, rebuilt on
+//! `POST /api/instances/reload` and refreshed on demand once older than
+//! [`MAX_INDEX_AGE`] (channels and rules are edited through other services).
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tracing::{debug, warn};
+use utoipa::ToSchema;
+
+use crate::product_loader::ProductLoader;
+
+/// Age after which a search refreshes the index first
+pub const MAX_INDEX_AGE: Duration = Duration::from_secs(60);
+
+/// Channel point tables with their point type code
+const POINT_TABLES: [(&str, &str); 4] = [
+    ("telemetry_points", "T"),
+    ("signal_points", "S"),
+    ("control_points", "C"),
+    ("adjustment_points", "A"),
+];
+
+/// Kind of a search result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchKind {
+    Instance,
+    Channel,
+    Point,
+    Product,
+    Rule,
+}
+
+impl SearchKind {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "instance" | "instances" => Some(Self::Instance),
+            "channel" | "channels" => Some(Self::Channel),
+            "point" | "points" => Some(Self::Point),
+            "product" | "products" => Some(Self::Product),
+            "rule" | "rules" => Some(Self::Rule),
+            _ => None,
+        }
+    }
+}
+
+/// One search result
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SearchHit {
+    pub kind: SearchKind,
+    /// Identifier within the kind (point ids are `channel:type:point`)
+    pub id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Owning object, e.g. the channel of a point or the product of an instance
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    /// API path of the object
+    pub link: String,
+    pub score: u32,
+}
+
+struct Entry {
+    hit: SearchHit,
+    name_lc: String,
+    description_lc: String,
+    context_lc: String,
+}
+
+impl Entry {
+    fn new(
+        kind: SearchKind,
+        id: String,
+        name: String,
+        description: Option<String>,
+        context: Option<String>,
+        link: String,
+    ) -> Self {
+        let description = description.filter(|d| !d.trim().is_empty());
+        Self {
+            name_lc: name.to_lowercase(),
+            description_lc: description.as_deref().unwrap_or("").to_lowercase(),
+            context_lc: context.as_deref().unwrap_or("").to_lowercase(),
+            hit: SearchHit {
+                kind,
+                id,
+                name,
+                description,
+                context,
+                link,
+                score: 0,
+            },
+        }
+    }
+
+    /// Score of one query token, `None` when it does not match
+    fn token_score(&self, token: &str) -> Option<u32> {
+        let name = if self.name_lc == token {
+            Some(100)
+        } else if self.name_lc.starts_with(token) {
+            Some(80)
+        } else if self.name_lc.contains(token) {
+            Some(60)
+        } else if is_subsequence(token, &self.name_lc) {
+            Some(20)
+        } else {
+            None
+        };
+        let id = (self.hit.id == token).then_some(90);
+        let description = self.description_lc.contains(token).then_some(30);
+        let context = self.context_lc.contains(token).then_some(25);
+        [name, id, description, context].into_iter().flatten().max()
+    }
+
+    /// Sum of token scores; every token has to match
+    fn score(&self, tokens: &[String]) -> Option<u32> {
+        tokens.iter().map(|t| self.token_score(t)).sum()
+    }
+}
+
+/// Whether all characters of `needle` appear in order in `haystack`
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut chars = haystack.chars();
+    needle.chars().all(|c| chars.any(|h| h == c))
+}
+
+/// Immutable snapshot of searchable objects
+pub struct SearchIndex {
+    entries: Vec<Entry>,
+    built_at: Instant,
+}
+
+impl Default for SearchIndex {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            // Empty index counts as stale
+            built_at: Instant::now() - MAX_INDEX_AGE,
+        }
+    }
+}
+
+impl SearchIndex {
+    /// Build the index from SQLite and the product library
+    ///
+    /// Missing tables (e.g. no comsrv configuration yet) are skipped.
+    pub async fn build(pool: &SqlitePool, product_loader: &ProductLoader) -> Self {
+        let mut entries = Vec::new();
+
+        match sqlx::query_as::<_, (i64, String, Option<String>)>(
+            "SELECT channel_id, name, protocol FROM channels ORDER BY channel_id",
+        )
+        .fetch_all(pool)
+        .await
+        {
+            Ok(rows) => entries.extend(rows.into_iter().map(|(id, name, protocol)| {
+                Entry::new(
+                    SearchKind::Channel,
+                    id.to_string(),
+                    name,
+                    None,
+                    protocol,
+                    format!("/api/channels/{}", id),
+                )
+            })),
+            Err(e) => warn!("Search index: channels skipped: {}", e),
+        }
+
+        for (table, point_type) in POINT_TABLES {
+            let sql = format!(
+                "SELECT p.channel_id, p.point_id, p.signal_name, p.description, c.name \
+                 FROM {} p LEFT JOIN channels c ON c.channel_id = p.channel_id",
+                table
+            );
+            match sqlx::query_as::<_, (i64, i64, String, Option<String>, Option<String>)>(&sql)
+                .fetch_all(pool)
+                .await
+            {
+                Ok(rows) => entries.extend(rows.into_iter().map(
+                    |(channel_id, point_id, name, description, channel_name)| {
+                        Entry::new(
+                            SearchKind::Point,
+                            format!("{}:{}:{}", channel_id, point_type, point_id),
+                            name,
+                            description,
+                            Some(format!(
+                                "{} {}",
+                                channel_name.unwrap_or_else(|| channel_id.to_string()),
+                                point_type
+                            )),
+                            format!(
+                                "/api/channels/{}/{}/points/{}",
+                                channel_id, point_type, point_id
+                            ),
+                        )
+                    },
+                )),
+                Err(e) => warn!("Search index: {} skipped: {}", table, e),
+            }
+        }
+
+        match sqlx::query_as::<_, (i64, String, String)>(
+            "SELECT instance_id, instance_name, product_name FROM instances ORDER BY instance_id",
+        )
+        .fetch_all(pool)
+        .await
+        {
+            Ok(rows) => entries.extend(rows.into_iter().map(|(id, name, product)| {
+                Entry::new(
+                    SearchKind::Instance,
+                    id.to_string(),
+                    name,
+                    None,
+                    Some(product),
+                    format!("/api/instances/{}", id),
+                )
+            })),
+            Err(e) => warn!("Search index: instances skipped: {}", e),
+        }
+
+        entries.extend(
+            product_loader
+                .get_all_products()
+                .into_iter()
+                .map(|product| {
+                    Entry::new(
+                        SearchKind::Product,
+                        product.product_name.clone(),
+                        product.product_name.clone(),
+                        None,
+                        product.parent_name,
+                        format!("/api/products/{}/points", product.product_name),
+                    )
+                }),
+        );
+
+        match sqlx::query_as::<_, (i64, String, Option<String>)>(
+            "SELECT id, name, description FROM rules ORDER BY id",
+        )
+        .fetch_all(pool)
+        .await
+        {
+            Ok(rows) => entries.extend(rows.into_iter().map(|(id, name, description)| {
+                Entry::new(
+                    SearchKind::Rule,
+                    id.to_string(),
+                    name,
+                    description,
+                    None,
+                    format!("/api/rules/{}", id),
+                )
+            })),
+            Err(e) => warn!("Search index: rules skipped: {}", e),
+        }
+
+        debug!("Search index built: {} entries", entries.len());
+        Self {
+            entries,
+            built_at: Instant::now(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Age of the snapshot
+    pub fn age(&self) -> Duration {
+        self.built_at.elapsed()
+    }
+
+    /// Best matches for a query, optionally restricted to some kinds
+    ///
+    /// Query tokens are separated by whitespace and all have to match (name,
+    /// id, description or context). Returns the total match count and the
+    /// top `limit` hits ordered by score.
+    pub fn search(
+        &self,
+        query: &str,
+        kinds: Option<&[SearchKind]>,
+        limit: usize,
+    ) -> (usize, Vec<SearchHit>) {
+        let tokens: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        if tokens.is_empty() {
+            return (0, Vec::new());
+        }
+
+        let mut hits: Vec<SearchHit> = self
+            .entries
+            .iter()
+            .filter(|entry| kinds.is_none_or(|kinds| kinds.contains(&entry.hit.kind)))
+            .filter_map(|entry| {
+                entry.score(&tokens).map(|score| SearchHit {
+                    score,
+                    ..entry.hit.clone()
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then(a.kind.cmp(&b.kind))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        let total = hits.len();
+        hits.truncate(limit);
+        (total, hits)
+    }
+}
+
+/// Shared, swappable search index
+pub struct SearchService {
+    index: RwLock<Arc<SearchIndex>>,
+}
+
+impl Default for SearchService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SearchService {
+    pub fn new() -> Self {
+        Self {
+            index: RwLock::new(Arc::new(SearchIndex::default())),
+        }
+    }
+
+    /// Rebuild the index and return its size
+    pub async fn rebuild(&self, pool: &SqlitePool, product_loader: &ProductLoader) -> usize {
+        let index = SearchIndex::build(pool, product_loader).await;
+        let len = index.len();
+        *self.index.write().await = Arc::new(index);
+        len
+    }
+
+    /// Current index, rebuilt first if older than [`MAX_INDEX_AGE`]
+    pub async fn fresh(
+        &self,
+        pool: &SqlitePool,
+        product_loader: &ProductLoader,
+    ) -> Arc<SearchIndex> {
+        {
+            let index = self.index.read().await;
+            if index.age() < MAX_INDEX_AGE {
+                return Arc::clone(&index);
+            }
+        }
+
+        let mut index = self.index.write().await;
+        // Another request may have rebuilt it while we waited
+        if index.age() >= MAX_INDEX_AGE {
+            *index = Arc::new(SearchIndex::build(pool, product_loader).await);
+        }
+        Arc::clone(&index)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // unwrap is acceptable in tests
+mod tests {
+    use super::*;
+
+    fn index() -> SearchIndex {
+        let entries = vec![
+            Entry::new(
+                SearchKind::Channel,
+                "1001".into(),
+                "PCS_Modbus".into(),
+                None,
+                Some("modbus_tcp".into()),
+                "/api/channels/1001".into(),
+            ),
+            Entry::new(
+                SearchKind::Point,
+                "1001:T:1".into(),
+                "DC_Voltage".into(),
+                Some("Battery DC bus voltage".into()),
+                Some("PCS_Modbus T".into()),
+                "/api/channels/1001/T/points/1".into(),
+            ),
+            Entry::new(
+                SearchKind::Instance,
+                "5".into(),
+                "pcs_01".into(),
+                None,
+                Some("PCS".into()),
+                "/api/instances/5".into(),
+            ),
+            Entry::new(
+                SearchKind::Rule,
+                "3".into(),
+                "SOC protection".into(),
+                Some("Stop discharge below 10% SOC".into()),
+                None,
+                "/api/rules/3".into(),
+            ),
+        ];
+        SearchIndex {
+            entries,
+            built_at: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_ranking() {
+        let index = index();
+
+        // Prefix on the channel beats the point whose context mentions it
+        let (total, hits) = index.search("pcs", None, 10);
+        assert_eq!(total, 3);
+        assert_eq!(hits[0].kind, SearchKind::Instance);
+        assert_eq!(hits[1].kind, SearchKind::Channel);
+        assert_eq!(hits[2].kind, SearchKind::Point);
+
+        // Every token has to match somewhere
+        let (_, hits) = index.search("voltage bus", None, 10);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].link, "/api/channels/1001/T/points/1");
+        assert!(index.search("voltage nothing", None, 10).1.is_empty());
+    }
+
+    #[test]
+    fn test_fuzzy_and_filters() {
+        let index = index();
+
+        // Subsequence match on the name
+        let (_, hits) = index.search("dcvlt", None, 10);
+        assert_eq!(hits[0].id, "1001:T:1");
+
+        // Exact id
+        let (_, hits) = index.search("1001", Some(&[SearchKind::Channel]), 10);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].score, 90);
+
+        // Description match, kind filter and limit
+        let (total, hits) = index.search("soc", Some(&[SearchKind::Rule]), 10);
+        assert_eq!((total, hits[0].id.as_str()), (1, "3"));
+        let (total, hits) = index.search("o", None, 1);
+        assert!(total > 1);
+        assert_eq!(hits.len(), 1);
+
+        assert_eq!(index.search("   ", None, 10).0, 0);
+    }
+}
@@ -0,0 +1,629 @@
+//! Data Change Subscriptions
+//!
+//! External systems register a webhook with a filter (instances, points,
+//! minimum interval) instead of polling `GET /api/instances/{id}/data`.
+//! `inst:{id}:M` is written by comsrv routing and the rule engine without
+//! notifying modsrv, so the dispatcher scans the measurement hashes of the
+//! subscribed instances every [`SCAN_INTERVAL`] and diffs them against the
+//! previous scan. Changed points are queued for every matching subscription;
+//! a newer value of a queued point replaces the older one, so each batch
+//! carries the latest value per point. A subscription receives at most one
+//! batch per `min_interval_ms`.
+//!
+//! Batches ([`ChangeBatch`]) are POSTed as JSON and retried with backoff; a
+//! batch that still fails is dropped and counted. One delivery per
+//! subscription is in flight at a time and batches are numbered, so
+//! receivers can detect gaps. With a `secret`, requests are signed:
+//!
+//! - `X-Voltage-Timestamp`: Unix time in milliseconds
+//! - `X-Voltage-Signature`: `sha256=<hex>`, the HMAC-SHA256 of
+//!   `{timestamp}.{body}` keyed by the secret
+//!
+//! Registrations are stored in the `data_subscriptions` table.
+
+use common::webhook::{self, WebhookAuth, WebhookTarget};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+use utoipa::ToSchema;
+use voltage_rtdb::{KeySpaceConfig, Rtdb};
+
+use crate::error::{ModSrvError, Result};
+
+/// Interval between scans of the subscribed measurement hashes
+pub const SCAN_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Queued points per subscription; the oldest are dropped beyond this
+const MAX_PENDING: usize = 10_000;
+
+/// Webhook subscription to measurement changes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DataSubscription {
+    /// Unique subscription name
+    pub name: String,
+    /// Callback URL (POST, JSON body)
+    pub url: String,
+    /// Signature key; never returned by the API
+    #[serde(default, skip_serializing)]
+    pub secret: Option<String>,
+    /// Instances whose measurements are watched
+    pub instances: Vec<u32>,
+    /// Only these measurement points (all when absent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub points: Option<Vec<u32>>,
+    /// Minimum time between two batches
+    #[serde(default = "default_min_interval_ms")]
+    pub min_interval_ms: u64,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u32,
+    #[serde(default = "common::serde_helpers::bool_true")]
+    pub enabled: bool,
+}
+
+fn default_min_interval_ms() -> u64 {
+    1000
+}
+
+fn default_timeout_ms() -> u32 {
+    3000
+}
+
+impl DataSubscription {
+    /// Check the registration before storing it
+    pub fn validate(&self) -> Result<()> {
+        let name = self.name.trim();
+        if name.is_empty() || name.len() > 64 {
+            return Err(ModSrvError::InvalidData(
+                "Subscription name must be 1-64 characters".to_string(),
+            ));
+        }
+        if !(self.url.starts_with("http://") || self.url.starts_with("https://")) {
+            return Err(ModSrvError::InvalidData(format!(
+                "Subscription URL must be http(s): {}",
+                self.url
+            )));
+        }
+        if self.instances.is_empty() {
+            return Err(ModSrvError::InvalidData(
+                "Subscription needs at least one instance".to_string(),
+            ));
+        }
+        if self.timeout_ms == 0 {
+            return Err(ModSrvError::InvalidData(
+                "Subscription timeout_ms must be positive".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether a change of this measurement point is delivered
+    pub fn matches(&self, instance_id: u32, point_id: u32) -> bool {
+        self.enabled
+            && self.instances.contains(&instance_id)
+            && self
+                .points
+                .as_ref()
+                .is_none_or(|points| points.contains(&point_id))
+    }
+}
+
+/// New value of one measurement point
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PointChange {
+    pub instance_id: u32,
+    pub point_id: u32,
+    /// Number, or the raw string when the value is not numeric
+    #[schema(value_type = Object)]
+    pub value: Value,
+}
+
+/// Payload posted to a subscription
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ChangeBatch {
+    pub subscription: String,
+    /// Batch number, consecutive per subscription since modsrv started
+    pub sequence: u64,
+    /// Batch creation time (milliseconds)
+    pub timestamp: i64,
+    pub changes: Vec<PointChange>,
+}
+
+/// Delivery counters of a subscription
+#[derive(Debug, Clone, Default, PartialEq, Serialize, ToSchema)]
+pub struct SubscriptionStatus {
+    /// Last batch number handed out
+    pub sequence: u64,
+    /// Points waiting for the next batch
+    pub pending: usize,
+    pub delivered: u64,
+    pub failed: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+/// Delivery state shared with the delivery task
+#[derive(Debug, Default)]
+struct Delivery {
+    in_flight: AtomicBool,
+    delivered: AtomicU64,
+    failed: AtomicU64,
+    last_error: Mutex<Option<String>>,
+}
+
+/// Changes waiting for the next batch of a subscription
+#[derive(Debug, Default)]
+struct Queue {
+    pending: BTreeMap<(u32, u32), Value>,
+    last_sent: Option<Instant>,
+    sequence: u64,
+    delivery: Arc<Delivery>,
+}
+
+/// Subscription registry and change dispatcher
+pub struct DataSubscriptions {
+    subscriptions: RwLock<HashMap<String, DataSubscription>>,
+    queues: Mutex<HashMap<String, Queue>>,
+    /// Measurement hash of each watched instance at the last scan
+    snapshots: Mutex<HashMap<u32, HashMap<String, String>>>,
+    http: reqwest::Client,
+}
+
+impl Default for DataSubscriptions {
+    fn default() -> Self {
+        Self {
+            subscriptions: RwLock::new(HashMap::new()),
+            queues: Mutex::new(HashMap::new()),
+            snapshots: Mutex::new(HashMap::new()),
+            http: reqwest::Client::new(),
+        }
+    }
+}
+
+impl DataSubscriptions {
+    /// Load registrations from SQLite, replacing the registry
+    pub async fn load(&self, pool: &sqlx::SqlitePool) -> Result<usize> {
+        type Row = (
+            String,
+            String,
+            Option<String>,
+            String,
+            Option<String>,
+            i64,
+            u32,
+            bool,
+        );
+        let rows: Vec<Row> = sqlx::query_as(
+            "SELECT name, url, secret, instances, points, min_interval_ms, timeout_ms, enabled \
+             FROM data_subscriptions",
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(storage_error)?;
+
+        let mut subscriptions = HashMap::new();
+        for (name, url, secret, instances, points, min_interval_ms, timeout_ms, enabled) in rows {
+            let parsed = serde_json::from_str(&instances).and_then(|instances| {
+                let points = points.as_deref().map(serde_json::from_str).transpose()?;
+                Ok((instances, points))
+            });
+            let (instances, points) = match parsed {
+                Ok(filter) => filter,
+                Err(e) => {
+                    warn!(
+                        "Subscription '{}' has an invalid filter, skipped: {}",
+                        name, e
+                    );
+                    continue;
+                },
+            };
+            subscriptions.insert(
+                name.clone(),
+                DataSubscription {
+                    name,
+                    url,
+                    secret,
+                    instances,
+                    points,
+                    min_interval_ms: min_interval_ms.max(0) as u64,
+                    timeout_ms,
+                    enabled,
+                },
+            );
+        }
+
+        let count = subscriptions.len();
+        *self.subscriptions.write().await = subscriptions;
+        self.lock_queues().clear();
+        Ok(count)
+    }
+
+    /// All registrations, sorted by name
+    pub async fn list(&self) -> Vec<DataSubscription> {
+        let mut subscriptions: Vec<_> = self.subscriptions.read().await.values().cloned().collect();
+        subscriptions.sort_by(|a, b| a.name.cmp(&b.name));
+        subscriptions
+    }
+
+    pub async fn get(&self, name: &str) -> Option<DataSubscription> {
+        self.subscriptions.read().await.get(name).cloned()
+    }
+
+    /// Create or replace a registration (SQLite and registry)
+    ///
+    /// Changes queued under the previous filter are discarded.
+    pub async fn upsert(
+        &self,
+        pool: &sqlx::SqlitePool,
+        subscription: DataSubscription,
+    ) -> Result<()> {
+        subscription.validate()?;
+        let instances = serde_json::to_string(&subscription.instances)
+            .map_err(|e| ModSrvError::SerializationError(e.to_string()))?;
+        let points = subscription
+            .points
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| ModSrvError::SerializationError(e.to_string()))?;
+        sqlx::query(
+            "INSERT INTO data_subscriptions \
+             (name, url, secret, instances, points, min_interval_ms, timeout_ms, enabled) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?) \
+             ON CONFLICT(name) DO UPDATE SET url = excluded.url, secret = excluded.secret, \
+             instances = excluded.instances, points = excluded.points, \
+             min_interval_ms = excluded.min_interval_ms, timeout_ms = excluded.timeout_ms, \
+             enabled = excluded.enabled, updated_at = CURRENT_TIMESTAMP",
+        )
+        .bind(&subscription.name)
+        .bind(&subscription.url)
+        .bind(&subscription.secret)
+        .bind(instances)
+        .bind(points)
+        .bind(subscription.min_interval_ms as i64)
+        .bind(subscription.timeout_ms)
+        .bind(subscription.enabled)
+        .execute(pool)
+        .await
+        .map_err(storage_error)?;
+
+        self.lock_queues().remove(&subscription.name);
+        self.subscriptions
+            .write()
+            .await
+            .insert(subscription.name.clone(), subscription);
+        Ok(())
+    }
+
+    /// Remove a registration; returns false if there was none
+    pub async fn remove(&self, pool: &sqlx::SqlitePool, name: &str) -> Result<bool> {
+        sqlx::query("DELETE FROM data_subscriptions WHERE name = ?")
+            .bind(name)
+            .execute(pool)
+            .await
+            .map_err(storage_error)?;
+        self.lock_queues().remove(name);
+        Ok(self.subscriptions.write().await.remove(name).is_some())
+    }
+
+    /// Delivery counters of a subscription
+    pub fn status(&self, name: &str) -> SubscriptionStatus {
+        let queues = self.lock_queues();
+        let Some(queue) = queues.get(name) else {
+            return SubscriptionStatus::default();
+        };
+        let last_error = queue
+            .delivery
+            .last_error
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        SubscriptionStatus {
+            sequence: queue.sequence,
+            pending: queue.pending.len(),
+            delivered: queue.delivery.delivered.load(Ordering::Relaxed),
+            failed: queue.delivery.failed.load(Ordering::Relaxed),
+            last_error,
+        }
+    }
+
+    /// Scan and deliver every [`SCAN_INTERVAL`] until cancelled
+    pub async fn run<R: Rtdb + 'static>(self: Arc<Self>, rtdb: Arc<R>, token: CancellationToken) {
+        let mut interval = tokio::time::interval(SCAN_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => break,
+                _ = interval.tick() => {},
+            }
+            for (subscription, batch, delivery) in self.scan(rtdb.as_ref(), Instant::now()).await {
+                let http = self.http.clone();
+                tokio::spawn(async move {
+                    deliver(&http, &subscription, &batch, &delivery).await;
+                    delivery.in_flight.store(false, Ordering::Release);
+                });
+            }
+        }
+    }
+
+    /// Diff the watched hashes and return the batches due at `now`
+    async fn scan<R: Rtdb>(
+        &self,
+        rtdb: &R,
+        now: Instant,
+    ) -> Vec<(DataSubscription, ChangeBatch, Arc<Delivery>)> {
+        let subscriptions: Vec<DataSubscription> = self
+            .subscriptions
+            .read()
+            .await
+            .values()
+            .filter(|s| s.enabled)
+            .cloned()
+            .collect();
+        let watched: HashSet<u32> = subscriptions
+            .iter()
+            .flat_map(|s| s.instances.iter().copied())
+            .collect();
+
+        let keyspace = KeySpaceConfig::production_cached();
+        let mut changes = Vec::new();
+        for &instance_id in &watched {
+            let current: HashMap<String, String> = match rtdb
+                .hash_get_all(&keyspace.instance_measurement_key(instance_id))
+                .await
+            {
+                Ok(hash) => hash
+                    .into_iter()
+                    .map(|(point, value)| (point, String::from_utf8_lossy(&value).into_owned()))
+                    .collect(),
+                Err(e) => {
+                    debug!(
+                        "Subscription scan of instance {} failed: {}",
+                        instance_id, e
+                    );
+                    continue;
+                },
+            };
+            let mut snapshots = self.lock_snapshots();
+            // The first scan of an instance is the baseline
+            if let Some(previous) = snapshots.get(&instance_id) {
+                for (point, value) in &current {
+                    if previous.get(point) != Some(value) {
+                        if let Ok(point_id) = point.parse::<u32>() {
+                            changes.push((instance_id, point_id, point_value(value)));
+                        }
+                    }
+                }
+            }
+            snapshots.insert(instance_id, current);
+        }
+        self.lock_snapshots().retain(|id, _| watched.contains(id));
+
+        let timestamp = chrono::Utc::now().timestamp_millis();
+        let mut queues = self.lock_queues();
+        let mut due = Vec::new();
+        for subscription in subscriptions {
+            let queue = queues.entry(subscription.name.clone()).or_default();
+            for (instance_id, point_id, value) in &changes {
+                if subscription.matches(*instance_id, *point_id) {
+                    queue
+                        .pending
+                        .insert((*instance_id, *point_id), value.clone());
+                }
+            }
+            while queue.pending.len() > MAX_PENDING {
+                queue.pending.pop_first();
+            }
+
+            let interval = Duration::from_millis(subscription.min_interval_ms);
+            let ready = queue
+                .last_sent
+                .is_none_or(|last| now.duration_since(last) >= interval);
+            if queue.pending.is_empty()
+                || !ready
+                || queue.delivery.in_flight.load(Ordering::Acquire)
+            {
+                continue;
+            }
+            queue.sequence += 1;
+            queue.last_sent = Some(now);
+            queue.delivery.in_flight.store(true, Ordering::Release);
+            let batch = ChangeBatch {
+                subscription: subscription.name.clone(),
+                sequence: queue.sequence,
+                timestamp,
+                changes: std::mem::take(&mut queue.pending)
+                    .into_iter()
+                    .map(|((instance_id, point_id), value)| PointChange {
+                        instance_id,
+                        point_id,
+                        value,
+                    })
+                    .collect(),
+            };
+            due.push((subscription, batch, Arc::clone(&queue.delivery)));
+        }
+        due
+    }
+
+    fn lock_queues(&self) -> std::sync::MutexGuard<'_, HashMap<String, Queue>> {
+        self.queues.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_snapshots(&self) -> std::sync::MutexGuard<'_, HashMap<u32, HashMap<String, String>>> {
+        self.snapshots.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Numeric hash values are sent as numbers
+fn point_value(raw: &str) -> Value {
+    raw.parse::<f64>()
+        .ok()
+        .and_then(serde_json::Number::from_f64)
+        .map(Value::Number)
+        .unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+fn storage_error(e: sqlx::Error) -> ModSrvError {
+    ModSrvError::DatabaseError(format!("Data subscriptions: {}", e))
+}
+
+/// POST a batch, retrying failed deliveries with backoff
+async fn deliver(
+    http: &reqwest::Client,
+    subscription: &DataSubscription,
+    batch: &ChangeBatch,
+    delivery: &Delivery,
+) {
+    let body = match serde_json::to_vec(batch) {
+        Ok(body) => body,
+        Err(e) => {
+            warn!(
+                "Subscription '{}' batch not serializable: {}",
+                subscription.name, e
+            );
+            return;
+        },
+    };
+    let target = WebhookTarget {
+        url: &subscription.url,
+        timeout: Duration::from_millis(subscription.timeout_ms as u64),
+        auth: subscription
+            .secret
+            .as_deref()
+            .map_or(WebhookAuth::None, WebhookAuth::Signed),
+    };
+
+    match webhook::deliver(http, &target, &body).await {
+        Ok(()) => {
+            debug!(
+                "Subscription '{}' batch {} delivered: {} changes",
+                subscription.name,
+                batch.sequence,
+                batch.changes.len()
+            );
+            delivery.delivered.fetch_add(1, Ordering::Relaxed);
+        },
+        Err(error) => {
+            warn!(
+                "Subscription '{}' batch {} dropped after {} attempts: {}",
+                subscription.name,
+                batch.sequence,
+                webhook::DELIVERY_ATTEMPTS,
+                error
+            );
+            delivery.failed.fetch_add(1, Ordering::Relaxed);
+            *delivery
+                .last_error
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()) = Some(error);
+        },
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap/json! are acceptable
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use serde_json::json;
+    use voltage_rtdb::MemoryRtdb;
+
+    fn subscription(name: &str) -> DataSubscription {
+        serde_json::from_value(json!({
+            "name": name,
+            "url": "http://scada.local/changes",
+            "instances": [1]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_filter_and_secret() {
+        let mut sub = subscription("scada");
+        assert!(sub.validate().is_ok());
+        assert!(sub.matches(1, 7));
+        assert!(!sub.matches(2, 7));
+        sub.points = Some(vec![3]);
+        assert!(!sub.matches(1, 7));
+        assert!(sub.matches(1, 3));
+        sub.instances.clear();
+        assert!(sub.validate().is_err());
+
+        // Secrets are accepted but never echoed
+        sub.secret = Some("k".to_string());
+        assert!(serde_json::to_value(&sub).unwrap().get("secret").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_scan_batches_latest_changes_per_interval() {
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::migrations::migrator().run(&pool).await.unwrap();
+        let rtdb = MemoryRtdb::new();
+        rtdb.hash_set("inst:1:M", "1", Bytes::from("10"))
+            .await
+            .unwrap();
+        rtdb.hash_set("inst:1:M", "2", Bytes::from("20"))
+            .await
+            .unwrap();
+
+        let registry = DataSubscriptions::default();
+        let mut sub = subscription("scada");
+        sub.points = Some(vec![1]);
+        sub.min_interval_ms = 1000;
+        registry.upsert(&pool, sub.clone()).await.unwrap();
+
+        let start = Instant::now();
+        // Baseline scan: nothing changed yet
+        assert!(registry.scan(&rtdb, start).await.is_empty());
+
+        rtdb.hash_set("inst:1:M", "1", Bytes::from("11"))
+            .await
+            .unwrap();
+        rtdb.hash_set("inst:1:M", "2", Bytes::from("21"))
+            .await
+            .unwrap();
+        let due = registry.scan(&rtdb, start).await;
+        assert_eq!(due.len(), 1);
+        let (_, batch, delivery) = &due[0];
+        assert_eq!(batch.sequence, 1);
+        assert_eq!(
+            batch.changes,
+            vec![PointChange {
+                instance_id: 1,
+                point_id: 1,
+                value: json!(11.0)
+            }]
+        );
+        delivery.in_flight.store(false, Ordering::Release);
+
+        // Within the interval changes are queued; the newest value wins
+        for value in ["12", "13"] {
+            rtdb.hash_set("inst:1:M", "1", Bytes::from(value))
+                .await
+                .unwrap();
+            let later = start + Duration::from_millis(500);
+            assert!(registry.scan(&rtdb, later).await.is_empty());
+        }
+        assert_eq!(registry.status("scada").pending, 1);
+        let due = registry
+            .scan(&rtdb, start + Duration::from_millis(1000))
+            .await;
+        assert_eq!(due[0].1.sequence, 2);
+        assert_eq!(due[0].1.changes[0].value, json!(13.0));
+
+        // Registrations survive a reload; secrets stay in the database
+        sub.secret = Some("hidden".to_string());
+        registry.upsert(&pool, sub.clone()).await.unwrap();
+        let reloaded = DataSubscriptions::default();
+        assert_eq!(reloaded.load(&pool).await.unwrap(), 1);
+        assert_eq!(reloaded.get("scada").await, Some(sub));
+        assert!(registry.remove(&pool, "scada").await.unwrap());
+        assert!(!registry.remove(&pool, "scada").await.unwrap());
+    }
+}
@@ -0,0 +1,94 @@
+//! In-process modsrv and rule engine for cross-service tests
+//!
+//! [`ModsrvFixture`] and [`RulesrvFixture`] start the model API and the
+//! rule API on a [`FixtureEnv`], over its `MemoryRtdb` and routing cache.
+//! In production both are served by the modsrv process on one port; the
+//! fixtures keep them apart so a test can address each like a service.
+//!
+//! Enabling `test-fixtures` switches [`AppState`] to `MemoryRtdb`; never
+//! enable it in a service build.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::Router;
+use common::sqlite::SqliteClient;
+use common::test_utils::services::{FixtureEnv, ServiceFixture};
+use voltage_rtdb::MemoryRtdb;
+use voltage_rules::RuleScheduler;
+
+use crate::app_state::AppState;
+use crate::config::ModsrvConfig;
+use crate::instance_manager::InstanceManager;
+use crate::product_loader::ProductLoader;
+use crate::rule_routes::{create_rule_routes, RuleEngineState};
+
+/// Rule scheduler tick in fixtures
+const RULE_TICK_MS: u64 = 100;
+
+/// Model API (instances, routing, actions) with its instances synced
+pub struct ModsrvFixture;
+
+#[async_trait(?Send)]
+impl ServiceFixture for ModsrvFixture {
+    const NAME: &'static str = "modsrv";
+    type State = Arc<AppState>;
+
+    async fn start(env: &FixtureEnv) -> Result<(Router, Self::State)> {
+        let pool = env.pool().clone();
+        let product_loader = Arc::new(ProductLoader::new(pool.clone()));
+        product_loader.init_schema().await?;
+
+        let instance_manager = Arc::new(InstanceManager::new(
+            pool.clone(),
+            Arc::clone(env.rtdb()),
+            Arc::clone(env.routing_cache()),
+            Arc::clone(&product_loader),
+        ));
+        // Done in the background by the service; fixtures wait for it
+        instance_manager.sync_instances_to_redis_with(4).await?;
+
+        let state = Arc::new(AppState::new(
+            Arc::new(ModsrvConfig::default()),
+            Some(Arc::new(SqliteClient::from_pool(pool))),
+            product_loader,
+            instance_manager,
+        ));
+        state.populate_name_cache().await?;
+        Ok((crate::routes::create_routes(Arc::clone(&state)), state))
+    }
+}
+
+/// Rule API with a running scheduler
+pub struct RulesrvFixture;
+
+#[async_trait(?Send)]
+impl ServiceFixture for RulesrvFixture {
+    const NAME: &'static str = "rulesrv";
+    type State = Arc<RuleScheduler<MemoryRtdb>>;
+
+    async fn start(env: &FixtureEnv) -> Result<(Router, Self::State)> {
+        let log_root = env
+            .db_path()
+            .parent()
+            .map_or_else(|| PathBuf::from("logs"), |dir| dir.join("logs"));
+        let scheduler = Arc::new(RuleScheduler::new(
+            Arc::clone(env.rtdb()),
+            Arc::clone(env.routing_cache()),
+            env.pool().clone(),
+            RULE_TICK_MS,
+            log_root,
+        ));
+        scheduler.load_rules().await?;
+        let running = Arc::clone(&scheduler);
+        tokio::spawn(async move { running.start().await });
+
+        let state = Arc::new(RuleEngineState::new(
+            env.pool().clone(),
+            Arc::clone(&scheduler),
+        ));
+        Ok((create_rule_routes(state), scheduler))
+    }
+}
@@ -303,7 +303,7 @@ async fn test_list_instances_paginated() {
 
     // Page 1: should have 10 items
     let (total, page1) = manager
-        .list_instances_paginated(None, 1, 10)
+        .list_instances_paginated(None, &[], 1, 10)
         .await
         .expect("Failed to paginate");
     assert_eq!(total, 15);
@@ -313,7 +313,7 @@ async fn test_list_instances_paginated() {
 
     // Page 2: should have 5 items
     let (total, page2) = manager
-        .list_instances_paginated(None, 2, 10)
+        .list_instances_paginated(None, &[], 2, 10)
         .await
         .expect("Failed to paginate");
     assert_eq!(total, 15);
@@ -323,7 +323,7 @@ async fn test_list_instances_paginated() {
 
     // Page 3: should be empty
     let (total, page3) = manager
-        .list_instances_paginated(None, 3, 10)
+        .list_instances_paginated(None, &[], 3, 10)
         .await
         .expect("Failed to paginate");
     assert_eq!(total, 15);
@@ -347,14 +347,14 @@ async fn test_list_instances_paginated_with_filter() {
 
     // Paginate Battery only (8 total)
     let (total, page1) = manager
-        .list_instances_paginated(Some("Battery"), 1, 5)
+        .list_instances_paginated(Some("Battery"), &[], 1, 5)
         .await
         .expect("Failed to paginate");
     assert_eq!(total, 8);
     assert_eq!(page1.len(), 5);
 
     let (total, page2) = manager
-        .list_instances_paginated(Some("Battery"), 2, 5)
+        .list_instances_paginated(Some("Battery"), &[], 2, 5)
         .await
         .expect("Failed to paginate");
     assert_eq!(total, 8);
@@ -452,7 +452,7 @@ async fn test_batch_create_instances() {
 
     // Verify all created
     let (total, _) = manager
-        .list_instances_paginated(None, 1, 100)
+        .list_instances_paginated(None, &[], 1, 100)
         .await
         .expect("Failed to list");
     assert_eq!(total, 20);
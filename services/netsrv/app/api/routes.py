@@ -3,6 +3,7 @@ API路由模块
 """
 
 from fastapi import APIRouter, HTTPException, Request, UploadFile, File, Form
+from fastapi.responses import PlainTextResponse
 from typing import Dict, Any
 from pathlib import Path
 import asyncio
@@ -10,8 +11,11 @@ from loguru import logger
 from app.services.data_forwarder import data_forwarder
 from app.services.alarm_broadcaster import alarm_broadcaster
 from app.services.certificate_manager import certificate_manager
+from app.services.cloud_connector import cloud_connector_manager
+from app.services.command_authorizer import command_authorizer
 from app.core.database import redis_manager
 from app.core.mqtt_client import mqtt_client
+from app.core.delivery_stats import delivery_stats
 from app.core.config import settings
 from app.core.config_loader import config_loader
 # 由于模块导入问题，直接在此处定义模型
@@ -139,6 +143,7 @@ async def broadcast_alarm(request: Request):
         if not mqtt_client.is_connected:
             # 等待最多30秒，每5秒检查一次连接状态
             for i in range(6):
+                delivery_stats.record_retry(mqtt_client.NETWORK_NAME)
                 await asyncio.sleep(5)
                 if mqtt_client.is_connected:
                     break
@@ -146,6 +151,7 @@ async def broadcast_alarm(request: Request):
             
             # 如果仍然未连接，返回503错误
             if not mqtt_client.is_connected:
+                delivery_stats.record_failed(mqtt_client.NETWORK_NAME)
                 raise HTTPException(status_code=503, detail="MQTT服务未连接，无法发送告警")
         
         # 广播告警消息（原样转发）
@@ -187,6 +193,64 @@ async def get_alarm_config():
         raise HTTPException(status_code=500, detail=f"获取告警配置失败: {str(e)}")
 
 
+@router.get("/cloud/connectors")
+async def get_cloud_connectors():
+    """
+    获取托管云平台连接器（Azure IoT Hub / AWS IoT Core）状态
+    """
+    try:
+        return {
+            "status": "success",
+            "connectors": cloud_connector_manager.get_status()
+        }
+    except Exception as e:
+        raise HTTPException(status_code=500, detail=f"获取云平台连接器状态失败: {str(e)}")
+
+
+@router.get("/stats")
+async def get_delivery_stats():
+    """
+    获取各网络投递统计
+
+    按网络（主MQTT及各云平台连接器）返回发送/失败/重试次数、缓冲积压、
+    未确认数量，以及统计窗口内的成功率和端到端延迟分位数（p50/p95/p99）。
+    超出 monitoring.report_monitor 阈值的网络标记为 degraded
+    """
+    try:
+        return {
+            "status": "success",
+            **delivery_stats.get_stats()
+        }
+    except Exception as e:
+        raise HTTPException(status_code=500, detail=f"获取投递统计失败: {str(e)}")
+
+
+@router.get("/metrics", response_class=PlainTextResponse)
+async def get_delivery_metrics():
+    """
+    以 Prometheus 文本格式输出投递统计
+    """
+    try:
+        return PlainTextResponse(delivery_stats.render_metrics(),
+                                 media_type="text/plain; version=0.0.4")
+    except Exception as e:
+        raise HTTPException(status_code=500, detail=f"获取投递指标失败: {str(e)}")
+
+
+@router.get("/commands/audit")
+async def get_command_audit(limit: int = 50):
+    """
+    获取最近的远程命令审计记录
+    """
+    try:
+        return {
+            "status": "success",
+            "records": command_authorizer.get_recent_audit(limit)
+        }
+    except Exception as e:
+        raise HTTPException(status_code=500, detail=f"获取命令审计记录失败: {str(e)}")
+
+
 # MQTT配置管理接口
 
 @router.get("/mqtt/config", response_model=MQTTConfigResponse)
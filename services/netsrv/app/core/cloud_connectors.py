@@ -0,0 +1,528 @@
+"""
+托管云平台连接器模块
+提供 Azure IoT Hub 和 AWS IoT Core 的 NetworkClient 实现：
+设备注册（DPS / Fleet Provisioning）、SAS/x509 认证、平台主题约定，
+以及设备孪生/影子的期望属性回调
+"""
+
+import base64
+import hashlib
+import hmac
+import json
+import queue
+import ssl
+import threading
+import time
+from pathlib import Path
+from typing import Any, Callable, Dict, Optional
+from urllib.parse import quote_plus
+
+import paho.mqtt.client as mqtt
+from loguru import logger
+from .delivery_stats import delivery_stats
+
+# 期望属性回调: (connector_name, {属性名: 值})
+DesiredHandler = Callable[[str, Dict[str, Any]], None]
+
+# 凭据在有效期的该比例处提前刷新
+CREDENTIAL_REFRESH_RATIO = 0.8
+
+
+def generate_sas_token(resource_uri: str, key: str, policy_name: Optional[str] = None,
+                       expiry_seconds: int = 3600) -> str:
+    """生成 Azure SAS 令牌（HMAC-SHA256）"""
+    expiry = int(time.time()) + expiry_seconds
+    encoded_uri = quote_plus(resource_uri)
+    to_sign = f"{encoded_uri}\n{expiry}".encode('utf-8')
+    signature = base64.b64encode(
+        hmac.new(base64.b64decode(key), to_sign, hashlib.sha256).digest()
+    ).decode('utf-8')
+
+    token = f"SharedAccessSignature sr={encoded_uri}&sig={quote_plus(signature)}&se={expiry}"
+    if policy_name:
+        token += f"&skn={policy_name}"
+    return token
+
+
+class NetworkClient:
+    """云端网络客户端基类，封装 MQTT over TLS 连接和消息分发"""
+
+    def __init__(self, name: str, config: Dict[str, Any]):
+        self.name = name
+        self.config = config
+        self.client: Optional[mqtt.Client] = None
+        self.is_connected = False
+        self.desired_handler: Optional[DesiredHandler] = None
+        self._handlers: Dict[str, Callable[[str, bytes], None]] = {}
+        self._request_id = 0
+        self._refresh_timer: Optional[threading.Timer] = None
+
+    # ---------- 子类实现 ----------
+
+    def provision(self) -> bool:
+        """设备注册，默认无需注册"""
+        return True
+
+    def _connection_params(self) -> Dict[str, Any]:
+        """返回 host/port/client_id/username/password/cert/key/ca"""
+        raise NotImplementedError
+
+    def _credentials_ttl(self) -> Optional[float]:
+        """连接凭据有效期（秒），None 表示凭据不过期"""
+        return None
+
+    def _refresh_credentials(self) -> bool:
+        """重新生成连接凭据并写入客户端，返回是否已刷新"""
+        return False
+
+    def _on_connected(self):
+        """连接建立后订阅平台主题"""
+
+    def telemetry_topic(self) -> str:
+        raise NotImplementedError
+
+    def report_properties(self, properties: Dict[str, Any]) -> bool:
+        """上报属性（孪生reported / 影子reported）"""
+        raise NotImplementedError
+
+    # ---------- 通用实现 ----------
+
+    def connect(self) -> bool:
+        """注册并连接到云平台"""
+        try:
+            if not self.provision():
+                logger.error(f"[{self.name}] 设备注册失败")
+                return False
+
+            params = self._connection_params()
+            self.client = _create_mqtt_client(params)
+            self.client.on_connect = self._on_connect
+            self.client.on_disconnect = self._on_disconnect
+            self.client.on_message = self._on_message
+            self.client.on_publish = self._on_publish
+            self.client.connect(params['host'], params.get('port', 8883),
+                                keepalive=self.config.get('keepalive', 120))
+            self.client.loop_start()
+            self._schedule_refresh()
+            logger.info(f"[{self.name}] 正在连接 {params['host']}")
+            return True
+
+        except Exception as e:
+            logger.error(f"[{self.name}] 连接失败: {e}")
+            return False
+
+    def disconnect(self):
+        """断开连接"""
+        self._cancel_refresh()
+        if self.client:
+            try:
+                self.client.loop_stop()
+                self.client.disconnect()
+            except Exception as e:
+                logger.warning(f"[{self.name}] 断开连接异常: {e}")
+        self.is_connected = False
+
+    def publish(self, topic: str, payload: Any, qos: int = 1) -> bool:
+        """发布消息"""
+        if not self.client or not self.is_connected:
+            logger.debug(f"[{self.name}] 未连接，跳过发送: {topic}")
+            delivery_stats.record_failed(self.name)
+            return False
+
+        if not isinstance(payload, (str, bytes)):
+            payload = json.dumps(payload, ensure_ascii=False)
+        result = self.client.publish(topic, payload, qos=qos)
+        if result.rc != mqtt.MQTT_ERR_SUCCESS:
+            delivery_stats.record_failed(self.name)
+            return False
+        delivery_stats.record_sent(self.name, mid=result.mid if qos > 0 else None)
+        return True
+
+    def publish_telemetry(self, message: Dict[str, Any]) -> bool:
+        """发布遥测数据"""
+        return self.publish(self.telemetry_topic(), message)
+
+    def subscribe(self, topic: str, handler: Callable[[str, bytes], None], qos: int = 1):
+        """订阅主题并注册处理器（支持 # 通配符前缀匹配）"""
+        self._handlers[topic] = handler
+        if self.client:
+            self.client.subscribe(topic, qos)
+
+    def _schedule_refresh(self):
+        """在凭据到期前安排刷新"""
+        self._cancel_refresh()
+        ttl = self._credentials_ttl()
+        if not ttl:
+            return
+        self._refresh_timer = threading.Timer(ttl * CREDENTIAL_REFRESH_RATIO, self._refresh_and_reconnect)
+        self._refresh_timer.daemon = True
+        self._refresh_timer.start()
+
+    def _cancel_refresh(self):
+        if self._refresh_timer:
+            self._refresh_timer.cancel()
+            self._refresh_timer = None
+
+    def _refresh_and_reconnect(self):
+        """凭据即将过期：换用新凭据重连，平台按新凭据重新计算有效期"""
+        if self.client and self._refresh_credentials():
+            logger.info(f"[{self.name}] 凭据即将过期，使用新凭据重连")
+            try:
+                self.client.reconnect()
+            except Exception as e:
+                # 网络线程会按新凭据继续自动重连
+                logger.warning(f"[{self.name}] 刷新凭据后重连失败: {e}")
+        self._schedule_refresh()
+
+    def _next_request_id(self) -> int:
+        self._request_id += 1
+        return self._request_id
+
+    def _dispatch_desired(self, desired: Dict[str, Any]):
+        """去除平台元数据后交给期望属性回调"""
+        properties = {k: v for k, v in desired.items() if not k.startswith('$')}
+        if properties and self.desired_handler:
+            self.desired_handler(self.name, properties)
+
+    def _on_connect(self, client, userdata, flags, rc):
+        if rc == 0:
+            self.is_connected = True
+            logger.info(f"[{self.name}] 连接成功")
+            for topic in self._handlers:
+                client.subscribe(topic, 1)
+            self._on_connected()
+        else:
+            logger.error(f"[{self.name}] 连接被拒绝，返回码: {rc}")
+
+    def _on_disconnect(self, client, userdata, rc):
+        self.is_connected = False
+        if rc != 0:
+            logger.warning(f"[{self.name}] 连接意外断开，返回码: {rc}")
+            # 断开可能由凭据过期引起，网络线程自动重连前换用新凭据
+            self._refresh_credentials()
+
+    def _on_publish(self, client, userdata, mid):
+        delivery_stats.record_acked(self.name, mid)
+
+    def _on_message(self, client, userdata, msg):
+        for topic, handler in self._handlers.items():
+            if mqtt.topic_matches_sub(topic, msg.topic):
+                try:
+                    handler(msg.topic, msg.payload)
+                except Exception as e:
+                    logger.error(f"[{self.name}] 处理消息失败: {msg.topic}, {e}")
+                return
+
+    def get_status(self) -> Dict[str, Any]:
+        """获取连接器状态"""
+        return {
+            "name": self.name,
+            "type": self.config.get('type'),
+            "connected": self.is_connected,
+        }
+
+
+def _create_mqtt_client(params: Dict[str, Any]) -> mqtt.Client:
+    """根据连接参数创建带TLS的MQTT客户端"""
+    client = mqtt.Client(client_id=params['client_id'], clean_session=True,
+                         protocol=mqtt.MQTTv311)
+    if params.get('username') is not None:
+        client.username_pw_set(params['username'], params.get('password'))
+
+    client.tls_set(
+        ca_certs=params.get('ca_cert'),
+        certfile=params.get('client_cert'),
+        keyfile=params.get('client_key'),
+        cert_reqs=ssl.CERT_REQUIRED,
+        tls_version=ssl.PROTOCOL_TLSv1_2
+    )
+    return client
+
+
+def _request_reply(params: Dict[str, Any], subscribe_topic: str,
+                   requests: Callable[[mqtt.Client, Callable[[], Optional[Dict]]], Optional[Dict]],
+                   timeout: float) -> Optional[Dict]:
+    """建立一次性MQTT连接执行请求/应答式注册流程
+
+    requests 接收客户端和一个等待下一条回复的函数，返回注册结果
+    """
+    replies: "queue.Queue" = queue.Queue()
+    connected = threading.Event()
+
+    def on_message(client, userdata, msg):
+        try:
+            payload = json.loads(msg.payload.decode('utf-8') or '{}')
+        except json.JSONDecodeError:
+            payload = {}
+        replies.put((msg.topic, payload))
+
+    def wait_reply() -> Optional[Dict]:
+        try:
+            topic, payload = replies.get(timeout=timeout)
+        except queue.Empty:
+            return None
+        return {"topic": topic, "payload": payload}
+
+    client = _create_mqtt_client(params)
+    client.on_connect = lambda c, u, f, rc: connected.set() if rc == 0 else None
+    client.on_message = on_message
+    client.connect(params['host'], params.get('port', 8883), keepalive=60)
+    client.loop_start()
+    try:
+        if not connected.wait(timeout):
+            logger.error(f"注册服务连接超时: {params['host']}")
+            return None
+        client.subscribe(subscribe_topic, 1)
+        return requests(client, wait_reply)
+    finally:
+        client.loop_stop()
+        client.disconnect()
+
+
+class AzureIoTHubClient(NetworkClient):
+    """Azure IoT Hub 连接器
+
+    - 认证: SAS（设备对称密钥）或 x509 证书
+    - 注册: 可选 Device Provisioning Service（DPS）
+    - 遥测: devices/{deviceId}/messages/events/
+    - 孪生: desired 属性变更映射为调节写入，写入结果作为 reported 上报
+    """
+
+    API_VERSION = "2021-04-12"
+    DPS_API_VERSION = "2019-03-31"
+
+    def __init__(self, name: str, config: Dict[str, Any]):
+        super().__init__(name, config)
+        self.hostname = config.get('hostname', '')
+        self.device_id = config.get('device_id', '')
+        self.auth = config.get('auth', {}) or {}
+
+    def provision(self) -> bool:
+        dps = self.config.get('provisioning', {}) or {}
+        if not dps.get('enabled', False):
+            return bool(self.hostname and self.device_id)
+
+        id_scope = dps['id_scope']
+        registration_id = dps.get('registration_id') or self.device_id
+        resource = f"{id_scope}/registrations/{registration_id}"
+        params = {
+            'host': dps.get('endpoint', 'global.azure-devices-provisioning.net'),
+            'client_id': registration_id,
+            'username': f"{resource}/api-version={self.DPS_API_VERSION}",
+            **self._auth_params(resource, policy_name='registration'),
+        }
+
+        def register(client, wait_reply):
+            client.publish(f"$dps/registrations/PUT/iotdps-register/?$rid={self._next_request_id()}",
+                           json.dumps({"registrationId": registration_id}), qos=1)
+            deadline = time.time() + dps.get('timeout', 60)
+            while time.time() < deadline:
+                reply = wait_reply()
+                if reply is None:
+                    return None
+                payload = reply['payload']
+                status = payload.get('status')
+                if status == 'assigned':
+                    return payload.get('registrationState', {})
+                if status != 'assigning':
+                    logger.error(f"[{self.name}] DPS注册失败: {payload}")
+                    return None
+                # 注册处理中，按DPS建议的间隔轮询操作状态
+                time.sleep(3)
+                client.publish(
+                    f"$dps/registrations/GET/iotdps-get-operationstatus/?$rid={self._next_request_id()}"
+                    f"&operationId={payload.get('operationId')}", "", qos=1)
+            return None
+
+        state = _request_reply(params, "$dps/registrations/res/#", register, dps.get('timeout', 60))
+        if not state:
+            return False
+
+        self.hostname = state.get('assignedHub', self.hostname)
+        self.device_id = state.get('deviceId', self.device_id)
+        logger.info(f"[{self.name}] DPS注册成功: {self.hostname}/{self.device_id}")
+        return True
+
+    def _auth_params(self, resource_uri: str, policy_name: Optional[str] = None) -> Dict[str, Any]:
+        """SAS 使用密码令牌，x509 使用客户端证书"""
+        if not self._uses_sas():
+            return {
+                'password': None,
+                'client_cert': self.auth.get('client_cert'),
+                'client_key': self.auth.get('client_key'),
+                'ca_cert': self.auth.get('ca_cert'),
+            }
+        return {
+            'password': generate_sas_token(resource_uri, self.auth['shared_access_key'], policy_name,
+                                           self.auth.get('token_ttl', 3600)),
+            'ca_cert': self.auth.get('ca_cert'),
+        }
+
+    def _connection_params(self) -> Dict[str, Any]:
+        return {
+            'host': self.hostname,
+            'client_id': self.device_id,
+            'username': self._username(),
+            **self._auth_params(self._resource_uri()),
+        }
+
+    def _username(self) -> str:
+        return f"{self.hostname}/{self.device_id}/?api-version={self.API_VERSION}"
+
+    def _resource_uri(self) -> str:
+        return f"{self.hostname}/devices/{self.device_id}"
+
+    def _uses_sas(self) -> bool:
+        return self.auth.get('type', 'sas') != 'x509'
+
+    def _credentials_ttl(self) -> Optional[float]:
+        return self.auth.get('token_ttl', 3600) if self._uses_sas() else None
+
+    def _refresh_credentials(self) -> bool:
+        if not self.client or not self._uses_sas():
+            return False
+        self.client.username_pw_set(self._username(), self._auth_params(self._resource_uri())['password'])
+        return True
+
+    def connect(self) -> bool:
+        self.subscribe("$iothub/twin/res/#", self._handle_twin_response)
+        self.subscribe("$iothub/twin/PATCH/properties/desired/#", self._handle_desired_patch)
+        return super().connect()
+
+    def _on_connected(self):
+        # 请求完整孪生，应用离线期间的期望属性
+        self.publish(f"$iothub/twin/GET/?$rid={self._next_request_id()}", "")
+
+    def telemetry_topic(self) -> str:
+        return f"devices/{self.device_id}/messages/events/"
+
+    def report_properties(self, properties: Dict[str, Any]) -> bool:
+        return self.publish(f"$iothub/twin/PATCH/properties/reported/?$rid={self._next_request_id()}",
+                            properties)
+
+    def _handle_twin_response(self, topic: str, payload: bytes):
+        # $iothub/twin/res/{status}/?$rid={rid}
+        status = topic.split('/')[3]
+        if status != '200' or not payload:
+            return
+        twin = json.loads(payload)
+        self._dispatch_desired(twin.get('desired', {}))
+
+    def _handle_desired_patch(self, topic: str, payload: bytes):
+        self._dispatch_desired(json.loads(payload))
+
+    def get_status(self) -> Dict[str, Any]:
+        return {**super().get_status(), "hostname": self.hostname, "device_id": self.device_id}
+
+
+class AwsIotCoreClient(NetworkClient):
+    """AWS IoT Core 连接器
+
+    - 认证: x509 设备证书
+    - 注册: 可选 Fleet Provisioning by claim（申领证书换取设备证书）
+    - 遥测: dt/{app}/{thingName}/telemetry（可配置）
+    - 影子: shadow delta 映射为调节写入，写入结果作为 reported 上报
+    """
+
+    def __init__(self, name: str, config: Dict[str, Any]):
+        super().__init__(name, config)
+        self.endpoint = config.get('endpoint', '')
+        self.thing_name = config.get('thing_name', '')
+        self.auth = config.get('auth', {}) or {}
+
+    def provision(self) -> bool:
+        fleet = self.config.get('provisioning', {}) or {}
+        if not fleet.get('enabled', False):
+            return bool(self.endpoint and self.thing_name)
+
+        cert_path = Path(self.auth['client_cert'])
+        key_path = Path(self.auth['client_key'])
+        if cert_path.exists() and key_path.exists():
+            logger.info(f"[{self.name}] 设备证书已存在，跳过Fleet Provisioning")
+            return True
+
+        template = fleet['template_name']
+        params = {
+            'host': self.endpoint,
+            'client_id': self.thing_name or f"provision-{int(time.time())}",
+            'username': None,
+            'ca_cert': self.auth.get('ca_cert'),
+            'client_cert': fleet['claim_cert'],
+            'client_key': fleet['claim_key'],
+        }
+
+        def register(client, wait_reply):
+            client.subscribe(f"$aws/provisioning-templates/{template}/provision/json/+", 1)
+            client.publish("$aws/certificates/create/json", "{}", qos=1)
+            created = wait_reply()
+            if not created or not created['topic'].endswith('accepted'):
+                logger.error(f"[{self.name}] 创建设备证书失败: {created}")
+                return None
+
+            certificate = created['payload']
+            client.publish(f"$aws/provisioning-templates/{template}/provision/json", json.dumps({
+                "certificateOwnershipToken": certificate['certificateOwnershipToken'],
+                "parameters": fleet.get('parameters', {}),
+            }), qos=1)
+            registered = wait_reply()
+            if not registered or not registered['topic'].endswith('accepted'):
+                logger.error(f"[{self.name}] Fleet Provisioning失败: {registered}")
+                return None
+            return {**certificate, **registered['payload']}
+
+        result = _request_reply(params, "$aws/certificates/create/json/+", register,
+                                fleet.get('timeout', 60))
+        if not result:
+            return False
+
+        cert_path.parent.mkdir(parents=True, exist_ok=True)
+        cert_path.write_text(result['certificatePem'])
+        key_path.write_text(result['privateKey'])
+        key_path.chmod(0o600)
+        self.thing_name = result.get('thingName', self.thing_name)
+        logger.info(f"[{self.name}] Fleet Provisioning成功: {self.thing_name}")
+        return True
+
+    def _connection_params(self) -> Dict[str, Any]:
+        return {
+            'host': self.endpoint,
+            'client_id': self.thing_name,
+            'username': None,
+            'ca_cert': self.auth.get('ca_cert'),
+            'client_cert': self.auth.get('client_cert'),
+            'client_key': self.auth.get('client_key'),
+        }
+
+    def _shadow_topic(self, suffix: str) -> str:
+        return f"$aws/things/{self.thing_name}/shadow/{suffix}"
+
+    def connect(self) -> bool:
+        self.subscribe(self._shadow_topic("update/delta"), self._handle_delta)
+        self.subscribe(self._shadow_topic("get/accepted"), self._handle_shadow_document)
+        return super().connect()
+
+    def _on_connected(self):
+        # 获取完整影子，应用离线期间的期望属性差异
+        self.publish(self._shadow_topic("get"), "")
+
+    def telemetry_topic(self) -> str:
+        topic = self.config.get('telemetry_topic', 'dt/voltage/{thingName}/telemetry')
+        return topic.replace('{thingName}', self.thing_name)
+
+    def report_properties(self, properties: Dict[str, Any]) -> bool:
+        return self.publish(self._shadow_topic("update"), {"state": {"reported": properties}})
+
+    def _handle_delta(self, topic: str, payload: bytes):
+        self._dispatch_desired(json.loads(payload).get('state', {}))
+
+    def _handle_shadow_document(self, topic: str, payload: bytes):
+        self._dispatch_desired(json.loads(payload).get('state', {}).get('delta', {}))
+
+    def get_status(self) -> Dict[str, Any]:
+        return {**super().get_status(), "endpoint": self.endpoint, "thing_name": self.thing_name}
+
+
+NETWORK_CLIENT_TYPES = {
+    "azure_iot_hub": AzureIoTHubClient,
+    "aws_iot_core": AwsIotCoreClient,
+}
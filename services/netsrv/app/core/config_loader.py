@@ -85,6 +85,18 @@ class ConfigLoader:
         """获取转发策略配置"""
         return self.get_config('forward_strategy', {})
     
+    def get_cloud_connectors_config(self) -> Dict[str, Any]:
+        """获取托管云平台连接器配置"""
+        return self.get_config('cloud_connectors', {}) or {}
+    
+    def get_command_authorization_config(self) -> Dict[str, Any]:
+        """获取远程命令授权配置"""
+        return self.get_config('command_authorization', {}) or {}
+    
+    def get_transform_config(self, network: str) -> Dict[str, Any]:
+        """获取指定网络的数据转换规则"""
+        return self.get_config(f'transforms.{network}', {}) or {}
+    
     def get_redis_source_config(self) -> Dict[str, Any]:
         """获取Redis数据源配置"""
         return self.get_config('redis_source', {})
@@ -0,0 +1,220 @@
+"""
+投递统计模块
+按网络（主MQTT及各云平台连接器）统计发送、失败、重试次数和缓冲积压，
+以及从数据采集到平台确认的端到端延迟分位数，用于提前发现云端链路劣化
+"""
+
+import math
+import threading
+import time
+from collections import deque
+from typing import Any, Callable, Deque, Dict, List, Optional, Tuple
+from .config_loader import config_loader
+
+# 每个网络保留的延迟样本/发送结果上限
+MAX_SAMPLES = 2048
+# 已发送但超过该时长仍未确认的消息视为丢失（秒）
+INFLIGHT_TIMEOUT = 300
+# 延迟分位数
+QUANTILES = (0.5, 0.95, 0.99)
+
+
+def percentile(sorted_values: List[float], q: float) -> Optional[float]:
+    """最近秩法计算分位数，输入需已排序"""
+    if not sorted_values:
+        return None
+    rank = max(1, math.ceil(q * len(sorted_values)))
+    return sorted_values[rank - 1]
+
+
+class NetworkStats:
+    """单个网络的投递统计"""
+
+    def __init__(self):
+        self.sent = 0
+        self.failed = 0
+        self.retried = 0
+        self.acked = 0
+        self.last_sent_at: Optional[float] = None
+        self.last_failed_at: Optional[float] = None
+        self.lag_samples: Deque[Tuple[float, float]] = deque(maxlen=MAX_SAMPLES)  # (记录时间, 延迟秒)
+        self.results: Deque[Tuple[float, bool]] = deque(maxlen=MAX_SAMPLES)  # (记录时间, 是否成功)
+        self.inflight: Dict[int, Tuple[float, float]] = {}  # mid -> (数据采集时间, 发送时间)
+        self.backlog: Optional[Callable[[], int]] = None
+
+
+class DeliveryStats:
+    """投递统计管理器（MQTT回调运行在网络线程，所有操作加锁）"""
+
+    def __init__(self):
+        self.networks: Dict[str, NetworkStats] = {}
+        self.lock = threading.Lock()
+
+    def _get(self, network: str) -> NetworkStats:
+        stats = self.networks.get(network)
+        if stats is None:
+            stats = self.networks[network] = NetworkStats()
+        return stats
+
+    def register_backlog(self, network: str, provider: Callable[[], int]):
+        """注册缓冲积压数量的读取函数"""
+        with self.lock:
+            self._get(network).backlog = provider
+
+    def record_sent(self, network: str, origin: Optional[float] = None, mid: Optional[int] = None):
+        """记录一次发送成功
+
+        origin 为数据采集时间，缺省为当前时间；给出 mid 时等待平台确认后再记录延迟
+        """
+        now = time.time()
+        origin = now if origin is None else origin
+        with self.lock:
+            stats = self._get(network)
+            stats.sent += 1
+            stats.last_sent_at = now
+            stats.results.append((now, True))
+            if mid is None:
+                stats.lag_samples.append((now, now - origin))
+            else:
+                stats.inflight[mid] = (origin, now)
+
+    def record_acked(self, network: str, mid: int):
+        """记录平台确认（QoS1 PUBACK），计算端到端延迟"""
+        now = time.time()
+        with self.lock:
+            stats = self._get(network)
+            pending = stats.inflight.pop(mid, None)
+            if pending is None:
+                # QoS0 消息不等待确认
+                return
+            stats.acked += 1
+            stats.lag_samples.append((now, now - pending[0]))
+
+    def record_failed(self, network: str, count: int = 1):
+        """记录发送失败（含未连接时被跳过的数据）"""
+        now = time.time()
+        with self.lock:
+            stats = self._get(network)
+            stats.failed += count
+            stats.last_failed_at = now
+            for _ in range(count):
+                stats.results.append((now, False))
+
+    def record_retry(self, network: str):
+        """记录一次重试"""
+        with self.lock:
+            self._get(network).retried += 1
+
+    def _expire_inflight(self, stats: NetworkStats, now: float):
+        """超时未确认的消息计为失败"""
+        expired = [mid for mid, (_, sent_at) in stats.inflight.items()
+                   if now - sent_at > INFLIGHT_TIMEOUT]
+        for mid in expired:
+            del stats.inflight[mid]
+            stats.failed += 1
+            stats.last_failed_at = now
+            stats.results.append((now, False))
+
+    def _network_snapshot(self, stats: NetworkStats, now: float, window: float,
+                          success_threshold: float, latency_threshold_ms: float) -> Dict[str, Any]:
+        self._expire_inflight(stats, now)
+
+        lags = sorted(lag for recorded_at, lag in stats.lag_samples if now - recorded_at <= window)
+        recent = [ok for recorded_at, ok in stats.results if now - recorded_at <= window]
+        success_rate = round(100.0 * sum(recent) / len(recent), 2) if recent else None
+
+        lag_ms = {f"p{int(q * 100)}": None if not lags else round(percentile(lags, q) * 1000, 1)
+                  for q in QUANTILES}
+        lag_ms["max"] = round(lags[-1] * 1000, 1) if lags else None
+        lag_ms["samples"] = len(lags)
+
+        backlog = 0
+        if stats.backlog:
+            try:
+                backlog = int(stats.backlog())
+            except Exception:
+                backlog = 0
+
+        # 与 monitoring.report_monitor 阈值比较：窗口内成功率偏低或 p95 延迟超限即视为劣化
+        degraded = bool(
+            (success_rate is not None and success_rate < success_threshold)
+            or (lag_ms["p95"] is not None and lag_ms["p95"] > latency_threshold_ms)
+        )
+
+        return {
+            "sent": stats.sent,
+            "failed": stats.failed,
+            "retried": stats.retried,
+            "acked": stats.acked,
+            "inflight": len(stats.inflight),
+            "backlog": backlog,
+            "success_rate": success_rate,
+            "lag_ms": lag_ms,
+            "last_sent_at": stats.last_sent_at,
+            "last_failed_at": stats.last_failed_at,
+            "degraded": degraded,
+        }
+
+    def get_stats(self) -> Dict[str, Any]:
+        """获取各网络的投递统计"""
+        monitor = config_loader.get_config('monitoring.report_monitor', {}) or {}
+        window = float(monitor.get('window', 300))
+        success_threshold = float(monitor.get('success_rate_threshold', 95))
+        latency_threshold_ms = float(monitor.get('latency_threshold', 1000))
+
+        now = time.time()
+        with self.lock:
+            networks = {name: self._network_snapshot(stats, now, window, success_threshold,
+                                                     latency_threshold_ms)
+                        for name, stats in self.networks.items()}
+
+        return {
+            "window_seconds": window,
+            "thresholds": {
+                "success_rate": success_threshold,
+                "latency_ms": latency_threshold_ms,
+            },
+            "networks": networks,
+        }
+
+    def render_metrics(self) -> str:
+        """以 Prometheus 文本格式输出投递统计"""
+        networks = self.get_stats()["networks"]
+        lines: List[str] = []
+
+        def family(name: str, metric_type: str, help_text: str, samples: List[Tuple[str, Any]]):
+            lines.append(f"# HELP {name} {help_text}")
+            lines.append(f"# TYPE {name} {metric_type}")
+            for labels, value in samples:
+                lines.append(f"{name}{{{labels}}} {value}")
+
+        def per_network(key: str) -> List[Tuple[str, Any]]:
+            return [(f'network="{name}"', stats[key]) for name, stats in networks.items()]
+
+        family("netsrv_delivery_sent_total", "counter", "Messages accepted for delivery",
+               per_network("sent"))
+        family("netsrv_delivery_failed_total", "counter", "Messages that failed or were skipped",
+               per_network("failed"))
+        family("netsrv_delivery_retried_total", "counter", "Delivery retries",
+               per_network("retried"))
+        family("netsrv_delivery_backlog", "gauge", "Messages buffered awaiting send",
+               per_network("backlog"))
+        family("netsrv_delivery_inflight", "gauge", "Messages sent but not yet acknowledged",
+               per_network("inflight"))
+        family("netsrv_delivery_degraded", "gauge", "1 if the network breaches report_monitor thresholds",
+               [(labels, int(value)) for labels, value in per_network("degraded")])
+
+        lag_samples = []
+        for name, stats in networks.items():
+            for q in QUANTILES:
+                value = stats["lag_ms"][f"p{int(q * 100)}"]
+                if value is not None:
+                    lag_samples.append((f'network="{name}",quantile="{q}"', value / 1000))
+        family("netsrv_delivery_lag_seconds", "gauge",
+               "End-to-end delivery lag quantiles over the stats window", lag_samples)
+
+        return "\n".join(lines) + "\n"
+
+
+# 全局投递统计实例
+delivery_stats = DeliveryStats()
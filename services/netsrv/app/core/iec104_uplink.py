@@ -0,0 +1,389 @@
+"""
+IEC 60870-5-104 上行模块
+网关作为被控站主动连接调度主站（TCP客户端），
+在主站发送 STARTDT 后按映射表自发上送点位，并响应总召唤
+"""
+
+import socket
+import struct
+import threading
+import time
+from collections import deque
+from itertools import islice
+from typing import Any, Deque, Dict, List, Optional, Tuple
+from loguru import logger
+
+from .cloud_connectors import NetworkClient
+from .delivery_stats import delivery_stats
+
+# APCI
+START_BYTE = 0x68
+U_STARTDT_ACT, U_STARTDT_CON = 0x07, 0x0B
+U_STOPDT_ACT, U_STOPDT_CON = 0x13, 0x23
+U_TESTFR_ACT, U_TESTFR_CON = 0x43, 0x83
+
+# ASDU类型标识
+M_SP_NA_1 = 1    # 单点遥信
+M_ME_NC_1 = 13   # 短浮点遥测
+C_IC_NA_1 = 100  # 总召唤
+C_CS_NA_1 = 103  # 时钟同步
+
+# 传送原因
+COT_SPONTANEOUS = 3
+COT_ACTIVATION = 6
+COT_ACTCON = 7
+COT_ACTTERM = 10
+COT_INTERROGATED = 20
+COT_UNKNOWN_TYPE = 44
+
+# 单个ASDU最多携带的信息对象数（保证APDU不超过253字节）
+MAX_OBJECTS_PER_ASDU = 20
+
+
+def encode_asdu(type_id: int, cot: int, common_address: int,
+                objects: List[Tuple[int, bytes]], negative: bool = False) -> bytes:
+    """编码ASDU（非顺序信息对象，2字节传送原因和公共地址）"""
+    cause = cot | (0x40 if negative else 0)
+    header = struct.pack('<BBBBH', type_id, len(objects) & 0x7F, cause, 0, common_address)
+    body = b''.join(struct.pack('<I', ioa)[:3] + element for ioa, element in objects)
+    return header + body
+
+
+def encode_element(point_type: str, value: Any) -> bytes:
+    """编码信息元素，非法值置无效品质位"""
+    try:
+        number = float(value)
+        quality = 0x00
+    except (TypeError, ValueError):
+        number = 0.0
+        quality = 0x80
+
+    if point_type == 'single':
+        return struct.pack('<B', (1 if number else 0) | quality)
+    return struct.pack('<fB', number, quality)
+
+
+class Iec104UplinkClient(NetworkClient):
+    """IEC 104 上行连接器
+
+    配置示例::
+
+        type: "iec104_uplink"
+        host: "10.0.0.10"
+        port: 2404
+        common_address: 1
+        points:
+          - {source: "inst", device: "1", data_type: "M", point: "1", ioa: 16385, type: "float"}
+          - {source: "inst", device: "1", data_type: "M", point: "5", ioa: 1, type: "single"}
+    """
+
+    def __init__(self, name: str, config: Dict[str, Any]):
+        super().__init__(name, config)
+        self.host = config.get('host', '')
+        self.port = int(config.get('port', 2404))
+        self.common_address = int(config.get('common_address', 1))
+        self.t1 = float(config.get('t1', 15))
+        self.t2 = float(config.get('t2', 10))
+        self.t3 = float(config.get('t3', 20))
+        self.k = int(config.get('k', 12))
+        self.w = int(config.get('w', 8))
+        self.reconnect_delay = float(config.get('reconnect_delay', 5))
+        self.deadband = float(config.get('deadband', 0))
+
+        # (source, device, data_type, point) -> 映射配置
+        self.point_map: Dict[Tuple[str, str, str, str], Dict[str, Any]] = {}
+        for point in config.get('points', []) or []:
+            key = (point['source'], str(point['device']), point.get('data_type', 'M'),
+                   str(point['point']))
+            self.point_map[key] = {'ioa': int(point['ioa']), 'type': point.get('type', 'float')}
+
+        self.values: Dict[int, Tuple[str, Any]] = {}  # ioa -> (type, value)
+        self.values_lock = threading.Lock()
+        # 待自发上送的变化值，每个信息对象只保留最新值: ioa -> (类型标识, 信息元素, 入队时间)
+        self.outbox: Dict[int, Tuple[int, bytes, float]] = {}
+        self.outbox_lock = threading.Lock()
+        # 本次会话待发送的应答（总召唤、对时确认等），优先于自发数据，同样受k窗口限制
+        self.replies: Deque[bytes] = deque()
+        delivery_stats.register_backlog(name, self._backlog)
+
+        self.sock: Optional[socket.socket] = None
+        self.running = False
+        self.thread: Optional[threading.Thread] = None
+        self._reset_session()
+
+    def _reset_session(self):
+        self.data_transfer = False
+        self.send_seq = 0
+        self.recv_seq = 0
+        self.unacked = 0
+        self.unconfirmed_recv = 0
+        self.last_recv_ack_time = 0.0
+        self.last_activity = time.monotonic()
+        self.testfr_sent_at: Optional[float] = None
+        # 新会话由主站总召唤取得全部当前值，上个会话积压的变化和应答不再发送
+        self.replies.clear()
+        with self.outbox_lock:
+            self.outbox.clear()
+
+    # ---------- NetworkClient 接口 ----------
+
+    def connect(self) -> bool:
+        if not self.host or not self.point_map:
+            logger.error(f"[{self.name}] 104上行配置不完整（host/points）")
+            return False
+        self.running = True
+        self.thread = threading.Thread(target=self._run, name=f"iec104-{self.name}", daemon=True)
+        self.thread.start()
+        return True
+
+    def disconnect(self):
+        self.running = False
+        self._close_socket()
+        if self.thread and self.thread.is_alive():
+            self.thread.join(timeout=5)
+
+    def telemetry_topic(self) -> str:
+        return f"iec104://{self.host}:{self.port}"
+
+    def publish_telemetry(self, message: Dict[str, Any]) -> bool:
+        """将点位报文映射为信息对象，变化量自发上送"""
+        changed: Dict[str, List[Tuple[int, bytes]]] = {}
+
+        for prop in message.get('property', []):
+            values = prop.get('value')
+            if not isinstance(values, dict):
+                continue
+            for point, value in values.items():
+                mapping = self.point_map.get((prop['source'], str(prop['device']),
+                                              prop['data_type'], str(point)))
+                if mapping and self._update_value(mapping['ioa'], mapping['type'], value):
+                    changed.setdefault(mapping['type'], []).append(
+                        (mapping['ioa'], encode_element(mapping['type'], value)))
+
+        for point_type, objects in changed.items():
+            self._queue_objects(self._type_id(point_type), objects)
+        return True
+
+    def report_properties(self, properties: Dict[str, Any]) -> bool:
+        return True
+
+    def get_status(self) -> Dict[str, Any]:
+        return {
+            **super().get_status(),
+            "host": self.host,
+            "port": self.port,
+            "data_transfer": self.data_transfer,
+            "mapped_points": len(self.point_map),
+            "pending": self._backlog(),
+        }
+
+    # ---------- 点位缓存 ----------
+
+    def _update_value(self, ioa: int, point_type: str, value: Any) -> bool:
+        """更新缓存值，超过死区或首次出现时返回True"""
+        with self.values_lock:
+            previous = self.values.get(ioa)
+            self.values[ioa] = (point_type, value)
+        if previous is None:
+            return True
+        try:
+            return abs(float(value) - float(previous[1])) > self.deadband
+        except (TypeError, ValueError):
+            return value != previous[1]
+
+    def _type_id(self, point_type: str) -> int:
+        return M_SP_NA_1 if point_type == 'single' else M_ME_NC_1
+
+    def _queue_objects(self, type_id: int, objects: List[Tuple[int, bytes]]):
+        """变化值入队，同一信息对象未发出的旧值被新值替换"""
+        queued_at = time.time()
+        with self.outbox_lock:
+            for ioa, element in objects:
+                self.outbox[ioa] = (type_id, element, queued_at)
+
+    def _take_batch(self) -> Optional[Tuple[int, List[Tuple[int, bytes]], float]]:
+        """按入队顺序取出同一类型的最多 MAX_OBJECTS_PER_ASDU 个信息对象"""
+        with self.outbox_lock:
+            if not self.outbox:
+                return None
+            type_id = next(iter(self.outbox.values()))[0]
+            ioas = list(islice((ioa for ioa, entry in self.outbox.items() if entry[0] == type_id),
+                               MAX_OBJECTS_PER_ASDU))
+            entries = [(ioa, self.outbox.pop(ioa)) for ioa in ioas]
+        objects = [(ioa, element) for ioa, (_, element, _) in entries]
+        return type_id, objects, min(queued_at for _, (_, _, queued_at) in entries)
+
+    def _backlog(self) -> int:
+        with self.outbox_lock:
+            return len(self.outbox)
+
+    # ---------- 连接与报文处理（运行在独立线程） ----------
+
+    def _run(self):
+        while self.running:
+            try:
+                self.sock = socket.create_connection((self.host, self.port), timeout=self.t1)
+                self.sock.settimeout(0.2)
+                self._reset_session()
+                self.is_connected = True
+                logger.info(f"[{self.name}] 已连接104主站 {self.host}:{self.port}")
+                self._session_loop()
+            except Exception as e:
+                if self.running:
+                    logger.warning(f"[{self.name}] 104连接异常: {e}")
+            finally:
+                self.is_connected = False
+                self.data_transfer = False
+                self._close_socket()
+
+            if self.running:
+                time.sleep(self.reconnect_delay)
+
+    def _session_loop(self):
+        buffer = b''
+        while self.running:
+            try:
+                chunk = self.sock.recv(1024)
+                if not chunk:
+                    raise ConnectionError("主站关闭连接")
+                buffer += chunk
+                self.last_activity = time.monotonic()
+            except socket.timeout:
+                pass
+
+            while len(buffer) >= 2:
+                if buffer[0] != START_BYTE:
+                    raise ConnectionError("非法起始字节")
+                length = buffer[1]
+                if len(buffer) < length + 2:
+                    break
+                self._handle_apdu(buffer[2:length + 2])
+                buffer = buffer[length + 2:]
+
+            self._check_timers()
+            self._send_pending()
+
+    def _check_timers(self):
+        now = time.monotonic()
+
+        # t2: 接收的I帧超时未确认时发送S帧
+        if self.unconfirmed_recv and now - self.last_recv_ack_time >= self.t2:
+            self._send_s_frame()
+
+        # t3: 空闲时发送测试帧，t1内无确认则断开重连
+        if self.testfr_sent_at is not None and now - self.testfr_sent_at > self.t1:
+            raise ConnectionError("TESTFR确认超时")
+        if self.testfr_sent_at is None and now - self.last_activity > self.t3:
+            self._send_u_frame(U_TESTFR_ACT)
+            self.testfr_sent_at = now
+
+    def _handle_apdu(self, apci_asdu: bytes):
+        control = apci_asdu[:4]
+
+        if control[0] & 0x03 == 0x03:  # U帧
+            function = control[0]
+            if function == U_STARTDT_ACT:
+                self._send_u_frame(U_STARTDT_CON)
+                self.data_transfer = True
+                logger.info(f"[{self.name}] 主站已启动数据传输(STARTDT)")
+            elif function == U_STOPDT_ACT:
+                self._send_u_frame(U_STOPDT_CON)
+                self.data_transfer = False
+            elif function == U_TESTFR_ACT:
+                self._send_u_frame(U_TESTFR_CON)
+            elif function == U_TESTFR_CON:
+                self.testfr_sent_at = None
+            return
+
+        # I帧和S帧都携带接收序号，用于确认已发送的I帧
+        ack_seq = struct.unpack('<H', control[2:4])[0] >> 1
+        self.unacked = (self.send_seq - ack_seq) % 32768
+
+        if control[0] & 0x01 == 0x01:  # S帧
+            return
+
+        self.recv_seq = (self.recv_seq + 1) % 32768
+        self.unconfirmed_recv += 1
+        if self.unconfirmed_recv == 1:
+            self.last_recv_ack_time = time.monotonic()
+        if self.unconfirmed_recv >= self.w:
+            self._send_s_frame()
+
+        self._handle_asdu(apci_asdu[4:])
+
+    def _handle_asdu(self, asdu: bytes):
+        if len(asdu) < 6:
+            return
+        type_id, _, cause, _, _ = struct.unpack('<BBBBH', asdu[:6])
+        # 命令类ASDU只有一个信息对象，回复时原样带回
+        ioa_qualifier = []
+        if len(asdu) >= 9:
+            ioa = struct.unpack('<I', asdu[6:9] + b'\x00')[0]
+            ioa_qualifier = [(ioa, asdu[9:])]
+
+        if type_id == C_IC_NA_1 and cause & 0x3F == COT_ACTIVATION:
+            self.replies.append(encode_asdu(C_IC_NA_1, COT_ACTCON, self.common_address, ioa_qualifier))
+            self._queue_interrogation_response()
+            self.replies.append(encode_asdu(C_IC_NA_1, COT_ACTTERM, self.common_address, ioa_qualifier))
+        elif type_id == C_CS_NA_1 and cause & 0x3F == COT_ACTIVATION:
+            # 网关时钟由系统NTP维护，仅确认
+            self.replies.append(encode_asdu(C_CS_NA_1, COT_ACTCON, self.common_address, ioa_qualifier))
+        else:
+            logger.debug(f"[{self.name}] 不支持的ASDU类型: {type_id}")
+            self.replies.append(encode_asdu(type_id, COT_UNKNOWN_TYPE, self.common_address,
+                                            ioa_qualifier, negative=True))
+
+    def _queue_interrogation_response(self):
+        with self.values_lock:
+            snapshot = dict(self.values)
+
+        grouped: Dict[str, List[Tuple[int, bytes]]] = {}
+        for ioa, (point_type, value) in sorted(snapshot.items()):
+            grouped.setdefault(point_type, []).append((ioa, encode_element(point_type, value)))
+
+        for point_type, objects in grouped.items():
+            for i in range(0, len(objects), MAX_OBJECTS_PER_ASDU):
+                self.replies.append(encode_asdu(self._type_id(point_type), COT_INTERROGATED,
+                                                self.common_address,
+                                                objects[i:i + MAX_OBJECTS_PER_ASDU]))
+
+    def _send_pending(self):
+        # 未启动数据传输或达到k值窗口时暂缓发送，应答先于自发数据
+        while self.data_transfer and self.unacked < self.k and self.replies:
+            self._send_asdu(self.replies.popleft())
+        while self.data_transfer and self.unacked < self.k:
+            batch = self._take_batch()
+            if batch is None:
+                return
+            type_id, objects, queued_at = batch
+            try:
+                self._send_asdu(encode_asdu(type_id, COT_SPONTANEOUS, self.common_address, objects))
+            except OSError:
+                delivery_stats.record_failed(self.name)
+                raise
+            delivery_stats.record_sent(self.name, queued_at)
+
+    def _send_asdu(self, asdu: bytes):
+        control = struct.pack('<HH', self.send_seq << 1, self.recv_seq << 1)
+        self._send_raw(control + asdu)
+        self.send_seq = (self.send_seq + 1) % 32768
+        self.unacked += 1
+        self.unconfirmed_recv = 0
+
+    def _send_s_frame(self):
+        self._send_raw(struct.pack('<BBH', 0x01, 0x00, self.recv_seq << 1))
+        self.unconfirmed_recv = 0
+
+    def _send_u_frame(self, function: int):
+        self._send_raw(bytes([function, 0, 0, 0]))
+
+    def _send_raw(self, apdu: bytes):
+        self.sock.sendall(bytes([START_BYTE, len(apdu)]) + apdu)
+
+    def _close_socket(self):
+        if self.sock:
+            try:
+                self.sock.close()
+            except OSError:
+                pass
+            self.sock = None
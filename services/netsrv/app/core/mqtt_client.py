@@ -12,10 +12,14 @@ from loguru import logger
 import paho.mqtt.client as mqtt
 from .config import settings
 from .config_loader import config_loader
+from .delivery_stats import delivery_stats
 
 class MQTTClient:
     """MQTT客户端管理器"""
     
+    # 投递统计中的网络名称
+    NETWORK_NAME = "mqtt"
+    
     def __init__(self):
         self.client: Optional[mqtt.Client] = None
         self.is_connected = False
@@ -315,6 +319,7 @@ class MQTTClient:
                 result = self.client.publish(topic, payload_str, qos, retain)
                 if result.rc == mqtt.MQTT_ERR_SUCCESS:
                     logger.debug(f"发布消息成功: {topic}")
+                    delivery_stats.record_sent(self.NETWORK_NAME, mid=result.mid if qos > 0 else None)
                     
                     # 立即强制发送消息（不等待确认）
                     try:
@@ -336,6 +341,7 @@ class MQTTClient:
                     return True
                 else:
                     logger.error(f"发布消息失败: {topic}, 错误码: {result.rc}")
+                    delivery_stats.record_failed(self.NETWORK_NAME)
                     
                     # 检查是否为连接相关错误，如果是则触发重连
                     if self._is_connection_error(result.rc):
@@ -345,9 +351,11 @@ class MQTTClient:
                     return False
             else:
                 logger.warning(f"MQTT客户端未连接，无法发布消息: {topic}")
+                delivery_stats.record_failed(self.NETWORK_NAME)
                 return False
         except Exception as e:
             logger.error(f"发布消息异常: {topic}, {e}")
+            delivery_stats.record_failed(self.NETWORK_NAME)
             return False
     
     def _is_connection_error(self, error_code: int) -> bool:
@@ -784,6 +792,7 @@ class MQTTClient:
     def _on_publish(self, client, userdata, mid):
         """消息发布回调"""
         logger.debug(f"消息发布完成，消息ID: {mid}")
+        delivery_stats.record_acked(self.NETWORK_NAME, mid)
     
     def _topic_match(self, pattern: str, topic: str) -> bool:
         """主题匹配检查"""
@@ -10,6 +10,13 @@ from loguru import logger
 from app.core.mqtt_client import mqtt_client
 from app.core.device_identity import device_identity
 from app.core.config_loader import config_loader
+from app.core.database import redis_manager
+from app.services.data_transformer import data_transformer
+
+try:
+    from voltage_common import templating
+except ImportError:  # 单独构建的服务镜像不包含公共模块
+    templating = None
 
 class AlarmBroadcaster:
     """告警广播服务"""
@@ -17,7 +24,9 @@ class AlarmBroadcaster:
     def __init__(self):
         self.alarm_topic_template = None
         self.formatted_alarm_topic = None
+        self.payload_template = None
         self._setup_topic()
+        self._setup_payload_template()
         
     def _setup_topic(self):
         """设置告警主题"""
@@ -39,6 +48,51 @@ class AlarmBroadcaster:
             logger.error(f"设置告警主题失败: {e}")
             return False
     
+    def _setup_payload_template(self):
+        """加载告警消息模板（alarm_payload），未启用时原样转发告警数据"""
+        if not config_loader.get_config('alarm_payload.enabled', False):
+            return
+        template = config_loader.get_config('alarm_payload.template')
+        if not isinstance(template, dict) or not template:
+            logger.error("告警消息模板配置缺失或不是对象，按原始数据转发")
+            return
+        if templating is None:
+            logger.warning("未找到公共模块 voltage_common，告警消息模板已禁用")
+            return
+        try:
+            templating.validate(template)
+        except templating.TemplateError as e:
+            logger.error(f"告警消息模板无效，按原始数据转发: {e}")
+            return
+        self.payload_template = template
+        logger.info("告警消息模板已启用")
+
+    def _build_template_context(self, alarm_data: Dict[str, Any]) -> Dict[str, Any]:
+        """构建模板上下文：告警字段 + 设备信息，实例名称和点位值按需从Redis读取"""
+        data = alarm_data.get('data') if isinstance(alarm_data.get('data'), dict) else {}
+
+        def instance():
+            client = redis_manager.get_client()
+            instance_id = data.get('channel_id')
+            name = None
+            if client and data.get('service_type') == 'modsrv' and instance_id is not None:
+                name = client.get(f"inst:{instance_id}:name")
+            return {'id': instance_id, 'name': name}
+
+        def points():
+            client = redis_manager.get_client()
+            if not client or not data.get('service_type'):
+                return {}
+            return client.hgetall(f"{data['service_type']}:{data.get('channel_id')}:{data.get('data_type')}")
+
+        return {
+            **alarm_data,
+            'alarm': alarm_data,
+            'device': device_identity.get_device_info(),
+            'instance': instance,
+            'points': points,
+        }
+
     def broadcast_alarm(self, alarm_data: Dict[str, Any]) -> bool:
         """
         广播告警消息
@@ -58,18 +112,26 @@ class AlarmBroadcaster:
                 logger.error(f"告警数据格式错误，必须是字典格式: {type(alarm_data)}")
                 return False
             
+            # 配置了消息模板时按模板渲染，否则直接转发原始数据
+            payload = alarm_data
+            if self.payload_template is not None:
+                payload = templating.render_payload(
+                    self.payload_template, self._build_template_context(alarm_data)
+                )
+            # 敏感字段脱敏（masking）
+            payload = data_transformer.mask_payload(payload)
+
             # 发布告警消息（使用QoS 1确保消息传输）
-            # 注意：直接转发原始数据，不做任何修改
             success = mqtt_client.publish(
                 topic=self.formatted_alarm_topic,
-                payload=alarm_data,
+                payload=payload,
                 qos=1,
                 retain=False  # 告警消息通常不需要保留
             )
             
             if success:
                 logger.info(f"告警消息发送成功: {self.formatted_alarm_topic}")
-                logger.debug(f"告警数据: {json.dumps(alarm_data, ensure_ascii=False, indent=2)}")
+                logger.debug(f"告警数据: {json.dumps(payload, ensure_ascii=False, indent=2)}")
                 return True
             else:
                 logger.error(f"告警消息发送失败: {self.formatted_alarm_topic}")
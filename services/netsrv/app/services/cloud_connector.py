@@ -0,0 +1,148 @@
+"""
+托管云平台连接器服务模块
+按配置创建 Azure IoT Hub / AWS IoT Core / IEC 104 上行连接器，转发点位数据，
+并将设备孪生/影子的期望属性映射为调节(A)写入
+"""
+
+import asyncio
+from typing import Any, Callable, Dict, List, Optional
+from loguru import logger
+from app.core.cloud_connectors import NETWORK_CLIENT_TYPES, NetworkClient
+from app.core.config_loader import config_loader
+from app.core.delivery_stats import delivery_stats
+from app.core.iec104_uplink import Iec104UplinkClient
+from app.services.data_transformer import data_transformer
+
+# 104上行连接器不走MQTT，单独注册
+CONNECTOR_TYPES = {
+    **NETWORK_CLIENT_TYPES,
+    "iec104_uplink": Iec104UplinkClient,
+}
+
+# 首次连接失败后的重试间隔（秒），按倍数退避至上限
+CONNECT_RETRY_INITIAL = 5
+CONNECT_RETRY_MAX = 300
+
+
+class CloudConnectorManager:
+    """托管云平台连接器管理器"""
+
+    def __init__(self):
+        self.connectors: Dict[str, NetworkClient] = {}
+        self.loop: Optional[asyncio.AbstractEventLoop] = None
+        self.retry_tasks: Dict[str, asyncio.Task] = {}
+
+    def start(self) -> int:
+        """创建并连接所有启用的连接器，返回启动数量"""
+        self.loop = asyncio.get_running_loop()
+
+        for name, config in config_loader.get_cloud_connectors_config().items():
+            if not config.get('enabled', False):
+                continue
+
+            client_class = CONNECTOR_TYPES.get(config.get('type'))
+            if client_class is None:
+                logger.error(f"未知的云平台连接器类型: {config.get('type')} ({name})")
+                continue
+
+            connector = client_class(name, config)
+            connector.desired_handler = self._handle_desired
+            if connector.connect():
+                self.connectors[name] = connector
+            else:
+                logger.error(f"云平台连接器启动失败，{CONNECT_RETRY_INITIAL}秒后重试: {name}")
+                self.retry_tasks[name] = asyncio.create_task(self._retry_connect(name, connector))
+
+        if self.connectors:
+            logger.info(f"云平台连接器已启动: {list(self.connectors.keys())}")
+        return len(self.connectors)
+
+    async def _retry_connect(self, name: str, connector: NetworkClient):
+        """后台按指数退避重试首次连接（注册/连接可能阻塞，在线程池中执行）"""
+        delay = CONNECT_RETRY_INITIAL
+        try:
+            while True:
+                await asyncio.sleep(delay)
+                if await self.loop.run_in_executor(None, connector.connect):
+                    self.connectors[name] = connector
+                    logger.info(f"云平台连接器重试启动成功: {name}")
+                    return
+                delay = min(delay * 2, CONNECT_RETRY_MAX)
+                logger.warning(f"云平台连接器启动失败，{delay}秒后重试: {name}")
+        finally:
+            self.retry_tasks.pop(name, None)
+
+    def stop(self):
+        """断开所有连接器"""
+        for task in list(self.retry_tasks.values()):
+            task.cancel()
+        self.retry_tasks.clear()
+        for connector in self.connectors.values():
+            connector.disconnect()
+        self.connectors.clear()
+
+    def has_connected(self) -> bool:
+        """是否有已连接的连接器"""
+        return any(c.is_connected for c in self.connectors.values())
+
+    def forward(self, data: List[Dict], build_message: Callable[[List[Dict]], Optional[Dict]]):
+        """按各连接器的转换规则格式化并发布遥测数据"""
+        for name, connector in self.connectors.items():
+            if not connector.is_connected:
+                delivery_stats.record_failed(name)
+                continue
+            try:
+                message = build_message(data_transformer.apply(name, data))
+                if message and not connector.publish_telemetry(message):
+                    logger.warning(f"[{name}] 遥测数据发送失败")
+            except Exception as e:
+                logger.error(f"[{name}] 转发数据异常: {e}")
+                delivery_stats.record_failed(name)
+
+    def record_skipped(self):
+        """所有网络均未连接、本周期未转发时，为每个连接器记一次失败"""
+        for name in self.connectors:
+            delivery_stats.record_failed(name)
+
+    def _handle_desired(self, name: str, properties: Dict[str, Any]):
+        """期望属性回调（运行在MQTT线程），切换到事件循环执行写入"""
+        if self.loop is None:
+            return
+        asyncio.run_coroutine_threadsafe(self._apply_desired(name, properties), self.loop)
+
+    async def _apply_desired(self, name: str, properties: Dict[str, Any]):
+        """将期望属性写入映射的调节点位，并上报写入成功的属性"""
+        from app.services.point_writer import point_writer
+
+        connector = self.connectors.get(name)
+        mapping = (connector.config.get('desired_properties', {}) or {}) if connector else {}
+        reported = {}
+
+        for prop, value in properties.items():
+            target = mapping.get(prop)
+            if not target:
+                logger.debug(f"[{name}] 期望属性未配置映射，忽略: {prop}")
+                continue
+
+            success, reason = await point_writer.authorized_write(
+                name, target['source'], str(target['device']), target.get('data_type', 'A'),
+                str(target['point']), value
+            )
+            if success:
+                reported[prop] = value
+                logger.info(f"[{name}] 期望属性写入成功: {prop} = {value}")
+            elif reason != "ok":
+                logger.warning(f"[{name}] 期望属性未通过授权: {prop} = {value} ({reason})")
+            else:
+                logger.error(f"[{name}] 期望属性写入失败: {prop} = {value}")
+
+        if reported and connector:
+            connector.report_properties(reported)
+
+    def get_status(self) -> List[Dict[str, Any]]:
+        """获取所有连接器状态"""
+        return [connector.get_status() for connector in self.connectors.values()]
+
+
+# 全局云平台连接器管理器实例
+cloud_connector_manager = CloudConnectorManager()
@@ -0,0 +1,149 @@
+"""
+远程命令授权模块
+云端下发的写入命令（MQTT单点写入 / 设备孪生期望属性）在写入前
+依次经过点位白名单、数值范围、频率限制检查，并记录审计日志
+"""
+
+import fnmatch
+import json
+import threading
+import time
+from collections import deque
+from pathlib import Path
+from typing import Any, Deque, Dict, List, Optional, Tuple
+from loguru import logger
+from app.core.config_loader import config_loader
+
+
+class CommandAuthorizer:
+    """远程命令授权器"""
+
+    def __init__(self):
+        self.lock = threading.Lock()
+        # 来源 -> 最近命令时间戳（滑动窗口限流）
+        self.origin_history: Dict[str, Deque[float]] = {}
+        # 点位 -> 最近一次放行时间（单点最小间隔）
+        self.point_last_time: Dict[str, float] = {}
+        self.recent_audit: Deque[Dict[str, Any]] = deque(maxlen=200)
+
+    def get_config(self) -> Dict[str, Any]:
+        return config_loader.get_command_authorization_config()
+
+    def authorize(self, origin: str, source: str, device: str, data_type: str,
+                  point: str, value: Any) -> Tuple[bool, str]:
+        """检查命令是否允许执行，返回 (是否放行, 原因)"""
+        config = self.get_config()
+        if not config.get('enabled', False):
+            return True, "authorization_disabled"
+
+        rule = self._match_rule(config.get('rules', []) or [], source, device, data_type, point)
+        if rule is None:
+            return False, "point_not_allowed"
+
+        allowed, reason = self._check_value(rule, value)
+        if not allowed:
+            return False, reason
+
+        return self._check_rate(config.get('rate_limit', {}) or {}, rule, origin,
+                                f"{source}:{device}:{data_type}:{point}")
+
+    def _match_rule(self, rules: List[Dict[str, Any]], source: str, device: str,
+                    data_type: str, point: str) -> Optional[Dict[str, Any]]:
+        """返回第一条匹配的白名单规则，未匹配表示拒绝"""
+        for rule in rules:
+            if not fnmatch.fnmatch(source, str(rule.get('source', '*'))):
+                continue
+            if not fnmatch.fnmatch(device, str(rule.get('device', '*'))):
+                continue
+            if data_type not in rule.get('data_types', ['A']):
+                continue
+            points = rule.get('points', ['*'])
+            if any(fnmatch.fnmatch(point, str(p)) for p in points):
+                return rule
+        return None
+
+    def _check_value(self, rule: Dict[str, Any], value: Any) -> Tuple[bool, str]:
+        """检查数值范围，配置了min/max时要求值为数值"""
+        minimum, maximum = rule.get('min'), rule.get('max')
+        allowed_values = rule.get('values')
+
+        if allowed_values is not None and value not in allowed_values and str(value) not in [
+                str(v) for v in allowed_values]:
+            return False, "value_not_allowed"
+
+        if minimum is None and maximum is None:
+            return True, "ok"
+
+        try:
+            number = float(value)
+        except (TypeError, ValueError):
+            return False, "value_not_numeric"
+
+        if minimum is not None and number < float(minimum):
+            return False, "value_below_min"
+        if maximum is not None and number > float(maximum):
+            return False, "value_above_max"
+        return True, "ok"
+
+    def _check_rate(self, rate_limit: Dict[str, Any], rule: Dict[str, Any], origin: str,
+                    point_id: str) -> Tuple[bool, str]:
+        """按来源滑动窗口和单点最小间隔限流，放行时记录本次时间"""
+        now = time.monotonic()
+        max_commands = int(rate_limit.get('max_commands', 0))
+        window = float(rate_limit.get('window_seconds', 60))
+        min_interval = float(rule.get('min_interval', rate_limit.get('min_interval', 0)))
+
+        with self.lock:
+            history = self.origin_history.setdefault(origin, deque())
+            while history and now - history[0] > window:
+                history.popleft()
+            if max_commands and len(history) >= max_commands:
+                return False, "rate_limited"
+
+            last = self.point_last_time.get(point_id)
+            if min_interval and last is not None and now - last < min_interval:
+                return False, "point_rate_limited"
+
+            history.append(now)
+            self.point_last_time[point_id] = now
+        return True, "ok"
+
+    def audit(self, origin: str, source: str, device: str, data_type: str, point: str,
+              value: Any, allowed: bool, reason: str, result: Optional[bool] = None,
+              msg_id: Optional[str] = None):
+        """记录审计日志（内存最近记录 + JSON Lines文件）"""
+        entry = {
+            "timestamp": int(time.time() * 1000),
+            "origin": origin,
+            "msg_id": msg_id,
+            "target": f"{source}:{device}:{data_type}:{point}",
+            "value": value,
+            "allowed": allowed,
+            "reason": reason,
+            "result": None if result is None else ("success" if result else "fail"),
+        }
+        self.recent_audit.append(entry)
+
+        if allowed:
+            logger.info(f"远程命令已授权: {entry}")
+        else:
+            logger.warning(f"远程命令被拒绝: {entry}")
+
+        audit_file = (self.get_config().get('audit', {}) or {}).get('file')
+        if not audit_file:
+            return
+        try:
+            path = Path(audit_file)
+            path.parent.mkdir(parents=True, exist_ok=True)
+            with self.lock, path.open('a', encoding='utf-8') as f:
+                f.write(json.dumps(entry, ensure_ascii=False, default=str) + "\n")
+        except Exception as e:
+            logger.error(f"写入命令审计日志失败: {e}")
+
+    def get_recent_audit(self, limit: int = 50) -> List[Dict[str, Any]]:
+        """获取最近的审计记录（新记录在前）"""
+        return list(self.recent_audit)[-limit:][::-1]
+
+
+# 全局远程命令授权器实例
+command_authorizer = CommandAuthorizer()
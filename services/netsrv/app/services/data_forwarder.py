@@ -11,13 +11,19 @@ from collections import defaultdict
 from loguru import logger
 from app.core.database import redis_manager
 from app.core.mqtt_client import mqtt_client
+from app.core.delivery_stats import delivery_stats
 from app.core.config_loader import config_loader
 from app.core.device_identity import device_identity
 from app.services.system_monitor import system_monitor
+from app.services.data_transformer import data_transformer
+from app.services.cloud_connector import cloud_connector_manager
 
 class DataForwarder:
     """数据转发服务"""
     
+    # 转换规则所属的网络名称（transforms.<network>）
+    NETWORK_NAME = "mqtt"
+    
     def __init__(self):
         self.is_running = False
         self.forward_task: Optional[asyncio.Task] = None
@@ -34,6 +40,9 @@ class DataForwarder:
         self.last_send_time = 0  # 上次发送时间
         self.send_interval = 1.0 / self.max_messages_per_second  # 发送间隔
         
+        # 限速队列即主MQTT网络的缓冲积压
+        delivery_stats.register_backlog(self.NETWORK_NAME, lambda: len(self.message_queue))
+        
     async def start(self):
         """启动数据转发服务"""
         if self.is_running:
@@ -53,20 +62,23 @@ class DataForwarder:
         self.forward_task = asyncio.create_task(self._forward_loop())
         logger.info("数据转发服务启动成功")
     
-    async def _rate_limited_send(self, topic: str, payload: str, qos: int = 0):
-        """限速发送消息，适应AWS IoT Core"""
+    async def _rate_limited_send(self, topic: str, payload: str, qos: int = 0,
+                                 origin: Optional[float] = None):
+        """限速发送消息，适应AWS IoT Core（origin为数据采集时间，用于统计端到端延迟）"""
         try:
             current_time = time.time()
+            if origin is None:
+                origin = current_time
             
             # 检查发送间隔
             if current_time - self.last_send_time < self.send_interval:
                 # 添加到队列，稍后发送
-                self.message_queue.append((topic, payload, qos))
+                self.message_queue.append((topic, payload, qos, origin))
                 logger.debug(f"消息加入队列（限速），队列长度: {len(self.message_queue)}")
                 # 不要直接 return，继续处理队列
             else:
                 # 直接发送
-                await self._send_message(topic, payload, qos)
+                await self._send_message(topic, payload, qos, origin)
                 self.last_send_time = current_time
             
             # 处理队列中的消息（无论是否直接发送都要处理队列）
@@ -92,13 +104,13 @@ class DataForwarder:
             # 发送队列中的消息
             sent_count = 0
             while self.message_queue and sent_count < max_send_count:
-                topic, payload, qos = self.message_queue.pop(0)
+                topic, payload, qos, origin = self.message_queue.pop(0)
                 
                 # 控制发送间隔
                 if sent_count > 0:
                     await asyncio.sleep(self.send_interval)
                 
-                await self._send_message(topic, payload, qos)
+                await self._send_message(topic, payload, qos, origin)
                 sent_count += 1
             
             if sent_count > 0:
@@ -108,11 +120,13 @@ class DataForwarder:
         except Exception as e:
             logger.error(f"处理消息队列失败: {e}")
     
-    async def _send_message(self, topic: str, payload: str, qos: int = 0):
+    async def _send_message(self, topic: str, payload: str, qos: int = 0,
+                            origin: Optional[float] = None):
         """发送单条消息"""
         try:
             if not mqtt_client.is_connected:
                 logger.debug(f"MQTT未连接，跳过消息发送: {topic}")
+                delivery_stats.record_failed(self.NETWORK_NAME)
                 return False
             
             # 使用MQTT客户端发送消息
@@ -120,13 +134,17 @@ class DataForwarder:
             
             if result.rc == 0:  # MQTT_ERR_SUCCESS
                 logger.debug(f"消息发送成功: {topic}")
+                # QoS1 在收到 PUBACK 后记录延迟
+                delivery_stats.record_sent(self.NETWORK_NAME, origin, result.mid if qos > 0 else None)
                 return True
             else:
                 logger.warning(f"消息发送失败: {topic}, 错误码: {result.rc}")
+                delivery_stats.record_failed(self.NETWORK_NAME)
                 return False
                 
         except Exception as e:
             logger.error(f"发送消息异常: {e}")
+            delivery_stats.record_failed(self.NETWORK_NAME)
             return False
     
     async def stop(self):
@@ -207,16 +225,33 @@ class DataForwarder:
         try:
             current_time = time.time()
             
+            cloud_connected = cloud_connector_manager.has_connected()
+            
             # 检查MQTT连接状态
-            if not mqtt_client.is_connected:
+            if not mqtt_client.is_connected and not cloud_connected:
                 logger.debug("MQTT未连接，跳过数据转发")
+                # 未连接时本周期数据未上送，按每个网络一次失败计入投递统计
+                delivery_stats.record_failed(self.NETWORK_NAME)
+                cloud_connector_manager.record_skipped()
                 return
             
             # 从Redis获取数据
             data = await self._fetch_data_from_redis()
+            
+            # 托管云平台连接器独立于主MQTT连接，各自应用转换规则
+            if data and cloud_connector_manager.connectors:
+                cloud_connector_manager.forward(data, self.build_property_message)
+            
+            if not mqtt_client.is_connected:
+                delivery_stats.record_failed(self.NETWORK_NAME)
+                return
+            
+            if data:
+                # 应用网络转换规则（筛选/重命名/换算/聚合）
+                data = data_transformer.apply(self.NETWORK_NAME, data)
             if data:
                 # 按数据类型分组并分别上送
-                await self._send_grouped_data(data)
+                await self._send_grouped_data(data, current_time)
             
             # 检查是否需要发送系统监控数据
             await self._check_and_send_system_monitor_data(current_time)
@@ -459,7 +494,7 @@ class DataForwarder:
         
         return converted_data
     
-    async def _send_grouped_data(self, data: List[Dict]):
+    async def _send_grouped_data(self, data: List[Dict], collected_at: Optional[float] = None):
         """按数据类型分组并分别上送数据"""
         try:
             # 按数据类型分组
@@ -480,17 +515,61 @@ class DataForwarder:
                     # 分割成多个批次
                     for i in range(0, len(group_items), batch_size):
                         batch_items = group_items[i:i + batch_size]
-                        await self._send_property_data(batch_items, group_key)
+                        await self._send_property_data(batch_items, group_key, collected_at)
                         logger.debug(f"分组 {group_key} 分割发送第 {i//batch_size + 1} 批，包含 {len(batch_items)} 个点位")
                 else:
                     # 直接发送整个分组
-                    await self._send_property_data(group_items, group_key)
+                    await self._send_property_data(group_items, group_key, collected_at)
                     logger.debug(f"分组 {group_key} 发送完成，包含 {len(group_items)} 个点位")
                     
         except Exception as e:
             logger.error(f"分组发送数据失败: {e}")
     
-    async def _send_property_data(self, data: List[Dict], group_key: str):
+    def build_property_message(self, data: List[Dict]) -> Optional[Dict[str, Any]]:
+        """将Redis数据格式化为点位上报报文，无数据时返回None"""
+        property_data = self._build_property_data(data)
+        if not property_data:
+            return None
+        return {
+            "timestamp": int(time.time()),
+            "property": property_data
+        }
+    
+    def _build_property_data(self, data: List[Dict]) -> List[Dict[str, Any]]:
+        """构建点位数据列表"""
+        property_data = []
+        
+        for item in data:
+            key_info = self._parse_key_format(item['key'])
+            
+            # 构建source和device字段
+            source = key_info['service']  # 服务名 (comsrv, modsrv)
+            device = key_info['channel'].replace(' ', '_')  # 通道ID，空格转换为下划线
+            
+            # 处理不同类型的值
+            if isinstance(item['value'], dict) and item['value'] and all(
+                    isinstance(v, dict) for v in item['value'].values()):
+                # 按实例聚合后的文档，逐个数据类型转换
+                converted_value = {data_type: self._convert_hash_values(values)
+                                   for data_type, values in item['value'].items()}
+            elif isinstance(item['value'], dict):
+                # 如果是hash类型，转换数字字符串为float
+                converted_value = self._convert_hash_values(item['value'])
+            else:
+                # 其他类型保持原值
+                converted_value = item['value']
+            
+            property_data.append({
+                "source": source,
+                "device": device,
+                "data_type": key_info['data_type'],
+                "value": converted_value
+            })
+        
+        return property_data
+    
+    async def _send_property_data(self, data: List[Dict], group_key: str,
+                                  collected_at: Optional[float] = None):
         """发送点位数据上报"""
         try:
             # 获取属性主题
@@ -498,51 +577,22 @@ class DataForwarder:
                 config_loader.get_config('mqtt_topics.property', 'property/{productSN}/{deviceSN}')
             )
             
-            # 构建点位数据
-            property_data = []
+            message = self.build_property_message(data)
             
-            for item in data:
-                key_info = self._parse_key_format(item['key'])
-                
-                # 构建source和device字段
-                source = key_info['service']  # 服务名 (comsrv, modsrv)
-                device = key_info['channel'].replace(' ', '_')  # 通道ID，空格转换为下划线
-                
-                # 处理不同类型的值
-                if isinstance(item['value'], dict):
-                    # 如果是hash类型，转换数字字符串为float
-                    converted_value = self._convert_hash_values(item['value'])
-                else:
-                    # 其他类型保持原值
-                    converted_value = item['value']
-                
-                point_data = {
-                    "source": source,
-                    "device": device,
-                    "data_type": key_info['data_type'],
-                    "value": converted_value
-                }
-                property_data.append(point_data)
-            
-            if property_data:
-                # 构建消息
-                current_timestamp = int(time.time())  # Unix时间戳
-                message = {
-                    "timestamp": current_timestamp,
-                    "property": property_data
-                }
-                
+            if message:
                 # 发送数据前检查MQTT连接状态
                 if not mqtt_client.is_connected:
                     logger.debug(f"MQTT未连接，跳过数据发送: {group_key}")
+                    delivery_stats.record_failed(self.NETWORK_NAME)
                     await self._handle_mqtt_failure(f"MQTT未连接，跳过数据发送: {group_key}")
                     return
                 
                 # 发送数据
-                await self._rate_limited_send(property_topic, json.dumps(message, ensure_ascii=False), qos=1)
+                await self._rate_limited_send(property_topic, json.dumps(message, ensure_ascii=False), qos=1,
+                                              origin=collected_at)
                 # 发送成功，重置失败计数器
                 self._reset_mqtt_failure_count()
-                logger.debug(f"点位数据上报成功: {property_topic}, 组: {group_key}, 数据量: {len(property_data)}")
+                logger.debug(f"点位数据上报成功: {property_topic}, 组: {group_key}, 数据量: {len(message['property'])}")
                 # 额外强制网络处理（在publish中已经处理了一次）
                 try:
                     for i in range(3):
@@ -0,0 +1,203 @@
+"""
+数据转换模块
+按网络配置对上报数据进行筛选、重命名、单位换算和按实例聚合，
+在格式化为MQTT报文之前执行；最后按全局 masking 配置对敏感字段脱敏
+"""
+
+import fnmatch
+from typing import Any, Dict, List, Optional
+from loguru import logger
+from app.core.config_loader import config_loader
+from app.core.database import redis_manager
+
+try:
+    from voltage_common.aliases import AliasResolver
+    from voltage_common.masking import Masker, MaskingError
+except ImportError:  # 单独构建的服务镜像不包含公共模块
+    AliasResolver = None
+    Masker = None
+
+
+class DataTransformer:
+    """按网络应用数据转换规则"""
+
+    def __init__(self):
+        # 按别名方案缓存解析器，多个网络共用
+        self._alias_resolvers: Dict[Optional[str], Any] = {}
+        self.masker = self._create_masker()
+
+    def _create_masker(self) -> Optional[Any]:
+        """按 masking 配置创建脱敏器，未启用返回None"""
+        config = config_loader.get_config('masking', {}) or {}
+        if not config.get('enabled', False):
+            return None
+        if Masker is None:
+            logger.error("未找到公共模块 voltage_common，无法对上报数据脱敏")
+            return None
+        try:
+            masker = Masker.from_config(config)
+        except MaskingError as e:
+            logger.error(f"脱敏配置无效: {e}")
+            return None
+        if masker is not None and not masker.salted:
+            logger.warning("脱敏未配置盐值，短编号等字段的哈希可被穷举还原")
+        return masker
+
+    def mask(self, data: List[Dict]) -> List[Dict]:
+        """对上报数据脱敏（所有网络、云平台连接器共用）"""
+        if self.masker is None or not data:
+            return data
+        return self.masker.mask_items(data)
+
+    def mask_payload(self, payload: Any) -> Any:
+        """对任意报文（如告警消息）按字段脱敏"""
+        if self.masker is None:
+            return payload
+        return self.masker.mask(payload)
+
+    def get_rules(self, network: str) -> Dict[str, Any]:
+        """获取指定网络的转换规则，未配置返回空字典"""
+        rules = config_loader.get_transform_config(network)
+        if not rules or not rules.get('enabled', True):
+            return {}
+        return rules
+
+    def apply(self, network: str, data: List[Dict]) -> List[Dict]:
+        """对一批数据应用指定网络的转换规则，转换失败时也会脱敏"""
+        rules = self.get_rules(network)
+        if not rules or not data:
+            return self.mask(data)
+
+        try:
+            result = []
+            for item in data:
+                transformed = self._transform_item(item, rules)
+                if transformed is not None:
+                    result.append(transformed)
+
+            aggregate = rules.get('aggregate', {}) or {}
+            if aggregate.get('by_instance', False):
+                result = self._aggregate_by_instance(result, aggregate)
+
+            return self.mask(result)
+
+        except Exception as e:
+            logger.error(f"网络 {network} 数据转换失败，使用原始数据: {e}")
+            return self.mask(data)
+
+    def _transform_item(self, item: Dict, rules: Dict[str, Any]) -> Optional[Dict]:
+        """对单个Redis键的数据执行筛选、换算和重命名"""
+        key = item['key']
+        select = rules.get('select', {}) or {}
+
+        # 键筛选：配置了keys时只转发匹配的键
+        key_patterns = select.get('keys', [])
+        if key_patterns and not any(fnmatch.fnmatch(key, p) for p in key_patterns):
+            return None
+
+        if not isinstance(item['value'], dict):
+            return item
+        value = dict(item['value'])
+
+        # 点位筛选：按键模式配置点位列表，未匹配的键保留全部点位
+        points = self._match_section(select.get('points', {}), key)
+        if points is not None:
+            value = {field: v for field, v in value.items()
+                     if any(fnmatch.fnmatch(field, str(p)) for p in points)}
+            if not value:
+                return None
+
+        # 单位换算在重命名之前，按原始点位ID匹配
+        for conversion in rules.get('convert', []) or []:
+            if not fnmatch.fnmatch(key, conversion.get('key', '*')):
+                continue
+            point = str(conversion.get('point', '*'))
+            for field in list(value.keys()):
+                if fnmatch.fnmatch(field, point):
+                    value[field] = self._convert_value(value[field], conversion)
+
+        # 点位别名：modsrv维护的稳定外部标识，点位重新编号后输出不变
+        resolver = self._alias_resolver(rules.get('aliases'))
+        aliases = resolver.mapping() if resolver is not None else {}
+
+        # 字段重命名（显式配置优先于别名）
+        renames = self._match_section(rules.get('rename', {}), key) or {}
+        if renames or aliases:
+            value = {str(renames.get(field) or aliases.get(f"{key}:{field}", field)): v
+                     for field, v in value.items()}
+
+        return {**item, 'value': value}
+
+    def _alias_resolver(self, config: Any) -> Optional[Any]:
+        """按 aliases 配置获取别名解析器，未启用返回None
+
+        aliases: true                      使用每个点位的默认别名
+        aliases: {enabled: true, scheme: kks, refresh: 60}
+        """
+        if isinstance(config, dict):
+            if not config.get('enabled', True):
+                return None
+            scheme = config.get('scheme')
+            ttl = float(config.get('refresh', 60))
+        elif config:
+            scheme, ttl = None, 60.0
+        else:
+            return None
+
+        if scheme not in self._alias_resolvers:
+            if AliasResolver is None:
+                logger.warning("未找到公共模块 voltage_common，点位别名已禁用")
+                self._alias_resolvers[scheme] = None
+            else:
+                self._alias_resolvers[scheme] = AliasResolver(
+                    redis_manager.get_client, scheme=scheme, ttl=ttl)
+        return self._alias_resolvers[scheme]
+
+    def _match_section(self, section: Dict[str, Any], key: str) -> Optional[Any]:
+        """返回第一个匹配键模式的配置项"""
+        for pattern, config in (section or {}).items():
+            if fnmatch.fnmatch(key, pattern):
+                return config
+        return None
+
+    def _convert_value(self, value: Any, conversion: Dict[str, Any]) -> Any:
+        """按 value * scale + offset 换算，非数值保持原值"""
+        try:
+            number = float(value)
+        except (ValueError, TypeError):
+            return value
+
+        converted = number * float(conversion.get('scale', 1)) + float(conversion.get('offset', 0))
+        decimals = conversion.get('decimals')
+        if decimals is not None:
+            converted = round(converted, int(decimals))
+        return converted
+
+    def _aggregate_by_instance(self, data: List[Dict], aggregate: Dict[str, Any]) -> List[Dict]:
+        """将同一实例的多个数据类型合并为一个文档
+
+        inst:1:M + inst:1:A -> inst:1:{data_type}，value为 {"M": {...}, "A": {...}}
+        """
+        data_type = aggregate.get('data_type', 'ALL')
+        merged: Dict[str, Dict] = {}
+        passthrough = []
+
+        for item in data:
+            parts = item['key'].split(':')
+            if len(parts) < 3:
+                passthrough.append(item)
+                continue
+
+            instance_key = f"{parts[0]}:{parts[1]}:{data_type}"
+            document = merged.setdefault(instance_key, {
+                'key': instance_key,
+                'value': {},
+                'timestamp': item.get('timestamp')
+            })
+            document['value'][parts[2]] = item['value']
+
+        return passthrough + list(merged.values())
+
+
+# 全局数据转换器实例
+data_transformer = DataTransformer()
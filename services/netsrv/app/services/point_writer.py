@@ -5,12 +5,13 @@
 
 import json
 import time
-from typing import Dict, Any, Optional
+from typing import Dict, Any, Optional, Tuple
 from loguru import logger
 from app.core.mqtt_client import mqtt_client
 from app.core.database import redis_manager
 from app.core.device_identity import device_identity
 from app.core.config_loader import config_loader
+from app.services.command_authorizer import command_authorizer
 
 class PointWriter:
     """单点写入服务"""
@@ -113,10 +114,17 @@ class PointWriter:
             
             logger.info(f"处理单点写入: source={source}, device={device}, data_type={data_type}, key={key}, value={value}")
             
-            # 写入Redis数据
-            write_success = await self._write_redis_data(source, device, data_type, key, value)
+            # 授权检查通过后写入Redis数据
+            write_success, reason = await self.authorized_write(
+                "mqtt", source, device, data_type, key, value, msg_id
+            )
             
-            if write_success:
+            if reason != "ok":
+                # 授权拒绝，回复拒绝原因
+                reply_message = self._build_unauthorized_reply(msg_id, reason)
+                if not mqtt_client.publish(self.reply_topic, reply_message, qos=1):
+                    logger.error(f"单点写入拒绝回复发送失败: {source}:{device}:{key}")
+            elif write_success:
                 # 构建成功回复消息
                 reply_message = self._build_success_reply(msg_id)
                 
@@ -138,6 +146,20 @@ class PointWriter:
         except Exception as e:
             logger.error(f"处理单点写入请求异常: {e}")
     
+    async def authorized_write(self, origin: str, source: str, device: str, data_type: str,
+                               key: str, value: Any, msg_id: Optional[str] = None) -> Tuple[bool, str]:
+        """经过命令授权后写入，返回 (是否写入成功, 原因)，原因为ok表示已通过授权"""
+        allowed, reason = command_authorizer.authorize(origin, source, device, data_type, key, value)
+        if not allowed:
+            command_authorizer.audit(origin, source, device, data_type, key, value,
+                                     False, reason, msg_id=msg_id)
+            return False, reason
+
+        success = await self._write_redis_data(source, device, data_type, key, value)
+        command_authorizer.audit(origin, source, device, data_type, key, value,
+                                 True, reason, result=success, msg_id=msg_id)
+        return success, "ok"
+    
     async def _write_redis_data(self, source: str, device: str, data_type: str, key: str, value: Any) -> bool:
         """写入Redis数据"""
         try:
@@ -237,6 +259,16 @@ class PointWriter:
             "msgId": msg_id
         }
 
+    def _build_unauthorized_reply(self, msg_id: str, reason: str) -> Dict[str, Any]:
+        """构建授权拒绝回复消息"""
+        return {
+            "result": "fail",
+            "error": "unauthorized",
+            "message": reason,
+            "msgId": msg_id,
+            "timestamp": int(time.time())
+        }
+
     def _send_validation_failure_reply(self, request_data: Dict[str, Any]):
         """发送验证失败回复"""
         try:
@@ -6,11 +6,17 @@ import asyncio
 import signal
 import sys
 from contextlib import asynccontextmanager
+from pathlib import Path
 from fastapi import FastAPI, Request
 from fastapi.middleware.cors import CORSMiddleware
 from fastapi.responses import JSONResponse
 from loguru import logger
 
+# 本地运行时加载公共模块（镜像中位于 /app/voltage_common）
+_SHARED_DIR = Path(__file__).resolve().parent.parent / "python-services"
+if _SHARED_DIR.is_dir() and str(_SHARED_DIR) not in sys.path:
+    sys.path.append(str(_SHARED_DIR))
+
 from app.core.config import settings
 from app.core.logger import setup_logger
 from app.core.database import redis_manager
@@ -22,6 +28,7 @@ from app.services.point_reader import point_reader
 from app.services.point_writer import point_writer
 from app.services.data_caller import data_caller
 from app.services.alarm_caller import alarm_caller
+from app.services.cloud_connector import cloud_connector_manager
 from app.api.routes import router
 
 # 设置日志
@@ -91,6 +98,12 @@ async def lifespan(app: FastAPI):
             logger.error("告警总召服务启动失败")
             raise Exception("告警总召服务启动失败")
         
+        # 启动托管云平台连接器（失败不影响主MQTT转发）
+        try:
+            cloud_connector_manager.start()
+        except Exception as e:
+            logger.error(f"云平台连接器启动失败: {e}")
+        
         logger.info("网络服务启动完成")
         startup_success = True
         
@@ -109,6 +122,9 @@ async def lifespan(app: FastAPI):
             await data_forwarder.stop()
             logger.info("数据转发器已停止")
             
+            # 断开云平台连接器
+            cloud_connector_manager.stop()
+            
             # 断开MQTT连接
             mqtt_client.disconnect()
             logger.info("MQTT连接已断开")
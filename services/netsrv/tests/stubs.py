@@ -0,0 +1,32 @@
+"""
+测试桩：测试环境未必安装 paho/loguru，且测试不加载配置文件
+在导入 app 模块之前调用 stub_modules()
+"""
+
+import sys
+import types
+from pathlib import Path
+from unittest import mock
+
+sys.path.insert(0, str(Path(__file__).resolve().parents[1]))
+
+
+def stub_modules():
+    if 'paho.mqtt.client' not in sys.modules:
+        client = types.ModuleType('paho.mqtt.client')
+        client.Client = mock.MagicMock
+        client.MQTTv311 = 4
+        client.MQTT_ERR_SUCCESS = 0
+        client.topic_matches_sub = lambda sub, topic: sub == topic
+        paho = types.ModuleType('paho')
+        paho.mqtt = types.ModuleType('paho.mqtt')
+        paho.mqtt.client = client
+        sys.modules.update({'paho': paho, 'paho.mqtt': paho.mqtt, 'paho.mqtt.client': client})
+    if 'loguru' not in sys.modules:
+        loguru = types.ModuleType('loguru')
+        loguru.logger = mock.MagicMock()
+        sys.modules['loguru'] = loguru
+    if 'app.core.delivery_stats' not in sys.modules:
+        stats = types.ModuleType('app.core.delivery_stats')
+        stats.delivery_stats = mock.MagicMock()
+        sys.modules['app.core.delivery_stats'] = stats
@@ -0,0 +1,85 @@
+"""
+云平台连接器测试：Azure SAS 令牌到期前/断线后刷新
+
+运行: python -m unittest discover -s tests（在 services/netsrv 目录下）
+"""
+
+import unittest
+from unittest import mock
+from urllib.parse import parse_qs
+
+from stubs import stub_modules
+
+stub_modules()
+
+from app.core import cloud_connectors  # noqa: E402
+from app.core.cloud_connectors import AzureIoTHubClient  # noqa: E402
+
+TTL = 600
+T0 = 1_700_000_000
+
+
+def _token_expiry(token: str) -> int:
+    return int(parse_qs(token.split(' ', 1)[1])['se'][0])
+
+
+class AzureSasRefreshTest(unittest.TestCase):
+
+    def setUp(self):
+        self.now = T0
+        patcher = mock.patch.object(cloud_connectors.time, 'time', lambda: self.now)
+        patcher.start()
+        self.addCleanup(patcher.stop)
+
+        # 定时器不实际运行，测试直接触发刷新
+        timer = mock.patch.object(cloud_connectors.threading, 'Timer')
+        self.timer = timer.start()
+        self.addCleanup(timer.stop)
+
+        self.client = mock.MagicMock()
+        factory = mock.patch.object(cloud_connectors, '_create_mqtt_client', return_value=self.client)
+        self.create_client = factory.start()
+        self.addCleanup(factory.stop)
+
+        self.connector = AzureIoTHubClient('azure', {
+            'type': 'azure_iot_hub',
+            'hostname': 'hub.azure-devices.net',
+            'device_id': 'ems-01',
+            'auth': {'type': 'sas', 'shared_access_key': 'c2VjcmV0', 'token_ttl': TTL},
+        })
+        self.assertTrue(self.connector.connect())
+
+    def last_password(self) -> str:
+        return self.client.username_pw_set.call_args[0][1]
+
+    def test_initial_token_expires_after_ttl(self):
+        params = self.create_client.call_args[0][0]
+        self.assertEqual(_token_expiry(params['password']), T0 + TTL)
+        self.assertEqual(self.timer.call_args[0][0], TTL * cloud_connectors.CREDENTIAL_REFRESH_RATIO)
+
+    def test_disconnect_after_expiry_uses_fresh_token(self):
+        self.now = T0 + TTL + 1
+        self.connector._on_disconnect(self.client, None, 7)
+
+        self.assertFalse(self.connector.is_connected)
+        self.assertEqual(self.client.username_pw_set.call_args[0][0],
+                         'hub.azure-devices.net/ems-01/?api-version=2021-04-12')
+        self.assertEqual(_token_expiry(self.last_password()), T0 + 2 * TTL + 1)
+
+    def test_refresh_before_expiry_reconnects_with_fresh_token(self):
+        self.now = T0 + int(TTL * cloud_connectors.CREDENTIAL_REFRESH_RATIO)
+        self.connector._refresh_and_reconnect()
+
+        self.assertEqual(_token_expiry(self.last_password()), self.now + TTL)
+        self.client.reconnect.assert_called_once()
+        # 为新令牌安排下一次刷新
+        self.assertEqual(self.timer.call_count, 2)
+
+    def test_x509_has_no_token_refresh(self):
+        self.connector.auth = {'type': 'x509'}
+        self.assertFalse(self.connector._refresh_credentials())
+        self.client.username_pw_set.assert_not_called()
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,106 @@
+"""
+IEC 104 上行测试：变化值按信息对象去重、会话重置清空积压、总召唤应答受k窗口限制
+
+运行: python -m unittest discover -s tests（在 services/netsrv 目录下）
+"""
+
+import struct
+import unittest
+
+from stubs import stub_modules
+
+stub_modules()
+
+from app.core import iec104_uplink  # noqa: E402
+from app.core.iec104_uplink import Iec104UplinkClient  # noqa: E402
+
+
+class FakeSocket:
+    def __init__(self):
+        self.sent = []
+
+    def sendall(self, data: bytes):
+        self.sent.append(data)
+
+    def asdus(self):
+        """已发送I帧的 (类型标识, 传送原因, [信息对象地址])"""
+        frames = []
+        for apdu in self.sent:
+            if apdu[2] & 0x01:
+                continue
+            type_id, count, cause = apdu[6], apdu[7] & 0x7F, apdu[8] & 0x3F
+            stride = 3 + (1 if type_id == iec104_uplink.M_SP_NA_1 else 5)
+            ioas = [struct.unpack('<I', apdu[12 + i * stride:15 + i * stride] + b'\x00')[0]
+                    for i in range(count)]
+            frames.append((type_id, cause, ioas))
+        return frames
+
+
+def _message(values):
+    return {'property': [{'source': 'inst', 'device': '1', 'data_type': 'M', 'value': values}]}
+
+
+class Iec104OutboxTest(unittest.TestCase):
+
+    def setUp(self):
+        self.client = Iec104UplinkClient('dispatch', {
+            'host': '10.0.0.10',
+            'k': 2,
+            'points': [
+                {'source': 'inst', 'device': '1', 'data_type': 'M', 'point': '1', 'ioa': 100},
+                {'source': 'inst', 'device': '1', 'data_type': 'M', 'point': '2', 'ioa': 101},
+                {'source': 'inst', 'device': '1', 'data_type': 'M', 'point': '3', 'ioa': 1,
+                 'type': 'single'},
+            ],
+        })
+        self.sock = FakeSocket()
+        self.client.sock = self.sock
+
+    def test_outbox_keeps_latest_value_per_ioa(self):
+        for value in (1.0, 2.0, 3.0):
+            self.client.publish_telemetry(_message({'1': value, '2': value}))
+        self.assertEqual(self.client._backlog(), 2)
+
+        self.client.data_transfer = True
+        self.client._send_pending()
+        self.assertEqual(self.sock.asdus(),
+                         [(iec104_uplink.M_ME_NC_1, iec104_uplink.COT_SPONTANEOUS, [100, 101])])
+        element = self.sock.sent[0][15:20]
+        self.assertEqual(struct.unpack('<fB', element), (3.0, 0))
+
+    def test_session_reset_clears_outbox(self):
+        self.client.publish_telemetry(_message({'1': 1.0, '3': 1}))
+        self.client.replies.append(b'stale')
+        self.client._reset_session()
+        self.assertEqual(self.client._backlog(), 0)
+        self.assertEqual(len(self.client.replies), 0)
+
+    def test_interrogation_response_respects_k_window(self):
+        self.client.publish_telemetry(_message({'1': 1.0, '2': 2.0, '3': 1}))
+        self.client.data_transfer = True
+        self.client._send_pending()
+        self.sock.sent.clear()
+
+        # 主站确认全部已发I帧后发起总召唤
+        gi = struct.pack('<HH', 0, self.client.send_seq << 1) + iec104_uplink.encode_asdu(
+            iec104_uplink.C_IC_NA_1, iec104_uplink.COT_ACTIVATION, 1, [(0, b'\x14')])
+        self.client._handle_apdu(gi)
+        self.client._send_pending()
+        # ACTCON、单点、浮点、ACTTERM 共4帧，k=2时只发出前两帧
+        self.assertEqual([frame[:2] for frame in self.sock.asdus()], [
+            (iec104_uplink.C_IC_NA_1, iec104_uplink.COT_ACTCON),
+            (iec104_uplink.M_SP_NA_1, iec104_uplink.COT_INTERROGATED),
+        ])
+
+        # 主站确认后继续发送剩余应答
+        ack = struct.pack('<BBH', 0x01, 0x00, self.client.send_seq << 1)
+        self.client._handle_apdu(ack)
+        self.client._send_pending()
+        self.assertEqual([frame[:2] for frame in self.sock.asdus()[2:]], [
+            (iec104_uplink.M_ME_NC_1, iec104_uplink.COT_INTERROGATED),
+            (iec104_uplink.C_IC_NA_1, iec104_uplink.COT_ACTTERM),
+        ])
+
+
+if __name__ == '__main__':
+    unittest.main()
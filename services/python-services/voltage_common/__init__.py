@@ -0,0 +1,4 @@
+"""
+Python服务公共模块
+镜像中位于 /app/voltage_common，本地运行时由各服务入口加入 sys.path
+"""
@@ -0,0 +1,70 @@
+"""
+点位别名解析
+读取 modsrv 发布到 Redis 的点位别名（客户点位编码、KKS/RDS-PP 标识），
+将内部地址 inst:{id}:{M|A}:{point} / comsrv:{id}:{T|S|C|A}:{point} 映射为稳定的外部标识。
+
+Redis 结构（由 modsrv 维护，SQLite point_aliases 表为唯一数据源）:
+    modsrv:alias                    别名 -> 地址
+    modsrv:alias:by_point           地址 -> 别名（每个地址一个，按方案名排序取第一个）
+    modsrv:alias:by_point:{scheme}  地址 -> 该方案下的别名
+
+点位重新编号后只需在 modsrv 中修改别名指向的地址，下游输出中的别名保持不变。
+"""
+
+import time
+from typing import Any, Dict, Optional
+
+__all__ = [
+    "ALIAS_KEY",
+    "AliasResolver",
+    "by_point_key",
+]
+
+ALIAS_KEY = "modsrv:alias"
+
+
+def by_point_key(scheme: Optional[str] = None) -> str:
+    """地址 -> 别名哈希键，未指定方案时为合并后的哈希"""
+    return f"{ALIAS_KEY}:by_point:{scheme}" if scheme else f"{ALIAS_KEY}:by_point"
+
+
+class AliasResolver:
+    """按方案缓存别名表，定期从Redis刷新
+
+    redis_client 为同步 redis.Redis（decode_responses=True），也可传入返回客户端的可调用对象，
+    以便在连接重建后取得新客户端。Redis 不可用时沿用上一次的缓存。
+    """
+
+    def __init__(self, redis_client: Any, scheme: Optional[str] = None, ttl: float = 60.0):
+        self._client = redis_client
+        self.scheme = scheme or None
+        self.ttl = ttl
+        self._by_point: Dict[str, str] = {}
+        self._loaded_at: Optional[float] = None
+
+    def _redis(self):
+        return self._client() if callable(self._client) else self._client
+
+    def refresh(self) -> bool:
+        """立即从Redis重新加载，成功返回True"""
+        client = self._redis()
+        if client is None:
+            return False
+        try:
+            self._by_point = dict(client.hgetall(by_point_key(self.scheme)) or {})
+        except Exception:
+            return False
+        finally:
+            # 失败时也推迟下一次尝试，避免Redis故障期间每个点位都去重连
+            self._loaded_at = time.monotonic()
+        return True
+
+    def mapping(self) -> Dict[str, str]:
+        """地址 -> 别名映射（过期时自动刷新）"""
+        if self._loaded_at is None or time.monotonic() - self._loaded_at >= self.ttl:
+            self.refresh()
+        return self._by_point
+
+    def alias(self, key: str, point_id: Any) -> Optional[str]:
+        """Redis哈希键 + 点位ID对应的别名，例如 ("inst:5:M", "1") -> "PCS1.P" """
+        return self.mapping().get(f"{key}:{point_id}")
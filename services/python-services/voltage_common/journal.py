@@ -0,0 +1,218 @@
+"""
+事件日志（Redis Streams）
+轮询与 pub/sub 都不保留离线期间的变化，服务重启后会丢失停机期间的点位与告警变化。
+事件日志将变化追加到 Redis Stream，消费者通过消费组读取，偏移量由 Redis 保存，
+重启后从上次确认的位置继续，补上停机期间的事件。
+
+Stream:
+    events:points   PointWritten，由 comsrv/modsrv 写入（VOLTAGE_EVENT_JOURNAL，见 voltage-rtdb journal.rs）
+    events:alarms   AlarmRaised / AlarmRecovered，由 alarmsrv 写入
+
+条目字段:
+    type    事件类型
+    ts      事件时间（毫秒）
+    其余字段由事件类型决定，PointWritten 为 key（哈希键）和 points（字段 -> 值 的JSON对象）
+
+保留策略: 每次追加时按条数（MAXLEN ~）和时间（MINID ~）裁剪。
+停机时间超过保留时长的消费者会缺失被裁剪的事件。
+
+消费:
+    consumer = JournalConsumer(redis_client, POINT_STREAM, group="hissrv", consumer="hissrv-1")
+    events = consumer.read(count=500)
+    ...  # 处理完成（例如已写入存储）后再确认
+    consumer.ack([event.id for event in events])
+
+未确认的事件在重启后重新投递（先读本消费者的待确认列表，再读新事件），即至少一次语义，
+处理需能容忍重复事件。seek() 可将消费组偏移量移到指定位置，从该位置重放。
+"""
+
+import json
+import time
+from datetime import datetime, timezone
+from typing import Any, Dict, Iterable, List, Optional
+
+__all__ = [
+    "ALARM_RAISED",
+    "ALARM_RECOVERED",
+    "ALARM_STREAM",
+    "JournalConsumer",
+    "JournalEvent",
+    "POINT_STREAM",
+    "POINT_WRITTEN",
+    "append",
+    "replay",
+]
+
+POINT_STREAM = "events:points"
+ALARM_STREAM = "events:alarms"
+
+POINT_WRITTEN = "PointWritten"
+ALARM_RAISED = "AlarmRaised"
+ALARM_RECOVERED = "AlarmRecovered"
+
+DEFAULT_MAX_LEN = 100_000
+DEFAULT_MAX_AGE = 24 * 3600
+
+
+def _text(value: Any) -> str:
+    if isinstance(value, bytes):
+        return value.decode("utf-8", "replace")
+    return str(value)
+
+
+class JournalEvent:
+    """一条日志事件"""
+
+    def __init__(self, event_id: str, fields: Dict[str, Any]):
+        self.id = _text(event_id)
+        self.fields = {_text(k): _text(v) for k, v in fields.items()}
+        self.type = self.fields.get("type", "")
+
+    @property
+    def key(self) -> str:
+        return self.fields.get("key", "")
+
+    @property
+    def timestamp(self) -> datetime:
+        """事件时间（UTC），缺失时取条目ID中的毫秒时间"""
+        ts = self.fields.get("ts") or self.id.split("-", 1)[0]
+        try:
+            return datetime.fromtimestamp(int(ts) / 1000.0, tz=timezone.utc)
+        except (TypeError, ValueError):
+            return datetime.now(timezone.utc)
+
+    @property
+    def points(self) -> Dict[str, str]:
+        """PointWritten 写入的字段 -> 值"""
+        try:
+            points = json.loads(self.fields.get("points", "{}"))
+        except ValueError:
+            return {}
+        return {str(k): str(v) for k, v in points.items()} if isinstance(points, dict) else {}
+
+    def get_json(self, name: str, default: Any = None) -> Any:
+        """按JSON解析字段，非JSON时返回原文本"""
+        value = self.fields.get(name)
+        if value is None:
+            return default
+        try:
+            return json.loads(value)
+        except ValueError:
+            return value
+
+    def __repr__(self) -> str:
+        return f"JournalEvent({self.id}, {self.type})"
+
+
+def append(redis_client: Any, stream: str, event_type: str, fields: Dict[str, Any],
+           max_len: int = DEFAULT_MAX_LEN, max_age: int = DEFAULT_MAX_AGE) -> str:
+    """追加一条事件并按保留策略裁剪，返回条目ID
+
+    非字符串字段值按JSON编码；max_age 单位为秒。
+    """
+    now_ms = int(time.time() * 1000)
+    entry = {"type": event_type, "ts": str(now_ms)}
+    for name, value in fields.items():
+        if value is None:
+            continue
+        entry[name] = value if isinstance(value, str) else json.dumps(value, ensure_ascii=False)
+
+    pipe = redis_client.pipeline(transaction=False)
+    pipe.xadd(stream, entry, maxlen=max_len, approximate=True)
+    pipe.xtrim(stream, minid=f"{max(now_ms - max_age * 1000, 0)}-0", approximate=True)
+    return _text(pipe.execute()[0])
+
+
+def replay(redis_client: Any, stream: str, after: Optional[str] = None,
+           count: int = 500) -> List[JournalEvent]:
+    """读取 after 之后（不含）的事件，不使用消费组，after 为空时从最早的事件开始"""
+    start = f"({after}" if after else "-"
+    entries = redis_client.xrange(stream, min=start, max="+", count=count)
+    return [JournalEvent(entry_id, fields) for entry_id, fields in entries if fields]
+
+
+class JournalConsumer:
+    """消费组读取器，偏移量（最后确认的条目）保存在 Redis
+
+    redis_client 为同步 redis.Redis，也可传入返回客户端的可调用对象，以便在连接重建后取得新客户端。
+    start_id 仅在首次创建消费组时生效: "$" 只接收之后的新事件，"0" 从保留的最早事件开始。
+    """
+
+    def __init__(self, redis_client: Any, stream: str, group: str, consumer: str,
+                 start_id: str = "$"):
+        self._client = redis_client
+        self.stream = stream
+        self.group = group
+        self.consumer = consumer
+        self.start_id = start_id
+        self._group_ready = False
+        # 重放待确认列表的位置，读完后切换到新事件（">"）
+        self._pending_cursor: Optional[str] = "0"
+
+    def _redis(self):
+        return self._client() if callable(self._client) else self._client
+
+    def ensure_group(self):
+        """创建消费组（Stream 不存在时一并创建），已存在时忽略"""
+        try:
+            self._redis().xgroup_create(self.stream, self.group, id=self.start_id, mkstream=True)
+        except Exception as e:
+            if "BUSYGROUP" not in str(e):
+                raise
+        self._group_ready = True
+
+    def read(self, count: int = 500, block_ms: Optional[int] = None) -> List[JournalEvent]:
+        """读取下一批事件
+
+        先按顺序返回上次退出前已投递但未确认的事件，之后返回新事件。
+        已被裁剪的待确认条目直接确认并跳过。
+        """
+        client = self._redis()
+        if client is None:
+            return []
+        if not self._group_ready:
+            self.ensure_group()
+
+        try:
+            while self._pending_cursor is not None:
+                entries = self._read_group(client, self._pending_cursor, count, None)
+                if not entries:
+                    self._pending_cursor = None
+                    break
+                self._pending_cursor = _text(entries[-1][0])
+                events = self._live_events(client, entries)
+                if events:
+                    return events
+            return self._live_events(client, self._read_group(client, ">", count, block_ms))
+        except Exception as e:
+            if "NOGROUP" in str(e):
+                # Stream 被删除（如 Redis 重建），下次读取时重新创建消费组
+                self._group_ready = False
+                self._pending_cursor = "0"
+            raise
+
+    def ack(self, event_ids: Iterable[str]) -> int:
+        """确认已处理的事件，返回确认条数"""
+        ids = list(event_ids)
+        if not ids:
+            return 0
+        return int(self._redis().xack(self.stream, self.group, *ids))
+
+    def seek(self, event_id: str):
+        """将消费组偏移量移到 event_id（"0" 为最早，"$" 为最新），之后的事件重新投递"""
+        if not self._group_ready:
+            self.ensure_group()
+        self._redis().xgroup_setid(self.stream, self.group, event_id)
+        self._pending_cursor = "0"
+
+    def _read_group(self, client, start: str, count: int, block_ms: Optional[int]):
+        response = client.xreadgroup(self.group, self.consumer, {self.stream: start},
+                                     count=count, block=block_ms)
+        # [[stream, [(id, fields), ...]]]
+        return response[0][1] if response else []
+
+    def _live_events(self, client, entries) -> List[JournalEvent]:
+        trimmed = [entry_id for entry_id, fields in entries if not fields]
+        if trimmed:
+            client.xack(self.stream, self.group, *trimmed)
+        return [JournalEvent(entry_id, fields) for entry_id, fields in entries if fields]
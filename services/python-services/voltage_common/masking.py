@@ -0,0 +1,164 @@
+"""
+导出数据脱敏
+数据离开边缘侧之前（netsrv 上送、hissrv 导出）按配置对敏感字段做哈希或删除，
+用于向数据分析服务商共享数据集（站点名称、客户编号等）。
+
+配置:
+    masking:
+      enabled: true
+      salt_env: VOLTAGE_MASKING_SALT    # 盐值所在环境变量，未设置时使用 salt
+      salt: ""
+      hash_length: 16                   # 哈希结果保留的十六进制位数（8-64）
+      rules:
+        - {field: "site_name", action: hash}
+        - {field: "customer_*", action: hash}
+        - {field: "contact.phone", action: drop}      # 嵌套字段按 . 路径匹配
+        - {key: "inst:*:config", field: "owner", action: drop}
+        - {key: "inst:*:name", action: hash}          # 未指定 field 时作用于键下全部字段
+
+规则按顺序匹配，第一条命中的规则生效。key 为 Redis 键模式（默认 *），field 为字段名或
+. 分隔的字段路径模式（默认 *），均为 fnmatch 通配符。哈希为 HMAC-SHA256(盐值, 原值)，
+同一原值在各服务、各次导出中结果相同，脱敏后的数据仍可关联。
+"""
+
+import fnmatch
+import hashlib
+import hmac
+import os
+from typing import Any, Dict, List, Optional, Tuple
+
+__all__ = [
+    "ACTIONS",
+    "DEFAULT_SALT_ENV",
+    "Masker",
+    "MaskingError",
+    "MaskRule",
+]
+
+ACTIONS = ("hash", "drop")
+DEFAULT_SALT_ENV = "VOLTAGE_MASKING_SALT"
+
+_DROP = object()
+
+
+class MaskingError(ValueError):
+    """脱敏配置错误"""
+
+
+class MaskRule:
+    """单条脱敏规则"""
+
+    __slots__ = ("key", "field", "action")
+
+    def __init__(self, action: str, field: str = "*", key: str = "*"):
+        if action not in ACTIONS:
+            raise MaskingError(f"未知的脱敏动作: {action}（可选 {', '.join(ACTIONS)}）")
+        self.action = action
+        self.field = str(field)
+        self.key = str(key)
+
+    def matches(self, key: str, name: str, path: str) -> bool:
+        return fnmatch.fnmatch(key, self.key) and (
+            fnmatch.fnmatch(name, self.field) or fnmatch.fnmatch(path, self.field))
+
+
+class Masker:
+    """按规则对导出数据做哈希/删除，返回脱敏后的副本，不修改输入"""
+
+    def __init__(self, rules: List[MaskRule], salt: str = "", hash_length: int = 16):
+        self.rules = rules
+        self._salt = salt.encode("utf-8")
+        self.hash_length = max(8, min(64, int(hash_length)))
+
+    @classmethod
+    def from_config(cls, config: Any) -> Optional["Masker"]:
+        """按 masking 配置创建，未启用或无规则时返回None；配置无效时抛出 MaskingError"""
+        if not isinstance(config, dict) or not config.get("enabled", False):
+            return None
+        rules = []
+        for index, rule in enumerate(config.get("rules") or []):
+            if not isinstance(rule, dict):
+                raise MaskingError(f"第 {index + 1} 条脱敏规则不是对象")
+            rules.append(MaskRule(rule.get("action", "hash"),
+                                  field=rule.get("field", "*"),
+                                  key=rule.get("key", "*")))
+        if not rules:
+            return None
+        salt = os.environ.get(config.get("salt_env") or DEFAULT_SALT_ENV) or config.get("salt") or ""
+        return cls(rules, salt=str(salt), hash_length=config.get("hash_length", 16))
+
+    @property
+    def salted(self) -> bool:
+        """未加盐时短编号等取值可被穷举还原"""
+        return bool(self._salt)
+
+    def hash_value(self, value: Any) -> Any:
+        """原值的稳定哈希，None 保持不变"""
+        if value is None:
+            return None
+        digest = hmac.new(self._salt, str(value).encode("utf-8"), hashlib.sha256).hexdigest()
+        return digest[:self.hash_length]
+
+    def _rule(self, key: str, name: str, path: str) -> Optional[MaskRule]:
+        for rule in self.rules:
+            if rule.matches(key, name, path):
+                return rule
+        return None
+
+    def _apply(self, rule: MaskRule, value: Any) -> Any:
+        if rule.action == "drop":
+            return _DROP
+        if isinstance(value, dict):
+            return {name: self.hash_value(v) for name, v in value.items()}
+        if isinstance(value, list):
+            return [self.hash_value(v) for v in value]
+        return self.hash_value(value)
+
+    def _walk(self, value: Any, key: str, path: str) -> Any:
+        if isinstance(value, dict):
+            result = {}
+            for name, child in value.items():
+                name = str(name)
+                child_path = f"{path}.{name}" if path else name
+                rule = self._rule(key, name, child_path)
+                masked = self._apply(rule, child) if rule else self._walk(child, key, child_path)
+                if masked is not _DROP:
+                    result[name] = masked
+            return result
+        if isinstance(value, list):
+            return [self._walk(item, key, path) for item in value]
+        return value
+
+    def mask(self, value: Any, key: str = "") -> Any:
+        """脱敏一个值：字典按字段匹配（含嵌套），标量值按字段 * 匹配整个键
+
+        整个值被删除时返回None。
+        """
+        if isinstance(value, (dict, list)):
+            return self._walk(value, key, "")
+        rule = self._rule(key, "*", "*") if key else None
+        if rule is None:
+            return value
+        masked = self._apply(rule, value)
+        return None if masked is _DROP else masked
+
+    def mask_field(self, key: str, field: str, value: Any) -> Tuple[bool, Any]:
+        """脱敏哈希键中的单个字段，返回 (是否保留, 值)"""
+        rule = self._rule(key, str(field), str(field))
+        if rule is None:
+            return True, value
+        masked = self._apply(rule, value)
+        return masked is not _DROP, None if masked is _DROP else masked
+
+    def mask_items(self, data: List[Dict[str, Any]]) -> List[Dict[str, Any]]:
+        """脱敏 [{'key': Redis键, 'value': 值, ...}] 形式的数据，整条被删除的项不再输出"""
+        result = []
+        for item in data:
+            key = str(item.get("key", ""))
+            value = self.mask(item.get("value"), key)
+            if value is None and item.get("value") is not None:
+                continue
+            if isinstance(value, dict) and not value and item.get("value"):
+                continue
+            result.append({**item, "value": value})
+        return result
@@ -0,0 +1,414 @@
+"""
+通知模板引擎
+类 handlebars 语法的轻量模板，用于渲染告警/事件通知（邮件主题、正文、Webhook/MQTT JSON）
+
+语法:
+    {{ rule.rule_name }}                变量，按 . 逐级取值（字典键、列表下标、对象属性）
+    {{ value | round 2 }}               过滤器，可串联: {{ ts | datetime "%H:%M" | default "-" }}
+    {{{ raw }}}                         不转义输出（仅 escape="html" 时有区别）
+    {{#if expr}}...{{else}}...{{/if}}   条件
+    {{#unless expr}}...{{/unless}}      反向条件
+    {{#each list}}{{@index}}: {{this}}{{/each}}
+                                        循环，字典循环时 {{@key}} 为键；元素为字典时可直接访问其字段
+    {{! 注释 }}
+
+过滤器参数: 带引号为字符串，数字/true/false/null 为字面量，其余按变量路径取值。
+上下文中的无参可调用对象在首次访问时求值并在本次渲染内缓存，可用于按需读取
+实例信息、点位值等开销较大的数据。
+"""
+
+import html
+import json
+import re
+from collections.abc import Mapping
+from datetime import datetime
+from functools import lru_cache
+from typing import Any, Callable, Dict, List, Optional, Tuple
+
+__all__ = [
+    "Template",
+    "TemplateError",
+    "register_filter",
+    "render",
+    "render_payload",
+    "validate",
+]
+
+_TAG_RE = re.compile(r"\{\{\{\s*(.*?)\s*\}\}\}|\{\{\s*(.*?)\s*\}\}", re.S)
+_SINGLE_TAG_RE = re.compile(r"\s*\{\{\s*([^#/!{].*?)\s*\}\}\s*", re.S)
+_ARG_RE = re.compile(r'"((?:[^"\\]|\\.)*)"|\'((?:[^\'\\]|\\.)*)\'|(\S+)')
+_NUMBER_RE = re.compile(r"-?\d+(\.\d+)?$")
+_LITERALS = {"true": True, "false": False, "null": None, "none": None}
+
+_filters: Dict[str, Callable[..., Any]] = {}
+
+
+class TemplateError(ValueError):
+    """模板语法错误"""
+
+
+def register_filter(name: str, func: Callable[..., Any]):
+    """注册过滤器，func(value, *args) -> value"""
+    _filters[name] = func
+
+
+# ---------------------------------------------------------------------------
+# 编译
+# ---------------------------------------------------------------------------
+
+class _Path:
+    """变量路径参数"""
+
+    __slots__ = ("path",)
+
+    def __init__(self, path: str):
+        self.path = path
+
+
+def _parse_arg(quoted_double: Optional[str], quoted_single: Optional[str], bare: Optional[str]) -> Any:
+    if quoted_double is not None:
+        return quoted_double.replace('\\"', '"')
+    if quoted_single is not None:
+        return quoted_single.replace("\\'", "'")
+    if _NUMBER_RE.match(bare):
+        return float(bare) if "." in bare else int(bare)
+    if bare.lower() in _LITERALS:
+        return _LITERALS[bare.lower()]
+    return _Path(bare)
+
+
+def _split_pipes(expr: str) -> List[str]:
+    """按 | 切分表达式，忽略引号内的 |"""
+    parts, current, quote = [], [], None
+    for ch in expr:
+        if quote:
+            if ch == quote:
+                quote = None
+        elif ch in "\"'":
+            quote = ch
+        elif ch == "|":
+            parts.append("".join(current))
+            current = []
+            continue
+        current.append(ch)
+    if quote:
+        raise TemplateError(f"引号未闭合: {expr}")
+    parts.append("".join(current))
+    return [p.strip() for p in parts]
+
+
+def _compile_expr(expr: str) -> Tuple[str, List[Tuple[str, List[Any]]]]:
+    """编译表达式为 (变量路径, [(过滤器名, 参数)])"""
+    segments = _split_pipes(expr)
+    path = segments[0]
+    if not path or " " in path:
+        raise TemplateError(f"无效的变量表达式: {{{{ {expr} }}}}")
+    chain = []
+    for segment in segments[1:]:
+        tokens = [_parse_arg(*m.groups()) for m in _ARG_RE.finditer(segment)]
+        if not tokens or not isinstance(tokens[0], _Path):
+            raise TemplateError(f"无效的过滤器: {segment}")
+        name = tokens[0].path
+        if name not in _filters:
+            raise TemplateError(f"未知的过滤器: {name}")
+        chain.append((name, tokens[1:]))
+    return path, chain
+
+
+def _compile(text: str) -> list:
+    """编译模板文本为节点树"""
+    root: list = []
+    # 栈元素: (块, 块外层正在写入的节点列表)；块: [类型, 表达式, 主体节点, else节点]
+    stack: List[Tuple[list, list]] = []
+    out = root
+    pos = 0
+    for match in _TAG_RE.finditer(text):
+        if match.start() > pos:
+            out.append(("text", text[pos:match.start()]))
+        pos = match.end()
+
+        raw_expr, expr = match.group(1), match.group(2)
+        if raw_expr is not None:
+            out.append(("var", _compile_expr(raw_expr), True))
+            continue
+        if expr.startswith("!"):
+            continue
+        if expr.startswith("#"):
+            kind, _, arg = expr[1:].partition(" ")
+            if kind not in ("if", "unless", "each") or not arg.strip():
+                raise TemplateError(f"无效的块标签: {{{{{expr}}}}}")
+            block = [kind, _compile_expr(arg.strip()), [], []]
+            out.append(("block", block))
+            stack.append((block, out))
+            out = block[2]
+        elif expr == "else":
+            if not stack or out is stack[-1][0][3]:
+                raise TemplateError("{{else}} 不在块内")
+            out = stack[-1][0][3]
+        elif expr.startswith("/"):
+            kind = expr[1:].strip()
+            if not stack or stack[-1][0][0] != kind:
+                raise TemplateError(f"块标签不匹配: {{{{{expr}}}}}")
+            out = stack.pop()[1]
+        else:
+            out.append(("var", _compile_expr(expr), False))
+
+    if stack:
+        raise TemplateError(f"块标签未闭合: {{{{#{stack[-1][0][0]}}}}}")
+    if pos < len(text):
+        out.append(("text", text[pos:]))
+    return root
+
+
+@lru_cache(maxsize=256)
+def _compile_cached(text: str) -> list:
+    return _compile(text)
+
+
+# ---------------------------------------------------------------------------
+# 渲染
+# ---------------------------------------------------------------------------
+
+class _Renderer:
+    """单次渲染的上下文（作用域链 + 惰性值缓存）"""
+
+    def __init__(self, context: Any, escape: Optional[str] = None):
+        self.root = context
+        self.escape = escape
+        self.lazy: Dict[int, Any] = {}
+
+    def force(self, value: Any) -> Any:
+        if callable(value) and not isinstance(value, type):
+            key = id(value)
+            if key not in self.lazy:
+                self.lazy[key] = (value, value())
+            return self.lazy[key][1]
+        return value
+
+    def get(self, obj: Any, name: str) -> Any:
+        obj = self.force(obj)
+        if obj is None:
+            return None
+        if isinstance(obj, Mapping):
+            value = obj.get(name)
+            if value is None and name.lstrip("-").isdigit():
+                value = obj.get(int(name))
+        elif isinstance(obj, (list, tuple)):
+            try:
+                value = obj[int(name)]
+            except (ValueError, IndexError):
+                value = None
+        elif name.startswith("_"):
+            value = None
+        else:
+            value = getattr(obj, name, None)
+        return self.force(value)
+
+    def lookup(self, path: str, scopes: List[Dict[str, Any]]) -> Any:
+        head, *rest = path.split(".")
+        if head == "@root":
+            value = self.force(self.root)
+        elif head == "this" or head.startswith("@"):
+            value = scopes[-1].get(head) if scopes else None
+        else:
+            value = None
+            for scope in reversed(scopes):
+                if head in scope:
+                    value = self.force(scope[head])
+                    break
+                this = scope.get("this")
+                if isinstance(self.force(this), Mapping) and head in self.force(this):
+                    value = self.get(this, head)
+                    break
+            else:
+                value = self.get(self.root, head)
+        for name in rest:
+            value = self.get(value, name)
+        return self.force(value)
+
+    def evaluate(self, compiled, scopes: List[Dict[str, Any]]) -> Any:
+        path, chain = compiled
+        value = self.lookup(path, scopes)
+        for name, args in chain:
+            resolved = [self.lookup(a.path, scopes) if isinstance(a, _Path) else a for a in args]
+            try:
+                value = _filters[name](value, *resolved)
+            except (TypeError, ValueError, OverflowError):
+                # 过滤器不适用于该值时保持原值
+                pass
+        return value
+
+    def render_nodes(self, nodes: list, scopes: List[Dict[str, Any]], out: List[str]):
+        for node in nodes:
+            kind = node[0]
+            if kind == "text":
+                out.append(node[1])
+            elif kind == "var":
+                text = _to_text(self.evaluate(node[1], scopes))
+                if self.escape == "html" and not node[2]:
+                    text = html.escape(text)
+                out.append(text)
+            else:
+                self.render_block(node[1], scopes, out)
+
+    def render_block(self, block: list, scopes: List[Dict[str, Any]], out: List[str]):
+        kind, compiled, body, else_body = block
+        value = self.evaluate(compiled, scopes)
+        if kind == "if":
+            self.render_nodes(body if _truthy(value) else else_body, scopes, out)
+        elif kind == "unless":
+            self.render_nodes(else_body if _truthy(value) else body, scopes, out)
+        else:
+            if isinstance(value, Mapping):
+                items = [(k, v) for k, v in value.items()]
+            elif isinstance(value, (list, tuple)):
+                items = list(enumerate(value))
+            else:
+                items = []
+            if not items:
+                self.render_nodes(else_body, scopes, out)
+                return
+            last = len(items) - 1
+            for index, (key, item) in enumerate(items):
+                scope = {
+                    "this": item,
+                    "@index": index,
+                    "@key": key,
+                    "@first": index == 0,
+                    "@last": index == last,
+                }
+                self.render_nodes(body, scopes + [scope], out)
+
+
+def _truthy(value: Any) -> bool:
+    if isinstance(value, str):
+        return value not in ("", "0", "false")
+    return bool(value)
+
+
+def _to_text(value: Any) -> str:
+    if value is None:
+        return ""
+    if isinstance(value, bool):
+        return "true" if value else "false"
+    if isinstance(value, float) and value.is_integer():
+        return str(int(value))
+    if isinstance(value, (dict, list, tuple)):
+        return json.dumps(value, ensure_ascii=False, default=str)
+    return str(value)
+
+
+class Template:
+    """已编译的文本模板"""
+
+    def __init__(self, text: str):
+        self.text = text
+        self.nodes = _compile_cached(text)
+
+    def render(self, context: Any, escape: Optional[str] = None) -> str:
+        """渲染模板，escape="html" 时对变量输出做HTML转义"""
+        out: List[str] = []
+        _Renderer(context, escape).render_nodes(self.nodes, [], out)
+        return "".join(out)
+
+
+def render(text: str, context: Any, escape: Optional[str] = None) -> str:
+    """渲染文本模板"""
+    return Template(text).render(context, escape)
+
+
+def render_payload(template: Any, context: Any) -> Any:
+    """渲染JSON结构模板
+
+    递归渲染字典/列表中的字符串（含字典键）。整个字符串只有一个变量标签时
+    保留其原始类型，例如 "{{ value }}" 渲染为数值而不是字符串。
+    """
+    renderer = _Renderer(context)
+
+    def text(node: str) -> str:
+        out: List[str] = []
+        renderer.render_nodes(_compile_cached(node), [], out)
+        return "".join(out)
+
+    def walk(node: Any) -> Any:
+        if isinstance(node, str):
+            single = _SINGLE_TAG_RE.fullmatch(node)
+            if single and "{{" not in single.group(1):
+                return renderer.evaluate(_compile_expr(single.group(1)), [])
+            return text(node)
+        if isinstance(node, Mapping):
+            # 键始终渲染为字符串
+            return {text(key) if isinstance(key, str) else key: walk(value)
+                    for key, value in node.items()}
+        if isinstance(node, (list, tuple)):
+            return [walk(item) for item in node]
+        return node
+
+    return walk(template)
+
+
+def validate(template: Any):
+    """检查文本或JSON结构模板的语法，错误时抛出 TemplateError"""
+    if isinstance(template, str):
+        _compile_cached(template)
+    elif isinstance(template, Mapping):
+        for key, value in template.items():
+            validate(key)
+            validate(value)
+    elif isinstance(template, (list, tuple)):
+        for item in template:
+            validate(item)
+
+
+# ---------------------------------------------------------------------------
+# 内置过滤器
+# ---------------------------------------------------------------------------
+
+def _default(value: Any, fallback: Any = "") -> Any:
+    return fallback if value is None or value == "" else value
+
+
+def _round(value: Any, digits: int = 2) -> Any:
+    rounded = round(float(value), int(digits))
+    return int(rounded) if int(digits) <= 0 else rounded
+
+
+def _datetime(value: Any, fmt: str = "%Y-%m-%d %H:%M:%S") -> str:
+    if isinstance(value, str):
+        value = float(value) if _NUMBER_RE.match(value) else datetime.fromisoformat(value)
+    if isinstance(value, (int, float)):
+        # 毫秒时间戳
+        value = datetime.fromtimestamp(value / 1000 if value > 1e11 else value)
+    return value.strftime(fmt)
+
+
+def _join(value: Any, sep: str = ", ") -> str:
+    return sep.join(_to_text(item) for item in value)
+
+
+def _truncate(value: Any, length: int = 80, suffix: str = "...") -> str:
+    text = _to_text(value)
+    return text if len(text) <= int(length) else text[:int(length)] + suffix
+
+
+def _map(value: Any, *pairs: str) -> Any:
+    """值映射: {{ level | map "1=紧急" "2=重要" "3=一般" }}"""
+    key = _to_text(value)
+    for pair in pairs:
+        src, sep, dst = str(pair).partition("=")
+        if sep and src == key:
+            return dst
+    return value
+
+
+register_filter("default", _default)
+register_filter("round", _round)
+register_filter("int", lambda value: int(float(value)))
+register_filter("float", lambda value: float(value))
+register_filter("upper", lambda value: _to_text(value).upper())
+register_filter("lower", lambda value: _to_text(value).lower())
+register_filter("datetime", _datetime)
+register_filter("json", lambda value: json.dumps(value, ensure_ascii=False, default=str))
+register_filter("join", _join)
+register_filter("truncate", _truncate)
+register_filter("map", _map)
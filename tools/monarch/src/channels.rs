@@ -2,12 +2,17 @@
 //!
 //! Provides functionality to manage communication channels
 
-use anyhow::Result;
-use clap::Subcommand;
+use anyhow::{anyhow, Result};
+use clap::{Args, Subcommand};
+use colored::Colorize;
 use reqwest::Client;
 use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use tracing::{info, warn};
 
+use crate::core::xlsx::{self, SheetOptions};
+
 #[cfg(feature = "lib-mode")]
 use crate::{context::ServiceContext, lib_api};
 
@@ -53,12 +58,128 @@ pub enum ChannelCommands {
     /// Check service health
     #[command(about = "Check communication service health")]
     Health,
+
+    /// Import point tables from an Excel workbook
+    #[command(about = "Import point tables from an Excel (.xlsx) workbook into channel CSVs")]
+    Import(ImportArgs),
+}
+
+/// Point table types and their CSV files inside a channel directory
+const POINT_TABLES: [(&str, &str); 8] = [
+    ("telemetry", "telemetry.csv"),
+    ("signal", "signal.csv"),
+    ("control", "control.csv"),
+    ("adjustment", "adjustment.csv"),
+    ("telemetry_mapping", "mapping/telemetry_mapping.csv"),
+    ("signal_mapping", "mapping/signal_mapping.csv"),
+    ("control_mapping", "mapping/control_mapping.csv"),
+    ("adjustment_mapping", "mapping/adjustment_mapping.csv"),
+];
+
+#[derive(Args)]
+pub struct ImportArgs {
+    /// Channel ID
+    pub channel_id: u32,
+
+    /// Workbook path (.xlsx)
+    pub file: PathBuf,
+
+    /// Sheet for a point table, e.g. telemetry=遥测 (default: sheet named after the table)
+    #[arg(long = "sheet", value_name = "TABLE=SHEET")]
+    pub sheets: Vec<String>,
+
+    /// Header row (1-indexed) when sheets start with title rows
+    #[arg(long, default_value_t = 1)]
+    pub header_row: usize,
+
+    /// Column for a field, by header or letter, e.g. point_id=A or telemetry.scale=Ratio
+    #[arg(long = "column", value_name = "[TABLE.]FIELD=COLUMN")]
+    pub columns: Vec<String>,
+}
+
+/// Convert workbook sheets into `comsrv/{channel_id}/*.csv` (run `monarch sync` afterwards)
+pub fn import_command(args: &ImportArgs, config_path: &Path) -> Result<()> {
+    let sheet_overrides = parse_pairs(&args.sheets)?;
+    let column_mappings = parse_pairs(&args.columns)?;
+    let available = xlsx::sheet_names(&args.file)?;
+    let channel_dir = config_path.join("comsrv").join(args.channel_id.to_string());
+
+    let mut imported = 0;
+    for (table, csv_file) in POINT_TABLES {
+        let sheet = sheet_overrides
+            .get(table)
+            .cloned()
+            .unwrap_or_else(|| table.to_string());
+        if !available.contains(&sheet) {
+            continue;
+        }
+
+        let mut options = SheetOptions::new(sheet.clone());
+        options.header_row = args.header_row;
+        for (key, column) in &column_mappings {
+            match key.split_once('.') {
+                Some((scope, field)) if scope == table => {
+                    options.columns.insert(field.to_string(), column.clone());
+                },
+                Some(_) => {},
+                None => {
+                    options.columns.insert(key.clone(), column.clone());
+                },
+            }
+        }
+
+        let csv = xlsx::sheet_to_csv(&args.file, &options)?;
+        let target = channel_dir.join(csv_file);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&target, &csv)?;
+
+        let rows = csv::Reader::from_reader(csv.as_slice()).records().count();
+        println!(
+            "{} {} -> {} ({} rows)",
+            "-".bright_cyan(),
+            sheet.bright_yellow(),
+            target.display(),
+            rows
+        );
+        imported += 1;
+    }
+
+    if imported == 0 {
+        return Err(anyhow!(
+            "No point table sheets found in {:?} (sheets: {})",
+            args.file,
+            available.join(", ")
+        ));
+    }
+
+    println!(
+        "\n{} Imported {} sheet(s) for channel {}. Run 'monarch sync comsrv' to apply.",
+        "DONE".green(),
+        imported,
+        args.channel_id
+    );
+    Ok(())
+}
+
+/// Parse repeated `key=value` options
+fn parse_pairs(values: &[String]) -> Result<HashMap<String, String>> {
+    values
+        .iter()
+        .map(|pair| {
+            pair.split_once('=')
+                .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+                .ok_or_else(|| anyhow!("Invalid option '{}': expected key=value", pair))
+        })
+        .collect()
 }
 
 pub async fn handle_command(
     cmd: ChannelCommands,
     service_ctx: Option<&ServiceContext>,
     base_url: Option<&str>,
+    config_path: &Path,
 ) -> Result<()> {
     // Determine which mode to use
     #[cfg(feature = "lib-mode")]
@@ -116,6 +237,7 @@ pub async fn handle_command(
                 ChannelCommands::Health => {
                     warn!("Health check not available in offline mode (lib API)");
                 },
+                ChannelCommands::Import(args) => import_command(&args, config_path)?,
             }
         }
     } else {
@@ -170,6 +292,8 @@ pub async fn handle_command(
                 let health = client.check_health().await?;
                 println!("Service health: {}", serde_json::to_string_pretty(&health)?);
             },
+            // Works on configuration files only, no service needed
+            ChannelCommands::Import(args) => import_command(&args, config_path)?,
         }
     }
 
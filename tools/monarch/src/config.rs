@@ -0,0 +1,75 @@
+//! Configuration inspection commands
+
+use anyhow::Result;
+use clap::Subcommand;
+use colored::*;
+use std::path::Path;
+
+use crate::core::linter::{ConfigLinter, LintFinding, LintSeverity};
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Cross-service semantic checks with fix suggestions
+    ///
+    /// Checks routing references, duplicate point IDs, unit consistency, unused
+    /// channels and rule point references across comsrv and modsrv files.
+    Lint {
+        /// Output as JSON (for scripts)
+        #[arg(long)]
+        json: bool,
+
+        /// Exit with an error code on warnings too
+        #[arg(long)]
+        strict: bool,
+    },
+}
+
+pub async fn handle_command(command: ConfigCommands, config_path: &Path) -> Result<()> {
+    match command {
+        ConfigCommands::Lint { json, strict } => {
+            let findings = ConfigLinter::new(config_path).lint()?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&findings)?);
+            } else {
+                print_findings(&findings);
+            }
+
+            let failed = findings
+                .iter()
+                .any(|f| strict || f.severity == LintSeverity::Error);
+            if failed {
+                std::process::exit(1);
+            }
+        },
+    }
+    Ok(())
+}
+
+fn print_findings(findings: &[LintFinding]) {
+    if findings.is_empty() {
+        println!("{} No configuration issues found", "✓".green());
+        return;
+    }
+
+    for finding in findings {
+        let label = match finding.severity {
+            LintSeverity::Error => "error".red().bold(),
+            LintSeverity::Warning => "warning".yellow().bold(),
+        };
+        println!("{}[{}]: {}", label, finding.check, finding.message);
+        println!("  {} {}", "-->".bright_blue(), finding.location);
+        println!("  {} {}", "fix:".cyan(), finding.suggestion);
+        println!();
+    }
+
+    let errors = findings
+        .iter()
+        .filter(|f| f.severity == LintSeverity::Error)
+        .count();
+    println!(
+        "{} error(s), {} warning(s)",
+        errors,
+        findings.len() - errors
+    );
+}
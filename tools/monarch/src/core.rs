@@ -12,9 +12,11 @@ use tracing::info;
 // Module declarations
 pub mod exporter;
 pub mod file_utils;
+pub mod linter;
 pub mod schema;
 pub mod syncer;
 pub mod validator;
+pub mod xlsx;
 
 // Re-export key types
 pub use common::ValidationResult;
@@ -1,7 +1,7 @@
 //! Utility functions for configuration loading and processing
 
 use anyhow::{Context, Result};
-use common::validation::{CsvFields, CsvHeaderValidator};
+use common::validation::{CsvDialects, CsvFields, CsvHeaderValidator, NormalizedCsv};
 use csv::Reader;
 use serde::de::DeserializeOwned;
 use serde_json::Value as JsonValue;
@@ -9,6 +9,8 @@ use std::collections::HashMap;
 use std::path::Path;
 use tracing::{debug, warn};
 
+use super::xlsx;
+
 /// Error that occurred while parsing a specific CSV row
 #[derive(Debug, Clone)]
 pub struct CsvRowError {
@@ -63,7 +65,22 @@ pub fn load_csv_with_errors<P: AsRef<Path>>(path: P) -> CsvResult<HashMap<String
     let file = std::fs::File::open(path)
         .with_context(|| format!("Failed to open CSV file: {:?}", path))?;
 
-    let mut reader = Reader::from_reader(file);
+    records_with_errors(file, path)
+}
+
+/// Load one sheet of an xlsx workbook with error recovery (see `load_csv_with_errors`)
+pub fn load_xlsx_with_errors(path: &Path, sheet: &str) -> CsvResult<HashMap<String, String>> {
+    debug!("Loading sheet '{}' from workbook: {:?}", sheet, path);
+
+    let csv = xlsx::sheet_to_csv(path, &xlsx::SheetOptions::new(sheet))?;
+    records_with_errors(csv.as_slice(), path)
+}
+
+fn records_with_errors<R: std::io::Read>(
+    source: R,
+    path: &Path,
+) -> CsvResult<HashMap<String, String>> {
+    let mut reader = Reader::from_reader(source);
     let headers = reader
         .headers()
         .with_context(|| format!("Failed to read CSV headers: {:?}", path))?
@@ -108,8 +125,9 @@ pub fn load_csv_with_errors<P: AsRef<Path>>(path: P) -> CsvResult<HashMap<String
 /// This version does not fail on individual row deserialization errors,
 /// instead collecting them for reporting while continuing to process valid rows.
 ///
-/// Additionally validates CSV header against expected fields defined by CsvFields trait.
-pub fn load_csv_typed_with_errors<T, P>(path: P) -> CsvResult<T>
+/// Vendor header spellings are mapped onto the fields defined by the CsvFields
+/// trait (see `CsvDialects`) and the mapped header is validated before parsing.
+pub fn load_csv_typed_with_errors<T, P>(path: P, dialects: &CsvDialects) -> CsvResult<T>
 where
     T: DeserializeOwned + CsvFields,
     P: AsRef<Path>,
@@ -120,44 +138,56 @@ where
         path
     );
 
-    let mut errors = Vec::new();
+    let normalized = CsvHeaderValidator::open_normalized::<T>(path, dialects)
+        .with_context(|| format!("Failed to open CSV file: {:?}", path))?;
+    deserialize_with_errors(normalized, path)
+}
 
-    // Step 1: Validate CSV header before processing
-    match CsvHeaderValidator::validate_csv_header::<T>(path) {
-        Ok(validation_result) => {
-            // Add validation errors as CSV row errors
-            for error in validation_result.errors {
-                errors.push(CsvRowError {
-                    row_number: 0, // 0 indicates header error
-                    error,
-                });
-            }
+/// Load one sheet of an xlsx workbook and deserialize with error recovery
+///
+/// The sheet is converted to CSV first, so header mapping and row error
+/// reporting behave exactly like `load_csv_typed_with_errors`.
+pub fn load_xlsx_typed_with_errors<T>(
+    path: &Path,
+    sheet: &str,
+    dialects: &CsvDialects,
+) -> CsvResult<T>
+where
+    T: DeserializeOwned + CsvFields,
+{
+    debug!("Loading sheet '{}' from workbook: {:?}", sheet, path);
 
-            // Log warnings but don't fail
-            for warning in validation_result.warnings {
-                tracing::warn!("CSV header warning: {}", warning);
-            }
+    let csv = xlsx::sheet_to_csv(path, &xlsx::SheetOptions::new(sheet))?;
+    let source = path.join(sheet);
+    let normalized =
+        CsvHeaderValidator::normalize_reader::<T, _>(csv.as_slice(), &source, dialects)?;
+    deserialize_with_errors(normalized, &source)
+}
 
-            // If header validation failed, we still try to parse (best effort)
-            // but the errors are already recorded
-        },
-        Err(e) => {
-            // If we can't even read the file for validation, add as error
-            errors.push(CsvRowError {
-                row_number: 0,
-                error: format!("Header validation failed: {}", e),
-            });
-        },
+fn deserialize_with_errors<T, R>(mut normalized: NormalizedCsv<R>, path: &Path) -> CsvResult<T>
+where
+    T: DeserializeOwned,
+    R: std::io::Read,
+{
+    let mut errors = Vec::new();
+
+    // Header was mapped and validated when opening; report its errors as CSV row errors
+    for error in normalized.validation.errors.drain(..) {
+        errors.push(CsvRowError {
+            row_number: 0, // 0 indicates header error
+            error,
+        });
     }
 
-    // Step 2: Attempt to load and deserialize records
-    let file = std::fs::File::open(path)
-        .with_context(|| format!("Failed to open CSV file: {:?}", path))?;
+    // Log warnings (including applied header renames) but don't fail
+    for warning in &normalized.validation.warnings {
+        tracing::warn!("CSV header warning: {}", warning);
+    }
 
-    let mut reader = Reader::from_reader(file);
+    // Attempt to deserialize records (best effort even if header failed)
     let mut records = Vec::new();
 
-    for (row_number, result) in reader.deserialize().enumerate() {
+    for (row_number, result) in normalized.reader.deserialize().enumerate() {
         let row_number = row_number + 1; // 1-indexed
 
         match result {
@@ -0,0 +1,904 @@
+//! Cross-service configuration lint
+//!
+//! Schema validation (`monarch sync --dry-run`) checks each file on its own.
+//! The linter loads comsrv channels/points, modsrv instances/routing and rule
+//! files together and reports semantic problems across them, each with a
+//! concrete fix suggestion.
+
+use anyhow::{Context, Result};
+use common::config_consistency::{collect_rule_references, RuleInstance};
+use common::validation::CsvDialects;
+use comsrv::core::config::{
+    AdjustmentPoint, ComsrvConfig, ControlPoint, Point, SignalPoint, TelemetryPoint,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use voltage_model::product_lib::{get_builtin_product, get_builtin_products, BuiltinProduct};
+use voltage_model::{NamingPolicy, UniqueScope};
+
+use super::file_utils::{
+    load_csv, load_csv_typed_with_errors, load_xlsx_typed_with_errors, CsvResult,
+};
+use super::xlsx;
+
+/// Lint finding severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    Error,
+    Warning,
+}
+
+/// Single lint finding
+#[derive(Debug, Clone, Serialize)]
+pub struct LintFinding {
+    /// Check identifier (e.g. "routing-unknown-channel")
+    pub check: &'static str,
+    pub severity: LintSeverity,
+    /// File (relative to the config path) and row/item the finding refers to
+    pub location: String,
+    pub message: String,
+    /// Actionable fix
+    pub suggestion: String,
+}
+
+impl LintFinding {
+    fn error(
+        check: &'static str,
+        location: impl Into<String>,
+        message: impl Into<String>,
+        suggestion: impl Into<String>,
+    ) -> Self {
+        Self {
+            check,
+            severity: LintSeverity::Error,
+            location: location.into(),
+            message: message.into(),
+            suggestion: suggestion.into(),
+        }
+    }
+
+    fn warning(
+        check: &'static str,
+        location: impl Into<String>,
+        message: impl Into<String>,
+        suggestion: impl Into<String>,
+    ) -> Self {
+        Self {
+            severity: LintSeverity::Warning,
+            ..Self::error(check, location, message, suggestion)
+        }
+    }
+}
+
+/// Channel point table: routing type, CSV file and xlsx sheet
+struct PointTable {
+    channel_type: &'static str,
+    label: &'static str,
+}
+
+const POINT_TABLES: [PointTable; 4] = [
+    PointTable {
+        channel_type: "T",
+        label: "telemetry",
+    },
+    PointTable {
+        channel_type: "S",
+        label: "signal",
+    },
+    PointTable {
+        channel_type: "C",
+        label: "control",
+    },
+    PointTable {
+        channel_type: "A",
+        label: "adjustment",
+    },
+];
+
+#[derive(Debug)]
+struct ChannelInfo {
+    name: String,
+    enabled: bool,
+}
+
+#[derive(Debug)]
+struct PointInfo {
+    signal_name: String,
+    unit: Option<String>,
+}
+
+#[derive(Debug)]
+struct InstanceInfo {
+    id: Option<u32>,
+    product_name: String,
+}
+
+#[derive(Debug)]
+struct RoutingRow {
+    instance: String,
+    row: usize,
+    channel_id: u32,
+    channel_type: String,
+    channel_point_id: u32,
+    instance_type: String,
+    instance_point_id: u32,
+}
+
+#[derive(Debug)]
+struct RuleReference {
+    file: String,
+    instance: RuleInstance,
+    point_type: String,
+    point_id: u32,
+}
+
+/// Everything the checks look at, loaded once from the config directory
+#[derive(Debug, Default)]
+struct LintModel {
+    channels: BTreeMap<u32, ChannelInfo>,
+    /// (channel_id, channel_type) -> point_id -> point
+    points: HashMap<(u32, &'static str), BTreeMap<u32, PointInfo>>,
+    instances: BTreeMap<String, InstanceInfo>,
+    routing: Vec<RoutingRow>,
+    rule_refs: Vec<RuleReference>,
+    /// `naming` section of global.yaml (built-in rules when absent)
+    naming: NamingPolicy,
+}
+
+/// Cross-service configuration linter
+pub struct ConfigLinter {
+    config_path: PathBuf,
+}
+
+impl ConfigLinter {
+    pub fn new(config_path: impl AsRef<Path>) -> Self {
+        Self {
+            config_path: config_path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Run all checks; errors are sorted before warnings
+    pub fn lint(&self) -> Result<Vec<LintFinding>> {
+        let mut findings = Vec::new();
+        let model = self.load(&mut findings)?;
+
+        check_routing(&model, &mut findings);
+        check_unused_channels(&model, &mut findings);
+        check_products(&model, &mut findings);
+        check_rule_references(&model, &mut findings);
+        check_instance_names(&model, &mut findings);
+
+        findings.sort_by_key(|f| f.severity);
+        Ok(findings)
+    }
+
+    fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(&self.config_path)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    }
+
+    fn load(&self, findings: &mut Vec<LintFinding>) -> Result<LintModel> {
+        let mut model = LintModel::default();
+        self.load_naming(&mut model, findings)?;
+        self.load_channels(&mut model, findings)?;
+        self.load_instances(&mut model)?;
+        self.load_routing(&mut model, findings)?;
+        self.load_rules(&mut model)?;
+        Ok(model)
+    }
+
+    fn load_channels(&self, model: &mut LintModel, findings: &mut Vec<LintFinding>) -> Result<()> {
+        let comsrv_dir = self.config_path.join("comsrv");
+        let yaml_path = comsrv_dir.join("comsrv.yaml");
+        if !yaml_path.exists() {
+            return Ok(());
+        }
+        let content = std::fs::read_to_string(&yaml_path)
+            .with_context(|| format!("Failed to read {:?}", yaml_path))?;
+        let config: ComsrvConfig =
+            serde_yaml::from_str(&content).context("Failed to parse comsrv.yaml")?;
+
+        let dialects_path = comsrv_dir.join("csv_dialects.yaml");
+        let dialects = if dialects_path.exists() {
+            CsvDialects::from_file(&dialects_path)?
+        } else {
+            CsvDialects::default()
+        };
+
+        for channel in &config.channels {
+            model.channels.insert(
+                channel.id(),
+                ChannelInfo {
+                    name: channel.name().to_string(),
+                    enabled: channel.core.enabled,
+                },
+            );
+
+            let dir = comsrv_dir.join(channel.id().to_string());
+            for table in &POINT_TABLES {
+                let points = match table.channel_type {
+                    "T" => self.load_points::<TelemetryPoint>(&dir, table, &dialects, |p| &p.base),
+                    "S" => self.load_points::<SignalPoint>(&dir, table, &dialects, |p| &p.base),
+                    "C" => self.load_points::<ControlPoint>(&dir, table, &dialects, |p| &p.base),
+                    _ => self.load_points::<AdjustmentPoint>(&dir, table, &dialects, |p| &p.base),
+                };
+                let Some((source, points)) = points? else {
+                    continue;
+                };
+
+                let entry = model
+                    .points
+                    .entry((channel.id(), table.channel_type))
+                    .or_default();
+                for point in points {
+                    if let Some(existing) = entry.get(&point.point_id) {
+                        findings.push(LintFinding::error(
+                            "duplicate-point-id",
+                            &source,
+                            format!(
+                                "Point ID {} is defined twice ('{}' and '{}')",
+                                point.point_id, existing.signal_name, point.signal_name
+                            ),
+                            format!("Give one of the rows a unique point_id in {}", source),
+                        ));
+                        continue;
+                    }
+                    entry.insert(
+                        point.point_id,
+                        PointInfo {
+                            signal_name: point.signal_name.clone(),
+                            unit: point.unit.clone().filter(|u| !u.trim().is_empty()),
+                        },
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Load one point table from CSV, falling back to the channel workbook like `sync`
+    fn load_points<T: DeserializeOwned + common::validation::CsvFields>(
+        &self,
+        dir: &Path,
+        table: &PointTable,
+        dialects: &CsvDialects,
+        base: fn(&T) -> &Point,
+    ) -> Result<Option<(String, Vec<Point>)>> {
+        let csv_path = dir.join(format!("{}.csv", table.label));
+        let workbook = dir.join(xlsx::CHANNEL_WORKBOOK);
+
+        let (result, source): (CsvResult<T>, String) = if csv_path.exists() {
+            (
+                load_csv_typed_with_errors::<T, _>(&csv_path, dialects),
+                self.relative(&csv_path),
+            )
+        } else if workbook.exists()
+            && xlsx::sheet_names(&workbook)?
+                .iter()
+                .any(|s| s == table.label)
+        {
+            (
+                load_xlsx_typed_with_errors::<T>(&workbook, table.label, dialects),
+                format!("{}#{}", self.relative(&workbook), table.label),
+            )
+        } else {
+            return Ok(None);
+        };
+
+        // Row parse errors are reported by `sync --dry-run`; lint only the rows that parse
+        let (points, _) = result?;
+        Ok(Some((
+            source,
+            points.iter().map(|p| base(p).clone()).collect(),
+        )))
+    }
+
+    fn load_naming(&self, model: &mut LintModel, findings: &mut Vec<LintFinding>) -> Result<()> {
+        let path = self.config_path.join("global.yaml");
+        if !path.exists() {
+            return Ok(());
+        }
+        let content =
+            std::fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        let data: JsonValue =
+            serde_yaml::from_str(&content).context("Failed to parse global.yaml")?;
+
+        if let Some(naming) = data.get("naming") {
+            match NamingPolicy::from_value(naming.clone()) {
+                Ok(policy) => model.naming = policy,
+                Err(e) => findings.push(LintFinding::error(
+                    "invalid-naming-policy",
+                    "global.yaml",
+                    e.to_string(),
+                    "Fix the naming section; built-in naming rules are used until then",
+                )),
+            }
+        }
+        Ok(())
+    }
+
+    fn load_instances(&self, model: &mut LintModel) -> Result<()> {
+        let path = self.config_path.join("modsrv").join("instances.yaml");
+        if !path.exists() {
+            return Ok(());
+        }
+        let content =
+            std::fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        let data: JsonValue =
+            serde_yaml::from_str(&content).context("Failed to parse instances.yaml")?;
+
+        // Same two formats as `sync`: array with explicit IDs, or legacy name-keyed object
+        let product_of = |value: &JsonValue| {
+            value
+                .get("product_name")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        match data.get("instances") {
+            Some(JsonValue::Array(instances)) => {
+                for instance in instances {
+                    if let Some(name) = instance.get("instance_name").and_then(|v| v.as_str()) {
+                        model.instances.insert(
+                            name.to_string(),
+                            InstanceInfo {
+                                id: instance
+                                    .get("instance_id")
+                                    .and_then(|v| v.as_u64())
+                                    .map(|id| id as u32),
+                                product_name: product_of(instance),
+                            },
+                        );
+                    }
+                }
+            },
+            Some(JsonValue::Object(instances)) => {
+                for (name, instance) in instances {
+                    model.instances.insert(
+                        name.clone(),
+                        InstanceInfo {
+                            id: None,
+                            product_name: product_of(instance),
+                        },
+                    );
+                }
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn load_routing(&self, model: &mut LintModel, findings: &mut Vec<LintFinding>) -> Result<()> {
+        let instances_dir = self.config_path.join("modsrv").join("instances");
+        if !instances_dir.is_dir() {
+            return Ok(());
+        }
+
+        let mut dirs: Vec<PathBuf> = std::fs::read_dir(&instances_dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.join("channel_routing.csv").exists())
+            .collect();
+        dirs.sort();
+
+        for dir in dirs {
+            let instance = dir
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let csv_path = dir.join("channel_routing.csv");
+            let source = self.relative(&csv_path);
+
+            for (idx, row) in load_csv(&csv_path)?.iter().enumerate() {
+                let number =
+                    |field: &str| row.get(field).and_then(|v| v.trim().parse::<u32>().ok());
+                let text = |field: &str| {
+                    row.get(field)
+                        .map(|v| v.trim().to_string())
+                        .unwrap_or_default()
+                };
+                match (
+                    number("channel_id"),
+                    number("channel_point_id"),
+                    number("instance_point_id"),
+                ) {
+                    (Some(channel_id), Some(channel_point_id), Some(instance_point_id)) => {
+                        model.routing.push(RoutingRow {
+                            instance: instance.clone(),
+                            row: idx + 1,
+                            channel_id,
+                            channel_type: text("channel_type"),
+                            channel_point_id,
+                            instance_type: text("instance_type"),
+                            instance_point_id,
+                        })
+                    },
+                    _ => findings.push(LintFinding::error(
+                        "routing-invalid-row",
+                        format!("{} row {}", source, idx + 1),
+                        "channel_id, channel_point_id and instance_point_id must be numbers",
+                        "Fill in numeric IDs or delete the row",
+                    )),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn load_rules(&self, model: &mut LintModel) -> Result<()> {
+        let rules_dir = self.config_path.join("modsrv").join("rules");
+        if !rules_dir.is_dir() {
+            return Ok(());
+        }
+
+        let mut files: Vec<PathBuf> = std::fs::read_dir(&rules_dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .collect();
+        files.sort();
+
+        for path in files {
+            let content = match path.extension().and_then(|e| e.to_str()) {
+                Some("json" | "yaml" | "yml") => std::fs::read_to_string(&path)?,
+                _ => continue,
+            };
+            let data: JsonValue = if path.extension().is_some_and(|e| e == "json") {
+                serde_json::from_str(&content)
+                    .with_context(|| format!("Failed to parse {:?}", path))?
+            } else {
+                serde_yaml::from_str(&content)
+                    .with_context(|| format!("Failed to parse {:?}", path))?
+            };
+
+            let file = self.relative(&path);
+            let mut refs = Vec::new();
+            collect_rule_references(&data, &mut refs);
+            for (instance, point_type, point_id) in refs {
+                // Report each referenced point once per file
+                let seen = model.rule_refs.iter().any(|r| {
+                    r.file == file
+                        && r.instance == instance
+                        && r.point_type == point_type
+                        && r.point_id == point_id
+                });
+                if !seen {
+                    model.rule_refs.push(RuleReference {
+                        file: file.clone(),
+                        instance,
+                        point_type,
+                        point_id,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Points of a product for an instance point type (M or A)
+fn product_points<'a>(
+    product: &'a BuiltinProduct,
+    instance_type: &str,
+) -> &'a [voltage_model::product_lib::PointDef] {
+    if instance_type == "A" {
+        &product.actions
+    } else {
+        &product.measurements
+    }
+}
+
+fn check_routing(model: &LintModel, findings: &mut Vec<LintFinding>) {
+    for row in &model.routing {
+        let location = format!(
+            "modsrv/instances/{}/channel_routing.csv row {}",
+            row.instance, row.row
+        );
+
+        if !model.instances.is_empty() && !model.instances.contains_key(&row.instance) {
+            findings.push(LintFinding::warning(
+                "routing-unknown-instance",
+                &location,
+                format!(
+                    "Instance '{}' is not defined in instances.yaml",
+                    row.instance
+                ),
+                format!(
+                    "Add '{}' to modsrv/instances.yaml or remove modsrv/instances/{}",
+                    row.instance, row.instance
+                ),
+            ));
+        }
+
+        let Some(channel) = model.channels.get(&row.channel_id) else {
+            findings.push(LintFinding::error(
+                "routing-unknown-channel",
+                &location,
+                format!("Channel {} does not exist", row.channel_id),
+                format!(
+                    "Add channel {} to comsrv/comsrv.yaml or use one of: {}",
+                    row.channel_id,
+                    model
+                        .channels
+                        .keys()
+                        .map(|id| id.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            ));
+            continue;
+        };
+
+        let Some(table) = POINT_TABLES
+            .iter()
+            .find(|t| t.channel_type == row.channel_type)
+        else {
+            findings.push(LintFinding::error(
+                "routing-invalid-type",
+                &location,
+                format!("Invalid channel_type '{}'", row.channel_type),
+                "Use T, S, C or A",
+            ));
+            continue;
+        };
+
+        let Some(point) = model
+            .points
+            .get(&(row.channel_id, table.channel_type))
+            .and_then(|points| points.get(&row.channel_point_id))
+        else {
+            findings.push(LintFinding::error(
+                "routing-unknown-point",
+                &location,
+                format!(
+                    "Channel {} ({}) has no {} point {}",
+                    row.channel_id, channel.name, table.label, row.channel_point_id
+                ),
+                format!(
+                    "Add point {} to comsrv/{}/{}.csv or fix channel_point_id",
+                    row.channel_point_id, row.channel_id, table.label
+                ),
+            ));
+            continue;
+        };
+
+        // Unit consistency between the channel point and the product point it feeds
+        let product = model
+            .instances
+            .get(&row.instance)
+            .and_then(|i| get_builtin_product(&i.product_name));
+        let product_unit = product.and_then(|p| {
+            product_points(p, &row.instance_type)
+                .iter()
+                .find(|d| d.id == row.instance_point_id)
+                .map(|d| d.unit.trim())
+                .filter(|u| !u.is_empty())
+        });
+        if let (Some(unit), Some(product_unit)) = (point.unit.as_deref(), product_unit) {
+            if !unit.trim().eq_ignore_ascii_case(product_unit) {
+                findings.push(LintFinding::warning(
+                    "unit-mismatch",
+                    &location,
+                    format!(
+                        "'{}' is in {} but {}{} expects {}",
+                        point.signal_name,
+                        unit.trim(),
+                        row.instance_type,
+                        row.instance_point_id,
+                        product_unit
+                    ),
+                    format!(
+                        "Set unit to '{}' in comsrv/{}/{}.csv and adjust scale accordingly",
+                        product_unit, row.channel_id, table.label
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+fn check_unused_channels(model: &LintModel, findings: &mut Vec<LintFinding>) {
+    let routed: HashSet<u32> = model.routing.iter().map(|r| r.channel_id).collect();
+    for (id, channel) in &model.channels {
+        if channel.enabled && !routed.contains(id) {
+            findings.push(LintFinding::warning(
+                "unused-channel",
+                "comsrv/comsrv.yaml",
+                format!(
+                    "Channel {} ({}) is not routed to any instance",
+                    id, channel.name
+                ),
+                format!(
+                    "Route it in a channel_routing.csv or set enabled: false on channel {}",
+                    id
+                ),
+            ));
+        }
+    }
+}
+
+fn check_products(model: &LintModel, findings: &mut Vec<LintFinding>) {
+    // Product checks need the built-in library; skip them in builds without it
+    if get_builtin_products().is_empty() {
+        return;
+    }
+
+    for (name, instance) in &model.instances {
+        if get_builtin_product(&instance.product_name).is_none() {
+            findings.push(LintFinding::error(
+                "unknown-product",
+                "modsrv/instances.yaml",
+                format!(
+                    "Instance '{}' uses unknown product '{}'",
+                    name, instance.product_name
+                ),
+                "Run 'monarch models products list' and use one of the listed names",
+            ));
+        }
+    }
+
+    for row in &model.routing {
+        let Some(product) = model
+            .instances
+            .get(&row.instance)
+            .and_then(|i| get_builtin_product(&i.product_name))
+        else {
+            continue;
+        };
+        let exists = product_points(product, &row.instance_type)
+            .iter()
+            .any(|d| d.id == row.instance_point_id);
+        if !exists {
+            findings.push(LintFinding::error(
+                "routing-unknown-instance-point",
+                format!(
+                    "modsrv/instances/{}/channel_routing.csv row {}",
+                    row.instance, row.row
+                ),
+                format!(
+                    "Product '{}' has no {} point {}",
+                    product.name, row.instance_type, row.instance_point_id
+                ),
+                format!(
+                    "Use a point from 'monarch models products show {}'",
+                    product.name
+                ),
+            ));
+        }
+    }
+}
+
+fn check_rule_references(model: &LintModel, findings: &mut Vec<LintFinding>) {
+    let by_id: HashMap<u32, (&String, &InstanceInfo)> = model
+        .instances
+        .iter()
+        .filter_map(|(name, info)| info.id.map(|id| (id, (name, info))))
+        .collect();
+
+    for reference in &model.rule_refs {
+        let (label, instance) = match &reference.instance {
+            RuleInstance::Name(name) => (name.clone(), model.instances.get(name)),
+            RuleInstance::Id(id) => {
+                // Numeric references can only be resolved when instances.yaml carries IDs
+                if by_id.is_empty() {
+                    continue;
+                }
+                (format!("#{}", id), by_id.get(id).map(|(_, info)| *info))
+            },
+        };
+        let point = format!("{}.{}{}", label, reference.point_type, reference.point_id);
+
+        let Some(instance) = instance else {
+            findings.push(LintFinding::error(
+                "rule-unknown-instance",
+                &reference.file,
+                format!(
+                    "Rule references {} but instance {} does not exist",
+                    point, label
+                ),
+                "Define the instance in modsrv/instances.yaml or fix the reference",
+            ));
+            continue;
+        };
+
+        let Some(product) = get_builtin_product(&instance.product_name) else {
+            continue;
+        };
+        let exists = product_points(product, &reference.point_type)
+            .iter()
+            .any(|d| d.id == reference.point_id);
+        if !exists {
+            findings.push(LintFinding::error(
+                "rule-unknown-point",
+                &reference.file,
+                format!(
+                    "Rule references {} but product '{}' has no such point",
+                    point, product.name
+                ),
+                format!(
+                    "Use a point from 'monarch models products show {}'",
+                    product.name
+                ),
+            ));
+        }
+    }
+}
+
+fn check_instance_names(model: &LintModel, findings: &mut Vec<LintFinding>) {
+    let policy = &model.naming.instance;
+    let names: Vec<&String> = model.instances.keys().collect();
+
+    for (i, name) in names.iter().enumerate() {
+        if let Err(e) = policy.validate(name) {
+            findings.push(LintFinding::error(
+                "instance-name-policy",
+                "modsrv/instances.yaml",
+                e.to_string(),
+                "Rename the instance to follow the naming section of global.yaml",
+            ));
+        }
+
+        // Exact duplicates cannot exist in the map; only policy collisions are left
+        if let Some(other) = names[..i]
+            .iter()
+            .find(|other| policy.same_name(other, name))
+        {
+            findings.push(LintFinding::error(
+                "instance-name-conflict",
+                "modsrv/instances.yaml",
+                format!(
+                    "Instances '{}' and '{}' differ only in case, which the naming policy forbids",
+                    other, name
+                ),
+                "Give one of the instances a distinct name",
+            ));
+        }
+
+        if policy.unique == UniqueScope::System {
+            if let Some((id, channel)) = model
+                .channels
+                .iter()
+                .find(|(_, channel)| policy.same_name(&channel.name, name))
+            {
+                findings.push(LintFinding::error(
+                    "instance-name-conflict",
+                    "modsrv/instances.yaml",
+                    format!(
+                        "Instance '{}' has the same name as channel {} ('{}')",
+                        name, id, channel.name
+                    ),
+                    "Names must be unique across instances and channels (naming unique: system)",
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, file: &str, content: &str) {
+        let path = dir.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_lint_cross_service_findings() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        write(
+            root,
+            "comsrv/comsrv.yaml",
+            r#"
+service:
+  name: comsrv
+channels:
+  - id: 1
+    name: pcs
+    protocol: virtual
+  - id: 2
+    name: spare
+    protocol: virtual
+"#,
+        );
+        write(
+            root,
+            "comsrv/1/telemetry.csv",
+            "point_id,signal_name,scale,offset,unit,reverse,data_type\n\
+             1,Voltage,1,0,V,false,float32\n\
+             1,Current,1,0,A,false,float32\n",
+        );
+        write(
+            root,
+            "modsrv/instances.yaml",
+            "instances:\n  pcs_01:\n    product_name: PCS\n",
+        );
+        write(
+            root,
+            "modsrv/instances/pcs_01/channel_routing.csv",
+            "channel_id,channel_type,channel_point_id,instance_type,instance_point_id\n\
+             1,T,1,M,1\n\
+             1,T,9,M,2\n\
+             5,T,1,M,3\n",
+        );
+        write(
+            root,
+            "modsrv/rules/r.yaml",
+            "trigger:\n  expression: \"ghost_01.M1 > 0\"\n",
+        );
+
+        let findings = ConfigLinter::new(root).lint().unwrap();
+        let checks: Vec<&str> = findings.iter().map(|f| f.check).collect();
+
+        assert!(checks.contains(&"duplicate-point-id"));
+        assert!(checks.contains(&"routing-unknown-point"));
+        assert!(checks.contains(&"routing-unknown-channel"));
+        assert!(checks.contains(&"unused-channel"));
+        assert!(checks.contains(&"rule-unknown-instance"));
+        assert!(findings.iter().all(|f| !f.suggestion.is_empty()));
+
+        // Built-in naming rules apply without a naming section
+        assert!(!checks.contains(&"instance-name-policy"));
+
+        // Errors come first
+        let first_warning = findings
+            .iter()
+            .position(|f| f.severity == LintSeverity::Warning)
+            .unwrap();
+        assert!(findings[first_warning..]
+            .iter()
+            .all(|f| f.severity == LintSeverity::Warning));
+    }
+
+    #[test]
+    fn test_lint_naming_policy() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        write(
+            root,
+            "global.yaml",
+            r#"
+naming:
+  instance:
+    pattern: "^[a-z]+_[0-9]{2}$"
+    case_sensitive: false
+    unique: system
+"#,
+        );
+        write(
+            root,
+            "comsrv/comsrv.yaml",
+            "service:\n  name: comsrv\nchannels:\n  - id: 1\n    name: PCS_01\n    protocol: virtual\n",
+        );
+        write(
+            root,
+            "modsrv/instances.yaml",
+            "instances:\n  pcs_01:\n    product_name: PCS\n  BMS-1:\n    product_name: Battery\n  bms_01:\n    product_name: Battery\n  Bms_01:\n    product_name: Battery\n",
+        );
+
+        let findings = ConfigLinter::new(root).lint().unwrap();
+        let messages = |check: &str| -> Vec<String> {
+            findings
+                .iter()
+                .filter(|f| f.check == check)
+                .map(|f| f.message.clone())
+                .collect()
+        };
+
+        let policy = messages("instance-name-policy");
+        assert_eq!(policy.len(), 2, "{:?}", policy);
+        assert!(policy.iter().any(|m| m.contains("BMS-1")));
+        assert!(policy.iter().any(|m| m.contains("Bms_01")));
+
+        let conflicts = messages("instance-name-conflict");
+        assert_eq!(conflicts.len(), 2, "{:?}", conflicts);
+        assert!(conflicts.iter().any(|m| m.contains("channel 1")));
+    }
+}
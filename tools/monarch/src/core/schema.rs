@@ -1,93 +1,35 @@
 //! Database schema initialization
 //!
 //! Provides unified database initialization for all VoltageEMS tables.
-//! All tables are created in a single `voltage.db` file.
+//! All tables are created in a single `voltage.db` file by applying each
+//! component's versioned migrations (see `voltage_infra::sqlite::migrations`).
 
 use anyhow::{Context, Result};
-use sqlx::{Row, SqlitePool};
+use common::sqlite::migrations::{MigrationReport, Migrator};
+use sqlx::SqlitePool;
 use std::path::Path;
-use tracing::{info, warn};
-
-// Import DDL constants from services (lib mode)
-use comsrv::core::config as comsrv_schema;
-use modsrv::config as modsrv_schema;
+use tracing::info;
 
 use super::file_utils;
 
-// ============================================================================
-// Rules DDL (defined locally since rules are managed by monarch)
-// ============================================================================
-
-/// Rules table SQL
-const RULE_CHAINS_TABLE: &str = r#"
-    CREATE TABLE IF NOT EXISTS rules (
-        id INTEGER PRIMARY KEY,
-        name TEXT NOT NULL,
-        description TEXT,
-        enabled BOOLEAN DEFAULT TRUE,
-        priority INTEGER DEFAULT 0,
-        cooldown_ms INTEGER DEFAULT 0,
-        nodes_json TEXT NOT NULL,
-        flow_json TEXT,
-        format TEXT DEFAULT 'vue-flow',
-        created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-    )
-"#;
-
-/// Rule history table SQL
-const RULE_HISTORY_TABLE: &str = r#"
-    CREATE TABLE IF NOT EXISTS rule_history (
-        id INTEGER PRIMARY KEY AUTOINCREMENT,
-        rule_id INTEGER NOT NULL,
-        triggered_at TIMESTAMP NOT NULL,
-        execution_result TEXT,
-        error TEXT,
-        FOREIGN KEY (rule_id) REFERENCES rules(id)
-    )
-"#;
-
-/// Migrate legacy rules table if needed
-///
-/// Checks if the rules table uses the old schema (id TEXT) and rebuilds it
-/// with the correct schema (id INTEGER). This handles upgrades from older versions.
-async fn migrate_rules_table_if_needed(pool: &SqlitePool) -> Result<()> {
-    // Check if rules table exists and get id column type
-    let row = sqlx::query("SELECT type FROM pragma_table_info('rules') WHERE name = 'id'")
-        .fetch_optional(pool)
-        .await?;
-
-    if let Some(row) = row {
-        let col_type: String = row.try_get("type")?;
-        // Optimization: eq_ignore_ascii_case avoids to_uppercase() allocation
-        if col_type.eq_ignore_ascii_case("TEXT") {
-            warn!("Detected legacy rules table (id TEXT), rebuilding with INTEGER schema...");
-
-            // Drop old tables (will be recreated with correct schema)
-            sqlx::query("DROP TABLE IF EXISTS rule_history")
-                .execute(pool)
-                .await?;
-            sqlx::query("DROP TABLE IF EXISTS rules")
-                .execute(pool)
-                .await?;
-
-            info!("Legacy rules table dropped, will be recreated with correct schema");
-        }
-    }
-
-    Ok(())
+/// Migrators for every component sharing voltage.db, in dependency order
+pub fn migrators() -> [Migrator; 3] {
+    [
+        comsrv::core::config::migrations::migrator(),
+        modsrv::migrations::migrator(),
+        voltage_rules::migrations::migrator(),
+    ]
 }
 
 /// Initialize all database tables in voltage.db
 ///
-/// Creates all tables, indexes, and triggers needed by VoltageEMS services.
-/// This is a unified initialization that replaces the old per-service approach.
+/// Applies pending migrations of every component; existing data is kept.
 ///
 /// @input db_path: `impl AsRef<Path>` - Path to SQLite database file
-/// @output `Result<()>` - Success or initialization error
-/// @throws anyhow::Error - Database connection or schema creation failure
-/// @side-effects Creates database file if not exists, creates all tables/indexes/triggers
-pub async fn init_database(db_path: impl AsRef<Path>) -> Result<()> {
+/// @output `Result<Vec<MigrationReport>>` - Per-component migration results
+/// @throws anyhow::Error - Database connection, migration failure or checksum mismatch
+/// @side-effects Creates database file if not exists, applies pending migrations
+pub async fn init_database(db_path: impl AsRef<Path>) -> Result<Vec<MigrationReport>> {
     let db_path = db_path.as_ref();
 
     // Ensure data directory exists
@@ -103,173 +45,11 @@ pub async fn init_database(db_path: impl AsRef<Path>) -> Result<()> {
     // Set file permissions for Docker compatibility
     file_utils::set_database_permissions(db_path)?;
 
-    // Run schema migrations before creating tables
-    migrate_rules_table_if_needed(&pool).await?;
-
-    // === Shared tables ===
-    sqlx::query(comsrv_schema::SERVICE_CONFIG_TABLE)
-        .execute(&pool)
-        .await?;
-    sqlx::query(comsrv_schema::SYNC_METADATA_TABLE)
-        .execute(&pool)
-        .await?;
-
-    // === Channel & Point tables (comsrv) ===
-    sqlx::query(comsrv_schema::CHANNELS_TABLE)
-        .execute(&pool)
-        .await?;
-    sqlx::query(comsrv_schema::TELEMETRY_POINTS_TABLE)
-        .execute(&pool)
-        .await?;
-    sqlx::query(comsrv_schema::SIGNAL_POINTS_TABLE)
-        .execute(&pool)
-        .await?;
-    sqlx::query(comsrv_schema::CONTROL_POINTS_TABLE)
-        .execute(&pool)
-        .await?;
-    sqlx::query(comsrv_schema::ADJUSTMENT_POINTS_TABLE)
-        .execute(&pool)
-        .await?;
-
-    // === Instance tables (modsrv) ===
-    // Note: Product tables (products, measurement_points, action_points, property_templates)
-    // have been removed. Products are now compile-time built-in constants from voltage-model crate.
-    sqlx::query(modsrv_schema::INSTANCES_TABLE)
-        .execute(&pool)
-        .await?;
-    sqlx::query(modsrv_schema::MEASUREMENT_ROUTING_TABLE)
-        .execute(&pool)
-        .await?;
-    sqlx::query(modsrv_schema::ACTION_ROUTING_TABLE)
-        .execute(&pool)
-        .await?;
-
-    // === Rule tables (rules engine) ===
-    sqlx::query(RULE_CHAINS_TABLE).execute(&pool).await?;
-    sqlx::query(RULE_HISTORY_TABLE).execute(&pool).await?;
-
-    // === Indexes ===
-    create_indexes(&pool).await?;
-
-    // === Triggers ===
-    create_triggers(&pool).await?;
+    let mut reports = Vec::new();
+    for migrator in migrators() {
+        reports.push(migrator.run(&pool).await?);
+    }
 
     info!("DB init: {}", db_path.display());
-    Ok(())
-}
-
-/// Create all database indexes
-async fn create_indexes(pool: &SqlitePool) -> Result<()> {
-    // Point tables indexes
-    sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_telemetry_points_channel ON telemetry_points(channel_id)",
-    )
-    .execute(pool)
-    .await?;
-    sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_signal_points_channel ON signal_points(channel_id)",
-    )
-    .execute(pool)
-    .await?;
-    sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_control_points_channel ON control_points(channel_id)",
-    )
-    .execute(pool)
-    .await?;
-    sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_adjustment_points_channel ON adjustment_points(channel_id)",
-    )
-    .execute(pool)
-    .await?;
-
-    // Instance routing indexes
-    // Note: Product indexes removed - products are compile-time constants
-    sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_measurement_routing_instance ON measurement_routing(instance_id)",
-    )
-    .execute(pool)
-    .await?;
-    sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_action_routing_instance ON action_routing(instance_id)",
-    )
-    .execute(pool)
-    .await?;
-
-    // Rule indexes
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_rules_enabled ON rules(enabled)")
-        .execute(pool)
-        .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_rule_history_rule ON rule_history(rule_id)")
-        .execute(pool)
-        .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_rule_history_time ON rule_history(triggered_at)")
-        .execute(pool)
-        .await?;
-
-    Ok(())
-}
-
-/// Create all database triggers for automatic cleanup
-async fn create_triggers(pool: &SqlitePool) -> Result<()> {
-    // When a telemetry point is deleted, remove corresponding measurement_routing records
-    sqlx::query(
-        "CREATE TRIGGER IF NOT EXISTS cleanup_routing_on_telemetry_delete
-         AFTER DELETE ON telemetry_points
-         FOR EACH ROW
-         BEGIN
-             DELETE FROM measurement_routing
-             WHERE channel_id = OLD.channel_id
-               AND channel_type = 'T'
-               AND channel_point_id = OLD.point_id;
-         END",
-    )
-    .execute(pool)
-    .await?;
-
-    // When a signal point is deleted, remove corresponding measurement_routing records
-    sqlx::query(
-        "CREATE TRIGGER IF NOT EXISTS cleanup_routing_on_signal_delete
-         AFTER DELETE ON signal_points
-         FOR EACH ROW
-         BEGIN
-             DELETE FROM measurement_routing
-             WHERE channel_id = OLD.channel_id
-               AND channel_type = 'S'
-               AND channel_point_id = OLD.point_id;
-         END",
-    )
-    .execute(pool)
-    .await?;
-
-    // When a control point is deleted, remove corresponding action_routing records
-    sqlx::query(
-        "CREATE TRIGGER IF NOT EXISTS cleanup_routing_on_control_delete
-         AFTER DELETE ON control_points
-         FOR EACH ROW
-         BEGIN
-             DELETE FROM action_routing
-             WHERE channel_id = OLD.channel_id
-               AND channel_type = 'C'
-               AND channel_point_id = OLD.point_id;
-         END",
-    )
-    .execute(pool)
-    .await?;
-
-    // When an adjustment point is deleted, remove corresponding action_routing records
-    sqlx::query(
-        "CREATE TRIGGER IF NOT EXISTS cleanup_routing_on_adjustment_delete
-         AFTER DELETE ON adjustment_points
-         FOR EACH ROW
-         BEGIN
-             DELETE FROM action_routing
-             WHERE channel_id = OLD.channel_id
-               AND channel_type = 'A'
-               AND channel_point_id = OLD.point_id;
-         END",
-    )
-    .execute(pool)
-    .await?;
-
-    Ok(())
+    Ok(reports)
 }
@@ -4,8 +4,9 @@
 //! to the SQLite database.
 
 use anyhow::{Context, Result};
-use common::validation::CsvFields;
+use common::validation::{CsvDialects, CsvFields};
 use comsrv::core::config::ComsrvConfig;
+use comsrv::core::plugins::ProtocolPlugins;
 use modsrv::config::ModsrvConfig;
 use serde::de::DeserializeOwned;
 use serde_json::Value as JsonValue;
@@ -13,9 +14,14 @@ use sqlx::{Sqlite, SqlitePool, Transaction};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use tracing::{debug, info, warn};
+use voltage_model::PointType;
 
-use super::file_utils::{flatten_json, load_csv, load_csv_typed_with_errors, load_csv_with_errors};
+use super::file_utils::{
+    flatten_json, load_csv, load_csv_typed_with_errors, load_csv_with_errors,
+    load_xlsx_typed_with_errors, load_xlsx_with_errors,
+};
 use super::schema;
+use super::xlsx;
 
 /// Normalize protocol_data numeric fields to JSON numbers (not strings)
 ///
@@ -113,10 +119,20 @@ fn normalize_protocol_mapping(
                 }
             }
         },
-        _ => {
-            // Unknown protocol: keep all as strings
-            for (key, value) in mapping {
-                normalized.insert(key, JsonValue::String(value));
+        other => {
+            // Plugin protocols: column kinds drive the conversion
+            // (unknown protocols keep all values as strings)
+            let cells: serde_json::Map<String, JsonValue> = mapping
+                .into_iter()
+                .map(|(key, value)| (key, JsonValue::String(value)))
+                .collect();
+            let cells = JsonValue::Object(cells);
+            let cells = match ProtocolPlugins::global().get(other) {
+                Some(plugin) => plugin.normalize_mapping(&cells),
+                None => cells,
+            };
+            if let JsonValue::Object(cells) = cells {
+                normalized.extend(cells);
             }
         },
     }
@@ -157,6 +173,8 @@ struct PointSyncConfig<'a> {
     table_name: &'a str,
     /// Type label for error messages (e.g., "telemetry")
     type_label: &'a str,
+    /// Point type checked against the protocol plugin
+    point_type: PointType,
 }
 
 /// Extracted point fields for database insertion
@@ -169,6 +187,30 @@ struct PointFields {
     offset: f64,
     reverse: bool,
     data_type: String,
+    /// Write scope (C/A points only)
+    access: Option<String>,
+}
+
+/// Load the vendor header alias table (`csv_dialects.yaml`) if present
+fn load_csv_dialects(config_dir: &Path, errors: &mut Vec<SyncError>) -> CsvDialects {
+    let path = config_dir.join("csv_dialects.yaml");
+    if !path.exists() {
+        return CsvDialects::default();
+    }
+
+    match CsvDialects::from_file(&path) {
+        Ok(dialects) => {
+            debug!("CSV dialects: {}", dialects.dialects.len());
+            dialects
+        },
+        Err(e) => {
+            errors.push(SyncError {
+                item: format!("CSV dialects: {}", path.display()),
+                error: e.to_string(),
+            });
+            CsvDialects::default()
+        },
+    }
 }
 
 /// Generic point type sync function
@@ -182,6 +224,7 @@ async fn sync_point_type<T, F>(
     channel_id: i32,
     protocol: &str,
     config: &PointSyncConfig<'_>,
+    dialects: &CsvDialects,
     extract_fields: F,
     errors: &mut Vec<SyncError>,
 ) -> Result<usize>
@@ -190,39 +233,79 @@ where
     F: Fn(&T) -> PointFields,
 {
     let csv_file = path.join(config.csv_filename);
-    if !csv_file.exists() {
-        return Ok(0);
-    }
+    let mapping_file = path.join(config.mapping_filename);
 
-    let (points, csv_errors) = load_csv_typed_with_errors::<T, _>(&csv_file)?;
+    // Without CSVs, fall back to points.xlsx with one sheet per point type
+    // (e.g. "telemetry" and "telemetry_mapping")
+    let workbook = path.join(xlsx::CHANNEL_WORKBOOK);
+    let sheets = if (!csv_file.exists() || !mapping_file.exists()) && workbook.exists() {
+        xlsx::sheet_names(&workbook)?
+    } else {
+        Vec::new()
+    };
+    let mapping_sheet = format!("{}_mapping", config.type_label);
+
+    let (points, csv_errors, source) = if csv_file.exists() {
+        let (points, csv_errors) = load_csv_typed_with_errors::<T, _>(&csv_file, dialects)?;
+        (points, csv_errors, config.csv_filename.to_string())
+    } else if sheets.iter().any(|s| s == config.type_label) {
+        let (points, csv_errors) =
+            load_xlsx_typed_with_errors::<T>(&workbook, config.type_label, dialects)?;
+        let source = format!("{}#{}", xlsx::CHANNEL_WORKBOOK, config.type_label);
+        (points, csv_errors, source)
+    } else {
+        return Ok(0);
+    };
 
     // Collect CSV parsing errors
     for csv_error in &csv_errors {
         errors.push(SyncError::from_csv_error(
             csv_error,
-            &format!("channel-{}/{}", channel_id, config.csv_filename),
+            &format!("channel-{}/{}", channel_id, source),
         ));
     }
 
     // Load corresponding mappings if they exist
-    let mapping_file = path.join(config.mapping_filename);
-    let mappings_json = if mapping_file.exists() {
-        let (mappings, mapping_csv_errors) = load_csv_with_errors(&mapping_file)?;
-
+    let mappings = if mapping_file.exists() {
+        Some((
+            load_csv_with_errors(&mapping_file)?,
+            config.mapping_filename.to_string(),
+        ))
+    } else if sheets.contains(&mapping_sheet) {
+        Some((
+            load_xlsx_with_errors(&workbook, &mapping_sheet)?,
+            format!("{}#{}", xlsx::CHANNEL_WORKBOOK, mapping_sheet),
+        ))
+    } else {
+        None
+    };
+    let mappings_json = if let Some(((mappings, mapping_csv_errors), mapping_source)) = mappings {
         // Collect mapping CSV errors
         for csv_error in &mapping_csv_errors {
             errors.push(SyncError::from_csv_error(
                 csv_error,
-                &format!("channel-{}/{}", channel_id, config.mapping_filename),
+                &format!("channel-{}/{}", channel_id, mapping_source),
             ));
         }
 
         // Normalize and convert to JSON, indexed by point_id
+        let plugin = ProtocolPlugins::global().get(protocol);
         let mut mapping_map = HashMap::new();
         for mapping in mappings {
             if let Some(point_id) = mapping.get("point_id") {
                 let point_id = point_id.clone();
                 let normalized = normalize_protocol_mapping(protocol, mapping);
+
+                // Check the row against the columns the protocol plugin declares
+                if let (Some(plugin), Ok(id)) = (&plugin, point_id.trim().parse::<u32>()) {
+                    let value = serde_json::to_value(&normalized).unwrap_or_default();
+                    for error in plugin.validate_mapping(config.point_type, id, &value) {
+                        errors.push(SyncError {
+                            item: format!("channel-{}/{}", channel_id, mapping_source),
+                            error,
+                        });
+                    }
+                }
                 mapping_map.insert(point_id, normalized);
             }
         }
@@ -233,11 +316,17 @@ where
 
     let mut count = 0;
 
-    // Build the INSERT query with table name
+    // Build the INSERT query with table name (C/A tables carry the write scope)
+    let has_access = matches!(
+        config.point_type,
+        PointType::Control | PointType::Adjustment
+    );
     let insert_sql = format!(
-        "INSERT INTO {} (point_id, channel_id, signal_name, scale, offset, unit, reverse, data_type, description, protocol_mappings)
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-        config.table_name
+        "INSERT INTO {} (point_id, channel_id, signal_name, scale, offset, unit, reverse, data_type, description, protocol_mappings{})
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?{})",
+        config.table_name,
+        if has_access { ", access" } else { "" },
+        if has_access { ", ?" } else { "" },
     );
 
     for point in points {
@@ -249,7 +338,7 @@ where
             .map(|m| serde_json::to_string(m).unwrap_or_else(|_| "{}".to_string()))
             .unwrap_or_else(|| "null".to_string());
 
-        if let Err(e) = sqlx::query(&insert_sql)
+        let mut query = sqlx::query(&insert_sql)
             .bind(fields.point_id)
             .bind(channel_id)
             .bind(&fields.signal_name)
@@ -259,10 +348,11 @@ where
             .bind(fields.reverse)
             .bind(&fields.data_type)
             .bind(&fields.description)
-            .bind(&protocol_mappings)
-            .execute(&mut **tx)
-            .await
-        {
+            .bind(&protocol_mappings);
+        if has_access {
+            query = query.bind(&fields.access);
+        }
+        if let Err(e) = query.execute(&mut **tx).await {
             errors.push(SyncError {
                 item: format!(
                     "channel-{}/{}/point-{}",
@@ -767,8 +857,15 @@ impl ConfigSyncer {
         config_dir: &Path,
         errors: &mut Vec<SyncError>,
     ) -> Result<usize> {
-        use comsrv::core::config::{AdjustmentPoint, ControlPoint, SignalPoint, TelemetryPoint};
+        use comsrv::core::config::{
+            AdjustmentPoint, ControlPoint, PointAccess, SignalPoint, TelemetryPoint,
+        };
+
+        // Unrestricted points keep a NULL scope
+        let scope =
+            |access: PointAccess| (access != PointAccess::Any).then(|| access.as_str().to_string());
 
+        let dialects = load_csv_dialects(config_dir, errors);
         let mut total_count = 0;
 
         // Iterate over every channel directory.
@@ -811,7 +908,9 @@ impl ConfigSyncer {
                     mapping_filename: "mapping/telemetry_mapping.csv",
                     table_name: "telemetry_points",
                     type_label: "telemetry",
+                    point_type: PointType::Telemetry,
                 },
+                &dialects,
                 |p| PointFields {
                     point_id: p.base.point_id,
                     signal_name: p.base.signal_name.clone(),
@@ -821,6 +920,7 @@ impl ConfigSyncer {
                     offset: p.offset,
                     reverse: p.reverse,
                     data_type: p.data_type.clone(),
+                    access: None,
                 },
                 errors,
             )
@@ -837,7 +937,9 @@ impl ConfigSyncer {
                     mapping_filename: "mapping/signal_mapping.csv",
                     table_name: "signal_points",
                     type_label: "signal",
+                    point_type: PointType::Signal,
                 },
+                &dialects,
                 |p| PointFields {
                     point_id: p.base.point_id,
                     signal_name: p.base.signal_name.clone(),
@@ -847,6 +949,7 @@ impl ConfigSyncer {
                     offset: 0.0,
                     reverse: p.reverse,
                     data_type: "int".to_string(),
+                    access: None,
                 },
                 errors,
             )
@@ -863,7 +966,9 @@ impl ConfigSyncer {
                     mapping_filename: "mapping/control_mapping.csv",
                     table_name: "control_points",
                     type_label: "control",
+                    point_type: PointType::Control,
                 },
+                &dialects,
                 |p| PointFields {
                     point_id: p.base.point_id,
                     signal_name: p.base.signal_name.clone(),
@@ -873,6 +978,7 @@ impl ConfigSyncer {
                     offset: 0.0,
                     reverse: false,
                     data_type: "bool".to_string(),
+                    access: scope(p.access),
                 },
                 errors,
             )
@@ -889,7 +995,9 @@ impl ConfigSyncer {
                     mapping_filename: "mapping/adjustment_mapping.csv",
                     table_name: "adjustment_points",
                     type_label: "adjustment",
+                    point_type: PointType::Adjustment,
                 },
+                &dialects,
                 |p| PointFields {
                     point_id: p.base.point_id,
                     signal_name: p.base.signal_name.clone(),
@@ -899,6 +1007,7 @@ impl ConfigSyncer {
                     offset: p.offset,
                     reverse: false,
                     data_type: p.data_type.clone(),
+                    access: scope(p.access),
                 },
                 errors,
             )
@@ -4,6 +4,7 @@
 //! using the shared validation framework.
 
 use anyhow::Result;
+use serde_json::{json, Value as JsonValue};
 use std::path::{Path, PathBuf};
 use tracing::{debug, info, warn};
 
@@ -14,7 +15,10 @@ use common::{
 
 // Import config types from service libs (lib-mode)
 use comsrv::core::config::ComsrvConfig;
-use modsrv::config::{ModsrvConfig, RulesConfig};
+use modsrv::config::{routing_rule_set, ModsrvConfig, RulesConfig};
+use voltage_model::NamingPolicy;
+
+use super::file_utils::load_csv;
 
 // Type aliases for validators
 type ComsrvValidator = GenericValidator<ComsrvConfig>;
@@ -120,7 +124,51 @@ impl ConfigValidator {
         // Load and validate using shared framework
         // Note: Errors from from_file already include file path + line number + reason
         let validator = ModsrvValidator::from_file(&yaml_path)?;
-        validator.validate(self.validation_level)
+        let mut result = validator.validate(self.validation_level)?;
+        result.merge(self.validate_routing_references()?);
+        Ok(result)
+    }
+
+    /// Validate instance routing against comsrv channels (cross-service references)
+    fn validate_routing_references(&self) -> Result<ValidationResult> {
+        let mut routing = Vec::new();
+        let instances_dir = self.config_path.join("modsrv").join("instances");
+        if instances_dir.is_dir() {
+            for entry in std::fs::read_dir(&instances_dir)? {
+                let instance_dir = entry?.path();
+                let routing_csv = instance_dir.join("channel_routing.csv");
+                if !routing_csv.exists() {
+                    continue;
+                }
+                let instance = instance_dir
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                for row in load_csv(&routing_csv)? {
+                    let mut row: serde_json::Map<String, JsonValue> = row
+                        .into_iter()
+                        .map(|(k, v)| (k, JsonValue::String(v)))
+                        .collect();
+                    row.insert("instance".to_string(), JsonValue::String(instance.clone()));
+                    routing.push(JsonValue::Object(row));
+                }
+            }
+        }
+
+        if routing.is_empty() {
+            return Ok(validation_ok());
+        }
+
+        // Channels come from comsrv.yaml; a missing or broken file is reported by comsrv itself
+        let comsrv_yaml = self.config_path.join("comsrv").join("comsrv.yaml");
+        let channels = std::fs::read_to_string(&comsrv_yaml)
+            .ok()
+            .and_then(|content| serde_yaml::from_str::<ComsrvConfig>(&content).ok())
+            .map(|config| config.channels)
+            .unwrap_or_default();
+
+        let document = json!({ "channels": channels, "routing": routing });
+        Ok(routing_rule_set().evaluate_value(&document, ValidationLevel::Business))
     }
 
     /// Validate rules configuration
@@ -155,9 +203,23 @@ impl ConfigValidator {
 
         // Load YAML and perform basic validation
         let yaml_content = std::fs::read_to_string(&yaml_path)?;
-        match serde_yaml::from_str::<serde_yaml::Value>(&yaml_content) {
-            Ok(_) => {
-                // Global config is valid YAML
+        match serde_yaml::from_str::<JsonValue>(&yaml_content) {
+            Ok(config) => {
+                // The naming policy is shared by modsrv, config-ui and the linter
+                if let Some(naming) = config.get("naming") {
+                    if let Err(e) = NamingPolicy::from_value(naming.clone()) {
+                        return Ok(validation_error(format!(
+                            "Invalid naming section in {:?}: {}",
+                            yaml_path, e
+                        )));
+                    }
+                }
+                if let Err(e) = validate_resource_profile(&config) {
+                    return Ok(validation_error(format!(
+                        "Invalid resource profile in {:?}: {}",
+                        yaml_path, e
+                    )));
+                }
                 Ok(validation_ok())
             },
             Err(e) => {
@@ -171,6 +233,34 @@ impl ConfigValidator {
     }
 }
 
+/// Check `profile` and `tuning.*` against the built-in resource profiles
+fn validate_resource_profile(config: &JsonValue) -> std::result::Result<(), String> {
+    use common::resource_profile::{ResourceProfile, Tuning};
+
+    if let Some(profile) = config.get("profile") {
+        profile
+            .as_str()
+            .ok_or_else(|| "profile must be a string".to_string())?
+            .parse::<ResourceProfile>()?;
+    }
+    if let Some(tuning) = config.get("tuning") {
+        let values = tuning
+            .as_object()
+            .ok_or_else(|| "tuning must be a mapping".to_string())?;
+        let mut scratch = Tuning::default();
+        for (name, value) in values {
+            let value = match value {
+                JsonValue::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            scratch
+                .set(name, &value)
+                .map_err(|e| format!("tuning.{}: {}", name, e))?;
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 #[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
 mod tests {
@@ -206,4 +296,48 @@ channels: []
             .iter()
             .any(|e| e.contains("at least one channel")));
     }
+
+    #[tokio::test]
+    async fn test_validator_routing_references() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path();
+        let comsrv_dir = config_path.join("comsrv");
+        let instance_dir = config_path.join("modsrv").join("instances").join("pcs_01");
+        fs::create_dir_all(&comsrv_dir).unwrap();
+        fs::create_dir_all(&instance_dir).unwrap();
+
+        fs::write(
+            comsrv_dir.join("comsrv.yaml"),
+            r#"
+service:
+  name: comsrv
+channels:
+  - id: 1
+    name: pcs
+    protocol: virtual
+"#,
+        )
+        .unwrap();
+        fs::write(
+            config_path.join("modsrv").join("modsrv.yaml"),
+            "service:\n  name: modsrv\n",
+        )
+        .unwrap();
+        fs::write(
+            instance_dir.join("channel_routing.csv"),
+            "channel_id,channel_type,channel_point_id,instance_type,instance_point_id\n\
+             1,T,1,M,1\n\
+             7,T,1,M,2\n",
+        )
+        .unwrap();
+
+        let validator = ConfigValidator::new(config_path);
+        let result = validator.validate_service("modsrv").await.unwrap();
+
+        assert!(!result.is_valid);
+        assert_eq!(
+            result.errors,
+            vec!["Instance pcs_01: routing references unknown channel 7".to_string()]
+        );
+    }
 }
@@ -0,0 +1,180 @@
+//! Excel (.xlsx) point table import
+//!
+//! Converts spreadsheet sheets into the CSV layout used by channel directories.
+//! Header spellings are handled by the CSV dialect mapping afterwards; this module
+//! only deals with sheet selection, header row position and explicit column mapping.
+
+use anyhow::{anyhow, Context, Result};
+use calamine::{open_workbook_auto, Data, Reader};
+use common::validation::normalize_header;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Workbook picked up by `monarch sync` when a channel has no point CSVs
+pub const CHANNEL_WORKBOOK: &str = "points.xlsx";
+
+/// Sheet and column mapping options for one point table
+#[derive(Debug, Clone)]
+pub struct SheetOptions {
+    /// Sheet name
+    pub sheet: String,
+    /// Header row (1-indexed); rows above it are titles/notes and are skipped
+    pub header_row: usize,
+    /// Canonical field -> column header or column letter (e.g. "C")
+    pub columns: HashMap<String, String>,
+}
+
+impl SheetOptions {
+    /// Options for a sheet with the header in the first row and no column mapping
+    pub fn new(sheet: impl Into<String>) -> Self {
+        Self {
+            sheet: sheet.into(),
+            header_row: 1,
+            columns: HashMap::new(),
+        }
+    }
+}
+
+/// List the sheet names of a workbook
+pub fn sheet_names(path: &Path) -> Result<Vec<String>> {
+    let workbook =
+        open_workbook_auto(path).with_context(|| format!("Failed to open workbook: {:?}", path))?;
+    Ok(workbook.sheet_names())
+}
+
+/// Read one sheet and convert it to CSV bytes
+pub fn sheet_to_csv(path: &Path, options: &SheetOptions) -> Result<Vec<u8>> {
+    let mut workbook =
+        open_workbook_auto(path).with_context(|| format!("Failed to open workbook: {:?}", path))?;
+    let range = workbook
+        .worksheet_range(&options.sheet)
+        .with_context(|| format!("Failed to read sheet '{}' in {:?}", options.sheet, path))?;
+
+    let grid: Vec<Vec<String>> = range
+        .rows()
+        .map(|row| row.iter().map(cell_to_string).collect())
+        .collect();
+
+    grid_to_csv(&grid, options)
+}
+
+/// Cell text as it would appear in a CSV export (integral floats without ".0")
+fn cell_to_string(cell: &Data) -> String {
+    match cell {
+        Data::Empty => String::new(),
+        Data::String(s) => s.trim().to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Convert a cell grid to CSV using the header row and column mapping
+pub fn grid_to_csv(grid: &[Vec<String>], options: &SheetOptions) -> Result<Vec<u8>> {
+    let header_index = options.header_row.max(1) - 1;
+    let mut headers = grid.get(header_index).cloned().ok_or_else(|| {
+        anyhow!(
+            "Sheet '{}' has no header row {}",
+            options.sheet,
+            options.header_row
+        )
+    })?;
+
+    for (field, column) in &options.columns {
+        let index = headers
+            .iter()
+            .position(|h| normalize_header(h) == normalize_header(column))
+            .or_else(|| column_letter_index(column))
+            .ok_or_else(|| {
+                anyhow!(
+                    "Column '{}' for field '{}' not found in sheet '{}'",
+                    column,
+                    field,
+                    options.sheet
+                )
+            })?;
+        if index >= headers.len() {
+            headers.resize(index + 1, String::new());
+        }
+        headers[index] = field.clone();
+    }
+
+    let mut writer = csv::WriterBuilder::new()
+        .flexible(true)
+        .from_writer(Vec::new());
+    writer.write_record(&headers)?;
+    for row in &grid[header_index + 1..] {
+        // Skip blank rows (spreadsheets often have trailing formatting)
+        if row.iter().all(|cell| cell.is_empty()) {
+            continue;
+        }
+        let mut record = row.clone();
+        record.resize(headers.len(), String::new());
+        writer.write_record(&record)?;
+    }
+
+    writer
+        .into_inner()
+        .map_err(|e| anyhow!("Failed to write CSV: {}", e))
+}
+
+/// Spreadsheet column letter ("A", "AB") to 0-based index
+fn column_letter_index(column: &str) -> Option<usize> {
+    if column.is_empty() || column.len() > 3 || !column.chars().all(|c| c.is_ascii_uppercase()) {
+        return None;
+    }
+    Some(
+        column
+            .chars()
+            .fold(0usize, |acc, c| acc * 26 + (c as usize - 'A' as usize + 1))
+            - 1,
+    )
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    fn grid(rows: &[&[&str]]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|row| row.iter().map(|c| c.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_column_letter_index() {
+        assert_eq!(column_letter_index("A"), Some(0));
+        assert_eq!(column_letter_index("Z"), Some(25));
+        assert_eq!(column_letter_index("AB"), Some(27));
+        assert_eq!(column_letter_index("Name"), None);
+    }
+
+    #[test]
+    fn test_grid_to_csv_with_header_row_and_columns() {
+        let sheet = grid(&[
+            &["PCS#1 telemetry", "", ""],
+            &["No.", "Point Desc", "Ratio"],
+            &["1", "Voltage", "0.1"],
+            &["", "", ""],
+            &["2", "Current", "0.01"],
+        ]);
+        let mut options = SheetOptions::new("遥测");
+        options.header_row = 2;
+        options
+            .columns
+            .insert("point_id".to_string(), "A".to_string());
+        options
+            .columns
+            .insert("signal_name".to_string(), "point desc".to_string());
+
+        let csv = String::from_utf8(grid_to_csv(&sheet, &options).unwrap()).unwrap();
+        assert_eq!(
+            csv,
+            "point_id,signal_name,Ratio\n1,Voltage,0.1\n2,Current,0.01\n"
+        );
+
+        options
+            .columns
+            .insert("unit".to_string(), "Units".to_string());
+        assert!(grid_to_csv(&sheet, &options).is_err());
+    }
+}
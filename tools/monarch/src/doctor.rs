@@ -1,16 +1,29 @@
 //! Health check and diagnostics for VoltageEMS system
+//!
+//! `monarch doctor` automates the field checklist: Redis reachability and
+//! memory, SQLite presence and integrity, disk space, clock skew against
+//! NTP, service heartbeats (/health) and routing consistency. Every check
+//! yields one result; the report lists the findings errors first, each
+//! with the command or action that addresses it.
 
 use anyhow::Result;
 use colored::*;
+use common::redis::RedisClient;
+use common::sqlite::ReadOnlyDatabase;
 use serde::Serialize;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use crate::utils::check_database_status;
 
+/// Timeout of each network probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
 /// Check result status
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+///
+/// Ordered by severity, so sorting puts errors last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum CheckStatus {
     Ok,
@@ -83,30 +96,64 @@ struct HealthResponse {
     checks: Option<serde_json::Value>,
 }
 
-/// Run all health checks
-pub async fn run_doctor(
-    config_path: impl AsRef<Path>,
-    db_path: impl AsRef<Path>,
-    verbose: bool,
-    json_output: bool,
-) -> Result<()> {
-    let config_path = config_path.as_ref();
-    let db_path = db_path.as_ref();
+/// Endpoints and paths the checks run against
+#[derive(Debug, Clone)]
+pub struct DoctorOptions {
+    pub config_path: PathBuf,
+    pub db_path: PathBuf,
+    pub redis_url: String,
+    pub comsrv_url: String,
+    pub modsrv_url: String,
+    /// NTP server (`host` or `host:port`) for the clock skew check
+    pub ntp_server: String,
+    pub verbose: bool,
+    pub json: bool,
+}
+
+/// Python services polled besides comsrv/modsrv: (name, default base URL)
+///
+/// They are optional parts of a deployment, so a missing one is a warning.
+/// `<NAME>_URL` overrides the base URL.
+const OPTIONAL_SERVICES: [(&str, &str); 4] = [
+    ("hissrv", "http://localhost:6004/hisApi"),
+    ("apigateway", "http://localhost:6005"),
+    ("netsrv", "http://localhost:6006"),
+    ("alarmsrv", "http://localhost:6007"),
+];
+
+/// Run all health checks and print the findings, most severe first
+pub async fn run_doctor(options: DoctorOptions) -> Result<()> {
+    let db_file = options.db_path.join("voltage.db");
     let mut results = Vec::new();
 
-    // Run all checks
+    // Infrastructure
     results.push(check_docker().await);
-    results.push(check_redis().await);
-    results.push(check_service("comsrv", 6001).await);
-    results.push(check_service("modsrv", 6002).await);
-    results.push(check_database(db_path).await);
-    results.push(check_config_files(config_path).await);
+    results.push(check_redis(&options.redis_url).await);
+    results.push(check_database(&db_file).await);
+    results.push(check_database_integrity(&db_file).await);
+    results.push(check_disk_space(&[&options.db_path, &options.config_path]));
+    results.push(check_clock_skew(&options.ntp_server).await);
     results.push(check_shared_memory().await);
 
-    if json_output {
-        println!("{}", serde_json::to_string_pretty(&results)?);
+    // Service heartbeats
+    results.push(check_service("comsrv", &options.comsrv_url, true).await);
+    results.push(check_service("modsrv", &options.modsrv_url, true).await);
+    for (name, default_url) in OPTIONAL_SERVICES {
+        let url = std::env::var(format!("{}_URL", name.to_ascii_uppercase()))
+            .unwrap_or_else(|_| default_url.to_string());
+        results.push(check_service(name, &url, false).await);
+    }
+
+    // Configuration
+    results.push(check_config_files(&options.config_path).await);
+    results.push(check_routing(&db_file, &options.modsrv_url).await);
+
+    if options.json {
+        let mut findings: Vec<&CheckResult> = results.iter().collect();
+        findings.sort_by_key(|r| std::cmp::Reverse(r.status));
+        println!("{}", serde_json::to_string_pretty(&findings)?);
     } else {
-        print_results(&results, verbose);
+        print_results(&results, options.verbose);
     }
 
     // Exit with error code if any check failed
@@ -147,73 +194,116 @@ async fn check_docker() -> CheckResult {
     }
 }
 
-/// Check Redis container status
-async fn check_redis() -> CheckResult {
+/// Check Redis connectivity and memory usage
+async fn check_redis(redis_url: &str) -> CheckResult {
     let start = Instant::now();
+    let client = match tokio::time::timeout(PROBE_TIMEOUT, RedisClient::new(redis_url)).await {
+        Ok(Ok(client)) => client,
+        _ => {
+            return CheckResult::error(
+                "Redis",
+                format!("Not reachable at {}", redis_url),
+                "monarch services start voltage-redis",
+            )
+            .with_duration(start.elapsed());
+        },
+    };
 
-    // First check if container is running
-    let container_check = Command::new("docker")
-        .args(["inspect", "-f", "{{.State.Running}}", "voltage-redis"])
-        .output();
+    let info = match tokio::time::timeout(PROBE_TIMEOUT, client.info("memory")).await {
+        Ok(Ok(info)) => info,
+        _ => {
+            return CheckResult::warning(
+                "Redis",
+                "Reachable but INFO memory failed",
+                "monarch services logs voltage-redis",
+            )
+            .with_duration(start.elapsed());
+        },
+    };
+    let memory = RedisMemory::parse(&info);
+    let used = format_bytes(memory.used);
+
+    let result = match memory.usage() {
+        None => CheckResult::ok("Redis", format!("Healthy, {} used (no maxmemory)", used)),
+        Some(usage) if usage >= 0.9 => CheckResult::error(
+            "Redis",
+            format!("{} used, {:.0}% of maxmemory", used, usage * 100.0),
+            "Raise maxmemory or remove stale keys: monarch rtdb scan '*'",
+        ),
+        Some(usage) if usage >= 0.75 => CheckResult::warning(
+            "Redis",
+            format!("{} used, {:.0}% of maxmemory", used, usage * 100.0),
+            "Check key growth: monarch rtdb scan '*'",
+        ),
+        Some(usage) => CheckResult::ok(
+            "Redis",
+            format!(
+                "Healthy, {} used ({:.0}% of maxmemory)",
+                used,
+                usage * 100.0
+            ),
+        ),
+    };
+    result.with_duration(start.elapsed())
+}
 
-    match container_check {
-        Ok(out) if out.status.success() => {
-            let running = String::from_utf8_lossy(&out.stdout).trim() == "true";
-            if !running {
-                return CheckResult::error(
-                    "voltage-redis",
-                    "Container stopped",
-                    "monarch services start voltage-redis",
-                )
-                .with_duration(start.elapsed());
+/// Fields of `INFO memory` the Redis check looks at
+#[derive(Debug, Default, PartialEq)]
+struct RedisMemory {
+    used: u64,
+    max: u64,
+}
+
+impl RedisMemory {
+    fn parse(info: &str) -> Self {
+        let mut memory = Self::default();
+        for line in info.lines() {
+            let Some((key, value)) = line.trim().split_once(':') else {
+                continue;
+            };
+            let value = value.trim().parse().unwrap_or(0);
+            match key {
+                "used_memory" => memory.used = value,
+                "maxmemory" => memory.max = value,
+                _ => {},
             }
+        }
+        memory
+    }
 
-            // Check Redis connectivity
-            let ping = Command::new("docker")
-                .args(["exec", "voltage-redis", "redis-cli", "ping"])
-                .output();
+    /// Share of maxmemory in use; None without a limit
+    fn usage(&self) -> Option<f64> {
+        (self.max > 0).then(|| self.used as f64 / self.max as f64)
+    }
+}
 
-            match ping {
-                Ok(p) if p.status.success() => {
-                    let response = String::from_utf8_lossy(&p.stdout).trim().to_string();
-                    if response == "PONG" {
-                        CheckResult::ok("voltage-redis", "Healthy (6379)")
-                            .with_duration(start.elapsed())
-                    } else {
-                        CheckResult::warning(
-                            "voltage-redis",
-                            "Container running but not responding",
-                            "monarch services logs voltage-redis",
-                        )
-                        .with_duration(start.elapsed())
-                    }
-                },
-                _ => CheckResult::warning(
-                    "voltage-redis",
-                    "Container running but ping failed",
-                    "monarch services logs voltage-redis",
-                )
-                .with_duration(start.elapsed()),
-            }
-        },
-        _ => CheckResult::error(
-            "voltage-redis",
-            "Container not found",
-            "monarch services start voltage-redis",
-        )
-        .with_duration(start.elapsed()),
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
     }
 }
 
-/// Check service health via HTTP endpoint
-async fn check_service(name: &str, port: u16) -> CheckResult {
+/// Check service health via its /health endpoint
+///
+/// An unreachable service is an error when `required`, a warning otherwise.
+async fn check_service(name: &str, base_url: &str, required: bool) -> CheckResult {
     let start = Instant::now();
-    let url = format!("http://localhost:{}/health", port);
+    let url = format!("{}/health", base_url.trim_end_matches('/'));
+    let endpoint = base_url
+        .trim_start_matches("http://")
+        .trim_start_matches("https://")
+        .trim_end_matches('/');
 
-    let client = match reqwest::Client::builder()
-        .timeout(Duration::from_secs(5))
-        .build()
-    {
+    let client = match reqwest::Client::builder().timeout(PROBE_TIMEOUT).build() {
         Ok(c) => c,
         Err(_) => {
             return CheckResult::error(
@@ -268,12 +358,12 @@ async fn check_service(name: &str, port: u16) -> CheckResult {
                         };
 
                         if health.status == "healthy" {
-                            CheckResult::ok(name, format!("Healthy ({}){}", port, extra))
+                            CheckResult::ok(name, format!("Healthy ({}){}", endpoint, extra))
                                 .with_duration(start.elapsed())
                         } else {
                             CheckResult::warning(
                                 name,
-                                format!("Degraded ({}){}", port, extra),
+                                format!("Degraded ({}){}", endpoint, extra),
                                 format!("monarch services logs {}", name),
                             )
                             .with_duration(start.elapsed())
@@ -281,7 +371,7 @@ async fn check_service(name: &str, port: u16) -> CheckResult {
                     },
                     Err(_) => CheckResult::warning(
                         name,
-                        format!("Running ({}) but invalid health response", port),
+                        format!("Running ({}) but invalid health response", endpoint),
                         format!("monarch services logs {}", name),
                     )
                     .with_duration(start.elapsed()),
@@ -289,7 +379,7 @@ async fn check_service(name: &str, port: u16) -> CheckResult {
             } else {
                 CheckResult::warning(
                     name,
-                    format!("Unhealthy ({}) - status {}", port, response.status()),
+                    format!("Unhealthy ({}) - status {}", endpoint, response.status()),
                     format!("monarch services logs {}", name),
                 )
                 .with_duration(start.elapsed())
@@ -303,18 +393,22 @@ async fn check_service(name: &str, port: u16) -> CheckResult {
             } else {
                 "Connection failed"
             };
-            CheckResult::error(name, msg, format!("monarch services start {}", name))
-                .with_duration(start.elapsed())
+            let suggestion = format!("monarch services start {}", name);
+            let result = if required {
+                CheckResult::error(name, msg, suggestion)
+            } else {
+                CheckResult::warning(name, msg, suggestion)
+            };
+            result.with_duration(start.elapsed())
         },
     }
 }
 
 /// Check SQLite database status
-async fn check_database(db_path: &Path) -> CheckResult {
+async fn check_database(db_file: &Path) -> CheckResult {
     let start = Instant::now();
-    let db_file = db_path.join("monarch.db");
 
-    match check_database_status(&db_file).await {
+    match check_database_status(db_file).await {
         Ok(status) => {
             if !status.exists {
                 CheckResult::error(
@@ -344,6 +438,326 @@ async fn check_database(db_path: &Path) -> CheckResult {
     }
 }
 
+/// Run `PRAGMA integrity_check` on the configuration database
+async fn check_database_integrity(db_file: &Path) -> CheckResult {
+    let start = Instant::now();
+    if !db_file.exists() {
+        return CheckResult::warning(
+            "SQLite Integrity",
+            "Skipped, no database",
+            "monarch init && monarch sync",
+        );
+    }
+
+    let problems: Result<Vec<String>> = async {
+        let database = ReadOnlyDatabase::open(db_file).await?;
+        Ok(sqlx::query_scalar("PRAGMA integrity_check")
+            .fetch_all(database.pool())
+            .await?)
+    }
+    .await;
+
+    match problems {
+        Ok(rows) if rows.len() == 1 && rows[0] == "ok" => {
+            CheckResult::ok("SQLite Integrity", "ok").with_duration(start.elapsed())
+        },
+        Ok(rows) => CheckResult::error(
+            "SQLite Integrity",
+            format!(
+                "{} problem(s), first: {}",
+                rows.len(),
+                rows.first().map(String::as_str).unwrap_or("-")
+            ),
+            format!(
+                "Stop services, back up {} and rebuild it: monarch init && monarch sync",
+                db_file.display()
+            ),
+        )
+        .with_duration(start.elapsed()),
+        Err(e) => CheckResult::error(
+            "SQLite Integrity",
+            format!("Check failed: {}", e),
+            "Check database file permissions",
+        )
+        .with_duration(start.elapsed()),
+    }
+}
+
+/// Free space of the filesystems holding the given paths
+fn check_disk_space(paths: &[&Path]) -> CheckResult {
+    let start = Instant::now();
+    let existing: Vec<&&Path> = paths.iter().filter(|p| p.exists()).collect();
+    if existing.is_empty() {
+        return CheckResult::warning(
+            "Disk Space",
+            "Skipped, data and config paths missing",
+            "Pass --db-path and --config-path",
+        );
+    }
+    let output = Command::new("df").arg("-Pk").args(existing).output();
+    let disks = match output {
+        Ok(out) if out.status.success() => parse_df(&String::from_utf8_lossy(&out.stdout)),
+        _ => {
+            return CheckResult::warning(
+                "Disk Space",
+                "df failed",
+                "Check the data and config paths exist",
+            )
+            .with_duration(start.elapsed());
+        },
+    };
+    let Some(fullest) = disks
+        .iter()
+        .min_by(|a, b| a.free_ratio().total_cmp(&b.free_ratio()))
+    else {
+        return CheckResult::warning(
+            "Disk Space",
+            "No filesystem found",
+            "Check the data and config paths exist",
+        )
+        .with_duration(start.elapsed());
+    };
+
+    let message = format!(
+        "{} {:.0}% free ({})",
+        fullest.mount,
+        fullest.free_ratio() * 100.0,
+        format_bytes(fullest.available_kb * 1024)
+    );
+    let suggestion = format!(
+        "Free space on {}: old logs, history exports, docker system prune",
+        fullest.mount
+    );
+    let result = if fullest.free_ratio() < 0.05 {
+        CheckResult::error("Disk Space", message, suggestion)
+    } else if fullest.free_ratio() < 0.15 {
+        CheckResult::warning("Disk Space", message, suggestion)
+    } else {
+        CheckResult::ok("Disk Space", message)
+    };
+    result.with_duration(start.elapsed())
+}
+
+/// Filesystem line of `df -Pk`
+#[derive(Debug, PartialEq)]
+struct DiskUsage {
+    mount: String,
+    total_kb: u64,
+    available_kb: u64,
+}
+
+impl DiskUsage {
+    fn free_ratio(&self) -> f64 {
+        if self.total_kb == 0 {
+            return 1.0;
+        }
+        self.available_kb as f64 / self.total_kb as f64
+    }
+}
+
+/// Parse `df -Pk` output, one entry per mount point
+fn parse_df(output: &str) -> Vec<DiskUsage> {
+    let mut disks: Vec<DiskUsage> = Vec::new();
+    for line in output.lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 6 {
+            continue;
+        }
+        let (Ok(total_kb), Ok(available_kb)) = (fields[1].parse(), fields[3].parse()) else {
+            continue;
+        };
+        // Mount points may contain spaces
+        let mount = fields[5..].join(" ");
+        if disks.iter().all(|d| d.mount != mount) {
+            disks.push(DiskUsage {
+                mount,
+                total_kb,
+                available_kb,
+            });
+        }
+    }
+    disks
+}
+
+/// Seconds between the NTP epoch (1900) and the Unix epoch
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+
+/// Compare the local clock with an NTP server (SNTP, RFC 4330)
+async fn check_clock_skew(server: &str) -> CheckResult {
+    let start = Instant::now();
+    match tokio::time::timeout(PROBE_TIMEOUT, query_clock_offset(server)).await {
+        Ok(Ok(offset_ms)) => {
+            let message = format!("{:+} ms vs {}", offset_ms, server);
+            let suggestion = "Enable time sync: timedatectl set-ntp true (or chronyc makestep)";
+            let result = match offset_ms.unsigned_abs() {
+                0..=1_000 => CheckResult::ok("Clock Skew", message),
+                1_001..=30_000 => CheckResult::warning("Clock Skew", message, suggestion),
+                _ => CheckResult::error("Clock Skew", message, suggestion),
+            };
+            result.with_duration(start.elapsed())
+        },
+        Ok(Err(e)) => CheckResult::warning(
+            "Clock Skew",
+            format!("{}: {}", server, e),
+            "Set a reachable server with --ntp-server",
+        )
+        .with_duration(start.elapsed()),
+        Err(_) => CheckResult::warning(
+            "Clock Skew",
+            format!("{} did not answer", server),
+            "Set a reachable server with --ntp-server",
+        )
+        .with_duration(start.elapsed()),
+    }
+}
+
+/// Offset of the server clock from the local clock in milliseconds
+async fn query_clock_offset(server: &str) -> Result<i64> {
+    let address = if server.contains(':') {
+        server.to_string()
+    } else {
+        format!("{}:123", server)
+    };
+    let socket = tokio::net::UdpSocket::bind("0.0.0.0:0").await?;
+    socket.connect(&address).await?;
+
+    // LI 0, version 4, mode 3 (client)
+    let mut request = [0u8; 48];
+    request[0] = 0x23;
+    let sent = unix_millis(SystemTime::now());
+    socket.send(&request).await?;
+    let mut response = [0u8; 48];
+    let len = socket.recv(&mut response).await?;
+    let received = unix_millis(SystemTime::now());
+    if len < 48 {
+        anyhow::bail!("short NTP response ({} bytes)", len);
+    }
+    if response[1] == 0 {
+        anyhow::bail!("server refused the request (stratum 0)");
+    }
+
+    let server_received = ntp_to_unix_millis(&response[32..40]);
+    let server_sent = ntp_to_unix_millis(&response[40..48]);
+    Ok(clock_offset(sent, server_received, server_sent, received))
+}
+
+fn unix_millis(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// 64-bit NTP timestamp (seconds since 1900 + 2^-32 fractions) as Unix ms
+fn ntp_to_unix_millis(bytes: &[u8]) -> i64 {
+    let seconds = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as u64;
+    let fraction = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as u64;
+    let millis = (fraction * 1000) >> 32;
+    (seconds as i64 - NTP_UNIX_OFFSET as i64) * 1000 + millis as i64
+}
+
+/// Clock offset from the four SNTP timestamps: ((t2 - t1) + (t3 - t4)) / 2
+fn clock_offset(t1: i64, t2: i64, t3: i64, t4: i64) -> i64 {
+    ((t2 - t1) + (t3 - t4)) / 2
+}
+
+/// Check routing rows against channel points and modsrv's live cache
+async fn check_routing(db_file: &Path, modsrv_url: &str) -> CheckResult {
+    let start = Instant::now();
+    if !db_file.exists() {
+        return CheckResult::warning(
+            "Routing",
+            "Skipped, no database",
+            "monarch init && monarch sync",
+        );
+    }
+
+    let orphans = match count_orphan_routes(db_file).await {
+        Ok(orphans) => orphans,
+        Err(e) => {
+            return CheckResult::warning(
+                "Routing",
+                format!("Could not read routing tables: {}", e),
+                "monarch sync --check",
+            )
+            .with_duration(start.elapsed());
+        },
+    };
+    if orphans > 0 {
+        return CheckResult::error(
+            "Routing",
+            format!("{} route(s) point at missing channel points", orphans),
+            "monarch config lint, then fix the routing CSVs and monarch sync",
+        )
+        .with_duration(start.elapsed());
+    }
+
+    // modsrv keeps routes in memory; compare with what SQLite holds
+    match routing_cache_drift(modsrv_url).await {
+        Some((missing, stale)) if missing + stale > 0 => CheckResult::warning(
+            "Routing",
+            format!(
+                "modsrv cache differs from SQLite: {} missing, {} stale",
+                missing, stale
+            ),
+            "monarch services restart modsrv",
+        ),
+        Some(_) => CheckResult::ok("Routing", "Consistent with channel points and modsrv"),
+        None => CheckResult::ok("Routing", "Consistent with channel points"),
+    }
+    .with_duration(start.elapsed())
+}
+
+/// Routing rows whose channel point does not exist
+async fn count_orphan_routes(db_file: &Path) -> Result<i64> {
+    let database = ReadOnlyDatabase::open(db_file).await?;
+    let mut orphans = 0;
+    for (routing, channel_type, points) in [
+        ("measurement_routing", "T", "telemetry_points"),
+        ("measurement_routing", "S", "signal_points"),
+        ("action_routing", "C", "control_points"),
+        ("action_routing", "A", "adjustment_points"),
+    ] {
+        let query = format!(
+            "SELECT COUNT(*) FROM {routing} r WHERE r.channel_type = ? AND NOT EXISTS \
+             (SELECT 1 FROM {points} p WHERE p.channel_id = r.channel_id \
+              AND p.point_id = r.channel_point_id)"
+        );
+        let count: i64 = sqlx::query_scalar(&query)
+            .bind(channel_type)
+            .fetch_one(database.pool())
+            .await?;
+        orphans += count;
+    }
+    Ok(orphans)
+}
+
+/// Routes missing from / stale in modsrv's cache; None when modsrv is down
+async fn routing_cache_drift(modsrv_url: &str) -> Option<(usize, usize)> {
+    let client = reqwest::Client::builder()
+        .timeout(PROBE_TIMEOUT)
+        .build()
+        .ok()?;
+    let body: serde_json::Value = client
+        .get(format!(
+            "{}/api/routing/cache",
+            modsrv_url.trim_end_matches('/')
+        ))
+        .send()
+        .await
+        .ok()?
+        .json()
+        .await
+        .ok()?;
+    let sqlite = body.get("data")?.get("sqlite")?;
+    let count = |key: &str| {
+        sqlite
+            .get(key)
+            .and_then(|v| v.as_array())
+            .map_or(0, Vec::len)
+    };
+    Some((count("missing_from_cache"), count("stale_in_cache")))
+}
+
 /// Check configuration files
 async fn check_config_files(config_path: &Path) -> CheckResult {
     let start = Instant::now();
@@ -447,10 +861,6 @@ fn print_results(results: &[CheckResult], verbose: bool) {
         };
 
         println!("│ {} {} {:<35} │", icon, name, message);
-
-        if let Some(ref suggestion) = result.suggestion {
-            println!("│   {} {:<52} │", "→".cyan(), suggestion.dimmed());
-        }
     }
 
     println!(
@@ -479,4 +889,72 @@ fn print_results(results: &[CheckResult], verbose: bool) {
         "└─────────────────────────────────────────────────────────┘".bright_blue()
     );
     println!();
+
+    // Findings in the order to work through them: errors, then warnings
+    let mut findings: Vec<&CheckResult> = results
+        .iter()
+        .filter(|r| r.status != CheckStatus::Ok)
+        .collect();
+    if findings.is_empty() {
+        return;
+    }
+    findings.sort_by_key(|r| std::cmp::Reverse(r.status));
+    println!("{}", "Findings (most severe first):".bold());
+    for (n, finding) in findings.iter().enumerate() {
+        let label = match finding.status {
+            CheckStatus::Error => "ERROR".red().bold(),
+            _ => "WARN ".yellow().bold(),
+        };
+        println!(
+            "{:>3}. {} {}: {}",
+            n + 1,
+            label,
+            finding.name,
+            finding.message
+        );
+        if let Some(ref suggestion) = finding.suggestion {
+            println!("       {} {}", "→".cyan(), suggestion.dimmed());
+        }
+    }
+    println!();
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_redis_memory_and_df() {
+        let memory = RedisMemory::parse(
+            "# Memory\r\nused_memory:805306368\r\nused_memory_human:768.00M\r\nmaxmemory:1073741824\r\n",
+        );
+        assert_eq!(memory.used, 805_306_368);
+        assert_eq!(memory.usage(), Some(0.75));
+        assert_eq!(RedisMemory::parse("used_memory:10").usage(), None);
+        assert_eq!(format_bytes(805_306_368), "768.0 MB");
+
+        let disks = parse_df(
+            "Filesystem     1024-blocks     Used Available Capacity Mounted on\n\
+             /dev/mmcblk0p2     29000000 27550000   1450000      95% /\n\
+             /dev/mmcblk0p2     29000000 27550000   1450000      95% /\n\
+             /dev/sda1         100000000  1000000  99000000       1% /mnt/data disk\n",
+        );
+        assert_eq!(disks.len(), 2);
+        assert_eq!(disks[0].free_ratio(), 0.05);
+        assert_eq!(disks[1].mount, "/mnt/data disk");
+    }
+
+    #[test]
+    fn test_ntp_timestamps_and_offset() {
+        // 2024-01-01T00:00:00.5Z
+        let seconds = (1_704_067_200 + NTP_UNIX_OFFSET) as u32;
+        let mut bytes = seconds.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&0x8000_0000u32.to_be_bytes());
+        assert_eq!(ntp_to_unix_millis(&bytes), 1_704_067_200_500);
+
+        // Server 2 s ahead, 100 ms round trip
+        assert_eq!(clock_offset(1_000, 3_050, 3_050, 1_100), 2_000);
+        assert_eq!(clock_offset(3_000, 1_050, 1_050, 3_100), -2_000);
+    }
 }
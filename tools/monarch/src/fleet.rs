@@ -0,0 +1,573 @@
+//! Multi-site fleet management
+//!
+//! A sites registry (`sites.yaml`) lists the config/db paths and service
+//! endpoints of each edge gateway, so one monarch install can work on any site
+//! with `--site <name>` or on all of them with `monarch fleet ...`.
+//!
+//! ```yaml
+//! sites:
+//!   north:
+//!     description: "North substation"
+//!     config_path: north/config      # relative to sites.yaml
+//!     db_path: north/data
+//!     redis_url: redis://10.0.1.5:6379
+//!     comsrv_url: http://10.0.1.5:6001
+//!     modsrv_url: http://10.0.1.5:6002
+//! ```
+
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use colored::*;
+use common::redis::RedisClient;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+use crate::context::ServiceConfig;
+use crate::core::MonarchCore;
+use crate::utils::check_database_status;
+
+/// Environment variable overriding the registry location
+pub const SITES_FILE_ENV: &str = "MONARCH_SITES";
+
+/// Timeout for each endpoint probe in `fleet status`
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Subcommand)]
+pub enum FleetCommands {
+    /// List registered sites
+    List,
+
+    /// Fleet-wide status summary (database sync state and service health per site)
+    Status {
+        /// Output as JSON (for scripts)
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Sync configuration on every site (or the given ones)
+    Sync {
+        /// Sites to sync (default: all)
+        sites: Vec<String>,
+
+        /// Validate only, don't write to databases (dry run)
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+
+        /// Force sync without validation (ignored if --dry-run)
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Compare configuration files of sites against a base site
+    Diff {
+        /// Reference site
+        base: String,
+
+        /// Sites to compare (default: all others)
+        sites: Vec<String>,
+    },
+}
+
+/// One edge gateway in the registry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Site {
+    #[serde(default)]
+    pub description: Option<String>,
+    pub config_path: PathBuf,
+    pub db_path: PathBuf,
+    #[serde(default)]
+    pub redis_url: Option<String>,
+    #[serde(default)]
+    pub comsrv_url: Option<String>,
+    #[serde(default)]
+    pub modsrv_url: Option<String>,
+}
+
+impl Site {
+    /// Apply this site's paths and Redis endpoint on top of a base configuration
+    pub fn apply(&self, config: &mut ServiceConfig) {
+        config.config_path = self.config_path.clone();
+        config.db_path = self.db_path.clone();
+        if let Some(redis_url) = &self.redis_url {
+            config.redis_url = redis_url.clone();
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SitesFile {
+    #[serde(default)]
+    sites: BTreeMap<String, Site>,
+}
+
+/// Sites registry loaded from `sites.yaml`
+#[derive(Debug)]
+pub struct SiteRegistry {
+    pub path: PathBuf,
+    pub sites: BTreeMap<String, Site>,
+}
+
+impl SiteRegistry {
+    /// Registry location: `$MONARCH_SITES`, /opt/MonarchEdge/sites.yaml or ./sites.yaml
+    pub fn default_path() -> PathBuf {
+        if let Ok(path) = std::env::var(SITES_FILE_ENV) {
+            return PathBuf::from(path);
+        }
+        let installed = Path::new("/opt/MonarchEdge/sites.yaml");
+        if installed.exists() {
+            installed.to_path_buf()
+        } else {
+            PathBuf::from("sites.yaml")
+        }
+    }
+
+    /// Load the registry; relative paths are resolved against the registry's directory
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read sites registry {:?}", path))?;
+        let file: SitesFile = serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse sites registry {:?}", path))?;
+
+        let base = path.parent().unwrap_or(Path::new("."));
+        let sites = file
+            .sites
+            .into_iter()
+            .map(|(name, mut site)| {
+                site.config_path = base.join(&site.config_path);
+                site.db_path = base.join(&site.db_path);
+                (name, site)
+            })
+            .collect();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            sites,
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Site> {
+        self.sites.get(name).ok_or_else(|| {
+            anyhow!(
+                "Unknown site '{}' in {:?}. Available: {}",
+                name,
+                self.path,
+                self.sites.keys().cloned().collect::<Vec<_>>().join(", ")
+            )
+        })
+    }
+
+    /// Resolve the given site names, or all sites when none are given
+    pub fn select<'a>(&'a self, names: &'a [String]) -> Result<Vec<(&'a str, &'a Site)>> {
+        if names.is_empty() {
+            return Ok(self.sites.iter().map(|(n, s)| (n.as_str(), s)).collect());
+        }
+        names
+            .iter()
+            .map(|name| Ok((name.as_str(), self.get(name)?)))
+            .collect()
+    }
+}
+
+/// Endpoint probe result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Probe {
+    Up,
+    Down,
+    /// Endpoint not configured for the site
+    None,
+}
+
+/// Status summary of one site
+#[derive(Debug, Serialize)]
+pub struct SiteStatus {
+    pub site: String,
+    pub config: bool,
+    pub database: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_sync: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<usize>,
+    pub redis: Probe,
+    pub comsrv: Probe,
+    pub modsrv: Probe,
+}
+
+pub async fn handle_command(command: FleetCommands, registry_path: &Path) -> Result<()> {
+    let registry = SiteRegistry::load(registry_path)?;
+
+    match command {
+        FleetCommands::List => list_sites(&registry),
+        FleetCommands::Status { json } => {
+            let statuses = collect_status(&registry).await;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&statuses)?);
+            } else {
+                print_status(&statuses);
+            }
+        },
+        FleetCommands::Sync {
+            sites,
+            dry_run,
+            force,
+        } => sync_sites(&registry, &sites, dry_run, force).await?,
+        FleetCommands::Diff { base, sites } => diff_sites(&registry, &base, &sites)?,
+    }
+    Ok(())
+}
+
+fn list_sites(registry: &SiteRegistry) {
+    println!(
+        "{} {}",
+        "Sites registry:".bright_cyan(),
+        registry.path.display()
+    );
+    println!();
+    for (name, site) in &registry.sites {
+        println!(
+            "{} {}",
+            name.bright_yellow(),
+            site.description.as_deref().unwrap_or_default().dimmed()
+        );
+        println!("   config: {}", site.config_path.display());
+        println!("   db:     {}", site.db_path.display());
+        for (label, url) in [
+            ("redis: ", &site.redis_url),
+            ("comsrv:", &site.comsrv_url),
+            ("modsrv:", &site.modsrv_url),
+        ] {
+            if let Some(url) = url {
+                println!("   {} {}", label, url);
+            }
+        }
+    }
+}
+
+async fn collect_status(registry: &SiteRegistry) -> Vec<SiteStatus> {
+    let mut tasks = JoinSet::new();
+    for (name, site) in &registry.sites {
+        let (name, site) = (name.clone(), site.clone());
+        tasks.spawn(async move { site_status(name, site).await });
+    }
+
+    let mut statuses = Vec::new();
+    while let Some(result) = tasks.join_next().await {
+        if let Ok(status) = result {
+            statuses.push(status);
+        }
+    }
+    statuses.sort_by(|a, b| a.site.cmp(&b.site));
+    statuses
+}
+
+async fn site_status(name: String, site: Site) -> SiteStatus {
+    let db_status = check_database_status(&site.db_path.join("voltage.db"))
+        .await
+        .ok()
+        .filter(|s| s.initialized);
+
+    let redis = match &site.redis_url {
+        Some(url) => match tokio::time::timeout(PROBE_TIMEOUT, RedisClient::new(url)).await {
+            Ok(Ok(_)) => Probe::Up,
+            _ => Probe::Down,
+        },
+        None => Probe::None,
+    };
+
+    SiteStatus {
+        site: name,
+        config: site.config_path.is_dir(),
+        database: db_status.is_some(),
+        last_sync: db_status.as_ref().and_then(|s| s.last_sync.clone()),
+        items: db_status.as_ref().and_then(|s| s.item_count),
+        redis,
+        comsrv: probe_health(site.comsrv_url.as_deref()).await,
+        modsrv: probe_health(site.modsrv_url.as_deref()).await,
+    }
+}
+
+async fn probe_health(base_url: Option<&str>) -> Probe {
+    let Some(base_url) = base_url else {
+        return Probe::None;
+    };
+    let Ok(client) = reqwest::Client::builder().timeout(PROBE_TIMEOUT).build() else {
+        return Probe::Down;
+    };
+    match client
+        .get(format!("{}/health", base_url.trim_end_matches('/')))
+        .send()
+        .await
+    {
+        Ok(response) if response.status().is_success() => Probe::Up,
+        _ => Probe::Down,
+    }
+}
+
+fn print_status(statuses: &[SiteStatus]) {
+    let probe = |p: Probe| match p {
+        Probe::Up => format!("{:<8}", "up").green(),
+        Probe::Down => format!("{:<8}", "down").red(),
+        Probe::None => format!("{:<8}", "-").dimmed(),
+    };
+    let flag = |ok: bool| {
+        if ok {
+            format!("{:<8}", "ok").green()
+        } else {
+            format!("{:<8}", "missing").red()
+        }
+    };
+
+    println!();
+    println!(
+        "{}",
+        format!(
+            "{:<16} {:<8} {:<8} {:<21} {:>7}  {:<8} {:<8} {:<8}",
+            "SITE", "CONFIG", "DB", "LAST SYNC", "ITEMS", "REDIS", "COMSRV", "MODSRV"
+        )
+        .bold()
+    );
+    for status in statuses {
+        println!(
+            "{:<16} {} {} {:<21} {:>7}  {} {} {}",
+            status.site,
+            flag(status.config),
+            flag(status.database),
+            status.last_sync.as_deref().unwrap_or("never"),
+            status
+                .items
+                .map(|n| n.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            probe(status.redis),
+            probe(status.comsrv),
+            probe(status.modsrv),
+        );
+    }
+
+    let healthy = statuses
+        .iter()
+        .filter(|s| {
+            s.config
+                && s.database
+                && [s.redis, s.comsrv, s.modsrv]
+                    .iter()
+                    .all(|p| *p != Probe::Down)
+        })
+        .count();
+    println!();
+    println!("{}/{} sites healthy", healthy, statuses.len());
+}
+
+async fn sync_sites(
+    registry: &SiteRegistry,
+    names: &[String],
+    dry_run: bool,
+    force: bool,
+) -> Result<()> {
+    let configs = ["global", "comsrv", "modsrv"];
+    let mut failed = Vec::new();
+
+    for (name, site) in registry.select(names)? {
+        println!();
+        println!("{} {}", "Site".bright_cyan(), name.bright_yellow());
+
+        // A failing site is reported and the remaining sites are still processed
+        let mut site_error = None;
+        for cfg in configs {
+            print!("{} {}... ", "-".bright_cyan(), cfg);
+            match sync_site_service(site, cfg, dry_run, force).await {
+                Ok(message) => println!("{}", message),
+                Err(e) => {
+                    println!("{}", "FAIL".red());
+                    eprintln!("   {} {:#}", "ERROR".red(), e);
+                    site_error = Some(e);
+                    break;
+                },
+            }
+        }
+        if site_error.is_some() {
+            failed.push(name.to_string());
+        }
+    }
+
+    println!();
+    if failed.is_empty() {
+        let action = if dry_run { "validated" } else { "synced" };
+        println!("{} All sites {}", "DONE".green(), action);
+        Ok(())
+    } else {
+        Err(anyhow!("Failed sites: {}", failed.join(", ")))
+    }
+}
+
+async fn sync_site_service(
+    site: &Site,
+    cfg: &str,
+    dry_run: bool,
+    force: bool,
+) -> Result<ColoredString> {
+    if dry_run || !force {
+        let result = MonarchCore::new(&site.config_path).validate(cfg).await?;
+        if !result.is_valid {
+            return Err(anyhow!(result.errors.join("; ")));
+        }
+        if dry_run {
+            return Ok("OK".green());
+        }
+    }
+
+    let core = MonarchCore::readwrite(&site.db_path, &site.config_path, cfg).await?;
+    let result = core.sync(cfg).await?;
+    Ok(if result.errors.is_empty() {
+        format!("OK ({} items)", result.items_synced).green()
+    } else {
+        format!("WARN ({} errors)", result.errors.len()).yellow()
+    })
+}
+
+/// File-level differences of one site against the base site
+#[derive(Debug, Default, PartialEq)]
+pub struct TreeDiff {
+    /// Files only present on the compared site
+    pub added: Vec<String>,
+    /// Files missing on the compared site
+    pub removed: Vec<String>,
+    /// Files present on both with different content
+    pub changed: Vec<String>,
+}
+
+impl TreeDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compare two configuration directories file by file
+pub fn diff_trees(base: &Path, other: &Path) -> Result<TreeDiff> {
+    let base_files = list_files(base)?;
+    let other_files = list_files(other)?;
+
+    let mut diff = TreeDiff::default();
+    for file in base_files.union(&other_files) {
+        match (base_files.contains(file), other_files.contains(file)) {
+            (true, false) => diff.removed.push(file.clone()),
+            (false, true) => diff.added.push(file.clone()),
+            _ => {
+                if std::fs::read(base.join(file))? != std::fs::read(other.join(file))? {
+                    diff.changed.push(file.clone());
+                }
+            },
+        }
+    }
+    Ok(diff)
+}
+
+/// Relative paths of all files below a directory
+fn list_files(root: &Path) -> Result<BTreeSet<String>> {
+    let mut files = BTreeSet::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries =
+            std::fs::read_dir(&dir).with_context(|| format!("Failed to read {:?}", dir))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if let Ok(relative) = path.strip_prefix(root) {
+                files.insert(relative.to_string_lossy().replace('\\', "/"));
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn diff_sites(registry: &SiteRegistry, base: &str, names: &[String]) -> Result<()> {
+    let base_site = registry.get(base)?;
+    let others: Vec<(&str, &Site)> = registry
+        .select(names)?
+        .into_iter()
+        .filter(|(name, _)| *name != base)
+        .collect();
+
+    for (name, site) in others {
+        println!();
+        println!(
+            "{} {} {} {}",
+            "Diff".bright_cyan(),
+            base.bright_yellow(),
+            "->".dimmed(),
+            name.bright_yellow()
+        );
+
+        let diff = diff_trees(&base_site.config_path, &site.config_path)?;
+        if diff.is_empty() {
+            println!("   {}", "identical".green());
+            continue;
+        }
+        for file in &diff.added {
+            println!("   {} {}", "+".green(), file);
+        }
+        for file in &diff.removed {
+            println!("   {} {}", "-".red(), file);
+        }
+        for file in &diff.changed {
+            println!("   {} {}", "~".yellow(), file);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, file: &str, content: &str) {
+        let path = dir.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_registry_resolves_relative_paths() {
+        let temp = TempDir::new().unwrap();
+        write(
+            temp.path(),
+            "sites.yaml",
+            "sites:\n  north:\n    config_path: north/config\n    db_path: /var/north\n    \
+             redis_url: redis://10.0.1.5:6379\n",
+        );
+
+        let registry = SiteRegistry::load(&temp.path().join("sites.yaml")).unwrap();
+        let site = registry.get("north").unwrap();
+        assert_eq!(site.config_path, temp.path().join("north/config"));
+        assert_eq!(site.db_path, PathBuf::from("/var/north"));
+
+        let mut config = ServiceConfig::auto_detect();
+        site.apply(&mut config);
+        assert_eq!(config.redis_url, "redis://10.0.1.5:6379");
+
+        assert!(registry.get("south").is_err());
+        assert_eq!(registry.select(&[]).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_diff_trees() {
+        let temp = TempDir::new().unwrap();
+        let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+        write(&a, "comsrv/comsrv.yaml", "channels: []");
+        write(&b, "comsrv/comsrv.yaml", "channels: [1]");
+        write(&a, "global.yaml", "x: 1");
+        write(&b, "global.yaml", "x: 1");
+        write(&a, "modsrv/instances.yaml", "");
+        write(&b, "comsrv/1/telemetry.csv", "");
+
+        let diff = diff_trees(&a, &b).unwrap();
+        assert_eq!(diff.added, vec!["comsrv/1/telemetry.csv"]);
+        assert_eq!(diff.removed, vec!["modsrv/instances.yaml"]);
+        assert_eq!(diff.changed, vec!["comsrv/comsrv.yaml"]);
+    }
+}
@@ -18,8 +18,8 @@ pub struct RuleSummary {
 }
 
 /// Type alias for rule database row to avoid clippy::type_complexity warning
-/// Fields: (id, name, description, enabled, priority, cooldown_ms, nodes_json)
-type RuleDbRow = (i64, String, Option<String>, i64, i64, i64, String);
+/// Fields: (id, name, description, enabled, priority, cooldown_ms, shadow, nodes_json)
+type RuleDbRow = (i64, String, Option<String>, i64, i64, i64, i64, String);
 
 /// Rules service - provides rule management and execution operations
 /// Uses ModsrvContext since rules have been merged into modsrv
@@ -64,14 +64,14 @@ impl<'a> RulesService<'a> {
     pub async fn get(&self, rule_id: i64) -> Result<Rule> {
         // Query database for rule
         let row: Option<RuleDbRow> = sqlx::query_as(
-            "SELECT id, name, description, enabled, priority, cooldown_ms, nodes_json
+            "SELECT id, name, description, enabled, priority, cooldown_ms, shadow, nodes_json
              FROM rules WHERE id = ?",
         )
         .bind(rule_id)
         .fetch_optional(&self.ctx.sqlite_pool)
         .await?;
 
-        let (id, name, description, enabled, priority, cooldown_ms, nodes_json) =
+        let (id, name, description, enabled, priority, cooldown_ms, shadow, nodes_json) =
             row.ok_or_else(|| LibApiError::not_found(format!("Rule '{}' not found", rule_id)))?;
 
         // Deserialize compact flow
@@ -85,6 +85,7 @@ impl<'a> RulesService<'a> {
             enabled: enabled != 0,
             priority: priority as u32,
             cooldown_ms: cooldown_ms as u64,
+            shadow: shadow != 0,
             flow,
         })
     }
@@ -100,8 +101,8 @@ impl<'a> RulesService<'a> {
 
         // Insert into database
         sqlx::query(
-            "INSERT INTO rules (id, name, description, enabled, priority, cooldown_ms, nodes_json)
-             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            "INSERT INTO rules (id, name, description, enabled, priority, cooldown_ms, shadow, nodes_json)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
         )
         .bind(rule.id)
         .bind(&rule.name)
@@ -109,6 +110,7 @@ impl<'a> RulesService<'a> {
         .bind(rule.enabled)
         .bind(rule.priority as i64)
         .bind(rule.cooldown_ms as i64)
+        .bind(rule.shadow)
         .bind(&nodes_json)
         .execute(&self.ctx.sqlite_pool)
         .await?;
@@ -127,7 +129,7 @@ impl<'a> RulesService<'a> {
 
         let result = sqlx::query(
             "UPDATE rules SET name = ?, description = ?, enabled = ?, priority = ?,
-                    cooldown_ms = ?, nodes_json = ?, updated_at = CURRENT_TIMESTAMP
+                    cooldown_ms = ?, shadow = ?, nodes_json = ?, updated_at = CURRENT_TIMESTAMP
              WHERE id = ?",
         )
         .bind(&rule.name)
@@ -135,6 +137,7 @@ impl<'a> RulesService<'a> {
         .bind(rule.enabled)
         .bind(rule.priority as i64)
         .bind(rule.cooldown_ms as i64)
+        .bind(rule.shadow)
         .bind(&nodes_json)
         .bind(rule_id)
         .execute(&self.ctx.sqlite_pool)
@@ -4,13 +4,16 @@
 //! service management, and operational control for all VoltageEMS services.
 
 mod channels;
+mod config;
 mod context;
 mod core;
 mod doctor;
+mod fleet;
 mod logs;
 mod models;
 mod rtdb;
 mod rules;
+mod scenario;
 mod services;
 mod shm;
 mod utils;
@@ -105,6 +108,7 @@ Configuration Management:
   status      Show current configuration status
   init        Initialize database schemas
   export      Export configuration from SQLite to YAML/CSV
+  config      Lint configuration across services (routing, points, rules)
 
 Service Operations:
   channels    Manage communication channels and protocols
@@ -112,16 +116,26 @@ Service Operations:
   rules       Manage and execute business rules
   services    Start, stop, and manage VoltageEMS services
   logs        Dynamically adjust log levels for running services
+  scenario    Record RTDB/SQLite state into a support bundle and replay it
+  doctor      Run the field health checklist and list findings by severity
+
+Fleet:
+  fleet       List, sync, diff and check status across registered sites
+  --site      Run any command against one site from the sites registry
 
 Examples:
   monarch sync                          # Sync all configurations
   monarch sync --dry-run                # Validate without syncing
+  monarch config lint                   # Cross-service checks with fix suggestions
   monarch channels list                 # List all channels
   monarch models products list          # List products
   monarch rules enable R001             # Enable a rule
   monarch services status               # Check service status
   monarch logs level all debug          # Switch all services to debug mode
   monarch logs get all                  # Show current log levels
+  monarch --site north sync             # Sync one site of the fleet
+  monarch fleet status                  # Status summary of all sites
+  monarch scenario record -O case.json  # Capture state for a support case
 
 Use 'monarch <command> --help' for more information on a specific command.")]
 #[command(version)]
@@ -152,6 +166,14 @@ struct Cli {
     /// Force online mode (use HTTP API only)
     #[arg(long, global = true)]
     online: bool,
+
+    /// Site from the sites registry (sets config/db paths and service endpoints)
+    #[arg(long, global = true)]
+    site: Option<String>,
+
+    /// Sites registry file (default: $MONARCH_SITES, /opt/MonarchEdge/sites.yaml or ./sites.yaml)
+    #[arg(long = "sites-file", global = true)]
+    sites_file: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -201,6 +223,20 @@ enum Commands {
         detailed: bool,
     },
 
+    /// Inspect configuration files
+    #[command(about = "Lint configuration across services")]
+    Config {
+        #[command(subcommand)]
+        command: config::ConfigCommands,
+    },
+
+    /// Manage a fleet of sites
+    #[command(about = "List, sync, diff and check status across registered sites")]
+    Fleet {
+        #[command(subcommand)]
+        command: fleet::FleetCommands,
+    },
+
     // === Service Management Commands ===
     /// Manage communication channels
     #[command(about = "Manage communication channels and protocols")]
@@ -230,6 +266,13 @@ enum Commands {
         command: rtdb::RtdbCommands,
     },
 
+    /// Support bundles of RTDB and SQLite state
+    #[command(about = "Record and replay RTDB/SQLite state for support cases")]
+    Scenario {
+        #[command(subcommand)]
+        command: scenario::ScenarioCommands,
+    },
+
     /// Manage Docker services
     #[command(about = "Start, stop, and manage VoltageEMS services")]
     Services {
@@ -261,6 +304,10 @@ enum Commands {
         /// Output as JSON (for scripts)
         #[arg(long)]
         json: bool,
+
+        /// NTP server for the clock skew check (host or host:port)
+        #[arg(long, default_value = "pool.ntp.org")]
+        ntp_server: String,
     },
 }
 
@@ -313,9 +360,22 @@ async fn main() -> Result<()> {
         .with_target(false)
         .init();
 
-    // Use ServiceConfig::auto_detect() as baseline, then override with CLI args
+    // Use ServiceConfig::auto_detect() as baseline, then the selected site, then CLI args
     let mut service_config = ServiceConfig::auto_detect();
 
+    let sites_file = cli
+        .sites_file
+        .as_deref()
+        .map(PathBuf::from)
+        .unwrap_or_else(fleet::SiteRegistry::default_path);
+    let site = match cli.site.as_deref() {
+        Some(name) => Some(fleet::SiteRegistry::load(&sites_file)?.get(name)?.clone()),
+        None => None,
+    };
+    if let Some(site) = &site {
+        site.apply(&mut service_config);
+    }
+
     // Override with CLI arguments if provided
     if let Some(config_path) = cli.config_path.as_deref() {
         service_config.config_path = PathBuf::from(config_path);
@@ -338,6 +398,9 @@ async fn main() -> Result<()> {
             config_path.display(),
             db_path.display()
         );
+        if let Some(name) = cli.site.as_deref() {
+            println!("{} {}", "Site:".bright_cyan(), name.bright_yellow());
+        }
         println!();
     }
 
@@ -423,27 +486,46 @@ async fn main() -> Result<()> {
             );
             export_command(output, detailed, config_path, db_path).await?;
         },
+        Commands::Fleet { command } => {
+            fleet::handle_command(command, &sites_file).await?;
+        },
+        Commands::Config { command } => {
+            config::handle_command(command, config_path).await?;
+        },
 
         // Service management commands
         Commands::Channels { command } => {
-            let base_url =
-                std::env::var("COMSRV_URL").unwrap_or_else(|_| "http://localhost:6001".to_string());
-            channels::handle_command(command, service_ctx.as_ref(), Some(&base_url)).await?;
+            let base_url = site
+                .as_ref()
+                .and_then(|s| s.comsrv_url.clone())
+                .or_else(|| std::env::var("COMSRV_URL").ok())
+                .unwrap_or_else(|| "http://localhost:6001".to_string());
+            channels::handle_command(command, service_ctx.as_ref(), Some(&base_url), config_path)
+                .await?;
         },
         Commands::Models { command } => {
-            let base_url =
-                std::env::var("MODSRV_URL").unwrap_or_else(|_| "http://localhost:6002".to_string());
+            let base_url = site
+                .as_ref()
+                .and_then(|s| s.modsrv_url.clone())
+                .or_else(|| std::env::var("MODSRV_URL").ok())
+                .unwrap_or_else(|| "http://localhost:6002".to_string());
             models::handle_command(command, service_ctx.as_ref(), Some(&base_url)).await?;
         },
         Commands::Rules { command } => {
             // rules merged into modsrv (port 6002)
-            let base_url =
-                std::env::var("RULES_URL").unwrap_or_else(|_| "http://localhost:6002".to_string());
+            let base_url = site
+                .as_ref()
+                .and_then(|s| s.modsrv_url.clone())
+                .or_else(|| std::env::var("RULES_URL").ok())
+                .unwrap_or_else(|| "http://localhost:6002".to_string());
             rules::handle_command(command, service_ctx.as_ref(), Some(&base_url)).await?;
         },
         Commands::Rtdb { command } => {
             rtdb::handle_command(command, service_ctx.as_ref()).await?;
         },
+        Commands::Scenario { command } => {
+            scenario::handle_command(command, &service_config).await?;
+        },
         Commands::Services { command } => {
             services::handle_command(command, service_ctx.as_ref()).await?;
         },
@@ -454,8 +536,32 @@ async fn main() -> Result<()> {
             // Shm command doesn't need async or service context
             shm::handle_command(command)?;
         },
-        Commands::Doctor { verbose, json } => {
-            doctor::run_doctor(config_path, db_path, verbose, json).await?;
+        Commands::Doctor {
+            verbose,
+            json,
+            ntp_server,
+        } => {
+            let comsrv_url = site
+                .as_ref()
+                .and_then(|s| s.comsrv_url.clone())
+                .or_else(|| std::env::var("COMSRV_URL").ok())
+                .unwrap_or_else(|| "http://localhost:6001".to_string());
+            let modsrv_url = site
+                .as_ref()
+                .and_then(|s| s.modsrv_url.clone())
+                .or_else(|| std::env::var("MODSRV_URL").ok())
+                .unwrap_or_else(|| "http://localhost:6002".to_string());
+            doctor::run_doctor(doctor::DoctorOptions {
+                config_path: config_path.clone(),
+                db_path: db_path.clone(),
+                redis_url: service_config.redis_url.clone(),
+                comsrv_url,
+                modsrv_url,
+                ntp_server,
+                verbose,
+                json,
+            })
+            .await?;
         },
     }
 
@@ -631,6 +737,9 @@ async fn status_command(detailed: bool, db_path: &Path) -> Result<()> {
                     if let Some(count) = status.item_count {
                         println!("   {} Items: {}", "-".bright_blue(), count);
                     }
+                    if let Some(version) = status.schema_version {
+                        println!("   {} Schema: {}", "-".bright_blue(), version);
+                    }
                 }
             },
             Err(_) => {
@@ -678,10 +787,9 @@ async fn init_command(db_path: &Path, force: bool) -> Result<()> {
             db_file.display()
         );
         println!(
-            "{} Running safe schema upgrade (CREATE TABLE IF NOT EXISTS)...",
+            "{} Applying pending schema migrations (existing data is kept)...",
             "INFO".bright_blue()
         );
-        // Continue to run schema init - it uses IF NOT EXISTS so it's safe
     }
 
     // Initialize all tables
@@ -692,7 +800,23 @@ async fn init_command(db_path: &Path, force: bool) -> Result<()> {
     );
 
     match schema::init_database(&db_file).await {
-        Ok(_) => println!("{}", "OK".green()),
+        Ok(reports) => {
+            println!("{}", "OK".green());
+            for report in reports {
+                let applied = if report.applied.is_empty() {
+                    "up to date".to_string()
+                } else {
+                    format!("applied {:?}", report.applied)
+                };
+                println!(
+                    "   {} {} v{} ({})",
+                    "-".bright_blue(),
+                    report.component,
+                    report.current_version,
+                    applied
+                );
+            }
+        },
         Err(e) => {
             println!("{}", "FAIL".red());
             eprintln!("   {} Failed to initialize database: {}", "ERROR".red(), e);
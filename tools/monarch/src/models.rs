@@ -30,6 +30,26 @@ pub enum ModelCommands {
         #[command(subcommand)]
         command: InstanceCommands,
     },
+
+    /// Inspect the routing cache of the running modsrv
+    #[command(about = "Dump routing cache stats, recent misses and entries (diff against SQLite)")]
+    RoutingCache {
+        /// Only entries routed to or from this instance
+        #[arg(short, long)]
+        instance: Option<u32>,
+        /// Only entries routed to or from this channel
+        #[arg(short, long)]
+        channel: Option<u32>,
+        /// Only this table (c2m, m2c, c2c)
+        #[arg(short, long)]
+        table: Option<String>,
+        /// Enable or disable recording of missed lookup keys
+        #[arg(long)]
+        trace: Option<bool>,
+        /// Reset hit/miss counters and recorded misses
+        #[arg(long)]
+        reset: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -135,6 +155,40 @@ pub async fn handle_command(
         ModelCommands::Instances { command } => {
             handle_instance_command(command, service_ctx, base_url).await
         },
+        ModelCommands::RoutingCache {
+            instance,
+            channel,
+            table,
+            trace,
+            reset,
+        } => {
+            // The cache lives in the modsrv process, so this is online only
+            let url = base_url.ok_or_else(|| {
+                anyhow::anyhow!("Base URL required for routing cache. Please set MODSRV_URL")
+            })?;
+            let client = client::ModelClient::new(url)?;
+
+            if trace.is_some() || reset {
+                let enabled = match trace {
+                    Some(enabled) => enabled,
+                    None => client.routing_cache(None, None, None).await?["data"]["stats"]
+                        ["miss_tracing"]
+                        .as_bool()
+                        .unwrap_or(false),
+                };
+                client.set_routing_cache_trace(enabled, reset).await?;
+                info!("Routing cache miss tracing: {}", enabled);
+            }
+
+            let cache = client
+                .routing_cache(instance, channel, table.as_deref())
+                .await?;
+            println!(
+                "Routing cache: {}",
+                serde_json::to_string_pretty(&cache["data"])?
+            );
+            Ok(())
+        },
     }
 }
 
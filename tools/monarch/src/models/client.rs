@@ -158,4 +158,58 @@ impl ModelClient {
             ))
         }
     }
+
+    // Routing cache diagnostics
+    pub async fn routing_cache(
+        &self,
+        instance_id: Option<u32>,
+        channel_id: Option<u32>,
+        table: Option<&str>,
+    ) -> Result<Value> {
+        let mut query: Vec<(&str, String)> = Vec::new();
+        if let Some(id) = instance_id {
+            query.push(("instance_id", id.to_string()));
+        }
+        if let Some(id) = channel_id {
+            query.push(("channel_id", id.to_string()));
+        }
+        if let Some(table) = table {
+            query.push(("table", table.to_string()));
+        }
+
+        let response = self
+            .client
+            .get(format!("{}/api/routing/cache", self.base_url))
+            .query(&query)
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            Ok(response.json().await?)
+        } else {
+            Err(anyhow::anyhow!(
+                "Failed to get routing cache: {}",
+                response.text().await?
+            ))
+        }
+    }
+
+    #[allow(clippy::disallowed_methods)] // json! macro internally uses unwrap (safe for known valid JSON)
+    pub async fn set_routing_cache_trace(&self, enabled: bool, reset: bool) -> Result<()> {
+        let response = self
+            .client
+            .put(format!("{}/api/routing/cache/trace", self.base_url))
+            .json(&serde_json::json!({ "enabled": enabled, "reset": reset }))
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Failed to set routing cache tracing: {}",
+                response.status()
+            ))
+        }
+    }
 }
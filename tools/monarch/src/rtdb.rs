@@ -3,8 +3,12 @@
 //! Provides direct Redis operations for debugging and inspection
 
 use anyhow::Result;
+use chrono::{Local, TimeZone};
 use clap::Subcommand;
+use colored::Colorize;
+use std::collections::{BTreeMap, HashMap};
 use tracing::{info, warn};
+use voltage_model::{KeySpaceConfig, PointType};
 
 #[cfg(feature = "lib-mode")]
 use crate::context::ServiceContext;
@@ -57,6 +61,9 @@ pub enum RtdbCommands {
     },
 
     /// Inspect key type and content
+    ///
+    /// Channel point hashes (`comsrv:<channel_id>:<T|S|C|A>`) are shown as a
+    /// table joining the value, timestamp and raw layers per point.
     #[command(about = "Inspect Redis key type and show content preview")]
     Inspect {
         /// Redis key
@@ -64,6 +71,9 @@ pub enum RtdbCommands {
         /// Show full content for Hash/List/Set
         #[arg(short, long)]
         full: bool,
+        /// Seconds after which a point value counts as stale
+        #[arg(long, default_value = "300")]
+        stale_secs: u64,
     },
 
     /// List common key patterns
@@ -99,8 +109,16 @@ pub async fn handle_command(cmd: RtdbCommands, service_ctx: Option<&ServiceConte
             RtdbCommands::Del { keys, force } => {
                 handle_del(&**rtdb, &keys, force).await?;
             },
-            RtdbCommands::Inspect { key, full } => {
-                handle_inspect(&**rtdb, &key, full).await?;
+            RtdbCommands::Inspect {
+                key,
+                full,
+                stale_secs,
+            } => {
+                if let Some((channel_id, point_type)) = channel_point_key(&key) {
+                    inspect_channel_points(&**rtdb, channel_id, point_type, stale_secs).await?;
+                } else {
+                    handle_inspect(&**rtdb, &key, full).await?;
+                }
             },
             RtdbCommands::Patterns => {
                 show_patterns();
@@ -297,6 +315,180 @@ async fn handle_inspect(rtdb: &impl Rtdb, key: &str, full: bool) -> Result<()> {
     Ok(())
 }
 
+/// Channel and point type of a channel point hash key (`comsrv:12:T`)
+fn channel_point_key(key: &str) -> Option<(u32, PointType)> {
+    let keyspace = KeySpaceConfig::production_cached();
+    let rest = key.strip_prefix(keyspace.data_prefix.as_str())?;
+    let (channel_id, point_type) = rest.strip_prefix(':')?.split_once(':')?;
+    let channel_id = channel_id.parse().ok()?;
+    let point_type = PointType::from_str(point_type)?;
+    (keyspace.channel_key(channel_id, point_type) == key).then_some((channel_id, point_type))
+}
+
+/// One point of a channel hash with its three layers
+#[derive(Debug, Clone, PartialEq)]
+struct PointRow {
+    point_id: u32,
+    value: Option<String>,
+    raw: Option<f64>,
+    /// Milliseconds since the epoch
+    timestamp_ms: Option<i64>,
+    quality: &'static str,
+}
+
+/// Join the layers of a channel hash by point ID
+///
+/// The RTDB stores no quality; it is derived from the layers: `good`,
+/// `stale` (older than `stale_ms`), `no_timestamp`, `invalid` (value not a
+/// number) or `missing` (timestamp or raw value without a value).
+fn decode_points(
+    values: HashMap<String, String>,
+    timestamps: HashMap<String, String>,
+    raws: HashMap<u32, f64>,
+    now_ms: i64,
+    stale_ms: i64,
+) -> Vec<PointRow> {
+    let mut rows: BTreeMap<u32, PointRow> = BTreeMap::new();
+    let row = |point_id: u32| PointRow {
+        point_id,
+        value: None,
+        raw: None,
+        timestamp_ms: None,
+        quality: "",
+    };
+    for (field, value) in values {
+        if let Ok(point_id) = field.parse::<u32>() {
+            rows.entry(point_id).or_insert_with(|| row(point_id)).value = Some(value);
+        }
+    }
+    for (field, ts) in timestamps {
+        if let Ok(point_id) = field.parse::<u32>() {
+            rows.entry(point_id)
+                .or_insert_with(|| row(point_id))
+                .timestamp_ms = ts.trim().parse::<i64>().ok().map(normalize_timestamp);
+        }
+    }
+    for (point_id, raw) in raws {
+        rows.entry(point_id).or_insert_with(|| row(point_id)).raw = Some(raw);
+    }
+
+    rows.into_values()
+        .map(|mut row| {
+            row.quality = match (&row.value, row.timestamp_ms) {
+                (None, _) => "missing",
+                (Some(value), _) if value.trim().parse::<f64>().is_err() => "invalid",
+                (Some(_), None) => "no_timestamp",
+                (Some(_), Some(ts)) if now_ms - ts > stale_ms => "stale",
+                (Some(_), Some(_)) => "good",
+            };
+            row
+        })
+        .collect()
+}
+
+/// Timestamps are written in milliseconds; older tooling wrote seconds
+fn normalize_timestamp(ts: i64) -> i64 {
+    if ts < 100_000_000_000 {
+        ts * 1000
+    } else {
+        ts
+    }
+}
+
+fn format_local_time(timestamp_ms: i64) -> String {
+    Local
+        .timestamp_millis_opt(timestamp_ms)
+        .single()
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S%.3f").to_string())
+        .unwrap_or_else(|| timestamp_ms.to_string())
+}
+
+fn format_age(age_ms: i64) -> String {
+    match age_ms {
+        ..0 => "future".to_string(),
+        0..1000 => format!("{}ms", age_ms),
+        1000..60_000 => format!("{:.1}s", age_ms as f64 / 1000.0),
+        60_000..3_600_000 => format!("{}m", age_ms / 60_000),
+        _ => format!("{}h", age_ms / 3_600_000),
+    }
+}
+
+#[cfg(feature = "lib-mode")]
+async fn inspect_channel_points(
+    rtdb: &impl Rtdb,
+    channel_id: u32,
+    point_type: PointType,
+    stale_secs: u64,
+) -> Result<()> {
+    let keyspace = KeySpaceConfig::production_cached();
+    let key = keyspace.channel_key(channel_id, point_type);
+    let ts_key = keyspace.channel_ts_key(channel_id, point_type);
+    let as_strings = |hash: HashMap<String, Bytes>| -> HashMap<String, String> {
+        hash.into_iter()
+            .map(|(field, value)| (field, String::from_utf8_lossy(&value).into_owned()))
+            .collect()
+    };
+    let values = as_strings(rtdb.hash_get_all(&key).await?);
+    let timestamps = as_strings(rtdb.hash_get_all(&ts_key).await?);
+    // Raw values may be stored compressed or not at all
+    let raws =
+        voltage_rtdb::helpers::read_raw_values(rtdb, keyspace, channel_id, point_type).await?;
+
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let stale_ms = i64::try_from(stale_secs.saturating_mul(1000)).unwrap_or(i64::MAX);
+    let rows = decode_points(values, timestamps, raws, now_ms, stale_ms);
+
+    println!("=== Channel Points ===");
+    println!(
+        "Key: {} (channel {}, {})",
+        key,
+        channel_id,
+        point_type.as_str()
+    );
+    println!(
+        "Layers: {}, {}, raw ({})",
+        key,
+        ts_key,
+        rtdb.raw_layer().mode().as_str()
+    );
+    if rows.is_empty() {
+        println!("\n⚠ No points stored");
+        return Ok(());
+    }
+
+    println!(
+        "\n{:>8}  {:>16}  {:>16}  {:<23}  {:>8}  QUALITY",
+        "POINT", "VALUE", "RAW", "TIMESTAMP", "AGE"
+    );
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for row in &rows {
+        *counts.entry(row.quality).or_default() += 1;
+        let quality = match row.quality {
+            "good" => row.quality.green(),
+            "stale" | "no_timestamp" => row.quality.yellow(),
+            _ => row.quality.red(),
+        };
+        println!(
+            "{:>8}  {:>16}  {:>16}  {:<23}  {:>8}  {}",
+            row.point_id,
+            row.value.as_deref().unwrap_or("-"),
+            row.raw
+                .map_or_else(|| "-".to_string(), |raw| raw.to_string()),
+            row.timestamp_ms
+                .map_or_else(|| "-".to_string(), format_local_time),
+            row.timestamp_ms
+                .map_or_else(|| "-".to_string(), |ts| format_age(now_ms - ts)),
+            quality
+        );
+    }
+    let summary: Vec<String> = counts
+        .iter()
+        .map(|(quality, count)| format!("{} {}", count, quality))
+        .collect();
+    println!("\n{} points: {}", rows.len(), summary.join(", "));
+    Ok(())
+}
+
 fn show_patterns() {
     println!("=== Common Redis Key Patterns in VoltageEMS ===\n");
 
@@ -327,6 +519,66 @@ fn show_patterns() {
     println!("Usage Examples:");
     println!("  monarch rtdb scan \"inst:*:M\"              - Scan all instance measurements");
     println!("  monarch rtdb get route:c2m                - Get C2M routing table");
+    println!("  monarch rtdb inspect comsrv:12:T          - Channel 12 telemetry with timestamps and raw values");
     println!("  monarch rtdb inspect inst:1:M --full      - Inspect instance 1 measurements");
     println!("  monarch rtdb del test:* --force           - Delete all test keys");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(field, value)| (field.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_channel_point_key() {
+        assert_eq!(
+            channel_point_key("comsrv:12:T"),
+            Some((12, PointType::Telemetry))
+        );
+        assert_eq!(
+            channel_point_key("comsrv:12:A"),
+            Some((12, PointType::Adjustment))
+        );
+        assert_eq!(channel_point_key("comsrv:12:T:ts"), None);
+        assert_eq!(channel_point_key("comsrv:x:T"), None);
+        assert_eq!(channel_point_key("inst:1:M"), None);
+    }
+
+    #[test]
+    fn test_decode_points_joins_layers() {
+        let now = 1_735_689_600_000;
+        let rows = decode_points(
+            hash(&[("1", "230.5"), ("2", "1.0"), ("3", "n/a"), ("4", "7")]),
+            hash(&[
+                ("1", &(now - 500).to_string()),
+                ("2", &((now - 600_000) / 1000).to_string()),
+                ("3", &now.to_string()),
+                ("5", &now.to_string()),
+            ]),
+            [(1, 2305.0), (5, 1.0)].into_iter().collect(),
+            now,
+            300_000,
+        );
+        let summary: Vec<(u32, &str)> = rows.iter().map(|r| (r.point_id, r.quality)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, "good"),
+                (2, "stale"),
+                (3, "invalid"),
+                (4, "no_timestamp"),
+                (5, "missing")
+            ]
+        );
+        assert_eq!(rows[0].raw, Some(2305.0));
+        // Second timestamps are widened to milliseconds
+        assert_eq!(rows[1].timestamp_ms, Some(now - 600_000));
+        assert_eq!(format_age(1_500), "1.5s");
+    }
+}
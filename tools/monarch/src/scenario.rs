@@ -0,0 +1,386 @@
+//! Scenario recorder for support cases
+//!
+//! Records the RTDB keys and the SQLite configuration of a site into one
+//! JSON bundle, and replays the RTDB part into a local Redis or a
+//! `MemoryRtdb` snapshot file for offline debugging.
+
+use anyhow::{bail, Context, Result};
+use clap::Subcommand;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Column, Row, SqlitePool, TypeInfo, ValueRef};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use voltage_rtdb::{MemoryRtdb, RedisRtdb, Rtdb, RtdbDump};
+
+use crate::context::ServiceConfig;
+
+/// Bundle format identifier, bumped on incompatible changes
+pub const BUNDLE_FORMAT: &str = "voltage-scenario/1";
+
+#[derive(Subcommand)]
+pub enum ScenarioCommands {
+    /// Record RTDB keys and SQLite tables into a bundle
+    #[command(about = "Record RTDB and SQLite state into a support bundle")]
+    Record {
+        /// Output bundle file
+        #[arg(short = 'O', long, default_value = "scenario.json")]
+        output: PathBuf,
+        /// RTDB key pattern to capture (repeatable, default: all keys)
+        #[arg(short, long = "pattern")]
+        patterns: Vec<String>,
+        /// Free-text note stored in the bundle (ticket, symptom)
+        #[arg(short, long)]
+        note: Option<String>,
+        /// Skip the SQLite tables
+        #[arg(long)]
+        no_sqlite: bool,
+    },
+
+    /// Replay the RTDB part of a bundle
+    #[command(about = "Replay bundle RTDB state into Redis or a MemoryRtdb snapshot")]
+    Replay {
+        /// Bundle file
+        bundle: PathBuf,
+        /// Target Redis URL (never defaults to the site Redis)
+        #[arg(long, conflicts_with = "snapshot")]
+        redis_url: Option<String>,
+        /// Delete keys matching the recorded patterns before replaying
+        #[arg(long, requires = "redis_url")]
+        clean: bool,
+        /// Write a MemoryRtdb snapshot file instead
+        #[arg(long)]
+        snapshot: Option<PathBuf>,
+    },
+
+    /// Show bundle contents
+    #[command(about = "Summarize a support bundle")]
+    Show {
+        /// Bundle file
+        bundle: PathBuf,
+    },
+}
+
+/// Recorded state of one site
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScenarioBundle {
+    pub format: String,
+    /// Unix timestamp (seconds) of the recording
+    pub created_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    pub rtdb: RtdbDump,
+    /// Table name -> contents
+    #[serde(default)]
+    pub sqlite: BTreeMap<String, TableDump>,
+}
+
+/// Rows of one SQLite table
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TableDump {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+}
+
+impl ScenarioBundle {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read bundle {}", path.display()))?;
+        let bundle: Self = serde_json::from_str(&text)
+            .with_context(|| format!("Invalid bundle {}", path.display()))?;
+        if bundle.format != BUNDLE_FORMAT {
+            bail!(
+                "Unsupported bundle format '{}' (expected '{}')",
+                bundle.format,
+                BUNDLE_FORMAT
+            );
+        }
+        Ok(bundle)
+    }
+}
+
+pub async fn handle_command(cmd: ScenarioCommands, config: &ServiceConfig) -> Result<()> {
+    match cmd {
+        ScenarioCommands::Record {
+            output,
+            patterns,
+            note,
+            no_sqlite,
+        } => record(config, &output, &patterns, note, no_sqlite).await,
+        ScenarioCommands::Replay {
+            bundle,
+            redis_url,
+            clean,
+            snapshot,
+        } => {
+            let bundle = ScenarioBundle::load(&bundle)?;
+            match (redis_url, snapshot) {
+                (Some(url), None) => replay_redis(&bundle, &url, clean).await,
+                (None, Some(path)) => replay_snapshot(&bundle, &path).await,
+                _ => bail!("Specify a replay target with --redis-url or --snapshot"),
+            }
+        },
+        ScenarioCommands::Show { bundle } => {
+            show(&ScenarioBundle::load(&bundle)?);
+            Ok(())
+        },
+    }
+}
+
+async fn record(
+    config: &ServiceConfig,
+    output: &Path,
+    patterns: &[String],
+    note: Option<String>,
+    no_sqlite: bool,
+) -> Result<()> {
+    let patterns: Vec<&str> = if patterns.is_empty() {
+        vec!["*"]
+    } else {
+        patterns.iter().map(String::as_str).collect()
+    };
+
+    println!(
+        "{} {}",
+        "Capturing RTDB from".bright_cyan(),
+        config.redis_url
+    );
+    let rtdb = RedisRtdb::new(&config.redis_url)
+        .await
+        .with_context(|| format!("Failed to connect to Redis at {}", config.redis_url))?;
+    let dump = RtdbDump::capture(&rtdb, &patterns).await?;
+
+    let sqlite = if no_sqlite {
+        BTreeMap::new()
+    } else {
+        let db_file = config.unified_db_path();
+        println!(
+            "{} {}",
+            "Capturing SQLite from".bright_cyan(),
+            db_file.display()
+        );
+        let pool = SqlitePool::connect(&format!("sqlite:{}?mode=ro", db_file.display()))
+            .await
+            .context("Failed to connect to database")?;
+        let tables = capture_tables(&pool).await;
+        pool.close().await;
+        tables?
+    };
+
+    let bundle = ScenarioBundle {
+        format: BUNDLE_FORMAT.to_string(),
+        created_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        note,
+        rtdb: dump,
+        sqlite,
+    };
+    std::fs::write(output, serde_json::to_vec_pretty(&bundle)?)
+        .with_context(|| format!("Failed to write {}", output.display()))?;
+
+    println!("{} {}", "✓ Bundle written to".green(), output.display());
+    show(&bundle);
+    Ok(())
+}
+
+/// Dump every user table of the database
+async fn capture_tables(pool: &SqlitePool) -> Result<BTreeMap<String, TableDump>> {
+    let names: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE type = 'table' \
+         AND name NOT LIKE 'sqlite_%' AND name NOT LIKE '_sqlx%' ORDER BY name",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut tables = BTreeMap::new();
+    for name in names {
+        let rows = sqlx::query(&format!("SELECT * FROM \"{}\"", name.replace('"', "\"\"")))
+            .fetch_all(pool)
+            .await
+            .with_context(|| format!("Failed to read table {}", name))?;
+        let mut table = TableDump::default();
+        if let Some(first) = rows.first() {
+            table.columns = first
+                .columns()
+                .iter()
+                .map(|c| c.name().to_string())
+                .collect();
+        }
+        for row in &rows {
+            table.rows.push(row_values(row)?);
+        }
+        tables.insert(name, table);
+    }
+    Ok(tables)
+}
+
+/// Convert a row by the storage class of each value
+fn row_values(row: &SqliteRow) -> Result<Vec<serde_json::Value>> {
+    let mut values = Vec::with_capacity(row.len());
+    for i in 0..row.len() {
+        let raw = row.try_get_raw(i)?;
+        let value = if raw.is_null() {
+            serde_json::Value::Null
+        } else {
+            match raw.type_info().name() {
+                "INTEGER" | "BOOLEAN" => row.try_get::<i64, _>(i)?.into(),
+                "REAL" => row.try_get::<f64, _>(i)?.into(),
+                "BLOB" => row.try_get::<Vec<u8>, _>(i)?.into(),
+                _ => row.try_get::<String, _>(i)?.into(),
+            }
+        };
+        values.push(value);
+    }
+    Ok(values)
+}
+
+async fn replay_redis(bundle: &ScenarioBundle, url: &str, clean: bool) -> Result<()> {
+    let rtdb = RedisRtdb::new(url)
+        .await
+        .with_context(|| format!("Failed to connect to Redis at {}", url))?;
+    if clean {
+        let mut removed = 0;
+        for pattern in &bundle.rtdb.patterns {
+            for key in rtdb.scan_match(pattern).await? {
+                if rtdb.del(&key).await? {
+                    removed += 1;
+                }
+            }
+        }
+        println!("{} {} existing keys", "Removed".yellow(), removed);
+    }
+    let stats = bundle.rtdb.restore(&rtdb).await?;
+    println!(
+        "{} {} keys into {}",
+        "✓ Replayed".green(),
+        stats.total(),
+        url
+    );
+    Ok(())
+}
+
+async fn replay_snapshot(bundle: &ScenarioBundle, path: &Path) -> Result<()> {
+    let rtdb = MemoryRtdb::new();
+    bundle.rtdb.restore(&rtdb).await?;
+    let stats = rtdb.save_snapshot(path)?;
+    println!(
+        "{} {} keys to {}",
+        "✓ Snapshot with".green(),
+        stats.total(),
+        path.display()
+    );
+    Ok(())
+}
+
+fn show(bundle: &ScenarioBundle) {
+    let stats = bundle.rtdb.stats();
+    println!("{} {}", "Format:".bright_cyan(), bundle.format);
+    println!(
+        "{} {}",
+        "Recorded at (unix):".bright_cyan(),
+        bundle.created_at
+    );
+    if let Some(note) = &bundle.note {
+        println!("{} {}", "Note:".bright_cyan(), note);
+    }
+    println!(
+        "{} {} keys (string {}, hash {}, list {}, set {}) from {}",
+        "RTDB:".bright_cyan(),
+        stats.total(),
+        stats.kv_count,
+        stats.hash_count,
+        stats.list_count,
+        stats.set_count,
+        bundle.rtdb.patterns.join(", ")
+    );
+    for (key, kind) in &bundle.rtdb.skipped {
+        println!("  {} {} ({})", "skipped".yellow(), key, kind);
+    }
+    println!("{} {} tables", "SQLite:".bright_cyan(), bundle.sqlite.len());
+    for (name, table) in &bundle.sqlite {
+        println!("  {:<32} {} rows", name, table.rows.len());
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::disallowed_methods)] // Test code - unwrap is acceptable
+mod tests {
+    use super::*;
+    use voltage_rtdb::Bytes;
+
+    #[tokio::test]
+    async fn test_capture_tables_keeps_storage_classes() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::query("CREATE TABLE channels (id INTEGER, name TEXT, ratio REAL, raw BLOB)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO channels VALUES (1, 'pcs', 0.5, x'0102'), (2, NULL, NULL, NULL)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let tables = capture_tables(&pool).await.unwrap();
+        let table = &tables["channels"];
+        assert_eq!(table.columns, vec!["id", "name", "ratio", "raw"]);
+        assert_eq!(
+            table.rows,
+            vec![
+                vec![1.into(), "pcs".into(), 0.5.into(), vec![1u8, 2].into()],
+                vec![
+                    2.into(),
+                    serde_json::Value::Null,
+                    serde_json::Value::Null,
+                    serde_json::Value::Null
+                ],
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bundle_roundtrip_and_snapshot_replay() {
+        let source = MemoryRtdb::new();
+        source
+            .hash_set("comsrv:1:T", "1", Bytes::from("12.5"))
+            .await
+            .unwrap();
+        let bundle = ScenarioBundle {
+            format: BUNDLE_FORMAT.to_string(),
+            created_at: 0,
+            note: Some("ticket 42".to_string()),
+            rtdb: RtdbDump::capture(&source, &["*"]).await.unwrap(),
+            sqlite: BTreeMap::new(),
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let bundle_path = dir.path().join("scenario.json");
+        std::fs::write(&bundle_path, serde_json::to_vec(&bundle).unwrap()).unwrap();
+        let loaded = ScenarioBundle::load(&bundle_path).unwrap();
+        assert_eq!(loaded.rtdb, bundle.rtdb);
+
+        let snap = dir.path().join("rtdb.snap");
+        replay_snapshot(&loaded, &snap).await.unwrap();
+        let replayed = MemoryRtdb::new();
+        replayed.restore_snapshot(&snap).unwrap();
+        assert_eq!(
+            replayed.hash_get("comsrv:1:T", "1").await.unwrap(),
+            Some(Bytes::from("12.5"))
+        );
+    }
+
+    #[test]
+    fn test_load_rejects_unknown_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bundle.json");
+        std::fs::write(
+            &path,
+            r#"{"format":"voltage-scenario/9","created_at":0,"rtdb":{"patterns":[],"keys":{}}}"#,
+        )
+        .unwrap();
+        let err = ScenarioBundle::load(&path).unwrap_err();
+        assert!(err.to_string().contains("Unsupported bundle format"));
+    }
+}
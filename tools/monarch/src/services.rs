@@ -493,7 +493,13 @@ pub async fn handle_command(
             .await?;
 
             // Display results
-            if outcome.routed {
+            if outcome.is_overridden() {
+                println!("\n⚠ Action not executed: point is under local override");
+                println!(
+                    "  ({})",
+                    outcome.route_result.unwrap_or_else(|| "N/A".to_string())
+                );
+            } else if outcome.routed {
                 println!("\n✓ Action executed and routed successfully");
                 println!(
                     "  Route result: {}",
@@ -1,7 +1,7 @@
 //! Utility functions for monarch CLI
 
 use anyhow::Result;
-use sqlx::SqlitePool;
+use common::sqlite::ReadOnlyDatabase;
 use std::path::Path;
 use tracing::debug;
 
@@ -31,15 +31,15 @@ pub async fn check_database_status(db_path: &Path) -> Result<DatabaseStatus> {
         });
     }
 
-    // Connect to database in read-only mode
-    let connection_string = format!("sqlite://{}?mode=ro", db_path.display());
-    let pool = SqlitePool::connect(&connection_string).await?;
+    // Read-only access never blocks running services
+    let database = ReadOnlyDatabase::open(db_path).await?;
+    let pool = database.pool();
 
     // Check if service_config table exists
     let table_exists: bool = sqlx::query_scalar(
         "SELECT 1 FROM sqlite_master WHERE type='table' AND name='service_config'",
     )
-    .fetch_optional(&pool)
+    .fetch_optional(pool)
     .await?
     .unwrap_or(false);
 
@@ -56,19 +56,22 @@ pub async fn check_database_status(db_path: &Path) -> Result<DatabaseStatus> {
     // Get last sync timestamp
     let last_sync: Option<String> =
         sqlx::query_scalar("SELECT value FROM service_config WHERE key = '_sync_timestamp'")
-            .fetch_optional(&pool)
+            .fetch_optional(pool)
             .await?;
 
     // Get item count
     let item_count: Option<i64> = sqlx::query_scalar("SELECT COUNT(*) FROM service_config")
-        .fetch_optional(&pool)
+        .fetch_optional(pool)
         .await?;
 
-    // Get schema version if available
-    let schema_version: Option<String> =
-        sqlx::query_scalar("SELECT value FROM service_config WHERE key = '_schema_version'")
-            .fetch_optional(&pool)
-            .await?;
+    // Per-component migration versions, e.g. "comsrv=1, modsrv=3, rules=1"
+    let mut versions = Vec::new();
+    for migrator in crate::core::schema::migrators() {
+        if let Some(applied) = migrator.applied(pool).await?.last() {
+            versions.push(format!("{}={}", migrator.component(), applied.version));
+        }
+    }
+    let schema_version = (!versions.is_empty()).then(|| versions.join(", "));
 
     Ok(DatabaseStatus {
         exists: true,